@@ -47,6 +47,12 @@ pub enum DatabaseURLParseError {
 
     #[error(transparent)]
     MySQL(#[from] mysql::UrlError),
+
+    #[error("Invalid TLS option in database URL: {0}")]
+    InvalidTlsOption(String),
+
+    #[error("Could not load CA certificates from {path}: {reason}")]
+    RootCertificate { path: String, reason: String },
 }
 
 /// Error type for the [`FromStr`] implementation for [`DatabaseType`]
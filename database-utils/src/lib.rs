@@ -15,6 +15,7 @@ use {mysql_async as mysql, tokio_postgres as pgsql};
 use crate::error::{DatabaseError, DatabaseURLParseError};
 
 pub mod error;
+pub mod tls;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DatabaseType {
@@ -108,7 +109,7 @@ impl FromStr for DatabaseURL {
     type Err = DatabaseURLParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.starts_with("mysql://") {
-            Ok(Self::MySQL(mysql::Opts::from_url(s)?))
+            Ok(Self::MySQL(tls::mysql_opts(s)?))
         } else if s.starts_with("postgresql://") {
             Ok(Self::PostgreSQL(pgsql::Config::from_str(s)?))
         } else {
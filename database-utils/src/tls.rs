@@ -0,0 +1,228 @@
+//! TLS configuration for connections to upstream databases.
+//!
+//! The client libraries used to connect to upstream databases don't understand all of the TLS
+//! options that can be given in a database URL, so the following options are removed from the URL
+//! and handled here before the rest of the URL is parsed:
+//!
+//! * PostgreSQL:
+//!   * `sslmode=verify-ca` requires TLS, and verifies that the server's certificate is signed by a
+//!     trusted CA. `sslmode=verify-full` additionally verifies that the certificate matches the
+//!     host being connected to. `disable`, `prefer` and `require` are also accepted, but unlike
+//!     with `libpq`, `prefer` and `require` verify the server's certificate like `verify-full`.
+//!   * `sslrootcert` is the path to a PEM file containing CA certificates to trust in addition to
+//!     the system's trusted CAs.
+//! * MySQL:
+//!   * `ssl-mode` is one of `DISABLED`, `PREFERRED` (the default), `REQUIRED`, `VERIFY_CA` or
+//!     `VERIFY_IDENTITY`, with the same meaning as for the `mysql` client, except that `PREFERRED`
+//!     doesn't attempt to use TLS.
+//!   * `ssl-ca` is the path to a CA certificate file to trust in addition to the system's trusted
+//!     CAs.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use mysql_async::{OptsBuilder, SslOpts};
+use native_tls::{Certificate, TlsConnector};
+use percent_encoding::percent_decode_str;
+use postgres_native_tls::MakeTlsConnector;
+use {mysql_async as mysql, tokio_postgres as pgsql};
+
+use crate::error::DatabaseURLParseError;
+
+const PEM_CERTIFICATE_END: &str = "-----END CERTIFICATE-----";
+
+/// Parse a PostgreSQL database URL, returning the connection configuration along with the TLS
+/// connector to use to connect to the database.
+pub fn postgres_config(
+    url: &str,
+) -> Result<(pgsql::Config, MakeTlsConnector), DatabaseURLParseError> {
+    let (url, mut params) = take_params(url, &["sslmode", "sslrootcert"]);
+
+    let mut builder = TlsConnector::builder();
+    let ssl_mode = match params.remove("sslmode").as_deref() {
+        Some("verify-ca") => {
+            builder.danger_accept_invalid_hostnames(true);
+            Some(pgsql::config::SslMode::Require)
+        }
+        Some("verify-full") => Some(pgsql::config::SslMode::Require),
+        Some("disable") => Some(pgsql::config::SslMode::Disable),
+        Some("prefer") => Some(pgsql::config::SslMode::Prefer),
+        Some("require") => Some(pgsql::config::SslMode::Require),
+        Some(mode) => {
+            return Err(DatabaseURLParseError::InvalidTlsOption(format!(
+                "sslmode={}",
+                mode
+            )))
+        }
+        None => None,
+    };
+    if let Some(path) = params.remove("sslrootcert") {
+        for certificate in load_pem_certificates(&path)? {
+            builder.add_root_certificate(certificate);
+        }
+    }
+
+    let mut config = pgsql::Config::from_str(&url)?;
+    if let Some(ssl_mode) = ssl_mode {
+        config.ssl_mode(ssl_mode);
+    }
+    let connector = builder
+        .build()
+        .map_err(|e| DatabaseURLParseError::InvalidTlsOption(e.to_string()))?;
+    Ok((config, MakeTlsConnector::new(connector)))
+}
+
+/// Parse a MySQL database URL into connection options, including the TLS options described in the
+/// [module documentation](self).
+pub fn mysql_opts(url: &str) -> Result<mysql::Opts, DatabaseURLParseError> {
+    let (url, mut params) = take_params(url, &["ssl-mode", "ssl-ca"]);
+    let opts = mysql::Opts::from_url(&url)?;
+
+    let ssl_opts = match params
+        .remove("ssl-mode")
+        .map(|mode| mode.to_ascii_uppercase())
+        .as_deref()
+    {
+        None | Some("DISABLED") | Some("PREFERRED") => None,
+        Some("REQUIRED") => Some(SslOpts::default().with_danger_accept_invalid_certs(true)),
+        Some("VERIFY_CA") => Some(SslOpts::default().with_danger_skip_domain_validation(true)),
+        Some("VERIFY_IDENTITY") => Some(SslOpts::default()),
+        Some(mode) => {
+            return Err(DatabaseURLParseError::InvalidTlsOption(format!(
+                "ssl-mode={}",
+                mode
+            )))
+        }
+    };
+    let ssl_opts = match (ssl_opts, params.remove("ssl-ca")) {
+        (Some(ssl_opts), Some(path)) => {
+            Some(ssl_opts.with_root_cert_path(Some(PathBuf::from(path))))
+        }
+        (None, Some(_)) => {
+            return Err(DatabaseURLParseError::InvalidTlsOption(
+                "ssl-ca requires ssl-mode to be REQUIRED, VERIFY_CA or VERIFY_IDENTITY".to_owned(),
+            ))
+        }
+        (ssl_opts, None) => ssl_opts,
+    };
+
+    Ok(match ssl_opts {
+        Some(ssl_opts) => OptsBuilder::from_opts(opts).ssl_opts(ssl_opts).into(),
+        None => opts,
+    })
+}
+
+/// Remove the query parameters named in `keys` from `url`, returning the rest of the URL along
+/// with the (percent-decoded) values of the removed parameters.
+fn take_params(url: &str, keys: &[&str]) -> (String, HashMap<String, String>) {
+    let (base, query) = match url.split_once('?') {
+        Some(parts) => parts,
+        None => return (url.to_owned(), HashMap::new()),
+    };
+
+    let mut taken = HashMap::new();
+    let mut remaining = vec![];
+    for param in query.split('&').filter(|param| !param.is_empty()) {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        if keys.contains(&key) {
+            taken.insert(
+                key.to_owned(),
+                percent_decode_str(value).decode_utf8_lossy().into_owned(),
+            );
+        } else {
+            remaining.push(param);
+        }
+    }
+
+    if remaining.is_empty() {
+        (base.to_owned(), taken)
+    } else {
+        (format!("{}?{}", base, remaining.join("&")), taken)
+    }
+}
+
+/// Load all the certificates in the PEM file at `path`
+fn load_pem_certificates(path: &str) -> Result<Vec<Certificate>, DatabaseURLParseError> {
+    let error = |reason: String| DatabaseURLParseError::RootCertificate {
+        path: path.to_owned(),
+        reason,
+    };
+    let pem = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+
+    // `Certificate::from_pem` only reads the first certificate in a bundle, so split the bundle up
+    let certificates = pem
+        .split_inclusive(PEM_CERTIFICATE_END)
+        .filter(|block| block.contains(PEM_CERTIFICATE_END))
+        .map(|block| Certificate::from_pem(block.as_bytes()).map_err(|e| error(e.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+    if certificates.is_empty() {
+        return Err(error("no certificates found".to_owned()));
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_params_removes_only_given_keys() {
+        let (url, params) = take_params(
+            "postgresql://u:p@host/db?sslmode=verify-full&application_name=x&sslrootcert=%2Fca.pem",
+            &["sslmode", "sslrootcert"],
+        );
+        assert_eq!(url, "postgresql://u:p@host/db?application_name=x");
+        assert_eq!(params["sslmode"], "verify-full");
+        assert_eq!(params["sslrootcert"], "/ca.pem");
+    }
+
+    #[test]
+    fn take_params_without_query() {
+        let (url, params) = take_params("mysql://root@localhost/db", &["ssl-mode"]);
+        assert_eq!(url, "mysql://root@localhost/db");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn postgres_verify_full_requires_tls() {
+        let (config, _) =
+            postgres_config("postgresql://postgres@localhost/db?sslmode=verify-full").unwrap();
+        assert_eq!(config.get_ssl_mode(), pgsql::config::SslMode::Require);
+    }
+
+    #[test]
+    fn postgres_invalid_sslmode() {
+        assert!(matches!(
+            postgres_config("postgresql://postgres@localhost/db?sslmode=sometimes"),
+            Err(DatabaseURLParseError::InvalidTlsOption(_))
+        ));
+    }
+
+    #[test]
+    fn mysql_verify_identity() {
+        let opts = mysql_opts("mysql://root@localhost/db?ssl-mode=VERIFY_IDENTITY").unwrap();
+        let ssl_opts = opts.ssl_opts().unwrap();
+        assert!(!ssl_opts.accept_invalid_certs());
+        assert!(!ssl_opts.skip_domain_validation());
+    }
+
+    #[test]
+    fn mysql_verify_ca_with_ca() {
+        let opts =
+            mysql_opts("mysql://root@localhost/db?ssl-mode=verify_ca&ssl-ca=/ca.pem").unwrap();
+        let ssl_opts = opts.ssl_opts().unwrap();
+        assert!(ssl_opts.skip_domain_validation());
+        assert_eq!(
+            ssl_opts.root_cert_path(),
+            Some(std::path::Path::new("/ca.pem"))
+        );
+    }
+
+    #[test]
+    fn mysql_preferred_does_not_use_tls() {
+        let opts = mysql_opts("mysql://root@localhost/db?ssl-mode=PREFERRED").unwrap();
+        assert!(opts.ssl_opts().is_none());
+    }
+}
@@ -17,6 +17,8 @@ pub struct ClientHandshake<'a> {
     pub password: &'a [u8],
     pub database: Option<&'a str>,
     pub auth_plugin_name: Option<&'a str>,
+    /// The compression level requested by the client, if it supports zstd compression
+    pub zstd_compression_level: Option<u8>,
 }
 
 /// Parse a "length-encoded integer" as specified by the [mysql binary protocol documentation][docs]
@@ -82,6 +84,23 @@ pub fn client_handshake(i: &[u8]) -> IResult<&[u8], ClientHandshake<'_>> {
         (i, None)
     };
 
+    // Connection attributes aren't used, but have to be skipped to get to the compression level
+    let (i, _) = if capabilities.contains(CapabilityFlags::CLIENT_CONNECT_ATTRS) {
+        opt(|i| {
+            let (i, attrs_length) = lenenc_int(i)?;
+            take(attrs_length as usize)(i)
+        })(i)?
+    } else {
+        (i, None)
+    };
+
+    let (i, zstd_compression_level) =
+        if capabilities.contains(CapabilityFlags::CLIENT_ZSTD_COMPRESSION_ALGORITHM) {
+            opt(le_u8)(i)?
+        } else {
+            (i, None)
+        };
+
     Ok((
         i,
         ClientHandshake {
@@ -92,6 +111,7 @@ pub fn client_handshake(i: &[u8]) -> IResult<&[u8], ClientHandshake<'_>> {
             password,
             database,
             auth_plugin_name,
+            zstd_compression_level,
         },
     ))
 }
@@ -246,4 +266,24 @@ mod tests {
             Command::ListFields(&b"select @@version_comment limit 1"[..])
         );
     }
+
+    #[tokio::test]
+    async fn it_parses_send_long_data() {
+        let data = &[
+            0x0c, 0x00, 0x00, 0x00, 0x18, 0x29, 0x00, 0x00, 0x00, 0x01, 0x00, 0x48, 0x65, 0x6c,
+            0x6c, 0x6f,
+        ];
+        let r = Cursor::new(&data[..]);
+        let mut pr = PacketReader::new(r);
+        let (_, p) = pr.next().await.unwrap().unwrap();
+        let (_, cmd) = parse(&p).unwrap();
+        assert_eq!(
+            cmd,
+            Command::SendLongData {
+                stmt: 41,
+                param: 1,
+                data: &b"Hello"[..],
+            }
+        );
+    }
 }
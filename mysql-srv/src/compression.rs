@@ -0,0 +1,162 @@
+//! Support for the [compressed protocol][docs].
+//!
+//! When compression is negotiated during the handshake, every packet sent after the client is
+//! authenticated is wrapped in a compressed packet, which has a 7 byte header: the length of the
+//! (possibly compressed) payload, a sequence id that is separate from the one in the packets
+//! within it, and the length of the payload before compression. If the latter is 0 the payload
+//! was sent uncompressed, which is done for payloads too small to be worth compressing.
+//!
+//! [docs]: https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_basic_compression.html
+
+use std::io::{self, Read, Write};
+
+use crate::myc::constants::CapabilityFlags;
+use crate::packet::U24_MAX;
+
+/// Payloads shorter than this are sent uncompressed
+const MIN_COMPRESS_LENGTH: usize = 50;
+
+/// The zstd compression level used if the client doesn't request one
+pub(crate) const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// A compression algorithm used for the compressed protocol
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Compression {
+    Zlib,
+    Zstd { level: i32 },
+}
+
+impl Compression {
+    /// Returns the compression algorithm requested by a client with the given capabilities, if
+    /// any. zstd is preferred if the client supports both algorithms.
+    pub(crate) fn negotiate(capabilities: CapabilityFlags, zstd_level: Option<u8>) -> Option<Self> {
+        if capabilities.contains(CapabilityFlags::CLIENT_ZSTD_COMPRESSION_ALGORITHM) {
+            Some(Self::Zstd {
+                level: zstd_level.map_or(DEFAULT_ZSTD_LEVEL, i32::from),
+            })
+        } else if capabilities.contains(CapabilityFlags::CLIENT_COMPRESS) {
+            Some(Self::Zlib)
+        } else {
+            None
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Zlib => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Self::Zstd { level } => zstd::bulk::compress(data, level),
+        }
+    }
+
+    /// Decompress `data`, which is `len` bytes long once decompressed
+    pub(crate) fn decompress(self, data: &[u8], len: usize) -> io::Result<Vec<u8>> {
+        let res = match self {
+            Self::Zlib => {
+                let mut res = Vec::with_capacity(len);
+                flate2::read::ZlibDecoder::new(data).read_to_end(&mut res)?;
+                res
+            }
+            Self::Zstd { .. } => zstd::bulk::decompress(data, len)?,
+        };
+
+        if res.len() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "compressed packet decompressed to {} bytes, expected {}",
+                    res.len(),
+                    len
+                ),
+            ));
+        }
+        Ok(res)
+    }
+
+    /// Append `data` to `out` as a sequence of compressed packets, starting with the sequence id
+    /// `seq`, which is updated to the id of the next compressed packet to send
+    pub(crate) fn encode(self, data: &[u8], seq: &mut u8, out: &mut Vec<u8>) -> io::Result<()> {
+        for chunk in data.chunks(U24_MAX) {
+            let (payload, uncompressed_len) = if chunk.len() < MIN_COMPRESS_LENGTH {
+                (None, 0)
+            } else {
+                (Some(self.compress(chunk)?), chunk.len())
+            };
+            let payload = payload.as_deref().unwrap_or(chunk);
+
+            out.extend_from_slice(&(payload.len() as u32).to_le_bytes()[..3]);
+            out.push(*seq);
+            out.extend_from_slice(&(uncompressed_len as u32).to_le_bytes()[..3]);
+            out.extend_from_slice(payload);
+            *seq = seq.wrapping_add(1);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(compression: Compression, data: &[u8]) {
+        let mut seq = 3;
+        let mut out = Vec::new();
+        compression.encode(data, &mut seq, &mut out).unwrap();
+        assert_eq!(seq, 4);
+
+        let payload_len = u32::from_le_bytes([out[0], out[1], out[2], 0]) as usize;
+        assert_eq!(out[3], 3);
+        let uncompressed_len = u32::from_le_bytes([out[4], out[5], out[6], 0]) as usize;
+        let payload = &out[7..];
+        assert_eq!(payload.len(), payload_len);
+
+        if uncompressed_len == 0 {
+            assert_eq!(payload, data);
+        } else {
+            assert_eq!(
+                compression.decompress(payload, uncompressed_len).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrip_zlib() {
+        roundtrip(Compression::Zlib, b"short");
+        roundtrip(Compression::Zlib, &[7; 4096]);
+    }
+
+    #[test]
+    fn roundtrip_zstd() {
+        let compression = Compression::Zstd {
+            level: DEFAULT_ZSTD_LEVEL,
+        };
+        roundtrip(compression, b"short");
+        roundtrip(compression, &[7; 4096]);
+    }
+
+    #[test]
+    fn negotiate_prefers_zstd() {
+        assert_eq!(
+            Compression::negotiate(
+                CapabilityFlags::CLIENT_COMPRESS
+                    | CapabilityFlags::CLIENT_ZSTD_COMPRESSION_ALGORITHM,
+                Some(7)
+            ),
+            Some(Compression::Zstd { level: 7 })
+        );
+        assert_eq!(
+            Compression::negotiate(CapabilityFlags::CLIENT_COMPRESS, None),
+            Some(Compression::Zlib)
+        );
+        assert_eq!(
+            Compression::negotiate(CapabilityFlags::CLIENT_PROTOCOL_41, None),
+            None
+        );
+    }
+}
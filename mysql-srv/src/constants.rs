@@ -53,6 +53,8 @@ pub const CAN_HANDLE_EXPIRED_PASSWORDS: u32 = 0x00400000;
 pub const SESSION_TRACK: u32 = 0x00800000;
 /// Can send OK after a Text Resultset.
 pub const DEPRECATE_EOF: u32 = 0x01000000;
+/// Can use the zstd compression algorithm for the compressed protocol
+pub const ZSTD_COMPRESSION_ALGORITHM: u32 = 0x04000000;
 /// Client supports plugin authentication
 pub const CLIENT_PLUGIN_AUTH: u32 = 0x00080000;
 
@@ -64,6 +64,9 @@
 //!                         column: "@@max_allowed_packet".to_owned(),
 //!                         coltype: myc::constants::ColumnType::MYSQL_TYPE_LONG,
 //!                         colflags: myc::constants::ColumnFlags::UNSIGNED_FLAG,
+//!                         column_length: 1024,
+//!                         character_set: myc::constants::UTF8_GENERAL_CI,
+//!                         decimals: 0,
 //!                     }];
 //!                     let mut w = results.start(cols).await?;
 //!                     w.write_row(iter::once(67108864u32))?;
@@ -78,12 +81,18 @@
 //!                     column: "a".to_string(),
 //!                     coltype: ColumnType::MYSQL_TYPE_LONGLONG,
 //!                     colflags: ColumnFlags::empty(),
+//!                     column_length: 1024,
+//!                     character_set: myc::constants::UTF8_GENERAL_CI,
+//!                     decimals: 0,
 //!                 },
 //!                 Column {
 //!                     table: "foo".to_string(),
 //!                     column: "b".to_string(),
 //!                     coltype: ColumnType::MYSQL_TYPE_STRING,
 //!                     colflags: ColumnFlags::empty(),
+//!                     column_length: 1024,
+//!                     character_set: myc::constants::UTF8_GENERAL_CI,
+//!                     decimals: 0,
 //!                 },
 //!             ];
 //!
@@ -150,26 +159,34 @@ use std::collections::HashMap;
 use std::io;
 
 use async_trait::async_trait;
-use constants::{CLIENT_PLUGIN_AUTH, PROTOCOL_41, RESERVED, SECURE_CONNECTION};
+use constants::{
+    CLIENT_PLUGIN_AUTH, COMPRESS, MULTI_RESULTS, MULTI_STATEMENTS, PROTOCOL_41, PS_MULTI_RESULTS,
+    RESERVED, SECURE_CONNECTION, SSL, ZSTD_COMPRESSION_ALGORITHM,
+};
 use error::{other_error, OtherErrorKind};
 use mysql_common::constants::CapabilityFlags;
-use tokio::io::{AsyncRead, AsyncWrite};
+use openssl::ssl::SslAcceptor;
+use tokio::io::{AsyncRead, AsyncWrite, WriteHalf};
 use tokio::net;
+use tokio_openssl::SslStream;
 use tracing::{debug, trace};
 use writers::write_err;
 
 use crate::authentication::{generate_auth_data, hash_password, AUTH_PLUGIN_NAME};
-pub use crate::myc::constants::{ColumnFlags, ColumnType, StatusFlags};
+use crate::compression::Compression;
+pub use crate::myc::constants::{ColumnFlags, ColumnType, StatusFlags, UTF8_GENERAL_CI};
 pub use crate::writers::prepare_column_definitions;
 
 mod authentication;
 mod commands;
+mod compression;
 mod constants;
 pub mod error;
 mod errorcodes;
 mod packet;
 mod params;
 mod resultset;
+mod tls;
 mod value;
 mod writers;
 
@@ -197,8 +214,23 @@ pub struct Column {
     ///
     /// Of particular interest are `ColumnFlags::UNSIGNED_FLAG` and `ColumnFlags::NOT_NULL_FLAG`.
     pub colflags: ColumnFlags,
+    /// The maximum length of this column's values.
+    ///
+    /// For numeric types this is the display width, and for string types the maximum length in
+    /// bytes (so it depends on the column's character set).
+    pub column_length: u32,
+    /// The id of the collation of this column's values.
+    ///
+    /// Columns of numeric, temporal and binary string types use [`BINARY_COLLATION`].
+    pub character_set: u16,
+    /// The number of digits after the decimal point shown for this column's values.
+    pub decimals: u8,
 }
 
+/// The id of the `binary` collation, which MySQL reports for every column that doesn't hold
+/// (non-binary) text
+pub const BINARY_COLLATION: u16 = 63;
+
 impl From<&mysql_async::Column> for Column {
     fn from(c: &mysql_async::Column) -> Self {
         Column {
@@ -206,6 +238,9 @@ impl From<&mysql_async::Column> for Column {
             column: c.name_str().to_string(),
             coltype: c.column_type(),
             colflags: c.flags(),
+            column_length: c.column_length(),
+            character_set: c.character_set(),
+            decimals: c.decimals(),
         }
     }
 }
@@ -269,6 +304,8 @@ pub struct MysqlIntermediary<B, R: AsyncRead + Unpin, W: AsyncWrite + Unpin> {
     shim: B,
     reader: packet::PacketReader<R>,
     writer: packet::PacketWriter<W>,
+    /// The capabilities the client declared in its handshake response
+    client_capabilities: CapabilityFlags,
 }
 
 impl<B: MysqlShim<net::tcp::OwnedWriteHalf> + Send>
@@ -284,6 +321,83 @@ impl<B: MysqlShim<net::tcp::OwnedWriteHalf> + Send>
     }
 }
 
+impl<B> MysqlIntermediary<B, net::TcpStream, net::TcpStream>
+where
+    B: MysqlShim<net::tcp::OwnedWriteHalf> + MysqlShim<WriteHalf<SslStream<net::TcpStream>>> + Send,
+{
+    /// Create a new server over a TCP stream that offers TLS to clients, using `acceptor`, and
+    /// process client commands until the client disconnects or an error occurs. If `require_tls`
+    /// is set, clients that don't request TLS are refused.
+    ///
+    /// A client that presents a certificate (which is verified by `acceptor`) is authenticated as
+    /// the user named by the common name of the certificate's subject without needing a password,
+    /// as long as it connects as that user.
+    pub async fn run_on_tls(
+        shim: B,
+        mut stream: net::TcpStream,
+        acceptor: &SslAcceptor,
+        require_tls: bool,
+    ) -> Result<(), io::Error> {
+        stream.set_nodelay(true)?;
+        let auth_data =
+            generate_auth_data().map_err(|_| other_error(OtherErrorKind::AuthDataErr))?;
+
+        {
+            let mut w = packet::PacketWriter::new(&mut stream);
+            w.write_packet(&greeting(&auth_data, CAPABILITIES | SSL))
+                .await?;
+            w.flush().await?;
+        }
+        let (seq, first_packet) = tls::read_first_packet(&mut stream).await?;
+
+        if tls::is_ssl_request(&first_packet) {
+            let stream = tls::accept(acceptor, stream).await?;
+            let identity = tls::client_identity(stream.ssl());
+            let (reader, writer) = tokio::io::split(stream);
+            let mut mi = MysqlIntermediary {
+                shim,
+                reader: packet::PacketReader::new(reader),
+                writer: packet::PacketWriter::new(writer),
+                client_capabilities: CapabilityFlags::empty(),
+            };
+            let (seq, handshake) = mi.reader.next().await?.ok_or_else(peer_terminated)?;
+            let handshake = handshake.to_vec();
+            if mi
+                .authenticate(&auth_data, seq, &handshake, identity)
+                .await?
+            {
+                mi.run().await?;
+            }
+        } else {
+            let (reader, writer) = stream.into_split();
+            let mut mi = MysqlIntermediary {
+                shim,
+                reader: packet::PacketReader::new(reader),
+                writer: packet::PacketWriter::new(writer),
+                client_capabilities: CapabilityFlags::empty(),
+            };
+            if require_tls {
+                debug!("Client did not request TLS, returning authentication error");
+                mi.writer.set_seq(seq + 1);
+                writers::write_err(
+                    ErrorKind::ER_ACCESS_DENIED_ERROR,
+                    b"Connections using insecure transport are prohibited",
+                    &mut mi.writer,
+                )
+                .await?;
+                return Ok(());
+            }
+            if mi
+                .authenticate(&auth_data, seq, &first_packet, None)
+                .await?
+            {
+                mi.run().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<B: MysqlShim<S> + Send, S: AsyncRead + AsyncWrite + Clone + Unpin + Send>
     MysqlIntermediary<B, S, S>
 {
@@ -307,11 +421,52 @@ where
 #[derive(Default)]
 struct StatementData {
     long_data: HashMap<u16, Vec<u8>>,
+    /// Set if the client sent long data for a parameter that the statement doesn't have, in which
+    /// case the next execution of the statement fails
+    long_data_error: bool,
     bound_types: Vec<(myc::constants::ColumnType, bool)>,
     params: u16,
 }
 
-const CAPABILITIES: u32 = PROTOCOL_41 | SECURE_CONNECTION | RESERVED | CLIENT_PLUGIN_AUTH;
+const CAPABILITIES: u32 = PROTOCOL_41
+    | SECURE_CONNECTION
+    | RESERVED
+    | CLIENT_PLUGIN_AUTH
+    | MULTI_STATEMENTS
+    | MULTI_RESULTS
+    | PS_MULTI_RESULTS
+    | COMPRESS
+    | ZSTD_COMPRESSION_ALGORITHM;
+
+fn peer_terminated() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "peer terminated connection",
+    )
+}
+
+/// Build the initial handshake packet sent to clients, advertising `capabilities`
+fn greeting(auth_data: &[u8], capabilities: u32) -> Vec<u8> {
+    let mut init_packet = Vec::with_capacity(
+        1 + 16 + 4 + 8 + 1 + 2 + 1 + 2 + 2 + 1 + 6 + 4 + 12 + 1 + AUTH_PLUGIN_NAME.len() + 1,
+    );
+    init_packet.extend_from_slice(&[10]); // protocol 10
+    init_packet.extend_from_slice(CURRENT_VERSION);
+    init_packet.extend_from_slice(&[0x08, 0x00, 0x00, 0x00]); // TODO: connection ID
+    init_packet.extend_from_slice(&auth_data[..8]);
+    init_packet.push(0);
+    init_packet.extend_from_slice(&capabilities.to_le_bytes()[..2]);
+    init_packet.extend_from_slice(&[0x21]); // UTF8_GENERAL_CI
+    init_packet.extend_from_slice(&[0x00, 0x00]); // status flags
+    init_packet.extend_from_slice(&capabilities.to_le_bytes()[2..]);
+    init_packet.extend_from_slice(&[auth_data.len() as u8]);
+    init_packet.extend_from_slice(&[0x00; 10][..]); // filler
+    init_packet.extend_from_slice(&auth_data[8..]);
+    init_packet.push(0);
+    init_packet.extend_from_slice(AUTH_PLUGIN_NAME.as_bytes());
+    init_packet.push(0);
+    init_packet
+}
 
 impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
     MysqlIntermediary<B, R, W>
@@ -325,6 +480,7 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
             shim,
             reader: r,
             writer: w,
+            client_capabilities: CapabilityFlags::empty(),
         };
         if mi.init().await? {
             mi.run().await?;
@@ -336,35 +492,30 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
         let auth_data =
             generate_auth_data().map_err(|_| other_error(OtherErrorKind::AuthDataErr))?;
 
-        let mut init_packet = Vec::with_capacity(
-            1 + 16 + 4 + 8 + 1 + 2 + 1 + 2 + 2 + 1 + 6 + 4 + 12 + 1 + AUTH_PLUGIN_NAME.len() + 1,
-        );
-        init_packet.extend_from_slice(&[10]); // protocol 10
-        init_packet.extend_from_slice(CURRENT_VERSION);
-        init_packet.extend_from_slice(&[0x08, 0x00, 0x00, 0x00]); // TODO: connection ID
-        init_packet.extend_from_slice(&auth_data[..8]);
-        init_packet.push(0);
-        init_packet.extend_from_slice(&CAPABILITIES.to_le_bytes()[..2]);
-        init_packet.extend_from_slice(&[0x21]); // UTF8_GENERAL_CI
-        init_packet.extend_from_slice(&[0x00, 0x00]); // status flags
-        init_packet.extend_from_slice(&CAPABILITIES.to_le_bytes()[2..]);
-        init_packet.extend_from_slice(&[auth_data.len() as u8]);
-        init_packet.extend_from_slice(&[0x00; 10][..]); // filler
-        init_packet.extend_from_slice(&auth_data[8..]);
-        init_packet.push(0);
-        init_packet.extend_from_slice(AUTH_PLUGIN_NAME.as_bytes());
-        init_packet.push(0);
-
-        self.writer.write_packet(&init_packet).await?;
+        self.writer
+            .write_packet(&greeting(&auth_data, CAPABILITIES))
+            .await?;
         self.writer.flush().await?;
 
-        let (seq, handshake_bytes) = self.reader.next().await?.ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::ConnectionAborted,
-                "peer terminated connection",
-            )
-        })?;
-        let handshake = commands::client_handshake(&handshake_bytes)
+        let (seq, handshake_bytes) = self.reader.next().await?.ok_or_else(peer_terminated)?;
+        let handshake_bytes = handshake_bytes.to_vec();
+        self.authenticate(&auth_data, seq, &handshake_bytes, None)
+            .await
+    }
+
+    /// Authenticate the client that sent the given handshake response packet, with sequence id
+    /// `seq`. Returns true if the client was successfully authenticated.
+    ///
+    /// * `tls_identity` - If the client is connected over TLS and presented a verified certificate,
+    ///   the name of the user it identifies.
+    async fn authenticate(
+        &mut self,
+        auth_data: &[u8],
+        seq: u8,
+        handshake_bytes: &[u8],
+        tls_identity: Option<String>,
+    ) -> Result<bool, io::Error> {
+        let handshake = commands::client_handshake(handshake_bytes)
             .map_err(|e| match e {
                 nom::Err::Incomplete(_) => io::Error::new(
                     io::ErrorKind::UnexpectedEof,
@@ -388,10 +539,13 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
             .1;
 
         self.writer.set_seq(seq + 1);
+        self.client_capabilities = handshake.capabilities;
 
         let username = handshake.username.to_owned();
         let password = handshake.password.to_vec();
         let client_auth_plugin = handshake.auth_plugin_name.map(|s| s.to_owned());
+        let compression =
+            Compression::negotiate(handshake.capabilities, handshake.zstd_compression_level);
 
         let handshake_password = if client_auth_plugin.iter().all(|apn| apn != AUTH_PLUGIN_NAME)
             // Some clients (at the very least certain versions of PHP's MySQL PDO library) send an
@@ -433,12 +587,8 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                 .await?;
             self.writer.flush().await?;
 
-            let (seq, auth_switch_response) = self.reader.next().await?.ok_or_else(|| {
-                io::Error::new(
-                    io::ErrorKind::ConnectionAborted,
-                    "peer terminated connection",
-                )
-            })?;
+            let (seq, auth_switch_response) =
+                self.reader.next().await?.ok_or_else(peer_terminated)?;
             self.writer.set_seq(seq + 1);
 
             auth_switch_response.to_vec()
@@ -446,16 +596,16 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
             password
         };
 
+        let password = self.shim.password_for_username(&username);
         let auth_success = !self.shim.require_authentication()
-            || self
-                .shim
-                .password_for_username(&username)
-                .map_or(false, |password| {
-                    let expected = hash_password(&password, &auth_data);
-                    let actual = handshake_password.as_slice();
-                    trace!(?expected, ?actual);
-                    expected == actual
-                });
+            // A verified client certificate authenticates the user it names
+            || (password.is_some() && tls_identity.as_deref() == Some(username.as_str()))
+            || password.map_or(false, |password| {
+                let expected = hash_password(&password, auth_data);
+                let actual = handshake_password.as_slice();
+                trace!(?expected, ?actual);
+                expected == actual
+            });
 
         if auth_success {
             debug!(%username, "Successfully authenticated client");
@@ -471,6 +621,13 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
         }
         self.writer.flush().await?;
 
+        // Everything after the response to the handshake is sent compressed, if the client asked
+        if let (true, Some(compression)) = (auth_success, compression) {
+            debug!(?compression, "Using compressed protocol");
+            self.reader.set_compression(compression);
+            self.writer.set_compression(compression);
+        }
+
         Ok(auth_success)
     }
 
@@ -489,7 +646,12 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                 .1;
             match cmd {
                 Command::Query(q) => {
-                    let w = QueryResultWriter::new(&mut self.writer, false);
+                    let w = QueryResultWriter::new(
+                        &mut self.writer,
+                        false,
+                        self.client_capabilities
+                            .contains(CapabilityFlags::CLIENT_MULTI_RESULTS),
+                    );
                     self.shim
                         .on_query(
                             ::std::str::from_utf8(q)
@@ -511,19 +673,26 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                         )
                         .await?;
                 }
-                Command::ResetStmtData(stmt) => {
-                    stmts
-                        .get_mut(&stmt)
-                        .ok_or_else(|| {
-                            io::Error::new(
-                                io::ErrorKind::InvalidData,
-                                format!("got reset data packet for unknown statement {}", stmt),
+                Command::ResetStmtData(stmt) => match stmts.get_mut(&stmt) {
+                    Some(state) => {
+                        state.long_data.clear();
+                        state.long_data_error = false;
+                        writers::write_ok_packet(&mut self.writer, 0, 0, StatusFlags::empty())
+                            .await?;
+                    }
+                    None => {
+                        writers::write_err(
+                            ErrorKind::ER_UNKNOWN_STMT_HANDLER,
+                            format!(
+                                "Unknown prepared statement handler ({}) given to mysqld_stmt_reset",
+                                stmt
                             )
-                        })?
-                        .long_data
-                        .clear();
-                    writers::write_ok_packet(&mut self.writer, 0, 0, StatusFlags::empty()).await?;
-                }
+                            .as_bytes(),
+                            &mut self.writer,
+                        )
+                        .await?;
+                    }
+                },
                 Command::Execute { stmt, params } => {
                     let state = stmts.get_mut(&stmt).ok_or_else(|| {
                         io::Error::new(
@@ -531,26 +700,36 @@ impl<B: MysqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                             format!("asked to execute unknown statement {}", stmt),
                         )
                     })?;
-                    {
+                    if state.long_data_error {
+                        state.long_data_error = false;
+                        writers::write_err(
+                            ErrorKind::ER_WRONG_ARGUMENTS,
+                            b"Incorrect arguments to mysqld_stmt_execute",
+                            &mut self.writer,
+                        )
+                        .await?;
+                    } else {
                         let params = params::ParamParser::new(params, state);
-                        let w = QueryResultWriter::new(&mut self.writer, true);
+                        let w = QueryResultWriter::new(
+                            &mut self.writer,
+                            true,
+                            self.client_capabilities
+                                .contains(CapabilityFlags::CLIENT_PS_MULTI_RESULTS),
+                        );
                         self.shim.on_execute(stmt, params, w).await?;
                     }
                     state.long_data.clear();
                 }
                 Command::SendLongData { stmt, param, data } => {
-                    stmts
-                        .get_mut(&stmt)
-                        .ok_or_else(|| {
-                            io::Error::new(
-                                io::ErrorKind::InvalidData,
-                                format!("got long data packet for unknown statement {}", stmt),
-                            )
-                        })?
-                        .long_data
-                        .entry(param)
-                        .or_insert_with(Vec::new)
-                        .extend(data);
+                    // NOTE: spec dictates no response from server, even on error, so errors are
+                    // reported when the statement is next executed
+                    match stmts.get_mut(&stmt) {
+                        Some(state) if param < state.params => {
+                            state.long_data.entry(param).or_default().extend(data);
+                        }
+                        Some(state) => state.long_data_error = true,
+                        None => debug!(%stmt, "Ignoring long data for unknown statement"),
+                    }
                 }
                 Command::Close(stmt) => {
                     self.shim.on_close(stmt).await;
@@ -3,14 +3,19 @@ use std::sync::Arc;
 
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+use crate::compression::Compression;
 use crate::error::{other_error, OtherErrorKind};
 
-const U24_MAX: usize = 16_777_215;
+pub(crate) const U24_MAX: usize = 16_777_215;
 
 pub struct PacketWriter<W> {
     pub seq: u8,
     w: W,
     queue: Vec<QueuedPacket>,
+    /// If set, packets are wrapped in compressed packets using this algorithm
+    compression: Option<Compression>,
+    /// The sequence id of the next compressed packet to write
+    compressed_seq: u8,
 }
 
 /// Type for packets being enqueued in the packet writer.
@@ -51,6 +56,24 @@ async fn write_all_vectored<'a, W: AsyncWrite + Unpin>(
     }
 }
 
+/// Write all of `slices` to `w`, wrapping them in compressed packets if `compression` is set
+async fn write_slices<'a, W: AsyncWrite + Unpin>(
+    w: &'a mut W,
+    compression: Option<Compression>,
+    compressed_seq: &mut u8,
+    slices: &'a mut [IoSlice<'a>],
+) -> io::Result<()> {
+    match compression {
+        None => write_all_vectored(w, slices).await,
+        Some(compression) => {
+            let data = slices.iter().map(|s| &s[..]).collect::<Vec<_>>().concat();
+            let mut out = Vec::with_capacity(data.len() + 7);
+            compression.encode(&data, compressed_seq, &mut out)?;
+            w.write_all(&out).await
+        }
+    }
+}
+
 // Gets an IoSlice to each of the packets currently enqueued in `queue`.
 fn queued_packet_slices(queue: &[QueuedPacket]) -> Vec<IoSlice<'_>> {
     if queue.is_empty() {
@@ -77,11 +100,21 @@ impl<W: AsyncWrite + Unpin> PacketWriter<W> {
             seq: 0,
             w,
             queue: Vec::new(),
+            compression: None,
+            compressed_seq: 0,
         }
     }
 
+    /// Set the sequence id of the next packet to write. Like MySQL, the sequence ids of
+    /// compressed packets are kept in step with the ids of the packets within them.
     pub fn set_seq(&mut self, seq: u8) {
         self.seq = seq;
+        self.compressed_seq = seq;
+    }
+
+    /// Wrap all packets written from now on in compressed packets using `compression`
+    pub(crate) fn set_compression(&mut self, compression: Compression) {
+        self.compression = Some(compression);
     }
 
     /// Flushes the writer. This function *must* be called before dropping the internal writer
@@ -118,7 +151,13 @@ impl<W: AsyncWrite + Unpin> PacketWriter<W> {
     pub async fn write_queued_packets(&mut self) -> Result<(), tokio::io::Error> {
         let mut slices = queued_packet_slices(&self.queue);
         if !slices.is_empty() {
-            write_all_vectored(&mut self.w, &mut slices).await?;
+            write_slices(
+                &mut self.w,
+                self.compression,
+                &mut self.compressed_seq,
+                &mut slices,
+            )
+            .await?;
             self.queue.clear();
         }
 
@@ -159,7 +198,13 @@ impl<W: AsyncWrite + Unpin> PacketWriter<W> {
             }
         }
 
-        write_all_vectored(&mut self.w, &mut slices).await?;
+        write_slices(
+            &mut self.w,
+            self.compression,
+            &mut self.compressed_seq,
+            &mut slices,
+        )
+        .await?;
         self.queue.clear();
 
         Ok(())
@@ -180,7 +225,13 @@ impl<W: AsyncWrite + Unpin> PacketWriter<W> {
             IoSlice::new(packet),
         ]);
 
-        write_all_vectored(&mut self.w, &mut slices).await?;
+        write_slices(
+            &mut self.w,
+            self.compression,
+            &mut self.compressed_seq,
+            &mut slices,
+        )
+        .await?;
 
         self.seq = self.seq.wrapping_add(1);
         Ok(())
@@ -192,6 +243,8 @@ pub struct PacketReader<R> {
     start: usize,
     remaining: usize,
     r: R,
+    /// If set, packets are read from compressed packets using this algorithm
+    compression: Option<Compression>,
 }
 
 impl<R> PacketReader<R> {
@@ -201,8 +254,14 @@ impl<R> PacketReader<R> {
             start: 0,
             remaining: 0,
             r,
+            compression: None,
         }
     }
+
+    /// Read all packets from now on from compressed packets using `compression`
+    pub(crate) fn set_compression(&mut self, compression: Compression) {
+        self.compression = Some(compression);
+    }
 }
 
 impl<R: AsyncRead + Unpin> PacketReader<R> {
@@ -243,20 +302,26 @@ impl<R: AsyncRead + Unpin> PacketReader<R> {
             // we need to read some more
             self.bytes.drain(0..self.start);
             self.start = 0;
-            let end = self.bytes.len();
-            let new_len = std::cmp::max(4096, end * 2);
-            self.bytes.resize(new_len, 0);
-            let read = {
-                let buf = self.bytes.get_mut(end..).ok_or_else(|| {
-                    other_error(OtherErrorKind::IndexErr {
-                        data: "self.bytes".to_string(),
-                        index: end,
-                        length: new_len,
-                    })
-                })?;
-                self.r.read(buf).await?
+            let read = match self.compression {
+                Some(compression) => self.read_compressed(compression).await?,
+                None => {
+                    let end = self.bytes.len();
+                    let new_len = std::cmp::max(4096, end * 2);
+                    self.bytes.resize(new_len, 0);
+                    let read = {
+                        let buf = self.bytes.get_mut(end..).ok_or_else(|| {
+                            other_error(OtherErrorKind::IndexErr {
+                                data: "self.bytes".to_string(),
+                                index: end,
+                                length: new_len,
+                            })
+                        })?;
+                        self.r.read(buf).await?
+                    };
+                    self.bytes.truncate(end + read);
+                    read
+                }
             };
-            self.bytes.truncate(end + read);
             self.remaining = self.bytes.len();
 
             if read == 0 {
@@ -271,6 +336,30 @@ impl<R: AsyncRead + Unpin> PacketReader<R> {
             }
         }
     }
+
+    /// Read a single compressed packet, and append its decompressed payload to the buffer.
+    /// Returns the number of bytes appended, which is 0 if the reader is at EOF.
+    async fn read_compressed(&mut self, compression: Compression) -> io::Result<usize> {
+        let mut header = [0u8; 7];
+        if self.r.read(&mut header[..1]).await? == 0 {
+            return Ok(0);
+        }
+        self.r.read_exact(&mut header[1..]).await?;
+
+        let payload_len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        let uncompressed_len = u32::from_le_bytes([header[4], header[5], header[6], 0]) as usize;
+
+        let mut payload = vec![0; payload_len];
+        self.r.read_exact(&mut payload).await?;
+        if uncompressed_len == 0 {
+            self.bytes.extend_from_slice(&payload);
+            Ok(payload_len)
+        } else {
+            self.bytes
+                .extend(compression.decompress(&payload, uncompressed_len)?);
+            Ok(uncompressed_len)
+        }
+    }
 }
 
 pub fn fullpacket(i: &[u8]) -> nom::IResult<&[u8], (u8, &[u8])> {
@@ -472,4 +561,35 @@ mod tests {
 
         assert!(reader.next().await.unwrap().is_none());
     }
+
+    #[tokio::test]
+    async fn test_compressed_write_read() {
+        for compression in [Compression::Zlib, Compression::Zstd { level: 3 }] {
+            let (u_out, u_in) = tokio::net::UnixStream::pair().unwrap();
+
+            let packets = vec![vec![0u8; 10], vec![1u8; 4096], vec![2u8; 100]];
+
+            let p = packets.clone();
+            tokio::spawn(async move {
+                let mut writer = PacketWriter::new(u_out);
+                writer.set_compression(compression);
+
+                for packet in &p {
+                    writer.enqueue_packet(packet.clone());
+                }
+                writer.flush().await.unwrap();
+            });
+
+            let mut reader = PacketReader::new(u_in);
+            reader.set_compression(compression);
+
+            for (seq, encoded) in packets.iter().enumerate() {
+                let decoded = reader.next().await.unwrap().unwrap();
+                assert_eq!(decoded.0, seq as u8);
+                assert_eq!(&decoded.1[..], encoded);
+            }
+
+            assert!(reader.next().await.unwrap().is_none());
+        }
+    }
 }
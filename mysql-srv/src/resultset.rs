@@ -116,17 +116,26 @@ pub struct QueryResultWriter<'a, W: AsyncWrite + Unpin> {
     pub(crate) is_bin: bool,
     pub(crate) writer: &'a mut PacketWriter<W>,
     last_end: Option<Finalizer>,
+    multi_results: bool,
 }
 
 impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
-    pub(crate) fn new(writer: &'a mut PacketWriter<W>, is_bin: bool) -> Self {
+    pub(crate) fn new(writer: &'a mut PacketWriter<W>, is_bin: bool, multi_results: bool) -> Self {
         QueryResultWriter {
             is_bin,
             writer,
             last_end: None,
+            multi_results,
         }
     }
 
+    /// Returns true if the client declared that it can receive more than one resultset in
+    /// response to this command (`CLIENT_MULTI_RESULTS` for queries, or `CLIENT_PS_MULTI_RESULTS`
+    /// for prepared statements), such as the results of a `CALL` to a stored procedure.
+    pub fn supports_multiple_results(&self) -> bool {
+        self.multi_results
+    }
+
     async fn finalize(&mut self, more_exists: bool) -> io::Result<()> {
         let mut status = match self.last_end {
             Some(Finalizer::Ok {
@@ -143,9 +152,9 @@ impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
             }
             _ => StatusFlags::empty(),
         };
-        if more_exists {
-            status.set(StatusFlags::SERVER_MORE_RESULTS_EXISTS, true);
-        }
+        // The status flags may have been copied from another server's response to a statement
+        // that did or didn't produce more results, so always set this flag ourselves
+        status.set(StatusFlags::SERVER_MORE_RESULTS_EXISTS, more_exists);
         match self.last_end.take() {
             None => Ok(()),
             Some(Finalizer::Ok {
@@ -472,6 +481,12 @@ impl<'a, W: AsyncWrite + Unpin + 'a> RowWriter<'a, W> {
         self
     }
 
+    /// Send the rows written so far to the client, rather than holding them until the resultset is
+    /// finished.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.result.writer.flush().await
+    }
+
     /// Reply to the client's query with an error.
     ///
     /// This also calls `no_more_results` implicitly.
@@ -0,0 +1,80 @@
+//! Support for [TLS connections][docs].
+//!
+//! A client that wants to use TLS replies to the server's initial handshake packet with an
+//! `SSLRequest` packet, which is a truncated handshake response packet with the `CLIENT_SSL`
+//! capability set, and then immediately starts the TLS handshake. The rest of the connection,
+//! starting with the client's full handshake response packet, is carried out over TLS.
+//!
+//! [docs]: https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_connection_phase.html#sect_protocol_connection_phase_initial_handshake_ssl_handshake
+
+use std::io;
+use std::pin::Pin;
+
+use openssl::nid::Nid;
+use openssl::ssl::{Ssl, SslAcceptor, SslRef};
+use openssl::x509::X509VerifyResult;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio_openssl::SslStream;
+
+use crate::myc::constants::CapabilityFlags;
+
+/// The length of an `SSLRequest` packet
+const SSL_REQUEST_LENGTH: usize = 32;
+
+/// Read a single packet from `stream`, without reading any bytes past its end.
+///
+/// The first packet sent by the client has to be read this way, since if it's an `SSLRequest`,
+/// the bytes following it are the start of the TLS handshake.
+pub(crate) async fn read_first_packet<S>(stream: &mut S) -> io::Result<(u8, Vec<u8>)>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = [0; 4];
+    stream.read_exact(&mut header).await?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    let mut payload = vec![0; len];
+    stream.read_exact(&mut payload).await?;
+    Ok((header[3], payload))
+}
+
+/// Returns true if `packet` is an `SSLRequest`, rather than a full handshake response
+pub(crate) fn is_ssl_request(packet: &[u8]) -> bool {
+    packet.len() == SSL_REQUEST_LENGTH
+        && CapabilityFlags::from_bits_truncate(u32::from_le_bytes([
+            packet[0], packet[1], packet[2], packet[3],
+        ]))
+        .contains(CapabilityFlags::CLIENT_SSL)
+}
+
+/// Complete the TLS handshake with a client that has sent an `SSLRequest`
+pub(crate) async fn accept<S>(acceptor: &SslAcceptor, stream: S) -> io::Result<SslStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let ssl = Ssl::new(acceptor.context()).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    let mut stream =
+        SslStream::new(ssl, stream).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    Pin::new(&mut stream)
+        .accept()
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::ConnectionAborted, e))?;
+    Ok(stream)
+}
+
+/// Returns the name of the user identified by the certificate presented by the client, if it
+/// presented one and it was verified: the common name of the certificate's subject.
+pub(crate) fn client_identity(ssl: &SslRef) -> Option<String> {
+    if ssl.verify_result() != X509VerifyResult::OK {
+        return None;
+    }
+    let certificate = ssl.peer_certificate()?;
+    let common_name = certificate
+        .subject_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()?;
+    common_name
+        .data()
+        .as_utf8()
+        .ok()
+        .map(|name| name.to_string())
+}
@@ -339,7 +339,7 @@ mod tests {
     use super::Value;
     use crate::myc::io::{ParseBuf, WriteMysqlExt};
     use crate::myc::proto::MySerialize;
-    use crate::{myc, Column, ColumnFlags, ColumnType};
+    use crate::{myc, Column, ColumnFlags, ColumnType, UTF8_GENERAL_CI};
 
     macro_rules! rt {
         ($name:ident, $t:ty, $v:expr, $ct:expr) => {
@@ -354,6 +354,9 @@ mod tests {
                     column: String::new(),
                     coltype: $ct,
                     colflags: ColumnFlags::empty(),
+                    column_length: 1024,
+                    character_set: UTF8_GENERAL_CI,
+                    decimals: 0,
                 };
 
                 if !$sig {
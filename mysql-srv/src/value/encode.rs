@@ -546,15 +546,23 @@ impl ToMysqlValue for NaiveDateTime {
         match c.coltype {
             ColumnType::MYSQL_TYPE_DATETIME | ColumnType::MYSQL_TYPE_TIMESTAMP => {
                 let us = self.nanosecond() / 1_000;
+                let midnight = self.time() == NaiveTime::from_hms(0, 0, 0);
 
+                // Like MySQL itself, leave out the time if it's midnight, and the microseconds if
+                // there aren't any, so clients get exactly the same bytes as they would from MySQL
                 if us != 0 {
                     w.write_u8(11u8)?;
-                } else {
+                } else if !midnight {
                     w.write_u8(7u8)?;
+                } else {
+                    w.write_u8(4u8)?;
                 }
                 w.write_u16::<LittleEndian>(self.year() as u16)?;
                 w.write_u8(self.month() as u8)?;
                 w.write_u8(self.day() as u8)?;
+                if midnight {
+                    return Ok(());
+                }
                 w.write_u8(self.hour() as u8)?;
                 w.write_u8(self.minute() as u8)?;
                 w.write_u8(self.second() as u8)?;
@@ -756,7 +764,7 @@ mod tests {
     use crate::myc::proto::MyDeserialize;
     use crate::myc::value::convert::from_value;
     use crate::myc::value::{BinValue, TextValue, Value, ValueDeserializer};
-    use crate::{Column, ColumnFlags, ColumnType};
+    use crate::{Column, ColumnFlags, ColumnType, UTF8_GENERAL_CI};
 
     mod roundtrip_text {
         use mysql_time::MysqlTime;
@@ -835,6 +843,9 @@ mod tests {
                         column: String::new(),
                         coltype: $ct,
                         colflags: ColumnFlags::empty(),
+                        column_length: 1024,
+                        character_set: UTF8_GENERAL_CI,
+                        decimals: 0,
                     };
 
                     if !$sig {
@@ -994,6 +1005,21 @@ mod tests {
             chrono::Utc.ymd(1989, 12, 7).and_hms(8, 0, 4).naive_utc(),
             ColumnType::MYSQL_TYPE_DATETIME
         );
+        rt!(
+            datetime_midnight,
+            chrono::NaiveDateTime,
+            chrono::Utc.ymd(1989, 12, 7).and_hms(0, 0, 0).naive_utc(),
+            ColumnType::MYSQL_TYPE_DATETIME
+        );
+        rt!(
+            datetime_micros,
+            chrono::NaiveDateTime,
+            chrono::Utc
+                .ymd(1989, 12, 7)
+                .and_hms_micro(8, 0, 4, 123_456)
+                .naive_utc(),
+            ColumnType::MYSQL_TYPE_DATETIME
+        );
         rt!(
             time,
             MysqlTime,
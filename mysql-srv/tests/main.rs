@@ -96,6 +96,9 @@ where
                         column: "@@max_allowed_packet".to_owned(),
                         coltype: myc::constants::ColumnType::MYSQL_TYPE_LONG,
                         colflags: myc::constants::ColumnFlags::UNSIGNED_FLAG,
+                        column_length: 1024,
+                        character_set: myc::constants::UTF8_GENERAL_CI,
+                        decimals: 0,
                     }];
                     let mut w = results.start(cols).await?;
                     w.write_row(iter::once(67108864u32))?;
@@ -294,6 +297,9 @@ fn no_rows() {
         column: "a".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
     TestingShim::new(
         move |_, w| {
@@ -376,6 +382,9 @@ fn it_queries_nulls() {
                 column: "a".to_owned(),
                 coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
                 colflags: myc::constants::ColumnFlags::empty(),
+                column_length: 1024,
+                character_set: myc::constants::UTF8_GENERAL_CI,
+                decimals: 0,
             }];
             Box::pin(async move {
                 let mut w = w.start(&cols).await?;
@@ -403,6 +412,9 @@ fn it_queries() {
                 column: "a".to_owned(),
                 coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
                 colflags: myc::constants::ColumnFlags::empty(),
+                column_length: 1024,
+                character_set: myc::constants::UTF8_GENERAL_CI,
+                decimals: 0,
             }];
             Box::pin(async move {
                 let mut w = w.start(&cols).await?;
@@ -430,6 +442,9 @@ fn multi_result() {
                 column: "a".to_owned(),
                 coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
                 colflags: myc::constants::ColumnFlags::empty(),
+                column_length: 1024,
+                character_set: myc::constants::UTF8_GENERAL_CI,
+                decimals: 0,
             }];
             Box::pin(async move {
                 let mut row = w.start(&cols).await?;
@@ -458,6 +473,48 @@ fn multi_result() {
     })
 }
 
+#[test]
+fn client_supports_multiple_results() {
+    TestingShim::new(
+        |_, w| {
+            Box::pin(async move {
+                assert!(w.supports_multiple_results());
+                w.completed(0, 0, None).await
+            })
+        },
+        |_| unreachable!(),
+        |_, _, _| unreachable!(),
+        |_, _| unreachable!(),
+    )
+    .test(|db| {
+        db.query_drop("CALL p()").unwrap();
+    })
+}
+
+#[test]
+fn last_result_clears_more_results_flag() {
+    TestingShim::new(
+        |_, w| {
+            Box::pin(async move {
+                // As if copied from an upstream response that was followed by more results
+                w.completed(
+                    0,
+                    0,
+                    Some(myc::constants::StatusFlags::SERVER_MORE_RESULTS_EXISTS),
+                )
+                .await
+            })
+        },
+        |_| unreachable!(),
+        |_, _, _| unreachable!(),
+        |_, _| unreachable!(),
+    )
+    .test(|db| {
+        db.query_drop("DELETE FROM foo").unwrap();
+        db.query_drop("DELETE FROM foo").unwrap();
+    })
+}
+
 #[test]
 fn it_queries_many_rows() {
     TestingShim::new(
@@ -468,12 +525,18 @@ fn it_queries_many_rows() {
                     column: "a".to_owned(),
                     coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
                     colflags: myc::constants::ColumnFlags::empty(),
+                    column_length: 1024,
+                    character_set: myc::constants::UTF8_GENERAL_CI,
+                    decimals: 0,
                 },
                 Column {
                     table: String::new(),
                     column: "b".to_owned(),
                     coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
                     colflags: myc::constants::ColumnFlags::empty(),
+                    column_length: 1024,
+                    character_set: myc::constants::UTF8_GENERAL_CI,
+                    decimals: 0,
                 },
             ];
             Box::pin(async move {
@@ -508,6 +571,9 @@ fn it_prepares() {
         column: "a".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
     let cols2 = cols.clone();
     let params = vec![Column {
@@ -515,6 +581,9 @@ fn it_prepares() {
         column: "c".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
 
     TestingShim::new(
@@ -565,42 +634,63 @@ fn insert_exec() {
             column: "username".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_VARCHAR,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "email".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_VARCHAR,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "pw".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_VARCHAR,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "created".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_DATETIME,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "session".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_VARCHAR,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "rss".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_VARCHAR,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "mail".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_VARCHAR,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
     ];
 
@@ -707,6 +797,9 @@ fn send_long() {
         column: "a".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
     let cols2 = cols.clone();
     let params = vec![Column {
@@ -714,6 +807,9 @@ fn send_long() {
         column: "c".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_BLOB,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
 
     TestingShim::new(
@@ -764,12 +860,18 @@ fn it_prepares_many() {
             column: "a".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "b".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
     ];
     let cols2 = cols.clone();
@@ -816,6 +918,9 @@ fn prepared_empty() {
         column: "a".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
     let cols2 = cols;
     let params = vec![Column {
@@ -823,6 +928,9 @@ fn prepared_empty() {
         column: "c".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
 
     TestingShim::new(
@@ -853,6 +961,9 @@ fn prepared_no_params() {
         column: "a".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
     let cols2 = cols.clone();
     let params = vec![];
@@ -888,12 +999,18 @@ fn prepared_nulls() {
             column: "a".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "b".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
     ];
     let cols2 = cols.clone();
@@ -903,12 +1020,18 @@ fn prepared_nulls() {
             column: "c".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: String::new(),
             column: "d".to_owned(),
             coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
             colflags: myc::constants::ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: myc::constants::UTF8_GENERAL_CI,
+            decimals: 0,
         },
     ];
 
@@ -965,6 +1088,9 @@ fn prepared_no_rows() {
         column: "a".to_owned(),
         coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
         colflags: myc::constants::ColumnFlags::empty(),
+        column_length: 1024,
+        character_set: myc::constants::UTF8_GENERAL_CI,
+        decimals: 0,
     }];
     let cols2 = cols.clone();
     TestingShim::new(
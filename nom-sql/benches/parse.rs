@@ -0,0 +1,151 @@
+//! This module contains [`criterion`] benchmarks for parsing a few representative kinds of
+//! queries:
+//!
+//! * Short OLTP-style reads and writes, which make up most of the queries sent by applications
+//! * A query with 50 joins, of the sort generated by ORMs eagerly loading deeply nested
+//!   associations
+//! * An `INSERT` statement with a large list of values, of the sort sent by bulk loads
+//!
+//! To run these benchmarks:
+//!
+//! ```notrust
+//! $ cargo criterion -p nom-sql --bench parse
+//! ```
+//!
+//! Criterion compares each run against the results of the previous one. To track regressions
+//! across a change, save a baseline before making it, then compare against that baseline after:
+//!
+//! ```notrust
+//! $ cargo bench -p nom-sql --bench parse -- --save-baseline before
+//! $ cargo bench -p nom-sql --bench parse -- --baseline before
+//! ```
+
+use std::fmt::Write;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nom_sql::{parse_query, Dialect};
+
+const OLTP_QUERIES: &[(&str, &str, Dialect)] = &[
+    (
+        "point_select",
+        "SELECT id, name, email, created_at FROM users WHERE id = ?",
+        Dialect::MySQL,
+    ),
+    (
+        "range_select",
+        "SELECT `orders`.`id`, `orders`.`total` FROM `orders` WHERE `orders`.`customer_id` = ? \
+         AND `orders`.`status` IN ('pending', 'shipped') ORDER BY `orders`.`created_at` DESC \
+         LIMIT 20",
+        Dialect::MySQL,
+    ),
+    (
+        "insert",
+        "INSERT INTO sessions (user_id, token, expires_at) VALUES (?, ?, ?)",
+        Dialect::MySQL,
+    ),
+    (
+        "update",
+        "UPDATE users SET last_login = ?, login_count = login_count + 1 WHERE id = ?",
+        Dialect::MySQL,
+    ),
+    (
+        "delete",
+        "DELETE FROM sessions WHERE expires_at < ?",
+        Dialect::MySQL,
+    ),
+    (
+        "point_select_postgres",
+        "SELECT \"users\".\"id\", \"users\".\"name\" FROM \"users\" WHERE \"users\".\"id\" = $1",
+        Dialect::PostgreSQL,
+    ),
+];
+
+/// Builds a query joining `num_joins` tables onto a root table, each through the one before it,
+/// selecting every column of every table, the way ORMs do when eagerly loading associations
+fn many_joins_query(num_joins: usize) -> String {
+    let mut query = String::from("SELECT t0.id, t0.name, t0.created_at");
+    for i in 1..=num_joins {
+        write!(
+            query,
+            ", t{i}.id, t{i}.t{}_id, t{i}.value, t{i}.updated_at",
+            i - 1
+        )
+        .unwrap();
+    }
+    query.push_str(" FROM table_0 AS t0");
+    for i in 1..=num_joins {
+        write!(
+            query,
+            " LEFT OUTER JOIN table_{i} AS t{i} ON t{i}.t{}_id = t{}.id",
+            i - 1,
+            i - 1
+        )
+        .unwrap();
+    }
+    query.push_str(" WHERE t0.id = ? AND t0.deleted_at IS NULL");
+    query
+}
+
+/// Builds an `INSERT` statement with `num_rows` rows of values
+fn large_insert_query(num_rows: usize) -> String {
+    let mut query =
+        String::from("INSERT INTO events (id, user_id, kind, payload, score, created_at) VALUES ");
+    for i in 0..num_rows {
+        if i > 0 {
+            query.push_str(", ");
+        }
+        write!(
+            query,
+            "({i}, {}, 'page_view', '{{\"path\": \"/items/{i}\", \"referrer\": null}}', {}.5, \
+             '2022-03-01 12:00:00')",
+            i % 97,
+            i % 10
+        )
+        .unwrap();
+    }
+    query
+}
+
+fn oltp(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Parse OLTP queries");
+    for (name, query, dialect) in OLTP_QUERIES {
+        parse_query(*dialect, query).unwrap();
+        group.throughput(Throughput::Bytes(query.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), query, |b, query| {
+            b.iter(|| parse_query(*dialect, query).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn many_joins(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Parse queries with many joins");
+    for num_joins in [10, 50] {
+        let query = many_joins_query(num_joins);
+        parse_query(Dialect::MySQL, &query).unwrap();
+        group.throughput(Throughput::Bytes(query.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_joins),
+            &query,
+            |b, query| b.iter(|| parse_query(Dialect::MySQL, query).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn large_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Parse large INSERT statements");
+    group.sample_size(20);
+    for num_rows in [100, 1_000, 10_000] {
+        let query = large_insert_query(num_rows);
+        parse_query(Dialect::MySQL, &query).unwrap();
+        group.throughput(Throughput::Bytes(query.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(num_rows), &query, |b, query| {
+            b.iter(|| parse_query(Dialect::MySQL, query).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, oltp, many_joins, large_insert);
+criterion_main!(benches);
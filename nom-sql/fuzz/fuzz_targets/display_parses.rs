@@ -0,0 +1,29 @@
+//! Checks that whenever arbitrary input parses as a MySQL statement, displaying that statement
+//! gives SQL that parses too.
+//!
+//! This is weaker than the round-trip property tested in `tests/round_trip.rs`, since displaying
+//! some statements loses information (such as the precision of floating-point literals), but it
+//! applies to everything the parser accepts rather than only to generated statements.
+//!
+//! To run this target:
+//!
+//! ```notrust
+//! $ cd nom-sql && cargo +nightly fuzz run display_parses
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nom_sql::{parse_query, parse_query_bytes, Dialect};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(statement) = parse_query_bytes(Dialect::MySQL, data) {
+        let displayed = statement.to_string();
+        if let Err(e) = parse_query(Dialect::MySQL, &displayed) {
+            panic!(
+                "Displayed statement failed to parse: {}\n{}\n{:?}",
+                e, displayed, statement
+            );
+        }
+    }
+});
@@ -0,0 +1,18 @@
+//! Checks that parsing arbitrary input in either dialect never panics.
+//!
+//! To run this target:
+//!
+//! ```notrust
+//! $ cd nom-sql && cargo +nightly fuzz run parse_query
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nom_sql::{parse_query_bytes, Dialect};
+
+fuzz_target!(|data: &[u8]| {
+    for dialect in [Dialect::MySQL, Dialect::PostgreSQL] {
+        let _ = parse_query_bytes(dialect, data);
+    }
+});
@@ -4,16 +4,17 @@
 use std::{fmt, str};
 
 use nom::branch::alt;
-use nom::bytes::complete::tag_no_case;
+use nom::bytes::complete::{tag, tag_no_case};
 use nom::combinator::{map, opt};
-use nom::multi::separated_list0;
-use nom::sequence::{preceded, terminated};
+use nom::multi::{separated_list0, separated_list1};
+use nom::sequence::{delimited, preceded, terminated};
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
 use crate::column::{column_specification, ColumnSpecification};
 use crate::common::{
-    literal, schema_table_reference_no_alias, statement_terminator, ws_sep_comma, Literal, TableKey,
+    literal, schema_table_reference_no_alias, statement_terminator, value_list, ws_sep_comma,
+    Literal, TableKey,
 };
 use crate::create::key_specification;
 use crate::table::Table;
@@ -295,6 +296,236 @@ pub fn alter_table_statement(
     }
 }
 
+/// Compression algorithm for the on-disk data of a base table, as specified in an
+/// `ALTER READYSET SET COMPRESSION` statement
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum CompressionAlgorithm {
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl fmt::Display for CompressionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "NONE"),
+            Self::Lz4 => write!(f, "LZ4"),
+            Self::Zstd => write!(f, "ZSTD"),
+        }
+    }
+}
+
+fn compression_algorithm(i: &[u8]) -> IResult<&[u8], CompressionAlgorithm> {
+    alt((
+        map(tag_no_case("none"), |_| CompressionAlgorithm::None),
+        map(tag_no_case("lz4"), |_| CompressionAlgorithm::Lz4),
+        map(tag_no_case("zstd"), |_| CompressionAlgorithm::Zstd),
+    ))(i)
+}
+
+/// `ALTER READYSET` statements
+///
+/// This is a non-standard ReadySet-specific extension to SQL
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum AlterReadysetStatement {
+    /// Manually trigger a compaction of the persisted state for a base table
+    CompactTable(Table),
+    /// Change the compression options for the persisted state of a base table, rewriting all
+    /// existing data with the new options
+    SetCompression {
+        table: Table,
+        algorithm: CompressionAlgorithm,
+        /// Whether to build shared compression dictionaries for the table's data
+        dictionary: bool,
+    },
+    /// Override the column a base table is sharded by, rather than having it chosen automatically
+    ShardTable { table: Table, column: SqlIdentifier },
+}
+
+impl fmt::Display for AlterReadysetStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ALTER READYSET ")?;
+        match self {
+            Self::CompactTable(table) => write!(f, "COMPACT TABLE {}", table),
+            Self::SetCompression {
+                table,
+                algorithm,
+                dictionary,
+            } => {
+                write!(f, "SET COMPRESSION {}", algorithm)?;
+                if *dictionary {
+                    write!(f, " WITH DICTIONARY")?;
+                }
+                write!(f, " ON TABLE {}", table)
+            }
+            Self::ShardTable { table, column } => {
+                write!(f, "TABLE {} SHARD BY (`{}`)", table, column)
+            }
+        }
+    }
+}
+
+fn compact_table(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], AlterReadysetStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("compact")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("table")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, table) = schema_table_reference_no_alias(dialect)(i)?;
+
+        Ok((i, AlterReadysetStatement::CompactTable(table)))
+    }
+}
+
+fn set_compression(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], AlterReadysetStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("set")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("compression")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, algorithm) = compression_algorithm(i)?;
+        let (i, dictionary) = opt(preceded(
+            whitespace1,
+            terminated(
+                tag_no_case("with"),
+                preceded(whitespace1, tag_no_case("dictionary")),
+            ),
+        ))(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("on")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("table")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, table) = schema_table_reference_no_alias(dialect)(i)?;
+
+        Ok((
+            i,
+            AlterReadysetStatement::SetCompression {
+                table,
+                algorithm,
+                dictionary: dictionary.is_some(),
+            },
+        ))
+    }
+}
+
+fn shard_table(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], AlterReadysetStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("table")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, table) = schema_table_reference_no_alias(dialect)(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("shard")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("by")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = tag("(")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, column) = dialect.identifier()(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = tag(")")(i)?;
+
+        Ok((i, AlterReadysetStatement::ShardTable { table, column }))
+    }
+}
+
+pub fn alter_readyset_statement(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], AlterReadysetStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("alter")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("readyset")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, statement) = alt((
+            compact_table(dialect),
+            set_compression(dialect),
+            shard_table(dialect),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+
+        Ok((i, statement))
+    }
+}
+
+/// `ALTER CACHE <name> PREFILL WHERE KEY IN (<key>, ...)`, which fills the partial state of the
+/// named cache for each of the given keys, so that reads of those keys don't miss once traffic is
+/// shifted to ReadySet. Each key is either a single literal, or a parenthesized list of literals
+/// for caches keyed on more than one column.
+///
+/// This is a non-standard ReadySet-specific extension to SQL
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct AlterCacheStatement {
+    pub name: SqlIdentifier,
+    pub prefill: Vec<Vec<Literal>>,
+}
+
+impl fmt::Display for AlterCacheStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ALTER CACHE `{}` PREFILL WHERE KEY IN (", self.name)?;
+        for (i, key) in self.prefill.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            match key.as_slice() {
+                [value] => write!(f, "{}", value)?,
+                values => write!(
+                    f,
+                    "({})",
+                    values
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+fn prefill_key(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<Literal>> {
+    move |i| {
+        alt((
+            delimited(
+                terminated(tag("("), whitespace0),
+                value_list(dialect),
+                preceded(whitespace0, tag(")")),
+            ),
+            map(literal(dialect), |value| vec![value]),
+        ))(i)
+    }
+}
+
+pub fn alter_cache_statement(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], AlterCacheStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("alter")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("cache")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, name) = dialect.identifier()(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("prefill")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("where")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("key")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("in")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, prefill) = delimited(
+            terminated(tag("("), whitespace0),
+            separated_list1(ws_sep_comma, prefill_key(dialect)),
+            preceded(whitespace0, tag(")")),
+        )(i)?;
+        let (i, _) = statement_terminator(i)?;
+
+        Ok((i, AlterCacheStatement { name, prefill }))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -326,6 +557,7 @@ mod tests {
             table: Table {
                 name: "employees".into(),
                 schema: None,
+                sample: None,
                 alias: None,
             },
             definitions: vec![
@@ -353,6 +585,154 @@ mod tests {
         assert_eq!(result.unwrap().1, expected);
     }
 
+    #[test]
+    fn parse_alter_readyset_compact_table() {
+        let res = alter_readyset_statement(Dialect::MySQL)(b"ALTER READYSET COMPACT TABLE t1;");
+        assert_eq!(
+            res.unwrap().1,
+            AlterReadysetStatement::CompactTable(Table::from("t1"))
+        );
+
+        let res =
+            alter_readyset_statement(Dialect::PostgreSQL)(b"alter readyset compact table s.t1");
+        assert_eq!(
+            res.unwrap().1,
+            AlterReadysetStatement::CompactTable(Table::from(("s", "t1")))
+        );
+    }
+
+    #[test]
+    fn display_alter_readyset_compact_table() {
+        let stmt = AlterReadysetStatement::CompactTable(Table::from("t1"));
+        assert_eq!(stmt.to_string(), "ALTER READYSET COMPACT TABLE `t1`");
+    }
+
+    #[test]
+    fn parse_alter_readyset_set_compression() {
+        let res = alter_readyset_statement(Dialect::MySQL)(
+            b"ALTER READYSET SET COMPRESSION ZSTD WITH DICTIONARY ON TABLE t1;",
+        );
+        assert_eq!(
+            res.unwrap().1,
+            AlterReadysetStatement::SetCompression {
+                table: Table::from("t1"),
+                algorithm: CompressionAlgorithm::Zstd,
+                dictionary: true,
+            }
+        );
+
+        let res = alter_readyset_statement(Dialect::PostgreSQL)(
+            b"alter readyset set compression none on table s.t1",
+        );
+        assert_eq!(
+            res.unwrap().1,
+            AlterReadysetStatement::SetCompression {
+                table: Table::from(("s", "t1")),
+                algorithm: CompressionAlgorithm::None,
+                dictionary: false,
+            }
+        );
+    }
+
+    #[test]
+    fn display_alter_readyset_set_compression() {
+        let stmt = AlterReadysetStatement::SetCompression {
+            table: Table::from("t1"),
+            algorithm: CompressionAlgorithm::Lz4,
+            dictionary: true,
+        };
+        assert_eq!(
+            stmt.to_string(),
+            "ALTER READYSET SET COMPRESSION LZ4 WITH DICTIONARY ON TABLE `t1`"
+        );
+    }
+
+    #[test]
+    fn parse_alter_readyset_shard_table() {
+        let res = alter_readyset_statement(Dialect::MySQL)(
+            b"ALTER READYSET TABLE t1 SHARD BY (tenant_id);",
+        );
+        assert_eq!(
+            res.unwrap().1,
+            AlterReadysetStatement::ShardTable {
+                table: Table::from("t1"),
+                column: "tenant_id".into(),
+            }
+        );
+
+        let res = alter_readyset_statement(Dialect::PostgreSQL)(
+            b"alter readyset table s.t1 shard by(\"tenant_id\")",
+        );
+        assert_eq!(
+            res.unwrap().1,
+            AlterReadysetStatement::ShardTable {
+                table: Table::from(("s", "t1")),
+                column: "tenant_id".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn display_alter_readyset_shard_table() {
+        let stmt = AlterReadysetStatement::ShardTable {
+            table: Table::from("t1"),
+            column: "tenant_id".into(),
+        };
+        assert_eq!(
+            stmt.to_string(),
+            "ALTER READYSET TABLE `t1` SHARD BY (`tenant_id`)"
+        );
+    }
+
+    #[test]
+    fn parse_alter_cache_prefill() {
+        let res = alter_cache_statement(Dialect::MySQL)(
+            b"ALTER CACHE q1 PREFILL WHERE KEY IN (1, (2, 'a'), ( 3 , 'b' ));",
+        );
+        assert_eq!(
+            res.unwrap().1,
+            AlterCacheStatement {
+                name: "q1".into(),
+                prefill: vec![
+                    vec![Literal::Integer(1)],
+                    vec![Literal::Integer(2), Literal::String("a".into())],
+                    vec![Literal::Integer(3), Literal::String("b".into())],
+                ],
+            }
+        );
+
+        let res = alter_cache_statement(Dialect::PostgreSQL)(
+            b"alter cache \"q1\" prefill where key in('a')",
+        );
+        assert_eq!(
+            res.unwrap().1,
+            AlterCacheStatement {
+                name: "q1".into(),
+                prefill: vec![vec![Literal::String("a".into())]],
+            }
+        );
+
+        assert!(
+            alter_cache_statement(Dialect::MySQL)(b"ALTER CACHE q1 PREFILL WHERE KEY IN ()")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn display_alter_cache_prefill() {
+        let stmt = AlterCacheStatement {
+            name: "q1".into(),
+            prefill: vec![
+                vec![Literal::Integer(1)],
+                vec![Literal::Integer(2), Literal::String("a".into())],
+            ],
+        };
+        assert_eq!(
+            stmt.to_string(),
+            "ALTER CACHE `q1` PREFILL WHERE KEY IN (1, (2, 'a'))"
+        );
+    }
+
     mod mysql {
         use super::*;
         use crate::common::ReferentialAction;
@@ -365,6 +745,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::AddColumn(ColumnSpecification {
@@ -388,6 +769,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![
@@ -422,6 +804,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::DropColumn {
@@ -440,6 +823,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::DropColumn {
@@ -458,6 +842,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::AlterColumn {
@@ -478,6 +863,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::AlterColumn {
@@ -651,6 +1037,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::AddColumn(ColumnSpecification {
@@ -667,6 +1054,25 @@ mod tests {
             assert_eq!(result.unwrap().1, expected);
         }
 
+        #[test]
+        fn parse_add_primary_key_constraint() {
+            let qstring = "ALTER TABLE public.t ADD CONSTRAINT t_pkey PRIMARY KEY (id)";
+            let expected = AlterTableStatement {
+                table: Table {
+                    name: "t".into(),
+                    schema: Some("public".into()),
+                    sample: None,
+                    alias: None,
+                },
+                definitions: vec![AlterTableDefinition::AddKey(TableKey::PrimaryKey {
+                    name: Some("t_pkey".into()),
+                    columns: vec![Column::from("id")],
+                })],
+            };
+            let result = alter_table_statement(Dialect::PostgreSQL)(qstring.as_bytes());
+            assert_eq!(result.unwrap().1, expected);
+        }
+
         #[test]
         fn parse_add_two_columns() {
             let qstring = "ALTER TABLE \"t\" ADD COLUMN \"c\" INT, ADD COLUMN \"d\" TEXT";
@@ -674,6 +1080,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![
@@ -708,6 +1115,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::DropColumn {
@@ -726,6 +1134,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::DropColumn {
@@ -744,6 +1153,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::AlterColumn {
@@ -764,6 +1174,7 @@ mod tests {
                 table: Table {
                     name: "t".into(),
                     schema: None,
+                    sample: None,
                     alias: None,
                 },
                 definitions: vec![AlterTableDefinition::AlterColumn {
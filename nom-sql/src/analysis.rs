@@ -6,8 +6,9 @@ use std::iter;
 use maplit::hashset;
 
 use crate::{
-    CacheInner, Column, CreateCacheStatement, Expression, FieldDefinitionExpression,
-    FieldReference, FunctionExpression, InValue, JoinConstraint, SelectStatement, SqlQuery, Table,
+    AlterReadysetStatement, CacheInner, Column, CreateCacheStatement, DeclareCursorStatement,
+    Expression, FieldDefinitionExpression, FieldReference, FunctionExpression, InValue,
+    JoinConstraint, JoinRightSide, SelectStatement, SqlQuery, Table,
 };
 
 /// Extension trait providing the `referred_tables` method to various parts of the AST
@@ -21,8 +22,21 @@ impl ReferredTables for SqlQuery {
         match *self {
             SqlQuery::CreateTable(ref ctq) => hashset![ctq.table.clone()],
             SqlQuery::AlterTable(ref atq) => hashset![atq.table.clone()],
+            SqlQuery::AlterReadyset(AlterReadysetStatement::CompactTable(ref t))
+            | SqlQuery::AlterReadyset(AlterReadysetStatement::SetCompression {
+                table: ref t,
+                ..
+            })
+            | SqlQuery::AlterReadyset(AlterReadysetStatement::ShardTable {
+                table: ref t, ..
+            }) => {
+                hashset![t.clone()]
+            }
             SqlQuery::Insert(ref iq) => hashset![iq.table.clone()],
-            SqlQuery::Select(ref sq) => sq.tables.iter().cloned().collect(),
+            SqlQuery::Select(ref sq)
+            | SqlQuery::DeclareCursor(DeclareCursorStatement { query: ref sq, .. }) => {
+                sq.tables.iter().cloned().collect()
+            }
             SqlQuery::CreateCache(CreateCacheStatement { inner: ref i, .. }) => match i {
                 CacheInner::Statement(sq) => sq.tables.iter().cloned().collect(),
                 CacheInner::Id(_) => HashSet::new(),
@@ -40,6 +54,11 @@ impl ReferredTables for SqlQuery {
                 // _existing_ tables that are utilized in the query.
                 .map(|op| op.from.clone())
                 .collect(),
+            SqlQuery::Maintenance(ref m) => m.tables.iter().cloned().collect(),
+            SqlQuery::Merge(ref m) => match m.source {
+                JoinRightSide::Table(ref source) => hashset![m.table.clone(), source.clone()],
+                _ => hashset![m.table.clone()],
+            },
             // If the type does not have any referred tables, we return an
             // empty hashset.
             SqlQuery::CreateView(_)
@@ -54,7 +73,16 @@ impl ReferredTables for SqlQuery {
             | SqlQuery::Use(_)
             | SqlQuery::Show(_)
             | SqlQuery::Explain(_)
-            | SqlQuery::DropCache(_) => HashSet::new(),
+            | SqlQuery::DropCache(_)
+            | SqlQuery::DropAllCaches(_)
+            | SqlQuery::AlterCache(_)
+            | SqlQuery::FlushWait(_)
+            | SqlQuery::Fetch(_)
+            | SqlQuery::CloseCursor(_)
+            | SqlQuery::Values(_)
+            | SqlQuery::Prepare(_)
+            | SqlQuery::Execute(_)
+            | SqlQuery::Deallocate(_) => HashSet::new(),
         }
     }
 }
@@ -136,7 +164,7 @@ impl<'a> ReferredColumnsIter<'a> {
         match fexpr {
             Avg { expr, .. } => self.visit_expr(expr),
             Count { expr, .. } => self.visit_expr(expr),
-            CountStar => None,
+            CountStar | Unsupported { .. } => None,
             Sum { expr, .. } => self.visit_expr(expr),
             Max(arg) => self.visit_expr(arg),
             Min(arg) => self.visit_expr(arg),
@@ -236,7 +264,7 @@ impl<'a> ReferredColumnsMut<'a> {
         match fexpr {
             Avg { expr, .. } => self.visit_expr(expr),
             Count { expr, .. } => self.visit_expr(expr),
-            CountStar => None,
+            CountStar | Unsupported { .. } => None,
             Sum { expr, .. } => self.visit_expr(expr),
             Max(arg) => self.visit_expr(arg),
             Min(arg) => self.visit_expr(arg),
@@ -332,7 +360,7 @@ impl SelectStatement {
                     columns_to_visit.extend(cols);
                     None
                 }
-                JoinConstraint::Empty => None,
+                JoinConstraint::Natural | JoinConstraint::Empty => None,
             }))
             .chain(&self.where_clause)
             .chain(&self.having)
@@ -368,7 +396,7 @@ pub fn is_aggregate(function: &FunctionExpression) -> bool {
         | FunctionExpression::Min(_)
         | FunctionExpression::GroupConcat { .. } => true,
         // For now, assume all "generic" function calls are not aggregates
-        FunctionExpression::Call { .. } => false,
+        FunctionExpression::Call { .. } | FunctionExpression::Unsupported { .. } => false,
     }
 }
 
@@ -215,7 +215,7 @@ pub fn walk_function_expression<'ast, V: Visitor<'ast>>(
     match function_expression {
         FunctionExpression::Avg { expr, .. } => visitor.visit_expression(expr.as_mut()),
         FunctionExpression::Count { expr, .. } => visitor.visit_expression(expr.as_mut()),
-        FunctionExpression::CountStar => Ok(()),
+        FunctionExpression::CountStar | FunctionExpression::Unsupported { .. } => Ok(()),
         FunctionExpression::Sum { expr, .. } => visitor.visit_expression(expr.as_mut()),
         FunctionExpression::Max(expr) => visitor.visit_expression(expr.as_mut()),
         FunctionExpression::Min(expr) => visitor.visit_expression(expr.as_mut()),
@@ -273,9 +273,14 @@ pub fn walk_join_clause<'ast, V: Visitor<'ast>>(
                 visitor.visit_table(table)?;
             }
         }
-        JoinRightSide::NestedSelect(statement, _) => {
+        JoinRightSide::NestedSelect(statement, _, _) => {
             visitor.visit_select_statement(statement.as_mut())?
         }
+        JoinRightSide::Values(values) => {
+            for expr in values.values.rows.iter_mut().flatten() {
+                visitor.visit_expression(expr)?;
+            }
+        }
     }
 
     visitor.visit_join_constraint(&mut join.constraint)
@@ -293,7 +298,7 @@ pub fn walk_join_constraint<'ast, V: Visitor<'ast>>(
             }
             Ok(())
         }
-        JoinConstraint::Empty => Ok(()),
+        JoinConstraint::Natural | JoinConstraint::Empty => Ok(()),
     }
 }
 
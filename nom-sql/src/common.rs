@@ -13,7 +13,7 @@ use launchpad::arbitrary::{
     arbitrary_positive_naive_date, arbitrary_timestamp_naive_date_time, arbitrary_uuid,
 };
 use nom::branch::alt;
-use nom::bytes::complete::{tag, tag_no_case, take_until};
+use nom::bytes::complete::{tag, tag_no_case, take_until, take_while, take_while1};
 use nom::character::complete::{digit1, line_ending};
 use nom::combinator::{map, map_parser, map_res, opt, peek, recognize};
 use nom::error::{ErrorKind, ParseError};
@@ -27,9 +27,9 @@ use serde::{Deserialize, Serialize};
 use test_strategy::Arbitrary;
 
 use crate::column::Column;
-use crate::dialect::Dialect;
+use crate::dialect::{Dialect, DialectKind};
 use crate::expression::expression;
-use crate::table::Table;
+use crate::table::{Table, TableSample};
 use crate::whitespace::{whitespace0, whitespace1};
 use crate::{Expression, FunctionExpression, SqlIdentifier};
 
@@ -246,6 +246,16 @@ pub enum ItemPlaceholder {
     QuestionMark,
     DollarNumber(u32),
     ColonNumber(u32),
+    /// A named placeholder, written `:name` (or `@name` in PostgreSQL, where `@` doesn't introduce
+    /// a user variable)
+    Named(
+        #[strategy(
+            proptest::string::string_regex("[a-zA-Z_][a-zA-Z0-9_]{0,15}")
+                .unwrap()
+                .prop_map(SqlIdentifier::from)
+        )]
+        SqlIdentifier,
+    ),
 }
 
 impl ToString for ItemPlaceholder {
@@ -254,6 +264,7 @@ impl ToString for ItemPlaceholder {
             ItemPlaceholder::QuestionMark => "?".to_string(),
             ItemPlaceholder::DollarNumber(ref i) => format!("${}", i),
             ItemPlaceholder::ColonNumber(ref i) => format!(":{}", i),
+            ItemPlaceholder::Named(ref name) => format!(":{}", name),
         }
     }
 }
@@ -1160,6 +1171,36 @@ fn delim_fx_args(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<Expre
     }
 }
 
+/// Parses the text of the arguments of a function call, up to (but not including) the closing
+/// parenthesis that matches the opening one before them, without trying to make sense of it
+fn raw_fx_args(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], &[u8]> {
+    move |i| {
+        let mut depth = 0usize;
+        let mut quote = None;
+        let mut pos = 0;
+        while pos < i.len() {
+            let c = i[pos];
+            match quote {
+                Some(_) if c == b'\\' && dialect.kind() == DialectKind::MySQL => pos += 1,
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None => match c {
+                    b'\'' | b'"' | b'`' => quote = Some(c),
+                    b'(' => depth += 1,
+                    b')' if depth == 0 => return Ok((&i[pos..], &i[..pos])),
+                    b')' => depth -= 1,
+                    _ => {}
+                },
+            }
+            pos += 1;
+        }
+        Err(nom::Err::Error(ParseError::from_error_kind(
+            i,
+            ErrorKind::TakeUntil,
+        )))
+    }
+}
+
 pub fn column_function(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], FunctionExpression> {
     move |i| {
         alt((
@@ -1219,6 +1260,18 @@ pub fn column_function(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Fun
                     arguments,
                 },
             ),
+            // Any other function call, whose arguments aren't just a list of expressions
+            map(
+                tuple((
+                    dialect.function_identifier(),
+                    whitespace0,
+                    delimited(tag("("), raw_fx_args(dialect), tag(")")),
+                )),
+                |(name, _, arguments)| FunctionExpression::Unsupported {
+                    name: name.to_string(),
+                    arguments: String::from_utf8_lossy(arguments).trim().to_owned(),
+                },
+            ),
         ))(i)
     }
 }
@@ -1276,6 +1329,22 @@ pub fn as_alias(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], SqlIdentif
     }
 }
 
+/// Parse a parenthesized list of names for the columns of an aliased derived table, such as the
+/// `(c1, c2)` in `(SELECT a, b FROM t) AS x (c1, c2)`
+pub(crate) fn alias_column_list(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<SqlIdentifier>> {
+    move |i| {
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = tag("(")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, columns) = separated_list1(ws_sep_comma, dialect.identifier())(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = tag(")")(i)?;
+        Ok((i, columns))
+    }
+}
+
 fn assignment_expr(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], (Column, Expression)> {
     move |i| {
         separated_pair(
@@ -1357,6 +1426,19 @@ pub fn field_definition_expr(
     }
 }
 
+/// Parse a `RETURNING` clause, listing the fields to return for each row written by an `INSERT`
+/// or `UPDATE` statement
+pub fn returning_clause(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<FieldDefinitionExpression>> {
+    move |i| {
+        preceded(
+            tuple((whitespace0, tag_no_case("returning"), whitespace1)),
+            field_definition_expr(dialect),
+        )(i)
+    }
+}
+
 // Parse list of table names.
 pub fn table_list(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<Table>> {
     move |i| separated_list1(ws_sep_comma, schema_table_reference(dialect))(i)
@@ -1402,6 +1484,31 @@ pub fn float_literal(i: &[u8]) -> IResult<&[u8], Literal> {
     )(i)
 }
 
+// The name of a named placeholder: a letter or underscore followed by any number of letters,
+// digits, or underscores
+fn placeholder_name(i: &[u8]) -> IResult<&[u8], SqlIdentifier> {
+    map_res(
+        recognize(pair(
+            take_while1(|c: u8| c.is_ascii_alphabetic() || c == b'_'),
+            take_while(|c: u8| c.is_ascii_alphanumeric() || c == b'_'),
+        )),
+        |name| str::from_utf8(name).map(SqlIdentifier::from),
+    )(i)
+}
+
+// Named placeholder, `:name` in any dialect or `@name` in PostgreSQL. MySQL uses `@name` for user
+// variables, so it's not a placeholder there.
+fn named_placeholder(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], ItemPlaceholder> {
+    move |i| {
+        let (i, _) = match dialect.kind() {
+            DialectKind::MySQL => tag(":")(i)?,
+            DialectKind::PostgreSQL => alt((tag(":"), tag("@")))(i)?,
+        };
+        let (i, name) = placeholder_name(i)?;
+        Ok((i, ItemPlaceholder::Named(name)))
+    }
+}
+
 fn boolean_literal(i: &[u8]) -> IResult<&[u8], Literal> {
     alt((
         map(tag_no_case("true"), |_| Literal::Boolean(true)),
@@ -1448,6 +1555,7 @@ pub fn literal(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Literal> {
                 ),
                 |num| Literal::Placeholder(ItemPlaceholder::DollarNumber(num)),
             ),
+            map(named_placeholder(dialect), Literal::Placeholder),
             boolean_literal,
         ))(i)
     }
@@ -1458,7 +1566,40 @@ pub fn value_list(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<Lite
     move |i| separated_list0(ws_sep_comma, literal(dialect))(i)
 }
 
-// Parse a reference to a named schema.table, with an optional alias
+// Parse a `TABLESAMPLE method (arguments) [REPEATABLE (seed)]` clause
+fn table_sample(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TableSample> {
+    move |i| {
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("tablesample")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, method) = dialect.identifier()(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, arguments) = delimited(
+            terminated(tag("("), whitespace0),
+            separated_list1(ws_sep_comma, expression(dialect)),
+            preceded(whitespace0, tag(")")),
+        )(i)?;
+        let (i, repeatable) = opt(preceded(
+            tuple((whitespace1, tag_no_case("repeatable"), whitespace0)),
+            delimited(
+                terminated(tag("("), whitespace0),
+                expression(dialect),
+                preceded(whitespace0, tag(")")),
+            ),
+        ))(i)?;
+
+        Ok((
+            i,
+            TableSample {
+                method,
+                arguments,
+                repeatable,
+            },
+        ))
+    }
+}
+
+// Parse a reference to a named schema.table, with an optional alias and `TABLESAMPLE` clause
 pub fn schema_table_reference(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Table> {
     move |i| {
         map(
@@ -1466,11 +1607,13 @@ pub fn schema_table_reference(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u
                 opt(pair(dialect.identifier(), tag("."))),
                 dialect.identifier(),
                 opt(as_alias(dialect)),
+                opt(table_sample(dialect)),
             )),
             |tup| Table {
                 name: tup.1,
                 alias: tup.2,
                 schema: tup.0.map(|(s, _)| s),
+                sample: tup.3,
             },
         )(i)
     }
@@ -1490,6 +1633,7 @@ pub fn schema_table_reference_no_alias(
                 name: tup.1,
                 alias: None,
                 schema: tup.0.map(|(s, _)| s),
+                sample: None,
             },
         )(i)
     }
@@ -1518,6 +1662,7 @@ pub fn table_reference(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Tab
                 name: tup.0,
                 alias: tup.1.as_deref().map(Into::into),
                 schema: None,
+                sample: None,
             }
         })(i)
     }
@@ -1539,11 +1684,11 @@ pub fn parse_comment(i: &[u8]) -> IResult<&[u8], String> {
 
 pub fn field_reference(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], FieldReference> {
     move |i| {
-        match dialect {
-            Dialect::PostgreSQL => map(expression(dialect), FieldReference::Expression)(i),
+        match dialect.kind() {
+            DialectKind::PostgreSQL => map(expression(dialect), FieldReference::Expression)(i),
             // Only MySQL supports numeric field references (postgresql considers them integer
             // literals, I'm pretty sure)
-            Dialect::MySQL => alt((
+            DialectKind::MySQL => alt((
                 map(
                     map_res(map_res(digit1, str::from_utf8), u64::from_str),
                     FieldReference::Numeric,
@@ -1662,6 +1807,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn unsupported_function_arguments() {
+        let res = test_parse!(
+            column_function(Dialect::PostgreSQL),
+            b"xmlelement(name foo, xmlattributes('x)' as bar), (a))"
+        );
+        assert_eq!(
+            res,
+            FunctionExpression::Unsupported {
+                name: "xmlelement".to_owned(),
+                arguments: "name foo, xmlattributes('x)' as bar), (a)".to_owned(),
+            }
+        );
+        assert_eq!(
+            res.to_string(),
+            "xmlelement(name foo, xmlattributes('x)' as bar), (a))"
+        );
+
+        let res = test_parse!(
+            column_function(Dialect::MySQL),
+            b"group_concat(a, b SEPARATOR '\\')')"
+        );
+        assert_eq!(
+            res,
+            FunctionExpression::Unsupported {
+                name: "group_concat".to_owned(),
+                arguments: "a, b SEPARATOR '\\')'".to_owned(),
+            }
+        );
+
+        let res = test_parse!(column_function(Dialect::MySQL), b"ST_Union( * )");
+        assert_eq!(
+            res,
+            FunctionExpression::Unsupported {
+                name: "ST_Union".to_owned(),
+                arguments: "*".to_owned(),
+            }
+        );
+
+        assert!(column_function(Dialect::MySQL)(b"foo(a, (b)").is_err());
+    }
+
     #[test]
     fn nested_function_call() {
         let res = test_parse!(column_function(Dialect::MySQL), b"max(min(foo))");
@@ -1778,6 +1965,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn named_placeholders() {
+        for dialect in [Dialect::MySQL, Dialect::PostgreSQL] {
+            assert_eq!(
+                test_parse!(literal(dialect), b":user_id"),
+                Literal::Placeholder(ItemPlaceholder::Named("user_id".into()))
+            );
+            assert_eq!(
+                test_parse!(literal(dialect), b":12"),
+                Literal::Placeholder(ItemPlaceholder::ColonNumber(12))
+            );
+        }
+
+        assert_eq!(
+            test_parse!(literal(Dialect::PostgreSQL), b"@user_id"),
+            Literal::Placeholder(ItemPlaceholder::Named("user_id".into()))
+        );
+        // `@name` is a user variable in MySQL
+        assert!(literal(Dialect::MySQL)(b"@user_id").is_err());
+    }
+
     #[test]
     fn boolean_literals() {
         for dialect in [Dialect::MySQL, Dialect::PostgreSQL] {
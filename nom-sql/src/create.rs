@@ -23,7 +23,7 @@ use crate::order::{order_type, OrderType};
 use crate::select::{nested_selection, selection, SelectStatement};
 use crate::table::Table;
 use crate::whitespace::{whitespace0, whitespace1};
-use crate::{ColumnConstraint, Dialect, SqlIdentifier};
+use crate::{ColumnConstraint, Dialect, DialectKind, SqlIdentifier};
 
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct CreateTableStatement {
@@ -31,12 +31,19 @@ pub struct CreateTableStatement {
     pub fields: Vec<ColumnSpecification>,
     pub keys: Option<Vec<TableKey>>,
     pub if_not_exists: bool,
+    /// Whether this creates a `TEMPORARY` table, which is only visible to the session that created
+    /// it and is dropped when that session ends
+    pub temporary: bool,
     pub options: Vec<CreateTableOption>,
 }
 
 impl fmt::Display for CreateTableStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "CREATE TABLE `{}` ", self.table.name)?;
+        write!(f, "CREATE ")?;
+        if self.temporary {
+            write!(f, "TEMPORARY ")?;
+        }
+        write!(f, "TABLE `{}` ", self.table.name)?;
         write!(f, "(")?;
         write!(
             f,
@@ -63,6 +70,8 @@ impl fmt::Display for CreateTableStatement {
             "{}",
             self.options
                 .iter()
+                // Options we don't keep track of are parsed as `Other`, which can't be displayed
+                .filter(|option| !matches!(option, CreateTableOption::Other))
                 .map(|option| format!("{}", option))
                 .collect::<Vec<_>>()
                 .join(", ")
@@ -241,8 +250,23 @@ fn full_text_key(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TableKey>
     }
 }
 
+// Parse rule for the `CONSTRAINT [name]` that may precede a key specification, returning the name
+// of the constraint if one was given.
+fn constraint_name(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Option<SqlIdentifier>> {
+    move |i| {
+        map(
+            opt(preceded(
+                terminated(tag_no_case("constraint"), whitespace1),
+                opt(terminated(dialect.identifier(), whitespace1)),
+            )),
+            Option::flatten,
+        )(i)
+    }
+}
+
 fn primary_key(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TableKey> {
     move |i| {
+        let (i, constraint_name) = constraint_name(dialect)(i)?;
         let (remaining_input, (_, name, _, columns, _)) = tuple((
             tag_no_case("primary key"),
             opt(preceded(whitespace1, dialect.identifier())),
@@ -258,7 +282,13 @@ fn primary_key(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TableKey> {
             )),
         ))(i)?;
 
-        Ok((remaining_input, TableKey::PrimaryKey { name, columns }))
+        Ok((
+            remaining_input,
+            TableKey::PrimaryKey {
+                name: name.or(constraint_name),
+                columns,
+            },
+        ))
     }
 }
 
@@ -375,6 +405,7 @@ fn using_index(i: &[u8]) -> IResult<&[u8], IndexType> {
 
 fn unique(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TableKey> {
     move |i| {
+        let (i, constraint_name) = constraint_name(dialect)(i)?;
         let (i, _) = tag_no_case("unique")(i)?;
         let (i, _) = opt(preceded(
             whitespace1,
@@ -393,7 +424,7 @@ fn unique(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TableKey> {
         Ok((
             i,
             TableKey::UniqueKey {
-                name,
+                name: name.or(constraint_name),
                 columns,
                 index_type,
             },
@@ -427,13 +458,7 @@ fn key_or_index(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TableKey>
 
 fn check_constraint(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TableKey> {
     move |i| {
-        let (i, name) = map(
-            opt(preceded(
-                terminated(tag_no_case("constraint"), whitespace1),
-                opt(terminated(dialect.identifier(), whitespace1)),
-            )),
-            Option::flatten,
-        )(i)?;
+        let (i, name) = constraint_name(dialect)(i)?;
         let (i, _) = tag_no_case("check")(i)?;
         let (i, _) = whitespace1(i)?;
         let (i, expr) = delimited(
@@ -478,14 +503,35 @@ pub fn field_specification_list(
 
 // Parse rule for a SQL CREATE TABLE query.
 // TODO(malte): support types, TEMPORARY tables, AS stmt
+// Parse the `TEMPORARY` keyword of a `CREATE TABLE` statement, and the whitespace after it, if
+// it's there. PostgreSQL also accepts `TEMP`, optionally preceded by `GLOBAL` or `LOCAL` (which
+// don't do anything).
+fn temporary(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], bool> {
+    move |i| {
+        let keyword = move |i| match dialect.kind() {
+            DialectKind::MySQL => tag_no_case("temporary")(i),
+            DialectKind::PostgreSQL => {
+                let (i, _) = opt(terminated(
+                    alt((tag_no_case("global"), tag_no_case("local"))),
+                    whitespace1,
+                ))(i)?;
+                alt((tag_no_case("temporary"), tag_no_case("temp")))(i)
+            }
+        };
+        let (i, temporary) = opt(terminated(keyword, whitespace1))(i)?;
+        Ok((i, temporary.is_some()))
+    }
+}
+
 pub fn creation(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], CreateTableStatement> {
     move |i| {
+        let (i, _) = tag_no_case("create")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, temporary) = temporary(dialect)(i)?;
         let (
             remaining_input,
-            (_, _, _, _, if_not_exists, table, _, _, _, fields_list, _, keys_list, _, _, _, opt, _),
+            (_, _, if_not_exists, table, _, _, _, fields_list, _, keys_list, _, _, _, opt, _),
         ) = tuple((
-            tag_no_case("create"),
-            whitespace1,
             tag_no_case("table"),
             whitespace1,
             if_not_exists,
@@ -612,6 +658,7 @@ pub fn creation(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], CreateTabl
                 fields,
                 keys,
                 if_not_exists,
+                temporary,
                 options: opt,
             },
         ))
@@ -809,6 +856,7 @@ mod tests {
                     ),
                 ],
                 if_not_exists: true,
+                temporary: false,
                 ..Default::default()
             }
         );
@@ -848,6 +896,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn create_temporary_table() {
+        let res = test_parse!(
+            creation(Dialect::MySQL),
+            b"CREATE TEMPORARY TABLE t (x integer)"
+        );
+        assert!(res.temporary);
+        assert_eq!(res.table, Table::from("t"));
+        assert_eq!(res.to_string(), "CREATE TEMPORARY TABLE `t` (`x` INT)");
+
+        let res = test_parse!(
+            creation(Dialect::PostgreSQL),
+            b"CREATE LOCAL TEMP TABLE t (x integer)"
+        );
+        assert!(res.temporary);
+        assert!(creation(Dialect::MySQL)(b"CREATE TEMP TABLE t (x integer)").is_err());
+
+        let res = test_parse!(
+            creation(Dialect::PostgreSQL),
+            b"CREATE TABLE temporary (x integer)"
+        );
+        assert!(!res.temporary);
+        assert_eq!(res.table, Table::from("temporary"));
+    }
+
     #[test]
     fn keys() {
         // simple primary key
@@ -986,6 +1059,7 @@ mod tests {
                     }
                 ]),
                 if_not_exists: false,
+                temporary: false,
                 options: vec![CreateTableOption::AutoIncrement(1000)],
             }
         )
@@ -1060,6 +1134,7 @@ mod tests {
                     },
                 ]),
                 if_not_exists: false,
+                temporary: false,
                 options: vec![CreateTableOption::AutoIncrement(10)],
             }
         )
@@ -1132,6 +1207,7 @@ mod tests {
                     },
                 ]),
                 if_not_exists: false,
+                temporary: false,
                 options: vec![],
             }
         )
@@ -1183,6 +1259,7 @@ mod tests {
                     columns: vec![col("id")]
                 },]),
                 if_not_exists: false,
+                temporary: false,
                 options: vec![CreateTableOption::AutoIncrement(1001)],
             }
         )
@@ -1294,6 +1371,7 @@ mod tests {
                     }],
                     keys: None,
                     if_not_exists: false,
+                    temporary: false,
                     options: vec![],
                 }
             );
@@ -1367,6 +1445,7 @@ mod tests {
                         }]
                     }]),
                     if_not_exists: false,
+                    temporary: false,
                     options: vec![],
                 }
             );
@@ -1403,6 +1482,7 @@ mod tests {
                         }]
                     }]),
                     if_not_exists: false,
+                    temporary: false,
                     options: vec![],
                 }
             );
@@ -1600,6 +1680,7 @@ mod tests {
                         },
                     ]),
                     if_not_exists: false,
+                    temporary: false,
                     options: vec![
                         CreateTableOption::Engine(Some("InnoDB".to_string())),
                         CreateTableOption::Charset("utf8mb4".to_string())
@@ -1708,6 +1789,29 @@ mod tests {
         use crate::table::Table;
         use crate::{ColumnConstraint, Literal, SqlType};
 
+        #[test]
+        fn named_key_constraints() {
+            let (_, res) = creation(Dialect::PostgreSQL)(
+                b"create table t (id int, constraint t_pkey primary key (id), \
+                  constraint t_id_key unique (id))",
+            )
+            .unwrap();
+            assert_eq!(
+                res.keys,
+                Some(vec![
+                    TableKey::PrimaryKey {
+                        name: Some("t_pkey".into()),
+                        columns: vec![Column::from("t.id")],
+                    },
+                    TableKey::UniqueKey {
+                        name: Some("t_id_key".into()),
+                        columns: vec![Column::from("t.id")],
+                        index_type: None,
+                    },
+                ])
+            );
+        }
+
         #[test]
         fn double_precision_column() {
             let (rem, res) =
@@ -1725,6 +1829,7 @@ mod tests {
                     }],
                     keys: None,
                     if_not_exists: false,
+                    temporary: false,
                     options: vec![],
                 }
             );
@@ -1822,6 +1927,7 @@ mod tests {
                         }]
                     }]),
                     if_not_exists: false,
+                    temporary: false,
                     options: vec![],
                 }
             );
@@ -1858,6 +1964,7 @@ mod tests {
                         }],
                     }]),
                     if_not_exists: false,
+                    temporary: false,
                     options: vec![],
                 }
             );
@@ -1985,6 +2092,7 @@ mod tests {
                         },
                     ]),
                     if_not_exists: false,
+                    temporary: false,
                     options: vec![
                         CreateTableOption::Engine(Some("InnoDB".to_string())),
                         CreateTableOption::Charset("utf8mb4".to_string())
@@ -2203,6 +2311,7 @@ mod tests {
                     },
                 ]),
                 if_not_exists: false,
+                temporary: false,
                 options: vec![
                     CreateTableOption::Engine(Some("InnoDB".to_string())),
                     CreateTableOption::Charset("utf8mb4".to_string()),
@@ -2239,6 +2348,7 @@ mod tests {
                 ],
                 keys: None,
                 if_not_exists: false,
+                temporary: false,
                 options: vec![
                     CreateTableOption::Charset("utf8mb4".to_string()),
                     CreateTableOption::Collate("utf8mb4_unicode_ci".to_string())
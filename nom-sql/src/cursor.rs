@@ -0,0 +1,261 @@
+use std::fmt;
+use std::str::{self, FromStr};
+
+use nom::branch::alt;
+use nom::bytes::complete::tag_no_case;
+use nom::character::complete::digit1;
+use nom::combinator::{map, map_res, opt};
+use nom::sequence::{preceded, terminated, tuple};
+use nom::IResult;
+use serde::{Deserialize, Serialize};
+
+use crate::common::statement_terminator;
+use crate::select::{nested_selection, SelectStatement};
+use crate::whitespace::whitespace1;
+use crate::{Dialect, SqlIdentifier};
+
+/// `DECLARE name CURSOR [WITH HOLD] FOR <select>`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct DeclareCursorStatement {
+    pub name: SqlIdentifier,
+    /// Whether the cursor can be used after the transaction that created it has committed
+    pub hold: bool,
+    pub query: SelectStatement,
+}
+
+impl fmt::Display for DeclareCursorStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DECLARE {} CURSOR ", self.name)?;
+        if self.hold {
+            write!(f, "WITH HOLD ")?;
+        }
+        write!(f, "FOR {}", self.query)
+    }
+}
+
+/// The number of rows to retrieve with a `FETCH` statement
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum FetchCount {
+    /// Fetch at most the given number of rows
+    Count(u64),
+    /// Fetch all remaining rows
+    All,
+}
+
+impl fmt::Display for FetchCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchCount::Count(n) => write!(f, "{}", n),
+            FetchCount::All => write!(f, "ALL"),
+        }
+    }
+}
+
+/// `FETCH [NEXT | FORWARD [n | ALL] | n | ALL] [FROM | IN] name`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct FetchStatement {
+    pub name: SqlIdentifier,
+    pub count: FetchCount,
+}
+
+impl fmt::Display for FetchStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FETCH {} FROM {}", self.count, self.name)
+    }
+}
+
+/// `CLOSE name` or `CLOSE ALL`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum CloseCursorStatement {
+    Cursor(SqlIdentifier),
+    All,
+}
+
+impl fmt::Display for CloseCursorStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CloseCursorStatement::Cursor(name) => write!(f, "CLOSE {}", name),
+            CloseCursorStatement::All => write!(f, "CLOSE ALL"),
+        }
+    }
+}
+
+pub fn declare_cursor(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], DeclareCursorStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("declare")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, name) = dialect.identifier()(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("cursor")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, hold) = opt(terminated(
+            alt((
+                map(tag_no_case("without"), |_| false),
+                map(tag_no_case("with"), |_| true),
+            )),
+            tuple((whitespace1, tag_no_case("hold"), whitespace1)),
+        ))(i)?;
+        let (i, _) = tag_no_case("for")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, query) = nested_selection(dialect)(i)?;
+        let (i, _) = statement_terminator(i)?;
+
+        Ok((
+            i,
+            DeclareCursorStatement {
+                name,
+                hold: hold.unwrap_or(false),
+                query,
+            },
+        ))
+    }
+}
+
+fn fetch_count(i: &[u8]) -> IResult<&[u8], FetchCount> {
+    let count = || {
+        map(
+            map_res(map_res(digit1, str::from_utf8), u64::from_str),
+            FetchCount::Count,
+        )
+    };
+    let all = || map(tag_no_case("all"), |_| FetchCount::All);
+
+    alt((
+        map(tag_no_case("next"), |_| FetchCount::Count(1)),
+        preceded(
+            tuple((tag_no_case("forward"), whitespace1)),
+            alt((all(), count())),
+        ),
+        map(tag_no_case("forward"), |_| FetchCount::Count(1)),
+        all(),
+        count(),
+    ))(i)
+}
+
+fn from_or_in(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((tag_no_case("from"), tag_no_case("in")))(i)
+}
+
+pub fn fetch(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], FetchStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("fetch")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, (count, name)) = alt((
+            map(
+                tuple((
+                    fetch_count,
+                    whitespace1,
+                    from_or_in,
+                    whitespace1,
+                    dialect.identifier(),
+                )),
+                |(count, _, _, _, name)| (count, name),
+            ),
+            map(
+                preceded(opt(tuple((from_or_in, whitespace1))), dialect.identifier()),
+                |name| (FetchCount::Count(1), name),
+            ),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+
+        Ok((i, FetchStatement { name, count }))
+    }
+}
+
+pub fn close_cursor(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], CloseCursorStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("close")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, stmt) = alt((
+            map(tag_no_case("all"), |_| CloseCursorStatement::All),
+            map(dialect.identifier(), CloseCursorStatement::Cursor),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+
+        Ok((i, stmt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_select_statement;
+
+    #[test]
+    fn declare_cursor_for_select() {
+        let res = declare_cursor(Dialect::PostgreSQL)(b"DECLARE c CURSOR FOR SELECT a FROM t")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res,
+            DeclareCursorStatement {
+                name: "c".into(),
+                hold: false,
+                query: parse_select_statement(Dialect::PostgreSQL, "SELECT a FROM t").unwrap(),
+            }
+        );
+        assert_eq!(res.to_string(), "DECLARE c CURSOR FOR SELECT `a` FROM `t`");
+    }
+
+    #[test]
+    fn declare_cursor_with_hold() {
+        let res = declare_cursor(Dialect::PostgreSQL)(
+            b"declare c cursor with hold for select a from t where b = 1;",
+        )
+        .unwrap()
+        .1;
+        assert!(res.hold);
+        assert_eq!(res.name, "c");
+
+        let res = declare_cursor(Dialect::PostgreSQL)(
+            b"declare c cursor without hold for select a from t",
+        )
+        .unwrap()
+        .1;
+        assert!(!res.hold);
+    }
+
+    #[test]
+    fn fetch_statements() {
+        let parse = |q: &str| fetch(Dialect::PostgreSQL)(q.as_bytes()).unwrap().1;
+        let stmt = |n, count| FetchStatement {
+            name: SqlIdentifier::from(n),
+            count,
+        };
+
+        assert_eq!(parse("FETCH c"), stmt("c", FetchCount::Count(1)));
+        assert_eq!(parse("FETCH FROM c"), stmt("c", FetchCount::Count(1)));
+        assert_eq!(parse("FETCH NEXT FROM c"), stmt("c", FetchCount::Count(1)));
+        assert_eq!(
+            parse("fetch 100 from c;"),
+            stmt("c", FetchCount::Count(100))
+        );
+        assert_eq!(
+            parse("FETCH FORWARD 10 IN c"),
+            stmt("c", FetchCount::Count(10))
+        );
+        assert_eq!(
+            parse("FETCH FORWARD ALL FROM c"),
+            stmt("c", FetchCount::All)
+        );
+        assert_eq!(parse("FETCH ALL IN c"), stmt("c", FetchCount::All));
+        assert_eq!(
+            parse("FETCH 10 FROM c").to_string(),
+            "FETCH 10 FROM c".to_owned()
+        );
+    }
+
+    #[test]
+    fn close_cursor_statements() {
+        assert_eq!(
+            close_cursor(Dialect::PostgreSQL)(b"CLOSE c").unwrap().1,
+            CloseCursorStatement::Cursor("c".into())
+        );
+        assert_eq!(
+            close_cursor(Dialect::PostgreSQL)(b"close all;").unwrap().1,
+            CloseCursorStatement::All
+        );
+    }
+}
@@ -1,13 +1,17 @@
 use std::{fmt, str};
 
-use nom::bytes::complete::tag_no_case;
-use nom::combinator::opt;
-use nom::sequence::{delimited, tuple};
+use itertools::Itertools;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::combinator::{map, opt};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, preceded, terminated, tuple};
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
-use crate::common::{schema_table_reference, statement_terminator};
-use crate::select::where_clause;
+use crate::common::{schema_table_reference, statement_terminator, ws_sep_comma};
+use crate::order::{order_clause, OrderClause};
+use crate::select::{limit_clause, table_references, where_clause, JoinClause, LimitClause};
 use crate::table::Table;
 use crate::whitespace::whitespace1;
 use crate::{Dialect, Expression};
@@ -15,35 +19,116 @@ use crate::{Dialect, Expression};
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct DeleteStatement {
     pub table: Table,
+    /// The other tables rows are deleted from in a MySQL multi-table `DELETE`, such as `t2` in
+    /// `DELETE t1, t2 FROM ...`
+    pub tables: Vec<Table>,
+    /// The tables the statement reads from, in a `USING` clause or the `FROM` clause of a MySQL
+    /// multi-table `DELETE`
+    pub using: Vec<Table>,
+    /// The joins between the tables the statement reads from
+    pub using_join: Vec<JoinClause>,
     pub where_clause: Option<Expression>,
+    /// The order to delete rows in, as in MySQL's `DELETE ... ORDER BY id LIMIT 1000`
+    pub order: Option<OrderClause>,
+    /// The maximum number of rows to delete
+    pub limit: Option<LimitClause>,
+}
+
+impl DeleteStatement {
+    /// Returns true if this statement refers to any tables other than [`table`](Self::table)
+    pub fn is_multi_table(&self) -> bool {
+        !self.tables.is_empty() || !self.using.is_empty() || !self.using_join.is_empty()
+    }
 }
 
 impl fmt::Display for DeleteStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "DELETE FROM `{}`", self.table.name)?;
+        for table in &self.tables {
+            write!(f, ", `{}`", table.name)?;
+        }
+        if !self.using.is_empty() {
+            write!(f, " USING {}", self.using.iter().join(", "))?;
+            for jc in &self.using_join {
+                write!(f, " {}", jc)?;
+            }
+        }
         if let Some(ref where_clause) = self.where_clause {
             write!(f, " WHERE ")?;
             write!(f, "{}", where_clause)?;
         }
+        if let Some(ref order) = self.order {
+            write!(f, " {}", order)?;
+        }
+        if let Some(ref limit) = self.limit {
+            write!(f, " {}", limit)?;
+        }
         Ok(())
     }
 }
 
+// The tables rows are deleted from, optionally written as `t.*` in MySQL
+fn targets(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<Table>> {
+    move |i| {
+        separated_list1(
+            ws_sep_comma,
+            alt((
+                map(terminated(dialect.identifier(), tag(".*")), |name| Table {
+                    name,
+                    ..Default::default()
+                }),
+                schema_table_reference(dialect),
+            )),
+        )(i)
+    }
+}
+
 pub fn deletion(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], DeleteStatement> {
     move |i| {
-        let (remaining_input, (_, _, table, where_clause, _)) = tuple((
-            tag_no_case("delete"),
-            delimited(whitespace1, tag_no_case("from"), whitespace1),
-            schema_table_reference(dialect),
-            opt(where_clause(dialect)),
-            statement_terminator,
-        ))(i)?;
+        let (remaining_input, (_, (mut tables, using), where_clause, order, limit, _)) =
+            tuple((
+                tag_no_case("delete"),
+                alt((
+                    // MySQL's `DELETE t1, t2 FROM t1 JOIN t2 ...`
+                    map(
+                        tuple((
+                            preceded(whitespace1, targets(dialect)),
+                            delimited(whitespace1, tag_no_case("from"), whitespace1),
+                            table_references(dialect),
+                        )),
+                        |(tables, _, using)| (tables, Some(using)),
+                    ),
+                    // `DELETE FROM t1 [USING t2 ...]`
+                    map(
+                        tuple((
+                            delimited(whitespace1, tag_no_case("from"), whitespace1),
+                            targets(dialect),
+                            opt(preceded(
+                                delimited(whitespace1, tag_no_case("using"), whitespace1),
+                                table_references(dialect),
+                            )),
+                        )),
+                        |(_, tables, using)| (tables, using),
+                    ),
+                )),
+                opt(where_clause(dialect)),
+                opt(order_clause(dialect)),
+                opt(limit_clause(dialect)),
+                statement_terminator,
+            ))(i)?;
+        let (using, using_join) = using.unwrap_or_default();
+        let table = tables.remove(0);
 
         Ok((
             remaining_input,
             DeleteStatement {
                 table,
+                tables,
+                using,
+                using_join,
                 where_clause,
+                order,
+                limit,
             },
         ))
     }
@@ -55,7 +140,7 @@ mod tests {
     use crate::column::Column;
     use crate::common::Literal;
     use crate::table::Table;
-    use crate::BinaryOperator;
+    use crate::{BinaryOperator, FieldReference};
 
     #[test]
     fn simple_delete() {
@@ -98,6 +183,7 @@ mod tests {
             DeleteStatement {
                 table: Table::from("users"),
                 where_clause: expected_where_cond,
+                ..Default::default()
             }
         );
     }
@@ -109,4 +195,81 @@ mod tests {
         let res = deletion(Dialect::MySQL)(qstring.as_bytes());
         assert_eq!(format!("{}", res.unwrap().1), expected);
     }
+
+    #[test]
+    fn multi_table_delete() {
+        let res = test_parse!(
+            deletion(Dialect::MySQL),
+            b"DELETE p, c FROM posts p JOIN comments c ON c.post = p.id WHERE p.spam = 1"
+        );
+        assert_eq!(res.table, Table::from("p"));
+        assert_eq!(res.tables, vec![Table::from("c")]);
+        assert_eq!(
+            res.using,
+            vec![Table {
+                alias: Some("p".into()),
+                ..Table::from("posts")
+            }]
+        );
+        assert_eq!(res.using_join.len(), 1);
+        assert!(res.is_multi_table());
+        assert_eq!(
+            res.to_string(),
+            "DELETE FROM `p`, `c` USING `posts` AS `p` \
+             JOIN `comments` AS `c` ON (`c`.`post` = `p`.`id`) WHERE (`p`.`spam` = 1)"
+        );
+
+        let res = test_parse!(
+            deletion(Dialect::MySQL),
+            b"DELETE t1.* FROM t1 LEFT JOIN t2 ON t1.id = t2.id WHERE t2.id IS NULL"
+        );
+        assert_eq!(res.table, Table::from("t1"));
+        assert!(res.tables.is_empty());
+        assert_eq!(res.using, vec![Table::from("t1")]);
+
+        let res = test_parse!(
+            deletion(Dialect::PostgreSQL),
+            b"DELETE FROM users USING bans WHERE users.id = bans.user_id"
+        );
+        assert_eq!(res.table, Table::from("users"));
+        assert_eq!(res.using, vec![Table::from("bans")]);
+        assert_eq!(
+            res.to_string(),
+            "DELETE FROM `users` USING `bans` WHERE (`users`.`id` = `bans`.`user_id`)"
+        );
+    }
+
+    #[test]
+    fn delete_with_order_and_limit() {
+        let res = test_parse!(
+            deletion(Dialect::MySQL),
+            b"DELETE FROM sessions WHERE expires < NOW() ORDER BY id LIMIT 1000"
+        );
+        assert_eq!(res.table, Table::from("sessions"));
+        assert!(res.where_clause.is_some());
+        assert_eq!(
+            res.order,
+            Some(OrderClause {
+                order_by: vec![(
+                    FieldReference::Expression(Expression::Column("id".into())),
+                    None
+                )]
+            })
+        );
+        assert_eq!(
+            res.limit,
+            Some(LimitClause {
+                limit: Literal::Integer(1000),
+                offset: None
+            })
+        );
+        assert_eq!(
+            res.to_string(),
+            "DELETE FROM `sessions` WHERE (`expires` < NOW()) ORDER BY `id` LIMIT 1000"
+        );
+
+        let res = test_parse!(deletion(Dialect::MySQL), b"DELETE FROM t LIMIT 10");
+        assert!(res.order.is_none());
+        assert!(res.limit.is_some());
+    }
 }
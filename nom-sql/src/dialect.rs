@@ -1,3 +1,4 @@
+use std::fmt;
 use std::str::{self, FromStr};
 
 use bit_vec::BitVec;
@@ -6,7 +7,7 @@ use nom::bytes::complete::{is_not, tag, tag_no_case, take, take_while1};
 use nom::character::complete::char;
 use nom::character::is_alphanumeric;
 use nom::combinator::{map, map_res, not, opt, peek};
-use nom::error::{ErrorKind, ParseError};
+use nom::error::{make_error, ErrorKind, ParseError};
 use nom::multi::fold_many0;
 use nom::sequence::{delimited, preceded};
 use nom::IResult;
@@ -57,8 +58,27 @@ fn bits(input: &[u8]) -> IResult<&[u8], BitVec> {
     )(input)
 }
 
-/// String literal value
-fn raw_string_quoted(input: &[u8], is_single_quote: bool) -> IResult<&[u8], Vec<u8>> {
+/// An escape sequence beginning with a backslash in a string literal
+fn backslash_escape(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((
+        map(tag("\\\\"), |_| &b"\\"[..]),
+        map(tag("\\b"), |_| &b"\x7f"[..]),
+        map(tag("\\r"), |_| &b"\r"[..]),
+        map(tag("\\n"), |_| &b"\n"[..]),
+        map(tag("\\t"), |_| &b"\t"[..]),
+        map(tag("\\0"), |_| &b"\0"[..]),
+        map(tag("\\Z"), |_| &b"\x1A"[..]),
+        preceded(tag("\\"), take(1usize)),
+    ))(i)
+}
+
+/// String literal value. If `backslash_escapes` is not set, backslashes are treated as ordinary
+/// characters, as in MySQL's `NO_BACKSLASH_ESCAPES` mode.
+fn raw_string_quoted(
+    input: &[u8],
+    is_single_quote: bool,
+    backslash_escapes: bool,
+) -> IResult<&[u8], Vec<u8>> {
     // TODO: clean up these assignments. lifetimes and temporary values made it difficult
     let quote_slice: &[u8] = if is_single_quote { b"\'" } else { b"\"" };
     let double_quote_slice: &[u8] = if is_single_quote { b"\'\'" } else { b"\"\"" };
@@ -67,7 +87,11 @@ fn raw_string_quoted(input: &[u8], is_single_quote: bool) -> IResult<&[u8], Vec<
         tag(quote_slice),
         fold_many0(
             alt((
-                is_not(backslash_quote),
+                is_not(if backslash_escapes {
+                    backslash_quote
+                } else {
+                    quote_slice
+                }),
                 map(tag(double_quote_slice), |_| -> &[u8] {
                     if is_single_quote {
                         b"\'"
@@ -75,14 +99,13 @@ fn raw_string_quoted(input: &[u8], is_single_quote: bool) -> IResult<&[u8], Vec<
                         b"\""
                     }
                 }),
-                map(tag("\\\\"), |_| &b"\\"[..]),
-                map(tag("\\b"), |_| &b"\x7f"[..]),
-                map(tag("\\r"), |_| &b"\r"[..]),
-                map(tag("\\n"), |_| &b"\n"[..]),
-                map(tag("\\t"), |_| &b"\t"[..]),
-                map(tag("\\0"), |_| &b"\0"[..]),
-                map(tag("\\Z"), |_| &b"\x1A"[..]),
-                preceded(tag("\\"), take(1usize)),
+                move |i| {
+                    if backslash_escapes {
+                        backslash_escape(i)
+                    } else {
+                        Err(nom::Err::Error(make_error(i, ErrorKind::IsNot)))
+                    }
+                },
             )),
             Vec::new,
             |mut acc: Vec<u8>, bytes: &[u8]| {
@@ -94,30 +117,112 @@ fn raw_string_quoted(input: &[u8], is_single_quote: bool) -> IResult<&[u8], Vec<
     )(input)
 }
 
-fn raw_string_single_quoted(i: &[u8]) -> IResult<&[u8], Vec<u8>> {
-    raw_string_quoted(i, true)
+/// The contents of an identifier quoted with double quotes, as in PostgreSQL or in MySQL with the
+/// `ANSI_QUOTES` SQL mode
+fn double_quoted_identifier(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    delimited(tag("\""), take_while1(|c| c != 0 && c != b'"'), tag("\""))(i)
 }
 
-fn raw_string_double_quoted(i: &[u8]) -> IResult<&[u8], Vec<u8>> {
-    raw_string_quoted(i, false)
+/// The database whose SQL a [`Dialect`] describes
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum DialectKind {
+    PostgreSQL,
+    MySQL,
+}
+
+/// Options for parsing MySQL, corresponding to the modes in the server's `sql_mode` which change
+/// how statements are parsed. See [the MySQL documentation][mysql-docs] for more information.
+///
+/// [mysql-docs]: https://dev.mysql.com/doc/refman/8.0/en/sql-mode.html
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MySqlParseOptions {
+    /// `ANSI_QUOTES`: double quotes (`"`) quote identifiers rather than string literals
+    pub ansi_quotes: bool,
+    /// `PIPES_AS_CONCAT`: `||` concatenates strings rather than being a synonym for `OR`
+    pub pipes_as_concat: bool,
+    /// `NO_BACKSLASH_ESCAPES`: backslash is an ordinary character in string literals rather than
+    /// the escape character
+    pub no_backslash_escapes: bool,
+}
+
+impl MySqlParseOptions {
+    /// The options used by a MySQL server with the default `sql_mode`
+    pub const DEFAULT: Self = Self {
+        ansi_quotes: false,
+        pipes_as_concat: false,
+        no_backslash_escapes: false,
+    };
+
+    /// Build parse options from the value of MySQL's `sql_mode` variable, a comma-separated list
+    /// of modes. Modes which don't affect parsing are ignored, and the `ANSI` combination mode
+    /// implies both `ANSI_QUOTES` and `PIPES_AS_CONCAT`.
+    pub fn from_sql_mode(sql_mode: &str) -> Self {
+        let mut options = Self::default();
+        for mode in sql_mode.split(',') {
+            match mode.trim().to_ascii_uppercase().as_str() {
+                "ANSI" => {
+                    options.ansi_quotes = true;
+                    options.pipes_as_concat = true;
+                }
+                "ANSI_QUOTES" => options.ansi_quotes = true,
+                "PIPES_AS_CONCAT" => options.pipes_as_concat = true,
+                "NO_BACKSLASH_ESCAPES" => options.no_backslash_escapes = true,
+                _ => {}
+            }
+        }
+        options
+    }
 }
 
 /// Specification for a SQL dialect to use when parsing
 ///
-/// Currently, Dialect controls the escape characters used for identifiers, and the quotes used to
-/// surround string literals, but may be extended to cover more dialect differences in the future
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub enum Dialect {
+/// Currently, Dialect controls the escape characters used for identifiers, the quotes used to
+/// surround string literals, and (for MySQL) the parse-affecting SQL modes described by
+/// [`MySqlParseOptions`], but may be extended to cover more dialect differences in the future
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Dialect {
+    kind: DialectKind,
+    mysql_options: MySqlParseOptions,
+}
+
+// The two base dialects are named like enum variants, since they're used like them
+#[allow(non_upper_case_globals)]
+impl Dialect {
     /// The SQL dialect used by PostgreSQL.
     ///
     /// Identifiers are escaped with double quotes (`"`) and strings use only single quotes (`'`)
-    PostgreSQL,
+    pub const PostgreSQL: Dialect = Dialect {
+        kind: DialectKind::PostgreSQL,
+        mysql_options: MySqlParseOptions::DEFAULT,
+    };
 
-    /// The SQL dialect used by MySQL.
+    /// The SQL dialect used by MySQL with the default `sql_mode`.
     ///
     /// Identifiers are escaped with backticks (`\``) or square brackets (`[` and `]`) and strings
     /// use either single quotes (`'`) or double quotes (`"`)
-    MySQL,
+    pub const MySQL: Dialect = Dialect {
+        kind: DialectKind::MySQL,
+        mysql_options: MySqlParseOptions::DEFAULT,
+    };
+
+    /// The SQL dialect used by MySQL with the given parse options
+    pub const fn mysql_with_options(options: MySqlParseOptions) -> Self {
+        Dialect {
+            kind: DialectKind::MySQL,
+            mysql_options: options,
+        }
+    }
+
+    /// Returns which database this dialect is for
+    pub fn kind(self) -> DialectKind {
+        self.kind
+    }
+
+    /// Returns the options for parsing MySQL used by this dialect. These are always the defaults
+    /// for PostgreSQL.
+    pub fn mysql_options(self) -> MySqlParseOptions {
+        self.mysql_options
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Error)]
@@ -136,11 +241,118 @@ impl FromStr for Dialect {
     }
 }
 
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            DialectKind::PostgreSQL => write!(f, "PostgreSQL"),
+            DialectKind::MySQL => write!(f, "MySQL"),
+        }
+    }
+}
+
+/// A piece of syntax (or a function) which is only supported by some [`Dialect`]s.
+///
+/// Use [`Dialect::supports`] to check whether a particular dialect supports a feature, and the
+/// [`Display`](fmt::Display) impl to describe the feature in errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Feature {
+    /// `RETURNING` clauses on `INSERT` and `UPDATE` statements
+    Returning,
+    /// `ON DUPLICATE KEY UPDATE` clauses on `INSERT` statements
+    OnDuplicateKeyUpdate,
+    /// The case-insensitive `ILIKE` and `NOT ILIKE` operators
+    ILike,
+    /// Casts written as `expr::type`
+    DoubleColonCast,
+    /// Bit vector literals, such as `B'0101'`
+    BitVectorLiterals,
+    /// Setting configuration parameters with `SET parameter TO value`
+    ConfigurationParameters,
+    /// The `UNSIGNED` variants of integer types
+    UnsignedIntegers,
+    /// Identifiers quoted with backticks (`` ` ``)
+    BacktickQuotedIdentifiers,
+    /// String literals quoted with double quotes (`"`)
+    DoubleQuotedStrings,
+    /// `FROM` clauses on `UPDATE` statements
+    UpdateFrom,
+    /// `UPDATE` and `DELETE` statements that modify more than one table, or join the table they
+    /// modify with others
+    MultiTableWrites,
+    /// `ORDER BY` and `LIMIT` clauses on `DELETE` statements
+    DeleteOrderLimit,
+}
+
+impl Feature {
+    /// All features, in the order they're declared
+    pub const ALL: &'static [Feature] = &[
+        Feature::Returning,
+        Feature::OnDuplicateKeyUpdate,
+        Feature::ILike,
+        Feature::DoubleColonCast,
+        Feature::BitVectorLiterals,
+        Feature::ConfigurationParameters,
+        Feature::UnsignedIntegers,
+        Feature::BacktickQuotedIdentifiers,
+        Feature::DoubleQuotedStrings,
+        Feature::UpdateFrom,
+        Feature::MultiTableWrites,
+        Feature::DeleteOrderLimit,
+    ];
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Feature::Returning => write!(f, "RETURNING"),
+            Feature::OnDuplicateKeyUpdate => write!(f, "ON DUPLICATE KEY UPDATE"),
+            Feature::ILike => write!(f, "ILIKE"),
+            Feature::DoubleColonCast => write!(f, "`::` casts"),
+            Feature::BitVectorLiterals => write!(f, "bit vector literals"),
+            Feature::ConfigurationParameters => write!(f, "SET ... TO"),
+            Feature::UnsignedIntegers => write!(f, "UNSIGNED integer types"),
+            Feature::BacktickQuotedIdentifiers => write!(f, "backtick-quoted identifiers"),
+            Feature::DoubleQuotedStrings => write!(f, "double-quoted strings"),
+            Feature::UpdateFrom => write!(f, "UPDATE ... FROM"),
+            Feature::MultiTableWrites => write!(f, "multi-table UPDATE and DELETE statements"),
+            Feature::DeleteOrderLimit => write!(f, "DELETE ... ORDER BY ... LIMIT"),
+        }
+    }
+}
+
 impl Dialect {
+    /// Returns true if this dialect supports the given [`Feature`]
+    pub fn supports(self, feature: Feature) -> bool {
+        match feature {
+            Feature::Returning
+            | Feature::ILike
+            | Feature::DoubleColonCast
+            | Feature::BitVectorLiterals
+            | Feature::ConfigurationParameters
+            | Feature::UpdateFrom => self.kind == DialectKind::PostgreSQL,
+            Feature::OnDuplicateKeyUpdate
+            | Feature::MultiTableWrites
+            | Feature::DeleteOrderLimit
+            | Feature::UnsignedIntegers
+            | Feature::BacktickQuotedIdentifiers => self.kind == DialectKind::MySQL,
+            Feature::DoubleQuotedStrings => {
+                self.kind == DialectKind::MySQL && !self.mysql_options.ansi_quotes
+            }
+        }
+    }
+
+    /// Returns all the [`Feature`]s supported by this dialect
+    pub fn supported_features(self) -> impl Iterator<Item = Feature> {
+        Feature::ALL
+            .iter()
+            .copied()
+            .filter(move |feature| self.supports(*feature))
+    }
+
     /// Parse a SQL identifier using this Dialect
     pub fn identifier(self) -> impl for<'a> Fn(&'a [u8]) -> IResult<&'a [u8], SqlIdentifier> {
-        move |i| match self {
-            Dialect::MySQL => map_res(
+        move |i| match self.kind {
+            DialectKind::MySQL => map_res(
                 alt((
                     preceded(
                         not(peek(sql_keyword_or_builtin_function)),
@@ -148,10 +360,17 @@ impl Dialect {
                     ),
                     delimited(tag("`"), take_while1(|c| c != 0 && c != b'`'), tag("`")),
                     delimited(tag("["), take_while1(is_sql_identifier), tag("]")),
+                    |i| {
+                        if self.mysql_options.ansi_quotes {
+                            double_quoted_identifier(i)
+                        } else {
+                            Err(nom::Err::Error(make_error(i, ErrorKind::Tag)))
+                        }
+                    },
                 )),
-                |v| str::from_utf8(v).map(Into::into),
+                |v| str::from_utf8(v).map(SqlIdentifier::interned),
             )(i),
-            Dialect::PostgreSQL => alt((
+            DialectKind::PostgreSQL => alt((
                 map_res(
                     preceded(
                         not(map_res(peek(sql_keyword_or_builtin_function), |i| {
@@ -166,29 +385,35 @@ impl Dialect {
                     |v| {
                         str::from_utf8(v)
                             .map(str::to_ascii_lowercase)
-                            .map(Into::into)
+                            .map(|s| SqlIdentifier::interned(&s))
                     },
                 ),
-                map_res(
-                    delimited(tag("\""), take_while1(|c| c != 0 && c != b'"'), tag("\"")),
-                    |v| str::from_utf8(v).map(Into::into),
-                ),
+                map_res(double_quoted_identifier, |v| {
+                    str::from_utf8(v).map(SqlIdentifier::interned)
+                }),
             ))(i),
         }
     }
 
     /// Parse a SQL function identifier using this Dialect
     pub fn function_identifier(self) -> impl for<'a> Fn(&'a [u8]) -> IResult<&'a [u8], &'a str> {
-        move |i| match self {
-            Dialect::MySQL => map_res(
+        move |i| match self.kind {
+            DialectKind::MySQL => map_res(
                 alt((
                     preceded(not(peek(sql_keyword)), take_while1(is_sql_identifier)),
                     delimited(tag("`"), take_while1(is_sql_identifier), tag("`")),
                     delimited(tag("["), take_while1(is_sql_identifier), tag("]")),
+                    |i| {
+                        if self.mysql_options.ansi_quotes {
+                            delimited(tag("\""), take_while1(is_sql_identifier), tag("\""))(i)
+                        } else {
+                            Err(nom::Err::Error(make_error(i, ErrorKind::Tag)))
+                        }
+                    },
                 )),
                 str::from_utf8,
             )(i),
-            Dialect::PostgreSQL => map_res(
+            DialectKind::PostgreSQL => map_res(
                 alt((
                     preceded(not(peek(sql_keyword)), take_while1(is_sql_identifier)),
                     delimited(tag("\""), take_while1(is_sql_identifier), tag("\"")),
@@ -200,12 +425,24 @@ impl Dialect {
 
     /// Parse the raw (byte) content of a string literal using this Dialect
     pub fn string_literal(self) -> impl for<'a> Fn(&'a [u8]) -> IResult<&'a [u8], Vec<u8>> {
-        move |i| match self {
-            Dialect::PostgreSQL => raw_string_single_quoted(i),
-            Dialect::MySQL => preceded(
-                opt(alt((tag("_utf8mb4"), tag("_utf8"), tag("_binary")))),
-                alt((raw_string_single_quoted, raw_string_double_quoted)),
-            )(i),
+        move |i| match self.kind {
+            DialectKind::PostgreSQL => raw_string_quoted(i, true, true),
+            DialectKind::MySQL => {
+                let backslash_escapes = !self.mysql_options.no_backslash_escapes;
+                preceded(
+                    opt(alt((tag("_utf8mb4"), tag("_utf8"), tag("_binary")))),
+                    alt((
+                        move |i| raw_string_quoted(i, true, backslash_escapes),
+                        move |i| {
+                            if self.mysql_options.ansi_quotes {
+                                Err(nom::Err::Error(make_error(i, ErrorKind::Tag)))
+                            } else {
+                                raw_string_quoted(i, false, backslash_escapes)
+                            }
+                        },
+                    )),
+                )(i)
+            }
         }
     }
 
@@ -225,20 +462,23 @@ impl Dialect {
     // TODO(fran): Improve this. This is very naive, and for Postgres specifically, it only
     //  parses the hex-formatted byte array. We need to also add support for the escaped format.
     pub fn bytes_literal(self) -> impl for<'a> Fn(&'a [u8]) -> IResult<&'a [u8], Vec<u8>> {
-        move |i| match self {
-            Dialect::PostgreSQL => raw_hex_bytes_psql(i),
-            Dialect::MySQL => raw_hex_bytes_mysql(i),
+        move |i| match self.kind {
+            DialectKind::PostgreSQL => raw_hex_bytes_psql(i),
+            DialectKind::MySQL => raw_hex_bytes_mysql(i),
         }
     }
 
     /// Parse the raw (byte) content of a bit vector literal using this Dialect.
     pub fn bitvec_literal(self) -> impl for<'a> Fn(&'a [u8]) -> IResult<&'a [u8], BitVec> {
-        move |i| match self {
-            Dialect::PostgreSQL => raw_bit_vector_psql(i),
-            Dialect::MySQL => Err(nom::Err::Error(nom::error::Error::new(
-                i,
-                nom::error::ErrorKind::Many0,
-            ))),
+        move |i| {
+            if self.supports(Feature::BitVectorLiterals) {
+                raw_bit_vector_psql(i)
+            } else {
+                Err(nom::Err::Error(nom::error::Error::new(
+                    i,
+                    nom::error::ErrorKind::Many0,
+                )))
+            }
         }
     }
 }
@@ -247,6 +487,24 @@ impl Dialect {
 mod tests {
     use super::*;
 
+    #[test]
+    fn feature_matrix() {
+        assert!(Dialect::PostgreSQL.supports(Feature::Returning));
+        assert!(!Dialect::MySQL.supports(Feature::Returning));
+        assert!(Dialect::MySQL.supports(Feature::OnDuplicateKeyUpdate));
+        assert!(!Dialect::PostgreSQL.supports(Feature::OnDuplicateKeyUpdate));
+        assert!(Dialect::PostgreSQL.supports(Feature::UpdateFrom));
+        assert!(!Dialect::PostgreSQL.supports(Feature::MultiTableWrites));
+        assert!(Dialect::MySQL.supports(Feature::MultiTableWrites));
+
+        assert!(Dialect::PostgreSQL
+            .supported_features()
+            .any(|feature| feature == Feature::BitVectorLiterals));
+        assert!(!Dialect::MySQL
+            .supported_features()
+            .any(|feature| feature == Feature::BitVectorLiterals));
+    }
+
     mod mysql {
         use super::*;
 
@@ -311,6 +569,69 @@ mod tests {
             let res = Dialect::MySQL.bytes_literal()(b"''");
             assert!(res.is_err());
         }
+
+        #[test]
+        fn parse_options_from_sql_mode() {
+            assert_eq!(
+                MySqlParseOptions::from_sql_mode(
+                    "ONLY_FULL_GROUP_BY,STRICT_TRANS_TABLES,NO_ENGINE_SUBSTITUTION"
+                ),
+                MySqlParseOptions::DEFAULT
+            );
+            assert_eq!(
+                MySqlParseOptions::from_sql_mode("ansi_quotes, NO_BACKSLASH_ESCAPES"),
+                MySqlParseOptions {
+                    ansi_quotes: true,
+                    pipes_as_concat: false,
+                    no_backslash_escapes: true,
+                }
+            );
+            assert_eq!(
+                MySqlParseOptions::from_sql_mode("ANSI"),
+                MySqlParseOptions {
+                    ansi_quotes: true,
+                    pipes_as_concat: true,
+                    no_backslash_escapes: false,
+                }
+            );
+        }
+
+        #[test]
+        fn ansi_quotes() {
+            let dialect = Dialect::mysql_with_options(MySqlParseOptions {
+                ansi_quotes: true,
+                ..Default::default()
+            });
+            assert_eq!(
+                dialect.identifier()(br#""state-province""#),
+                Ok((&b""[..], "state-province".into()))
+            );
+            assert!(dialect.identifier()(b"`primary`").is_ok());
+            assert!(dialect.string_literal()(br#""foo""#).is_err());
+            assert_eq!(
+                dialect.string_literal()(b"'foo'"),
+                Ok((&b""[..], b"foo".to_vec()))
+            );
+            assert!(!dialect.supports(Feature::DoubleQuotedStrings));
+
+            assert!(Dialect::MySQL.identifier()(br#""foo""#).is_err());
+        }
+
+        #[test]
+        fn no_backslash_escapes() {
+            let dialect = Dialect::mysql_with_options(MySqlParseOptions {
+                no_backslash_escapes: true,
+                ..Default::default()
+            });
+            assert_eq!(
+                dialect.string_literal()(br"'C:\new\'"),
+                Ok((&b""[..], br"C:\new\".to_vec()))
+            );
+            assert_eq!(
+                dialect.string_literal()(br"'it''s\n'"),
+                Ok((&b""[..], br"it's\n".to_vec()))
+            );
+        }
     }
 
     mod postgres {
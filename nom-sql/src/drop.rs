@@ -5,14 +5,14 @@ use itertools::Itertools;
 use nom::bytes::complete::tag_no_case;
 use nom::combinator::{map, opt};
 use nom::multi::separated_list1;
-use nom::sequence::preceded;
+use nom::sequence::{preceded, terminated, tuple};
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
 use crate::common::{statement_terminator, table_list, ws_sep_comma};
 use crate::table::Table;
 use crate::whitespace::whitespace1;
-use crate::{Dialect, SqlIdentifier};
+use crate::{Dialect, DialectKind, SqlIdentifier};
 
 fn if_exists(i: &[u8]) -> IResult<&[u8], bool> {
     map(
@@ -36,11 +36,17 @@ fn restrict_cascade(i: &[u8]) -> IResult<&[u8], (bool, bool)> {
 pub struct DropTableStatement {
     pub tables: Vec<Table>,
     pub if_exists: bool,
+    /// Whether this is a MySQL `DROP TEMPORARY TABLE`, which only drops temporary tables
+    pub temporary: bool,
 }
 
 impl Display for DropTableStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "DROP TABLE ")?;
+        write!(f, "DROP ")?;
+        if self.temporary {
+            write!(f, "TEMPORARY ")?;
+        }
+        write!(f, "TABLE ")?;
         if self.if_exists {
             write!(f, "IF EXISTS ")?;
         }
@@ -59,6 +65,10 @@ pub fn drop_table(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], DropTabl
     move |i| {
         let (i, _) = tag_no_case("drop")(i)?;
         let (i, _) = whitespace1(i)?;
+        let (i, temporary) = match dialect.kind() {
+            DialectKind::MySQL => opt(terminated(tag_no_case("temporary"), whitespace1))(i)?,
+            DialectKind::PostgreSQL => (i, None),
+        };
         let (i, _) = tag_no_case("table")(i)?;
         let (i, _) = whitespace1(i)?;
         let (i, if_exists) = if_exists(i)?;
@@ -66,7 +76,14 @@ pub fn drop_table(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], DropTabl
         let (i, _) = restrict_cascade(i)?;
         let (i, _) = statement_terminator(i)?;
 
-        Ok((i, DropTableStatement { tables, if_exists }))
+        Ok((
+            i,
+            DropTableStatement {
+                tables,
+                if_exists,
+                temporary: temporary.is_some(),
+            },
+        ))
     }
 }
 
@@ -92,6 +109,42 @@ pub fn drop_cached_query(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], D
         Ok((i, DropCacheStatement { name }))
     }
 }
+
+/// `DROP ALL CACHES [MATCHING '<pattern>']`, which drops every cached query, or only those whose
+/// text matches the given LIKE pattern
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct DropAllCachesStatement {
+    pub pattern: Option<String>,
+}
+
+impl Display for DropAllCachesStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DROP ALL CACHES")?;
+        if let Some(pattern) = &self.pattern {
+            write!(f, " MATCHING '{}'", pattern.replace('\'', "''"))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn drop_all_caches(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], DropAllCachesStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("drop")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("all")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("caches")(i)?;
+        let (i, pattern) = opt(preceded(
+            tuple((whitespace1, tag_no_case("matching"), whitespace1)),
+            dialect.utf8_string_literal(),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+        Ok((i, DropAllCachesStatement { pattern }))
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct DropViewStatement {
     pub views: Vec<SqlIdentifier>,
@@ -131,6 +184,22 @@ mod tests {
     use super::*;
     use crate::table::Table;
 
+    #[test]
+    fn drop_all_caches_statement() {
+        let res = drop_all_caches(Dialect::MySQL)(b"DROP ALL CACHES;");
+        assert_eq!(res.unwrap().1, DropAllCachesStatement { pattern: None });
+
+        let res = drop_all_caches(Dialect::MySQL)(b"drop all caches matching '%FROM `t`%'");
+        let stmt = res.unwrap().1;
+        assert_eq!(
+            stmt,
+            DropAllCachesStatement {
+                pattern: Some("%FROM `t`%".to_owned())
+            }
+        );
+        assert_eq!(stmt.to_string(), "DROP ALL CACHES MATCHING '%FROM `t`%'");
+    }
+
     #[test]
     fn simple_drop_table() {
         let qstring = "DROP TABLE users;";
@@ -140,6 +209,7 @@ mod tests {
             DropTableStatement {
                 tables: vec![Table::from("users")],
                 if_exists: false,
+                temporary: false,
             }
         );
     }
@@ -152,6 +222,19 @@ mod tests {
         assert_eq!(format!("{}", res.unwrap().1), expected);
     }
 
+    #[test]
+    fn drop_temporary_table() {
+        let res = test_parse!(
+            drop_table(Dialect::MySQL),
+            b"DROP TEMPORARY TABLE IF EXISTS t"
+        );
+        assert!(res.temporary);
+        assert!(res.if_exists);
+        assert_eq!(res.to_string(), "DROP TEMPORARY TABLE IF EXISTS `t`");
+
+        assert!(drop_table(Dialect::PostgreSQL)(b"DROP TEMPORARY TABLE t").is_err());
+    }
+
     #[test]
     fn parse_drop_cached_query() {
         let res = test_parse!(drop_cached_query(Dialect::MySQL), b"DROP CACHE test");
@@ -3,62 +3,117 @@ use std::fmt::{self, Display};
 use nom::branch::alt;
 use nom::bytes::complete::tag_no_case;
 use nom::combinator::{map, opt};
-use nom::sequence::{terminated, tuple};
+use nom::sequence::{preceded, terminated, tuple};
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
 use crate::common::statement_terminator;
+use crate::create::create_cached_query;
 use crate::whitespace::whitespace1;
+use crate::{CreateCacheStatement, Dialect, SqlIdentifier};
 
 /// EXPLAIN statements
 ///
 /// This is a non-standard ReadySet-specific extension to SQL
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum ExplainStatement {
-    /// Print a (maybe simplified) graphviz representation of the current query graph to stdout
-    Graphviz { simplified: bool },
+    /// Print a (maybe simplified) graphviz representation of the current query graph to stdout,
+    /// or only of the part of the graph for the cache named `for_cache`
+    Graphviz {
+        simplified: bool,
+        for_cache: Option<SqlIdentifier>,
+    },
     /// Provides metadata about the last statement that was executed.
     LastStatement,
+    /// Shows which existing cache (if any) the cache named `for_cache` shares dataflow nodes with
+    Reuse { for_cache: SqlIdentifier },
+    /// Plans the dataflow for a cache without creating it, and shows the nodes it would add
+    CreateCache(CreateCacheStatement),
 }
 
 impl Display for ExplainStatement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "EXPLAIN ")?;
         match self {
-            ExplainStatement::Graphviz { simplified } => {
+            ExplainStatement::Graphviz {
+                simplified,
+                for_cache,
+            } => {
                 if *simplified {
                     write!(f, "SIMPLIFIED ")?;
                 }
-                write!(f, "GRAPHVIZ;")
+                write!(f, "GRAPHVIZ")?;
+                if let Some(name) = for_cache {
+                    write!(f, " FOR CACHE `{}`", name)?;
+                }
+                write!(f, ";")
             }
             ExplainStatement::LastStatement => write!(f, "LAST STATEMENT;"),
+            ExplainStatement::Reuse { for_cache } => {
+                write!(f, "REUSE FOR CACHE `{}`;", for_cache)
+            }
+            ExplainStatement::CreateCache(stmt) => write!(f, "{};", stmt),
         }
     }
 }
 
-fn explain_graphviz(i: &[u8]) -> IResult<&[u8], ExplainStatement> {
-    let (i, simplified) = opt(terminated(tag_no_case("simplified"), whitespace1))(i)?;
-    let (i, _) = tag_no_case("graphviz")(i)?;
-    Ok((
-        i,
-        ExplainStatement::Graphviz {
-            simplified: simplified.is_some(),
-        },
-    ))
+fn explain_graphviz(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], ExplainStatement> {
+    move |i| {
+        let (i, simplified) = opt(terminated(tag_no_case("simplified"), whitespace1))(i)?;
+        let (i, _) = tag_no_case("graphviz")(i)?;
+        let (i, for_cache) = opt(preceded(
+            tuple((
+                whitespace1,
+                tag_no_case("for"),
+                whitespace1,
+                tag_no_case("cache"),
+                whitespace1,
+            )),
+            dialect.identifier(),
+        ))(i)?;
+        Ok((
+            i,
+            ExplainStatement::Graphviz {
+                simplified: simplified.is_some(),
+                for_cache,
+            },
+        ))
+    }
 }
 
-pub(crate) fn explain_statement(i: &[u8]) -> IResult<&[u8], ExplainStatement> {
-    let (i, _) = tag_no_case("explain")(i)?;
-    let (i, _) = whitespace1(i)?;
-    let (i, stmt) = alt((
-        explain_graphviz,
-        map(
-            tuple((tag_no_case("last"), whitespace1, tag_no_case("statement"))),
-            |_| ExplainStatement::LastStatement,
-        ),
-    ))(i)?;
-    let (i, _) = statement_terminator(i)?;
-    Ok((i, stmt))
+fn explain_reuse(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], ExplainStatement> {
+    move |i| {
+        let (i, _) = tuple((
+            tag_no_case("reuse"),
+            whitespace1,
+            tag_no_case("for"),
+            whitespace1,
+            tag_no_case("cache"),
+            whitespace1,
+        ))(i)?;
+        let (i, for_cache) = dialect.identifier()(i)?;
+        Ok((i, ExplainStatement::Reuse { for_cache }))
+    }
+}
+
+pub(crate) fn explain_statement(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], ExplainStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("explain")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, stmt) = alt((
+            explain_graphviz(dialect),
+            explain_reuse(dialect),
+            map(create_cached_query(dialect), ExplainStatement::CreateCache),
+            map(
+                tuple((tag_no_case("last"), whitespace1, tag_no_case("statement"))),
+                |_| ExplainStatement::LastStatement,
+            ),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+        Ok((i, stmt))
+    }
 }
 
 #[cfg(test)]
@@ -68,16 +123,72 @@ mod tests {
     #[test]
     fn explain_graphviz() {
         assert_eq!(
-            explain_statement(b"explain graphviz;").unwrap().1,
-            ExplainStatement::Graphviz { simplified: false }
+            explain_statement(Dialect::MySQL)(b"explain graphviz;")
+                .unwrap()
+                .1,
+            ExplainStatement::Graphviz {
+                simplified: false,
+                for_cache: None
+            }
+        );
+    }
+
+    #[test]
+    fn explain_graphviz_for_cache() {
+        let res = explain_statement(Dialect::MySQL)(b"EXPLAIN SIMPLIFIED GRAPHVIZ FOR CACHE `q1`;")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res,
+            ExplainStatement::Graphviz {
+                simplified: true,
+                for_cache: Some("q1".into())
+            }
+        );
+        assert_eq!(
+            res.to_string(),
+            "EXPLAIN SIMPLIFIED GRAPHVIZ FOR CACHE `q1`;"
         );
     }
 
     #[test]
     fn explain_last_statement() {
         assert_eq!(
-            explain_statement(b"explain last statement;").unwrap().1,
+            explain_statement(Dialect::MySQL)(b"explain last statement;")
+                .unwrap()
+                .1,
             ExplainStatement::LastStatement
         );
     }
+
+    #[test]
+    fn explain_reuse() {
+        let res = explain_statement(Dialect::MySQL)(b"explain reuse for cache q2;")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res,
+            ExplainStatement::Reuse {
+                for_cache: "q2".into()
+            }
+        );
+        assert_eq!(res.to_string(), "EXPLAIN REUSE FOR CACHE `q2`;");
+    }
+
+    #[test]
+    fn explain_create_cache() {
+        let res =
+            explain_statement(Dialect::MySQL)(b"explain create cache q3 from select id from t;")
+                .unwrap()
+                .1;
+        let stmt = match &res {
+            ExplainStatement::CreateCache(stmt) => stmt,
+            _ => panic!("expected EXPLAIN CREATE CACHE, got {:?}", res),
+        };
+        assert_eq!(stmt.name, Some("q3".into()));
+        assert_eq!(
+            res.to_string(),
+            "EXPLAIN CREATE CACHE `q3` FROM SELECT `id` FROM `t`;"
+        );
+    }
 }
@@ -7,6 +7,7 @@ use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
 use nom::character::complete::char;
 use nom::combinator::{complete, map, opt};
+use nom::error::{make_error, ErrorKind};
 use nom::multi::{many0, separated_list0};
 use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::{IResult, Parser};
@@ -21,7 +22,7 @@ use crate::common::{
 use crate::select::nested_selection;
 use crate::set::{variable_scope_prefix, Variable};
 use crate::whitespace::{whitespace0, whitespace1};
-use crate::{Column, Dialect, Literal, SelectStatement, SqlType};
+use crate::{Column, Dialect, DialectKind, Literal, SelectStatement, SqlType};
 
 /// Function call expressions
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -67,6 +68,11 @@ pub enum FunctionExpression {
         name: String,
         arguments: Vec<Expression>,
     },
+
+    /// Call to a function whose arguments aren't just a list of expressions, such as
+    /// `XMLELEMENT(NAME foo, bar)` or `GROUP_CONCAT(a, b SEPARATOR '-')`. The arguments are kept
+    /// verbatim, and queries containing such calls can only be proxied to the upstream database
+    Unsupported { name: String, arguments: String },
 }
 
 impl FunctionExpression {
@@ -82,7 +88,9 @@ impl FunctionExpression {
             | FunctionExpression::GroupConcat { expr: arg, .. } => {
                 Either::Left(iter::once(arg.as_ref()))
             }
-            FunctionExpression::CountStar => Either::Right(Either::Left(iter::empty())),
+            FunctionExpression::CountStar | FunctionExpression::Unsupported { .. } => {
+                Either::Right(Either::Left(iter::empty()))
+            }
             FunctionExpression::Call { arguments, .. } => {
                 Either::Right(Either::Right(arguments.iter()))
             }
@@ -118,6 +126,9 @@ impl Display for FunctionExpression {
             FunctionExpression::Call { name, arguments } => {
                 write!(f, "{}({})", name, arguments.iter().join(", "))
             }
+            FunctionExpression::Unsupported { name, arguments } => {
+                write!(f, "{}({})", name, arguments)
+            }
         }
     }
 }
@@ -352,7 +363,7 @@ impl Display for Expression {
             } => {
                 write!(
                     f,
-                    "{} {}BETWEEN {} AND {}",
+                    "({} {}BETWEEN {} AND {})",
                     operand,
                     if *negated { "NOT " } else { "" },
                     min,
@@ -360,11 +371,11 @@ impl Display for Expression {
                 )
             }
             Expression::In { lhs, rhs, negated } => {
-                write!(f, "{}", lhs)?;
+                write!(f, "({}", lhs)?;
                 if *negated {
                     write!(f, " NOT")?;
                 }
-                write!(f, " IN ({})", rhs)
+                write!(f, " IN ({}))", rhs)
             }
             Expression::NestedSelect(q) => write!(f, "({})", q),
             Expression::Cast {
@@ -406,6 +417,10 @@ enum TokenTree {
     Primary(Expression),
     Group(Vec<TokenTree>),
     PgsqlCast(Box<TokenTree>, SqlType),
+    /// `<expr> AT TIME ZONE <zone>`
+    AtTimeZone(Box<TokenTree>, Box<TokenTree>),
+    /// `||` in MySQL with the `PIPES_AS_CONCAT` SQL mode
+    Concat,
 }
 
 // no_and_or variants of `infix`, `rest`, and `token_tree` allow parsing (binary op) expressions in
@@ -470,16 +485,35 @@ fn infix_no_and_or(i: &[u8]) -> IResult<&[u8], TokenTree> {
     Ok((i, TokenTree::Infix(operator)))
 }
 
-fn infix(i: &[u8]) -> IResult<&[u8], TokenTree> {
-    complete(alt((
-        map(terminated(tag_no_case("and"), whitespace1), |_| {
-            TokenTree::Infix(BinaryOperator::And)
-        }),
-        map(terminated(tag_no_case("or"), whitespace1), |_| {
-            TokenTree::Infix(BinaryOperator::Or)
-        }),
-        infix_no_and_or,
-    )))(i)
+/// In MySQL, `||` is a synonym for `OR` unless the `PIPES_AS_CONCAT` SQL mode is set, in which
+/// case it concatenates its operands
+fn pipes(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree> {
+    move |i| {
+        if dialect.kind() != DialectKind::MySQL {
+            return Err(nom::Err::Error(make_error(i, ErrorKind::Tag)));
+        }
+        let (i, _) = tag("||")(i)?;
+        if dialect.mysql_options().pipes_as_concat {
+            Ok((i, TokenTree::Concat))
+        } else {
+            Ok((i, TokenTree::Infix(BinaryOperator::Or)))
+        }
+    }
+}
+
+fn infix(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree> {
+    move |i| {
+        complete(alt((
+            map(terminated(tag_no_case("and"), whitespace1), |_| {
+                TokenTree::Infix(BinaryOperator::And)
+            }),
+            map(terminated(tag_no_case("or"), whitespace1), |_| {
+                TokenTree::Infix(BinaryOperator::Or)
+            }),
+            pipes(dialect),
+            infix_no_and_or,
+        )))(i)
+    }
 }
 
 fn prefix(i: &[u8]) -> IResult<&[u8], TokenTree> {
@@ -504,6 +538,32 @@ fn primary_inner(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree
                 let (i, _) = whitespace0(i)?;
                 let (i, _) = char(')')(i)?;
 
+                // A parenthesized expression can be the left-hand side of an IN or BETWEEN
+                // predicate
+                if let Ok((i, (negated, rhs))) = preceded(whitespace1, in_suffix(dialect))(i) {
+                    return Ok((
+                        i,
+                        TokenTree::Primary(Expression::In {
+                            lhs: Box::new(ExprParser.parse(&mut tree.into_iter()).unwrap()),
+                            rhs,
+                            negated,
+                        }),
+                    ));
+                }
+                if let Ok((i, (negated, min, max))) =
+                    preceded(whitespace1, between_suffix(dialect))(i)
+                {
+                    return Ok((
+                        i,
+                        TokenTree::Primary(Expression::Between {
+                            operand: Box::new(ExprParser.parse(&mut tree.into_iter()).unwrap()),
+                            min,
+                            max,
+                            negated,
+                        }),
+                    ));
+                }
+
                 Ok((i, TokenTree::Group(tree)))
             },
             move |i| {
@@ -515,7 +575,7 @@ fn primary_inner(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree
     }
 }
 
-fn primary(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree> {
+fn primary_cast(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree> {
     move |i| {
         let (i, expr) = primary_inner(dialect)(i)?;
         let (i, t) = opt(move |i| {
@@ -533,13 +593,38 @@ fn primary(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree> {
     }
 }
 
+fn at_time_zone(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree> {
+    move |i| {
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("at")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("time")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("zone")(i)?;
+        let (i, _) = whitespace1(i)?;
+        primary_cast(dialect)(i)
+    }
+}
+
+fn primary(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], TokenTree> {
+    move |i| {
+        let (mut i, mut expr) = primary_cast(dialect)(i)?;
+        // `AT TIME ZONE` binds tighter than any other operator, and is left-associative
+        while let Ok((rest, zone)) = at_time_zone(dialect)(i) {
+            expr = TokenTree::AtTimeZone(Box::new(expr), Box::new(zone));
+            i = rest;
+        }
+        Ok((i, expr))
+    }
+}
+
 fn rest(
     dialect: Dialect,
 ) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<(TokenTree, Vec<TokenTree>, TokenTree)>> {
     move |i| {
         many0(move |i| {
             let (i, _) = whitespace0(i)?;
-            let (i, infix_tree) = infix(i)?;
+            let (i, infix_tree) = infix(dialect)(i)?;
             let (i, _) = whitespace0(i)?;
             let (i, prefix_tree) = many0(prefix)(i)?;
             let (i, _) = whitespace0(i)?;
@@ -638,6 +723,10 @@ where
             Primary(_) => Affix::Nilfix,
             Group(_) => Affix::Nilfix,
             PgsqlCast(_, _) => Affix::Nilfix,
+            AtTimeZone(_, _) => Affix::Nilfix,
+            // MySQL gives `||` with `PIPES_AS_CONCAT` a higher precedence than any other binary
+            // operator
+            Concat => Affix::Infix(Precedence(13), Associativity::Left),
         })
     }
 
@@ -655,6 +744,16 @@ where
                     postgres_style: true,
                 }
             }
+            // Like PostgreSQL, represent `expr AT TIME ZONE zone` as a call to `timezone(zone,
+            // expr)`
+            AtTimeZone(box expr, box zone) => {
+                let expr = self.parse(&mut vec![expr].into_iter()).unwrap();
+                let zone = self.parse(&mut vec![zone].into_iter()).unwrap();
+                Expression::Call(FunctionExpression::Call {
+                    name: "timezone".to_owned(),
+                    arguments: vec![zone, expr],
+                })
+            }
             _ => unreachable!("Invalid fixity for non-primary token"),
         })
     }
@@ -667,6 +766,13 @@ where
     ) -> Result<Self::Output, Self::Error> {
         let op = match op {
             TokenTree::Infix(op) => op,
+            // Represent `lhs || rhs` as a call to `concat(lhs, rhs)`
+            TokenTree::Concat => {
+                return Ok(Expression::Call(FunctionExpression::Call {
+                    name: "concat".to_owned(),
+                    arguments: vec![lhs, rhs],
+                }))
+            }
             _ => unreachable!("Invalid fixity for infix op"),
         };
 
@@ -723,10 +829,10 @@ fn in_rhs(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], InValue> {
     }
 }
 
-fn in_expr(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Expression> {
+/// Parses everything in an IN (or NOT IN) predicate after its left-hand side, returning whether
+/// it's negated along with its right-hand side
+fn in_suffix(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], (bool, InValue)> {
     move |i| {
-        let (i, lhs) = terminated(in_lhs(dialect), whitespace1)(i)?;
-
         let (i, not) = opt(terminated(tag_no_case("not"), whitespace1))(i)?;
         let (i, _) = tag_no_case("in")(i)?;
         let (i, _) = whitespace0(i)?;
@@ -737,12 +843,21 @@ fn in_expr(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Expression> {
         let (i, _) = whitespace0(i)?;
         let (i, _) = char(')')(i)?;
 
+        Ok((i, (not.is_some(), rhs)))
+    }
+}
+
+fn in_expr(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Expression> {
+    move |i| {
+        let (i, lhs) = terminated(in_lhs(dialect), whitespace1)(i)?;
+        let (i, (negated, rhs)) = in_suffix(dialect)(i)?;
+
         Ok((
             i,
             Expression::In {
                 lhs: Box::new(lhs),
                 rhs,
-                negated: not.is_some(),
+                negated,
             },
         ))
     }
@@ -771,10 +886,13 @@ fn between_max(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Expression>
     }
 }
 
-fn between_expr(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Expression> {
+/// Parses everything in a BETWEEN (or NOT BETWEEN) predicate after its operand, returning whether
+/// it's negated along with its minimum and maximum
+#[allow(clippy::type_complexity)]
+fn between_suffix(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], (bool, Box<Expression>, Box<Expression>)> {
     move |i| {
-        let (i, operand) = map(between_operand(dialect), Box::new)(i)?;
-        let (i, _) = whitespace1(i)?;
         let (i, not) = opt(terminated(tag_no_case("not"), whitespace1))(i)?;
         let (i, _) = tag_no_case("between")(i)?;
         let (i, _) = whitespace1(i)?;
@@ -784,13 +902,23 @@ fn between_expr(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Expression
         let (i, _) = whitespace1(i)?;
         let (i, max) = map(between_max(dialect), Box::new)(i)?;
 
+        Ok((i, (not.is_some(), min, max)))
+    }
+}
+
+fn between_expr(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Expression> {
+    move |i| {
+        let (i, operand) = map(between_operand(dialect), Box::new)(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, (negated, min, max)) = between_suffix(dialect)(i)?;
+
         Ok((
             i,
             Expression::Between {
                 operand,
                 min,
                 max,
-                negated: not.is_some(),
+                negated,
             },
         ))
     }
@@ -1475,7 +1603,7 @@ mod tests {
             };
             assert_eq!(c1, expected1);
 
-            let expected1 = "`id` NOT IN (1, 2)";
+            let expected1 = "(`id` NOT IN (1, 2))";
             assert_eq!(format!("{}", c1), expected1);
         }
 
@@ -1551,6 +1679,46 @@ mod tests {
             assert_eq!(result, expected);
         }
 
+        #[test]
+        fn parenthesized_in_and_between_operands() {
+            let sum = Expression::BinaryOp {
+                op: BinaryOperator::Add,
+                lhs: Box::new(Expression::Column("x".into())),
+                rhs: Box::new(Expression::Literal(Literal::Integer(1))),
+            };
+
+            let (remaining, result) = expression(Dialect::MySQL)(b"(x + 1) not in (1, 2)").unwrap();
+            assert_eq!(std::str::from_utf8(remaining).unwrap(), "");
+            assert_eq!(
+                result,
+                Expression::In {
+                    lhs: Box::new(sum.clone()),
+                    rhs: InValue::List(vec![
+                        Expression::Literal(Literal::Integer(1)),
+                        Expression::Literal(Literal::Integer(2)),
+                    ]),
+                    negated: true,
+                }
+            );
+
+            let (remaining, result) =
+                expression(Dialect::MySQL)(b"(x + 1) between 1 and 2 and y").unwrap();
+            assert_eq!(std::str::from_utf8(remaining).unwrap(), "");
+            assert_eq!(
+                result,
+                Expression::BinaryOp {
+                    op: BinaryOperator::And,
+                    lhs: Box::new(Expression::Between {
+                        operand: Box::new(sum),
+                        min: Box::new(Expression::Literal(Literal::Integer(1))),
+                        max: Box::new(Expression::Literal(Literal::Integer(2))),
+                        negated: false,
+                    }),
+                    rhs: Box::new(Expression::Column("y".into())),
+                }
+            );
+        }
+
         #[test]
         fn ilike() {
             let qs = b"name ILIKE ?";
@@ -1662,6 +1830,7 @@ mod tests {
 
     mod mysql {
         use super::*;
+        use crate::MySqlParseOptions;
 
         mod conditions {
             use super::*;
@@ -1798,6 +1967,42 @@ mod tests {
                 );
             }
         }
+
+        #[test]
+        fn pipes_as_or() {
+            let res = expression(Dialect::MySQL)(b"a || b");
+            assert_eq!(
+                res.unwrap().1,
+                Expression::BinaryOp {
+                    lhs: Box::new(Expression::Column("a".into())),
+                    op: BinaryOperator::Or,
+                    rhs: Box::new(Expression::Column("b".into())),
+                }
+            );
+        }
+
+        #[test]
+        fn pipes_as_concat() {
+            let dialect = Dialect::mysql_with_options(MySqlParseOptions {
+                pipes_as_concat: true,
+                ..Default::default()
+            });
+            let res = expression(dialect)(b"a || 'b' = c");
+            assert_eq!(
+                res.unwrap().1,
+                Expression::BinaryOp {
+                    lhs: Box::new(Expression::Call(FunctionExpression::Call {
+                        name: "concat".to_owned(),
+                        arguments: vec![
+                            Expression::Column("a".into()),
+                            Expression::Literal("b".into()),
+                        ],
+                    })),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expression::Column("c".into())),
+                }
+            );
+        }
     }
 
     mod postgres {
@@ -1956,5 +2161,55 @@ mod tests {
                 );
             }
         }
+
+        #[test]
+        fn at_time_zone() {
+            let res = test_parse!(
+                expression(Dialect::PostgreSQL),
+                b"ts AT TIME ZONE 'America/New_York' = '2022-01-01'"
+            );
+            assert_eq!(
+                res,
+                Expression::BinaryOp {
+                    lhs: Box::new(Expression::Call(FunctionExpression::Call {
+                        name: "timezone".to_owned(),
+                        arguments: vec![
+                            Expression::Literal(Literal::String("America/New_York".into())),
+                            Expression::Column(Column::from("ts")),
+                        ],
+                    })),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expression::Literal(Literal::String("2022-01-01".into()))),
+                }
+            );
+        }
+
+        #[test]
+        fn at_time_zone_chained() {
+            let res = test_parse!(
+                expression(Dialect::PostgreSQL),
+                b"ts::timestamp at time zone 'UTC' at time zone tz"
+            );
+            assert_eq!(
+                res,
+                Expression::Call(FunctionExpression::Call {
+                    name: "timezone".to_owned(),
+                    arguments: vec![
+                        Expression::Column(Column::from("tz")),
+                        Expression::Call(FunctionExpression::Call {
+                            name: "timezone".to_owned(),
+                            arguments: vec![
+                                Expression::Literal(Literal::String("UTC".into())),
+                                Expression::Cast {
+                                    expr: Box::new(Expression::Column(Column::from("ts"))),
+                                    ty: SqlType::Timestamp,
+                                    postgres_style: true,
+                                },
+                            ],
+                        }),
+                    ],
+                })
+            );
+        }
     }
 }
@@ -0,0 +1,56 @@
+use std::fmt::{self, Display};
+
+use nom::bytes::complete::tag_no_case;
+use nom::sequence::tuple;
+use nom::IResult;
+use serde::{Deserialize, Serialize};
+
+use crate::common::statement_terminator;
+use crate::whitespace::whitespace1;
+use crate::Dialect;
+
+/// `READYSET FLUSH WAIT` statements, which block until every write ReadySet has replicated so far
+/// has propagated through the dataflow graph to its caches
+///
+/// This is a non-standard ReadySet-specific extension to SQL
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct FlushWaitStatement;
+
+impl Display for FlushWaitStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "READYSET FLUSH WAIT")
+    }
+}
+
+pub(crate) fn flush_wait(_: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], FlushWaitStatement> {
+    move |i| {
+        let (i, _) = tuple((
+            tag_no_case("readyset"),
+            whitespace1,
+            tag_no_case("flush"),
+            whitespace1,
+            tag_no_case("wait"),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+        Ok((i, FlushWaitStatement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_flush_wait() {
+        let res = flush_wait(Dialect::MySQL)(b"readyset  flush WAIT;")
+            .unwrap()
+            .1;
+        assert_eq!(res, FlushWaitStatement);
+        assert_eq!(res.to_string(), "READYSET FLUSH WAIT");
+    }
+
+    #[test]
+    fn flush_without_wait() {
+        assert!(flush_wait(Dialect::MySQL)(b"READYSET FLUSH").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! Comment hints that tell ReadySet how to execute a single statement, for example:
+//!
+//! ```sql
+//! SELECT /*+ readyset=proxy */ * FROM t WHERE id = ?
+//! ```
+//!
+//! A hint is written inside a comment starting with `/*+` (the syntax MySQL uses for optimizer
+//! hints), either at the start of the statement or directly after its first keyword. The comment
+//! can contain other hints meant for the upstream database, which are ignored.
+
+use std::fmt;
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case, take_till1, take_until};
+use nom::character::complete::{alpha0, multispace0};
+use nom::combinator::{map, value};
+use nom::multi::many0;
+use nom::sequence::{delimited, preceded, tuple};
+use nom::IResult;
+use serde::{Deserialize, Serialize};
+
+use crate::whitespace::multiline_comment;
+
+/// A hint given in a comment of a statement for how ReadySet should execute it
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum ReadysetHint {
+    /// `readyset=proxy`: always proxy the statement to the upstream database
+    Proxy,
+    /// `readyset=cached`: execute the statement against ReadySet, creating a cache for it if
+    /// necessary
+    Cached,
+}
+
+impl fmt::Display for ReadysetHint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadysetHint::Proxy => write!(f, "/*+ readyset=proxy */"),
+            ReadysetHint::Cached => write!(f, "/*+ readyset=cached */"),
+        }
+    }
+}
+
+fn readyset_hint_item(i: &[u8]) -> IResult<&[u8], ReadysetHint> {
+    let (i, _) = tag_no_case("readyset")(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, _) = tag("=")(i)?;
+    let (i, _) = multispace0(i)?;
+    alt((
+        value(ReadysetHint::Proxy, tag_no_case("proxy")),
+        value(ReadysetHint::Cached, tag_no_case("cached")),
+    ))(i)
+}
+
+/// Parse a `/*+ ... */` comment, returning the first ReadySet hint in it, if any
+fn hint_comment(i: &[u8]) -> IResult<&[u8], Option<ReadysetHint>> {
+    let (i, body) = delimited(tag("/*+"), take_until("*/"), tag("*/"))(i)?;
+    let (_, hints) = many0(preceded(
+        multispace0,
+        alt((
+            map(readyset_hint_item, Some),
+            map(take_till1(|c: u8| c.is_ascii_whitespace()), |_| None),
+        )),
+    ))(body)?;
+    Ok((i, hints.into_iter().flatten().next()))
+}
+
+/// Parse any number of comments, returning the first ReadySet hint in them, if any
+fn hint_comments(i: &[u8]) -> IResult<&[u8], Option<ReadysetHint>> {
+    map(
+        many0(preceded(
+            multispace0,
+            alt((hint_comment, map(multiline_comment, |_| None))),
+        )),
+        |hints| hints.into_iter().flatten().next(),
+    )(i)
+}
+
+/// Returns the ReadySet hint given in a comment at the start of `query`, or directly after its
+/// first keyword, if any
+pub fn readyset_hint(query: &str) -> Option<ReadysetHint> {
+    let res: IResult<&[u8], _> =
+        tuple((hint_comments, multispace0, alpha0, hint_comments))(query.as_bytes());
+    let (_, (before_keyword, _, _, after_keyword)) = res.ok()?;
+    before_keyword.or(after_keyword)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hint_after_keyword() {
+        assert_eq!(
+            readyset_hint("SELECT /*+ readyset=proxy */ * FROM t"),
+            Some(ReadysetHint::Proxy)
+        );
+        assert_eq!(
+            readyset_hint("select /*+ READYSET = CACHED */ x from t"),
+            Some(ReadysetHint::Cached)
+        );
+    }
+
+    #[test]
+    fn hint_before_keyword() {
+        assert_eq!(
+            readyset_hint("  /*+ readyset=cached */ SELECT * FROM t"),
+            Some(ReadysetHint::Cached)
+        );
+    }
+
+    #[test]
+    fn hint_among_other_hints() {
+        assert_eq!(
+            readyset_hint("SELECT /* a comment */ /*+ NO_INDEX(t) readyset=proxy */ * FROM t"),
+            Some(ReadysetHint::Proxy)
+        );
+    }
+
+    #[test]
+    fn no_hint() {
+        assert_eq!(readyset_hint("SELECT * FROM t"), None);
+        assert_eq!(readyset_hint("SELECT /* readyset=proxy */ * FROM t"), None);
+        assert_eq!(
+            readyset_hint("SELECT /*+ readyset=sometimes */ * FROM t"),
+            None
+        );
+        assert_eq!(
+            readyset_hint("SELECT * FROM t WHERE x = '/*+ readyset=proxy */'"),
+            None
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for hint in [ReadysetHint::Proxy, ReadysetHint::Cached] {
+            assert_eq!(readyset_hint(&format!("{} SELECT 1", hint)), Some(hint));
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::{fmt, str};
 
 use nom::bytes::complete::{tag, tag_no_case};
-use nom::combinator::opt;
+use nom::combinator::{map, opt};
 use nom::multi::separated_list1;
 use nom::sequence::{delimited, preceded, terminated, tuple};
 use nom::IResult;
@@ -9,9 +9,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::column::Column;
 use crate::common::{
-    assignment_expr_list, field_list, schema_table_reference_no_alias, statement_terminator,
-    value_list, ws_sep_comma, Literal,
+    assignment_expr_list, field_list, returning_clause, schema_table_reference_no_alias,
+    statement_terminator, value_list, ws_sep_comma, FieldDefinitionExpression, Literal,
 };
+use crate::select::{nested_selection, SelectStatement};
 use crate::table::Table;
 use crate::whitespace::{whitespace0, whitespace1};
 use crate::{Dialect, Expression};
@@ -21,13 +22,22 @@ pub struct InsertStatement {
     pub table: Table,
     pub fields: Option<Vec<Column>>,
     pub data: Vec<Vec<Literal>>,
+    /// The query whose rows are inserted, if this is an `INSERT ... SELECT` statement, in which
+    /// case `data` is empty
+    pub select: Option<Box<SelectStatement>>,
     pub ignore: bool,
     pub on_duplicate: Option<Vec<(Column, Expression)>>,
+    /// The fields to return for each inserted row, if the statement has a `RETURNING` clause
+    pub returning: Option<Vec<FieldDefinitionExpression>>,
 }
 
 impl fmt::Display for InsertStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "INSERT INTO `{}`", self.table.name)?;
+        write!(f, "INSERT ")?;
+        if self.ignore {
+            write!(f, "IGNORE ")?;
+        }
+        write!(f, "INTO `{}`", self.table.name)?;
         if let Some(ref fields) = self.fields {
             write!(
                 f,
@@ -39,22 +49,49 @@ impl fmt::Display for InsertStatement {
                     .join(", ")
             )?;
         }
-        write!(
-            f,
-            " VALUES {}",
-            self.data
-                .iter()
-                .map(|datas| format!(
-                    "({})",
-                    datas
-                        .iter()
-                        .map(|l| l.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ))
-                .collect::<Vec<_>>()
-                .join(", ")
-        )
+        if let Some(ref select) = self.select {
+            write!(f, " {}", select)?;
+        } else {
+            write!(
+                f,
+                " VALUES {}",
+                self.data
+                    .iter()
+                    .map(|datas| format!(
+                        "({})",
+                        datas
+                            .iter()
+                            .map(|l| l.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        if let Some(ref on_duplicate) = self.on_duplicate {
+            write!(
+                f,
+                " ON DUPLICATE KEY UPDATE {}",
+                on_duplicate
+                    .iter()
+                    .map(|(col, expr)| format!("{} = {}", col, expr))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        if let Some(ref returning) = self.returning {
+            write!(
+                f,
+                " RETURNING {}",
+                returning
+                    .iter()
+                    .map(|field| field.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -90,13 +127,33 @@ fn on_duplicate(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<(Colum
     }
 }
 
+/// The rows inserted by an `INSERT` statement: either a list of values or a query
+enum Source {
+    Values(Vec<Vec<Literal>>),
+    Select(Box<SelectStatement>),
+}
+
+fn source(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Source> {
+    move |i| {
+        // Don't backtrack into parsing a query once we've seen `VALUES`, so that errors in the
+        // list of values are reported where they occur
+        let values: IResult<&[u8], &[u8]> = terminated(tag_no_case("values"), whitespace0)(i);
+        match values {
+            Ok((i, _)) => map(separated_list1(ws_sep_comma, data(dialect)), Source::Values)(i),
+            Err(_) => map(nested_selection(dialect), |select| {
+                Source::Select(Box::new(select))
+            })(i),
+        }
+    }
+}
+
 // Parse rule for a SQL insert query.
-// TODO(malte): support REPLACE, nested selection, DEFAULT VALUES
+// TODO(malte): support REPLACE, DEFAULT VALUES
 pub fn insertion(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], InsertStatement> {
     move |i| {
         let (
             remaining_input,
-            (_, ignore_res, _, _, _, table, _, fields, _, _, data, on_duplicate, _),
+            (_, ignore_res, _, _, _, table, _, fields, source, on_duplicate, returning, _),
         ) = tuple((
             tag_no_case("insert"),
             opt(preceded(whitespace1, tag_no_case("ignore"))),
@@ -106,14 +163,17 @@ pub fn insertion(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], InsertSta
             schema_table_reference_no_alias(dialect),
             whitespace0,
             opt(fields(dialect)),
-            tag_no_case("values"),
-            whitespace0,
-            separated_list1(ws_sep_comma, data(dialect)),
+            source(dialect),
             opt(on_duplicate(dialect)),
+            opt(returning_clause(dialect)),
             statement_terminator,
         ))(i)?;
         assert!(table.alias.is_none());
         let ignore = ignore_res.is_some();
+        let (data, select) = match source {
+            Source::Values(data) => (data, None),
+            Source::Select(select) => (vec![], Some(select)),
+        };
 
         Ok((
             remaining_input,
@@ -121,8 +181,10 @@ pub fn insertion(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], InsertSta
                 table,
                 fields,
                 data,
+                select,
                 ignore,
                 on_duplicate,
+                returning,
             },
         ))
     }
@@ -154,6 +216,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn insert_select() {
+        let qstring = "INSERT INTO users (id, name) SELECT id, name FROM admins WHERE active = 1";
+
+        let res = test_parse!(insertion(Dialect::MySQL), qstring.as_bytes());
+        assert_eq!(
+            res.fields,
+            Some(vec![Column::from("id"), Column::from("name")])
+        );
+        assert!(res.data.is_empty());
+        let select = res.select.as_ref().unwrap();
+        assert_eq!(select.tables, vec![Table::from("admins")]);
+        assert!(select.where_clause.is_some());
+        assert_eq!(
+            res.to_string(),
+            "INSERT INTO `users` (`id`, `name`) SELECT `id`, `name` FROM `admins` WHERE (`active` = 1)"
+        );
+
+        let res = test_parse!(
+            insertion(Dialect::PostgreSQL),
+            b"INSERT INTO users SELECT * FROM admins"
+        );
+        assert!(res.fields.is_none());
+        assert!(res.select.is_some());
+    }
+
     mod mysql {
         use super::*;
         use crate::column::Column;
@@ -475,5 +563,30 @@ mod tests {
                 }
             );
         }
+
+        #[test]
+        fn insert_with_returning() {
+            let qstring = "INSERT INTO users (name) VALUES ('test') RETURNING id, *;";
+
+            let res = insertion(Dialect::PostgreSQL)(qstring.as_bytes());
+            let stmt = res.unwrap().1;
+            assert_eq!(
+                stmt,
+                InsertStatement {
+                    table: Table::from("users"),
+                    fields: Some(vec![Column::from("name")]),
+                    data: vec![vec!["test".into()]],
+                    returning: Some(vec![
+                        FieldDefinitionExpression::from(Column::from("id")),
+                        FieldDefinitionExpression::All,
+                    ]),
+                    ..Default::default()
+                }
+            );
+            assert_eq!(
+                stmt.to_string(),
+                "INSERT INTO `users` (`name`) VALUES ('test') RETURNING `id`, *"
+            );
+        }
     }
 }
@@ -11,6 +11,7 @@ use test_strategy::Arbitrary;
 use crate::column::Column;
 use crate::select::SelectStatement;
 use crate::table::Table;
+use crate::values::ValuesTable;
 use crate::{Expression, SqlIdentifier};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -19,19 +20,30 @@ pub enum JoinRightSide {
     Table(Table),
     /// A comma-separated (and implicitly joined) sequence of tables.
     Tables(Vec<Table>),
-    /// A nested selection, represented as (query, alias).
-    NestedSelect(Box<SelectStatement>, SqlIdentifier),
+    /// A nested selection, represented as (query, alias, column names). The list of column names
+    /// is empty unless the query renames the columns of the subquery, as in `AS x (c1, c2)`.
+    NestedSelect(Box<SelectStatement>, SqlIdentifier, Vec<SqlIdentifier>),
+    /// A `VALUES` table constructor, with an alias and optionally names for its columns.
+    Values(ValuesTable),
 }
 
 impl fmt::Display for JoinRightSide {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             JoinRightSide::Table(t) => write!(f, "{}", t),
-            JoinRightSide::NestedSelect(subquery, alias) => {
+            JoinRightSide::NestedSelect(subquery, alias, columns) => {
                 write!(f, "({}) AS {}", subquery, alias)?;
+                if !columns.is_empty() {
+                    write!(
+                        f,
+                        " ({})",
+                        columns.iter().map(|c| format!("`{}`", c)).join(", ")
+                    )?;
+                }
                 Ok(())
             }
             JoinRightSide::Tables(ts) => write!(f, "({})", ts.iter().join(", ")),
+            JoinRightSide::Values(values) => write!(f, "{}", values),
         }
     }
 }
@@ -70,6 +82,8 @@ impl fmt::Display for JoinOperator {
 pub enum JoinConstraint {
     On(Expression),
     Using(Vec<Column>),
+    /// A `NATURAL` join, which joins on all the columns with the same name in both sides
+    Natural,
     Empty,
 }
 
@@ -86,7 +100,7 @@ impl fmt::Display for JoinConstraint {
                     .collect::<Vec<_>>()
                     .join(", ")
             )?,
-            JoinConstraint::Empty => {}
+            JoinConstraint::Natural | JoinConstraint::Empty => {}
         }
         Ok(())
     }
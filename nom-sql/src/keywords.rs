@@ -14,7 +14,7 @@ use crate::common::eof;
 // which is implemented for tuples sizes up to 21. Because of this constraint
 // on maximum tuple sizes, keywords are aggregated into groups of 20
 
-fn keyword_follow_char(i: &[u8]) -> IResult<&[u8], &[u8]> {
+pub(crate) fn keyword_follow_char(i: &[u8]) -> IResult<&[u8], &[u8]> {
     peek(alt((
         tag(" "),
         tag("\n"),
@@ -160,6 +160,7 @@ fn keyword_p_to_t(i: &[u8]) -> IResult<&[u8], &[u8]> {
 
 fn keyword_t_to_z(i: &[u8]) -> IResult<&[u8], &[u8]> {
     alt((
+        terminated(tag_no_case("TABLESAMPLE"), keyword_follow_char),
         terminated(tag_no_case("TRANSACTION"), keyword_follow_char),
         terminated(tag_no_case("TRIGGER"), keyword_follow_char),
         terminated(tag_no_case("UNION"), keyword_follow_char),
@@ -8,7 +8,10 @@ extern crate pretty_assertions;
 
 pub use sql_identifier::SqlIdentifier;
 
-pub use self::alter::{AlterColumnOperation, AlterTableDefinition, AlterTableStatement};
+pub use self::alter::{
+    AlterCacheStatement, AlterColumnOperation, AlterReadysetStatement, AlterTableDefinition,
+    AlterTableStatement, CompressionAlgorithm,
+};
 pub use self::column::{Column, ColumnConstraint, ColumnSpecification};
 pub use self::common::{
     Double, FieldDefinitionExpression, FieldReference, Float, IndexType, ItemPlaceholder, Literal,
@@ -19,17 +22,26 @@ pub use self::create::{
     CacheInner, CreateCacheStatement, CreateTableStatement, CreateViewStatement,
     SelectSpecification,
 };
+pub use self::create_table_options::CreateTableOption;
+pub use self::cursor::{CloseCursorStatement, DeclareCursorStatement, FetchCount, FetchStatement};
 pub use self::delete::DeleteStatement;
-pub use self::dialect::Dialect;
-pub use self::drop::{DropCacheStatement, DropTableStatement, DropViewStatement};
+pub use self::dialect::{Dialect, DialectKind, Feature, MySqlParseOptions};
+pub use self::drop::{
+    DropAllCachesStatement, DropCacheStatement, DropTableStatement, DropViewStatement,
+};
 pub use self::explain::ExplainStatement;
 pub use self::expression::{
     BinaryOperator, Expression, FunctionExpression, InValue, UnaryOperator,
 };
+pub use self::flush::FlushWaitStatement;
+pub use self::hint::{readyset_hint, ReadysetHint};
 pub use self::insert::InsertStatement;
 pub use self::join::{JoinConstraint, JoinOperator, JoinRightSide};
+pub use self::maintenance::{MaintenanceOperation, MaintenanceStatement};
+pub use self::merge::{MergeAction, MergeClause, MergeStatement};
 pub use self::order::{OrderClause, OrderType};
 pub use self::parser::*;
+pub use self::prepare::{DeallocateStatement, ExecuteStatement, PrepareStatement};
 pub use self::select::{
     CommonTableExpression, GroupByClause, JoinClause, LimitClause, SelectStatement,
 };
@@ -39,9 +51,10 @@ pub use self::set::{
     VariableScope,
 };
 pub use self::show::ShowStatement;
-pub use self::table::Table;
+pub use self::table::{Table, TableSample};
 pub use self::update::UpdateStatement;
 pub use self::use_statement::UseStatement;
+pub use self::values::{ValuesStatement, ValuesTable};
 
 pub mod parser;
 
@@ -57,14 +70,20 @@ mod common;
 mod compound_select;
 mod create;
 mod create_table_options;
+mod cursor;
 mod delete;
 mod drop;
 mod explain;
 mod expression;
+mod flush;
+mod hint;
 mod insert;
 mod join;
 mod keywords;
+mod maintenance;
+mod merge;
 mod order;
+mod prepare;
 mod rename;
 mod select;
 mod set;
@@ -74,4 +93,5 @@ mod table;
 mod transaction;
 mod update;
 mod use_statement;
+mod values;
 pub mod whitespace;
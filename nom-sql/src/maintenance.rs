@@ -0,0 +1,330 @@
+use std::fmt;
+
+use itertools::Itertools;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::combinator::{map, opt, value};
+use nom::multi::{many0, separated_list1};
+use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::IResult;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{schema_table_reference_no_alias, statement_terminator, ws_sep_comma};
+use crate::keywords::keyword_follow_char;
+use crate::table::Table;
+use crate::whitespace::{whitespace0, whitespace1};
+use crate::{Dialect, DialectKind, SqlIdentifier};
+
+/// The kind of maintenance a [`MaintenanceStatement`] performs
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum MaintenanceOperation {
+    /// `ANALYZE`, which refreshes the statistics the database keeps about the tables
+    Analyze,
+    /// `VACUUM` (PostgreSQL only)
+    Vacuum,
+    /// `OPTIMIZE TABLE` (MySQL only)
+    Optimize,
+    /// `CHECK TABLE` (MySQL only)
+    Check,
+    /// `REPAIR TABLE` (MySQL only)
+    Repair,
+    /// `CHECKSUM TABLE` (MySQL only)
+    Checksum,
+}
+
+impl fmt::Display for MaintenanceOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaintenanceOperation::Analyze => write!(f, "ANALYZE"),
+            MaintenanceOperation::Vacuum => write!(f, "VACUUM"),
+            MaintenanceOperation::Optimize => write!(f, "OPTIMIZE"),
+            MaintenanceOperation::Check => write!(f, "CHECK"),
+            MaintenanceOperation::Repair => write!(f, "REPAIR"),
+            MaintenanceOperation::Checksum => write!(f, "CHECKSUM"),
+        }
+    }
+}
+
+/// A statement that maintains tables in the database without changing their contents, such as
+/// `ANALYZE TABLE t`, `VACUUM`, or `OPTIMIZE TABLE t`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceStatement {
+    pub operation: MaintenanceOperation,
+    /// Modifiers given before the tables, such as `NO_WRITE_TO_BINLOG` or `VERBOSE`, in uppercase
+    pub modifiers: Vec<SqlIdentifier>,
+    /// The tables to maintain. This is empty for a PostgreSQL `ANALYZE` or `VACUUM` without a list
+    /// of tables, which maintains every table in the database.
+    pub tables: Vec<Table>,
+    /// Options given after the tables, such as the `QUICK` in `CHECK TABLE t QUICK`, in uppercase
+    pub options: Vec<SqlIdentifier>,
+}
+
+impl fmt::Display for MaintenanceStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+        for modifier in &self.modifiers {
+            write!(f, " {}", modifier)?;
+        }
+        if !self.tables.is_empty() {
+            if self.operation != MaintenanceOperation::Vacuum {
+                write!(f, " TABLE")?;
+            }
+            write!(f, " {}", self.tables.iter().join(", "))?;
+        }
+        for option in &self.options {
+            write!(f, " {}", option)?;
+        }
+        Ok(())
+    }
+}
+
+// A keyword that isn't the prefix of a longer word, returned as written in `word`
+fn keyword(word: &'static str) -> impl Fn(&[u8]) -> IResult<&[u8], SqlIdentifier> {
+    move |i| {
+        map(terminated(tag_no_case(word), keyword_follow_char), |_| {
+            SqlIdentifier::from(word)
+        })(i)
+    }
+}
+
+fn table_names(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<Table>> {
+    move |i| separated_list1(ws_sep_comma, schema_table_reference_no_alias(dialect))(i)
+}
+
+// The options after the tables of a MySQL maintenance statement
+fn mysql_options(
+    operation: MaintenanceOperation,
+) -> impl Fn(&[u8]) -> IResult<&[u8], SqlIdentifier> {
+    move |i| match operation {
+        MaintenanceOperation::Check => alt((
+            map(
+                tuple((tag_no_case("for"), whitespace1, keyword("UPGRADE"))),
+                |_| SqlIdentifier::from("FOR UPGRADE"),
+            ),
+            keyword("QUICK"),
+            keyword("FAST"),
+            keyword("MEDIUM"),
+            keyword("EXTENDED"),
+            keyword("CHANGED"),
+        ))(i),
+        MaintenanceOperation::Repair => {
+            alt((keyword("QUICK"), keyword("EXTENDED"), keyword("USE_FRM")))(i)
+        }
+        MaintenanceOperation::Checksum => alt((keyword("QUICK"), keyword("EXTENDED")))(i),
+        _ => Err(nom::Err::Error(nom::error::Error::new(
+            i,
+            nom::error::ErrorKind::Alt,
+        ))),
+    }
+}
+
+// Parse rule for the MySQL `ANALYZE TABLE`, `OPTIMIZE TABLE`, `CHECK TABLE`, `REPAIR TABLE`, and
+// `CHECKSUM TABLE` statements
+fn mysql_maintenance(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], MaintenanceStatement> {
+    move |i| {
+        let (i, operation) = alt((
+            value(MaintenanceOperation::Analyze, tag_no_case("analyze")),
+            value(MaintenanceOperation::Optimize, tag_no_case("optimize")),
+            value(MaintenanceOperation::Checksum, tag_no_case("checksum")),
+            value(MaintenanceOperation::Check, tag_no_case("check")),
+            value(MaintenanceOperation::Repair, tag_no_case("repair")),
+        ))(i)?;
+        let (i, modifier) = match operation {
+            MaintenanceOperation::Check | MaintenanceOperation::Checksum => (i, None),
+            _ => opt(preceded(
+                whitespace1,
+                alt((keyword("NO_WRITE_TO_BINLOG"), keyword("LOCAL"))),
+            ))(i)?,
+        };
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("table")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, tables) = table_names(dialect)(i)?;
+        let (i, options) = many0(preceded(whitespace1, mysql_options(operation)))(i)?;
+
+        Ok((
+            i,
+            MaintenanceStatement {
+                operation,
+                modifiers: modifier.into_iter().collect(),
+                tables,
+                options,
+            },
+        ))
+    }
+}
+
+// Parse rule for the PostgreSQL `ANALYZE` and `VACUUM` statements.
+//
+// The options given in parentheses must be bare names, such as `(VERBOSE, SKIP_LOCKED)`, and
+// columns can't be given for the tables
+fn postgres_maintenance(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], MaintenanceStatement> {
+    move |i| {
+        let (i, operation) = alt((
+            value(MaintenanceOperation::Analyze, tag_no_case("analyze")),
+            value(MaintenanceOperation::Vacuum, tag_no_case("vacuum")),
+        ))(i)?;
+        let (i, _) = keyword_follow_char(i)?;
+        let (i, modifiers) = alt((
+            preceded(
+                whitespace0,
+                delimited(
+                    terminated(tag("("), whitespace0),
+                    separated_list1(
+                        ws_sep_comma,
+                        map(dialect.identifier(), |option| {
+                            SqlIdentifier::from(option.to_uppercase())
+                        }),
+                    ),
+                    preceded(whitespace0, tag(")")),
+                ),
+            ),
+            move |i| match operation {
+                MaintenanceOperation::Vacuum => many0(preceded(
+                    whitespace1,
+                    alt((
+                        keyword("FULL"),
+                        keyword("FREEZE"),
+                        keyword("VERBOSE"),
+                        keyword("ANALYZE"),
+                    )),
+                ))(i),
+                _ => many0(preceded(whitespace1, keyword("VERBOSE")))(i),
+            },
+        ))(i)?;
+        let (i, tables) = opt(preceded(whitespace0, table_names(dialect)))(i)?;
+
+        Ok((
+            i,
+            MaintenanceStatement {
+                operation,
+                modifiers,
+                tables: tables.unwrap_or_default(),
+                options: vec![],
+            },
+        ))
+    }
+}
+
+// Parse rule for a table maintenance statement
+pub fn maintenance(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], MaintenanceStatement> {
+    move |i| {
+        let (i, statement) = match dialect.kind() {
+            DialectKind::MySQL => mysql_maintenance(dialect)(i)?,
+            DialectKind::PostgreSQL => postgres_maintenance(dialect)(i)?,
+        };
+        let (i, _) = statement_terminator(i)?;
+        Ok((i, statement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mysql_analyze() {
+        let res = test_parse!(
+            maintenance(Dialect::MySQL),
+            b"ANALYZE NO_WRITE_TO_BINLOG TABLE t1, db.t2"
+        );
+        assert_eq!(
+            res,
+            MaintenanceStatement {
+                operation: MaintenanceOperation::Analyze,
+                modifiers: vec!["NO_WRITE_TO_BINLOG".into()],
+                tables: vec![
+                    Table::from("t1"),
+                    Table {
+                        schema: Some("db".into()),
+                        ..Table::from("t2")
+                    }
+                ],
+                options: vec![],
+            }
+        );
+        assert_eq!(
+            res.to_string(),
+            "ANALYZE NO_WRITE_TO_BINLOG TABLE `t1`, `db`.`t2`"
+        );
+    }
+
+    #[test]
+    fn mysql_table_maintenance() {
+        let res = test_parse!(maintenance(Dialect::MySQL), b"optimize local table t;");
+        assert_eq!(res.operation, MaintenanceOperation::Optimize);
+        assert_eq!(res.to_string(), "OPTIMIZE LOCAL TABLE `t`");
+
+        let res = test_parse!(
+            maintenance(Dialect::MySQL),
+            b"CHECK TABLE t1, t2 FOR UPGRADE quick"
+        );
+        assert_eq!(res.operation, MaintenanceOperation::Check);
+        assert_eq!(res.tables.len(), 2);
+        assert_eq!(
+            res.options,
+            vec![SqlIdentifier::from("FOR UPGRADE"), "QUICK".into()]
+        );
+
+        let res = test_parse!(maintenance(Dialect::MySQL), b"CHECKSUM TABLE t EXTENDED");
+        assert_eq!(res.operation, MaintenanceOperation::Checksum);
+        assert_eq!(res.to_string(), "CHECKSUM TABLE `t` EXTENDED");
+
+        let res = test_parse!(maintenance(Dialect::MySQL), b"REPAIR TABLE t USE_FRM");
+        assert_eq!(res.operation, MaintenanceOperation::Repair);
+        assert_eq!(res.options, vec![SqlIdentifier::from("USE_FRM")]);
+    }
+
+    #[test]
+    fn mysql_requires_table() {
+        assert!(maintenance(Dialect::MySQL)(b"ANALYZE t").is_err());
+        assert!(maintenance(Dialect::MySQL)(b"VACUUM t").is_err());
+        assert!(maintenance(Dialect::MySQL)(b"OPTIMIZE TABLE t QUICK").is_err());
+    }
+
+    #[test]
+    fn postgres_analyze() {
+        let res = test_parse!(maintenance(Dialect::PostgreSQL), b"ANALYZE");
+        assert_eq!(res.operation, MaintenanceOperation::Analyze);
+        assert!(res.tables.is_empty());
+
+        let res = test_parse!(
+            maintenance(Dialect::PostgreSQL),
+            b"analyze verbose public.t1, t2"
+        );
+        assert_eq!(res.modifiers, vec![SqlIdentifier::from("VERBOSE")]);
+        assert_eq!(res.tables.len(), 2);
+        assert_eq!(res.tables[0].schema, Some("public".into()));
+    }
+
+    #[test]
+    fn postgres_vacuum() {
+        let res = test_parse!(maintenance(Dialect::PostgreSQL), b"VACUUM FULL ANALYZE t");
+        assert_eq!(
+            res,
+            MaintenanceStatement {
+                operation: MaintenanceOperation::Vacuum,
+                modifiers: vec!["FULL".into(), "ANALYZE".into()],
+                tables: vec![Table::from("t")],
+                options: vec![],
+            }
+        );
+        assert_eq!(res.to_string(), "VACUUM FULL ANALYZE `t`");
+
+        let res = test_parse!(
+            maintenance(Dialect::PostgreSQL),
+            b"VACUUM (verbose, skip_locked) full_table"
+        );
+        assert_eq!(
+            res.modifiers,
+            vec![SqlIdentifier::from("VERBOSE"), "SKIP_LOCKED".into()]
+        );
+        assert_eq!(res.tables, vec![Table::from("full_table")]);
+
+        let res = test_parse!(maintenance(Dialect::PostgreSQL), b"vacuum;");
+        assert!(res.modifiers.is_empty());
+        assert!(res.tables.is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+use std::fmt;
+
+use itertools::Itertools;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::combinator::{map, opt, value};
+use nom::multi::{many1, separated_list1};
+use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::IResult;
+use serde::{Deserialize, Serialize};
+
+use crate::column::Column;
+use crate::common::{
+    assignment_expr_list, field_list, schema_table_reference, statement_terminator, ws_sep_comma,
+};
+use crate::expression::expression;
+use crate::join::JoinRightSide;
+use crate::keywords::keyword_follow_char;
+use crate::select::join_rhs;
+use crate::table::Table;
+use crate::whitespace::{whitespace0, whitespace1};
+use crate::{Dialect, Expression};
+
+/// What a `WHEN` clause of a [`MergeStatement`] does with the rows it applies to
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum MergeAction {
+    /// `UPDATE SET ...`, for rows of the target table that match a row of the source
+    Update(Vec<(Column, Expression)>),
+    /// `DELETE`, for rows of the target table that match a row of the source
+    Delete,
+    /// `INSERT [(columns)] VALUES (...)`, for rows of the source that don't match any row of the
+    /// target table
+    Insert {
+        fields: Vec<Column>,
+        values: Vec<Expression>,
+    },
+    /// `DO NOTHING`
+    DoNothing,
+}
+
+impl fmt::Display for MergeAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeAction::Update(fields) => write!(
+                f,
+                "UPDATE SET {}",
+                fields
+                    .iter()
+                    .map(|(col, expr)| format!("{} = {}", col, expr))
+                    .join(", ")
+            ),
+            MergeAction::Delete => write!(f, "DELETE"),
+            MergeAction::Insert { fields, values } => {
+                write!(f, "INSERT ")?;
+                if !fields.is_empty() {
+                    write!(f, "({}) ", fields.iter().join(", "))?;
+                }
+                write!(f, "VALUES ({})", values.iter().join(", "))
+            }
+            MergeAction::DoNothing => write!(f, "DO NOTHING"),
+        }
+    }
+}
+
+/// A `WHEN [NOT] MATCHED [AND condition] THEN action` clause of a [`MergeStatement`]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct MergeClause {
+    /// Whether this clause applies to rows of the target table that match a row of the source
+    /// (`WHEN MATCHED`), rather than to rows of the source that don't match any (`WHEN NOT
+    /// MATCHED`)
+    pub matched: bool,
+    /// The extra condition given after `AND`, if any
+    pub condition: Option<Expression>,
+    pub action: MergeAction,
+}
+
+impl fmt::Display for MergeClause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WHEN ")?;
+        if !self.matched {
+            write!(f, "NOT ")?;
+        }
+        write!(f, "MATCHED")?;
+        if let Some(ref condition) = self.condition {
+            write!(f, " AND {}", condition)?;
+        }
+        write!(f, " THEN {}", self.action)
+    }
+}
+
+/// A `MERGE INTO target USING source ON condition WHEN ...` statement, which inserts, updates or
+/// deletes rows of the target table depending on whether they match the rows of the source
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct MergeStatement {
+    /// The table to modify
+    pub table: Table,
+    /// The table or subquery whose rows are merged into the target table
+    pub source: JoinRightSide,
+    /// The condition rows of the target table and the source are matched on
+    pub on: Expression,
+    /// The `WHEN` clauses, in the order they're checked
+    pub clauses: Vec<MergeClause>,
+}
+
+impl fmt::Display for MergeStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "MERGE INTO {} USING {} ON {} {}",
+            self.table,
+            self.source,
+            self.on,
+            self.clauses.iter().join(" ")
+        )
+    }
+}
+
+fn merge_action(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], MergeAction> {
+    move |i| {
+        let update = map(
+            preceded(
+                tuple((
+                    tag_no_case("update"),
+                    whitespace1,
+                    tag_no_case("set"),
+                    whitespace1,
+                )),
+                assignment_expr_list(dialect),
+            ),
+            MergeAction::Update,
+        );
+        let delete = value(
+            MergeAction::Delete,
+            terminated(tag_no_case("delete"), keyword_follow_char),
+        );
+        let insert = map(
+            tuple((
+                tag_no_case("insert"),
+                opt(preceded(
+                    whitespace0,
+                    delimited(
+                        terminated(tag("("), whitespace0),
+                        field_list(dialect),
+                        preceded(whitespace0, tag(")")),
+                    ),
+                )),
+                whitespace0,
+                tag_no_case("values"),
+                whitespace0,
+                delimited(
+                    terminated(tag("("), whitespace0),
+                    separated_list1(ws_sep_comma, expression(dialect)),
+                    preceded(whitespace0, tag(")")),
+                ),
+            )),
+            |(_, fields, _, _, _, values)| MergeAction::Insert {
+                fields: fields.unwrap_or_default(),
+                values,
+            },
+        );
+        let do_nothing = value(
+            MergeAction::DoNothing,
+            tuple((tag_no_case("do"), whitespace1, tag_no_case("nothing"))),
+        );
+        alt((update, delete, insert, do_nothing))(i)
+    }
+}
+
+fn merge_clause(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], MergeClause> {
+    move |i| {
+        let (i, _) = tag_no_case("when")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, not) = opt(terminated(tag_no_case("not"), whitespace1))(i)?;
+        let (i, _) = tag_no_case("matched")(i)?;
+        let (i, condition) = opt(preceded(
+            delimited(whitespace1, tag_no_case("and"), whitespace1),
+            expression(dialect),
+        ))(i)?;
+        let (i, _) = delimited(whitespace1, tag_no_case("then"), whitespace1)(i)?;
+        let (rest, action) = merge_action(dialect)(i)?;
+
+        // Only rows that match can be updated or deleted, and only rows that don't can be inserted
+        let matched = not.is_none();
+        let valid = match action {
+            MergeAction::Update(_) | MergeAction::Delete => matched,
+            MergeAction::Insert { .. } => !matched,
+            MergeAction::DoNothing => true,
+        };
+        if !valid {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                i,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+
+        Ok((
+            rest,
+            MergeClause {
+                matched,
+                condition,
+                action,
+            },
+        ))
+    }
+}
+
+// Parse rule for a MERGE statement
+pub fn merge(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], MergeStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("merge")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("into")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, table) = schema_table_reference(dialect)(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("using")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, source) = join_rhs(dialect)(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("on")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, on) = expression(dialect)(i)?;
+        let (i, clauses) = many1(preceded(whitespace1, merge_clause(dialect)))(i)?;
+        let (i, _) = statement_terminator(i)?;
+
+        Ok((
+            i,
+            MergeStatement {
+                table,
+                source,
+                on,
+                clauses,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinaryOperator, Literal};
+
+    #[test]
+    fn merge_all_actions() {
+        let res = test_parse!(
+            merge(Dialect::PostgreSQL),
+            b"MERGE INTO t AS x USING s ON x.id = s.id \
+              WHEN MATCHED AND s.deleted THEN DELETE \
+              WHEN MATCHED THEN UPDATE SET v = s.v \
+              WHEN NOT MATCHED AND s.v > 0 THEN INSERT (id, v) VALUES (s.id, s.v) \
+              WHEN NOT MATCHED THEN DO NOTHING"
+        );
+        assert_eq!(
+            res.table,
+            Table {
+                alias: Some("x".into()),
+                ..Table::from("t")
+            }
+        );
+        assert_eq!(res.source, JoinRightSide::Table(Table::from("s")));
+        assert_eq!(
+            res.on,
+            Expression::BinaryOp {
+                lhs: Box::new(Expression::Column(Column::from("x.id"))),
+                op: BinaryOperator::Equal,
+                rhs: Box::new(Expression::Column(Column::from("s.id"))),
+            }
+        );
+        assert_eq!(
+            res.clauses,
+            vec![
+                MergeClause {
+                    matched: true,
+                    condition: Some(Expression::Column(Column::from("s.deleted"))),
+                    action: MergeAction::Delete,
+                },
+                MergeClause {
+                    matched: true,
+                    condition: None,
+                    action: MergeAction::Update(vec![(
+                        Column::from("v"),
+                        Expression::Column(Column::from("s.v"))
+                    )]),
+                },
+                MergeClause {
+                    matched: false,
+                    condition: Some(Expression::BinaryOp {
+                        lhs: Box::new(Expression::Column(Column::from("s.v"))),
+                        op: BinaryOperator::Greater,
+                        rhs: Box::new(Expression::Literal(Literal::Integer(0))),
+                    }),
+                    action: MergeAction::Insert {
+                        fields: vec![Column::from("id"), Column::from("v")],
+                        values: vec![
+                            Expression::Column(Column::from("s.id")),
+                            Expression::Column(Column::from("s.v"))
+                        ],
+                    },
+                },
+                MergeClause {
+                    matched: false,
+                    condition: None,
+                    action: MergeAction::DoNothing,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_using_subquery() {
+        let res = test_parse!(
+            merge(Dialect::PostgreSQL),
+            b"merge into t using (select id from s) as src on t.id = src.id \
+              when not matched then insert values (src.id);"
+        );
+        assert!(matches!(res.source, JoinRightSide::NestedSelect(..)));
+        assert_eq!(
+            res.to_string(),
+            "MERGE INTO `t` USING (SELECT `id` FROM `s`) AS src ON (`t`.`id` = `src`.`id`) \
+             WHEN NOT MATCHED THEN INSERT VALUES (`src`.`id`)"
+        );
+    }
+
+    #[test]
+    fn merge_invalid_actions() {
+        assert!(merge(Dialect::PostgreSQL)(
+            b"MERGE INTO t USING s ON t.id = s.id WHEN NOT MATCHED THEN DELETE"
+        )
+        .is_err());
+        assert!(merge(Dialect::PostgreSQL)(
+            b"MERGE INTO t USING s ON t.id = s.id WHEN MATCHED THEN INSERT VALUES (1)"
+        )
+        .is_err());
+        assert!(merge(Dialect::PostgreSQL)(b"MERGE INTO t USING s ON t.id = s.id").is_err());
+    }
+}
@@ -5,19 +5,32 @@ use nom::combinator::map;
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
-use crate::alter::{alter_table_statement, AlterTableStatement};
+use crate::alter::{
+    alter_cache_statement, alter_readyset_statement, alter_table_statement, AlterCacheStatement,
+    AlterReadysetStatement, AlterTableStatement,
+};
 use crate::compound_select::{compound_selection, CompoundSelectStatement};
 use crate::create::{
     create_cached_query, creation, key_specification, view_creation, CreateCacheStatement,
     CreateTableStatement, CreateViewStatement,
 };
+use crate::cursor::{
+    close_cursor, declare_cursor, fetch, CloseCursorStatement, DeclareCursorStatement,
+    FetchStatement,
+};
 use crate::delete::{deletion, DeleteStatement};
 use crate::drop::{
-    drop_cached_query, drop_table, drop_view, DropCacheStatement, DropTableStatement,
-    DropViewStatement,
+    drop_all_caches, drop_cached_query, drop_table, drop_view, DropAllCachesStatement,
+    DropCacheStatement, DropTableStatement, DropViewStatement,
 };
 use crate::explain::{explain_statement, ExplainStatement};
+use crate::flush::{flush_wait, FlushWaitStatement};
 use crate::insert::{insertion, InsertStatement};
+use crate::maintenance::{maintenance, MaintenanceOperation, MaintenanceStatement};
+use crate::merge::{merge, MergeStatement};
+use crate::prepare::{
+    deallocate, execute, prepare, DeallocateStatement, ExecuteStatement, PrepareStatement,
+};
 use crate::rename::{rename_table, RenameTableStatement};
 use crate::select::{selection, SelectStatement};
 use crate::set::{set, SetStatement};
@@ -28,6 +41,8 @@ use crate::transaction::{
 };
 use crate::update::{updating, UpdateStatement};
 use crate::use_statement::{use_statement, UseStatement};
+use crate::values::{values, ValuesStatement};
+use crate::whitespace::whitespace0;
 use crate::{Dialect, TableKey};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -37,7 +52,10 @@ pub enum SqlQuery {
     CreateView(CreateViewStatement),
     CreateCache(CreateCacheStatement),
     DropCache(DropCacheStatement),
+    DropAllCaches(DropAllCachesStatement),
     AlterTable(AlterTableStatement),
+    AlterReadyset(AlterReadysetStatement),
+    AlterCache(AlterCacheStatement),
     Insert(InsertStatement),
     CompoundSelect(CompoundSelectStatement),
     Select(SelectStatement),
@@ -53,6 +71,16 @@ pub enum SqlQuery {
     Use(UseStatement),
     Show(ShowStatement),
     Explain(ExplainStatement),
+    DeclareCursor(DeclareCursorStatement),
+    Fetch(FetchStatement),
+    CloseCursor(CloseCursorStatement),
+    FlushWait(FlushWaitStatement),
+    Values(ValuesStatement),
+    Maintenance(MaintenanceStatement),
+    Merge(MergeStatement),
+    Prepare(PrepareStatement),
+    Execute(ExecuteStatement),
+    Deallocate(DeallocateStatement),
 }
 
 impl fmt::Display for SqlQuery {
@@ -64,12 +92,15 @@ impl fmt::Display for SqlQuery {
             SqlQuery::CreateView(ref create) => write!(f, "{}", create),
             SqlQuery::CreateCache(ref create) => write!(f, "{}", create),
             SqlQuery::DropCache(ref drop) => write!(f, "{}", drop),
+            SqlQuery::DropAllCaches(ref drop) => write!(f, "{}", drop),
             SqlQuery::Delete(ref delete) => write!(f, "{}", delete),
             SqlQuery::DropTable(ref drop) => write!(f, "{}", drop),
             SqlQuery::DropView(ref drop) => write!(f, "{}", drop),
             SqlQuery::Update(ref update) => write!(f, "{}", update),
             SqlQuery::Set(ref set) => write!(f, "{}", set),
             SqlQuery::AlterTable(ref alter) => write!(f, "{}", alter),
+            SqlQuery::AlterReadyset(ref alter) => write!(f, "{}", alter),
+            SqlQuery::AlterCache(ref alter) => write!(f, "{}", alter),
             SqlQuery::CompoundSelect(ref compound) => write!(f, "{}", compound),
             SqlQuery::StartTransaction(ref tx) => write!(f, "{}", tx),
             SqlQuery::Commit(ref commit) => write!(f, "{}", commit),
@@ -78,6 +109,16 @@ impl fmt::Display for SqlQuery {
             SqlQuery::Use(ref use_db) => write!(f, "{}", use_db),
             SqlQuery::Show(ref show) => write!(f, "{}", show),
             SqlQuery::Explain(ref explain) => write!(f, "{}", explain),
+            SqlQuery::DeclareCursor(ref declare) => write!(f, "{}", declare),
+            SqlQuery::Fetch(ref fetch) => write!(f, "{}", fetch),
+            SqlQuery::CloseCursor(ref close) => write!(f, "{}", close),
+            SqlQuery::FlushWait(ref flush) => write!(f, "{}", flush),
+            SqlQuery::Values(ref values) => write!(f, "{}", values),
+            SqlQuery::Maintenance(ref maintenance) => write!(f, "{}", maintenance),
+            SqlQuery::Merge(ref merge) => write!(f, "{}", merge),
+            SqlQuery::Prepare(ref prepare) => write!(f, "{}", prepare),
+            SqlQuery::Execute(ref execute) => write!(f, "{}", execute),
+            SqlQuery::Deallocate(ref deallocate) => write!(f, "{}", deallocate),
         }
     }
 }
@@ -100,12 +141,15 @@ impl SqlQuery {
             Self::CreateView(_) => "CREATE VIEW",
             Self::CreateCache(_) => "CREATE CACHE",
             Self::DropCache(_) => "DROP CACHE",
+            Self::DropAllCaches(_) => "DROP ALL CACHES",
             Self::Delete(_) => "DELETE",
             Self::DropTable(_) => "DROP TABLE",
             Self::DropView(_) => "DROP VIEW",
             Self::Update(_) => "UPDATE",
             Self::Set(_) => "SET",
             Self::AlterTable(_) => "ALTER TABLE",
+            Self::AlterReadyset(_) => "ALTER READYSET",
+            Self::AlterCache(_) => "ALTER CACHE",
             Self::CompoundSelect(_) => "SELECT",
             Self::StartTransaction(_) => "START TRANSACTION",
             Self::Commit(_) => "COMMIT",
@@ -114,12 +158,31 @@ impl SqlQuery {
             Self::Use(_) => "USE",
             Self::Show(_) => "SHOW",
             Self::Explain(_) => "EXPLAIN",
+            Self::DeclareCursor(_) => "DECLARE CURSOR",
+            Self::Fetch(_) => "FETCH",
+            Self::CloseCursor(_) => "CLOSE CURSOR",
+            Self::FlushWait(_) => "READYSET FLUSH WAIT",
+            Self::Values(_) => "VALUES",
+            Self::Maintenance(stmt) => match stmt.operation {
+                MaintenanceOperation::Analyze => "ANALYZE",
+                MaintenanceOperation::Vacuum => "VACUUM",
+                MaintenanceOperation::Optimize => "OPTIMIZE TABLE",
+                MaintenanceOperation::Check => "CHECK TABLE",
+                MaintenanceOperation::Repair => "REPAIR TABLE",
+                MaintenanceOperation::Checksum => "CHECKSUM TABLE",
+            },
+            Self::Merge(_) => "MERGE",
+            Self::Prepare(_) => "PREPARE",
+            Self::Execute(_) => "EXECUTE",
+            Self::Deallocate(_) => "DEALLOCATE",
         }
     }
 }
 
 pub fn sql_query(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], SqlQuery> {
     move |i| {
+        // Skip any comments before the statement, such as hints
+        let (i, _) = whitespace0(i)?;
         alt((
             map(creation(dialect), SqlQuery::CreateTable),
             map(insertion(dialect), SqlQuery::Insert),
@@ -134,13 +197,28 @@ pub fn sql_query(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], SqlQuery>
             map(create_cached_query(dialect), SqlQuery::CreateCache),
             map(drop_cached_query(dialect), SqlQuery::DropCache),
             map(alter_table_statement(dialect), SqlQuery::AlterTable),
+            map(alter_readyset_statement(dialect), SqlQuery::AlterReadyset),
             map(start_transaction(dialect), SqlQuery::StartTransaction),
             map(commit(dialect), SqlQuery::Commit),
             map(rollback(dialect), SqlQuery::Rollback),
             map(rename_table(dialect), SqlQuery::RenameTable),
             map(use_statement(dialect), SqlQuery::Use),
             map(show(dialect), SqlQuery::Show),
-            map(explain_statement, SqlQuery::Explain),
+            alt((
+                map(explain_statement(dialect), SqlQuery::Explain),
+                map(declare_cursor(dialect), SqlQuery::DeclareCursor),
+                map(fetch(dialect), SqlQuery::Fetch),
+                map(close_cursor(dialect), SqlQuery::CloseCursor),
+                map(drop_all_caches(dialect), SqlQuery::DropAllCaches),
+                map(alter_cache_statement(dialect), SqlQuery::AlterCache),
+                map(flush_wait(dialect), SqlQuery::FlushWait),
+                map(values(dialect), SqlQuery::Values),
+                map(maintenance(dialect), SqlQuery::Maintenance),
+                map(merge(dialect), SqlQuery::Merge),
+                map(prepare(dialect), SqlQuery::Prepare),
+                map(execute(dialect), SqlQuery::Execute),
+                map(deallocate(dialect), SqlQuery::Deallocate),
+            )),
         ))(i)
     }
 }
@@ -165,6 +243,67 @@ where
     parse_query_bytes(dialect, input.as_ref().trim().as_bytes())
 }
 
+/// The byte offset in `input` at which `res`, the result of parsing `input`, failed
+fn error_offset<O>(input: &[u8], res: IResult<&[u8], O>) -> usize {
+    match res {
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => input.len() - e.input.len(),
+        _ => 0,
+    }
+}
+
+/// Returns the byte offset in `input` (after trimming whitespace from either end of it) at which
+/// it stops being a valid SQL query, or `None` if it parses successfully.
+///
+/// Since [`sql_query`] only reports the error of the last kind of statement it tried, this tries
+/// each kind of statement separately and returns the furthest offset any of them got to.
+pub fn parse_error_offset<T>(dialect: Dialect, input: T) -> Option<usize>
+where
+    T: AsRef<str>,
+{
+    let input = input.as_ref().trim().as_bytes();
+    if sql_query(dialect)(input).is_ok() {
+        return None;
+    }
+    let res: IResult<&[u8], _> = whitespace0(input);
+    let i = res.map_or(input, |(i, _)| i);
+
+    // This must try all the same statements as `sql_query`
+    [
+        error_offset(input, creation(dialect)(i)),
+        error_offset(input, insertion(dialect)(i)),
+        error_offset(input, compound_selection(dialect)(i)),
+        error_offset(input, selection(dialect)(i)),
+        error_offset(input, deletion(dialect)(i)),
+        error_offset(input, drop_table(dialect)(i)),
+        error_offset(input, drop_view(dialect)(i)),
+        error_offset(input, updating(dialect)(i)),
+        error_offset(input, set(dialect)(i)),
+        error_offset(input, view_creation(dialect)(i)),
+        error_offset(input, create_cached_query(dialect)(i)),
+        error_offset(input, drop_cached_query(dialect)(i)),
+        error_offset(input, alter_table_statement(dialect)(i)),
+        error_offset(input, alter_readyset_statement(dialect)(i)),
+        error_offset(input, start_transaction(dialect)(i)),
+        error_offset(input, commit(dialect)(i)),
+        error_offset(input, rollback(dialect)(i)),
+        error_offset(input, rename_table(dialect)(i)),
+        error_offset(input, use_statement(dialect)(i)),
+        error_offset(input, show(dialect)(i)),
+        error_offset(input, explain_statement(dialect)(i)),
+        error_offset(input, declare_cursor(dialect)(i)),
+        error_offset(input, fetch(dialect)(i)),
+        error_offset(input, close_cursor(dialect)(i)),
+        error_offset(input, drop_all_caches(dialect)(i)),
+        error_offset(input, maintenance(dialect)(i)),
+        error_offset(input, merge(dialect)(i)),
+        error_offset(input, prepare(dialect)(i)),
+        error_offset(input, execute(dialect)(i)),
+        error_offset(input, deallocate(dialect)(i)),
+    ]
+    .into_iter()
+    .max()
+}
+
 /// Parse a select statement from a byte slice
 pub fn parse_select_statement_bytes<T>(
     dialect: Dialect,
@@ -321,6 +460,32 @@ mod tests {
         assert_eq!(expected3, format!("{}", res3.unwrap()));
     }
 
+    #[test]
+    fn hinted_queries() {
+        let expected = "SELECT * FROM `users`";
+        for qstring in [
+            "/*+ readyset=cached */ SELECT * FROM users",
+            "SELECT /*+ readyset=proxy */ * FROM users",
+        ] {
+            let res = parse_query(Dialect::MySQL, qstring).unwrap();
+            assert_eq!(expected, res.to_string());
+        }
+    }
+
+    #[test]
+    fn parse_error_offsets() {
+        assert_eq!(
+            parse_error_offset(Dialect::MySQL, "SELECT a FROM t WHERE x = 1 2"),
+            Some(28)
+        );
+        assert_eq!(
+            parse_error_offset(Dialect::MySQL, "  INSERT INTO t VALUES (1, 2"),
+            Some(26)
+        );
+        assert_eq!(parse_error_offset(Dialect::MySQL, "SELCT 1"), Some(0));
+        assert_eq!(parse_error_offset(Dialect::MySQL, "SELECT 1"), None);
+    }
+
     #[test]
     fn format_select_query_with_where_clause() {
         let qstring0 = "select name, password from users as u where user='aaa' and password= 'xxx'";
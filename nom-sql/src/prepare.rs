@@ -0,0 +1,227 @@
+use std::fmt;
+
+use itertools::Itertools;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::combinator::{map, opt, recognize};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::IResult;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{statement_terminator, type_identifier, ws_sep_comma};
+use crate::expression::expression;
+use crate::parser::sql_query;
+use crate::whitespace::{whitespace0, whitespace1};
+use crate::{Dialect, Expression, SqlIdentifier, SqlType};
+
+/// `PREPARE name [(type, ...)] AS statement`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct PrepareStatement {
+    pub name: SqlIdentifier,
+    /// The types of the statement's parameters, if given
+    pub types: Vec<SqlType>,
+    /// The text of the statement to prepare, as it was written in the query
+    pub statement: String,
+}
+
+impl fmt::Display for PrepareStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PREPARE {} ", self.name)?;
+        if !self.types.is_empty() {
+            write!(f, "({}) ", self.types.iter().join(", "))?;
+        }
+        write!(f, "AS {}", self.statement)
+    }
+}
+
+/// `EXECUTE name [(argument, ...)]`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ExecuteStatement {
+    pub name: SqlIdentifier,
+    pub arguments: Vec<Expression>,
+}
+
+impl fmt::Display for ExecuteStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EXECUTE {}", self.name)?;
+        if !self.arguments.is_empty() {
+            write!(f, " ({})", self.arguments.iter().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// `DEALLOCATE [PREPARE] name` or `DEALLOCATE [PREPARE] ALL`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum DeallocateStatement {
+    Statement(SqlIdentifier),
+    All,
+}
+
+impl fmt::Display for DeallocateStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeallocateStatement::Statement(name) => write!(f, "DEALLOCATE {}", name),
+            DeallocateStatement::All => write!(f, "DEALLOCATE ALL"),
+        }
+    }
+}
+
+pub fn prepare(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], PrepareStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("prepare")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, name) = dialect.identifier()(i)?;
+        let (i, types) = opt(preceded(
+            whitespace0,
+            delimited(
+                terminated(tag("("), whitespace0),
+                separated_list1(ws_sep_comma, type_identifier(dialect)),
+                preceded(whitespace0, tag(")")),
+            ),
+        ))(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("as")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, statement) = recognize(sql_query(dialect))(i)?;
+        let (i, _) = statement_terminator(i)?;
+
+        Ok((
+            i,
+            PrepareStatement {
+                name,
+                types: types.unwrap_or_default(),
+                statement: String::from_utf8_lossy(statement)
+                    .trim_end()
+                    .trim_end_matches(';')
+                    .to_owned(),
+            },
+        ))
+    }
+}
+
+pub fn execute(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], ExecuteStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("execute")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, name) = dialect.identifier()(i)?;
+        let (i, arguments) = opt(preceded(
+            whitespace0,
+            delimited(
+                terminated(tag("("), whitespace0),
+                separated_list1(ws_sep_comma, expression(dialect)),
+                preceded(whitespace0, tag(")")),
+            ),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+
+        Ok((
+            i,
+            ExecuteStatement {
+                name,
+                arguments: arguments.unwrap_or_default(),
+            },
+        ))
+    }
+}
+
+pub fn deallocate(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], DeallocateStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("deallocate")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = opt(tuple((tag_no_case("prepare"), whitespace1)))(i)?;
+        let (i, stmt) = alt((
+            map(terminated(tag_no_case("all"), statement_terminator), |_| {
+                DeallocateStatement::All
+            }),
+            map(
+                terminated(dialect.identifier(), statement_terminator),
+                DeallocateStatement::Statement,
+            ),
+        ))(i)?;
+
+        Ok((i, stmt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Literal;
+
+    #[test]
+    fn prepare_with_types() {
+        let res = test_parse!(
+            prepare(Dialect::PostgreSQL),
+            b"PREPARE q1 (int, text) AS SELECT * FROM t WHERE id = $1 AND name = $2;"
+        );
+        assert_eq!(
+            res,
+            PrepareStatement {
+                name: "q1".into(),
+                types: vec![SqlType::Int(None), SqlType::Text],
+                statement: "SELECT * FROM t WHERE id = $1 AND name = $2".to_owned(),
+            }
+        );
+        assert_eq!(
+            res.to_string(),
+            "PREPARE q1 (INT, TEXT) AS SELECT * FROM t WHERE id = $1 AND name = $2"
+        );
+    }
+
+    #[test]
+    fn prepare_without_types() {
+        let res = test_parse!(
+            prepare(Dialect::PostgreSQL),
+            b"prepare ins as insert into t (id) values ($1)"
+        );
+        assert_eq!(res.name, "ins");
+        assert!(res.types.is_empty());
+        assert_eq!(res.statement, "insert into t (id) values ($1)");
+    }
+
+    #[test]
+    fn prepare_invalid_statement() {
+        assert!(prepare(Dialect::PostgreSQL)(b"PREPARE q1 AS SELEC 1").is_err());
+    }
+
+    #[test]
+    fn execute_with_arguments() {
+        let res = test_parse!(execute(Dialect::PostgreSQL), b"EXECUTE q1 (1, 'a')");
+        assert_eq!(
+            res,
+            ExecuteStatement {
+                name: "q1".into(),
+                arguments: vec![
+                    Expression::Literal(Literal::Integer(1)),
+                    Expression::Literal(Literal::String("a".to_owned())),
+                ],
+            }
+        );
+        assert_eq!(res.to_string(), "EXECUTE q1 (1, 'a')");
+    }
+
+    #[test]
+    fn execute_without_arguments() {
+        let res = test_parse!(execute(Dialect::PostgreSQL), b"execute q1;");
+        assert_eq!(
+            res,
+            ExecuteStatement {
+                name: "q1".into(),
+                arguments: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn deallocate_statement() {
+        let res = test_parse!(deallocate(Dialect::PostgreSQL), b"DEALLOCATE q1");
+        assert_eq!(res, DeallocateStatement::Statement("q1".into()));
+        let res = test_parse!(deallocate(Dialect::PostgreSQL), b"deallocate prepare q1;");
+        assert_eq!(res, DeallocateStatement::Statement("q1".into()));
+        let res = test_parse!(deallocate(Dialect::PostgreSQL), b"DEALLOCATE ALL");
+        assert_eq!(res, DeallocateStatement::All);
+        assert_eq!(res.to_string(), "DEALLOCATE ALL");
+    }
+}
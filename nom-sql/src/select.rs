@@ -6,12 +6,12 @@ use nom::bytes::complete::{tag, tag_no_case};
 use nom::combinator::{map, opt};
 use nom::error::ErrorKind;
 use nom::multi::{many0, separated_list1};
-use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
 use crate::common::{
-    as_alias, field_definition_expr, field_list, field_reference_list, literal,
+    alias_column_list, as_alias, field_definition_expr, field_list, field_reference_list, literal,
     schema_table_reference, table_list, terminated_with_statement_terminator, ws_sep_comma,
     FieldDefinitionExpression,
 };
@@ -19,6 +19,7 @@ use crate::expression::expression;
 use crate::join::{join_operator, JoinConstraint, JoinOperator, JoinRightSide};
 use crate::order::{order_clause, OrderClause};
 use crate::table::Table;
+use crate::values::{values_table, ValuesTable};
 use crate::whitespace::{whitespace0, whitespace1};
 use crate::{Dialect, Expression, FieldReference, FunctionExpression, Literal, SqlIdentifier};
 
@@ -52,9 +53,17 @@ pub struct JoinClause {
 
 impl fmt::Display for JoinClause {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.constraint == JoinConstraint::Natural {
+            write!(f, "NATURAL ")?;
+        }
         write!(f, "{}", self.operator)?;
         write!(f, " {}", self.right)?;
-        write!(f, " {}", self.constraint)?;
+        if !matches!(
+            self.constraint,
+            JoinConstraint::Natural | JoinConstraint::Empty
+        ) {
+            write!(f, " {}", self.constraint)?;
+        }
         Ok(())
     }
 }
@@ -121,6 +130,22 @@ impl SelectStatement {
             _ => false,
         })
     }
+
+    /// If this query selects from a `VALUES` table constructor rather than from a table, returns
+    /// that table constructor
+    pub fn values_from(&self) -> Option<&ValuesTable> {
+        if !self.tables.is_empty() {
+            return None;
+        }
+        match self.join.first() {
+            Some(JoinClause {
+                operator: JoinOperator::CrossJoin,
+                right: JoinRightSide::Values(values),
+                constraint: JoinConstraint::Empty,
+            }) => Some(values),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for SelectStatement {
@@ -143,7 +168,11 @@ impl fmt::Display for SelectStatement {
                 .join(", ")
         )?;
 
-        if !self.tables.is_empty() {
+        let mut joins = self.join.iter();
+        if let Some(values) = self.values_from() {
+            write!(f, " FROM {}", values)?;
+            joins.next();
+        } else if !self.tables.is_empty() {
             write!(f, " FROM ")?;
             write!(
                 f,
@@ -155,7 +184,7 @@ impl fmt::Display for SelectStatement {
                     .join(", ")
             )?;
         }
-        for jc in &self.join {
+        for jc in joins {
             write!(f, " {}", jc)?;
         }
         if let Some(ref where_clause) = self.where_clause {
@@ -257,7 +286,7 @@ fn join_constraint(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], JoinCon
 // Parse JOIN clause
 fn join_clause(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], JoinClause> {
     move |i| {
-        let (remaining_input, (_, _natural, operator, _, right, _, constraint)) = tuple((
+        let (remaining_input, (_, natural, operator, _, right, _, constraint)) = tuple((
             whitespace0,
             opt(terminated(tag_no_case("natural"), whitespace1)),
             join_operator,
@@ -267,6 +296,19 @@ fn join_clause(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], JoinClause>
             join_constraint(dialect),
         ))(i)?;
 
+        // A NATURAL join takes its join condition from the columns of the tables, so it can't
+        // have one of its own
+        let constraint = match (natural, constraint) {
+            (None, constraint) => constraint,
+            (Some(_), JoinConstraint::Empty) => JoinConstraint::Natural,
+            (Some(_), _) => {
+                return Err(nom::Err::Error(nom::error::Error::new(
+                    i,
+                    ErrorKind::Verify,
+                )))
+            }
+        };
+
         Ok((
             remaining_input,
             JoinClause {
@@ -278,7 +320,7 @@ fn join_clause(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], JoinClause>
     }
 }
 
-fn join_rhs(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], JoinRightSide> {
+pub(crate) fn join_rhs(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], JoinRightSide> {
     move |i| {
         let nested_select = map(
             tuple((
@@ -288,15 +330,19 @@ fn join_rhs(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], JoinRightSide>
                     preceded(whitespace0, tag(")")),
                 ),
                 as_alias(dialect),
+                opt(alias_column_list(dialect)),
             )),
-            |(statement, alias)| JoinRightSide::NestedSelect(Box::new(statement), alias),
+            |(statement, alias, columns)| {
+                JoinRightSide::NestedSelect(Box::new(statement), alias, columns.unwrap_or_default())
+            },
         );
+        let values = map(values_table(dialect), JoinRightSide::Values);
         let table = map(schema_table_reference(dialect), JoinRightSide::Table);
         let tables = map(
             delimited(tag("("), table_list(dialect), tag(")")),
             JoinRightSide::Tables,
         );
-        alt((nested_select, table, tables))(i)
+        alt((nested_select, values, table, tables))(i)
     }
 }
 
@@ -326,7 +372,12 @@ pub fn selection(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], SelectSta
 #[derive(Debug)]
 enum FromClause {
     Tables(Vec<Table>),
-    NestedSelect(Box<SelectStatement>, Option<SqlIdentifier>),
+    NestedSelect(
+        Box<SelectStatement>,
+        Option<SqlIdentifier>,
+        Vec<SqlIdentifier>,
+    ),
+    Values(ValuesTable),
     Join {
         lhs: Box<FromClause>,
         join_clause: JoinClause,
@@ -341,9 +392,18 @@ impl FromClause {
         // allowed
         let lhs_nested_select = Err("SELECT from subqueries is not currently supported".to_owned());
 
+        // A VALUES table constructor on the lhs is represented as a cross join onto a FROM clause
+        // with no tables, which (having a single row) leaves the rows of the VALUES unchanged
+        let lhs_values = |values| JoinClause {
+            operator: JoinOperator::CrossJoin,
+            right: JoinRightSide::Values(values),
+            constraint: JoinConstraint::Empty,
+        };
+
         match self {
             Tables(tables) => Ok((tables, vec![])),
-            NestedSelect(_, _) => lhs_nested_select,
+            NestedSelect(..) => lhs_nested_select,
+            Values(values) => Ok((vec![], vec![lhs_values(values)])),
             Join {
                 mut lhs,
                 join_clause,
@@ -352,7 +412,11 @@ impl FromClause {
                 let tables = loop {
                     match *lhs {
                         Tables(tables) => break tables,
-                        NestedSelect(_, _) => return lhs_nested_select,
+                        NestedSelect(..) => return lhs_nested_select,
+                        Values(values) => {
+                            joins.push(lhs_values(values));
+                            break vec![];
+                        }
                         Join {
                             lhs: new_lhs,
                             join_clause,
@@ -377,8 +441,15 @@ fn nested_select(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], FromClaus
         let (i, _) = whitespace0(i)?;
         let (i, _) = tag(")")(i)?;
 
-        let (i, alias) = opt(as_alias(dialect))(i)?;
-        Ok((i, FromClause::NestedSelect(Box::new(selection), alias)))
+        let (i, alias) = opt(pair(as_alias(dialect), opt(alias_column_list(dialect))))(i)?;
+        let (alias, columns) = match alias {
+            Some((alias, columns)) => (Some(alias), columns.unwrap_or_default()),
+            None => (None, vec![]),
+        };
+        Ok((
+            i,
+            FromClause::NestedSelect(Box::new(selection), alias, columns),
+        ))
     }
 }
 
@@ -403,6 +474,7 @@ fn nested_from_clause(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], From
         alt((
             delimited(tag("("), from_clause_join(dialect), tag(")")),
             map(table_list(dialect), FromClause::Tables),
+            map(values_table(dialect), FromClause::Values),
             nested_select(dialect),
         ))(i)
     }
@@ -414,11 +486,31 @@ fn from_clause_tree(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], FromCl
             delimited(tag("("), from_clause_join(dialect), tag(")")),
             from_clause_join(dialect),
             map(table_list(dialect), FromClause::Tables),
+            map(values_table(dialect), FromClause::Values),
             nested_select(dialect),
         ))(i)
     }
 }
 
+/// Parse a list of tables and the joins between them, written the same way as in the `FROM` clause
+/// of a `SELECT`, for statements that read from other tables such as multi-table `UPDATE`s. The
+/// list must start with a table.
+#[allow(clippy::type_complexity)]
+pub(crate) fn table_references(
+    dialect: Dialect,
+) -> impl Fn(&[u8]) -> IResult<&[u8], (Vec<Table>, Vec<JoinClause>)> {
+    move |i| {
+        let (rest, (from, extra_joins)) =
+            tuple((from_clause_tree(dialect), many0(join_clause(dialect))))(i)?;
+        let (tables, mut join) = match from.into_tables_and_joins() {
+            Ok((tables, join)) if !tables.is_empty() => (tables, join),
+            _ => return Err(nom::Err::Error(nom::error::Error::new(i, ErrorKind::Tag))),
+        };
+        join.extend(extra_joins);
+        Ok((rest, (tables, join)))
+    }
+}
+
 fn from_clause(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], FromClause> {
     move |i| {
         let (i, _) = whitespace0(i)?;
@@ -521,7 +613,7 @@ mod tests {
     use super::*;
     use crate::column::Column;
     use crate::common::{FieldDefinitionExpression, ItemPlaceholder, Literal, SqlType};
-    use crate::table::Table;
+    use crate::table::{Table, TableSample};
     use crate::{BinaryOperator, Expression, FunctionExpression, InValue};
 
     fn columns(cols: &[&str]) -> Vec<FieldDefinitionExpression> {
@@ -728,6 +820,7 @@ mod tests {
                     name: "PaperTag".into(),
                     alias: Some("t".into()),
                     schema: None,
+                    sample: None,
                 },],
                 fields: vec![FieldDefinitionExpression::All],
                 ..Default::default()
@@ -749,6 +842,7 @@ mod tests {
                     name: "PaperTag".into(),
                     alias: Some("t".into()),
                     schema: Some("db1".into()),
+                    sample: None,
                 },],
                 fields: vec![FieldDefinitionExpression::All],
                 ..Default::default()
@@ -1275,6 +1369,55 @@ mod tests {
         assert_eq!(res.unwrap().1, expected);
     }
 
+    #[test]
+    fn using_multiple_columns() {
+        let res = test_parse!(
+            selection(Dialect::MySQL),
+            b"SELECT * FROM a JOIN b USING (x, y)"
+        );
+        assert_eq!(
+            res.join[0].constraint,
+            JoinConstraint::Using(vec![Column::from("x"), Column::from("y")])
+        );
+        assert_eq!(
+            res.to_string(),
+            "SELECT * FROM `a` JOIN `b` USING (`x`, `y`)"
+        );
+    }
+
+    #[test]
+    fn natural_joins() {
+        let res = test_parse!(
+            selection(Dialect::MySQL),
+            b"SELECT * FROM a NATURAL JOIN b NATURAL LEFT JOIN c"
+        );
+        assert_eq!(
+            res.join,
+            vec![
+                JoinClause {
+                    operator: JoinOperator::Join,
+                    right: JoinRightSide::Table(Table::from("b")),
+                    constraint: JoinConstraint::Natural,
+                },
+                JoinClause {
+                    operator: JoinOperator::LeftJoin,
+                    right: JoinRightSide::Table(Table::from("c")),
+                    constraint: JoinConstraint::Natural,
+                }
+            ]
+        );
+        assert_eq!(
+            res.to_string(),
+            "SELECT * FROM `a` NATURAL JOIN `b` NATURAL LEFT JOIN `c`"
+        );
+    }
+
+    #[test]
+    fn natural_join_with_constraint() {
+        assert!(selection(Dialect::MySQL)(b"SELECT * FROM a NATURAL JOIN b USING (x)").is_err());
+        assert!(selection(Dialect::MySQL)(b"SELECT * FROM a NATURAL JOIN b ON a.x = b.x").is_err());
+    }
+
     #[test]
     fn multi_join() {
         // simplified from
@@ -1449,7 +1592,7 @@ mod tests {
             fields: columns(&["o_id", "ol_i_id"]),
             join: vec![JoinClause {
                 operator: JoinOperator::Join,
-                right: JoinRightSide::NestedSelect(Box::new(inner_select), "ids".into()),
+                right: JoinRightSide::NestedSelect(Box::new(inner_select), "ids".into(), vec![]),
                 constraint: JoinConstraint::On(Expression::BinaryOp {
                     lhs: Box::new(Expression::Column(Column::from("orders.o_id"))),
                     op: BinaryOperator::Equal,
@@ -1462,6 +1605,64 @@ mod tests {
         assert_eq!(res.unwrap().1, outer_select);
     }
 
+    #[test]
+    fn join_against_nested_select_with_column_aliases() {
+        let qstr =
+            "SELECT t.id, x.c1 FROM t JOIN (SELECT a, b FROM u) AS x (c1, c2) ON t.id = x.c2";
+        let res = test_parse!(selection(Dialect::MySQL), qstr.as_bytes());
+        match &res.join[0].right {
+            JoinRightSide::NestedSelect(stmt, alias, column_names) => {
+                assert_eq!(stmt.fields, columns(&["a", "b"]));
+                assert_eq!(alias, "x");
+                assert_eq!(*column_names, vec![SqlIdentifier::from("c1"), "c2".into()]);
+            }
+            right => panic!("Expected a nested select, got {:?}", right),
+        }
+        assert_eq!(
+            res.to_string(),
+            "SELECT `t`.`id`, `x`.`c1` FROM `t` JOIN (SELECT `a`, `b` FROM `u`) AS x (`c1`, `c2`) \
+             ON (`t`.`id` = `x`.`c2`)"
+        );
+    }
+
+    #[test]
+    fn table_sample() {
+        let res = test_parse!(
+            selection(Dialect::PostgreSQL),
+            b"SELECT * FROM t AS x TABLESAMPLE BERNOULLI (10) REPEATABLE (42) JOIN u ON x.a = u.a"
+        );
+        assert_eq!(
+            res.tables[0].sample,
+            Some(TableSample {
+                method: "bernoulli".into(),
+                arguments: vec![Expression::Literal(10.into())],
+                repeatable: Some(Expression::Literal(42.into())),
+            })
+        );
+        assert_eq!(res.tables[0].alias, Some("x".into()));
+        assert_eq!(
+            res.to_string(),
+            "SELECT * FROM `t` AS `x` TABLESAMPLE bernoulli (10) REPEATABLE (42) \
+             JOIN `u` ON (`x`.`a` = `u`.`a`)"
+        );
+
+        let res = test_parse!(
+            selection(Dialect::MySQL),
+            b"SELECT a FROM t tablesample system(1)"
+        );
+        assert_eq!(
+            res.tables[0],
+            Table {
+                sample: Some(TableSample {
+                    method: "system".into(),
+                    arguments: vec![Expression::Literal(1.into())],
+                    repeatable: None,
+                }),
+                ..Table::from("t")
+            }
+        );
+    }
+
     #[test]
     fn project_arithmetic_expressions() {
         let qstr = "SELECT MAX(o_id)-3333 FROM orders;";
@@ -1886,5 +2087,30 @@ mod tests {
                 "SELECT EXISTS (SELECT * FROM `groups` WHERE (`id` = ?)) AS `exists`"
             );
         }
+
+        #[test]
+        fn select_from_values() {
+            let qstr = "SELECT t.id, u.name FROM (VALUES (1, 'a'), (2, 'b')) AS t (id, name) \
+                        JOIN users u ON u.id = t.id";
+            let res = test_parse!(selection(Dialect::PostgreSQL), qstr.as_bytes());
+            assert!(res.tables.is_empty());
+            assert_eq!(res.join.len(), 2);
+            let values = res.values_from().unwrap();
+            assert_eq!(values.alias, "t");
+            assert_eq!(values.values.rows.len(), 2);
+            assert_eq!(
+                res.to_string(),
+                "SELECT `t`.`id`, `u`.`name` FROM (VALUES (1, 'a'), (2, 'b')) AS `t` (`id`, `name`) \
+                 JOIN `users` AS `u` ON (`u`.`id` = `t`.`id`)"
+            );
+
+            let res = test_parse!(
+                selection(Dialect::PostgreSQL),
+                b"SELECT * FROM users JOIN (VALUES (1)) AS v (id) ON users.id = v.id"
+            );
+            assert_eq!(res.tables, vec![Table::from("users")]);
+            assert!(res.values_from().is_none());
+            assert!(matches!(res.join[0].right, JoinRightSide::Values(_)));
+        }
     }
 }
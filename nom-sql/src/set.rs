@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 use crate::common::{literal, statement_terminator};
 use crate::expression::expression;
 use crate::whitespace::{whitespace0, whitespace1};
-use crate::{Dialect, Expression, Literal, SqlIdentifier};
+use crate::{Dialect, Expression, Feature, Literal, SqlIdentifier};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum SetStatement {
@@ -329,7 +329,7 @@ pub fn set(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], SetStatement> {
         let (i, _) = whitespace1(i)?;
         let (i, statement) = alt((
             move |i| {
-                if dialect == Dialect::PostgreSQL {
+                if dialect.supports(Feature::ConfigurationParameters) {
                     set_postgres_parameter
                         .map(SetStatement::PostgresParameter)
                         .parse(i)
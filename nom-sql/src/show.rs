@@ -18,6 +18,11 @@ pub enum ShowStatement {
     CachedQueries,
     ProxiedQueries,
     ReadySetStatus,
+    ReadySetReplicationStatus,
+    ReadySetQueryStats,
+    ReadySetMemory,
+    ReadySetSuggestions,
+    ReadySetStatistics,
 }
 
 impl fmt::Display for ShowStatement {
@@ -29,6 +34,11 @@ impl fmt::Display for ShowStatement {
             Self::CachedQueries => write!(f, "CACHES"),
             Self::ProxiedQueries => write!(f, "PROXIED QUERIES"),
             Self::ReadySetStatus => write!(f, "READYSET STATUS"),
+            Self::ReadySetReplicationStatus => write!(f, "READYSET REPLICATION STATUS"),
+            Self::ReadySetQueryStats => write!(f, "READYSET QUERY STATS"),
+            Self::ReadySetMemory => write!(f, "READYSET MEMORY"),
+            Self::ReadySetSuggestions => write!(f, "READYSET SUGGESTIONS"),
+            Self::ReadySetStatistics => write!(f, "READYSET STATISTICS"),
         }
     }
 }
@@ -48,6 +58,46 @@ pub fn show(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], ShowStatement>
                 tuple((tag_no_case("readyset"), whitespace1, tag_no_case("status"))),
                 |_| ShowStatement::ReadySetStatus,
             ),
+            map(
+                tuple((
+                    tag_no_case("readyset"),
+                    whitespace1,
+                    tag_no_case("replication"),
+                    whitespace1,
+                    tag_no_case("status"),
+                )),
+                |_| ShowStatement::ReadySetReplicationStatus,
+            ),
+            map(
+                tuple((
+                    tag_no_case("readyset"),
+                    whitespace1,
+                    tag_no_case("query"),
+                    whitespace1,
+                    tag_no_case("stats"),
+                )),
+                |_| ShowStatement::ReadySetQueryStats,
+            ),
+            map(
+                tuple((tag_no_case("readyset"), whitespace1, tag_no_case("memory"))),
+                |_| ShowStatement::ReadySetMemory,
+            ),
+            map(
+                tuple((
+                    tag_no_case("readyset"),
+                    whitespace1,
+                    tag_no_case("suggestions"),
+                )),
+                |_| ShowStatement::ReadySetSuggestions,
+            ),
+            map(
+                tuple((
+                    tag_no_case("readyset"),
+                    whitespace1,
+                    tag_no_case("statistics"),
+                )),
+                |_| ShowStatement::ReadySetStatistics,
+            ),
             map(show_tables(dialect), ShowStatement::Tables),
             map(tag_no_case("events"), |_| ShowStatement::Events),
         ))(i)?;
@@ -239,4 +289,59 @@ mod tests {
         assert_eq!(res1, ShowStatement::ReadySetStatus);
         assert_eq!(res2, ShowStatement::ReadySetStatus);
     }
+
+    #[test]
+    fn show_readyset_replication_status() {
+        let qstring1 = "SHOW READYSET REPLICATION STATUS";
+        let res1 = show(Dialect::PostgreSQL)(qstring1.as_bytes()).unwrap().1;
+        let qstring2 = "show\treadyset\treplication\tstatus";
+        let res2 = show(Dialect::PostgreSQL)(qstring2.as_bytes()).unwrap().1;
+        assert_eq!(res1, ShowStatement::ReadySetReplicationStatus);
+        assert_eq!(res2, ShowStatement::ReadySetReplicationStatus);
+        assert_eq!(res1.to_string(), qstring1);
+    }
+
+    #[test]
+    fn show_readyset_query_stats() {
+        let qstring1 = "SHOW READYSET QUERY STATS";
+        let res1 = show(Dialect::MySQL)(qstring1.as_bytes()).unwrap().1;
+        let qstring2 = "show\treadyset\tquery\tstats";
+        let res2 = show(Dialect::MySQL)(qstring2.as_bytes()).unwrap().1;
+        assert_eq!(res1, ShowStatement::ReadySetQueryStats);
+        assert_eq!(res2, ShowStatement::ReadySetQueryStats);
+        assert_eq!(res1.to_string(), qstring1);
+    }
+
+    #[test]
+    fn show_readyset_memory() {
+        let qstring1 = "SHOW READYSET MEMORY";
+        let res1 = show(Dialect::MySQL)(qstring1.as_bytes()).unwrap().1;
+        let qstring2 = "show\treadyset\tmemory";
+        let res2 = show(Dialect::MySQL)(qstring2.as_bytes()).unwrap().1;
+        assert_eq!(res1, ShowStatement::ReadySetMemory);
+        assert_eq!(res2, ShowStatement::ReadySetMemory);
+        assert_eq!(res1.to_string(), qstring1);
+    }
+
+    #[test]
+    fn show_readyset_suggestions() {
+        let qstring1 = "SHOW READYSET SUGGESTIONS";
+        let res1 = show(Dialect::MySQL)(qstring1.as_bytes()).unwrap().1;
+        let qstring2 = "show\treadyset\tsuggestions";
+        let res2 = show(Dialect::MySQL)(qstring2.as_bytes()).unwrap().1;
+        assert_eq!(res1, ShowStatement::ReadySetSuggestions);
+        assert_eq!(res2, ShowStatement::ReadySetSuggestions);
+        assert_eq!(res1.to_string(), qstring1);
+    }
+
+    #[test]
+    fn show_readyset_statistics() {
+        let qstring1 = "SHOW READYSET STATISTICS";
+        let res1 = show(Dialect::MySQL)(qstring1.as_bytes()).unwrap().1;
+        let qstring2 = "show\treadyset\tstatistics";
+        let res2 = show(Dialect::MySQL)(qstring2.as_bytes()).unwrap().1;
+        assert_eq!(res1, ShowStatement::ReadySetStatistics);
+        assert_eq!(res2, ShowStatement::ReadySetStatistics);
+        assert_eq!(res1.to_string(), qstring1);
+    }
 }
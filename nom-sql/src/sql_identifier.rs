@@ -1,11 +1,40 @@
+use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{PoisonError, RwLock};
 
+use lazy_static::lazy_static;
 use proptest::arbitrary::Arbitrary;
 
 const TINYTEXT_WIDTH: usize = 14;
 
+/// The length, in bytes, of the longest string that will be interned by
+/// [`SqlIdentifier::interned`]. This is the maximum length of an identifier in both MySQL and
+/// PostgreSQL (for identifiers made up of ASCII characters), so longer strings are unlikely to be
+/// the names of actual tables and columns.
+const MAX_INTERNED_LEN: usize = 64;
+
+/// The maximum total length, in bytes, of the strings interned by [`SqlIdentifier::interned`].
+/// Interned strings are never freed, so this bounds the memory the interner can use.
+const MAX_INTERNED_BYTES: usize = 4 << 20;
+
+/// The strings interned by [`SqlIdentifier::interned`]
+#[derive(Default)]
+struct Interner {
+    strings: HashSet<Text>,
+    /// The total length of `strings`, in bytes
+    bytes: usize,
+}
+
+lazy_static! {
+    /// Entries are never removed, so two interned [`Text`]s with different pointers are always
+    /// different strings.
+    static ref INTERNER: RwLock<Interner> = Default::default();
+}
+
 /// A String especially optimized for inline storage of short strings, and fast cloning of longer
 /// strings
 #[derive(Clone, PartialEq, Eq)]
@@ -21,10 +50,10 @@ pub struct TinyText {
     t: [u8; TINYTEXT_WIDTH],
 }
 
-/// A thin pointer over an Arc<[u8]>
+/// A thin pointer over an Arc<[u8]>, with a header recording whether the string was interned
 #[repr(transparent)]
 #[derive(Clone)]
-pub struct Text(triomphe::ThinArc<(), u8>);
+pub struct Text(triomphe::ThinArc<bool, u8>);
 
 impl TinyText {
     /// Extracts a string slice containing the entire `TinyText`.
@@ -111,7 +140,42 @@ impl Text {
     #[inline]
     fn from_slice(v: &[u8]) -> Self {
         std::str::from_utf8(v).expect("Must always be UTF8");
-        Self(triomphe::ThinArc::from_header_and_slice((), v))
+        Self(triomphe::ThinArc::from_header_and_slice(false, v))
+    }
+
+    /// Returns true if this `Text` was created by [`Text::interned`]
+    #[inline]
+    fn is_interned(&self) -> bool {
+        self.0.header.header
+    }
+
+    /// Returns the interned `Text` for `s`, interning it first if it hasn't been yet. Strings
+    /// longer than [`MAX_INTERNED_LEN`], or that don't fit in the interner any more, aren't
+    /// interned.
+    fn interned(s: &str) -> Self {
+        if s.len() > MAX_INTERNED_LEN {
+            return s.into();
+        }
+        if let Some(t) = INTERNER
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .strings
+            .get(s)
+        {
+            return t.clone();
+        }
+
+        let mut interner = INTERNER.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(t) = interner.strings.get(s) {
+            return t.clone();
+        }
+        if interner.bytes + s.len() > MAX_INTERNED_BYTES {
+            return s.into();
+        }
+        let t = Self(triomphe::ThinArc::from_header_and_slice(true, s.as_bytes()));
+        interner.strings.insert(t.clone());
+        interner.bytes += s.len();
+        t
     }
 }
 
@@ -125,7 +189,10 @@ impl TryFrom<&[u8]> for Text {
 
 impl From<&str> for Text {
     fn from(t: &str) -> Self {
-        Self(triomphe::ThinArc::from_header_and_slice((), t.as_bytes()))
+        Self(triomphe::ThinArc::from_header_and_slice(
+            false,
+            t.as_bytes(),
+        ))
     }
 }
 
@@ -141,10 +208,28 @@ impl PartialEq for Text {
             return true;
         }
 
+        // Every string is only ever interned once, so two different interned `Text`s can't be
+        // equal
+        if self.is_interned() && other.is_interned() {
+            return false;
+        }
+
         self.as_str().eq(other.as_str())
     }
 }
 
+impl Hash for Text {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Borrow<str> for Text {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
 impl Ord for Text {
     fn cmp(&self, other: &Self) -> Ordering {
         self.as_str().cmp(other.as_str())
@@ -181,6 +266,34 @@ impl SqlIdentifier {
             SqlIdentifier::Text(t) => t.as_bytes(),
         }
     }
+
+    /// Create a new `SqlIdentifier` from `s`, reusing the allocation of any other identifier
+    /// created from the same string with this function.
+    ///
+    /// Identifiers short enough to be stored inline don't need an allocation at all, but longer
+    /// identifiers are interned in a global table, so that repeatedly parsing queries that refer to
+    /// the same tables and columns doesn't allocate the same strings over and over, and so that
+    /// comparing two interned identifiers only needs to compare their pointers. Identifiers longer
+    /// than any valid table or column name aren't interned, and neither is anything once the
+    /// interner holds a few megabytes of strings, so that clients can't grow it without bound.
+    pub fn interned(s: &str) -> Self {
+        match TinyText::try_from(s) {
+            Ok(tt) => SqlIdentifier::Tiny(tt),
+            Err(_) => SqlIdentifier::Text(Text::interned(s)),
+        }
+    }
+
+    /// Returns true if `self` and `other` are stored in the same allocation (or are both stored
+    /// inline). This is a cheap check that's true for identifiers created by
+    /// [`SqlIdentifier::interned`] from the same string, as long as that string was interned.
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SqlIdentifier::Tiny(t1), SqlIdentifier::Tiny(t2)) => t1 == t2,
+            (SqlIdentifier::Text(t1), SqlIdentifier::Text(t2)) => t1.0.as_ptr() == t2.0.as_ptr(),
+            _ => false,
+        }
+    }
 }
 
 impl Default for SqlIdentifier {
@@ -393,12 +506,45 @@ mod tests {
     use launchpad::{eq_laws, hash_laws, ord_laws};
     use test_strategy::proptest;
 
-    use super::SqlIdentifier;
+    use super::{SqlIdentifier, MAX_INTERNED_LEN};
 
     eq_laws!(SqlIdentifier);
     ord_laws!(SqlIdentifier);
     hash_laws!(SqlIdentifier);
 
+    #[test]
+    fn interned() {
+        let name = "a_rather_long_column_name";
+        let a = SqlIdentifier::interned(name);
+        let b = SqlIdentifier::interned(name);
+        assert!(a.ptr_eq(&b));
+        assert_eq!(a, b);
+        assert_eq!(a, SqlIdentifier::from(name));
+        assert_ne!(
+            a,
+            SqlIdentifier::interned("another_rather_long_column_name")
+        );
+        assert!(!a.ptr_eq(&SqlIdentifier::from(name)));
+    }
+
+    #[test]
+    fn long_strings_not_interned() {
+        let name = "a".repeat(MAX_INTERNED_LEN + 1);
+        let a = SqlIdentifier::interned(&name);
+        let b = SqlIdentifier::interned(&name);
+        assert_eq!(a, b);
+        assert!(!a.ptr_eq(&b));
+    }
+
+    #[proptest]
+    fn interned_eq(s1: String, s2: String) {
+        assert_eq!(
+            SqlIdentifier::interned(&s1) == SqlIdentifier::interned(&s2),
+            s1 == s2
+        );
+        assert_eq!(SqlIdentifier::interned(&s1), SqlIdentifier::from(s1));
+    }
+
     #[proptest]
     fn serde_roundtrip(val: SqlIdentifier) {
         let ser = bincode::serialize(&val).unwrap();
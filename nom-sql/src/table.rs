@@ -1,14 +1,17 @@
 use std::{fmt, str};
 
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use crate::SqlIdentifier;
+use crate::{Expression, SqlIdentifier};
 
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct Table {
     pub name: SqlIdentifier,
     pub alias: Option<SqlIdentifier>,
     pub schema: Option<SqlIdentifier>,
+    /// A `TABLESAMPLE` clause on a table in the `FROM` clause of a query
+    pub sample: Option<TableSample>,
 }
 
 impl fmt::Display for Table {
@@ -20,6 +23,9 @@ impl fmt::Display for Table {
         if let Some(ref alias) = self.alias {
             write!(f, " AS `{}`", alias)?;
         }
+        if let Some(ref sample) = self.sample {
+            write!(f, " {}", sample)?;
+        }
         Ok(())
     }
 }
@@ -30,6 +36,7 @@ impl From<SqlIdentifier> for Table {
             name,
             alias: None,
             schema: None,
+            sample: None,
         }
     }
 }
@@ -40,6 +47,7 @@ impl From<&SqlIdentifier> for Table {
             name: name.clone(),
             alias: None,
             schema: None,
+            sample: None,
         }
     }
 }
@@ -50,6 +58,7 @@ impl<'a> From<&'a str> for Table {
             name: t.into(),
             alias: None,
             schema: None,
+            sample: None,
         }
     }
 }
@@ -59,6 +68,34 @@ impl<'a> From<(&'a str, &'a str)> for Table {
             name: t.1.into(),
             alias: None,
             schema: Some(t.0.into()),
+            sample: None,
         }
     }
 }
+
+/// A `TABLESAMPLE` clause, such as `TABLESAMPLE BERNOULLI (10) REPEATABLE (42)`, which reads only a
+/// random sample of the rows of a table
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct TableSample {
+    /// The name of the sampling method, such as `SYSTEM` or `BERNOULLI`
+    pub method: SqlIdentifier,
+    /// The arguments to the sampling method
+    pub arguments: Vec<Expression>,
+    /// The seed given to the sampling method in a `REPEATABLE` clause, if any
+    pub repeatable: Option<Expression>,
+}
+
+impl fmt::Display for TableSample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "TABLESAMPLE {} ({})",
+            self.method,
+            self.arguments.iter().join(", ")
+        )?;
+        if let Some(ref seed) = self.repeatable {
+            write!(f, " REPEATABLE ({})", seed)?;
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,19 @@
 use std::{fmt, str};
 
+use itertools::Itertools;
+use nom::branch::alt;
 use nom::bytes::complete::tag_no_case;
-use nom::combinator::opt;
-use nom::sequence::tuple;
+use nom::combinator::{map, opt, peek};
+use nom::sequence::{preceded, terminated, tuple};
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
 use crate::column::Column;
-use crate::common::{assignment_expr_list, schema_table_reference_no_alias, statement_terminator};
-use crate::select::where_clause;
+use crate::common::{
+    assignment_expr_list, returning_clause, schema_table_reference_no_alias, statement_terminator,
+    FieldDefinitionExpression,
+};
+use crate::select::{table_references, where_clause, JoinClause};
 use crate::table::Table;
 use crate::whitespace::{whitespace0, whitespace1};
 use crate::{Dialect, Expression};
@@ -16,51 +21,123 @@ use crate::{Dialect, Expression};
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct UpdateStatement {
     pub table: Table,
+    /// The other tables in a MySQL multi-table `UPDATE`, such as `t2` in `UPDATE t1, t2 SET ...`
+    pub tables: Vec<Table>,
+    /// The joins in a MySQL multi-table `UPDATE`, such as `UPDATE t1 JOIN t2 ON ... SET ...`
+    pub join: Vec<JoinClause>,
     pub fields: Vec<(Column, Expression)>,
+    /// The tables in a PostgreSQL `FROM` clause, such as `t2` in `UPDATE t1 SET ... FROM t2`
+    pub from: Vec<Table>,
+    /// The joins in a PostgreSQL `FROM` clause
+    pub from_join: Vec<JoinClause>,
     pub where_clause: Option<Expression>,
+    /// The fields to return for each updated row, if the statement has a `RETURNING` clause
+    pub returning: Option<Vec<FieldDefinitionExpression>>,
+}
+
+impl UpdateStatement {
+    /// Returns true if this statement refers to any tables other than [`table`](Self::table)
+    pub fn is_multi_table(&self) -> bool {
+        !self.tables.is_empty()
+            || !self.join.is_empty()
+            || !self.from.is_empty()
+            || !self.from_join.is_empty()
+    }
 }
 
 impl fmt::Display for UpdateStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "UPDATE `{}` ", self.table.name)?;
+        write!(f, "UPDATE `{}`", self.table.name)?;
+        if let Some(ref alias) = self.table.alias {
+            write!(f, " AS `{}`", alias)?;
+        }
+        for table in &self.tables {
+            write!(f, ", {}", table)?;
+        }
+        for jc in &self.join {
+            write!(f, " {}", jc)?;
+        }
         assert!(!self.fields.is_empty());
         write!(
             f,
-            "SET {}",
+            " SET {}",
             self.fields
                 .iter()
                 .map(|&(ref col, ref literal)| format!("{} = {}", col, literal))
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
+        if !self.from.is_empty() {
+            write!(f, " FROM {}", self.from.iter().join(", "))?;
+            for jc in &self.from_join {
+                write!(f, " {}", jc)?;
+            }
+        }
         if let Some(ref where_clause) = self.where_clause {
             write!(f, " WHERE ")?;
             write!(f, "{}", where_clause)?;
         }
+        if let Some(ref returning) = self.returning {
+            write!(
+                f,
+                " RETURNING {}",
+                returning
+                    .iter()
+                    .map(|field| field.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
         Ok(())
     }
 }
 
 pub fn updating(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], UpdateStatement> {
     move |i| {
-        let (remaining_input, (_, _, table, _, _, _, fields, _, where_clause, _)) = tuple((
+        let (
+            remaining_input,
+            (_, _, (mut tables, join), _, _, _, fields, _, from, where_clause, returning, _),
+        ) = tuple((
             tag_no_case("update"),
             whitespace1,
-            schema_table_reference_no_alias(dialect),
-            whitespace1,
+            // A single table is parsed on its own first, since PostgreSQL doesn't reserve `SET`
+            // and it would otherwise be parsed as the table's alias
+            alt((
+                map(
+                    terminated(
+                        schema_table_reference_no_alias(dialect),
+                        peek(preceded(whitespace1, tag_no_case("set"))),
+                    ),
+                    |table| (vec![table], vec![]),
+                ),
+                table_references(dialect),
+            )),
+            whitespace0,
             tag_no_case("set"),
             whitespace1,
             assignment_expr_list(dialect),
             whitespace0,
+            opt(preceded(
+                terminated(tag_no_case("from"), whitespace1),
+                table_references(dialect),
+            )),
             opt(where_clause(dialect)),
+            opt(returning_clause(dialect)),
             statement_terminator,
         ))(i)?;
+        let (from, from_join) = from.unwrap_or_default();
+        let table = tables.remove(0);
         Ok((
             remaining_input,
             UpdateStatement {
                 table,
+                tables,
+                join,
                 fields,
+                from,
+                from_join,
                 where_clause,
+                returning,
             },
         ))
     }
@@ -115,6 +192,7 @@ mod tests {
                     ),
                 ],
                 where_clause: expected_where_cond,
+                ..Default::default()
             }
         );
     }
@@ -152,6 +230,7 @@ mod tests {
                     },
                 ),],
                 where_clause: expected_where_cond,
+                ..Default::default()
             }
         );
     }
@@ -250,6 +329,41 @@ mod tests {
                 }
             );
         }
+
+        #[test]
+        fn multi_table_update() {
+            let res = test_parse!(
+                updating(Dialect::MySQL),
+                b"UPDATE posts p JOIN users u ON p.author = u.id SET p.karma = u.karma \
+                  WHERE u.id = 1"
+            );
+            assert_eq!(
+                res.table,
+                Table {
+                    alias: Some("p".into()),
+                    ..Table::from("posts")
+                }
+            );
+            assert!(res.tables.is_empty());
+            assert_eq!(res.join.len(), 1);
+            assert!(res.is_multi_table());
+            assert_eq!(
+                res.to_string(),
+                "UPDATE `posts` AS `p` JOIN `users` AS `u` ON (`p`.`author` = `u`.`id`) \
+                 SET `p`.`karma` = `u`.`karma` WHERE (`u`.`id` = 1)"
+            );
+
+            let res = test_parse!(
+                updating(Dialect::MySQL),
+                b"UPDATE t1, t2 SET t1.a = t2.a WHERE t1.id = t2.id"
+            );
+            assert_eq!(res.table, Table::from("t1"));
+            assert_eq!(res.tables, vec![Table::from("t2")]);
+            assert_eq!(
+                res.to_string(),
+                "UPDATE `t1`, `t2` SET `t1`.`a` = `t2`.`a` WHERE (`t1`.`id` = `t2`.`id`)"
+            );
+        }
     }
 
     mod postgres {
@@ -315,5 +429,53 @@ mod tests {
                 }
             );
         }
+
+        #[test]
+        fn update_with_returning() {
+            let qstring = "UPDATE users SET karma = 1 WHERE id = 2 RETURNING karma AS k";
+
+            let res = updating(Dialect::PostgreSQL)(qstring.as_bytes());
+            let stmt = res.unwrap().1;
+            assert_eq!(
+                stmt.returning,
+                Some(vec![FieldDefinitionExpression::Expression {
+                    expr: Expression::Column(Column::from("karma")),
+                    alias: Some("k".into()),
+                }])
+            );
+            assert_eq!(
+                stmt.to_string(),
+                "UPDATE `users` SET `karma` = 1 WHERE (`id` = 2) RETURNING `karma` AS `k`"
+            );
+        }
+
+        #[test]
+        fn update_from() {
+            let res = test_parse!(
+                updating(Dialect::PostgreSQL),
+                b"UPDATE users SET karma = s.karma FROM scores s JOIN periods p ON s.period = p.id \
+                  WHERE users.id = s.user_id AND p.current"
+            );
+            assert_eq!(res.table, Table::from("users"));
+            assert_eq!(
+                res.from,
+                vec![Table {
+                    alias: Some("s".into()),
+                    ..Table::from("scores")
+                }]
+            );
+            assert_eq!(res.from_join.len(), 1);
+            assert!(res.is_multi_table());
+            assert_eq!(
+                res.to_string(),
+                "UPDATE `users` SET `karma` = `s`.`karma` FROM `scores` AS `s` \
+                 JOIN `periods` AS `p` ON (`s`.`period` = `p`.`id`) \
+                 WHERE ((`users`.`id` = `s`.`user_id`) AND `p`.`current`)"
+            );
+            assert!(
+                !test_parse!(updating(Dialect::PostgreSQL), b"UPDATE users SET karma = 1")
+                    .is_multi_table()
+            );
+        }
     }
 }
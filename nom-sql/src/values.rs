@@ -0,0 +1,229 @@
+use std::fmt;
+
+use itertools::Itertools;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::combinator::opt;
+use nom::multi::separated_list1;
+use nom::sequence::terminated;
+use nom::IResult;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    alias_column_list, as_alias, terminated_with_statement_terminator, ws_sep_comma,
+};
+use crate::expression::expression;
+use crate::whitespace::whitespace0;
+use crate::{Dialect, DialectKind, Expression, SqlIdentifier};
+
+/// A `VALUES` table constructor, such as `VALUES (1, 'a'), (2, 'b')`: a constant relation with a
+/// row for each list of expressions
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ValuesStatement {
+    /// The rows of the relation, which all have the same number of columns
+    pub rows: Vec<Vec<Expression>>,
+}
+
+impl ValuesStatement {
+    /// Returns the number of columns in the relation
+    pub fn arity(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    /// Returns the names the columns of the relation are given if they aren't named explicitly,
+    /// which are `column_0`, `column_1`, ... in MySQL and `column1`, `column2`, ... in PostgreSQL
+    pub fn column_names(&self, dialect: Dialect) -> Vec<SqlIdentifier> {
+        (0..self.arity())
+            .map(|i| match dialect.kind() {
+                DialectKind::MySQL => format!("column_{}", i).into(),
+                DialectKind::PostgreSQL => format!("column{}", i + 1).into(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for ValuesStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "VALUES {}",
+            self.rows
+                .iter()
+                .map(|row| format!("({})", row.iter().join(", ")))
+                .join(", ")
+        )
+    }
+}
+
+/// A `VALUES` table constructor used as a derived table, eg `(VALUES (1, 2)) AS t (a, b)`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ValuesTable {
+    pub values: ValuesStatement,
+    pub alias: SqlIdentifier,
+    /// The names given to the columns of the relation, if any
+    pub columns: Vec<SqlIdentifier>,
+}
+
+impl ValuesTable {
+    /// Returns the names of the columns of the relation, using the names given to them in the
+    /// query if there are any
+    pub fn column_names(&self, dialect: Dialect) -> Vec<SqlIdentifier> {
+        if self.columns.is_empty() {
+            self.values.column_names(dialect)
+        } else {
+            self.columns.clone()
+        }
+    }
+}
+
+impl fmt::Display for ValuesTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}) AS `{}`", self.values, self.alias)?;
+        if !self.columns.is_empty() {
+            write!(
+                f,
+                " ({})",
+                self.columns.iter().map(|c| format!("`{}`", c)).join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// A single row of a VALUES table constructor, optionally written as `ROW(...)` as MySQL requires
+fn values_row(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<Expression>> {
+    move |i| {
+        let (i, _) = opt(terminated(tag_no_case("row"), whitespace0))(i)?;
+        let (i, _) = tag("(")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, row) = separated_list1(ws_sep_comma, expression(dialect))(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = tag(")")(i)?;
+        Ok((i, row))
+    }
+}
+
+// Parse a VALUES table constructor, without a statement terminator
+pub fn values_statement(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], ValuesStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("values")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (rest, rows) = separated_list1(ws_sep_comma, values_row(dialect))(i)?;
+
+        let arity = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != arity) {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                i,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+
+        Ok((rest, ValuesStatement { rows }))
+    }
+}
+
+// Parse rule for a VALUES table constructor used as a statement
+pub fn values(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], ValuesStatement> {
+    move |i| terminated_with_statement_terminator(values_statement(dialect))(i)
+}
+
+// Parse a VALUES table constructor used as a derived table, with an alias and optionally names for
+// its columns
+pub(crate) fn values_table(dialect: Dialect) -> impl Fn(&[u8]) -> IResult<&[u8], ValuesTable> {
+    move |i| {
+        let (i, _) = tag("(")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, values) = values_statement(dialect)(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = tag(")")(i)?;
+        let (i, alias) = as_alias(dialect)(i)?;
+        let (rest, columns) = opt(alias_column_list(dialect))(i)?;
+
+        let columns = columns.unwrap_or_default();
+        if !columns.is_empty() && columns.len() != values.arity() {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                i,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+
+        Ok((
+            rest,
+            ValuesTable {
+                values,
+                alias,
+                columns,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Literal;
+
+    #[test]
+    fn standalone() {
+        let res = test_parse!(values(Dialect::PostgreSQL), b"VALUES (1, 'a'), (2, 'b')");
+        assert_eq!(
+            res,
+            ValuesStatement {
+                rows: vec![
+                    vec![
+                        Expression::Literal(Literal::Integer(1)),
+                        Expression::Literal("a".into())
+                    ],
+                    vec![
+                        Expression::Literal(Literal::Integer(2)),
+                        Expression::Literal("b".into())
+                    ],
+                ]
+            }
+        );
+        assert_eq!(res.to_string(), "VALUES (1, 'a'), (2, 'b')");
+        assert_eq!(
+            res.column_names(Dialect::PostgreSQL),
+            vec![SqlIdentifier::from("column1"), "column2".into()]
+        );
+        assert_eq!(
+            res.column_names(Dialect::MySQL),
+            vec![SqlIdentifier::from("column_0"), "column_1".into()]
+        );
+    }
+
+    #[test]
+    fn mysql_row_constructors() {
+        let res = test_parse!(values(Dialect::MySQL), b"values row(1, 2), ROW (3, 4)");
+        assert_eq!(res.rows.len(), 2);
+        assert_eq!(res.arity(), 2);
+    }
+
+    #[test]
+    fn mismatched_row_lengths() {
+        assert!(values(Dialect::MySQL)(b"VALUES (1, 2), (3)").is_err());
+    }
+
+    #[test]
+    fn derived_table() {
+        let res = test_parse!(
+            values_table(Dialect::PostgreSQL),
+            b"(VALUES (1, 'a'), (2, 'b')) AS t (id, name)"
+        );
+        assert_eq!(res.alias, "t");
+        assert_eq!(res.columns, vec![SqlIdentifier::from("id"), "name".into()]);
+        assert_eq!(res.values.rows.len(), 2);
+        assert_eq!(
+            res.to_string(),
+            "(VALUES (1, 'a'), (2, 'b')) AS `t` (`id`, `name`)"
+        );
+
+        let res = test_parse!(values_table(Dialect::MySQL), b"(VALUES ROW(1)) v");
+        assert!(res.columns.is_empty());
+        assert_eq!(
+            res.column_names(Dialect::MySQL),
+            vec![SqlIdentifier::from("column_0")]
+        );
+
+        assert!(values_table(Dialect::MySQL)(b"(VALUES (1, 2)) AS t (a)").is_err());
+    }
+}
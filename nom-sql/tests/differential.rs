@@ -0,0 +1,237 @@
+//! Differential tests checking nom-sql's parser against the parsers of real MySQL and PostgreSQL
+//! servers, to catch the places where the SQL we accept (or produce) drifts from the SQL the
+//! servers accept.
+//!
+//! These tests check that:
+//!
+//! * Statements generated from the AST (see the [`generate`] module) are displayed as SQL that both
+//!   nom-sql and the server accept
+//! * Every query in the test corpora that the server accepts is also accepted by nom-sql
+//!
+//! The servers are only asked to *prepare* each statement, and only syntax errors count as
+//! failures - the statements refer to tables that don't exist, so the servers reject almost all
+//! of them after parsing them.
+//!
+//! These tests need running servers, so they're ignored unless the `differential_tests` feature is
+//! enabled. They connect using the same environment variables as the rest of the test suite:
+//!
+//! * `MYSQL_HOST` and `MYSQL_TCP_PORT`, as `root` with password `noria`
+//! * `PGHOST`, `PGPORT`, `PGUSER`, and `PGPASSWORD`
+//!
+//! ```notrust
+//! $ cargo test -p nom-sql --features differential_tests --test differential
+//! ```
+
+mod generate;
+
+use std::cell::RefCell;
+use std::{env, fs};
+
+use mysql_async::prelude::Queryable;
+use nom_sql::{parse_query, Dialect};
+use proptest::prelude::*;
+use proptest::test_runner::TestRunner;
+use tokio::runtime::Runtime;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::NoTls;
+
+use crate::generate::{statement, Target};
+
+/// Files in the test corpora with one query per line, written in SQL that should be accepted by
+/// both MySQL and PostgreSQL
+const CORPORA: &[&str] = &[
+    "tests/exists-queries.txt",
+    "tests/finkelstein82.txt",
+    "tests/hyrise-test-queries.txt",
+    "tests/tpc-w-queries.txt",
+];
+
+/// Files in the test corpora with one query per line, written in MySQL's dialect of SQL.
+///
+/// PostgreSQL accepts any sequence of operator characters as an operator when parsing, so it
+/// accepts (and nom-sql's PostgreSQL dialect rejects) MySQL-specific syntax in these queries.
+const MYSQL_CORPORA: &[&str] = &["tests/hotcrp-queries.txt"];
+
+/// `ER_PARSE_ERROR` and `ER_SYNTAX_ERROR`
+const MYSQL_SYNTAX_ERRORS: &[u16] = &[1064, 1149];
+
+fn corpus(files: &[&str]) -> Vec<String> {
+    files
+        .iter()
+        .flat_map(|path| {
+            fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("--"))
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Convert the MySQL-style backtick-quoted identifiers in `query` (as written by the `Display`
+/// impls of the AST) to PostgreSQL-style double-quoted identifiers
+fn to_postgres(query: &str) -> String {
+    let mut in_string = false;
+    query
+        .chars()
+        .map(|c| match c {
+            '\'' => {
+                in_string = !in_string;
+                c
+            }
+            '`' if !in_string => '"',
+            _ => c,
+        })
+        .collect()
+}
+
+struct MySQL {
+    rt: Runtime,
+    conn: RefCell<mysql_async::Conn>,
+}
+
+impl MySQL {
+    fn connect() -> Self {
+        let rt = Runtime::new().unwrap();
+        let conn = rt
+            .block_on(mysql_async::Conn::new(
+                mysql_async::OptsBuilder::default()
+                    .user(Some("root"))
+                    .pass(Some("noria"))
+                    .ip_or_hostname(env::var("MYSQL_HOST").unwrap_or_else(|_| "127.0.0.1".into()))
+                    .tcp_port(
+                        env::var("MYSQL_TCP_PORT")
+                            .unwrap_or_else(|_| "3306".into())
+                            .parse()
+                            .unwrap(),
+                    ),
+            ))
+            .unwrap();
+        Self {
+            rt,
+            conn: RefCell::new(conn),
+        }
+    }
+
+    /// Returns the error MySQL gives when parsing `query`, if it's a syntax error
+    fn syntax_error(&self, query: &str) -> Option<mysql_async::ServerError> {
+        match self.rt.block_on(self.conn.borrow_mut().prep(query)) {
+            Err(mysql_async::Error::Server(e)) if MYSQL_SYNTAX_ERRORS.contains(&e.code) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+struct PostgreSQL {
+    rt: Runtime,
+    client: tokio_postgres::Client,
+}
+
+impl PostgreSQL {
+    fn connect() -> Self {
+        let rt = Runtime::new().unwrap();
+        let (client, connection) = rt
+            .block_on(
+                tokio_postgres::Config::new()
+                    .user(&env::var("PGUSER").unwrap_or_else(|_| "postgres".into()))
+                    .password(env::var("PGPASSWORD").unwrap_or_else(|_| "noria".into()))
+                    .host(&env::var("PGHOST").unwrap_or_else(|_| "localhost".into()))
+                    .port(
+                        env::var("PGPORT")
+                            .unwrap_or_else(|_| "5432".into())
+                            .parse()
+                            .unwrap(),
+                    )
+                    .dbname("postgres")
+                    .connect(NoTls),
+            )
+            .unwrap();
+        rt.spawn(connection);
+        Self { rt, client }
+    }
+
+    /// Returns the error PostgreSQL gives when parsing `query`, if it's a syntax error
+    fn syntax_error(&self, query: &str) -> Option<tokio_postgres::Error> {
+        match self.rt.block_on(self.client.prepare(query)) {
+            Err(e) if e.code() == Some(&SqlState::SYNTAX_ERROR) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(not(feature = "differential_tests"), ignore)]
+fn mysql_accepts_displayed_statements() {
+    let mysql = MySQL::connect();
+    TestRunner::default()
+        .run(&statement(Target::MySQL), |statement| {
+            let displayed = statement.to_string();
+            prop_assert_eq!(parse_query(Dialect::MySQL, &displayed), Ok(statement));
+            if let Some(e) = mysql.syntax_error(&displayed) {
+                return Err(TestCaseError::fail(format!(
+                    "MySQL rejected {}: {}",
+                    displayed, e
+                )));
+            }
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+#[cfg_attr(not(feature = "differential_tests"), ignore)]
+fn postgres_accepts_displayed_statements() {
+    let postgres = PostgreSQL::connect();
+    TestRunner::default()
+        .run(&statement(Target::PostgreSQL), |statement| {
+            let displayed = to_postgres(&statement.to_string());
+            prop_assert_eq!(parse_query(Dialect::PostgreSQL, &displayed), Ok(statement));
+            if let Some(e) = postgres.syntax_error(&displayed) {
+                return Err(TestCaseError::fail(format!(
+                    "PostgreSQL rejected {}: {}",
+                    displayed, e
+                )));
+            }
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+#[cfg_attr(not(feature = "differential_tests"), ignore)]
+fn nom_sql_accepts_what_mysql_accepts() {
+    let mysql = MySQL::connect();
+    let rejected = corpus(CORPORA)
+        .into_iter()
+        .chain(corpus(MYSQL_CORPORA))
+        .filter(|query| {
+            mysql.syntax_error(query).is_none() && parse_query(Dialect::MySQL, query).is_err()
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        rejected.is_empty(),
+        "nom-sql failed to parse queries accepted by MySQL:\n{}",
+        rejected.join("\n")
+    );
+}
+
+#[test]
+#[cfg_attr(not(feature = "differential_tests"), ignore)]
+fn nom_sql_accepts_what_postgres_accepts() {
+    let postgres = PostgreSQL::connect();
+    let rejected = corpus(CORPORA)
+        .into_iter()
+        .map(|query| to_postgres(&query))
+        .filter(|query| {
+            postgres.syntax_error(query).is_none()
+                && parse_query(Dialect::PostgreSQL, query).is_err()
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        rejected.is_empty(),
+        "nom-sql failed to parse queries accepted by PostgreSQL:\n{}",
+        rejected.join("\n")
+    );
+}
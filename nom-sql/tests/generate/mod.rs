@@ -0,0 +1,301 @@
+//! [`proptest`] strategies for generating SQL statements, shared between the tests that check
+//! nom-sql against its own display impls and the tests that check it against real database
+//! servers.
+//!
+//! Statements are built from the [`Arbitrary`] impls of the AST types that have them, restricted to
+//! the subset of the AST that can currently be displayed as SQL that parses back to the same AST -
+//! notably, floating-point and numeric literals are left out, since displaying them can lose
+//! precision, negative integer literals are left out, since they're parsed as the negation of a
+//! positive integer, and `ILIKE` is left out, since it's displayed as MySQL's (case-insensitive)
+//! `LIKE`.
+//!
+//! [`Arbitrary`]: proptest::arbitrary::Arbitrary
+
+// Not every test uses every strategy
+#![allow(dead_code)]
+
+use nom_sql::{
+    BinaryOperator, Column, DeleteStatement, Expression, FieldDefinitionExpression, FieldReference,
+    GroupByClause, InValue, InsertStatement, ItemPlaceholder, JoinClause, JoinConstraint,
+    JoinOperator, JoinRightSide, LimitClause, Literal, OrderClause, OrderType, SelectStatement,
+    SqlIdentifier, SqlQuery, Table, UnaryOperator, UpdateStatement,
+};
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+
+/// What needs to accept the generated statements, on top of nom-sql itself
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Only nom-sql
+    NomSql,
+    /// A MySQL server, which only supports `?` placeholders and MySQL-specific syntax like
+    /// `INSERT IGNORE`
+    MySQL,
+    /// A PostgreSQL server, which only supports `$n` placeholders and none of MySQL's extensions
+    PostgreSQL,
+}
+
+impl Target {
+    /// Returns true if statements need to be accepted by a real database server
+    fn is_server(self) -> bool {
+        self != Target::NomSql
+    }
+}
+
+pub fn identifier() -> impl Strategy<Value = SqlIdentifier> {
+    "[a-z][a-z0-9_]{0,8}".prop_map(SqlIdentifier::from)
+}
+
+pub fn table() -> impl Strategy<Value = Table> {
+    (identifier(), option::of(identifier())).prop_map(|(name, alias)| Table {
+        name,
+        alias,
+        schema: None,
+        sample: None,
+    })
+}
+
+pub fn unaliased_table() -> impl Strategy<Value = Table> {
+    identifier().prop_map(Table::from)
+}
+
+pub fn column() -> impl Strategy<Value = Column> {
+    (identifier(), option::of(identifier())).prop_map(|(name, table)| Column { name, table })
+}
+
+pub fn unqualified_column() -> impl Strategy<Value = Column> {
+    identifier().prop_map(|name| Column { name, table: None })
+}
+
+pub fn literal(target: Target) -> impl Strategy<Value = Literal> {
+    any::<Literal>()
+        .prop_filter("Literal can't be round-tripped", |lit| {
+            !matches!(
+                lit,
+                Literal::Float(_)
+                    | Literal::Double(_)
+                    | Literal::Numeric(..)
+                    | Literal::Blob(_)
+                    | Literal::ByteArray(_)
+                    | Literal::BitVector(_)
+            ) && !matches!(lit, Literal::Integer(i) if *i < 0)
+        })
+        .prop_filter("Servers don't accept NUL bytes in queries", move |lit| {
+            !(target.is_server() && matches!(lit, Literal::String(s) if s.contains('\0')))
+        })
+        .prop_map(move |lit| match (lit, target) {
+            (Literal::Placeholder(_), Target::MySQL) => {
+                Literal::Placeholder(ItemPlaceholder::QuestionMark)
+            }
+            (Literal::Placeholder(_), Target::PostgreSQL) => {
+                Literal::Placeholder(ItemPlaceholder::DollarNumber(1))
+            }
+            (lit, _) => lit,
+        })
+}
+
+pub fn binary_operator() -> impl Strategy<Value = BinaryOperator> {
+    any::<BinaryOperator>().prop_filter("ILIKE is displayed as LIKE", |op| {
+        !matches!(op, BinaryOperator::ILike | BinaryOperator::NotILike)
+    })
+}
+
+pub fn expression(target: Target) -> impl Strategy<Value = Expression> {
+    let leaf = prop_oneof![
+        literal(target).prop_map(Expression::Literal),
+        column().prop_map(Expression::Column),
+    ];
+    leaf.prop_recursive(4, 32, 4, move |inner| {
+        prop_oneof![
+            (
+                inner.clone(),
+                binary_operator(),
+                inner.clone(),
+                prop_oneof![
+                    Just(Literal::Null),
+                    any::<bool>().prop_map(Literal::Boolean)
+                ]
+            )
+                .prop_map(move |(lhs, op, rhs, is_rhs)| {
+                    // Servers only accept `IS` and `IS NOT` with NULL or a boolean
+                    let rhs = if target.is_server()
+                        && matches!(op, BinaryOperator::Is | BinaryOperator::IsNot)
+                    {
+                        Expression::Literal(is_rhs)
+                    } else {
+                        rhs
+                    };
+                    Expression::BinaryOp {
+                        lhs: Box::new(lhs),
+                        op,
+                        rhs: Box::new(rhs),
+                    }
+                }),
+            (
+                prop_oneof![Just(UnaryOperator::Not), Just(UnaryOperator::Neg)],
+                inner.clone()
+            )
+                .prop_map(|(op, rhs)| Expression::UnaryOp {
+                    op,
+                    rhs: Box::new(rhs)
+                }),
+            (inner.clone(), inner.clone(), inner.clone(), any::<bool>()).prop_map(
+                |(operand, min, max, negated)| Expression::Between {
+                    operand: Box::new(operand),
+                    min: Box::new(min),
+                    max: Box::new(max),
+                    negated,
+                }
+            ),
+            (inner.clone(), vec(inner.clone(), 1..4), any::<bool>()).prop_map(
+                |(lhs, list, negated)| Expression::In {
+                    lhs: Box::new(lhs),
+                    rhs: InValue::List(list),
+                    negated,
+                }
+            ),
+            (inner.clone(), inner.clone(), option::of(inner)).prop_map(
+                |(condition, then_expr, else_expr)| Expression::CaseWhen {
+                    condition: Box::new(condition),
+                    then_expr: Box::new(then_expr),
+                    else_expr: else_expr.map(Box::new),
+                }
+            ),
+        ]
+    })
+}
+
+pub fn field(target: Target) -> impl Strategy<Value = FieldDefinitionExpression> {
+    prop_oneof![
+        Just(FieldDefinitionExpression::All),
+        (expression(target), option::of(identifier()))
+            .prop_map(|(expr, alias)| FieldDefinitionExpression::Expression { expr, alias }),
+    ]
+}
+
+pub fn fields(target: Target) -> impl Strategy<Value = Vec<FieldDefinitionExpression>> {
+    vec(field(target), 1..4).prop_map(move |mut fields| {
+        // MySQL only accepts an unqualified `*` as the first field
+        if target == Target::MySQL {
+            let all = fields
+                .iter()
+                .any(|f| matches!(f, FieldDefinitionExpression::All));
+            fields.retain(|f| !matches!(f, FieldDefinitionExpression::All));
+            if all {
+                fields.insert(0, FieldDefinitionExpression::All);
+            }
+        }
+        fields
+    })
+}
+
+pub fn column_reference() -> impl Strategy<Value = FieldReference> {
+    column().prop_map(|c| FieldReference::Expression(Expression::Column(c)))
+}
+
+pub fn join(target: Target) -> impl Strategy<Value = JoinClause> {
+    (any::<JoinOperator>(), table(), expression(target)).prop_map(|(operator, right, on)| {
+        JoinClause {
+            operator,
+            right: JoinRightSide::Table(right),
+            constraint: JoinConstraint::On(on),
+        }
+    })
+}
+
+pub fn select_statement(target: Target) -> impl Strategy<Value = SelectStatement> {
+    (
+        vec(table(), 1..3),
+        any::<bool>(),
+        fields(target),
+        vec(join(target), 0..3),
+        option::of(expression(target)),
+        option::of(vec(column_reference(), 1..3)),
+        option::of(vec(
+            (column_reference(), option::of(any::<OrderType>())),
+            1..3,
+        )),
+        option::of((0..1000i64, option::of(0..1000i64))),
+    )
+        .prop_map(
+            |(tables, distinct, fields, join, where_clause, group_by, order, limit)| {
+                SelectStatement {
+                    ctes: vec![],
+                    tables,
+                    distinct,
+                    fields,
+                    join,
+                    where_clause,
+                    group_by: group_by.map(|fields| GroupByClause { fields }),
+                    having: None,
+                    order: order.map(|order_by| OrderClause { order_by }),
+                    limit: limit.map(|(limit, offset)| LimitClause {
+                        limit: limit.into(),
+                        offset: offset.map(Literal::from),
+                    }),
+                }
+            },
+        )
+}
+
+pub fn insert_statement(target: Target) -> impl Strategy<Value = InsertStatement> {
+    // PostgreSQL has neither `INSERT IGNORE` nor `ON DUPLICATE KEY UPDATE`
+    let mysql_extensions = target != Target::PostgreSQL;
+    (1..4usize)
+        .prop_flat_map(move |width| {
+            (
+                unaliased_table(),
+                option::of(vec(unqualified_column(), width)),
+                vec(vec(literal(target), width), 1..4),
+                any::<bool>().prop_map(move |ignore| ignore && mysql_extensions),
+                option::of(vec((unqualified_column(), expression(target)), 1..3))
+                    .prop_map(move |on_duplicate| on_duplicate.filter(|_| mysql_extensions)),
+            )
+        })
+        .prop_map(
+            |(table, fields, data, ignore, on_duplicate)| InsertStatement {
+                table,
+                fields,
+                data,
+                select: None,
+                ignore,
+                on_duplicate,
+                returning: None,
+            },
+        )
+}
+
+pub fn update_statement(target: Target) -> impl Strategy<Value = UpdateStatement> {
+    (
+        unaliased_table(),
+        vec((unqualified_column(), expression(target)), 1..4),
+        option::of(expression(target)),
+    )
+        .prop_map(|(table, fields, where_clause)| UpdateStatement {
+            table,
+            fields,
+            where_clause,
+            returning: None,
+            ..Default::default()
+        })
+}
+
+pub fn delete_statement(target: Target) -> impl Strategy<Value = DeleteStatement> {
+    (unaliased_table(), option::of(expression(target))).prop_map(|(table, where_clause)| {
+        DeleteStatement {
+            table,
+            where_clause,
+            ..Default::default()
+        }
+    })
+}
+
+pub fn statement(target: Target) -> impl Strategy<Value = SqlQuery> {
+    prop_oneof![
+        select_statement(target).prop_map(SqlQuery::Select),
+        insert_statement(target).prop_map(SqlQuery::Insert),
+        update_statement(target).prop_map(SqlQuery::Update),
+        delete_statement(target).prop_map(SqlQuery::Delete),
+    ]
+}
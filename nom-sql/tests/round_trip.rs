@@ -0,0 +1,22 @@
+//! Property tests checking that displaying any statement generated from the AST and parsing the
+//! result again gives back the same statement.
+//!
+//! See the [`generate`] module for the subset of the AST that statements are generated from.
+
+mod generate;
+
+use nom_sql::{parse_query, Dialect, SqlQuery};
+use test_strategy::proptest;
+
+use crate::generate::{statement, Target};
+
+#[proptest]
+fn statement_round_trip(#[strategy(statement(Target::NomSql))] statement: SqlQuery) {
+    let displayed = statement.to_string();
+    assert_eq!(
+        parse_query(Dialect::MySQL, &displayed),
+        Ok(statement),
+        "{}",
+        displayed
+    );
+}
@@ -4,6 +4,7 @@ use std::convert::TryFrom;
 use std::io;
 
 use async_trait::async_trait;
+use futures::stream;
 use postgres_types::Type;
 use psql_srv::{run_backend, Backend, Column, Error, PrepareResponse, QueryResponse};
 use tokio::net::TcpListener;
@@ -24,7 +25,7 @@ struct ServeOneBackend;
 impl Backend for ServeOneBackend {
     type Value = Value;
     type Row = Vec<Value>;
-    type Resultset = Vec<Self::Row>;
+    type Resultset = stream::Iter<std::vec::IntoIter<Result<Self::Row, Error>>>;
 
     const SERVER_VERSION: &'static str = "example";
 
@@ -38,7 +39,7 @@ impl Backend for ServeOneBackend {
                 name: "bar".to_string(),
                 col_type: Type::INT4,
             }],
-            resultset: vec![vec![Value(psql_srv::Value::Int(100))]],
+            resultset: stream::iter(vec![Ok(vec![Value(psql_srv::Value::Int(100))])]),
         })
     }
 
@@ -63,7 +64,7 @@ impl Backend for ServeOneBackend {
                 name: "bar".to_string(),
                 col_type: Type::INT4,
             }],
-            resultset: vec![vec![Value(psql_srv::Value::Int(100))]],
+            resultset: stream::iter(vec![Ok(vec![Value(psql_srv::Value::Int(100))])]),
         })
     }
 
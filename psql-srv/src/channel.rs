@@ -69,7 +69,7 @@ where
     /// Write a `Response` (actually the `BackendMessage`s generated a `Response`) to the channel.
     pub async fn send<S>(&mut self, item: Response<R, S>) -> Result<(), EncodeError>
     where
-        S: IntoIterator<Item = R>,
+        S: Stream<Item = Result<R, Error>> + Unpin,
     {
         item.write(&mut self.0).await
     }
@@ -77,4 +77,10 @@ where
     pub async fn flush(&mut self) -> Result<(), EncodeError> {
         self.0.flush().await
     }
+
+    /// Consume the channel, returning the underlying byte stream. Any buffered data that has yet
+    /// to be decoded is discarded.
+    pub fn into_inner(self) -> C {
+        self.0.into_inner()
+    }
 }
@@ -6,7 +6,7 @@ use bit_vec::BitVec;
 use bytes::{Buf, Bytes, BytesMut};
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
 use eui48::MacAddress;
-use postgres_types::{FromSql, Type};
+use postgres_types::{FromSql, Kind, Type};
 use rust_decimal::prelude::FromStr;
 use rust_decimal::Decimal;
 use tokio_util::codec::Decoder;
@@ -28,6 +28,7 @@ const ID_DESCRIBE: u8 = b'D';
 const ID_EXECUTE: u8 = b'E';
 const ID_FLUSH: u8 = b'H';
 const ID_PARSE: u8 = b'P';
+const ID_PASSWORD: u8 = b'p';
 const ID_QUERY: u8 = b'Q';
 const ID_SYNC: u8 = b'S';
 const ID_TERMINATE: u8 = b'X';
@@ -228,6 +229,10 @@ impl<R: IntoIterator<Item: TryInto<Value, Error = BackendError>>> Decoder for Co
                 }))
             }
 
+            ID_PASSWORD => Ok(Some(Authenticate {
+                body: msg.split_off(0),
+            })),
+
             ID_QUERY => Ok(Some(Query {
                 query: get_str(msg)?,
             })),
@@ -336,10 +341,36 @@ fn get_binary_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
         Type::JSONB => Ok(Value::Jsonb(serde_json::Value::from_sql(t, buf)?)),
         Type::BIT => Ok(Value::Bit(BitVec::from_sql(t, buf)?)),
         Type::VARBIT => Ok(Value::VarBit(BitVec::from_sql(t, buf)?)),
-        _ => Err(Error::UnsupportedType(t.clone())),
+        _ => match t.kind() {
+            Kind::Array(elem) => get_binary_array(buf, elem, t),
+            _ => Err(Error::UnsupportedType(t.clone())),
+        },
     }
 }
 
+/// Reads a one-dimensional array of values of type `elem` in the binary format, which consists
+/// of the number of dimensions, a flag for whether the array contains any nulls, the oid of the
+/// element type, the length and lower bound of each dimension, and then each element (with its own
+/// length prefix)
+fn get_binary_array(src: &mut Bytes, elem: &Type, t: &Type) -> Result<Value, Error> {
+    let ndim = get_i32(src)?;
+    let _has_nulls = get_i32(src)?;
+    let _elem_oid = get_i32(src)?;
+    let values = match ndim {
+        0 => vec![],
+        1 => {
+            let len = get_i32(src)?;
+            let _lower_bound = get_i32(src)?;
+            (0..len)
+                .map(|_| get_binary_value(src, elem))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+        // Multi-dimensional arrays aren't supported
+        _ => return Err(Error::UnsupportedType(t.clone())),
+    };
+    Ok(Value::Array(values, elem.clone()))
+}
+
 fn get_bitvec_from_str(bit_str: &str) -> Result<BitVec, Error> {
     let mut bits = BitVec::with_capacity(bit_str.len());
     for c in bit_str.chars() {
@@ -359,7 +390,10 @@ fn get_text_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
     }
 
     let text = BytesStr::try_from(src.split_to(usize::try_from(len)?))?;
-    let text_str: &str = text.borrow();
+    parse_text_value(text.borrow(), t)
+}
+
+fn parse_text_value(text_str: &str, t: &Type) -> Result<Value, Error> {
     match *t {
         Type::BOOL => Ok(Value::Bool(text_str == BOOL_TRUE_TEXT_REP)),
         Type::VARCHAR => Ok(Value::Varchar(text_str.into())),
@@ -413,8 +447,72 @@ fn get_text_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
             .map(Value::Jsonb),
         Type::BIT => get_bitvec_from_str(text_str).map(Value::Bit),
         Type::VARBIT => get_bitvec_from_str(text_str).map(Value::VarBit),
-        _ => Err(Error::UnsupportedType(t.clone())),
+        _ => match t.kind() {
+            Kind::Array(elem) => parse_text_array(text_str, elem, t),
+            _ => Err(Error::UnsupportedType(t.clone())),
+        },
+    }
+}
+
+/// Parses the text representation of a one-dimensional array of values of type `elem`, such as
+/// `{1,NULL,"a, b"}`
+fn parse_text_array(text_str: &str, elem: &Type, t: &Type) -> Result<Value, Error> {
+    let invalid = || Error::InvalidTextArrayValue(text_str.to_owned());
+    let inner = text_str
+        .trim()
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .ok_or_else(invalid)?;
+
+    let mut values = vec![];
+    if inner.trim().is_empty() {
+        return Ok(Value::Array(values, elem.clone()));
+    }
+
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut element = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => element.push(chars.next().ok_or_else(invalid)?),
+                    Some(c) => element.push(c),
+                    None => return Err(invalid()),
+                }
+            }
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            values.push(parse_text_value(&element, elem)?);
+        } else {
+            while let Some(c) = chars.next_if(|&c| c != ',') {
+                match c {
+                    // Multi-dimensional arrays aren't supported
+                    '{' => return Err(Error::UnsupportedType(t.clone())),
+                    '\\' => element.push(chars.next().ok_or_else(invalid)?),
+                    c => element.push(c),
+                }
+            }
+            let element = element.trim_end();
+            if element.is_empty() {
+                return Err(invalid());
+            }
+            values.push(if element.eq_ignore_ascii_case("NULL") {
+                Value::Null
+            } else {
+                parse_text_value(element, elem)?
+            });
+        }
+
+        match chars.next() {
+            Some(',') => continue,
+            None => break,
+            Some(_) => return Err(invalid()),
+        }
     }
+
+    Ok(Value::Array(values, elem.clone()))
 }
 
 #[cfg(test)]
@@ -884,6 +982,22 @@ mod tests {
         assert!(codec.decode(&mut buf).is_err());
     }
 
+    #[test]
+    fn test_decode_authenticate() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        codec.set_start_up_complete();
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'p'); // message id
+        buf.put_i32(4 + 6); // size
+        buf.extend_from_slice(b"c=biws");
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Authenticate {
+                body: Bytes::from_static(b"c=biws")
+            })
+        );
+    }
+
     #[test]
     fn test_decode_sync() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -1417,4 +1531,57 @@ mod tests {
             DataValue::TimestampTz(expected)
         );
     }
+
+    #[test]
+    fn test_decode_binary_array() {
+        let mut buf = BytesMut::new();
+        buf.put_i32(-1); // size (placeholder)
+        vec![Some(1i32), None, Some(3)]
+            .to_sql(&Type::INT4_ARRAY, &mut buf)
+            .unwrap(); // add value
+        let value_len = buf.len() - 4;
+        let mut window = buf
+            .get_mut(0..4)
+            .ok_or_else(|| Error::InternalError("error writing message field".to_string()))
+            .unwrap();
+        window.put_i32(value_len as i32); // put the actual length
+        assert_eq!(
+            get_binary_value(&mut buf.freeze(), &Type::INT4_ARRAY).unwrap(),
+            DataValue::Array(
+                vec![DataValue::Int(1), DataValue::Null, DataValue::Int(3)],
+                Type::INT4
+            )
+        );
+    }
+
+    #[test]
+    fn test_decode_text_array() {
+        let text = br#"{a, NULL,"b, \"c\"",""}"#;
+        let mut buf = BytesMut::new();
+        buf.put_i32(text.len() as i32); // size
+        buf.extend_from_slice(text);
+        assert_eq!(
+            get_text_value(&mut buf.freeze(), &Type::TEXT_ARRAY).unwrap(),
+            DataValue::Array(
+                vec![
+                    DataValue::Text("a".into()),
+                    DataValue::Null,
+                    DataValue::Text("b, \"c\"".into()),
+                    DataValue::Text("".into()),
+                ],
+                Type::TEXT
+            )
+        );
+    }
+
+    #[test]
+    fn test_decode_text_empty_array() {
+        let mut buf = BytesMut::new();
+        buf.put_i32(2); // size
+        buf.extend_from_slice(b"{}");
+        assert_eq!(
+            get_text_value(&mut buf.freeze(), &Type::INT4_ARRAY).unwrap(),
+            DataValue::Array(vec![], Type::INT4)
+        );
+    }
 }
@@ -14,7 +14,7 @@ use crate::message::ErrorSeverity;
 use crate::message::TransferFormat::{self, *};
 use crate::value::Value;
 
-const ID_AUTHENTICATION_OK: u8 = b'R';
+const ID_AUTHENTICATION_REQUEST: u8 = b'R';
 const ID_BIND_COMPLETE: u8 = b'2';
 const ID_CLOSE_COMPLETE: u8 = b'3';
 const ID_COMMAND_COMPLETE: u8 = b'C';
@@ -23,10 +23,14 @@ const ID_ERROR_RESPONSE: u8 = b'E';
 const ID_PARAMETER_DESCRIPTION: u8 = b't';
 const ID_PARAMETER_STATUS: u8 = b'S';
 const ID_PARSE_COMPLETE: u8 = b'1';
+const ID_PORTAL_SUSPENDED: u8 = b's';
 const ID_READY_FOR_QUERY: u8 = b'Z';
 const ID_ROW_DESCRIPTION: u8 = b'T';
 
 const AUTHENTICATION_OK_SUCCESS: i32 = 0;
+const AUTHENTICATION_SASL: i32 = 10;
+const AUTHENTICATION_SASL_CONTINUE: i32 = 11;
+const AUTHENTICATION_SASL_FINAL: i32 = 12;
 
 const COMMAND_COMPLETE_DELETE_TAG: &str = "DELETE";
 const COMMAND_COMPLETE_INSERT_TAG: &str = "INSERT";
@@ -44,6 +48,7 @@ const ERROR_RESPONSE_SEVERITY_FATAL: &str = "FATAL";
 const ERROR_RESPONSE_SEVERITY_PANIC: &str = "PANIC";
 const ERROR_RESPONSE_TERMINATOR: u8 = b'\0';
 
+const ARRAY_NULL_TEXT_REP: &str = "NULL";
 const BOOL_FALSE_TEXT_REP: &str = "f";
 const BOOL_TRUE_TEXT_REP: &str = "t";
 const COUNT_PLACEHOLDER: i16 = -1;
@@ -88,11 +93,35 @@ where
 
     match message {
         AuthenticationOk => {
-            put_u8(ID_AUTHENTICATION_OK, dst);
+            put_u8(ID_AUTHENTICATION_REQUEST, dst);
             put_i32(LENGTH_PLACEHOLDER, dst);
             put_i32(AUTHENTICATION_OK_SUCCESS, dst);
         }
 
+        AuthenticationSasl { allowed_mechanisms } => {
+            put_u8(ID_AUTHENTICATION_REQUEST, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(AUTHENTICATION_SASL, dst);
+            for mechanism in allowed_mechanisms {
+                put_str(&mechanism, dst);
+            }
+            put_u8(NUL_BYTE, dst);
+        }
+
+        AuthenticationSaslContinue { sasl_data } => {
+            put_u8(ID_AUTHENTICATION_REQUEST, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(AUTHENTICATION_SASL_CONTINUE, dst);
+            dst.put_slice(&sasl_data);
+        }
+
+        AuthenticationSaslFinal { outcome } => {
+            put_u8(ID_AUTHENTICATION_REQUEST, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(AUTHENTICATION_SASL_FINAL, dst);
+            dst.put_slice(&outcome);
+        }
+
         BindComplete => {
             put_u8(ID_BIND_COMPLETE, dst);
             put_i32(LENGTH_PLACEHOLDER, dst);
@@ -212,6 +241,11 @@ where
             put_i32(LENGTH_PLACEHOLDER, dst);
         }
 
+        PortalSuspended => {
+            put_u8(ID_PORTAL_SUSPENDED, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+        }
+
         ReadyForQuery { status } => {
             put_u8(ID_READY_FOR_QUERY, dst);
             put_i32(LENGTH_PLACEHOLDER, dst);
@@ -379,6 +413,9 @@ fn put_binary_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
         Value::VarBit(bits) => {
             bits.to_sql(&Type::VARBIT, dst)?;
         }
+        Value::Array(elems, elem_type) => {
+            put_binary_array(elems, elem_type, dst)?;
+        }
     };
     // Update the length field to match the recently serialized data length in `dst`. The 4 byte
     // length field itself is excluded from the length calculation.
@@ -387,9 +424,25 @@ fn put_binary_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
     Ok(())
 }
 
-fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
-    use std::fmt::Write;
+/// Writes a one-dimensional array of values of type `elem_type` in the binary format, which
+/// consists of the number of dimensions, a flag for whether the array contains any nulls, the oid
+/// of the element type, the length and lower bound of each dimension, and then each element (with
+/// its own length prefix)
+fn put_binary_array(elems: Vec<Value>, elem_type: Type, dst: &mut BytesMut) -> Result<(), Error> {
+    put_i32(if elems.is_empty() { 0 } else { 1 }, dst);
+    put_i32(i32::from(elems.contains(&Value::Null)), dst);
+    put_type(elem_type, dst)?;
+    if !elems.is_empty() {
+        put_i32(i32::try_from(elems.len())?, dst);
+        put_i32(1, dst); // lower bound
+    }
+    for elem in elems {
+        put_binary_value(elem, dst)?;
+    }
+    Ok(())
+}
 
+fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
     if val == Value::Null {
         put_i32(LENGTH_NULL_SENTINEL, dst);
         return Ok(());
@@ -397,6 +450,18 @@ fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
 
     let start_ofs = dst.len();
     put_i32(LENGTH_PLACEHOLDER, dst);
+    write_text_value(val, dst)?;
+    // Update the length field to match the recently serialized data length in `dst`. The 4 byte
+    // length field itself is excluded from the length calculation.
+    let value_len = dst.len() - start_ofs - 4;
+    set_i32(i32::try_from(value_len)?, dst, start_ofs)?;
+    Ok(())
+}
+
+/// Writes the text representation of `val`, without a length prefix
+fn write_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
+    use std::fmt::Write;
+
     match val {
         #[allow(clippy::unreachable)]
         Value::Null => {
@@ -478,14 +543,49 @@ fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
                 .collect::<Vec<String>>()
                 .join("")
         )?,
+        Value::Array(elems, _) => {
+            dst.put_u8(b'{');
+            for (i, elem) in elems.into_iter().enumerate() {
+                if i > 0 {
+                    dst.put_u8(b',');
+                }
+                if elem == Value::Null {
+                    dst.put_slice(ARRAY_NULL_TEXT_REP.as_bytes());
+                    continue;
+                }
+                let mut text = BytesMut::new();
+                write_text_value(elem, &mut text)?;
+                put_array_element_text(&text, dst);
+            }
+            dst.put_u8(b'}');
+        }
     };
-    // Update the length field to match the recently serialized data length in `dst`. The 4 byte
-    // length field itself is excluded from the length calculation.
-    let value_len = dst.len() - start_ofs - 4;
-    set_i32(i32::try_from(value_len)?, dst, start_ofs)?;
     Ok(())
 }
 
+/// Writes the text representation of an element of an array, quoting and escaping it if it would
+/// otherwise be ambiguous
+fn put_array_element_text(text: &[u8], dst: &mut BytesMut) {
+    let needs_quotes = text.is_empty()
+        || text.eq_ignore_ascii_case(ARRAY_NULL_TEXT_REP.as_bytes())
+        || text
+            .iter()
+            .any(|&b| matches!(b, b'{' | b'}' | b',' | b'"' | b'\\') || b.is_ascii_whitespace());
+    if !needs_quotes {
+        dst.extend_from_slice(text);
+        return;
+    }
+
+    dst.put_u8(b'"');
+    for &b in text {
+        if matches!(b, b'"' | b'\\') {
+            dst.put_u8(b'\\');
+        }
+        dst.put_u8(b);
+    }
+    dst.put_u8(b'"');
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -535,6 +635,47 @@ mod tests {
         assert_eq!(buf, exp);
     }
 
+    #[test]
+    fn test_encode_authentication_sasl() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                AuthenticationSasl {
+                    allowed_mechanisms: vec!["SCRAM-SHA-256".to_owned()],
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'R'); // message id
+        exp.put_i32(4 + 4 + 14 + 1); // message length
+        exp.put_i32(10); // SASL code
+        exp.extend_from_slice(b"SCRAM-SHA-256\0");
+        exp.put_u8(b'\0'); // end of mechanisms
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_authentication_sasl_continue() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                AuthenticationSaslContinue {
+                    sasl_data: b"r=abc,s=def,i=4096".to_vec(),
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'R'); // message id
+        exp.put_i32(4 + 4 + 18); // message length
+        exp.put_i32(11); // SASL continue code
+        exp.extend_from_slice(b"r=abc,s=def,i=4096");
+        assert_eq!(buf, exp);
+    }
+
     #[test]
     fn test_encode_bind_complete() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -557,6 +698,17 @@ mod tests {
         assert_eq!(buf, exp);
     }
 
+    #[test]
+    fn test_encode_portal_suspended() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec.encode(PortalSuspended, &mut buf).unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b's'); // message id
+        exp.put_i32(4); // message length
+        assert_eq!(buf, exp);
+    }
+
     #[test]
     fn test_encode_command_complete_delete() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -1410,4 +1562,52 @@ mod tests {
         exp.extend_from_slice(b"2020-01-02 08:04:05.660 +05:00");
         assert_eq!(buf, exp);
     }
+
+    #[test]
+    fn test_encode_binary_array() {
+        let mut buf = BytesMut::new();
+        put_binary_value(
+            DataValue::Array(
+                vec![DataValue::Int(1), DataValue::Null, DataValue::Int(3)],
+                Type::INT4,
+            ),
+            &mut buf,
+        )
+        .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_i32(-1); // size (placeholder)
+        vec![Some(1i32), None, Some(3)]
+            .to_sql(&Type::INT4_ARRAY, &mut exp)
+            .unwrap(); // add value
+        let value_len = exp.len() - 4;
+        let mut window = exp
+            .get_mut(0..4)
+            .ok_or_else(|| Error::InternalError("error writing message field".to_string()))
+            .unwrap();
+        window.put_i32(value_len as i32); // put the actual length
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_text_array() {
+        let mut buf = BytesMut::new();
+        put_text_value(
+            DataValue::Array(
+                vec![
+                    DataValue::Text("a".into()),
+                    DataValue::Null,
+                    DataValue::Text("b, \"c\"".into()),
+                    DataValue::Text("".into()),
+                ],
+                Type::TEXT,
+            ),
+            &mut buf,
+        )
+        .unwrap();
+        let exp_text = br#"{a,NULL,"b, \"c\"",""}"#;
+        let mut exp = BytesMut::new();
+        exp.put_i32(exp_text.len() as i32);
+        exp.extend_from_slice(exp_text);
+        assert_eq!(buf, exp);
+    }
 }
@@ -58,6 +58,9 @@ pub enum DecodeError {
     #[error("invalid text bit vector value: {0}")]
     InvalidTextBitVectorValue(String),
 
+    #[error("invalid text array value: {0}")]
+    InvalidTextArrayValue(String),
+
     #[error("invalid type: {0}")]
     InvalidType(u32),
 
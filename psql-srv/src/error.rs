@@ -8,6 +8,9 @@ use crate::message::FrontendMessage;
 
 #[derive(Debug, Error)]
 pub enum Error {
+    #[error("password authentication failed for user \"{0}\"")]
+    AuthenticationFailure(String),
+
     #[error("decode error: {0}")]
     DecodeError(#[from] DecodeError),
 
@@ -17,6 +20,9 @@ pub enum Error {
     #[error("incorrect format count: {0}")]
     IncorrectFormatCount(usize),
 
+    #[error("connections using insecure transport are prohibited")]
+    InsecureTransport,
+
     #[error("internal error: {0}")]
     InternalError(String),
 
@@ -35,6 +41,9 @@ pub enum Error {
     #[error("parse error: {0}")]
     ParseError(String),
 
+    #[error("protocol violation: {0}")]
+    ProtocolViolation(String),
+
     #[error("unimplemented: {0}")]
     Unimplemented(String),
 
@@ -22,12 +22,16 @@ mod message;
 mod protocol;
 mod response;
 mod runner;
+mod scram;
+mod tls;
 pub mod util;
 mod value;
 
 use std::convert::TryInto;
 
 use async_trait::async_trait;
+use futures::Stream;
+use openssl::ssl::SslAcceptor;
 use postgres_types::Type;
 use protocol::Protocol;
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -54,9 +58,11 @@ pub trait Backend {
     /// produce `Self::Value`s.
     type Row: IntoIterator<Item = Self::Value>;
 
-    /// An associated type representing a resultset returned by a SQL query, which can be iterated
-    /// to produce `Self::Row`s.
-    type Resultset: IntoIterator<Item = Self::Row>;
+    /// An associated type representing a resultset returned by a SQL query, which is streamed to
+    /// produce `Self::Row`s. The rows are sent to the frontend as the stream produces them, so a
+    /// resultset doesn't have to be held in memory all at once. If the stream produces an error,
+    /// the error is sent to the frontend in place of the rest of the resultset.
+    type Resultset: Stream<Item = Result<Self::Row, Error>> + Unpin + Send + 'static;
 
     /// Initializes the backend.
     ///
@@ -94,6 +100,19 @@ pub trait Backend {
     ///
     /// * `statement_id` - The identifier of the prepared statement to close.
     async fn on_close(&mut self, statement_id: u32) -> Result<(), Error>;
+
+    /// Retrieve the password for the user with the given username, if any.
+    ///
+    /// If the user doesn't exist, return [`None`].
+    fn password_for_username(&self, _username: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Return true if clients must authenticate (using SCRAM-SHA-256) as one of the users known to
+    /// [`Backend::password_for_username`] before they can issue queries.
+    fn require_authentication(&self) -> bool {
+        false
+    }
 }
 
 /// A description of a column, either in the parameters to a query or in a resultset
@@ -135,6 +154,24 @@ pub enum QueryResponse<S> {
     Delete(u64),
     /// The response to a command statement such as "CREATE TABLE".
     Command,
+    /// The response to an insert, update, or delete statement with a `RETURNING` clause, including
+    /// the rows returned by the statement. One row is returned for each row written.
+    Returning {
+        /// The kind of statement that returned the rows.
+        kind: WriteKind,
+        /// The schema of the rows returned by the statement.
+        schema: Vec<Column>,
+        /// The rows returned by the statement.
+        resultset: S,
+    },
+}
+
+/// The kind of a write statement that returns rows, as part of a [`QueryResponse::Returning`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteKind {
+    Insert,
+    Update,
+    Delete,
 }
 
 /// Run a `Backend` on the provided bytestream until the bytestream is remotely closed.
@@ -147,12 +184,31 @@ pub async fn run_backend<B: Backend, C: AsyncRead + AsyncWrite + Unpin>(backend:
     runner::Runner::run(backend, channel).await
 }
 
+/// Like [`run_backend`], but offers TLS to the frontend, using `acceptor` to perform the TLS
+/// handshake with frontends that request it. If `require_tls` is set, frontends that don't request
+/// TLS are refused.
+///
+/// If `acceptor` verifies client certificates, a frontend that presents a certificate whose
+/// subject's common name is the user it connects as is authenticated as that user without a
+/// password, provided the backend knows of the user (see [`Backend::password_for_username`]).
+pub async fn run_backend_with_tls<B, C>(
+    backend: B,
+    channel: C,
+    acceptor: &SslAcceptor,
+    require_tls: bool,
+) where
+    B: Backend,
+    C: AsyncRead + AsyncWrite + Unpin,
+{
+    tls::run(backend, channel, acceptor, require_tls).await
+}
+
 pub async fn send_immediate_err<B, C>(channel: C, error: Error) -> Result<(), Error>
 where
     B: Backend,
     C: AsyncRead + AsyncWrite + Unpin,
 {
-    let packet = Protocol::new().on_error::<B>(error).await?;
+    let packet = Protocol::<B::Row>::new().on_error::<B>(error).await?;
     channel::Channel::new(channel).send(packet).await?;
     Ok(())
 }
@@ -10,6 +10,7 @@ use crate::value::Value;
 
 const READY_FOR_QUERY_IDLE: u8 = b'I';
 const SSL_RESPONSE_N: u8 = b'N';
+const SSL_RESPONSE_S: u8 = b'S';
 
 /// A message to be sent by a Postgresql backend (server). The different types of backend messages,
 /// and the fields they contain, are described in the
@@ -25,6 +26,15 @@ const SSL_RESPONSE_N: u8 = b'N';
 #[derive(Debug, PartialEq)]
 pub enum BackendMessage<R> {
     AuthenticationOk,
+    AuthenticationSasl {
+        allowed_mechanisms: Vec<String>,
+    },
+    AuthenticationSaslContinue {
+        sasl_data: Vec<u8>,
+    },
+    AuthenticationSaslFinal {
+        outcome: Vec<u8>,
+    },
     BindComplete,
     CloseComplete,
     CommandComplete {
@@ -47,6 +57,7 @@ pub enum BackendMessage<R> {
         parameter_value: String,
     },
     ParseComplete,
+    PortalSuspended,
     ReadyForQuery {
         status: u8,
     },
@@ -70,6 +81,12 @@ impl<R: IntoIterator<Item: TryInto<Value, Error = Error>>> BackendMessage<R> {
             byte: SSL_RESPONSE_N,
         }
     }
+
+    pub fn ssl_response_s() -> BackendMessage<R> {
+        BackendMessage::SSLResponse {
+            byte: SSL_RESPONSE_S,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use bytes::Bytes;
 use postgres_types::Type;
 
 use crate::bytes::BytesStr;
@@ -21,6 +22,12 @@ use crate::value::Value;
 /// [`Bytes`]: https://docs.rs/bytes/0.5.6/bytes/struct.Bytes.html
 #[derive(Debug, PartialEq)]
 pub enum FrontendMessage {
+    /// A `PasswordMessage`, `SASLInitialResponse` or `SASLResponse` message, which all share the
+    /// same message id. The body can only be interpreted knowing which authentication exchange is
+    /// in progress, so it's left to the protocol to parse.
+    Authenticate {
+        body: Bytes,
+    },
     Bind {
         portal_name: BytesStr,
         prepared_statement_name: BytesStr,
@@ -65,6 +72,7 @@ pub enum StatementName {
 impl fmt::Display for FrontendMessage {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            Self::Authenticate { .. } => write!(f, "Authenticate"),
             Self::Bind { .. } => write!(f, "Bind"),
             Self::Close { .. } => write!(f, "Close"),
             Self::Describe { .. } => write!(f, "Describe"),
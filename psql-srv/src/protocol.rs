@@ -1,7 +1,12 @@
 use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
 use std::sync::Arc;
 
+use bytes::{Buf, Bytes};
+use futures::stream::{BoxStream, Peekable};
+use futures::{StreamExt, TryStreamExt};
 use postgres_types::Type;
 use smallvec::smallvec;
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -14,10 +19,11 @@ use crate::message::StatementName::*;
 use crate::message::TransferFormat::{self, *};
 use crate::message::{CommandCompleteTag, ErrorSeverity, FieldDescription, SqlState};
 use crate::response::Response;
+use crate::scram::{ChannelBinding, ScramExchange, SCRAM_SHA_256, SCRAM_SHA_256_PLUS};
 use crate::util::type_is_oid;
 use crate::value::Value;
 use crate::QueryResponse::*;
-use crate::{Backend, Column, PrepareResponse};
+use crate::{Backend, Column, PrepareResponse, WriteKind};
 
 const ATTTYPMOD_NONE: i32 = -1;
 const TRANSFER_FORMAT_PLACEHOLDER: TransferFormat = TransferFormat::Text;
@@ -36,6 +42,7 @@ const UNKNOWN_TABLE: i32 = 0;
 /// The state transitions are:
 ///
 /// * StartingUp -> Ready
+/// * StartingUp -> Authenticating -> Ready
 /// * Ready -> Extended
 /// * Extended -> Error
 /// * Error -> Ready
@@ -44,6 +51,9 @@ pub(crate) enum State {
     /// The server is starting up
     StartingUp,
 
+    /// The client has sent its startup message, and must authenticate before it can issue queries
+    Authenticating,
+
     /// The server is ready to accept queries
     Ready,
 
@@ -62,7 +72,10 @@ pub(crate) enum State {
 
 /// A struct to maintain state for an implementation of the backend side of the PostgreSQL
 /// frontend/backend protocol.
-pub struct Protocol {
+///
+/// `R` is the type of the rows returned by the backend, some of which may be retained by portals
+/// whose execution has been suspended.
+pub struct Protocol<R> {
     /// The current state of the request-response flow
     state: State,
 
@@ -75,7 +88,27 @@ pub struct Protocol {
     /// A portal is a combination of a prepared statement and a list of values provided by the
     /// frontend for the prepared statement's parameters. This `HashMap` contains these parameter
     /// values as well as metadata about the portal, and is keyed by the portal's name.
-    portals: HashMap<String, PortalData>,
+    portals: HashMap<String, PortalData<R>>,
+
+    /// While the client is authenticating, the state of its authentication exchange
+    authentication: Option<Authentication>,
+
+    /// If the client is connected over TLS and presented a verified certificate, the name of the
+    /// user it identifies
+    tls_client_identity: Option<String>,
+
+    /// If the client is connected over TLS, the `tls-server-end-point` channel binding data of
+    /// the connection, which allows the client to authenticate using `SCRAM-SHA-256-PLUS`
+    tls_server_end_point: Option<Vec<u8>>,
+}
+
+/// The state of a client's authentication, which happens after the client has sent its startup
+/// message and before it's allowed to issue queries.
+struct Authentication {
+    user: String,
+    database: String,
+    /// The in-progress SCRAM exchange, once the client has sent its initial SASL response
+    scram: Option<ScramExchange>,
 }
 
 /// A prepared statement allows a frontend to specify the general form of a SQL statement while
@@ -91,25 +124,68 @@ struct PreparedStatementData {
 /// A portal is a combination of a prepared statement and a list of values provided by the frontend
 /// for the prepared statement's parameters. This struct contains these parameter values as well as
 /// metadata about the portal.
-#[derive(Debug, PartialEq)]
-struct PortalData {
+#[derive(Debug)]
+struct PortalData<R> {
     prepared_statement_id: u32,
     prepared_statement_name: String,
     params: Vec<Value>,
     result_transfer_formats: Arc<Vec<TransferFormat>>,
+    /// If a previous execution of the portal was suspended after reaching the row limit of an
+    /// `Execute` request, the rows it has yet to return
+    suspended: Option<SuspendedRows<R>>,
+}
+
+/// The rows remaining to be returned by a portal whose execution has been suspended
+struct SuspendedRows<R> {
+    /// The rest of the resultset returned by the backend, which is only read from as the portal
+    /// is executed
+    rows: Peekable<BoxStream<'static, Result<R, Error>>>,
+    /// If set, the rows are being returned by a write statement of this kind, rather than by a
+    /// select statement
+    write_kind: Option<WriteKind>,
+}
+
+impl<R> fmt::Debug for SuspendedRows<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SuspendedRows")
+            .field("write_kind", &self.write_kind)
+            .finish_non_exhaustive()
+    }
 }
 
 /// An implementation of the backend side of the PostgreSQL frontend/backend protocol. See
 /// `on_request` for the primary entry point.
-impl Protocol {
-    pub fn new() -> Protocol {
+impl<R> Protocol<R> {
+    pub fn new() -> Protocol<R> {
         Protocol {
             state: State::StartingUp,
             prepared_statements: HashMap::new(),
             portals: HashMap::new(),
+            authentication: None,
+            tls_client_identity: None,
+            tls_server_end_point: None,
         }
     }
 
+    /// Record that the client is connected over TLS and presented a verified certificate
+    /// identifying the given user, who is then allowed to connect without a password.
+    pub(crate) fn set_tls_client_identity(&mut self, user: String) {
+        self.tls_client_identity = Some(user);
+    }
+
+    /// Record that the client is connected over TLS, with the given `tls-server-end-point` channel
+    /// binding data (the hash of the server's certificate), so that `SCRAM-SHA-256-PLUS` is
+    /// offered to it.
+    pub(crate) fn set_tls_server_end_point(&mut self, data: Vec<u8>) {
+        self.tls_server_end_point = Some(data);
+    }
+
+    /// Returns true if the client has yet to finish authenticating. Any error in this state ends
+    /// the connection.
+    pub(crate) fn is_authenticating(&self) -> bool {
+        self.state == State::Authenticating
+    }
+
     /// The core implementation of the backend side of the PostgreSQL frontend/backend protocol.
     /// This implementation processes a message received from the frontend, forwards suitable
     /// requests to a `Backend`, and returns appropriate responses as a `Result`.
@@ -125,7 +201,7 @@ impl Protocol {
     ///   the frontend/backend protocol state in order to parse some types of frontend messages.)
     /// * returns - A `Response` representing a sequence of `BackendMessage`s to return to the
     ///   frontend, otherwise an `Error` if a failure occurs.
-    pub async fn on_request<B: Backend, C: AsyncRead + AsyncWrite + Unpin>(
+    pub async fn on_request<B: Backend<Row = R>, C: AsyncRead + AsyncWrite + Unpin>(
         &mut self,
         message: FrontendMessage,
         backend: &mut B,
@@ -141,37 +217,101 @@ impl Protocol {
                 }
 
                 // A request to start up a connection, with some metadata provided.
-                StartupMessage { database, .. } => {
+                StartupMessage { user, database, .. } => {
                     let database = database
                         .ok_or_else(|| Error::Unsupported("database is required".to_string()))?;
-                    backend.on_init(database.borrow()).await?;
-                    self.state = State::Ready;
-                    channel.set_start_up_complete();
-                    Ok(Response::Messages(smallvec![
-                        AuthenticationOk,
-                        BackendMessage::ParameterStatus {
-                            parameter_name: "client_encoding".to_owned(),
-                            parameter_value: "UTF8".to_owned(),
-                        },
-                        BackendMessage::ParameterStatus {
-                            parameter_name: "DateStyle".to_owned(),
-                            parameter_value: "ISO".to_owned(),
-                        },
-                        BackendMessage::ParameterStatus {
-                            parameter_name: "TimeZone".to_owned(),
-                            parameter_value: "UTC".to_owned(),
-                        },
-                        BackendMessage::ParameterStatus {
-                            parameter_name: "server_version".to_owned(),
-                            parameter_value: B::SERVER_VERSION.to_owned(),
-                        },
-                        BackendMessage::ready_for_query_idle(),
-                    ]))
+
+                    if backend.require_authentication() {
+                        let user =
+                            user.ok_or_else(|| Error::Unsupported("user is required".to_string()))?;
+                        // A verified client certificate authenticates the user it names
+                        let name: &str = user.borrow();
+                        if self.tls_client_identity.as_deref() == Some(name)
+                            && backend.password_for_username(name).is_some()
+                        {
+                            return self
+                                .complete_start_up(database.borrow(), None, backend, channel)
+                                .await;
+                        }
+                        self.authentication = Some(Authentication {
+                            user: user.to_string(),
+                            database: database.to_string(),
+                            scram: None,
+                        });
+                        self.state = State::Authenticating;
+                        channel.set_start_up_complete();
+                        let allowed_mechanisms = if self.tls_server_end_point.is_some() {
+                            vec![SCRAM_SHA_256_PLUS.to_owned(), SCRAM_SHA_256.to_owned()]
+                        } else {
+                            vec![SCRAM_SHA_256.to_owned()]
+                        };
+                        return Ok(Response::Message(AuthenticationSasl { allowed_mechanisms }));
+                    }
+
+                    self.complete_start_up(database.borrow(), None, backend, channel)
+                        .await
                 }
 
                 m => Err(Error::UnsupportedMessage(m)),
             },
 
+            State::Authenticating => match message {
+                // The client's response in a SASL authentication exchange
+                Authenticate { body } => {
+                    let mut authentication = self.authentication.take().ok_or_else(|| {
+                        Error::InternalError("missing authentication state".to_string())
+                    })?;
+
+                    match authentication.scram.take() {
+                        None => {
+                            let (mechanism, data) = parse_sasl_initial_response(body)?;
+                            let channel_binding =
+                                match (mechanism.as_str(), self.tls_server_end_point.as_deref()) {
+                                    (SCRAM_SHA_256, None) => ChannelBinding::Unsupported,
+                                    (SCRAM_SHA_256, Some(_)) => ChannelBinding::NotUsed,
+                                    (SCRAM_SHA_256_PLUS, Some(data)) => {
+                                        ChannelBinding::TlsServerEndPoint(data)
+                                    }
+                                    _ => {
+                                        return Err(Error::ProtocolViolation(format!(
+                                            "client selected an invalid SASL authentication \
+                                             mechanism: {}",
+                                            mechanism
+                                        )))
+                                    }
+                                };
+                            let password = backend.password_for_username(&authentication.user);
+                            let (scram, server_first) = ScramExchange::start(
+                                authentication.user.clone(),
+                                password.as_deref(),
+                                &data,
+                                channel_binding,
+                            )?;
+                            authentication.scram = Some(scram);
+                            self.authentication = Some(authentication);
+                            Ok(Response::Message(AuthenticationSaslContinue {
+                                sasl_data: server_first.into_bytes(),
+                            }))
+                        }
+                        Some(scram) => {
+                            let server_final = scram.finish(&body)?;
+                            self.complete_start_up(
+                                &authentication.database,
+                                Some(server_final.into_bytes()),
+                                backend,
+                                channel,
+                            )
+                            .await
+                        }
+                    }
+                }
+
+                // A request to terminate the connection.
+                Terminate => Ok(Response::Empty),
+
+                m => Err(Error::UnsupportedMessage(m)),
+            },
+
             _ => match message {
                 // A request to bind parameters to a prepared statement, creating a portal.
                 Bind {
@@ -212,6 +352,7 @@ impl Protocol {
                             prepared_statement_name: prepared_statement_name.to_string(),
                             params,
                             result_transfer_formats: Arc::new(result_transfer_formats),
+                            suspended: None,
                         },
                     );
                     Ok(Response::Message(BindComplete))
@@ -233,7 +374,11 @@ impl Protocol {
                                 backend.on_close(id).await?;
                                 channel.clear_statement_param_types(name.borrow() as &str);
                                 self.prepared_statements.remove(name.borrow() as &str);
-                                // TODO Remove all portals referencing this prepared statement.
+                                // Closing a prepared statement also closes all portals
+                                // constructed from it.
+                                self.portals.retain(|_, portal| {
+                                    portal.prepared_statement_name != name.borrow() as &str
+                                });
                             }
                         }
                     };
@@ -291,75 +436,128 @@ impl Protocol {
                 },
 
                 // A request to execute a portal (a combination of a prepared statement with
-                // parameter values).
-                Execute { portal_name, .. } => {
+                // parameter values). If `limit` is positive, at most that many rows are returned,
+                // and the portal's execution is suspended if any rows remain, to be continued by
+                // the next request to execute the portal.
+                Execute { portal_name, limit } => {
                     self.state = State::Extended;
-                    let PortalData {
-                        prepared_statement_id,
-                        params,
-                        result_transfer_formats,
-                        ..
-                    } = self
+                    let portal = self
                         .portals
-                        .get(portal_name.borrow() as &str)
+                        .get_mut(portal_name.borrow() as &str)
                         .ok_or_else(|| Error::MissingPreparedStatement(portal_name.to_string()))?;
-                    let response = backend.on_execute(*prepared_statement_id, params).await?;
-                    let res = if let Select { resultset, .. } = response {
-                        Ok(Response::Select {
-                            header: None,
-                            resultset,
-                            result_transfer_formats: Some(result_transfer_formats.clone()),
-                            trailer: None,
-                        })
-                    } else {
-                        let tag = match response {
-                            Insert(n) => CommandCompleteTag::Insert(n),
-                            Update(n) => CommandCompleteTag::Update(n),
-                            Delete(n) => CommandCompleteTag::Delete(n),
-                            Command => CommandCompleteTag::Empty,
-                            #[allow(clippy::unreachable)]
-                            Select { .. } => {
-                                unreachable!("Select is handled as a special case above.")
+                    let result_transfer_formats = Some(portal.result_transfer_formats.clone());
+
+                    let SuspendedRows {
+                        mut rows,
+                        write_kind,
+                    } = match portal.suspended.take() {
+                        Some(suspended) => suspended,
+                        None => {
+                            let response = backend
+                                .on_execute(portal.prepared_statement_id, &portal.params)
+                                .await?;
+                            let (resultset, write_kind) = match response {
+                                Select { resultset, .. } => (resultset, None),
+                                Returning {
+                                    kind, resultset, ..
+                                } => (resultset, Some(kind)),
+                                Insert(_) | Update(_) | Delete(_) | Command => {
+                                    let tag = match response {
+                                        Insert(n) => CommandCompleteTag::Insert(n),
+                                        Update(n) => CommandCompleteTag::Update(n),
+                                        Delete(n) => CommandCompleteTag::Delete(n),
+                                        Command => CommandCompleteTag::Empty,
+                                        #[allow(clippy::unreachable)]
+                                        Select { .. } | Returning { .. } => {
+                                            unreachable!(
+                                                "Select is handled as a special case above."
+                                            )
+                                        }
+                                    };
+                                    self.state = State::Ready;
+                                    return Ok(Response::Message(CommandComplete { tag }));
+                                }
+                            };
+
+                            if limit <= 0 {
+                                self.state = State::Ready;
+                                return Ok(Response::Select {
+                                    header: None,
+                                    resultset,
+                                    result_transfer_formats,
+                                    trailer: None,
+                                    write_kind,
+                                });
                             }
-                        };
-                        Ok(Response::Message(CommandComplete { tag }))
+
+                            SuspendedRows {
+                                rows: resultset.boxed().peekable(),
+                                write_kind,
+                            }
+                        }
                     };
+
+                    let mut batch = Vec::new();
+                    while limit <= 0 || batch.len() < limit as usize {
+                        match rows.try_next().await? {
+                            Some(row) => batch.push(row),
+                            None => break,
+                        }
+                    }
+                    let suspended = Pin::new(&mut rows).peek().await.is_some();
+                    if suspended {
+                        portal.suspended = Some(SuspendedRows { rows, write_kind });
+                    }
+
                     self.state = State::Ready;
-                    res
+                    Ok(Response::PortalRows {
+                        rows: batch,
+                        result_transfer_formats,
+                        write_kind,
+                        suspended,
+                    })
                 }
 
                 // A request to directly execute a complete SQL statement, without creating a
                 // prepared statement.
                 Query { query } => {
                     let response = backend.on_query(query.borrow()).await?;
-                    if let Select { schema, resultset } = response {
-                        Ok(Response::Select {
-                            header: Some(RowDescription {
-                                field_descriptions: schema
-                                    .iter()
-                                    .map(|i| make_field_description(i, Text))
-                                    .collect::<Result<Vec<FieldDescription>, Error>>()?,
-                            }),
+                    let (schema, resultset, write_kind) = match response {
+                        Select { schema, resultset } => (schema, resultset, None),
+                        Returning {
+                            kind,
+                            schema,
                             resultset,
-                            result_transfer_formats: None,
-                            trailer: Some(BackendMessage::ready_for_query_idle()),
-                        })
-                    } else {
-                        let tag = match response {
-                            Insert(n) => CommandCompleteTag::Insert(n),
-                            Update(n) => CommandCompleteTag::Update(n),
-                            Delete(n) => CommandCompleteTag::Delete(n),
-                            Command => CommandCompleteTag::Empty,
-                            #[allow(clippy::unreachable)]
-                            Select { .. } => {
-                                unreachable!("Select is handled as a special case above.")
-                            }
-                        };
-                        Ok(Response::Messages(smallvec![
-                            CommandComplete { tag },
-                            BackendMessage::ready_for_query_idle(),
-                        ]))
-                    }
+                        } => (schema, resultset, Some(kind)),
+                        Insert(_) | Update(_) | Delete(_) | Command => {
+                            let tag = match response {
+                                Insert(n) => CommandCompleteTag::Insert(n),
+                                Update(n) => CommandCompleteTag::Update(n),
+                                Delete(n) => CommandCompleteTag::Delete(n),
+                                Command => CommandCompleteTag::Empty,
+                                #[allow(clippy::unreachable)]
+                                Select { .. } | Returning { .. } => {
+                                    unreachable!("Select is handled as a special case above.")
+                                }
+                            };
+                            return Ok(Response::Messages(smallvec![
+                                CommandComplete { tag },
+                                BackendMessage::ready_for_query_idle(),
+                            ]));
+                        }
+                    };
+                    Ok(Response::Select {
+                        header: Some(RowDescription {
+                            field_descriptions: schema
+                                .iter()
+                                .map(|i| make_field_description(i, Text))
+                                .collect::<Result<Vec<FieldDescription>, Error>>()?,
+                        }),
+                        resultset,
+                        result_transfer_formats: None,
+                        trailer: Some(BackendMessage::ready_for_query_idle()),
+                        write_kind,
+                    })
                 }
 
                 // A request to create a prepared statement.
@@ -405,39 +603,96 @@ impl Protocol {
         }
     }
 
+    /// Complete the start up of a connection to `database`, once the client has authenticated (if
+    /// required), returning the messages that tell the client it can issue queries.
+    ///
+    /// * `sasl_outcome` - If the client authenticated using SASL, the additional data sent to the
+    ///   client in the final message of the exchange.
+    async fn complete_start_up<B: Backend<Row = R>, C: AsyncRead + AsyncWrite + Unpin>(
+        &mut self,
+        database: &str,
+        sasl_outcome: Option<Vec<u8>>,
+        backend: &mut B,
+        channel: &mut Channel<C, B::Row>,
+    ) -> Result<Response<B::Row, B::Resultset>, Error> {
+        backend.on_init(database).await?;
+        self.state = State::Ready;
+        channel.set_start_up_complete();
+
+        let mut messages = smallvec![];
+        if let Some(outcome) = sasl_outcome {
+            messages.push(AuthenticationSaslFinal { outcome });
+        }
+        messages.extend([
+            AuthenticationOk,
+            BackendMessage::ParameterStatus {
+                parameter_name: "client_encoding".to_owned(),
+                parameter_value: "UTF8".to_owned(),
+            },
+            BackendMessage::ParameterStatus {
+                parameter_name: "DateStyle".to_owned(),
+                parameter_value: "ISO".to_owned(),
+            },
+            BackendMessage::ParameterStatus {
+                parameter_name: "TimeZone".to_owned(),
+                parameter_value: "UTC".to_owned(),
+            },
+            BackendMessage::ParameterStatus {
+                parameter_name: "server_version".to_owned(),
+                parameter_value: B::SERVER_VERSION.to_owned(),
+            },
+            BackendMessage::ready_for_query_idle(),
+        ]);
+        Ok(Response::Messages(messages))
+    }
+
     /// An error handler producing an `ErrorResponse` message.
     ///
     /// * `error` - an `Error` that has occurred while communicating with the frontend or handling
     ///   one of the frontend's requests.
     /// * returns - A `Response` containing an `ErrorResponse` message to send to the frontend.
-    pub async fn on_error<B: Backend>(
+    pub async fn on_error<B: Backend<Row = R>>(
         &mut self,
         error: Error,
     ) -> Result<Response<B::Row, B::Resultset>, Error> {
         match self.state {
             State::StartingUp | State::Extended => {
                 self.state = State::Error;
-                Ok(Response::Message(make_error_response(error)))
+                Ok(Response::Message(make_error_response(
+                    error,
+                    ErrorSeverity::Error,
+                )))
+            }
+            // Authentication errors are fatal, and the connection is closed after they're sent
+            State::Authenticating => {
+                self.authentication = None;
+                Ok(Response::Message(make_error_response(
+                    error,
+                    ErrorSeverity::Fatal,
+                )))
             }
             _ => Ok(Response::Messages(smallvec![
-                make_error_response(error),
+                make_error_response(error, ErrorSeverity::Error),
                 BackendMessage::ready_for_query_idle(),
             ])),
         }
     }
 }
 
-fn make_error_response<R>(error: Error) -> BackendMessage<R> {
+pub(crate) fn make_error_response<R>(error: Error, severity: ErrorSeverity) -> BackendMessage<R> {
     let sqlstate = match error {
+        Error::AuthenticationFailure(_) => SqlState::INVALID_PASSWORD,
         Error::DecodeError(_) => SqlState::IO_ERROR,
         Error::EncodeError(_) => SqlState::IO_ERROR,
         Error::IncorrectFormatCount(_) => SqlState::IO_ERROR,
+        Error::InsecureTransport => SqlState::INVALID_AUTHORIZATION_SPECIFICATION,
         Error::InternalError(_) => SqlState::INTERNAL_ERROR,
         Error::InvalidInteger(_) => SqlState::DATATYPE_MISMATCH,
         Error::IoError(_) => SqlState::IO_ERROR,
         Error::MissingPortal(_) => SqlState::UNDEFINED_PSTATEMENT,
         Error::MissingPreparedStatement(_) => SqlState::UNDEFINED_PSTATEMENT,
         Error::ParseError(_) => SqlState::INVALID_PSTATEMENT_DEFINITION,
+        Error::ProtocolViolation(_) => SqlState::PROTOCOL_VIOLATION,
         Error::Unimplemented(_) => SqlState::FEATURE_NOT_SUPPORTED,
         Error::Unknown(_) => SqlState::INTERNAL_ERROR,
         Error::Unsupported(_) => SqlState::FEATURE_NOT_SUPPORTED,
@@ -446,12 +701,37 @@ fn make_error_response<R>(error: Error) -> BackendMessage<R> {
         Error::PostgresError(ref e) => e.code().cloned().unwrap_or(SqlState::INTERNAL_ERROR),
     };
     ErrorResponse {
-        severity: ErrorSeverity::Error,
+        severity,
         sqlstate,
         message: error.to_string(),
     }
 }
 
+/// Parse the body of a `SASLInitialResponse` message into the name of the SASL mechanism selected
+/// by the client, and the mechanism's initial response.
+fn parse_sasl_initial_response(mut body: Bytes) -> Result<(String, Bytes), Error> {
+    let malformed =
+        || Error::ProtocolViolation("malformed SASLInitialResponse message".to_string());
+
+    let nul_pos = body
+        .iter()
+        .position(|&c| c == b'\0')
+        .ok_or_else(malformed)?;
+    let mechanism = String::from_utf8(body.split_to(nul_pos).to_vec()).map_err(|_| malformed())?;
+    body.advance(1); // skip the nul byte
+
+    if body.remaining() < 4 {
+        return Err(malformed());
+    }
+    let data = match body.get_i32() {
+        // A length of -1 means there's no initial response
+        -1 => Bytes::new(),
+        len if usize::try_from(len) == Ok(body.remaining()) => body,
+        _ => return Err(malformed()),
+    };
+    Ok((mechanism, data))
+}
+
 fn make_field_description(
     col: &Column,
     transfer_format: TransferFormat,
@@ -502,6 +782,7 @@ mod tests {
     use async_trait::async_trait;
     use bytes::BytesMut;
     use futures::task::Context;
+    use futures::Stream;
     use tokio::io::ReadBuf;
     use tokio_test::block_on;
 
@@ -527,6 +808,19 @@ mod tests {
         }
     }
 
+    // A resultset of rows held in memory, which (unlike most streams) can be compared in
+    // assertions.
+    #[derive(Debug, PartialEq)]
+    struct Resultset(Vec<Vec<Value>>);
+
+    impl Stream for Resultset {
+        type Item = Result<Vec<Value>, Error>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready((!self.0.is_empty()).then(|| Ok(self.0.remove(0))))
+        }
+    }
+
     // A dummy `Backend` that records the values passed to it and can return a few hard-coded
     // responses.
     struct Backend {
@@ -535,6 +829,8 @@ mod tests {
 
         is_prepare_err: bool,
 
+        require_authentication: bool,
+
         database: Option<String>,
         last_query: Option<String>,
         last_prepare: Option<String>,
@@ -549,6 +845,7 @@ mod tests {
                 is_query_err: false,
                 is_query_read: true,
                 is_prepare_err: false,
+                require_authentication: false,
                 database: None,
                 last_query: None,
                 last_prepare: None,
@@ -563,7 +860,7 @@ mod tests {
     impl crate::Backend for Backend {
         type Value = Value;
         type Row = Vec<Self::Value>;
-        type Resultset = Vec<Self::Row>;
+        type Resultset = Resultset;
 
         const SERVER_VERSION: &'static str = "test";
 
@@ -588,10 +885,10 @@ mod tests {
                             col_type: Type::FLOAT8,
                         },
                     ],
-                    resultset: vec![
+                    resultset: Resultset(vec![
                         vec![Value(DataValue::Int(88)), Value(DataValue::Double(0.123))],
                         vec![Value(DataValue::Int(22)), Value(DataValue::Double(0.456))],
-                    ],
+                    ]),
                 })
             } else {
                 Ok(QueryResponse::Delete(5))
@@ -641,10 +938,10 @@ mod tests {
                             col_type: Type::FLOAT8,
                         },
                     ],
-                    resultset: vec![
+                    resultset: Resultset(vec![
                         vec![Value(DataValue::Int(88)), Value(DataValue::Double(0.123))],
                         vec![Value(DataValue::Int(22)), Value(DataValue::Double(0.456))],
-                    ],
+                    ]),
                 })
             } else {
                 Ok(QueryResponse::Delete(5))
@@ -655,6 +952,14 @@ mod tests {
             self.last_close = Some(statement_id);
             Ok(())
         }
+
+        fn password_for_username(&self, username: &str) -> Option<Vec<u8>> {
+            (username == "user_name").then(|| b"password".to_vec())
+        }
+
+        fn require_authentication(&self) -> bool {
+            self.require_authentication
+        }
     }
 
     // A dummy `AsyncRead + AsyncWrite` that does not read or write any data.
@@ -756,6 +1061,143 @@ mod tests {
         assert!(block_on(protocol.on_request(request, &mut backend, &mut channel)).is_err(),);
     }
 
+    #[test]
+    fn startup_message_requiring_authentication() {
+        let mut protocol = Protocol::new();
+        let request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        let mut backend = Backend::new();
+        backend.require_authentication = true;
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        // The client is asked to authenticate using SCRAM-SHA-256.
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::Message(BackendMessage::AuthenticationSasl {
+                allowed_mechanisms: vec!["SCRAM-SHA-256".to_owned()]
+            })
+        );
+        assert_eq!(protocol.state, State::Authenticating);
+        // The backend isn't initialized until the client has authenticated.
+        assert!(backend.database.is_none());
+
+        // Queries cannot be issued before the client has authenticated.
+        let request = FrontendMessage::Query {
+            query: bytes_str("SELECT * FROM test;"),
+        };
+        assert!(block_on(protocol.on_request(request, &mut backend, &mut channel)).is_err());
+        assert!(backend.last_query.is_none());
+    }
+
+    #[test]
+    fn startup_message_requiring_authentication_over_tls() {
+        let mut protocol = Protocol::new();
+        protocol.set_tls_server_end_point(b"certificate hash".to_vec());
+        let request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        let mut backend = Backend::new();
+        backend.require_authentication = true;
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        // Channel binding is offered to clients connected over TLS.
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::Message(BackendMessage::AuthenticationSasl {
+                allowed_mechanisms: vec![
+                    "SCRAM-SHA-256-PLUS".to_owned(),
+                    "SCRAM-SHA-256".to_owned()
+                ]
+            })
+        );
+
+        let mut body = BytesMut::new();
+        body.extend_from_slice(b"SCRAM-SHA-256-PLUS\0");
+        let data = b"p=tls-server-end-point,,n=,r=abcdef";
+        body.extend_from_slice(&(data.len() as i32).to_be_bytes());
+        body.extend_from_slice(data);
+        let request = FrontendMessage::Authenticate {
+            body: body.freeze(),
+        };
+        assert!(matches!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::Message(BackendMessage::AuthenticationSaslContinue { .. })
+        ));
+        assert!(protocol.is_authenticating());
+    }
+
+    #[test]
+    fn startup_message_with_tls_client_identity() {
+        let mut protocol = Protocol::new();
+        protocol.set_tls_client_identity("user_name".to_owned());
+        let request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        let mut backend = Backend::new();
+        backend.require_authentication = true;
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        // A client certificate naming the user authenticates the client without SASL.
+        block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap();
+        assert_eq!(protocol.state, State::Ready);
+        assert_eq!(backend.database.unwrap(), "database_name");
+    }
+
+    #[test]
+    fn startup_message_with_tls_client_identity_for_other_user() {
+        let mut protocol = Protocol::new();
+        protocol.set_tls_client_identity("someone_else".to_owned());
+        let request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        let mut backend = Backend::new();
+        backend.require_authentication = true;
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap();
+        assert_eq!(protocol.state, State::Authenticating);
+        assert!(backend.database.is_none());
+    }
+
+    #[test]
+    fn sasl_initial_response_with_unknown_mechanism() {
+        let mut protocol = Protocol::new();
+        let mut backend = Backend::new();
+        backend.require_authentication = true;
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        let startup_request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        block_on(protocol.on_request(startup_request, &mut backend, &mut channel)).unwrap();
+
+        let mut body = BytesMut::new();
+        body.extend_from_slice(b"SCRAM-SHA-256-PLUS\0");
+        body.extend_from_slice(&(-1i32).to_be_bytes());
+        let request = FrontendMessage::Authenticate {
+            body: body.freeze(),
+        };
+        let error = block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap_err();
+        assert!(matches!(error, Error::ProtocolViolation(_)));
+        assert_eq!(
+            block_on(protocol.on_error::<Backend>(error)).unwrap(),
+            Response::Message(ErrorResponse {
+                severity: ErrorSeverity::Fatal,
+                sqlstate: SqlState::PROTOCOL_VIOLATION,
+                message: "protocol violation: client selected an invalid SASL authentication \
+                          mechanism: SCRAM-SHA-256-PLUS"
+                    .to_string()
+            })
+        );
+        assert!(protocol.is_authenticating());
+    }
+
     #[test]
     fn regular_mode_message_without_startup() {
         let mut protocol = Protocol::new();
@@ -872,12 +1314,13 @@ mod tests {
                         },
                     ],
                 }),
-                resultset: vec![
+                resultset: Resultset(vec![
                     vec![Value(DataValue::Int(88)), Value(DataValue::Double(0.123))],
                     vec![Value(DataValue::Int(22)), Value(DataValue::Double(0.456))]
-                ],
+                ]),
                 result_transfer_formats: None,
-                trailer: Some(BackendMessage::ready_for_query_idle())
+                trailer: Some(BackendMessage::ready_for_query_idle()),
+                write_kind: None
             }
         );
         assert_eq!(backend.last_query.unwrap(), "SELECT * FROM test;");
@@ -1036,18 +1479,18 @@ mod tests {
             block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
             Response::Message(BindComplete)
         );
+        let portal = protocol.portals.get("portal1").unwrap();
+        assert_eq!(portal.prepared_statement_id, 0);
+        assert_eq!(portal.prepared_statement_name, "prepared1");
         assert_eq!(
-            *protocol.portals.get("portal1").unwrap(),
-            PortalData {
-                prepared_statement_id: 0,
-                prepared_statement_name: "prepared1".to_string(),
-                params: vec![DataValue::Double(0.8887), DataValue::Int(45678)],
-                result_transfer_formats: Arc::new(vec![
-                    TransferFormat::Text,
-                    TransferFormat::Binary
-                ])
-            }
+            portal.params,
+            vec![DataValue::Double(0.8887), DataValue::Int(45678)]
         );
+        assert_eq!(
+            portal.result_transfer_formats,
+            Arc::new(vec![TransferFormat::Text, TransferFormat::Binary])
+        );
+        assert!(portal.suspended.is_none());
     }
 
     #[test]
@@ -1082,16 +1525,19 @@ mod tests {
             block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
             Response::Message(BindComplete)
         );
+        let portal = protocol.portals.get("portal1").unwrap();
+        assert_eq!(portal.prepared_statement_id, 0);
+        assert_eq!(portal.prepared_statement_name, "prepared1");
         assert_eq!(
-            *protocol.portals.get("portal1").unwrap(),
-            PortalData {
-                prepared_statement_id: 0,
-                prepared_statement_name: "prepared1".to_string(),
-                params: vec![DataValue::Double(0.8887), DataValue::Int(45678)],
-                // The transfer formats are set to the default value (Text).
-                result_transfer_formats: Arc::new(vec![TransferFormat::Text, TransferFormat::Text])
-            }
+            portal.params,
+            vec![DataValue::Double(0.8887), DataValue::Int(45678)]
+        );
+        // The transfer formats are set to the default value (Text).
+        assert_eq!(
+            portal.result_transfer_formats,
+            Arc::new(vec![TransferFormat::Text, TransferFormat::Text])
         );
+        assert!(portal.suspended.is_none());
     }
 
     #[test]
@@ -1126,19 +1572,19 @@ mod tests {
             block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
             Response::Message(BindComplete)
         );
+        let portal = protocol.portals.get("portal1").unwrap();
+        assert_eq!(portal.prepared_statement_id, 0);
+        assert_eq!(portal.prepared_statement_name, "prepared1");
         assert_eq!(
-            *protocol.portals.get("portal1").unwrap(),
-            PortalData {
-                prepared_statement_id: 0,
-                prepared_statement_name: "prepared1".to_string(),
-                params: vec![DataValue::Double(0.8887), DataValue::Int(45678)],
-                // The single transfer format is applied to both fields.
-                result_transfer_formats: Arc::new(vec![
-                    TransferFormat::Binary,
-                    TransferFormat::Binary
-                ])
-            }
+            portal.params,
+            vec![DataValue::Double(0.8887), DataValue::Int(45678)]
+        );
+        // The single transfer format is applied to both fields.
+        assert_eq!(
+            portal.result_transfer_formats,
+            Arc::new(vec![TransferFormat::Binary, TransferFormat::Binary])
         );
+        assert!(portal.suspended.is_none());
     }
 
     #[test]
@@ -1226,8 +1672,17 @@ mod tests {
         block_on(protocol.on_request(parse_request, &mut backend, &mut channel)).unwrap();
         assert!(protocol.prepared_statements.get("prepared1").is_some());
 
+        let bind_request = FrontendMessage::Bind {
+            prepared_statement_name: bytes_str("prepared1"),
+            portal_name: bytes_str("portal1"),
+            params: vec![DataValue::Double(0.8887), DataValue::Int(45678)],
+            result_transfer_formats: vec![],
+        };
+        block_on(protocol.on_request(bind_request, &mut backend, &mut channel)).unwrap();
+        assert!(protocol.portals.get("portal1").is_some());
+
         // A prepared statement close request calls close on the backend and removes Protocol state
-        // for the prepared statement.
+        // for the prepared statement, and for the portals constructed from it.
         let request = FrontendMessage::Close {
             name: PreparedStatement(bytes_str("prepared1")),
         };
@@ -1237,6 +1692,7 @@ mod tests {
         );
         assert_eq!(backend.last_close.unwrap(), 0);
         assert!(protocol.prepared_statements.get("prepared1").is_none());
+        assert!(protocol.portals.get("portal1").is_none());
     }
 
     #[test]
@@ -1529,15 +1985,16 @@ mod tests {
             block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
             Response::Select {
                 header: None,
-                resultset: vec![
+                resultset: Resultset(vec![
                     vec![Value(DataValue::Int(88)), Value(DataValue::Double(0.123))],
                     vec![Value(DataValue::Int(22)), Value(DataValue::Double(0.456))]
-                ],
+                ]),
                 result_transfer_formats: Some(Arc::new(vec![
                     TransferFormat::Text,
                     TransferFormat::Binary
                 ])),
-                trailer: None
+                trailer: None,
+                write_kind: None
             }
         );
         assert_eq!(backend.last_execute_id.unwrap(), 0);
@@ -1547,6 +2004,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn execute_read_with_limit() {
+        let mut protocol = Protocol::new();
+        let mut backend = Backend::new();
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+
+        let startup_request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        block_on(protocol.on_request(startup_request, &mut backend, &mut channel)).unwrap();
+
+        let parse_request = FrontendMessage::Parse {
+            prepared_statement_name: bytes_str("prepared1"),
+            query: bytes_str("SELECT * FROM test WHERE x = $1 AND y = $2;"),
+            parameter_data_types: vec![],
+        };
+        block_on(protocol.on_request(parse_request, &mut backend, &mut channel)).unwrap();
+
+        let bind_request = FrontendMessage::Bind {
+            prepared_statement_name: bytes_str("prepared1"),
+            portal_name: bytes_str("portal1"),
+            params: vec![DataValue::Double(0.8887), DataValue::Int(45678)],
+            result_transfer_formats: vec![TransferFormat::Text, TransferFormat::Binary],
+        };
+        block_on(protocol.on_request(bind_request, &mut backend, &mut channel)).unwrap();
+
+        let result_transfer_formats =
+            Some(Arc::new(vec![TransferFormat::Text, TransferFormat::Binary]));
+
+        // Executing with a row limit returns only the first row, and suspends the portal.
+        let request = FrontendMessage::Execute {
+            portal_name: bytes_str("portal1"),
+            limit: 1,
+        };
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::PortalRows {
+                rows: vec![vec![
+                    Value(DataValue::Int(88)),
+                    Value(DataValue::Double(0.123))
+                ]],
+                result_transfer_formats: result_transfer_formats.clone(),
+                write_kind: None,
+                suspended: true
+            }
+        );
+        assert!(protocol.portals["portal1"].suspended.is_some());
+        backend.last_execute_id = None;
+
+        // Executing the portal again returns the remaining row without re-executing the
+        // statement, and completes the portal.
+        let request = FrontendMessage::Execute {
+            portal_name: bytes_str("portal1"),
+            limit: 1,
+        };
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::PortalRows {
+                rows: vec![vec![
+                    Value(DataValue::Int(22)),
+                    Value(DataValue::Double(0.456))
+                ]],
+                result_transfer_formats,
+                write_kind: None,
+                suspended: false
+            }
+        );
+        assert!(backend.last_execute_id.is_none());
+        assert!(protocol.portals["portal1"].suspended.is_none());
+    }
+
     #[test]
     fn execute_error() {
         let mut protocol = Protocol::new();
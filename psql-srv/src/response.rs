@@ -6,8 +6,10 @@ use smallvec::SmallVec;
 
 use crate::codec::EncodeError;
 use crate::error::Error;
-use crate::message::{BackendMessage, CommandCompleteTag, TransferFormat};
+use crate::message::{BackendMessage, CommandCompleteTag, ErrorSeverity, TransferFormat};
+use crate::protocol::make_error_response;
 use crate::value::Value;
+use crate::WriteKind;
 
 /// An encapsulation of a complete response produced by a Postgresql backend in response to a
 /// request. The response will be sent to the frontend as a sequence of zero or more
@@ -20,20 +22,34 @@ pub enum Response<R, S> {
     /// Send multiple messages at once
     Messages(SmallVec<[BackendMessage<R>; 2]>),
 
-    /// `Select` is the most complex variant, containing data rows to be sent to the frontend in
-    /// response to a select query.
+    /// `Select` is the most complex variant, containing a stream of data rows to be sent to the
+    /// frontend in response to a select query. If the stream produces an error, an error response
+    /// is sent after the rows that were already sent, in place of `CommandComplete`.
     Select {
         header: Option<BackendMessage<R>>,
         resultset: S,
         result_transfer_formats: Option<Arc<Vec<TransferFormat>>>,
         trailer: Option<BackendMessage<R>>,
+        /// If set, the rows were returned by a write statement of this kind, rather than by a
+        /// select statement
+        write_kind: Option<WriteKind>,
+    },
+
+    /// A batch of the rows produced by executing a portal with a row limit. If `suspended` is set,
+    /// the portal still has rows remaining, and `PortalSuspended` is sent in place of
+    /// `CommandComplete`.
+    PortalRows {
+        rows: Vec<R>,
+        result_transfer_formats: Option<Arc<Vec<TransferFormat>>>,
+        write_kind: Option<WriteKind>,
+        suspended: bool,
     },
 }
 
 impl<R, S> Response<R, S>
 where
     R: IntoIterator<Item: TryInto<Value, Error = Error>>,
-    S: IntoIterator<Item = R>,
+    S: Stream<Item = Result<R, Error>> + Unpin,
 {
     pub async fn write<K>(self, sink: &mut K) -> Result<(), EncodeError>
     where
@@ -59,26 +75,39 @@ where
 
             Select {
                 header,
-                resultset,
+                mut resultset,
                 result_transfer_formats,
                 trailer,
+                write_kind,
             } => {
                 if let Some(header) = header {
                     sink.feed(header).await?;
                 }
 
                 let mut n_rows = 0;
-                for r in resultset {
-                    sink.feed(BackendMessage::DataRow {
-                        values: r,
-                        explicit_transfer_formats: result_transfer_formats.clone(),
-                    })
-                    .await?;
-                    n_rows += 1;
+                let mut error = None;
+                while let Some(r) = resultset.next().await {
+                    match r {
+                        Ok(r) => {
+                            sink.feed(BackendMessage::DataRow {
+                                values: r,
+                                explicit_transfer_formats: result_transfer_formats.clone(),
+                            })
+                            .await?;
+                            n_rows += 1;
+                        }
+                        Err(e) => {
+                            error = Some(e);
+                            break;
+                        }
+                    }
                 }
 
-                sink.feed(BackendMessage::CommandComplete {
-                    tag: CommandCompleteTag::Select(n_rows),
+                sink.feed(match error {
+                    Some(e) => make_error_response(e, ErrorSeverity::Error),
+                    None => BackendMessage::CommandComplete {
+                        tag: command_complete_tag(write_kind, n_rows),
+                    },
                 })
                 .await?;
 
@@ -88,10 +117,48 @@ where
 
                 sink.flush().await
             }
+
+            PortalRows {
+                rows,
+                result_transfer_formats,
+                write_kind,
+                suspended,
+            } => {
+                let n_rows = rows.len() as u64;
+                for r in rows {
+                    sink.feed(BackendMessage::DataRow {
+                        values: r,
+                        explicit_transfer_formats: result_transfer_formats.clone(),
+                    })
+                    .await?;
+                }
+
+                if suspended {
+                    sink.feed(BackendMessage::PortalSuspended).await?;
+                } else {
+                    sink.feed(BackendMessage::CommandComplete {
+                        tag: command_complete_tag(write_kind, n_rows),
+                    })
+                    .await?;
+                }
+
+                sink.flush().await
+            }
         }
     }
 }
 
+/// Returns the tag to send after `n_rows` rows, returned by either a select statement or a write
+/// statement of the given kind
+fn command_complete_tag(write_kind: Option<WriteKind>, n_rows: u64) -> CommandCompleteTag {
+    match write_kind {
+        None => CommandCompleteTag::Select(n_rows),
+        Some(WriteKind::Insert) => CommandCompleteTag::Insert(n_rows),
+        Some(WriteKind::Update) => CommandCompleteTag::Update(n_rows),
+        Some(WriteKind::Delete) => CommandCompleteTag::Delete(n_rows),
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -101,11 +168,18 @@ mod tests {
     use tokio_test::block_on;
 
     use super::*;
+    use crate::message::SqlState;
     use crate::value::Value as DataValue;
 
     #[derive(Clone, Debug, PartialEq)]
     struct Value(DataValue);
 
+    type Resultset = stream::Iter<std::vec::IntoIter<Result<Vec<Value>, Error>>>;
+
+    fn resultset(rows: Vec<Vec<Value>>) -> Resultset {
+        stream::iter(rows.into_iter().map(Ok).collect::<Vec<_>>())
+    }
+
     impl TryFrom<Value> for DataValue {
         type Error = Error;
 
@@ -116,7 +190,7 @@ mod tests {
 
     #[test]
     fn write_empty() {
-        let response = Response::<Vec<Value>, Vec<Vec<Value>>>::Empty;
+        let response = Response::<Vec<Value>, Resultset>::Empty;
         let validating_sink = sink::unfold(0, |i, _m: BackendMessage<Vec<Value>>| {
             async move {
                 // No messages are expected.
@@ -130,8 +204,7 @@ mod tests {
 
     #[test]
     fn write_message() {
-        let response =
-            Response::<Vec<Value>, Vec<Vec<Value>>>::Message(BackendMessage::BindComplete);
+        let response = Response::<Vec<Value>, Resultset>::Message(BackendMessage::BindComplete);
         let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
             async move {
                 match i {
@@ -148,7 +221,7 @@ mod tests {
 
     #[test]
     fn write_message2() {
-        let response = Response::<Vec<Value>, Vec<Vec<Value>>>::Messages(smallvec![
+        let response = Response::<Vec<Value>, Resultset>::Messages(smallvec![
             BackendMessage::BindComplete,
             BackendMessage::CloseComplete,
         ]);
@@ -169,11 +242,12 @@ mod tests {
 
     #[test]
     fn write_select_simple_empty() {
-        let response = Response::<Vec<Value>, Vec<Vec<Value>>>::Select {
+        let response = Response::<Vec<Value>, Resultset>::Select {
             header: None,
-            resultset: vec![],
+            resultset: resultset(vec![]),
             result_transfer_formats: None,
             trailer: None,
+            write_kind: None,
         };
         let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
             async move {
@@ -196,19 +270,20 @@ mod tests {
 
     #[test]
     fn write_select() {
-        let response = Response::<Vec<Value>, Vec<Vec<Value>>>::Select {
+        let response = Response::<Vec<Value>, Resultset>::Select {
             header: Some(BackendMessage::RowDescription {
                 field_descriptions: vec![],
             }),
-            resultset: vec![
+            resultset: resultset(vec![
                 vec![Value(DataValue::Int(5)), Value(DataValue::Double(0.123))],
                 vec![Value(DataValue::Int(99)), Value(DataValue::Double(0.456))],
-            ],
+            ]),
             result_transfer_formats: Some(Arc::new(vec![
                 TransferFormat::Text,
                 TransferFormat::Binary,
             ])),
             trailer: Some(BackendMessage::ready_for_query_idle()),
+            write_kind: None,
         };
         let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
             async move {
@@ -258,4 +333,111 @@ mod tests {
         futures::pin_mut!(validating_sink);
         assert!(block_on(response.write(&mut validating_sink)).is_ok());
     }
+
+    #[test]
+    fn write_returning() {
+        let response = Response::<Vec<Value>, Resultset>::Select {
+            header: None,
+            resultset: resultset(vec![vec![Value(DataValue::Int(5))]]),
+            result_transfer_formats: None,
+            trailer: None,
+            write_kind: Some(WriteKind::Update),
+        };
+        let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
+            async move {
+                match i {
+                    0 => assert_eq!(
+                        m,
+                        BackendMessage::DataRow {
+                            values: vec![Value(DataValue::Int(5))],
+                            explicit_transfer_formats: None
+                        }
+                    ),
+                    1 => assert_eq!(
+                        m,
+                        BackendMessage::CommandComplete {
+                            tag: CommandCompleteTag::Update(1)
+                        }
+                    ),
+                    // No further messages are expected.
+                    _ => assert!(false),
+                }
+                Ok::<_, EncodeError>(i + 1)
+            }
+        });
+        futures::pin_mut!(validating_sink);
+        assert!(block_on(response.write(&mut validating_sink)).is_ok());
+    }
+
+    #[test]
+    fn write_select_error() {
+        let response = Response::<Vec<Value>, Resultset>::Select {
+            header: None,
+            resultset: stream::iter(vec![
+                Ok(vec![Value(DataValue::Int(5))]),
+                Err(Error::InternalError("error requested".to_string())),
+                Ok(vec![Value(DataValue::Int(6))]),
+            ]),
+            result_transfer_formats: None,
+            trailer: Some(BackendMessage::ready_for_query_idle()),
+            write_kind: None,
+        };
+        let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
+            async move {
+                match i {
+                    0 => assert_eq!(
+                        m,
+                        BackendMessage::DataRow {
+                            values: vec![Value(DataValue::Int(5))],
+                            explicit_transfer_formats: None
+                        }
+                    ),
+                    // The error is sent in place of the rest of the rows and CommandComplete
+                    1 => assert_eq!(
+                        m,
+                        BackendMessage::ErrorResponse {
+                            severity: ErrorSeverity::Error,
+                            sqlstate: SqlState::INTERNAL_ERROR,
+                            message: "internal error: error requested".to_string()
+                        }
+                    ),
+                    2 => assert_eq!(m, BackendMessage::ready_for_query_idle()),
+                    // No further messages are expected.
+                    _ => assert!(false),
+                }
+                Ok::<_, EncodeError>(i + 1)
+            }
+        });
+        futures::pin_mut!(validating_sink);
+        assert!(block_on(response.write(&mut validating_sink)).is_ok());
+    }
+
+    #[test]
+    fn write_portal_rows_suspended() {
+        let response = Response::<Vec<Value>, Resultset>::PortalRows {
+            rows: vec![vec![Value(DataValue::Int(5))]],
+            result_transfer_formats: None,
+            write_kind: None,
+            suspended: true,
+        };
+        let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
+            async move {
+                match i {
+                    0 => assert_eq!(
+                        m,
+                        BackendMessage::DataRow {
+                            values: vec![Value(DataValue::Int(5))],
+                            explicit_transfer_formats: None
+                        }
+                    ),
+                    1 => assert_eq!(m, BackendMessage::PortalSuspended),
+                    // No further messages are expected.
+                    _ => assert!(false),
+                }
+                Ok::<_, EncodeError>(i + 1)
+            }
+        });
+        futures::pin_mut!(validating_sink);
+        assert!(block_on(response.write(&mut validating_sink)).is_ok());
+    }
 }
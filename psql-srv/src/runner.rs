@@ -10,30 +10,51 @@ use crate::{codec, Backend};
 pub struct Runner<B: Backend, C> {
     backend: B,
     channel: Channel<C, B::Row>,
-    protocol: Protocol,
+    protocol: Protocol<B::Row>,
 }
 
 impl<B: Backend, C: AsyncRead + AsyncWrite + Unpin> Runner<B, C> {
     /// A simple run loop. For each `FrontendMessage` received on `channel`, use `protocol` to
     /// generate a response. Then send the response. If an error occurs, use `protocol` to generate
-    /// an error response, then send the error response.
+    /// an error response, then send the error response. If the error occurred while the client was
+    /// authenticating, the connection is then closed.
     pub async fn run(backend: B, byte_channel: C) {
+        Self::run_on(backend, Channel::new(byte_channel), Protocol::new(), None).await
+    }
+
+    /// Run the loop described in [`Runner::run`] on an existing `channel` and `protocol`,
+    /// handling `first_message` (which has already been read from the channel) first, if given.
+    pub(crate) async fn run_on(
+        backend: B,
+        channel: Channel<C, B::Row>,
+        protocol: Protocol<B::Row>,
+        first_message: Option<Result<FrontendMessage, codec::DecodeError>>,
+    ) {
         let mut runner = Runner {
             backend,
-            channel: Channel::new(byte_channel),
-            protocol: Protocol::new(),
+            channel,
+            protocol,
         };
 
-        while let Some(message) = runner.channel.next().await {
+        let mut next = first_message;
+        if next.is_none() {
+            next = runner.channel.next().await;
+        }
+        while let Some(message) = next {
             match runner.handle_request(message).await {
                 Ok(_) => {}
                 Err(e) => {
+                    let fatal = runner.protocol.is_authenticating();
                     runner
                         .handle_error(e)
                         .await
                         .unwrap_or_else(|e| eprintln!("{}", e));
+                    if fatal {
+                        break;
+                    }
                 }
             };
+            next = runner.channel.next().await;
         }
     }
 
@@ -0,0 +1,430 @@
+//! The server side of the [SCRAM-SHA-256][rfc7677] SASL authentication mechanism, which allows a
+//! client to prove that it knows a user's password without sending the password to the server.
+//!
+//! An exchange consists of two round trips. The client sends a `client-first-message` containing a
+//! nonce, to which the server replies with a `server-first-message` containing the combined nonce,
+//! the salt and the iteration count used to salt the password. The client then sends a
+//! `client-final-message` containing a proof that it knows the salted password, to which the
+//! server replies with a `server-final-message` containing a signature proving that it knows the
+//! salted password as well.
+//!
+//! Over TLS, channel binding (the `SCRAM-SHA-256-PLUS` mechanism) is offered as well, which binds
+//! the exchange to the TLS connection it's carried out over, so that it can't be relayed by a
+//! man-in-the-middle. Like PostgreSQL, only the [`tls-server-end-point`][rfc5929] channel binding
+//! type is supported, which binds the exchange to the hash of the server's certificate. As required
+//! by [RFC 5802][rfc5802], a client that supports channel binding but didn't use it even though it
+//! was offered is rejected, since that means the offer was removed by a man-in-the-middle.
+//!
+//! [rfc7677]: https://datatracker.ietf.org/doc/html/rfc7677
+//! [rfc5929]: https://datatracker.ietf.org/doc/html/rfc5929#section-4
+//! [rfc5802]: https://datatracker.ietf.org/doc/html/rfc5802#section-6
+
+use hmac::{Hmac, Mac, NewMac};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+/// The name of the SASL mechanism implemented by this module
+pub(crate) const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+
+/// The name of the SASL mechanism implemented by this module, with channel binding
+pub(crate) const SCRAM_SHA_256_PLUS: &str = "SCRAM-SHA-256-PLUS";
+
+/// The number of random bytes in the server's part of the nonce
+const NONCE_LENGTH: usize = 18;
+
+/// The number of random bytes in the salt
+const SALT_LENGTH: usize = 16;
+
+/// The number of iterations used to salt passwords, which is the default used by PostgreSQL
+const ITERATIONS: u32 = 4096;
+
+/// Whether, and how, an exchange is bound to the connection it's carried out over
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ChannelBinding<'a> {
+    /// The connection doesn't support channel binding, so it wasn't offered to the client
+    Unsupported,
+    /// Channel binding was offered to the client, but it selected `SCRAM-SHA-256`
+    NotUsed,
+    /// The client selected `SCRAM-SHA-256-PLUS`, binding the exchange to the server certificate
+    /// with the given hash
+    TlsServerEndPoint(&'a [u8]),
+}
+
+/// A SCRAM-SHA-256 exchange with a client, after the `server-first-message` has been sent
+pub(crate) struct ScramExchange {
+    /// The user being authenticated
+    user: String,
+    /// The user's salted password, or `None` if the user doesn't exist. In that case the exchange
+    /// is still carried out (so that clients can't tell whether a user exists), but always fails.
+    salted_password: Option<[u8; 32]>,
+    /// The channel binding data the client must send in its `client-final-message`: the
+    /// `gs2-header` it sent at the start of its `client-first-message`, followed by the channel
+    /// binding data of the connection if it selected `SCRAM-SHA-256-PLUS`
+    channel_binding: Vec<u8>,
+    client_first_bare: String,
+    server_first: String,
+    /// The combined client and server nonce
+    nonce: String,
+}
+
+impl ScramExchange {
+    /// Start an exchange authenticating `user`, whose password is `password` (or who doesn't exist
+    /// if `password` is `None`), in response to the given `client-first-message`.
+    ///
+    /// Returns the exchange along with the `server-first-message` to send to the client.
+    pub(crate) fn start(
+        user: String,
+        password: Option<&[u8]>,
+        client_first: &[u8],
+        channel_binding: ChannelBinding,
+    ) -> Result<(Self, String), Error> {
+        let client_first = std::str::from_utf8(client_first)
+            .map_err(|_| malformed("client-first-message is not valid UTF-8"))?;
+
+        let mut parts = client_first.splitn(3, ',');
+        let cbind_flag = parts.next().unwrap_or_default();
+        let authzid = parts
+            .next()
+            .ok_or_else(|| malformed("missing authorization identity"))?;
+        let client_first_bare = parts
+            .next()
+            .ok_or_else(|| malformed("missing client-first-message-bare"))?;
+
+        let binding_data = match (cbind_flag, channel_binding) {
+            // The client doesn't support channel binding, or supports it but saw that it wasn't
+            // offered
+            ("n", ChannelBinding::Unsupported | ChannelBinding::NotUsed)
+            | ("y", ChannelBinding::Unsupported) => &[][..],
+            ("y", ChannelBinding::NotUsed) => {
+                return Err(Error::ProtocolViolation(
+                    "client supports channel binding, but didn't use it even though it was \
+                     offered"
+                        .to_owned(),
+                ))
+            }
+            ("n" | "y", ChannelBinding::TlsServerEndPoint(_)) => {
+                return Err(Error::ProtocolViolation(
+                    "client selected SCRAM-SHA-256-PLUS without channel binding".to_owned(),
+                ))
+            }
+            ("p=tls-server-end-point", ChannelBinding::TlsServerEndPoint(data)) => data,
+            (f, ChannelBinding::TlsServerEndPoint(_)) if f.starts_with("p=") => {
+                return Err(Error::Unsupported(format!(
+                    "unsupported SCRAM channel binding type: {}",
+                    &f[2..]
+                )))
+            }
+            (f, _) if f.starts_with("p=") => {
+                return Err(Error::ProtocolViolation(
+                    "client requires channel binding, but didn't select SCRAM-SHA-256-PLUS"
+                        .to_owned(),
+                ))
+            }
+            _ => return Err(malformed("invalid channel binding flag")),
+        };
+        if !authzid.is_empty() {
+            return Err(Error::Unsupported(
+                "SCRAM authorization identities are not supported".to_owned(),
+            ));
+        }
+
+        let mut attributes = client_first_bare.split(',');
+        // The username is ignored, since the user is the one named in the startup message
+        if !attributes.next().unwrap_or_default().starts_with("n=") {
+            return Err(malformed("missing username"));
+        }
+        let client_nonce = attributes
+            .next()
+            .and_then(|attr| attr.strip_prefix("r="))
+            .filter(|nonce| !nonce.is_empty())
+            .ok_or_else(|| malformed("missing nonce"))?;
+
+        let mut rng = rand::thread_rng();
+        let salt: [u8; SALT_LENGTH] = rng.gen();
+        let server_nonce: [u8; NONCE_LENGTH] = rng.gen();
+        let nonce = format!("{}{}", client_nonce, base64::encode(server_nonce));
+        let server_first = format!("r={},s={},i={}", nonce, base64::encode(salt), ITERATIONS);
+
+        Ok((
+            Self {
+                user,
+                salted_password: password.map(|password| salt_password(password, &salt)),
+                channel_binding: [format!("{},,", cbind_flag).as_bytes(), binding_data].concat(),
+                client_first_bare: client_first_bare.to_owned(),
+                server_first: server_first.clone(),
+                nonce,
+            },
+            server_first,
+        ))
+    }
+
+    /// Verify the proof in the given `client-final-message`, and if it's correct, return the
+    /// `server-final-message` to send to the client.
+    pub(crate) fn finish(self, client_final: &[u8]) -> Result<String, Error> {
+        let client_final = std::str::from_utf8(client_final)
+            .map_err(|_| malformed("client-final-message is not valid UTF-8"))?;
+        let (client_final_without_proof, proof) = client_final
+            .rsplit_once(",p=")
+            .ok_or_else(|| malformed("missing proof"))?;
+
+        let mut attributes = client_final_without_proof.split(',');
+        let channel_binding = attributes
+            .next()
+            .and_then(|attr| attr.strip_prefix("c="))
+            .ok_or_else(|| malformed("missing channel binding"))?;
+        if base64::decode(channel_binding).ok().as_deref() != Some(&self.channel_binding[..]) {
+            return Err(Error::ProtocolViolation(
+                "SCRAM channel binding check failed".to_owned(),
+            ));
+        }
+        if attributes.next().and_then(|attr| attr.strip_prefix("r=")) != Some(self.nonce.as_str()) {
+            return Err(malformed("nonce does not match"));
+        }
+        let proof = base64::decode(proof).map_err(|_| malformed("invalid proof"))?;
+
+        let salted_password = match self.salted_password {
+            Some(salted_password) => salted_password,
+            None => return Err(Error::AuthenticationFailure(self.user)),
+        };
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare, self.server_first, client_final_without_proof
+        );
+
+        let client_key = hmac(&salted_password, b"Client Key");
+        let stored_key = Sha256::digest(&client_key);
+        let client_signature = hmac(&stored_key, auth_message.as_bytes());
+        if proof.len() != client_signature.len() {
+            return Err(Error::AuthenticationFailure(self.user));
+        }
+        let recovered_client_key = proof
+            .iter()
+            .zip(client_signature)
+            .map(|(p, s)| p ^ s)
+            .collect::<Vec<_>>();
+        // Compare every byte, so that the time taken doesn't depend on how much of the proof was
+        // correct
+        let difference = Sha256::digest(&recovered_client_key)
+            .iter()
+            .zip(stored_key.iter())
+            .fold(0, |acc, (a, b)| acc | (a ^ b));
+        if difference != 0 {
+            return Err(Error::AuthenticationFailure(self.user));
+        }
+
+        let server_key = hmac(&salted_password, b"Server Key");
+        let server_signature = hmac(&server_key, auth_message.as_bytes());
+        Ok(format!("v={}", base64::encode(server_signature)))
+    }
+}
+
+fn malformed(reason: &str) -> Error {
+    Error::ProtocolViolation(format!("malformed SCRAM message: {}", reason))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    let mut res = [0; 32];
+    res.copy_from_slice(&mac.finalize().into_bytes());
+    res
+}
+
+/// Compute `SaltedPassword := Hi(Normalize(password), salt, i)`, where `Hi` is PBKDF2 with
+/// HMAC-SHA-256.
+fn salt_password(password: &[u8], salt: &[u8]) -> [u8; 32] {
+    // Like PostgreSQL, fall back to the raw password if it can't be normalized with SASLprep
+    let normalized = std::str::from_utf8(password)
+        .ok()
+        .and_then(|password| stringprep::saslprep(password).ok());
+    let password = normalized.as_deref().map_or(password, str::as_bytes);
+
+    let mut u = hmac(password, &[salt, &1u32.to_be_bytes()].concat());
+    let mut res = u;
+    for _ in 1..ITERATIONS {
+        u = hmac(password, &u);
+        for (r, u) in res.iter_mut().zip(u) {
+            *r ^= u;
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the `client-final-message` sent by a client that knows `password`, in response to
+    /// `server_first`
+    fn client_final(
+        password: &[u8],
+        client_first_bare: &str,
+        server_first: &str,
+        channel_binding: &[u8],
+    ) -> String {
+        let mut attributes = server_first.split(',');
+        let nonce = attributes.next().unwrap().strip_prefix("r=").unwrap();
+        let salt = base64::decode(attributes.next().unwrap().strip_prefix("s=").unwrap()).unwrap();
+        let client_final_without_proof =
+            format!("c={},r={}", base64::encode(channel_binding), nonce);
+
+        let client_key = hmac(&salt_password(password, &salt), b"Client Key");
+        let auth_message = format!(
+            "{},{},{}",
+            client_first_bare, server_first, client_final_without_proof
+        );
+        let client_signature = hmac(&Sha256::digest(&client_key), auth_message.as_bytes());
+        let proof = client_key
+            .iter()
+            .zip(client_signature)
+            .map(|(k, s)| k ^ s)
+            .collect::<Vec<_>>();
+        format!("{},p={}", client_final_without_proof, base64::encode(proof))
+    }
+
+    #[test]
+    fn correct_password() {
+        let (exchange, server_first) = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"n,,n=,r=abcdef",
+            ChannelBinding::NotUsed,
+        )
+        .unwrap();
+        let client_final = client_final(b"password", "n=,r=abcdef", &server_first, b"n,,");
+        assert!(exchange.finish(client_final.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn incorrect_password() {
+        let (exchange, server_first) = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"n,,n=,r=abcdef",
+            ChannelBinding::NotUsed,
+        )
+        .unwrap();
+        let client_final = client_final(b"wrong", "n=,r=abcdef", &server_first, b"n,,");
+        let res = exchange.finish(client_final.as_bytes());
+        assert!(matches!(res, Err(Error::AuthenticationFailure(user)) if user == "user"));
+    }
+
+    #[test]
+    fn tls_server_end_point_channel_binding() {
+        let (exchange, server_first) = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"p=tls-server-end-point,,n=,r=abcdef",
+            ChannelBinding::TlsServerEndPoint(b"certificate hash"),
+        )
+        .unwrap();
+        let client_final = client_final(
+            b"password",
+            "n=,r=abcdef",
+            &server_first,
+            b"p=tls-server-end-point,,certificate hash",
+        );
+        assert!(exchange.finish(client_final.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn rejects_mismatched_channel_binding() {
+        let (exchange, server_first) = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"p=tls-server-end-point,,n=,r=abcdef",
+            ChannelBinding::TlsServerEndPoint(b"certificate hash"),
+        )
+        .unwrap();
+        // The client sees a different certificate, eg because it's connected to a
+        // man-in-the-middle
+        let client_final = client_final(
+            b"password",
+            "n=,r=abcdef",
+            &server_first,
+            b"p=tls-server-end-point,,other certificate hash",
+        );
+        let res = exchange.finish(client_final.as_bytes());
+        assert!(matches!(res, Err(Error::ProtocolViolation(_))));
+    }
+
+    #[test]
+    fn rejects_unsupported_channel_binding_type() {
+        let res = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"p=tls-unique,,n=,r=abcdef",
+            ChannelBinding::TlsServerEndPoint(b"certificate hash"),
+        );
+        assert!(matches!(res, Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn rejects_channel_binding_downgrade() {
+        // The client supports channel binding, but thinks the server doesn't even though it was
+        // offered
+        let res = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"y,,n=,r=abcdef",
+            ChannelBinding::NotUsed,
+        );
+        assert!(matches!(res, Err(Error::ProtocolViolation(_))));
+    }
+
+    #[test]
+    fn accepts_channel_binding_support_when_not_offered() {
+        let res = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"y,,n=,r=abcdef",
+            ChannelBinding::Unsupported,
+        );
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn rejects_required_channel_binding() {
+        let res = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"p=tls-server-end-point,,n=,r=abcdef",
+            ChannelBinding::Unsupported,
+        );
+        assert!(matches!(res, Err(Error::ProtocolViolation(_))));
+    }
+
+    #[test]
+    fn rejects_mismatched_nonce() {
+        let (exchange, server_first) = ScramExchange::start(
+            "user".to_owned(),
+            Some(b"password"),
+            b"n,,n=,r=abcdef",
+            ChannelBinding::Unsupported,
+        )
+        .unwrap();
+        assert!(server_first.starts_with("r=abcdef"));
+        let res = exchange.finish(b"c=biws,r=abcdef,p=AAAA");
+        assert!(matches!(res, Err(Error::ProtocolViolation(_))));
+    }
+
+    #[test]
+    fn unknown_user_fails() {
+        let (exchange, server_first) = ScramExchange::start(
+            "user".to_owned(),
+            None,
+            b"n,,n=,r=abcdef",
+            ChannelBinding::Unsupported,
+        )
+        .unwrap();
+        let nonce = server_first
+            .split(',')
+            .next()
+            .and_then(|attr| attr.strip_prefix("r="))
+            .unwrap();
+        let res = exchange.finish(format!("c=biws,r={},p=AAAA", nonce).as_bytes());
+        assert!(matches!(res, Err(Error::AuthenticationFailure(user)) if user == "user"));
+    }
+}
@@ -0,0 +1,137 @@
+//! Support for [TLS connections][docs].
+//!
+//! A client that wants to use TLS sends an `SSLRequest` message before its startup message. If the
+//! server replies with `S`, the client starts the TLS handshake, and the rest of the connection,
+//! starting with the client's startup message, is carried out over TLS.
+//!
+//! [docs]: https://www.postgresql.org/docs/13/protocol-flow.html#id-1.10.5.7.11
+
+use std::pin::Pin;
+
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::ssl::{Ssl, SslAcceptor, SslRef};
+use openssl::x509::X509VerifyResult;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_openssl::SslStream;
+
+use crate::channel::Channel;
+use crate::error::Error;
+use crate::message::{BackendMessage, ErrorSeverity, FrontendMessage};
+use crate::protocol::{make_error_response, Protocol};
+use crate::response::Response;
+use crate::runner::Runner;
+use crate::Backend;
+
+/// Run `backend` on `byte_channel`, offering TLS to the client using `acceptor`. See
+/// [`crate::run_backend_with_tls`].
+pub(crate) async fn run<B, C>(
+    backend: B,
+    byte_channel: C,
+    acceptor: &SslAcceptor,
+    require_tls: bool,
+) where
+    B: Backend,
+    C: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut channel = Channel::<C, B::Row>::new(byte_channel);
+    let first_message = match channel.next().await {
+        Some(message) => message,
+        None => return,
+    };
+
+    if !matches!(first_message, Ok(FrontendMessage::SSLRequest)) {
+        if require_tls {
+            let response: Response<B::Row, B::Resultset> = Response::Message(make_error_response(
+                Error::InsecureTransport,
+                ErrorSeverity::Fatal,
+            ));
+            channel
+                .send(response)
+                .await
+                .unwrap_or_else(|e| eprintln!("{}", e));
+            return;
+        }
+        Runner::run_on(backend, channel, Protocol::new(), Some(first_message)).await;
+        return;
+    }
+
+    let response: Response<B::Row, B::Resultset> =
+        Response::Message(BackendMessage::ssl_response_s());
+    if let Err(e) = channel.send(response).await {
+        eprintln!("{}", e);
+        return;
+    }
+    // The client waits for our response before starting the TLS handshake, so the channel can't
+    // have buffered anything past the `SSLRequest`
+    let stream = match accept(acceptor, channel.into_inner()).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let mut protocol = Protocol::new();
+    if let Some(user) = client_identity(stream.ssl()) {
+        protocol.set_tls_client_identity(user);
+    }
+    if let Some(data) = server_end_point(stream.ssl()) {
+        protocol.set_tls_server_end_point(data);
+    }
+    Runner::run_on(backend, Channel::new(stream), protocol, None).await;
+}
+
+/// Complete the TLS handshake with a client that has been sent an `S` in response to its
+/// `SSLRequest`
+async fn accept<S>(acceptor: &SslAcceptor, stream: S) -> Result<SslStream<S>, Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let ssl = Ssl::new(acceptor.context()).map_err(|e| Error::InternalError(e.to_string()))?;
+    let mut stream =
+        SslStream::new(ssl, stream).map_err(|e| Error::InternalError(e.to_string()))?;
+    Pin::new(&mut stream)
+        .accept()
+        .await
+        .map_err(|e| Error::ProtocolViolation(format!("TLS handshake failed: {}", e)))?;
+    Ok(stream)
+}
+
+/// Returns the name of the user identified by the certificate presented by the client, if it
+/// presented one and it was verified: the common name of the certificate's subject.
+fn client_identity(ssl: &SslRef) -> Option<String> {
+    if ssl.verify_result() != X509VerifyResult::OK {
+        return None;
+    }
+    let certificate = ssl.peer_certificate()?;
+    let common_name = certificate
+        .subject_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()?;
+    common_name
+        .data()
+        .as_utf8()
+        .ok()
+        .map(|name| name.to_string())
+}
+
+/// Returns the [`tls-server-end-point`][rfc5929] channel binding data of the connection: the hash
+/// of the server's certificate, using the hash function of the certificate's signature algorithm,
+/// or SHA-256 if that's MD5 or SHA-1. Returns `None` if the signature algorithm doesn't use a hash
+/// function, in which case channel binding isn't offered.
+///
+/// [rfc5929]: https://datatracker.ietf.org/doc/html/rfc5929#section-4.1
+fn server_end_point(ssl: &SslRef) -> Option<Vec<u8>> {
+    let certificate = ssl.certificate()?;
+    let algorithms = certificate
+        .signature_algorithm()
+        .object()
+        .nid()
+        .signature_algorithms()?;
+    let digest = match algorithms.digest {
+        Nid::MD5 | Nid::SHA1 => MessageDigest::sha256(),
+        nid => MessageDigest::from_nid(nid)?,
+    };
+    certificate.digest(digest).ok().map(|hash| hash.to_vec())
+}
@@ -3,6 +3,7 @@ use std::net::IpAddr;
 use bit_vec::BitVec;
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
 use eui48::MacAddress;
+use postgres_types::Type;
 use readyset_data::Text;
 use rust_decimal::Decimal;
 use uuid::Uuid;
@@ -35,4 +36,6 @@ pub enum Value {
     Jsonb(serde_json::Value),
     Bit(BitVec),
     VarBit(BitVec),
+    /// A one-dimensional array of values of the given element type
+    Array(Vec<Value>, Type),
 }
@@ -0,0 +1,103 @@
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+use futures::stream;
+use postgres::error::SqlState;
+use postgres::NoTls;
+use psql_srv::{run_backend, Backend, Error, PrepareResponse, QueryResponse};
+use tokio::net::TcpListener;
+
+struct Value(psql_srv::Value);
+
+impl TryFrom<Value> for psql_srv::Value {
+    type Error = Error;
+
+    fn try_from(v: Value) -> Result<Self, Self::Error> {
+        Ok(v.0)
+    }
+}
+
+struct AuthenticatingBackend;
+
+#[async_trait]
+impl Backend for AuthenticatingBackend {
+    type Value = Value;
+    type Row = Vec<Value>;
+    type Resultset = stream::Iter<std::vec::IntoIter<Result<Self::Row, Error>>>;
+
+    const SERVER_VERSION: &'static str = "example";
+
+    async fn on_init(&mut self, _database: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn on_query(&mut self, _query: &str) -> Result<QueryResponse<Self::Resultset>, Error> {
+        Ok(QueryResponse::Select {
+            schema: vec![],
+            resultset: stream::iter(vec![]),
+        })
+    }
+
+    async fn on_prepare(&mut self, _query: &str) -> Result<PrepareResponse, Error> {
+        Err(Error::Unsupported("prepare".to_owned()))
+    }
+
+    async fn on_execute(
+        &mut self,
+        _statement_id: u32,
+        _params: &[psql_srv::Value],
+    ) -> Result<QueryResponse<Self::Resultset>, Error> {
+        Err(Error::Unsupported("execute".to_owned()))
+    }
+
+    async fn on_close(&mut self, _statement_id: u32) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn password_for_username(&self, username: &str) -> Option<Vec<u8>> {
+        (username == "user").then(|| b"pencil".to_vec())
+    }
+
+    fn require_authentication(&self) -> bool {
+        true
+    }
+}
+
+/// Start a server for a single connection, and connect to it as `user` with `password`
+async fn connect(user: &str, password: &str) -> Result<tokio_postgres::Client, postgres::Error> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        run_backend(AuthenticatingBackend, socket).await;
+    });
+
+    let (client, conn) = tokio_postgres::Config::default()
+        .host("localhost")
+        .port(port)
+        .dbname("noria")
+        .user(user)
+        .password(password)
+        .connect(NoTls)
+        .await?;
+    tokio::spawn(conn);
+    Ok(client)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn scram_sha_256() {
+    let client = connect("user", "pencil").await.unwrap();
+    client.simple_query("SELECT 1").await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wrong_password() {
+    let error = connect("user", "not pencil").await.err().unwrap();
+    assert_eq!(error.code(), Some(&SqlState::INVALID_PASSWORD));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn unknown_user() {
+    let error = connect("someone else", "pencil").await.err().unwrap();
+    assert_eq!(error.code(), Some(&SqlState::INVALID_PASSWORD));
+}
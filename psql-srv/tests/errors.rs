@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 
 use async_trait::async_trait;
-use futures::Future;
+use futures::{stream, Future};
 use postgres::NoTls;
 use postgres_types::Type;
 use psql_srv::{run_backend, Backend, Column, Error, PrepareResponse, QueryResponse};
@@ -26,6 +26,7 @@ enum ErrorPosition {
     Prepare,
     Execute,
     Serialize,
+    Resultset,
 }
 
 #[derive(Clone, Copy)]
@@ -35,7 +36,7 @@ struct ErrorBackend(ErrorPosition);
 impl Backend for ErrorBackend {
     type Value = Value;
     type Row = Vec<Value>;
-    type Resultset = Vec<Self::Row>;
+    type Resultset = stream::Iter<std::vec::IntoIter<Result<Self::Row, Error>>>;
 
     const SERVER_VERSION: &'static str = "example";
 
@@ -49,7 +50,7 @@ impl Backend for ErrorBackend {
         } else {
             Ok(QueryResponse::Select {
                 schema: vec![],
-                resultset: vec![],
+                resultset: stream::iter(vec![]),
             })
         }
     }
@@ -81,11 +82,20 @@ impl Backend for ErrorBackend {
                     name: "x".to_owned(),
                     col_type: Type::BOOL,
                 }],
-                resultset: vec![vec![Value(Err(Error::InternalError("factory".to_owned())))]],
+                resultset: stream::iter(vec![Ok(vec![Value(Err(Error::InternalError(
+                    "factory".to_owned(),
+                )))])]),
+            }),
+            ErrorPosition::Resultset => Ok(QueryResponse::Select {
+                schema: vec![Column {
+                    name: "x".to_owned(),
+                    col_type: Type::BOOL,
+                }],
+                resultset: stream::iter(vec![Err(Error::InternalError("in a".to_owned()))]),
             }),
             _ => Ok(QueryResponse::Select {
                 schema: vec![],
-                resultset: vec![],
+                resultset: stream::iter(vec![]),
             }),
         }
     }
@@ -214,3 +224,16 @@ async fn serialize_error() {
     })
     .await
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn resultset_error() {
+    error_test(ErrorPosition::Resultset, |client| async move {
+        let res = client.query("SELECT 1 as one", &[]).await;
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().as_db_error().unwrap().message(),
+            "internal error: in a"
+        );
+    })
+    .await
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::io;
 use std::marker::Send;
 use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex, RwLock};
@@ -20,31 +21,43 @@ use maplit::hashmap;
 use metrics::SharedString;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use nom_sql::{Dialect, SelectStatement, SqlQuery};
-use readyset::consensus::{AuthorityControl, AuthorityType, ConsulAuthority};
+use readyset::consensus::{
+    Authority, AuthorityControl, AuthorityType, AuthorityWorkerHeartbeatResponse,
+};
 use readyset::metrics::recorded;
 use readyset::{ControllerHandle, ReadySetError};
 use readyset_client::backend::noria_connector::{NoriaConnector, ReadBehavior};
-use readyset_client::backend::MigrationMode;
+use readyset_client::backend::{MigrationMode, DEFAULT_PARSED_QUERY_CACHE_SIZE};
+use readyset_client::cache_file::create_caches_from_file;
 use readyset_client::http_router::NoriaAdapterHttpRouter;
 use readyset_client::migration_handler::MigrationHandler;
 use readyset_client::outputs_synchronizer::OutputsSynchronizer;
-use readyset_client::query_status_cache::{MigrationStyle, QueryStatusCache};
+use readyset_client::query_status_cache::{CachingPolicy, MigrationStyle, QueryStatusCache};
 use readyset_client::rewrite::anonymize_literals;
-use readyset_client::{Backend, BackendBuilder, QueryHandler, UpstreamDatabase};
+use readyset_client::slow_log::{write_slow_log, SlowLog};
+use readyset_client::{
+    Backend, BackendBuilder, PooledUpstream, QueryHandler, UpstreamDatabase, UpstreamPool,
+    UpstreamPoolConfig,
+};
 use readyset_client_metrics::QueryExecutionEvent;
 use readyset_dataflow::Readers;
 use readyset_server::worker::readers::{Ack, BlockingRead, ReadRequestHandler, READERS};
 use readyset_server::Builder;
 use stream_cancel::Valve;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::UdpSocket;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::time::timeout;
 use tokio::{net, select};
-use tokio_stream::wrappers::TcpListenerStream;
+use tokio_stream::wrappers::{TcpListenerStream, UnixListenerStream};
 use tracing::{debug, debug_span, error, info, info_span, span, warn, Level};
 use tracing_futures::Instrument;
 
+pub use crate::tls::{ConnectionTls, TlsOptions};
+
+mod tls;
+
 const RETRY_TIMEOUT: Duration = Duration::from_micros(100);
 
 const REGISTER_HTTP_INTERVAL: Duration = Duration::from_secs(20);
@@ -59,14 +72,31 @@ pub trait ConnectionHandler {
     type UpstreamDatabase: UpstreamDatabase;
     type Handler: QueryHandler;
 
+    /// Process a new client connection on `stream`, using TLS as described by `tls` if it's set
     async fn process_connection(
         &mut self,
         stream: net::TcpStream,
-        backend: Backend<Self::UpstreamDatabase, Self::Handler>,
+        backend: Backend<PooledUpstream<Self::UpstreamDatabase>, Self::Handler>,
+        tls: Option<ConnectionTls>,
+    );
+
+    /// Process a new client connection on a Unix domain socket
+    async fn process_unix_connection(
+        &mut self,
+        stream: net::UnixStream,
+        backend: Backend<PooledUpstream<Self::UpstreamDatabase>, Self::Handler>,
     );
 
     /// Return an immediate error to a newly-established connection, then immediately disconnect
-    async fn immediate_error(self, stream: net::TcpStream, error_message: String);
+    async fn immediate_error<S>(self, stream: S, error_message: String)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+}
+
+/// A client connection accepted by one of the adapter's listeners
+enum ClientConnection {
+    Tcp(net::TcpStream),
+    Unix(net::UnixStream),
 }
 
 /// Represents which database interface is being adapted to communicate with ReadySet.
@@ -134,23 +164,51 @@ pub struct Options {
     #[clap(long, short = 'a', env = "LISTEN_ADDRESS", parse(try_from_str))]
     address: Option<SocketAddr>,
 
+    /// Path of a Unix domain socket to listen on for client connections, in addition to
+    /// listening on TCP. Any existing socket at this path is replaced.
+    #[clap(long, env = "UNIX_SOCKET")]
+    unix_socket: Option<PathBuf>,
+
+    /// Comma-separated list of the IDs of Unix users, other than the user the adapter runs as,
+    /// who are allowed to connect to --unix-socket. Connections from any other user are refused.
+    #[clap(
+        long,
+        env = "UNIX_SOCKET_ALLOWED_UIDS",
+        requires = "unix-socket",
+        use_delimiter = true
+    )]
+    unix_socket_allowed_uids: Vec<u32>,
+
     /// ReadySet deployment ID to attach to
     #[clap(long, env = "NORIA_DEPLOYMENT", forbid_empty_values = true)]
     deployment: String,
 
-    /// Authority connection string.
+    /// Authority connection string. For the kubernetes authority, this is the URL of the
+    /// Kubernetes API server if it starts with http:// or https://, and is otherwise ignored in
+    /// favor of the in-cluster API server.
     // TODO(justin): The default_value should depend on the value of authority.
     #[clap(long, env = "AUTHORITY_ADDRESS", default_value = "127.0.0.1:8500")]
     authority_address: String,
 
-    /// The authority to use. Possible values: zookeeper, consul.
-    #[clap(long, env = "AUTHORITY", default_value = "consul", possible_values = &["consul", "zookeeper"])]
+    /// The authority to use. Possible values: zookeeper, consul, kubernetes.
+    #[clap(long, env = "AUTHORITY", default_value = "consul", possible_values = &["consul", "zookeeper", "kubernetes"])]
     authority: AuthorityType,
 
-    /// Log slow queries (> 5ms)
+    /// Log queries which take longer than --slow-query-threshold to execute as warnings
     #[clap(long)]
     log_slow: bool,
 
+    /// Threshold, in milliseconds, above which a query's end-to-end latency counts as slow for
+    /// --log-slow and --slow-query-log-file
+    #[clap(long, env = "SLOW_QUERY_THRESHOLD_MS", default_value = "5")]
+    slow_query_threshold: u64,
+
+    /// Append slow queries to this file as JSON lines, recording whether their time went to
+    /// filling cache misses, re-filling evicted keys, or falling back to the upstream database.
+    /// Implies --log-slow.
+    #[clap(long, env = "SLOW_QUERY_LOG_FILE", parse(from_os_str))]
+    slow_query_log_file: Option<PathBuf>,
+
     /// Don't require authentication for any client connections
     #[clap(long, env = "ALLOW_UNAUTHENTICATED_CONNECTIONS")]
     allow_unauthenticated_connections: bool,
@@ -195,10 +253,47 @@ pub struct Options {
     password: Option<RedactedString>,
 
     /// URL for the upstream database to connect to. Should include username and password if
-    /// necessary
+    /// necessary. For PostgreSQL, `channel_binding=require` can be passed to require SCRAM channel
+    /// binding when connecting over TLS.
+    ///
+    /// The server's TLS certificate can be verified by passing `sslmode=verify-ca` or
+    /// `sslmode=verify-full` for PostgreSQL, or `ssl-mode=VERIFY_CA` or
+    /// `ssl-mode=VERIFY_IDENTITY` for MySQL. Additional CA certificates to trust can be given with
+    /// `sslrootcert` (PostgreSQL) or `ssl-ca` (MySQL).
     #[clap(long, env = "UPSTREAM_DB_URL")]
     upstream_db_url: Option<RedactedString>,
 
+    /// Number of connections to the upstream database to keep open, even while they're idle.
+    ///
+    /// Connections to the upstream database are shared between client connections, and are only
+    /// held by a client connection while it's running a statement, in a transaction, or after
+    /// it's changed the state of its session (for example with `SET`).
+    #[clap(long, env = "UPSTREAM_POOL_MIN_SIZE", default_value = "1")]
+    upstream_pool_min_size: usize,
+
+    /// Maximum number of connections to the upstream database to have open at once
+    #[clap(long, env = "UPSTREAM_POOL_MAX_SIZE", default_value = "64")]
+    upstream_pool_max_size: usize,
+
+    /// Time, in seconds, after which idle connections to the upstream database are closed, if
+    /// more than --upstream-pool-min-size connections are open
+    #[clap(long, env = "UPSTREAM_POOL_IDLE_TIMEOUT", default_value = "300")]
+    upstream_pool_idle_timeout: u64,
+
+    /// Interval, in seconds, at which idle connections to the upstream database are checked to
+    /// still be usable
+    #[clap(
+        long,
+        env = "UPSTREAM_POOL_HEALTH_CHECK_INTERVAL",
+        default_value = "30"
+    )]
+    upstream_pool_health_check_interval: u64,
+
+    /// Time, in seconds, to wait for a connection to the upstream database when
+    /// --upstream-pool-max-size connections are already in use
+    #[clap(long, env = "UPSTREAM_POOL_CHECKOUT_TIMEOUT", default_value = "30")]
+    upstream_pool_checkout_timeout: u64,
+
     /// The region the worker is hosted in. Required to route view requests to specific regions.
     #[clap(long, env = "NORIA_REGION")]
     region: Option<String>,
@@ -216,8 +311,8 @@ pub struct Options {
     #[clap(long, hide = true, env = "QUERY_LOG_AD_HOC", requires = "query-log")]
     query_log_ad_hoc: bool,
 
-    /// Use the AWS EC2 metadata service to determine the external address of this ReadySet adapter's
-    /// http endpoint.
+    /// Use the AWS EC2 metadata service to determine the external address of this ReadySet
+    /// adapter's http endpoint.
     #[clap(long)]
     use_aws_external_address: bool,
 
@@ -257,6 +352,29 @@ pub struct Options {
     #[clap(long, env = "EXPLICIT_MIGRATIONS", conflicts_with = "async-migrations")]
     explicit_migrations: bool,
 
+    /// Never cache queries matching this LIKE pattern, in which `%` matches any string and `_`
+    /// matches any single character. Patterns are matched case-insensitively against the text of
+    /// queries as shown by `SHOW PROXIED QUERIES`. Queries are still cached if they're allowed by
+    /// --cache-allow-query-ids or with `CREATE CACHE`. May be specified multiple times.
+    #[clap(long, multiple_occurrences = true, requires = "upstream-db-url")]
+    cache_deny_pattern: Vec<String>,
+
+    /// Only cache queries which have been explicitly allowed, by --cache-allow-query-ids or with
+    /// `CREATE CACHE`. All other queries are proxied to the upstream database.
+    #[clap(long, env = "CACHE_ALLOW_LIST", requires = "upstream-db-url")]
+    cache_allow_list: bool,
+
+    /// Comma-separated list of the IDs of queries, as shown by `SHOW PROXIED QUERIES`, which may
+    /// always be cached, even if --cache-allow-list or --cache-deny-pattern would prevent it
+    #[clap(long, env = "CACHE_ALLOW_QUERY_IDS", use_delimiter = true)]
+    cache_allow_query_ids: Vec<String>,
+
+    /// Path to a file of `CREATE CACHE` statements, all of which are created in a single
+    /// migration when the adapter starts. If any of the caches can't be created, none of them are,
+    /// and the adapter exits after logging why each failing cache couldn't be created.
+    #[clap(long, env = "CACHE_FILE", parse(from_os_str))]
+    cache_file: Option<PathBuf>,
+
     // TODO(DAN): require explicit migrations
     /// Specifies the polling interval in seconds for requesting outputs from the Leader.
     #[clap(long, env = "OUTPUTS_POLLING_INTERVAL", default_value = "300")]
@@ -299,10 +417,77 @@ pub struct Options {
     #[clap(long, env = "NON_BLOCKING_READS")]
     non_blocking_reads: bool,
 
-    /// Run ReadySet in standalone mode, running a readyset-server and readyset-mysql instance within
-    /// this adapter.
+    /// Run ReadySet in standalone mode, running a readyset-server and readyset-mysql instance
+    /// within this adapter.
     #[clap(long, env = "STANDALONE")]
     standalone: bool,
+
+    /// Apply writes to ReadySet's base tables as well as to the upstream database, so that
+    /// subsequent reads of cached queries reflect them without waiting for replication. Writes are
+    /// only applied to tables with a primary key, outside of transactions. The readyset-server
+    /// must also be run with `--write-through`.
+    #[clap(long, env = "WRITE_THROUGH", requires = "upstream-db-url")]
+    write_through: bool,
+
+    /// Make reads of cached queries on a connection wait until ReadySet has replicated the writes
+    /// previously made on that connection, so that each connection reads its own writes. Reads
+    /// that can't be served consistently within --read-your-writes-timeout-ms are proxied to the
+    /// upstream database.
+    #[clap(long, env = "READ_YOUR_WRITES", requires = "upstream-db-url")]
+    read_your_writes: bool,
+
+    /// The maximum time, in milliseconds, that a read waits for ReadySet to replicate the
+    /// connection's writes when --read-your-writes is enabled, before being proxied upstream
+    #[clap(long, env = "READ_YOUR_WRITES_TIMEOUT_MS", default_value = "1000")]
+    read_your_writes_timeout_ms: u64,
+
+    /// Serve reads inside a transaction from ReadySet until the transaction executes anything
+    /// other than a SELECT, with every read waiting for ReadySet to replicate up to a watermark
+    /// recorded at the transaction's first read. Reads that can't reach the watermark within
+    /// --consistent-transaction-reads-timeout-ms are proxied to the upstream database.
+    #[clap(
+        long,
+        env = "CONSISTENT_TRANSACTION_READS",
+        requires = "upstream-db-url"
+    )]
+    consistent_transaction_reads: bool,
+
+    /// The maximum time, in milliseconds, that a read inside a transaction waits for ReadySet to
+    /// reach the transaction's watermark when --consistent-transaction-reads is enabled, before
+    /// being proxied upstream
+    #[clap(
+        long,
+        env = "CONSISTENT_TRANSACTION_READS_TIMEOUT_MS",
+        default_value = "1000"
+    )]
+    consistent_transaction_reads_timeout_ms: u64,
+
+    /// The maximum time, in milliseconds, that a read of a cached query may take on ReadySet -
+    /// usually because it's waiting for cache misses to be filled - before it's abandoned and
+    /// proxied to the upstream database instead. If unset, reads wait for cache misses to be
+    /// filled.
+    #[clap(long, env = "READ_TIMEOUT_MS", requires = "upstream-db-url")]
+    read_timeout_ms: Option<u64>,
+
+    /// If set, the results of cached queries are read from ReadySet and sent to the client a page
+    /// of at most this many rows at a time, so that very large result sets are never held in the
+    /// adapter's memory all at once. Results read from the readers of a standalone adapter are
+    /// never paged.
+    #[clap(long, env = "RESULT_PAGE_SIZE")]
+    result_page_size: Option<usize>,
+
+    /// The maximum number of parsed queries to keep for each client connection, so that queries
+    /// which are run repeatedly don't have to be parsed again. Set to 0 to disable caching parsed
+    /// queries.
+    #[clap(
+        long,
+        env = "PARSED_QUERY_CACHE_SIZE",
+        default_value_t = DEFAULT_PARSED_QUERY_CACHE_SIZE
+    )]
+    parsed_query_cache_size: usize,
+
+    #[clap(flatten)]
+    tls: TlsOptions,
 }
 
 impl<H> NoriaAdapter<H>
@@ -335,13 +520,32 @@ where
             )
         }
 
+        let tls = tls::Tls::new(options.tls.clone())?;
+        if let Some(tls) = &tls {
+            rt.handle().spawn(tls.clone().reload_on_change());
+        }
+
         let listen_address = options.address.unwrap_or(self.default_address);
         let listener = rt.block_on(tokio::net::TcpListener::bind(&listen_address))?;
 
         info!(%listen_address, "Listening for new connections");
 
+        let unix_listener = match &options.unix_socket {
+            Some(path) => {
+                let _guard = rt.enter();
+                let unix_listener = bind_unix_socket(path)?;
+                info!(path = %path.display(), "Listening for new connections on Unix socket");
+                Some(unix_listener)
+            }
+            None => None,
+        };
+        let mut allowed_unix_uids = options.unix_socket_allowed_uids.clone();
+        // SAFETY: getuid has no preconditions, and always succeeds
+        allowed_unix_uids.push(unsafe { libc::getuid() });
+
         let auto_increments: Arc<RwLock<HashMap<String, AtomicUsize>>> = Arc::default();
         let query_cache: Arc<RwLock<HashMap<SelectStatement, String>>> = Arc::default();
+        let schema_generation: Arc<AtomicUsize> = Arc::default();
 
         let rs_connect = span!(
             Level::INFO,
@@ -377,8 +581,17 @@ where
             let _guard = rt.enter();
             tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()).unwrap()
         };
+        let unix_connections = match unix_listener {
+            Some(unix_listener) => UnixListenerStream::new(unix_listener)
+                .map(|r| r.map(ClientConnection::Unix))
+                .boxed(),
+            None => futures_util::stream::pending().boxed(),
+        };
         let mut listener = Box::pin(futures_util::stream::select(
-            TcpListenerStream::new(listener),
+            futures_util::stream::select(
+                TcpListenerStream::new(listener).map(|r| r.map(ClientConnection::Tcp)),
+                unix_connections,
+            ),
             futures_util::stream::select(
                 ctrlc
                     .map(|r| {
@@ -433,6 +646,32 @@ where
             None
         };
 
+        let slowlog = if options.log_slow || options.slow_query_log_file.is_some() {
+            let slowlog = SlowLog::new(Duration::from_millis(options.slow_query_threshold));
+            match &options.slow_query_log_file {
+                Some(path) => {
+                    let file = std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)?;
+                    let (slowlog_sender, slowlog_receiver) = tokio::sync::mpsc::unbounded_channel();
+                    rt.spawn(
+                        write_slow_log(slowlog_receiver, tokio::fs::File::from_std(file)).map(
+                            |res| {
+                                if let Err(error) = res {
+                                    error!(%error, "Error writing to slow query log");
+                                }
+                            },
+                        ),
+                    );
+                    Some(slowlog.with_sender(slowlog_sender))
+                }
+                None => Some(slowlog),
+            }
+        } else {
+            None
+        };
+
         let noria_read_behavior = if options.non_blocking_reads {
             ReadBehavior::NonBlocking
         } else {
@@ -446,8 +685,14 @@ where
         } else {
             MigrationStyle::InRequestPath
         };
-        let query_status_cache: &'static _ =
-            Box::leak(Box::new(QueryStatusCache::with_style(migration_style)));
+        let caching_policy = CachingPolicy {
+            deny_patterns: options.cache_deny_pattern.clone(),
+            allow_list_only: options.cache_allow_list,
+            allowed_ids: options.cache_allow_query_ids.clone(),
+        };
+        let query_status_cache: &'static _ = Box::leak(Box::new(
+            QueryStatusCache::with_style(migration_style).with_caching_policy(caching_policy),
+        ));
 
         if options.async_migrations || options.explicit_migrations {
             let upstream_db_url = options.upstream_db_url.as_ref().map(|u| u.0.clone());
@@ -551,9 +796,10 @@ where
         // Spin up async thread that is in charge of creating a session with the authority,
         // regularly updating the heartbeat to keep the session live, and registering the adapters
         // http endpoint.
-        // For now we only support registering adapters over consul.
-        if let AuthorityType::Consul = options.authority {
+        // For now we only support registering adapters over consul and kubernetes.
+        if let AuthorityType::Consul | AuthorityType::Kubernetes = options.authority {
             let fut = reconcile_endpoint_registration(
+                options.authority.clone(),
                 authority_address,
                 deployment,
                 options.metrics_address.port(),
@@ -575,6 +821,8 @@ where
             let r = readers.clone();
             let auth_address = options.authority_address.clone();
             builder.set_replicator_url(options.upstream_db_url.as_ref().unwrap().0.clone());
+            builder.set_dialect(self.dialect);
+            builder.set_write_through(options.write_through);
             let persistence_params = readyset_server::PersistenceParameters::new(
                 readyset_server::DurabilityMode::Permanent,
                 Some(deployment.clone()),
@@ -605,18 +853,101 @@ where
             None
         };
 
+        if let Some(path) = &options.cache_file {
+            let mut ch = ch.clone();
+            let dialect = self.dialect;
+            let statuses = rt.block_on(async {
+                // Caches can only be created once the leader is ready
+                while !matches!(ch.leader_ready().await, Ok(true)) {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                create_caches_from_file(&mut ch, path, dialect, query_status_cache).await
+            })?;
+
+            if statuses.iter().all(|status| status.error.is_none()) {
+                for name in statuses.iter().filter_map(|status| status.name.as_ref()) {
+                    info!(%name, path = %path.display(), "Created cache from cache file");
+                }
+            } else {
+                for status in &statuses {
+                    if let Some(error) = &status.error {
+                        error!(
+                            name = ?status.name,
+                            %error,
+                            "Could not create cache from cache file"
+                        );
+                    }
+                }
+                bail!(
+                    "Could not create the caches in {}; none of them were created",
+                    path.display()
+                );
+            }
+        }
+
+        let upstream_pool = match &options.upstream_db_url {
+            Some(url) => {
+                if options.upstream_pool_min_size > options.upstream_pool_max_size {
+                    bail!("--upstream-pool-min-size must not exceed --upstream-pool-max-size");
+                }
+                let pool = UpstreamPool::<H::UpstreamDatabase>::new(
+                    url.0.clone(),
+                    UpstreamPoolConfig {
+                        min_size: options.upstream_pool_min_size,
+                        max_size: options.upstream_pool_max_size,
+                        idle_timeout: Duration::from_secs(options.upstream_pool_idle_timeout),
+                        health_check_interval: Duration::from_secs(
+                            options.upstream_pool_health_check_interval.max(1),
+                        ),
+                        checkout_timeout: Duration::from_secs(
+                            options.upstream_pool_checkout_timeout,
+                        ),
+                    },
+                );
+                rt.handle()
+                    .spawn(UpstreamPool::maintain(Arc::downgrade(&pool)));
+                Some(pool)
+            }
+            None => None,
+        };
+
         while let Some(Ok(s)) = rt.block_on(listener.next()) {
-            let connection = span!(Level::DEBUG, "connection", addr = ?s.peer_addr().unwrap());
+            let connection = match &s {
+                ClientConnection::Tcp(s) => {
+                    span!(Level::DEBUG, "connection", addr = ?s.peer_addr().unwrap())
+                }
+                ClientConnection::Unix(s) => {
+                    span!(Level::DEBUG, "connection", uid = ?peer_uid(s))
+                }
+            };
             connection.in_scope(|| info!("Accepted new connection"));
 
+            let s = match s {
+                ClientConnection::Unix(s)
+                    if !peer_uid(&s).map_or(false, |uid| allowed_unix_uids.contains(&uid)) =>
+                {
+                    connection.in_scope(|| warn!("Refusing connection from disallowed Unix user"));
+                    let connection_handler = self.connection_handler.clone();
+                    rt.handle().spawn(
+                        connection_handler
+                            .immediate_error(s, "Access denied for Unix socket user".to_owned())
+                            .instrument(connection),
+                    );
+                    continue;
+                }
+                s => s,
+            };
+
             // bunch of stuff to move into the async block below
             let ch = ch.clone();
             let (auto_increments, query_cache) = (auto_increments.clone(), query_cache.clone());
             let mut connection_handler = self.connection_handler.clone();
             let region = options.region.clone();
-            let upstream_db_url = options.upstream_db_url.clone();
+            let result_page_size = options.result_page_size;
+            let upstream_pool = upstream_pool.clone();
+            let connection_tls = tls.as_ref().map(tls::Tls::for_connection);
             let backend_builder = BackendBuilder::new()
-                .slowlog(options.log_slow)
+                .slowlog(slowlog.clone())
                 .users(users.clone())
                 .require_authentication(!options.allow_unauthenticated_connections)
                 .dialect(self.dialect)
@@ -629,7 +960,19 @@ where
                 })
                 .migration_mode(migration_mode)
                 .query_max_failure_seconds(options.query_max_failure_seconds)
-                .fallback_recovery_seconds(options.fallback_recovery_seconds);
+                .fallback_recovery_seconds(options.fallback_recovery_seconds)
+                .write_through(options.write_through)
+                .read_your_writes(
+                    options
+                        .read_your_writes
+                        .then(|| Duration::from_millis(options.read_your_writes_timeout_ms)),
+                )
+                .consistent_transaction_reads(options.consistent_transaction_reads.then(|| {
+                    Duration::from_millis(options.consistent_transaction_reads_timeout_ms)
+                }))
+                .read_timeout(options.read_timeout_ms.map(Duration::from_millis))
+                .parsed_query_cache_size(options.parsed_query_cache_size)
+                .schema_generation(schema_generation.clone());
 
             // Initialize the reader layer for the adapter.
             let r = options.standalone.then(|| {
@@ -651,7 +994,12 @@ where
                     }
                 });
                 rt.handle().spawn(retries);
-                ReadRequestHandler::new(readers.clone(), tx, Duration::from_secs(5))
+                ReadRequestHandler::new(
+                    readers.clone(),
+                    tx,
+                    Duration::from_secs(5),
+                    Default::default(),
+                )
             });
 
             let query_status_cache = query_status_cache;
@@ -666,12 +1014,13 @@ where
                         r,
                     )
                     .instrument(debug_span!("Building ReadySet connector"))
-                    .await;
+                    .await
+                    .with_result_page_size(result_page_size);
 
-                    let upstream_res = if let Some(upstream_db_url) = &upstream_db_url {
+                    let upstream_res = if let Some(upstream_pool) = upstream_pool {
                         timeout(
                             UPSTREAM_CONNECTION_TIMEOUT,
-                            H::UpstreamDatabase::connect(upstream_db_url.0.clone()),
+                            PooledUpstream::new(upstream_pool),
                         )
                         .instrument(debug_span!("Connecting to upstream database"))
                         .await
@@ -689,11 +1038,27 @@ where
                                 backend_builder
                                     .clone()
                                     .build(noria, upstream, query_status_cache);
-                            connection_handler.process_connection(s, backend).await;
+                            match s {
+                                ClientConnection::Tcp(s) => {
+                                    connection_handler
+                                        .process_connection(s, backend, connection_tls)
+                                        .await
+                                }
+                                ClientConnection::Unix(s) => {
+                                    connection_handler.process_unix_connection(s, backend).await
+                                }
+                            }
                         }
                         Err(error) => {
                             error!(%error, "Error during initial connection establishment");
-                            connection_handler.immediate_error(s, error).await;
+                            match s {
+                                ClientConnection::Tcp(s) => {
+                                    connection_handler.immediate_error(s, error).await
+                                }
+                                ClientConnection::Unix(s) => {
+                                    connection_handler.immediate_error(s, error).await
+                                }
+                            }
                         }
                     }
 
@@ -707,6 +1072,12 @@ where
         // Dropping the sender acts as a shutdown signal.
         drop(shutdown_sender);
 
+        if let Some(path) = &options.unix_socket {
+            if let Err(error) = std::fs::remove_file(path) {
+                warn!(%error, path = %path.display(), "Could not remove Unix socket");
+            }
+        }
+
         // Shut down all tcp streams started by the adapters http router.
         drop(router_handle);
 
@@ -720,6 +1091,30 @@ where
     }
 }
 
+/// Bind a listener to the Unix domain socket at `path`, first removing any socket left behind at
+/// that path by a previous run of the adapter
+fn bind_unix_socket(path: &Path) -> io::Result<net::UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    net::UnixListener::bind(path)
+}
+
+/// Returns the ID of the Unix user on the other end of `stream`, as reported by the kernel
+fn peer_uid(stream: &net::UnixStream) -> Option<u32> {
+    stream.peer_cred().ok().map(|cred| cred.uid())
+}
+
 async fn my_ip(destination: &str, use_aws_external: bool) -> Option<IpAddr> {
     if use_aws_external {
         return my_aws_ip().await.ok();
@@ -763,23 +1158,38 @@ async fn my_aws_ip() -> anyhow::Result<IpAddr> {
         .parse()?)
 }
 
-/// Facilitates continuously updating consul with this adapters externally accessibly http
+/// Facilitates continuously updating the authority with this adapters externally accessibly http
 /// endpoint.
 async fn reconcile_endpoint_registration(
+    authority: AuthorityType,
     authority_address: String,
     deployment: String,
     port: u16,
     use_aws_external: bool,
 ) {
-    let authority =
-        ConsulAuthority::new(&format!("http://{}/{}", &authority_address, &deployment)).unwrap();
+    // We find our IP by routing towards the authority, which for the kubernetes authority running
+    // in-cluster is the API server rather than the authority address.
+    let ip_destination = match (
+        &authority,
+        std::env::var("KUBERNETES_SERVICE_HOST"),
+        std::env::var("KUBERNETES_SERVICE_PORT"),
+    ) {
+        (AuthorityType::Kubernetes, Ok(host), Ok(port)) => format!("{}:{}", host, port),
+        _ => authority_address.clone(),
+    };
+    let authority = authority
+        .to_authority(&authority_address, &deployment)
+        .await;
 
     let mut interval = tokio::time::interval(REGISTER_HTTP_INTERVAL);
     let mut session_id = None;
 
-    async fn needs_refresh(id: &Option<String>, consul: &ConsulAuthority) -> bool {
+    async fn needs_refresh(id: &Option<String>, authority: &Authority) -> bool {
         if let Some(id) = id {
-            consul.worker_heartbeat(id.to_owned()).await.is_err()
+            !matches!(
+                authority.adapter_heartbeat(id.to_owned()).await,
+                Ok(AuthorityWorkerHeartbeatResponse::Alive)
+            )
         } else {
             true
         }
@@ -799,7 +1209,7 @@ async fn reconcile_endpoint_registration(
 
         // We try to update our http endpoint every iteration regardless because it may
         // have changed.
-        let ip = match my_ip(&authority_address, use_aws_external).await {
+        let ip = match my_ip(&ip_destination, use_aws_external).await {
             Some(ip) => ip,
             None => {
                 // Failed to retrieve our IP. We'll try again on next tick iteration.
@@ -968,4 +1378,23 @@ mod tests {
         assert_eq!(opts.max_processing_minutes, 15);
         assert_eq!(opts.migration_task_interval, 20000);
     }
+
+    #[test]
+    fn arg_parsing_unix_socket() {
+        let opts = Options::parse_from(vec![
+            "readyset-psql",
+            "--deployment",
+            "test",
+            "--authority-address",
+            "zookeeper:2181",
+            "--allow-unauthenticated-connections",
+            "--unix-socket",
+            "/tmp/.s.PGSQL.5432",
+            "--unix-socket-allowed-uids",
+            "1000,1001",
+        ]);
+
+        assert_eq!(opts.unix_socket, Some(PathBuf::from("/tmp/.s.PGSQL.5432")));
+        assert_eq!(opts.unix_socket_allowed_uids, vec![1000, 1001]);
+    }
 }
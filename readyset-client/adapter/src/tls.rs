@@ -0,0 +1,175 @@
+//! TLS termination for client connections to the adapter.
+//!
+//! The certificate and key presented to clients are reloaded from disk when the adapter receives
+//! `SIGHUP`, and whenever the files are seen to have changed, so that certificates can be rotated
+//! without restarting the adapter. Connections established before a reload keep using the
+//! certificates they were established with.
+
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use clap::Parser;
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+use openssl::x509::X509Name;
+use tokio::select;
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{error, info};
+
+/// How often to check the certificate files for changes
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Parser, Debug, Clone)]
+pub struct TlsOptions {
+    /// Path to a PEM file containing the certificate chain to present to clients. Enables TLS for
+    /// client connections.
+    #[clap(long, env = "TLS_CERT", requires = "tls-key")]
+    tls_cert: Option<PathBuf>,
+
+    /// Path to a PEM file containing the private key for --tls-cert
+    #[clap(long, env = "TLS_KEY", requires = "tls-cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Path to a PEM file containing the CA certificates used to verify client certificates.
+    ///
+    /// A client that presents a certificate signed by one of these CAs is authenticated as the
+    /// user named by the common name of the certificate's subject, without a password, if it
+    /// connects as that user.
+    #[clap(long, env = "TLS_CLIENT_CA", requires = "tls-cert")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// Refuse TLS connections from clients that don't present a certificate signed by one of the
+    /// CAs in --tls-client-ca
+    #[clap(long, env = "TLS_REQUIRE_CLIENT_CERT", requires = "tls-client-ca")]
+    tls_require_client_cert: bool,
+
+    /// Refuse connections from clients that don't use TLS
+    #[clap(long, env = "REQUIRE_TLS", requires = "tls-cert")]
+    require_tls: bool,
+}
+
+/// The TLS configuration to use for a single client connection
+pub struct ConnectionTls {
+    /// The acceptor to use to perform the TLS handshake with the client
+    pub acceptor: Arc<SslAcceptor>,
+    /// Whether to refuse the connection if the client doesn't request TLS
+    pub required: bool,
+}
+
+/// The TLS configuration for client connections, whose certificates can be reloaded while the
+/// adapter is running
+#[derive(Clone)]
+pub(crate) struct Tls {
+    options: TlsOptions,
+    acceptor: Arc<RwLock<Arc<SslAcceptor>>>,
+}
+
+impl Tls {
+    /// Load the TLS configuration described by `options`, or return `None` if TLS isn't enabled
+    pub(crate) fn new(options: TlsOptions) -> anyhow::Result<Option<Self>> {
+        if options.tls_cert.is_none() {
+            return Ok(None);
+        }
+        let acceptor = build_acceptor(&options)?;
+        Ok(Some(Self {
+            options,
+            acceptor: Arc::new(RwLock::new(Arc::new(acceptor))),
+        }))
+    }
+
+    /// Returns the TLS configuration to use for a new client connection
+    pub(crate) fn for_connection(&self) -> ConnectionTls {
+        ConnectionTls {
+            acceptor: Arc::clone(&self.acceptor.read().unwrap()),
+            required: self.options.require_tls,
+        }
+    }
+
+    /// Reload the certificates whenever SIGHUP is received or the certificate files change, until
+    /// the adapter shuts down. If the certificates fail to load, the previous ones are kept.
+    pub(crate) async fn reload_on_change(self) {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(error) => {
+                error!(%error, "Could not listen for SIGHUP; TLS certificates won't be reloaded");
+                return;
+            }
+        };
+        let mut interval = tokio::time::interval(RELOAD_POLL_INTERVAL);
+        let mut last_modified = self.last_modified();
+
+        loop {
+            select! {
+                _ = hangup.recv() => info!("Received SIGHUP, reloading TLS certificates"),
+                _ = interval.tick() => {
+                    let modified = self.last_modified();
+                    if modified == last_modified {
+                        continue;
+                    }
+                    last_modified = modified;
+                    info!("TLS certificate files changed, reloading TLS certificates");
+                }
+            }
+
+            match build_acceptor(&self.options) {
+                Ok(acceptor) => {
+                    *self.acceptor.write().unwrap() = Arc::new(acceptor);
+                    info!("Reloaded TLS certificates");
+                }
+                Err(error) => error!(
+                    %error,
+                    "Could not reload TLS certificates; keeping previous certificates"
+                ),
+            }
+        }
+    }
+
+    /// Returns the modification times of the certificate files, to detect when they change
+    fn last_modified(&self) -> Vec<Option<SystemTime>> {
+        [
+            &self.options.tls_cert,
+            &self.options.tls_key,
+            &self.options.tls_client_ca,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|path| path.metadata().and_then(|m| m.modified()).ok())
+        .collect()
+    }
+}
+
+fn build_acceptor(options: &TlsOptions) -> anyhow::Result<SslAcceptor> {
+    let (cert, key) = match (&options.tls_cert, &options.tls_key) {
+        (Some(cert), Some(key)) => (cert, key),
+        _ => anyhow::bail!("Both --tls-cert and --tls-key must be specified"),
+    };
+
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server())?;
+    builder
+        .set_certificate_chain_file(cert)
+        .with_context(|| format!("Loading TLS certificate from {}", cert.display()))?;
+    builder
+        .set_private_key_file(key, SslFiletype::PEM)
+        .with_context(|| format!("Loading TLS private key from {}", key.display()))?;
+    builder
+        .check_private_key()
+        .context("TLS private key does not match certificate")?;
+
+    if let Some(client_ca) = &options.tls_client_ca {
+        builder.set_ca_file(client_ca).with_context(|| {
+            format!(
+                "Loading client CA certificates from {}",
+                client_ca.display()
+            )
+        })?;
+        builder.set_client_ca_list(X509Name::load_client_ca_file(client_ca)?);
+        let mut mode = SslVerifyMode::PEER;
+        if options.tls_require_client_cert {
+            mode |= SslVerifyMode::FAIL_IF_NO_PEER_CERT;
+        }
+        builder.set_verify(mode);
+    }
+
+    Ok(builder.build())
+}
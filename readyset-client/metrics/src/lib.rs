@@ -22,6 +22,10 @@ pub struct QueryExecutionEvent {
     /// SqlQuery associated with this execution event.
     pub query: Option<Arc<SqlQuery>>,
 
+    /// The id of the read, as shown by `SHOW CACHES` and `SHOW PROXIED QUERIES`, if it is a read
+    /// that ReadySet could rewrite
+    pub query_id: Option<String>,
+
     /// How long the request spent in parsing.
     pub parse_duration: Option<Duration>,
 
@@ -39,6 +43,12 @@ pub struct QueryExecutionEvent {
 
     /// Number of cache misses which occurred as part of a query
     pub cache_misses: Option<u64>,
+
+    /// How long ReadySet spent waiting for cache misses to be filled by upqueries
+    pub upquery_duration: Option<Duration>,
+
+    /// Whether keys were evicted from ReadySet's caches while the query was waiting for upqueries
+    pub evicted_during_upquery: bool,
 }
 
 #[derive(Debug, PartialEq, Serialize, Clone, Copy, Default)]
@@ -137,12 +147,15 @@ impl QueryExecutionEvent {
             event: t,
             sql_type: SqlQueryType::Other,
             query: None,
+            query_id: None,
             parse_duration: None,
             upstream_duration: None,
             readyset_duration: None,
             noria_error: None,
             destination: None,
             cache_misses: None,
+            upquery_duration: None,
+            evicted_during_upquery: false,
         }
     }
 
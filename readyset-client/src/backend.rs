@@ -5,10 +5,125 @@
 //! will execute as follows:
 //!
 //! * `INSERT`, `DELETE`, `UPDATE` - on upstream
-//! * Anything inside a transaction - on upstream
+//! * Anything inside a transaction - on upstream, unless [consistent transaction
+//!   reads](#consistent-transaction-reads) are enabled
 //! * `SELECT` - on ReadySet
 //! * Anything that failed on ReadySet, or while a migration is ongoing - on upstream
 //!
+//! ## Hints
+//!
+//! A `SELECT` statement can override how it's executed with a comment hint, either at the start of
+//! the statement or directly after the `SELECT` keyword:
+//!
+//! * `/*+ readyset=proxy */` - always execute the statement on upstream
+//! * `/*+ readyset=cached */` - migrate the statement in the request path if it isn't cached yet,
+//!   as if the migration mode were [`MigrationMode::InRequestPath`]
+//!
+//! ## Caching policy
+//!
+//! The adapter can be configured with a [`CachingPolicy`] which denies caching queries matching
+//! certain patterns, or only allows caching queries which have been explicitly registered by ID.
+//! `SELECT` statements the policy doesn't allow to be cached are always executed on upstream.
+//! Running `CREATE CACHE` for a query registers it, and dropping its cache with `DROP CACHE` or
+//! `DROP ALL CACHES [MATCHING '<pattern>']` unregisters it again.
+//!
+//! [`CachingPolicy`]: crate::query_status_cache::CachingPolicy
+//!
+//! ## Write-through
+//!
+//! If write-through is enabled, `INSERT`, `DELETE` and `UPDATE` statements that succeed on the
+//! upstream outside of a transaction are also applied directly to ReadySet's base tables, so that
+//! subsequent reads on the same connection reflect them without waiting for replication. This is
+//! only done for tables with a primary key, and for inserts that specify every primary key column,
+//! since the replicator resolves conflicts with the replicated version of the write by primary
+//! key. Failing to apply a write to ReadySet is not an error, since the write will still be
+//! replicated.
+//!
+//! The replicated version of a write must always win over the version written through, so before
+//! writing through the backend checks the upstream's current replication log position, which is
+//! at or after the write's. If ReadySet has already replicated up to that position, the write (and
+//! possibly later changes to the same rows) has already been applied, and it isn't written through.
+//! The same goes for writes whose position can't be determined.
+//!
+//! ## Read-your-writes
+//!
+//! If read-your-writes is enabled, after a write succeeds on the upstream outside of a transaction
+//! (or a transaction is committed) the backend records the upstream's current replication log
+//! position. Reads that would be served by ReadySet first wait for ReadySet to have replicated up
+//! to that position, so that they observe the connection's own writes. If replication doesn't
+//! catch up within the configured timeout, the read is served by the upstream instead. Since the
+//! recorded position can be later than the write itself (for example if other clients have written
+//! to the upstream in the meantime), reads may wait longer than strictly necessary.
+//!
+//! ## Consistent transaction reads
+//!
+//! If consistent transaction reads are enabled, `SELECT` statements inside a transaction are
+//! served by ReadySet rather than proxied, as long as the transaction hasn't executed anything
+//! other than a `SELECT` yet. The first such read records the upstream's current replication log
+//! position as the transaction's watermark, and every read of a cached query in the transaction
+//! first waits for ReadySet to have replicated up to that watermark, so that no read in the
+//! transaction observes a state older than what the transaction would have seen on the upstream.
+//! Reads that can't catch up within the configured timeout are served by the upstream instead.
+//! Once the transaction executes any other statement, the rest of the transaction is proxied so
+//! that it observes its own uncommitted writes.
+//!
+//! Since ReadySet's caches aren't versioned, the watermark is a lower bound: reads may still
+//! reflect writes that were replicated after the watermark was recorded.
+//!
+//! ## Read timeouts
+//!
+//! If a read timeout is configured, reads of cached queries that haven't completed on ReadySet
+//! within the timeout - usually because they missed in the cache and are waiting for upqueries to
+//! fill the missing keys - are abandoned and executed against the upstream database instead, so
+//! that clients aren't left waiting on slow cache fills. Abandoning the read doesn't cancel the
+//! upqueries, so later reads of the same keys can still be served by ReadySet.
+//!
+//! ## Cursors
+//!
+//! `DECLARE ... CURSOR FOR <select>` statements outside of a transaction are executed like any
+//! other `SELECT`. If the query is served by ReadySet, its results are kept in the backend and
+//! returned in pages by later `FETCH` statements, which are answered by ReadySet even inside a
+//! transaction. Otherwise the `DECLARE` is proxied to the upstream, along with any `FETCH` and
+//! `CLOSE` statements for that cursor.
+//!
+//! ## Prepared statements
+//!
+//! Statements prepared with `PREPARE name AS ...` are prepared the same way as statements prepared
+//! over the extended protocol, and `EXECUTE name (...)` executes them with its (literal) arguments
+//! as parameters, so they can be served by ReadySet too. `DEALLOCATE` only forgets the statement's
+//! name, since statement identifiers are never reused.
+//!
+//! ## Temporary tables
+//!
+//! Temporary tables only exist on the upstream connection that created them, so
+//! `CREATE TEMPORARY TABLE` statements are always proxied, and the backend keeps track of the
+//! temporary tables created in its session. Any statement that refers to one of them (including in
+//! a join or subquery) is proxied too, rather than being attempted against ReadySet, until the
+//! table is dropped again. Without an upstream, creating a temporary table is an error.
+//!
+//! ## Table samples
+//!
+//! Queries that read a random sample of the rows of a table with `TABLESAMPLE` return different
+//! results every time they're executed, so they can't be cached, and are always proxied.
+//!
+//! ## Unsupported function calls
+//!
+//! Calls to functions whose arguments aren't just a list of expressions (such as the XML
+//! functions, or `GROUP_CONCAT` with several arguments) are parsed without interpreting their
+//! arguments, and any query containing one is always proxied.
+//!
+//! ## MERGE statements
+//!
+//! `MERGE` statements can't be executed by ReadySet, so they're always proxied to the upstream,
+//! and fail without one. The rows they write reach ReadySet through replication.
+//!
+//! ## Maintenance statements
+//!
+//! Table maintenance statements such as `ANALYZE`, `VACUUM` and `OPTIMIZE TABLE` are proxied to
+//! the upstream, and are no-ops without one. Once an `ANALYZE` has succeeded, ReadySet also
+//! recomputes the statistics it keeps about the rows of the analyzed tables (see `SHOW READYSET
+//! STATISTICS`) from the rows the tables currently hold.
+//!
 //! # The execution flow
 //!
 //! ## Prepare
@@ -67,22 +182,34 @@
 //! only trigger on networking related errors specifically to try to prevent this feature from
 //! being too heavy handed.
 use std::borrow::Cow;
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::{self, Debug};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use dataflow_expression::like::{CaseInsensitive, LikePattern};
 use futures::future::{self, OptionFuture};
 use launchpad::redacted::Sensitive;
+use lru::LruCache;
 use mysql_common::row::convert::{FromRow, FromRowError};
+use nom_sql::analysis::visit::{walk_function_expression, Visitor};
+use nom_sql::analysis::ReferredTables;
 use nom_sql::{
-    CacheInner, CreateCacheStatement, DeleteStatement, Dialect, DropCacheStatement,
-    InsertStatement, SelectStatement, ShowStatement, SqlIdentifier, SqlQuery, UpdateStatement,
+    AlterCacheStatement, AlterReadysetStatement, CacheInner, CloseCursorStatement,
+    CreateCacheStatement, CreateTableStatement, DeallocateStatement, DeclareCursorStatement,
+    DeleteStatement, Dialect, DropAllCachesStatement, DropCacheStatement, ExecuteStatement,
+    Expression, Feature, FetchStatement, FunctionExpression, InsertStatement, MaintenanceOperation,
+    PrepareStatement, ReadysetHint, SelectStatement, ShowStatement, SqlIdentifier, SqlQuery,
+    SqlType, Table, UpdateStatement,
 };
 use readyset::consistency::Timestamp;
+use readyset::replication::ReplicationOffset;
 use readyset::results::Results;
 use readyset::ColumnSchema;
 use readyset_client_metrics::{
@@ -96,17 +223,37 @@ use timestamp_service::client::{TimestampClient, WriteId, WriteKey};
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::{error, instrument, trace, warn};
 
+use crate::catalog::{Catalog, CatalogTable, DEFAULT_DATABASE};
 use crate::query_status_cache::{
-    DeniedQuery, ExecutionInfo, ExecutionState, MigrationState, QueryStatus, QueryStatusCache,
+    anonymized_query_text, hash_to_query_id, CacheSuggestion, DeniedQuery, ExecutionInfo,
+    ExecutionState, MigrationState, QueryStats, QueryStatus, QueryStatusCache, UnparsedQuery,
+    UnsupportedReason,
 };
+use crate::slow_log::SlowLog;
 use crate::upstream_database::NoriaCompare;
 pub use crate::upstream_database::UpstreamPrepare;
+use crate::upstream_pool::replayed_session_variables;
+use crate::utils::hash_select_query;
 use crate::{rewrite, QueryHandler, UpstreamDatabase};
 
+mod cursor;
 pub mod noria_connector;
 
+use self::cursor::Cursor;
 pub use self::noria_connector::NoriaConnector;
 
+/// How often to check whether ReadySet has replicated a connection's writes, when waiting to serve
+/// a read with read-your-writes enabled
+const REPLICATION_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The maximum time a `READYSET FLUSH WAIT` statement waits for writes to propagate to ReadySet's
+/// caches before returning an error
+const FLUSH_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of parsed queries each connection keeps by default, if not overridden with
+/// [`BackendBuilder::parsed_query_cache_size`]
+pub const DEFAULT_PARSED_QUERY_CACHE_SIZE: usize = 1000;
+
 /// Query metadata used to plan query prepare
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
@@ -120,7 +267,7 @@ enum PrepareMeta {
     /// ReadySet does not implement this prepared statement. The statement may also be invalid SQL
     Unimplemented,
     /// A write query (Insert, Update, Delete)
-    Write { stmt: SqlQuery },
+    Write { stmt: Arc<SqlQuery> },
     /// A read (Select; may be extended in the future)
     Select(PrepareSelectMeta),
 }
@@ -171,8 +318,9 @@ enum ProxyState {
     /// This is the initial behavior used when an upstream database is configured for a backend
     Fallback,
 
-    /// We are inside a transaction, so proxy all statements upstream, but return to
-    /// [`ProxyState::Fallback`] when the transaction is finished
+    /// We are inside a transaction, so proxy all statements upstream (other than reads, if
+    /// [consistent transaction reads](Backend#consistent-transaction-reads) are enabled), but
+    /// return to [`ProxyState::Fallback`] when the transaction is finished
     InTransaction,
 
     /// Unconditionally proxy all statements upstream, and do not leave this state when leaving
@@ -205,6 +353,14 @@ impl ProxyState {
         }
     }
 
+    /// Returns `true` if the proxy state is [`InTransaction`].
+    ///
+    /// [`InTransaction`]: ProxyState::InTransaction
+    #[must_use]
+    fn is_in_transaction(&self) -> bool {
+        matches!(self, Self::InTransaction)
+    }
+
     /// Returns `true` if the proxy state is [`Fallback`].
     ///
     /// [`Fallback`]: ProxyState::Fallback
@@ -218,7 +374,7 @@ impl ProxyState {
 #[must_use]
 #[derive(Clone)]
 pub struct BackendBuilder {
-    slowlog: bool,
+    slowlog: Option<SlowLog>,
     dialect: Dialect,
     users: HashMap<String, String>,
     require_authentication: bool,
@@ -232,12 +388,18 @@ pub struct BackendBuilder {
     migration_mode: MigrationMode,
     query_max_failure_seconds: u64,
     fallback_recovery_seconds: u64,
+    write_through: bool,
+    read_your_writes: Option<Duration>,
+    consistent_transaction_reads: Option<Duration>,
+    read_timeout: Option<Duration>,
+    parsed_query_cache_size: usize,
+    schema_generation: Arc<AtomicUsize>,
 }
 
 impl Default for BackendBuilder {
     fn default() -> Self {
         BackendBuilder {
-            slowlog: false,
+            slowlog: None,
             dialect: Dialect::MySQL,
             users: Default::default(),
             require_authentication: true,
@@ -251,6 +413,12 @@ impl Default for BackendBuilder {
             migration_mode: MigrationMode::InRequestPath,
             query_max_failure_seconds: (i64::MAX / 1000) as u64,
             fallback_recovery_seconds: 0,
+            write_through: false,
+            read_your_writes: None,
+            consistent_transaction_reads: None,
+            read_timeout: None,
+            parsed_query_cache_size: DEFAULT_PARSED_QUERY_CACHE_SIZE,
+            schema_generation: Default::default(),
         }
     }
 }
@@ -275,7 +443,9 @@ impl BackendBuilder {
         };
 
         Backend {
-            parsed_query_cache: HashMap::new(),
+            parsed_query_cache: LruCache::new(self.parsed_query_cache_size),
+            parsed_query_cache_generation: self.schema_generation.load(Ordering::Acquire),
+            schema_generation: self.schema_generation,
             prepared_statements: Vec::new(),
             noria,
             upstream,
@@ -296,11 +466,22 @@ impl BackendBuilder {
             last_query: None,
             query_max_failure_duration: Duration::new(self.query_max_failure_seconds, 0),
             fallback_recovery_duration: Duration::new(self.fallback_recovery_seconds, 0),
+            write_through: self.write_through,
+            read_your_writes: self.read_your_writes,
+            consistent_transaction_reads: self.consistent_transaction_reads,
+            read_timeout: self.read_timeout,
+            replication_ticket: None,
+            transaction_watermark: None,
+            transaction_wrote: false,
+            cursors: HashMap::new(),
+            named_statements: HashMap::new(),
+            temporary_tables: HashSet::new(),
             _query_handler: PhantomData,
         }
     }
 
-    pub fn slowlog(mut self, slowlog: bool) -> Self {
+    /// Log queries which take longer than a threshold to execute to the given [`SlowLog`]
+    pub fn slowlog(mut self, slowlog: Option<SlowLog>) -> Self {
         self.slowlog = slowlog;
         self
     }
@@ -370,6 +551,54 @@ impl BackendBuilder {
         self.fallback_recovery_seconds = secs;
         self
     }
+
+    /// Specifies whether writes should be applied to ReadySet's base tables as well as to the
+    /// upstream database. See the [module-level documentation](self#write-through).
+    pub fn write_through(mut self, write_through: bool) -> Self {
+        self.write_through = write_through;
+        self
+    }
+
+    /// Specifies whether reads should wait for ReadySet to replicate the writes previously made on
+    /// the same connection, and the maximum time to wait before serving the read from the upstream
+    /// database instead. See the [module-level documentation](self#read-your-writes).
+    pub fn read_your_writes(mut self, timeout: Option<Duration>) -> Self {
+        self.read_your_writes = timeout;
+        self
+    }
+
+    /// Specifies whether reads inside a transaction should be served by ReadySet against a
+    /// replication watermark shared by the whole transaction, and the maximum time a read waits for
+    /// ReadySet to reach the watermark before being served by the upstream database instead. See
+    /// the [module-level documentation](self#consistent-transaction-reads).
+    pub fn consistent_transaction_reads(mut self, timeout: Option<Duration>) -> Self {
+        self.consistent_transaction_reads = timeout;
+        self
+    }
+
+    /// Specifies the maximum time that a read of a cached query may take on ReadySet before it's
+    /// abandoned and served by the upstream database instead. See the [module-level
+    /// documentation](self#read-timeouts).
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Specifies the maximum number of parsed queries to keep per connection, so that statements
+    /// which are run repeatedly only have to be parsed once. If 0, queries are parsed every time
+    /// they're run.
+    pub fn parsed_query_cache_size(mut self, size: usize) -> Self {
+        self.parsed_query_cache_size = size;
+        self
+    }
+
+    /// Specifies a counter of the DDL statements run on any connection, shared by every backend
+    /// built for the same adapter. Each backend discards its parsed queries whenever the counter
+    /// changes, so that DDL run on one connection is seen by all of them.
+    pub fn schema_generation(mut self, schema_generation: Arc<AtomicUsize>) -> Self {
+        self.schema_generation = schema_generation;
+        self
+    }
 }
 
 /// A [`CachedPreparedStatement`] stores the data needed for an immediate
@@ -390,6 +619,8 @@ where
     parsed_query: Option<Arc<SqlQuery>>,
     /// If statement was succesfully rewritten, will store the rewritten statement
     rewritten: Option<SelectStatement>,
+    /// The id of the rewritten statement, used to record the latency of its executions
+    query_id: Option<String>,
 }
 
 impl<DB> CachedPreparedStatement<DB>
@@ -426,8 +657,14 @@ pub struct Backend<DB, Handler>
 where
     DB: UpstreamDatabase,
 {
-    // a cache of all previously parsed queries
-    parsed_query_cache: HashMap<String, SqlQuery>,
+    /// The most recently parsed queries, keyed by a hash of their text. The text is kept alongside
+    /// the parsed query to tell apart queries whose hashes collide.
+    parsed_query_cache: LruCache<u64, (String, Arc<SqlQuery>)>,
+    /// The value of `schema_generation` when `parsed_query_cache` was last cleared
+    parsed_query_cache_generation: usize,
+    /// Incremented by every backend of this adapter that runs a statement which invalidates
+    /// parsed queries
+    schema_generation: Arc<AtomicUsize>,
     // all queries previously prepared on ReadySet or upstream, mapped by their ID.
     prepared_statements: Vec<CachedPreparedStatement<DB>>,
     /// ReadySet connector used for reads, and writes when no upstream DB is present
@@ -435,7 +672,7 @@ where
     /// Optional connector to the upstream DB. Used for fallback reads and all writes if it exists
     upstream: Option<DB>,
     proxy_state: ProxyState,
-    slowlog: bool,
+    slowlog: Option<SlowLog>,
     /// SQL dialect to use when parsing queries from clients
     dialect: Dialect,
     /// Map from username to password for all users allowed to connect to the db
@@ -479,6 +716,39 @@ where
     /// repeatedly failed for query_max_failure_duration.
     fallback_recovery_duration: Duration,
 
+    /// Whether writes are applied to ReadySet's base tables as well as to the upstream database
+    write_through: bool,
+
+    /// If set, the maximum time to wait for ReadySet to replicate the writes made on this
+    /// connection before serving a read from ReadySet
+    read_your_writes: Option<Duration>,
+    /// If set, reads inside a transaction are served by ReadySet once it has replicated up to the
+    /// transaction's watermark, waiting at most this long before serving them from the upstream
+    consistent_transaction_reads: Option<Duration>,
+    /// If set, the maximum time that a read of a cached query may take on ReadySet before it's
+    /// served by the upstream database instead
+    read_timeout: Option<Duration>,
+    /// The position in the upstream database's replication log of the latest write made on this
+    /// connection that ReadySet isn't yet known to have replicated
+    replication_ticket: Option<ReplicationOffset>,
+    /// The position in the upstream database's replication log that every read of a cached query
+    /// in the current transaction must observe, recorded at the transaction's first read
+    transaction_watermark: Option<ReplicationOffset>,
+    /// Whether the current transaction has executed anything other than a `SELECT`, after which
+    /// the rest of the transaction is proxied
+    transaction_wrote: bool,
+
+    /// Cursors declared on this connection whose query was executed against ReadySet, by name
+    cursors: HashMap<SqlIdentifier, Cursor>,
+
+    /// The identifiers of the statements prepared on this connection with `PREPARE`, along with
+    /// the parameter types given for them, by name
+    named_statements: HashMap<SqlIdentifier, (u32, Vec<SqlType>)>,
+
+    /// The names of the temporary tables created on this connection's upstream session, which
+    /// every statement referring to is proxied
+    temporary_tables: HashSet<SqlIdentifier>,
+
     _query_handler: PhantomData<Handler>,
 }
 
@@ -749,8 +1019,15 @@ where
                     }
                 }
 
-                self.query_status_cache
-                    .update_query_migration_state(&select_meta.rewritten, state);
+                if state == MigrationState::Unsupported {
+                    self.query_status_cache.mark_unsupported(
+                        &select_meta.rewritten,
+                        UnsupportedReason::SchemaMismatch,
+                    );
+                } else {
+                    self.query_status_cache
+                        .update_query_migration_state(&select_meta.rewritten, state);
+                }
             }
             Some(Err(e)) => {
                 if e.caused_by_view_not_found() {
@@ -759,11 +1036,11 @@ where
                         &select_meta.rewritten,
                         MigrationState::Pending,
                     );
-                } else if e.caused_by_unsupported() {
-                    self.query_status_cache.update_query_migration_state(
-                        &select_meta.rewritten,
-                        MigrationState::Unsupported,
-                    );
+                } else if let Some(reason) =
+                    UnsupportedReason::from_error(&select_meta.rewritten, e)
+                {
+                    self.query_status_cache
+                        .mark_unsupported(&select_meta.rewritten, reason);
                 } else {
                     error!(
                         error = %e,
@@ -800,14 +1077,38 @@ where
         event.sql_type = SqlQueryType::Write;
         if let Some(ref mut upstream) = self.upstream {
             let _t = event.start_upstream_timer();
-            let res = upstream.prepare(query).await.map(PrepareResult::Upstream);
+            let upstream_res = upstream.prepare(query).await;
             self.last_query = Some(QueryInfo {
                 destination: QueryDestination::Upstream,
                 noria_error: String::new(),
             });
-            res
+            let upstream_res = upstream_res?;
+
+            if self.write_through && Self::can_write_through(&mut self.noria, stmt).await {
+                let noria_res = match stmt {
+                    SqlQuery::Insert(stmt) => {
+                        self.noria.prepare_insert(stmt.clone(), prep_idx).await
+                    }
+                    SqlQuery::Delete(stmt) => {
+                        self.noria.prepare_delete(stmt.clone(), prep_idx).await
+                    }
+                    SqlQuery::Update(stmt) => {
+                        self.noria.prepare_update(stmt.clone(), prep_idx).await
+                    }
+                    _ => internal!(),
+                };
+                match noria_res {
+                    Ok(noria_res) => return Ok(PrepareResult::Both(noria_res, upstream_res)),
+                    Err(error) => {
+                        warn!(%error, "Error preparing write on ReadySet, not writing through")
+                    }
+                }
+            }
+
+            Ok(PrepareResult::Upstream(upstream_res))
         } else {
             let _t = event.start_noria_timer();
+            check_write_features(self.dialect, stmt)?;
             let res = match stmt {
                 SqlQuery::Insert(stmt) => self.noria.prepare_insert(stmt.clone(), prep_idx).await?,
                 SqlQuery::Delete(stmt) => self.noria.prepare_delete(stmt.clone(), prep_idx).await?,
@@ -824,20 +1125,27 @@ where
     }
 
     /// Provides metadata required to prepare a select query
-    fn plan_prepare_select(&mut self, stmt: nom_sql::SelectStatement) -> PrepareMeta {
+    fn plan_prepare_select(
+        &mut self,
+        stmt: nom_sql::SelectStatement,
+        hint: Option<ReadysetHint>,
+    ) -> PrepareMeta {
         let mut rewritten = stmt.clone();
         if rewrite::process_query(&mut rewritten).is_err() {
             warn!(statement = %Sensitive(&stmt), "This statement could not be rewritten by ReadySet");
             PrepareMeta::FailedToRewrite
         } else {
             // For select statements we will always try to check with ReadySet if it already migrated,
-            // unless it is explicitely known to be not supported.
+            // unless it is explicitely known to be not supported, or the caching policy doesn't
+            // allow it to be cached.
             let should_do_noria = self.query_status_cache.query_migration_state(&rewritten)
-                != MigrationState::Unsupported;
+                != MigrationState::Unsupported
+                && (!self.has_fallback() || self.query_status_cache.is_cacheable(&rewritten));
             // For select statements only InRequestPath should trigger migrations synchornously,
-            // or if no upstream is present
-            let must_migrate =
-                self.migration_mode == MigrationMode::InRequestPath || !self.has_fallback();
+            // or if no upstream is present, unless the statement has a `readyset=cached` hint
+            let must_migrate = self.migration_mode == MigrationMode::InRequestPath
+                || !self.has_fallback()
+                || hint == Some(ReadysetHint::Cached);
 
             PrepareMeta::Select(PrepareSelectMeta {
                 stmt,
@@ -862,11 +1170,23 @@ where
             }
         };
 
-        match parsed_query {
-            SqlQuery::Select(stmt) => self.plan_prepare_select(stmt),
-            SqlQuery::Insert(_) | SqlQuery::Update(_) | SqlQuery::Delete(_) => {
-                PrepareMeta::Write { stmt: parsed_query }
+        let hint = nom_sql::readyset_hint(query);
+        match &*parsed_query {
+            SqlQuery::Select(_) if hint == Some(ReadysetHint::Proxy) && self.has_fallback() => {
+                PrepareMeta::Proxy
             }
+            _ if self.has_fallback() && self.refers_to_temporary_table(&parsed_query) => {
+                PrepareMeta::Proxy
+            }
+            _ if self.has_fallback() && samples_tables(&parsed_query) => PrepareMeta::Proxy,
+            _ if self.has_fallback() && calls_unsupported_functions(&parsed_query) => {
+                PrepareMeta::Proxy
+            }
+            SqlQuery::Merge(_) if self.has_fallback() => PrepareMeta::Proxy,
+            SqlQuery::Select(stmt) => self.plan_prepare_select(stmt.clone(), hint),
+            SqlQuery::Insert(_) | SqlQuery::Update(_) | SqlQuery::Delete(_) => PrepareMeta::Write {
+                stmt: Arc::clone(&parsed_query),
+            },
             // Invalid prepare statement
             SqlQuery::CreateTable(..)
             | SqlQuery::CreateView(..)
@@ -880,10 +1200,23 @@ where
             | SqlQuery::DropTable(..)
             | SqlQuery::DropView(..)
             | SqlQuery::AlterTable(..)
+            | SqlQuery::AlterReadyset(..)
+            | SqlQuery::AlterCache(..)
             | SqlQuery::RenameTable(..)
             | SqlQuery::CreateCache(..)
             | SqlQuery::DropCache(..)
-            | SqlQuery::Explain(_) => {
+            | SqlQuery::DropAllCaches(..)
+            | SqlQuery::Explain(_)
+            | SqlQuery::DeclareCursor(_)
+            | SqlQuery::Fetch(_)
+            | SqlQuery::CloseCursor(_)
+            | SqlQuery::FlushWait(_)
+            | SqlQuery::Values(_)
+            | SqlQuery::Maintenance(_)
+            | SqlQuery::Merge(_)
+            | SqlQuery::Prepare(_)
+            | SqlQuery::Execute(_)
+            | SqlQuery::Deallocate(_) => {
                 warn!(statement = %Sensitive(&parsed_query), "Statement cannot be prepared by ReadySet");
                 PrepareMeta::Unimplemented
             }
@@ -937,7 +1270,7 @@ where
         let res = self.do_prepare(&meta, query, &mut query_event).await?;
 
         let (parsed_query, migration_state, rewritten) = match meta {
-            PrepareMeta::Write { stmt } => (Some(Arc::new(stmt)), MigrationState::Successful, None),
+            PrepareMeta::Write { stmt } => (Some(stmt), MigrationState::Successful, None),
             PrepareMeta::Select(PrepareSelectMeta {
                 stmt, rewritten, ..
             }) => (
@@ -948,12 +1281,16 @@ where
             _ => (None, MigrationState::Successful, None),
         };
 
+        let query_id = rewritten
+            .as_ref()
+            .map(|q| hash_to_query_id(hash_select_query(q)));
         let cache_entry = CachedPreparedStatement {
             prep: res,
             migration_state,
             execution_info: None,
             parsed_query,
             rewritten,
+            query_id,
         };
 
         self.prepared_statements.push(cache_entry);
@@ -967,6 +1304,7 @@ where
         prep: &noria_connector::PrepareResult,
         params: &[DataType],
         ticket: Option<Timestamp>,
+        read_timeout: Option<Duration>,
         event: &mut QueryExecutionEvent,
     ) -> ReadySetResult<QueryResult<'a, DB>> {
         use noria_connector::PrepareResult::*;
@@ -978,9 +1316,11 @@ where
             Select {
                 statement_id: id, ..
             } => {
-                noria
-                    .execute_prepared_select(*id, params, ticket, event)
-                    .await
+                with_read_timeout(
+                    read_timeout,
+                    noria.execute_prepared_select(*id, params, ticket, event),
+                )
+                .await
             }
             Insert {
                 statement_id: id, ..
@@ -1005,7 +1345,7 @@ where
 
     /// Execute a prepared statement on ReadySet
     async fn execute_upstream<'a>(
-        upstream: &'a mut Option<DB>,
+        upstream: &mut Option<DB>,
         prep: &UpstreamPrepare<DB>,
         params: &[DataType],
         event: &mut QueryExecutionEvent,
@@ -1033,15 +1373,17 @@ where
     #[allow(clippy::too_many_arguments)] // meh.
     async fn execute_cascade<'a>(
         noria: &'a mut NoriaConnector,
-        upstream: &'a mut Option<DB>,
+        upstream: &mut Option<DB>,
         noria_prep: &noria_connector::PrepareResult,
         upstream_prep: &UpstreamPrepare<DB>,
         params: &[DataType],
         ex_info: Option<&mut ExecutionInfo>,
         ticket: Option<Timestamp>,
+        read_timeout: Option<Duration>,
         event: &mut QueryExecutionEvent,
     ) -> Result<QueryResult<'a, DB>, DB::Error> {
-        let noria_res = Self::execute_noria(noria, noria_prep, params, ticket, event).await;
+        let noria_res =
+            Self::execute_noria(noria, noria_prep, params, ticket, read_timeout, event).await;
         match noria_res {
             Ok(noria_ok) => {
                 if let Some(info) = ex_info {
@@ -1060,7 +1402,10 @@ where
                         info.execute_unsupported();
                     }
                 }
-                if !matches!(noria_err, ReadySetError::ReaderMissingKey) {
+                if !matches!(
+                    noria_err,
+                    ReadySetError::ReaderMissingKey | ReadySetError::UpqueryTimeout
+                ) {
                     warn!(error = %noria_err, "Error received from ReadySet, sending query to fallback");
                 }
 
@@ -1166,6 +1511,7 @@ where
 
         let mut event = QueryExecutionEvent::new(EventType::Execute);
         event.query = cached_statement.parsed_query.clone();
+        event.query_id = cached_statement.query_id.clone();
 
         let upstream = &mut self.upstream;
         let noria = &mut self.noria;
@@ -1192,18 +1538,62 @@ where
             self.fallback_recovery_duration,
         ) || cached_statement.is_unsupported_execute();
 
+        let is_write = match &cached_statement.prep {
+            PrepareResult::Noria(prep) | PrepareResult::Both(prep, _) => prep.is_write(),
+            PrepareResult::Upstream(_) => matches!(
+                cached_statement.parsed_query.as_deref(),
+                Some(SqlQuery::Insert(_) | SqlQuery::Update(_) | SqlQuery::Delete(_))
+            ),
+        };
+        let record_write =
+            is_write && self.read_your_writes.is_some() && self.proxy_state.is_fallback();
+        let in_transaction =
+            self.consistent_transaction_reads.is_some() && self.proxy_state.is_in_transaction();
+        if in_transaction && is_write {
+            self.transaction_wrote = true;
+        }
+        // Reads that would be served by ReadySet first wait for it to replicate this connection's
+        // writes, or to reach the current transaction's watermark
+        let caught_up = !matches!(cached_statement.prep, PrepareResult::Both(..))
+            || is_write
+            || should_fallback
+            || if in_transaction {
+                !self.transaction_wrote
+                    && Self::wait_for_transaction_watermark(
+                        upstream,
+                        noria,
+                        &mut self.transaction_watermark,
+                        self.consistent_transaction_reads,
+                    )
+                    .await
+            } else {
+                Self::wait_for_replication(
+                    noria,
+                    &mut self.replication_ticket,
+                    self.read_your_writes,
+                )
+                .await
+            };
+
         let result = match &cached_statement.prep {
             PrepareResult::Noria(prep) => {
-                Self::execute_noria(noria, prep, params, ticket, &mut event)
+                Self::execute_noria(noria, prep, params, ticket, None, &mut event)
                     .await
                     .map_err(Into::into)
             }
             PrepareResult::Upstream(prep) => {
                 Self::execute_upstream(upstream, prep, params, &mut event, false).await
             }
-            PrepareResult::Both(.., uprep) if should_fallback => {
+            PrepareResult::Both(.., uprep) if should_fallback || !caught_up => {
                 Self::execute_upstream(upstream, uprep, params, &mut event, false).await
             }
+            PrepareResult::Both(nprep, uprep) if nprep.is_write() => {
+                let res = Self::execute_upstream(upstream, uprep, params, &mut event, false).await;
+                if res.is_ok() && self.proxy_state.is_fallback() {
+                    Self::execute_write_through(upstream, noria, nprep, params).await;
+                }
+                res
+            }
             PrepareResult::Both(nprep, uprep) => {
                 if cached_statement.execution_info.is_none() {
                     cached_statement.execution_info = Some(ExecutionInfo {
@@ -1219,26 +1609,31 @@ where
                     params,
                     cached_statement.execution_info.as_mut(),
                     ticket,
+                    self.read_timeout,
                     &mut event,
                 )
                 .await
             }
         };
 
+        if record_write && result.is_ok() {
+            Self::update_replication_ticket(upstream, &mut self.replication_ticket).await;
+        }
+
         if let Some(e) = event.noria_error.as_ref() {
             if e.caused_by_view_not_found() {
                 // This can happen during cascade execution if the ReadySet query was removed from
                 // another connection
                 Self::invalidate_prepared_cache_entry(&mut cached_statement.prep);
-            } else if e.caused_by_unsupported() {
+            } else if e.caused_by_unsupported() || e.no_such_function().is_some() {
                 // On an unsupported execute we update the query migration state to be unsupported.
                 //
                 // Must exist or we would not have executed the query against ReadySet.
                 #[allow(clippy::unwrap_used)]
-                self.query_status_cache.update_query_migration_state(
-                    cached_statement.rewritten.as_ref().unwrap(),
-                    MigrationState::Unsupported,
-                );
+                let rewritten = cached_statement.rewritten.as_ref().unwrap();
+                if let Some(reason) = UnsupportedReason::from_error(rewritten, e) {
+                    self.query_status_cache.mark_unsupported(rewritten, reason);
+                }
             }
         }
 
@@ -1250,11 +1645,253 @@ where
                 .map(|e| e.to_string())
                 .unwrap_or_default(),
         });
-        log_query(self.query_log_sender.as_ref(), event, self.slowlog);
+        log_query(
+            self.query_log_sender.as_ref(),
+            event,
+            self.slowlog.as_ref(),
+            self.query_status_cache,
+        );
 
         result
     }
 
+    /// Returns true if the given write can also be applied directly to ReadySet's base tables.
+    ///
+    /// The replicator resolves conflicts between rows written through and the rows later
+    /// replicated from upstream by primary key, so the table must have a primary key, and inserts
+    /// must specify all of its columns rather than rely on the upstream to generate them.
+    ///
+    /// The rows inserted by an `INSERT ... SELECT` come from a query of the upstream database, so
+    /// they're never written through, and are only applied to ReadySet once they're replicated.
+    /// The same goes for the rows modified by `UPDATE`s and `DELETE`s that refer to other tables,
+    /// and by `DELETE`s limited to a number of rows, since only the upstream database knows which
+    /// rows those are.
+    async fn can_write_through(noria: &mut NoriaConnector, stmt: &SqlQuery) -> bool {
+        let table = match stmt {
+            SqlQuery::Insert(InsertStatement {
+                select: Some(_), ..
+            }) => return false,
+            SqlQuery::Update(stmt) if stmt.is_multi_table() => return false,
+            SqlQuery::Delete(stmt) if stmt.is_multi_table() || stmt.limit.is_some() => {
+                return false
+            }
+            SqlQuery::Insert(InsertStatement { table, .. })
+            | SqlQuery::Update(UpdateStatement { table, .. })
+            | SqlQuery::Delete(DeleteStatement { table, .. }) => table,
+            _ => return false,
+        };
+
+        let key_columns = match noria.primary_key_columns(&table.name).await {
+            Ok(Some(key_columns)) => key_columns,
+            Ok(None) => return false,
+            Err(error) => {
+                warn!(%error, table = %table.name, "Error looking up table to write through to");
+                return false;
+            }
+        };
+
+        match stmt {
+            SqlQuery::Insert(InsertStatement {
+                fields: Some(fields),
+                ..
+            }) => key_columns
+                .iter()
+                .all(|key| fields.iter().any(|field| field.name == *key)),
+            _ => true,
+        }
+    }
+
+    /// Returns true if ReadySet may have replicated a write that just succeeded upstream already,
+    /// in which case writing it through could overwrite later changes replicated since. See the
+    /// [module-level documentation](self#write-through).
+    async fn write_replicated(upstream: &mut Option<DB>, noria: &mut NoriaConnector) -> bool {
+        let offset = match upstream {
+            Some(upstream) => upstream.replication_offset().await,
+            None => return true,
+        };
+        match offset {
+            Ok(Some(offset)) => match noria.has_replicated(&offset).await {
+                Ok(replicated) => replicated,
+                Err(error) => {
+                    warn!(%error, "Error checking ReadySet's replication offsets");
+                    true
+                }
+            },
+            Ok(None) => true,
+            Err(error) => {
+                warn!(%error, "Error getting replication offset from upstream");
+                true
+            }
+        }
+    }
+
+    /// Apply a write that has already succeeded upstream to ReadySet's base tables, if possible.
+    ///
+    /// Errors are logged rather than returned, since the write will still be replicated.
+    async fn write_through(upstream: &mut Option<DB>, noria: &mut NoriaConnector, stmt: &SqlQuery) {
+        if !Self::can_write_through(noria, stmt).await
+            || Self::write_replicated(upstream, noria).await
+        {
+            return;
+        }
+
+        let res = match stmt {
+            SqlQuery::Insert(q) => noria.handle_insert(q).await.map(|_| ()),
+            SqlQuery::Update(q) => noria.handle_update(q).await.map(|_| ()),
+            SqlQuery::Delete(q) => noria.handle_delete(q).await.map(|_| ()),
+            _ => Ok(()),
+        };
+        if let Err(error) = res {
+            warn!(%error, "Error writing through to ReadySet");
+        }
+    }
+
+    /// Execute a prepared write that has already succeeded upstream against ReadySet's base
+    /// tables.
+    ///
+    /// Errors are logged rather than returned, since the write will still be replicated.
+    async fn execute_write_through(
+        upstream: &mut Option<DB>,
+        noria: &mut NoriaConnector,
+        prep: &noria_connector::PrepareResult,
+        params: &[DataType],
+    ) {
+        use noria_connector::PrepareResult::*;
+
+        if Self::write_replicated(upstream, noria).await {
+            return;
+        }
+
+        let res = match prep {
+            Insert {
+                statement_id: id, ..
+            } => noria.execute_prepared_insert(*id, params).await.map(|_| ()),
+            Update {
+                statement_id: id, ..
+            } => noria.execute_prepared_update(*id, params).await.map(|_| ()),
+            Delete {
+                statement_id: id, ..
+            } => noria.execute_prepared_delete(*id, params).await.map(|_| ()),
+            Select { .. } => Ok(()),
+        };
+        if let Err(error) = res {
+            warn!(%error, "Error writing through to ReadySet");
+        }
+    }
+
+    /// Record the upstream's current replication log position in `ticket` after a write, so that
+    /// later reads wait for ReadySet to replicate the write. See the
+    /// [module-level documentation](self#read-your-writes).
+    async fn update_replication_ticket(
+        upstream: &mut Option<DB>,
+        ticket: &mut Option<ReplicationOffset>,
+    ) {
+        let upstream = match upstream {
+            Some(upstream) => upstream,
+            None => return,
+        };
+
+        match upstream.replication_offset().await {
+            Ok(Some(offset)) => {
+                if offset.try_max_into(ticket).is_err() {
+                    // The upstream has moved on to a different replication log, which every later
+                    // write will also be in
+                    *ticket = Some(offset);
+                }
+            }
+            Ok(None) => {}
+            Err(error) => warn!(%error, "Error getting replication offset from upstream"),
+        }
+    }
+
+    /// Wait for ReadySet to replicate the writes recorded in `ticket`, for at most `timeout`.
+    ///
+    /// Returns `false` if ReadySet didn't catch up in time, in which case the read should be
+    /// served by the upstream instead.
+    async fn wait_for_replication(
+        noria: &mut NoriaConnector,
+        ticket: &mut Option<ReplicationOffset>,
+        timeout: Option<Duration>,
+    ) -> bool {
+        let (offset, timeout) = match (ticket.clone(), timeout) {
+            (Some(offset), Some(timeout)) => (offset, timeout),
+            _ => return true,
+        };
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match noria.has_replicated(&offset).await {
+                Ok(true) => {
+                    *ticket = None;
+                    return true;
+                }
+                Ok(false) => {}
+                Err(error) => {
+                    warn!(%error, "Error checking ReadySet's replication offsets");
+                    return false;
+                }
+            }
+
+            if Instant::now() >= deadline {
+                trace!(%offset, "Timed out waiting for ReadySet to replicate writes");
+                return false;
+            }
+            tokio::time::sleep(REPLICATION_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Wait for ReadySet to replicate up to the current transaction's watermark, for at most
+    /// `timeout`, first recording the upstream's current replication log position as the
+    /// watermark if this is the transaction's first read. See the [module-level
+    /// documentation](self#consistent-transaction-reads).
+    ///
+    /// Returns `false` if the watermark couldn't be recorded or ReadySet didn't reach it in time,
+    /// in which case the read should be served by the upstream instead.
+    async fn wait_for_transaction_watermark(
+        upstream: &mut Option<DB>,
+        noria: &mut NoriaConnector,
+        watermark: &mut Option<ReplicationOffset>,
+        timeout: Option<Duration>,
+    ) -> bool {
+        if watermark.is_none() {
+            Self::update_replication_ticket(upstream, watermark).await;
+        }
+        if watermark.is_none() {
+            return false;
+        }
+        // Every read in the transaction waits for the same watermark, so don't clear it once
+        // ReadySet has caught up
+        Self::wait_for_replication(noria, &mut watermark.clone(), timeout).await
+    }
+
+    /// Returns `true` if `query` is a read inside a transaction that should be served by ReadySet
+    /// rather than proxied. See the [module-level
+    /// documentation](self#consistent-transaction-reads).
+    fn is_consistent_transaction_read(&self, query: &SqlQuery) -> bool {
+        self.consistent_transaction_reads.is_some()
+            && self.proxy_state.is_in_transaction()
+            && !self.transaction_wrote
+            && matches!(query, SqlQuery::Select(_))
+    }
+
+    /// Handles a `READYSET FLUSH WAIT` statement by waiting for ReadySet to replicate every write
+    /// committed to the upstream database so far, if there is one, and then for every write
+    /// ReadySet has replicated to propagate through the dataflow graph to its caches
+    async fn flush_wait(&mut self) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        let deadline = Instant::now() + FLUSH_WAIT_TIMEOUT;
+        let mut offset = None;
+        Self::update_replication_ticket(&mut self.upstream, &mut offset).await;
+        if !Self::wait_for_replication(&mut self.noria, &mut offset, Some(FLUSH_WAIT_TIMEOUT)).await
+        {
+            return Err(ReadySetError::FlushTimeout);
+        }
+
+        self.noria
+            .flush_wait(deadline.saturating_duration_since(Instant::now()))
+            .await?;
+        Ok(noria_connector::QueryResult::Empty)
+    }
+
     /// Should only be called with a SqlQuery that is of type StartTransaction, Commit, or
     /// Rollback. Used to handle transaction boundary queries.
     pub async fn handle_transaction_boundaries(
@@ -1269,16 +1906,27 @@ where
             SqlQuery::StartTransaction(_) => {
                 let result = QueryResult::Upstream(upstream.start_tx().await?);
                 self.proxy_state.start_transaction();
+                self.transaction_watermark = None;
+                self.transaction_wrote = false;
                 Ok(result)
             }
             SqlQuery::Commit(_) => {
                 let result = QueryResult::Upstream(upstream.commit().await?);
                 self.proxy_state.end_transaction();
+                self.transaction_watermark = None;
+                if self.read_your_writes.is_some() {
+                    Self::update_replication_ticket(
+                        &mut self.upstream,
+                        &mut self.replication_ticket,
+                    )
+                    .await;
+                }
                 Ok(result)
             }
             SqlQuery::Rollback(_) => {
                 let result = QueryResult::Upstream(upstream.rollback().await?);
                 self.proxy_state.end_transaction();
+                self.transaction_watermark = None;
                 Ok(result)
             }
             _ => {
@@ -1331,9 +1979,33 @@ where
         self.noria.handle_create_cached_query(name, &stmt).await?;
         self.query_status_cache
             .update_query_migration_state(&stmt, MigrationState::Successful);
+        self.query_status_cache.allow_caching(&stmt);
         Ok(noria_connector::QueryResult::Empty)
     }
 
+    /// Returns the query that the body of a `CREATE CACHE` statement refers to, looking it up by
+    /// id in the query status cache if necessary
+    fn cache_statement(&self, inner: &CacheInner) -> ReadySetResult<SelectStatement> {
+        match inner {
+            CacheInner::Statement(st) => Ok(*st.clone()),
+            CacheInner::Id(id) => self
+                .query_status_cache
+                .query(id.as_str())
+                .ok_or_else(|| ReadySetError::NoQueryForId { id: id.to_string() }),
+        }
+    }
+
+    /// Handles an `EXPLAIN CREATE CACHE` request, by planning the dataflow for the query without
+    /// creating the cache
+    async fn explain_create_cache(
+        &mut self,
+        name: Option<&str>,
+        mut stmt: SelectStatement,
+    ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        rewrite::process_query(&mut stmt)?;
+        self.noria.explain_create_cache(name, &stmt).await
+    }
+
     /// Forwards a `DROP CACHE` request to ReadySet
     async fn drop_cached_query(
         &mut self,
@@ -1344,11 +2016,31 @@ where
         if let Some(stmt) = maybe_select_statement {
             self.query_status_cache
                 .update_query_migration_state(&stmt, MigrationState::Pending);
+            self.query_status_cache.disallow_caching(&stmt);
             self.invalidate_prepared_statments_cache(&stmt);
         }
         Ok(noria_connector::QueryResult::Empty)
     }
 
+    /// Handles a `DROP ALL CACHES` request, dropping every cached query whose text (as shown by
+    /// `SHOW CACHES`) matches `pattern`, or every cached query if there's no pattern
+    async fn drop_all_caches(
+        &mut self,
+        pattern: Option<&str>,
+    ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        let pattern = pattern.map(|pattern| LikePattern::new(pattern, CaseInsensitive));
+        for (name, stmt) in self.noria.cached_queries().await? {
+            if let Some(pattern) = &pattern {
+                if !pattern.matches(&anonymized_query_text(&stmt)) {
+                    continue;
+                }
+            }
+            self.drop_cached_query(&name).await?;
+            self.query_status_cache.disallow_caching(&stmt);
+        }
+        Ok(noria_connector::QueryResult::Empty)
+    }
+
     /// Responds to a `SHOW PROXIED QUERIES` query
     async fn show_proxied_queries(
         &mut self,
@@ -1373,16 +2065,18 @@ where
                 create_dummy_column("query id"),
                 create_dummy_column("proxied query"),
                 create_dummy_column("readyset supported"),
+                create_dummy_column("proxied reason"),
             ]),
 
             columns: Cow::Owned(vec![
                 "query id".into(),
                 "proxied query".into(),
                 "readyset supported".into(),
+                "proxied reason".into(),
             ]),
         };
 
-        let data = queries
+        let mut data = queries
             .into_iter()
             .map(
                 |DeniedQuery {
@@ -1396,21 +2090,39 @@ where
                         MigrationState::Unsupported => "unsupported",
                     }
                     .to_string();
+                    let reason = status
+                        .unsupported_reason
+                        .map(|reason| reason.to_string())
+                        .unwrap_or_default();
                     rewrite::anonymize_literals(&mut query);
                     vec![
                         DataType::from(id),
                         DataType::from(query.to_string()),
                         DataType::from(s),
+                        DataType::from(reason),
                     ]
                 },
             )
             .collect::<Vec<_>>();
+        // Queries ReadySet couldn't parse are always proxied, but aren't in the deny list since
+        // they have no parsed form
+        data.extend(self.query_status_cache.unparsed_queries().into_iter().map(
+            |UnparsedQuery { id, query, reason }| {
+                vec![
+                    DataType::from(id),
+                    DataType::from(query),
+                    DataType::from("unsupported"),
+                    DataType::from(reason.to_string()),
+                ]
+            },
+        ));
         let data = vec![Results::new(
             data,
             Arc::new([
                 "query id".into(),
                 "proxied query".into(),
                 "readyset supported".into(),
+                "proxied reason".into(),
             ]),
         )];
         Ok(noria_connector::QueryResult::Select {
@@ -1419,72 +2131,444 @@ where
         })
     }
 
-    async fn query_noria_extensions<'a>(
-        &'a mut self,
-        query: &'a SqlQuery,
-        event: &mut QueryExecutionEvent,
-    ) -> Option<ReadySetResult<noria_connector::QueryResult<'static>>> {
-        event.sql_type = SqlQueryType::Other; // Those will get cleared if it was not destined to ReadySet
-        event.destination = Some(QueryDestination::Readyset);
-
-        let _t = event.start_noria_timer();
-
-        let res = match query {
-            SqlQuery::Explain(nom_sql::ExplainStatement::LastStatement) => {
-                self.explain_last_statement()
-            }
-            SqlQuery::Explain(nom_sql::ExplainStatement::Graphviz { simplified }) => {
-                self.noria.graphviz(*simplified).await
-            }
-            SqlQuery::CreateCache(CreateCacheStatement { name, inner }) => {
-                let st = match inner {
-                    CacheInner::Statement(st) => *st.clone(),
-                    CacheInner::Id(id) => match self.query_status_cache.query(id.as_str()) {
-                        Some(st) => st,
-                        None => {
-                            return Some(Err(ReadySetError::NoQueryForId { id: id.to_string() }))
-                        }
-                    },
-                };
-                self.create_cached_query(name.as_deref(), st).await
-            }
-            SqlQuery::DropCache(DropCacheStatement { name }) => {
-                self.drop_cached_query(name.as_str()).await
-            }
-            SqlQuery::Show(ShowStatement::CachedQueries) => self.noria.verbose_outputs().await,
-            SqlQuery::Show(ShowStatement::ReadySetStatus) => self.noria.readyset_status().await,
-            SqlQuery::Show(ShowStatement::ProxiedQueries) => self.show_proxied_queries().await,
-            _ => {
-                drop(_t);
-                event.readyset_duration.take(); // Clear readyset timer, since it was not a readyset request
-                return None;
-            }
+    /// Responds to a `SHOW READYSET QUERY STATS` query
+    fn show_query_stats(&self) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        const COLUMNS: [&str; 10] = [
+            "query id",
+            "query text",
+            "cached count",
+            "cached p50 ms",
+            "cached p90 ms",
+            "cached p99 ms",
+            "proxied count",
+            "proxied p50 ms",
+            "proxied p90 ms",
+            "proxied p99 ms",
+        ];
+        let create_dummy_column = |n: &str| ColumnSchema {
+            spec: nom_sql::ColumnSpecification {
+                column: nom_sql::Column {
+                    name: n.into(),
+                    table: None,
+                },
+                sql_type: nom_sql::SqlType::Text,
+                constraints: vec![],
+                comment: None,
+            },
+            base: None,
+        };
+        let select_schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(COLUMNS.iter().map(|&c| create_dummy_column(c)).collect()),
+            columns: Cow::Owned(COLUMNS.iter().map(|&c| c.into()).collect()),
         };
 
-        Some(res)
-    }
-
-    async fn query_adhoc_select(
-        &mut self,
-        original_query: &str,
-        stmt: &SelectStatement,
-        event: &mut QueryExecutionEvent,
-    ) -> Result<QueryResult<'_, DB>, DB::Error> {
-        event.sql_type = SqlQueryType::Read;
-        if self.query_log_ad_hoc_queries {
-            event.query = Some(Arc::new(SqlQuery::Select(stmt.clone())));
+        let mut stats = self.query_status_cache.query_stats();
+        // Show the most frequently executed queries first
+        stats.sort_by_key(|s| {
+            std::cmp::Reverse(s.latencies.cached.count() + s.latencies.proxied.count())
+        });
+        let data = stats
+            .into_iter()
+            .map(
+                |QueryStats {
+                     id,
+                     query,
+                     latencies,
+                 }| {
+                    let mut row = vec![DataType::from(id), DataType::from(query)];
+                    for histogram in [&latencies.cached, &latencies.proxied] {
+                        row.push(DataType::from(histogram.count().to_string()));
+                        row.extend([0.5, 0.9, 0.99].into_iter().map(|q| {
+                            histogram
+                                .quantile(q)
+                                .map(|latency| {
+                                    DataType::from(format!("{:.3}", latency.as_secs_f64() * 1000.0))
+                                })
+                                .unwrap_or(DataType::None)
+                        }));
+                    }
+                    row
+                },
+            )
+            .collect();
+        let data = vec![Results::new(
+            data,
+            COLUMNS.iter().map(|&c| SqlIdentifier::from(c)).collect(),
+        )];
+        Ok(noria_connector::QueryResult::Select {
+            data,
+            select_schema,
+        })
+    }
+
+    /// Responds to a `SHOW READYSET SUGGESTIONS` query, listing the proxied reads that would
+    /// benefit the most from being cached.
+    ///
+    /// The estimated memory cost of caching a read is the size of the data in the tables it reads
+    /// from, which is what a fully materialized cache could grow to hold at most. Partially
+    /// materialized caches only hold the results for the keys that are actually read, so will
+    /// typically use much less.
+    async fn show_suggestions(&mut self) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        const COLUMNS: [&str; 7] = [
+            "query id",
+            "proxied query",
+            "proxied count",
+            "proxied p50 ms",
+            "total proxied ms",
+            "estimated memory bytes",
+            "suggestion",
+        ];
+        let create_dummy_column = |n: &str| ColumnSchema {
+            spec: nom_sql::ColumnSpecification {
+                column: nom_sql::Column {
+                    name: n.into(),
+                    table: None,
+                },
+                sql_type: nom_sql::SqlType::Text,
+                constraints: vec![],
+                comment: None,
+            },
+            base: None,
+        };
+        let select_schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(COLUMNS.iter().map(|&c| create_dummy_column(c)).collect()),
+            columns: Cow::Owned(COLUMNS.iter().map(|&c| c.into()).collect()),
+        };
+
+        let suggestions = self.query_status_cache.cache_suggestions();
+        // Still list the suggestions if ReadySet can't be reached, just without an estimate of
+        // their memory cost
+        let table_sizes = if suggestions.is_empty() {
+            None
+        } else {
+            self.noria
+                .table_sizes()
+                .await
+                .map_err(|error| warn!(%error, "Could not get the sizes of tables"))
+                .ok()
+        };
+        let data = suggestions
+            .into_iter()
+            .map(
+                |CacheSuggestion {
+                     id,
+                     query,
+                     tables,
+                     proxied,
+                 }| {
+                    let memory = table_sizes.as_ref().map(|sizes| {
+                        tables
+                            .iter()
+                            .filter_map(|table| sizes.get(table))
+                            .sum::<u64>()
+                    });
+                    vec![
+                        DataType::from(id.clone()),
+                        DataType::from(query),
+                        DataType::from(proxied.count().to_string()),
+                        proxied
+                            .quantile(0.5)
+                            .map(|latency| {
+                                DataType::from(format!("{:.3}", latency.as_secs_f64() * 1000.0))
+                            })
+                            .unwrap_or(DataType::None),
+                        DataType::from(format!("{:.3}", proxied.total().as_secs_f64() * 1000.0)),
+                        memory
+                            .map(|bytes| DataType::from(bytes.to_string()))
+                            .unwrap_or(DataType::None),
+                        DataType::from(format!("CREATE CACHE FROM {}", id)),
+                    ]
+                },
+            )
+            .collect();
+        let data = vec![Results::new(
+            data,
+            COLUMNS.iter().map(|&c| SqlIdentifier::from(c)).collect(),
+        )];
+        Ok(noria_connector::QueryResult::Select {
+            data,
+            select_schema,
+        })
+    }
+
+    /// Answers `stmt` from the schemas of ReadySet's base tables, if it's a query of one of the
+    /// catalog tables emulated by [`crate::catalog`] that's simple enough for us to evaluate
+    async fn query_catalog(
+        &mut self,
+        stmt: &SelectStatement,
+        event: &mut QueryExecutionEvent,
+    ) -> Option<ReadySetResult<noria_connector::QueryResult<'static>>> {
+        let table = CatalogTable::from_query(self.dialect, stmt)?;
+        event.sql_type = SqlQueryType::Read;
+        event.destination = Some(QueryDestination::Readyset);
+        let _t = event.start_noria_timer();
+
+        let tables = match self.noria.table_schemas().await {
+            Ok(tables) => tables,
+            Err(e) => return Some(Err(e)),
+        };
+        let catalog = Catalog {
+            dialect: self.dialect,
+            database: self.database().unwrap_or(DEFAULT_DATABASE).to_owned(),
+            tables,
+        };
+        catalog.query(table, stmt).map(Ok)
+    }
+
+    /// Answers `query` if it's a `VALUES` table constructor, or a `SELECT` from one, made of
+    /// expressions simple enough for us to evaluate. These are constant relations, so they can be
+    /// answered without ReadySet's schema or the upstream database.
+    fn query_values(
+        &mut self,
+        query: &SqlQuery,
+        event: &mut QueryExecutionEvent,
+    ) -> Option<ReadySetResult<noria_connector::QueryResult<'static>>> {
+        let catalog = Catalog {
+            dialect: self.dialect,
+            database: self.database().unwrap_or(DEFAULT_DATABASE).to_owned(),
+            tables: vec![],
+        };
+        let res = match query {
+            SqlQuery::Values(values) => catalog.values(values)?,
+            SqlQuery::Select(stmt) => catalog.query_values(stmt)?,
+            _ => return None,
+        };
+        event.sql_type = SqlQueryType::Read;
+        event.destination = Some(QueryDestination::Readyset);
+        Some(Ok(res))
+    }
+
+    async fn query_noria_extensions<'a>(
+        &'a mut self,
+        query: &'a SqlQuery,
+        event: &mut QueryExecutionEvent,
+    ) -> Option<ReadySetResult<noria_connector::QueryResult<'static>>> {
+        event.sql_type = SqlQueryType::Other; // Those will get cleared if it was not destined to ReadySet
+        event.destination = Some(QueryDestination::Readyset);
+
+        let _t = event.start_noria_timer();
+
+        let res = match query {
+            SqlQuery::Explain(nom_sql::ExplainStatement::LastStatement) => {
+                self.explain_last_statement()
+            }
+            SqlQuery::Explain(nom_sql::ExplainStatement::Graphviz {
+                simplified,
+                for_cache,
+            }) => self.noria.graphviz(*simplified, for_cache.as_ref()).await,
+            SqlQuery::Explain(nom_sql::ExplainStatement::Reuse { for_cache }) => {
+                self.noria.explain_reuse(for_cache).await
+            }
+            SqlQuery::Explain(nom_sql::ExplainStatement::CreateCache(CreateCacheStatement {
+                name,
+                inner,
+            })) => match self.cache_statement(inner) {
+                Ok(st) => self.explain_create_cache(name.as_deref(), st).await,
+                Err(e) => Err(e),
+            },
+            SqlQuery::CreateCache(CreateCacheStatement { name, inner }) => {
+                match self.cache_statement(inner) {
+                    Ok(st) => self.create_cached_query(name.as_deref(), st).await,
+                    Err(e) => Err(e),
+                }
+            }
+            SqlQuery::DropCache(DropCacheStatement { name }) => {
+                self.drop_cached_query(name.as_str()).await
+            }
+            SqlQuery::DropAllCaches(DropAllCachesStatement { pattern }) => {
+                self.drop_all_caches(pattern.as_deref()).await
+            }
+            SqlQuery::AlterCache(AlterCacheStatement { name, prefill }) => {
+                self.noria.prefill_cache(name.as_str(), prefill).await
+            }
+            SqlQuery::FlushWait(_) => self.flush_wait().await,
+            SqlQuery::Show(ShowStatement::CachedQueries) => self.noria.verbose_outputs().await,
+            SqlQuery::Show(ShowStatement::ReadySetStatus) => {
+                let upstream_offset = match self.upstream.as_mut() {
+                    Some(upstream) => upstream.replication_offset().await.unwrap_or_else(|error| {
+                        warn!(%error, "Error getting replication offset from upstream");
+                        None
+                    }),
+                    None => None,
+                };
+                self.noria.readyset_status(upstream_offset).await
+            }
+            SqlQuery::Show(ShowStatement::ReadySetReplicationStatus) => {
+                self.noria.replication_status().await
+            }
+            SqlQuery::Show(ShowStatement::ProxiedQueries) => self.show_proxied_queries().await,
+            SqlQuery::Show(ShowStatement::ReadySetQueryStats) => self.show_query_stats(),
+            SqlQuery::Show(ShowStatement::ReadySetMemory) => self.noria.readyset_memory().await,
+            SqlQuery::Show(ShowStatement::ReadySetSuggestions) => self.show_suggestions().await,
+            SqlQuery::Show(ShowStatement::ReadySetStatistics) => {
+                self.noria.readyset_statistics().await
+            }
+            SqlQuery::AlterReadyset(AlterReadysetStatement::CompactTable(table)) => {
+                self.noria.compact_table(table.name.as_str()).await
+            }
+            SqlQuery::AlterReadyset(AlterReadysetStatement::SetCompression {
+                table,
+                algorithm,
+                dictionary,
+            }) => {
+                self.noria
+                    .set_table_compression(table.name.as_str(), *algorithm, *dictionary)
+                    .await
+            }
+            SqlQuery::AlterReadyset(AlterReadysetStatement::ShardTable { table, column }) => {
+                self.noria
+                    .set_table_sharding(table.name.as_str(), column.as_str())
+                    .await
+            }
+            _ => {
+                drop(_t);
+                event.readyset_duration.take(); // Clear readyset timer, since it was not a readyset request
+                return None;
+            }
+        };
+
+        Some(res)
+    }
+
+    /// Handles a `DECLARE ... CURSOR` statement. If the cursor's query is executed against
+    /// ReadySet, the cursor is kept in the backend so that its rows can be returned by later
+    /// `FETCH` statements; otherwise the statement will have been proxied upstream.
+    async fn declare_cursor(
+        &mut self,
+        query: &str,
+        stmt: &DeclareCursorStatement,
+        event: &mut QueryExecutionEvent,
+    ) -> Result<QueryResult<'static, DB>, DB::Error> {
+        if self.cursors.contains_key(&stmt.name) {
+            return Err(ReadySetError::CursorAlreadyExists {
+                name: stmt.name.to_string(),
+            }
+            .into());
+        }
+
+        // Cursors hold all of their remaining rows, so read the entire result set up front
+        let res = match self.query_adhoc_select(query, &stmt.query, event).await? {
+            QueryResult::Noria(res) => QueryResult::Noria(res.buffered().await?),
+            res => res,
+        };
+        match res.into_owned() {
+            QueryResult::Noria(noria_connector::QueryResult::Select {
+                data,
+                select_schema,
+            }) => {
+                self.cursors
+                    .insert(stmt.name.clone(), Cursor::new(data, select_schema));
+                Ok(QueryResult::Noria(noria_connector::QueryResult::Empty))
+            }
+            QueryResult::Noria(_) => internal!("SELECT for cursor did not return a result set"),
+            res @ QueryResult::Upstream(_) => Ok(res),
+        }
+    }
+
+    /// Handles a `PREPARE` statement by preparing its statement like [`Backend::prepare`] does,
+    /// and remembering the new statement's identifier so that `EXECUTE` can refer to it by name
+    async fn prepare_named(
+        &mut self,
+        stmt: &PrepareStatement,
+    ) -> Result<QueryResult<'static, DB>, DB::Error> {
+        if self.named_statements.contains_key(&stmt.name) {
+            return Err(ReadySetError::PreparedStatementAlreadyExists {
+                name: stmt.name.to_string(),
+            }
+            .into());
+        }
+
+        self.prepare(&stmt.statement).await?;
+        let id = self.last_prepared_id();
+        self.named_statements
+            .insert(stmt.name.clone(), (id, stmt.types.clone()));
+        Ok(QueryResult::Noria(noria_connector::QueryResult::Empty))
+    }
+
+    /// Handles an `EXECUTE` statement by executing the statement prepared with `PREPARE` under its
+    /// name, coercing its arguments to the parameter types given in the `PREPARE` if there were any
+    async fn execute_named(
+        &mut self,
+        stmt: &ExecuteStatement,
+    ) -> Result<QueryResult<'_, DB>, DB::Error> {
+        let (id, types) = self.named_statements.get(&stmt.name).ok_or_else(|| {
+            ReadySetError::PreparedStatementNotFound {
+                name: stmt.name.to_string(),
+            }
+        })?;
+        let id = *id;
+        let params = stmt
+            .arguments
+            .iter()
+            .enumerate()
+            .map(|(idx, arg)| {
+                let value = match arg {
+                    Expression::Literal(lit) => DataType::try_from(lit)?,
+                    _ => unsupported!("Arguments to EXECUTE must be literals"),
+                };
+                match types.get(idx) {
+                    Some(ty) => value.coerce_to(ty),
+                    None => Ok(value),
+                }
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+
+        self.execute(id, &params).await
+    }
+
+    /// Handles a `DEALLOCATE` statement. The statements themselves stay prepared, since their
+    /// identifiers are positions in `prepared_statements`, but can no longer be executed by name.
+    fn deallocate(
+        &mut self,
+        stmt: &DeallocateStatement,
+    ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        match stmt {
+            DeallocateStatement::Statement(name) => {
+                if self.named_statements.remove(name).is_none() {
+                    return Err(ReadySetError::PreparedStatementNotFound {
+                        name: name.to_string(),
+                    });
+                }
+            }
+            DeallocateStatement::All => self.named_statements.clear(),
+        }
+        Ok(noria_connector::QueryResult::Empty)
+    }
+
+    async fn query_adhoc_select(
+        &mut self,
+        original_query: &str,
+        stmt: &SelectStatement,
+        event: &mut QueryExecutionEvent,
+    ) -> Result<QueryResult<'_, DB>, DB::Error> {
+        event.sql_type = SqlQueryType::Read;
+        if self.query_log_ad_hoc_queries {
+            event.query = Some(Arc::new(SqlQuery::Select(stmt.clone())));
         }
 
+        // Queries with a `readyset=cached` hint are migrated in the request path, like in
+        // InRequestPath mode
+        let migrate = self.migration_mode == MigrationMode::InRequestPath
+            || nom_sql::readyset_hint(original_query) == Some(ReadysetHint::Cached);
+
         // TODO(vlad): don't rewrite multiple times, it is wasteful
         let mut rewritten = stmt.clone();
         let mut status = if rewrite::process_query(&mut rewritten).is_ok() {
+            event.query_id = Some(hash_to_query_id(hash_select_query(&rewritten)));
             self.query_status_cache.query_status(&rewritten)
         } else {
             QueryStatus {
                 migration_state: MigrationState::Unsupported,
                 execution_info: None,
+                unsupported_reason: None,
             }
         };
+        // Queries that the caching policy doesn't allow to be cached are always proxied
+        if self.has_fallback() && !self.query_status_cache.is_cacheable(&rewritten) {
+            return self.query_fallback(original_query, event).await;
+        }
         let original_status = status.clone();
         let did_work = if let Some(ref mut i) = status.execution_info {
             i.reset_if_exceeded_recovery(
@@ -1495,9 +2579,7 @@ where
             false
         };
 
-        if self.has_fallback()
-            && (self.migration_mode != MigrationMode::InRequestPath
-                && status.migration_state != MigrationState::Successful)
+        if self.has_fallback() && !migrate && status.migration_state != MigrationState::Successful
             || (status.migration_state == MigrationState::Unsupported)
             || (self.has_fallback()
                 && status
@@ -1516,18 +2598,42 @@ where
             return self.query_fallback(original_query, event).await;
         }
 
+        let caught_up = if self.consistent_transaction_reads.is_some()
+            && self.proxy_state.is_in_transaction()
+        {
+            Self::wait_for_transaction_watermark(
+                &mut self.upstream,
+                &mut self.noria,
+                &mut self.transaction_watermark,
+                self.consistent_transaction_reads,
+            )
+            .await
+        } else {
+            Self::wait_for_replication(
+                &mut self.noria,
+                &mut self.replication_ticket,
+                self.read_your_writes,
+            )
+            .await
+        };
+        if self.has_fallback() && !caught_up {
+            return self.query_fallback(original_query, event).await;
+        }
+
         let noria_res = {
             event.destination = Some(QueryDestination::Readyset);
             let start = Instant::now();
-            let res = self
-                .noria
-                .handle_select(
-                    stmt.clone(),
-                    self.ticket.clone(),
-                    self.migration_mode == MigrationMode::InRequestPath,
-                    event,
-                )
-                .await;
+            // Only reads of queries that are already cached time out, so that the migration
+            // creating the cache isn't abandoned
+            let read_timeout = self.read_timeout.filter(|_| {
+                self.has_fallback() && status.migration_state == MigrationState::Successful
+            });
+            let res = with_read_timeout(
+                read_timeout,
+                self.noria
+                    .handle_select(stmt.clone(), self.ticket.clone(), migrate, event),
+            )
+            .await;
             event.readyset_duration = Some(start.elapsed());
             res
         };
@@ -1562,8 +2668,9 @@ where
 
                 if noria_err.caused_by_view_not_found() {
                     status.migration_state = MigrationState::Pending;
-                } else if noria_err.caused_by_unsupported() {
+                } else if let Some(reason) = UnsupportedReason::from_error(&rewritten, &noria_err) {
                     status.migration_state = MigrationState::Unsupported;
+                    status.unsupported_reason = Some(reason);
                 };
 
                 if status != original_status {
@@ -1592,18 +2699,49 @@ where
     pub async fn query(&mut self, query: &str) -> Result<QueryResult<'_, DB>, DB::Error> {
         let mut event = QueryExecutionEvent::new(EventType::Query);
         let query_log_sender = self.query_log_sender.clone();
-        let slowlog = self.slowlog;
+        let slowlog = self.slowlog.clone();
+        let query_status_cache = self.query_status_cache;
 
-        let parse_result = {
+        let parsed_query = {
             let _t = event.start_parse_timer();
             self.parse_query(query)
         };
+        let parse_result = parsed_query.as_deref();
+
+        if let Ok(SqlQuery::CloseCursor(CloseCursorStatement::All)) = parse_result {
+            self.cursors.clear();
+        }
+        // Executing a prepared statement keeps track of whether it wrote itself
+        if self.proxy_state.is_in_transaction()
+            && !matches!(
+                parse_result,
+                Ok(SqlQuery::Select(_)
+                    | SqlQuery::Prepare(_)
+                    | SqlQuery::Execute(_)
+                    | SqlQuery::Deallocate(_))
+            )
+        {
+            self.transaction_wrote = true;
+        }
+        // Temporary tables created or dropped by the statement, which are only tracked once it
+        // has succeeded
+        let (created_temporary_table, dropped_tables) = match parse_result {
+            Ok(SqlQuery::CreateTable(CreateTableStatement {
+                table,
+                temporary: true,
+                ..
+            })) => (Some(table.name.clone()), vec![]),
+            Ok(SqlQuery::DropTable(stmt)) => {
+                (None, stmt.tables.iter().map(|t| t.name.clone()).collect())
+            }
+            _ => (None, vec![]),
+        };
 
         let result = match parse_result {
             // Parse error, but no fallback exists
             Err(e) if !self.has_fallback() => {
                 error!("{}", e);
-                Err(e.into())
+                Err(e.clone().into())
             }
             // Parse error, send to fallback
             Err(e) => {
@@ -1612,9 +2750,60 @@ where
                 }
                 self.query_fallback(query, &mut event).await
             }
+            // Cursors declared against ReadySet are read from ReadySet, even inside a transaction
+            Ok(SqlQuery::Fetch(FetchStatement { name, count }))
+                if let Some(cursor) = self.cursors.get_mut(name) =>
+            {
+                event.sql_type = SqlQueryType::Read;
+                event.destination = Some(QueryDestination::Readyset);
+                Ok(QueryResult::Noria(cursor.fetch(*count)))
+            }
+            Ok(SqlQuery::CloseCursor(CloseCursorStatement::Cursor(name)))
+                if self.cursors.contains_key(name) =>
+            {
+                self.cursors.remove(name);
+                event.destination = Some(QueryDestination::Readyset);
+                Ok(QueryResult::Noria(noria_connector::QueryResult::Empty))
+            }
+            // Statements prepared with `PREPARE` go through the same machinery as the extended
+            // protocol, which decides where to prepare and execute them
+            Ok(SqlQuery::Prepare(stmt)) => self.prepare_named(stmt).await,
+            Ok(SqlQuery::Execute(stmt)) => self.execute_named(stmt).await,
+            Ok(SqlQuery::Deallocate(stmt)) => self
+                .deallocate(stmt)
+                .map(QueryResult::Noria)
+                .map_err(Into::into),
             // Parsed but proxy mode means we should send upstream
-            Ok(_) if self.proxy_state.should_proxy() => self.query_fallback(query, &mut event).await,
-            Ok(ref parsed_query) if Handler::requires_fallback(parsed_query) => {
+            Ok(parsed_query)
+                if self.proxy_state.should_proxy()
+                    && !self.is_consistent_transaction_read(parsed_query) =>
+            {
+                self.query_fallback(query, &mut event).await
+            }
+            // Reads with a `readyset=proxy` hint are always sent upstream
+            Ok(SqlQuery::Select(_) | SqlQuery::DeclareCursor(_))
+                if self.has_fallback()
+                    && nom_sql::readyset_hint(query) == Some(ReadysetHint::Proxy) =>
+            {
+                self.query_fallback(query, &mut event).await
+            }
+            // Temporary tables only exist on the upstream
+            Ok(parsed_query)
+                if self.has_fallback() && self.refers_to_temporary_table(parsed_query) =>
+            {
+                self.query_fallback(query, &mut event).await
+            }
+            // Samples of tables are different on every execution, so they can't be cached
+            Ok(parsed_query) if self.has_fallback() && samples_tables(parsed_query) => {
+                self.query_fallback(query, &mut event).await
+            }
+            // ReadySet can't make sense of calls to functions with arguments it couldn't parse
+            Ok(parsed_query)
+                if self.has_fallback() && calls_unsupported_functions(parsed_query) =>
+            {
+                self.query_fallback(query, &mut event).await
+            }
+            Ok(parsed_query) if Handler::requires_fallback(parsed_query) => {
                 if self.has_fallback() {
                     // Query requires a fallback and we can send it to fallback
                     self.query_fallback(query, &mut event).await
@@ -1625,14 +2814,30 @@ where
                         .map_err(Into::into)
                 }
             }
-            Ok(ref parsed_query) if let Some(noria_extension) = self.query_noria_extensions(parsed_query, &mut event).await => {
+            // Queries of the catalog tables we emulate are answered from ReadySet's own schema
+            Ok(SqlQuery::Select(stmt)) if let Some(res) = self.query_catalog(stmt, &mut event).await => {
+                res.map(Into::into).map_err(Into::into)
+            }
+            // Constant VALUES relations are evaluated by the adapter
+            Ok(parsed_query @ (SqlQuery::Values(_) | SqlQuery::Select(_))) if let Some(res) = self.query_values(parsed_query, &mut event) => {
+                res.map(Into::into).map_err(Into::into)
+            }
+            Ok(parsed_query) if let Some(noria_extension) = self.query_noria_extensions(parsed_query, &mut event).await => {
                 noria_extension.map(Into::into).map_err(Into::into)
             }
-            Ok(SqlQuery::Select(ref stmt)) => self.query_adhoc_select(query, stmt, &mut event).await,
+            Ok(SqlQuery::Select(stmt)) => self.query_adhoc_select(query, stmt, &mut event).await,
+            Ok(SqlQuery::DeclareCursor(stmt)) => self.declare_cursor(query, stmt, &mut event).await,
             Ok(parsed_query) => self.query_adhoc_non_select(query, &mut event, parsed_query).await,
         }
         .map(|r| r.into_owned());
 
+        if result.is_ok() {
+            self.temporary_tables.extend(created_temporary_table);
+            for table in &dropped_tables {
+                self.temporary_tables.remove(table);
+            }
+        }
+
         self.last_query = event.destination.map(|d| QueryInfo {
             destination: d,
             noria_error: event
@@ -1641,7 +2846,12 @@ where
                 .map(|e| e.to_string())
                 .unwrap_or_default(),
         });
-        log_query(query_log_sender.as_ref(), event, slowlog);
+        log_query(
+            query_log_sender.as_ref(),
+            event,
+            slowlog.as_ref(),
+            query_status_cache,
+        );
 
         result
     }
@@ -1666,34 +2876,91 @@ where
         &self.ticket
     }
 
-    fn parse_query(&mut self, query: &str) -> ReadySetResult<SqlQuery> {
-        match self.parsed_query_cache.entry(query.to_owned()) {
-            Entry::Occupied(entry) => Ok(entry.get().clone()),
-            Entry::Vacant(entry) => {
-                trace!("Parsing query");
-                match nom_sql::parse_query(self.dialect, query) {
-                    Ok(parsed_query) => Ok(entry.insert(parsed_query).clone()),
-                    Err(_) => {
-                        // error is useless anyway
-                        error!("query can't be parsed: \"{}\"", Sensitive(&query));
-                        Err(ReadySetError::UnparseableQuery {
-                            query: query.to_string(),
-                        })
-                    }
+    fn parse_query(&mut self, query: &str) -> ReadySetResult<Arc<SqlQuery>> {
+        let mut hasher = DefaultHasher::new();
+        query.hash(&mut hasher);
+        let key = hasher.finish();
+
+        // Another connection may have run DDL since we parsed the queries we have
+        let schema_generation = self.schema_generation.load(Ordering::Acquire);
+        if schema_generation != self.parsed_query_cache_generation {
+            self.parsed_query_cache.clear();
+            self.parsed_query_cache_generation = schema_generation;
+        }
+
+        if let Some((text, parsed_query)) = self.parsed_query_cache.get(&key) {
+            if text == query {
+                return Ok(Arc::clone(parsed_query));
+            }
+        }
+
+        trace!("Parsing query");
+        match nom_sql::parse_query(self.dialect, query) {
+            Ok(parsed_query) => {
+                let parsed_query = Arc::new(parsed_query);
+                if Self::invalidates_parsed_queries(&parsed_query) {
+                    self.parsed_query_cache.clear();
+                    self.parsed_query_cache_generation =
+                        self.schema_generation.fetch_add(1, Ordering::AcqRel) + 1;
+                } else if self.parsed_query_cache.cap() > 0 {
+                    self.parsed_query_cache
+                        .put(key, (query.to_owned(), Arc::clone(&parsed_query)));
+                }
+                Ok(parsed_query)
+            }
+            Err(_) => {
+                // error is useless anyway
+                error!("query can't be parsed: \"{}\"", Sensitive(&query));
+                if query
+                    .trim_start()
+                    .get(..6)
+                    .map_or(false, |kw| kw.eq_ignore_ascii_case("select"))
+                {
+                    self.query_status_cache.insert_unparsed(query, self.dialect);
                 }
+                Err(ReadySetError::UnparseableQuery {
+                    query: query.to_string(),
+                })
             }
         }
     }
 
+    /// Returns whether `query` changes the schema, in which case every previously parsed query is
+    /// dropped from the parsed query cache. DDL statements are never cached themselves, so running
+    /// the same one twice clears the cache both times.
+    fn invalidates_parsed_queries(query: &SqlQuery) -> bool {
+        matches!(
+            query,
+            SqlQuery::CreateTable(_)
+                | SqlQuery::CreateView(_)
+                | SqlQuery::AlterTable(_)
+                | SqlQuery::DropTable(_)
+                | SqlQuery::DropView(_)
+                | SqlQuery::RenameTable(_)
+        )
+    }
+
+    /// Returns whether `query` creates a temporary table, or refers to one created earlier on this
+    /// connection
+    fn refers_to_temporary_table(&self, query: &SqlQuery) -> bool {
+        match query {
+            SqlQuery::CreateTable(CreateTableStatement {
+                temporary: true, ..
+            }) => true,
+            _ if self.temporary_tables.is_empty() => false,
+            _ => referred_table_names(query)
+                .iter()
+                .any(|table| self.temporary_tables.contains(table)),
+        }
+    }
+
     #[instrument(level = "trace", name = "query", skip_all)]
     async fn query_adhoc_non_select<'a>(
         &'a mut self,
         query: &str,
         event: &mut QueryExecutionEvent,
-        parse_result: SqlQuery,
+        parsed_query: &SqlQuery,
     ) -> Result<QueryResult<'a, DB>, DB::Error> {
-        let parsed_query = &parse_result;
-
         // If we have an upstream then we will pass valid set statements across to that upstream.
         // If no upstream is present we will ignore the statement
         // Disallowed set statements always produce an error
@@ -1743,6 +3010,9 @@ where
             if let Some(ref mut upstream) = self.upstream {
                 match parsed_query {
                     SqlQuery::Select(_) => unreachable!("read path returns prior"),
+                    SqlQuery::Prepare(_) | SqlQuery::Execute(_) | SqlQuery::Deallocate(_) => {
+                        unreachable!("prepared statements are handled prior")
+                    }
                     SqlQuery::Insert(InsertStatement { table: t, .. })
                     | SqlQuery::Update(UpdateStatement { table: t, .. })
                     | SqlQuery::Delete(DeleteStatement { table: t, .. }) => {
@@ -1780,7 +3050,21 @@ where
                             destination: QueryDestination::Upstream,
                             noria_error: String::new(),
                         });
-                        Ok(QueryResult::Upstream(query_result?))
+                        let query_result = query_result?;
+
+                        if self.write_through && self.proxy_state.is_fallback() {
+                            Self::write_through(&mut self.upstream, &mut self.noria, parsed_query)
+                                .await;
+                        }
+                        if self.read_your_writes.is_some() && self.proxy_state.is_fallback() {
+                            Self::update_replication_ticket(
+                                &mut self.upstream,
+                                &mut self.replication_ticket,
+                            )
+                            .await;
+                        }
+
+                        Ok(QueryResult::Upstream(query_result))
                     }
 
                     // Table Create / Drop (RYW not supported)
@@ -1802,7 +3086,54 @@ where
                     SqlQuery::RenameTable(_) => {
                         unsupported!("{} not yet supported", parsed_query.query_type());
                     }
-                    SqlQuery::Set(_) | SqlQuery::CompoundSelect(_) | SqlQuery::Show(_) => {
+                    SqlQuery::Set(stmt) => {
+                        let res = match replayed_session_variables(stmt) {
+                            Some(variables) => {
+                                upstream.set_session_variables(query, variables).await
+                            }
+                            None => upstream.query(query).await,
+                        }
+                        .map(QueryResult::Upstream);
+                        self.last_query = Some(QueryInfo {
+                            destination: QueryDestination::Upstream,
+                            noria_error: String::new(),
+                        });
+                        res
+                    }
+                    SqlQuery::CompoundSelect(_)
+                    | SqlQuery::Values(_)
+                    | SqlQuery::Show(_)
+                    | SqlQuery::Fetch(_)
+                    | SqlQuery::CloseCursor(_) => {
+                        let res = upstream.query(query).await.map(QueryResult::Upstream);
+                        self.last_query = Some(QueryInfo {
+                            destination: QueryDestination::Upstream,
+                            noria_error: String::new(),
+                        });
+                        res
+                    }
+                    SqlQuery::Maintenance(stmt) => {
+                        let res = upstream.query(query).await.map(QueryResult::Upstream);
+                        self.last_query = Some(QueryInfo {
+                            destination: QueryDestination::Upstream,
+                            noria_error: String::new(),
+                        });
+                        if res.is_ok() && stmt.operation == MaintenanceOperation::Analyze {
+                            // The statistics are only informational, so failing to refresh them
+                            // shouldn't fail the statement
+                            if let Err(error) =
+                                self.noria.refresh_table_statistics(&stmt.tables).await
+                            {
+                                warn!(%error, "failed to refresh table statistics");
+                            }
+                        }
+                        res
+                    }
+                    SqlQuery::Merge(_) => {
+                        event.sql_type = SqlQueryType::Write;
+                        event.destination = Some(QueryDestination::Upstream);
+
+                        let _t = event.start_upstream_timer();
                         let res = upstream.query(query).await.map(QueryResult::Upstream);
                         self.last_query = Some(QueryInfo {
                             destination: QueryDestination::Upstream,
@@ -1829,7 +3160,14 @@ where
                             }
                         }
                     }
-                    SqlQuery::CreateCache(_) | SqlQuery::DropCache(_) | SqlQuery::Explain(_) => {
+                    SqlQuery::CreateCache(_)
+                    | SqlQuery::DropCache(_)
+                    | SqlQuery::DropAllCaches(_)
+                    | SqlQuery::AlterReadyset(_)
+                    | SqlQuery::AlterCache(_)
+                    | SqlQuery::Explain(_)
+                    | SqlQuery::DeclareCursor(_)
+                    | SqlQuery::FlushWait(_) => {
                         unreachable!("path returns prior")
                     }
                 }
@@ -1841,6 +3179,9 @@ where
                 let res = match parsed_query {
                     // CREATE VIEW will still trigger migrations with epxlicit-migrations enabled
                     SqlQuery::CreateView(q) => self.noria.handle_create_view(q).await,
+                    SqlQuery::CreateTable(q) if q.temporary => {
+                        unsupported!("temporary tables require an upstream database")
+                    }
                     SqlQuery::CreateTable(q) => self.noria.handle_table_operation(q.clone()).await,
                     SqlQuery::AlterTable(q) => self.noria.handle_table_operation(q.clone()).await,
                     SqlQuery::DropTable(q) => self.noria.handle_table_operation(q.clone()).await,
@@ -1852,6 +3193,11 @@ where
                             .await;
                         res
                     }
+                    SqlQuery::Insert(_) | SqlQuery::Update(_) | SqlQuery::Delete(_)
+                        if let Err(e) = check_write_features(self.dialect, &parsed_query) =>
+                    {
+                        Err(e)
+                    }
                     SqlQuery::Insert(q) => self.noria.handle_insert(q).await,
                     SqlQuery::Update(q) => self.noria.handle_update(q).await,
                     SqlQuery::Delete(q) => self.noria.handle_delete(q).await,
@@ -1862,6 +3208,22 @@ where
                     SqlQuery::Set(_) | SqlQuery::Commit(_) => {
                         Ok(noria_connector::QueryResult::Empty)
                     }
+                    // Cursors that exist in ReadySet are handled prior
+                    SqlQuery::Fetch(FetchStatement { name, .. })
+                    | SqlQuery::CloseCursor(CloseCursorStatement::Cursor(name)) => {
+                        Err(ReadySetError::CursorNotFound {
+                            name: name.to_string(),
+                        })
+                    }
+                    SqlQuery::CloseCursor(CloseCursorStatement::All) => {
+                        Ok(noria_connector::QueryResult::Empty)
+                    }
+                    SqlQuery::Maintenance(stmt) => {
+                        if stmt.operation == MaintenanceOperation::Analyze {
+                            self.noria.refresh_table_statistics(&stmt.tables).await?;
+                        }
+                        Ok(noria_connector::QueryResult::Empty)
+                    }
                     _ => {
                         error!("unsupported query");
                         unsupported!("query type unsupported");
@@ -1891,17 +3253,15 @@ where
 fn log_query(
     sender: Option<&UnboundedSender<QueryExecutionEvent>>,
     event: QueryExecutionEvent,
-    slowlog: bool,
+    slowlog: Option<&SlowLog>,
+    query_status_cache: &QueryStatusCache,
 ) {
-    const SLOW_DURATION: std::time::Duration = std::time::Duration::from_millis(5);
+    if let Some(slowlog) = slowlog {
+        slowlog.log(&event);
+    }
 
-    if slowlog
-        && (event.upstream_duration.unwrap_or_default() > SLOW_DURATION
-            || event.readyset_duration.unwrap_or_default() > SLOW_DURATION)
-    {
-        if let Some(query) = &event.query {
-            warn!(query = %Sensitive(&query), readyset_time = ?event.readyset_duration, upstream_time = ?event.upstream_duration, "slow query");
-        }
+    if let Some(id) = &event.query_id {
+        query_status_cache.record_latency(id, &event);
     }
 
     if let Some(sender) = sender {
@@ -1911,3 +3271,211 @@ fn log_query(
         }
     }
 }
+
+/// Collects the tables a statement refers to, including in its joins and subqueries
+#[derive(Default)]
+struct TablesVisitor(Vec<Table>);
+
+impl<'ast> Visitor<'ast> for TablesVisitor {
+    type Error = !;
+
+    fn visit_table(&mut self, table: &'ast mut Table) -> Result<(), Self::Error> {
+        self.0.push(table.clone());
+        Ok(())
+    }
+}
+
+/// Returns the names of the tables `query` refers to, including in its joins and subqueries
+fn referred_table_names(query: &SqlQuery) -> HashSet<SqlIdentifier> {
+    all_referred_tables(query)
+        .into_iter()
+        .map(|table| table.name)
+        .collect()
+}
+
+/// Returns whether `query` reads a sample of the rows of any table with `TABLESAMPLE`, including
+/// in its joins and subqueries
+fn samples_tables(query: &SqlQuery) -> bool {
+    all_referred_tables(query)
+        .iter()
+        .any(|table| table.sample.is_some())
+}
+
+/// Records whether a statement calls any function whose arguments couldn't be parsed
+#[derive(Default)]
+struct UnsupportedFunctionsVisitor(bool);
+
+impl<'ast> Visitor<'ast> for UnsupportedFunctionsVisitor {
+    type Error = !;
+
+    fn visit_function_expression(
+        &mut self,
+        function_expression: &'ast mut FunctionExpression,
+    ) -> Result<(), Self::Error> {
+        if matches!(function_expression, FunctionExpression::Unsupported { .. }) {
+            self.0 = true;
+        }
+        walk_function_expression(self, function_expression)
+    }
+}
+
+/// Returns whether `query` calls any function whose arguments ReadySet couldn't parse (see
+/// [`FunctionExpression::Unsupported`]), including in its joins and subqueries
+fn calls_unsupported_functions(query: &SqlQuery) -> bool {
+    let mut visitor = UnsupportedFunctionsVisitor::default();
+    #[allow(clippy::unwrap_used)] // error is !, which can never be returned
+    walk_query(&mut visitor, &mut query.clone()).unwrap();
+    visitor.0
+}
+
+/// Returns the tables `query` refers to, including in its joins and subqueries
+fn all_referred_tables(query: &SqlQuery) -> Vec<Table> {
+    let mut visitor = TablesVisitor::default();
+    #[allow(clippy::unwrap_used)] // error is !, which can never be returned
+    walk_query(&mut visitor, &mut query.clone()).unwrap();
+    visitor.0
+}
+
+/// Walks `visitor` over the parts of `query` that can refer to tables or contain expressions
+fn walk_query<V>(visitor: &mut V, query: &mut SqlQuery) -> Result<(), !>
+where
+    V: for<'ast> Visitor<'ast, Error = !>,
+{
+    match query {
+        SqlQuery::Select(stmt)
+        | SqlQuery::DeclareCursor(DeclareCursorStatement { query: stmt, .. }) => {
+            visitor.visit_select_statement(stmt)?
+        }
+        SqlQuery::CompoundSelect(stmt) => {
+            for (_, select) in &mut stmt.selects {
+                visitor.visit_select_statement(select)?;
+            }
+        }
+        SqlQuery::Insert(stmt) => {
+            visitor.visit_table(&mut stmt.table)?;
+            if let Some(select) = &mut stmt.select {
+                visitor.visit_select_statement(select)?;
+            }
+        }
+        SqlQuery::Update(stmt) => {
+            visitor.visit_table(&mut stmt.table)?;
+            for table in stmt.tables.iter_mut().chain(&mut stmt.from) {
+                visitor.visit_table(table)?;
+            }
+            for join in stmt.join.iter_mut().chain(&mut stmt.from_join) {
+                visitor.visit_join_clause(join)?;
+            }
+            if let Some(where_clause) = &mut stmt.where_clause {
+                visitor.visit_where_clause(where_clause)?;
+            }
+        }
+        SqlQuery::Delete(stmt) => {
+            visitor.visit_table(&mut stmt.table)?;
+            for table in stmt.tables.iter_mut().chain(&mut stmt.using) {
+                visitor.visit_table(table)?;
+            }
+            for join in &mut stmt.using_join {
+                visitor.visit_join_clause(join)?;
+            }
+            if let Some(where_clause) = &mut stmt.where_clause {
+                visitor.visit_where_clause(where_clause)?;
+            }
+        }
+        SqlQuery::DropTable(stmt) => {
+            for table in &mut stmt.tables {
+                visitor.visit_table(table)?;
+            }
+        }
+        _ => {
+            for mut table in query.referred_tables() {
+                visitor.visit_table(&mut table)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns an error if `stmt` is a write using syntax that can't be executed directly against
+/// ReadySet's base tables, either because `dialect` doesn't support it at all or because it's only
+/// supported when writing to an upstream database
+fn check_write_features(dialect: Dialect, stmt: &SqlQuery) -> ReadySetResult<()> {
+    if let SqlQuery::Insert(InsertStatement {
+        select: Some(_), ..
+    }) = stmt
+    {
+        unsupported!("INSERT ... SELECT is only supported when writing to an upstream database");
+    }
+
+    let features = match stmt {
+        SqlQuery::Insert(InsertStatement {
+            returning,
+            on_duplicate,
+            ..
+        }) => [
+            returning.as_ref().map(|_| Feature::Returning),
+            on_duplicate.as_ref().map(|_| Feature::OnDuplicateKeyUpdate),
+            None,
+        ],
+        SqlQuery::Update(UpdateStatement {
+            returning,
+            tables,
+            join,
+            from,
+            ..
+        }) => [
+            returning.as_ref().map(|_| Feature::Returning),
+            (!from.is_empty()).then(|| Feature::UpdateFrom),
+            (!tables.is_empty() || !join.is_empty()).then(|| Feature::MultiTableWrites),
+        ],
+        SqlQuery::Delete(DeleteStatement {
+            tables,
+            order,
+            limit,
+            ..
+        }) => [
+            None,
+            (order.is_some() || limit.is_some()).then(|| Feature::DeleteOrderLimit),
+            (!tables.is_empty()).then(|| Feature::MultiTableWrites),
+        ],
+        _ => return Ok(()),
+    };
+
+    for feature in features.into_iter().flatten() {
+        if !dialect.supports(feature) {
+            unsupported!("{} is not supported by {}", feature, dialect);
+        }
+        if matches!(feature, Feature::Returning | Feature::DeleteOrderLimit) {
+            unsupported!(
+                "{} is only supported when writing to an upstream database",
+                feature
+            );
+        }
+    }
+
+    let multi_table = match stmt {
+        SqlQuery::Update(stmt) => stmt.is_multi_table(),
+        SqlQuery::Delete(stmt) => stmt.is_multi_table(),
+        _ => false,
+    };
+    if multi_table {
+        unsupported!(
+            "UPDATE and DELETE statements that refer to other tables are only supported when \
+             writing to an upstream database"
+        );
+    }
+    Ok(())
+}
+
+/// Runs `read` against ReadySet, failing with [`ReadySetError::UpqueryTimeout`] if it doesn't
+/// complete within `timeout` (if set) so that it can be served by the upstream database instead
+async fn with_read_timeout<F, T>(timeout: Option<Duration>, read: F) -> ReadySetResult<T>
+where
+    F: Future<Output = ReadySetResult<T>>,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, read)
+            .await
+            .unwrap_or(Err(ReadySetError::UpqueryTimeout)),
+        None => read.await,
+    }
+}
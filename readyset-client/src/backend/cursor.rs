@@ -0,0 +1,99 @@
+//! Server-side cursors created with `DECLARE ... CURSOR FOR <select>`.
+//!
+//! When the query a cursor is declared for can be executed against ReadySet, its results are read
+//! from the reader once, when the cursor is declared, and are then returned to the client in pages
+//! by subsequent `FETCH` statements, until the cursor is closed with `CLOSE`.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use nom_sql::{FetchCount, SqlIdentifier};
+use readyset::results::Results;
+use readyset_data::DataType;
+
+use crate::backend::noria_connector::QueryResult;
+use crate::backend::SelectSchema;
+
+/// The remaining rows of a cursor declared against ReadySet
+pub(crate) struct Cursor {
+    rows: VecDeque<Vec<DataType>>,
+    columns: Arc<[SqlIdentifier]>,
+    select_schema: SelectSchema<'static>,
+}
+
+impl Cursor {
+    /// Create a new cursor over the given results of a `SELECT` against ReadySet
+    pub(crate) fn new(data: Vec<Results>, select_schema: SelectSchema<'static>) -> Self {
+        let columns = match data.first() {
+            Some(results) => results.columns().clone(),
+            None => select_schema.columns.iter().cloned().collect(),
+        };
+        let rows = data
+            .into_iter()
+            .flat_map(Vec::<Vec<DataType>>::from)
+            .collect();
+
+        Self {
+            rows,
+            columns,
+            select_schema,
+        }
+    }
+
+    /// Remove and return up to `count` of the remaining rows of the cursor
+    pub(crate) fn fetch(&mut self, count: FetchCount) -> QueryResult<'static> {
+        let n = match count {
+            FetchCount::Count(n) => usize::try_from(n).unwrap_or(usize::MAX),
+            FetchCount::All => usize::MAX,
+        };
+        let rows = self.rows.drain(..n.min(self.rows.len())).collect();
+
+        QueryResult::Select {
+            data: vec![Results::new(rows, Arc::clone(&self.columns))],
+            select_schema: self.select_schema.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    fn fetched_rows(res: QueryResult<'static>) -> Vec<Vec<DataType>> {
+        match res {
+            QueryResult::Select { data, .. } => data
+                .into_iter()
+                .flat_map(Vec::<Vec<DataType>>::from)
+                .collect(),
+            _ => panic!("Expected select result"),
+        }
+    }
+
+    #[test]
+    fn fetch_in_pages() {
+        let columns: Arc<[SqlIdentifier]> = vec!["a".into()].into();
+        let mut cursor = Cursor::new(
+            vec![
+                Results::new(vec![vec![1.into()], vec![2.into()]], Arc::clone(&columns)),
+                Results::new(vec![vec![3.into()]], Arc::clone(&columns)),
+            ],
+            SelectSchema {
+                use_bogo: false,
+                schema: Cow::Owned(vec![]),
+                columns: Cow::Owned(vec!["a".into()]),
+            },
+        );
+
+        assert_eq!(
+            fetched_rows(cursor.fetch(FetchCount::Count(2))),
+            vec![vec![DataType::from(1)], vec![DataType::from(2)]]
+        );
+        assert_eq!(
+            fetched_rows(cursor.fetch(FetchCount::All)),
+            vec![vec![DataType::from(3)]]
+        );
+        assert!(fetched_rows(cursor.fetch(FetchCount::Count(1))).is_empty());
+    }
+}
@@ -3,26 +3,33 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::ops::Bound;
+use std::pin::Pin;
 use std::sync::{atomic, Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 use dataflow_expression::Expression as DataflowExpression;
+use futures_util::stream::{BoxStream, Stream, StreamExt, TryStreamExt};
 use itertools::Itertools;
 use launchpad::redacted::Sensitive;
 use nom_sql::{
-    self, BinaryOperator, ColumnConstraint, DeleteStatement, InsertStatement, Literal,
-    SelectStatement, SqlIdentifier, SqlQuery, SqlType, UpdateStatement,
+    self, BinaryOperator, ColumnConstraint, CompressionAlgorithm, CreateTableStatement,
+    DeleteStatement, InsertStatement, Literal, SelectStatement, SqlIdentifier, SqlQuery, SqlType,
+    UpdateStatement,
 };
 use readyset::consistency::Timestamp;
-use readyset::internal::LocalNodeIndex;
+use readyset::internal::{LocalNodeIndex, MaterializationStatus};
 use readyset::recipe::changelist::{Change, ChangeList};
+use readyset::replication::ReplicationOffset;
 use readyset::results::Results;
+use readyset::storage::{CompressionOptions, CompressionType, DEFAULT_MAX_DICT_BYTES};
 use readyset::{
     ColumnSchema, ControllerHandle, KeyColumnIdx, KeyComparison, ReadQuery, ReadySetError,
     ReadySetResult, SchemaType, Table, TableOperation, View, ViewPlaceholder, ViewQuery,
     ViewSchema,
 };
 use readyset_data::noria_type::Type;
-use readyset_data::DataType;
+use readyset_data::{enums, Collation, DataType};
 use readyset_errors::ReadySetError::PreparedStatementMissing;
 use readyset_errors::{
     internal, internal_err, invariant_eq, table_err, unsupported, unsupported_err,
@@ -180,6 +187,10 @@ impl PrepareResult {
             | PrepareResult::Update { statement_id, .. } => *statement_id,
         }
     }
+    /// Returns true if this is an `INSERT`, `UPDATE`, or `DELETE` statement
+    pub fn is_write(&self) -> bool {
+        !matches!(self, PrepareResult::Select { .. })
+    }
 }
 
 /// A single row in the variable table associated with [`QueryResult::MetaVariables`].
@@ -211,6 +222,14 @@ pub enum QueryResult<'a> {
         data: Vec<Results>,
         select_schema: SelectSchema<'a>,
     },
+    /// The results of a select whose result set is too large to be read from ReadySet all at
+    /// once, so is read a page at a time. `data` contains the first page of results, and `rest`
+    /// reads the remaining pages.
+    StreamingSelect {
+        data: Vec<Results>,
+        rest: ResultStream,
+        select_schema: SelectSchema<'a>,
+    },
     Update {
         num_rows_updated: u64,
         last_inserted_id: u64,
@@ -237,6 +256,15 @@ impl<'a> QueryResult<'a> {
                 data,
                 select_schema: select_schema.into_owned(),
             },
+            QueryResult::StreamingSelect {
+                data,
+                rest,
+                select_schema,
+            } => QueryResult::StreamingSelect {
+                data,
+                rest,
+                select_schema: select_schema.into_owned(),
+            },
             // Have to manually pass each variant to convince rustc that the
             // returned type is really owned
             QueryResult::Empty => QueryResult::Empty,
@@ -259,6 +287,44 @@ impl<'a> QueryResult<'a> {
             QueryResult::MetaVariables(vec) => QueryResult::MetaVariables(vec),
         }
     }
+
+    /// If this is a [`QueryResult::StreamingSelect`], read all of its remaining pages of results
+    /// into a [`QueryResult::Select`], for callers that need the entire result set at once
+    pub async fn buffered(self) -> ReadySetResult<QueryResult<'a>> {
+        match self {
+            QueryResult::StreamingSelect {
+                mut data,
+                mut rest,
+                select_schema,
+            } => {
+                while let Some(page) = rest.try_next().await? {
+                    data.extend(page);
+                }
+                Ok(QueryResult::Select {
+                    data,
+                    select_schema,
+                })
+            }
+            res => Ok(res),
+        }
+    }
+}
+
+/// The remaining pages of the results of a [`QueryResult::StreamingSelect`]
+pub struct ResultStream(BoxStream<'static, ReadySetResult<Vec<Results>>>);
+
+impl fmt::Debug for ResultStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResultStream").finish_non_exhaustive()
+    }
+}
+
+impl Stream for ResultStream {
+    type Item = ReadySetResult<Vec<Results>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
 }
 
 #[derive(Clone)]
@@ -391,6 +457,10 @@ pub struct NoriaConnector {
     /// A read request handler that may be used to service reads from readers
     /// on the same server.
     read_request_handler: Option<ReadRequestHandler>,
+
+    /// If set, the results of selects are read from ReadySet a page of at most this many rows at a
+    /// time, and returned as a [`QueryResult::StreamingSelect`].
+    result_page_size: Option<usize>,
 }
 
 /// The read behavior used when executing a read against ReadySet.
@@ -436,6 +506,7 @@ impl Clone for NoriaConnector {
             failed_views: self.failed_views.clone(),
             read_behavior: self.read_behavior,
             read_request_handler: self.read_request_handler.clone(),
+            result_page_size: self.result_page_size,
         }
     }
 }
@@ -483,24 +554,68 @@ impl NoriaConnector {
             failed_views: HashSet::new(),
             read_behavior,
             read_request_handler,
+            result_page_size: None,
         }
     }
 
+    /// Read the results of selects from ReadySet a page of at most `result_page_size` rows at a
+    /// time, rather than all at once, so that large result sets can be returned to the client
+    /// without holding all of their rows in memory.
+    ///
+    /// Reads served by a local [`ReadRequestHandler`] are never paged.
+    pub fn with_result_page_size(mut self, result_page_size: Option<usize>) -> Self {
+        self.result_page_size = result_page_size;
+        self
+    }
+
     pub(crate) async fn graphviz(
         &mut self,
         simplified: bool,
+        for_cache: Option<&SqlIdentifier>,
     ) -> ReadySetResult<QueryResult<'static>> {
         let noria = &mut self.inner.get_mut().await?.noria;
 
-        let (label, graphviz) = if simplified {
-            ("SIMPLIFIED GRAPHVIZ", noria.simple_graphviz().await?)
+        let label = if simplified {
+            "SIMPLIFIED GRAPHVIZ"
         } else {
-            ("GRAPHVIZ", noria.graphviz().await?)
+            "GRAPHVIZ"
+        };
+        let graphviz = match for_cache {
+            Some(name) => noria.graphviz_for_query(name.as_str(), simplified).await?,
+            None if simplified => noria.simple_graphviz().await?,
+            None => noria.graphviz().await?,
         };
 
         Ok(QueryResult::Meta(vec![(label, graphviz).into()]))
     }
 
+    /// Returns which existing cache (if any) the cache named `name` shares dataflow nodes with
+    pub(crate) async fn explain_reuse(
+        &mut self,
+        name: &SqlIdentifier,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        let noria = &mut self.inner.get_mut().await?.noria;
+        let reuse = noria.query_reuse(name.as_str()).await?;
+
+        let (reused_query, reused_nodes) = match reuse {
+            Some(reuse) => (reuse.reused_query, reuse.reused_nodes.to_string()),
+            None => ("none".to_owned(), "0".to_owned()),
+        };
+        Ok(QueryResult::Meta(vec![
+            ("name", name.to_string()).into(),
+            ("reused cache", reused_query).into(),
+            ("reused nodes", reused_nodes).into(),
+        ]))
+    }
+
+    /// Returns the name and query of every query cached in ReadySet
+    pub(crate) async fn cached_queries(
+        &mut self,
+    ) -> ReadySetResult<BTreeMap<String, SelectStatement>> {
+        let noria = &mut self.inner.get_mut().await?.noria;
+        noria.verbose_outputs().await
+    }
+
     pub(crate) async fn verbose_outputs(&mut self) -> ReadySetResult<QueryResult<'static>> {
         let noria = &mut self.inner.get_mut().await?.noria;
         let outputs = noria.verbose_outputs().await?;
@@ -559,6 +674,39 @@ impl NoriaConnector {
         Ok(table_handle.node)
     }
 
+    /// Returns the names of the primary key columns of the base table with the given name, or
+    /// `None` if the table doesn't have a primary key
+    pub(crate) async fn primary_key_columns(
+        &mut self,
+        table: &str,
+    ) -> ReadySetResult<Option<Vec<SqlIdentifier>>> {
+        let table = self.inner.get_mut().await?.get_noria_table(table).await?;
+        if !table.has_primary_key() {
+            return Ok(None);
+        }
+        Ok(Some(
+            table
+                .key()
+                .iter()
+                .filter_map(|&col| table.columns().get(col).cloned())
+                .collect(),
+        ))
+    }
+
+    /// Returns the schemas of all the base tables in ReadySet, in order of name
+    pub(crate) async fn table_schemas(&mut self) -> ReadySetResult<Vec<CreateTableStatement>> {
+        let tables = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.inputs()
+        )?;
+        let mut schemas = Vec::with_capacity(tables.len());
+        for name in tables.keys() {
+            let table = self.inner.get_mut().await?.get_noria_table(name).await?;
+            schemas.extend(table.schema().cloned());
+        }
+        Ok(schemas)
+    }
+
     pub async fn handle_insert(
         &mut self,
         q: &nom_sql::InsertStatement,
@@ -910,11 +1058,86 @@ impl NoriaConnector {
         Ok(QueryResult::Empty)
     }
 
-    pub(crate) async fn readyset_status(&mut self) -> ReadySetResult<QueryResult<'static>> {
-        let status = noria_await!(
+    /// Handles an `ALTER READYSET COMPACT TABLE` statement by manually compacting the persisted
+    /// state of the given base table
+    pub(crate) async fn compact_table(
+        &mut self,
+        table: &str,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.compact_table(table)
+        )?;
+
+        Ok(QueryResult::Empty)
+    }
+
+    /// Handles an `ALTER READYSET SET COMPRESSION` statement by changing the compression options
+    /// for the persisted state of the given base table
+    pub(crate) async fn set_table_compression(
+        &mut self,
+        table: &str,
+        algorithm: CompressionAlgorithm,
+        dictionary: bool,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        let options = CompressionOptions {
+            compression_type: match algorithm {
+                CompressionAlgorithm::None => CompressionType::None,
+                CompressionAlgorithm::Lz4 => CompressionType::Lz4,
+                CompressionAlgorithm::Zstd => CompressionType::Zstd,
+            },
+            max_dict_bytes: if dictionary {
+                DEFAULT_MAX_DICT_BYTES
+            } else {
+                0
+            },
+        };
+
+        noria_await!(
+            self.inner.get_mut().await?,
+            self.inner
+                .get_mut()
+                .await?
+                .noria
+                .set_table_compression(table, options)
+        )?;
+
+        Ok(QueryResult::Empty)
+    }
+
+    /// Handles an `ALTER READYSET TABLE ... SHARD BY` statement by overriding the column the given
+    /// base table is sharded by
+    pub(crate) async fn set_table_sharding(
+        &mut self,
+        table: &str,
+        column: &str,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        noria_await!(
+            self.inner.get_mut().await?,
+            self.inner
+                .get_mut()
+                .await?
+                .noria
+                .set_table_sharding(table, column)
+        )?;
+
+        Ok(QueryResult::Empty)
+    }
+
+    /// Returns the status of ReadySet, including how far replication is behind the upstream
+    /// database's replication log if its current offset in the log, `upstream_offset`, is known
+    pub(crate) async fn readyset_status(
+        &mut self,
+        upstream_offset: Option<ReplicationOffset>,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        let mut status = noria_await!(
             self.inner.get_mut().await?,
             self.inner.get_mut().await?.noria.status()
         )?;
+        if let (Some(offset), Some(upstream_offset)) = (&status.replication_offset, upstream_offset)
+        {
+            status.replication_lag_bytes = offset.bytes_behind(&upstream_offset);
+        }
 
         // Converts from ReadySetStatus -> Vec<(String, String)> -> QueryResult
         Ok(QueryResult::MetaVariables(
@@ -924,6 +1147,283 @@ impl NoriaConnector {
                 .collect(),
         ))
     }
+
+    /// Returns true if ReadySet has replicated every write to the upstream database up to
+    /// `offset`.
+    ///
+    /// Since writes are replicated in order, this is the case once the replication offset of the
+    /// schema or of any table has reached `offset`.
+    pub(crate) async fn has_replicated(
+        &mut self,
+        offset: &ReplicationOffset,
+    ) -> ReadySetResult<bool> {
+        let offsets = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.replication_offsets()
+        )?;
+
+        Ok(offsets
+            .schema
+            .iter()
+            .chain(offsets.tables.values().flatten())
+            .any(|replicated| replicated >= offset))
+    }
+
+    /// Waits, for at most `timeout`, until every write ReadySet has replicated so far has
+    /// propagated through the dataflow graph to every reader
+    pub(crate) async fn flush_wait(&mut self, timeout: Duration) -> ReadySetResult<()> {
+        noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.flush_wait(timeout)
+        )
+    }
+
+    /// Returns the estimated size in bytes of the data stored in each base table, summed over all
+    /// of its shards
+    pub(crate) async fn table_sizes(&mut self) -> ReadySetResult<HashMap<SqlIdentifier, u64>> {
+        let tables = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.inputs()
+        )?;
+        let stats = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.statistics()
+        )?;
+
+        let mut sizes_by_node = HashMap::new();
+        for (_, nodes) in stats.domains.values() {
+            for (node, stats) in nodes {
+                *sizes_by_node.entry(*node).or_insert(0) += stats.mem_size;
+            }
+        }
+
+        Ok(tables
+            .into_iter()
+            .map(|(name, node)| {
+                let size = sizes_by_node.get(&node).copied().unwrap_or(0);
+                (name.into(), size)
+            })
+            .collect())
+    }
+
+    /// Returns the memory used by the state of every materialized node in the dataflow graph,
+    /// broken down into keys, rows and overhead, with the largest consumers first
+    pub(crate) async fn readyset_memory(&mut self) -> ReadySetResult<QueryResult<'static>> {
+        const COLUMNS: [&str; 8] = [
+            "domain",
+            "node",
+            "description",
+            "materialization",
+            "key bytes",
+            "row bytes",
+            "overhead bytes",
+            "total bytes",
+        ];
+
+        let stats = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.statistics()
+        )?;
+
+        let mut nodes = stats
+            .domains
+            .iter()
+            .flat_map(|((domain, shard), (_, nodes))| {
+                nodes.iter().filter_map(move |(node, stats)| {
+                    let materialization = match stats.materialized {
+                        MaterializationStatus::Not => return None,
+                        MaterializationStatus::Full => "full",
+                        MaterializationStatus::Partial { .. } => "partial",
+                    };
+                    Some((
+                        format!("{}.{}", domain.index(), shard),
+                        node.index(),
+                        stats.desc.clone(),
+                        materialization,
+                        stats.memory,
+                    ))
+                })
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|(_, _, _, _, memory)| std::cmp::Reverse(memory.total()));
+
+        let data = nodes
+            .into_iter()
+            .map(|(domain, node, desc, materialization, memory)| {
+                vec![
+                    DataType::from(domain),
+                    DataType::from(node as u64),
+                    DataType::from(desc),
+                    DataType::from(materialization),
+                    DataType::from(memory.key_bytes),
+                    DataType::from(memory.row_bytes),
+                    DataType::from(memory.overhead_bytes),
+                    DataType::from(memory.total()),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let select_schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(
+                COLUMNS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &name)| ColumnSchema {
+                        spec: nom_sql::ColumnSpecification {
+                            column: nom_sql::Column {
+                                name: name.into(),
+                                table: None,
+                            },
+                            sql_type: if i == 1 || i >= 4 {
+                                nom_sql::SqlType::UnsignedBigint(None)
+                            } else {
+                                nom_sql::SqlType::Text
+                            },
+                            constraints: vec![],
+                            comment: None,
+                        },
+                        base: None,
+                    })
+                    .collect(),
+            ),
+            columns: Cow::Owned(COLUMNS.iter().map(|&c| c.into()).collect()),
+        };
+        let data = vec![Results::new(
+            data,
+            COLUMNS.iter().map(|&c| c.into()).collect(),
+        )];
+        Ok(QueryResult::Select {
+            data,
+            select_schema,
+        })
+    }
+
+    /// Handles an `ANALYZE` statement by recomputing the statistics collected about the rows of
+    /// each of the given base tables, or of every base table if none are given. Tables that don't
+    /// exist in ReadySet are skipped.
+    pub(crate) async fn refresh_table_statistics(
+        &mut self,
+        tables: &[nom_sql::Table],
+    ) -> ReadySetResult<()> {
+        let inputs = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.inputs()
+        )?;
+
+        for table in inputs.keys().filter(|name| {
+            tables.is_empty() || tables.iter().any(|t| t.name.as_str() == name.as_str())
+        }) {
+            noria_await!(
+                self.inner.get_mut().await?,
+                self.inner
+                    .get_mut()
+                    .await?
+                    .noria
+                    .refresh_table_statistics(table)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the statistics collected about the rows of every base table: the number of rows in
+    /// each table, and an estimate of the number of distinct values and the smallest and largest
+    /// value written to each of its columns
+    pub(crate) async fn readyset_statistics(&mut self) -> ReadySetResult<QueryResult<'static>> {
+        const COLUMNS: [&str; 6] = ["table", "column", "rows", "distinct values", "min", "max"];
+
+        let tables = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.table_statistics()
+        )?;
+
+        let to_text = |v: Option<DataType>| v.map_or(DataType::None, |v| v.to_string().into());
+        let data = tables
+            .into_iter()
+            .flat_map(|(table, (columns, stats))| {
+                let rows = stats.rows;
+                columns
+                    .into_iter()
+                    .zip(stats.columns)
+                    .map(move |(column, col_stats)| {
+                        vec![
+                            DataType::from(table.as_str()),
+                            DataType::from(column.as_str()),
+                            DataType::from(rows),
+                            DataType::from(col_stats.distinct),
+                            to_text(col_stats.min),
+                            to_text(col_stats.max),
+                        ]
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let select_schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(
+                COLUMNS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &name)| ColumnSchema {
+                        spec: nom_sql::ColumnSpecification {
+                            column: nom_sql::Column {
+                                name: name.into(),
+                                table: None,
+                            },
+                            sql_type: if i == 2 || i == 3 {
+                                nom_sql::SqlType::UnsignedBigint(None)
+                            } else {
+                                nom_sql::SqlType::Text
+                            },
+                            constraints: vec![],
+                            comment: None,
+                        },
+                        base: None,
+                    })
+                    .collect(),
+            ),
+            columns: Cow::Owned(COLUMNS.iter().map(|&c| c.into()).collect()),
+        };
+        let data = vec![Results::new(
+            data,
+            COLUMNS.iter().map(|&c| c.into()).collect(),
+        )];
+        Ok(QueryResult::Select {
+            data,
+            select_schema,
+        })
+    }
+
+    /// Returns the most recently recorded state of the upstream replication slot, or an empty
+    /// table if there isn't one (such as when replicating from MySQL).
+    pub(crate) async fn replication_status(&mut self) -> ReadySetResult<QueryResult<'static>> {
+        let status = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner.get_mut().await?.noria.replication_slot_status()
+        )?;
+
+        let vars = match status {
+            Some(status) => {
+                let or_null = |v: Option<String>| v.unwrap_or_else(|| "NULL".to_owned());
+                vec![
+                    ("Slot Name", status.slot_name),
+                    ("Active", status.active.to_string()),
+                    ("Restart LSN", or_null(status.restart_lsn)),
+                    ("Confirmed Flush LSN", or_null(status.confirmed_flush_lsn)),
+                    (
+                        "Lag Bytes",
+                        or_null(status.lag_bytes.map(|lag| lag.to_string())),
+                    ),
+                ]
+            }
+            None => vec![],
+        };
+
+        Ok(QueryResult::MetaVariables(
+            vars.into_iter().map(MetaVariable::from).collect(),
+        ))
+    }
 }
 
 impl NoriaConnector {
@@ -954,6 +1454,85 @@ impl NoriaConnector {
         Ok(())
     }
 
+    /// This function handles EXPLAIN CREATE CACHE statements, by planning the dataflow for the
+    /// cache without installing it and listing the nodes that it would add to the graph, along
+    /// with the estimated size of the state of each materialized node.
+    pub(crate) async fn explain_create_cache(
+        &mut self,
+        name: Option<&str>,
+        statement: &nom_sql::SelectStatement,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        const COLUMNS: [&str; 4] = ["node", "description", "materialization", "estimated bytes"];
+
+        let name: SqlIdentifier = name
+            .map(|s| s.into())
+            .unwrap_or_else(|| utils::generate_query_name(statement).into());
+        let changelist = ChangeList {
+            changes: vec![Change::create_cache(name, statement.clone())],
+        };
+
+        let nodes = noria_await!(
+            self.inner.get_mut().await?,
+            self.inner
+                .get_mut()
+                .await?
+                .noria
+                .explain_migration(changelist)
+        )?;
+
+        let data = nodes
+            .into_iter()
+            .map(|node| {
+                let materialization = match node.materialization {
+                    MaterializationStatus::Not => "not",
+                    MaterializationStatus::Full => "full",
+                    MaterializationStatus::Partial { .. } => "partial",
+                };
+                vec![
+                    DataType::from(node.index.index() as u64),
+                    DataType::from(node.description),
+                    DataType::from(materialization),
+                    node.estimated_bytes.map_or(DataType::None, DataType::from),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let select_schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(
+                COLUMNS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &name)| ColumnSchema {
+                        spec: nom_sql::ColumnSpecification {
+                            column: nom_sql::Column {
+                                name: name.into(),
+                                table: None,
+                            },
+                            sql_type: if i == 0 || i == 3 {
+                                nom_sql::SqlType::UnsignedBigint(None)
+                            } else {
+                                nom_sql::SqlType::Text
+                            },
+                            constraints: vec![],
+                            comment: None,
+                        },
+                        base: None,
+                    })
+                    .collect(),
+            ),
+            columns: Cow::Owned(COLUMNS.iter().map(|&c| c.into()).collect()),
+        };
+        let data = vec![Results::new(
+            data,
+            COLUMNS.iter().map(|&c| c.into()).collect(),
+        )];
+        Ok(QueryResult::Select {
+            data,
+            select_schema,
+        })
+    }
+
     async fn get_view(
         &mut self,
         q: &nom_sql::SelectStatement,
@@ -1009,6 +1588,55 @@ impl NoriaConnector {
         Ok(())
     }
 
+    /// Handles an `ALTER CACHE ... PREFILL` statement by looking up each of the given keys in the
+    /// cache with the given name, waiting for any misses to be filled by upqueries. Each key
+    /// contains a value for each of the cache's key columns, in order
+    pub(crate) async fn prefill_cache(
+        &mut self,
+        name: &str,
+        keys: &[Vec<Literal>],
+    ) -> ReadySetResult<QueryResult<'static>> {
+        let region = self.region.clone();
+        let view = self
+            .inner
+            .get_mut()
+            .await?
+            .get_noria_view(name, region.as_deref(), false)
+            .await?;
+
+        let key_len = match view.key_map().iter().map(|(_, idx)| idx + 1).max() {
+            Some(key_len) => key_len,
+            None => unsupported!("Cache {} has no key columns to prefill", name),
+        };
+        let key_comparisons = keys
+            .iter()
+            .map(|key| {
+                if key.len() != key_len {
+                    return Err(ReadySetError::WrongColumnCount(key_len, key.len()));
+                }
+                let key = key
+                    .iter()
+                    .map(DataType::try_from)
+                    .collect::<ReadySetResult<Vec<_>>>()?;
+                KeyComparison::try_from(key).map_err(|e| internal_err(e.to_string()))
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+
+        let rows = view
+            .multi_lookup(key_comparisons, true)
+            .await?
+            .into_results()
+            .ok_or(ReadySetError::ReaderMissingKey)?
+            .iter()
+            .map(|results| results.len())
+            .sum::<usize>();
+
+        Ok(QueryResult::Meta(vec![
+            ("keys", keys.len().to_string()).into(),
+            ("rows", rows.to_string()).into(),
+        ]))
+    }
+
     pub fn select_statement_from_name(&self, name: &str) -> Option<SelectStatement> {
         self.view_cache.select_statement_from_name(name)
     }
@@ -1020,6 +1648,46 @@ impl NoriaConnector {
     ) -> ReadySetResult<QueryResult<'_>> {
         let table = &q.table.name;
 
+        // Values generated for auto-increment columns continue on from the largest value that has
+        // been replicated from the upstream database, if any. Replication keeps going while we
+        // generate values, so that's looked up again for every insert into a table with an
+        // auto-increment column, and we continue from whichever is larger.
+        let has_auto_increment = self
+            .inner
+            .get_mut()
+            .await?
+            .get_noria_table(table)
+            .await?
+            .schema()
+            .map_or(false, |schema| {
+                schema
+                    .fields
+                    .iter()
+                    .any(|c| c.constraints.contains(&ColumnConstraint::AutoIncrement))
+            });
+        let replicated = if has_auto_increment {
+            noria_await!(
+                self.inner.get_mut().await?,
+                self.inner.get_mut().await?.noria.auto_increments()
+            )?
+            .remove(table.as_str())
+            .unwrap_or(0)
+        } else {
+            0
+        };
+        let ai = &self.auto_increments;
+        tokio::task::block_in_place(|| {
+            if let Some(last_insert_id) = ai.read().unwrap().get(table.as_str()) {
+                last_insert_id.fetch_max(replicated as usize, atomic::Ordering::SeqCst);
+                return;
+            }
+            ai.write()
+                .unwrap()
+                .entry(table.to_string())
+                .or_insert_with(|| atomic::AtomicUsize::new(0))
+                .fetch_max(replicated as usize, atomic::Ordering::SeqCst);
+        });
+
         // create a mutator if we don't have one for this table already
         trace!(%table, "insert::access mutator");
         let putter = self.inner.get_mut().await?.get_noria_table(table).await?;
@@ -1056,16 +1724,6 @@ impl NoriaConnector {
         }
 
         let ai = &mut self.auto_increments;
-        tokio::task::block_in_place(|| {
-            let ai_lock = ai.read().unwrap();
-            if ai_lock.get(table.as_str()).is_none() {
-                drop(ai_lock);
-                ai.write()
-                    .unwrap()
-                    .entry(table.to_string())
-                    .or_insert_with(|| atomic::AtomicUsize::new(0));
-            }
-        });
         let mut buf = vec![vec![DataType::None; schema.fields.len()]; data.len()];
         let mut first_inserted_id = None;
         tokio::task::block_in_place(|| -> ReadySetResult<_> {
@@ -1101,8 +1759,13 @@ impl NoriaConnector {
                                 ReadySetError::NoSuchColumn(col.column.name.to_string()),
                             )
                         })?;
-                    // query can specify an explicit AUTO_INCREMENT value
-                    if !columns_specified.contains(&col.column) {
+                    // query can specify an explicit AUTO_INCREMENT value, which later generated
+                    // values must be larger than
+                    if let Some(ci) = columns_specified.iter().position(|c| *c == col.column) {
+                        if let Some(value) = row.get(ci).and_then(|v| u64::try_from(v).ok()) {
+                            last_insert_id.fetch_max(value as usize, atomic::Ordering::SeqCst);
+                        }
+                    } else {
                         let id = last_insert_id.fetch_add(1, atomic::Ordering::SeqCst) as i64 + 1;
                         if first_inserted_id.is_none() {
                             first_inserted_id = Some(id);
@@ -1159,7 +1822,7 @@ impl NoriaConnector {
                 let mut uq = UpdateStatement {
                     table: nom_sql::Table::from(table.as_str()),
                     fields: update_fields.clone(),
-                    where_clause: None,
+                    ..Default::default()
                 };
                 utils::extract_update_params_and_fields(
                     &mut uq,
@@ -1296,6 +1959,7 @@ impl NoriaConnector {
             ticket,
             self.read_behavior,
             self.read_request_handler.as_mut(),
+            self.result_page_size,
             event,
         )
         .await;
@@ -1437,6 +2101,7 @@ impl NoriaConnector {
                     ticket,
                     self.read_behavior,
                     self.read_request_handler.as_mut(),
+                    self.result_page_size,
                     event,
                 )
                 .await
@@ -1482,6 +2147,14 @@ fn build_view_query(
     read_behavior: ReadBehavior,
 ) -> ReadySetResult<ViewQuery> {
     let projected_schema = getter_schema.schema(SchemaType::ProjectedSchema);
+    // Lookup keys must have the same collation as the values in the column they're looked up in,
+    // so that they compare (and hash) equal to those values
+    let collation = |key_column_idx: usize| {
+        projected_schema
+            .get(key_column_idx)
+            .and_then(|c| Collation::for_column(&c.spec))
+            .unwrap_or_default()
+    };
 
     let mut key_types = getter_schema.col_types(
         key_map.iter().map(|(_, key_column_idx)| *key_column_idx),
@@ -1573,7 +2246,9 @@ fn build_view_query(
                                 continue;
                             };
                             // parameter numbering is 1-based, but vecs are 0-based, so subtract 1
-                            let value = key[*idx - 1].coerce_to(key_type)?;
+                            let value = key[*idx - 1]
+                                .coerce_to(key_type)?
+                                .with_collation(collation(*key_column_idx));
 
                             let make_op = |op| DataflowExpression::Op {
                                 left: Box::new(DataflowExpression::Column {
@@ -1637,8 +2312,12 @@ fn build_view_query(
                         ViewPlaceholder::Between(lower_idx, upper_idx) => {
                             let key_type = key_types[key_column_idx];
                             // parameter numbering is 1-based, but vecs are 0-based, so subtract 1
-                            let lower_value = key[*lower_idx - 1].coerce_to(key_type)?;
-                            let upper_value = key[*upper_idx - 1].coerce_to(key_type)?;
+                            let lower_value = key[*lower_idx - 1]
+                                .coerce_to(key_type)?
+                                .with_collation(collation(*key_column_idx));
+                            let upper_value = key[*upper_idx - 1]
+                                .coerce_to(key_type)?
+                                .with_collation(collation(*key_column_idx));
                             let (lower_key, upper_key) =
                                 bounds.get_or_insert_with(Default::default);
                             lower_key.push(lower_value);
@@ -1696,9 +2375,40 @@ fn build_view_query(
     })
 }
 
+/// Replace the values of the `ENUM` columns in `data`, which are stored as the indices of their
+/// variants (see [`readyset_data::enums`]), with the variants themselves
+fn enum_indices_to_variants(
+    data: &mut [Results],
+    schema: &[ColumnSchema],
+    columns: &[SqlIdentifier],
+) {
+    let enum_columns = schema
+        .iter()
+        .filter_map(|cs| match &cs.spec.sql_type {
+            SqlType::Enum(variants) => columns
+                .iter()
+                .position(|c| *c == cs.spec.column.name)
+                .map(|idx| (idx, variants)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if enum_columns.is_empty() {
+        return;
+    }
+
+    for row in data.iter_mut().flat_map(|results| results.iter_mut()) {
+        for &(idx, variants) in &enum_columns {
+            if let Some(value) = row.get_mut(idx) {
+                *value = enums::variant(value, variants);
+            }
+        }
+    }
+}
+
 /// Run the supplied [`SelectStatement`] on the supplied [`View`]
 /// Assumption: the [`View`] was created for that specific [`SelectStatement`]
 #[allow(clippy::needless_lifetimes)] // clippy erroneously thinks the timelife can be elided
+#[allow(clippy::too_many_arguments)]
 async fn do_read<'a>(
     getter: &'a mut View,
     q: &nom_sql::SelectStatement,
@@ -1706,6 +2416,7 @@ async fn do_read<'a>(
     ticket: Option<Timestamp>,
     read_behavior: ReadBehavior,
     read_request_handler: Option<&'a mut ReadRequestHandler>,
+    result_page_size: Option<usize>,
     event: &mut readyset_client_metrics::QueryExecutionEvent,
 ) -> ReadySetResult<QueryResult<'a>> {
     let use_bogo = raw_keys.is_empty();
@@ -1720,7 +2431,9 @@ async fn do_read<'a>(
         read_behavior,
     )?;
 
-    let data = if let Some(rh) = read_request_handler {
+    // The stream of the remaining pages of the results, if they're being read a page at a time
+    let mut rest = None;
+    let mut data = if let Some(rh) = read_request_handler {
         let request = readyset::Tagged::from(ReadQuery::Normal {
             target: (*getter.node(), getter.name(), 0),
             query: vq.clone(),
@@ -1752,6 +2465,14 @@ async fn do_read<'a>(
                 .into_results()
                 .ok_or(ReadySetError::ReaderMissingKey)?
         }
+    } else if let Some(page_size) = result_page_size {
+        let mut pages = getter.lookup_stream(vq, page_size).boxed();
+        let data = pages
+            .try_next()
+            .await?
+            .ok_or_else(|| internal_err("No results returned for the first page of a read"))?;
+        rest = Some(pages);
+        data
     } else {
         getter
             .raw_lookup(vq)
@@ -1770,14 +2491,45 @@ async fn do_read<'a>(
             })
             .sum(),
     );
+    for stats in data.iter().filter_map(|result| result.stats.as_ref()) {
+        if stats.cache_misses > 0 {
+            event.upquery_duration = event.upquery_duration.max(Some(stats.upquery_duration));
+        }
+        event.evicted_during_upquery |= stats.evicted_during_upquery;
+    }
     trace!("select::complete");
 
-    Ok(QueryResult::Select {
-        data,
-        select_schema: SelectSchema {
-            use_bogo,
-            schema: Cow::Borrowed(getter.schema().unwrap().schema(SchemaType::ReturnedSchema)), /* Safe because we already unwrapped above */
-            columns: Cow::Borrowed(getter.columns()),
+    let returned_schema = getter
+        .schema()
+        .ok_or_else(|| internal_err("No schema for view"))?
+        .schema(SchemaType::ReturnedSchema);
+    enum_indices_to_variants(&mut data, returned_schema, getter.columns());
+
+    let select_schema = SelectSchema {
+        use_bogo,
+        schema: Cow::Borrowed(getter.schema().unwrap().schema(SchemaType::ReturnedSchema)), /* Safe because we already unwrapped above */
+        columns: Cow::Borrowed(getter.columns()),
+    };
+    Ok(match rest {
+        Some(pages) => {
+            let returned_schema = returned_schema.to_vec();
+            let columns = getter.column_slice();
+            QueryResult::StreamingSelect {
+                data,
+                rest: ResultStream(
+                    pages
+                        .map_ok(move |mut data| {
+                            enum_indices_to_variants(&mut data, &returned_schema, &columns);
+                            data
+                        })
+                        .boxed(),
+                ),
+                select_schema,
+            }
+        }
+        None => QueryResult::Select {
+            data,
+            select_schema,
         },
     })
 }
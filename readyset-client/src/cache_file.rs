@@ -0,0 +1,111 @@
+//! Creating caches in bulk from a file of `CREATE CACHE` statements, so that the set of queries
+//! cached by ReadySet can be kept alongside the code of an application and created when it's
+//! deployed.
+//!
+//! All of the caches in a file are created in a single migration: if any of them can't be created,
+//! none of them are, and the status returned for each cache records why it couldn't be created.
+
+use std::path::Path;
+
+use nom_sql::{CacheInner, CreateCacheStatement, Dialect, SelectStatement, SqlIdentifier};
+use readyset::recipe::changelist::{Change, ChangeList};
+use readyset::{CacheCreationStatus, ControllerHandle, ReadySetResult};
+use readyset_errors::bad_request_err;
+
+use crate::query_status_cache::{MigrationState, QueryStatusCache};
+use crate::{rewrite, utils};
+
+/// Parses the `CREATE CACHE` statements in `contents`, written in the given SQL `dialect`, into
+/// the name and query of each cache, rewriting each query the same way as queries in `CREATE
+/// CACHE` statements run by clients. Caches without a name are given a name generated from their
+/// query.
+fn parse_caches(
+    contents: &str,
+    dialect: Dialect,
+) -> ReadySetResult<Vec<(SqlIdentifier, SelectStatement)>> {
+    ChangeList::from_str_with_dialect(contents, dialect)?
+        .into_iter()
+        .map(|change| {
+            let (name, mut stmt) = match change {
+                Change::CreateCache(CreateCacheStatement {
+                    name,
+                    inner: CacheInner::Statement(stmt),
+                }) => (name, *stmt),
+                _ => {
+                    return Err(bad_request_err(
+                        "Cache files must only contain CREATE CACHE ... FROM SELECT statements",
+                    ))
+                }
+            };
+            rewrite::process_query(&mut stmt)?;
+            let name = name.unwrap_or_else(|| utils::generate_query_name(&stmt).into());
+            Ok((name, stmt))
+        })
+        .collect()
+}
+
+/// Creates every cache in the file of `CREATE CACHE` statements at `path`, written in the given
+/// SQL `dialect`, in a single migration, and returns the status of each cache in the order they
+/// appear in the file.
+///
+/// If every cache is created, each of their queries is marked as allowed to be cached in
+/// `query_status_cache`, the same as if they'd been created with `CREATE CACHE`.
+pub async fn create_caches_from_file(
+    controller: &mut ControllerHandle,
+    path: &Path,
+    dialect: Dialect,
+    query_status_cache: &QueryStatusCache,
+) -> ReadySetResult<Vec<CacheCreationStatus>> {
+    let caches = parse_caches(&std::fs::read_to_string(path)?, dialect)?;
+    let changes = ChangeList {
+        changes: caches
+            .iter()
+            .map(|(name, stmt)| Change::create_cache(name.clone(), stmt.clone()))
+            .collect(),
+    };
+
+    let statuses = controller.create_caches(changes).await?;
+    if statuses.iter().all(|status| status.error.is_none()) {
+        for (_, stmt) in &caches {
+            query_status_cache.update_query_migration_state(stmt, MigrationState::Successful);
+            query_status_cache.allow_caching(stmt);
+        }
+    }
+
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_caches() {
+        let caches = parse_caches(
+            "CREATE CACHE by_id FROM SELECT * FROM t WHERE id = ?;
+             CREATE CACHE FROM SELECT * FROM t WHERE x = 1;",
+            Dialect::MySQL,
+        )
+        .unwrap();
+
+        assert_eq!(caches.len(), 2);
+        assert_eq!(caches[0].0.as_str(), "by_id");
+        assert!(caches[1].0.as_str().starts_with("q_"));
+        // The literal was replaced with a placeholder, as for `CREATE CACHE` run by a client
+        assert_eq!(
+            caches[1].1,
+            nom_sql::parse_select_statement(Dialect::MySQL, "SELECT * FROM t WHERE x = $1")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_other_statements() {
+        parse_caches(
+            "CREATE CACHE by_id FROM SELECT * FROM t WHERE id = ?;
+             CREATE TABLE t2 (id int);",
+            Dialect::MySQL,
+        )
+        .unwrap_err();
+    }
+}
@@ -0,0 +1,1237 @@
+//! Emulation of the catalog tables that ORMs and other clients query to introspect the schema of
+//! the database.
+//!
+//! Queries against a curated set of catalog tables - `tables`, `columns` and `key_column_usage` in
+//! `information_schema`, and (in PostgreSQL) `pg_namespace`, `pg_class`, `pg_type` and
+//! `pg_attribute` in `pg_catalog` - are answered by the adapter from the schemas of ReadySet's base
+//! tables rather than being proxied upstream. This means introspecting the schema doesn't require
+//! the upstream database to be available, and that the schema clients see is the one ReadySet is
+//! actually serving.
+//!
+//! Only simple queries of these tables are emulated: a `SELECT` of columns from a single catalog
+//! table with no joins or grouping, filtered by a `WHERE` clause made of comparisons between its
+//! columns, literals and the functions that return the current database or schema, and optionally
+//! ordered and limited. Any other query is executed the same way it would be otherwise.
+//!
+//! The same evaluation is used to answer queries of constant `VALUES` table constructors, either on
+//! their own or as the only relation in the `FROM` clause of a `SELECT`.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use dataflow_expression::like::{CaseInsensitive, CaseSensitive, LikePattern};
+use nom_sql::{
+    BinaryOperator, Column, ColumnConstraint, ColumnSpecification, CreateTableStatement, Dialect,
+    DialectKind, Expression, FieldDefinitionExpression, FieldReference, FunctionExpression,
+    InValue, Literal, OrderType, SelectStatement, SqlIdentifier, SqlType, Table, TableKey,
+    UnaryOperator, ValuesStatement,
+};
+use readyset::results::Results;
+use readyset::ColumnSchema;
+use readyset_data::DataType;
+
+use crate::backend::noria_connector::QueryResult;
+use crate::backend::SelectSchema;
+
+/// The name of the database reported when we aren't connected to an upstream database
+pub(crate) const DEFAULT_DATABASE: &str = "readyset";
+
+/// The oid of the `pg_catalog` schema in PostgreSQL
+const PG_CATALOG_NAMESPACE: u32 = 11;
+
+/// The oid of the `public` schema in PostgreSQL
+const PUBLIC_NAMESPACE: u32 = 2200;
+
+/// The first oid PostgreSQL assigns to user-defined objects. Base tables are given consecutive oids
+/// starting from this one, in order of name.
+const FIRST_TABLE_OID: u32 = 16384;
+
+/// The PostgreSQL types that ReadySet supports, as the oid, name and length of each, along with the
+/// name `information_schema` uses for it
+const PG_TYPES: &[(u32, &str, i16, &str)] = &[
+    (16, "bool", 1, "boolean"),
+    (17, "bytea", -1, "bytea"),
+    (20, "int8", 8, "bigint"),
+    (21, "int2", 2, "smallint"),
+    (23, "int4", 4, "integer"),
+    (25, "text", -1, "text"),
+    (26, "oid", 4, "oid"),
+    (114, "json", -1, "json"),
+    (700, "float4", 4, "real"),
+    (701, "float8", 8, "double precision"),
+    (829, "macaddr", 6, "macaddr"),
+    (869, "inet", -1, "inet"),
+    (1042, "bpchar", -1, "character"),
+    (1043, "varchar", -1, "character varying"),
+    (1082, "date", 4, "date"),
+    (1083, "time", 8, "time without time zone"),
+    (1114, "timestamp", 8, "timestamp without time zone"),
+    (1184, "timestamptz", 8, "timestamp with time zone"),
+    (1560, "bit", -1, "bit"),
+    (1562, "varbit", -1, "bit varying"),
+    (1700, "numeric", -1, "numeric"),
+    (2950, "uuid", 16, "uuid"),
+    (3802, "jsonb", -1, "jsonb"),
+];
+
+/// Returns the entry in [`PG_TYPES`] for the PostgreSQL type used to represent values of `ty`
+fn pg_type(ty: &SqlType) -> &'static (u32, &'static str, i16, &'static str) {
+    use SqlType::*;
+    let name = match ty {
+        Bool => "bool",
+        Char(_) => "bpchar",
+        Varchar(_) => "varchar",
+        Int(_) | Serial | UnsignedSmallint(_) => "int4",
+        Bigint(_) | BigSerial | UnsignedInt(_) => "int8",
+        Smallint(_) | Tinyint(_) | UnsignedTinyint(_) => "int2",
+        UnsignedBigint(_) | Numeric(_) | Decimal(..) => "numeric",
+        Real | Float => "float4",
+        Double => "float8",
+        Text | Tinytext | Mediumtext | Longtext | Enum(_) => "text",
+        Blob | Longblob | Mediumblob | Tinyblob | Binary(_) | Varbinary(_) | ByteArray => "bytea",
+        Date => "date",
+        Time => "time",
+        Timestamp | DateTime(_) => "timestamp",
+        TimestampTz => "timestamptz",
+        Json => "json",
+        Jsonb => "jsonb",
+        MacAddr => "macaddr",
+        Inet => "inet",
+        Uuid => "uuid",
+        Bit(_) => "bit",
+        Varbit(_) => "varbit",
+    };
+    #[allow(clippy::unwrap_used)] // every name above is in PG_TYPES
+    let ty = PG_TYPES.iter().find(|(_, n, _, _)| *n == name).unwrap();
+    ty
+}
+
+/// One of the catalog tables that can be queried through the adapter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CatalogTable {
+    Tables,
+    Columns,
+    KeyColumnUsage,
+    PgNamespace,
+    PgClass,
+    PgType,
+    PgAttribute,
+}
+
+impl CatalogTable {
+    /// Returns the catalog table referred to by `table` in `dialect`, if it's one we emulate. The
+    /// tables in `pg_catalog` can be referred to without a schema, since it's always in
+    /// PostgreSQL's search path.
+    pub(crate) fn from_table(dialect: Dialect, table: &Table) -> Option<Self> {
+        let schema = table.schema.as_ref().map(|s| s.to_ascii_lowercase());
+        let name = table.name.to_ascii_lowercase();
+        match (schema.as_deref(), name.as_str()) {
+            (Some("information_schema"), "tables") => Some(Self::Tables),
+            (Some("information_schema"), "columns") => Some(Self::Columns),
+            (Some("information_schema"), "key_column_usage") => Some(Self::KeyColumnUsage),
+            (None | Some("pg_catalog"), name) if dialect.kind() == DialectKind::PostgreSQL => {
+                match name {
+                    "pg_namespace" => Some(Self::PgNamespace),
+                    "pg_class" => Some(Self::PgClass),
+                    "pg_type" => Some(Self::PgType),
+                    "pg_attribute" => Some(Self::PgAttribute),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the catalog table queried by `stmt`, if it queries a single catalog table
+    pub(crate) fn from_query(dialect: Dialect, stmt: &SelectStatement) -> Option<Self> {
+        match stmt.tables.as_slice() {
+            [table] if stmt.join.is_empty() && stmt.ctes.is_empty() => {
+                Self::from_table(dialect, table)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the names and types of the columns of this table. MySQL names the columns of the
+    /// tables in `information_schema` in upper case.
+    fn columns(self, dialect: Dialect) -> Vec<(String, SqlType)> {
+        let text = || SqlType::Text;
+        let columns: Vec<(&str, SqlType)> = match (self, dialect) {
+            (Self::Tables, _) => vec![
+                ("table_catalog", text()),
+                ("table_schema", text()),
+                ("table_name", text()),
+                ("table_type", text()),
+            ],
+            (Self::Columns, _) => {
+                let mut columns = vec![
+                    ("table_catalog", text()),
+                    ("table_schema", text()),
+                    ("table_name", text()),
+                    ("column_name", text()),
+                    ("ordinal_position", SqlType::Bigint(None)),
+                    ("column_default", text()),
+                    ("is_nullable", text()),
+                    ("data_type", text()),
+                    ("character_maximum_length", SqlType::Bigint(None)),
+                ];
+                match dialect.kind() {
+                    DialectKind::MySQL => columns.extend([
+                        ("column_type", text()),
+                        ("column_key", text()),
+                        ("extra", text()),
+                    ]),
+                    DialectKind::PostgreSQL => columns.push(("udt_name", text())),
+                }
+                columns
+            }
+            (Self::KeyColumnUsage, _) => {
+                let mut columns = vec![
+                    ("constraint_catalog", text()),
+                    ("constraint_schema", text()),
+                    ("constraint_name", text()),
+                    ("table_catalog", text()),
+                    ("table_schema", text()),
+                    ("table_name", text()),
+                    ("column_name", text()),
+                    ("ordinal_position", SqlType::Bigint(None)),
+                    ("position_in_unique_constraint", SqlType::Bigint(None)),
+                ];
+                if dialect.kind() == DialectKind::MySQL {
+                    columns.extend([
+                        ("referenced_table_schema", text()),
+                        ("referenced_table_name", text()),
+                        ("referenced_column_name", text()),
+                    ]);
+                }
+                columns
+            }
+            (Self::PgNamespace, _) => vec![("oid", SqlType::Int(None)), ("nspname", text())],
+            (Self::PgClass, _) => vec![
+                ("oid", SqlType::Int(None)),
+                ("relname", text()),
+                ("relnamespace", SqlType::Int(None)),
+                ("relkind", SqlType::Char(Some(1))),
+                ("relnatts", SqlType::Smallint(None)),
+            ],
+            (Self::PgType, _) => vec![
+                ("oid", SqlType::Int(None)),
+                ("typname", text()),
+                ("typnamespace", SqlType::Int(None)),
+                ("typlen", SqlType::Smallint(None)),
+                ("typtype", SqlType::Char(Some(1))),
+            ],
+            (Self::PgAttribute, _) => vec![
+                ("attrelid", SqlType::Int(None)),
+                ("attname", text()),
+                ("atttypid", SqlType::Int(None)),
+                ("attlen", SqlType::Smallint(None)),
+                ("attnum", SqlType::Smallint(None)),
+                ("attnotnull", SqlType::Bool),
+                ("atthasdef", SqlType::Bool),
+                ("attisdropped", SqlType::Bool),
+            ],
+        };
+
+        let upper_case = dialect.kind() == DialectKind::MySQL
+            && matches!(self, Self::Tables | Self::Columns | Self::KeyColumnUsage);
+        columns
+            .into_iter()
+            .map(|(name, ty)| {
+                let name = if upper_case {
+                    name.to_ascii_uppercase()
+                } else {
+                    name.to_owned()
+                };
+                (name, ty)
+            })
+            .collect()
+    }
+
+    /// Returns the rows of this table describing the schema in `catalog`, with one value for each
+    /// of the columns returned by [`Self::columns`]
+    fn rows(self, catalog: &Catalog) -> Vec<Vec<DataType>> {
+        let dialect = catalog.dialect;
+        let (table_catalog, table_schema) = match dialect.kind() {
+            DialectKind::MySQL => ("def", catalog.database.as_str()),
+            DialectKind::PostgreSQL => (catalog.database.as_str(), "public"),
+        };
+
+        match self {
+            Self::Tables => catalog
+                .tables
+                .iter()
+                .map(|table| {
+                    vec![
+                        table_catalog.into(),
+                        table_schema.into(),
+                        table.table.name.as_str().into(),
+                        "BASE TABLE".into(),
+                    ]
+                })
+                .collect(),
+            Self::Columns => catalog
+                .tables
+                .iter()
+                .flat_map(|table| {
+                    let constraints = constraints(dialect, table);
+                    table.fields.iter().enumerate().map(move |(i, field)| {
+                        let mut row = vec![
+                            table_catalog.into(),
+                            table_schema.into(),
+                            table.table.name.as_str().into(),
+                            field.column.name.as_str().into(),
+                            DataType::from(i + 1),
+                            column_default(dialect, &table.table, field).into(),
+                            if is_nullable(field, &constraints) {
+                                "YES"
+                            } else {
+                                "NO"
+                            }
+                            .into(),
+                            data_type(dialect, &field.sql_type).into(),
+                            match field.sql_type {
+                                SqlType::Char(len) | SqlType::Varchar(len) => len.into(),
+                                _ => DataType::None,
+                            },
+                        ];
+                        match dialect.kind() {
+                            DialectKind::MySQL => row.extend([
+                                field.sql_type.to_string().to_ascii_lowercase().into(),
+                                column_key(field, &constraints).into(),
+                                if is_auto_increment(field) {
+                                    "auto_increment"
+                                } else {
+                                    ""
+                                }
+                                .into(),
+                            ]),
+                            DialectKind::PostgreSQL => row.push(pg_type(&field.sql_type).1.into()),
+                        }
+                        row
+                    })
+                })
+                .collect(),
+            Self::KeyColumnUsage => catalog
+                .tables
+                .iter()
+                .flat_map(|table| {
+                    constraints(dialect, table)
+                        .into_iter()
+                        .filter(|constraint| constraint.kind != ConstraintKind::Index)
+                        .flat_map(move |constraint| {
+                            let columns = constraint.columns.clone();
+                            columns.into_iter().enumerate().map(move |(i, column)| {
+                                let referenced = constraint
+                                    .references
+                                    .as_ref()
+                                    .map(|(table, columns)| (table.clone(), columns.get(i)));
+                                let mut row = vec![
+                                    table_catalog.into(),
+                                    table_schema.into(),
+                                    constraint.name.as_str().into(),
+                                    table_catalog.into(),
+                                    table_schema.into(),
+                                    table.table.name.as_str().into(),
+                                    column.as_str().into(),
+                                    DataType::from(i + 1),
+                                    referenced
+                                        .as_ref()
+                                        .map(|_| DataType::from(i + 1))
+                                        .unwrap_or(DataType::None),
+                                ];
+                                if dialect.kind() == DialectKind::MySQL {
+                                    row.extend(match referenced {
+                                        Some((table, column)) => [
+                                            table_schema.into(),
+                                            table.as_str().into(),
+                                            column.map(|c| c.as_str()).into(),
+                                        ],
+                                        None => [DataType::None, DataType::None, DataType::None],
+                                    })
+                                }
+                                row
+                            })
+                        })
+                })
+                .collect(),
+            Self::PgNamespace => vec![
+                vec![PG_CATALOG_NAMESPACE.into(), "pg_catalog".into()],
+                vec![PUBLIC_NAMESPACE.into(), "public".into()],
+            ],
+            Self::PgClass => catalog
+                .tables
+                .iter()
+                .enumerate()
+                .map(|(i, table)| {
+                    vec![
+                        table_oid(i).into(),
+                        table.table.name.as_str().into(),
+                        PUBLIC_NAMESPACE.into(),
+                        "r".into(),
+                        table.fields.len().into(),
+                    ]
+                })
+                .collect(),
+            Self::PgType => PG_TYPES
+                .iter()
+                .map(|(oid, name, len, _)| {
+                    vec![
+                        (*oid).into(),
+                        (*name).into(),
+                        PG_CATALOG_NAMESPACE.into(),
+                        (*len).into(),
+                        "b".into(),
+                    ]
+                })
+                .collect(),
+            Self::PgAttribute => catalog
+                .tables
+                .iter()
+                .enumerate()
+                .flat_map(|(i, table)| {
+                    let constraints = constraints(dialect, table);
+                    table.fields.iter().enumerate().map(move |(attnum, field)| {
+                        let (type_oid, _, type_len, _) = pg_type(&field.sql_type);
+                        vec![
+                            table_oid(i).into(),
+                            field.column.name.as_str().into(),
+                            (*type_oid).into(),
+                            (*type_len).into(),
+                            (attnum + 1).into(),
+                            (!is_nullable(field, &constraints)).into(),
+                            column_default(dialect, &table.table, field)
+                                .is_some()
+                                .into(),
+                            false.into(),
+                        ]
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns the oid given to the base table at position `index` in [`Catalog::tables`]
+fn table_oid(index: usize) -> u32 {
+    FIRST_TABLE_OID + index as u32
+}
+
+fn is_auto_increment(field: &ColumnSpecification) -> bool {
+    field.constraints.contains(&ColumnConstraint::AutoIncrement)
+}
+
+/// Returns the default value of `field`, formatted the way `information_schema.columns` does for
+/// `dialect`
+fn column_default(dialect: Dialect, table: &Table, field: &ColumnSpecification) -> Option<String> {
+    if dialect.kind() == DialectKind::PostgreSQL && is_auto_increment(field) {
+        return Some(format!(
+            "nextval('{}_{}_seq'::regclass)",
+            table.name, field.column.name
+        ));
+    }
+    field.constraints.iter().find_map(|c| match c {
+        ColumnConstraint::DefaultValue(Literal::Null) => None,
+        ColumnConstraint::DefaultValue(Literal::String(s))
+            if dialect.kind() == DialectKind::MySQL =>
+        {
+            Some(s.clone())
+        }
+        ColumnConstraint::DefaultValue(lit) => Some(lit.to_string()),
+        _ => None,
+    })
+}
+
+/// Returns the name `information_schema.columns` gives the type `ty` in `dialect`
+fn data_type(dialect: Dialect, ty: &SqlType) -> String {
+    match dialect.kind() {
+        DialectKind::MySQL => {
+            let ty = ty.to_string().to_ascii_lowercase();
+            match ty.find(['(', ' ']) {
+                Some(end) => ty[..end].to_owned(),
+                None => ty,
+            }
+        }
+        DialectKind::PostgreSQL => pg_type(ty).3.to_owned(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    ForeignKey,
+    Index,
+}
+
+/// A key of a base table
+#[derive(Debug)]
+struct Constraint {
+    kind: ConstraintKind,
+    name: String,
+    columns: Vec<SqlIdentifier>,
+    /// The table and columns referenced by a foreign key
+    references: Option<(SqlIdentifier, Vec<SqlIdentifier>)>,
+}
+
+/// Returns the keys of `table`, named the way `dialect` names them if they weren't given a name,
+/// with the primary key first. Indexes that aren't constraints are only used for MySQL's
+/// `COLUMN_KEY`, and aren't included in `information_schema.key_column_usage`.
+fn constraints(dialect: Dialect, table: &CreateTableStatement) -> Vec<Constraint> {
+    let table_name = table.table.name.as_str();
+    let name_or = |name: &Option<SqlIdentifier>, kind: ConstraintKind, columns: &[Column]| {
+        if let Some(name) = name {
+            return name.to_string();
+        }
+        let first_column = columns.first().map(|c| c.name.as_str()).unwrap_or_default();
+        match (dialect.kind(), kind) {
+            (DialectKind::MySQL, ConstraintKind::PrimaryKey) => "PRIMARY".to_owned(),
+            (DialectKind::MySQL, _) => first_column.to_owned(),
+            (DialectKind::PostgreSQL, ConstraintKind::PrimaryKey) => format!("{}_pkey", table_name),
+            (DialectKind::PostgreSQL, ConstraintKind::Unique) => {
+                format!("{}_{}_key", table_name, first_column)
+            }
+            (DialectKind::PostgreSQL, _) => format!("{}_{}_fkey", table_name, first_column),
+        }
+    };
+    let names = |columns: &[Column]| columns.iter().map(|c| c.name.clone()).collect();
+
+    let mut constraints = vec![];
+    for key in table.keys.iter().flatten() {
+        let (kind, name, columns, references) = match key {
+            TableKey::PrimaryKey { name, columns } => {
+                (ConstraintKind::PrimaryKey, name, columns, None)
+            }
+            TableKey::UniqueKey { name, columns, .. } => {
+                (ConstraintKind::Unique, name, columns, None)
+            }
+            TableKey::ForeignKey {
+                name,
+                columns,
+                target_table,
+                target_columns,
+                ..
+            } => (
+                ConstraintKind::ForeignKey,
+                name,
+                columns,
+                Some((target_table.name.clone(), names(target_columns))),
+            ),
+            TableKey::FulltextKey { columns, .. } | TableKey::Key { columns, .. } => {
+                constraints.push(Constraint {
+                    kind: ConstraintKind::Index,
+                    name: String::new(),
+                    columns: names(columns),
+                    references: None,
+                });
+                continue;
+            }
+            TableKey::CheckConstraint { .. } => continue,
+        };
+        constraints.push(Constraint {
+            kind,
+            name: name_or(name, kind, columns),
+            columns: names(columns),
+            references,
+        });
+    }
+    // Keys declared on a column may also be present in the table's keys
+    for field in &table.fields {
+        for (constraint, kind) in [
+            (ColumnConstraint::PrimaryKey, ConstraintKind::PrimaryKey),
+            (ColumnConstraint::Unique, ConstraintKind::Unique),
+        ] {
+            let columns = [field.column.clone()];
+            if field.constraints.contains(&constraint)
+                && !constraints
+                    .iter()
+                    .any(|c| c.kind == kind && c.columns == names(&columns))
+            {
+                constraints.push(Constraint {
+                    kind,
+                    name: name_or(&None, kind, &columns),
+                    columns: names(&columns),
+                    references: None,
+                });
+            }
+        }
+    }
+
+    constraints.sort_by_key(|c| c.kind != ConstraintKind::PrimaryKey);
+    constraints
+}
+
+fn is_nullable(field: &ColumnSpecification, constraints: &[Constraint]) -> bool {
+    !field.constraints.contains(&ColumnConstraint::NotNull)
+        && !constraints
+            .iter()
+            .any(|c| c.kind == ConstraintKind::PrimaryKey && c.columns.contains(&field.column.name))
+}
+
+/// Returns MySQL's `COLUMN_KEY` for `field`: `PRI` if it's part of the primary key, `UNI` if it's
+/// the only column of a unique key, and `MUL` if it's the first column of any other key.
+fn column_key(field: &ColumnSpecification, constraints: &[Constraint]) -> &'static str {
+    let name = &field.column.name;
+    if constraints
+        .iter()
+        .any(|c| c.kind == ConstraintKind::PrimaryKey && c.columns.contains(name))
+    {
+        "PRI"
+    } else if constraints
+        .iter()
+        .any(|c| c.kind == ConstraintKind::Unique && c.columns == [name.clone()])
+    {
+        "UNI"
+    } else if constraints.iter().any(|c| c.columns.first() == Some(name)) {
+        "MUL"
+    } else {
+        ""
+    }
+}
+
+/// The schema that catalog queries are answered from
+#[derive(Debug)]
+pub(crate) struct Catalog {
+    pub(crate) dialect: Dialect,
+    /// The name of the database clients are connected to
+    pub(crate) database: String,
+    /// The schemas of the base tables, in order of name
+    pub(crate) tables: Vec<CreateTableStatement>,
+}
+
+impl Catalog {
+    /// Answers `stmt`, a query of `table`, if it's simple enough for us to evaluate
+    pub(crate) fn query(
+        &self,
+        table: CatalogTable,
+        stmt: &SelectStatement,
+    ) -> Option<QueryResult<'static>> {
+        self.query_relation(
+            &stmt.tables[0],
+            table.columns(self.dialect),
+            table.rows(self),
+            stmt,
+        )
+    }
+
+    /// Answers `stmt` if it selects from a `VALUES` table constructor (and nothing else) and is
+    /// simple enough for us to evaluate
+    pub(crate) fn query_values(&self, stmt: &SelectStatement) -> Option<QueryResult<'static>> {
+        let values = stmt.values_from()?;
+        if stmt.join.len() != 1 || !stmt.ctes.is_empty() {
+            return None;
+        }
+
+        let table = Table::from(values.alias.as_str());
+        let (columns, rows) =
+            self.values_relation(&values.values, values.column_names(self.dialect))?;
+        self.query_relation(&table, columns, rows, stmt)
+    }
+
+    /// Answers a `VALUES` statement, if all of its rows are made of expressions we can evaluate
+    pub(crate) fn values(&self, values: &ValuesStatement) -> Option<QueryResult<'static>> {
+        let (columns, rows) = self.values_relation(values, values.column_names(self.dialect))?;
+        Some(select_result(
+            columns
+                .into_iter()
+                .map(|(name, ty)| (name.into(), ty))
+                .collect(),
+            rows,
+        ))
+    }
+
+    /// Evaluates the rows of `values`, returning them along with the names and types of its
+    /// columns. The type of each column is taken from its value in the first row.
+    fn values_relation(
+        &self,
+        values: &ValuesStatement,
+        names: Vec<SqlIdentifier>,
+    ) -> Option<(Vec<(String, SqlType)>, Vec<Vec<DataType>>)> {
+        let empty = Table::default();
+        let scope = Scope {
+            catalog: self,
+            table: &empty,
+            columns: vec![],
+        };
+        let rows = values
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|expr| scope.eval(expr, &[]))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let columns = names
+            .into_iter()
+            .zip(rows.first()?)
+            .map(|(name, value)| (name.to_string(), value.sql_type().unwrap_or(SqlType::Text)))
+            .collect();
+        Some((columns, rows))
+    }
+
+    /// Answers `stmt`, a query of the relation named by `table` with the given columns and rows, if
+    /// it's simple enough for us to evaluate
+    fn query_relation(
+        &self,
+        table: &Table,
+        columns: Vec<(String, SqlType)>,
+        relation: Vec<Vec<DataType>>,
+        stmt: &SelectStatement,
+    ) -> Option<QueryResult<'static>> {
+        if stmt.group_by.is_some() || stmt.having.is_some() {
+            return None;
+        }
+
+        let scope = Scope {
+            catalog: self,
+            table,
+            columns,
+        };
+
+        // Check the query only uses expressions we can evaluate by evaluating them against a row of
+        // nulls, so that we don't answer unsupported queries when the table happens to be empty
+        let nulls = vec![DataType::None; scope.columns.len()];
+        if let Some(expr) = &stmt.where_clause {
+            scope.eval(expr, &nulls)?;
+        }
+        for (field, _) in stmt.order.iter().flat_map(|order| &order.order_by) {
+            if let FieldReference::Expression(expr) = field {
+                scope.eval(expr, &nulls)?;
+            }
+        }
+
+        let mut fields = vec![];
+        for field in &stmt.fields {
+            match field {
+                FieldDefinitionExpression::All | FieldDefinitionExpression::AllInTable(_) => fields
+                    .extend(scope.columns.iter().map(|(name, ty)| {
+                        (
+                            SqlIdentifier::from(name.as_str()),
+                            ty.clone(),
+                            Expression::Column(Column {
+                                name: name.as_str().into(),
+                                table: None,
+                            }),
+                        )
+                    })),
+                FieldDefinitionExpression::Expression { expr, alias } => {
+                    let (name, ty) = match expr {
+                        Expression::Column(column) => {
+                            let (name, ty) = &scope.columns[scope.column(column)?];
+                            (SqlIdentifier::from(name.as_str()), ty.clone())
+                        }
+                        Expression::Literal(_) | Expression::Call(_) => (
+                            expr.to_string().into(),
+                            scope
+                                .eval(expr, &nulls)?
+                                .sql_type()
+                                .unwrap_or(SqlType::Text),
+                        ),
+                        _ => return None,
+                    };
+                    fields.push((alias.clone().unwrap_or(name), ty, expr.clone()));
+                }
+            }
+        }
+
+        let mut rows = vec![];
+        for row in relation {
+            if let Some(expr) = &stmt.where_clause {
+                if !scope.eval(expr, &row)?.is_truthy() {
+                    continue;
+                }
+            }
+            let projected = fields
+                .iter()
+                .map(|(_, _, expr)| scope.eval(expr, &row))
+                .collect::<Option<Vec<_>>>()?;
+            let order_key = stmt
+                .order
+                .iter()
+                .flat_map(|order| &order.order_by)
+                .map(|(field, _)| match field {
+                    FieldReference::Numeric(n) => {
+                        projected.get((*n as usize).checked_sub(1)?).cloned()
+                    }
+                    FieldReference::Expression(expr) => scope.eval(expr, &row),
+                })
+                .collect::<Option<Vec<_>>>()?;
+            rows.push((order_key, projected));
+        }
+
+        if let Some(order) = &stmt.order {
+            rows.sort_by(|(a, _), (b, _)| {
+                a.iter()
+                    .zip(b)
+                    .zip(&order.order_by)
+                    .map(|((a, b), (_, order_type))| match order_type {
+                        Some(OrderType::OrderDescending) => b.cmp(a),
+                        _ => a.cmp(b),
+                    })
+                    .find(|ord| *ord != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+
+        let mut rows = rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>();
+        if stmt.distinct {
+            let mut seen = HashSet::new();
+            rows.retain(|row| seen.insert(row.clone()));
+        }
+        if let Some(limit) = &stmt.limit {
+            let to_usize = |lit: &Literal| {
+                usize::try_from(u64::try_from(DataType::try_from(lit).ok()?).ok()?).ok()
+            };
+            let offset = match &limit.offset {
+                Some(offset) => to_usize(offset)?,
+                None => 0,
+            };
+            rows = rows
+                .into_iter()
+                .skip(offset)
+                .take(to_usize(&limit.limit)?)
+                .collect();
+        }
+
+        Some(select_result(
+            fields.into_iter().map(|(name, ty, _)| (name, ty)).collect(),
+            rows,
+        ))
+    }
+}
+
+/// Builds the result of a query returning `rows`, with columns of the given names and types
+fn select_result(
+    fields: Vec<(SqlIdentifier, SqlType)>,
+    rows: Vec<Vec<DataType>>,
+) -> QueryResult<'static> {
+    let columns = fields
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    let schema = fields
+        .into_iter()
+        .map(|(name, sql_type)| ColumnSchema {
+            spec: ColumnSpecification {
+                column: Column { name, table: None },
+                sql_type,
+                constraints: vec![],
+                comment: None,
+            },
+            base: None,
+        })
+        .collect::<Vec<_>>();
+    QueryResult::Select {
+        data: vec![Results::new(rows, columns.clone().into())],
+        select_schema: SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(schema),
+            columns: Cow::Owned(columns),
+        },
+    }
+}
+
+/// The relation being queried, used to evaluate expressions against its rows
+struct Scope<'a> {
+    catalog: &'a Catalog,
+    /// The relation as it was named in the query
+    table: &'a Table,
+    columns: Vec<(String, SqlType)>,
+}
+
+impl<'a> Scope<'a> {
+    /// Returns the position of `column` in the relation, if it refers to one of its columns
+    fn column(&self, column: &Column) -> Option<usize> {
+        if let Some(table) = &column.table {
+            let name = self.table.alias.as_ref().unwrap_or(&self.table.name);
+            if !table.eq_ignore_ascii_case(name) {
+                return None;
+            }
+        }
+        self.columns
+            .iter()
+            .position(|(name, _)| name.eq_ignore_ascii_case(&column.name))
+    }
+
+    /// Evaluates `expr` against `row`, returning `None` if it isn't an expression we support
+    fn eval(&self, expr: &Expression, row: &[DataType]) -> Option<DataType> {
+        let dialect = self.catalog.dialect;
+        match expr {
+            Expression::Column(column) => row.get(self.column(column)?).cloned(),
+            Expression::Literal(lit) => DataType::try_from(lit).ok(),
+            Expression::Call(FunctionExpression::Call { name, arguments })
+                if arguments.is_empty() =>
+            {
+                match (dialect.kind(), name.to_ascii_lowercase().as_str()) {
+                    (DialectKind::MySQL, "database" | "schema")
+                    | (DialectKind::PostgreSQL, "current_database") => {
+                        Some(self.catalog.database.as_str().into())
+                    }
+                    (DialectKind::PostgreSQL, "current_schema") => Some("public".into()),
+                    _ => None,
+                }
+            }
+            Expression::UnaryOp {
+                op: UnaryOperator::Not,
+                rhs,
+            } => {
+                let value = self.eval(rhs, row)?;
+                if value.is_none() {
+                    Some(DataType::None)
+                } else {
+                    Some((!value.is_truthy()).into())
+                }
+            }
+            Expression::BinaryOp {
+                lhs,
+                op: op @ (BinaryOperator::Is | BinaryOperator::IsNot),
+                rhs,
+            } if matches!(**rhs, Expression::Literal(Literal::Null)) => {
+                let is_null = self.eval(lhs, row)?.is_none();
+                Some((is_null == (*op == BinaryOperator::Is)).into())
+            }
+            Expression::BinaryOp { lhs, op, rhs } => {
+                let lhs = self.eval(lhs, row)?;
+                let rhs = self.eval(rhs, row)?;
+                match op {
+                    BinaryOperator::And | BinaryOperator::Or => {
+                        let short_circuit = *op == BinaryOperator::Or;
+                        if [&lhs, &rhs]
+                            .iter()
+                            .any(|v| !v.is_none() && v.is_truthy() == short_circuit)
+                        {
+                            Some(short_circuit.into())
+                        } else if lhs.is_none() || rhs.is_none() {
+                            Some(DataType::None)
+                        } else {
+                            Some((!short_circuit).into())
+                        }
+                    }
+                    _ if lhs.is_none() || rhs.is_none() => Some(DataType::None),
+                    BinaryOperator::Like
+                    | BinaryOperator::NotLike
+                    | BinaryOperator::ILike
+                    | BinaryOperator::NotILike => {
+                        // MySQL's catalog tables use case-insensitive collations
+                        let case_sensitivity = match op {
+                            BinaryOperator::Like | BinaryOperator::NotLike
+                                if dialect.kind() == DialectKind::PostgreSQL =>
+                            {
+                                CaseSensitive
+                            }
+                            _ => CaseInsensitive,
+                        };
+                        let pattern =
+                            LikePattern::new(<&str>::try_from(&rhs).ok()?, case_sensitivity);
+                        let matches = pattern.matches(<&str>::try_from(&lhs).ok()?);
+                        let negated =
+                            matches!(op, BinaryOperator::NotLike | BinaryOperator::NotILike);
+                        Some((matches != negated).into())
+                    }
+                    BinaryOperator::Equal => Some((lhs == rhs).into()),
+                    BinaryOperator::NotEqual => Some((lhs != rhs).into()),
+                    BinaryOperator::Greater => Some((lhs > rhs).into()),
+                    BinaryOperator::GreaterOrEqual => Some((lhs >= rhs).into()),
+                    BinaryOperator::Less => Some((lhs < rhs).into()),
+                    BinaryOperator::LessOrEqual => Some((lhs <= rhs).into()),
+                    _ => None,
+                }
+            }
+            Expression::In {
+                lhs,
+                rhs: InValue::List(list),
+                negated,
+            } => {
+                let lhs = self.eval(lhs, row)?;
+                let list = list
+                    .iter()
+                    .map(|expr| self.eval(expr, row))
+                    .collect::<Option<Vec<_>>>()?;
+                if lhs.is_none() {
+                    Some(DataType::None)
+                } else {
+                    Some((list.contains(&lhs) != *negated).into())
+                }
+            }
+            Expression::Between {
+                operand,
+                min,
+                max,
+                negated,
+            } => {
+                let operand = self.eval(operand, row)?;
+                let min = self.eval(min, row)?;
+                let max = self.eval(max, row)?;
+                if operand.is_none() || min.is_none() || max.is_none() {
+                    Some(DataType::None)
+                } else {
+                    Some(((min <= operand && operand <= max) != *negated).into())
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_create_table, parse_query, SqlQuery};
+
+    use super::*;
+
+    fn catalog(dialect: Dialect) -> Catalog {
+        let tables = [
+            "CREATE TABLE posts (id INT NOT NULL AUTO_INCREMENT, author INT, title VARCHAR(255) \
+             DEFAULT 'untitled', PRIMARY KEY (id), \
+             CONSTRAINT posts_author FOREIGN KEY (author) REFERENCES users (id))",
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT NOT NULL)",
+        ]
+        .iter()
+        .map(|t| parse_create_table(dialect, *t).unwrap())
+        .collect();
+        Catalog {
+            dialect,
+            database: "db".to_owned(),
+            tables,
+        }
+    }
+
+    fn rows(result: QueryResult<'static>) -> (Vec<SqlIdentifier>, Vec<Vec<DataType>>) {
+        match result {
+            QueryResult::Select {
+                data,
+                select_schema,
+            } => (
+                select_schema.columns.into_owned(),
+                data.into_iter()
+                    .flat_map(Vec::<Vec<DataType>>::from)
+                    .collect(),
+            ),
+            _ => panic!("not a select result"),
+        }
+    }
+
+    fn query(catalog: &Catalog, query: &str) -> Option<(Vec<SqlIdentifier>, Vec<Vec<DataType>>)> {
+        let stmt = match parse_query(catalog.dialect, query).unwrap() {
+            SqlQuery::Select(stmt) => stmt,
+            _ => panic!("not a select"),
+        };
+        let table = CatalogTable::from_query(catalog.dialect, &stmt)?;
+        catalog.query(table, &stmt).map(rows)
+    }
+
+    #[test]
+    fn mysql_tables() {
+        let catalog = catalog(Dialect::MySQL);
+        let (columns, rows) = query(
+            &catalog,
+            "SELECT table_name AS name, TABLE_TYPE FROM information_schema.tables \
+             WHERE table_schema = DATABASE() ORDER BY table_name DESC",
+        )
+        .unwrap();
+        assert_eq!(columns, vec!["name", "TABLE_TYPE"]);
+        assert_eq!(
+            rows,
+            vec![
+                vec!["users".into(), "BASE TABLE".into()],
+                vec!["posts".into(), "BASE TABLE".into()]
+            ]
+        );
+    }
+
+    #[test]
+    fn mysql_columns() {
+        let catalog = catalog(Dialect::MySQL);
+        let (_, rows) = query(
+            &catalog,
+            "SELECT column_name, column_default, is_nullable, data_type, column_type, \
+             column_key, extra FROM information_schema.columns \
+             WHERE table_schema = 'db' AND table_name = 'posts' ORDER BY ordinal_position",
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    "id".into(),
+                    DataType::None,
+                    "NO".into(),
+                    "int".into(),
+                    "int".into(),
+                    "PRI".into(),
+                    "auto_increment".into()
+                ],
+                vec![
+                    "author".into(),
+                    DataType::None,
+                    "YES".into(),
+                    "int".into(),
+                    "int".into(),
+                    "MUL".into(),
+                    "".into()
+                ],
+                vec![
+                    "title".into(),
+                    "untitled".into(),
+                    "YES".into(),
+                    "varchar".into(),
+                    "varchar(255)".into(),
+                    "".into(),
+                    "".into()
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn mysql_key_column_usage() {
+        let catalog = catalog(Dialect::MySQL);
+        let (_, rows) = query(
+            &catalog,
+            "SELECT constraint_name, table_name, column_name, referenced_table_name, \
+             referenced_column_name FROM information_schema.key_column_usage \
+             WHERE table_schema = 'db' ORDER BY table_name, constraint_name",
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    "PRIMARY".into(),
+                    "posts".into(),
+                    "id".into(),
+                    DataType::None,
+                    DataType::None
+                ],
+                vec![
+                    "posts_author".into(),
+                    "posts".into(),
+                    "author".into(),
+                    "users".into(),
+                    "id".into()
+                ],
+                vec![
+                    "PRIMARY".into(),
+                    "users".into(),
+                    "id".into(),
+                    DataType::None,
+                    DataType::None
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn postgres_catalog() {
+        let catalog = catalog(Dialect::PostgreSQL);
+        let (_, rows) = query(
+            &catalog,
+            "SELECT attname, atttypid, attnum, attnotnull FROM pg_attribute \
+             WHERE attrelid = 16385 ORDER BY 3",
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["id".into(), 23u32.into(), 1usize.into(), true.into()],
+                vec!["name".into(), 25u32.into(), 2usize.into(), true.into()],
+            ]
+        );
+
+        let (_, rows) = query(
+            &catalog,
+            "SELECT nspname FROM pg_namespace WHERE nspname NOT LIKE 'pg_%'",
+        )
+        .unwrap();
+        assert_eq!(rows, vec![vec!["public".into()]]);
+
+        let (_, rows) = query(
+            &catalog,
+            "SELECT column_name, data_type, column_default FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name IN ('posts') LIMIT 1",
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![vec![
+                "id".into(),
+                "integer".into(),
+                "nextval('posts_id_seq'::regclass)".into()
+            ]]
+        );
+    }
+
+    #[test]
+    fn values() {
+        let catalog = catalog(Dialect::PostgreSQL);
+        let values = match parse_query(catalog.dialect, "VALUES (1, 'a'), (2, 'b')").unwrap() {
+            SqlQuery::Values(values) => values,
+            _ => panic!("not a values statement"),
+        };
+        assert_eq!(
+            rows(catalog.values(&values).unwrap()),
+            (
+                vec!["column1".into(), "column2".into()],
+                vec![vec![1.into(), "a".into()], vec![2.into(), "b".into()]]
+            )
+        );
+
+        let query_values = |query: &str| {
+            let stmt = match parse_query(catalog.dialect, query).unwrap() {
+                SqlQuery::Select(stmt) => stmt,
+                _ => panic!("not a select"),
+            };
+            catalog.query_values(&stmt).map(rows)
+        };
+        assert_eq!(
+            query_values(
+                "SELECT t.name FROM (VALUES (1, 'a'), (2, 'b'), (3, 'c')) AS t (id, name) \
+                 WHERE id >= 2 ORDER BY id DESC"
+            ),
+            Some((
+                vec!["name".into()],
+                vec![vec!["c".into()], vec!["b".into()]]
+            ))
+        );
+        assert_eq!(
+            query_values("SELECT * FROM (VALUES (1)) AS t"),
+            Some((vec!["column1".into()], vec![vec![1.into()]]))
+        );
+        assert!(
+            query_values("SELECT * FROM (VALUES (1)) AS t (id) JOIN users ON users.id = t.id")
+                .is_none()
+        );
+        assert!(query_values("SELECT * FROM (VALUES (?)) AS t").is_none());
+    }
+
+    #[test]
+    fn unsupported_queries() {
+        let pg_catalog = catalog(Dialect::PostgreSQL);
+        assert!(query(
+            &pg_catalog,
+            "SELECT attname, typname FROM pg_attribute a, pg_type t WHERE a.atttypid = t.oid"
+        )
+        .is_none());
+
+        let catalog = catalog(Dialect::MySQL);
+        assert!(query(&catalog, "SELECT * FROM tables").is_none());
+        assert!(query(&catalog, "SELECT * FROM pg_type").is_none());
+        assert!(query(
+            &catalog,
+            "SELECT count(*) FROM information_schema.tables GROUP BY table_schema"
+        )
+        .is_none());
+        assert!(query(
+            &catalog,
+            "SELECT * FROM information_schema.tables WHERE table_name = ?"
+        )
+        .is_none());
+        assert!(query(
+            &catalog,
+            "SELECT * FROM information_schema.tables WHERE nonexistent = 1"
+        )
+        .is_none());
+    }
+}
@@ -32,7 +32,7 @@ pub struct NoriaAdapterHttpRouter {
     pub valve: Valve,
 
     /// Used to retrive the prometheus scrape's render as a String when servicing
-    /// HTTP requests on /prometheus.
+    /// HTTP requests on /prometheus and /metrics.
     pub prometheus_handle: Option<PrometheusHandle>,
 }
 
@@ -164,7 +164,7 @@ impl Service<Request<Body>> for NoriaAdapterHttpRouter {
     ///
     /// * **URL**
     ///
-    ///   `/prometheus`
+    ///   `/prometheus` or `/metrics`
     ///
     /// * **Method:**
     ///
@@ -251,7 +251,7 @@ impl Service<Request<Body>> for NoriaAdapterHttpRouter {
 
                 Ok(res.unwrap())
             }),
-            (&Method::GET, "/prometheus") => {
+            (&Method::GET, "/prometheus" | "/metrics") => {
                 let body = self.prometheus_handle.as_ref().map(|x| x.render());
                 let res = res.header(CONTENT_TYPE, "text/plain");
                 let res = match body {
@@ -8,18 +8,24 @@
 #![feature(bool_to_option)]
 
 pub mod backend;
+pub mod cache_file;
+mod catalog;
 pub mod http_router;
 #[allow(dead_code)]
 pub mod migration_handler;
 pub mod outputs_synchronizer;
 mod query_handler;
+pub mod query_latency;
 #[allow(dead_code)] // TODO(ENG-685): Remove when utilized in main.
 pub mod query_status_cache;
 pub mod rewrite;
+pub mod slow_log;
 pub mod upstream_database;
+pub mod upstream_pool;
 mod utils;
 
 pub use crate::backend::{Backend, BackendBuilder};
 pub use crate::outputs_synchronizer::OutputsSynchronizer;
 pub use crate::query_handler::QueryHandler;
 pub use crate::upstream_database::{UpstreamDatabase, UpstreamPrepare};
+pub use crate::upstream_pool::{PooledUpstream, UpstreamPool, UpstreamPoolConfig};
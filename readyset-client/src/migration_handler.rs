@@ -18,7 +18,7 @@ use tokio::select;
 use tracing::{error, info, instrument, warn};
 
 use crate::backend::{noria_connector, NoriaConnector};
-use crate::query_status_cache::{MigrationState, QueryStatusCache};
+use crate::query_status_cache::{MigrationState, QueryStatusCache, UnsupportedReason};
 use crate::upstream_database::{IsFatalError, NoriaCompare};
 use crate::{utils, UpstreamDatabase};
 
@@ -101,7 +101,10 @@ where
                         }
                     } else {
                         for q in to_process {
-                            self.perform_migration(&q.0).await
+                            // Queries the caching policy doesn't allow to be cached stay pending
+                            if self.query_status_cache.is_cacheable(&q.0) {
+                                self.perform_migration(&q.0).await
+                            }
                         }
                     }
 
@@ -196,12 +199,11 @@ where
                 self.query_status_cache
                     .update_query_migration_state(stmt, MigrationState::Successful);
             }
-            Err(e) if e.caused_by_unsupported() => {
+            Err(e) if let Some(reason) = UnsupportedReason::from_error(stmt, &e) => {
                 error!(error = %e, query = %Sensitive(&stmt), "Select query is unsupported in ReadySet");
 
                 self.start_time.remove(stmt);
-                self.query_status_cache
-                    .update_query_migration_state(stmt, MigrationState::Unsupported);
+                self.query_status_cache.mark_unsupported(stmt, reason);
             }
             // Errors that were not caused by unsupported may be transient, do nothing
             // so we may retry the migration on this query.
@@ -212,7 +214,7 @@ where
                 if Instant::now() - *self.start_time.get(stmt).unwrap() > self.max_retry {
                     // Query failed for long enough, it is unsupported.
                     self.query_status_cache
-                        .update_query_migration_state(stmt, MigrationState::Unsupported);
+                        .mark_unsupported(stmt, UnsupportedReason::MigrationFailed);
                 }
             }
         }
@@ -243,10 +245,9 @@ where
                 self.query_status_cache
                     .update_query_migration_state(stmt, MigrationState::DryRunSucceeded);
             }
-            Err(e) if e.caused_by_unsupported() => {
+            Err(e) if let Some(reason) = UnsupportedReason::from_error(stmt, &e) => {
                 self.start_time.remove(stmt);
-                self.query_status_cache
-                    .update_query_migration_state(stmt, MigrationState::Unsupported);
+                self.query_status_cache.mark_unsupported(stmt, reason);
             }
             _ => {} // Leave it as pending.
         }
@@ -0,0 +1,157 @@
+//! Latency distributions of the reads executed by an adapter, tracked separately for executions
+//! served from ReadySet's caches and executions proxied to the upstream database so the two can
+//! be compared with `SHOW READYSET QUERY STATS`.
+use std::time::Duration;
+
+use readyset_client_metrics::{QueryDestination, QueryExecutionEvent};
+
+/// Number of buckets per doubling of latency. Quantiles are reported as the upper bound of the
+/// bucket they fall in, so they're overestimated by at most a factor of `2^(1/4)`, or about 19%
+const BUCKETS_PER_DOUBLING: f64 = 4.0;
+
+/// Number of buckets, which covers latencies of up to 2^32 microseconds (a little over an hour).
+/// Longer latencies are recorded in the last bucket
+const NUM_BUCKETS: usize = 128;
+
+/// A histogram of latencies, with exponentially sized buckets
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    buckets: [u64; NUM_BUCKETS],
+    count: u64,
+    total: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; NUM_BUCKETS],
+            count: 0,
+            total: Duration::ZERO,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn bucket(latency: Duration) -> usize {
+        let micros = latency.as_micros().max(1) as f64;
+        ((micros.log2() * BUCKETS_PER_DOUBLING).ceil() as usize).min(NUM_BUCKETS - 1)
+    }
+
+    fn upper_bound(bucket: usize) -> Duration {
+        Duration::from_secs_f64(2f64.powf(bucket as f64 / BUCKETS_PER_DOUBLING) / 1_000_000.0)
+    }
+
+    /// Record a single latency in the histogram
+    pub fn record(&mut self, latency: Duration) {
+        self.buckets[Self::bucket(latency)] += 1;
+        self.count += 1;
+        self.total += latency;
+    }
+
+    /// Returns the number of latencies recorded in the histogram
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the sum of the latencies recorded in the histogram
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns an estimate of the latency at quantile `q` (between 0 and 1) of the histogram, or
+    /// `None` if no latencies have been recorded
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let target = ((q * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return Some(Self::upper_bound(bucket));
+            }
+        }
+        Some(Self::upper_bound(NUM_BUCKETS - 1))
+    }
+}
+
+/// The latency distributions of a single query
+#[derive(Debug, Clone, Default)]
+pub struct QueryLatencies {
+    /// Latencies of executions of the query that were served by ReadySet
+    pub cached: LatencyHistogram,
+    /// Latencies of executions of the query that were proxied to the upstream database, including
+    /// executions that fell back to the upstream database after failing in ReadySet
+    pub proxied: LatencyHistogram,
+}
+
+impl QueryLatencies {
+    /// Record the end-to-end latency of the query execution described by `event`
+    pub fn record(&mut self, event: &QueryExecutionEvent) {
+        let latency = [
+            event.parse_duration,
+            event.readyset_duration,
+            event.upstream_duration,
+        ]
+        .into_iter()
+        .flatten()
+        .sum();
+        match event.destination {
+            Some(QueryDestination::Readyset) => self.cached.record(latency),
+            Some(QueryDestination::Upstream | QueryDestination::ReadysetThenUpstream) => {
+                self.proxied.record(latency)
+            }
+            Some(QueryDestination::Both) | None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_histogram() {
+        assert_eq!(LatencyHistogram::default().quantile(0.5), None);
+    }
+
+    #[test]
+    fn quantiles() {
+        let mut histogram = LatencyHistogram::default();
+        for ms in 1..=100 {
+            histogram.record(Duration::from_millis(ms));
+        }
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.total(), Duration::from_millis(5050));
+
+        for (q, expected) in [(0.5, 50), (0.9, 90), (0.99, 99)] {
+            let estimate = histogram.quantile(q).unwrap();
+            assert!(
+                estimate >= Duration::from_millis(expected)
+                    && estimate.as_secs_f64() <= expected as f64 / 1000.0 * 1.19,
+                "p{} estimated as {:?}",
+                q * 100.0,
+                estimate
+            );
+        }
+    }
+
+    #[test]
+    fn records_by_destination() {
+        use readyset_client_metrics::EventType;
+
+        let mut latencies = QueryLatencies::default();
+        let mut event = QueryExecutionEvent::new(EventType::Execute);
+        event.readyset_duration = Some(Duration::from_millis(1));
+        event.destination = Some(QueryDestination::Readyset);
+        latencies.record(&event);
+        event.upstream_duration = Some(Duration::from_millis(10));
+        event.destination = Some(QueryDestination::ReadysetThenUpstream);
+        latencies.record(&event);
+
+        assert_eq!(latencies.cached.count(), 1);
+        assert_eq!(latencies.proxied.count(), 1);
+        assert!(latencies.proxied.quantile(0.5).unwrap() >= Duration::from_millis(11));
+    }
+}
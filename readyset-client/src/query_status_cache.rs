@@ -1,14 +1,30 @@
 //! The query status cache provides a thread-safe window into an adapter's
 //! knowledge about queries, currently the migration status of a query in
-//! ReadySet.
+//! ReadySet, and whether the adapter's [`CachingPolicy`] allows it to be cached.
+//!
+//! Queries that ReadySet doesn't support are recorded along with an [`UnsupportedReason`], as are
+//! reads that ReadySet fails to parse, so that `SHOW PROXIED QUERIES` can tell users what to
+//! rewrite. The latencies of the reads that are proxied to the upstream database are used to rank
+//! the queries it would be most worthwhile to cache, as shown by `SHOW READYSET SUGGESTIONS`.
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use dashmap::DashMap;
-use nom_sql::SelectStatement;
+use dashmap::{DashMap, DashSet};
+use dataflow_expression::like::{CaseInsensitive, LikePattern};
+use lazy_static::lazy_static;
+use nom_sql::analysis::visit::{walk_select_statement, Visitor};
+use nom_sql::{Dialect, DialectKind, SelectStatement, SqlIdentifier, Table};
+use readyset_client_metrics::QueryExecutionEvent;
+use readyset_errors::ReadySetError;
+use readyset_sql_passes::is_correlated;
+use regex::Regex;
 use serde::ser::SerializeSeq;
 use serde::{Serialize, Serializer};
 
+use crate::query_latency::{LatencyHistogram, QueryLatencies};
 use crate::rewrite::anonymize_literals;
 use crate::utils::hash_select_query;
 
@@ -24,6 +40,9 @@ pub fn hash_to_query_id(hash: u64) -> String {
 pub struct QueryStatus {
     pub migration_state: MigrationState,
     pub execution_info: Option<ExecutionInfo>,
+    /// Why the query isn't supported by ReadySet, if it's [`MigrationState::Unsupported`] and
+    /// the reason is known
+    pub unsupported_reason: Option<UnsupportedReason>,
 }
 
 impl QueryStatus {
@@ -31,10 +50,107 @@ impl QueryStatus {
         Self {
             migration_state: MigrationState::Pending,
             execution_info: None,
+            unsupported_reason: None,
         }
     }
 }
 
+/// Why ReadySet doesn't support a query, shown by `SHOW PROXIED QUERIES` as a machine-readable
+/// code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnsupportedReason {
+    /// The query failed to parse. The offset is the byte offset in the query, with its literals
+    /// anonymized, at which it stopped being valid SQL
+    ParseError { offset: usize },
+    /// The query calls a function that ReadySet doesn't support
+    UnsupportedFunction(String),
+    /// The query contains a correlated subquery
+    CorrelatedSubquery,
+    /// The schema of the query's results in ReadySet doesn't match the upstream database's
+    SchemaMismatch,
+    /// Migrating the query kept failing for longer than the maximum retry time
+    MigrationFailed,
+    /// ReadySet doesn't support some other part of the query, described by the message
+    Unsupported(String),
+}
+
+impl UnsupportedReason {
+    /// Returns why ReadySet doesn't support `q`, if `error`, returned when preparing or executing
+    /// `q`, means that it doesn't
+    pub fn from_error(q: &Query, error: &ReadySetError) -> Option<Self> {
+        if let Some(name) = error.no_such_function() {
+            return Some(Self::UnsupportedFunction(name.to_owned()));
+        }
+        if !error.caused_by_unsupported() {
+            return None;
+        }
+        if has_correlated_subquery(q) {
+            return Some(Self::CorrelatedSubquery);
+        }
+        Some(Self::Unsupported(error.to_string()))
+    }
+}
+
+impl fmt::Display for UnsupportedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParseError { offset } => write!(f, "parse_error:{}", offset),
+            Self::UnsupportedFunction(name) => write!(f, "unsupported_function:{}", name),
+            Self::CorrelatedSubquery => write!(f, "correlated_subquery"),
+            Self::SchemaMismatch => write!(f, "schema_mismatch"),
+            Self::MigrationFailed => write!(f, "migration_failed"),
+            Self::Unsupported(message) => write!(f, "unsupported:{}", message),
+        }
+    }
+}
+
+/// Finds subqueries that refer to columns of the queries they're nested in
+#[derive(Default)]
+struct CorrelatedSubqueryVisitor {
+    depth: usize,
+    found: bool,
+}
+
+impl<'ast> Visitor<'ast> for CorrelatedSubqueryVisitor {
+    type Error = !;
+
+    fn visit_select_statement(
+        &mut self,
+        select_statement: &'ast mut SelectStatement,
+    ) -> Result<(), Self::Error> {
+        if self.depth > 0 && is_correlated(select_statement) {
+            self.found = true;
+        }
+        self.depth += 1;
+        walk_select_statement(self, select_statement)?;
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+/// Collects the names of the tables a query reads from, including in its subqueries
+#[derive(Default)]
+struct TablesVisitor(Vec<SqlIdentifier>);
+
+impl<'ast> Visitor<'ast> for TablesVisitor {
+    type Error = !;
+
+    fn visit_table(&mut self, table: &'ast mut Table) -> Result<(), Self::Error> {
+        if !self.0.contains(&table.name) {
+            self.0.push(table.name.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if `q` contains a correlated subquery
+fn has_correlated_subquery(q: &Query) -> bool {
+    let mut visitor = CorrelatedSubqueryVisitor::default();
+    #[allow(clippy::unwrap_used)] // error is !, which can never be returned
+    visitor.visit_select_statement(&mut q.clone()).unwrap();
+    visitor.found
+}
+
 /// Represents the current migration state of a given query. This state should be updated any time
 /// a migration is performed, or we learn that the migration state has changed, i.e. we receive a
 /// ViewNotFound error indicating a query is not migrated.
@@ -212,6 +328,66 @@ pub struct DeniedQuery {
     pub status: QueryStatus,
 }
 
+/// A read that ReadySet failed to parse, which was proxied to the upstream database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnparsedQuery {
+    /// The query's id, which is derived from its anonymized text
+    pub id: String,
+    /// The text of the query, with its literals anonymized
+    pub query: String,
+    /// Why the query isn't supported, which is always [`UnsupportedReason::ParseError`]
+    pub reason: UnsupportedReason,
+}
+
+/// The latencies of a read, as shown by `SHOW READYSET QUERY STATS`
+#[derive(Debug, Clone)]
+pub struct QueryStats {
+    /// The query's id
+    pub id: String,
+    /// The text of the query, with its literals anonymized
+    pub query: String,
+    pub latencies: QueryLatencies,
+}
+
+/// A read that is being proxied to the upstream database but that ReadySet could cache, as shown
+/// by `SHOW READYSET SUGGESTIONS`
+#[derive(Debug, Clone)]
+pub struct CacheSuggestion {
+    /// The query's id
+    pub id: String,
+    /// The text of the query, with its literals anonymized
+    pub query: String,
+    /// The names of the tables the query reads from
+    pub tables: Vec<SqlIdentifier>,
+    /// The latencies of the executions of the query that were proxied to the upstream database
+    pub proxied: LatencyHistogram,
+}
+
+/// Which queries ReadySet may cache. Queries that aren't allowed to be cached are always proxied to
+/// the upstream database.
+///
+/// Queries can be explicitly allowed to be cached by their ID (as shown by `SHOW PROXIED
+/// QUERIES`), either here or by running `CREATE CACHE` for them, which overrides the rest of the
+/// policy.
+#[derive(Default)]
+pub struct CachingPolicy {
+    /// Queries whose text (with literals anonymized, as shown by `SHOW PROXIED QUERIES`) matches
+    /// any of these case-insensitive LIKE patterns aren't cached, unless they've been explicitly
+    /// allowed
+    pub deny_patterns: Vec<String>,
+    /// If true, only queries which have been explicitly allowed are cached
+    pub allow_list_only: bool,
+    /// The IDs of queries which are explicitly allowed to be cached
+    pub allowed_ids: Vec<String>,
+}
+
+impl CachingPolicy {
+    /// Returns true if every query may be cached
+    fn allows_all(&self) -> bool {
+        !self.allow_list_only && self.deny_patterns.is_empty()
+    }
+}
+
 /// A metadata cache for all queries that have been processed by this
 /// adapter. Thread-safe.
 pub struct QueryStatusCache {
@@ -227,6 +403,26 @@ pub struct QueryStatusCache {
     /// Holds the current style of migration, whether async or explicit, which may change the
     /// behavior of some internal methods.
     style: MigrationStyle,
+
+    /// Which queries may be cached.
+    caching_policy: CachingPolicy,
+
+    /// The caching policy's deny patterns.
+    deny_patterns: Vec<LikePattern>,
+
+    /// The IDs of queries that have been explicitly allowed to be cached, either by the caching
+    /// policy or with `CREATE CACHE`.
+    allowed_ids: DashSet<String>,
+
+    /// Whether each query, by id, matches one of the caching policy's deny patterns, so that the
+    /// text of each query only has to be matched against the patterns once.
+    denied_ids: DashMap<String, bool>,
+
+    /// Reads that failed to parse, keyed by id.
+    unparsed: DashMap<String, UnparsedQuery>,
+
+    /// The latencies of executions of each read, keyed by id.
+    latencies: DashMap<String, QueryLatencies>,
 }
 
 impl Default for QueryStatusCache {
@@ -238,11 +434,7 @@ impl Default for QueryStatusCache {
 impl QueryStatusCache {
     /// Constructs a new QueryStatusCache with the migration style set to Async.
     pub fn new() -> QueryStatusCache {
-        QueryStatusCache {
-            statuses: DashMap::new(),
-            ids: DashMap::new(),
-            style: MigrationStyle::InRequestPath,
-        }
+        Self::with_style(MigrationStyle::InRequestPath)
     }
 
     /// Insert a query into the status cache. Inserts into the status and hash maps.
@@ -259,9 +451,61 @@ impl QueryStatusCache {
             statuses: DashMap::new(),
             ids: DashMap::new(),
             style,
+            caching_policy: CachingPolicy::default(),
+            deny_patterns: vec![],
+            allowed_ids: DashSet::new(),
+            denied_ids: DashMap::new(),
+            unparsed: DashMap::new(),
+            latencies: DashMap::new(),
         }
     }
 
+    /// Sets the policy for which queries may be cached.
+    pub fn with_caching_policy(mut self, mut caching_policy: CachingPolicy) -> QueryStatusCache {
+        self.allowed_ids = caching_policy.allowed_ids.drain(..).collect();
+        self.deny_patterns = caching_policy
+            .deny_patterns
+            .iter()
+            .map(|pattern| LikePattern::new(pattern, CaseInsensitive))
+            .collect();
+        self.caching_policy = caching_policy;
+        self
+    }
+
+    /// Returns true if the caching policy allows `q` to be cached.
+    pub fn is_cacheable(&self, q: &Query) -> bool {
+        if self.caching_policy.allows_all() {
+            return true;
+        }
+        let id = hash_to_query_id(hash_select_query(q));
+        if self.allowed_ids.contains(&id) {
+            return true;
+        }
+        if self.caching_policy.allow_list_only {
+            return false;
+        }
+        let denied = *self.denied_ids.entry(id).or_insert_with(|| {
+            let text = anonymized_query_text(q);
+            self.deny_patterns
+                .iter()
+                .any(|pattern| pattern.matches(&text))
+        });
+        !denied
+    }
+
+    /// Explicitly allows `q` to be cached, regardless of the caching policy.
+    pub fn allow_caching(&self, q: &Query) {
+        self.allowed_ids
+            .insert(hash_to_query_id(hash_select_query(q)));
+    }
+
+    /// Revokes an explicit allowance for `q` to be cached, so that it's only cached if the
+    /// caching policy allows it.
+    pub fn disallow_caching(&self, q: &Query) {
+        self.allowed_ids
+            .remove(&hash_to_query_id(hash_select_query(q)));
+    }
+
     /// This function returns the query migration state of a query. If the query does not exist
     /// within the query status cache, an entry is created and the query is set to
     /// PendingMigration.
@@ -409,6 +653,29 @@ impl QueryStatusCache {
                     QueryStatus {
                         migration_state: m,
                         execution_info: None,
+                        unsupported_reason: None,
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates a queries migration state to `MigrationState::Unsupported` for the given `reason`,
+    /// unless it already was.
+    pub fn mark_unsupported(&self, q: &Query, reason: UnsupportedReason) {
+        match self.statuses.get_mut(q) {
+            Some(mut s) if s.migration_state != MigrationState::Unsupported => {
+                s.migration_state = MigrationState::Unsupported;
+                s.unsupported_reason = Some(reason);
+            }
+            None => {
+                let _ = self.insert(
+                    q.clone(),
+                    QueryStatus {
+                        migration_state: MigrationState::Unsupported,
+                        execution_info: None,
+                        unsupported_reason: Some(reason),
                     },
                 );
             }
@@ -424,6 +691,7 @@ impl QueryStatusCache {
             Some(mut s) if s.migration_state != MigrationState::Unsupported => {
                 s.migration_state = status.migration_state;
                 s.execution_info = status.execution_info;
+                s.unsupported_reason = status.unsupported_reason;
             }
             Some(mut s) => {
                 s.execution_info = status.execution_info;
@@ -504,6 +772,115 @@ impl QueryStatusCache {
     pub fn query(&self, id: &str) -> Option<Query> {
         self.ids.get(id).map(|r| (*(*r.value())).clone())
     }
+
+    /// Records `query`, a read in the given SQL `dialect` which failed to parse, so that it's
+    /// listed by [`unparsed_queries`](Self::unparsed_queries).
+    pub fn insert_unparsed(&self, query: &str, dialect: Dialect) {
+        let query = anonymize_unparsed_literals(query.trim(), dialect);
+        let mut hasher = DefaultHasher::new();
+        query.hash(&mut hasher);
+        let id = hash_to_query_id(hasher.finish());
+
+        self.unparsed.entry(id.clone()).or_insert_with(|| {
+            let offset = nom_sql::parse_error_offset(dialect, &query).unwrap_or(0);
+            UnparsedQuery {
+                id,
+                query,
+                reason: UnsupportedReason::ParseError { offset },
+            }
+        });
+    }
+
+    /// Returns the reads that have failed to parse.
+    pub fn unparsed_queries(&self) -> Vec<UnparsedQuery> {
+        self.unparsed.iter().map(|r| r.value().clone()).collect()
+    }
+
+    /// Records the latency of the execution of the read with the given `id` described by `event`.
+    pub fn record_latency(&self, id: &str, event: &QueryExecutionEvent) {
+        self.latencies
+            .entry(id.to_owned())
+            .or_default()
+            .record(event);
+    }
+
+    /// Returns the latencies of every read that has been executed at least once.
+    pub fn query_stats(&self) -> Vec<QueryStats> {
+        self.latencies
+            .iter()
+            .filter_map(|r| {
+                let query = self.ids.get(r.key())?;
+                Some(QueryStats {
+                    id: r.key().clone(),
+                    query: anonymized_query_text(query.value()),
+                    latencies: r.value().clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the reads that have been proxied to the upstream database but that could be cached,
+    /// because they're neither cached already, known to be unsupported, nor disallowed by the
+    /// caching policy. The reads that spent the most time in total in the upstream database, and
+    /// so that would benefit the most from being cached, come first.
+    pub fn cache_suggestions(&self) -> Vec<CacheSuggestion> {
+        let mut suggestions = self
+            .latencies
+            .iter()
+            .filter(|r| r.value().proxied.count() > 0)
+            .filter_map(|r| {
+                let query = self.ids.get(r.key())?;
+                let query = query.value();
+                let state = self.statuses.get(query)?.migration_state.clone();
+                if matches!(
+                    state,
+                    MigrationState::Successful | MigrationState::Unsupported
+                ) || !self.is_cacheable(query)
+                {
+                    return None;
+                }
+
+                let mut tables = TablesVisitor::default();
+                #[allow(clippy::unwrap_used)] // error is !, which can never be returned
+                tables
+                    .visit_select_statement(&mut (**query).clone())
+                    .unwrap();
+                Some(CacheSuggestion {
+                    id: r.key().clone(),
+                    query: anonymized_query_text(query),
+                    tables: tables.0,
+                    proxied: r.value().proxied.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        suggestions.sort_by_key(|s| std::cmp::Reverse(s.proxied.total()));
+        suggestions
+    }
+}
+
+lazy_static! {
+    static ref MYSQL_LITERALS: Regex =
+        Regex::new(r#"'(?:[^'\\]|\\.|'')*'|"(?:[^"\\]|\\.|"")*"|\b\d+(?:\.\d+)?\b"#).unwrap();
+    static ref POSTGRESQL_LITERALS: Regex =
+        Regex::new(r#"'(?:[^'\\]|\\.|'')*'|\b\d+(?:\.\d+)?\b"#).unwrap();
+}
+
+/// Replaces the literals in `query`, which couldn't be parsed, with `'<anonymized>'`, like
+/// [`anonymize_literals`] does for parsed queries.
+fn anonymize_unparsed_literals(query: &str, dialect: Dialect) -> String {
+    let literals = match dialect.kind() {
+        DialectKind::MySQL => &*MYSQL_LITERALS,
+        DialectKind::PostgreSQL => &*POSTGRESQL_LITERALS,
+    };
+    literals.replace_all(query, "'<anonymized>'").into_owned()
+}
+
+/// Returns the text of `q` with its literals anonymized, as shown by `SHOW PROXIED QUERIES` and
+/// `SHOW CACHES`.
+pub fn anonymized_query_text(q: &Query) -> String {
+    let mut q = q.clone();
+    anonymize_literals(&mut q);
+    q.to_string()
 }
 
 /// MigrationStyle is used to communicate which style of managing migrations we have configured.
@@ -581,6 +958,186 @@ mod tests {
         assert_eq!(cache.deny_list().len(), 1);
     }
 
+    #[test]
+    fn caching_policy_deny_patterns() {
+        let cache = QueryStatusCache::new().with_caching_policy(CachingPolicy {
+            deny_patterns: vec!["%from `t2`%".to_owned()],
+            ..Default::default()
+        });
+        let allowed = select_statement("SELECT * FROM t1 WHERE x = 1").unwrap();
+        let denied = select_statement("SELECT * FROM t2 WHERE x = 1").unwrap();
+
+        assert!(cache.is_cacheable(&allowed));
+        assert!(!cache.is_cacheable(&denied));
+
+        cache.allow_caching(&denied);
+        assert!(cache.is_cacheable(&denied));
+        cache.disallow_caching(&denied);
+        assert!(!cache.is_cacheable(&denied));
+    }
+
+    #[test]
+    fn caching_policy_allow_list() {
+        let allowed = select_statement("SELECT * FROM t1").unwrap();
+        let other = select_statement("SELECT * FROM t2").unwrap();
+        let cache = QueryStatusCache::new().with_caching_policy(CachingPolicy {
+            allow_list_only: true,
+            allowed_ids: vec![hash_to_query_id(hash_select_query(&allowed))],
+            ..Default::default()
+        });
+
+        assert!(cache.is_cacheable(&allowed));
+        assert!(!cache.is_cacheable(&other));
+    }
+
+    #[test]
+    fn unsupported_reasons() {
+        let cache = QueryStatusCache::new();
+        let query = select_statement("SELECT * FROM t1").unwrap();
+
+        cache.mark_unsupported(&query, UnsupportedReason::MigrationFailed);
+        cache.mark_unsupported(&query, UnsupportedReason::SchemaMismatch);
+        let denied = cache.deny_list();
+        assert_eq!(denied.len(), 1);
+        assert_eq!(
+            denied[0].status.unsupported_reason,
+            Some(UnsupportedReason::MigrationFailed)
+        );
+        assert_eq!(
+            UnsupportedReason::MigrationFailed.to_string(),
+            "migration_failed"
+        );
+    }
+
+    #[test]
+    fn unsupported_reason_from_error() {
+        let query = select_statement("SELECT * FROM t1").unwrap();
+        let correlated =
+            select_statement("SELECT * FROM t1 WHERE EXISTS (SELECT * FROM t2 WHERE t2.x = t1.x)")
+                .unwrap();
+        let unsupported = ReadySetError::Unsupported("unions".to_owned());
+
+        assert_eq!(
+            UnsupportedReason::from_error(&query, &ReadySetError::NoSuchFunction("foo".into())),
+            Some(UnsupportedReason::UnsupportedFunction("foo".into()))
+        );
+        assert_eq!(
+            UnsupportedReason::from_error(&correlated, &unsupported),
+            Some(UnsupportedReason::CorrelatedSubquery)
+        );
+        assert_eq!(
+            UnsupportedReason::from_error(&query, &unsupported)
+                .unwrap()
+                .to_string(),
+            "unsupported:Operation unsupported: unions"
+        );
+        assert_eq!(
+            UnsupportedReason::from_error(&query, &ReadySetError::NoQuorum),
+            None
+        );
+    }
+
+    #[test]
+    fn unparsed_queries_are_anonymized() {
+        let cache = QueryStatusCache::new();
+        cache.insert_unparsed("SELECT a FROM t WHERE x = 1 2", Dialect::MySQL);
+        cache.insert_unparsed("SELECT a FROM t WHERE x = 3 4", Dialect::MySQL);
+
+        let unparsed = cache.unparsed_queries();
+        assert_eq!(unparsed.len(), 1);
+        assert_eq!(
+            unparsed[0].query,
+            "SELECT a FROM t WHERE x = '<anonymized>' '<anonymized>'"
+        );
+        assert_eq!(
+            unparsed[0].reason,
+            UnsupportedReason::ParseError { offset: 41 }
+        );
+    }
+
+    #[test]
+    fn query_stats() {
+        use readyset_client_metrics::{EventType, QueryDestination};
+
+        let cache = QueryStatusCache::new();
+        let query = select_statement("SELECT * FROM t1 WHERE x = 42").unwrap();
+        cache.query_status(&query);
+        let id = hash_to_query_id(hash_select_query(&query));
+
+        let mut event = QueryExecutionEvent::new(EventType::Query);
+        event.readyset_duration = Some(Duration::from_millis(1));
+        event.destination = Some(QueryDestination::Readyset);
+        cache.record_latency(&id, &event);
+        event.destination = Some(QueryDestination::Upstream);
+        cache.record_latency(&id, &event);
+        cache.record_latency(&id, &event);
+
+        let stats = cache.query_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].id, id);
+        assert!(!stats[0].query.contains("42"));
+        assert_eq!(stats[0].latencies.cached.count(), 1);
+        assert_eq!(stats[0].latencies.proxied.count(), 2);
+    }
+
+    #[test]
+    fn cache_suggestions() {
+        use readyset_client_metrics::{EventType, QueryDestination};
+
+        let cache = QueryStatusCache::new();
+        let record = |q: &str, destination, millis| {
+            let query = select_statement(q).unwrap();
+            cache.query_status(&query);
+            let mut event = QueryExecutionEvent::new(EventType::Query);
+            event.upstream_duration = Some(Duration::from_millis(millis));
+            event.destination = Some(destination);
+            cache.record_latency(&hash_to_query_id(hash_select_query(&query)), &event);
+            query
+        };
+
+        let slow = record(
+            "SELECT * FROM t1 JOIN t2 ON t1.a = t2.b WHERE t1.x = 1",
+            QueryDestination::Upstream,
+            100,
+        );
+        let fast = record(
+            "SELECT * FROM t1 WHERE x = 1",
+            QueryDestination::Upstream,
+            1,
+        );
+        record(
+            "SELECT * FROM t1 WHERE y = 1",
+            QueryDestination::Readyset,
+            1,
+        );
+        let cached = record(
+            "SELECT * FROM t2 WHERE x = 1",
+            QueryDestination::Upstream,
+            10,
+        );
+        cache.update_query_migration_state(&cached, MigrationState::Successful);
+        let unsupported = record(
+            "SELECT * FROM t3 WHERE x = 1",
+            QueryDestination::Upstream,
+            10,
+        );
+        cache.update_query_migration_state(&unsupported, MigrationState::Unsupported);
+
+        let suggestions = cache.cache_suggestions();
+        assert_eq!(
+            suggestions.iter().map(|s| s.id.clone()).collect::<Vec<_>>(),
+            vec![
+                hash_to_query_id(hash_select_query(&slow)),
+                hash_to_query_id(hash_select_query(&fast))
+            ]
+        );
+        assert_eq!(
+            suggestions[0].tables,
+            vec![SqlIdentifier::from("t1"), SqlIdentifier::from("t2")]
+        );
+        assert_eq!(suggestions[0].proxied.total(), Duration::from_millis(100));
+    }
+
     #[test]
     fn query_is_inferred_denied_explicit() {
         let cache = QueryStatusCache::with_style(MigrationStyle::Explicit);
@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use std::cmp::max;
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::{iter, mem};
 
@@ -7,8 +8,9 @@ use itertools::{Either, Itertools};
 use nom_sql::analysis::visit::{self, Visitor};
 use nom_sql::{
     BinaryOperator, Expression, InValue, ItemPlaceholder, LimitClause, Literal, SelectStatement,
+    SqlIdentifier,
 };
-use readyset_errors::{unsupported, ReadySetError, ReadySetResult};
+use readyset_errors::{bad_request_err, unsupported, ReadySetError, ReadySetResult};
 
 /// Struct storing information about parameters processed from a raw user supplied query, which
 /// provides support for converting a user-supplied parameter list into a set of lookup keys to pass
@@ -19,23 +21,27 @@ use readyset_errors::{unsupported, ReadySetError, ReadySetResult};
 /// pass to ReadySet.
 #[derive(Debug, Clone)]
 pub struct ProcessedQueryParams {
+    placeholder_names: Option<Vec<Option<SqlIdentifier>>>,
     reordered_placeholders: Option<Vec<usize>>,
     rewritten_in_conditions: Vec<RewrittenIn>,
     auto_parameters: Vec<(usize, Literal)>,
 }
 
 /// This rewrite pass accomplishes the following:
+/// - Replaces named placeholders with question mark placeholders
 /// - Remaps dollar sign placeholders so that they appear in order
 /// - Replaces literals with placeholders when they can be used as lookup indices in the ReadySet
 ///   dataflow representation of the query. Note that this pass may not replace all literals and is
 ///   therefore cannot guarantee that the rewritten query is free of user PII.
 /// - Collapses 'WHERE <expr> IN ?, ... ?' to 'WHERE <expr> = ?'
 pub fn process_query(query: &mut SelectStatement) -> ReadySetResult<ProcessedQueryParams> {
+    let placeholder_names = positional_placeholders(query)?;
     let reordered_placeholders = reorder_numbered_placeholders(query);
     let auto_parameters = auto_parametrize_query(query);
     let rewritten_in_conditions = collapse_where_in(query)?;
     number_placeholders(query)?;
     Ok(ProcessedQueryParams {
+        placeholder_names,
         reordered_placeholders,
         rewritten_in_conditions,
         auto_parameters,
@@ -43,6 +49,59 @@ pub fn process_query(query: &mut SelectStatement) -> ReadySetResult<ProcessedQue
 }
 
 impl ProcessedQueryParams {
+    /// Bind values to the placeholders of the query, taking the values of question mark
+    /// placeholders from `positional` in order, and the values of named placeholders from `named`
+    /// by name. A name which appears more than once in the query is bound to the same value each
+    /// time.
+    ///
+    /// The result has a value for each placeholder in the query, in order, and can be passed to
+    /// [`make_keys`](Self::make_keys).
+    pub fn bind_params<T>(
+        &self,
+        positional: &[T],
+        named: &HashMap<SqlIdentifier, T>,
+    ) -> ReadySetResult<Vec<T>>
+    where
+        T: Clone,
+    {
+        let names = match &self.placeholder_names {
+            Some(names) => names,
+            None => {
+                if let Some(name) = named.keys().next() {
+                    return Err(bad_request_err(format!("Unknown named parameter {}", name)));
+                }
+                return Ok(positional.to_vec());
+            }
+        };
+
+        if let Some(name) = named
+            .keys()
+            .find(|name| !names.iter().flatten().any(|n| n == *name))
+        {
+            return Err(bad_request_err(format!("Unknown named parameter {}", name)));
+        }
+
+        let mut positional = positional.iter();
+        let params = names
+            .iter()
+            .map(|name| match name {
+                Some(name) => named
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| bad_request_err(format!("Missing named parameter {}", name))),
+                None => positional
+                    .next()
+                    .cloned()
+                    .ok_or_else(|| bad_request_err("Wrong number of parameters supplied")),
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+        if positional.next().is_some() {
+            return Err(bad_request_err("Wrong number of parameters supplied"));
+        }
+
+        Ok(params)
+    }
+
     pub(crate) fn make_keys<'param, T>(
         &self,
         params: &'param [T],
@@ -269,6 +328,55 @@ where
     ))
 }
 
+#[derive(Default)]
+struct PositionalPlaceholdersVisitor {
+    names: Vec<Option<SqlIdentifier>>,
+    has_numbered: bool,
+}
+
+impl<'ast> Visitor<'ast> for PositionalPlaceholdersVisitor {
+    type Error = !;
+
+    fn visit_literal(&mut self, literal: &'ast mut Literal) -> Result<(), Self::Error> {
+        if let Literal::Placeholder(item) = literal {
+            match item {
+                ItemPlaceholder::QuestionMark => self.names.push(None),
+                ItemPlaceholder::Named(name) => {
+                    self.names.push(Some(mem::take(name)));
+                    *item = ItemPlaceholder::QuestionMark;
+                }
+                ItemPlaceholder::DollarNumber(_) | ItemPlaceholder::ColonNumber(_) => {
+                    self.has_numbered = true
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces named placeholders in `query` with question mark placeholders, so that each occurrence
+/// of a name becomes its own positional parameter. Returns the name of each placeholder in the
+/// query, in order (or `None` for placeholders which were already question marks), or `None` if
+/// the query doesn't have any named placeholders.
+fn positional_placeholders(
+    query: &mut SelectStatement,
+) -> ReadySetResult<Option<Vec<Option<SqlIdentifier>>>> {
+    let mut visitor = PositionalPlaceholdersVisitor::default();
+
+    #[allow(clippy::unwrap_used)] // Error is !, so can't be returned
+    visitor.visit_select_statement(query).unwrap();
+
+    if visitor.names.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+    if visitor.has_numbered {
+        unsupported!("Named placeholders can't be mixed with numbered placeholders");
+    }
+
+    Ok(Some(visitor.names))
+}
+
 struct ReorderNumberedPlaceholdersVisitor {
     current: u32,
     out: Vec<usize>,
@@ -367,6 +475,9 @@ impl<'ast> Visitor<'ast> for NumberPlaceholdersVisitor {
                 ItemPlaceholder::ColonNumber(_) => {
                     unsupported!("colon-number placeholders aren't supported")
                 }
+                ItemPlaceholder::Named(_) => {
+                    unsupported!("named placeholders must be made positional before numbering")
+                }
             }
         }
         Ok(())
@@ -1311,5 +1422,40 @@ mod tests {
 
             assert_eq!(keys, vec![vec!["x".into(), "y".into(), "z".into()]]);
         }
+
+        #[test]
+        fn named_mixed_with_positional() {
+            let mut query =
+                parse_select_statement("SELECT * FROM t WHERE x = :x AND y = ? AND z = :x");
+            let processed = process_query(&mut query).unwrap();
+            assert_eq!(
+                query,
+                parse_select_statement("SELECT * FROM t WHERE x = $1 AND y = $2 AND z = $3")
+            );
+
+            let named = HashMap::from([("x".into(), DataType::from("x"))]);
+            let params = processed.bind_params(&["y".into()], &named).unwrap();
+            assert_eq!(params, vec!["x".into(), "y".into(), "x".into()]);
+            assert_eq!(
+                processed.make_keys(&params).unwrap()[0].to_vec(),
+                vec!["x".into(), "y".into(), "x".into()]
+            );
+
+            processed.bind_params(&[], &named).unwrap_err();
+            processed
+                .bind_params(&["y".into()], &HashMap::new())
+                .unwrap_err();
+            let unknown = HashMap::from([
+                ("x".into(), DataType::from("x")),
+                ("w".into(), DataType::from("w")),
+            ]);
+            processed.bind_params(&["y".into()], &unknown).unwrap_err();
+        }
+
+        #[test]
+        fn named_mixed_with_numbered() {
+            let mut query = parse_select_statement("SELECT * FROM t WHERE x = :x AND y = $1");
+            process_query(&mut query).unwrap_err();
+        }
     }
 }
@@ -0,0 +1,238 @@
+//! The slow query log records queries whose end-to-end latency in the adapter exceeds a
+//! configurable threshold, along with where that time went - filling cache misses with upqueries,
+//! re-filling keys which were evicted while a read was waiting for them, or falling back to the
+//! upstream database.
+//!
+//! Slow queries are always logged as warnings, and can also be written to a file as JSON lines
+//! (one [`SlowQuery`] per line) for ingestion by other tools.
+use std::io;
+use std::time::Duration;
+
+use launchpad::redacted::Sensitive;
+use nom_sql::SqlQuery;
+use readyset_client_metrics::{EventType, QueryDestination, QueryExecutionEvent};
+use serde::Serialize;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tracing::warn;
+
+use crate::rewrite::anonymize_literals;
+
+/// Where the time spent executing a slow query went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlowQueryCause {
+    /// The query missed in ReadySet's caches, and waited for the misses to be filled by upqueries
+    UpqueryFill,
+    /// The query missed in ReadySet's caches, and keys were evicted while it was waiting for the
+    /// misses to be filled
+    Eviction,
+    /// The query failed in ReadySet, and was retried against the upstream database
+    UpstreamFallback,
+    /// The query was executed against the upstream database
+    Upstream,
+    /// The query was executed against ReadySet without missing in its caches
+    Readyset,
+}
+
+impl SlowQueryCause {
+    fn for_event(event: &QueryExecutionEvent) -> Self {
+        match event.destination {
+            Some(QueryDestination::ReadysetThenUpstream) => Self::UpstreamFallback,
+            Some(QueryDestination::Upstream | QueryDestination::Both) => Self::Upstream,
+            Some(QueryDestination::Readyset) | None => match event.upquery_duration {
+                Some(_) if event.evicted_during_upquery => Self::Eviction,
+                Some(_) => Self::UpqueryFill,
+                None => Self::Readyset,
+            },
+        }
+    }
+}
+
+/// A single entry in the slow query log
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQuery {
+    /// When the query finished executing, in RFC 3339 format
+    pub timestamp: String,
+    /// The query, with literals anonymized if it is a `SELECT`
+    pub query: String,
+    pub event_type: EventType,
+    pub destination: Option<QueryDestination>,
+    pub cause: SlowQueryCause,
+    /// The end-to-end latency of the query, in milliseconds
+    pub total_ms: f64,
+    pub parse_ms: Option<f64>,
+    pub readyset_ms: Option<f64>,
+    /// How long the query waited for cache misses to be filled, in milliseconds. Included in
+    /// `readyset_ms`
+    pub upquery_ms: Option<f64>,
+    pub upstream_ms: Option<f64>,
+    pub cache_misses: Option<u64>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+/// Configuration for the slow query log, shared by every connection in an adapter
+#[derive(Debug, Clone)]
+pub struct SlowLog {
+    threshold: Duration,
+    sender: Option<UnboundedSender<SlowQuery>>,
+}
+
+impl SlowLog {
+    /// Create a new slow query log which logs queries which take longer than `threshold` as
+    /// warnings
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            sender: None,
+        }
+    }
+
+    /// Also send slow queries to `sender`, to be written to a file by [`write_slow_log`]
+    pub fn with_sender(mut self, sender: UnboundedSender<SlowQuery>) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Log the query executed by `event` if it took longer than the threshold
+    pub(crate) fn log(&self, event: &QueryExecutionEvent) {
+        let total = [
+            event.parse_duration,
+            event.readyset_duration,
+            event.upstream_duration,
+        ]
+        .into_iter()
+        .flatten()
+        .sum::<Duration>();
+        if total <= self.threshold {
+            return;
+        }
+        let query = match &event.query {
+            Some(query) => query,
+            None => return,
+        };
+
+        let cause = SlowQueryCause::for_event(event);
+        warn!(
+            query = %Sensitive(&query),
+            readyset_time = ?event.readyset_duration,
+            upstream_time = ?event.upstream_duration,
+            upquery_time = ?event.upquery_duration,
+            ?cause,
+            "slow query"
+        );
+
+        if let Some(sender) = &self.sender {
+            let query = match query.as_ref() {
+                SqlQuery::Select(stmt) => {
+                    let mut stmt = stmt.clone();
+                    anonymize_literals(&mut stmt);
+                    stmt.to_string()
+                }
+                query => Sensitive(query).to_string(),
+            };
+
+            // Drop the entry if the slow log writer has shut down
+            let _ = sender.send(SlowQuery {
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                query,
+                event_type: event.event,
+                destination: event.destination,
+                cause,
+                total_ms: millis(total),
+                parse_ms: event.parse_duration.map(millis),
+                readyset_ms: event.readyset_duration.map(millis),
+                upquery_ms: event.upquery_duration.map(millis),
+                upstream_ms: event.upstream_duration.map(millis),
+                cache_misses: event.cache_misses,
+            });
+        }
+    }
+}
+
+/// Write every slow query received on `receiver` to `out` as a line of JSON, until every sender
+/// has been dropped
+pub async fn write_slow_log<W>(
+    mut receiver: UnboundedReceiver<SlowQuery>,
+    mut out: W,
+) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(entry) = receiver.recv().await {
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        out.write_all(&line).await?;
+        out.flush().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nom_sql::{parse_query, Dialect};
+
+    use super::*;
+
+    fn slow_read(destination: QueryDestination) -> QueryExecutionEvent {
+        let mut event = QueryExecutionEvent::new(EventType::Query);
+        event.query = Some(Arc::new(
+            parse_query(Dialect::MySQL, "SELECT * FROM t WHERE id = 1").unwrap(),
+        ));
+        event.destination = Some(destination);
+        event.readyset_duration = Some(Duration::from_millis(50));
+        event
+    }
+
+    #[test]
+    fn cause_for_event() {
+        let mut event = slow_read(QueryDestination::Readyset);
+        assert_eq!(SlowQueryCause::for_event(&event), SlowQueryCause::Readyset);
+
+        event.upquery_duration = Some(Duration::from_millis(40));
+        assert_eq!(
+            SlowQueryCause::for_event(&event),
+            SlowQueryCause::UpqueryFill
+        );
+
+        event.evicted_during_upquery = true;
+        assert_eq!(SlowQueryCause::for_event(&event), SlowQueryCause::Eviction);
+
+        let event = slow_read(QueryDestination::ReadysetThenUpstream);
+        assert_eq!(
+            SlowQueryCause::for_event(&event),
+            SlowQueryCause::UpstreamFallback
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_json_lines() {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let slow_log = SlowLog::new(Duration::from_millis(10)).with_sender(tx);
+
+        let mut event = slow_read(QueryDestination::Readyset);
+        event.upquery_duration = Some(Duration::from_millis(40));
+        slow_log.log(&event);
+        // Fast queries aren't logged
+        event.readyset_duration = Some(Duration::from_millis(1));
+        slow_log.log(&event);
+        drop(slow_log);
+
+        let mut out = vec![];
+        write_slow_log(rx, &mut out).await.unwrap();
+        let lines = String::from_utf8(out).unwrap();
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1);
+
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry["cause"], "upquery_fill");
+        assert_eq!(entry["destination"], "Readyset");
+        assert_eq!(entry["upquery_ms"], 40.0);
+        assert!(!entry["query"].as_str().unwrap().contains('1'));
+    }
+}
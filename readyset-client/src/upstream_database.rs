@@ -2,6 +2,7 @@ use std::error::Error;
 use std::fmt::Debug;
 
 use async_trait::async_trait;
+use readyset::replication::ReplicationOffset;
 use readyset::ColumnSchema;
 use readyset_data::DataType;
 use readyset_errors::ReadySetError;
@@ -113,6 +114,23 @@ pub trait UpstreamDatabase: Sized + Send {
     where
         S: AsRef<str> + Send + Sync + 'a;
 
+    /// Execute `query`, a `SET` statement which only assigns the given session variables, given as
+    /// pairs of the variable's name and its new value formatted as SQL.
+    ///
+    /// By default this just runs `query`, but implementations which run a session's statements on
+    /// more than one connection to the upstream database use the variables to recreate the
+    /// session's state on every connection they use.
+    async fn set_session_variables<'a, S>(
+        &'a mut self,
+        query: S,
+        _variables: Vec<(String, String)>,
+    ) -> Result<Self::QueryResult, Self::Error>
+    where
+        S: AsRef<str> + Send + Sync + 'a,
+    {
+        self.query(query).await
+    }
+
     /// Execute a raw, un-prepared write query, constructing and returning a RYW ticket for the
     /// write
     // TODO: newtype RYW ticket, not just String
@@ -134,4 +152,20 @@ pub trait UpstreamDatabase: Sized + Send {
 
     /// Return schema dump from the upstream database, for inclusion in a query analysis bundle.
     async fn schema_dump(&mut self) -> Result<Vec<u8>, anyhow::Error>;
+
+    /// Check that the connection to the upstream database is still usable
+    async fn check_health(&mut self) -> Result<(), Self::Error> {
+        self.query("SELECT 1").await?;
+        Ok(())
+    }
+
+    /// Return the current position of the upstream database's replication log, which is at or
+    /// after the position of every write that has completed on this connection, or `None` if the
+    /// upstream database doesn't support reporting its replication log position.
+    ///
+    /// Used to implement read-your-writes consistency, by waiting for ReadySet to replicate up to
+    /// this position before serving a read.
+    async fn replication_offset(&mut self) -> Result<Option<ReplicationOffset>, Self::Error> {
+        Ok(None)
+    }
 }
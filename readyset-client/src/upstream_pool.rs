@@ -0,0 +1,971 @@
+//! A bounded pool of connections to the upstream database, shared between client connections.
+//!
+//! Without a pool, every client connection to the adapter has its own upstream connection, so
+//! thousands of clients falling back to the upstream database at once means thousands of
+//! upstream connections. Instead, a [`PooledUpstream`] checks a connection out of an
+//! [`UpstreamPool`] for each statement it runs and returns it to the pool afterwards, so many
+//! clients can share a small number of upstream connections.
+//!
+//! Some statements leave state behind on the connection they ran on, so a [`PooledUpstream`]
+//! keeps its connection checked out (*pins* it):
+//!
+//! * from the start of a transaction until it's committed or rolled back
+//! * for the rest of the client's session after running a statement that starts with one of
+//!   [`SESSION_KEYWORDS`] (such as `SET` or `USE`), or that creates a temporary table
+//!
+//! Setting one of the [`REPLAYED_SESSION_VARIABLES`], which change how statements behave (such as
+//! the SQL mode, time zone and character set), doesn't pin the connection. Instead, the value is
+//! recorded, and set again on each connection the session uses before running a statement on it,
+//! so statements behave the same as they would on a connection of the client's own.
+//!
+//! Prepared statements are prepared on each upstream connection the first time they're used on
+//! it, and stay prepared for as long as that connection is open.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use nom_sql::{Expression, PostgresParameterScope, SetNames, SetStatement, VariableScope};
+use readyset::replication::ReplicationOffset;
+use readyset_data::DataType;
+use readyset_errors::ReadySetError;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, warn};
+
+use crate::upstream_database::{IsFatalError, UpstreamDatabase, UpstreamPrepare};
+
+/// The first words of statements which change the state of the session they're run in, or which
+/// rely on later statements being run in the same session
+pub const SESSION_KEYWORDS: &[&str] = &[
+    "BEGIN",
+    "DECLARE",
+    "LISTEN",
+    "LOCK",
+    "PREPARE",
+    "SAVEPOINT",
+    "SET",
+    "START",
+    "USE",
+];
+
+/// The session variables which are set again on each connection used by a [`PooledUpstream`],
+/// instead of pinning the connection they were set on. `names` stands for MySQL's `SET NAMES`.
+pub const REPLAYED_SESSION_VARIABLES: &[&str] = &[
+    "character_set_client",
+    "character_set_connection",
+    "character_set_results",
+    "client_encoding",
+    "collation_connection",
+    "names",
+    "search_path",
+    "sql_mode",
+    "time_zone",
+    "timezone",
+];
+
+/// Configuration for an [`UpstreamPool`]
+#[derive(Debug, Clone)]
+pub struct UpstreamPoolConfig {
+    /// The number of connections to keep open, even while they're idle
+    pub min_size: usize,
+    /// The maximum number of connections to have open at once
+    pub max_size: usize,
+    /// How long a connection can be idle before it's closed, if more than `min_size` connections
+    /// are open
+    pub idle_timeout: Duration,
+    /// How often to check that idle connections are still usable, and to close connections which
+    /// have been idle for longer than `idle_timeout`
+    pub health_check_interval: Duration,
+    /// How long to wait for a connection when `max_size` connections are already in use
+    pub checkout_timeout: Duration,
+}
+
+impl UpstreamPoolConfig {
+    /// Configuration for a pool that's only used by a single [`PooledUpstream`]
+    fn dedicated() -> Self {
+        Self {
+            min_size: 0,
+            max_size: 1,
+            idle_timeout: Duration::MAX,
+            health_check_interval: Duration::MAX,
+            checkout_timeout: Duration::MAX,
+        }
+    }
+}
+
+/// A connection to the upstream database owned by an [`UpstreamPool`]
+struct Connection<DB: UpstreamDatabase> {
+    upstream: DB,
+    /// The statements that have been prepared on this connection, by query
+    prepared: HashMap<String, UpstreamPrepare<DB>>,
+    /// The [`REPLAYED_SESSION_VARIABLES`] that have been set on this connection, in the order they
+    /// were set
+    session_variables: Vec<(String, String)>,
+}
+
+impl<DB: UpstreamDatabase> Connection<DB> {
+    fn new(upstream: DB) -> Self {
+        Self {
+            upstream,
+            prepared: HashMap::new(),
+            session_variables: vec![],
+        }
+    }
+
+    /// Prepare `query` on this connection, unless it's already been prepared on it
+    async fn prepare(&mut self, query: &str) -> Result<&UpstreamPrepare<DB>, DB::Error> {
+        if !self.prepared.contains_key(query) {
+            let prepare = self.upstream.prepare(query).await?;
+            self.prepared.insert(query.to_owned(), prepare);
+        }
+        Ok(&self.prepared[query])
+    }
+
+    /// Set the session variables on this connection to `variables`, resetting any variables that
+    /// were set on it before but aren't in `variables`
+    async fn set_session_variables(
+        &mut self,
+        variables: &[(String, String)],
+    ) -> Result<(), DB::Error> {
+        if self.session_variables == variables {
+            return Ok(());
+        }
+        for (name, _) in &self.session_variables {
+            if !variables.iter().any(|(n, _)| n == name) {
+                self.upstream
+                    .query(set_variable_query(name, "DEFAULT"))
+                    .await?;
+            }
+        }
+        for (name, value) in variables {
+            self.upstream.query(set_variable_query(name, value)).await?;
+        }
+        self.session_variables = variables.to_vec();
+        Ok(())
+    }
+}
+
+/// A connection which isn't checked out of an [`UpstreamPool`]
+struct IdleConnection<DB: UpstreamDatabase> {
+    connection: Connection<DB>,
+    /// When the connection was returned to the pool
+    idle_since: Instant,
+    /// When the connection was last known to be usable
+    checked_at: Instant,
+}
+
+/// A bounded pool of connections to an upstream database. See the [module
+/// documentation](self) for more information.
+pub struct UpstreamPool<DB: UpstreamDatabase> {
+    url: String,
+    config: UpstreamPoolConfig,
+    /// Connections which aren't checked out, with the most recently used at the back
+    idle: Mutex<VecDeque<IdleConnection<DB>>>,
+    /// Holds `max_size` permits; a permit is held for every connection that's checked out or
+    /// being opened, so that at most `max_size` connections are open at once
+    permits: Arc<Semaphore>,
+}
+
+impl<DB: UpstreamDatabase> UpstreamPool<DB> {
+    /// Create a new pool of connections to the upstream database at `url`. Connections are
+    /// opened as they're needed, and by [`maintain`](Self::maintain).
+    pub fn new(url: String, config: UpstreamPoolConfig) -> Arc<Self> {
+        Arc::new(Self {
+            url,
+            permits: Arc::new(Semaphore::new(config.max_size)),
+            config,
+            idle: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// Return the URL of the upstream database
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Check a connection out of the pool, opening a new connection if there are no idle
+    /// connections, and waiting for a connection to be returned if `max_size` connections are
+    /// already checked out.
+    async fn get(self: &Arc<Self>) -> Result<PoolConnection<DB>, DB::Error> {
+        let permit = tokio::time::timeout(
+            self.config.checkout_timeout,
+            Arc::clone(&self.permits).acquire_owned(),
+        )
+        .await
+        .map_err(|_| ReadySetError::UpstreamPoolTimeout)?
+        .map_err(|_| ReadySetError::Internal("Upstream connection pool was closed".to_owned()))?;
+
+        let idle = self.idle.lock().unwrap().pop_back();
+        let connection = match idle {
+            Some(idle) => idle.connection,
+            None => {
+                debug!("Opening new upstream connection");
+                Connection::new(DB::connect(self.url.clone()).await?)
+            }
+        };
+
+        Ok(PoolConnection {
+            connection: Some(connection),
+            pool: Arc::clone(self),
+            _permit: permit,
+        })
+    }
+
+    /// Return a connection to the pool, making it available to be checked out again
+    fn put(&self, connection: Connection<DB>) {
+        let now = Instant::now();
+        self.idle.lock().unwrap().push_back(IdleConnection {
+            connection,
+            idle_since: now,
+            checked_at: now,
+        });
+    }
+
+    /// Returns the number of connections which are open, or being opened
+    fn open_connections(&self) -> usize {
+        let checked_out = self.config.max_size - self.permits.available_permits();
+        self.idle.lock().unwrap().len() + checked_out
+    }
+
+    /// Every `health_check_interval`, close connections which have been idle for longer than
+    /// `idle_timeout`, check that the remaining idle connections are still usable, and open
+    /// connections until there are at least `min_size`. Runs until the pool is dropped.
+    pub async fn maintain(pool: Weak<Self>) {
+        let health_check_interval = match pool.upgrade() {
+            Some(pool) => pool.config.health_check_interval,
+            None => return,
+        };
+        let mut interval = tokio::time::interval(health_check_interval);
+        loop {
+            interval.tick().await;
+            let pool = match pool.upgrade() {
+                Some(pool) => pool,
+                None => return,
+            };
+            pool.close_expired();
+            pool.check_idle().await;
+            pool.fill().await;
+        }
+    }
+
+    /// Close connections which have been idle for longer than `idle_timeout`, while more than
+    /// `min_size` connections are open
+    fn close_expired(&self) {
+        let mut excess = self.open_connections().saturating_sub(self.config.min_size);
+        let now = Instant::now();
+        self.idle.lock().unwrap().retain(|idle| {
+            if excess > 0
+                && now.saturating_duration_since(idle.idle_since) >= self.config.idle_timeout
+            {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Check that connections which haven't been checked within the last `health_check_interval`
+    /// are still usable, closing them if they aren't
+    async fn check_idle(&self) {
+        let now = Instant::now();
+        let mut unchecked = self.idle.lock().unwrap().len();
+        while unchecked > 0 {
+            unchecked -= 1;
+            // Connections being checked aren't in the idle list, so hold a permit for them instead
+            let _permit = match Arc::clone(&self.permits).try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            let idle = {
+                let mut idle = self.idle.lock().unwrap();
+                let position = idle.iter().position(|idle| {
+                    idle.checked_at < now
+                        && now.saturating_duration_since(idle.checked_at)
+                            >= self.config.health_check_interval
+                });
+                match position.and_then(|position| idle.remove(position)) {
+                    Some(idle) => idle,
+                    None => return,
+                }
+            };
+
+            let IdleConnection {
+                mut connection,
+                idle_since,
+                ..
+            } = idle;
+            match connection.upstream.check_health().await {
+                Ok(()) => self.idle.lock().unwrap().push_front(IdleConnection {
+                    connection,
+                    idle_since,
+                    checked_at: Instant::now(),
+                }),
+                Err(error) => warn!(%error, "Closing unusable upstream connection"),
+            }
+        }
+    }
+
+    /// Open connections until at least `min_size` are open
+    async fn fill(&self) {
+        while self.open_connections() < self.config.min_size {
+            let _permit = match Arc::clone(&self.permits).try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            match DB::connect(self.url.clone()).await {
+                Ok(upstream) => self.put(Connection::new(upstream)),
+                Err(error) => {
+                    warn!(%error, "Could not open upstream connection");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// A connection checked out of an [`UpstreamPool`], which is returned to the pool when dropped
+struct PoolConnection<DB: UpstreamDatabase> {
+    /// Only `None` once the connection has been discarded
+    connection: Option<Connection<DB>>,
+    pool: Arc<UpstreamPool<DB>>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<DB: UpstreamDatabase> PoolConnection<DB> {
+    #[allow(clippy::expect_used)] // only taken by `discard`, which consumes self
+    fn get(&mut self) -> &mut Connection<DB> {
+        self.connection
+            .as_mut()
+            .expect("PoolConnection used after being discarded")
+    }
+
+    /// Close the connection instead of returning it to the pool
+    fn discard(mut self) {
+        self.connection = None;
+    }
+}
+
+impl<DB: UpstreamDatabase> Drop for PoolConnection<DB> {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.put(connection);
+        }
+    }
+}
+
+/// Returns true if running `query` should pin the connection it's run on to the session. See the
+/// [module documentation](self).
+fn pins_session(query: &str) -> bool {
+    let mut words = query
+        .split_whitespace()
+        .map(|word| word.trim_end_matches(';').to_ascii_uppercase());
+    match words.next().as_deref() {
+        Some("CREATE") => matches!(words.next().as_deref(), Some("TEMPORARY" | "TEMP")),
+        Some(word) => SESSION_KEYWORDS.contains(&word),
+        None => false,
+    }
+}
+
+/// Returns the variables assigned by `set`, as pairs of each variable's name and its new value
+/// formatted as SQL, if `set` only assigns literal values to [`REPLAYED_SESSION_VARIABLES`] for the
+/// rest of the session
+pub fn replayed_session_variables(set: &SetStatement) -> Option<Vec<(String, String)>> {
+    let variables = match set {
+        SetStatement::Variable(set) => set
+            .variables
+            .iter()
+            .map(|(variable, value)| match (variable.scope, value) {
+                (VariableScope::Local | VariableScope::Session, Expression::Literal(_)) => {
+                    Some((variable.name.to_ascii_lowercase(), value.to_string()))
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?,
+        SetStatement::Names(SetNames { charset, collation }) => {
+            let value = match collation {
+                Some(collation) => format!("'{}' COLLATE '{}'", charset, collation),
+                None => format!("'{}'", charset),
+            };
+            vec![("names".to_owned(), value)]
+        }
+        SetStatement::PostgresParameter(set) => match set.scope {
+            None | Some(PostgresParameterScope::Session) => {
+                vec![(set.name.to_ascii_lowercase(), set.value.to_string())]
+            }
+            // Reverted at the end of the current transaction
+            Some(PostgresParameterScope::Local) => return None,
+        },
+    };
+    variables
+        .iter()
+        .all(|(name, _)| REPLAYED_SESSION_VARIABLES.contains(&name.as_str()))
+        .then(|| variables)
+}
+
+/// Returns a statement which sets the session variable `name` to `value`
+fn set_variable_query(name: &str, value: &str) -> String {
+    if name == "names" {
+        format!("SET NAMES {}", value)
+    } else {
+        format!("SET {} = {}", name, value)
+    }
+}
+
+/// An [`UpstreamDatabase`] for a single client session, which runs statements on connections
+/// checked out of an [`UpstreamPool`] shared with other sessions. See the [module
+/// documentation](self) for more information.
+pub struct PooledUpstream<DB: UpstreamDatabase> {
+    pool: Arc<UpstreamPool<DB>>,
+    /// The connection currently checked out of the pool, if any
+    connection: Option<PoolConnection<DB>>,
+    /// Whether there's an ongoing transaction on `connection`
+    in_transaction: bool,
+    /// Whether the session state of `connection` has been changed
+    session_pinned: bool,
+    /// The [`REPLAYED_SESSION_VARIABLES`] set in this session, in the order they were set
+    session_variables: Vec<(String, String)>,
+    /// The database name included in the connection string, if any
+    database: Option<String>,
+    /// The query for each statement prepared in this session, by statement ID
+    statements: HashMap<u32, Arc<str>>,
+    next_statement_id: u32,
+}
+
+impl<DB: UpstreamDatabase> PooledUpstream<DB> {
+    /// Create a new session using connections from `pool`, checking that a connection can be
+    /// made to the upstream database
+    pub async fn new(pool: Arc<UpstreamPool<DB>>) -> Result<Self, DB::Error> {
+        let mut connection = pool.get().await?;
+        let database = connection.get().upstream.database().map(ToOwned::to_owned);
+        drop(connection);
+
+        Ok(Self {
+            pool,
+            connection: None,
+            in_transaction: false,
+            session_pinned: false,
+            session_variables: vec![],
+            database,
+            statements: HashMap::new(),
+            next_statement_id: 0,
+        })
+    }
+
+    /// Returns the connection to run the next statement on, checking one out of the pool if
+    /// necessary, with this session's variables set on it
+    async fn connection(&mut self) -> Result<&mut Connection<DB>, DB::Error> {
+        let mut connection = match self.connection.take() {
+            Some(connection) => connection,
+            None => self.pool.get().await?,
+        };
+        if let Err(error) = connection
+            .get()
+            .set_session_variables(&self.session_variables)
+            .await
+        {
+            // We don't know which of the variables were set, so don't reuse the connection
+            connection.discard();
+            self.in_transaction = false;
+            self.session_pinned = false;
+            return Err(error);
+        }
+        Ok(self.connection.insert(connection).get())
+    }
+
+    /// Return the connection to the pool, unless it's pinned to this session
+    fn release(&mut self) {
+        if !self.in_transaction && !self.session_pinned {
+            self.connection = None;
+        }
+    }
+
+    /// Close the connection, losing any transaction or session state on it
+    fn discard(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            connection.discard();
+        }
+        self.in_transaction = false;
+        self.session_pinned = false;
+    }
+
+    /// Release or discard the connection after running a statement on it, depending on `result`
+    fn finish<T>(&mut self, result: Result<T, DB::Error>) -> Result<T, DB::Error> {
+        match &result {
+            Err(error) if error.is_fatal() => self.discard(),
+            _ => self.release(),
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<DB: UpstreamDatabase> UpstreamDatabase for PooledUpstream<DB> {
+    type QueryResult = DB::QueryResult;
+    type StatementMeta = DB::StatementMeta;
+    type Error = DB::Error;
+
+    /// Create a session with its own pool of a single connection, which isn't shared with any
+    /// other session
+    async fn connect(url: String) -> Result<Self, Self::Error> {
+        Self::new(UpstreamPool::new(url, UpstreamPoolConfig::dedicated())).await
+    }
+
+    /// Discard the connection pinned to this session, if any; the next statement is run on a
+    /// connection without any of the session's state
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        self.discard();
+        self.session_variables.clear();
+        Ok(())
+    }
+
+    fn url(&self) -> &str {
+        self.pool.url()
+    }
+
+    fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    async fn prepare<'a, S>(&'a mut self, query: S) -> Result<UpstreamPrepare<Self>, Self::Error>
+    where
+        S: AsRef<str> + Send + Sync + 'a,
+    {
+        let query = query.as_ref();
+        let result = self
+            .connection()
+            .await?
+            .prepare(query)
+            .await
+            .map(|prepare| prepare.meta.clone());
+        let meta = self.finish(result)?;
+
+        let statement_id = self.next_statement_id;
+        self.next_statement_id += 1;
+        self.statements.insert(statement_id, query.into());
+        Ok(UpstreamPrepare { statement_id, meta })
+    }
+
+    async fn execute(
+        &mut self,
+        statement_id: u32,
+        params: &[DataType],
+    ) -> Result<Self::QueryResult, Self::Error> {
+        let query = Arc::clone(
+            self.statements
+                .get(&statement_id)
+                .ok_or(ReadySetError::PreparedStatementMissing { statement_id })?,
+        );
+        let connection = self.connection().await?;
+        let result = match connection.prepare(&query).await {
+            Ok(prepare) => {
+                let upstream_statement_id = prepare.statement_id;
+                connection
+                    .upstream
+                    .execute(upstream_statement_id, params)
+                    .await
+            }
+            Err(error) => Err(error),
+        };
+        self.finish(result)
+    }
+
+    async fn query<'a, S>(&'a mut self, query: S) -> Result<Self::QueryResult, Self::Error>
+    where
+        S: AsRef<str> + Send + Sync + 'a,
+    {
+        let query = query.as_ref();
+        if pins_session(query) {
+            self.session_pinned = true;
+        }
+        let result = self.connection().await?.upstream.query(query).await;
+        self.finish(result)
+    }
+
+    /// Record the variables set by `query` to be set again on each connection this session uses,
+    /// instead of pinning the connection. Inside a transaction the variables may be reverted by a
+    /// rollback, so `query` is run like any other statement.
+    async fn set_session_variables<'a, S>(
+        &'a mut self,
+        query: S,
+        variables: Vec<(String, String)>,
+    ) -> Result<Self::QueryResult, Self::Error>
+    where
+        S: AsRef<str> + Send + Sync + 'a,
+    {
+        if self.in_transaction {
+            return self.query(query).await;
+        }
+        let result = self
+            .connection()
+            .await?
+            .upstream
+            .query(query.as_ref())
+            .await;
+        if result.is_ok() {
+            for (name, value) in variables {
+                self.session_variables.retain(|(n, _)| *n != name);
+                self.session_variables.push((name, value));
+            }
+            // The connection was in sync with the session before running `query`, so it still is
+            if let Some(connection) = self.connection.as_mut() {
+                connection.get().session_variables = self.session_variables.clone();
+            }
+        }
+        self.finish(result)
+    }
+
+    async fn handle_ryw_write<'a, S>(
+        &'a mut self,
+        query: S,
+    ) -> Result<(Self::QueryResult, String), Self::Error>
+    where
+        S: AsRef<str> + Send + Sync + 'a,
+    {
+        let result = self
+            .connection()
+            .await?
+            .upstream
+            .handle_ryw_write(query.as_ref())
+            .await;
+        self.finish(result)
+    }
+
+    async fn start_tx(&mut self) -> Result<Self::QueryResult, Self::Error> {
+        let result = self.connection().await?.upstream.start_tx().await;
+        if result.is_ok() {
+            self.in_transaction = true;
+        }
+        self.finish(result)
+    }
+
+    async fn commit(&mut self) -> Result<Self::QueryResult, Self::Error> {
+        let result = self.connection().await?.upstream.commit().await;
+        self.in_transaction = false;
+        if result.is_err() {
+            // Don't return a connection that might still have a transaction open to the pool
+            self.discard();
+        }
+        self.finish(result)
+    }
+
+    async fn rollback(&mut self) -> Result<Self::QueryResult, Self::Error> {
+        let result = self.connection().await?.upstream.rollback().await;
+        self.in_transaction = false;
+        if result.is_err() {
+            self.discard();
+        }
+        self.finish(result)
+    }
+
+    async fn schema_dump(&mut self) -> Result<Vec<u8>, anyhow::Error> {
+        let result = self.connection().await?.upstream.schema_dump().await;
+        self.release();
+        result
+    }
+
+    async fn replication_offset(&mut self) -> Result<Option<ReplicationOffset>, Self::Error> {
+        let result = self.connection().await?.upstream.replication_offset().await;
+        self.finish(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use nom_sql::Dialect;
+    use readyset::ColumnSchema;
+
+    use super::*;
+    use crate::upstream_database::NoriaCompare;
+
+    static NEXT_CONNECTION_ID: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, thiserror::Error)]
+    enum FakeError {
+        #[error(transparent)]
+        ReadySet(#[from] ReadySetError),
+        #[error("connection lost")]
+        ConnectionLost,
+    }
+
+    impl IsFatalError for FakeError {
+        fn is_fatal(&self) -> bool {
+            matches!(self, FakeError::ConnectionLost)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct FakeMeta;
+
+    impl NoriaCompare for FakeMeta {
+        type Error = FakeError;
+
+        fn compare(&self, _: &[ColumnSchema], _: &[ColumnSchema]) -> Result<(), FakeError> {
+            Ok(())
+        }
+    }
+
+    /// An upstream database whose results are the ID of the connection they were run on
+    struct FakeUpstream {
+        id: usize,
+        url: String,
+        prepared: HashSet<u32>,
+    }
+
+    #[async_trait]
+    impl UpstreamDatabase for FakeUpstream {
+        type QueryResult = usize;
+        type StatementMeta = FakeMeta;
+        type Error = FakeError;
+
+        async fn connect(url: String) -> Result<Self, FakeError> {
+            Ok(Self {
+                id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+                url,
+                prepared: HashSet::new(),
+            })
+        }
+
+        async fn reset(&mut self) -> Result<(), FakeError> {
+            Ok(())
+        }
+
+        fn url(&self) -> &str {
+            &self.url
+        }
+
+        async fn prepare<'a, S>(&'a mut self, _query: S) -> Result<UpstreamPrepare<Self>, FakeError>
+        where
+            S: AsRef<str> + Send + Sync + 'a,
+        {
+            let statement_id = self.prepared.len() as u32;
+            self.prepared.insert(statement_id);
+            Ok(UpstreamPrepare {
+                statement_id,
+                meta: FakeMeta,
+            })
+        }
+
+        async fn execute(&mut self, statement_id: u32, _: &[DataType]) -> Result<usize, FakeError> {
+            if self.prepared.contains(&statement_id) {
+                Ok(self.id)
+            } else {
+                Err(ReadySetError::PreparedStatementMissing { statement_id }.into())
+            }
+        }
+
+        async fn query<'a, S>(&'a mut self, query: S) -> Result<usize, FakeError>
+        where
+            S: AsRef<str> + Send + Sync + 'a,
+        {
+            if query.as_ref() == "DISCONNECT" {
+                Err(FakeError::ConnectionLost)
+            } else {
+                Ok(self.id)
+            }
+        }
+
+        async fn handle_ryw_write<'a, S>(&'a mut self, _: S) -> Result<(usize, String), FakeError>
+        where
+            S: AsRef<str> + Send + Sync + 'a,
+        {
+            Ok((self.id, String::new()))
+        }
+
+        async fn start_tx(&mut self) -> Result<usize, FakeError> {
+            Ok(self.id)
+        }
+
+        async fn commit(&mut self) -> Result<usize, FakeError> {
+            Ok(self.id)
+        }
+
+        async fn rollback(&mut self) -> Result<usize, FakeError> {
+            Ok(self.id)
+        }
+
+        async fn schema_dump(&mut self) -> Result<Vec<u8>, anyhow::Error> {
+            Ok(vec![])
+        }
+    }
+
+    fn pool(max_size: usize) -> Arc<UpstreamPool<FakeUpstream>> {
+        UpstreamPool::new(
+            "fake://upstream".to_owned(),
+            UpstreamPoolConfig {
+                min_size: 0,
+                max_size,
+                idle_timeout: Duration::from_secs(60),
+                health_check_interval: Duration::from_secs(60),
+                checkout_timeout: Duration::from_millis(50),
+            },
+        )
+    }
+
+    #[test]
+    fn session_statements_pin() {
+        assert!(pins_session("SET autocommit = 0"));
+        assert!(pins_session("  use db;"));
+        assert!(pins_session("BEGIN;"));
+        assert!(pins_session("create temporary table t (x int)"));
+        assert!(!pins_session("CREATE TABLE t (x int)"));
+        assert!(!pins_session("SELECT * FROM t"));
+        assert!(!pins_session(""));
+    }
+
+    #[tokio::test]
+    async fn sessions_share_connections() {
+        let pool = pool(1);
+        let mut first = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let mut second = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let id = first.query("SELECT 1").await.unwrap();
+        assert_eq!(second.query("SELECT 1").await.unwrap(), id);
+        assert_eq!(pool.open_connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn session_state_pins_connection() {
+        let pool = pool(2);
+        let mut first = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let mut second = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let id = first.query("SET @x = 1").await.unwrap();
+        assert_ne!(second.query("SELECT 1").await.unwrap(), id);
+        assert_eq!(first.query("SELECT @x").await.unwrap(), id);
+    }
+
+    #[test]
+    fn replayed_session_variables_from_set() {
+        let variables = |dialect, query| match nom_sql::parse_query(dialect, query).unwrap() {
+            nom_sql::SqlQuery::Set(set) => replayed_session_variables(&set),
+            _ => panic!("not a SET statement"),
+        };
+        assert_eq!(
+            variables(
+                Dialect::MySQL,
+                "SET sql_mode = 'ANSI', @@session.time_zone = '+00:00'"
+            ),
+            Some(vec![
+                ("sql_mode".to_owned(), "'ANSI'".to_owned()),
+                ("time_zone".to_owned(), "'+00:00'".to_owned()),
+            ])
+        );
+        assert_eq!(
+            variables(Dialect::MySQL, "SET NAMES utf8mb4 COLLATE utf8mb4_bin"),
+            Some(vec![(
+                "names".to_owned(),
+                "'utf8mb4' COLLATE 'utf8mb4_bin'".to_owned()
+            )])
+        );
+        assert_eq!(
+            variables(Dialect::PostgreSQL, "SET search_path = public"),
+            Some(vec![("search_path".to_owned(), "public".to_owned())])
+        );
+        assert_eq!(variables(Dialect::MySQL, "SET @x = 1"), None);
+        assert_eq!(
+            variables(Dialect::MySQL, "SET sql_mode = 'ANSI', autocommit = 0"),
+            None
+        );
+        assert_eq!(
+            variables(Dialect::MySQL, "SET GLOBAL time_zone = 'UTC'"),
+            None
+        );
+        assert_eq!(
+            variables(Dialect::PostgreSQL, "SET LOCAL search_path = public"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn session_variables_are_replayed_without_pinning() {
+        let pool = pool(1);
+        let mut first = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let mut second = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let variables = vec![("sql_mode".to_owned(), "'ANSI'".to_owned())];
+        let id = first
+            .set_session_variables("SET sql_mode = 'ANSI'", variables.clone())
+            .await
+            .unwrap();
+        let idle_variables = || {
+            pool.idle.lock().unwrap()[0]
+                .connection
+                .session_variables
+                .clone()
+        };
+        assert_eq!(idle_variables(), variables);
+
+        // Another session can use the connection, after resetting the variable
+        assert_eq!(second.query("SELECT 1").await.unwrap(), id);
+        assert!(idle_variables().is_empty());
+
+        first.query("SELECT 1").await.unwrap();
+        assert_eq!(idle_variables(), variables);
+
+        first.reset().await.unwrap();
+        first.query("SELECT 1").await.unwrap();
+        assert!(idle_variables().is_empty());
+    }
+
+    #[tokio::test]
+    async fn transaction_pins_connection_until_commit() {
+        let pool = pool(1);
+        let mut first = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let mut second = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        first.start_tx().await.unwrap();
+        assert!(matches!(
+            second.query("SELECT 1").await,
+            Err(FakeError::ReadySet(ReadySetError::UpstreamPoolTimeout))
+        ));
+        first.commit().await.unwrap();
+        second.query("SELECT 1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn prepared_statements_are_prepared_on_each_connection() {
+        let pool = pool(2);
+        let mut first = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let mut second = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let prepare = first.prepare("SELECT ?").await.unwrap();
+        // Pin the connection the statement was prepared on to the other session
+        let id = second.query("SET @x = 1").await.unwrap();
+        assert_ne!(first.execute(prepare.statement_id, &[]).await.unwrap(), id);
+    }
+
+    #[tokio::test]
+    async fn fatal_errors_discard_connection() {
+        let pool = pool(1);
+        let mut session = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let id = session.query("SELECT 1").await.unwrap();
+        session.query("DISCONNECT").await.unwrap_err();
+        assert_ne!(session.query("SELECT 1").await.unwrap(), id);
+    }
+
+    #[tokio::test]
+    async fn idle_connections_above_min_size_are_closed() {
+        let pool = UpstreamPool::<FakeUpstream>::new(
+            "fake://upstream".to_owned(),
+            UpstreamPoolConfig {
+                min_size: 1,
+                max_size: 2,
+                idle_timeout: Duration::ZERO,
+                health_check_interval: Duration::ZERO,
+                checkout_timeout: Duration::from_millis(50),
+            },
+        );
+        let mut first = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        let mut second = PooledUpstream::new(Arc::clone(&pool)).await.unwrap();
+        first.start_tx().await.unwrap();
+        second.start_tx().await.unwrap();
+        first.commit().await.unwrap();
+        second.commit().await.unwrap();
+        assert_eq!(pool.open_connections(), 2);
+
+        pool.close_expired();
+        pool.check_idle().await;
+        pool.fill().await;
+        assert_eq!(pool.open_connections(), 1);
+    }
+}
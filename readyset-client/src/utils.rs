@@ -11,13 +11,10 @@ use readyset_errors::{
     bad_request_err, invariant, invariant_eq, unsupported, unsupported_err, ReadySetResult,
 };
 
+/// Hashes the normalized text of `q`, so that the query ids and names derived from the hash stay
+/// the same across restarts of the adapter and the server
 pub(crate) fn hash_select_query(q: &SelectStatement) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let mut h = DefaultHasher::new();
-    q.hash(&mut h);
-    h.finish()
+    readyset::recipe::hash_query(q)
 }
 
 // Helper for flatten_conditional - returns true if the
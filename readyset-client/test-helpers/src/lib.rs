@@ -147,6 +147,7 @@ where
     let mut builder = Builder::for_tests();
     builder.set_allow_topk(true);
     builder.set_allow_paginate(true);
+    builder.set_dialect(A::DIALECT);
     if !partial {
         builder.disable_partial();
     }
@@ -8,6 +8,7 @@ use mysql_srv::MysqlIntermediary;
 use readyset_client::backend::noria_connector::ReadBehavior;
 use readyset_client::backend::{BackendBuilder, MigrationMode, QueryInfo};
 use readyset_client::query_status_cache::QueryStatusCache;
+use readyset_client::PooledUpstream;
 use readyset_mysql::{Backend, MySqlQueryHandler, MySqlUpstream};
 use readyset_server::Handle;
 use tokio::net::TcpStream;
@@ -67,7 +68,7 @@ impl MySQLAdapter {
 #[async_trait]
 impl Adapter for MySQLAdapter {
     type ConnectionOpts = mysql_async::Opts;
-    type Upstream = MySqlUpstream;
+    type Upstream = PooledUpstream<MySqlUpstream>;
     type Handler = MySqlQueryHandler;
 
     const DIALECT: nom_sql::Dialect = nom_sql::Dialect::MySQL;
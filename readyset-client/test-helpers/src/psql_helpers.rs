@@ -3,7 +3,7 @@ use std::env;
 use async_trait::async_trait;
 use readyset_client::backend::noria_connector::ReadBehavior;
 use readyset_client::backend::BackendBuilder;
-use readyset_client::Backend;
+use readyset_client::{Backend, PooledUpstream};
 use readyset_psql::{PostgreSqlQueryHandler, PostgreSqlUpstream};
 use readyset_server::Handle;
 use tokio::net::TcpStream;
@@ -57,7 +57,7 @@ pub struct PostgreSQLAdapter;
 #[async_trait]
 impl Adapter for PostgreSQLAdapter {
     type ConnectionOpts = tokio_postgres::Config;
-    type Upstream = PostgreSqlUpstream;
+    type Upstream = PooledUpstream<PostgreSqlUpstream>;
     type Handler = PostgreSqlQueryHandler;
 
     const DIALECT: nom_sql::Dialect = nom_sql::Dialect::PostgreSQL;
@@ -92,6 +92,6 @@ impl Adapter for PostgreSQLAdapter {
     }
 
     async fn run_backend(backend: Backend<Self::Upstream, Self::Handler>, s: TcpStream) {
-        psql_srv::run_backend(readyset_psql::Backend(backend), s).await
+        psql_srv::run_backend(readyset_psql::Backend::new(backend), s).await
     }
 }
@@ -35,7 +35,7 @@ async fn main() -> Result<()> {
         ReadBehavior::Blocking,
     )
     .await;
-    let slowlog = false;
+    let slowlog = None;
     let users: &'static HashMap<String, String> = Box::leak(Box::new(hashmap! {
         "user".to_owned() => "pw".to_owned()
     }));
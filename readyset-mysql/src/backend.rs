@@ -1,19 +1,22 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::iter;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures_util::stream::{self, StreamExt};
 use launchpad::redacted::Sensitive;
 use mysql_async::consts::StatusFlags;
 use mysql_srv::{
     Column, ColumnFlags, ColumnType, InitWriter, MsqlSrvError, MysqlShim, QueryResultWriter,
-    RowWriter, StatementMetaWriter,
+    RowWriter, StatementMetaWriter, UTF8_GENERAL_CI,
 };
-use readyset_client::backend::noria_connector::MetaVariable;
+use readyset_client::backend::noria_connector::{MetaVariable, ResultStream};
 use readyset_client::backend::{
     noria_connector, QueryResult, SinglePrepareResult, UpstreamPrepare,
 };
+use readyset_client::PooledUpstream;
 use readyset_data::DataType;
 use readyset_errors::{internal, internal_err, ReadySetError};
 use tokio::io::{self, AsyncWrite};
@@ -21,8 +24,9 @@ use tracing::{error, trace};
 use upstream::StatementMeta;
 
 use crate::schema::convert_column;
+use crate::session::SessionState;
 use crate::upstream::{self, MySqlUpstream};
-use crate::value::mysql_value_to_datatype;
+use crate::value::{mysql_json_text, mysql_value_to_datatype};
 use crate::{Error, MySqlQueryHandler};
 
 async fn write_column<W: AsyncWrite + Unpin>(
@@ -35,20 +39,29 @@ async fn write_column<W: AsyncWrite + Unpin>(
         // NOTE(malte): the code repetition here is unfortunate, but it's hard to factor
         // this out into a helper since i has a different time depending on the DataType
         // variant.
+        // Values that don't fit in the signedness of the column are written with their own, so
+        // that they're never wrapped around to a different value
         DataType::Int(i) => {
-            if cs.colflags.contains(ColumnFlags::UNSIGNED_FLAG) {
+            if cs.colflags.contains(ColumnFlags::UNSIGNED_FLAG) && i >= 0 {
                 rw.write_col(i as usize)
             } else {
                 rw.write_col(i as isize)
             }
         }
         DataType::UnsignedInt(i) => {
-            if cs.colflags.contains(ColumnFlags::UNSIGNED_FLAG) {
+            if cs.colflags.contains(ColumnFlags::UNSIGNED_FLAG) || i > i64::MAX as u64 {
                 rw.write_col(i as usize)
             } else {
                 rw.write_col(i as isize)
             }
         }
+        DataType::Text(_) | DataType::TinyText(_) if cs.coltype == ColumnType::MYSQL_TYPE_JSON => {
+            let json = <&str>::try_from(c)?;
+            match mysql_json_text(json) {
+                Some(json) => rw.write_col(json),
+                None => rw.write_col(json),
+            }
+        }
         DataType::Text(ref t) => rw.write_col(t.as_str()),
         DataType::TinyText(ref t) => rw.write_col(t.as_str()),
         ref dt @ (DataType::Float(..) | DataType::Double(..)) => match cs.coltype {
@@ -122,6 +135,9 @@ async fn write_meta_table<W: AsyncWrite + Unpin>(
             column: v.name.to_string(),
             coltype: ColumnType::MYSQL_TYPE_STRING,
             colflags: ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: UTF8_GENERAL_CI,
+            decimals: 0,
         })
         .collect::<Vec<_>>();
 
@@ -148,12 +164,18 @@ async fn write_meta_variables<W: AsyncWrite + Unpin>(
             column: "Variable_name".to_string(),
             coltype: ColumnType::MYSQL_TYPE_STRING,
             colflags: ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: UTF8_GENERAL_CI,
+            decimals: 0,
         },
         Column {
             table: "".to_owned(),
             column: "Value".to_string(),
             coltype: ColumnType::MYSQL_TYPE_STRING,
             colflags: ColumnFlags::empty(),
+            column_length: 1024,
+            character_set: UTF8_GENERAL_CI,
+            decimals: 0,
         },
     ];
     let mut writer = results.start(&cols).await?;
@@ -165,24 +187,215 @@ async fn write_meta_variables<W: AsyncWrite + Unpin>(
     writer.finish().await
 }
 
+/// Writes the values of session information functions selected by a query as a table with a single
+/// row, with the given column names
+async fn write_session_functions<W: AsyncWrite + Unpin>(
+    values: Vec<(String, DataType)>,
+    results: QueryResultWriter<'_, W>,
+) -> io::Result<()> {
+    let cols = values
+        .iter()
+        .map(|(name, value)| Column {
+            table: "".to_owned(),
+            column: name.clone(),
+            coltype: ColumnType::MYSQL_TYPE_LONGLONG,
+            colflags: if matches!(value, DataType::UnsignedInt(_)) {
+                ColumnFlags::UNSIGNED_FLAG
+            } else {
+                ColumnFlags::empty()
+            },
+            column_length: 21,
+            character_set: mysql_srv::BINARY_COLLATION,
+            decimals: 0,
+        })
+        .collect::<Vec<_>>();
+
+    let mut writer = results.start(&cols).await?;
+    for ((_, value), col) in values.iter().zip(&cols) {
+        if let Err(e) = write_column(&mut writer, value, col).await {
+            return handle_column_write_err(e, writer).await;
+        }
+    }
+    writer.end_row()?;
+    writer.finish().await
+}
+
+/// If `result` is a [`noria_connector::QueryResult::StreamingSelect`], split off the stream of
+/// the remaining pages of its results, leaving a [`noria_connector::QueryResult::Select`] of the
+/// first page
+#[allow(clippy::type_complexity)]
+fn split_result_stream(
+    result: Result<QueryResult<'_, PooledUpstream<MySqlUpstream>>, Error>,
+) -> (
+    Result<QueryResult<'_, PooledUpstream<MySqlUpstream>>, Error>,
+    Option<ResultStream>,
+) {
+    match result {
+        Ok(QueryResult::Noria(noria_connector::QueryResult::StreamingSelect {
+            data,
+            rest,
+            select_schema,
+        })) => (
+            Ok(QueryResult::Noria(noria_connector::QueryResult::Select {
+                data,
+                select_schema,
+            })),
+            Some(rest),
+        ),
+        result => (result, None),
+    }
+}
+
+/// Update the values of the session information functions with the result of a statement
+fn record_result(
+    session: &mut SessionState,
+    result: &Result<QueryResult<'_, PooledUpstream<MySqlUpstream>>, Error>,
+) {
+    match result {
+        Ok(QueryResult::Noria(noria_connector::QueryResult::Insert {
+            num_rows_inserted: rows,
+            first_inserted_id: last_inserted_id,
+        }))
+        | Ok(QueryResult::Noria(noria_connector::QueryResult::Update {
+            num_rows_updated: rows,
+            last_inserted_id,
+        }))
+        | Ok(QueryResult::Upstream(upstream::QueryResult::WriteResult {
+            num_rows_affected: rows,
+            last_inserted_id,
+            ..
+        })) => session.record_write(*rows, *last_inserted_id),
+        Ok(QueryResult::Noria(noria_connector::QueryResult::Delete { num_rows_deleted })) => {
+            session.record_write(*num_rows_deleted, 0)
+        }
+        Ok(QueryResult::Noria(noria_connector::QueryResult::Select { data, .. })) => {
+            session.record_read(data.iter().map(|results| results.len()).sum())
+        }
+        Ok(QueryResult::Noria(noria_connector::QueryResult::Meta(_))) => session.record_read(1),
+        Ok(QueryResult::Noria(noria_connector::QueryResult::MetaVariables(vars))) => {
+            session.record_read(vars.len())
+        }
+        Ok(QueryResult::Upstream(upstream::QueryResult::ReadResult { data, .. })) => {
+            session.record_read(data.len())
+        }
+        Ok(QueryResult::Upstream(upstream::QueryResult::MultipleResults(result_sets))) => {
+            // Like MySQL, the values reflect the last statement executed
+            match result_sets.last() {
+                Some(upstream::QueryResult::WriteResult {
+                    num_rows_affected,
+                    last_inserted_id,
+                    ..
+                }) => session.record_write(*num_rows_affected, *last_inserted_id),
+                Some(upstream::QueryResult::ReadResult { data, .. }) => {
+                    session.record_read(data.len())
+                }
+                _ => session.record_statement(),
+            }
+        }
+        Ok(_) => session.record_statement(),
+        Err(_) => session.record_error(),
+    }
+}
+
+/// Writes each of the result sets of a statement that was proxied upstream and produced more than
+/// one, such as a `CALL` of a stored procedure or a query containing multiple statements
+async fn write_upstream_result_sets<W: AsyncWrite + Unpin>(
+    result_sets: Vec<upstream::QueryResult>,
+    results: QueryResultWriter<'_, W>,
+) -> io::Result<()> {
+    if !results.supports_multiple_results() {
+        return results
+            .error(
+                mysql_srv::ErrorKind::ER_SP_BADSELECT,
+                "Statement returned multiple result sets, which the client does not support"
+                    .as_bytes(),
+            )
+            .await;
+    }
+
+    // The columns of every result set need to outlive the writer, so convert them all up front
+    let columns = result_sets
+        .iter()
+        .map(|result_set| match result_set {
+            upstream::QueryResult::ReadResult { data, columns, .. } => data
+                .first()
+                .map(|row| row.columns_ref())
+                .or(columns.as_deref())
+                .unwrap_or_default()
+                .iter()
+                .map(|c| c.into())
+                .collect::<Vec<Column>>(),
+            _ => vec![],
+        })
+        .collect::<Vec<_>>();
+
+    let mut results = results;
+    for (result_set, cols) in result_sets.into_iter().zip(&columns) {
+        results = match result_set {
+            upstream::QueryResult::ReadResult {
+                data, status_flags, ..
+            } => {
+                let mut rw = results.start(cols).await?;
+                for r in data {
+                    for coli in 0..cols.len() {
+                        rw.write_col(&r[coli])?;
+                    }
+                    rw.end_row()?
+                }
+                rw.set_status_flags(status_flags).finish_one().await?
+            }
+            upstream::QueryResult::WriteResult {
+                num_rows_affected,
+                last_inserted_id,
+                status_flags,
+            } => {
+                results
+                    .complete_one(num_rows_affected, last_inserted_id, Some(status_flags))
+                    .await?
+            }
+            upstream::QueryResult::Command { status_flags } => {
+                results
+                    .start(&[])
+                    .await?
+                    .set_status_flags(status_flags)
+                    .finish_one()
+                    .await?
+            }
+            upstream::QueryResult::MultipleResults(_) => {
+                let e = Error::from(internal_err("Upstream result sets cannot be nested"));
+                error!(err = %e);
+                return results
+                    .error(e.error_kind(), e.to_string().as_bytes())
+                    .await;
+            }
+        };
+    }
+    results.no_more_results().await
+}
+
 pub struct Backend {
     /// Handle to the backing ReadySet client
-    noria: readyset_client::Backend<MySqlUpstream, MySqlQueryHandler>,
+    noria: readyset_client::Backend<PooledUpstream<MySqlUpstream>, MySqlQueryHandler>,
     /// A cache of schemas per statement id
     schema_cache: HashMap<u32, CachedSchema>,
+    /// The values of the session information functions, such as `LAST_INSERT_ID()`
+    session: SessionState,
 }
 
 impl Backend {
-    pub fn new(noria: readyset_client::Backend<MySqlUpstream, MySqlQueryHandler>) -> Self {
+    pub fn new(
+        noria: readyset_client::Backend<PooledUpstream<MySqlUpstream>, MySqlQueryHandler>,
+    ) -> Self {
         Backend {
             noria,
             schema_cache: HashMap::new(),
+            session: SessionState::default(),
         }
     }
 }
 
 impl Deref for Backend {
-    type Target = readyset_client::Backend<MySqlUpstream, MySqlQueryHandler>;
+    type Target = readyset_client::Backend<PooledUpstream<MySqlUpstream>, MySqlQueryHandler>;
 
     fn deref(&self) -> &Self::Target {
         &self.noria
@@ -313,7 +526,10 @@ where
         // derived directly from ParamParser.
         let params_result = params
             .into_iter()
-            .flat_map(|p| p.map(|pval| mysql_value_to_datatype(pval.value).map_err(Error::from)))
+            .map(|p| {
+                p.map_err(Error::from)
+                    .and_then(|pval| mysql_value_to_datatype(pval.value).map_err(Error::from))
+            })
             .collect::<Result<Vec<DataType>, Error>>();
 
         let datatype_params = match params_result {
@@ -329,7 +545,10 @@ where
         // We have to perform this check before self is mutably borrowed by execute
         let is_cached = self.schema_cache.contains_key(&id);
 
-        let res = match self.execute(id, &datatype_params).await {
+        let (result, rest) = split_result_stream(self.noria.execute(id, &datatype_params).await);
+        record_result(&mut self.session, &result);
+        let streaming = rest.is_some();
+        let res = match result {
             Ok(QueryResult::Noria(noria_connector::QueryResult::Select {
                 data,
                 select_schema,
@@ -367,25 +586,45 @@ where
                 let mut rw = results
                     .start_with_cache(mysql_schema, preencoded_schema.clone())
                     .await?;
-                for r in data.into_iter().flatten() {
-                    for (c, pos) in mysql_schema.iter().zip(column_map.iter()) {
-                        match pos {
-                            Some(coli) => {
-                                if let Err(e) = write_column(&mut rw, &r[*coli], c).await {
-                                    return handle_column_write_err(e, rw).await;
-                                };
-                            }
-                            None => {
-                                let e = Error::from(internal_err(format!(
-                                    "tried to emit column {:?} not in getter with schema {:?}",
-                                    c.column, mysql_schema
-                                )));
-                                error!(err = %e);
-                                return rw.error(e.error_kind(), e.to_string().as_bytes()).await;
+                let mut pages =
+                    stream::iter(iter::once(Ok(data))).chain(stream::iter(rest).flatten());
+                let mut num_rows = 0;
+                while let Some(page) = pages.next().await {
+                    let page = match page {
+                        Ok(page) => page,
+                        Err(e) => return handle_column_write_err(e.into(), rw).await,
+                    };
+                    for r in page.into_iter().flatten() {
+                        for (c, pos) in mysql_schema.iter().zip(column_map.iter()) {
+                            match pos {
+                                Some(coli) => {
+                                    if let Err(e) = write_column(&mut rw, &r[*coli], c).await {
+                                        return handle_column_write_err(e, rw).await;
+                                    };
+                                }
+                                None => {
+                                    let e = Error::from(internal_err(format!(
+                                        "tried to emit column {:?} not in getter with schema {:?}",
+                                        c.column, mysql_schema
+                                    )));
+                                    error!(err = %e);
+                                    return rw
+                                        .error(e.error_kind(), e.to_string().as_bytes())
+                                        .await;
+                                }
                             }
                         }
+                        rw.end_row()?;
+                        num_rows += 1;
+                    }
+                    // Send each page of a streamed result set to the client as soon as it's
+                    // read, rather than holding every page until the result set is finished
+                    if streaming {
+                        rw.flush().await?;
                     }
-                    rw.end_row()?;
+                }
+                if streaming {
+                    self.session.record_read(num_rows);
                 }
                 rw.finish().await
             }
@@ -449,6 +688,9 @@ where
                     rw.set_status_flags(status_flags).finish().await
                 }
             }
+            Ok(QueryResult::Upstream(upstream::QueryResult::MultipleResults(result_sets))) => {
+                write_upstream_result_sets(result_sets, results).await
+            }
             Err(e @ Error::ReadySet(ReadySetError::PreparedStatementMissing { .. })) => {
                 return results
                     .error(e.error_kind(), "non-existent statement".as_bytes())
@@ -527,7 +769,15 @@ where
     async fn on_close(&mut self, _: u32) {}
 
     async fn on_query(&mut self, query: &str, results: QueryResultWriter<'_, W>) -> io::Result<()> {
-        let res = match self.query(query).await {
+        if let Some(values) = self.session.evaluate(query) {
+            self.session.record_read(1);
+            return write_session_functions(values, results).await;
+        }
+
+        let (result, rest) = split_result_stream(self.noria.query(query).await);
+        record_result(&mut self.session, &result);
+        let streaming = rest.is_some();
+        let res = match result {
             Ok(QueryResult::Noria(noria_connector::QueryResult::Empty)) => {
                 results.completed(0, 0, None).await
             }
@@ -543,8 +793,15 @@ where
             })) => {
                 let schema = convert_columns!(select_schema.schema, results);
                 let mut rw = results.start(&schema).await?;
-                for resultsets in data {
-                    for r in resultsets {
+                let mut pages =
+                    stream::iter(iter::once(Ok(data))).chain(stream::iter(rest).flatten());
+                let mut num_rows = 0;
+                while let Some(page) = pages.next().await {
+                    let page = match page {
+                        Ok(page) => page,
+                        Err(e) => return handle_column_write_err(e.into(), rw).await,
+                    };
+                    for r in page.into_iter().flatten() {
                         for c in &schema {
                             match select_schema
                                 .columns
@@ -569,8 +826,17 @@ where
                             }
                         }
                         rw.end_row()?;
+                        num_rows += 1;
+                    }
+                    // Send each page of a streamed result set to the client as soon as it's
+                    // read, rather than holding every page until the result set is finished
+                    if streaming {
+                        rw.flush().await?;
                     }
                 }
+                if streaming {
+                    self.session.record_read(num_rows);
+                }
                 rw.finish().await
             }
             Ok(QueryResult::Noria(noria_connector::QueryResult::Update {
@@ -588,6 +854,15 @@ where
             Ok(QueryResult::Noria(noria_connector::QueryResult::MetaVariables(vars))) => {
                 write_meta_variables(vars, results).await
             }
+            Ok(QueryResult::Noria(noria_connector::QueryResult::StreamingSelect { .. })) => {
+                let e = Error::from(internal_err(
+                    "Streamed result set wasn't split by split_result_stream",
+                ));
+                error!(err = %e);
+                results
+                    .error(e.error_kind(), e.to_string().as_bytes())
+                    .await
+            }
             Ok(QueryResult::Upstream(upstream::QueryResult::WriteResult {
                 num_rows_affected,
                 last_inserted_id,
@@ -630,6 +905,9 @@ where
                 let rw = results.start(&[]).await?;
                 rw.set_status_flags(status_flags).finish().await
             }
+            Ok(QueryResult::Upstream(upstream::QueryResult::MultipleResults(result_sets))) => {
+                write_upstream_result_sets(result_sets, results).await
+            }
             Err(Error::MySql(mysql_async::Error::Server(mysql_async::ServerError {
                 code,
                 message,
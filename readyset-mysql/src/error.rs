@@ -1,5 +1,6 @@
 use std::io;
 
+use database_utils::error::DatabaseURLParseError;
 use mysql_srv::MsqlSrvError;
 use readyset::ReadySetError;
 use readyset_client::upstream_database::IsFatalError;
@@ -18,6 +19,9 @@ pub enum Error {
 
     #[error(transparent)]
     Io(#[from] io::Error),
+
+    #[error(transparent)]
+    DatabaseUrl(#[from] DatabaseURLParseError),
 }
 
 impl Error {
@@ -2,6 +2,7 @@ mod backend;
 mod error;
 mod query_handler;
 mod schema;
+mod session;
 mod upstream;
 mod value;
 
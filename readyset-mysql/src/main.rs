@@ -4,7 +4,9 @@ use async_trait::async_trait;
 use clap::Parser;
 use mysql_srv::MysqlIntermediary;
 use nom_sql::Dialect;
-use readyset_client_adapter::{ConnectionHandler, DatabaseType, NoriaAdapter};
+use readyset_client::PooledUpstream;
+use readyset_client_adapter::{ConnectionHandler, ConnectionTls, DatabaseType, NoriaAdapter};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net;
 use tracing::{error, instrument};
 
@@ -35,14 +37,37 @@ impl ConnectionHandler for MysqlHandler {
     async fn process_connection(
         &mut self,
         stream: net::TcpStream,
-        backend: readyset_client::Backend<MySqlUpstream, MySqlQueryHandler>,
+        backend: readyset_client::Backend<PooledUpstream<MySqlUpstream>, MySqlQueryHandler>,
+        tls: Option<ConnectionTls>,
     ) {
-        if let Err(e) = MysqlIntermediary::run_on_tcp(Backend::new(backend), stream).await {
+        let backend = Backend::new(backend);
+        let res = match tls {
+            Some(tls) => {
+                MysqlIntermediary::run_on_tls(backend, stream, &tls.acceptor, tls.required).await
+            }
+            None => MysqlIntermediary::run_on_tcp(backend, stream).await,
+        };
+        if let Err(e) = res {
             error!(err = %e, "connection lost");
         }
     }
 
-    async fn immediate_error(self, stream: net::TcpStream, error_message: String) {
+    #[instrument(level = "debug", "connection", skip_all)]
+    async fn process_unix_connection(
+        &mut self,
+        stream: net::UnixStream,
+        backend: readyset_client::Backend<PooledUpstream<MySqlUpstream>, MySqlQueryHandler>,
+    ) {
+        let (reader, writer) = stream.into_split();
+        if let Err(e) = MysqlIntermediary::run_on(Backend::new(backend), reader, writer).await {
+            error!(err = %e, "connection lost");
+        }
+    }
+
+    async fn immediate_error<S>(self, stream: S, error_message: String)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         if let Err(error) = mysql_srv::send_immediate_err(
             stream,
             mysql_srv::ErrorKind::ER_UNKNOWN_ERROR,
@@ -1,6 +1,6 @@
 #![warn(clippy::panic)]
 
-use nom_sql::{self, ColumnConstraint, SqlType};
+use nom_sql::{self, ColumnConstraint, Literal, SqlType};
 use readyset_errors::{unsupported, ReadySetResult};
 
 /// Checks if `c1` is a subtype of `c2`.
@@ -28,87 +28,161 @@ pub(crate) fn is_subtype(c1: mysql_srv::ColumnType, c2: mysql_srv::ColumnType) -
     )
 }
 
+/// The maximum number of bytes needed to encode a single character in the character set we report
+/// for text columns (utf8)
+const MAX_BYTES_PER_CHAR: u32 = 3;
+
+/// The value MySQL reports for the number of decimals of floating-point columns, which don't have a
+/// fixed number of digits after the decimal point
+const NOT_FIXED_DECIMALS: u8 = 0x1f;
+
+/// Returns the number of characters needed to display a decimal number with the given precision
+/// and scale, including the sign and decimal point
+fn decimal_length(precision: u32, scale: u8) -> u32 {
+    precision + if scale > 0 { 2 } else { 1 }
+}
+
+/// Converts the given column specification to the metadata we send to clients to describe
+/// columns with that specification.
+///
+/// The metadata matches what MySQL itself would report for a column of the same type, since some
+/// clients (JDBC, and ORMs built on it) validate it strictly - for example, by checking that
+/// `NOT NULL` columns never return nulls, or by using the reported length and number of decimals
+/// to decide how to decode values.
 pub(crate) fn convert_column(
     col: &nom_sql::ColumnSpecification,
 ) -> ReadySetResult<mysql_srv::Column> {
     let mut colflags = mysql_srv::ColumnFlags::empty();
+    let mut decimals = 0;
+    let mut text = false;
     use mysql_srv::ColumnType::*;
 
-    let coltype = match col.sql_type {
-        SqlType::Mediumtext => MYSQL_TYPE_VAR_STRING,
-        SqlType::Longtext => MYSQL_TYPE_BLOB,
-        SqlType::Text => MYSQL_TYPE_BLOB,
-        SqlType::Varchar(_) => MYSQL_TYPE_VAR_STRING,
-        SqlType::Int(_) => MYSQL_TYPE_LONG,
+    let (coltype, column_length) = match col.sql_type {
+        SqlType::Tinytext => {
+            text = true;
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_BLOB, 255)
+        }
+        SqlType::Text => {
+            text = true;
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_BLOB, 65_535)
+        }
+        SqlType::Mediumtext => {
+            text = true;
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_BLOB, 16_777_215)
+        }
+        SqlType::Longtext => {
+            text = true;
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_BLOB, u32::MAX)
+        }
+        SqlType::Varchar(len) => {
+            text = true;
+            (MYSQL_TYPE_VAR_STRING, u32::from(len.unwrap_or(255)))
+        }
+        SqlType::Char(len) => {
+            text = true;
+            (MYSQL_TYPE_STRING, u32::from(len.unwrap_or(1)))
+        }
+        SqlType::Enum(ref variants) => {
+            text = true;
+            colflags |= mysql_srv::ColumnFlags::ENUM_FLAG;
+            let len = variants
+                .iter()
+                .map(|v| match v {
+                    Literal::String(s) => s.len(),
+                    v => v.to_string().len(),
+                })
+                .max()
+                .unwrap_or(0);
+            (MYSQL_TYPE_STRING, len as u32)
+        }
+        SqlType::Int(_) | SqlType::Serial => (MYSQL_TYPE_LONG, 11),
         SqlType::UnsignedInt(_) => {
             colflags |= mysql_srv::ColumnFlags::UNSIGNED_FLAG;
-            MYSQL_TYPE_LONG
+            (MYSQL_TYPE_LONG, 10)
         }
-        SqlType::Bigint(_) => MYSQL_TYPE_LONGLONG,
+        SqlType::Bigint(_) | SqlType::BigSerial => (MYSQL_TYPE_LONGLONG, 20),
         SqlType::UnsignedBigint(_) => {
             colflags |= mysql_srv::ColumnFlags::UNSIGNED_FLAG;
-            MYSQL_TYPE_LONGLONG
+            (MYSQL_TYPE_LONGLONG, 20)
         }
-        SqlType::Tinyint(_) => MYSQL_TYPE_TINY,
+        SqlType::Tinyint(_) => (MYSQL_TYPE_TINY, 4),
         SqlType::UnsignedTinyint(_) => {
             colflags |= mysql_srv::ColumnFlags::UNSIGNED_FLAG;
-            MYSQL_TYPE_TINY
+            (MYSQL_TYPE_TINY, 3)
         }
-        SqlType::Smallint(_) => MYSQL_TYPE_SHORT,
+        SqlType::Smallint(_) => (MYSQL_TYPE_SHORT, 6),
         SqlType::UnsignedSmallint(_) => {
             colflags |= mysql_srv::ColumnFlags::UNSIGNED_FLAG;
-            MYSQL_TYPE_SHORT
-        }
-        SqlType::Bool => MYSQL_TYPE_BIT,
-        SqlType::DateTime(_) => MYSQL_TYPE_DATETIME,
-        SqlType::Float => MYSQL_TYPE_FLOAT,
-        SqlType::Decimal(_, _) => MYSQL_TYPE_NEWDECIMAL,
-        SqlType::Char(_) => {
-            // TODO(grfn): I'm not sure if this is right
-            MYSQL_TYPE_STRING
-        }
-        SqlType::Blob => MYSQL_TYPE_BLOB,
-        SqlType::Longblob => MYSQL_TYPE_LONG_BLOB,
-        SqlType::Mediumblob => MYSQL_TYPE_MEDIUM_BLOB,
-        SqlType::Tinyblob => MYSQL_TYPE_TINY_BLOB,
-        SqlType::Double => MYSQL_TYPE_DOUBLE,
-        SqlType::Real => {
-            // a generous reading of
-            // https://dev.mysql.com/doc/refman/8.0/en/floating-point-types.html seems to
-            // indicate that real is equivalent to float
-            // TODO(grfn): Make sure that's the case
-            MYSQL_TYPE_FLOAT
+            (MYSQL_TYPE_SHORT, 5)
         }
-        SqlType::Tinytext => {
-            // TODO(grfn): How does the mysql binary protocol handle
-            // tinytext? is it just an alias for tinyblob or is there a flag
-            // we need?
-            unsupported!()
+        // BOOL is an alias for TINYINT(1) in MySQL
+        SqlType::Bool => (MYSQL_TYPE_TINY, 1),
+        SqlType::Float | SqlType::Real => {
+            // REAL is an alias for DOUBLE in MySQL, unless the REAL_AS_FLOAT SQL mode is enabled,
+            // but we parse it as a float
+            decimals = NOT_FIXED_DECIMALS;
+            (MYSQL_TYPE_FLOAT, 12)
         }
-        SqlType::Date => MYSQL_TYPE_DATE,
-        SqlType::Timestamp => MYSQL_TYPE_TIMESTAMP,
-        SqlType::TimestampTz => {
-            unsupported!("MySQL does not support the timestamp with time zone type")
+        SqlType::Double => {
+            decimals = NOT_FIXED_DECIMALS;
+            (MYSQL_TYPE_DOUBLE, 22)
         }
-        SqlType::Binary(_) => {
-            // TODO(grfn): I don't know if this is right
-            colflags |= mysql_srv::ColumnFlags::BINARY_FLAG;
-            MYSQL_TYPE_STRING
+        SqlType::Decimal(precision, scale) => {
+            decimals = scale;
+            (
+                MYSQL_TYPE_NEWDECIMAL,
+                decimal_length(precision.into(), scale),
+            )
         }
-        SqlType::Varbinary(_) => {
-            // TODO(grfn): I don't know if this is right
-            colflags |= mysql_srv::ColumnFlags::BINARY_FLAG;
-            MYSQL_TYPE_VAR_STRING
+        SqlType::Numeric(precision) => {
+            // DECIMAL and NUMERIC are the same type in MySQL, and both default to DECIMAL(10, 0)
+            let (precision, scale) = precision.unwrap_or((10, None));
+            decimals = scale.unwrap_or(0);
+            (
+                MYSQL_TYPE_NEWDECIMAL,
+                decimal_length(precision.into(), decimals),
+            )
         }
-        SqlType::Enum(_) => {
-            // TODO(grfn): I don't know if this is right
-            colflags |= mysql_srv::ColumnFlags::ENUM_FLAG;
-            MYSQL_TYPE_VAR_STRING
+        SqlType::Blob | SqlType::ByteArray => {
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_BLOB, 65_535)
+        }
+        SqlType::Longblob => {
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_LONG_BLOB, u32::MAX)
+        }
+        SqlType::Mediumblob => {
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_MEDIUM_BLOB, 16_777_215)
+        }
+        SqlType::Tinyblob => {
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_TINY_BLOB, 255)
+        }
+        SqlType::Binary(len) => (MYSQL_TYPE_STRING, u32::from(len.unwrap_or(1))),
+        SqlType::Varbinary(len) => (MYSQL_TYPE_VAR_STRING, u32::from(len)),
+        SqlType::Date => (MYSQL_TYPE_DATE, 10),
+        SqlType::DateTime(fsp) => {
+            let fsp = fsp.unwrap_or(0);
+            decimals = fsp as u8;
+            (
+                MYSQL_TYPE_DATETIME,
+                19 + if fsp > 0 { u32::from(fsp) + 1 } else { 0 },
+            )
+        }
+        SqlType::Timestamp => (MYSQL_TYPE_TIMESTAMP, 19),
+        SqlType::TimestampTz => {
+            unsupported!("MySQL does not support the timestamp with time zone type")
+        }
+        SqlType::Time => (MYSQL_TYPE_TIME, 10),
+        SqlType::Json => {
+            colflags |= mysql_srv::ColumnFlags::BLOB_FLAG;
+            (MYSQL_TYPE_JSON, u32::MAX)
         }
-        SqlType::Time => MYSQL_TYPE_TIME,
-        SqlType::Json => MYSQL_TYPE_JSON,
-        SqlType::ByteArray => MYSQL_TYPE_BLOB,
-        SqlType::Numeric(_) => MYSQL_TYPE_DECIMAL,
         SqlType::MacAddr => unsupported!("MySQL does not support the MACADDR type"),
         SqlType::Inet => unsupported!("MySQL does not support the INET type"),
         SqlType::Uuid => unsupported!("MySQL does not support the UUID type"),
@@ -116,14 +190,23 @@ pub(crate) fn convert_column(
         SqlType::Bit(size_opt) => {
             let size = size_opt.unwrap_or(1);
             if size < 64 {
-                MYSQL_TYPE_BIT
+                colflags |= mysql_srv::ColumnFlags::UNSIGNED_FLAG;
+                (MYSQL_TYPE_BIT, u32::from(size))
             } else {
                 unsupported!("MySQL bit type cannot have a size bigger than 64")
             }
         }
         SqlType::Varbit(_) => unsupported!("MySQL does not support the bit varying type"),
-        SqlType::Serial => MYSQL_TYPE_LONG,
-        SqlType::BigSerial => MYSQL_TYPE_LONGLONG,
+    };
+
+    let (column_length, character_set) = if text {
+        (
+            column_length.saturating_mul(MAX_BYTES_PER_CHAR),
+            mysql_srv::UTF8_GENERAL_CI,
+        )
+    } else {
+        colflags |= mysql_srv::ColumnFlags::BINARY_FLAG;
+        (column_length, mysql_srv::BINARY_COLLATION)
     };
 
     for c in &col.constraints {
@@ -135,7 +218,9 @@ pub(crate) fn convert_column(
                 colflags |= mysql_srv::ColumnFlags::NOT_NULL_FLAG;
             }
             ColumnConstraint::PrimaryKey => {
+                // Primary key columns are implicitly NOT NULL
                 colflags |= mysql_srv::ColumnFlags::PRI_KEY_FLAG;
+                colflags |= mysql_srv::ColumnFlags::NOT_NULL_FLAG;
             }
             ColumnConstraint::Unique => {
                 colflags |= mysql_srv::ColumnFlags::UNIQUE_KEY_FLAG;
@@ -149,6 +234,9 @@ pub(crate) fn convert_column(
         column: col.column.name.to_string(),
         coltype,
         colflags,
+        column_length,
+        character_set,
+        decimals,
     })
 }
 
@@ -223,4 +311,53 @@ mod tests {
             assert!(c1 == c2);
         }
     }
+
+    fn convert(sql_type: SqlType, constraints: Vec<ColumnConstraint>) -> mysql_srv::Column {
+        convert_column(&nom_sql::ColumnSpecification {
+            column: "t.c".into(),
+            sql_type,
+            constraints,
+            comment: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn convert_decimal() {
+        let col = convert(SqlType::Decimal(8, 3), vec![]);
+        assert_eq!(col.coltype, MYSQL_TYPE_NEWDECIMAL);
+        assert_eq!(col.column_length, 10);
+        assert_eq!(col.decimals, 3);
+        assert_eq!(col.character_set, mysql_srv::BINARY_COLLATION);
+        assert!(col.colflags.contains(mysql_srv::ColumnFlags::BINARY_FLAG));
+    }
+
+    #[test]
+    fn convert_varchar() {
+        let col = convert(SqlType::Varchar(Some(20)), vec![ColumnConstraint::NotNull]);
+        assert_eq!(col.coltype, MYSQL_TYPE_VAR_STRING);
+        assert_eq!(col.column_length, 60);
+        assert_eq!(col.decimals, 0);
+        assert_eq!(col.character_set, mysql_srv::UTF8_GENERAL_CI);
+        assert!(col.colflags.contains(mysql_srv::ColumnFlags::NOT_NULL_FLAG));
+        assert!(!col.colflags.contains(mysql_srv::ColumnFlags::BINARY_FLAG));
+    }
+
+    #[test]
+    fn convert_datetime_with_fractional_seconds() {
+        let col = convert(SqlType::DateTime(Some(3)), vec![]);
+        assert_eq!(col.coltype, MYSQL_TYPE_DATETIME);
+        assert_eq!(col.column_length, 23);
+        assert_eq!(col.decimals, 3);
+    }
+
+    #[test]
+    fn primary_key_is_not_null() {
+        let col = convert(SqlType::Int(None), vec![ColumnConstraint::PrimaryKey]);
+        assert_eq!(col.coltype, MYSQL_TYPE_LONG);
+        assert_eq!(col.column_length, 11);
+        assert!(col.colflags.contains(
+            mysql_srv::ColumnFlags::PRI_KEY_FLAG | mysql_srv::ColumnFlags::NOT_NULL_FLAG
+        ));
+    }
 }
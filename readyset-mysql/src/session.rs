@@ -0,0 +1,169 @@
+//! MySQL's session information functions: `LAST_INSERT_ID()`, `ROW_COUNT()` and `FOUND_ROWS()`.
+//!
+//! These return information about the statements previously executed on the same connection, so
+//! they can't be proxied upstream - the upstream connection that executes them may not be the one
+//! that executed the statements they refer to, and statements executed by ReadySet itself never
+//! reach the upstream database at all. Instead, the adapter keeps track of the values they return
+//! for each connection in a [`SessionState`], fed by the results of every statement it executes,
+//! and answers queries that select only these functions itself.
+
+use nom_sql::{
+    Dialect, Expression, FieldDefinitionExpression, FunctionExpression, SelectStatement, SqlQuery,
+};
+use readyset_data::DataType;
+
+/// A session information function with no arguments
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SessionFunction {
+    LastInsertId,
+    RowCount,
+    FoundRows,
+}
+
+impl SessionFunction {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "last_insert_id" => Some(Self::LastInsertId),
+            "row_count" => Some(Self::RowCount),
+            "found_rows" => Some(Self::FoundRows),
+            _ => None,
+        }
+    }
+}
+
+/// The values returned by the session information functions for a single connection
+#[derive(Debug)]
+pub(crate) struct SessionState {
+    /// The first automatically generated value inserted into an `AUTO_INCREMENT` column by the
+    /// most recent statement that generated one
+    last_insert_id: u64,
+    /// The number of rows affected by the previous statement, or -1 if it was a query that
+    /// returned rows (or failed)
+    row_count: i64,
+    /// The number of rows returned by the most recent query that returned rows
+    found_rows: u64,
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        Self {
+            last_insert_id: 0,
+            row_count: -1,
+            found_rows: 0,
+        }
+    }
+}
+
+impl SessionState {
+    /// Record the result of a statement that wrote to a table. Like MySQL, `LAST_INSERT_ID()` is
+    /// left as it is if the statement didn't generate an auto-increment value.
+    pub(crate) fn record_write(&mut self, rows_affected: u64, last_insert_id: u64) {
+        self.row_count = rows_affected as i64;
+        if last_insert_id != 0 {
+            self.last_insert_id = last_insert_id;
+        }
+    }
+
+    /// Record the result of a query that returned `rows` rows
+    pub(crate) fn record_read(&mut self, rows: usize) {
+        self.row_count = -1;
+        self.found_rows = rows as u64;
+    }
+
+    /// Record the result of a statement that neither wrote to a table nor returned rows
+    pub(crate) fn record_statement(&mut self) {
+        self.row_count = 0;
+    }
+
+    /// Record that a statement failed
+    pub(crate) fn record_error(&mut self) {
+        self.row_count = -1;
+    }
+
+    /// If `query` is a `SELECT` of nothing but session information functions, returns the name
+    /// and value of each column of its single result row.
+    pub(crate) fn evaluate(&self, query: &str) -> Option<Vec<(String, DataType)>> {
+        // Avoid parsing queries that can't possibly call one of the functions
+        let lowercase = query.to_ascii_lowercase();
+        if !["last_insert_id", "row_count", "found_rows"]
+            .iter()
+            .any(|name| lowercase.contains(name))
+        {
+            return None;
+        }
+
+        let stmt = match nom_sql::parse_query(Dialect::MySQL, query) {
+            Ok(SqlQuery::Select(stmt)) => stmt,
+            _ => return None,
+        };
+        if stmt
+            != (SelectStatement {
+                fields: stmt.fields.clone(),
+                ..Default::default()
+            })
+        {
+            return None;
+        }
+
+        stmt.fields
+            .iter()
+            .map(|field| match field {
+                FieldDefinitionExpression::Expression {
+                    expr: expr @ Expression::Call(FunctionExpression::Call { name, arguments }),
+                    alias,
+                } if arguments.is_empty() => {
+                    let value = match SessionFunction::from_name(name)? {
+                        SessionFunction::LastInsertId => DataType::from(self.last_insert_id),
+                        SessionFunction::RowCount => DataType::from(self.row_count),
+                        SessionFunction::FoundRows => DataType::from(self.found_rows),
+                    };
+                    let name = match alias {
+                        Some(alias) => alias.to_string(),
+                        None => expr.to_string(),
+                    };
+                    Some((name, value))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_session_functions() {
+        let mut session = SessionState::default();
+        session.record_write(3, 42);
+        assert_eq!(
+            session.evaluate("SELECT LAST_INSERT_ID(), row_count() AS n"),
+            Some(vec![
+                ("LAST_INSERT_ID()".to_owned(), DataType::from(42u64)),
+                ("n".to_owned(), DataType::from(3i64)),
+            ])
+        );
+
+        // Statements that don't generate an id leave LAST_INSERT_ID() alone
+        session.record_write(1, 0);
+        session.record_read(5);
+        assert_eq!(
+            session.evaluate("select last_insert_id() as id, row_count() as n, found_rows() as f"),
+            Some(vec![
+                ("id".to_owned(), DataType::from(42u64)),
+                ("n".to_owned(), DataType::from(-1i64)),
+                ("f".to_owned(), DataType::from(5u64)),
+            ])
+        );
+    }
+
+    #[test]
+    fn ignores_other_queries() {
+        let session = SessionState::default();
+        assert_eq!(session.evaluate("SELECT 1"), None);
+        assert_eq!(session.evaluate("SELECT LAST_INSERT_ID(5)"), None);
+        assert_eq!(session.evaluate("SELECT LAST_INSERT_ID(), x FROM t"), None);
+        assert_eq!(session.evaluate("SELECT row_count FROM t"), None);
+    }
+}
@@ -4,8 +4,9 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use mysql_async::consts::{CapabilityFlags, StatusFlags};
-use mysql_async::prelude::Queryable;
-use mysql_async::{Column, Conn, Opts, OptsBuilder, Row, TxOpts, UrlError};
+use mysql_async::prelude::{Protocol, Queryable};
+use mysql_async::{Column, Conn, OptsBuilder, Row, TxOpts};
+use readyset::replication::ReplicationOffset;
 use readyset::ColumnSchema;
 use readyset_client::upstream_database::NoriaCompare;
 use readyset_client::{UpstreamDatabase, UpstreamPrepare};
@@ -37,6 +38,76 @@ pub enum QueryResult {
     Command {
         status_flags: StatusFlags,
     },
+    /// The results of a statement that produced more than one result set, such as a `CALL` of a
+    /// stored procedure or a query containing multiple statements, in the order they were
+    /// returned. None of the results are themselves [`QueryResult::MultipleResults`].
+    MultipleResults(Vec<QueryResult>),
+}
+
+impl QueryResult {
+    /// Build a [`QueryResult`] from the result sets returned by [`read_result_sets`], with the
+    /// given status flags
+    fn from_result_sets(mut result_sets: Vec<QueryResult>, status_flags: StatusFlags) -> Self {
+        for result_set in &mut result_sets {
+            match result_set {
+                QueryResult::WriteResult {
+                    status_flags: flags,
+                    ..
+                }
+                | QueryResult::ReadResult {
+                    status_flags: flags,
+                    ..
+                }
+                | QueryResult::Command {
+                    status_flags: flags,
+                } => *flags = status_flags,
+                QueryResult::MultipleResults(_) => {}
+            }
+        }
+
+        if result_sets.len() == 1 {
+            result_sets.remove(0)
+        } else {
+            QueryResult::MultipleResults(result_sets)
+        }
+    }
+}
+
+/// Read every result set from `result`, so that none are left unread on the connection.
+///
+/// The status flags of the returned results are left empty, since they can only be read from the
+/// connection once `result` has been consumed.
+async fn read_result_sets<P>(
+    mut result: mysql_async::QueryResult<'_, '_, P>,
+) -> Result<Vec<QueryResult>, Error>
+where
+    P: Protocol + Send,
+{
+    let mut result_sets = vec![];
+    loop {
+        let columns = result.columns().ok_or_else(|| {
+            ReadySetError::Internal("The mysql_async result was already consumed".to_string())
+        })?;
+        if columns.len() > 0 {
+            result_sets.push(QueryResult::ReadResult {
+                data: result.collect().await?,
+                columns: Some(columns),
+                status_flags: StatusFlags::empty(),
+            });
+        } else {
+            result_sets.push(QueryResult::WriteResult {
+                num_rows_affected: result.affected_rows(),
+                last_inserted_id: result.last_insert_id().unwrap_or(0),
+                status_flags: StatusFlags::empty(),
+            });
+            // Move on to the next result set, if there is one
+            result.collect::<Row>().await?;
+        }
+
+        if result.is_empty() {
+            return Ok(result_sets);
+        }
+    }
 }
 
 /// A connector to an underlying mysql store. This is really just a wrapper for the mysql crate.
@@ -102,8 +173,7 @@ impl UpstreamDatabase for MySqlUpstream {
         // GTID information is used for RYW
         // Currently this causes rows affected to return an incorrect result, so this is feature
         // gated.
-        let opts =
-            Opts::from_url(&url).map_err(|e: UrlError| Error::MySql(mysql_async::Error::Url(e)))?;
+        let opts = database_utils::tls::mysql_opts(&url)?;
         let span = info_span!(
             "Connecting to MySQL upstream",
             host = %opts.ip_or_hostname(),
@@ -176,7 +246,7 @@ impl UpstreamDatabase for MySqlUpstream {
 
     async fn execute(&mut self, id: u32, params: &[DataType]) -> Result<Self::QueryResult, Error> {
         let params = dt_to_value_params(params)?;
-        let mut result = self
+        let result = self
             .conn
             .exec_iter(
                 self.prepared_statements.get(&id).ok_or(Error::ReadySet(
@@ -185,47 +255,25 @@ impl UpstreamDatabase for MySqlUpstream {
                 params,
             )
             .await?;
-        let columns = result.columns().ok_or_else(|| {
-            ReadySetError::Internal("The mysql_async result was already consumed".to_string())
-        })?;
+        let result_sets = read_result_sets(result).await?;
 
-        if columns.len() > 0 {
-            Ok(QueryResult::ReadResult {
-                data: result.collect().await?,
-                columns: Some(columns),
-                status_flags: self.conn.status(),
-            })
-        } else {
-            Ok(QueryResult::WriteResult {
-                num_rows_affected: result.affected_rows(),
-                last_inserted_id: result.last_insert_id().unwrap_or(1),
-                status_flags: self.conn.status(),
-            })
-        }
+        Ok(QueryResult::from_result_sets(
+            result_sets,
+            self.conn.status(),
+        ))
     }
 
     async fn query<'a, S>(&'a mut self, query: S) -> Result<Self::QueryResult, Error>
     where
         S: AsRef<str> + Send + Sync + 'a,
     {
-        let mut result = self.conn.query_iter(query).await?;
+        let result = self.conn.query_iter(query).await?;
+        let result_sets = read_result_sets(result).await?;
 
-        let columns = result.columns().ok_or_else(|| {
-            ReadySetError::Internal("The mysql_async result was already consumed".to_string())
-        })?;
-        if columns.len() > 0 {
-            Ok(QueryResult::ReadResult {
-                data: result.collect().await?,
-                columns: Some(columns),
-                status_flags: self.conn.status(),
-            })
-        } else {
-            Ok(QueryResult::WriteResult {
-                num_rows_affected: result.affected_rows(),
-                last_inserted_id: result.last_insert_id().unwrap_or(1),
-                status_flags: self.conn.status(),
-            })
-        }
+        Ok(QueryResult::from_result_sets(
+            result_sets,
+            self.conn.status(),
+        ))
     }
 
     /// Executes the given query on the mysql backend.
@@ -303,6 +351,26 @@ impl UpstreamDatabase for MySqlUpstream {
         }
         Ok(dump.into_bytes())
     }
+
+    /// Returns the current position of the binlog, or `None` if binary logging is disabled
+    async fn replication_offset(&mut self) -> Result<Option<ReplicationOffset>, Error> {
+        let status = self
+            .conn
+            .query_first::<Row, _>("SHOW MASTER STATUS")
+            .await?
+            .and_then(|row| {
+                let file: String = row.get_opt("File")?.ok()?;
+                let position: u32 = row.get_opt("Position")?.ok()?;
+                Some((file, position))
+            });
+
+        match status {
+            Some((file, position)) => Ok(Some(ReplicationOffset::from_binlog_position(
+                &file, position,
+            )?)),
+            None => Ok(None),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -400,4 +468,28 @@ mod tests {
 
         assert!(s.compare(&schema_spec, &param_specs).is_err());
     }
+
+    #[test]
+    fn from_result_sets() {
+        let command = || QueryResult::Command {
+            status_flags: StatusFlags::empty(),
+        };
+        let flags = StatusFlags::SERVER_STATUS_AUTOCOMMIT;
+
+        assert!(matches!(
+            QueryResult::from_result_sets(vec![command()], flags),
+            QueryResult::Command { status_flags } if status_flags == flags
+        ));
+
+        match QueryResult::from_result_sets(vec![command(), command()], flags) {
+            QueryResult::MultipleResults(results) => {
+                assert_eq!(results.len(), 2);
+                assert!(results.iter().all(|result| matches!(
+                    result,
+                    QueryResult::Command { status_flags } if *status_flags == flags
+                )));
+            }
+            result => panic!("Expected multiple results, got {:?}", result),
+        }
+    }
 }
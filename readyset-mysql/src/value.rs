@@ -39,3 +39,61 @@ pub(crate) fn mysql_value_to_datatype(value: Value) -> ReadySetResult<DataType>
         ),
     })
 }
+
+/// Formats the JSON in `json` the way MySQL formats values of its `JSON` type - with a space after
+/// every `,` and `:`, and with the keys of objects sorted by length and then bytewise (the order
+/// MySQL stores them in) - or returns `None` if `json` isn't valid JSON.
+///
+/// JSON values replicated from the binlog are stored in whatever format they were converted to,
+/// so this makes sure clients get exactly the same bytes as they would from MySQL.
+pub(crate) fn mysql_json_text(json: &str) -> Option<String> {
+    fn write(value: &serde_json::Value, out: &mut String) {
+        match value {
+            serde_json::Value::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    write(value, out);
+                }
+                out.push(']');
+            }
+            serde_json::Value::Object(map) => {
+                let mut entries = map.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(key, _)| (key.len(), *key));
+                out.push('{');
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    out.push_str(&serde_json::Value::from(key.as_str()).to_string());
+                    out.push_str(": ");
+                    write(value, out);
+                }
+                out.push('}');
+            }
+            value => out.push_str(&value.to_string()),
+        }
+    }
+
+    let value = serde_json::from_str(json).ok()?;
+    let mut out = String::with_capacity(json.len());
+    write(&value, &mut out);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_text_matches_mysql() {
+        assert_eq!(
+            mysql_json_text(r#"{"bb":[1,2.5,null],"a":{"c":"x\"y"},"ab":true}"#).unwrap(),
+            r#"{"a": {"c": "x\"y"}, "ab": true, "bb": [1, 2.5, null]}"#
+        );
+        assert_eq!(mysql_json_text("[]").unwrap(), "[]");
+        assert_eq!(mysql_json_text("not json"), None);
+    }
+}
@@ -1,8 +1,11 @@
+use std::time::Duration;
+
 use mysql_async::prelude::*;
 use readyset_client::backend::noria_connector::ReadBehavior;
 use readyset_client::backend::UnsupportedSetMode;
 use readyset_client::BackendBuilder;
-use readyset_client_test_helpers::mysql_helpers::MySQLAdapter;
+use readyset_client_metrics::QueryDestination;
+use readyset_client_test_helpers::mysql_helpers::{last_query_info, MySQLAdapter};
 use readyset_client_test_helpers::{self, sleep};
 use readyset_server::Handle;
 use serial_test::serial;
@@ -235,3 +238,116 @@ async fn proxy_unsupported_sets() {
         1,
     );
 }
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn read_timeout_falls_back_to_upstream() {
+    let (opts, _handle) = setup_with(
+        BackendBuilder::new()
+            .require_authentication(false)
+            .read_timeout(Some(Duration::from_nanos(1))),
+    )
+    .await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+
+    conn.query_drop("CREATE TABLE t (x int)").await.unwrap();
+    conn.query_drop("INSERT INTO t (x) values (1)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    // The first execution migrates the query, after which reads from ReadySet can't possibly
+    // finish in time and are retried against the upstream database
+    let _: Vec<(i32,)> = conn.query("SELECT x FROM t WHERE x = 1").await.unwrap();
+    let rows: Vec<(i32,)> = conn.query("SELECT x FROM t WHERE x = 1").await.unwrap();
+    assert_eq!(rows, vec![(1,)]);
+    let info = last_query_info(&mut conn).await;
+    assert_eq!(info.destination, QueryDestination::ReadysetThenUpstream);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn null_semantics_match_upstream() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    let mut upstream = mysql_async::Conn::from_url(MySQLAdapter::url_with_db("noria"))
+        .await
+        .unwrap();
+
+    conn.query_drop("CREATE TABLE t (id int PRIMARY KEY, x int)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (id, x) VALUES (1, 2), (2, NULL), (3, 1), (4, NULL), (5, 3)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let queries = [
+        "SELECT x FROM t ORDER BY x ASC",
+        "SELECT x FROM t ORDER BY x DESC",
+        "SELECT x FROM t ORDER BY x ASC LIMIT 2",
+        "SELECT x FROM t ORDER BY x DESC LIMIT 2",
+        "SELECT x, count(*) FROM t GROUP BY x ORDER BY x",
+        "SELECT id FROM t WHERE x IN (1, NULL) ORDER BY id",
+        "SELECT id FROM t WHERE x NOT IN (1, NULL) ORDER BY id",
+        "SELECT id FROM t WHERE x IS NULL OR x > 1 ORDER BY id",
+        "SELECT id FROM t WHERE x > 1 AND x IS NOT NULL ORDER BY id",
+    ];
+
+    let rows = |rows: Vec<mysql_async::Row>| {
+        rows.into_iter()
+            .map(|row| {
+                row.unwrap()
+                    .into_iter()
+                    .map(mysql_async::from_value::<Option<i64>>)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for query in queries {
+        // Run each query twice, so that the second run is served from the cache if ReadySet
+        // supports it
+        let _: Vec<mysql_async::Row> = conn.query(query).await.unwrap();
+        let actual = rows(conn.query(query).await.unwrap());
+        let expected = rows(upstream.query(query).await.unwrap());
+        assert_eq!(actual, expected, "{}", query);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn temporary_tables_are_proxied() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+
+    conn.query_drop("CREATE TABLE t (id int PRIMARY KEY)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (id) VALUES (1), (2)")
+        .await
+        .unwrap();
+    conn.query_drop("CREATE TEMPORARY TABLE tmp (id int)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO tmp (id) VALUES (2)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let rows: Vec<(i32,)> = conn
+        .query("SELECT t.id FROM t JOIN tmp ON t.id = tmp.id")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(2,)]);
+    let info = last_query_info(&mut conn).await;
+    assert_eq!(info.destination, QueryDestination::Upstream);
+
+    // Queries that don't refer to the temporary table are still served by ReadySet
+    conn.query_drop("DROP TEMPORARY TABLE tmp").await.unwrap();
+    let _: Vec<(i32,)> = conn.query("SELECT id FROM t").await.unwrap();
+    let rows: Vec<(i32,)> = conn.query("SELECT id FROM t").await.unwrap();
+    assert_eq!(rows.len(), 2);
+    let info = last_query_info(&mut conn).await;
+    assert_eq!(info.destination, QueryDestination::Readyset);
+}
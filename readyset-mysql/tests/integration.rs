@@ -1579,6 +1579,62 @@ async fn create_query_cache_where_in() {
     assert_eq!(new_queries.len(), queries.len());
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn alter_cache_prefill() {
+    let (opts, _handle) = setup(true).await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (id INT, name TEXT);")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (id, name) VALUES (1, 'a'), (2, 'b')")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("CREATE CACHE test FROM SELECT name FROM t WHERE id = ?;")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let prefilled: (String, String) = conn
+        .query_first("ALTER CACHE test PREFILL WHERE KEY IN (1, 2, 3);")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(prefilled, ("3".to_owned(), "2".to_owned()));
+
+    // Keys have to have a value for every key column of the cache
+    assert!(conn
+        .query_drop("ALTER CACHE test PREFILL WHERE KEY IN ((1, 'a'));")
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn readyset_flush_wait() {
+    let (opts, _handle) = setup(true).await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (id INT, name TEXT);")
+        .await
+        .unwrap();
+    conn.query_drop("CREATE CACHE test FROM SELECT id FROM t;")
+        .await
+        .unwrap();
+    conn.query_drop("READYSET FLUSH WAIT;").await.unwrap();
+
+    for id in 1..=10 {
+        conn.query_drop(format!("INSERT INTO t (id, name) VALUES ({}, 'a')", id))
+            .await
+            .unwrap();
+    }
+    // Once the flush returns, every write has propagated to the cache
+    conn.query_drop("READYSET FLUSH WAIT;").await.unwrap();
+
+    let mut ids: Vec<i32> = conn.query("SELECT id FROM t").await.unwrap();
+    ids.sort_unstable();
+    assert_eq!(ids, (1..=10).collect::<Vec<_>>());
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn show_readyset_status() {
     let (opts, _handle) = setup(true).await;
@@ -1616,3 +1672,78 @@ async fn simple_nonblocking_select() {
     rows.sort_by_key(|(a, _)| *a);
     assert_eq!(rows, vec![(4, 2)]);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn enum_ordering_and_filters() {
+    let (opts, _handle) = setup(true).await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE sizes (id INT, size ENUM('small', 'medium', 'large'))")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop(
+        "INSERT INTO sizes (id, size) VALUES (1, 'large'), (2, 'small'), (3, 'medium'), (4, 'small')",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    // ENUM values sort in the order their variants are defined, not alphabetically
+    let rows: Vec<(i32, String)> = conn
+        .query("SELECT id, size FROM sizes ORDER BY size, id")
+        .await
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            (2, "small".to_owned()),
+            (4, "small".to_owned()),
+            (3, "medium".to_owned()),
+            (1, "large".to_owned()),
+        ]
+    );
+
+    let ids: Vec<i32> = conn
+        .exec(
+            "SELECT id FROM sizes WHERE size = ? ORDER BY id",
+            ("small",),
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![2, 4]);
+
+    let ids: Vec<i32> = conn
+        .query("SELECT id FROM sizes WHERE size = 'Medium'")
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![3]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn session_information_functions() {
+    let (opts, _handle) = setup(true).await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE users (id INT PRIMARY KEY AUTO_INCREMENT, name TEXT)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO users (name) VALUES ('a'), ('b')")
+        .await
+        .unwrap();
+    let row: Option<(u64, i64)> = conn
+        .query_first("SELECT LAST_INSERT_ID(), ROW_COUNT()")
+        .await
+        .unwrap();
+    assert_eq!(row, Some((1, 2)));
+
+    sleep().await;
+    let names: Vec<String> = conn.query("SELECT name FROM users").await.unwrap();
+    assert_eq!(names.len(), 2);
+    let row: Option<(u64, u64, i64)> = conn
+        .query_first("SELECT last_insert_id() AS id, FOUND_ROWS(), ROW_COUNT()")
+        .await
+        .unwrap();
+    assert_eq!(row, Some((1, 2, -1)));
+}
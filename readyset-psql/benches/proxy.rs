@@ -18,6 +18,7 @@ use std::net::SocketAddr;
 use async_trait::async_trait;
 use criterion::{criterion_group, criterion_main, Criterion};
 use futures::future::{try_select, Either};
+use futures::stream;
 use readyset_psql::Value;
 use psql_srv::QueryResponse;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -80,7 +81,7 @@ impl psql_srv::Backend for Backend {
 
     type Value = Value;
     type Row = Vec<Self::Value>;
-    type Resultset = Vec<Self::Row>;
+    type Resultset = stream::Iter<std::vec::IntoIter<Result<Self::Row, psql_srv::Error>>>;
 
     async fn on_init(&mut self, _database: &str) -> Result<(), psql_srv::Error> {
         Ok(())
@@ -109,17 +110,18 @@ impl psql_srv::Backend for Backend {
                         .collect()
                 })
                 .unwrap_or_default(),
-            resultset: res
-                .into_iter()
-                .map(|r| {
-                    (0..r.len())
-                        .map(|i| Value {
-                            col_type: r.columns()[i].type_().clone(),
-                            value: r.get(i),
-                        })
-                        .collect()
-                })
-                .collect(),
+            resultset: stream::iter(
+                res.into_iter()
+                    .map(|r| {
+                        Ok((0..r.len())
+                            .map(|i| Value {
+                                col_type: r.columns()[i].type_().clone(),
+                                value: r.get(i),
+                            })
+                            .collect())
+                    })
+                    .collect::<Vec<_>>(),
+            ),
         })
     }
 
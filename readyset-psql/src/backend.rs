@@ -3,13 +3,18 @@ use std::ops::Deref;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono_tz::Tz;
 use eui48::MacAddressFormat;
-use readyset_client::backend as cl;
-use readyset_data::DataType;
+use nom_sql::{
+    Dialect, PostgresParameterScope, SetPostgresParameter, SetPostgresParameterValue, SetStatement,
+    SqlQuery,
+};
 use psql_srv as ps;
+use readyset_client::{backend as cl, PooledUpstream};
+use readyset_data::DataType;
 
 use crate::error::Error;
-use crate::query_handler::PostgreSqlQueryHandler;
+use crate::query_handler::{time_zone, PostgreSqlQueryHandler};
 use crate::response::{PrepareResponse, QueryResponse};
 use crate::resultset::Resultset;
 use crate::row::Row;
@@ -20,27 +25,92 @@ use crate::PostgreSqlUpstream;
 /// requests provided to `psql_srv::Backend` trait function implementations are forwared to the
 /// wrapped ReadySet client's `Backend`. All request parameters and response results are forwarded
 /// using type conversion.
-pub struct Backend(pub cl::Backend<PostgreSqlUpstream, PostgreSqlQueryHandler>);
+pub struct Backend {
+    inner: cl::Backend<PooledUpstream<PostgreSqlUpstream>, PostgreSqlQueryHandler>,
+
+    /// The value of the `TimeZone` session variable, as set by the client. `TIMESTAMPTZ` values in
+    /// results are rendered in this time zone, or left in UTC if it hasn't been set.
+    time_zone: Option<Tz>,
+}
 
 impl Deref for Backend {
-    type Target = cl::Backend<PostgreSqlUpstream, PostgreSqlQueryHandler>;
+    type Target = cl::Backend<PooledUpstream<PostgreSqlUpstream>, PostgreSqlQueryHandler>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 
 impl Backend {
+    pub fn new(
+        inner: cl::Backend<PooledUpstream<PostgreSqlUpstream>, PostgreSqlQueryHandler>,
+    ) -> Self {
+        Backend {
+            inner,
+            time_zone: None,
+        }
+    }
+
     async fn query(&mut self, query: &str) -> Result<QueryResponse<'_>, Error> {
-        Ok(QueryResponse(self.0.query(query).await?))
+        Ok(QueryResponse(self.inner.query(query).await?))
     }
 
     async fn prepare(&mut self, query: &str) -> Result<PrepareResponse<'_>, Error> {
-        Ok(PrepareResponse(self.0.prepare(query).await?))
+        Ok(PrepareResponse(self.inner.prepare(query).await?))
     }
 
     async fn execute(&mut self, id: u32, params: &[DataType]) -> Result<QueryResponse<'_>, Error> {
-        Ok(QueryResponse(self.0.execute(id, params).await?))
+        Ok(QueryResponse(self.inner.execute(id, params).await?))
+    }
+
+    /// If `query` (which has just been run successfully) sets the `TimeZone` session variable,
+    /// record the time zone it was set to
+    fn record_time_zone(&mut self, query: &str) {
+        // Avoid parsing every query just to find the (rare) SET statements
+        let is_set = query
+            .trim_start()
+            .get(..3)
+            .map_or(false, |kw| kw.eq_ignore_ascii_case("set"));
+        if !is_set {
+            return;
+        }
+
+        if let Ok(SqlQuery::Set(SetStatement::PostgresParameter(SetPostgresParameter {
+            scope: None | Some(PostgresParameterScope::Session),
+            name,
+            value,
+        }))) = nom_sql::parse_query(Dialect::PostgreSQL, query)
+        {
+            if name.eq_ignore_ascii_case("timezone") {
+                self.time_zone = match value {
+                    SetPostgresParameterValue::Default => None,
+                    SetPostgresParameterValue::Value(val) => time_zone(&val),
+                };
+            }
+        }
+    }
+
+    /// Render the `TIMESTAMPTZ` values in the rows of `response` in the session's time zone
+    fn with_time_zone(
+        &self,
+        response: ps::QueryResponse<Resultset>,
+    ) -> ps::QueryResponse<Resultset> {
+        match response {
+            ps::QueryResponse::Select { schema, resultset } => ps::QueryResponse::Select {
+                schema,
+                resultset: resultset.with_time_zone(self.time_zone),
+            },
+            ps::QueryResponse::Returning {
+                kind,
+                schema,
+                resultset,
+            } => ps::QueryResponse::Returning {
+                kind,
+                schema,
+                resultset: resultset.with_time_zone(self.time_zone),
+            },
+            response => response,
+        }
     }
 }
 
@@ -57,7 +127,9 @@ impl ps::Backend for Backend {
     }
 
     async fn on_query(&mut self, query: &str) -> Result<ps::QueryResponse<Resultset>, ps::Error> {
-        self.query(query).await?.try_into()
+        let response: ps::QueryResponse<Resultset> = self.query(query).await?.try_into()?;
+        self.record_time_zone(query);
+        Ok(self.with_time_zone(response))
     }
 
     async fn on_prepare(&mut self, query: &str) -> Result<ps::PrepareResponse, ps::Error> {
@@ -74,12 +146,22 @@ impl ps::Backend for Backend {
             .iter()
             .map(|p| ParamRef(p).try_into())
             .collect::<Result<Vec<DataType>, ps::Error>>()?;
-        self.execute(statement_id, &params).await?.try_into()
+        let response: ps::QueryResponse<Resultset> =
+            self.execute(statement_id, &params).await?.try_into()?;
+        Ok(self.with_time_zone(response))
     }
 
     async fn on_close(&mut self, _statement_id: u32) -> Result<(), ps::Error> {
         Ok(())
     }
+
+    fn password_for_username(&self, username: &str) -> Option<Vec<u8>> {
+        self.users.get(username).cloned().map(String::into_bytes)
+    }
+
+    fn require_authentication(&self) -> bool {
+        self.require_authentication
+    }
 }
 
 /// A simple wrapper around a request parameter `psql_srv::Value` reference, facilitiating
@@ -118,6 +200,10 @@ impl TryFrom<ParamRef<'_>> for DataType {
             ps::Value::Uuid(uuid) => Ok(DataType::from(uuid.to_string())),
             ps::Value::Json(v) | ps::Value::Jsonb(v) => Ok(DataType::from(v.to_string())),
             ps::Value::Bit(bits) | ps::Value::VarBit(bits) => Ok(DataType::from(bits.clone())),
+            ps::Value::Array(_, elem_type) => Err(ps::Error::Unsupported(format!(
+                "array parameters (of type {}[])",
+                elem_type
+            ))),
         }
     }
 }
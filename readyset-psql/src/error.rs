@@ -1,8 +1,9 @@
 use std::io;
 
+use database_utils::error::DatabaseURLParseError;
+use psql_srv as ps;
 use readyset::ReadySetError;
 use readyset_client::upstream_database::IsFatalError;
-use psql_srv as ps;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,6 +16,9 @@ pub enum Error {
 
     #[error(transparent)]
     Io(#[from] io::Error),
+
+    #[error(transparent)]
+    DatabaseUrl(#[from] DatabaseURLParseError),
 }
 
 impl From<Error> for ps::Error {
@@ -29,6 +33,7 @@ impl From<Error> for ps::Error {
             ReadySet(ReadySetError::Unsupported(s)) => ps::Error::Unsupported(s),
             ReadySet(e) => ps::Error::Unknown(e.to_string()),
             PostgreSql(e) => e.into(),
+            DatabaseUrl(e) => ps::Error::Unknown(e.to_string()),
         }
     }
 }
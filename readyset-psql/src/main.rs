@@ -3,10 +3,11 @@ use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use async_trait::async_trait;
 use clap::Parser;
 use nom_sql::Dialect;
-use psql_srv::run_backend;
-use readyset_client::backend as cl;
-use readyset_client_adapter::{ConnectionHandler, DatabaseType, NoriaAdapter};
+use psql_srv::{run_backend, run_backend_with_tls};
+use readyset_client::{backend as cl, PooledUpstream};
+use readyset_client_adapter::{ConnectionHandler, ConnectionTls, DatabaseType, NoriaAdapter};
 use readyset_psql::{Backend, PostgreSqlQueryHandler, PostgreSqlUpstream};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net;
 use tracing::{error, instrument};
 
@@ -26,13 +27,29 @@ impl ConnectionHandler for PsqlHandler {
     async fn process_connection(
         &mut self,
         stream: net::TcpStream,
-        backend: cl::Backend<PostgreSqlUpstream, PostgreSqlQueryHandler>,
+        backend: cl::Backend<PooledUpstream<PostgreSqlUpstream>, PostgreSqlQueryHandler>,
+        tls: Option<ConnectionTls>,
     ) {
-        let backend = Backend(backend);
-        run_backend(backend, stream).await;
+        let backend = Backend::new(backend);
+        match tls {
+            Some(tls) => run_backend_with_tls(backend, stream, &tls.acceptor, tls.required).await,
+            None => run_backend(backend, stream).await,
+        }
+    }
+
+    #[instrument(level = "debug", "connection", skip_all)]
+    async fn process_unix_connection(
+        &mut self,
+        stream: net::UnixStream,
+        backend: cl::Backend<PooledUpstream<PostgreSqlUpstream>, PostgreSqlQueryHandler>,
+    ) {
+        run_backend(Backend::new(backend), stream).await;
     }
 
-    async fn immediate_error(self, stream: net::TcpStream, error_message: String) {
+    async fn immediate_error<S>(self, stream: S, error_message: String)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         if let Err(error) = psql_srv::send_immediate_err::<Backend, _>(
             stream,
             psql_srv::Error::InternalError(error_message),
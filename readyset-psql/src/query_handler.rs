@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use chrono_tz::Tz;
 use lazy_static::lazy_static;
 use nom_sql::{
     Literal, PostgresParameterValue, PostgresParameterValueInner, SetNames, SetPostgresParameter,
@@ -47,6 +48,24 @@ impl AllowedParameterValue {
     }
 }
 
+/// Returns the time zone named by the value of a `SET timezone` statement, if it's the name of a
+/// time zone we know about
+pub(crate) fn time_zone(val: &PostgresParameterValue) -> Option<Tz> {
+    match val {
+        PostgresParameterValue::Single(PostgresParameterValueInner::Literal(Literal::String(
+            s,
+        ))) => s.parse().ok(),
+        PostgresParameterValue::Single(PostgresParameterValueInner::Identifier(ident)) => {
+            ident.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn is_known_time_zone(val: &PostgresParameterValue) -> bool {
+    time_zone(val).is_some()
+}
+
 fn search_path_starts_with_public(val: &PostgresParameterValue) -> bool {
     let is_public = |val: &PostgresParameterValueInner| match val {
         PostgresParameterValueInner::Literal(Literal::String(s)) => s == "public",
@@ -333,10 +352,10 @@ lazy_static! {
                 PostgresParameterValue::literal("utf-8"),
                 PostgresParameterValue::literal("UTF8"),
             ])),
-            ("timezone", AllowedParameterValue::literal("UTC")),
+            ("timezone", AllowedParameterValue::Predicate(is_known_time_zone)),
             ("datestyle", AllowedParameterValue::literal("ISO")),
             ("extra_float_digits", AllowedParameterValue::literal(1)),
-            ("TimeZone",  AllowedParameterValue::literal("Etc/UTC")),
+            ("TimeZone",  AllowedParameterValue::Predicate(is_known_time_zone)),
             ("bytea_output",  AllowedParameterValue::literal("hex")),
             ("search_path", AllowedParameterValue::Predicate(search_path_starts_with_public)),
             ("transform_null_equals", AllowedParameterValue::literal(false)),
@@ -439,4 +458,17 @@ mod tests {
     fn client_encoding_utf8_allowed() {
         is_allowed("SET client_encoding = 'UTF8'");
     }
+
+    #[test]
+    fn known_time_zones_allowed() {
+        is_allowed("SET timezone = 'UTC'");
+        is_allowed("SET TimeZone = 'America/New_York'");
+        is_allowed("SET timezone = DEFAULT");
+    }
+
+    #[test]
+    fn unknown_time_zones_forbidden() {
+        is_forbidden("SET timezone = 'Not/A_Zone'");
+        is_forbidden("SET timezone = 5");
+    }
 }
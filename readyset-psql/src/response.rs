@@ -8,6 +8,7 @@ use psql_srv::Column;
 use readyset::results::Results;
 use readyset::ColumnSchema;
 use readyset_client::backend::{self as cl, noria_connector, SinglePrepareResult, UpstreamPrepare};
+use readyset_client::PooledUpstream;
 use upstream::StatementMeta;
 
 use crate::resultset::Resultset;
@@ -16,7 +17,7 @@ use crate::{upstream, PostgreSqlUpstream};
 
 /// A simple wrapper around ReadySet client's `PrepareResult`, facilitating conversion to
 /// `psql_srv::PrepareResponse`.
-pub struct PrepareResponse<'a>(pub &'a cl::PrepareResult<PostgreSqlUpstream>);
+pub struct PrepareResponse<'a>(pub &'a cl::PrepareResult<PooledUpstream<PostgreSqlUpstream>>);
 
 impl<'a> PrepareResponse<'a> {
     pub fn try_into_ps(self, prepared_statement_id: u32) -> Result<ps::PrepareResponse, ps::Error> {
@@ -54,7 +55,7 @@ impl<'a> PrepareResponse<'a> {
 
 /// A simple wrapper around ReadySet client's `QueryResult`, facilitating conversion to
 /// `psql_srv::QueryResponse`.
-pub struct QueryResponse<'a>(pub cl::QueryResult<'a, PostgreSqlUpstream>);
+pub struct QueryResponse<'a>(pub cl::QueryResult<'a, PooledUpstream<PostgreSqlUpstream>>);
 
 impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
     type Error = ps::Error;
@@ -80,6 +81,18 @@ impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
                     resultset,
                 })
             }
+            Noria(NoriaResult::StreamingSelect {
+                data,
+                rest,
+                select_schema,
+            }) => {
+                let select_schema = SelectSchema(select_schema);
+                let resultset = Resultset::try_new(data, &select_schema)?.with_rest(rest);
+                Ok(Select {
+                    schema: select_schema.try_into()?,
+                    resultset,
+                })
+            }
             Noria(NoriaResult::Update {
                 num_rows_updated, ..
             }) => Ok(Update(num_rows_updated)),
@@ -165,20 +178,14 @@ impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
                     resultset,
                 })
             }
-            Upstream(upstream::QueryResult::Read { data: rows }) => {
-                let schema = match rows.first() {
-                    None => vec![],
-                    Some(row) => row
-                        .columns()
-                        .iter()
-                        .map(|c| Column {
-                            name: c.name().to_owned(),
-                            col_type: c.type_().clone(),
-                        })
-                        .collect(),
-                };
-                Ok(ps::QueryResponse::Select {
-                    schema,
+            Upstream(upstream::QueryResult::Read { data: rows }) => Ok(ps::QueryResponse::Select {
+                schema: upstream_schema(&rows),
+                resultset: Resultset::try_from(rows)?,
+            }),
+            Upstream(upstream::QueryResult::WriteReturning { kind, data: rows }) => {
+                Ok(ps::QueryResponse::Returning {
+                    kind,
+                    schema: upstream_schema(&rows),
                     resultset: Resultset::try_from(rows)?,
                 })
             }
@@ -189,3 +196,18 @@ impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
         }
     }
 }
+
+/// Returns the schema of rows returned by the upstream database
+fn upstream_schema(rows: &[tokio_postgres::Row]) -> Vec<Column> {
+    match rows.first() {
+        None => vec![],
+        Some(row) => row
+            .columns()
+            .iter()
+            .map(|c| Column {
+                name: c.name().to_owned(),
+                col_type: c.type_().clone(),
+            })
+            .collect(),
+    }
+}
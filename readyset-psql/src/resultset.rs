@@ -1,22 +1,31 @@
 use std::convert::TryFrom;
-use std::iter;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 
+use chrono_tz::Tz;
+use futures::{ready, Stream, StreamExt};
 use nom_sql::SqlIdentifier;
 use readyset::results::Results;
+use readyset_client::backend::noria_connector::ResultStream;
 use readyset_data::DataType;
 use psql_srv as ps;
 use tokio_postgres::types::Type;
 
+use crate::error::Error;
 use crate::row::Row;
 use crate::schema::{type_to_pgsql, SelectSchema};
 
 /// A structure that contains a `Vec<Results>`, as provided by `QueryResult::ReadySetSelect`, and
-/// facilitates iteration over these results as `Row` values.
+/// facilitates streaming these results as `Row` values.
 pub struct Resultset {
     /// The query result data, comprising nested `Vec`s of rows that may come from separate ReadySet
     /// interface lookups performed by the backend.
-    results: Vec<Results>,
+    results: std::iter::Flatten<std::vec::IntoIter<Results>>,
+
+    /// If the results are being read from ReadySet a page at a time, the pages of results that
+    /// have yet to be read, which are only read once all the rows in `results` have been streamed.
+    rest: Option<ResultStream>,
 
     /// The fields to project for each row. A `Results` returned by a ReadySet interface lookup may
     /// contain extraneous fields that should not be projected into the query result output. In
@@ -28,6 +37,9 @@ pub struct Resultset {
 
     /// The data types of the projected fields for each row.
     project_field_types: Arc<Vec<Type>>,
+
+    /// The time zone to render `TIMESTAMPTZ` values in, if not UTC.
+    time_zone: Option<Tz>,
 }
 
 impl Resultset {
@@ -62,38 +74,59 @@ impl Resultset {
                 .collect::<Result<Vec<_>, _>>()?,
         );
         Ok(Resultset {
-            results,
+            results: results.into_iter().flatten(),
+            rest: None,
             project_fields,
             project_field_types,
+            time_zone: None,
         })
     }
+
+    /// Stream the rows of the given remaining pages of results after the rows of this resultset
+    pub fn with_rest(self, rest: ResultStream) -> Self {
+        Resultset {
+            rest: Some(rest),
+            ..self
+        }
+    }
+
+    /// Render the `TIMESTAMPTZ` values in this resultset in the given time zone, rather than UTC
+    pub fn with_time_zone(self, time_zone: Option<Tz>) -> Self {
+        Resultset { time_zone, ..self }
+    }
 }
 
-// An iterator over the rows contained within the `Resultset`.
-impl IntoIterator for Resultset {
-    type Item = Row;
-    #[allow(clippy::type_complexity)]
-    type IntoIter = std::iter::Map<
-        std::iter::Zip<
-            std::iter::Flatten<std::vec::IntoIter<Results>>,
-            std::iter::Repeat<(Arc<Vec<usize>>, Arc<Vec<Type>>)>,
-        >,
-        fn((readyset::results::Row, (Arc<Vec<usize>>, Arc<Vec<Type>>))) -> Row,
-    >;
+// A stream of the rows contained within the `Resultset`.
+impl Stream for Resultset {
+    type Item = Result<Row, ps::Error>;
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.results
-            .into_iter()
-            .flatten()
-            .zip(iter::repeat((
-                self.project_fields,
-                self.project_field_types,
-            )))
-            .map(|(values, (project_fields, project_field_types))| Row {
-                values: values.into(),
-                project_fields,
-                project_field_types,
-            })
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(values) = self.results.next() {
+                return Poll::Ready(Some(Ok(Row {
+                    values: values.into(),
+                    project_fields: Arc::clone(&self.project_fields),
+                    project_field_types: Arc::clone(&self.project_field_types),
+                    time_zone: self.time_zone,
+                })));
+            }
+
+            let rest = match &mut self.rest {
+                Some(rest) => rest,
+                None => return Poll::Ready(None),
+            };
+            match ready!(rest.poll_next_unpin(cx)) {
+                Some(Ok(page)) => self.results = page.into_iter().flatten(),
+                Some(Err(e)) => {
+                    self.rest = None;
+                    return Poll::Ready(Some(Err(Error::from(e).into())));
+                }
+                None => {
+                    self.rest = None;
+                    return Poll::Ready(None);
+                }
+            }
+        }
     }
 }
 
@@ -123,9 +156,13 @@ impl TryFrom<Vec<tokio_postgres::Row>> for Resultset {
         let column_names: Vec<SqlIdentifier> = columns.iter().map(|c| c.name().into()).collect();
         let column_types: Vec<Type> = columns.iter().map(|c| c.type_().clone()).collect();
         Ok(Resultset {
-            results: vec![Results::new(result_rows, Arc::from(&column_names[..]))],
+            results: vec![Results::new(result_rows, Arc::from(&column_names[..]))]
+                .into_iter()
+                .flatten(),
+            rest: None,
             project_fields: Arc::new((0_usize..columns.len()).collect()),
             project_field_types: Arc::new(column_types),
+            time_zone: None,
         })
     }
 }
@@ -143,14 +180,16 @@ mod tests {
     use super::*;
 
     fn collect_resultset_values(resultset: Resultset) -> Vec<Vec<ps::Value>> {
-        resultset
-            .into_iter()
-            .map(|r| {
-                r.into_iter()
-                    .map(|v| ps::Value::try_from(v).unwrap())
-                    .collect::<Vec<ps::Value>>()
-            })
-            .collect::<Vec<Vec<ps::Value>>>()
+        futures::executor::block_on(
+            resultset
+                .map(|r| {
+                    r.unwrap()
+                        .into_iter()
+                        .map(|v| ps::Value::try_from(v).unwrap())
+                        .collect::<Vec<ps::Value>>()
+                })
+                .collect::<Vec<Vec<ps::Value>>>(),
+        )
     }
 
     #[test]
@@ -169,8 +208,8 @@ mod tests {
             }]),
             columns: Cow::Owned(vec!["col1".into()]),
         });
-        let resultset = Resultset::try_new(results, &schema).unwrap();
-        assert_eq!(resultset.results, Vec::<Results>::new());
+        let mut resultset = Resultset::try_new(results, &schema).unwrap();
+        assert!(resultset.results.next().is_none());
         assert_eq!(resultset.project_fields, Arc::new(vec![0]));
         assert_eq!(resultset.project_field_types, Arc::new(vec![Type::INT8]));
     }
@@ -267,8 +306,8 @@ mod tests {
                 "bogokey".into(),
             ]),
         });
-        let resultset = Resultset::try_new(results, &schema).unwrap();
-        assert_eq!(resultset.results, Vec::<Results>::new());
+        let mut resultset = Resultset::try_new(results, &schema).unwrap();
+        assert!(resultset.results.next().is_none());
         // The projected field indices of "col1" and "col2" within `columns` are 0 and 2. The
         // unprojected "col3" and "bogokey" fields are excluded.
         assert_eq!(resultset.project_fields, Arc::new(vec![0, 2]));
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use chrono::Offset;
+use chrono_tz::Tz;
 use readyset_data::DataType;
 use tokio_postgres::types::Type;
 
@@ -23,6 +25,9 @@ pub struct Row {
 
     /// The data types of the projected fields for this row.
     pub project_field_types: Arc<Vec<Type>>,
+
+    /// The time zone to render `TIMESTAMPTZ` values in, if not UTC.
+    pub time_zone: Option<Tz>,
 }
 
 impl IntoIterator for Row {
@@ -50,7 +55,13 @@ impl Iterator for RowIterator {
     fn next(&mut self) -> Option<Value> {
         let col_type = self.row.project_field_types.get(self.pos)?.clone();
         let i = *self.row.project_fields.get(self.pos)?;
-        let value = self.row.values.get(i)?.clone();
+        let value = match (self.row.values.get(i)?, self.row.time_zone) {
+            (DataType::TimestampTz(ts), Some(tz)) if col_type == Type::TIMESTAMPTZ => {
+                let ts = ts.to_chrono().with_timezone(&tz);
+                DataType::TimestampTz(ts.with_timezone(&ts.offset().fix()).into())
+            }
+            (value, _) => value.clone(),
+        };
         self.pos += 1;
         Some(Value { col_type, value })
     }
@@ -60,6 +71,7 @@ impl Iterator for RowIterator {
 mod tests {
     use std::convert::TryFrom;
 
+    use chrono::{DateTime, FixedOffset};
     use psql_srv as ps;
     use rust_decimal::Decimal;
 
@@ -77,6 +89,7 @@ mod tests {
             values: vec![],
             project_fields: Arc::new(vec![]),
             project_field_types: Arc::new(vec![]),
+            time_zone: None,
         };
         assert_eq!(collect_row_values(row), Vec::<ps::Value>::new());
     }
@@ -87,6 +100,7 @@ mod tests {
             values: vec![DataType::Int(43)],
             project_fields: Arc::new(vec![0]),
             project_field_types: Arc::new(vec![Type::INT4]),
+            time_zone: None,
         };
         assert_eq!(collect_row_values(row), vec![ps::Value::Int(43)]);
     }
@@ -109,6 +123,7 @@ mod tests {
                 Type::FLOAT4,
                 Type::NUMERIC,
             ]),
+            time_zone: None,
         };
         assert_eq!(
             collect_row_values(row),
@@ -142,6 +157,7 @@ mod tests {
                 Type::FLOAT4,
                 Type::NUMERIC,
             ]),
+            time_zone: None,
         };
         assert_eq!(
             collect_row_values(row),
@@ -178,6 +194,7 @@ mod tests {
                 Type::FLOAT4,
                 Type::NUMERIC,
             ]),
+            time_zone: None,
         };
         assert_eq!(
             collect_row_values(row),
@@ -190,4 +207,22 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn iterate_row_with_time_zone() {
+        let ts = DateTime::<FixedOffset>::parse_from_rfc3339("2022-06-01T12:00:00+00:00").unwrap();
+        let row = Row {
+            values: vec![DataType::from(ts), DataType::from(ts)],
+            project_fields: Arc::new(vec![0, 1]),
+            project_field_types: Arc::new(vec![Type::TIMESTAMPTZ, Type::TIMESTAMP]),
+            time_zone: Some(chrono_tz::America::New_York),
+        };
+        let values = collect_row_values(row);
+        match &values[0] {
+            ps::Value::TimestampTz(v) => assert_eq!(v.to_rfc3339(), "2022-06-01T08:00:00-04:00"),
+            v => panic!("expected a TIMESTAMPTZ value, got {:?}", v),
+        }
+        // Only TIMESTAMPTZ columns are affected by the time zone
+        assert_eq!(values[1], ps::Value::Timestamp(ts.naive_local()));
+    }
 }
@@ -1,19 +1,19 @@
 use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::os::unix::ffi::OsStrExt;
-use std::str::FromStr;
 
 use async_trait::async_trait;
 use futures::TryStreamExt;
+use pgsql::config::Host;
+use pgsql::types::Type;
+use pgsql::{GenericResult, Row};
+use psql_srv::{Column, WriteKind};
+use readyset::replication::ReplicationOffset;
 use readyset::ColumnSchema;
 use readyset_client::upstream_database::NoriaCompare;
 use readyset_client::{UpstreamDatabase, UpstreamPrepare};
 use readyset_data::DataType;
 use readyset_errors::{unsupported, ReadySetError};
-use pgsql::config::Host;
-use pgsql::types::Type;
-use pgsql::{Config, GenericResult, Row};
-use psql_srv::Column;
 use tokio::process::Command;
 use tokio_postgres as pgsql;
 use tracing::{info, info_span};
@@ -28,8 +28,9 @@ pub struct PostgreSqlUpstream {
     client: pgsql::Client,
     /// A tokio task that handles the connection, required by `tokio_postgres` to operate
     _connection_handle: tokio::task::JoinHandle<Result<(), pgsql::Error>>,
-    /// Map from prepared statement IDs to prepared statements
-    prepared_statements: HashMap<u32, pgsql::Statement>,
+    /// Map from prepared statement IDs to prepared statements, along with the kind of write
+    /// performed by each statement, if any
+    prepared_statements: HashMap<u32, (pgsql::Statement, Option<WriteKind>)>,
     /// ID for the next prepared statement
     statement_id_counter: u32,
     /// The original URL used to create the connection
@@ -38,8 +39,17 @@ pub struct PostgreSqlUpstream {
 
 #[derive(Debug)]
 pub enum QueryResult {
-    Read { data: Vec<Row> },
-    Write { num_rows_affected: u64 },
+    Read {
+        data: Vec<Row>,
+    },
+    Write {
+        num_rows_affected: u64,
+    },
+    /// The rows returned by an `INSERT`, `UPDATE`, or `DELETE` statement with a `RETURNING` clause
+    WriteReturning {
+        kind: WriteKind,
+        data: Vec<Row>,
+    },
     Command,
 }
 
@@ -51,6 +61,52 @@ pub struct StatementMeta {
     pub schema: Vec<Column>,
 }
 
+/// Returns the kind of write performed by `query`, if it's an `INSERT`, `UPDATE`, or `DELETE`
+/// statement, so that rows returned by a `RETURNING` clause can be reported to the client with the
+/// right command tag.
+fn write_kind(query: &str) -> Option<WriteKind> {
+    let keyword = query
+        .trim_start()
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()?;
+    if keyword.eq_ignore_ascii_case("insert") {
+        Some(WriteKind::Insert)
+    } else if keyword.eq_ignore_ascii_case("update") {
+        Some(WriteKind::Update)
+    } else if keyword.eq_ignore_ascii_case("delete") {
+        Some(WriteKind::Delete)
+    } else {
+        None
+    }
+}
+
+/// Collect the results of a statement into a [`QueryResult`]
+fn query_result(
+    results: impl IntoIterator<Item = GenericResult>,
+    write_kind: Option<WriteKind>,
+) -> QueryResult {
+    let mut results = results.into_iter().peekable();
+
+    // If results starts with a command complete then return a write result.
+    // This could happen if a write returns no results, which is fine
+    //
+    // Otherwise return all the rows we get and ignore the command complete at the end
+    if let Some(GenericResult::NumRows(n)) = results.peek() {
+        QueryResult::Write {
+            num_rows_affected: *n,
+        }
+    } else {
+        let mut data = Vec::new();
+        while let Some(GenericResult::Row(r)) = results.next() {
+            data.push(r);
+        }
+        match write_kind {
+            Some(kind) => QueryResult::WriteReturning { kind, data },
+            None => QueryResult::Read { data },
+        }
+    }
+}
+
 // Returns if the schema plausibly matches the sets of columns.
 fn schema_column_match(schema: &[ColumnSchema], columns: &[Type]) -> Result<(), Error> {
     if schema.len() != columns.len() {
@@ -102,9 +158,10 @@ impl UpstreamDatabase for PostgreSqlUpstream {
     type Error = Error;
 
     async fn connect(url: String) -> Result<Self, Error> {
-        let config = Config::from_str(&url)?;
-        let connector = native_tls::TlsConnector::builder().build().unwrap(); // Never returns an error
-        let tls = postgres_native_tls::MakeTlsConnector::new(connector);
+        // tokio-postgres authenticates with SCRAM-SHA-256 if the server asks for it, and uses
+        // SCRAM-SHA-256-PLUS (binding the exchange to the TLS connection) when connected over TLS,
+        // unless `channel_binding=disable` is set in the URL
+        let (config, tls) = database_utils::tls::postgres_config(&url)?;
         let span = info_span!(
             "Connecting to PostgreSQL upstream",
             host = ?config.get_hosts(),
@@ -158,7 +215,8 @@ impl UpstreamDatabase for PostgreSqlUpstream {
 
         self.statement_id_counter += 1;
         let statement_id = self.statement_id_counter;
-        self.prepared_statements.insert(statement_id, statement);
+        self.prepared_statements
+            .insert(statement_id, (statement, write_kind(query)));
 
         Ok(UpstreamPrepare { statement_id, meta })
     }
@@ -167,24 +225,9 @@ impl UpstreamDatabase for PostgreSqlUpstream {
     where
         S: AsRef<str> + Send + Sync + 'a,
     {
-        let results = self.client.generic_query(query.as_ref(), &[]).await?;
-        let mut results = results.into_iter().peekable();
-
-        // If results starts with a command complete then return a write result.
-        // This could happen if a write returns no results, which is fine
-        //
-        // Otherwise return all the rows we get and ignore the command complete at the end
-        if let Some(GenericResult::NumRows(n)) = results.peek() {
-            Ok(QueryResult::Write {
-                num_rows_affected: *n,
-            })
-        } else {
-            let mut data = Vec::new();
-            while let Some(GenericResult::Row(r)) = results.next() {
-                data.push(r);
-            }
-            Ok(QueryResult::Read { data })
-        }
+        let query = query.as_ref();
+        let results = self.client.generic_query(query, &[]).await?;
+        Ok(query_result(results, write_kind(query)))
     }
 
     async fn handle_ryw_write<'a, S>(
@@ -202,7 +245,7 @@ impl UpstreamDatabase for PostgreSqlUpstream {
         statement_id: u32,
         params: &[DataType],
     ) -> Result<Self::QueryResult, Error> {
-        let statement = self
+        let (statement, write_kind) = self
             .prepared_statements
             .get(&statement_id)
             .ok_or(ReadySetError::PreparedStatementMissing { statement_id })?;
@@ -214,23 +257,7 @@ impl UpstreamDatabase for PostgreSqlUpstream {
             .try_collect()
             .await?;
 
-        let mut results = results.into_iter().peekable();
-
-        // If results starts with a command complete then return a write result.
-        // This could happen if a write returns no results, which is fine
-        //
-        // Otherwise return all the rows we get and ignore the command complete at the end
-        if let Some(GenericResult::NumRows(n)) = results.peek() {
-            Ok(QueryResult::Write {
-                num_rows_affected: *n,
-            })
-        } else {
-            let mut data = Vec::new();
-            while let Some(GenericResult::Row(r)) = results.next() {
-                data.push(r);
-            }
-            Ok(QueryResult::Read { data })
-        }
+        Ok(query_result(results, *write_kind))
     }
 
     /// Handle starting a transaction with the upstream database.
@@ -252,7 +279,7 @@ impl UpstreamDatabase for PostgreSqlUpstream {
     }
 
     async fn schema_dump(&mut self) -> Result<Vec<u8>, anyhow::Error> {
-        let config = Config::from_str(&self.url)?;
+        let (config, _) = database_utils::tls::postgres_config(&self.url)?;
         let mut pg_dump = Command::new("pg_dump");
         pg_dump.arg("--schema-only");
         if let Some(host) = config
@@ -279,6 +306,20 @@ impl UpstreamDatabase for PostgreSqlUpstream {
         }
         Ok(pg_dump.output().await?.stdout)
     }
+
+    /// Returns the current WAL write location, in the same form as the replication offsets
+    /// recorded by the replicator
+    async fn replication_offset(&mut self) -> Result<Option<ReplicationOffset>, Error> {
+        let lsn: i64 = self
+            .client
+            .query_one("SELECT (pg_current_wal_lsn() - '0/0')::int8", &[])
+            .await?
+            .try_get(0)?;
+        Ok(Some(ReplicationOffset {
+            offset: lsn as u128,
+            replication_log_name: String::new(),
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -375,4 +416,19 @@ mod tests {
 
         assert!(s.compare(&schema_spec, &param_specs).is_err());
     }
+
+    #[test]
+    fn write_kind_of_query() {
+        assert_eq!(
+            write_kind("INSERT INTO t VALUES (1) RETURNING *"),
+            Some(WriteKind::Insert)
+        );
+        assert_eq!(
+            write_kind("  update t SET x = 1 RETURNING x"),
+            Some(WriteKind::Update)
+        );
+        assert_eq!(write_kind("DELETE FROM t"), Some(WriteKind::Delete));
+        assert_eq!(write_kind("SELECT * FROM t"), None);
+        assert_eq!(write_kind(""), None);
+    }
 }
@@ -5,6 +5,7 @@ use eui48::MacAddress;
 use readyset_data::DataType;
 use ps::util::type_is_oid;
 use psql_srv as ps;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use tokio_postgres::types::Type;
 use tracing::{error, trace};
@@ -39,9 +40,9 @@ impl TryFrom<Value> for ps::Value {
             (Type::INT4, DataType::Int(v)) => Ok(ps::Value::Int(v as _)),
             (Type::INT8, DataType::Int(v)) => Ok(ps::Value::Bigint(v as _)),
 
-            (Type::INT2, DataType::UnsignedInt(v)) => Ok(ps::Value::Smallint(v as _)),
-            (Type::INT4, DataType::UnsignedInt(v)) => Ok(ps::Value::Int(v as _)),
-            (Type::INT8, DataType::UnsignedInt(v)) => Ok(ps::Value::Bigint(v as _)),
+            (Type::INT2, DataType::UnsignedInt(v)) => Ok(ps::Value::Smallint(v.try_into()?)),
+            (Type::INT4, DataType::UnsignedInt(v)) => Ok(ps::Value::Int(v.try_into()?)),
+            (Type::INT8, DataType::UnsignedInt(v)) => Ok(ps::Value::Bigint(v.try_into()?)),
 
             (ref ty, DataType::UnsignedInt(v)) if type_is_oid(ty) => {
                 Ok(ps::Value::Oid(v.try_into()?))
@@ -49,10 +50,22 @@ impl TryFrom<Value> for ps::Value {
             (ref ty, DataType::Int(v)) if type_is_oid(ty) => Ok(ps::Value::Oid(v.try_into()?)),
 
             (Type::FLOAT4, DataType::Float(f)) => Ok(ps::Value::Float(f)),
+            (Type::FLOAT4, DataType::Double(f)) => Ok(ps::Value::Float(f as f32)),
             (Type::FLOAT8, DataType::Double(f)) => Ok(ps::Value::Double(f)),
+            (Type::FLOAT8, DataType::Float(f)) => Ok(ps::Value::Double(f.into())),
+            (Type::FLOAT8, DataType::Numeric(ref d)) => {
+                Ok(ps::Value::Double(d.to_f64().ok_or_else(|| {
+                    ps::Error::InternalError(format!("{} out of range", d))
+                })?))
+            }
             (Type::NUMERIC, DataType::Double(f)) => Ok(ps::Value::Numeric(
                 <Decimal>::try_from(f).map_err(|e| ps::Error::InternalError(e.to_string()))?,
             )),
+            (Type::NUMERIC, DataType::Float(f)) => Ok(ps::Value::Numeric(
+                <Decimal>::try_from(f).map_err(|e| ps::Error::InternalError(e.to_string()))?,
+            )),
+            (Type::NUMERIC, DataType::Int(v)) => Ok(ps::Value::Numeric(v.into())),
+            (Type::NUMERIC, DataType::UnsignedInt(v)) => Ok(ps::Value::Numeric(v.into())),
             (Type::NUMERIC, DataType::Numeric(ref d)) => Ok(ps::Value::Numeric(*d.as_ref())),
             (Type::TEXT, DataType::Text(v)) => Ok(ps::Value::Text(v)),
             (Type::TEXT, DataType::TinyText(t)) => Ok(ps::Value::Text(t.as_str().into())),
@@ -158,4 +171,16 @@ mod tests {
             ps::Value::Text("aaaaaaaaaaaaaa".into())
         );
     }
+
+    #[test]
+    fn int_numeric() {
+        let val = Value {
+            col_type: Type::NUMERIC,
+            value: DataType::Int(42),
+        };
+        assert_eq!(
+            ps::Value::try_from(val).unwrap(),
+            ps::Value::Numeric(Decimal::from(42))
+        );
+    }
 }
@@ -1,13 +1,16 @@
 use chrono::NaiveDate;
 use readyset_client::backend::UnsupportedSetMode;
 use readyset_client::BackendBuilder;
-use readyset_client_test_helpers::psql_helpers::{setup_w_fallback, setup_w_fallback_with};
+use readyset_client_test_helpers::psql_helpers::{
+    setup_w_fallback, setup_w_fallback_with, upstream_config,
+};
 use readyset_client_test_helpers::sleep;
+use readyset_data::DataType;
 use serial_test::serial;
 
 mod common;
 use common::connect;
-use tokio_postgres::SimpleQueryMessage;
+use tokio_postgres::{NoTls, SimpleQueryMessage};
 
 #[tokio::test(flavor = "multi_thread")]
 #[serial]
@@ -91,7 +94,9 @@ async fn prepare_execute_fallback() {
 #[serial]
 async fn proxy_unsupported_sets() {
     let (config, _handle) = setup_w_fallback_with(
-        BackendBuilder::new().unsupported_set_mode(UnsupportedSetMode::Proxy),
+        BackendBuilder::new()
+            .require_authentication(false)
+            .unsupported_set_mode(UnsupportedSetMode::Proxy),
     )
     .await;
     let client = connect(config).await;
@@ -108,3 +113,107 @@ async fn proxy_unsupported_sets() {
         NaiveDate::from_ymd(2022, 3, 5)
     );
 }
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn null_semantics_match_upstream() {
+    let (config, _handle) = setup_w_fallback().await;
+    let client = connect(config).await;
+    let (upstream, upstream_conn) = upstream_config()
+        .dbname("noria")
+        .connect(NoTls)
+        .await
+        .unwrap();
+    tokio::spawn(upstream_conn);
+
+    client
+        .simple_query("CREATE TABLE t (id int PRIMARY KEY, x int)")
+        .await
+        .unwrap();
+    client
+        .simple_query("INSERT INTO t (id, x) VALUES (1, 2), (2, NULL), (3, 1), (4, NULL), (5, 3)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let queries = [
+        "SELECT x FROM t ORDER BY x ASC",
+        "SELECT x FROM t ORDER BY x DESC",
+        "SELECT x FROM t ORDER BY x ASC LIMIT 2",
+        "SELECT x FROM t ORDER BY x DESC LIMIT 2",
+        "SELECT x, count(*) FROM t GROUP BY x ORDER BY x",
+        "SELECT id FROM t WHERE x IN (1, NULL) ORDER BY id",
+        "SELECT id FROM t WHERE x NOT IN (1, NULL) ORDER BY id",
+        "SELECT id FROM t WHERE x IS NULL OR x > 1 ORDER BY id",
+        "SELECT id FROM t WHERE x > 1 AND x IS NOT NULL ORDER BY id",
+    ];
+
+    let rows = |rows: Vec<tokio_postgres::Row>| {
+        rows.iter()
+            .map(|row| {
+                (0..row.len())
+                    .map(|i| row.get::<_, DataType>(i))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for query in queries {
+        // Run each query twice, so that the second run is served from the cache if ReadySet
+        // supports it
+        client.query(query, &[]).await.unwrap();
+        let actual = rows(client.query(query, &[]).await.unwrap());
+        let expected = rows(upstream.query(query, &[]).await.unwrap());
+        assert_eq!(actual, expected, "{}", query);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn table_samples_are_proxied() {
+    let (config, _handle) = setup_w_fallback().await;
+    let client = connect(config).await;
+
+    client
+        .simple_query("CREATE TABLE t (id int PRIMARY KEY)")
+        .await
+        .unwrap();
+    client
+        .simple_query("INSERT INTO t (id) VALUES (1), (2), (3)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let query = "SELECT count(*) FROM t TABLESAMPLE BERNOULLI (100) REPEATABLE (1)";
+    for _ in 0..2 {
+        let count = client.query_one(query, &[]).await.unwrap().get::<_, i64>(0);
+        assert_eq!(count, 3);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn unsupported_function_calls_are_proxied() {
+    let (config, _handle) = setup_w_fallback().await;
+    let client = connect(config).await;
+
+    client
+        .simple_query("CREATE TABLE t (id int PRIMARY KEY, name text)")
+        .await
+        .unwrap();
+    client
+        .simple_query("INSERT INTO t (id, name) VALUES (1, 'a')")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let query = "SELECT xmlelement(name item, xmlattributes(id AS id), name)::text FROM t";
+    for _ in 0..2 {
+        let xml = client
+            .query_one(query, &[])
+            .await
+            .unwrap()
+            .get::<_, String>(0);
+        assert_eq!(xml, "<item id=\"1\">a</item>");
+    }
+}
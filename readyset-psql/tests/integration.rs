@@ -781,6 +781,34 @@ async fn prepared_select() {
     assert_eq!(row.get::<usize, i32>(1), 2);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn prepare_execute_deallocate() {
+    let (opts, _handle) = setup(true).await;
+    let conn = connect(opts).await;
+    conn.simple_query("CREATE TABLE test (x int, y int)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.simple_query("INSERT INTO test (x, y) VALUES (4, 2)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.simple_query("PREPARE q (int) AS SELECT test.y FROM test WHERE x = $1")
+        .await
+        .unwrap();
+    let res = conn.simple_query("EXECUTE q ('4')").await.unwrap();
+    let row = match res.first().unwrap() {
+        SimpleQueryMessage::Row(r) => r,
+        _ => panic!(),
+    };
+    assert_eq!(row.get(0).unwrap(), "2");
+
+    conn.simple_query("DEALLOCATE q").await.unwrap();
+    conn.simple_query("EXECUTE q (4)").await.unwrap_err();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn select_quoting_names() {
     let (opts, _handle) = setup(true).await;
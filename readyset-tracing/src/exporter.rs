@@ -0,0 +1,28 @@
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum TracingExporter {
+    /// Send spans to a Jaeger agent, using [`opentelemetry_jaeger`]
+    Jaeger,
+
+    /// Send spans to an OpenTelemetry collector using OTLP over gRPC, using
+    /// [`opentelemetry_otlp`]
+    Otlp,
+}
+
+/// Error type for the [`FromStr`] implementation for [`TracingExporter`]
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid tracing exporter '{0}', expected one of 'jaeger' or 'otlp'")]
+pub struct InvalidTracingExporter(String);
+
+impl FromStr for TracingExporter {
+    type Err = InvalidTracingExporter;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jaeger" => Ok(Self::Jaeger),
+            "otlp" => Ok(Self::Otlp),
+            _ => Err(InvalidTracingExporter(s.to_owned())),
+        }
+    }
+}
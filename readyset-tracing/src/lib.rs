@@ -123,8 +123,10 @@
 use std::str::FromStr;
 
 use clap::Parser;
-use opentelemetry::runtime;
-use opentelemetry::sdk::trace::Tracer;
+use opentelemetry::sdk::trace::{self, Tracer};
+use opentelemetry::sdk::Resource;
+use opentelemetry::{runtime, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
 pub use readyset_tracing_proc_macros::{instrument_child, instrument_remote, instrument_root};
 use tracing::{warn, Subscriber};
 use tracing_opentelemetry::OpenTelemetryLayer;
@@ -134,6 +136,8 @@ use tracing_subscriber::{fmt, EnvFilter, Layer};
 
 mod error;
 pub use error::Error;
+mod exporter;
+use exporter::TracingExporter;
 mod logformat;
 use logformat::LogFormat;
 mod percent;
@@ -178,10 +182,21 @@ pub struct Options {
     #[clap(long, env = "LOG_LEVEL", default_value = "info")]
     log_level: String,
 
-    /// Host and port to send traces/spans to
+    /// Host and port to send traces/spans to. For the `otlp` exporter, this is the URL of an
+    /// OpenTelemetry collector's gRPC endpoint, such as `http://localhost:4317`
     #[clap(long, env = "TRACING_HOST")]
     tracing_host: Option<String>,
 
+    /// Protocol to use when sending traces/spans to the tracing host
+    #[clap(
+        long,
+        env = "TRACING_EXPORTER",
+        parse(try_from_str),
+        default_value = "jaeger",
+        possible_values = &["jaeger", "otlp"]
+    )]
+    tracing_exporter: TracingExporter,
+
     /// Portion of traces that will be sent to the tracing endpoint; [0.0~100.0]
     #[clap(long, env = "TRACING_SAMPLE_PERCENT", default_value_t = Percent(0.01))]
     tracing_sample_percent: Percent,
@@ -193,6 +208,7 @@ impl Default for Options {
             log_format: LogFormat::Full,
             log_level: "info".to_owned(),
             tracing_host: None,
+            tracing_exporter: TracingExporter::Jaeger,
             tracing_sample_percent: Percent(0.01),
         }
     }
@@ -203,11 +219,24 @@ impl Options {
     where
         S: Subscriber + for<'span> LookupSpan<'span>,
     {
-        let tracer = opentelemetry_jaeger::new_pipeline()
-            .with_agent_endpoint(self.tracing_host.as_ref().unwrap())
-            .with_service_name(service_name)
-            .with_auto_split_batch(true)
-            .install_batch(runtime::Tokio)?;
+        let tracer = match self.tracing_exporter {
+            TracingExporter::Jaeger => opentelemetry_jaeger::new_pipeline()
+                .with_agent_endpoint(self.tracing_host.as_ref().unwrap())
+                .with_service_name(service_name)
+                .with_auto_split_batch(true)
+                .install_batch(runtime::Tokio)?,
+            TracingExporter::Otlp => opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(self.tracing_host.as_ref().unwrap()),
+                )
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", service_name.to_owned()),
+                ])))
+                .install_batch(runtime::Tokio)?,
+        };
 
         Ok(tracing_opentelemetry::layer().with_tracer(tracer))
     }
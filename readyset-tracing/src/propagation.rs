@@ -34,7 +34,12 @@ impl Extractor for RequestContext {
 impl RequestContext {
     #[inline]
     pub fn from_current_span() -> Option<Self> {
-        let span = Span::current();
+        Self::from_span(&Span::current())
+    }
+
+    /// Returns the context of `span`, or `None` if `span` is disabled
+    #[inline]
+    pub fn from_span(span: &Span) -> Option<Self> {
         if span.is_disabled() {
             None
         } else {
@@ -44,6 +49,40 @@ impl RequestContext {
             Some(ctx)
         }
     }
+
+    /// Builds a context from the headers of a request (such as an HTTP request) which was made
+    /// with the headers returned by [`headers`](Self::headers), using `get` to look up the value
+    /// of a header by name. Returns `None` if the request has none of the headers.
+    pub fn from_headers<'a, F>(get: F) -> Option<Self>
+    where
+        F: Fn(&str) -> Option<&'a str>,
+    {
+        let ctx = RequestContext {
+            inner: TraceContextPropagator::new()
+                .fields()
+                .filter_map(|field| Some((field.to_owned(), get(field)?.to_owned())))
+                .collect(),
+        };
+        if ctx.inner.is_empty() {
+            None
+        } else {
+            Some(ctx)
+        }
+    }
+
+    /// Returns the names and values of the headers to add to a request (such as an HTTP request)
+    /// to propagate this context
+    pub fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.inner.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Creates a span for handling a request which was made with this context, as a child of the
+    /// span the request was made in
+    #[inline]
+    pub fn child_span(&self, mut span: Span) -> Span {
+        self.set_spans_parent(&mut span);
+        span
+    }
     #[inline]
     pub fn set_spans_parent(&self, span: &mut Span) {
         let propagator = TraceContextPropagator::new();
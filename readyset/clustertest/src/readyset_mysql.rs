@@ -461,11 +461,13 @@ async fn dry_run_evaluates_support() {
         query_id.clone(),
         "SELECT * FROM `t1` WHERE (`uid` = $1)".to_string(),
         "pending".to_string(),
+        "".to_string(),
     )]);
     results.write(&[(
         query_id,
         "SELECT * FROM `t1` WHERE (`uid` = $1)".to_string(),
         "yes".to_string(),
+        "".to_string(),
     )]);
 
     // Verify that the query eventually reaches the "yes" state in the
@@ -0,0 +1,367 @@
+//! Collations, which determine how text values are compared to each other, and so how they're
+//! sorted in `ORDER BY` and which values are considered equal in filters, joins, and `GROUP BY`.
+//!
+//! Text values carry the collation of the column they were read from (see
+//! [`DataType::with_collation`](crate::DataType::with_collation)), and comparisons between two
+//! text values use the [resolved](Collation::resolve) collation of both. Values that don't come
+//! from a column with a known collation, such as literals in queries, use [`Collation::Binary`],
+//! which compares the UTF-8 bytes of the strings, so that comparisons against a column use the
+//! collation of the column.
+//!
+//! Only a handful of the collations supported by MySQL and PostgreSQL are implemented, and only
+//! approximately: the case-insensitive collations fold case and strip accents using the Unicode
+//! character database rather than the exact weight tables the upstream databases use, which gives
+//! the same results for the Latin, Greek, and Cyrillic scripts but may not for others.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::iter;
+
+use nom_sql::{ColumnConstraint, ColumnSpecification, CreateTableOption};
+use serde::{Deserialize, Serialize};
+use strum_macros::FromRepr;
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+use unicode_normalization::UnicodeNormalization;
+
+/// A collation for text values.
+///
+/// Variants are ordered by precedence, so that comparisons between a value with one collation and
+/// a value with another use the greater of the two once they're given the same collation (see
+/// [`Collation::resolve`]).
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    FromRepr,
+)]
+#[repr(u8)]
+pub enum Collation {
+    /// Compare the UTF-8 bytes of strings. This is PostgreSQL's `C` collation, and (for UTF-8
+    /// strings) the same as MySQL's `utf8mb4_bin` collation apart from trailing spaces.
+    #[default]
+    Binary = 0,
+    /// MySQL's `utf8mb4_general_ci` collation, which compares strings case-insensitively and
+    /// accent-insensitively one character at a time, ignoring trailing spaces.
+    GeneralCi = 1,
+    /// MySQL's `utf8mb4_0900_ai_ci` collation (the default collation in MySQL 8), which compares
+    /// strings case-insensitively and accent-insensitively, expanding characters like `ß` to
+    /// `ss`, and sorting punctuation before digits and digits before letters.
+    AiCi = 2,
+    /// PostgreSQL's `en_US.UTF-8` collation, which sorts strings ignoring accents and case first,
+    /// then by accents, then with lowercase letters before uppercase ones, but only considers
+    /// strings equal if they're byte-for-byte identical.
+    EnUs = 3,
+}
+
+impl Collation {
+    /// Returns the collation with the given name, as written in a `COLLATE` clause in MySQL or
+    /// PostgreSQL, or `None` if the collation isn't supported.
+    ///
+    /// Names are matched case-insensitively, and can be quoted.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim_matches(|c| c == '"' || c == '\'' || c == '`');
+        match name.to_ascii_lowercase().as_str() {
+            "c" | "posix" | "ucs_basic" | "binary" | "utf8mb4_bin" | "utf8_bin" | "utf8mb3_bin" => {
+                Some(Collation::Binary)
+            }
+            "utf8mb4_general_ci" | "utf8_general_ci" | "utf8mb3_general_ci" => {
+                Some(Collation::GeneralCi)
+            }
+            "utf8mb4_0900_ai_ci" => Some(Collation::AiCi),
+            "en_us.utf-8" | "en_us.utf8" | "en_us" | "en-us-x-icu" => Some(Collation::EnUs),
+            _ => None,
+        }
+    }
+
+    /// Returns the collation declared for the column `spec` with a `COLLATE` clause, if it's
+    /// supported
+    pub fn for_column(spec: &ColumnSpecification) -> Option<Self> {
+        spec.constraints.iter().find_map(|c| match c {
+            ColumnConstraint::Collation(name) => Self::from_name(name),
+            _ => None,
+        })
+    }
+
+    /// Returns the default collation declared for the columns of a table created with `options`,
+    /// if it's supported
+    pub fn for_table(options: &[CreateTableOption]) -> Option<Self> {
+        options.iter().find_map(|o| match o {
+            CreateTableOption::Collate(name) => Self::from_name(name),
+            _ => None,
+        })
+    }
+
+    /// Returns the collation to use when comparing a value with this collation to a value with
+    /// `other`
+    #[inline]
+    pub fn resolve(self, other: Collation) -> Collation {
+        self.max(other)
+    }
+
+    /// Compare `a`, which has this collation, to `b`, which has the collation `other`.
+    ///
+    /// Strings are only compared using their collation if they both have the same one. Strings
+    /// with different collations are never equal, and are ordered by their collations first, so
+    /// that equality agrees with [hashing](Self::hash_str) and the ordering is total. Strings from
+    /// columns with different collations need to be given the same, [resolved](Self::resolve)
+    /// collation before they're compared.
+    pub fn compare_exact(self, a: &str, other: Collation, b: &str) -> Ordering {
+        self.cmp(&other).then_with(|| self.compare(a, b))
+    }
+
+    /// Compare two strings using this collation
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Collation::Binary => a.cmp(b),
+            Collation::GeneralCi => general_ci_weights(a).cmp(general_ci_weights(b)),
+            Collation::AiCi => ai_ci_weights(a).cmp(ai_ci_weights(b)),
+            Collation::EnUs => ai_ci_weights(a)
+                .cmp(ai_ci_weights(b))
+                .then_with(|| accent_key(a).cmp(accent_key(b)))
+                .then_with(|| case_key(a).cmp(case_key(b)))
+                .then_with(|| a.cmp(b)),
+        }
+    }
+
+    /// Returns a string which is byte-for-byte identical to the normalized form of every other
+    /// string that [compares](Collation::compare) equal to `s` using this collation, for use in
+    /// places that can only compare strings by their bytes, like hashes and the keys of
+    /// persisted indexes.
+    ///
+    /// Only the case-insensitive collations change strings; for [`Collation::Binary`] and
+    /// [`Collation::EnUs`] (which only considers identical strings equal) this returns `s`.
+    pub fn normalize(self, s: &str) -> Cow<'_, str> {
+        match self {
+            Collation::Binary | Collation::EnUs => Cow::Borrowed(s),
+            Collation::GeneralCi => Cow::Owned(
+                general_ci_weights(s)
+                    .map(|w| char::from_u32(w).unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect(),
+            ),
+            Collation::AiCi => Cow::Owned(ai_ci_chars(s).collect()),
+        }
+    }
+
+    /// Feed a string into `state`, such that any two strings that [compare](Collation::compare)
+    /// equal using this collation hash the same way, by hashing its [normalized](Self::normalize)
+    /// form
+    pub fn hash_str<H: Hasher>(self, s: &str, state: &mut H) {
+        self.normalize(s).hash(state)
+    }
+}
+
+/// The weights of the characters of `s` in `utf8mb4_general_ci`: each character is compared by
+/// its uppercase form without any accents, characters outside the basic multilingual plane are
+/// all equal, and trailing spaces are ignored
+fn general_ci_weights(s: &str) -> impl Iterator<Item = u32> + '_ {
+    s.trim_end_matches(' ').chars().map(|c| {
+        if c as u32 > 0xFFFF {
+            return 0xFFFD;
+        }
+        if c == 'ß' {
+            return 'S' as u32;
+        }
+        let mut base = None;
+        decompose_canonical(c, |d| {
+            base.get_or_insert(d);
+        });
+        let base = base.unwrap_or(c);
+        let mut upper = base.to_uppercase();
+        match (upper.next(), upper.next()) {
+            (Some(u), None) => u as u32,
+            _ => base as u32,
+        }
+    })
+}
+
+/// Letters that are compared as if they were one or two other letters in `utf8mb4_0900_ai_ci`,
+/// rather than as accented forms of another letter
+fn expand(c: char) -> (char, Option<char>) {
+    match c {
+        'ß' => ('s', Some('s')),
+        'æ' | 'Æ' => ('a', Some('e')),
+        'œ' | 'Œ' => ('o', Some('e')),
+        'ø' | 'Ø' => ('o', None),
+        'ł' | 'Ł' => ('l', None),
+        'đ' | 'Đ' => ('d', None),
+        'ħ' | 'Ħ' => ('h', None),
+        'ŧ' | 'Ŧ' => ('t', None),
+        'ı' => ('i', None),
+        _ => (c, None),
+    }
+}
+
+/// The weight of a lowercase, unaccented character in `utf8mb4_0900_ai_ci`, or `None` if the
+/// character is ignored. Whitespace sorts before punctuation and symbols, which sort before digits,
+/// which sort before letters.
+fn ai_ci_weight(c: char) -> Option<u32> {
+    let group = if c.is_control() {
+        return None;
+    } else if c.is_whitespace() {
+        1
+    } else if c.is_alphabetic() {
+        4
+    } else if c.is_numeric() {
+        3
+    } else {
+        2
+    };
+    Some((group << 24) | c as u32)
+}
+
+/// The characters of `s` that are compared in `utf8mb4_0900_ai_ci`: lowercase and without any
+/// accents, with letters like `ß` expanded, and without any ignored characters
+fn ai_ci_chars(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(|c| {
+            let (first, second) = expand(c);
+            iter::once(first).chain(second)
+        })
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_control())
+}
+
+/// The weights of the characters of `s` in `utf8mb4_0900_ai_ci`, which is also the first level of
+/// comparison in `en_US.UTF-8`
+fn ai_ci_weights(s: &str) -> impl Iterator<Item = u32> + '_ {
+    ai_ci_chars(s).filter_map(ai_ci_weight)
+}
+
+/// The second level of comparison in `en_US.UTF-8`: `s` with its case folded but its accents
+/// kept
+fn accent_key(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.nfd().flat_map(char::to_lowercase)
+}
+
+/// The third level of comparison in `en_US.UTF-8`: whether each letter of `s` is uppercase, so that
+/// lowercase letters sort first
+fn case_key(s: &str) -> impl Iterator<Item = bool> + '_ {
+    s.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(char::is_uppercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+
+    fn hash(collation: Collation, s: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        collation.hash_str(s, &mut hasher);
+        hasher.finish()
+    }
+
+    #[track_caller]
+    fn assert_equal(collation: Collation, a: &str, b: &str) {
+        assert_eq!(
+            collation.compare(a, b),
+            Ordering::Equal,
+            "{:?} = {:?}",
+            a,
+            b
+        );
+        assert_eq!(collation.normalize(a), collation.normalize(b));
+        assert_eq!(hash(collation, a), hash(collation, b), "hash({:?})", a);
+    }
+
+    #[track_caller]
+    fn assert_sorted(collation: Collation, strs: &[&str]) {
+        let mut sorted = strs.to_vec();
+        sorted.sort_by(|a, b| collation.compare(a, b));
+        assert_eq!(sorted, strs);
+        for pair in strs.windows(2) {
+            assert_eq!(
+                collation.compare(pair[0], pair[1]),
+                Ordering::Less,
+                "{:?} < {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn from_name() {
+        assert_eq!(Collation::from_name("C"), Some(Collation::Binary));
+        assert_eq!(Collation::from_name("\"C\""), Some(Collation::Binary));
+        assert_eq!(
+            Collation::from_name("UTF8MB4_GENERAL_CI"),
+            Some(Collation::GeneralCi)
+        );
+        assert_eq!(
+            Collation::from_name("utf8mb4_0900_ai_ci"),
+            Some(Collation::AiCi)
+        );
+        assert_eq!(
+            Collation::from_name("\"en_US.UTF-8\""),
+            Some(Collation::EnUs)
+        );
+        assert_eq!(Collation::from_name("latin1_swedish_ci"), None);
+    }
+
+    #[test]
+    fn resolve() {
+        assert_eq!(
+            Collation::Binary.resolve(Collation::GeneralCi),
+            Collation::GeneralCi
+        );
+        assert_eq!(Collation::AiCi.resolve(Collation::Binary), Collation::AiCi);
+    }
+
+    #[test]
+    fn binary() {
+        assert_sorted(Collation::Binary, &["A", "B", "a", "b", "á"]);
+        assert_eq!(hash(Collation::Binary, "abc"), {
+            let mut hasher = DefaultHasher::new();
+            "abc".hash(&mut hasher);
+            hasher.finish()
+        });
+    }
+
+    #[test]
+    fn general_ci() {
+        let c = Collation::GeneralCi;
+        assert_equal(c, "abc", "ABC");
+        assert_equal(c, "resume", "Résumé");
+        assert_equal(c, "abc", "abc   ");
+        assert_eq!(c.normalize("Résumé  "), "RESUME");
+        assert_equal(c, "ß", "s");
+        assert_equal(c, "😀", "🐱");
+        assert_sorted(c, &[" a", "1", "a", "B", "c"]);
+    }
+
+    #[test]
+    fn ai_ci() {
+        let c = Collation::AiCi;
+        assert_equal(c, "abc", "ABC");
+        assert_equal(c, "resume", "Résumé");
+        assert_equal(c, "ß", "ss");
+        assert_equal(c, "Æsir", "aesir");
+        assert_equal(c, "a\u{1}b", "ab");
+        assert_eq!(c.compare("abc ", "abc"), Ordering::Greater);
+        assert_sorted(c, &[" ", "-", "1", "2", "a", "B", "c"]);
+    }
+
+    #[test]
+    fn en_us() {
+        // Orderings are from PostgreSQL 15's "en-US-x-icu" collation
+        let c = Collation::EnUs;
+        assert_sorted(c, &["a", "A", "á", "Á", "b", "B"]);
+        assert_sorted(c, &["ab", "Ab", "áb", "ac"]);
+        assert_sorted(c, &["-", "1", "a"]);
+        assert_equal(c, "abc", "abc");
+        assert_ne!(c.compare("abc", "ABC"), Ordering::Equal);
+    }
+}
@@ -0,0 +1,108 @@
+//! MySQL `ENUM` values.
+//!
+//! Values of `ENUM` columns are stored as their index in the list of the column's variants,
+//! starting at 1, as [`DataType::UnsignedInt`]s. Index 0 is MySQL's "error value" for strings that
+//! aren't any of the variants, which is displayed as the empty string. Comparing and sorting the
+//! stored indices gives the same results as MySQL, which sorts `ENUM` values in the order their
+//! variants are listed in the column definition rather than lexicographically.
+
+use nom_sql::{Literal, SqlType};
+use readyset_errors::{ReadySetError, ReadySetResult};
+
+use crate::DataType;
+
+/// Returns the string value of the given enum variant
+fn variant_str(variant: &Literal) -> String {
+    match variant {
+        Literal::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+/// Returns the index of the variant of an `ENUM` column with the given `variants` named by `s`, or
+/// the error value (index 0) if `s` doesn't name any of them. Like MySQL, variants are matched
+/// case-insensitively and ignoring trailing spaces.
+pub(crate) fn coerce_str(s: &str, variants: &[Literal]) -> DataType {
+    let s = s.trim_end_matches(' ').to_lowercase();
+    let index = variants
+        .iter()
+        .position(|v| variant_str(v).trim_end_matches(' ').to_lowercase() == s)
+        .map_or(0, |i| i + 1);
+    DataType::UnsignedInt(index as u64)
+}
+
+/// Checks that `index` is a valid index into an `ENUM` column with the given `variants`, and
+/// returns it as the value of that column
+pub(crate) fn coerce_index(index: Option<u64>, variants: &[Literal]) -> ReadySetResult<DataType> {
+    match index {
+        Some(index) if index <= variants.len() as u64 => Ok(DataType::UnsignedInt(index)),
+        _ => Err(ReadySetError::DataTypeConversionError {
+            src_type: "Int".to_string(),
+            target_type: SqlType::Enum(variants.to_vec()).to_string(),
+            details: "out of bounds".to_string(),
+        }),
+    }
+}
+
+/// Returns the string value of the stored `ENUM` `value` of a column with the given `variants`,
+/// for returning to clients. Values other than integers (such as `NULL`) are returned unchanged.
+pub fn variant(value: &DataType, variants: &[Literal]) -> DataType {
+    let index = match value {
+        DataType::UnsignedInt(i) => *i as usize,
+        DataType::Int(i) if *i >= 0 => *i as usize,
+        _ => return value.clone(),
+    };
+    match index.checked_sub(1).and_then(|i| variants.get(i)) {
+        Some(v) => DataType::from(variant_str(v)),
+        None => DataType::from(""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variants() -> Vec<Literal> {
+        vec!["small".into(), "medium".into(), "large".into()]
+    }
+
+    #[test]
+    fn strings_to_indices() {
+        assert_eq!(coerce_str("small", &variants()), DataType::UnsignedInt(1));
+        assert_eq!(coerce_str("Large ", &variants()), DataType::UnsignedInt(3));
+        assert_eq!(coerce_str("huge", &variants()), DataType::UnsignedInt(0));
+    }
+
+    #[test]
+    fn indices_to_strings() {
+        assert_eq!(
+            variant(&DataType::UnsignedInt(2), &variants()),
+            "medium".into()
+        );
+        assert_eq!(variant(&DataType::UnsignedInt(0), &variants()), "".into());
+        assert_eq!(variant(&DataType::None, &variants()), DataType::None);
+    }
+
+    #[test]
+    fn sorts_in_definition_order() {
+        let mut vals = ["small", "large", "medium"]
+            .iter()
+            .map(|s| DataType::from(*s).coerce_to(&SqlType::Enum(variants())))
+            .collect::<ReadySetResult<Vec<_>>>()
+            .unwrap();
+        vals.sort();
+        assert_eq!(
+            vals.iter()
+                .map(|v| variant(v, &variants()))
+                .collect::<Vec<_>>(),
+            vec![
+                DataType::from("small"),
+                DataType::from("medium"),
+                DataType::from("large")
+            ]
+        );
+        assert!(DataType::from(4)
+            .coerce_to(&SqlType::Enum(variants()))
+            .is_err());
+    }
+}
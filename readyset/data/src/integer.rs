@@ -1,9 +1,11 @@
 use std::convert::TryFrom;
 use std::fmt;
+use std::sync::atomic::{self, AtomicBool};
 use std::sync::Arc;
 
 use readyset_errors::{ReadySetError, ReadySetResult};
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 use crate::{DataType, SqlType};
 
@@ -31,6 +33,95 @@ impl IntAsFloat for u64 {
     }
 }
 
+/// What to do when the result of arithmetic on two integers doesn't fit in a `BIGINT`, or in a
+/// `BIGINT UNSIGNED` if either operand is unsigned.
+///
+/// Integer values are always 64 bits wide, so arithmetic on narrower integer types is always done
+/// in (and never overflows before reaching the range of) `BIGINT`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IntegerOverflow {
+    /// Return an error, as both MySQL ("BIGINT value is out of range", regardless of whether
+    /// strict mode is enabled) and PostgreSQL ("bigint out of range") do
+    #[default]
+    Error,
+    /// Promote the result to a wider type instead of returning an error: negative results of
+    /// unsigned arithmetic become signed `BIGINT`s, as with MySQL's `NO_UNSIGNED_SUBTRACTION`
+    /// `sql_mode`, and results outside the range of 64-bit integers become `NUMERIC`s
+    Promote,
+}
+
+/// Whether integer arithmetic in this process promotes results on overflow - see
+/// [`IntegerOverflow::set_global`]
+static PROMOTE_ON_OVERFLOW: AtomicBool = AtomicBool::new(false);
+
+impl IntegerOverflow {
+    /// Set the overflow behavior of all integer arithmetic performed by the arithmetic operator
+    /// impls on [`DataType`] in this process. Defaults to [`IntegerOverflow::Error`].
+    pub fn set_global(self) {
+        PROMOTE_ON_OVERFLOW.store(self == IntegerOverflow::Promote, atomic::Ordering::Relaxed);
+    }
+
+    /// Returns the overflow behavior last set with [`IntegerOverflow::set_global`]
+    pub fn global() -> Self {
+        if PROMOTE_ON_OVERFLOW.load(atomic::Ordering::Relaxed) {
+            IntegerOverflow::Promote
+        } else {
+            IntegerOverflow::Error
+        }
+    }
+}
+
+/// Perform the arithmetic operation `op` (one of `+`, `-`, `*` or `/`, computed by `checked_op`) on
+/// two integers, widened to `i128`s so that the result can be checked against the range of its
+/// type. The result is a `BIGINT UNSIGNED` if either operand is unsigned, as in MySQL, and a
+/// `BIGINT` otherwise; if it doesn't fit, it's handled according to `overflow`.
+pub(crate) fn arithmetic(
+    op: &str,
+    checked_op: fn(i128, i128) -> Option<i128>,
+    a: i128,
+    b: i128,
+    unsigned: bool,
+    overflow: IntegerOverflow,
+) -> ReadySetResult<DataType> {
+    let type_name = if unsigned {
+        "BIGINT UNSIGNED"
+    } else {
+        "BIGINT"
+    };
+    let out_of_range = || ReadySetError::DataTypeConversionError {
+        src_type: type_name.to_string(),
+        target_type: type_name.to_string(),
+        details: format!("value is out of range in '({} {} {})'", a, op, b),
+    };
+
+    if op == "/" && b == 0 {
+        return Err(ReadySetError::DataTypeConversionError {
+            src_type: type_name.to_string(),
+            target_type: type_name.to_string(),
+            details: "division by zero".to_string(),
+        });
+    }
+
+    let res = checked_op(a, b).ok_or_else(out_of_range)?;
+    if unsigned {
+        if let Ok(res) = u64::try_from(res) {
+            return Ok(DataType::UnsignedInt(res));
+        }
+    } else if let Ok(res) = i64::try_from(res) {
+        return Ok(DataType::Int(res));
+    }
+
+    match overflow {
+        IntegerOverflow::Error => Err(out_of_range()),
+        IntegerOverflow::Promote => match i64::try_from(res) {
+            Ok(res) => Ok(DataType::Int(res)),
+            Err(_) => Decimal::try_from_i128_with_scale(res, 0)
+                .map(DataType::from)
+                .map_err(|_| out_of_range()),
+        },
+    }
+}
+
 /// Coerce an integer value according to MySQL rules to the best of our abilities
 pub(crate) fn coerce_integer<I, S>(
     val: I,
@@ -178,15 +269,14 @@ where
         SqlType::Real | SqlType::Float => Ok(DataType::Float(val.to_f32())),
         SqlType::Numeric(_) | SqlType::Decimal(_, _) => Ok(DataType::Numeric(Arc::new(val.into()))),
 
-        SqlType::Enum(_)
-        | SqlType::MacAddr
-        | SqlType::Inet
-        | SqlType::Uuid
-        | SqlType::Bit(_)
-        | SqlType::Varbit(_) => Err(ReadySetError::DataTypeConversionError {
-            src_type: src_type_name.to_string(),
-            target_type: sql_type.to_string(),
-            details: "Not allowed".to_string(),
-        }),
+        SqlType::Enum(variants) => crate::enums::coerce_index(u64::try_from(val).ok(), variants),
+
+        SqlType::MacAddr | SqlType::Inet | SqlType::Uuid | SqlType::Bit(_) | SqlType::Varbit(_) => {
+            Err(ReadySetError::DataTypeConversionError {
+                src_type: src_type_name.to_string(),
+                target_type: sql_type.to_string(),
+                details: "Not allowed".to_string(),
+            })
+        }
     }
 }
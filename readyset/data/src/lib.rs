@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::hash::{Hash, Hasher};
@@ -14,13 +15,20 @@ use readyset_errors::{internal, ReadySetError, ReadySetResult};
 use proptest::prelude::{prop_oneof, Arbitrary};
 use tokio_postgres::types::{accepts, to_sql_checked, FromSql, IsNull, ToSql, Type};
 
+mod collation;
+pub mod enums;
 mod float;
 mod integer;
 pub mod noria_type;
+pub mod nulls;
+mod numeric;
 mod serde;
 mod text;
 mod timestamp;
 
+pub use crate::collation::Collation;
+pub use crate::integer::IntegerOverflow;
+pub use crate::nulls::NullOrder;
 pub use crate::text::{Text, TinyText};
 pub use crate::timestamp::{TimestampTz, TIMESTAMP_FORMAT};
 
@@ -172,7 +180,9 @@ impl DataType {
     #[must_use]
     pub fn deep_clone(&self) -> Self {
         match *self {
-            DataType::Text(ref text) => DataType::Text(text.as_str().into()),
+            DataType::Text(ref text) => {
+                DataType::Text(Text::from(text.as_str()).with_collation(text.collation()))
+            }
             DataType::ByteArray(ref bytes) => DataType::ByteArray(Arc::new(bytes.as_ref().clone())),
             DataType::BitVector(ref bits) => DataType::from(bits.as_ref().clone()),
             ref dt => dt.clone(),
@@ -201,6 +211,68 @@ impl DataType {
         matches!(*self, DataType::Text(_) | DataType::TinyText(_))
     }
 
+    /// Returns the collation used to compare this value to other strings, if it's a string
+    pub fn collation(&self) -> Option<Collation> {
+        match self {
+            DataType::Text(t) => Some(t.collation()),
+            DataType::TinyText(tt) => Some(tt.collation()),
+            _ => None,
+        }
+    }
+
+    /// Returns this value with its collation set to `collation` if it's a string, or unchanged
+    /// otherwise.
+    ///
+    /// Values read from a column should be given the collation of that column, so that
+    /// comparisons between them (and between them and other strings) use that collation.
+    #[must_use]
+    pub fn with_collation(self, collation: Collation) -> Self {
+        match self {
+            DataType::Text(t) => DataType::Text(t.with_collation(collation)),
+            DataType::TinyText(tt) => DataType::TinyText(tt.with_collation(collation)),
+            dt => dt,
+        }
+    }
+
+    /// Returns `self` and `other` with the same collation if they're both strings, using the
+    /// collation that comparisons between them should use (see [`Collation::resolve`]), or
+    /// unchanged otherwise.
+    ///
+    /// Strings with different collations never compare equal, so this needs to be called wherever
+    /// strings which can have different collations, like a column and a literal, are compared.
+    #[must_use]
+    pub fn resolve_collations(self, other: Self) -> (Self, Self) {
+        match (self.collation(), other.collation()) {
+            (Some(a), Some(b)) if a != b => {
+                let collation = a.resolve(b);
+                (
+                    self.with_collation(collation),
+                    other.with_collation(collation),
+                )
+            }
+            _ => (self, other),
+        }
+    }
+
+    /// Returns this value in a form whose serialization is byte-for-byte identical to that of
+    /// every other value that compares equal to it, for use in places that can only compare values
+    /// by their serialized bytes, like the keys of persisted indexes.
+    ///
+    /// Strings with a collation other than [`Collation::Binary`] are replaced by their
+    /// [normalized](Collation::normalize) form, without a collation. Other values are unchanged.
+    pub fn collation_key(&self) -> Cow<'_, DataType> {
+        match self.collation() {
+            Some(collation) if collation != Collation::Binary => {
+                // this unwrap should be safe because no error path in try_from for &str on Text or
+                // TinyText
+                #[allow(clippy::unwrap_used)]
+                let s: &str = <&str>::try_from(self).unwrap();
+                Cow::Owned(DataType::from(collation.normalize(s).as_ref()))
+            }
+            _ => Cow::Borrowed(self),
+        }
+    }
+
     /// Checks if this value is of a timestamp data type.
     pub fn is_datetime(&self) -> bool {
         matches!(*self, DataType::TimestampTz(_))
@@ -358,8 +430,9 @@ impl DataType {
         match self {
             DataType::None => Ok(DataType::None),
             dt if dt.sql_type().as_ref() == Some(ty) => Ok(self.clone()),
-            DataType::Text(t) => t.coerce_to(ty),
-            DataType::TinyText(tt) => tt.coerce_to(ty),
+            // Strings coerced to other string types keep their collation
+            DataType::Text(t) => t.coerce_to(ty).map(|dt| dt.with_collation(t.collation())),
+            DataType::TinyText(tt) => tt.coerce_to(ty).map(|dt| dt.with_collation(tt.collation())),
             DataType::TimestampTz(tz) => tz.coerce_to(ty),
             DataType::Int(v) => integer::coerce_integer(*v, "Int", ty),
             DataType::UnsignedInt(v) => integer::coerce_integer(*v, "UnsignedInt", ty),
@@ -443,17 +516,7 @@ impl PartialEq for DataType {
             (&DataType::Text(ref a), &DataType::Text(ref b)) => a == b,
             (&DataType::TinyText(ref a), &DataType::TinyText(ref b)) => a == b,
             (&DataType::Text(..), &DataType::TinyText(..))
-            | (&DataType::TinyText(..), &DataType::Text(..)) => {
-                // this unwrap should be safe because no error path in try_from for &str on Text or
-                // TinyText
-                #[allow(clippy::unwrap_used)]
-                let a: &str = <&str>::try_from(self).unwrap();
-                // this unwrap should be safe because no error path in try_from for &str on Text or
-                // TinyText
-                #[allow(clippy::unwrap_used)]
-                let b: &str = <&str>::try_from(other).unwrap();
-                a == b
-            }
+            | (&DataType::TinyText(..), &DataType::Text(..)) => self.cmp(other) == Ordering::Equal,
             (&DataType::Text(..) | &DataType::TinyText(..), &DataType::TimestampTz(ref dt)) => {
                 // this unwrap should be safe because no error path in try_from for &str on Text or
                 // TinyText
@@ -563,7 +626,7 @@ impl Ord for DataType {
     fn cmp(&self, other: &DataType) -> Ordering {
         match (self, other) {
             (&DataType::Text(ref a), &DataType::Text(ref b)) => a.cmp(b),
-            (&DataType::TinyText(ref a), &DataType::TinyText(ref b)) => a.as_str().cmp(b.as_str()),
+            (&DataType::TinyText(ref a), &DataType::TinyText(ref b)) => a.cmp(b),
             (&DataType::Text(..), &DataType::TinyText(..))
             | (&DataType::TinyText(..), &DataType::Text(..)) => {
                 // this unwrap should be safe because no error path in try_from for &str on Text or
@@ -574,7 +637,11 @@ impl Ord for DataType {
                 // TinyText
                 #[allow(clippy::unwrap_used)]
                 let b: &str = <&str>::try_from(other).unwrap();
-                a.cmp(b)
+                self.collation().unwrap_or_default().compare_exact(
+                    a,
+                    other.collation().unwrap_or_default(),
+                    b,
+                )
             }
             (
                 &DataType::Text(..) | &DataType::TinyText(..),
@@ -724,7 +791,10 @@ impl Hash for DataType {
                 // TinyText
                 #[allow(clippy::unwrap_used)]
                 let t: &str = <&str>::try_from(self).unwrap();
-                t.hash(state)
+                // Strings that compare equal under their collation need to hash the same way.
+                // Strings with different collations are never equal, so the collation itself
+                // doesn't need to be hashed.
+                self.collation().unwrap_or_default().hash_str(t, state)
             }
             DataType::TimestampTz(ts) => ts.hash(state),
             DataType::Time(ref t) => t.hash(state),
@@ -1535,7 +1605,7 @@ impl ToSql for DataType {
                 | &Type::REGROLE
                 | &Type::REGTYPE,
             ) => u32::try_from(*x)?.to_sql(ty, out),
-            (Self::UnsignedInt(x), _) => (*x as i64).to_sql(ty, out),
+            (Self::UnsignedInt(x), _) => i64::try_from(*x)?.to_sql(ty, out),
             (Self::Float(x), _) => x.to_sql(ty, out),
             (Self::Double(x), _) => x.to_sql(ty, out),
             (Self::Numeric(d), _) => d.to_sql(ty, out),
@@ -1772,16 +1842,27 @@ impl TryFrom<&DataType> for mysql_common::value::Value {
 }
 
 // Performs an arithmetic operation on two numeric DataTypes,
-// returning a new DataType as the result.
+// returning a new DataType as the result. Operations on two integers
+// are performed by $checked_op, with overflow handled according to
+// the global IntegerOverflow. Operations on Numerics (and integers)
+// are performed by $numeric_op, which gives the result the same scale
+// as the upstream database would.
 macro_rules! arithmetic_operation (
-    ($op:tt, $first:ident, $second:ident) => (
+    ($op:tt, $checked_op:path, $numeric_op:path, $first:ident, $second:ident) => (
         match ($first, $second) {
             (&DataType::None, _) | (_, &DataType::None) => DataType::None,
-            (&DataType::Int(a), &DataType::Int(b)) => (a $op b).into(),
-            (&DataType::UnsignedInt(a), &DataType::UnsignedInt(b)) => (a $op b).into(),
-
-            (&DataType::UnsignedInt(a), &DataType::Int(b)) => DataType::try_from(i128::from(a) $op i128::from(b))?,
-            (&DataType::Int(a), &DataType::UnsignedInt(b)) => DataType::try_from(i128::from(a) $op i128::from(b))?,
+            (&DataType::Int(a), &DataType::Int(b)) => integer::arithmetic(
+                stringify!($op), $checked_op, a.into(), b.into(), false, IntegerOverflow::global()
+            )?,
+            (&DataType::UnsignedInt(a), &DataType::UnsignedInt(b)) => integer::arithmetic(
+                stringify!($op), $checked_op, a.into(), b.into(), true, IntegerOverflow::global()
+            )?,
+            (&DataType::UnsignedInt(a), &DataType::Int(b)) => integer::arithmetic(
+                stringify!($op), $checked_op, a.into(), b.into(), true, IntegerOverflow::global()
+            )?,
+            (&DataType::Int(a), &DataType::UnsignedInt(b)) => integer::arithmetic(
+                stringify!($op), $checked_op, a.into(), b.into(), true, IntegerOverflow::global()
+            )?,
 
             (first @ &DataType::Int(..), second @ &DataType::Float(..)) |
             (first @ &DataType::UnsignedInt(..), second @ &DataType::Float(..)) |
@@ -1789,7 +1870,8 @@ macro_rules! arithmetic_operation (
             (first @ &DataType::Float(..), second @ &DataType::UnsignedInt(..)) |
             (first @ &DataType::Float(..), second @ &DataType::Float(..)) |
             (first @ &DataType::Float(..), second @ &DataType::Double(..)) |
-            (first @ &DataType::Float(..), second @ &DataType::Numeric(..)) => {
+            (first @ &DataType::Float(..), second @ &DataType::Numeric(..)) |
+            (first @ &DataType::Numeric(..), second @ &DataType::Float(..)) => {
                 let a: f32 = f32::try_from(first)?;
                 let b: f32 = f32::try_from(second)?;
                 DataType::try_from(a $op b)?
@@ -1801,7 +1883,8 @@ macro_rules! arithmetic_operation (
             (first @ &DataType::Double(..), second @ &DataType::UnsignedInt(..)) |
             (first @ &DataType::Double(..), second @ &DataType::Double(..)) |
             (first @ &DataType::Double(..), second @ &DataType::Float(..)) |
-            (first @ &DataType::Double(..), second @ &DataType::Numeric(..)) => {
+            (first @ &DataType::Double(..), second @ &DataType::Numeric(..)) |
+            (first @ &DataType::Numeric(..), second @ &DataType::Double(..)) => {
                 let a: f64 = f64::try_from(first)?;
                 let b: f64 = f64::try_from(second)?;
                 DataType::try_from(a $op b)?
@@ -1824,40 +1907,9 @@ macro_rules! arithmetic_operation (
                         target_type: "Decimal".to_string(),
                         details: e.to_string(),
                     })?;
-                DataType::from(a $op b)
-            }
-            (first @ &DataType::Numeric(..), second @ &DataType::Float(..)) => {
-                let a: Decimal = Decimal::try_from(first)
-                    .map_err(|e| ReadySetError::DataTypeConversionError {
-                        src_type: "DataType".to_string(),
-                        target_type: "Decimal".to_string(),
-                        details: e.to_string(),
-                    })?;
-                let b: Decimal = f32::try_from(second).and_then(|f| Decimal::from_f32(f)
-                    .ok_or(ReadySetError::DataTypeConversionError {
-                        src_type: "DataType".to_string(),
-                        target_type: "Decimal".to_string(),
-                        details: "".to_string(),
-                    }))?;
-                DataType::from(a $op b)
-            }
-            (first @ &DataType::Numeric(..), second @ &DataType::Double(..)) => {
-                let a: Decimal = Decimal::try_from(first)
-                    .map_err(|e| ReadySetError::DataTypeConversionError {
-                        src_type: "DataType".to_string(),
-                        target_type: "Decimal".to_string(),
-                        details: e.to_string(),
-                    })?;
-                let b: Decimal = f64::try_from(second).and_then(|f| Decimal::from_f64(f)
-                    .ok_or(ReadySetError::DataTypeConversionError {
-                        src_type: "DataType".to_string(),
-                        target_type: "Decimal".to_string(),
-                        details: "".to_string(),
-                    }))?;
-                DataType::from(a $op b)
+                DataType::from($numeric_op(a, b)?)
             }
 
-
             (first, second) => panic!(
                 "can't {} a {:?} and {:?}",
                 stringify!($op),
@@ -1872,7 +1924,7 @@ impl<'a, 'b> Add<&'b DataType> for &'a DataType {
     type Output = ReadySetResult<DataType>;
 
     fn add(self, other: &'b DataType) -> Self::Output {
-        Ok(arithmetic_operation!(+, self, other))
+        Ok(arithmetic_operation!(+, i128::checked_add, numeric::add, self, other))
     }
 }
 
@@ -1880,7 +1932,7 @@ impl<'a, 'b> Sub<&'b DataType> for &'a DataType {
     type Output = ReadySetResult<DataType>;
 
     fn sub(self, other: &'b DataType) -> Self::Output {
-        Ok(arithmetic_operation!(-, self, other))
+        Ok(arithmetic_operation!(-, i128::checked_sub, numeric::sub, self, other))
     }
 }
 
@@ -1888,7 +1940,7 @@ impl<'a, 'b> Mul<&'b DataType> for &'a DataType {
     type Output = ReadySetResult<DataType>;
 
     fn mul(self, other: &'b DataType) -> Self::Output {
-        Ok(arithmetic_operation!(*, self, other))
+        Ok(arithmetic_operation!(*, i128::checked_mul, numeric::mul, self, other))
     }
 }
 
@@ -1896,7 +1948,7 @@ impl<'a, 'b> Div<&'b DataType> for &'a DataType {
     type Output = ReadySetResult<DataType>;
 
     fn div(self, other: &'b DataType) -> Self::Output {
-        Ok(arithmetic_operation!(/, self, other))
+        Ok(arithmetic_operation!(/, i128::checked_div, numeric::div, self, other))
     }
 }
 
@@ -2337,6 +2389,91 @@ mod tests {
         assert_eq!((&DataType::Int(4) / &DataType::from(2)).unwrap(), 2.into());
     }
 
+    // Expected results in this test are from MySQL 8.0 and PostgreSQL 15
+    #[test]
+    fn integer_overflow() {
+        let err = |res: ReadySetResult<DataType>| res.unwrap_err().to_string();
+
+        assert!(err(&DataType::Int(i64::MAX) + &DataType::Int(1)).contains("out of range"));
+        assert!(err(&DataType::Int(i64::MIN) - &DataType::Int(1)).contains("out of range"));
+        assert!(err(&DataType::Int(i64::MIN) / &DataType::Int(-1)).contains("out of range"));
+        assert!(err(&DataType::Int(1) / &DataType::Int(0)).contains("division by zero"));
+        assert!(
+            err(&DataType::UnsignedInt(u64::MAX) * &DataType::UnsignedInt(2))
+                .contains("BIGINT UNSIGNED value is out of range")
+        );
+        // As in MySQL, the result of arithmetic on an unsigned operand is unsigned
+        assert!(err(&DataType::UnsignedInt(1) - &DataType::Int(2)).contains("out of range"));
+        assert_eq!(
+            (&DataType::UnsignedInt(u64::MAX) - &DataType::Int(1)).unwrap(),
+            DataType::UnsignedInt(u64::MAX - 1)
+        );
+        assert_eq!(
+            (&DataType::Int(i64::MAX) + &DataType::UnsignedInt(1)).unwrap(),
+            DataType::UnsignedInt(i64::MAX as u64 + 1)
+        );
+
+        let promote = |op, checked_op, a: i128, b: i128, unsigned| {
+            integer::arithmetic(op, checked_op, a, b, unsigned, IntegerOverflow::Promote).unwrap()
+        };
+        assert_eq!(
+            promote("+", i128::checked_add, i64::MAX.into(), 1, false),
+            DataType::from(Decimal::from(i64::MAX as u64 + 1))
+        );
+        assert_eq!(
+            promote("-", i128::checked_sub, 1, 2, true),
+            DataType::Int(-1)
+        );
+        assert_eq!(
+            promote("*", i128::checked_mul, u64::MAX.into(), 2, true),
+            DataType::from(Decimal::from(u64::MAX) * Decimal::from(2))
+        );
+        assert!(integer::arithmetic(
+            "*",
+            i128::checked_mul,
+            u64::MAX.into(),
+            u64::MAX.into(),
+            true,
+            IntegerOverflow::Promote
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unsigned_bigint_full_range() {
+        let max = DataType::from("18446744073709551615")
+            .coerce_to(&SqlType::UnsignedBigint(None))
+            .unwrap();
+        assert_eq!(max, DataType::UnsignedInt(u64::MAX));
+        assert_eq!(max.to_string(), "18446744073709551615");
+        assert!(max > DataType::Int(i64::MAX));
+        assert!(DataType::UnsignedInt(i64::MAX as u64 + 1) > DataType::Int(i64::MAX));
+        assert!(DataType::Int(-1) < DataType::UnsignedInt(0));
+        assert_ne!(max, DataType::Int(-1));
+        assert_eq!(u64::try_from(&max).unwrap(), u64::MAX);
+        assert!(i64::try_from(&max).is_err());
+    }
+
+    #[test]
+    fn numeric_arithmetic_scale() {
+        let numeric = |s: &str| DataType::from(s.parse::<Decimal>().unwrap());
+        assert_eq!(
+            (&numeric("19.99") * &DataType::Int(3)).unwrap().to_string(),
+            "59.97"
+        );
+        assert_eq!(
+            (&numeric("1.10") + &numeric("2.205")).unwrap().to_string(),
+            "3.305"
+        );
+        assert_eq!(
+            (&numeric("100.00") / &DataType::Int(3))
+                .unwrap()
+                .to_string(),
+            "33.3333333333333333"
+        );
+        assert!((&numeric("1.5") / &DataType::Int(0)).is_err());
+    }
+
     #[test]
     #[should_panic(expected = "can't + a TinyText(\"hi\") and Int(5)")]
     fn add_invalid_types() {
@@ -2345,6 +2482,78 @@ mod tests {
         let _ = &a + &b;
     }
 
+    #[test]
+    fn collated_text() {
+        let hash = |dt: &DataType| {
+            use std::collections::hash_map::DefaultHasher;
+            let mut s = DefaultHasher::new();
+            dt.hash(&mut s);
+            s.finish()
+        };
+
+        let tiny = DataType::from("abc").with_collation(Collation::GeneralCi);
+        let tiny_upper = DataType::from("ABC").with_collation(Collation::GeneralCi);
+        let long = DataType::from("this is a long string").with_collation(Collation::AiCi);
+        let long_upper = DataType::from("THIS IS A LONG STRING").with_collation(Collation::AiCi);
+        assert_eq!(tiny.collation(), Some(Collation::GeneralCi));
+        assert_eq!(long.collation(), Some(Collation::AiCi));
+        assert_eq!(DataType::from("abc").collation(), Some(Collation::Binary));
+        assert_eq!(DataType::Int(1).collation(), None);
+
+        assert_eq!(tiny, tiny_upper);
+        assert_eq!(hash(&tiny), hash(&tiny_upper));
+        assert_eq!(long, long_upper);
+        assert_eq!(hash(&long), hash(&long_upper));
+        assert_ne!(DataType::from("abc"), DataType::from("ABC"));
+
+        // Strings with different collations are never equal, and only compare using a collation
+        // once they've been given the same one
+        assert_ne!(tiny, DataType::from("ABC"));
+        assert_ne!(tiny, DataType::from("abc"));
+        assert_ne!(DataType::from("THIS IS A LONG STRING"), long);
+        let (a, b) = tiny.clone().resolve_collations(DataType::from("ABC"));
+        assert_eq!(a.collation(), Some(Collation::GeneralCi));
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        let (a, b) = DataType::from("THIS IS A LONG STRING").resolve_collations(long.clone());
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        let (a, b) = DataType::from("b")
+            .resolve_collations(DataType::from("A").with_collation(Collation::GeneralCi));
+        assert_eq!(a.cmp(&b), Ordering::Greater);
+
+        // Collation keys of equal values serialize the same way
+        assert_eq!(
+            bincode::serialize(&tiny.collation_key()).unwrap(),
+            bincode::serialize(&tiny_upper.collation_key()).unwrap()
+        );
+        assert_eq!(
+            bincode::serialize(&long.collation_key()).unwrap(),
+            bincode::serialize(&long_upper.collation_key()).unwrap()
+        );
+        assert!(matches!(
+            DataType::from("abc").collation_key(),
+            Cow::Borrowed(_)
+        ));
+
+        // Coercions to other string types keep the collation
+        assert_eq!(
+            tiny.coerce_to(&SqlType::Char(Some(5))).unwrap().collation(),
+            Some(Collation::GeneralCi)
+        );
+
+        for dt in [&tiny, &long] {
+            let json: DataType = serde_json::from_str(&serde_json::to_string(dt).unwrap()).unwrap();
+            assert_eq!(json.collation(), dt.collation());
+            let bincode: DataType = bincode::deserialize(&bincode::serialize(dt).unwrap()).unwrap();
+            assert_eq!(bincode.collation(), dt.collation());
+            assert_eq!(
+                <&str>::try_from(&bincode).unwrap(),
+                <&str>::try_from(dt).unwrap()
+            );
+        }
+    }
+
     #[test]
     fn data_type_debug() {
         let tiny_text: DataType = "hi".try_into().unwrap();
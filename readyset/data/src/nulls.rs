@@ -0,0 +1,184 @@
+//! The semantics of SQL `NULL` values, in one place so that every operator treats them the same
+//! way, and the same way as the upstream database.
+//!
+//! * `NULL` is never equal to anything, including another `NULL`, in a comparison - `NULL = NULL`
+//!   and `NULL <> 1` are both `NULL`. Logical operators use three-valued logic (see [`and`] and
+//!   [`or`]), so `x NOT IN (1, NULL)`, which is `x <> 1 AND x <> NULL`, is never true, and `1 IN
+//!   (1, NULL)` is true.
+//! * `NULL` values *are* equal to each other as group keys, so `GROUP BY` and `DISTINCT` put all
+//!   rows with a `NULL` key into the same group, in both MySQL and PostgreSQL. [`DataType`]'s
+//!   [`Eq`] and [`Hash`] impls already do this.
+//! * Where `NULL` values sort relative to other values in `ORDER BY` depends on the dialect, and is
+//!   described by a [`NullOrder`].
+
+use std::cmp::Ordering;
+
+use nom_sql::{Dialect, DialectKind, OrderType};
+use serde::{Deserialize, Serialize};
+
+use crate::DataType;
+
+/// Where `NULL` values sort relative to all other values in an ascending `ORDER BY`. Descending
+/// orders are the reverse, so `NULL` values sort at the other end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NullOrder {
+    /// `NULL` sorts before all other values, as in MySQL
+    #[default]
+    NullsFirst,
+    /// `NULL` sorts after all other values, as in PostgreSQL
+    NullsLast,
+}
+
+impl From<Dialect> for NullOrder {
+    fn from(dialect: Dialect) -> Self {
+        match dialect.kind() {
+            DialectKind::MySQL => NullOrder::NullsFirst,
+            DialectKind::PostgreSQL => NullOrder::NullsLast,
+        }
+    }
+}
+
+impl NullOrder {
+    /// Compare `a` to `b` in ascending order, placing `NULL` values according to this
+    /// [`NullOrder`]
+    pub fn compare(self, a: &DataType, b: &DataType) -> Ordering {
+        match (a.is_none(), b.is_none(), self) {
+            (true, true, _) => Ordering::Equal,
+            (true, false, NullOrder::NullsFirst) | (false, true, NullOrder::NullsLast) => {
+                Ordering::Less
+            }
+            (true, false, NullOrder::NullsLast) | (false, true, NullOrder::NullsFirst) => {
+                Ordering::Greater
+            }
+            (false, false, _) => a.cmp(b),
+        }
+    }
+
+    /// Compare `a` to `b` in the order given by `order_type`, placing `NULL` values according to
+    /// this [`NullOrder`]
+    pub fn compare_ordered(self, order_type: OrderType, a: &DataType, b: &DataType) -> Ordering {
+        match order_type {
+            OrderType::OrderAscending => self.compare(a, b),
+            OrderType::OrderDescending => self.compare(b, a),
+        }
+    }
+
+    /// Compare the rows `a` and `b` by the columns and directions in `order`, placing `NULL`
+    /// values according to this [`NullOrder`]
+    pub fn compare_rows(
+        self,
+        order: &[(usize, OrderType)],
+        a: &[DataType],
+        b: &[DataType],
+    ) -> Ordering {
+        order
+            .iter()
+            .map(|&(idx, order_type)| self.compare_ordered(order_type, &a[idx], &b[idx]))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Returns the result of `a AND b` in three-valued logic: false if either side is false, `NULL` if
+/// either side is `NULL` and neither is false, and true otherwise
+pub fn and(a: &DataType, b: &DataType) -> DataType {
+    match (a.is_none(), b.is_none()) {
+        (false, false) => (a.is_truthy() && b.is_truthy()).into(),
+        (true, false) if !b.is_truthy() => false.into(),
+        (false, true) if !a.is_truthy() => false.into(),
+        _ => DataType::None,
+    }
+}
+
+/// Returns the result of `a OR b` in three-valued logic: true if either side is true, `NULL` if
+/// either side is `NULL` and neither is true, and false otherwise
+pub fn or(a: &DataType, b: &DataType) -> DataType {
+    match (a.is_none(), b.is_none()) {
+        (false, false) => (a.is_truthy() || b.is_truthy()).into(),
+        (true, false) if b.is_truthy() => true.into(),
+        (false, true) if a.is_truthy() => true.into(),
+        _ => DataType::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(null_order: NullOrder, order_type: OrderType) -> Vec<DataType> {
+        let mut vals = vec![DataType::from(2), DataType::None, DataType::from(1)];
+        vals.sort_by(|a, b| null_order.compare_ordered(order_type, a, b));
+        vals
+    }
+
+    // Expected results in these tests are from MySQL 8.0 and PostgreSQL 15
+
+    #[test]
+    fn mysql_order() {
+        let null_order = NullOrder::from(Dialect::MySQL);
+        assert_eq!(
+            sorted(null_order, OrderType::OrderAscending),
+            vec![DataType::None, 1.into(), 2.into()]
+        );
+        assert_eq!(
+            sorted(null_order, OrderType::OrderDescending),
+            vec![2.into(), 1.into(), DataType::None]
+        );
+    }
+
+    #[test]
+    fn postgres_order() {
+        let null_order = NullOrder::from(Dialect::PostgreSQL);
+        assert_eq!(
+            sorted(null_order, OrderType::OrderAscending),
+            vec![1.into(), 2.into(), DataType::None]
+        );
+        assert_eq!(
+            sorted(null_order, OrderType::OrderDescending),
+            vec![DataType::None, 2.into(), 1.into()]
+        );
+    }
+
+    #[test]
+    fn rows_order_by_each_column_in_turn() {
+        let order = [
+            (0, OrderType::OrderAscending),
+            (1, OrderType::OrderDescending),
+        ];
+        let mut rows = vec![
+            vec![DataType::from(1), DataType::None],
+            vec![DataType::None, DataType::from(1)],
+            vec![DataType::from(1), DataType::from(2)],
+        ];
+        rows.sort_by(|a, b| NullOrder::NullsLast.compare_rows(&order, a, b));
+        assert_eq!(
+            rows,
+            vec![
+                vec![DataType::from(1), DataType::None],
+                vec![DataType::from(1), DataType::from(2)],
+                vec![DataType::None, DataType::from(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn three_valued_logic() {
+        let t = DataType::from(true);
+        let f = DataType::from(false);
+        let n = DataType::None;
+
+        assert_eq!(and(&t, &t), t);
+        assert_eq!(and(&t, &f), f);
+        assert_eq!(and(&n, &f), f);
+        assert_eq!(and(&f, &n), f);
+        assert_eq!(and(&n, &t), n);
+        assert_eq!(and(&n, &n), n);
+
+        assert_eq!(or(&f, &f), f);
+        assert_eq!(or(&t, &f), t);
+        assert_eq!(or(&n, &t), t);
+        assert_eq!(or(&t, &n), t);
+        assert_eq!(or(&n, &f), n);
+        assert_eq!(or(&n, &n), n);
+    }
+}
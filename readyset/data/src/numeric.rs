@@ -0,0 +1,181 @@
+//! Arithmetic on [`Decimal`] values, with the scale (number of digits after the decimal point) of
+//! each result chosen and rounded the way PostgreSQL does for `NUMERIC` values, so that results are
+//! identical to the ones the upstream database would return.
+//!
+//! [`DataType::Numeric`](crate::DataType::Numeric) values only ever come from PostgreSQL (MySQL's
+//! `DECIMAL` columns are mapped to other types), so PostgreSQL's rules are the only ones
+//! implemented here. MySQL's rules only differ for division.
+
+use readyset_errors::{ReadySetError, ReadySetResult};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// The minimum number of significant digits in the result of a division (`NUMERIC_MIN_SIG_DIGITS`
+/// in PostgreSQL)
+const MIN_SIG_DIGITS: i32 = 16;
+
+/// The number of decimal digits in each digit of PostgreSQL's base-10000 representation of
+/// `NUMERIC` values (`DEC_DIGITS` in PostgreSQL)
+const DEC_DIGITS: i32 = 4;
+
+/// The largest scale a [`Decimal`] can have
+const MAX_SCALE: u32 = 28;
+
+fn overflow(op: &str) -> ReadySetError {
+    ReadySetError::DataTypeConversionError {
+        src_type: "Numeric".to_string(),
+        target_type: "Numeric".to_string(),
+        details: format!("overflow in numeric {}", op),
+    }
+}
+
+/// Returns the weight and value of the most significant digit of `d` in PostgreSQL's base-10000
+/// representation of `NUMERIC` values, or `(0, 0)` if `d` is zero
+fn first_digit(d: &Decimal) -> (i32, u128) {
+    let mantissa = d.mantissa().unsigned_abs();
+    if mantissa == 0 {
+        return (0, 0);
+    }
+    let num_digits = mantissa.to_string().len() as i32;
+    // The power of ten of the most significant decimal digit
+    let exponent = num_digits - 1 - d.scale() as i32;
+    let weight = exponent.div_euclid(DEC_DIGITS);
+    // The number of decimal digits in the most significant base-10000 digit, which can be more
+    // than the number of digits in the mantissa if the value has trailing zeros
+    let digits_in_first = exponent - weight * DEC_DIGITS + 1;
+    let first = if digits_in_first <= num_digits {
+        mantissa / 10u128.pow((num_digits - digits_in_first) as u32)
+    } else {
+        mantissa * 10u128.pow((digits_in_first - num_digits) as u32)
+    };
+    (weight, first)
+}
+
+/// Returns the scale of the result of dividing `a` by `b`, following `select_div_scale` in
+/// PostgreSQL: enough digits to give at least [`MIN_SIG_DIGITS`] significant digits, and no fewer
+/// than the scale of either input
+fn div_scale(a: &Decimal, b: &Decimal) -> u32 {
+    let (weight_a, first_a) = first_digit(a);
+    let (weight_b, first_b) = first_digit(b);
+    let mut quotient_weight = weight_a - weight_b;
+    if first_a <= first_b {
+        quotient_weight -= 1;
+    }
+
+    let scale = (MIN_SIG_DIGITS - quotient_weight * DEC_DIGITS)
+        .max(a.scale() as i32)
+        .max(b.scale() as i32)
+        .max(0) as u32;
+    // Unlike PostgreSQL, we can't represent any more digits than this
+    scale.min(MAX_SCALE)
+}
+
+/// Add `a` and `b`, giving a result with the larger of their scales
+pub(crate) fn add(a: Decimal, b: Decimal) -> ReadySetResult<Decimal> {
+    a.checked_add(b).ok_or_else(|| overflow("addition"))
+}
+
+/// Subtract `b` from `a`, giving a result with the larger of their scales
+pub(crate) fn sub(a: Decimal, b: Decimal) -> ReadySetResult<Decimal> {
+    a.checked_sub(b).ok_or_else(|| overflow("subtraction"))
+}
+
+/// Multiply `a` by `b`, giving a result with the sum of their scales
+pub(crate) fn mul(a: Decimal, b: Decimal) -> ReadySetResult<Decimal> {
+    a.checked_mul(b).ok_or_else(|| overflow("multiplication"))
+}
+
+/// Divide `a` by `b`, giving a result with the scale chosen by [`div_scale`], rounded half away
+/// from zero
+pub(crate) fn div(a: Decimal, b: Decimal) -> ReadySetResult<Decimal> {
+    if b.is_zero() {
+        return Err(ReadySetError::DataTypeConversionError {
+            src_type: "Numeric".to_string(),
+            target_type: "Numeric".to_string(),
+            details: "division by zero".to_string(),
+        });
+    }
+    let scale = div_scale(&a, &b);
+    let mut res = a
+        .checked_div(b)
+        .ok_or_else(|| overflow("division"))?
+        .round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+    // Pad the result with trailing zeros up to the chosen scale, if they fit
+    res.rescale(scale);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn add_sub_keep_larger_scale() {
+        assert_eq!(add(dec("1.10"), dec("2.205")).unwrap().to_string(), "3.305");
+        assert_eq!(add(dec("1.10"), dec("1.10")).unwrap().to_string(), "2.20");
+        assert_eq!(sub(dec("1.10"), dec("1")).unwrap().to_string(), "0.10");
+    }
+
+    #[test]
+    fn mul_sums_scales() {
+        assert_eq!(mul(dec("1.10"), dec("1.10")).unwrap().to_string(), "1.2100");
+        assert_eq!(mul(dec("19.99"), dec("3")).unwrap().to_string(), "59.97");
+    }
+
+    #[test]
+    fn div_matches_postgres() {
+        // Expected values are from PostgreSQL 15
+        assert_eq!(
+            div(dec("1"), dec("3")).unwrap().to_string(),
+            "0.33333333333333333333"
+        );
+        assert_eq!(
+            div(dec("1"), dec("4")).unwrap().to_string(),
+            "0.25000000000000000000"
+        );
+        assert_eq!(
+            div(dec("2"), dec("3")).unwrap().to_string(),
+            "0.66666666666666666667"
+        );
+        assert_eq!(
+            div(dec("10"), dec("4")).unwrap().to_string(),
+            "2.5000000000000000"
+        );
+        assert_eq!(
+            div(dec("100.00"), dec("3")).unwrap().to_string(),
+            "33.3333333333333333"
+        );
+        assert_eq!(
+            div(dec("7"), dec("2.5")).unwrap().to_string(),
+            "2.8000000000000000"
+        );
+        assert_eq!(
+            div(dec("-1"), dec("3")).unwrap().to_string(),
+            "-0.33333333333333333333"
+        );
+        assert_eq!(
+            div(dec("12345678.9"), dec("0.03")).unwrap().to_string(),
+            "411522630.00000000"
+        );
+        assert_eq!(
+            div(dec("0"), dec("3")).unwrap().to_string(),
+            "0.00000000000000000000"
+        );
+    }
+
+    #[test]
+    fn div_by_zero() {
+        assert!(div(dec("1.5"), Decimal::ZERO).is_err());
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        assert!(add(Decimal::MAX, Decimal::ONE).is_err());
+        assert!(mul(Decimal::MAX, dec("2")).is_err());
+    }
+}
@@ -12,7 +12,7 @@ use serde_bytes::{ByteBuf, Bytes};
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames, FromRepr};
 
-use crate::{DataType, Text, TimestampTz, TinyText};
+use crate::{Collation, DataType, Text, TimestampTz, TinyText};
 
 #[derive(EnumVariantNames, EnumString, FromRepr, Clone, Copy)]
 enum Field {
@@ -27,6 +27,9 @@ enum Field {
     BitVector,
     TimestampTz,
     Max,
+    /// Text with a collation other than [`Collation::Binary`], serialized as the collation
+    /// followed by the bytes of the text
+    CollatedText,
 }
 
 enum TextOrTinyText {
@@ -34,6 +37,15 @@ enum TextOrTinyText {
     TinyText(TinyText),
 }
 
+impl From<TextOrTinyText> for DataType {
+    fn from(tt: TextOrTinyText) -> Self {
+        match tt {
+            TextOrTinyText::TinyText(tt) => DataType::TinyText(tt),
+            TextOrTinyText::Text(t) => DataType::Text(t),
+        }
+    }
+}
+
 #[inline(always)]
 fn serialize_variant<S, T>(serializer: S, variant: Field, value: &T) -> Result<S::Ok, S::Error>
 where
@@ -61,11 +73,19 @@ impl serde::ser::Serialize for DataType {
             DataType::UnsignedInt(v) => serialize_variant(serializer, Field::Int, &i128::from(*v)),
             DataType::Float(f) => serialize_variant(serializer, Field::Float, &f.to_bits()),
             DataType::Double(f) => serialize_variant(serializer, Field::Double, &f.to_bits()),
-            DataType::Text(v) => {
-                serialize_variant(serializer, Field::Text, Bytes::new(v.as_bytes()))
-            }
-            DataType::TinyText(v) => {
-                serialize_variant(serializer, Field::Text, Bytes::new(v.as_bytes()))
+            DataType::Text(..) | DataType::TinyText(..) => {
+                // this unwrap should be safe because no error path in try_from for &str on Text or
+                // TinyText
+                #[allow(clippy::unwrap_used)]
+                let bytes = Bytes::new(<&str>::try_from(self).unwrap().as_bytes());
+                match self.collation().unwrap_or_default() {
+                    Collation::Binary => serialize_variant(serializer, Field::Text, bytes),
+                    collation => serialize_variant(
+                        serializer,
+                        Field::CollatedText,
+                        &(collation as u8, bytes),
+                    ),
+                }
             }
             DataType::Time(v) => serialize_variant(serializer, Field::Time, &v),
             DataType::ByteArray(a) => {
@@ -110,7 +130,7 @@ impl<'de> Deserialize<'de> for DataType {
                 } else {
                     Err(serde::de::Error::invalid_value(
                         serde::de::Unexpected::Unsigned(val),
-                        &"variant index 0 <= i < 11",
+                        &"variant index 0 <= i < 12",
                     ))
                 }
             }
@@ -178,12 +198,21 @@ impl<'de> Deserialize<'de> for DataType {
                     (Field::Numeric, variant) => VariantAccess::newtype_variant::<Decimal>(variant)
                         .map(|d| DataType::Numeric(Arc::new(d))),
                     (Field::Text, variant) => {
-                        VariantAccess::newtype_variant::<TextOrTinyText>(variant).map(|tt| match tt
-                        {
-                            TextOrTinyText::TinyText(tt) => DataType::TinyText(tt),
-                            TextOrTinyText::Text(t) => DataType::Text(t),
-                        })
+                        VariantAccess::newtype_variant::<TextOrTinyText>(variant).map(Into::into)
                     }
+                    (Field::CollatedText, variant) => VariantAccess::newtype_variant::<(
+                        u8,
+                        TextOrTinyText,
+                    )>(variant)
+                    .and_then(|(collation, tt)| {
+                        let collation = Collation::from_repr(collation).ok_or_else(|| {
+                            serde::de::Error::invalid_value(
+                                serde::de::Unexpected::Unsigned(collation as _),
+                                &"collation",
+                            )
+                        })?;
+                        Ok(DataType::from(tt).with_collation(collation))
+                    }),
                     (Field::Time, variant) => {
                         VariantAccess::newtype_variant::<MysqlTime>(variant).map(DataType::Time)
                     }
@@ -10,21 +10,32 @@ use std::sync::atomic::Ordering::Relaxed;
 use nom_sql::SqlType;
 use readyset_errors::{ReadySetError, ReadySetResult};
 
-use crate::DataType;
+use crate::{Collation, DataType};
 
 const TINYTEXT_WIDTH: usize = 14;
 
+/// The bits of [`TinyText::len`] that hold the length of the string. The remaining bits hold its
+/// [`Collation`].
+const TINYTEXT_LEN_MASK: u8 = 0x0F;
+
 /// An optimized storage for very short strings
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct TinyText {
     len: u8,
     t: [u8; TINYTEXT_WIDTH],
 }
 
+/// The header stored alongside the bytes of a [`Text`]
+struct TextHeader {
+    /// Whether the bytes have been validated as UTF-8
+    validated: AtomicBool,
+    collation: Collation,
+}
+
 /// A thin pointer over an Arc<[u8]> with lazy UTF-8 validation
 #[repr(transparent)]
 #[derive(Clone)]
-pub struct Text(triomphe::ThinArc<AtomicBool, u8>);
+pub struct Text(triomphe::ThinArc<TextHeader, u8>);
 
 impl TinyText {
     /// Extracts a string slice containing the entire `TinyText`.
@@ -37,7 +48,20 @@ impl TinyText {
     /// Extract the underlying slice
     #[inline]
     pub fn as_bytes(&self) -> &[u8] {
-        &self.t[..self.len as usize]
+        &self.t[..(self.len & TINYTEXT_LEN_MASK) as usize]
+    }
+
+    /// Returns the collation used to compare this `TinyText` to other strings
+    #[inline]
+    pub fn collation(&self) -> Collation {
+        Collation::from_repr(self.len >> 4).unwrap_or_default()
+    }
+
+    /// Returns this `TinyText` with its collation set to `collation`
+    #[inline]
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.len = (self.len & TINYTEXT_LEN_MASK) | ((collation as u8) << 4);
+        self
     }
 
     /// A convenience method to create constant ASCII `TinyText`
@@ -119,12 +143,12 @@ impl Text {
     #[inline]
     pub fn as_str(&self) -> &str {
         // Check if already validated
-        if self.0.header.header.load(Relaxed) {
+        if self.0.header.header.validated.load(Relaxed) {
             // SAFETY: Safe because we checked validation flag
             unsafe { std::str::from_utf8_unchecked(self.as_bytes()) }
         } else {
             let validated = std::str::from_utf8(self.as_bytes()).expect("Must always be UTF8");
-            self.0.header.header.store(true, Relaxed);
+            self.0.header.header.validated.store(true, Relaxed);
             validated
         }
     }
@@ -135,10 +159,35 @@ impl Text {
     #[inline]
     pub fn from_slice_unchecked(v: &[u8]) -> Self {
         Self(triomphe::ThinArc::from_header_and_slice(
-            AtomicBool::new(false),
+            TextHeader {
+                validated: AtomicBool::new(false),
+                collation: Collation::Binary,
+            },
             v,
         ))
     }
+
+    /// Returns the collation used to compare this `Text` to other strings
+    #[inline]
+    pub fn collation(&self) -> Collation {
+        self.0.header.header.collation
+    }
+
+    /// Returns this `Text` with its collation set to `collation`. Since the collation is shared
+    /// between all clones of a `Text`, this copies the underlying bytes unless the collation is
+    /// unchanged.
+    pub fn with_collation(self, collation: Collation) -> Self {
+        if self.collation() == collation {
+            return self;
+        }
+        Self(triomphe::ThinArc::from_header_and_slice(
+            TextHeader {
+                validated: AtomicBool::new(self.0.header.header.validated.load(Relaxed)),
+                collation,
+            },
+            self.as_bytes(),
+        ))
+    }
 }
 
 impl TryFrom<&[u8]> for Text {
@@ -152,7 +201,10 @@ impl TryFrom<&[u8]> for Text {
 impl From<&str> for Text {
     fn from(t: &str) -> Self {
         Self(triomphe::ThinArc::from_header_and_slice(
-            AtomicBool::new(true),
+            TextHeader {
+                validated: AtomicBool::new(true),
+                collation: Collation::Binary,
+            },
             t.as_bytes(),
         ))
     }
@@ -166,18 +218,40 @@ impl PartialOrd for Text {
 
 impl PartialEq for Text {
     fn eq(&self, other: &Self) -> bool {
-        self.as_str() == other.as_str()
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl Ord for Text {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.as_str().cmp(other.as_str())
+        self.collation()
+            .compare_exact(self.as_str(), other.collation(), other.as_str())
     }
 }
 
 impl Eq for Text {}
 
+impl PartialOrd for TinyText {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TinyText {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Ord for TinyText {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.collation()
+            .compare_exact(self.as_str(), other.collation(), other.as_str())
+    }
+}
+
+impl Eq for TinyText {}
+
 impl fmt::Debug for Text {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self.as_str())
@@ -412,9 +486,9 @@ pub(crate) trait TextCoerce: Sized + Clone + Into<DataType> {
                 .map_err(|e| Self::coerce_err(sql_type, e))?
                 .into()),
 
-            SqlType::Enum(_) | SqlType::Bit(_) | SqlType::Varbit(_) => {
-                Err(Self::coerce_err(sql_type, "Not allowed"))
-            }
+            SqlType::Enum(variants) => Ok(crate::enums::coerce_str(str, variants)),
+
+            SqlType::Bit(_) | SqlType::Varbit(_) => Err(Self::coerce_err(sql_type, "Not allowed")),
         }
     }
 }
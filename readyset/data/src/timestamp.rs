@@ -71,9 +71,9 @@ impl TimestampTz {
         self.extra[2] & 0b_10 != 0
     }
 
-    /// Returns true if timezone should be displayed
+    /// Returns true if this timestamp has a time zone (i.e. it's a `TIMESTAMP WITH TIME ZONE`)
     #[inline(always)]
-    fn has_timezone(&self) -> bool {
+    pub fn has_timezone(&self) -> bool {
         self.extra[2] & TimestampTz::TIMEZONE_FLAG != 0
     }
 
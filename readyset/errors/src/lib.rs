@@ -67,6 +67,23 @@ pub enum ReadySetError {
     #[error("No query known by id {id}")]
     NoQueryForId { id: String },
 
+    /// The adapter received a `FETCH` or `CLOSE` statement for a cursor that does not exist
+    #[error("Cursor {name} does not exist")]
+    CursorNotFound { name: String },
+
+    /// The adapter received a `DECLARE` statement for a cursor that already exists
+    #[error("Cursor {name} already exists")]
+    CursorAlreadyExists { name: String },
+
+    /// The adapter received an `EXECUTE` or `DEALLOCATE` statement for a prepared statement that
+    /// does not exist
+    #[error("Prepared statement {name} does not exist")]
+    PreparedStatementNotFound { name: String },
+
+    /// The adapter received a `PREPARE` statement for a prepared statement that already exists
+    #[error("Prepared statement {name} already exists")]
+    PreparedStatementAlreadyExists { name: String },
+
     /// The adapter will return this error on any set statement that is not
     /// explicitly allowed.
     #[error("Set statement disallowed: {}", Sensitive(statement))]
@@ -104,6 +121,19 @@ pub enum ReadySetError {
         source: Box<ReadySetError>,
     },
 
+    /// The state of the nodes a migration would add is estimated to use more memory than the
+    /// configured budget for a single migration.
+    #[error(
+        "Migration is estimated to use {estimated_bytes} bytes of memory, which exceeds the \
+         budget of {budget_bytes} bytes"
+    )]
+    MigrationMemoryBudgetExceeded {
+        /// The estimated memory used by the state of the nodes the migration would add.
+        estimated_bytes: u64,
+        /// The configured memory budget for a single migration.
+        budget_bytes: u64,
+    },
+
     /// A migration failed to apply. The dataflow graph may be in an inconsistent state.
     #[error("Failed to apply migration: {source}")]
     MigrationApplyFailed {
@@ -242,6 +272,14 @@ pub enum ReadySetError {
     #[error("Upquery timeout")]
     UpqueryTimeout,
 
+    /// Timed out waiting for writes to propagate through the dataflow graph to every reader.
+    #[error("Timed out waiting for writes to propagate to readers")]
+    FlushTimeout,
+
+    /// Timed out waiting for a connection from the upstream connection pool.
+    #[error("Timed out waiting for a connection to the upstream database")]
+    UpstreamPoolTimeout,
+
     /// The query specified an empty lookup key.
     #[error("the query specified an empty lookup key")]
     EmptyKey,
@@ -250,6 +288,11 @@ pub enum ReadySetError {
     #[error("the queries lookup key is not found at the reader")]
     ReaderMissingKey,
 
+    /// A cursor over the remaining pages of a result set couldn't be found at the reader, either
+    /// because it was already read in full or because it expired.
+    #[error("cursor {0} not found at the reader")]
+    CursorNotFound(u64),
+
     /// A prepared statement is missing.
     #[error("Prepared statement with ID {statement_id} not found")]
     PreparedStatementMissing {
@@ -614,6 +657,18 @@ impl ReadySetError {
     pub fn caused_by_data_type_conversion(&self) -> bool {
         self.any_cause(|e| matches!(e, Self::DataTypeConversionError { .. }))
     }
+
+    /// Returns the name of the function that doesn't exist, if the error either *is*
+    /// [`NoSuchFunction`], or was *caused by* [`NoSuchFunction`]
+    pub fn no_such_function(&self) -> Option<&str> {
+        match self {
+            Self::NoSuchFunction(name) => Some(name),
+            _ => self
+                .source()
+                .and_then(|e| e.downcast_ref::<Box<ReadySetError>>())
+                .and_then(|e| e.no_such_function()),
+        }
+    }
 }
 
 /// Make a new [`ReadySetError::Internal`] with the provided string-able argument.
@@ -936,4 +991,16 @@ mod test {
         };
         assert!(err.caused_by_unsupported());
     }
+
+    #[test]
+    fn no_such_function_two_deep() {
+        let err = ReadySetError::RpcFailed {
+            during: "test".to_owned(),
+            source: Box::new(ReadySetError::MigrationPlanFailed {
+                source: Box::new(ReadySetError::NoSuchFunction("foo".to_owned())),
+            }),
+        };
+        assert_eq!(err.no_such_function(), Some("foo"));
+        assert_eq!(ReadySetError::NoQuorum.no_such_function(), None);
+    }
 }
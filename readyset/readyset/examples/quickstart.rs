@@ -3,6 +3,16 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use readyset::consensus::Authority;
 use readyset::{ControllerHandle, ZookeeperAuthority};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct ArticleWithVoteCount {
+    aid: i32,
+    title: String,
+    url: String,
+    votes: Option<i64>,
+}
 
 #[tokio::main]
 async fn main() {
@@ -66,6 +76,6 @@ async fn main() {
     tokio::time::sleep(Duration::from_millis(1000)).await;
 
     println!("Reading...");
-    let article = awvc.lookup(&[aid.into()], true).await.unwrap();
+    let article: Vec<ArticleWithVoteCount> = awvc.lookup_as(&[aid.into()], true).await.unwrap();
     println!("{:#?}", article);
 }
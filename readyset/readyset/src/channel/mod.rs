@@ -294,6 +294,20 @@ impl<K: Eq + Hash + Clone, T> ChannelCoordinator<K, T> {
         }
     }
 
+    /// Remove both the remote address and the local channel for `key`, if any
+    pub fn remove<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        #[allow(clippy::expect_used)]
+        // This can only fail if the mutex is poisoned, in which case we can't recover,
+        // so we allow to panic if that happens.
+        let mut guard = self.inner.write().expect("poisoned mutex");
+        guard.addrs.remove(key);
+        guard.locals.remove(key);
+    }
+
     pub fn clear(&self) {
         let mut guard = self.inner.write().expect("poisoned mutex");
         guard.addrs.clear();
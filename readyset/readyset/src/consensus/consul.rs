@@ -429,7 +429,8 @@ impl ConsulAuthority {
     }
 
     fn prefix_with_deployment(&self, path: &str) -> String {
-        format!("{}/{}", &self.deployment, path)
+        // Paths used with other authorities are absolute, so accept (and ignore) a leading slash
+        format!("{}/{}", &self.deployment, path.trim_start_matches('/'))
     }
 
     #[cfg(test)]
@@ -689,6 +690,45 @@ impl AuthorityControl for ConsulAuthority {
         }
     }
 
+    async fn depose_leader(&self, leader: &LeaderPayload) -> Result<bool, Error> {
+        let kv_pair = match kv::read(
+            &self.consul,
+            &self.prefix_with_deployment(CONTROLLER_KEY),
+            None,
+        )
+        .await
+        {
+            Ok(r) => match get_kv_pair(r) {
+                Ok(kv_pair) => kv_pair,
+                Err(_) => return Ok(false),
+            },
+            Err(e) => bail!(ConsulAuthorityError::RequestFailed(e.to_string())),
+        };
+
+        let session = match (kv_pair.session, kv_pair.value) {
+            (Some(session), Some(value)) => {
+                let bytes: Vec<u8> = value.try_into()?;
+                if serde_json::from_slice::<LeaderPayload>(&bytes)? != *leader {
+                    return Ok(false);
+                }
+                session
+            }
+            _ => return Ok(false),
+        };
+
+        // Destroying the leader's session releases its lock on the leader key, and also marks the
+        // leader's worker as failed since it shares the same session. If the leader is still
+        // running, it will fail its next heartbeat and shut down rather than continue acting as
+        // the leader.
+        match consulrs::session::delete(&self.consul, &session, None).await {
+            Ok(_) => {
+                warn!(%session, "deposed unresponsive leader");
+                Ok(true)
+            }
+            Err(e) => bail!(ConsulAuthorityError::RequestFailed(e.to_string())),
+        }
+    }
+
     // Block until there is any leader.
     async fn get_leader(&self) -> Result<LeaderPayload, Error> {
         loop {
@@ -997,6 +1037,14 @@ impl AuthorityControl for ConsulAuthority {
         }
     }
 
+    async fn adapter_heartbeat(
+        &self,
+        id: AdapterId,
+    ) -> Result<AuthorityWorkerHeartbeatResponse, Error> {
+        // Adapters hold their key with the same kind of session as workers.
+        self.worker_heartbeat(id).await
+    }
+
     async fn get_adapters(&self) -> Result<HashSet<SocketAddr>, Error> {
         let adapter_ids: HashSet<AdapterId> = match kv::read(
             &self.consul,
@@ -1150,6 +1198,43 @@ mod tests {
         assert_eq!(&authority_3.get_leader().await.unwrap(), &payload_3);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn depose_leader() {
+        let authority_address = test_authority_address("depose_leader");
+        let authority = Arc::new(ConsulAuthority::new(&authority_address).unwrap());
+        authority.init().await.unwrap();
+        authority.delete_all_keys().await;
+
+        let payload = LeaderPayload {
+            controller_uri: url::Url::parse("http://127.0.0.1:2181").unwrap(),
+            nonce: 1,
+        };
+        authority.become_leader(payload.clone()).await.unwrap();
+
+        let authority_2 = Arc::new(ConsulAuthority::new(&authority_address).unwrap());
+        authority_2.init().await.unwrap();
+        let payload_2 = LeaderPayload {
+            controller_uri: url::Url::parse("http://127.0.0.1:2182").unwrap(),
+            nonce: 2,
+        };
+
+        // Deposing a node that isn't the leader does nothing.
+        assert!(!authority_2.depose_leader(&payload_2).await.unwrap());
+        assert_eq!(&authority.get_leader().await.unwrap(), &payload);
+
+        assert!(authority_2.depose_leader(&payload).await.unwrap());
+        assert_eq!(
+            authority_2.try_get_leader().await.unwrap(),
+            GetLeaderResult::NoLeader
+        );
+        assert_eq!(
+            authority_2.become_leader(payload_2.clone()).await.unwrap(),
+            Some(payload_2.clone())
+        );
+        assert_eq!(&authority_2.get_leader().await.unwrap(), &payload_2);
+    }
+
     #[tokio::test]
     #[serial]
     async fn retrieve_workers() {
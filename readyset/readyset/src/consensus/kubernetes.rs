@@ -0,0 +1,1227 @@
+//! # Kubernetes Authority
+//! An authority backed by the Kubernetes API, so that deployments running on Kubernetes can elect a
+//! leader and store the controller state without also running Consul or ZooKeeper.
+//!
+//! The authority talks to the API server directly over HTTP. When running inside a pod it uses the
+//! in-cluster API server and the pod's service account, which needs permission to get, list,
+//! create, update and delete `leases` (in the `coordination.k8s.io` API group) and `configmaps` in
+//! its namespace. The namespace is read from the `POD_NAMESPACE` environment variable, falling back
+//! to the namespace of the service account.
+//!
+//! ## Objects
+//! Every object is named with the prefix `readyset-<deployment>-` and labelled with
+//! `readyset.io/deployment=<deployment>`.
+//!
+//! | Object | Description |
+//! | ------ | ----------- |
+//! | Lease `controller` | held by the leader; with its [`LeaderPayload`] and [`StateVersion`] |
+//! | Lease `worker-<id>` | held by each worker; annotated with its [`WorkerDescriptor`] |
+//! | Lease `adapter-<id>` | held by each adapter; annotated with its http endpoint |
+//! | ConfigMap `kv` | the values written with [`AuthorityControl::read_modify_write`] |
+//! | ConfigMaps `state-<version>-<n>` | chunk `n` of version `<version>` of the controller state |
+//!
+//! Leases are held for [`LEASE_DURATION`] after they were last renewed, and are renewed on every
+//! call to [`AuthorityControl::worker_heartbeat`]. Writes to leases and to the `kv` ConfigMap use
+//! the object's `resourceVersion`, so they only succeed if nobody else modified the object since
+//! it was read.
+//!
+//! ## Controller state
+//! ConfigMaps are limited to 1 MiB, so like in Consul the serialized and compressed controller
+//! state is split across several chunks. Each update writes the new state to chunks under a new,
+//! unique version, and then points the controller lease at that version. Since updating the lease
+//! fails if it has been modified since we checked that we hold it, the new state only becomes
+//! visible if we were still the leader when it was written. Unlike Consul, a leader that has lost
+//! leadership may still write chunks, but never to a version that is referenced by the lease.
+//! Once the lease points at the new version, the chunks of the previous version are deleted.
+//!
+//! ## Limitations
+//! Expired worker and adapter leases, and the chunks written by leaders which lost leadership
+//! partway through an update, are never deleted.
+//!
+//! Every value written with [`AuthorityControl::read_modify_write`] is stored in the single `kv`
+//! ConfigMap, so all of them together must fit in [`MAX_CONFIG_MAP_SIZE`]. Writes that would grow
+//! the ConfigMap past that fail with an error rather than being rejected by the API server.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{bail, Error};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use futures::stream::FuturesOrdered;
+use futures::TryStreamExt;
+use metrics::gauge;
+use parking_lot::Mutex;
+use readyset_errors::internal_err;
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
+use tracing::{error, warn};
+use url::Url;
+
+use super::{
+    AdapterId, AuthorityControl, AuthorityWorkerHeartbeatResponse, GetLeaderResult, LeaderPayload,
+    WorkerDescriptor, WorkerId,
+};
+use crate::metrics::recorded;
+use crate::ReadySetResult;
+
+/// Directory the service account's credentials are mounted at in every pod.
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+/// Label holding the (sanitized) deployment an object belongs to.
+const DEPLOYMENT_LABEL: &str = "readyset.io/deployment";
+/// Label distinguishing worker leases, adapter leases and controller state chunks.
+const ROLE_LABEL: &str = "readyset.io/role";
+/// Label holding the version of the controller state a chunk belongs to.
+const STATE_VERSION_LABEL: &str = "readyset.io/state-version";
+/// Annotation on the controller lease holding the leader's payload.
+const LEADER_ANNOTATION: &str = "readyset.io/leader";
+/// Annotation on the controller lease holding the current [`StateVersion`].
+const STATE_ANNOTATION: &str = "readyset.io/state";
+/// Annotation on worker and adapter leases holding their descriptor.
+const DESCRIPTOR_ANNOTATION: &str = "readyset.io/descriptor";
+/// The amount of time a lease is held for after it was last renewed.
+const LEASE_DURATION: Duration = Duration::from_secs(20);
+/// The timeout for requests to the API server.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// The size of each chunk of the controller state. ConfigMaps are limited to 1 MiB, and binary
+/// data is base64 encoded.
+const CHUNK_SIZE: usize = 512000;
+/// The key each chunk is stored under in its ConfigMap.
+const CHUNK_KEY: &str = "state";
+/// The maximum size of the (base64 encoded) data in a ConfigMap. The API server rejects objects
+/// over 1 MiB, so this leaves some room for the object's metadata.
+const MAX_CONFIG_MAP_SIZE: usize = 1_000_000;
+
+/// Errors returned by the Kubernetes Authority.
+#[derive(ThisError, Debug)]
+enum KubernetesAuthorityError {
+    /// An object or value that was expected to exist does not.
+    #[error("Authority returned no value for {0}")]
+    EmptyReadResponse(String),
+
+    /// The authority tried to perform a write requiring leadership, but does not hold the
+    /// controller lease.
+    #[error("An authority that has lost leadership attempted to issue a write")]
+    WriteIssuedFromLostLeader,
+
+    /// The request to the API server failed, or returned an unexpected status.
+    #[error("Error issuing request {0}")]
+    RequestFailed(String),
+
+    /// Decompressing the controller state failed.
+    #[error("Error during (de)compression")]
+    CompressionFailed,
+
+    /// Writing a value would grow the `kv` ConfigMap past [`MAX_CONFIG_MAP_SIZE`].
+    #[error(
+        "Writing {path} would grow the kv ConfigMap to {size} bytes, but ConfigMaps are limited \
+         to {} bytes",
+        MAX_CONFIG_MAP_SIZE
+    )]
+    ValueTooLarge { path: String, size: usize },
+}
+
+/// Serializes timestamps in the `MicroTime` format the Kubernetes API expects.
+mod micro_time {
+    use chrono::{DateTime, Utc};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6fZ";
+
+    pub(super) fn serialize<S>(time: &Option<DateTime<Utc>>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match time {
+            Some(time) => s.serialize_some(&time.format(FORMAT).to_string()),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D>(d: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(d)?
+            .map(|time| {
+                DateTime::parse_from_rfc3339(&time)
+                    .map(|time| time.with_timezone(&Utc))
+                    .map_err(D::Error::custom)
+            })
+            .transpose()
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectMeta {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resource_version: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LeaseSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    holder_identity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lease_duration_seconds: Option<i64>,
+    #[serde(default, with = "micro_time", skip_serializing_if = "Option::is_none")]
+    acquire_time: Option<DateTime<Utc>>,
+    #[serde(default, with = "micro_time", skip_serializing_if = "Option::is_none")]
+    renew_time: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lease_transitions: Option<i64>,
+}
+
+/// A `coordination.k8s.io/v1` Lease.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Lease {
+    api_version: String,
+    kind: String,
+    metadata: ObjectMeta,
+    #[serde(default)]
+    spec: LeaseSpec,
+}
+
+impl Lease {
+    fn new(metadata: ObjectMeta) -> Self {
+        Self {
+            api_version: "coordination.k8s.io/v1".to_owned(),
+            kind: "Lease".to_owned(),
+            metadata,
+            spec: LeaseSpec::default(),
+        }
+    }
+
+    /// Returns the identity of the holder of the lease, or `None` if the lease is not held or has
+    /// expired.
+    fn holder(&self, now: DateTime<Utc>) -> Option<&str> {
+        let holder = self
+            .spec
+            .holder_identity
+            .as_deref()
+            .filter(|holder| !holder.is_empty())?;
+        let renewed = self.spec.renew_time.or(self.spec.acquire_time)?;
+        let duration = self
+            .spec
+            .lease_duration_seconds
+            .unwrap_or(LEASE_DURATION.as_secs() as i64);
+        if renewed + chrono::Duration::seconds(duration) < now {
+            None
+        } else {
+            Some(holder)
+        }
+    }
+
+    fn acquire(&mut self, identity: &str, now: DateTime<Utc>) {
+        self.spec.holder_identity = Some(identity.to_owned());
+        self.spec.lease_duration_seconds = Some(LEASE_DURATION.as_secs() as i64);
+        self.spec.acquire_time = Some(now);
+        self.spec.renew_time = Some(now);
+        self.spec.lease_transitions = Some(self.spec.lease_transitions.unwrap_or(0) + 1);
+    }
+
+    fn release(&mut self) {
+        self.spec.holder_identity = None;
+    }
+}
+
+/// A `v1` ConfigMap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigMap {
+    api_version: String,
+    kind: String,
+    metadata: ObjectMeta,
+    /// Base64 encoded values.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    binary_data: BTreeMap<String, String>,
+}
+
+impl ConfigMap {
+    fn new(metadata: ObjectMeta) -> Self {
+        Self {
+            api_version: "v1".to_owned(),
+            kind: "ConfigMap".to_owned(),
+            metadata,
+            binary_data: BTreeMap::new(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.binary_data.get(key).map(base64::decode).transpose()?)
+    }
+
+    fn set(&mut self, key: String, value: &[u8]) {
+        self.binary_data.insert(key, base64::encode(value));
+    }
+
+    /// The size of the keys and (encoded) values stored in the ConfigMap.
+    fn data_size(&self) -> usize {
+        self.binary_data
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ObjectList<T> {
+    items: Vec<T>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct StateVersion {
+    version: String,
+    num_chunks: usize,
+}
+
+/// Converts `name` into something that can be used in the name of a Kubernetes object and as the
+/// value of a label: lowercase alphanumeric characters and `-`.
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_owned()
+}
+
+/// Converts a path passed to [`AuthorityControl::try_read`] and
+/// [`AuthorityControl::read_modify_write`] into a valid ConfigMap key, which may only contain
+/// alphanumeric characters, `-`, `_` and `.`. Every other character (including `.` itself) is
+/// escaped as `.` followed by its hex code.
+fn path_to_key(path: &str) -> String {
+    let mut key = String::with_capacity(path.len());
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+            key.push(b as char);
+        } else {
+            key.push_str(&format!(".{:02x}", b));
+        }
+    }
+    key
+}
+
+/// A minimal client for the parts of the Kubernetes API used by the authority.
+struct KubernetesClient {
+    http: reqwest::Client,
+    api_server: Url,
+    /// The file the bearer token is read from. Service account tokens are rotated, so this is
+    /// re-read on every request.
+    token_path: Option<PathBuf>,
+    namespace: String,
+}
+
+impl KubernetesClient {
+    fn new(api_server: Option<Url>) -> ReadySetResult<Self> {
+        let service_account = PathBuf::from(SERVICE_ACCOUNT_DIR);
+        let mut builder = reqwest::Client::builder().timeout(REQUEST_TIMEOUT);
+        let api_server = match api_server {
+            Some(url) => url,
+            None => {
+                let host = std::env::var("KUBERNETES_SERVICE_HOST")
+                    .map_err(|_| internal_err("KUBERNETES_SERVICE_HOST is not set"))?;
+                let port = std::env::var("KUBERNETES_SERVICE_PORT")
+                    .map_err(|_| internal_err("KUBERNETES_SERVICE_PORT is not set"))?;
+                let host = if host.contains(':') {
+                    format!("[{}]", host)
+                } else {
+                    host
+                };
+                let ca = std::fs::read(service_account.join("ca.crt"))
+                    .map_err(|e| internal_err(format!("Failed to read cluster CA: {}", e)))?;
+                builder = builder.add_root_certificate(
+                    reqwest::Certificate::from_pem(&ca)
+                        .map_err(|e| internal_err(format!("Invalid cluster CA: {}", e)))?,
+                );
+                Url::parse(&format!("https://{}:{}", host, port))
+                    .map_err(|e| internal_err(format!("Invalid API server address: {}", e)))?
+            }
+        };
+
+        let token_path = service_account.join("token");
+        let namespace = match std::env::var("POD_NAMESPACE") {
+            Ok(namespace) => namespace,
+            Err(_) => std::fs::read_to_string(service_account.join("namespace"))
+                .map(|namespace| namespace.trim().to_owned())
+                .unwrap_or_else(|_| "default".to_owned()),
+        };
+
+        Ok(Self {
+            http: builder
+                .build()
+                .map_err(|e| internal_err(format!("Failed to build http client: {}", e)))?,
+            api_server,
+            token_path: token_path.exists().then(|| token_path),
+            namespace,
+        })
+    }
+
+    fn url(&self, path: String, label_selector: Option<&str>) -> Result<Url, Error> {
+        let mut url = self.api_server.join(&path)?;
+        if let Some(selector) = label_selector {
+            url.query_pairs_mut().append_pair("labelSelector", selector);
+        }
+        Ok(url)
+    }
+
+    fn lease_url(&self, name: Option<&str>, label_selector: Option<&str>) -> Result<Url, Error> {
+        let mut path = format!(
+            "/apis/coordination.k8s.io/v1/namespaces/{}/leases",
+            self.namespace
+        );
+        if let Some(name) = name {
+            path = path + "/" + name;
+        }
+        self.url(path, label_selector)
+    }
+
+    fn config_map_url(
+        &self,
+        name: Option<&str>,
+        label_selector: Option<&str>,
+    ) -> Result<Url, Error> {
+        let mut path = format!("/api/v1/namespaces/{}/configmaps", self.namespace);
+        if let Some(name) = name {
+            path = path + "/" + name;
+        }
+        self.url(path, label_selector)
+    }
+
+    async fn request<B: Serialize>(
+        &self,
+        method: Method,
+        url: Url,
+        body: Option<&B>,
+    ) -> Result<reqwest::Response, Error> {
+        let mut request = self.http.request(method, url);
+        if let Some(token_path) = &self.token_path {
+            let token = tokio::fs::read_to_string(token_path).await?;
+            request = request.bearer_auth(token.trim());
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| KubernetesAuthorityError::RequestFailed(e.to_string()).into())
+    }
+
+    /// Returns the body of `response` if it was successful, `None` if its status was
+    /// `allowed_failure`, and an error otherwise.
+    async fn parse_response<T: DeserializeOwned>(
+        response: reqwest::Response,
+        allowed_failure: StatusCode,
+    ) -> Result<Option<T>, Error> {
+        let status = response.status();
+        if status.is_success() {
+            Ok(Some(response.json().await?))
+        } else if status == allowed_failure {
+            Ok(None)
+        } else {
+            bail!(KubernetesAuthorityError::RequestFailed(format!(
+                "{}: {}",
+                status,
+                response.text().await.unwrap_or_default()
+            )))
+        }
+    }
+
+    /// Returns the object at `url`, or `None` if it does not exist.
+    async fn get<T: DeserializeOwned>(&self, url: Url) -> Result<Option<T>, Error> {
+        let response = self.request::<()>(Method::GET, url, None).await?;
+        Self::parse_response(response, StatusCode::NOT_FOUND).await
+    }
+
+    /// Returns the objects in the collection at `url`.
+    async fn list<T: DeserializeOwned>(&self, url: Url) -> Result<Vec<T>, Error> {
+        Ok(self
+            .get::<ObjectList<T>>(url)
+            .await?
+            .map(|list| list.items)
+            .unwrap_or_default())
+    }
+
+    /// Creates `object` in the collection at `url`, returning `None` if it already exists.
+    async fn create<T: Serialize + DeserializeOwned>(
+        &self,
+        url: Url,
+        object: &T,
+    ) -> Result<Option<T>, Error> {
+        let response = self.request(Method::POST, url, Some(object)).await?;
+        Self::parse_response(response, StatusCode::CONFLICT).await
+    }
+
+    /// Replaces the object at `url` with `object`, returning `None` if the object has been
+    /// modified since the `resourceVersion` in `object` was read.
+    async fn replace<T: Serialize + DeserializeOwned>(
+        &self,
+        url: Url,
+        object: &T,
+    ) -> Result<Option<T>, Error> {
+        let response = self.request(Method::PUT, url, Some(object)).await?;
+        Self::parse_response(response, StatusCode::CONFLICT).await
+    }
+
+    /// Deletes the object at `url`.
+    async fn delete(&self, url: Url) -> Result<(), Error> {
+        let response = self.request::<()>(Method::DELETE, url, None).await?;
+        Self::parse_response::<serde_json::Value>(response, StatusCode::NOT_FOUND).await?;
+        Ok(())
+    }
+}
+
+/// Coordinator that shares connection information between workers and clients using Kubernetes
+/// Leases and ConfigMaps.
+pub struct KubernetesAuthority {
+    client: KubernetesClient,
+
+    /// Deployment associated with this authority, sanitized by [`sanitize_name`].
+    deployment: String,
+
+    /// The identity this authority holds leases with, which is also its worker id.
+    identity: String,
+
+    /// The holder of the controller lease and the time they acquired it, the last time we saw a
+    /// leader.
+    known_leader: Mutex<Option<(String, Option<DateTime<Utc>>)>>,
+}
+
+impl KubernetesAuthority {
+    /// Create a new instance. If `api_server` is an http(s) URL, such as the address of
+    /// `kubectl proxy`, it is used as the address of the Kubernetes API server. Otherwise, the
+    /// in-cluster API server and service account are used.
+    pub fn new(api_server: &str, deployment: &str) -> ReadySetResult<Self> {
+        let api_server = if api_server.starts_with("http://") || api_server.starts_with("https://")
+        {
+            Some(
+                Url::parse(api_server)
+                    .map_err(|e| internal_err(format!("Invalid API server address: {}", e)))?,
+            )
+        } else {
+            None
+        };
+
+        Ok(Self {
+            client: KubernetesClient::new(api_server)?,
+            deployment: sanitize_name(deployment),
+            identity: uuid::Uuid::new_v4().to_string(),
+            known_leader: Mutex::new(None),
+        })
+    }
+
+    fn object_name(&self, suffix: &str) -> String {
+        format!("readyset-{}-{}", self.deployment, suffix)
+    }
+
+    fn object_meta(&self, name: String, role: Option<&str>) -> ObjectMeta {
+        let mut labels = BTreeMap::new();
+        labels.insert(DEPLOYMENT_LABEL.to_owned(), self.deployment.clone());
+        if let Some(role) = role {
+            labels.insert(ROLE_LABEL.to_owned(), role.to_owned());
+        }
+        ObjectMeta {
+            name,
+            labels,
+            ..Default::default()
+        }
+    }
+
+    fn role_selector(&self, role: &str) -> String {
+        format!(
+            "{}={},{}={}",
+            DEPLOYMENT_LABEL, self.deployment, ROLE_LABEL, role
+        )
+    }
+
+    fn controller_lease_url(&self) -> Result<Url, Error> {
+        self.client
+            .lease_url(Some(&self.object_name("controller")), None)
+    }
+
+    fn worker_lease_name(&self, id: &str) -> String {
+        self.object_name(&format!("worker-{}", id))
+    }
+
+    fn adapter_lease_name(&self, id: &str) -> String {
+        self.object_name(&format!("adapter-{}", id))
+    }
+
+    fn kv_config_map_url(&self) -> Result<Url, Error> {
+        self.client
+            .config_map_url(Some(&self.object_name("kv")), None)
+    }
+
+    fn state_chunk_name(&self, version: &str, chunk: usize) -> String {
+        self.object_name(&format!("state-{}-{}", version, chunk))
+    }
+
+    fn set_known_leader(&self, lease: &Lease) {
+        *self.known_leader.lock() = lease
+            .spec
+            .holder_identity
+            .clone()
+            .map(|holder| (holder, lease.spec.acquire_time));
+    }
+
+    /// Create the lease `name`, or take it over if it already exists, and annotate it with
+    /// `descriptor`.
+    async fn register_lease<T: Serialize>(
+        &self,
+        name: String,
+        role: &str,
+        descriptor: &T,
+    ) -> Result<(), Error> {
+        let descriptor = serde_json::to_string(descriptor)?;
+        loop {
+            let url = self.client.lease_url(Some(&name), None)?;
+            let registered = match self.client.get::<Lease>(url.clone()).await? {
+                Some(mut lease) => {
+                    lease
+                        .metadata
+                        .annotations
+                        .insert(DESCRIPTOR_ANNOTATION.to_owned(), descriptor.clone());
+                    lease.acquire(&self.identity, Utc::now());
+                    self.client.replace(url, &lease).await?
+                }
+                None => {
+                    let mut lease = Lease::new(self.object_meta(name.clone(), Some(role)));
+                    lease
+                        .metadata
+                        .annotations
+                        .insert(DESCRIPTOR_ANNOTATION.to_owned(), descriptor.clone());
+                    lease.acquire(&self.identity, Utc::now());
+                    self.client
+                        .create(self.client.lease_url(None, None)?, &lease)
+                        .await?
+                }
+            };
+
+            if registered.is_some() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Renew the lease at `url`. Returns false if we do not hold the lease.
+    async fn renew_lease(&self, url: Url) -> Result<bool, Error> {
+        loop {
+            let mut lease = match self.client.get::<Lease>(url.clone()).await? {
+                Some(lease) => lease,
+                None => return Ok(false),
+            };
+            let now = Utc::now();
+            if lease.holder(now) != Some(self.identity.as_str()) {
+                return Ok(false);
+            }
+
+            lease.spec.renew_time = Some(now);
+            if self.client.replace(url.clone(), &lease).await?.is_some() {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Renew our worker lease, and the controller lease if we are the leader. Returns false if we
+    /// no longer hold either of them.
+    async fn renew_leases(&self, id: &str) -> Result<bool, Error> {
+        let worker_url = self
+            .client
+            .lease_url(Some(&self.worker_lease_name(id)), None)?;
+        if !self.renew_lease(worker_url).await? {
+            return Ok(false);
+        }
+
+        let is_leader = self
+            .client
+            .get::<Lease>(self.controller_lease_url()?)
+            .await?
+            .map_or(false, |lease| {
+                lease.spec.holder_identity.as_deref() == Some(self.identity.as_str())
+            });
+        if is_leader {
+            return self.renew_lease(self.controller_lease_url()?).await;
+        }
+
+        Ok(true)
+    }
+
+    /// Read the controller state stored in the chunks of `version`.
+    async fn read_controller_state<P: DeserializeOwned>(
+        &self,
+        version: &StateVersion,
+    ) -> Result<P, Error> {
+        let chunk_futures: FuturesOrdered<_> = (0..version.num_chunks)
+            .map(|c| async move {
+                let name = self.state_chunk_name(&version.version, c);
+                Ok(self
+                    .client
+                    .get::<ConfigMap>(self.client.config_map_url(Some(&name), None)?)
+                    .await?
+                    .map(|config_map| config_map.get(CHUNK_KEY))
+                    .transpose()?
+                    .flatten()
+                    .ok_or(KubernetesAuthorityError::EmptyReadResponse(name))?)
+            })
+            .collect();
+        let chunks: Result<Vec<Vec<u8>>, Error> = chunk_futures.try_collect().await;
+
+        let data = cloudflare_zlib::inflate(&chunks?.concat())
+            .map_err(|_| KubernetesAuthorityError::CompressionFailed)?;
+        Ok(rmp_serde::from_slice(&data)?)
+    }
+
+    /// Write `controller_state` to the chunks of a new version, which is returned.
+    async fn write_controller_state<P: Serialize>(
+        &self,
+        controller_state: &P,
+    ) -> Result<StateVersion, Error> {
+        let new_val = rmp_serde::to_vec(controller_state)?;
+        let compressed = super::Compressor::compress(&new_val);
+
+        gauge!(recorded::DATAFLOW_STATE_SERIALIZED, compressed.len() as f64);
+
+        let version = uuid::Uuid::new_v4().to_simple().to_string();
+        let chunks = compressed.chunks(CHUNK_SIZE).collect::<Vec<_>>();
+        let num_chunks = chunks.len();
+
+        let chunk_writes: Vec<_> = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let mut meta = self.object_meta(self.state_chunk_name(&version, i), Some("state"));
+                meta.labels
+                    .insert(STATE_VERSION_LABEL.to_owned(), version.clone());
+                let mut config_map = ConfigMap::new(meta);
+                config_map.set(CHUNK_KEY.to_owned(), chunk);
+                async move {
+                    match self
+                        .client
+                        .create(self.client.config_map_url(None, None)?, &config_map)
+                        .await?
+                    {
+                        Some(_) => Ok(()),
+                        None => bail!(KubernetesAuthorityError::RequestFailed(format!(
+                            "ConfigMap {} already exists",
+                            config_map.metadata.name
+                        ))),
+                    }
+                }
+            })
+            .collect();
+
+        join_all(chunk_writes)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(StateVersion {
+            version,
+            num_chunks,
+        })
+    }
+
+    /// Delete the chunks of `version`, logging rather than returning any errors as leftover chunks
+    /// are harmless.
+    async fn delete_controller_state(&self, version: &StateVersion) {
+        // Chunks are deleted one at a time by name rather than with a single delete of the
+        // collection, so that we don't need the `deletecollection` permission on configmaps.
+        let results = join_all((0..version.num_chunks).map(|c| async move {
+            let name = self.state_chunk_name(&version.version, c);
+            self.client
+                .delete(self.client.config_map_url(Some(&name), None)?)
+                .await
+        }))
+        .await;
+        for error in results.into_iter().filter_map(Result::err) {
+            warn!(%error, version = %version.version, "failed to delete controller state chunk");
+        }
+    }
+
+    /// Returns the leader payload annotated on `lease` if it is held.
+    fn leader_payload(lease: &Lease) -> Result<Option<LeaderPayload>, Error> {
+        if lease.holder(Utc::now()).is_none() {
+            return Ok(None);
+        }
+        Ok(lease
+            .metadata
+            .annotations
+            .get(LEADER_ANNOTATION)
+            .map(|payload| serde_json::from_str(payload))
+            .transpose()?)
+    }
+}
+
+#[async_trait]
+impl AuthorityControl for KubernetesAuthority {
+    async fn init(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn become_leader(&self, payload: LeaderPayload) -> Result<Option<LeaderPayload>, Error> {
+        let url = self.controller_lease_url()?;
+        let now = Utc::now();
+
+        let acquired = match self.client.get::<Lease>(url.clone()).await? {
+            Some(lease) if lease.holder(now).is_some() => None,
+            Some(mut lease) => {
+                lease.metadata.annotations.insert(
+                    LEADER_ANNOTATION.to_owned(),
+                    serde_json::to_string(&payload)?,
+                );
+                lease.acquire(&self.identity, now);
+                self.client.replace(url, &lease).await?
+            }
+            None => {
+                let mut lease = Lease::new(self.object_meta(self.object_name("controller"), None));
+                lease.metadata.annotations.insert(
+                    LEADER_ANNOTATION.to_owned(),
+                    serde_json::to_string(&payload)?,
+                );
+                lease.acquire(&self.identity, now);
+                self.client
+                    .create(self.client.lease_url(None, None)?, &lease)
+                    .await?
+            }
+        };
+
+        Ok(acquired.map(|lease| {
+            self.set_known_leader(&lease);
+            payload
+        }))
+    }
+
+    async fn surrender_leadership(&self) -> Result<(), Error> {
+        let url = self.controller_lease_url()?;
+        loop {
+            let mut lease = match self.client.get::<Lease>(url.clone()).await? {
+                Some(lease)
+                    if lease.spec.holder_identity.as_deref() == Some(self.identity.as_str()) =>
+                {
+                    lease
+                }
+                _ => return Ok(()),
+            };
+
+            lease.release();
+            if self.client.replace(url.clone(), &lease).await?.is_some() {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn depose_leader(&self, leader: &LeaderPayload) -> Result<bool, Error> {
+        let url = self.controller_lease_url()?;
+        let mut lease = match self.client.get::<Lease>(url.clone()).await? {
+            Some(lease) => lease,
+            None => return Ok(false),
+        };
+        if Self::leader_payload(&lease)?.as_ref() != Some(leader) {
+            return Ok(false);
+        }
+        // Set by `leader_payload` returning `Some`
+        #[allow(clippy::unwrap_used)]
+        let holder = lease.spec.holder_identity.clone().unwrap();
+
+        lease.release();
+        if self.client.replace(url, &lease).await?.is_none() {
+            // The lease was renewed or taken over since we read it
+            return Ok(false);
+        }
+
+        // Deleting the leader's worker lease marks it as failed, so that if the leader is still
+        // running it will fail its next heartbeat and shut down rather than continue acting as the
+        // leader.
+        self.client
+            .delete(
+                self.client
+                    .lease_url(Some(&self.worker_lease_name(&holder)), None)?,
+            )
+            .await?;
+        warn!(%holder, "deposed unresponsive leader");
+        Ok(true)
+    }
+
+    // Block until there is any leader.
+    async fn get_leader(&self) -> Result<LeaderPayload, Error> {
+        let url = self.controller_lease_url()?;
+        loop {
+            if let Ok(Some(lease)) = self.client.get::<Lease>(url.clone()).await {
+                if let Some(payload) = Self::leader_payload(&lease)? {
+                    return Ok(payload);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    async fn try_get_leader(&self) -> Result<GetLeaderResult, Error> {
+        let lease = match self.client.get::<Lease>(self.controller_lease_url()?).await {
+            Ok(Some(lease)) => lease,
+            _ => return Ok(GetLeaderResult::NoLeader),
+        };
+        let payload = match Self::leader_payload(&lease)? {
+            Some(payload) => payload,
+            None => return Ok(GetLeaderResult::NoLeader),
+        };
+
+        let term = lease
+            .spec
+            .holder_identity
+            .clone()
+            .map(|holder| (holder, lease.spec.acquire_time));
+        if *self.known_leader.lock() == term {
+            return Ok(GetLeaderResult::Unchanged);
+        }
+
+        self.set_known_leader(&lease);
+        Ok(GetLeaderResult::NewLeader(payload))
+    }
+
+    fn can_watch(&self) -> bool {
+        false
+    }
+
+    async fn watch_leader(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn watch_workers(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn try_read<P: DeserializeOwned>(&self, path: &str) -> Result<Option<P>, Error> {
+        Ok(
+            match self
+                .client
+                .get::<ConfigMap>(self.kv_config_map_url()?)
+                .await?
+            {
+                Some(config_map) => config_map
+                    .get(&path_to_key(path))?
+                    .map(|bytes| serde_json::from_slice(&bytes))
+                    .transpose()?,
+                None => None,
+            },
+        )
+    }
+
+    async fn try_read_raw(&self, path: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(
+            match self
+                .client
+                .get::<ConfigMap>(self.kv_config_map_url()?)
+                .await?
+            {
+                Some(config_map) => config_map
+                    .get(&path_to_key(path))?
+                    .map(|bytes| serde_json::from_slice::<Vec<u8>>(&bytes))
+                    .transpose()?,
+                None => None,
+            },
+        )
+    }
+
+    async fn read_modify_write<F, P, E>(&self, path: &str, mut f: F) -> Result<Result<P, E>, Error>
+    where
+        F: Send + FnMut(Option<P>) -> Result<P, E>,
+        P: Send + Serialize + DeserializeOwned,
+        E: Send,
+    {
+        let key = path_to_key(path);
+        let url = self.kv_config_map_url()?;
+        loop {
+            let config_map = self.client.get::<ConfigMap>(url.clone()).await?;
+            let current = match &config_map {
+                Some(config_map) => config_map
+                    .get(&key)?
+                    .map(|bytes| serde_json::from_slice(&bytes))
+                    .transpose()?,
+                None => None,
+            };
+
+            let r = match f(current) {
+                Ok(r) => r,
+                Err(e) => return Ok(Err(e)),
+            };
+            let value = serde_json::to_vec(&r)?;
+
+            let (mut config_map, exists) = match config_map {
+                Some(config_map) => (config_map, true),
+                None => (
+                    ConfigMap::new(self.object_meta(self.object_name("kv"), None)),
+                    false,
+                ),
+            };
+            config_map.set(key.clone(), &value);
+            let size = config_map.data_size();
+            if size > MAX_CONFIG_MAP_SIZE {
+                bail!(KubernetesAuthorityError::ValueTooLarge {
+                    path: path.to_owned(),
+                    size,
+                });
+            }
+
+            // Both writes fail if someone else wrote the ConfigMap since we read it, in which case
+            // we try again.
+            let written = if exists {
+                self.client.replace(url.clone(), &config_map).await?
+            } else {
+                self.client
+                    .create(self.client.config_map_url(None, None)?, &config_map)
+                    .await?
+            };
+            if written.is_some() {
+                return Ok(Ok(r));
+            }
+        }
+    }
+
+    async fn update_controller_state<F, U, P, E>(
+        &self,
+        mut f: F,
+        _: U,
+    ) -> Result<Result<P, E>, Error>
+    where
+        F: Send + FnMut(Option<P>) -> Result<P, E>,
+        U: Send,
+        P: Send + Serialize + DeserializeOwned,
+        E: Send,
+    {
+        let url = self.controller_lease_url()?;
+        loop {
+            let mut lease = match self.client.get::<Lease>(url.clone()).await? {
+                Some(lease) if lease.holder(Utc::now()) == Some(self.identity.as_str()) => lease,
+                _ => bail!(KubernetesAuthorityError::WriteIssuedFromLostLeader),
+            };
+
+            let current_version = lease
+                .metadata
+                .annotations
+                .get(STATE_ANNOTATION)
+                .map(|version| serde_json::from_str::<StateVersion>(version))
+                .transpose()?;
+            let current_state = match &current_version {
+                Some(version) => Some(self.read_controller_state(version).await?),
+                None => None,
+            };
+
+            let r = match f(current_state) {
+                Ok(r) => r,
+                Err(e) => return Ok(Err(e)),
+            };
+
+            let new_version = self.write_controller_state(&r).await?;
+
+            // Pointing the lease at the new version only succeeds if the lease hasn't been
+            // modified since we checked that we hold it.
+            lease.metadata.annotations.insert(
+                STATE_ANNOTATION.to_owned(),
+                serde_json::to_string(&new_version)?,
+            );
+            lease.spec.renew_time = Some(Utc::now());
+            if self.client.replace(url.clone(), &lease).await?.is_some() {
+                if let Some(version) = current_version {
+                    self.delete_controller_state(&version).await;
+                }
+                return Ok(Ok(r));
+            }
+
+            // The lease was modified, most likely by our own heartbeat renewing it, so try again
+            // from the top (which also checks that we are still the leader)
+            self.delete_controller_state(&new_version).await;
+        }
+    }
+
+    async fn register_worker(&self, payload: WorkerDescriptor) -> Result<Option<WorkerId>, Error>
+    where
+        WorkerDescriptor: Serialize,
+    {
+        self.register_lease(self.worker_lease_name(&self.identity), "worker", &payload)
+            .await?;
+        Ok(Some(self.identity.clone()))
+    }
+
+    async fn worker_heartbeat(
+        &self,
+        id: WorkerId,
+    ) -> Result<AuthorityWorkerHeartbeatResponse, Error> {
+        Ok(match self.renew_leases(&id).await {
+            Ok(true) => AuthorityWorkerHeartbeatResponse::Alive,
+            Ok(false) => {
+                error!("Authority failed to heartbeat: lease is no longer held");
+                AuthorityWorkerHeartbeatResponse::Failed
+            }
+            Err(e) => {
+                error!("Authority failed to heartbeat: {}", e.to_string());
+                AuthorityWorkerHeartbeatResponse::Failed
+            }
+        })
+    }
+
+    async fn get_workers(&self) -> Result<HashSet<WorkerId>, Error> {
+        let now = Utc::now();
+        Ok(self
+            .client
+            .list::<Lease>(
+                self.client
+                    .lease_url(None, Some(&self.role_selector("worker")))?,
+            )
+            .await?
+            .iter()
+            .filter_map(|lease| lease.holder(now).map(|holder| holder.to_owned()))
+            .collect())
+    }
+
+    async fn worker_data(
+        &self,
+        worker_ids: Vec<WorkerId>,
+    ) -> Result<HashMap<WorkerId, WorkerDescriptor>, Error> {
+        let mut worker_descriptors: HashMap<WorkerId, WorkerDescriptor> = HashMap::new();
+
+        for w in worker_ids {
+            let name = self.worker_lease_name(&w);
+            let descriptor = self
+                .client
+                .get::<Lease>(self.client.lease_url(Some(&name), None)?)
+                .await?
+                .and_then(|mut lease| lease.metadata.annotations.remove(DESCRIPTOR_ANNOTATION))
+                .ok_or(KubernetesAuthorityError::EmptyReadResponse(name))?;
+            worker_descriptors.insert(w, serde_json::from_str(&descriptor)?);
+        }
+
+        Ok(worker_descriptors)
+    }
+
+    async fn register_adapter(&self, endpoint: SocketAddr) -> Result<Option<AdapterId>, Error> {
+        // Adapters re-register on a regular cadence, which also renews their lease.
+        self.register_lease(
+            self.adapter_lease_name(&self.identity),
+            "adapter",
+            &endpoint,
+        )
+        .await?;
+        Ok(Some(self.identity.clone()))
+    }
+
+    async fn adapter_heartbeat(
+        &self,
+        id: AdapterId,
+    ) -> Result<AuthorityWorkerHeartbeatResponse, Error> {
+        let url = self
+            .client
+            .lease_url(Some(&self.adapter_lease_name(&id)), None)?;
+        Ok(match self.renew_lease(url).await {
+            Ok(true) => AuthorityWorkerHeartbeatResponse::Alive,
+            Ok(false) => {
+                error!("Authority failed to heartbeat adapter: lease is no longer held");
+                AuthorityWorkerHeartbeatResponse::Failed
+            }
+            Err(e) => {
+                error!("Authority failed to heartbeat adapter: {}", e.to_string());
+                AuthorityWorkerHeartbeatResponse::Failed
+            }
+        })
+    }
+
+    async fn get_adapters(&self) -> Result<HashSet<SocketAddr>, Error> {
+        let now = Utc::now();
+        self.client
+            .list::<Lease>(
+                self.client
+                    .lease_url(None, Some(&self.role_selector("adapter")))?,
+            )
+            .await?
+            .iter()
+            .filter(|lease| lease.holder(now).is_some())
+            .filter_map(|lease| lease.metadata.annotations.get(DESCRIPTOR_ANNOTATION))
+            .map(|endpoint| serde_json::from_str(endpoint).map_err(Error::from))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_deployment_names() {
+        assert_eq!(sanitize_name("my_Deployment"), "my-deployment");
+        assert_eq!(sanitize_name("_test.1_"), "test-1");
+    }
+
+    #[test]
+    fn escape_paths() {
+        assert_eq!(path_to_key("snapshot_progress"), "snapshot_progress");
+        assert_eq!(path_to_key("/a.b"), ".2fa.2eb");
+        assert_ne!(path_to_key("a/b"), path_to_key("a.2fb"));
+    }
+
+    #[test]
+    fn config_map_size() {
+        let mut config_map = ConfigMap::new(ObjectMeta::default());
+        assert_eq!(config_map.data_size(), 0);
+        config_map.set("key".to_owned(), b"value");
+        // "value" is 8 bytes once base64 encoded
+        assert_eq!(config_map.data_size(), 3 + 8);
+        config_map.set("key".to_owned(), &vec![0; MAX_CONFIG_MAP_SIZE]);
+        assert!(config_map.data_size() > MAX_CONFIG_MAP_SIZE);
+    }
+
+    #[test]
+    fn lease_expiry() {
+        let now = Utc::now();
+        let mut lease = Lease::new(ObjectMeta::default());
+        assert_eq!(lease.holder(now), None);
+
+        lease.acquire("a", now);
+        assert_eq!(lease.holder(now), Some("a"));
+        assert_eq!(lease.holder(now + chrono::Duration::seconds(10)), Some("a"));
+        assert_eq!(lease.holder(now + chrono::Duration::seconds(30)), None);
+
+        lease.release();
+        assert_eq!(lease.holder(now), None);
+    }
+
+    #[test]
+    fn lease_serialization() {
+        let now = Utc::now();
+        let mut lease = Lease::new(ObjectMeta {
+            name: "readyset-test-controller".to_owned(),
+            ..Default::default()
+        });
+        lease.acquire("a", now);
+
+        let json = serde_json::to_value(&lease).unwrap();
+        assert_eq!(json["spec"]["holderIdentity"], "a");
+        assert_eq!(json["spec"]["leaseDurationSeconds"], 20);
+        // MicroTime requires exactly 6 digits of fractional seconds
+        assert_eq!(
+            json["spec"]["renewTime"].as_str().unwrap().len(),
+            "2022-01-01T00:00:00.000000Z".len()
+        );
+
+        let round_tripped: Lease = serde_json::from_value(json).unwrap();
+        assert_eq!(round_tripped.holder(now), Some("a"));
+    }
+}
@@ -231,6 +231,24 @@ impl AuthorityControl for LocalAuthority {
         Ok(())
     }
 
+    async fn depose_leader(&self, leader: &LeaderPayload) -> Result<bool, Error> {
+        let mut store_inner = self.store.inner_lock()?;
+
+        let is_leader = match store_inner.keys.get(CONTROLLER_KEY) {
+            Some(data) => serde_json::from_slice::<LeaderPayload>(data)? == *leader,
+            None => false,
+        };
+        if !is_leader {
+            return Ok(false);
+        }
+
+        store_inner.keys.remove(CONTROLLER_KEY);
+        store_inner.leader_epoch += 1;
+
+        self.store.inner_notify_all();
+        Ok(true)
+    }
+
     async fn get_leader(&self) -> Result<LeaderPayload, Error> {
         let mut store_inner = self.store.inner_lock()?;
         while !store_inner.keys.contains_key(CONTROLLER_KEY) {
@@ -429,6 +447,13 @@ impl AuthorityControl for LocalAuthority {
         todo!();
     }
 
+    async fn adapter_heartbeat(
+        &self,
+        _: AdapterId,
+    ) -> Result<AuthorityWorkerHeartbeatResponse, Error> {
+        todo!();
+    }
+
     async fn get_adapters(&self) -> Result<HashSet<SocketAddr>, Error> {
         todo!();
     }
@@ -1,4 +1,4 @@
-//! Trait for interacting with an conensus system (Zookeeper, Consul, etcd) to determine
+//! Trait for interacting with an conensus system (Zookeeper, Consul, Kubernetes) to determine
 //! which ReadySet worker acts as the controller, which ReadySet workers exist, detecting failed
 //! workers which necessitate changes, and storing cluster wide global state.
 
@@ -14,9 +14,11 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 mod consul;
+mod kubernetes;
 mod local;
 pub mod zk;
 pub use self::consul::ConsulAuthority;
+pub use self::kubernetes::KubernetesAuthority;
 pub use self::local::{LocalAuthority, LocalAuthorityStore};
 pub use self::zk::ZookeeperAuthority;
 use crate::ControllerDescriptor;
@@ -31,8 +33,8 @@ pub type VolumeId = String;
 pub type WorkerId = String;
 pub type AdapterId = String;
 
-/// A response to a `worker_heartbeat`, to inform the worker of its
-/// status within the system.
+/// A response to a `worker_heartbeat` or `adapter_heartbeat`, to inform the worker or adapter of
+/// its status within the system.
 #[derive(Debug, PartialEq)]
 pub enum AuthorityWorkerHeartbeatResponse {
     Alive,
@@ -83,6 +85,11 @@ pub trait AuthorityControl: Send + Sync {
     /// important that the node calling this function is actually the leader.
     async fn surrender_leadership(&self) -> Result<(), Error>;
 
+    /// Forcibly remove `leader` from leadership, allowing another node to become leader. This is
+    /// used by standbys to take over from a leader that has stopped responding, and does nothing
+    /// if `leader` is no longer the leader. Returns true if `leader` was deposed.
+    async fn depose_leader(&self, leader: &LeaderPayload) -> Result<bool, Error>;
+
     /// Returns the payload for the current leader, blocking if there is not currently a leader.
     /// This method is intended for clients to determine the current leader.
     async fn get_leader(&self) -> Result<LeaderPayload, Error>;
@@ -165,6 +172,13 @@ pub trait AuthorityControl: Send + Sync {
     /// Register an adapters http port.
     async fn register_adapter(&self, endpoint: SocketAddr) -> Result<Option<AdapterId>, Error>;
 
+    /// Adapters run this function on a regular cadence to keep their registration alive. Returns
+    /// `Failed` if the adapter's registration was lost, and it needs to register again.
+    async fn adapter_heartbeat(
+        &self,
+        id: AdapterId,
+    ) -> Result<AuthorityWorkerHeartbeatResponse, Error>;
+
     /// Retrieves the current set of adapter endpoints from the authority.
     async fn get_adapters(&self) -> Result<HashSet<SocketAddr>, Error>;
 }
@@ -176,6 +190,7 @@ pub trait AuthorityControl: Send + Sync {
 pub enum Authority {
     ZookeeperAuthority,
     ConsulAuthority,
+    KubernetesAuthority,
     LocalAuthority,
 }
 
@@ -184,6 +199,7 @@ pub enum Authority {
 pub enum AuthorityType {
     Zookeeper,
     Consul,
+    Kubernetes,
     Local,
 }
 
@@ -193,6 +209,7 @@ impl FromStr for AuthorityType {
         match s {
             "zookeeper" => Ok(AuthorityType::Zookeeper),
             "consul" => Ok(AuthorityType::Consul),
+            "kubernetes" => Ok(AuthorityType::Kubernetes),
             "local" => Ok(AuthorityType::Local),
             other => Err(anyhow!("Invalid authority type: {}", other)),
         }
@@ -204,6 +221,7 @@ impl ToString for AuthorityType {
         match &self {
             AuthorityType::Zookeeper => "zookeeper".to_string(),
             AuthorityType::Consul => "consul".to_string(),
+            AuthorityType::Kubernetes => "kubernetes".to_string(),
             AuthorityType::Local => "local".to_string(),
         }
     }
@@ -220,6 +238,9 @@ impl AuthorityType {
             AuthorityType::Consul => Authority::from(
                 ConsulAuthority::new(&format!("http://{}/{}", &addr, &deployment)).unwrap(),
             ),
+            AuthorityType::Kubernetes => {
+                Authority::from(KubernetesAuthority::new(addr, deployment).unwrap())
+            }
             AuthorityType::Local => Authority::from(LocalAuthority::new()),
         }
     }
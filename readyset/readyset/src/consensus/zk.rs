@@ -204,6 +204,28 @@ impl AuthorityControl for ZookeeperAuthority {
         Ok(())
     }
 
+    async fn depose_leader(&self, leader: &LeaderPayload) -> Result<bool, Error> {
+        let (data, stat) = match self.zk.get_data(CONTROLLER_KEY, false).await {
+            Ok(res) => res,
+            Err(ZkError::NoNode) => return Ok(false),
+            Err(e) => bail!(e),
+        };
+        if serde_json::from_slice::<LeaderPayload>(&data)? != *leader {
+            return Ok(false);
+        }
+
+        // Only delete the leader key if it hasn't changed since we read it, so we can't depose a
+        // leader that was elected in the meantime
+        match self.zk.delete(CONTROLLER_KEY, Some(stat.version)).await {
+            Ok(()) => {
+                warn!(epoch = %stat.czxid, "deposed unresponsive leader");
+                Ok(true)
+            }
+            Err(ZkError::NoNode | ZkError::BadVersion) => Ok(false),
+            Err(e) => bail!(e),
+        }
+    }
+
     async fn get_leader(&self) -> Result<LeaderPayload, Error> {
         loop {
             match self.zk.get_data(CONTROLLER_KEY, false).await {
@@ -434,6 +456,13 @@ impl AuthorityControl for ZookeeperAuthority {
         todo!();
     }
 
+    async fn adapter_heartbeat(
+        &self,
+        _: AdapterId,
+    ) -> Result<AuthorityWorkerHeartbeatResponse, Error> {
+        todo!();
+    }
+
     async fn get_adapters(&self) -> Result<HashSet<SocketAddr>, Error> {
         todo!();
     }
@@ -1,6 +1,7 @@
 //! Primitives and structs related to maintaining different consistency
 //! models within the ReadySet dataflow graph.
 use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use proptest::arbitrary::Arbitrary;
 use serde::{Deserialize, Serialize};
@@ -112,6 +113,25 @@ impl Timestamp {
         }
         true
     }
+
+    /// Returns a new flush barrier, a timestamp which is only satisfied by readers that have
+    /// processed every write their base tables received before the barrier was sent to them. See
+    /// [`ControllerHandle::flush_wait`](crate::ControllerHandle::flush_wait).
+    ///
+    /// Barriers are keyed by an index that no base table has, so that they don't interfere with
+    /// read-your-write timestamps, and hold the current time so that later barriers satisfy
+    /// earlier ones.
+    pub fn flush_barrier() -> Timestamp {
+        // SAFETY: The index is only used as a key in the timestamp, and never to look up a node
+        let key = unsafe { LocalNodeIndex::make(u32::MAX) };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        Timestamp {
+            map: HashMap::from([(key, now)]),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -238,6 +258,21 @@ mod tests {
         assert_eq!(&t, &Timestamp::join(&t, &t));
     }
 
+    #[test]
+    fn later_flush_barriers_satisfy_earlier_ones() {
+        let first = Timestamp::flush_barrier();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        let second = Timestamp::flush_barrier();
+        assert!(second.satisfies(&first));
+        assert!(!first.satisfies(&second));
+
+        // Barriers don't affect read-your-write timestamps
+        let b1 = unsafe { LocalNodeIndex::make(0) };
+        let ryw = create_timestamp(vec![(b1, 2)]);
+        assert!(Timestamp::join(&ryw, &second).satisfies(&ryw));
+        assert!(!ryw.satisfies(&second));
+    }
+
     #[test]
     fn join_calculates_max() {
         // SAFETY: The local node indices are safe as they are 0-indexed
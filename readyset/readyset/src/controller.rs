@@ -14,6 +14,7 @@ use readyset_errors::{
 };
 use parking_lot::RwLock;
 use petgraph::graph::NodeIndex;
+use readyset_tracing::propagation::RequestContext;
 use serde::{Deserialize, Serialize};
 use tower::buffer::Buffer;
 use tower::ServiceExt;
@@ -22,18 +23,22 @@ use tracing::trace;
 use url::Url;
 
 use crate::consensus::{Authority, AuthorityControl};
+use crate::consistency::Timestamp;
 use crate::debug::info::GraphInfo;
 use crate::debug::stats;
 use crate::metrics::MetricsDump;
 use crate::recipe::changelist::ChangeList;
-use crate::recipe::ExtendRecipeSpec;
-use crate::replication::ReplicationOffsets;
+use crate::recipe::{ExtendRecipeSpec, QueryReuse};
+use crate::replication::{
+    ReplicationOffsets, ReplicationSlotStatus, SnapshotProgress, TableNamespace, TableSchemaVersion,
+};
 use crate::status::ReadySetStatus;
+use crate::storage::CompressionOptions;
 use crate::table::{Table, TableBuilder, TableRpc};
-use crate::view::{View, ViewBuilder, ViewRpc};
+use crate::view::{LookupResult, View, ViewBuilder, ViewQuery, ViewRpc};
 use crate::{
-    ActivationResult, ReaderReplicationResult, ReaderReplicationSpec, ReplicationOffset,
-    ViewFilter, ViewRequest,
+    ActivationResult, CacheCreationStatus, PlannedNode, ReaderReplicationResult,
+    ReaderReplicationSpec, ReplicationOffset, ViewFilter, ViewRequest,
 };
 
 mod rpc;
@@ -62,6 +67,8 @@ struct ControllerRequest {
     path: &'static str,
     request: Vec<u8>,
     timeout: Option<Duration>,
+    /// The context of the trace the request was made in, if it is being traced
+    trace_context: Option<RequestContext>,
 }
 
 impl ControllerRequest {
@@ -74,6 +81,7 @@ impl ControllerRequest {
             path,
             request: bincode::serialize(&r)?,
             timeout,
+            trace_context: RequestContext::from_current_span(),
         })
     }
 }
@@ -96,6 +104,7 @@ impl Service<ControllerRequest> for Controller {
         let request_timeout = req.timeout.unwrap_or(Duration::MAX);
         let path = req.path;
         let body = req.request;
+        let trace_context = req.trace_context;
         let start = Instant::now();
         let mut last_error_desc: Option<String> = None;
 
@@ -124,7 +133,11 @@ impl Service<ControllerRequest> for Controller {
                 //             (if you try and use the `url` directly instead of stringifying)
                 #[allow(clippy::unwrap_used)]
                 let string_url = url.as_ref().unwrap().join(path)?.to_string();
-                let r = hyper::Request::post(string_url)
+                let mut r = hyper::Request::post(string_url);
+                for (name, value) in trace_context.iter().flat_map(|ctx| ctx.headers()) {
+                    r = r.header(name, value);
+                }
+                let r = r
                     .body(hyper::Body::from(body.clone()))
                     .map_err(|e| internal_err(format!("http request failed: {}", e)))?;
 
@@ -202,6 +215,7 @@ pub struct ControllerHandle {
     tracer: tracing::Dispatch,
     request_timeout: Option<Duration>,
     migration_timeout: Option<Duration>,
+    table_namespace: TableNamespace,
 }
 
 impl Clone for ControllerHandle {
@@ -213,6 +227,7 @@ impl Clone for ControllerHandle {
             tracer: self.tracer.clone(),
             request_timeout: self.request_timeout,
             migration_timeout: self.migration_timeout,
+            table_namespace: self.table_namespace.clone(),
         }
     }
 }
@@ -224,6 +239,10 @@ impl Clone for ControllerHandle {
 /// set to. Number of cores, perhaps?
 const CONTROLLER_BUFFER_SIZE: usize = 8;
 
+/// How often [`ControllerHandle::flush_wait`] checks whether a reader has received the flush
+/// barrier
+const FLUSH_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
 impl ControllerHandle {
     #[doc(hidden)]
     pub fn make(
@@ -255,9 +274,19 @@ impl ControllerHandle {
             tracer,
             request_timeout,
             migration_timeout,
+            table_namespace: Default::default(),
         }
     }
 
+    /// Set the namespace of the base tables that this handle writes to and alters. Table names
+    /// passed to and returned from the methods used for replication are then the names of the
+    /// tables in the upstream database.
+    ///
+    /// See [`TableNamespace`] for more information.
+    pub fn set_table_namespace(&mut self, table_namespace: TableNamespace) {
+        self.table_namespace = table_namespace;
+    }
+
     /// Check that the `ControllerHandle` can accept another request.
     ///
     /// Note that this method _must_ return `Poll::Ready` before any other methods that return
@@ -458,8 +487,14 @@ impl ControllerHandle {
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
     pub fn table<'a>(&'a mut self, name: &str) -> impl Future<Output = ReadySetResult<Table>> + 'a {
+        let name = self.table_namespace.table_name(name);
+        self.base_table(name)
+    }
+
+    /// Obtain a `Table` for the base table with the given name in ReadySet, ignoring the table
+    /// namespace
+    fn base_table(&mut self, name: String) -> impl Future<Output = ReadySetResult<Table>> + '_ {
         let domains = self.domains.clone();
-        let name = name.to_string();
         async move {
             let body: hyper::body::Bytes = self
                 .handle
@@ -490,6 +525,20 @@ impl ControllerHandle {
         self.rpc("get_statistics", (), self.request_timeout)
     }
 
+    /// Get the statistics collected about the rows of every base table, keyed by table name, along
+    /// with the names of each table's columns.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn table_statistics(
+        &mut self,
+    ) -> impl Future<
+        Output = ReadySetResult<
+            BTreeMap<SqlIdentifier, (Vec<SqlIdentifier>, stats::TableStatistics)>,
+        >,
+    > + '_ {
+        self.rpc("table_statistics", (), self.request_timeout)
+    }
+
     /// Flush all partial state, evicting all rows present.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -497,6 +546,48 @@ impl ControllerHandle {
         self.rpc("flush_partial", (), self.request_timeout)
     }
 
+    /// Wait, for at most `timeout`, until every write that ReadySet's base tables have received so
+    /// far has propagated through the dataflow graph to every reader.
+    ///
+    /// This sends a [flush barrier](Timestamp::flush_barrier) to every base table, then waits for
+    /// every reader to have received the barrier along all of its paths from the base tables.
+    /// Since each node processes its inputs in order, a reader that has received the barrier
+    /// reflects every write sent to its base tables before it.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub async fn flush_wait(&mut self, timeout: Duration) -> ReadySetResult<()> {
+        let deadline = Instant::now() + timeout;
+        let barrier = Timestamp::flush_barrier();
+
+        for name in self.inputs().await?.into_keys() {
+            self.base_table(name)
+                .await?
+                .update_timestamp(barrier.clone())
+                .await?;
+        }
+
+        for name in self.outputs().await?.into_keys() {
+            let mut view = self.view(name.as_str()).await?;
+            loop {
+                let query = ViewQuery {
+                    key_comparisons: vec![],
+                    block: false,
+                    filter: None,
+                    timestamp: Some(barrier.clone()),
+                };
+                match view.raw_lookup(query).await? {
+                    LookupResult::Results(..) => break,
+                    LookupResult::NonBlockingMiss if Instant::now() >= deadline => {
+                        return Err(ReadySetError::FlushTimeout)
+                    }
+                    LookupResult::NonBlockingMiss => tokio::time::sleep(FLUSH_POLL_INTERVAL).await,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Performs a dry-run migration with the given set of queries.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -512,6 +603,32 @@ impl ControllerHandle {
         self.rpc("dry_run", request, self.migration_timeout)
     }
 
+    /// Plans the migration for the given set of changes without applying it, and returns the
+    /// dataflow nodes it would add to the graph, or the error that planning the migration failed
+    /// with.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn explain_migration(
+        &mut self,
+        changes: ChangeList,
+    ) -> impl Future<Output = ReadySetResult<Vec<PlannedNode>>> + '_ {
+        let changes = self.table_namespace.namespace_changelist(changes);
+        self.rpc("explain_migration", changes, self.migration_timeout)
+    }
+
+    /// Creates all of the caches in the given list of `CREATE CACHE` changes in a single
+    /// migration, or none of them if any of them can't be created. Returns the status of each
+    /// cache, in the order they were given.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn create_caches(
+        &mut self,
+        changes: ChangeList,
+    ) -> impl Future<Output = ReadySetResult<Vec<CacheCreationStatus>>> + '_ {
+        let changes = self.table_namespace.namespace_changelist(changes);
+        self.rpc("create_caches", changes, self.migration_timeout)
+    }
+
     /// Extend the existing recipe with the given set of queries.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -520,7 +637,7 @@ impl ControllerHandle {
         changes: ChangeList,
     ) -> impl Future<Output = ReadySetResult<ActivationResult>> + '_ {
         let request = ExtendRecipeSpec {
-            changes,
+            changes: self.table_namespace.namespace_changelist(changes),
             ..Default::default()
         };
 
@@ -535,7 +652,7 @@ impl ControllerHandle {
         changes: ChangeList,
     ) -> impl Future<Output = ReadySetResult<ActivationResult>> + '_ {
         let request = ExtendRecipeSpec {
-            changes,
+            changes: self.table_namespace.namespace_changelist(changes),
             require_leader_ready: false,
             ..Default::default()
         };
@@ -552,13 +669,41 @@ impl ControllerHandle {
         replication_offset: &ReplicationOffset,
         require_leader_ready: bool,
     ) -> impl Future<Output = ReadySetResult<ActivationResult>> + '_ {
-        let request = ExtendRecipeSpec {
-            changes,
-            replication_offset: Some(Cow::Borrowed(replication_offset)),
-            require_leader_ready,
-        };
-
-        self.rpc("extend_recipe", request, self.migration_timeout)
+        let changes = self.table_namespace.namespace_changelist(changes);
+        let namespace = self.table_namespace.name().map(str::to_owned);
+        let replication_offset = replication_offset.clone();
+        async move {
+            match namespace {
+                // The schema offset stored with the recipe belongs to the primary upstream
+                // database, so the offsets of namespaced upstream databases are stored separately
+                Some(namespace) => {
+                    let request = ExtendRecipeSpec {
+                        changes,
+                        require_leader_ready,
+                        ..Default::default()
+                    };
+                    let res = self
+                        .rpc("extend_recipe", request, self.migration_timeout)
+                        .await?;
+                    self.rpc(
+                        "set_namespace_schema_replication_offset",
+                        (namespace, Some(replication_offset)),
+                        self.request_timeout,
+                    )
+                    .await?;
+                    Ok(res)
+                }
+                None => {
+                    let request = ExtendRecipeSpec {
+                        changes,
+                        replication_offset: Some(Cow::Owned(replication_offset)),
+                        require_leader_ready,
+                    };
+                    self.rpc("extend_recipe", request, self.migration_timeout)
+                        .await
+                }
+            }
+        }
     }
 
     /// Remove all nodes related to the query with the given name
@@ -570,16 +715,36 @@ impl ControllerHandle {
 
     /// Set the replication offset for the schema, which is stored with the recipe.
     ///
+    /// If this handle has a [namespaced](Self::set_table_namespace) upstream database, the offset
+    /// is stored for that upstream database instead.
+    ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
     pub fn set_schema_replication_offset(
         &mut self,
         replication_offset: Option<&ReplicationOffset>,
     ) -> impl Future<Output = ReadySetResult<()>> + '_ {
-        self.rpc(
-            "set_schema_replication_offset",
-            replication_offset,
-            self.request_timeout,
-        )
+        let namespace = self.table_namespace.name().map(str::to_owned);
+        let replication_offset = replication_offset.cloned();
+        async move {
+            match namespace {
+                Some(namespace) => {
+                    self.rpc(
+                        "set_namespace_schema_replication_offset",
+                        (namespace, replication_offset),
+                        self.request_timeout,
+                    )
+                    .await
+                }
+                None => {
+                    self.rpc(
+                        "set_schema_replication_offset",
+                        replication_offset,
+                        self.request_timeout,
+                    )
+                    .await
+                }
+            }
+        }
     }
 
     /// Fetch a graphviz description of the dataflow graph.
@@ -596,6 +761,33 @@ impl ControllerHandle {
         self.rpc("simple_graphviz", (), self.request_timeout)
     }
 
+    /// Fetch a graphviz description of the part of the dataflow graph that the query named
+    /// `name` reads from, simplified if `simplified` is true.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn graphviz_for_query(
+        &mut self,
+        name: &str,
+        simplified: bool,
+    ) -> impl Future<Output = ReadySetResult<String>> + '_ {
+        self.rpc(
+            "graphviz_for_query",
+            (name, !simplified),
+            self.request_timeout,
+        )
+    }
+
+    /// Fetch which existing query the query named `name` reused the dataflow nodes of when it was
+    /// added, or `None` if it was built from scratch.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn query_reuse(
+        &mut self,
+        name: &str,
+    ) -> impl Future<Output = ReadySetResult<Option<QueryReuse>>> + '_ {
+        self.rpc("query_reuse", name, self.request_timeout)
+    }
+
     /// Replicate the readers associated with the list of queries to the given worker.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -660,6 +852,15 @@ impl ControllerHandle {
         self.rpc("healthy_workers", (), self.request_timeout)
     }
 
+    /// Move all the domains running on the worker at `worker` onto other workers, and stop
+    /// scheduling domains onto it, so that it can be shut down without its domains being
+    /// unavailable until its failure is detected.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn drain_worker(&mut self, worker: Url) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("drain_worker", worker, self.migration_timeout)
+    }
+
     /// Get the url of the current ReadySet controller.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -671,10 +872,116 @@ impl ControllerHandle {
     ///
     /// See [the documentation for PersistentState](::noria_dataflow::state::persistent_state) for
     /// more information about replication offsets.
+    ///
+    /// If this handle has a [table namespace](Self::set_table_namespace), only the tables in that
+    /// namespace are returned, keyed by their upstream names.
     pub fn replication_offsets(
         &mut self,
     ) -> impl Future<Output = ReadySetResult<ReplicationOffsets>> + '_ {
-        self.rpc("replication_offsets", (), self.request_timeout)
+        async move {
+            let mut offsets: ReplicationOffsets = self
+                .rpc("replication_offsets", (), self.request_timeout)
+                .await?;
+            if let Some(namespace) = self.table_namespace.name().map(str::to_owned) {
+                offsets.schema = self
+                    .rpc(
+                        "namespace_schema_replication_offset",
+                        namespace,
+                        self.request_timeout,
+                    )
+                    .await?;
+            }
+            self.table_namespace.filter_offsets(&mut offsets);
+            Ok(offsets)
+        }
+    }
+
+    /// Get the progress of all unfinished table snapshots, keyed by table name.
+    ///
+    /// See [`SnapshotProgress`] for more information.
+    pub fn snapshot_progress(
+        &mut self,
+    ) -> impl Future<Output = ReadySetResult<HashMap<String, SnapshotProgress>>> + '_ {
+        async move {
+            let progress: HashMap<String, SnapshotProgress> = self
+                .rpc("snapshot_progress", (), self.request_timeout)
+                .await?;
+            if self.table_namespace.is_identity() {
+                return Ok(progress);
+            }
+            Ok(progress
+                .into_iter()
+                .filter_map(|(table, progress)| {
+                    self.table_namespace
+                        .upstream_table_name(&table)
+                        .map(|table| (table.to_owned(), progress))
+                })
+                .collect())
+        }
+    }
+
+    /// Record the progress of the snapshot of the table with the given name, or clear it if
+    /// `progress` is `None`.
+    ///
+    /// See [`SnapshotProgress`] for more information.
+    pub fn set_snapshot_progress(
+        &mut self,
+        table: &str,
+        progress: Option<SnapshotProgress>,
+    ) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        let table = self.table_namespace.table_name(table);
+        self.rpc(
+            "set_snapshot_progress",
+            (table, progress),
+            self.request_timeout,
+        )
+    }
+
+    /// Get the most recently recorded state of the upstream replication slot, if any.
+    ///
+    /// See [`ReplicationSlotStatus`] for more information.
+    pub fn replication_slot_status(
+        &mut self,
+    ) -> impl Future<Output = ReadySetResult<Option<ReplicationSlotStatus>>> + '_ {
+        self.rpc("replication_slot_status", (), self.request_timeout)
+    }
+
+    /// Record the state of the upstream replication slot, or clear it if `status` is `None`.
+    pub fn set_replication_slot_status(
+        &mut self,
+        status: Option<ReplicationSlotStatus>,
+    ) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("set_replication_slot_status", status, self.request_timeout)
+    }
+
+    /// Get the largest value of the auto-increment column of each table that has been replicated
+    /// from the upstream database, keyed by table name.
+    pub fn auto_increments(
+        &mut self,
+    ) -> impl Future<Output = ReadySetResult<HashMap<String, u64>>> + '_ {
+        self.rpc("auto_increments", (), self.request_timeout)
+    }
+
+    /// Record the largest values of the auto-increment columns of the given tables that have been
+    /// replicated from the upstream database. Values smaller than the ones already recorded for
+    /// the same tables are ignored, so the recorded values never go backwards.
+    pub fn record_auto_increments(
+        &mut self,
+        values: HashMap<String, u64>,
+    ) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("record_auto_increments", values, self.request_timeout)
+    }
+
+    /// Get every version of the definition of the base table with the given name that has been
+    /// recorded in the controller's schema registry, oldest first.
+    ///
+    /// See [`TableSchemaVersion`] for more information.
+    pub fn table_schema_versions(
+        &mut self,
+        table: &str,
+    ) -> impl Future<Output = ReadySetResult<Vec<TableSchemaVersion>>> + '_ {
+        let table = self.table_namespace.table_name(table);
+        self.rpc("table_schema_versions", table, self.request_timeout)
     }
 
     /// Get a list of all current tables node indexes that are involved in snapshotting.
@@ -684,6 +991,66 @@ impl ControllerHandle {
         self.rpc("snapshotting_tables", (), self.request_timeout)
     }
 
+    /// Manually compact the persisted state of the base table with the given name.
+    ///
+    /// This blocks until compaction has finished, which can take a long time for large tables, so
+    /// it uses the migration timeout rather than the request timeout.
+    pub fn compact_table(&mut self, table: &str) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("compact_table", table, self.migration_timeout)
+    }
+
+    /// Flush all writes made so far to the base table with the given name to disk.
+    ///
+    /// Writes to a table in snapshot mode skip the write-ahead log, so they can be lost in a crash
+    /// until they are flushed.
+    pub fn flush_table(&mut self, table: &str) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        let table = self.table_namespace.table_name(table);
+        self.rpc("flush_table", table, self.request_timeout)
+    }
+
+    /// Recompute the statistics collected about the rows of the base table with the given name
+    /// (see [`Self::table_statistics`]) from the rows it currently holds.
+    ///
+    /// This reads every row of the table, so like [`Self::compact_table`] it uses the migration
+    /// timeout.
+    pub fn refresh_table_statistics(
+        &mut self,
+        table: &str,
+    ) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("refresh_table_statistics", table, self.migration_timeout)
+    }
+
+    /// Change the compression options for the on-disk data of the base table with the given
+    /// name.
+    ///
+    /// This blocks until all existing data has been rewritten with the new compression options,
+    /// so like [`Self::compact_table`] it uses the migration timeout.
+    pub fn set_table_compression(
+        &mut self,
+        table: &str,
+        options: CompressionOptions,
+    ) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc(
+            "set_table_compression",
+            (table, options),
+            self.migration_timeout,
+        )
+    }
+
+    /// Override the column that the base table with the given name is sharded by, rather than
+    /// having it chosen automatically.
+    ///
+    /// The sharding of a base table is fixed when the table is created, so this takes effect for
+    /// tables that don't exist yet, or that are later dropped and re-created. It returns an error
+    /// for an existing table that's sharded by a different column.
+    pub fn set_table_sharding(
+        &mut self,
+        table: &str,
+        column: &str,
+    ) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("set_table_sharding", (table, column), self.request_timeout)
+    }
+
     /// Return whether the leader is ready or not.
     pub fn leader_ready(&mut self) -> impl Future<Output = ReadySetResult<bool>> + '_ {
         self.rpc("leader_ready", (), self.request_timeout)
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use petgraph::graph::NodeIndex;
+use readyset_data::DataType;
 use serde::{Deserialize, Serialize};
 
 use crate::internal::*;
@@ -23,6 +24,123 @@ pub struct DomainStats {
     pub total_forward_time: u64,
     /// Total wall-clock time spent waiting for work in this domain.
     pub wait_time: u64,
+    /// Number of times state has been evicted from this domain to free memory.
+    pub evictions: u64,
+}
+
+/// A breakdown of the memory used by the state of a node, in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    /// Memory used by the keys which have been filled in the node's partial indexes.
+    pub key_bytes: u64,
+    /// Memory used by the rows stored in the node's state.
+    pub row_bytes: u64,
+    /// Estimated memory used by the data structures holding the keys and rows, such as the
+    /// pointers to each row kept by every index.
+    pub overhead_bytes: u64,
+}
+
+impl MemoryUsage {
+    /// Returns the total memory used by the node's state.
+    pub fn total(&self) -> u64 {
+        self.key_bytes + self.row_bytes + self.overhead_bytes
+    }
+}
+
+/// Statistics about the values in a single column of a base table.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnStatistics {
+    /// An estimate of the number of distinct non-NULL values written to the column.
+    pub distinct: u64,
+    /// The registers of the [HyperLogLog][] sketch `distinct` was estimated from, which are
+    /// needed to combine the estimates for several shards of a table.
+    ///
+    /// [HyperLogLog]: https://en.wikipedia.org/wiki/HyperLogLog
+    #[serde(default)]
+    pub distinct_registers: Vec<u8>,
+    /// The smallest non-NULL value written to the column, if any.
+    ///
+    /// Deleting that value doesn't change this, so this is a lower bound on the values currently
+    /// in the column.
+    pub min: Option<DataType>,
+    /// The largest non-NULL value written to the column, if any.
+    ///
+    /// Deleting that value doesn't change this, so this is an upper bound on the values currently
+    /// in the column.
+    pub max: Option<DataType>,
+}
+
+/// Statistics about the rows of a base table, collected as the table is snapshotted and kept up to
+/// date as it's written to.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableStatistics {
+    /// The number of rows in the table.
+    pub rows: u64,
+    /// Statistics about each of the table's columns, in order.
+    pub columns: Vec<ColumnStatistics>,
+}
+
+impl TableStatistics {
+    /// Combines these statistics with the statistics for another shard of the same table.
+    ///
+    /// Shards may hold the same values, so rather than summing the distinct value estimates, the
+    /// sketches they were estimated from are merged into a sketch of the values in both shards.
+    pub fn merge(&mut self, other: TableStatistics) {
+        self.rows += other.rows;
+        if self.columns.len() < other.columns.len() {
+            self.columns
+                .resize_with(other.columns.len(), Default::default);
+        }
+        for (col, other) in self.columns.iter_mut().zip(other.columns) {
+            if col.distinct_registers.is_empty() && col.distinct == 0 {
+                col.distinct = other.distinct;
+                col.distinct_registers = other.distinct_registers;
+            } else if !other.distinct_registers.is_empty()
+                && col.distinct_registers.len() == other.distinct_registers.len()
+            {
+                for (register, other) in col
+                    .distinct_registers
+                    .iter_mut()
+                    .zip(other.distinct_registers)
+                {
+                    *register = (*register).max(other);
+                }
+                col.distinct = estimate_distinct(&col.distinct_registers);
+            } else {
+                // Without sketches to merge, the best we can do is an upper bound
+                col.distinct = (col.distinct + other.distinct).min(self.rows);
+                col.distinct_registers.clear();
+            }
+            col.min = match (col.min.take(), other.min) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            col.max = match (col.max.take(), other.max) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+}
+
+/// Estimates the number of distinct values added to the [HyperLogLog][] sketch with the given
+/// `registers`.
+///
+/// [HyperLogLog]: https://en.wikipedia.org/wiki/HyperLogLog
+pub fn estimate_distinct(registers: &[u8]) -> u64 {
+    let m = registers.len() as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let sum: f64 = registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+    let estimate = alpha * m * m / sum;
+
+    let zeros = registers.iter().filter(|&&r| r == 0).count();
+    if estimate <= 2.5 * m && zeros != 0 {
+        // Small range correction: linear counting is more accurate while many registers are still
+        // empty
+        (m * (m / zeros as f64).ln()).round() as u64
+    } else {
+        estimate.round() as u64
+    }
 }
 
 /// Statistics about a node.
@@ -38,6 +156,14 @@ pub struct NodeStats {
     pub process_ptime: u64,
     /// Total memory size of this node's state.
     pub mem_size: u64,
+    /// (A potentially inaccurate estimate of) the number of rows in this node's state.
+    #[serde(default)]
+    pub rows: u64,
+    /// Breakdown of the memory used by this node's state.
+    pub memory: MemoryUsage,
+    /// Statistics about the rows written to this node, if it's a base table.
+    #[serde(default)]
+    pub table_statistics: Option<TableStatistics>,
     /// The materialization type of this node's state.
     pub materialized: MaterializationStatus,
     /// The value returned from Ingredient::probe.
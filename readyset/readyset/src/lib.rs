@@ -115,6 +115,7 @@ use std::convert::TryFrom;
 use std::default::Default;
 pub mod recipe;
 pub mod replication;
+pub mod storage;
 
 #[doc(hidden)]
 pub mod channel;
@@ -248,6 +249,39 @@ pub struct ActivationResult {
     pub expressions_added: usize,
     /// Number of expressions the recipe removed compared to the prior recipe.
     pub expressions_removed: usize,
+    /// Names of the existing views and queries that were re-planned because a table they depend
+    /// on was altered.
+    pub replanned_queries: Vec<SqlIdentifier>,
+    /// Names of the existing views and queries that were removed because a table they depend on
+    /// was dropped, or because they could no longer be planned after it was altered.
+    pub dropped_queries: Vec<SqlIdentifier>,
+}
+
+/// A dataflow node that a migration would add to the graph, as found by planning the migration
+/// without applying it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlannedNode {
+    /// The index the node would have in the dataflow graph
+    pub index: NodeIndex,
+    /// A description of the operation the node performs
+    pub description: String,
+    /// How the state of the node would be materialized
+    pub materialization: MaterializationStatus,
+    /// An estimate of the memory, in bytes, that the state of the node would use if it was fully
+    /// populated. This is `None` if the node isn't materialized, or if there are no statistics
+    /// about the base tables it reads from.
+    pub estimated_bytes: Option<u64>,
+}
+
+/// The outcome of creating one of the caches in a batch, as returned by
+/// [`ControllerHandle::create_caches`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CacheCreationStatus {
+    /// The name of the cache, if one was given
+    pub name: Option<SqlIdentifier>,
+    /// The error that planning the cache failed with, or `None` if it could be planned. If any
+    /// cache in the batch can't be planned, none of the caches in the batch are created.
+    pub error: Option<ReadySetError>,
 }
 
 /// Represents a request to replicate readers for the given queries into the given worker.
@@ -304,6 +338,8 @@ pub fn shard_by(dt: &DataType, shards: usize) -> usize {
             // this unwrap should be safe because we just coerced dt to a text
             #[allow(clippy::unwrap_used)]
             let s: &str = <&str>::try_from(&str_dt).unwrap();
+            // strings that compare equal under their collation need to go to the same shard
+            let s = str_dt.collation().unwrap_or_default().normalize(s);
             hasher.write(s.as_bytes());
             hasher.finish() as usize % shards
         }
@@ -333,6 +333,43 @@ pub mod recorded {
     /// | node | The NodeIndex of the ingress node were the packet was sent. |
     pub const EGRESS_NODE_SENT_PACKETS: &str = "egress.sent_packets";
 
+    /// Counter: The number of evictions that a domain has performed. Each eviction frees memory
+    /// from one or more nodes in the domain.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | domain | The index of the domain. |
+    /// | shard | The shard identifier of the domain. |
+    pub const DOMAIN_EVICTIONS: &str = "domain.evictions";
+
+    /// Gauge: The number of packets queued within a domain waiting to be processed: replay
+    /// requests waiting for a free replay slot, and packets the domain has sent to itself.
+    /// Recorded periodically, along with the domain's state sizes.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | domain | The index of the domain. |
+    /// | shard | The shard identifier of the domain. |
+    pub const DOMAIN_QUEUE_DEPTH: &str = "domain.queue_depth";
+
+    /// Gauge: The number of packets sent to a domain by the other domains on its worker which the
+    /// domain hasn't received yet. Recorded periodically, along with the domain's state sizes.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | domain | The index of the domain. |
+    /// | shard | The shard identifier of the domain. |
+    pub const DOMAIN_INPUT_QUEUE_DEPTH: &str = "domain.input_queue_depth";
+
+    /// Counter: The number of times a domain stopped accepting writes to its base tables because
+    /// too many packets were queued for the domains on its worker.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | domain | The index of the domain. |
+    /// | shard | The shard identifier of the domain. |
+    pub const DOMAIN_INGESTION_PAUSED: &str = "domain.ingestion_paused";
+
     /// Counter: The number of eviction packets that a domain has received.
     ///
     /// | Tag | Description |
@@ -359,6 +396,28 @@ pub mod recorded {
     /// request.
     pub const SERVER_VIEW_UPQUERY_DURATION: &str = "server.view_query_upquery_duration_us";
 
+    /// Counter: The number of reads of a single reader that were served entirely from its cache.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | reader | The name of the reader (the name of the cache, for cached queries). |
+    pub const READER_LOOKUP_HIT: &str = "reader.lookup_hit";
+
+    /// Counter: The number of reads of a single reader that required at least a partial replay.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | reader | The name of the reader (the name of the cache, for cached queries). |
+    pub const READER_LOOKUP_MISS: &str = "reader.lookup_miss";
+
+    /// Histogram: The amount of time in microseconds spent waiting for an upquery during a read
+    /// request to a single reader.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | reader | The name of the reader (the name of the cache, for cached queries). |
+    pub const READER_UPQUERY_DURATION: &str = "reader.upquery_duration_us";
+
     /// Counter: The number of times a dataflow node type is added to the
     /// dataflow graph. Recorded at the time the new graph is committed.
     ///
@@ -408,6 +467,21 @@ pub mod recorded {
     /// | status | SnapshotStatusTag |
     pub const REPLICATOR_SNAPSHOT_STATUS: &str = "replicator.snapshot_status";
 
+    /// Gauge: The percentage of a table's rows that have been copied so far during the
+    /// replicator's initial snapshot.
+    ///
+    /// | Tag | Description |
+    /// | table | The name of the table being snapshotted. |
+    pub const REPLICATOR_SNAPSHOT_PERCENT: &str = "replicator.snapshot_percent";
+
+    /// Gauge: The number of bytes of WAL retained by the upstream PostgreSQL database for the
+    /// replicator's logical replication slot.
+    pub const REPLICATOR_SLOT_LAG_BYTES: &str = "replicator.slot_lag_bytes";
+
+    /// Gauge: The number of seconds between a change being committed in the upstream database and
+    /// the replicator receiving it, recorded as changes are received.
+    pub const REPLICATOR_LAG_SECONDS: &str = "replicator.lag_seconds";
+
     /// Counter: Number of failures encountered when following the replication
     /// log.
     pub const REPLICATOR_FAILURE: &str = "replicator.update_failure";
@@ -415,6 +489,10 @@ pub mod recorded {
     /// Counter: Number of replication actions performed successfully.
     pub const REPLICATOR_SUCCESS: &str = "replicator.update_success";
 
+    /// Counter: Number of times the replicator has been restarted to reconnect to the upstream
+    /// database after failing.
+    pub const REPLICATOR_RECONNECTS: &str = "replicator.reconnects";
+
     /// Gauge: Indicates whether a server is the leader. Set to 1 when the
     /// server is leader, 0 for follower.
     pub const CONTROLLER_IS_LEADER: &str = "controller.is_leader";
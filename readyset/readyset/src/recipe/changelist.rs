@@ -35,8 +35,8 @@ use std::str::FromStr;
 
 use nom_sql::{
     AlterTableStatement, CacheInner, CreateCacheStatement, CreateTableStatement,
-    CreateViewStatement, DropTableStatement, DropViewStatement, SelectStatement, SqlIdentifier,
-    SqlQuery,
+    CreateViewStatement, Dialect, DropTableStatement, DropViewStatement, SelectStatement,
+    SqlIdentifier, SqlQuery,
 };
 use readyset_errors::{unsupported, ReadySetError, ReadySetResult};
 use serde::{Deserialize, Serialize};
@@ -62,6 +62,17 @@ impl FromStr for ChangeList {
     type Err = ReadySetError;
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::from_str_with_dialect(value, parse::CANONICAL_DIALECT)
+    }
+}
+
+impl ChangeList {
+    /// Parse a list of changes from `value`, a list of queries in the given SQL `dialect`.
+    ///
+    /// Statements which come from a source other than the canonical dialect of the server (such as
+    /// DDL replicated from a MySQL server with a non-default `sql_mode`) should be parsed with
+    /// this rather than [`FromStr`], so that they're parsed the same way the source parsed them.
+    pub fn from_str_with_dialect(value: &str, dialect: Dialect) -> ReadySetResult<Self> {
         // We separate the queries first, so that we can parse them one by
         // one and get a correct error message when an individual query fails to be
         // parsed.
@@ -93,7 +104,7 @@ impl FromStr for ChangeList {
         };
         let changes = queries.into_iter().fold(
             Ok(Vec::new()),
-            |acc: ReadySetResult<Vec<Change>>, query| match parse::query_expr(query) {
+            |acc: ReadySetResult<Vec<Change>>, query| match parse::query_expr(query, dialect) {
                 Result::Err(nom::Err::Error(e)) => mk_error!(e.input),
                 Result::Err(nom::Err::Failure(e)) => mk_error!(e.input),
                 Result::Err(_) => mk_error!(value),
@@ -237,11 +248,11 @@ mod parse {
     /// The canonical SQL dialect used for central ReadySet server recipes. All direct clients of
     /// readyset-server must use this dialect for their SQL recipes, and all adapters and client
     /// libraries must translate into this dialect as part of handling requests from users
-    const CANONICAL_DIALECT: Dialect = Dialect::MySQL;
+    pub(super) const CANONICAL_DIALECT: Dialect = Dialect::MySQL;
 
-    pub(super) fn query_expr(input: &str) -> nom::IResult<&str, SqlQuery> {
+    pub(super) fn query_expr(input: &str, dialect: Dialect) -> nom::IResult<&str, SqlQuery> {
         let (input, _) = whitespace0(input)?;
-        let (input, expr) = match sql_query(CANONICAL_DIALECT)(input.as_bytes()) {
+        let (input, expr) = match sql_query(dialect)(input.as_bytes()) {
             Ok((i, e)) => Ok((std::str::from_utf8(i).unwrap(), e)),
             Err(nom::Err::Incomplete(n)) => Err(nom::Err::Incomplete(n)),
             Err(nom::Err::Error(nom::error::Error { input, code })) => {
@@ -3,8 +3,10 @@
 pub mod changelist;
 
 use std::borrow::Cow;
+use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
 
 use crate::recipe::changelist::ChangeList;
 use crate::ReplicationOffset;
@@ -31,3 +33,56 @@ impl<'a> Default for ExtendRecipeSpec<'a> {
         }
     }
 }
+
+/// Describes how a query was built on top of the dataflow nodes of a query that was already
+/// installed when it was added, rather than from scratch
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QueryReuse {
+    /// The name of the existing query whose nodes were reused
+    pub reused_query: String,
+    /// The number of MIR nodes of the existing query that were reused (including the nodes for
+    /// base tables)
+    pub reused_nodes: usize,
+}
+
+/// Computes a hash of `query` from its normalized text (as it's displayed), to identify queries
+/// which weren't given a name with an id of the form `q_<hash>`.
+///
+/// Unlike a hash computed with [`std::hash::Hash`], this only depends on the text of the query, so
+/// ids derived from it stay the same across restarts, leader changes and upgrades.
+pub fn hash_query<Q>(query: &Q) -> u64
+where
+    Q: Display + ?Sized,
+{
+    let mut hasher = Sha1::new();
+    hasher.update(query.to_string().as_bytes());
+    // The SHA-1 digest is 20 bytes long, so it's safe to consume only 8 bytes
+    u64::from_le_bytes(hasher.finalize()[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_select_statement, Dialect};
+
+    use super::*;
+
+    #[test]
+    fn hash_query_is_stable() {
+        assert_eq!(
+            hash_query("SELECT * FROM `t` WHERE (`id` = ?)"),
+            0x51e89fabbe6a3bfc
+        );
+    }
+
+    #[test]
+    fn hash_query_normalizes_text() {
+        let q1 = parse_select_statement(Dialect::MySQL, "SELECT * FROM t WHERE id = ?").unwrap();
+        let q2 =
+            parse_select_statement(Dialect::MySQL, "select *\n  from t\n  where (id = ?)").unwrap();
+        assert_eq!(hash_query(&q1), hash_query(&q2));
+        assert_eq!(
+            hash_query(&q1),
+            hash_query("SELECT * FROM `t` WHERE (`id` = ?)")
+        );
+    }
+}
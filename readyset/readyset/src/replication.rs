@@ -5,10 +5,15 @@ use std::cmp::{min_by_key, Ordering};
 use std::collections::HashMap;
 use std::fmt;
 use std::hash::Hash;
+use std::str::FromStr;
 
-use nom_sql::SqlIdentifier;
+use nom_sql::{CreateTableStatement, SqlIdentifier};
+use readyset_data::DataType;
 use readyset_errors::{ReadySetError, ReadySetResult};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::recipe::changelist::{Change, ChangeList};
 
 /// A data type representing an offset in a replication log
 ///
@@ -59,6 +64,37 @@ impl PartialOrd for ReplicationOffset {
 }
 
 impl ReplicationOffset {
+    /// Construct a [`ReplicationOffset`] for the given position within a MySQL binlog file.
+    ///
+    /// The binlog basename is used as the [`replication_log_name`](Self::replication_log_name),
+    /// and the offset encodes the length of the binlog file's numeric suffix in the top 5 bits
+    /// (which allows the suffix to be formatted back with any leading zeroes), the value of the
+    /// suffix in the following 59 bits, and the position within the file in the last 64 bits.
+    /// Since at most 17 decimal digits fit in 59 bits, longer suffixes are rejected.
+    pub fn from_binlog_position(binlog_file: &str, position: u32) -> ReadySetResult<Self> {
+        let (basename, suffix) = binlog_file.rsplit_once('.').ok_or_else(|| {
+            ReadySetError::ReplicationFailed(format!("Invalid binlog name {}", binlog_file))
+        })?;
+
+        let suffix_len = suffix.len() as u128;
+
+        if suffix_len > 17 {
+            return Err(ReadySetError::ReplicationFailed(format!(
+                "Invalid binlog suffix {}",
+                binlog_file
+            )));
+        }
+
+        let suffix = suffix.parse::<u128>().map_err(|_| {
+            ReadySetError::ReplicationFailed(format!("Invalid binlog suffix {}", binlog_file))
+        })?;
+
+        Ok(ReplicationOffset {
+            offset: (suffix_len << 123) + (suffix << 64) + (position as u128),
+            replication_log_name: basename.to_string(),
+        })
+    }
+
     /// Try to mutate `other` to take the maximum of its offset and the offset of
     /// `self`. If `other` is `None`, will assign it to `Some(self.clone)`.
     ///
@@ -82,6 +118,38 @@ impl ReplicationOffset {
 
         Ok(())
     }
+
+    /// Returns the number of bytes of the replication log between this offset and `other`, or
+    /// `None` if `other` is before this offset, or in a different replication log (or, for MySQL,
+    /// a different binlog file)
+    pub fn bytes_behind(&self, other: &ReplicationOffset) -> Option<u128> {
+        if self.replication_log_name != other.replication_log_name
+            || self.offset >> 64 != other.offset >> 64
+        {
+            return None;
+        }
+        other.offset.checked_sub(self.offset)
+    }
+}
+
+impl FromStr for ReplicationOffset {
+    type Err = ReadySetError;
+
+    /// Parses a [`ReplicationOffset`] from its [`Display`](fmt::Display) representation
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || ReadySetError::ReplicationFailed(format!("Invalid replication offset {}", s));
+
+        if let Some(offset) = s.strip_prefix("wal[").and_then(|s| s.strip_suffix(']')) {
+            return Ok(ReplicationOffset {
+                offset: offset.parse().map_err(|_| invalid())?,
+                replication_log_name: String::new(),
+            });
+        }
+
+        let (binlog_file, position) = s.rsplit_once(':').ok_or_else(invalid)?;
+        Self::from_binlog_position(binlog_file, position.parse().map_err(|_| invalid())?)
+    }
 }
 
 /// Set of replication offsets for the entire system
@@ -231,10 +299,276 @@ impl ReplicationOffsets {
     }
 }
 
+/// The progress of an unfinished snapshot of a single table from an upstream database.
+///
+/// This is only recorded when replicating from MySQL; PostgreSQL snapshots always start over.
+/// The replicator records this with the controller as it copies a table, which persists it in the
+/// authority, so that a snapshot interrupted by a restart can resume after the last row that was
+/// copied instead of starting over. Progress is only tracked for tables with a primary key, since
+/// rows are copied in primary key order.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct SnapshotProgress {
+    /// The replication offset the snapshot of the table was started at. Rows copied after
+    /// resuming the snapshot may reflect later changes, so once the snapshot finishes the table's
+    /// replication offset is set to this offset, and those changes are replicated again.
+    pub offset: ReplicationOffset,
+    /// The primary key of the last row that was copied into the table
+    pub last_key: Vec<DataType>,
+    /// The number of rows that have been copied into the table so far
+    pub rows_copied: u64,
+    /// The number of rows in the table when the snapshot was started or last resumed, if known
+    #[serde(default)]
+    pub total_rows: Option<u64>,
+}
+
+/// The state of the logical replication slot on the upstream database that the replicator is
+/// reading from.
+///
+/// This is only recorded when replicating from PostgreSQL. The replicator periodically reads this
+/// from `pg_replication_slots` and records it with the controller, which persists it in the
+/// authority so that it can be returned by `SHOW READYSET REPLICATION STATUS`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ReplicationSlotStatus {
+    /// The name of the replication slot
+    pub slot_name: String,
+    /// Whether a replication connection is currently streaming from the slot
+    pub active: bool,
+    /// The oldest WAL location which the upstream database must retain for the slot
+    pub restart_lsn: Option<String>,
+    /// The latest WAL location that the replicator has acknowledged as applied
+    pub confirmed_flush_lsn: Option<String>,
+    /// The number of bytes of WAL retained by the slot, between its `restart_lsn` and the
+    /// current WAL location of the upstream database
+    pub lag_bytes: Option<u64>,
+}
+
+/// A single version of the definition of a base table, as recorded in the controller's schema
+/// registry.
+///
+/// The controller records a new version every time a table is created or altered, along with the
+/// replication offset of the DDL statement that changed it, so that the history of a table's schema
+/// can be inspected when debugging replication issues.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct TableSchemaVersion {
+    /// The number of this version, starting at 0 for the definition the table was created with
+    pub version: u64,
+    /// The definition of the table as of this version
+    pub statement: CreateTableStatement,
+    /// The offset in the replication log of the DDL statement that produced this version, if it
+    /// was replicated from an upstream database
+    pub replication_offset: Option<ReplicationOffset>,
+}
+
+/// The namespace of the base tables replicated from a single upstream database, when ReadySet is
+/// replicating from more than one upstream database.
+///
+/// Tables replicated from an upstream database with a namespace are named `<namespace>_<table>`
+/// in ReadySet, so that tables with the same name in different upstream databases don't conflict
+/// with each other and can be joined in cached queries. Tables replicated from the primary
+/// upstream database have no namespace, and keep their upstream names.
+///
+/// A [`ControllerHandle`](crate::ControllerHandle) with a namespace set translates between
+/// upstream and ReadySet table names in the requests made by the replicator.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableNamespace {
+    /// The name of the namespace, or `None` for the primary upstream database
+    name: Option<String>,
+    /// The names of the namespaces of all other upstream databases
+    others: Vec<String>,
+}
+
+impl TableNamespace {
+    /// Create a new [`TableNamespace`] with the given name, or for the primary upstream database
+    /// if `name` is `None`. Tables in any of the namespaces in `others` are hidden from this
+    /// namespace.
+    pub fn new(name: Option<String>, others: Vec<String>) -> Self {
+        Self { name, others }
+    }
+
+    /// Returns the name of this namespace, or `None` for the primary upstream database
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns true if table names are left unchanged by this namespace
+    pub fn is_identity(&self) -> bool {
+        self.name.is_none() && self.others.is_empty()
+    }
+
+    /// Returns the name of the base table in ReadySet for the upstream table with the given name
+    pub fn table_name(&self, upstream: &str) -> String {
+        match &self.name {
+            Some(name) => format!("{}_{}", name, upstream),
+            None => upstream.to_owned(),
+        }
+    }
+
+    /// Returns the name of the upstream table for the base table in ReadySet with the given name,
+    /// or `None` if the table doesn't belong to this namespace
+    pub fn upstream_table_name<'a>(&self, table: &'a str) -> Option<&'a str> {
+        fn strip<'a>(table: &'a str, namespace: &str) -> Option<&'a str> {
+            table
+                .strip_prefix(namespace)
+                .and_then(|t| t.strip_prefix('_'))
+                .filter(|t| !t.is_empty())
+        }
+
+        match &self.name {
+            Some(name) => strip(table, name),
+            None if self.others.iter().any(|ns| strip(table, ns).is_some()) => None,
+            None => Some(table),
+        }
+    }
+
+    /// Rename all the tables created, altered or dropped by `changelist` into this namespace.
+    ///
+    /// Views and caches reference upstream tables by name and can't be renamed, so they're
+    /// skipped in namespaced upstream databases.
+    pub fn namespace_changelist(&self, changelist: ChangeList) -> ChangeList {
+        if self.name.is_none() {
+            return changelist;
+        }
+
+        let changes = changelist
+            .changes
+            .into_iter()
+            .filter_map(|change| match change {
+                Change::CreateTable(mut stmt) => {
+                    stmt.table.name = self.table_name(&stmt.table.name).into();
+                    for key in stmt.keys.iter_mut().flatten() {
+                        if let nom_sql::TableKey::ForeignKey { target_table, .. } = key {
+                            target_table.name = self.table_name(&target_table.name).into();
+                        }
+                    }
+                    Some(Change::CreateTable(stmt))
+                }
+                Change::AlterTable(mut stmt) => {
+                    stmt.table.name = self.table_name(&stmt.table.name).into();
+                    Some(Change::AlterTable(stmt))
+                }
+                Change::Drop { name, if_exists } => Some(Change::Drop {
+                    name: self.table_name(&name).into(),
+                    if_exists,
+                }),
+                change @ (Change::CreateView(_) | Change::CreateCache(_)) => {
+                    warn!(
+                        namespace = ?self.name,
+                        ?change,
+                        "Skipping view replicated from namespaced upstream database"
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        ChangeList { changes }
+    }
+
+    /// Remove all tables that don't belong to this namespace from `offsets`, and rename the
+    /// remaining tables to their upstream names
+    pub fn filter_offsets(&self, offsets: &mut ReplicationOffsets) {
+        if self.is_identity() {
+            return;
+        }
+
+        offsets.tables = std::mem::take(&mut offsets.tables)
+            .into_iter()
+            .filter_map(|(table, offset)| {
+                self.upstream_table_name(&table)
+                    .map(|upstream| (upstream.into(), offset))
+            })
+            .collect();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    mod from_binlog_position {
+        use super::*;
+
+        #[test]
+        fn round_trips_through_display() {
+            let offset = ReplicationOffset::from_binlog_position("binlog.000042", 1234).unwrap();
+            assert_eq!(offset.replication_log_name, "binlog");
+            assert_eq!(offset.to_string(), "binlog.000042:1234");
+        }
+
+        #[test]
+        fn orders_by_file_then_position() {
+            let offset =
+                |file, position| ReplicationOffset::from_binlog_position(file, position).unwrap();
+            assert!(offset("binlog.000001", 500) < offset("binlog.000001", 501));
+            assert!(offset("binlog.000001", 500) < offset("binlog.000002", 4));
+        }
+
+        #[test]
+        fn invalid_name() {
+            assert!(ReplicationOffset::from_binlog_position("binlog", 4).is_err());
+            assert!(ReplicationOffset::from_binlog_position("binlog.index", 4).is_err());
+        }
+    }
+
+    mod from_str {
+        use super::*;
+
+        #[test]
+        fn binlog_position() {
+            let offset = ReplicationOffset::from_binlog_position("binlog.000042", 1234).unwrap();
+            assert_eq!(
+                offset.to_string().parse::<ReplicationOffset>().unwrap(),
+                offset
+            );
+        }
+
+        #[test]
+        fn wal_position() {
+            let offset = ReplicationOffset {
+                offset: 123456,
+                replication_log_name: String::new(),
+            };
+            assert_eq!(
+                offset.to_string().parse::<ReplicationOffset>().unwrap(),
+                offset
+            );
+        }
+
+        #[test]
+        fn invalid() {
+            assert!("wal[x]".parse::<ReplicationOffset>().is_err());
+            assert!("binlog.000001".parse::<ReplicationOffset>().is_err());
+        }
+    }
+
+    mod bytes_behind {
+        use super::*;
+
+        #[test]
+        fn same_binlog_file() {
+            let offset =
+                |file, position| ReplicationOffset::from_binlog_position(file, position).unwrap();
+            assert_eq!(
+                offset("binlog.000001", 500).bytes_behind(&offset("binlog.000001", 600)),
+                Some(100)
+            );
+            assert_eq!(
+                offset("binlog.000001", 600).bytes_behind(&offset("binlog.000001", 500)),
+                None
+            );
+        }
+
+        #[test]
+        fn different_binlog_file() {
+            let offset =
+                |file, position| ReplicationOffset::from_binlog_position(file, position).unwrap();
+            assert_eq!(
+                offset("binlog.000001", 500).bytes_behind(&offset("binlog.000002", 4)),
+                None
+            );
+        }
+    }
+
     mod max_offset {
         use super::*;
 
@@ -342,4 +676,56 @@ mod tests {
             assert!(res.is_none());
         }
     }
+
+    mod table_namespace {
+        use super::*;
+
+        #[test]
+        fn table_names() {
+            let primary = TableNamespace::new(None, vec!["billing".to_owned()]);
+            let billing = TableNamespace::new(Some("billing".to_owned()), vec![]);
+
+            assert_eq!(primary.table_name("invoices"), "invoices");
+            assert_eq!(billing.table_name("invoices"), "billing_invoices");
+
+            assert_eq!(primary.upstream_table_name("users"), Some("users"));
+            assert_eq!(primary.upstream_table_name("billing_invoices"), None);
+            assert_eq!(billing.upstream_table_name("users"), None);
+            assert_eq!(
+                billing.upstream_table_name("billing_invoices"),
+                Some("invoices")
+            );
+        }
+
+        #[test]
+        fn filter_offsets() {
+            let offset = ReplicationOffset {
+                offset: 1,
+                replication_log_name: "test".to_owned(),
+            };
+            let offsets = ReplicationOffsets {
+                schema: None,
+                tables: HashMap::from([
+                    ("users".into(), Some(offset.clone())),
+                    ("billing_invoices".into(), None),
+                ]),
+            };
+
+            let mut primary_offsets = offsets.clone();
+            TableNamespace::new(None, vec!["billing".to_owned()])
+                .filter_offsets(&mut primary_offsets);
+            assert_eq!(
+                primary_offsets.tables,
+                HashMap::from([("users".into(), Some(offset))])
+            );
+
+            let mut billing_offsets = offsets;
+            TableNamespace::new(Some("billing".to_owned()), vec![])
+                .filter_offsets(&mut billing_offsets);
+            assert_eq!(
+                billing_offsets.tables,
+                HashMap::from([("invoices".into(), None)])
+            );
+        }
+    }
 }
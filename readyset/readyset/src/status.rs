@@ -7,15 +7,28 @@
 //!
 //! These two converions are used to convert the [`ReadySetStatus`] structs to a format
 //! that can be passed to various SQL clients.
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
 
 use mysql_common::row::Row;
-use readyset_errors::{internal, ReadySetError};
+use readyset_errors::{internal, internal_err, ReadySetError};
 use serde::{Deserialize, Serialize};
 
+use crate::replication::ReplicationOffset;
+
 // Consts for variable names.
 const SNAPSHOT_STATUS_VARIABLE: &str = "Snapshot Status";
+/// Prefix of the variables holding the snapshot progress of each table, followed by the table name
+const TABLE_SNAPSHOT_PROGRESS_PREFIX: &str = "Snapshot Progress: ";
+const REPLICATOR_RECONNECTS_VARIABLE: &str = "Replicator Reconnect Attempts";
+const LAST_REPLICATOR_ERROR_VARIABLE: &str = "Last Replicator Error";
+const REPLICATION_OFFSET_VARIABLE: &str = "Replication Offset";
+const REPLICATION_LAG_VARIABLE: &str = "Replication Lag Bytes";
+const TOTAL_MEMORY_VARIABLE: &str = "Total Memory Bytes";
+const PARTIAL_MEMORY_VARIABLE: &str = "Partial Memory Bytes";
+const EVICTIONS_VARIABLE: &str = "Evictions";
+const CONTROLLER_LEADER_VARIABLE: &str = "Controller Leader";
 
 /// ReadySetStatus holds information regarding the status of ReadySet, similar to
 /// [`SHOW STATUS`](https://dev.mysql.com/doc/refman/8.0/en/show-status.html) in MySQL.
@@ -25,7 +38,29 @@ const SNAPSHOT_STATUS_VARIABLE: &str = "Snapshot Status";
 pub struct ReadySetStatus {
     /// The snapshot status of the current leader.
     pub snapshot_status: SnapshotStatus,
-    //TODO: Include binlog position and other fields helpful for evaluating a ReadySet cluster.
+    /// The tables that are still being snapshotted, along with the percentage of each table's
+    /// rows that have been copied so far, if known.
+    pub table_snapshot_progress: BTreeMap<String, Option<f64>>,
+    /// The number of times the replicator has been restarted to reconnect to the upstream
+    /// database after failing.
+    pub replicator_reconnects: u64,
+    /// The error that caused the replicator to most recently be restarted, if any.
+    pub last_replicator_error: Option<String>,
+    /// The offset in the upstream database's replication log up to which every table has been
+    /// replicated, if any.
+    pub replication_offset: Option<ReplicationOffset>,
+    /// The number of bytes of the upstream database's replication log that have yet to be
+    /// replicated, if known. Only the adapter can fill this in, since the controller doesn't know
+    /// the upstream database's current replication offset.
+    pub replication_lag_bytes: Option<u128>,
+    /// The total size of all materialized state, in bytes.
+    pub total_memory_bytes: u64,
+    /// The size of all partially materialized state, which can be evicted, in bytes.
+    pub partial_memory_bytes: u64,
+    /// The number of times state has been evicted to free memory.
+    pub evictions: u64,
+    /// The URI of the controller that is currently the leader.
+    pub controller_leader: Option<String>,
 }
 
 impl TryFrom<Vec<(String, String)>> for ReadySetStatus {
@@ -33,13 +68,61 @@ impl TryFrom<Vec<(String, String)>> for ReadySetStatus {
     fn try_from(vars: Vec<(String, String)>) -> Result<Self, Self::Error> {
         let mut res = ReadySetStatus {
             snapshot_status: SnapshotStatus::InProgress,
+            table_snapshot_progress: BTreeMap::new(),
+            replicator_reconnects: 0,
+            last_replicator_error: None,
+            replication_offset: None,
+            replication_lag_bytes: None,
+            total_memory_bytes: 0,
+            partial_memory_bytes: 0,
+            evictions: 0,
+            controller_leader: None,
         };
         for v in vars {
             match (v.0.as_str(), v.1) {
                 (SNAPSHOT_STATUS_VARIABLE, v) => res.snapshot_status = SnapshotStatus::try_from(v)?,
-                (_, _) => {
-                    internal!("Invalid ReadySetStatus variable")
+                (REPLICATOR_RECONNECTS_VARIABLE, v) => {
+                    res.replicator_reconnects = v
+                        .parse()
+                        .map_err(|_| internal_err("Invalid replicator reconnect attempts"))?
+                }
+                (LAST_REPLICATOR_ERROR_VARIABLE, v) => res.last_replicator_error = Some(v),
+                (REPLICATION_OFFSET_VARIABLE, v) => res.replication_offset = Some(v.parse()?),
+                (REPLICATION_LAG_VARIABLE, v) => {
+                    res.replication_lag_bytes = Some(
+                        v.parse()
+                            .map_err(|_| internal_err("Invalid replication lag"))?,
+                    )
+                }
+                (TOTAL_MEMORY_VARIABLE, v) => {
+                    res.total_memory_bytes = v
+                        .parse()
+                        .map_err(|_| internal_err("Invalid total memory"))?
+                }
+                (PARTIAL_MEMORY_VARIABLE, v) => {
+                    res.partial_memory_bytes = v
+                        .parse()
+                        .map_err(|_| internal_err("Invalid partial memory"))?
                 }
+                (EVICTIONS_VARIABLE, v) => {
+                    res.evictions = v.parse().map_err(|_| internal_err("Invalid evictions"))?
+                }
+                (CONTROLLER_LEADER_VARIABLE, v) => res.controller_leader = Some(v),
+                (name, v) => match name.strip_prefix(TABLE_SNAPSHOT_PROGRESS_PREFIX) {
+                    Some(table) => {
+                        let percent = match v.strip_suffix('%') {
+                            Some(percent) => Some(
+                                percent
+                                    .parse()
+                                    .map_err(|_| internal_err("Invalid snapshot progress"))?,
+                            ),
+                            None => None,
+                        };
+                        res.table_snapshot_progress
+                            .insert(table.to_owned(), percent);
+                    }
+                    None => internal!("Invalid ReadySetStatus variable"),
+                },
             }
         }
 
@@ -49,10 +132,45 @@ impl TryFrom<Vec<(String, String)>> for ReadySetStatus {
 
 impl From<ReadySetStatus> for Vec<(String, String)> {
     fn from(status: ReadySetStatus) -> Vec<(String, String)> {
-        vec![(
+        let mut res = vec![(
             SNAPSHOT_STATUS_VARIABLE.to_string(),
             status.snapshot_status.to_string(),
-        )]
+        )];
+        for (table, percent) in status.table_snapshot_progress {
+            res.push((
+                format!("{}{}", TABLE_SNAPSHOT_PROGRESS_PREFIX, table),
+                match percent {
+                    Some(percent) => format!("{:.1}%", percent),
+                    None => SnapshotStatus::InProgress.to_string(),
+                },
+            ));
+        }
+        res.push((
+            REPLICATOR_RECONNECTS_VARIABLE.to_string(),
+            status.replicator_reconnects.to_string(),
+        ));
+        if let Some(error) = status.last_replicator_error {
+            res.push((LAST_REPLICATOR_ERROR_VARIABLE.to_string(), error));
+        }
+        if let Some(offset) = status.replication_offset {
+            res.push((REPLICATION_OFFSET_VARIABLE.to_string(), offset.to_string()));
+        }
+        if let Some(lag) = status.replication_lag_bytes {
+            res.push((REPLICATION_LAG_VARIABLE.to_string(), lag.to_string()));
+        }
+        res.push((
+            TOTAL_MEMORY_VARIABLE.to_string(),
+            status.total_memory_bytes.to_string(),
+        ));
+        res.push((
+            PARTIAL_MEMORY_VARIABLE.to_string(),
+            status.partial_memory_bytes.to_string(),
+        ));
+        res.push((EVICTIONS_VARIABLE.to_string(), status.evictions.to_string()));
+        if let Some(leader) = status.controller_leader {
+            res.push((CONTROLLER_LEADER_VARIABLE.to_string(), leader));
+        }
+        res
     }
 }
 
@@ -116,8 +234,63 @@ mod tests {
     fn readyset_status_round_trip() {
         let original = ReadySetStatus {
             snapshot_status: SnapshotStatus::Completed,
+            table_snapshot_progress: BTreeMap::new(),
+            replicator_reconnects: 0,
+            last_replicator_error: None,
+            replication_offset: None,
+            replication_lag_bytes: None,
+            total_memory_bytes: 0,
+            partial_memory_bytes: 0,
+            evictions: 0,
+            controller_leader: None,
+        };
+        let intermediate: Vec<(String, String)> = original.clone().into();
+        let round_tripped = ReadySetStatus::try_from(intermediate).unwrap();
+
+        assert_eq!(original, round_tripped);
+    }
+
+    #[test]
+    fn readyset_status_round_trip_with_replicator_error() {
+        let original = ReadySetStatus {
+            snapshot_status: SnapshotStatus::InProgress,
+            table_snapshot_progress: BTreeMap::new(),
+            replicator_reconnects: 3,
+            last_replicator_error: Some("Connection refused".to_owned()),
+            replication_offset: None,
+            replication_lag_bytes: None,
+            total_memory_bytes: 0,
+            partial_memory_bytes: 0,
+            evictions: 0,
+            controller_leader: None,
+        };
+        let intermediate: Vec<(String, String)> = original.clone().into();
+        let round_tripped = ReadySetStatus::try_from(intermediate).unwrap();
+
+        assert_eq!(original, round_tripped);
+    }
+
+    #[test]
+    fn readyset_status_round_trip_with_all_fields() {
+        let original = ReadySetStatus {
+            snapshot_status: SnapshotStatus::InProgress,
+            table_snapshot_progress: BTreeMap::from([
+                ("t1".to_owned(), Some(42.5)),
+                ("t2".to_owned(), None),
+            ]),
+            replicator_reconnects: 1,
+            last_replicator_error: None,
+            replication_offset: Some(
+                ReplicationOffset::from_binlog_position("binlog.000003", 1234).unwrap(),
+            ),
+            replication_lag_bytes: Some(100),
+            total_memory_bytes: 4096,
+            partial_memory_bytes: 1024,
+            evictions: 7,
+            controller_leader: Some("http://127.0.0.1:6033/".to_owned()),
         };
         let intermediate: Vec<(String, String)> = original.clone().into();
+        assert!(intermediate.contains(&("Snapshot Progress: t1".to_owned(), "42.5%".to_owned())));
         let round_tripped = ReadySetStatus::try_from(intermediate).unwrap();
 
         assert_eq!(original, round_tripped);
@@ -0,0 +1,77 @@
+//! Data types for configuring how base table data is stored on disk.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The default maximum size, in bytes, of the dictionary used when dictionary compression is
+/// enabled for a base table.
+pub const DEFAULT_MAX_DICT_BYTES: u32 = 16 * 1024;
+
+/// The compression algorithm used for the on-disk data of a base table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompressionType {
+    /// Don't compress data at all
+    None,
+    /// Compress data with LZ4. Cheap to compress and decompress, at the cost of compression ratio.
+    Lz4,
+    /// Compress data with Zstandard. Slower than LZ4, but achieves significantly better
+    /// compression ratios, especially for large text columns.
+    Zstd,
+}
+
+impl fmt::Display for CompressionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Lz4 => write!(f, "lz4"),
+            Self::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid compression type; expected one of none, lz4, or zstd")]
+pub struct InvalidCompressionType;
+
+impl FromStr for CompressionType {
+    type Err = InvalidCompressionType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "lz4" => Ok(Self::Lz4),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(InvalidCompressionType),
+        }
+    }
+}
+
+/// Options controlling how the on-disk data of a base table is compressed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionOptions {
+    /// The compression algorithm to use
+    pub compression_type: CompressionType,
+    /// The maximum size, in bytes, of the dictionary to build for each compressed file. Shared
+    /// dictionaries significantly improve compression ratios for tables with many small, similar
+    /// text values. If 0, dictionary compression is disabled.
+    pub max_dict_bytes: u32,
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self {
+            compression_type: CompressionType::Lz4,
+            max_dict_bytes: 0,
+        }
+    }
+}
+
+impl CompressionOptions {
+    /// Returns true if dictionary compression is enabled
+    pub fn uses_dictionary(&self) -> bool {
+        self.compression_type != CompressionType::None && self.max_dict_bytes > 0
+    }
+}
@@ -5,7 +5,7 @@ use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant, SystemTime};
-use std::{fmt, iter};
+use std::{fmt, iter, mem};
 
 use async_bincode::{AsyncBincodeStream, AsyncDestination};
 use derive_more::TryInto;
@@ -14,12 +14,13 @@ use futures_util::stream::futures_unordered::FuturesUnordered;
 use futures_util::stream::TryStreamExt;
 use futures_util::{future, ready};
 use itertools::Either;
-use nom_sql::{CreateTableStatement, SqlIdentifier};
-use readyset_data::DataType;
+use nom_sql::{CreateTableStatement, SqlIdentifier, SqlType};
+use readyset_data::{Collation, DataType};
 use readyset_errors::{
     internal, internal_err, rpc_err, table_err, unsupported, ReadySetError, ReadySetResult,
 };
 use petgraph::graph::NodeIndex;
+use readyset_tracing::propagation::RequestContext;
 use serde::{Deserialize, Serialize};
 use tokio::io::AsyncWriteExt;
 use tokio_tower::multiplex;
@@ -27,7 +28,7 @@ use tower::balance::p2c::Balance;
 use tower::buffer::Buffer;
 use tower::limit::concurrency::ConcurrencyLimit;
 use tower_service::Service;
-use tracing::error;
+use tracing::{error, warn};
 use vec_map::VecMap;
 
 use crate::channel::CONNECTION_FROM_BASE;
@@ -108,6 +109,9 @@ pub enum TableOperation {
     /// Enter or exit snapshot mode for the underlying persistent storage. In snapshot mode
     /// compactions are disabled and writes don't go into WAL first.
     SetSnapshotMode(bool),
+
+    /// Delete every row in the table, as of this point in the group of table operations.
+    Truncate,
 }
 
 impl TableOperation {
@@ -136,12 +140,37 @@ impl TableOperation {
             TableOperation::InsertOrUpdate { row, .. } => Some(&row[key_col]),
             TableOperation::SetReplicationOffset(_) => None,
             TableOperation::SetSnapshotMode(_) => None,
+            TableOperation::Truncate => None,
         };
 
         if let Some(key) = key {
             Either::Left(iter::once(crate::shard_by(key, num_shards)))
         } else {
-            // updates to replication offsets should hit all shards
+            // updates to replication offsets and truncates should hit all shards
+            Either::Right(0..num_shards)
+        }
+    }
+
+    /// Construct an iterator over the shards this TableOperation should target, for a table
+    /// that's sharded by `shard_col` rather than by its key.
+    ///
+    /// Operations that only identify the row they apply to by its key are sent to every shard,
+    /// since the shard holding that row can't be determined from the key.
+    pub fn shards_by_column(
+        &self,
+        shard_col: usize,
+        num_shards: usize,
+    ) -> impl Iterator<Item = usize> {
+        let value = match self {
+            TableOperation::Insert(row)
+            | TableOperation::DeleteRow { row }
+            | TableOperation::InsertOrUpdate { row, .. } => row.get(shard_col),
+            _ => None,
+        };
+
+        if let Some(value) = value {
+            Either::Left(iter::once(crate::shard_by(value, num_shards)))
+        } else {
             Either::Right(0..num_shards)
         }
     }
@@ -255,6 +284,8 @@ pub struct PacketData {
     pub data: PacketPayload,
     /// Optional packet trace to associate with the packet.
     pub trace: Option<PacketTrace>,
+    /// The context of the trace the write is part of, if it is being traced
+    pub trace_context: Option<RequestContext>,
 }
 
 /// Wrapper around types that can be propagated to base tables
@@ -282,6 +313,8 @@ pub struct TableBuilder {
     pub addr: LocalNodeIndex,
     pub key_is_primary: bool,
     pub key: Vec<usize>,
+    /// The column the table is sharded by, if it's sharded by a column other than its key
+    pub shard_column: Option<usize>,
     pub dropped: VecMap<DataType>,
 
     pub table_name: SqlIdentifier,
@@ -290,6 +323,11 @@ pub struct TableBuilder {
 
     /// The amount of time before a table request RPC is terminated.
     pub table_request_timeout: Duration,
+
+    /// The length in bytes beyond which text and byte values are truncated before being written
+    /// to the table, if any.
+    #[serde(default)]
+    pub max_value_size: Option<usize>,
 }
 
 impl TableBuilder {
@@ -331,20 +369,27 @@ impl TableBuilder {
             conns.push(s);
         }
 
+        let collations = column_collations(self.schema.as_ref(), &self.columns, &self.dropped);
+        let enum_columns = enum_columns(self.schema.as_ref(), &self.columns, &self.dropped);
+
         Table {
             ni: self.ni,
             node: self.addr,
             key: self.key,
             key_is_primary: self.key_is_primary,
+            shard_column: self.shard_column,
             columns: self.columns,
             dropped: self.dropped,
             table_name: self.table_name,
             schema: self.schema,
+            collations,
+            enum_columns,
             dst_is_local: false,
             shard_addrs: addrs,
             shards: conns,
             last_trace_sample: Instant::now(),
             request_timeout: self.table_request_timeout,
+            max_value_size: self.max_value_size,
         }
     }
 }
@@ -362,15 +407,23 @@ pub struct Table {
     pub node: LocalNodeIndex,
     key_is_primary: bool,
     key: Vec<usize>,
+    shard_column: Option<usize>,
     columns: Vec<SqlIdentifier>,
     dropped: VecMap<DataType>,
     table_name: SqlIdentifier,
     schema: Option<CreateTableStatement>,
+    /// The collation of each column of the base table, including dropped columns, or an empty
+    /// `Vec` if every column uses [`Collation::Binary`]
+    collations: Vec<Collation>,
+    /// The type of each `ENUM` column of the base table, keyed by the column's index
+    enum_columns: VecMap<SqlType>,
     dst_is_local: bool,
     shards: Vec<TableRpc>,
     shard_addrs: Vec<SocketAddr>,
     last_trace_sample: Instant,
     request_timeout: Duration,
+    /// The length in bytes beyond which text and byte values are truncated, if any
+    max_value_size: Option<usize>,
 }
 
 impl fmt::Debug for Table {
@@ -380,12 +433,16 @@ impl fmt::Debug for Table {
             .field("node", &self.node)
             .field("key_is_primary", &self.key_is_primary)
             .field("key", &self.key)
+            .field("shard_column", &self.shard_column)
             .field("columns", &self.columns)
             .field("dropped", &self.dropped)
             .field("table_name", &self.table_name)
             .field("schema", &self.schema)
+            .field("collations", &self.collations)
+            .field("enum_columns", &self.enum_columns)
             .field("dst_is_local", &self.dst_is_local)
             .field("shard_addrs", &self.shard_addrs)
+            .field("max_value_size", &self.max_value_size)
             .finish()
     }
 }
@@ -461,6 +518,7 @@ impl Table {
                     }
                     TableOperation::SetReplicationOffset(_) => {}
                     TableOperation::SetSnapshotMode(_) => {}
+                    TableOperation::Truncate => {}
                 }
             }
             Ok(())
@@ -483,23 +541,25 @@ impl Table {
             }
             _ => {
                 let key_len = self.key.len();
-                let key_col = match self.key.get(0) {
+                // Either the key column, or the column the table is sharded by if it's not the key
+                let shard_by = match (self.shard_column, self.key.get(0)) {
+                    (Some(col), _) => Either::Right(col),
                     // If it's `None`, then it's empty.
-                    None => {
+                    (None, None) => {
                         return future::Either::Right(future::Either::Left(future::Either::Left(
                             future::Either::Left(
                                 async move { internal!("sharded base without a key") },
                             ),
                         )))
                     }
-                    Some(_) if key_len != 1 => {
+                    (None, Some(_)) if key_len != 1 => {
                         return future::Either::Right(future::Either::Left(future::Either::Left(
                             future::Either::Right(async move {
                                 internal!("base sharded by complex key")
                             }),
                         )))
                     }
-                    Some(&k) => k,
+                    (None, Some(&k)) => Either::Left(k),
                 };
 
                 let _guard = span.as_ref().map(tracing::Span::enter);
@@ -514,7 +574,11 @@ impl Table {
                     }
                 };
                 for r in ops.drain(..) {
-                    for shard in r.shards(key_col, nshards) {
+                    let shards = match shard_by {
+                        Either::Left(key_col) => Either::Left(r.shards(key_col, nshards)),
+                        Either::Right(col) => Either::Right(r.shards_by_column(col, nshards)),
+                    };
+                    for shard in shards {
                         // The `shard` index belongs to the range `0..nshards`,
                         // so it's not out of bounds.
                         #[allow(clippy::indexing_slicing)]
@@ -529,6 +593,7 @@ impl Table {
                             dst: i.dst,
                             data: PacketPayload::Input(rs),
                             trace: i.trace.clone(),
+                            trace_context: i.trace_context.clone(),
                         };
 
                         let p = unsafe { LocalOrNot::new_for_dst(new_i, self.dst_is_local) };
@@ -657,6 +722,7 @@ impl Service<TableRequest> for Table {
                     dst: self.node,
                     data: PacketPayload::Timestamp(t),
                     trace: None,
+                    trace_context: RequestContext::from_current_span(),
                 };
                 future::Either::Right(self.timestamp(p).map_err(|e| table_err(tn, e)))
             }
@@ -683,6 +749,18 @@ impl Table {
         &self.columns
     }
 
+    /// Get the indices of the columns that make up the key of this base table, or an empty slice
+    /// if it doesn't have one.
+    pub fn key(&self) -> &[usize] {
+        &self.key
+    }
+
+    /// Returns true if this table has a primary key, rather than being keyed by the column it's
+    /// sharded by (if any).
+    pub fn has_primary_key(&self) -> bool {
+        self.key_is_primary && !self.key.is_empty()
+    }
+
     /// Get the schema that was used to create this base table.
     ///
     /// Note that this will *not* be updated if the underlying recipe changes and adds or removes
@@ -692,93 +770,173 @@ impl Table {
     }
 
     fn inject_dropped_cols(&self, r: &mut TableOperation) -> ReadySetResult<()> {
-        use std::mem;
-        let ndropped = self.dropped.len();
-        if ndropped != 0 {
-            // inject defaults for dropped columns
-            let dropped = self.dropped.iter().rev();
-
-            // get a handle to the underlying data vector
-            let r = match *r {
-                TableOperation::Insert(ref mut row)
-                | TableOperation::InsertOrUpdate { ref mut row, .. } => row,
-                _ => unimplemented!("we need to shift the update/delete cols!"),
-            };
-            // TODO: what about updates? do we need to rewrite the set vector?
-
-            // we want to be a bit careful here to avoid shifting elements multiple times. we
-            // do this by moving from the back, and swapping the tail element to the end of the
-            // vector until we hit each index.
-
-            // in other words, if we have two default values, we're going to start out with:
-            //
-            // |####..|
-            //
-            // where # are "real" fields in the record and . are None values.
-            // we want to end up with something like
-            //
-            // |#d##d#|
-            //
-            // if columns 1 and 4 were dropped (d here signifies the default values).
-            // what makes this tricky is that we need to preserve the order of all the #.
-            // to accomplish this, we're going to move the # to the end of the record, one at a
-            // time, starting with the last one, and then "inject" the default values as we go.
-            // that way, we only make one pass over the record!
-            //
-            // in particular, progress is going to look like this (i've swapped # for col #):
-            //
-            // |1234..|  hole = 5, next_insert = 4, last_unmoved = 3
-            // swap 4 and last .
-            // |123..4|  hole = 4, next_insert = 4, last_unmoved = 2
-            // hole == next_insert, so insert default value
-            // |123.d4|  hole = 4, next_insert = 4, last_unmoved = 2
-            // move on to next dropped column
-            // |123.d4|  hole = 3, next_insert = 1, last_unmoved = 2
-            // swap 3 and last .
-            // |12.3d4|  hole = 2, next_insert = 1, last_unmoved = 1
-            // swap 2 and last .
-            // |1.23d4|  hole = 1, next_insert = 1, last_unmoved = 0
-            // hole == next_insert, so insert default value
-            // |1d23d4|
-            // move on to next dropped column, but since there is none, we're done
-
-            // make room in the record
-            let n = r.len() + ndropped;
-            let mut hole = n;
-            let mut last_unmoved = r.len() - 1;
-            r.resize(n, DataType::None);
-
-            // keep trying to insert the next dropped column
-            for (next_insert, default) in dropped {
-                // think of this being at the bottom of the loop
-                // we just hoist it here to avoid underflow if we ever insert at 0
-                hole -= 1;
-
-                // shift elements until the next free slot is the one we want to insert into
-                while hole != next_insert {
-                    // shift another element so the free slot is at a lower index
-                    r.swap(last_unmoved, hole);
-                    hole -= 1;
-
-                    if last_unmoved == 0 {
-                        // there are no more elements -- the next slot to insert at better be [0]
-                        debug_assert_eq!(next_insert, 0);
-                        debug_assert_eq!(hole, 0);
-                        break;
-                    }
-                    last_unmoved -= 1;
+        if self.dropped.is_empty() {
+            return Ok(());
+        }
+
+        // inject defaults for dropped columns into rows, and leave them unmodified in updates.
+        // keys only contain the values of the key columns, so they don't need to change.
+        let unmodified = |_: &DataType| Modification::None;
+        match r {
+            TableOperation::Insert(row) | TableOperation::DeleteRow { row } => {
+                inject_dropped(row, &self.dropped, Clone::clone, DataType::None)
+            }
+            TableOperation::InsertOrUpdate { row, update } => {
+                inject_dropped(row, &self.dropped, Clone::clone, DataType::None)?;
+                inject_dropped(update, &self.dropped, unmodified, Modification::None)
+            }
+            TableOperation::Update { update, .. } => {
+                inject_dropped(update, &self.dropped, unmodified, Modification::None)
+            }
+            TableOperation::DeleteByKey { .. }
+            | TableOperation::SetReplicationOffset(_)
+            | TableOperation::SetSnapshotMode(_)
+            | TableOperation::Truncate => Ok(()),
+        }
+    }
+
+    /// Give the text values in `r` the collations of their columns, so that they're compared
+    /// using those collations in the dataflow graph
+    fn apply_collations(&self, r: &mut TableOperation) {
+        if self.collations.is_empty() {
+            return;
+        }
+
+        let collate = |value: &mut DataType, collation: &Collation| {
+            if value.is_string() {
+                *value = mem::take(value).with_collation(*collation);
+            }
+        };
+        let collate_update = |update: &mut Vec<Modification>| {
+            for (m, collation) in update.iter_mut().zip(&self.collations) {
+                if let Modification::Set(value) | Modification::Apply(_, value) = m {
+                    collate(value, collation);
+                }
+            }
+        };
+        let collate_key = |key: &mut Vec<DataType>| {
+            for (value, col) in key.iter_mut().zip(&self.key) {
+                if let Some(collation) = self.collations.get(*col) {
+                    collate(value, collation);
                 }
+            }
+        };
 
-                // we're at the right index -- insert the dropped value
-                let current = match r.get_mut(next_insert) {
-                    Some(v) => v,
-                    None => internal!("index out of bounds"),
-                };
-                let old = mem::replace(current, default.clone());
-                debug_assert_eq!(old, DataType::None);
+        match r {
+            TableOperation::Insert(row) | TableOperation::DeleteRow { row } => {
+                for (value, collation) in row.iter_mut().zip(&self.collations) {
+                    collate(value, collation);
+                }
             }
+            TableOperation::InsertOrUpdate { row, update } => {
+                for (value, collation) in row.iter_mut().zip(&self.collations) {
+                    collate(value, collation);
+                }
+                collate_update(update);
+            }
+            TableOperation::Update { update, key } => {
+                collate_update(update);
+                collate_key(key);
+            }
+            TableOperation::DeleteByKey { key } => collate_key(key),
+            TableOperation::SetReplicationOffset(_)
+            | TableOperation::SetSnapshotMode(_)
+            | TableOperation::Truncate => {}
+        }
+    }
+
+    /// Truncate the text and byte values in `r` which are longer than `max_value_size` bytes, so
+    /// that a single very wide value can't blow up the size of the messages sent through the
+    /// dataflow graph and of materialized state
+    fn truncate_wide_values(&self, r: &mut TableOperation) {
+        let max_len = match self.max_value_size {
+            Some(max_len) => max_len,
+            None => return,
+        };
+
+        let truncate = |value: &mut DataType| {
+            if let Some(truncated) = truncate_value(value, max_len) {
+                warn!(
+                    table = %self.table_name,
+                    max_len,
+                    "Truncating value which exceeds the maximum value size"
+                );
+                *value = truncated;
+            }
+        };
+        let truncate_update = |update: &mut Vec<Modification>| {
+            for m in update {
+                if let Modification::Set(value) | Modification::Apply(_, value) = m {
+                    truncate(value);
+                }
+            }
+        };
+
+        match r {
+            TableOperation::Insert(row)
+            | TableOperation::DeleteRow { row }
+            | TableOperation::DeleteByKey { key: row } => row.iter_mut().for_each(&truncate),
+            TableOperation::InsertOrUpdate { row, update } => {
+                row.iter_mut().for_each(&truncate);
+                truncate_update(update);
+            }
+            TableOperation::Update { update, key } => {
+                truncate_update(update);
+                key.iter_mut().for_each(&truncate);
+            }
+            TableOperation::SetReplicationOffset(_)
+            | TableOperation::SetSnapshotMode(_)
+            | TableOperation::Truncate => {}
+        }
+    }
+
+    /// Convert the values of the `ENUM` columns in `r` to the indices of their variants, which is
+    /// how they're stored in the dataflow graph (see [`readyset_data::enums`])
+    fn coerce_enums(&self, r: &mut TableOperation) -> ReadySetResult<()> {
+        if self.enum_columns.is_empty() {
+            return Ok(());
+        }
+
+        let coerce_row = |row: &mut Vec<DataType>| -> ReadySetResult<()> {
+            for (col, ty) in &self.enum_columns {
+                if let Some(value) = row.get_mut(col) {
+                    *value = value.coerce_to(ty)?;
+                }
+            }
+            Ok(())
+        };
+        let coerce_update = |update: &mut Vec<Modification>| -> ReadySetResult<()> {
+            for (col, ty) in &self.enum_columns {
+                if let Some(Modification::Set(value)) = update.get_mut(col) {
+                    *value = value.coerce_to(ty)?;
+                }
+            }
+            Ok(())
+        };
+        let coerce_key = |key: &mut Vec<DataType>| -> ReadySetResult<()> {
+            for (value, col) in key.iter_mut().zip(&self.key) {
+                if let Some(ty) = self.enum_columns.get(*col) {
+                    *value = value.coerce_to(ty)?;
+                }
+            }
+            Ok(())
+        };
+
+        match r {
+            TableOperation::Insert(row) | TableOperation::DeleteRow { row } => coerce_row(row),
+            TableOperation::InsertOrUpdate { row, update } => {
+                coerce_row(row)?;
+                coerce_update(update)
+            }
+            TableOperation::Update { update, key } => {
+                coerce_update(update)?;
+                coerce_key(key)
+            }
+            TableOperation::DeleteByKey { key } => coerce_key(key),
+            TableOperation::SetReplicationOffset(_)
+            | TableOperation::SetSnapshotMode(_)
+            | TableOperation::Truncate => Ok(()),
         }
-        Ok(())
     }
 
     /// Generates a PacketTrace object every TRACE_SAMPLE_RATE. This performs
@@ -802,12 +960,17 @@ impl Table {
     fn prep_records(&mut self, mut ops: Vec<TableOperation>) -> ReadySetResult<PacketData> {
         for r in &mut ops {
             self.inject_dropped_cols(r)?;
+            self.coerce_enums(r)?;
+            // Truncating text loses its collation, so this must come before `apply_collations`
+            self.truncate_wide_values(r);
+            self.apply_collations(r);
         }
 
         Ok(PacketData {
             dst: self.node,
             data: PacketPayload::Input(ops),
             trace: self.generate_trace_info(),
+            trace_context: RequestContext::from_current_span(),
         })
     }
 
@@ -991,3 +1154,177 @@ impl Table {
         .await
     }
 }
+
+/// Returns the collation declared for each column of a base table with the given `schema`, in the
+/// order of the columns in its base node (with the `dropped` columns given [`Collation::Binary`]),
+/// or an empty `Vec` if every column uses [`Collation::Binary`]
+fn column_collations(
+    schema: Option<&CreateTableStatement>,
+    columns: &[SqlIdentifier],
+    dropped: &VecMap<DataType>,
+) -> Vec<Collation> {
+    let schema = match schema {
+        Some(schema) => schema,
+        None => return vec![],
+    };
+    let mut collations = columns.iter().map(|name| {
+        schema
+            .fields
+            .iter()
+            .find(|field| field.column.name == *name)
+            .and_then(Collation::for_column)
+            .unwrap_or_default()
+    });
+    let collations = (0..columns.len() + dropped.len())
+        .map(|i| {
+            if dropped.contains_key(i) {
+                Collation::Binary
+            } else {
+                collations.next().unwrap_or_default()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if collations.iter().all(|c| *c == Collation::Binary) {
+        vec![]
+    } else {
+        collations
+    }
+}
+
+/// Returns the type of each `ENUM` column of a base table with the given `schema`, keyed by the
+/// column's index in its base node
+fn enum_columns(
+    schema: Option<&CreateTableStatement>,
+    columns: &[SqlIdentifier],
+    dropped: &VecMap<DataType>,
+) -> VecMap<SqlType> {
+    let schema = match schema {
+        Some(schema) => schema,
+        None => return VecMap::new(),
+    };
+    (0..columns.len() + dropped.len())
+        .filter(|i| !dropped.contains_key(*i))
+        .zip(columns)
+        .filter_map(|(i, name)| {
+            schema
+                .fields
+                .iter()
+                .find(|field| field.column.name == *name)
+                .filter(|field| matches!(field.sql_type, SqlType::Enum(_)))
+                .map(|field| (i, field.sql_type.clone()))
+        })
+        .collect()
+}
+
+/// If `value` is text or bytes longer than `max_len` bytes, returns it truncated to at most
+/// `max_len` bytes. Text is truncated at the last character boundary that fits.
+fn truncate_value(value: &DataType, max_len: usize) -> Option<DataType> {
+    match value {
+        DataType::ByteArray(bytes) if bytes.len() > max_len => {
+            Some(DataType::ByteArray(Arc::new(bytes[..max_len].to_vec())))
+        }
+        DataType::Text(_) | DataType::TinyText(_) => {
+            let s = <&str>::try_from(value).ok()?;
+            if s.len() <= max_len {
+                return None;
+            }
+            let end = (0..=max_len)
+                .rev()
+                .find(|&i| s.is_char_boundary(i))
+                .unwrap_or(0);
+            Some(DataType::from(&s[..end]))
+        }
+        _ => None,
+    }
+}
+
+/// Insert a value for each of the `dropped` columns into its position in `r`, shifting the
+/// remaining elements of `r` along. The value for each column is computed from its default with
+/// `value`, and `filler` is used as a placeholder while shifting elements.
+fn inject_dropped<T>(
+    r: &mut Vec<T>,
+    dropped: &VecMap<DataType>,
+    value: impl Fn(&DataType) -> T,
+    filler: T,
+) -> ReadySetResult<()>
+where
+    T: Clone + PartialEq + fmt::Debug,
+{
+    use std::mem;
+    let ndropped = dropped.len();
+    let dropped = dropped.iter().rev();
+
+    // we want to be a bit careful here to avoid shifting elements multiple times. we
+    // do this by moving from the back, and swapping the tail element to the end of the
+    // vector until we hit each index.
+
+    // in other words, if we have two default values, we're going to start out with:
+    //
+    // |####..|
+    //
+    // where # are "real" fields in the record and . are None values.
+    // we want to end up with something like
+    //
+    // |#d##d#|
+    //
+    // if columns 1 and 4 were dropped (d here signifies the default values).
+    // what makes this tricky is that we need to preserve the order of all the #.
+    // to accomplish this, we're going to move the # to the end of the record, one at a
+    // time, starting with the last one, and then "inject" the default values as we go.
+    // that way, we only make one pass over the record!
+    //
+    // in particular, progress is going to look like this (i've swapped # for col #):
+    //
+    // |1234..|  hole = 5, next_insert = 4, last_unmoved = 3
+    // swap 4 and last .
+    // |123..4|  hole = 4, next_insert = 4, last_unmoved = 2
+    // hole == next_insert, so insert default value
+    // |123.d4|  hole = 4, next_insert = 4, last_unmoved = 2
+    // move on to next dropped column
+    // |123.d4|  hole = 3, next_insert = 1, last_unmoved = 2
+    // swap 3 and last .
+    // |12.3d4|  hole = 2, next_insert = 1, last_unmoved = 1
+    // swap 2 and last .
+    // |1.23d4|  hole = 1, next_insert = 1, last_unmoved = 0
+    // hole == next_insert, so insert default value
+    // |1d23d4|
+    // move on to next dropped column, but since there is none, we're done
+
+    // make room in the record
+    let n = r.len() + ndropped;
+    let mut hole = n;
+    let mut last_unmoved = r.len() - 1;
+    r.resize(n, filler.clone());
+
+    // keep trying to insert the next dropped column
+    for (next_insert, default) in dropped {
+        // think of this being at the bottom of the loop
+        // we just hoist it here to avoid underflow if we ever insert at 0
+        hole -= 1;
+
+        // shift elements until the next free slot is the one we want to insert into
+        while hole != next_insert {
+            // shift another element so the free slot is at a lower index
+            r.swap(last_unmoved, hole);
+            hole -= 1;
+
+            if last_unmoved == 0 {
+                // there are no more elements -- the next slot to insert at better be [0]
+                debug_assert_eq!(next_insert, 0);
+                debug_assert_eq!(hole, 0);
+                break;
+            }
+            last_unmoved -= 1;
+        }
+
+        // we're at the right index -- insert the dropped value
+        let current = match r.get_mut(next_insert) {
+            Some(v) => v,
+            None => internal!("index out of bounds"),
+        };
+        let old = mem::replace(current, value(default));
+        debug_assert_eq!(old, filler);
+    }
+    Ok(())
+}
@@ -14,7 +14,7 @@ use async_bincode::{AsyncBincodeStream, AsyncDestination};
 use dataflow_expression::Expression as DataflowExpression;
 use futures_util::future::TryFutureExt;
 use futures_util::stream::futures_unordered::FuturesUnordered;
-use futures_util::stream::{StreamExt, TryStreamExt};
+use futures_util::stream::{self, Stream, StreamExt, TryStreamExt};
 use futures_util::{future, ready};
 use launchpad::intervals::{cmp_start_end, BoundPair};
 use launchpad::redacted::Sensitive;
@@ -25,6 +25,7 @@ use petgraph::graph::NodeIndex;
 use proptest::arbitrary::Arbitrary;
 use readyset_tracing::presampled::instrument_if_enabled;
 use readyset_tracing::propagation::Instrumented;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use tokio_tower::multiplex;
 use tower::balance::p2c::Balance;
@@ -37,6 +38,7 @@ use tracing_futures::Instrument;
 use vec1::Vec1;
 
 pub(crate) mod results;
+mod row_deserializer;
 
 use self::results::{Results, Row};
 use crate::consistency::Timestamp;
@@ -738,6 +740,23 @@ pub enum ReadQuery {
         /// Where to read from
         target: (NodeIndex, SqlIdentifier, usize),
     },
+    /// Read from a leaf view, returning only the first page of at most `page_size` rows of the
+    /// result set for each key, and keeping the rest of the rows in a cursor at the reader
+    Paged {
+        /// Where to read from
+        target: (NodeIndex, SqlIdentifier, usize),
+        /// View query to run
+        query: ViewQuery,
+        /// The maximum number of rows to return for each key
+        page_size: usize,
+    },
+    /// Read the next page of the rows kept in a cursor by a [`ReadQuery::Paged`] read
+    NextPage {
+        /// The cursor to read from
+        cursor: u64,
+        /// The maximum number of rows to return for each key
+        page_size: usize,
+    },
 }
 
 /// The result of a lookup to a view.
@@ -778,6 +797,11 @@ impl<D> LookupResult<D> {
 pub struct ReadReplyStats {
     /// The count of cache misses which have occurred
     pub cache_misses: u64,
+    /// How long the read spent waiting for cache misses to be filled by upqueries
+    pub upquery_duration: Duration,
+    /// Whether keys were evicted from the reader while the read was waiting for upqueries, which
+    /// can force the read to wait for keys to be filled again
+    pub evicted_during_upquery: bool,
 }
 
 impl ReadReplyStats {
@@ -786,6 +810,9 @@ impl ReadReplyStats {
     pub fn merge(&self, other: &Self) -> Self {
         Self {
             cache_misses: self.cache_misses + other.cache_misses,
+            // Reads to different shards wait for their upqueries concurrently
+            upquery_duration: self.upquery_duration.max(other.upquery_duration),
+            evicted_during_upquery: self.evicted_during_upquery || other.evicted_during_upquery,
         }
     }
 }
@@ -799,6 +826,9 @@ pub enum ReadReply<D = ReadReplyBatch> {
     Size(usize),
     // Read keys of view
     Keys(Vec<Vec<DataType>>),
+    /// A page of the results of a [`ReadQuery::Paged`] or [`ReadQuery::NextPage`] request, along
+    /// with the cursor to read the next page from, if there are any rows left
+    Page(ReadySetResult<(LookupResult<D>, Option<u64>)>),
 }
 
 impl<D> ReadReply<D> {
@@ -1083,6 +1113,9 @@ impl Service<ViewQuery> for View {
         }
 
         span.in_scope(|| tracing::trace!("shard request"));
+        // A query without keys only checks the reader's timestamp, which every shard has to
+        // satisfy
+        let all_shards = query.key_comparisons.is_empty() && query.timestamp.is_some();
         let mut shard_queries = vec![Vec::new(); self.shards.len()];
         for comparison in query.key_comparisons.drain(..) {
             for shard in comparison.shard_keys(self.shards.len()) {
@@ -1101,7 +1134,7 @@ impl Service<ViewQuery> for View {
                 .enumerate()
                 .zip(shard_queries.into_iter())
                 .filter_map(|((shardi, shard), shard_queries)| {
-                    if shard_queries.is_empty() {
+                    if shard_queries.is_empty() && !all_shards {
                         // poll_ready reserves a sender slot which we have to release
                         // we do that by dropping the old handle and replacing it with a clone
                         // https://github.com/tokio-rs/tokio/issues/898
@@ -1312,6 +1345,102 @@ impl View {
         self.raw_lookup((key_comparisons, block, None).into()).await
     }
 
+    /// Issue a raw `ViewQuery` against this view, and return a stream of the results read a page
+    /// of at most `page_size` rows (of the result set for each key) at a time.
+    ///
+    /// Unlike [`raw_lookup`](View::raw_lookup), only one page of the results is sent from the
+    /// reader at a time, so this can be used to read result sets too large to buffer all at once.
+    /// Each shard reads the results for its keys in full when the first page is requested, and
+    /// keeps the rest of the rows in a cursor which the following pages are read from, so all the
+    /// pages for a shard come from a single snapshot of the view. A non-blocking miss is returned
+    /// as [`ReadySetError::ReaderMissingKey`].
+    pub fn lookup_stream(
+        &self,
+        mut query: ViewQuery,
+        page_size: usize,
+    ) -> impl Stream<Item = ReadySetResult<Vec<Results>>> + Send + 'static {
+        let page_size = page_size.max(1);
+        let num_shards = self.shards.len();
+        let mut shard_queries = vec![Vec::new(); num_shards];
+        for comparison in query.key_comparisons.drain(..) {
+            for shard in comparison.shard_keys(num_shards) {
+                #[allow(clippy::indexing_slicing)]
+                // We built `shard_queries` to be the correct length, so it's safe to access
+                // it by index in this case.
+                shard_queries[shard].push(comparison.clone());
+            }
+        }
+        // A query without keys only checks the reader's timestamp, which every shard has to
+        // satisfy
+        let all_shards =
+            shard_queries.iter().all(|keys| keys.is_empty()) && query.timestamp.is_some();
+        let shard_queries = shard_queries
+            .into_iter()
+            .enumerate()
+            .filter(|(_, keys)| all_shards || !keys.is_empty())
+            .map(|(shardi, keys)| {
+                (
+                    shardi,
+                    ViewQuery {
+                        key_comparisons: keys,
+                        ..query.clone()
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        stream::try_unfold(
+            (self.clone(), shard_queries.into_iter(), None),
+            move |(mut view, mut shard_queries, cursor)| async move {
+                let (shardi, request) = match cursor {
+                    Some((shardi, cursor)) => (shardi, ReadQuery::NextPage { cursor, page_size }),
+                    None => match shard_queries.next() {
+                        Some((shardi, query)) => (
+                            shardi,
+                            ReadQuery::Paged {
+                                target: (view.node, view.name.clone(), shardi),
+                                query,
+                                page_size,
+                            },
+                        ),
+                        None => return Ok(None),
+                    },
+                };
+
+                let ni = view.node;
+                let shard = view
+                    .shards
+                    .get_mut(shardi)
+                    .ok_or_else(|| internal_err("Shard index out of bounds"))?;
+                future::poll_fn(|cx| shard.poll_ready(cx))
+                    .await
+                    .map_err(rpc_err!("View::lookup_stream"))
+                    .map_err(|e| view_err(ni, e))?;
+                let reply = shard
+                    .call(Instrumented::from(Tagged::from(request)))
+                    .await
+                    .map_err(rpc_err!("View::lookup_stream"))
+                    .map_err(|e| view_err(ni, e))?;
+                let (results, cursor) = match reply.v {
+                    ReadReply::Page(page) => page?,
+                    _ => return Err(internal_err("Unexpected response type from reader service")),
+                };
+
+                let columns = Arc::clone(&view.columns);
+                let results = results
+                    .map_results(|rows, stats| {
+                        Results::with_stats(rows.into(), Arc::clone(&columns), stats.clone())
+                    })
+                    .into_results()
+                    .ok_or(ReadySetError::ReaderMissingKey)?;
+                Ok(Some((
+                    results,
+                    (view, shard_queries, cursor.map(|cursor| (shardi, cursor))),
+                )))
+            },
+        )
+    }
+
     /// Retrieve the first query result for the given parameter value.
     ///
     /// The method will block if the results are not yet available only when `block` is `true`.
@@ -1324,6 +1453,19 @@ impl View {
         self.lookup_first_ryw(key, block, None).await
     }
 
+    /// Retrieve the query results for the given parameter value, deserializing each row into a
+    /// `T`.
+    ///
+    /// See [`ResultRow::deserialize`](crate::results::ResultRow::deserialize) for how rows are
+    /// mapped onto `T`. The method will block if the results are not yet available only when
+    /// `block` is `true`.
+    pub async fn lookup_as<T>(&mut self, key: &[DataType], block: bool) -> ReadySetResult<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.lookup(key, block).await?.deserialize()
+    }
+
     /// Retrieve the query results for the given parameter value.
     ///
     /// The method will block if the results are not yet available or do not have a timestamp
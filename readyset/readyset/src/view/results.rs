@@ -1,10 +1,14 @@
 use std::fmt;
-use std::ops::Deref;
+use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 use nom_sql::SqlIdentifier;
 use readyset_data::DataType;
+use readyset_errors::ReadySetResult;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 
+use super::row_deserializer::deserialize_row;
 use crate::ReadReplyStats;
 
 /// A result set from a ReadySet query.
@@ -41,10 +45,25 @@ impl Results {
         }
     }
 
+    /// The names of the columns in each returned row.
+    pub fn columns(&self) -> &Arc<[SqlIdentifier]> {
+        &self.columns
+    }
+
     /// Iterate over references to the returned rows.
     pub fn iter(&self) -> ResultIter<'_> {
         self.into_iter()
     }
+
+    /// Deserialize each of the returned rows into a `T`.
+    ///
+    /// See [`ResultRow::deserialize`] for how rows are mapped onto `T`.
+    pub fn deserialize<T>(&self) -> ReadySetResult<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.iter().map(|row| row.deserialize()).collect()
+    }
 }
 
 impl From<Results> for Vec<Vec<DataType>> {
@@ -105,6 +124,18 @@ impl<'a> ResultRow<'a> {
         let index = self.columns.iter().position(|col| *col == field)?;
         self.result.get(index)
     }
+
+    /// Deserialize the row into any type which implements [`Deserialize`].
+    ///
+    /// Structs and maps are deserialized from the fields of the row by name, and tuples and
+    /// sequences from the values of the row in order. Text and byte values can be borrowed from the
+    /// row, as `&str` and `&[u8]`.
+    pub fn deserialize<T>(&self) -> ReadySetResult<T>
+    where
+        T: Deserialize<'a>,
+    {
+        deserialize_row(self.result, self.columns)
+    }
 }
 
 impl PartialEq<[DataType]> for ResultRow<'_> {
@@ -151,6 +182,12 @@ impl Deref for Results {
     }
 }
 
+impl DerefMut for Results {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.results
+    }
+}
+
 impl AsRef<[Vec<DataType>]> for Results {
     fn as_ref(&self) -> &[Vec<DataType>] {
         &self.results
@@ -330,4 +367,14 @@ impl Row {
             .get_mut(index)
             .map(|r| std::mem::replace(r, DataType::None))
     }
+
+    /// Deserialize the row into any type which implements [`Deserialize`].
+    ///
+    /// See [`ResultRow::deserialize`] for how the row is mapped onto `T`.
+    pub fn deserialize<'a, T>(&'a self) -> ReadySetResult<T>
+    where
+        T: Deserialize<'a>,
+    {
+        deserialize_row(&self.row, &self.columns)
+    }
 }
@@ -0,0 +1,335 @@
+//! A [`serde::Deserializer`] for the rows of the results of ReadySet queries, so that rows can be
+//! decoded into any type which implements [`Deserialize`].
+//!
+//! A row can be deserialized into a struct or a map, whose fields are matched to the columns of
+//! the row by name, or into a tuple or a sequence, whose elements are the values of the columns of
+//! the row in order.
+
+use std::any::type_name;
+use std::convert::TryFrom;
+use std::slice;
+
+use nom_sql::SqlIdentifier;
+use readyset_data::DataType;
+use readyset_errors::{ReadySetError, ReadySetResult};
+use serde::de::value::{Error, StrDeserializer};
+use serde::de::{
+    self, DeserializeSeed, Error as _, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize};
+
+/// Deserializes `row`, whose columns are named by `columns`, into a `T`
+pub(crate) fn deserialize_row<'a, T>(
+    row: &'a [DataType],
+    columns: &'a [SqlIdentifier],
+) -> ReadySetResult<T>
+where
+    T: Deserialize<'a>,
+{
+    T::deserialize(RowDeserializer { row, columns }).map_err(|e| {
+        ReadySetError::DataTypeConversionError {
+            src_type: "Row".to_owned(),
+            target_type: type_name::<T>().to_owned(),
+            details: e.to_string(),
+        }
+    })
+}
+
+/// Deserializes a row, as a map from the name of each column to its value, or as a sequence of its
+/// values
+struct RowDeserializer<'a> {
+    row: &'a [DataType],
+    columns: &'a [SqlIdentifier],
+}
+
+impl<'de> de::Deserializer<'de> for RowDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(ColumnsAccess {
+            columns: self.columns.iter().zip(self.row.iter()),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(ValuesAccess(self.row.iter()))
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct enum identifier ignored_any
+    }
+}
+
+/// Visits the columns of a row as a map from the name of each column to its value
+struct ColumnsAccess<'a> {
+    columns: std::iter::Zip<slice::Iter<'a, SqlIdentifier>, slice::Iter<'a, DataType>>,
+    /// The value of the column whose name was visited last
+    value: Option<&'a DataType>,
+}
+
+impl<'de> MapAccess<'de> for ColumnsAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.columns.next() {
+            Some((column, value)) => {
+                self.value = Some(value);
+                let column: StrDeserializer<'de, Error> = column.as_str().into_deserializer();
+                seed.deserialize(column).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::custom("value visited before its column"))?;
+        seed.deserialize(ValueDeserializer(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.columns.len())
+    }
+}
+
+/// Visits the values of a row in order
+struct ValuesAccess<'a>(slice::Iter<'a, DataType>);
+
+impl<'de> SeqAccess<'de> for ValuesAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.0
+            .next()
+            .map(|value| seed.deserialize(ValueDeserializer(value)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// Deserializes a single value in a row
+struct ValueDeserializer<'a>(&'a DataType);
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            DataType::None => visitor.visit_none(),
+            DataType::Int(i) => visitor.visit_i64(*i),
+            DataType::UnsignedInt(i) => visitor.visit_u64(*i),
+            DataType::Float(f) => visitor.visit_f32(*f),
+            DataType::Double(f) => visitor.visit_f64(*f),
+            DataType::Text(_) | DataType::TinyText(_) => {
+                visitor.visit_borrowed_str(<&str>::try_from(self.0).map_err(Error::custom)?)
+            }
+            DataType::ByteArray(bytes) => visitor.visit_borrowed_bytes(bytes.as_slice()),
+            DataType::TimestampTz(_)
+            | DataType::Time(_)
+            | DataType::Numeric(_)
+            | DataType::BitVector(_) => visitor.visit_string(self.0.to_string()),
+            DataType::Max => Err(Error::custom("the maximal value can't be deserialized")),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            DataType::None => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Booleans are stored as integers
+        match self.0 {
+            DataType::Int(i) => visitor.visit_bool(*i != 0),
+            DataType::UnsignedInt(i) => visitor.visit_bool(*i != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn columns(names: &[&str]) -> Vec<SqlIdentifier> {
+        names.iter().map(|&name| name.into()).collect()
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Article {
+        id: i32,
+        title: String,
+        votes: Option<u64>,
+        published: bool,
+    }
+
+    #[test]
+    fn struct_by_column_name() {
+        let columns = columns(&["title", "id", "published", "votes"]);
+        let row = vec![
+            DataType::from("hello"),
+            DataType::from(1),
+            DataType::from(1),
+            DataType::None,
+        ];
+        assert_eq!(
+            deserialize_row::<Article>(&row, &columns).unwrap(),
+            Article {
+                id: 1,
+                title: "hello".to_owned(),
+                votes: None,
+                published: true
+            }
+        );
+    }
+
+    #[test]
+    fn borrowed_str() {
+        #[derive(Deserialize)]
+        struct Title<'a> {
+            title: &'a str,
+        }
+
+        let columns = columns(&["title"]);
+        let row = vec![DataType::from("a title that is too long to be tiny")];
+        assert_eq!(
+            deserialize_row::<Title<'_>>(&row, &columns).unwrap().title,
+            "a title that is too long to be tiny"
+        );
+    }
+
+    #[test]
+    fn tuple_in_order() {
+        let columns = columns(&["id", "score"]);
+        let row = vec![DataType::from(1), DataType::try_from(2.5f64).unwrap()];
+        assert_eq!(
+            deserialize_row::<(i64, f64)>(&row, &columns).unwrap(),
+            (1, 2.5)
+        );
+    }
+
+    #[test]
+    fn map() {
+        let columns = columns(&["a", "b"]);
+        let row = vec![DataType::from(1), DataType::from(2)];
+        assert_eq!(
+            deserialize_row::<HashMap<String, i64>>(&row, &columns).unwrap(),
+            HashMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)])
+        );
+    }
+
+    #[test]
+    fn missing_column() {
+        let columns = columns(&["id", "title"]);
+        let row = vec![DataType::from(1), DataType::from("hello")];
+        let err = deserialize_row::<Article>(&row, &columns).unwrap_err();
+        assert!(err.caused_by_data_type_conversion());
+    }
+
+    #[test]
+    fn out_of_range() {
+        let columns = columns(&["id"]);
+        let row = vec![DataType::from(i64::MAX)];
+        deserialize_row::<(i32,)>(&row, &columns).unwrap_err();
+    }
+}
@@ -1,4 +1,4 @@
-mod like;
+pub mod like;
 pub mod utils;
 
 use std::borrow::Borrow;
@@ -7,13 +7,16 @@ use std::fmt;
 use std::fmt::Formatter;
 use std::ops::{Add, Sub};
 
-use chrono::{Datelike, LocalResult, NaiveDate, NaiveDateTime, TimeZone};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset,
+    TimeZone,
+};
 use chrono_tz::Tz;
 use maths::int::integer_rnd;
 use mysql_time::MysqlTime;
 use nom_sql::{BinaryOperator, SqlType};
 use readyset_data::noria_type::Type;
-use readyset_data::DataType;
+use readyset_data::{nulls, DataType};
 use readyset_errors::{ReadySetError, ReadySetResult};
 use rust_decimal::prelude::ToPrimitive;
 use serde::{Deserialize, Serialize};
@@ -36,6 +39,8 @@ pub enum BuiltinFunction {
     Addtime(Box<Expression>, Box<Expression>),
     /// round(expr, prec)
     Round(Box<Expression>, Box<Expression>),
+    /// timezone(zone, expr), which is what `expr AT TIME ZONE zone` is parsed as
+    Timezone(Box<Expression>, Box<Expression>),
 }
 
 impl BuiltinFunction {
@@ -175,6 +180,19 @@ impl BuiltinFunction {
                 };
                 Ok((Self::Round(expr, prec), ty))
             }
+            "timezone" => {
+                let arity_error = || ReadySetError::ArityError("timezone".to_owned());
+                let zone = Box::new(args.next().ok_or_else(arity_error)?);
+                let expr = Box::new(args.next().ok_or_else(arity_error)?);
+                // Converting a timestamp with a time zone gives a timestamp without one, and vice
+                // versa
+                let ty = if *expr.ty() == Type::Sql(SqlType::TimestampTz) {
+                    Type::Sql(SqlType::Timestamp)
+                } else {
+                    Type::Sql(SqlType::TimestampTz)
+                };
+                Ok((Self::Timezone(zone, expr), ty))
+            }
             _ => Err(ReadySetError::NoSuchFunction(name.to_owned())),
         }
     }
@@ -206,6 +224,9 @@ impl fmt::Display for BuiltinFunction {
             Round(arg1, precision) => {
                 write!(f, "round({}, {})", arg1, precision)
             }
+            Timezone(zone, arg) => {
+                write!(f, "timezone({}, {})", zone, arg)
+            }
         }
     }
 }
@@ -347,6 +368,9 @@ impl Expression {
 
                 let left = left.eval(record)?;
                 let right = right.eval(record)?;
+                // Strings with different collations, like a column and a literal, are compared
+                // using the collation resolved from both of them
+                let (left, right) = left.resolve_collations(right);
 
                 macro_rules! like {
                     ($case_sensitivity: expr, $negated: expr) => {{
@@ -380,8 +404,8 @@ impl Expression {
                     Subtract => Ok((non_null!(left) - non_null!(right))?),
                     Multiply => Ok((non_null!(left) * non_null!(right))?),
                     Divide => Ok((non_null!(left) / non_null!(right))?),
-                    And => Ok((non_null!(left).is_truthy() && non_null!(right).is_truthy()).into()),
-                    Or => Ok((non_null!(left).is_truthy() || non_null!(right).is_truthy()).into()),
+                    And => Ok(nulls::and(&left, &right)),
+                    Or => Ok(nulls::or(&left, &right)),
                     Equal => Ok((non_null!(left) == non_null!(right)).into()),
                     NotEqual => Ok((non_null!(left) != non_null!(right)).into()),
                     Greater => Ok((non_null!(left) > non_null!(right)).into()),
@@ -553,6 +577,26 @@ impl Expression {
                         }),
                     }
                 }
+                BuiltinFunction::Timezone(arg1, arg2) => {
+                    let zone = arg1.eval(record)?;
+                    let param = arg2.eval(record)?;
+                    let zone_cast = try_cast_or_none!(non_null!(zone), &SqlType::Text);
+                    let tz = parse_time_zone(<&str>::try_from(&zone_cast)?)?;
+                    match non_null!(param) {
+                        DataType::TimestampTz(ts) if ts.has_timezone() => {
+                            Ok(DataType::TimestampTz(
+                                ts.to_chrono().with_timezone(&tz).naive_local().into(),
+                            ))
+                        }
+                        param => {
+                            let param_cast = try_cast_or_none!(param, &SqlType::Timestamp);
+                            Ok(DataType::TimestampTz(
+                                from_local_datetime(&tz, &NaiveDateTime::try_from(&param_cast)?)
+                                    .into(),
+                            ))
+                        }
+                    }
+                }
             },
             CaseWhen {
                 condition,
@@ -618,6 +662,35 @@ pub fn convert_tz(
     Ok(datetime_tz.with_timezone(&target_tz).naive_local())
 }
 
+/// Parses the name of a time zone, as accepted by `AT TIME ZONE` and the `TimeZone` session
+/// variable in PostgreSQL
+pub fn parse_time_zone(name: &str) -> ReadySetResult<Tz> {
+    name.parse()
+        .map_err(|_| ReadySetError::ProjectExpressionBuiltInFunctionError {
+            function: "timezone".to_owned(),
+            message: format!("time zone \"{}\" not recognized", name),
+        })
+}
+
+/// Interprets `datetime` as a local time in the time zone `tz`.
+///
+/// Like PostgreSQL, local times that are skipped over by a transition (such as the start of
+/// daylight saving time) are interpreted with the offset from before the transition, and local
+/// times that occur twice are interpreted as the later of the two instants.
+pub fn from_local_datetime(tz: &Tz, datetime: &NaiveDateTime) -> DateTime<FixedOffset> {
+    let dt = match tz.from_local_datetime(datetime) {
+        LocalResult::Single(dt) => dt,
+        LocalResult::Ambiguous(earlier, later) => earlier.max(later),
+        LocalResult::None => {
+            let offset = tz
+                .offset_from_utc_datetime(&(*datetime - Duration::days(1)))
+                .fix();
+            return DateTime::from_utc(*datetime - offset, offset);
+        }
+    };
+    dt.with_timezone(&dt.offset().fix())
+}
+
 fn day_of_week(date: &NaiveDate) -> u8 {
     date.weekday().number_from_sunday() as u8
 }
@@ -648,6 +721,7 @@ mod tests {
     use std::convert::TryInto;
 
     use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+    use readyset_data::Collation;
     use test_strategy::proptest;
     use Expression::*;
 
@@ -728,6 +802,73 @@ mod tests {
         assert_op!(BinaryOperator::Equal, text_dt, 1u8);
     }
 
+    #[test]
+    fn eval_comparisons_with_collations() {
+        let expr = |op| Op {
+            left: Box::new(make_column(0)),
+            right: Box::new(make_literal("ABC".into())),
+            op,
+            ty: Type::Unknown,
+        };
+        let record = [DataType::from("abc").with_collation(Collation::GeneralCi)];
+        assert_eq!(
+            expr(BinaryOperator::Equal)
+                .eval::<DataType>(&record)
+                .unwrap(),
+            1u8.into()
+        );
+        assert_eq!(
+            expr(BinaryOperator::Less)
+                .eval::<DataType>(&record)
+                .unwrap(),
+            0u8.into()
+        );
+
+        let record = [DataType::from("abc")];
+        assert_eq!(
+            expr(BinaryOperator::Equal)
+                .eval::<DataType>(&record)
+                .unwrap(),
+            0u8.into()
+        );
+    }
+
+    #[test]
+    fn eval_in_lists_with_nulls() {
+        // `x IN (1, NULL)` and `x NOT IN (1, NULL)`, the way they're lowered from SQL
+        let in_list = |comparison_op, logical_op| Op {
+            left: Box::new(Op {
+                left: Box::new(make_column(0)),
+                op: comparison_op,
+                right: Box::new(make_literal(1.into())),
+                ty: Type::Sql(SqlType::Bool),
+            }),
+            op: logical_op,
+            right: Box::new(Op {
+                left: Box::new(make_column(0)),
+                op: comparison_op,
+                right: Box::new(make_literal(DataType::None)),
+                ty: Type::Sql(SqlType::Bool),
+            }),
+            ty: Type::Sql(SqlType::Bool),
+        };
+        let is_in = in_list(BinaryOperator::Equal, BinaryOperator::Or);
+        let not_in = in_list(BinaryOperator::NotEqual, BinaryOperator::And);
+
+        // Expected results are from MySQL 8.0 and PostgreSQL 15, which agree
+        assert_eq!(is_in.eval::<DataType>(&[1.into()]).unwrap(), true.into());
+        assert_eq!(is_in.eval::<DataType>(&[2.into()]).unwrap(), DataType::None);
+        assert_eq!(not_in.eval::<DataType>(&[1.into()]).unwrap(), false.into());
+        assert_eq!(
+            not_in.eval::<DataType>(&[2.into()]).unwrap(),
+            DataType::None
+        );
+        assert_eq!(
+            not_in.eval::<DataType>(&[DataType::None]).unwrap(),
+            DataType::None
+        );
+    }
+
     #[test]
     fn eval_cast() {
         let expr = Cast {
@@ -818,6 +959,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn eval_call_timezone() {
+        let expr = make_call(BuiltinFunction::Timezone(
+            Box::new(make_column(0)),
+            Box::new(make_column(1)),
+        ));
+        let eval =
+            |zone: &str, ts: DataType| expr.eval::<DataType>(&[zone.try_into().unwrap(), ts]);
+        let utc = |s: &str| DateTime::<FixedOffset>::parse_from_rfc3339(s).unwrap();
+        let naive = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+
+        // Expected values are from PostgreSQL 15
+
+        // A timestamp with a time zone gives the local time in the given zone
+        let res = eval(
+            "America/New_York",
+            DataType::TimestampTz(utc("2022-06-01T12:00:00+00:00").into()),
+        )
+        .unwrap();
+        assert_eq!(res.to_string(), "2022-06-01 08:00:00");
+
+        // A timestamp without a time zone is interpreted as a local time in the given zone
+        let res = eval("America/New_York", naive("2022-06-01 08:00:00").into()).unwrap();
+        match res {
+            DataType::TimestampTz(ts) => {
+                assert!(ts.has_timezone());
+                assert_eq!(ts.to_chrono(), utc("2022-06-01T12:00:00+00:00"));
+            }
+            _ => panic!("expected a timestamp, got {:?}", res),
+        }
+
+        // Ambiguous local times are interpreted as the later instant
+        let res = eval("America/New_York", naive("2022-11-06 01:30:00").into()).unwrap();
+        assert_eq!(
+            DataType::TimestampTz(utc("2022-11-06T06:30:00+00:00").into()),
+            res
+        );
+
+        // Skipped local times are interpreted with the offset from before the transition
+        let res = eval("America/New_York", naive("2022-03-13 02:30:00").into()).unwrap();
+        assert_eq!(
+            DataType::TimestampTz(utc("2022-03-13T07:30:00+00:00").into()),
+            res
+        );
+
+        assert_eq!(
+            eval("America/New_York", DataType::None).unwrap(),
+            DataType::None
+        );
+        assert!(eval("Not/A_Zone", naive("2022-06-01 08:00:00").into()).is_err());
+    }
+
     #[test]
     fn eval_call_day_of_week() {
         let expr = make_call(BuiltinFunction::DayOfWeek(Box::new(make_column(0))));
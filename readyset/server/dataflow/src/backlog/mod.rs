@@ -1,11 +1,13 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::ops::RangeBounds;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::Arc;
 
 use ahash::RandomState;
 use common::SizeOf;
 use readyset::consistency::Timestamp;
+use readyset::debug::stats::MemoryUsage;
 use readyset::KeyComparison;
 use reader_map::refs::{Values, ValuesIter};
 use reader_map::EvictionStrategy;
@@ -111,6 +113,7 @@ fn new_inner(
         _ => make!(Many),
     };
 
+    let evictions = Arc::new(AtomicU64::new(0));
     let w = WriteHandle {
         partial: trigger.is_some(),
         handle: w,
@@ -118,6 +121,8 @@ fn new_inner(
         cols,
         contiguous,
         mem_size: 0,
+        key_size: 0,
+        evictions: evictions.clone(),
     };
 
     let r = SingleReadHandle {
@@ -125,6 +130,7 @@ fn new_inner(
         trigger,
         index,
         post_lookup: Default::default(),
+        evictions,
     };
 
     (r, w)
@@ -147,6 +153,11 @@ pub(crate) struct WriteHandle {
     index: Index,
     contiguous: bool,
     mem_size: usize,
+    /// The portion of `mem_size` used by the keys which have been filled
+    key_size: usize,
+    /// Number of times keys have been evicted from this reader, shared with its
+    /// [`SingleReadHandle`]s
+    evictions: Arc<AtomicU64>,
 }
 
 type Key<'a> = Cow<'a, [DataType]>;
@@ -190,7 +201,9 @@ impl<'a> MutWriteHandleEntry<'a> {
             // TODO(ENG-726): Trying to introspect how much memory these data structures
             // are using for storing key value pairs can provide a poor estimate. Handling
             // memory tracking closer to where the data is stored will be beneficial.
-            self.handle.mem_size += self.key_value_size(&self.key);
+            let key_size = self.key_value_size(&self.key);
+            self.handle.mem_size += key_size;
+            self.handle.key_size += key_size;
             self.handle.handle.clear(self.key);
             Ok(())
         } else {
@@ -206,10 +219,12 @@ impl<'a> MutWriteHandleEntry<'a> {
             .meta_get_and(&self.key, |rs| Ok(rs.map(SizeOf::deep_size_of).sum()))
             .map(|(size, _)| size)
             .unwrap_or(0);
+        let key_size = self.key_value_size(&self.key);
         self.handle.mem_size = self
             .handle
             .mem_size
-            .saturating_sub(size as usize + self.key_value_size(&self.key));
+            .saturating_sub(size as usize + key_size);
+        self.handle.key_size = self.handle.key_size.saturating_sub(key_size);
         self.handle.handle.empty(self.key)
     }
 }
@@ -320,6 +335,15 @@ impl WriteHandle {
         self.partial
     }
 
+    /// Returns a breakdown of the memory used by this reader's state
+    pub(crate) fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            key_bytes: self.key_size as u64,
+            row_bytes: self.mem_size.saturating_sub(self.key_size) as u64,
+            overhead_bytes: self.size_of(),
+        }
+    }
+
     /// Attempt to evict `bytes` from state. This approximates the number of keys to evict,
     /// these keys may not have exactly `bytes` worth of state.
     pub(crate) fn evict_bytes(&mut self, bytes: usize) -> u64 {
@@ -331,13 +355,26 @@ impl WriteHandle {
                 self.mem_size
             );
 
-            bytes_to_be_freed += self.handle.evict(bytes as f64 / self.mem_size as f64);
+            let (freed, key_freed) = self.handle.evict(bytes as f64 / self.mem_size as f64);
+            bytes_to_be_freed += freed;
+            self.key_size = self.key_size.saturating_sub(key_freed as usize);
+        }
+        if bytes_to_be_freed > 0 {
+            self.evictions.fetch_add(1, AtomicOrdering::Relaxed);
         }
 
         self.mem_size = self.mem_size.saturating_sub(bytes_to_be_freed as usize);
         bytes_to_be_freed
     }
 
+    /// Evict the given keys from state
+    pub(crate) fn evict_keys(&mut self, keys: &[KeyComparison]) {
+        for key in keys {
+            self.mark_hole(key);
+        }
+        self.evictions.fetch_add(1, AtomicOrdering::Relaxed);
+    }
+
     pub(crate) fn mark_hole(&mut self, key: &KeyComparison) {
         match key {
             KeyComparison::Equal(k) => self.mut_with_key(k.as_vec()).mark_hole(),
@@ -393,6 +430,7 @@ pub struct SingleReadHandle {
     trigger: Option<Arc<dyn Trigger>>,
     index: Index,
     pub post_lookup: PostLookup,
+    evictions: Arc<AtomicU64>,
 }
 
 impl std::fmt::Debug for SingleReadHandle {
@@ -406,6 +444,11 @@ impl std::fmt::Debug for SingleReadHandle {
 }
 
 impl SingleReadHandle {
+    /// Returns the number of times keys have been evicted from this reader
+    pub fn evictions(&self) -> u64 {
+        self.evictions.load(AtomicOrdering::Relaxed)
+    }
+
     /// Trigger a replay of a missing key from a partially materialized view.
     pub fn trigger<'a, I>(&self, keys: I) -> bool
     where
@@ -472,6 +515,11 @@ impl SingleReadHandle {
         }
     }
 
+    /// Returns true if this handle is for a partially materialized view
+    pub fn is_partial(&self) -> bool {
+        self.trigger.is_some()
+    }
+
     pub fn len(&self) -> usize {
         self.handle.len()
     }
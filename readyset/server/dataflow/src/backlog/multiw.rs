@@ -82,9 +82,11 @@ impl Handle {
     }
 
     /// Evict keys that were selected by the assigned eviction strategy from the state, and return
-    /// the number of bytes freed. The amount of keys evicted will be ceil(len() * ratio)
-    pub fn evict(&mut self, ratio: f64) -> u64 {
+    /// the number of bytes freed, along with how many of those bytes were used by the evicted keys.
+    /// The amount of keys evicted will be ceil(len() * ratio)
+    pub fn evict(&mut self, ratio: f64) -> (u64, u64) {
         let mut mem_freed = 0u64;
+        let mut key_mem_freed = 0u64;
 
         // Each row's state is composed of: The key, the bytes required to hold the Row
         // data structure, and the set of Values in the row (DataTypes).
@@ -92,22 +94,25 @@ impl Handle {
             Handle::Single(ref mut h) => {
                 let base_value_size = h.base_value_size() as u64;
                 h.evict_keys(ratio).for_each(|r| {
-                    mem_freed += r.1.iter().map(|r| r.deep_size_of() as u64).sum::<u64>()
-                        + r.0.deep_size_of()
-                        + base_value_size;
+                    let key_size = r.0.deep_size_of() + base_value_size;
+                    mem_freed +=
+                        r.1.iter().map(|r| r.deep_size_of() as u64).sum::<u64>() + key_size;
+                    key_mem_freed += key_size;
                 })
             }
             Handle::Many(ref mut h) => {
                 let base_value_size = h.base_value_size() as u64;
                 h.evict_keys(ratio).for_each(|r| {
-                    mem_freed += r.1.iter().map(|r| r.deep_size_of() as u64).sum::<u64>()
-                        + r.0.iter().map(|r| r.deep_size_of() as u64).sum::<u64>()
-                        + base_value_size
+                    let key_size =
+                        r.0.iter().map(|r| r.deep_size_of() as u64).sum::<u64>() + base_value_size;
+                    mem_freed +=
+                        r.1.iter().map(|r| r.deep_size_of() as u64).sum::<u64>() + key_size;
+                    key_mem_freed += key_size;
                 })
             }
         }
 
-        mem_freed
+        (mem_freed, key_mem_freed)
     }
 
     pub fn refresh(&mut self) {
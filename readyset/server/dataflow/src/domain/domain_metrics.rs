@@ -26,6 +26,7 @@ pub(super) struct DomainMetrics {
     index: SharedString,
     shard: SharedString,
     eviction_requests: Counter,
+    evictions: Counter,
     eviction_time: Histogram,
     eviction_size: Histogram,
 
@@ -33,6 +34,7 @@ pub(super) struct DomainMetrics {
     reader_state_size: Gauge,
     base_table_size: Gauge,
     total_node_state_size: Gauge,
+    queue_depth: Gauge,
 
     packets_sent: [Counter; PacketDiscriminants::COUNT],
 
@@ -88,11 +90,13 @@ impl DomainMetrics {
                 recorded::DOMAIN_TOTAL_NODE_STATE_SIZE_BYTES,
                 labels.clone()
             ),
+            queue_depth: register_gauge!(recorded::DOMAIN_QUEUE_DEPTH, labels.clone()),
 
             eviction_requests: register_counter!(
                 recorded::DOMAIN_EVICTION_REQUESTS,
                 labels.clone()
             ),
+            evictions: register_counter!(recorded::DOMAIN_EVICTIONS, labels.clone()),
             eviction_time: register_histogram!(recorded::DOMAIN_EVICTION_TIME, labels.clone()),
             eviction_size: register_histogram!(recorded::DOMAIN_EVICTION_FREED_MEMORY, labels),
             chuncked_replay_start_time: Default::default(),
@@ -116,6 +120,7 @@ impl DomainMetrics {
     }
 
     pub(super) fn rec_eviction_time(&self, time: Duration, total_freed: u64) {
+        self.evictions.increment(1);
         self.eviction_time.record(time.as_micros() as f64);
         self.eviction_size.record(total_freed as f64);
     }
@@ -355,6 +360,10 @@ impl DomainMetrics {
         self.total_node_state_size.set(node as f64);
     }
 
+    pub(super) fn set_queue_depth(&self, depth: usize) {
+        self.queue_depth.set(depth as f64);
+    }
+
     pub(super) fn set_node_state_size(&mut self, node: LocalNodeIndex, size: u64) {
         if let Some(gauge) = self.node_state_size.get(node) {
             gauge.set(size as f64);
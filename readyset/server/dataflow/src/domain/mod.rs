@@ -8,25 +8,27 @@ use std::fmt::Debug;
 use std::ops::Bound;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::{cell, cmp, mem, time};
+use std::{cell, cmp, fs, io, mem, time};
 
 use ahash::RandomState;
-use dataflow_state::{MaterializedNodeState, RangeLookupResult};
+use dataflow_state::{MaterializedNodeState, RangeLookupResult, SpillParameters};
 use failpoint_macros::failpoint;
 use futures_util::future::FutureExt;
 use futures_util::stream::StreamExt;
 pub use internal::DomainIndex;
 use launchpad::redacted::Sensitive;
 use launchpad::Indices;
+use nom_sql::SqlIdentifier;
 use readyset::internal::Index;
 use readyset::replication::ReplicationOffset;
 use readyset::{channel, internal, KeyComparison, ReadySetError};
 use readyset_errors::{internal, internal_err, ReadySetResult};
 use petgraph::graph::NodeIndex;
+use readyset_tracing::propagation::RequestContext;
 use serde::{Deserialize, Serialize};
 use timekeeper::{RealTime, SimpleTracker, ThreadTime, Timer, TimerSet};
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use tracing::{debug, error, info, trace, warn};
+use tracing::{debug, error, info, info_span, trace, warn, Span};
 use unbounded_interval_tree::IntervalTree;
 use vec1::Vec1;
 
@@ -36,7 +38,7 @@ use crate::node::{NodeProcessingResult, ProcessEnv};
 use crate::payload::{PrettyReplayPath, ReplayPieceContext, SourceSelection};
 use crate::prelude::*;
 use crate::processing::ColumnMiss;
-use crate::{DomainRequest, Readers};
+use crate::{DomainRequest, PacketDiscriminants, Readers};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Config {
@@ -56,6 +58,24 @@ pub struct Config {
 
     #[serde(default)]
     pub eviction_kind: crate::EvictionKind,
+
+    /// If set, keys evicted from partially materialized state are written to a local on-disk
+    /// cache rather than being discarded, so that refilling them later doesn't require an
+    /// upquery.
+    #[serde(default)]
+    pub partial_spill: Option<SpillParameters>,
+
+    /// If set, text and byte values longer than this many bytes are truncated to this length,
+    /// with a warning, before they're written to base tables, so that a single very wide value
+    /// can't blow up the size of domain messages and materialized state.
+    #[serde(default)]
+    pub max_value_size: Option<usize>,
+
+    /// If set, domains stop accepting writes to their base tables while more than this many
+    /// packets are queued to be processed by the domains on their worker, so that the writers
+    /// (such as the replicator) are slowed down rather than memory growing without bound.
+    #[serde(default)]
+    pub max_queued_packets: Option<usize>,
 }
 
 const BATCH_SIZE: usize = 256;
@@ -404,6 +424,7 @@ impl DomainBuilder {
 
             total_replay_time: Timer::new(),
             total_forward_time: Timer::new(),
+            evictions: 0,
 
             aggressively_update_state_sizes: self.config.aggressively_update_state_sizes,
             replay_completed: false,
@@ -411,6 +432,9 @@ impl DomainBuilder {
             metrics: domain_metrics::DomainMetrics::new(self.index, self.shard.unwrap_or(0)),
 
             eviction_kind: self.config.eviction_kind,
+            partial_spill: self.config.partial_spill,
+            max_queued_packets: self.config.max_queued_packets,
+            trace_span: Span::none(),
         }
     }
 }
@@ -487,6 +511,8 @@ pub struct Domain {
     total_replay_time: Timer<SimpleTracker, RealTime>,
     /// time spent processing ordinary, forward updates
     total_forward_time: Timer<SimpleTracker, RealTime>,
+    /// number of times state has been evicted to free memory
+    evictions: u64,
 
     /// If set to `true`, the metric tracking the in-memory size of materialized state will be
     /// updated after every packet is handled, rather than only when requested by the eviction
@@ -498,6 +524,14 @@ pub struct Domain {
 
     metrics: domain_metrics::DomainMetrics,
     eviction_kind: crate::EvictionKind,
+    partial_spill: Option<SpillParameters>,
+    max_queued_packets: Option<usize>,
+
+    /// The span for the packet currently being handled, if that packet is part of a trace.
+    ///
+    /// Replay requests sent while handling the packet carry this span's context, so that their
+    /// handling shows up in the same trace.
+    trace_span: Span,
 }
 
 impl Domain {
@@ -511,6 +545,12 @@ impl Domain {
         self.shard.unwrap_or(0)
     }
 
+    /// Return the number of queued packets beyond which this domain stops accepting writes to
+    /// its base tables, if any
+    pub fn max_queued_packets(&self) -> Option<usize> {
+        self.max_queued_packets
+    }
+
     fn snapshotting_base_nodes(&self) -> Vec<LocalNodeIndex> {
         self.state
             .iter()
@@ -598,6 +638,7 @@ impl Domain {
                         keys: miss_keys.clone(),
                         unishard: true, // local replays are necessarily single-shard
                         requesting_shard: self.shard.unwrap_or(0),
+                        trace_context: RequestContext::from_span(&self.trace_span),
                     }));
                 continue;
             }
@@ -616,7 +657,7 @@ impl Domain {
         &mut self,
         miss_in: LocalNodeIndex,
         miss_columns: &[usize],
-        missed_keys: HashSet<(KeyComparison, KeyComparison)>,
+        mut missed_keys: HashSet<(KeyComparison, KeyComparison)>,
         was_single_shard: bool,
         requesting_shard: usize,
         needed_for: Tag,
@@ -634,6 +675,55 @@ impl Domain {
             .replay_paths
             .columns_are_generated(miss_in, miss_columns);
 
+        // If any of the keys we missed on were recently evicted from the state we missed in and
+        // spilled to disk, we can fill them locally rather than issuing an upquery, and then redo
+        // the replay immediately
+        if !is_generated {
+            if let Some(state) = self.state.get_mut(miss_in) {
+                let mut filled = HashSet::new();
+                missed_keys.retain(|(replay_key, miss_key)| {
+                    let already_requested = w.redos.contains_key(&Hole {
+                        node: miss_in,
+                        column_indices: miss_columns.to_vec(),
+                        key: miss_key.clone(),
+                    });
+                    if !already_requested && state.fill_from_spill(miss_columns, miss_key) {
+                        filled.insert(replay_key.clone());
+                        false
+                    } else {
+                        true
+                    }
+                });
+                // Replays that also missed on other keys will be redone once those are filled
+                for (replay_key, _) in &missed_keys {
+                    filled.remove(replay_key);
+                }
+
+                if !filled.is_empty() {
+                    trace!(
+                        %miss_in,
+                        keys = ?filled,
+                        "filled replay misses from spilled state"
+                    );
+                    self.delayed_for_self
+                        .push_back(Box::new(Packet::RequestPartialReplay {
+                            tag: needed_for,
+                            keys: filled.into_iter().collect(),
+                            unishard: was_single_shard,
+                            requesting_shard,
+                            trace_context: RequestContext::from_span(&self.trace_span),
+                        }));
+                }
+            }
+
+            if missed_keys.is_empty() {
+                if !w.redos.is_empty() {
+                    self.waiting.insert(miss_in, w);
+                }
+                return Ok(());
+            }
+        }
+
         // Map of replays we need to do, grouped by the set of columns at the *target* of the replay
         // (which in the case of remapped upqueries might be different than the columns we missed
         // on!)
@@ -762,6 +852,7 @@ impl Domain {
                             unishard: false, // ask_all is true, so replay is sharded
                             keys: keys.clone(), // sad to clone here
                             requesting_shard: self.shard.unwrap_or(0),
+                            trace_context: RequestContext::from_span(&self.trace_span),
                         }))
                         .is_err()
                     {
@@ -788,6 +879,7 @@ impl Domain {
                         keys,
                         unishard: true, // only one option, so only one path
                         requesting_shard: self.shard.unwrap_or(0),
+                        trace_context: RequestContext::from_span(&self.trace_span),
                     }))
                     .is_err()
                 {
@@ -811,6 +903,7 @@ impl Domain {
                             keys,
                             unishard: true, // !ask_all, so only one path
                             requesting_shard: self.shard.unwrap_or(0),
+                            trace_context: RequestContext::from_span(&self.trace_span),
                         }))
                         .is_err()
                     {
@@ -1326,10 +1419,12 @@ impl Domain {
                 match state {
                     InitialState::PartialLocal { strict, weak } => {
                         if !self.state.contains_key(node) {
-                            self.state.insert(
-                                node,
-                                MaterializedNodeState::Memory(MemoryState::default()),
-                            );
+                            let state = match &self.partial_spill {
+                                Some(params) => MemoryState::with_spill(params),
+                                None => MemoryState::default(),
+                            };
+                            self.state
+                                .insert(node, MaterializedNodeState::Memory(state));
                         }
                         let state = self.state.get_mut(node).unwrap();
                         for (index, tags) in strict {
@@ -1399,11 +1494,12 @@ impl Domain {
                                 let cols = index.columns.clone();
                                 tokio::spawn(
                                     UnboundedReceiverStream::new(rx)
-                                        .map(move |misses| {
+                                        .map(move |(misses, trace_context)| {
                                             Box::new(Packet::RequestReaderReplay {
                                                 keys: misses,
                                                 cols: cols.clone(),
                                                 node,
+                                                trace_context,
                                             })
                                         })
                                         .map(Ok)
@@ -1423,13 +1519,16 @@ impl Domain {
                             index,
                             move |misses: &mut dyn Iterator<Item = &KeyComparison>| {
                                 let n = txs.len();
+                                // Misses are triggered from the read request that hit them, so
+                                // the replay continues that request's trace
+                                let trace_context = RequestContext::from_current_span();
                                 if n == 1 {
                                     let misses = misses.cloned().collect::<Vec<_>>();
                                     if misses.is_empty() {
                                         return true;
                                     }
                                     #[allow(clippy::indexing_slicing)] // just checked len is 1
-                                    txs[0].send(misses).is_ok()
+                                    txs[0].send((misses, trace_context)).is_ok()
                                 } else {
                                     // TODO: compound reader
                                     let mut per_shard = HashMap::new();
@@ -1448,7 +1547,12 @@ impl Domain {
                                     per_shard.into_iter().all(|(shard, keys)| {
                                         #[allow(clippy::indexing_slicing)]
                                         // we know txs.len() is equal to num_shards
-                                        txs[shard].send(keys.into_iter().cloned().collect()).is_ok()
+                                        txs[shard]
+                                            .send((
+                                                keys.into_iter().cloned().collect(),
+                                                trace_context.clone(),
+                                            ))
+                                            .is_ok()
                                     })
                                 }
                             },
@@ -1819,6 +1923,13 @@ impl Domain {
                     }
                 }
 
+                // now that the reader is ready to accept replays, re-request any keys it held
+                // before a restart
+                let n = node_ref.borrow();
+                if n.as_reader().map_or(false, |r| r.is_partial()) {
+                    self.warm_reader(n.global_addr(), n.name().clone());
+                }
+
                 Ok(None)
             }
             DomainRequest::GetStatistics => {
@@ -1828,6 +1939,7 @@ impl Domain {
                     total_replay_time: self.total_replay_time.num_nanoseconds(),
                     total_forward_time: self.total_forward_time.num_nanoseconds(),
                     wait_time: self.wait_time.num_nanoseconds(),
+                    evictions: self.evictions,
                 };
 
                 let node_stats: HashMap<
@@ -1852,6 +1964,21 @@ impl Domain {
                                     .map(|s| s.deep_size_of())
                                     .unwrap_or(0)
                             });
+                        let rows = self
+                            .state
+                            .get(local_index)
+                            .map(|s| s.rows() as u64)
+                            .unwrap_or(0);
+                        let memory = n
+                            .as_reader()
+                            .map(|r| r.memory_usage().unwrap_or_default())
+                            .unwrap_or_else(|| {
+                                self.state
+                                    .get(local_index)
+                                    .map(|s| s.memory_usage())
+                                    .unwrap_or_default()
+                            });
+                        let table_statistics = n.get_base().map(|b| b.statistics());
 
                         let mat_state = n
                             .as_reader()
@@ -1891,6 +2018,9 @@ impl Domain {
                                     process_time: time,
                                     process_ptime: ptime,
                                     mem_size,
+                                    rows,
+                                    memory,
+                                    table_statistics,
                                     materialized: mat_state,
                                     probe_result,
                                 },
@@ -1914,6 +2044,59 @@ impl Domain {
             DomainRequest::RequestSnapshottingTables => {
                 Ok(Some(bincode::serialize(&self.snapshotting_base_nodes())?))
             }
+            DomainRequest::CompactBase { node } => {
+                let state = self
+                    .state
+                    .get(node)
+                    .and_then(|s| s.as_persistent())
+                    .ok_or_else(|| ReadySetError::InvalidNodeType {
+                        node_index: node.id(),
+                        expected_type: NodeType::Base,
+                    })?;
+                state.compact();
+                Ok(None)
+            }
+            DomainRequest::FlushBase { node } => {
+                let state = self
+                    .state
+                    .get(node)
+                    .and_then(|s| s.as_persistent())
+                    .ok_or_else(|| ReadySetError::InvalidNodeType {
+                        node_index: node.id(),
+                        expected_type: NodeType::Base,
+                    })?;
+                state.flush();
+                Ok(None)
+            }
+            DomainRequest::SetBaseCompression { node, options } => {
+                let state = self
+                    .state
+                    .get_mut(node)
+                    .and_then(|s| s.as_persistent_mut())
+                    .ok_or_else(|| ReadySetError::InvalidNodeType {
+                        node_index: node.id(),
+                        expected_type: NodeType::Base,
+                    })?;
+                state.set_compression(options)?;
+                Ok(None)
+            }
+            DomainRequest::RefreshBaseStatistics { node } => {
+                let mut n = self
+                    .nodes
+                    .get(node)
+                    .ok_or_else(|| ReadySetError::NoSuchNode(node.id()))?
+                    .borrow_mut();
+                match (n.get_base_mut(), self.state.get(node)) {
+                    (Some(base), Some(state)) => {
+                        base.refresh_statistics(state.cloned_records());
+                        Ok(None)
+                    }
+                    _ => Err(ReadySetError::InvalidNodeType {
+                        node_index: node.id(),
+                        expected_type: NodeType::Base,
+                    }),
+                }
+            }
             DomainRequest::Packet(pkt) => {
                 self.handle_packet(Box::new(pkt), executor)?;
                 Ok(None)
@@ -1945,8 +2128,36 @@ impl Domain {
         ret
     }
 
-    #[allow(clippy::cognitive_complexity)]
+    /// Handle a single packet, within a span that continues the packet's trace if it has one
     fn handle(&mut self, m: Box<Packet>, executor: &mut dyn Executor) -> Result<(), ReadySetError> {
+        let span = match m.trace_context() {
+            Some(ctx) => {
+                let packet_type: &'static str = PacketDiscriminants::from(&*m).into();
+                ctx.child_span(info_span!(
+                    "domain_packet",
+                    domain = %self.index,
+                    shard = self.shard.unwrap_or(0),
+                    packet_type
+                ))
+            }
+            None => Span::none(),
+        };
+        if span.is_disabled() {
+            return self.handle_inner(m, executor);
+        }
+
+        let previous_span = mem::replace(&mut self.trace_span, span.clone());
+        let res = span.in_scope(|| self.handle_inner(m, executor));
+        self.trace_span = previous_span;
+        res
+    }
+
+    #[allow(clippy::cognitive_complexity)]
+    fn handle_inner(
+        &mut self,
+        m: Box<Packet>,
+        executor: &mut dyn Executor,
+    ) -> Result<(), ReadySetError> {
         // TODO(eta): better error handling here.
         // In particular one dodgy packet can kill the whole domain, which is probably not what we
         // want.
@@ -1986,6 +2197,7 @@ impl Domain {
                 mut keys,
                 cols,
                 node,
+                ..
             } => {
                 let start = time::Instant::now();
                 self.total_replay_time.start();
@@ -2051,6 +2263,7 @@ impl Domain {
                 keys,
                 unishard,
                 requesting_shard,
+                ..
             } => {
                 trace!(%tag, ?keys, "got replay request");
                 let start = time::Instant::now();
@@ -2080,6 +2293,47 @@ impl Domain {
         Ok(())
     }
 
+    /// If the keys of the given partially materialized reader were snapshotted to disk (see
+    /// [`PersistenceParameters::reader_key_snapshot_interval`]), request replays for all of them,
+    /// so that a reader recreated after a restart comes back with the keys it had before
+    fn warm_reader(&self, gid: NodeIndex, name: SqlIdentifier) {
+        let shard = self.shard.unwrap_or(0);
+        let path = match self.persistence_parameters.reader_keys_path(&name, shard) {
+            Some(path) => path,
+            None => return,
+        };
+        let keys = match fs::read(&path) {
+            Ok(bytes) => match bincode::deserialize::<Vec<Vec<DataType>>>(&bytes) {
+                Ok(keys) => keys,
+                Err(error) => {
+                    warn!(%error, reader = %name, "Could not decode reader key snapshot");
+                    return;
+                }
+            },
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return,
+            Err(error) => {
+                warn!(%error, reader = %name, "Could not read reader key snapshot");
+                return;
+            }
+        };
+        let keys = keys
+            .into_iter()
+            .filter_map(|key| Vec1::try_from(key).ok().map(KeyComparison::from))
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return;
+        }
+
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let readers = self.readers.lock().unwrap();
+        if let Some(reader) = readers.get(&(gid, name.clone(), shard)) {
+            info!(reader = %name, %shard, keys = keys.len(), "Warming reader from key snapshot");
+            if !reader.trigger(keys.iter()) {
+                warn!(reader = %name, "Could not request replays to warm reader");
+            }
+        }
+    }
+
     /// Timed purges happen when [`FrontierStrategy`] is not None, in which case all keys
     /// are purged from the node after a given amount of time
     fn handle_timed_purges(&mut self) {
@@ -3264,6 +3518,7 @@ impl Domain {
                             unishard,
                             keys,
                             requesting_shard,
+                            trace_context: RequestContext::from_span(&self.trace_span),
                         }));
                 }
 
@@ -3615,6 +3870,7 @@ impl Domain {
                     total_freed += freed;
                 }
 
+                self.evictions += 1;
                 self.metrics.rec_eviction_time(start.elapsed(), total_freed);
             }
             Packet::EvictKeys {
@@ -3729,6 +3985,8 @@ impl Domain {
             self.estimated_base_tables_size(),
             total_node_state + reader_size,
         );
+        self.metrics
+            .set_queue_depth(self.replay_request_queue.len() + self.delayed_for_self.len());
 
         self.state_size.store(total as usize, Ordering::Release);
         // no response sent, as worker will read the atomic
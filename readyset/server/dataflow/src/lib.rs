@@ -47,7 +47,7 @@ pub type Readers = Arc<
 pub type DomainConfig = domain::Config;
 
 pub use dataflow_expression::{BuiltinFunction, Expression};
-pub use dataflow_state::{DurabilityMode, PersistenceParameters};
+pub use dataflow_state::{DurabilityMode, PersistenceParameters, SpillParameters};
 
 pub use crate::domain::{Domain, DomainBuilder, DomainIndex};
 pub use crate::node::special::reader::post_lookup;
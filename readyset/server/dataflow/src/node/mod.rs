@@ -517,6 +517,17 @@ impl Node {
         self.columns.len() - 1
     }
 
+    /// Replace the column at the given index
+    pub fn set_column(&mut self, index: usize, column: Column) -> ReadySetResult<()> {
+        match self.columns.get_mut(index) {
+            Some(c) => {
+                *c = column;
+                Ok(())
+            }
+            None => internal!("column index {} out of bounds", index),
+        }
+    }
+
     pub fn has_domain(&self) -> bool {
         self.domain.is_some()
     }
@@ -137,7 +137,9 @@ impl Node {
                 // NOTE: bases only accept BaseOperations
                 match m.take().map(|p| *p) {
                     Some(Packet::Input { inner, .. }) => {
-                        let PacketData { dst, data, trace } = unsafe { inner.take() };
+                        let PacketData {
+                            dst, data, trace, ..
+                        } = unsafe { inner.take() };
                         let data = data
                             .try_into()
                             .expect("Payload of Input packet was not of Input type");
@@ -160,6 +162,8 @@ impl Node {
                             env.state.get_mut(addr).and_then(|s| s.as_persistent_mut()),
                         ) {
                             s.set_snapshot_mode(SnapshotMode::SnapshotModeEnabled);
+                            // Entering snapshot mode clears the table
+                            b.clear_statistics();
                         }
 
                         // When a replay originates at a base node, we replay the data *through*
@@ -171,6 +175,7 @@ impl Node {
                         //
                         // So: only materialize if the message we're processing is not a replay!
                         if keyed_by.is_none() {
+                            b.update_statistics(&rs);
                             materialize(&mut rs, replication_offset, None, env.state.get_mut(addr));
                         }
 
@@ -600,6 +605,7 @@ impl Node {
                         dst,
                         data: PacketPayload::Timestamp(timestamp),
                         trace: None,
+                        trace_context: None,
                     }),
                 });
 
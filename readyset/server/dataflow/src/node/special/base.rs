@@ -1,3 +1,5 @@
+mod statistics;
+
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -7,6 +9,7 @@ use itertools::Itertools;
 use launchpad::redacted::Sensitive;
 use launchpad::Indices;
 use maplit::hashmap;
+use readyset::debug::stats::TableStatistics;
 use readyset::replication::ReplicationOffset;
 use readyset::{Modification, Operation, TableOperation};
 use readyset_data::DataTypeKind;
@@ -15,6 +18,7 @@ use serde::{Deserialize, Serialize};
 use tracing::warn;
 use vec_map::VecMap;
 
+use self::statistics::StatisticsCollector;
 use crate::prelude::*;
 use crate::processing::LookupIndex;
 
@@ -67,6 +71,11 @@ pub struct Base {
     defaults: Vec<DataType>,
     dropped: Vec<usize>,
     unmodified: bool,
+
+    /// Statistics about the rows written to this base table. These are only kept in memory by
+    /// the domain, so after a restart they only cover the writes made since then.
+    #[serde(skip)]
+    statistics: StatisticsCollector,
 }
 
 impl Base {
@@ -145,6 +154,28 @@ impl Base {
             .collect()
     }
 
+    /// Update the statistics about this table with records about to be written to it
+    pub(crate) fn update_statistics(&mut self, records: &Records) {
+        self.statistics.update(records)
+    }
+
+    /// Forget the statistics about this table, for when it starts being re-snapshotted
+    pub(crate) fn clear_statistics(&mut self) {
+        self.statistics.clear()
+    }
+
+    /// Recompute the statistics about this table from the rows it currently holds, so that they no
+    /// longer take into account values that have since been deleted or updated
+    pub(crate) fn refresh_statistics(&mut self, rows: Vec<Vec<DataType>>) {
+        self.statistics.clear();
+        self.statistics.update(&rows.into())
+    }
+
+    /// Returns statistics about the rows written to this table
+    pub fn statistics(&self) -> TableStatistics {
+        self.statistics.statistics()
+    }
+
     /// Extend a row with default values if it does not match the current schema length
     pub(crate) fn fix(&self, row: &mut Vec<DataType>) {
         if self.unmodified {
@@ -169,6 +200,7 @@ impl Clone for Base {
             defaults: self.defaults.clone(),
             dropped: self.dropped.clone(),
             unmodified: self.unmodified,
+            statistics: self.statistics.clone(),
         }
     }
 }
@@ -181,6 +213,7 @@ impl Default for Base {
             defaults: Vec::new(),
             dropped: Vec::new(),
             unmodified: true,
+            statistics: Default::default(),
         }
     }
 }
@@ -194,6 +227,7 @@ fn key_val(i: usize, col: usize, r: &TableOperation) -> Option<&DataType> {
         TableOperation::InsertOrUpdate { ref row, .. } => Some(&row[col]),
         TableOperation::SetReplicationOffset(_) => None,
         TableOperation::SetSnapshotMode(_) => None,
+        TableOperation::Truncate => None,
     }
 }
 
@@ -259,6 +293,58 @@ impl Base {
         mut ops: Vec<TableOperation>,
         state: &StateMap,
         snapshot_mode: SnapshotMode,
+    ) -> ReadySetResult<BaseWrite> {
+        let last_truncate = match ops.iter().rposition(|op| *op == TableOperation::Truncate) {
+            Some(pos) => pos,
+            None => return self.process_ops(our_index, ops, state, snapshot_mode, false),
+        };
+
+        // Every row stored in the table is removed by the truncate, along with the effects of all
+        // the writes that precede it in the batch, so only the changes to the replication offset
+        // and snapshot mode are kept from those
+        let mut i = 0;
+        ops.retain(|op| {
+            let keep = i > last_truncate
+                || matches!(
+                    op,
+                    TableOperation::SetReplicationOffset(_) | TableOperation::SetSnapshotMode(_)
+                );
+            i += 1;
+            keep
+        });
+
+        let db = match state.get(our_index) {
+            Some(x) => x,
+            None => internal!("base must be materialized"),
+        };
+        let mut records = db
+            .cloned_records()
+            .into_iter()
+            .map(|mut row| {
+                self.fix(&mut row);
+                Record::Negative(row)
+            })
+            .collect::<Vec<_>>();
+
+        let write = self.process_ops(our_index, ops, state, snapshot_mode, true)?;
+        records.extend(write.records);
+
+        Ok(BaseWrite {
+            records: records.into(),
+            ..write
+        })
+    }
+
+    /// Compute the deltas required to apply the list of the provided `TableOperation`, none of
+    /// which may be a `Truncate`, to the base table. If `truncated` is set, the rows stored in the
+    /// table are treated as having already been removed.
+    fn process_ops(
+        &mut self,
+        our_index: LocalNodeIndex,
+        mut ops: Vec<TableOperation>,
+        state: &StateMap,
+        snapshot_mode: SnapshotMode,
+        truncated: bool,
     ) -> ReadySetResult<BaseWrite> {
         let key_cols = match &self.primary_key {
             Some(key) if !ops.is_empty() => key.as_ref(),
@@ -330,6 +416,7 @@ impl Base {
                     Some(TouchedKey::Inserted(row)) => Some(row.clone()), /* Row was added in previous iteration */
                     Some(TouchedKey::Deleted) => None,                    /* Row was deleted */
                     // previously
+                    None if truncated => None,
                     None => match db.lookup(key_cols, &KeyType::from(&key)) {
                         LookupResult::Missing => internal!(),
                         LookupResult::Some(rows) if rows.is_empty() => None,
@@ -394,6 +481,7 @@ impl Base {
                     }
                     TableOperation::SetSnapshotMode(_)
                     | TableOperation::SetReplicationOffset(_)
+                    | TableOperation::Truncate
                     | TableOperation::InsertOrUpdate { .. } => {
                         // This is unreachable, because all of those cases are handled above
                     }
@@ -787,6 +875,121 @@ mod tests {
             )
         }
 
+        #[test]
+        fn truncate_keyed() {
+            let mut b = Base::new().with_primary_key([0]);
+
+            let ni = unsafe { LocalNodeIndex::make(0u32) };
+
+            let mut state = MaterializedNodeState::Persistent(PersistentState::new(
+                String::from("truncate_keyed"),
+                Vec::<Box<[usize]>>::new(),
+                &PersistenceParameters::default(),
+            ));
+
+            state.add_key(Index::hash_map(vec![0]), None);
+
+            let mut recs = vec![
+                Record::Positive(vec![1.into(), 2.into()]),
+                Record::Positive(vec![2.into(), 3.into()]),
+            ]
+            .into();
+            state.process_records(&mut recs, None, None);
+
+            let mut state_map = NodeMap::new();
+            state_map.insert(ni, state);
+
+            let mut write = b
+                .process(
+                    ni,
+                    vec![
+                        TableOperation::Insert(vec![3.into(), 4.into()]),
+                        TableOperation::SetReplicationOffset(ReplicationOffset {
+                            offset: 1,
+                            replication_log_name: "binlog".to_owned(),
+                        }),
+                        TableOperation::Truncate,
+                        TableOperation::Insert(vec![1.into(), 5.into()]),
+                        TableOperation::DeleteByKey {
+                            key: vec![2.into()],
+                        },
+                    ],
+                    &state_map,
+                    SnapshotMode::SnapshotModeDisabled,
+                )
+                .unwrap();
+            write.records.sort();
+
+            assert_eq!(
+                write,
+                BaseWrite {
+                    records: vec![
+                        Record::Positive(vec![1.into(), 5.into()]),
+                        Record::Negative(vec![1.into(), 2.into()]),
+                        Record::Negative(vec![2.into(), 3.into()]),
+                    ]
+                    .into(),
+                    replication_offset: Some(ReplicationOffset {
+                        offset: 1,
+                        replication_log_name: "binlog".to_owned(),
+                    }),
+                    set_snapshot_mode: None,
+                }
+            )
+        }
+
+        #[test]
+        fn truncate_unkeyed() {
+            let mut b = Base::new();
+
+            let ni = unsafe { LocalNodeIndex::make(0u32) };
+
+            let mut state = MaterializedNodeState::Persistent(PersistentState::new(
+                String::from("truncate_unkeyed"),
+                Vec::<Box<[usize]>>::new(),
+                &PersistenceParameters::default(),
+            ));
+
+            state.add_key(Index::hash_map(vec![0]), None);
+
+            let mut recs = vec![
+                Record::Positive(vec![1.into(), 2.into()]),
+                Record::Positive(vec![2.into(), 3.into()]),
+            ]
+            .into();
+            state.process_records(&mut recs, None, None);
+
+            let mut state_map = NodeMap::new();
+            state_map.insert(ni, state);
+
+            let mut write = b
+                .process(
+                    ni,
+                    vec![
+                        TableOperation::Truncate,
+                        TableOperation::Insert(vec![3.into(), 4.into()]),
+                    ],
+                    &state_map,
+                    SnapshotMode::SnapshotModeDisabled,
+                )
+                .unwrap();
+            write.records.sort();
+
+            assert_eq!(
+                write,
+                BaseWrite {
+                    records: vec![
+                        Record::Positive(vec![3.into(), 4.into()]),
+                        Record::Negative(vec![1.into(), 2.into()]),
+                        Record::Negative(vec![2.into(), 3.into()]),
+                    ]
+                    .into(),
+                    replication_offset: None,
+                    set_snapshot_mode: None,
+                }
+            )
+        }
+
         #[test]
         fn delete_after_key_update() {
             let mut b = Base::new().with_primary_key([0]);
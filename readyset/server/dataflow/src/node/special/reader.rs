@@ -5,6 +5,7 @@ use std::time::SystemTime;
 use failpoint_macros::failpoint;
 use metrics::histogram;
 use readyset::consistency::Timestamp;
+use readyset::debug::stats::MemoryUsage;
 use readyset::metrics::recorded;
 use readyset::{KeyColumnIdx, KeyComparison, ViewPlaceholder};
 use serde::{Deserialize, Serialize};
@@ -145,6 +146,10 @@ impl Reader {
         self.writer.as_ref().map(SizeOf::deep_size_of)
     }
 
+    pub(crate) fn memory_usage(&self) -> Option<MemoryUsage> {
+        self.writer.as_ref().map(backlog::WriteHandle::memory_usage)
+    }
+
     /// Evict a randomly selected key, returning the number of bytes evicted.
     pub(crate) fn evict_bytes(&mut self, bytes: usize) -> u64 {
         let mut bytes_freed = 0;
@@ -158,9 +163,7 @@ impl Reader {
     pub(in crate::node) fn on_eviction(&mut self, keys: &[KeyComparison]) {
         // NOTE: *could* be None if reader has been created but its state hasn't been built yet
         if let Some(w) = self.writer.as_mut() {
-            for k in keys {
-                w.mark_hole(k);
-            }
+            w.evict_keys(keys);
             w.swap();
         }
     }
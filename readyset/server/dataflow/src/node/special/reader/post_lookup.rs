@@ -9,6 +9,7 @@ use itertools::Either;
 use launchpad::Indices;
 use nom_sql::OrderType;
 use readyset::ReadySetResult;
+use readyset_data::NullOrder;
 use readyset_errors::internal_err;
 use serde::{Deserialize, Serialize};
 
@@ -154,6 +155,9 @@ pub struct PostLookup {
     ///
     /// If an empty `Vec` is specified, all rows are sorted as if they were equal to each other.
     pub order_by: Option<Vec<(usize, OrderType)>>,
+    /// Where `NULL` values sort in `order_by`
+    #[serde(default)]
+    pub null_order: NullOrder,
     /// Maximum number of records to return
     pub limit: Option<usize>,
     /// Indices of the columns requested in the query. Reader will filter out all other projected
@@ -171,7 +175,12 @@ pub struct PostLookup {
 impl PostLookup {
     /// Returns true if this set of post-lookup operations is a no-op
     pub fn is_empty(&self) -> bool {
-        *self == Self::default()
+        // `null_order` is irrelevant if we don't order the results
+        *self
+            == Self {
+                null_order: self.null_order,
+                ..Self::default()
+            }
     }
 
     /// Apply this set of post-lookup operations, optionally filtering by an [`Expression`], to the
@@ -216,7 +225,12 @@ impl PostLookup {
             Either::Right(filtered)
         };
 
-        let ordered_limited = do_order_limit(aggregated, self.order_by.as_deref(), self.limit);
+        let ordered_limited = do_order_limit(
+            aggregated,
+            self.order_by.as_deref(),
+            self.null_order,
+            self.limit,
+        );
 
         let returned_cols = if let Some(c) = &self.returned_cols {
             c
@@ -288,6 +302,7 @@ where
 fn do_order<'a, I>(
     iter: I,
     indices: &[(usize, OrderType)],
+    null_order: NullOrder,
 ) -> impl Iterator<Item = Vec<Cow<'a, DataType>>>
 where
     I: Iterator<Item = Vec<Cow<'a, DataType>>>,
@@ -295,22 +310,11 @@ where
     // TODO(eta): is there a way to avoid buffering all the results?
     let mut results = iter.collect::<Vec<_>>();
     results.sort_by(|a, b| {
-        // protip: look at what `Ordering::then` does if you're confused by this
-        //
-        // TODO(eta): Technically, this is inefficient, because you can break out of the fold
-        //            early if you hit something that isn't `Ordering::Equal`. In practice though
-        //            it's likely to be neglegible.
-        // NOTE(grfn): or LLVM / branch prediction just optimizes it away!
         indices
             .iter()
-            .map(|&(idx, order_type)| {
-                let ret = a[idx].cmp(&b[idx]);
-                match order_type {
-                    OrderType::OrderAscending => ret,
-                    OrderType::OrderDescending => ret.reverse(),
-                }
-            })
-            .fold(Ordering::Equal, |acc, next| acc.then(next))
+            .map(|&(idx, order_type)| null_order.compare_ordered(order_type, &a[idx], &b[idx]))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
     });
     results.into_iter()
 }
@@ -318,6 +322,7 @@ where
 fn do_order_limit<'a, I>(
     iter: I,
     order_by: Option<&[(usize, OrderType)]>,
+    null_order: NullOrder,
     limit: Option<usize>,
 ) -> impl Iterator<Item = Vec<Cow<'a, DataType>>>
 where
@@ -325,10 +330,10 @@ where
 {
     match (order_by, limit) {
         (None, None) => OrderedLimitedIter::Original(iter),
-        (Some(indices), None) => OrderedLimitedIter::Ordered(do_order(iter, indices)),
+        (Some(indices), None) => OrderedLimitedIter::Ordered(do_order(iter, indices, null_order)),
         (None, Some(lim)) => OrderedLimitedIter::Limited(iter.take(lim)),
         (Some(indices), Some(lim)) => {
-            OrderedLimitedIter::OrderedLimited(do_order(iter, indices).take(lim))
+            OrderedLimitedIter::OrderedLimited(do_order(iter, indices, null_order).take(lim))
         }
     }
 }
@@ -514,6 +519,46 @@ mod tests {
             );
         }
 
+        #[test]
+        fn order_with_nulls() {
+            let records: Vec<Box<[DataType]>> = vec![
+                vec![2.into()].into_boxed_slice(),
+                vec![DataType::None].into_boxed_slice(),
+                vec![1.into()].into_boxed_slice(),
+            ];
+            let order = |null_order, order_type| {
+                PostLookup {
+                    order_by: Some(vec![(0, order_type)]),
+                    null_order,
+                    ..Default::default()
+                }
+                .process(records.iter(), &None)
+                .unwrap()
+                .into_iter()
+                .map(|row| row[0].clone().into_owned())
+                .collect::<Vec<_>>()
+            };
+
+            // MySQL
+            assert_eq!(
+                order(NullOrder::NullsFirst, OrderType::OrderAscending),
+                vec![DataType::None, 1.into(), 2.into()]
+            );
+            assert_eq!(
+                order(NullOrder::NullsFirst, OrderType::OrderDescending),
+                vec![2.into(), 1.into(), DataType::None]
+            );
+            // PostgreSQL
+            assert_eq!(
+                order(NullOrder::NullsLast, OrderType::OrderAscending),
+                vec![1.into(), 2.into(), DataType::None]
+            );
+            assert_eq!(
+                order(NullOrder::NullsLast, OrderType::OrderDescending),
+                vec![DataType::None, 2.into(), 1.into()]
+            );
+        }
+
         #[test]
         fn filter_aggregate_order_limit() {
             let records: Vec<Box<[DataType]>> = vec![
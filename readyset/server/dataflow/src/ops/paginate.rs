@@ -7,6 +7,7 @@ use std::mem;
 use itertools::Itertools;
 use launchpad::Indices;
 use nom_sql::OrderType;
+use readyset_data::NullOrder;
 use serde::{Deserialize, Serialize};
 
 use crate::ops::utils::Order;
@@ -87,6 +88,7 @@ impl Paginate {
     pub fn new(
         src: NodeIndex,
         order: Vec<(usize, OrderType)>,
+        null_order: NullOrder,
         group_by: Vec<usize>,
         limit: usize,
     ) -> Self {
@@ -95,7 +97,7 @@ impl Paginate {
             our_index: None,
             page_number_col: None,
             group_by,
-            order: order.into(),
+            order: Order::new(order, null_order),
             limit,
         }
     }
@@ -383,6 +385,7 @@ mod tests {
             Paginate::new(
                 s.as_global(),
                 vec![(0, OrderType::OrderDescending)],
+                NullOrder::default(),
                 vec![1],
                 3,
             ),
@@ -10,6 +10,7 @@ use launchpad::Indices;
 use maplit::hashmap;
 use nom_sql::OrderType;
 use readyset::internal;
+use readyset_data::NullOrder;
 use readyset_errors::{internal, internal_err, invariant, ReadySetResult};
 use serde::{Deserialize, Serialize};
 use tracing::trace;
@@ -90,11 +91,13 @@ impl TopK {
     ///
     /// * `src` - this operator's ancestor
     /// * `order` - The list of columns to compute top k over
+    /// * `null_order` - where `NULL` values sort in `order`
     /// * `group_by` - the columns that this operator is keyed on
     /// * `k` - the maximum number of results per group.
     pub fn new(
         src: NodeIndex,
         order: Vec<(usize, OrderType)>,
+        null_order: NullOrder,
         group_by: Vec<usize>,
         k: usize,
     ) -> Self {
@@ -102,7 +105,7 @@ impl TopK {
             src: src.into(),
             our_index: None,
             group_by,
-            order: order.into(),
+            order: Order::new(order, null_order),
             k,
         }
     }
@@ -474,7 +477,7 @@ mod tests {
         g.set_op(
             "topk",
             &["x", "y", "z"],
-            TopK::new(s.as_global(), cmp_rows, vec![1], 3),
+            TopK::new(s.as_global(), cmp_rows, NullOrder::default(), vec![1], 3),
             true,
         );
         (g, s)
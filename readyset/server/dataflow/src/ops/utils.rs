@@ -3,30 +3,22 @@ use std::fmt::Display;
 
 use itertools::Itertools;
 use nom_sql::OrderType;
+use readyset_data::NullOrder;
 use serde::{Deserialize, Serialize};
 
 use crate::prelude::DataType;
 
+/// An ordering of records by a list of columns, each in ascending or descending order, with
+/// `NULL` values placed according to a [`NullOrder`]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub(crate) struct Order(Vec<(usize, OrderType)>);
+pub(crate) struct Order(Vec<(usize, OrderType)>, NullOrder);
 impl Order {
-    pub(crate) fn cmp(&self, a: &[DataType], b: &[DataType]) -> Ordering {
-        for &(c, ref order_type) in &self.0 {
-            let result = match *order_type {
-                OrderType::OrderAscending => a[c].cmp(&b[c]),
-                OrderType::OrderDescending => b[c].cmp(&a[c]),
-            };
-            if result != Ordering::Equal {
-                return result;
-            }
-        }
-        Ordering::Equal
+    pub(crate) fn new(columns: Vec<(usize, OrderType)>, null_order: NullOrder) -> Self {
+        Order(columns, null_order)
     }
-}
 
-impl From<Vec<(usize, OrderType)>> for Order {
-    fn from(other: Vec<(usize, OrderType)>) -> Self {
-        Order(other)
+    pub(crate) fn cmp(&self, a: &[DataType], b: &[DataType]) -> Ordering {
+        self.1.compare_rows(&self.0, a, b)
     }
 }
 
@@ -3,7 +3,9 @@ use std::fmt::{self, Display};
 
 use itertools::Itertools;
 use readyset::internal::LocalOrNot;
+use readyset::storage::CompressionOptions;
 use readyset::{self, KeyComparison, PacketData, PacketTrace};
+use readyset_tracing::propagation::RequestContext;
 use serde::{Deserialize, Serialize};
 use strum_macros::{EnumCount, EnumDiscriminants, EnumIter, IntoStaticStr};
 use vec1::Vec1;
@@ -245,6 +247,24 @@ pub enum DomainRequest {
     /// Request a list of base table nodes that are currently involved in snapshotting.
     RequestSnapshottingTables,
 
+    /// Manually compact the persisted state of the given base table node, blocking until the
+    /// compaction has finished.
+    CompactBase { node: LocalNodeIndex },
+
+    /// Flush all writes to the persisted state of the given base table node to disk, including
+    /// writes made without the write-ahead log while the node is in snapshot mode.
+    FlushBase { node: LocalNodeIndex },
+
+    /// Change the compression options for the persisted state of the given base table node,
+    /// rewriting all existing data with the new options.
+    SetBaseCompression {
+        node: LocalNodeIndex,
+        options: CompressionOptions,
+    },
+
+    /// Recompute the statistics about the given base table node from the rows it currently holds.
+    RefreshBaseStatistics { node: LocalNodeIndex },
+
     /// Process the packet, as per usual
     Packet(Packet),
 
@@ -316,6 +336,8 @@ pub enum Packet {
         keys: Vec<KeyComparison>,
         unishard: bool,
         requesting_shard: usize,
+        /// The context of the trace the replay is part of, if it is being traced
+        trace_context: Option<RequestContext>,
     },
 
     /// Ask domain (nicely) to replay a particular set of keys into a Reader.
@@ -323,6 +345,8 @@ pub enum Packet {
         node: LocalNodeIndex,
         cols: Vec<usize>,
         keys: Vec<KeyComparison>,
+        /// The context of the trace of the read that missed, if it is being traced
+        trace_context: Option<RequestContext>,
     },
 
     /// A packet used solely to drive the event loop forward.
@@ -427,6 +451,20 @@ impl Packet {
         }
     }
 
+    /// Returns the context of the trace this packet is part of, if it is being traced
+    pub(crate) fn trace_context(&self) -> Option<&RequestContext> {
+        match *self {
+            Packet::Input { ref inner, .. } => unsafe { inner.deref() }.trace_context.as_ref(),
+            Packet::RequestPartialReplay {
+                ref trace_context, ..
+            }
+            | Packet::RequestReaderReplay {
+                ref trace_context, ..
+            } => trace_context.as_ref(),
+            _ => None,
+        }
+    }
+
     pub(crate) fn is_regular(&self) -> bool {
         matches!(*self, Packet::Message { .. })
     }
@@ -5,6 +5,7 @@ mod memory_state;
 mod mk_key;
 mod persistent_state;
 mod single_state;
+mod spill;
 
 use std::borrow::Cow;
 use std::fmt::{self, Debug};
@@ -17,6 +18,7 @@ use ahash::RandomState;
 use common::{KeyType, RangeKey, Records, SizeOf, Tag};
 use derive_more::From;
 use hashbag::HashBag;
+use readyset::debug::stats::MemoryUsage;
 use readyset::internal::Index;
 use readyset::replication::ReplicationOffset;
 use readyset::KeyComparison;
@@ -28,6 +30,7 @@ pub use self::memory_state::MemoryState;
 pub use self::persistent_state::{
     DurabilityMode, PersistenceParameters, PersistentState, SnapshotMode,
 };
+pub use self::spill::SpillParameters;
 
 /// Information about state evicted via a call to [`State::evict_bytes`]
 pub struct StateEvicted<'a> {
@@ -181,6 +184,9 @@ pub trait State: SizeOf + Send {
     /// state
     fn rows(&self) -> usize;
 
+    /// Return a breakdown of the memory used by this state
+    fn memory_usage(&self) -> MemoryUsage;
+
     /// Return a copy of all records. Panics if the state is only partially materialized.
     fn cloned_records(&self) -> Vec<Vec<DataType>>;
 
@@ -195,6 +201,12 @@ pub trait State: SizeOf + Send {
     /// Remove all rows from this state
     fn clear(&mut self);
 
+    /// Attempt to fill the hole for `key` in the partial index on `columns` using rows that were
+    /// previously spilled to disk when that key was evicted, returning true if the hole was filled.
+    ///
+    /// If this returns false, the hole must be filled by a replay as usual.
+    fn fill_from_spill(&mut self, columns: &[usize], key: &KeyComparison) -> bool;
+
     /// Tear down the state, freeing any resources.
     /// For those states that are backed by resources outside ReadySet, the implementation of this
     /// method should guarantee that those resources are freed.
@@ -332,6 +344,13 @@ impl State for MaterializedNodeState {
         }
     }
 
+    fn memory_usage(&self) -> MemoryUsage {
+        match self {
+            MaterializedNodeState::Memory(ms) => ms.memory_usage(),
+            MaterializedNodeState::Persistent(ps) => ps.memory_usage(),
+        }
+    }
+
     fn cloned_records(&self) -> Vec<Vec<DataType>> {
         match self {
             MaterializedNodeState::Memory(ms) => ms.cloned_records(),
@@ -360,6 +379,13 @@ impl State for MaterializedNodeState {
         }
     }
 
+    fn fill_from_spill(&mut self, columns: &[usize], key: &KeyComparison) -> bool {
+        match self {
+            MaterializedNodeState::Memory(ms) => ms.fill_from_spill(columns, key),
+            MaterializedNodeState::Persistent(ps) => ps.fill_from_spill(columns, key),
+        }
+    }
+
     fn tear_down(self) -> ReadySetResult<()> {
         match self {
             MaterializedNodeState::Memory(ms) => ms.tear_down(),
@@ -2,16 +2,18 @@ use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
 
 use common::{IndexType, KeyType, RangeKey, Record, Records, SizeOf, Tag};
+use readyset::debug::stats::MemoryUsage;
 use readyset::internal::Index;
 use readyset::replication::ReplicationOffset;
 use readyset::KeyComparison;
 use readyset_data::DataType;
 use readyset_errors::ReadySetResult;
 use rand::{self, Rng};
-use tracing::trace;
+use tracing::{trace, warn};
 
 use crate::keyed_state::KeyedState;
 use crate::single_state::SingleState;
+use crate::spill::{SpillCache, SpillParameters};
 use crate::{
     LookupResult, PersistentState, RangeLookupResult, RecordResult, Row, Rows, State, StateEvicted,
 };
@@ -22,9 +24,14 @@ pub struct MemoryState {
     weak_indices: HashMap<Vec<usize>, KeyedState>,
     by_tag: HashMap<Tag, usize>,
     mem_size: u64,
+    /// The portion of `mem_size` used by the keys filled in partial indices
+    key_bytes: u64,
     /// The latest replication offset that has been written to the base table backed by this
     /// [`MemoryState`], it is only used when [`LocalAuthority`] is the ReadySet authority.
     replication_offset: Option<ReplicationOffset>,
+    /// If set, keys evicted from partial indices are written here rather than discarded. See
+    /// [the `spill` module](crate::spill) for more information.
+    spill: Option<SpillCache>,
 }
 
 impl SizeOf for MemoryState {
@@ -150,10 +157,27 @@ impl State for MemoryState {
         self.state.iter().map(SingleState::rows).sum()
     }
 
+    fn memory_usage(&self) -> MemoryUsage {
+        // Rows are shared between indices, but every index holds its own pointer to each row
+        let row_pointers = self.state.iter().map(SingleState::rows).sum::<usize>();
+        MemoryUsage {
+            key_bytes: self.key_bytes,
+            row_bytes: self.mem_size.saturating_sub(self.key_bytes),
+            overhead_bytes: (std::mem::size_of::<Self>()
+                + row_pointers * std::mem::size_of::<Row>()) as u64,
+        }
+    }
+
     fn mark_filled(&mut self, key: KeyComparison, tag: Tag) {
         debug_assert!(!self.state.is_empty(), "filling uninitialized index");
         let index = self.by_tag[&tag];
-        self.mem_size += base_row_bytes_from_comparison(&key);
+        if let (Some(spill), KeyComparison::Equal(key)) = (&mut self.spill, &key) {
+            // We're being filled by a replay, so the spilled rows (if any) are no longer needed
+            spill.invalidate(index, key.as_slice());
+        }
+        let key_bytes = base_row_bytes_from_comparison(&key);
+        self.mem_size += key_bytes;
+        self.key_bytes += key_bytes;
         self.state[index].mark_filled(key);
     }
 
@@ -161,9 +185,9 @@ impl State for MemoryState {
         debug_assert!(!self.state.is_empty(), "filling uninitialized index");
         let index = self.by_tag[&tag];
         let freed_bytes = self.state[index].mark_hole(key);
-        self.mem_size = self
-            .mem_size
-            .saturating_sub(freed_bytes + base_row_bytes_from_comparison(key));
+        let key_bytes = base_row_bytes_from_comparison(key);
+        self.mem_size = self.mem_size.saturating_sub(freed_bytes + key_bytes);
+        self.key_bytes = self.key_bytes.saturating_sub(key_bytes);
     }
 
     fn lookup<'a>(&'a self, columns: &[usize], key: &KeyType) -> LookupResult<'a> {
@@ -293,6 +317,7 @@ impl State for MemoryState {
         let mut rng = rand::thread_rng();
         let state_index = rng.gen_range(0, self.state.len());
         let mut bytes_freed = 0u64;
+        let mut key_bytes_freed = 0u64;
         let mut keys_evicted = Vec::new();
 
         while bytes_freed < bytes as u64 {
@@ -304,6 +329,14 @@ impl State for MemoryState {
             }
 
             let (keys, rows) = evicted?;
+            if let Some(spill) = &mut self.spill {
+                if self.state[state_index].partial()
+                    && self.state[state_index].index_type() == IndexType::HashMap
+                {
+                    let spilled_rows = rows.iter().map(|r| Vec::clone(&**r)).collect();
+                    spill.spill(state_index, keys.clone(), spilled_rows);
+                }
+            }
             for row in &rows {
                 for (key, weak_index) in self.weak_indices.iter_mut() {
                     weak_index.remove(key, row, None);
@@ -315,7 +348,9 @@ impl State for MemoryState {
                     bytes_freed += row.deep_size_of();
                 }
             }
-            bytes_freed += base_row_bytes(&keys);
+            let key_bytes = base_row_bytes(&keys);
+            bytes_freed += key_bytes;
+            key_bytes_freed += key_bytes;
             keys_evicted.push(keys);
         }
 
@@ -324,6 +359,7 @@ impl State for MemoryState {
         }
 
         self.mem_size = self.mem_size.saturating_sub(bytes_freed);
+        self.key_bytes = self.key_bytes.saturating_sub(key_bytes_freed);
         return Some(StateEvicted {
             index: self.state[state_index].index(),
             keys_evicted,
@@ -336,6 +372,18 @@ impl State for MemoryState {
         // this can happen if an upstream domain issues an eviction for a replay path that we have
         // been told about, but that has not yet been finalized.
         self.by_tag.get(&tag).cloned().map(move |state_index| {
+            // These keys are being evicted because they were evicted upstream of us, which means
+            // writes to them will now be dropped before they reach us - so anything we spilled
+            // for them might go stale
+            if let Some(spill) = &mut self.spill {
+                for key in keys {
+                    match key {
+                        KeyComparison::Equal(key) => spill.invalidate(state_index, key.as_slice()),
+                        KeyComparison::Range(_) => spill.invalidate_index(state_index),
+                    }
+                }
+            }
+
             let rows = self.state[state_index].evict_keys(keys);
             let mut bytes = 0;
 
@@ -354,6 +402,7 @@ impl State for MemoryState {
             let key_bytes = keys.iter().map(base_row_bytes_from_comparison).sum::<u64>();
 
             self.mem_size = self.mem_size.saturating_sub(bytes + key_bytes);
+            self.key_bytes = self.key_bytes.saturating_sub(key_bytes);
             (self.state[state_index].index(), bytes)
         })
     }
@@ -362,7 +411,52 @@ impl State for MemoryState {
         for state in &mut self.state {
             state.clear();
         }
+        if let Some(spill) = &mut self.spill {
+            spill.clear();
+        }
         self.mem_size = 0;
+        self.key_bytes = 0;
+    }
+
+    fn fill_from_spill(&mut self, columns: &[usize], key: &KeyComparison) -> bool {
+        let spill = match &mut self.spill {
+            Some(spill) if !spill.is_empty() => spill,
+            _ => return false,
+        };
+        let equal = match key {
+            KeyComparison::Equal(equal) => equal,
+            KeyComparison::Range(_) => return false,
+        };
+        let index = match self.state_for(columns, IndexType::HashMap) {
+            Some(index) if self.state[index].partial() => index,
+            _ => return false,
+        };
+        let rows = match spill.take(index, equal.as_slice()) {
+            Some(rows) => rows,
+            None => return false,
+        };
+
+        trace!(
+            ?columns,
+            ?key,
+            rows = rows.len(),
+            "filling hole from spilled state"
+        );
+        let key_bytes = base_row_bytes(equal.as_slice());
+        self.mem_size += key_bytes;
+        self.key_bytes += key_bytes;
+        self.state[index].mark_filled(key.clone());
+        for row in rows {
+            let row = Row::from(row);
+            self.mem_size += row.deep_size_of();
+            for (key, weak_index) in self.weak_indices.iter_mut() {
+                // SAFETY: row remains inside the same state
+                weak_index.insert(key, unsafe { row.clone() }, false);
+            }
+            self.state[index].insert_row(row);
+        }
+
+        true
     }
 
     fn replication_offset(&self) -> Option<&ReplicationOffset> {
@@ -392,6 +486,43 @@ impl State for MemoryState {
 }
 
 impl MemoryState {
+    /// Construct a new, empty [`MemoryState`] which spills keys evicted from its partial indices
+    /// to disk according to the given `params`. If the on-disk cache can't be created, falls back
+    /// to discarding evicted keys.
+    pub fn with_spill(params: &SpillParameters) -> Self {
+        let spill = match SpillCache::new(params) {
+            Ok(spill) => Some(spill),
+            Err(error) => {
+                warn!(%error, "Could not create spill cache for partial state");
+                None
+            }
+        };
+
+        Self {
+            spill,
+            ..Default::default()
+        }
+    }
+
+    /// If this state has spilled any keys to disk, discard anything spilled for the keys of `row`
+    /// in any partial index, since we might be about to drop a write to those keys
+    fn invalidate_spilled(&mut self, row: &[DataType]) {
+        let spill = match &mut self.spill {
+            Some(spill) if !spill.is_empty() => spill,
+            _ => return,
+        };
+        for (i, state) in self.state.iter().enumerate() {
+            if state.partial() && state.index_type() == IndexType::HashMap {
+                let key = state
+                    .columns()
+                    .iter()
+                    .map(|&c| row[c].clone())
+                    .collect::<Vec<_>>();
+                spill.invalidate(i, &key);
+            }
+        }
+    }
+
     /// Returns the index in `self.state` of the index keyed on `cols` and with the given
     /// `index_type`, or None if no such index exists.
     fn state_for(&self, cols: &[usize], index_type: IndexType) -> Option<usize> {
@@ -417,6 +548,7 @@ impl MemoryState {
             // SAFETY: row remains inside the same state
             self.state[i].insert_row(unsafe { r.clone() })
         } else {
+            self.invalidate_spilled(&r);
             let mut hit_any = false;
             for i in 0..self.state.len() {
                 // SAFETY: row remains inside the same state
@@ -439,6 +571,7 @@ impl MemoryState {
     }
 
     fn remove(&mut self, r: &[DataType]) -> bool {
+        self.invalidate_spilled(r);
         let mut hit = false;
         for s in &mut self.state {
             if let Some(row) = s.remove_row(r, &mut hit) {
@@ -768,6 +901,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn memory_usage() {
+        let mut state = MemoryState::default();
+        state.add_key(Index::hash_map(vec![0]), Some(vec![Tag::new(0)]));
+        assert_eq!(state.memory_usage().key_bytes, 0);
+        assert_eq!(state.memory_usage().row_bytes, 0);
+
+        let mut records: Records = vec![
+            (vec![1.into(), "A".into()], true),
+            (vec![1.into(), "B".into()], true),
+        ]
+        .into();
+        state.mark_filled(KeyComparison::Equal(vec1![1.into()]), Tag::new(0));
+        state.process_records(&mut records, Some(Tag::new(0)), None);
+
+        let usage = state.memory_usage();
+        assert!(usage.key_bytes > 0);
+        assert!(usage.row_bytes > 0);
+        assert!(usage.overhead_bytes > 0);
+        assert_eq!(usage.key_bytes + usage.row_bytes, state.deep_size_of());
+
+        state.evict_keys(Tag::new(0), &[KeyComparison::Equal(vec1![1.into()])]);
+        let usage = state.memory_usage();
+        assert_eq!(usage.key_bytes, 0);
+        assert_eq!(usage.row_bytes, 0);
+    }
+
     mod weak_indices {
         use super::*;
 
@@ -852,4 +1012,69 @@ mod tests {
             );
         }
     }
+
+    mod spill {
+        use super::*;
+
+        fn setup() -> MemoryState {
+            let mut state = MemoryState::with_spill(&SpillParameters {
+                dir: None,
+                max_bytes: 1024 * 1024,
+            });
+
+            state.add_key(Index::hash_map(vec![0]), Some(vec![Tag::new(0)]));
+            let mut records: Records = vec![
+                (vec![1.into(), "A".into()], true),
+                (vec![1.into(), "B".into()], true),
+            ]
+            .into();
+            state.mark_filled(KeyComparison::Equal(vec1![1.into()]), Tag::new(0));
+            state.process_records(&mut records, Some(Tag::new(0)), None);
+
+            let evicted = state.evict_bytes(usize::MAX).unwrap();
+            assert_eq!(evicted.keys_evicted, vec![vec![DataType::from(1)]]);
+            assert!(state.lookup(&[0], &KeyType::Single(&1.into())).is_missing());
+
+            state
+        }
+
+        #[test]
+        fn evict_fill_from_spill() {
+            let mut state = setup();
+            assert!(state.fill_from_spill(&[0], &KeyComparison::Equal(vec1![1.into()])));
+
+            match state.lookup(&[0], &KeyType::Single(&1.into())) {
+                LookupResult::Some(rows) => {
+                    let mut rows = rows.into_iter().collect::<Vec<_>>();
+                    rows.sort();
+                    assert_eq!(
+                        rows,
+                        vec![vec![1.into(), "A".into()], vec![1.into(), "B".into()]]
+                    );
+                }
+                LookupResult::Missing => panic!("expected key to be filled from spill"),
+            }
+
+            // Spilled rows can only be used once
+            assert!(!state.fill_from_spill(&[0], &KeyComparison::Equal(vec1![1.into()])));
+        }
+
+        #[test]
+        fn dropped_write_invalidates_spill() {
+            let mut state = setup();
+            let mut records: Records = vec![(vec![1.into(), "C".into()], true)].into();
+            state.process_records(&mut records, None, None);
+            // The write hit a hole, so it was dropped...
+            assert!(records.is_empty());
+            // ...which means the spilled rows are no longer up-to-date
+            assert!(!state.fill_from_spill(&[0], &KeyComparison::Equal(vec1![1.into()])));
+        }
+
+        #[test]
+        fn upstream_eviction_invalidates_spill() {
+            let mut state = setup();
+            state.evict_keys(Tag::new(0), &[KeyComparison::Equal(vec1![1.into()])]);
+            assert!(!state.fill_from_spill(&[0], &KeyComparison::Equal(vec1![1.into()])));
+        }
+    }
 }
@@ -72,11 +72,13 @@ use std::time::{Duration, Instant};
 
 use bincode::Options;
 use common::{IndexType, KeyType, RangeKey, Record, Records, SizeOf, Tag};
+use readyset::debug::stats::MemoryUsage;
 use readyset::internal::Index;
 use readyset::replication::ReplicationOffset;
+use readyset::storage::{CompressionOptions, CompressionType};
 use readyset::KeyComparison;
 use readyset_data::DataType;
-use readyset_errors::{ReadySetError, ReadySetResult};
+use readyset_errors::{internal_err, ReadySetError, ReadySetResult};
 use rocksdb::{self, PlainTableFactoryOptions, SliceTransform, WriteBatch};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -84,6 +86,7 @@ use tempfile::{tempdir, TempDir};
 use test_strategy::Arbitrary;
 use thiserror::Error;
 use tracing::{error, info, warn};
+use tuple::TupleElements;
 
 use crate::{LookupResult, RangeLookupResult, RecordResult, State};
 
@@ -108,6 +111,24 @@ const PK_CF: &str = "0";
 // Maximum rows per WriteBatch when building new indices for existing rows.
 const INDEX_BATCH_SIZE: usize = 10_000;
 
+// Default value for `PersistenceParameters::max_bytes_for_level_base`.
+const DEFAULT_MAX_BYTES_FOR_LEVEL_BASE: u64 = 1024 * 1024 * 1024;
+
+// Default value for `PersistenceParameters::target_file_size_base`.
+const DEFAULT_TARGET_FILE_SIZE_BASE: u64 = 256 * 1024 * 1024;
+
+// Refill period for the compaction rate limiter, in microseconds (RocksDB's recommended default).
+const RATE_LIMITER_REFILL_PERIOD_US: i64 = 100_000;
+
+// Fairness between high and low priority requests for the compaction rate limiter (RocksDB's
+// recommended default).
+const RATE_LIMITER_FAIRNESS: i32 = 10;
+
+// Compression options other than the dictionary size, which we always leave at RocksDB's defaults.
+const COMPRESSION_WINDOW_BITS: i32 = -14;
+const COMPRESSION_LEVEL: i32 = 32767;
+const COMPRESSION_STRATEGY: i32 = 0;
+
 /// Load the metadata from the database, stored in the `DEFAULT_CF` column family under the
 /// `META_KEY`
 fn get_meta(db: &rocksdb::DB) -> PersistentMeta<'static> {
@@ -226,6 +247,35 @@ pub struct PersistenceParameters {
     /// An optional path to a directory where to store the DB files, if None will be stored in the
     /// current working directory
     pub db_dir: Option<PathBuf>,
+    /// Target total size, in bytes, of level 1 of the LSM tree for each column family. Deeper
+    /// levels are sized as multiples of this value.
+    #[serde(default = "default_max_bytes_for_level_base")]
+    pub max_bytes_for_level_base: u64,
+    /// Target size, in bytes, of the individual files written by compactions.
+    #[serde(default = "default_target_file_size_base")]
+    pub target_file_size_base: u64,
+    /// If set, limits the rate (in bytes per second) at which RocksDB writes to disk during
+    /// flushes and compactions, shared across all column families of a table.
+    #[serde(default)]
+    pub compaction_rate_limit: Option<i64>,
+    /// Compression options used for newly created tables. Once a table has been created, its
+    /// compression options are persisted alongside its data, and can only be changed with
+    /// [`PersistentState::set_compression`].
+    #[serde(default)]
+    pub compression: CompressionOptions,
+    /// If set, the keys present in every partially materialized reader are written to disk at
+    /// this interval, and re-requested when the reader is recreated after a restart so that it
+    /// comes back warm. Only takes effect with [`DurabilityMode::Permanent`].
+    #[serde(default)]
+    pub reader_key_snapshot_interval: Option<Duration>,
+}
+
+fn default_max_bytes_for_level_base() -> u64 {
+    DEFAULT_MAX_BYTES_FOR_LEVEL_BASE
+}
+
+fn default_target_file_size_base() -> u64 {
+    DEFAULT_TARGET_FILE_SIZE_BASE
 }
 
 impl Default for PersistenceParameters {
@@ -235,6 +285,11 @@ impl Default for PersistenceParameters {
             db_filename_prefix: String::from("soup"),
             persistence_threads: 1,
             db_dir: None,
+            max_bytes_for_level_base: default_max_bytes_for_level_base(),
+            target_file_size_base: default_target_file_size_base(),
+            compaction_rate_limit: None,
+            compression: CompressionOptions::default(),
+            reader_key_snapshot_interval: None,
         }
     }
 }
@@ -267,8 +322,26 @@ impl PersistenceParameters {
             db_filename_prefix,
             persistence_threads,
             db_dir,
+            ..Default::default()
         }
     }
+
+    /// Returns the path of the file that the keys of the reader with the given name and shard are
+    /// snapshotted to, or `None` if reader keys shouldn't be snapshotted
+    pub fn reader_keys_path(&self, reader: &str, shard: usize) -> Option<PathBuf> {
+        if self.mode != DurabilityMode::Permanent || self.reader_key_snapshot_interval.is_none() {
+            return None;
+        }
+        let mut path = self.db_dir.clone().unwrap_or_else(|| ".".into());
+        path.push(format!(
+            "{}-{}-{}",
+            self.db_filename_prefix.replace('-', "_"),
+            reader,
+            shard
+        ));
+        path.set_extension("keys");
+        Some(path)
+    }
 }
 
 /// Data structure used to persist metadata about the [`PersistentState`] to rocksdb
@@ -281,6 +354,12 @@ struct PersistentMeta<'a> {
     /// The latest replication offset that has been written to the base table backed by this
     /// [`PersistentState`]. Corresponds to [`PersistentState::replication_offset`]
     replication_offset: Option<Cow<'a, ReplicationOffset>>,
+
+    /// The compression options the data for this table is stored with. Corresponds to
+    /// [`PersistentState::compression`]. If not set, the compression options from the
+    /// [`PersistenceParameters`] are used.
+    #[serde(default)]
+    compression: Option<CompressionOptions>,
 }
 
 #[derive(Clone)]
@@ -312,6 +391,8 @@ pub struct PersistentState {
     /// When set to true [`SnapshotMode::SnapshotModeEnabled`] compaction will be disabled and
     /// writes will bypass WAL and fsync
     snapshot_mode: SnapshotMode,
+    /// The compression options the data for this table is stored with
+    compression: CompressionOptions,
 }
 
 impl<'a> PersistentMeta<'a> {
@@ -380,13 +461,7 @@ impl State for PersistentState {
                 // options.sync=true,    will it persist the previous write too?
                 // A: No. After the program crashes, writes with option.disableWAL=true will be
                 // lost, if they are not flushed    to SST files.
-                for index in &self.indices {
-                    self.db
-                        .flush_cf(self.db.cf_handle(&index.column_family).unwrap())
-                        .expect("Flush to disk failed");
-                }
-
-                self.db.flush().expect("Flush to disk failed");
+                self.flush();
             }
             opts.set_sync(true);
         }
@@ -545,6 +620,26 @@ impl State for PersistentState {
             .unwrap() as usize
     }
 
+    fn memory_usage(&self) -> MemoryUsage {
+        // Everything but the rows which haven't been flushed out of the memtables yet lives on
+        // disk
+        let memtable_bytes = self
+            .indices
+            .iter()
+            .filter_map(|idx| self.db.cf_handle(&idx.column_family))
+            .filter_map(|cf| {
+                self.db
+                    .property_int_value_cf(cf, "rocksdb.cur-size-all-mem-tables")
+                    .ok()
+                    .flatten()
+            })
+            .sum();
+        MemoryUsage {
+            row_bytes: memtable_bytes,
+            ..Default::default()
+        }
+    }
+
     fn is_useful(&self) -> bool {
         !self.indices.is_empty()
     }
@@ -583,6 +678,11 @@ impl State for PersistentState {
         unreachable!("can't clear PersistentState")
     }
 
+    fn fill_from_spill(&mut self, _columns: &[usize], _key: &KeyComparison) -> bool {
+        // PersistentState is never partial, so never has holes to fill
+        false
+    }
+
     fn add_weak_key(&mut self, index: Index) {
         self.add_key(index, None);
     }
@@ -615,6 +715,67 @@ impl State for PersistentState {
     }
 }
 
+/// A key in an index, which serializes the same way as the value, tuple, or sequence of values it
+/// was built from, except that each value is replaced by its
+/// [collation key](DataType::collation_key), so that strings which compare equal under the
+/// collation of their column are stored and looked up under the same key
+struct CollationKey<'a> {
+    values: Vec<Cow<'a, DataType>>,
+    shape: KeyShape,
+}
+
+#[derive(Clone, Copy)]
+enum KeyShape {
+    Single,
+    Tuple,
+    Seq,
+}
+
+impl<'a> CollationKey<'a> {
+    fn new<I>(values: I, shape: KeyShape) -> Self
+    where
+        I: IntoIterator<Item = &'a DataType>,
+    {
+        Self {
+            values: values.into_iter().map(DataType::collation_key).collect(),
+            shape,
+        }
+    }
+
+    fn single(value: &'a DataType) -> Self {
+        Self::new(std::iter::once(value), KeyShape::Single)
+    }
+
+    fn tuple<I: IntoIterator<Item = &'a DataType>>(values: I) -> Self {
+        Self::new(values, KeyShape::Tuple)
+    }
+
+    fn seq<I: IntoIterator<Item = &'a DataType>>(values: I) -> Self {
+        Self::new(values, KeyShape::Seq)
+    }
+}
+
+impl Serialize for CollationKey<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeTuple;
+
+        match self.shape {
+            KeyShape::Single => self.values[0].serialize(serializer),
+            KeyShape::Seq => serializer.collect_seq(&self.values),
+            KeyShape::Tuple => {
+                let mut tuple = serializer.serialize_tuple(self.values.len())?;
+                for value in &self.values {
+                    tuple.serialize_element(value)?;
+                }
+                tuple.end()
+            }
+        }
+    }
+}
+
 fn serialize_key<K: serde::Serialize, E: serde::Serialize>(k: K, extra: E) -> Vec<u8> {
     let size: u64 = bincode::options().serialized_size(&k).unwrap();
     bincode::options().serialize(&(size, k, extra)).unwrap()
@@ -633,7 +794,7 @@ fn deserialize_row<T: AsRef<[u8]>>(bytes: T) -> Vec<DataType> {
 /// index type.
 fn base_options(params: &PersistenceParameters) -> rocksdb::Options {
     let mut opts = rocksdb::Options::default();
-    opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+    apply_compression(&mut opts, &params.compression);
     opts.create_if_missing(true);
     opts.create_missing_column_families(true);
     opts.set_allow_concurrent_memtable_write(false);
@@ -648,8 +809,16 @@ fn base_options(params: &PersistenceParameters) -> rocksdb::Options {
     }
 
     // Increase a few default limits:
-    opts.set_max_bytes_for_level_base(1024 * 1024 * 1024);
-    opts.set_target_file_size_base(256 * 1024 * 1024);
+    opts.set_max_bytes_for_level_base(params.max_bytes_for_level_base);
+    opts.set_target_file_size_base(params.target_file_size_base);
+
+    if let Some(rate_limit) = params.compaction_rate_limit {
+        opts.set_ratelimiter(
+            rate_limit,
+            RATE_LIMITER_REFILL_PERIOD_US,
+            RATE_LIMITER_FAIRNESS,
+        );
+    }
 
     // Keep up to 4 parallel memtables:
     opts.set_max_write_buffer_number(4);
@@ -657,6 +826,43 @@ fn base_options(params: &PersistenceParameters) -> rocksdb::Options {
     opts
 }
 
+/// Configure the given set of rocksdb options to compress data according to `compression`
+fn apply_compression(opts: &mut rocksdb::Options, compression: &CompressionOptions) {
+    opts.set_compression_type(match compression.compression_type {
+        CompressionType::None => rocksdb::DBCompressionType::None,
+        CompressionType::Lz4 => rocksdb::DBCompressionType::Lz4,
+        CompressionType::Zstd => rocksdb::DBCompressionType::Zstd,
+    });
+    opts.set_compression_options(
+        COMPRESSION_WINDOW_BITS,
+        COMPRESSION_LEVEL,
+        COMPRESSION_STRATEGY,
+        compression.max_dict_bytes as i32,
+    );
+}
+
+/// Build the string-valued column family options corresponding to `compression`, for changing the
+/// compression of an already-open column family with [`rocksdb::DB::set_options_cf`]
+fn compression_cf_options(compression: &CompressionOptions) -> [(&'static str, String); 2] {
+    let compression_type = match compression.compression_type {
+        CompressionType::None => "kNoCompression",
+        CompressionType::Lz4 => "kLZ4Compression",
+        CompressionType::Zstd => "kZSTD",
+    };
+    let compression_opts = format!(
+        "{}:{}:{}:{}",
+        COMPRESSION_WINDOW_BITS,
+        COMPRESSION_LEVEL,
+        COMPRESSION_STRATEGY,
+        compression.max_dict_bytes
+    );
+
+    [
+        ("compression", compression_type.to_owned()),
+        ("compression_opts", compression_opts),
+    ]
+}
+
 /// Representation of the set of parameters for an index in persistent state
 ///
 /// This type is constructed either via an [`Index`] (with the `From<&Index>`) impl, directly from
@@ -776,7 +982,7 @@ impl PersistentState {
             }
         };
 
-        let default_options = base_options(params);
+        let mut default_options = base_options(params);
         // We use a column family for each index, and one for metadata.
         // When opening the DB the exact same column families needs to be used,
         // so we'll have to retrieve the existing ones first:
@@ -785,9 +991,18 @@ impl PersistentState {
             Err(_err) => vec![DEFAULT_CF.to_string()],
         };
 
-        let cf_index_params = DB::open_for_read_only(&default_options, &full_path, false)
+        let existing_meta = DB::open_for_read_only(&default_options, &full_path, false)
             .ok()
-            .map(|db| get_meta(&db))
+            .map(|db| get_meta(&db));
+
+        // Tables that already exist keep the compression options they were created with
+        let compression = existing_meta
+            .as_ref()
+            .and_then(|meta| meta.compression)
+            .unwrap_or(params.compression);
+        apply_compression(&mut default_options, &compression);
+
+        let cf_index_params = existing_meta
             .into_iter()
             .flat_map(|meta: PersistentMeta| {
                 meta.indices
@@ -871,6 +1086,7 @@ impl PersistentState {
             db,
             _tmpdir: tmpdir,
             snapshot_mode: SnapshotMode::SnapshotModeDisabled,
+            compression,
         };
 
         if let Some(pk) = state.unique_keys.first().cloned() {
@@ -1064,6 +1280,7 @@ impl PersistentState {
             indices: self.indices.iter().map(|pi| pi.index.clone()).collect(),
             epoch: self.epoch,
             replication_offset: self.replication_offset().map(Cow::Borrowed),
+            compression: Some(self.compression),
         }
     }
 
@@ -1124,8 +1341,82 @@ impl PersistentState {
     }
 
     fn disable_snapshot_mode(&mut self) {
+        self.compact();
+    }
+
+    /// Returns the compression options the data for this table is stored with
+    pub fn compression(&self) -> &CompressionOptions {
+        &self.compression
+    }
+
+    /// Change the compression options for the data in this table.
+    ///
+    /// Unless the table is currently snapshotting (in which case the data will be compacted once
+    /// snapshotting finishes anyway), this triggers a manual compaction to rewrite all existing
+    /// data with the new compression options, which blocks until the compaction has finished.
+    ///
+    /// If the new options can't be applied to every column family, the ones they were applied to
+    /// are changed back to the previous options and an error is returned, leaving the table's
+    /// compression options unchanged.
+    pub fn set_compression(&mut self, compression: CompressionOptions) -> ReadySetResult<()> {
+        let set_options = |compression: &CompressionOptions, index: &PersistentIndex| {
+            let cf_options = compression_cf_options(compression);
+            let cf_options = cf_options
+                .iter()
+                .map(|(k, v)| (*k, v.as_str()))
+                .collect::<Vec<_>>();
+            let cf = self.db.cf_handle(&index.column_family).ok_or_else(|| {
+                internal_err(format!("Column family {} not found", index.column_family))
+            })?;
+            self.db.set_options_cf(cf, &cf_options).map_err(|err| {
+                internal_err(format!(
+                    "Error setting compression options for table {}: {}",
+                    self.name, err
+                ))
+            })
+        };
+
+        for (i, index) in self.indices.iter().enumerate() {
+            if let Err(err) = set_options(&compression, index) {
+                for index in &self.indices[..i] {
+                    if let Err(error) = set_options(&self.compression, index) {
+                        error!(%error, "Error restoring previous compression options");
+                    }
+                }
+                return Err(err);
+            }
+        }
+
+        self.compression = compression;
+        self.persist_meta();
+        apply_compression(&mut self.default_options, &compression);
+        info!(table = %self.name, ?compression, "Changed table compression");
+
+        if !self.is_snapshotting() {
+            self.compact();
+        }
+        Ok(())
+    }
+
+    /// Flush the memtables of every column family in this table to SST files, so that all writes
+    /// made so far survive a crash even if they were made without the WAL in snapshot mode.
+    pub fn flush(&self) {
+        for index in &self.indices {
+            self.db
+                .flush_cf(self.db.cf_handle(&index.column_family).unwrap())
+                .expect("Flush to disk failed");
+        }
+
+        self.db.flush().expect("Flush to disk failed");
+    }
+
+    /// Perform a manual compaction of every column family in this table, blocking until all
+    /// compactions have finished.
+    ///
+    /// This is mostly useful after bulk loads, to reclaim space and restore read performance
+    /// without waiting for RocksDB's automatic compactions to catch up.
+    pub fn compact(&self) {
         for index in &self.indices {
-            // Perform a manual compaction for each column family
             self.compact_cf(index);
         }
     }
@@ -1254,6 +1545,10 @@ impl PersistentState {
         let mut opts = rocksdb::CompactOptions::default();
         // We don't want to block other compactions happening in parallel
         opts.set_exclusive_manual_compaction(false);
+        // RocksDB skips rewriting the files in the bottommost level by default, which is where most
+        // of the data lives, so force it to rewrite them so they pick up any changed options, such
+        // as the compression type
+        opts.set_bottommost_level_compaction(rocksdb::BottommostLevelCompaction::Force);
         db.compact_range_cf_opt(cf, Option::<&[u8]>::None, Option::<&[u8]>::None, &opts);
 
         info!(table = %self.name, cf = %index.column_family, "Compaction finished");
@@ -1279,16 +1574,14 @@ impl PersistentState {
     // unique.
     //
     // Self::serialize_raw_key is responsible for serializing the underlying KeyType tuple directly
-    // (without the enum variant), plus any extra information as described above.
+    // (without the enum variant), plus any extra information as described above. Values in the key
+    // are serialized as their collation keys (see `CollationKey`).
     fn serialize_raw_key<S: serde::Serialize>(key: &KeyType, extra: S) -> Vec<u8> {
+        let values = (0..key.len()).filter_map(|i| key.get(i));
         match key {
-            KeyType::Single(k) => serialize_key(k, extra),
-            KeyType::Double(k) => serialize_key(k, extra),
-            KeyType::Tri(k) => serialize_key(k, extra),
-            KeyType::Quad(k) => serialize_key(k, extra),
-            KeyType::Quin(k) => serialize_key(k, extra),
-            KeyType::Sex(k) => serialize_key(k, extra),
-            KeyType::Multi(k) => serialize_key(k, extra),
+            KeyType::Single(k) => serialize_key(CollationKey::single(k), extra),
+            KeyType::Multi(_) => serialize_key(CollationKey::seq(values), extra),
+            _ => serialize_key(CollationKey::tuple(values), extra),
         }
     }
 
@@ -1322,15 +1615,39 @@ impl PersistentState {
                 range.1.map(|k| serialize_key(k, &extra.1)),
             )
         }
+        macro_rules! tuple_range {
+            ($range: expr) => {{
+                let (lower, upper) = *$range;
+                do_serialize_range(
+                    (
+                        lower.map(|k| CollationKey::tuple(k.into_elements())),
+                        upper.map(|k| CollationKey::tuple(k.into_elements())),
+                    ),
+                    extra,
+                )
+            }};
+        }
         match key {
             RangeKey::Unbounded => (Unbounded, Unbounded),
-            RangeKey::Single(range) => do_serialize_range(*range, extra),
-            RangeKey::Double(range) => do_serialize_range(*range, extra),
-            RangeKey::Tri(range) => do_serialize_range(*range, extra),
-            RangeKey::Quad(range) => do_serialize_range(*range, extra),
-            RangeKey::Quin(range) => do_serialize_range(*range, extra),
-            RangeKey::Sex(range) => do_serialize_range(*range, extra),
-            RangeKey::Multi(range) => do_serialize_range(*range, extra),
+            RangeKey::Single((lower, upper)) => do_serialize_range(
+                (
+                    lower.map(CollationKey::single),
+                    upper.map(CollationKey::single),
+                ),
+                extra,
+            ),
+            RangeKey::Double(range) => tuple_range!(range),
+            RangeKey::Tri(range) => tuple_range!(range),
+            RangeKey::Quad(range) => tuple_range!(range),
+            RangeKey::Quin(range) => tuple_range!(range),
+            RangeKey::Sex(range) => tuple_range!(range),
+            RangeKey::Multi((lower, upper)) => do_serialize_range(
+                (
+                    lower.map(|k| CollationKey::seq(k.iter())),
+                    upper.map(|k| CollationKey::seq(k.iter())),
+                ),
+                extra,
+            ),
         }
     }
 
@@ -1695,6 +2012,26 @@ mod tests {
         state
     }
 
+    #[test]
+    fn reader_keys_path() {
+        let mut params = PersistenceParameters::new(
+            DurabilityMode::Permanent,
+            Some("my-deployment".into()),
+            1,
+            Some("/data".into()),
+        );
+        assert_eq!(params.reader_keys_path("q1", 0), None);
+
+        params.reader_key_snapshot_interval = Some(Duration::from_secs(60));
+        assert_eq!(
+            params.reader_keys_path("q1", 2),
+            Some(PathBuf::from("/data/my_deployment-q1-2.keys"))
+        );
+
+        params.mode = DurabilityMode::MemoryOnly;
+        assert_eq!(params.reader_keys_path("q1", 2), None);
+    }
+
     #[test]
     fn persistent_state_is_partial() {
         let state = setup_persistent("persistent_state_is_partial", None);
@@ -2017,6 +2354,113 @@ mod tests {
         }
     }
 
+    #[test]
+    fn persistent_state_compact() {
+        let params = PersistenceParameters {
+            compaction_rate_limit: Some(64 * 1024 * 1024),
+            ..Default::default()
+        };
+        let mut state = PersistentState::new(String::from("compact"), Some(&[0]), &params);
+        let rows: Vec<Vec<DataType>> = (0..100)
+            .map(|i| vec![i.into(), format!("row {}", i).into()])
+            .collect();
+        state.process_records(&mut rows.clone().into(), None, None);
+
+        state.compact();
+
+        match state.lookup(&[0], &KeyType::Single(&42.into())) {
+            LookupResult::Some(RecordResult::Owned(found)) => {
+                assert_eq!(found, vec![rows[42].clone()]);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the highest compression ratio of the SST files at any level of the column family
+    /// for the given index of `state`
+    fn compression_ratio(state: &PersistentState, index: usize) -> f64 {
+        let cf = state
+            .db
+            .cf_handle(&state.indices[index].column_family)
+            .unwrap();
+        (0..7)
+            .filter_map(|level| {
+                state
+                    .db
+                    .property_value_cf(cf, &format!("rocksdb.compression-ratio-at-level{}", level))
+                    .unwrap()
+            })
+            .map(|ratio| ratio.parse::<f64>().unwrap())
+            .fold(-1.0, f64::max)
+    }
+
+    #[test]
+    fn persistent_state_set_compression_rewrites_data() {
+        let params = PersistenceParameters {
+            compression: CompressionOptions {
+                compression_type: CompressionType::None,
+                max_dict_bytes: 0,
+            },
+            ..Default::default()
+        };
+        let mut state = PersistentState::new(String::from("recompress"), Some(&[0]), &params);
+        let rows: Vec<Vec<DataType>> = (0..1000)
+            .map(|i| vec![i.into(), "a".repeat(500).into()])
+            .collect();
+        state.process_records(&mut rows.into(), None, None);
+        state.compact();
+        let uncompressed = compression_ratio(&state, 0);
+        assert!(uncompressed > 0.0);
+
+        state
+            .set_compression(CompressionOptions {
+                compression_type: CompressionType::Zstd,
+                max_dict_bytes: 0,
+            })
+            .unwrap();
+
+        // All of the existing data, including the files in the bottommost level, should have been
+        // rewritten with the new compression type
+        let compressed = compression_ratio(&state, 0);
+        assert!(
+            compressed > uncompressed * 10.0,
+            "compression ratio went from {} to {}",
+            uncompressed,
+            compressed
+        );
+    }
+
+    #[test]
+    fn persistent_state_set_compression() {
+        let (_dir, name) = get_tmp_path();
+        let mut params = PersistenceParameters::default();
+        params.mode = DurabilityMode::Permanent;
+        let zstd = CompressionOptions {
+            compression_type: CompressionType::Zstd,
+            max_dict_bytes: 16 * 1024,
+        };
+        let rows: Vec<Vec<DataType>> = (0..100)
+            .map(|i| vec![i.into(), format!("row {}", i).into()])
+            .collect();
+        {
+            let mut state = PersistentState::new(name.clone(), Some(&[0]), &params);
+            assert_eq!(state.compression(), &params.compression);
+            state.process_records(&mut rows.clone().into(), None, None);
+            state.set_compression(zstd).unwrap();
+        }
+
+        // The compression options should be kept when the table is reopened, regardless of the
+        // default in the params
+        let state = PersistentState::new(name, Some(&[0]), &params);
+        assert_eq!(state.compression(), &zstd);
+        match state.lookup(&[0], &KeyType::Single(&42.into())) {
+            LookupResult::Some(RecordResult::Owned(found)) => {
+                assert_eq!(found, vec![rows[42].clone()]);
+            }
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     fn persistent_state_recover_unique_key() {
         let (_dir, name) = get_tmp_path();
@@ -0,0 +1,290 @@
+//! An optional on-disk tier for partial state evicted from memory.
+//!
+//! When a [`MemoryState`](crate::MemoryState) is configured with [`SpillParameters`], keys that
+//! are evicted from its partial indices due to memory pressure are written (along with all of
+//! their rows) to a local RocksDB instance instead of being discarded. If a later lookup misses
+//! on one of those keys, the hole can be filled by reading the rows back from disk, rather than
+//! by performing an upquery through the graph.
+//!
+//! Spilled entries are only valid as long as no writes for their key have been dropped by the
+//! state since the key was evicted, so the owning state is responsible for [invalidating]
+//! entries whenever that might have happened.
+//!
+//! [invalidating]: SpillCache::invalidate
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+
+use bincode::Options;
+use readyset_data::DataType;
+use serde::{Deserialize, Serialize};
+use tempfile::{tempdir, tempdir_in, TempDir};
+use tracing::warn;
+
+/// Minimum length of the queue of spilled keys before we bother removing stale entries from it
+const MIN_ORDER_LEN_TO_COMPACT: usize = 1024;
+
+/// Parameters for the on-disk tier that evicted partial state is spilled to
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpillParameters {
+    /// Directory to create spill files in. If not set, the system temporary directory is used.
+    pub dir: Option<PathBuf>,
+    /// Maximum number of bytes of evicted rows to keep on disk for each materialized node. Once
+    /// this is exceeded, the least recently spilled keys are discarded.
+    pub max_bytes: u64,
+}
+
+/// The size and insertion order of a single spilled key
+struct SpillEntry {
+    seq: u64,
+    bytes: u64,
+}
+
+/// A bounded, on-disk cache of rows evicted from the partial indices of a single state
+pub(crate) struct SpillCache {
+    db: rocksdb::DB,
+    /// Map from the position of an index in the owning state, to the keys spilled from that index
+    entries: HashMap<usize, HashMap<Vec<DataType>, SpillEntry>>,
+    /// Keys in the order they were spilled, oldest first. Keys that have since been removed or
+    /// re-spilled are skipped when discarding entries, by comparing against
+    /// [`SpillEntry::seq`].
+    order: VecDeque<(u64, usize, Vec<DataType>)>,
+    next_seq: u64,
+    bytes: u64,
+    max_bytes: u64,
+    _dir: TempDir,
+}
+
+fn serialize_key(index: usize, key: &[DataType]) -> Vec<u8> {
+    bincode::options().serialize(&(index, key)).unwrap()
+}
+
+impl SpillCache {
+    pub(crate) fn new(params: &SpillParameters) -> anyhow::Result<Self> {
+        let dir = match &params.dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)?;
+                tempdir_in(dir)?
+            }
+            None => tempdir()?,
+        };
+
+        let mut opts = rocksdb::Options::default();
+        opts.create_if_missing(true);
+        opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+        let db = rocksdb::DB::open(&opts, dir.path())?;
+
+        Ok(Self {
+            db,
+            entries: Default::default(),
+            order: Default::default(),
+            next_seq: 0,
+            bytes: 0,
+            max_bytes: params.max_bytes,
+            _dir: dir,
+        })
+    }
+
+    /// Returns true if there are no keys currently spilled to disk
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.values().all(HashMap::is_empty)
+    }
+
+    /// Write the given `rows` for `key` in the index at position `index` to disk, discarding the
+    /// oldest spilled keys if this would exceed the configured maximum size
+    pub(crate) fn spill(&mut self, index: usize, key: Vec<DataType>, rows: Vec<Vec<DataType>>) {
+        let value = bincode::options().serialize(&rows).unwrap();
+        let bytes = value.len() as u64;
+        if bytes > self.max_bytes {
+            return;
+        }
+
+        let mut write_opts = rocksdb::WriteOptions::default();
+        // This is only a cache, so there's no need to survive a crash
+        write_opts.disable_wal(true);
+        if let Err(error) = self
+            .db
+            .put_opt(serialize_key(index, &key), value, &write_opts)
+        {
+            warn!(%error, "Error spilling evicted state to disk");
+            return;
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.order.push_back((seq, index, key.clone()));
+        if let Some(old) = self
+            .entries
+            .entry(index)
+            .or_default()
+            .insert(key, SpillEntry { seq, bytes })
+        {
+            self.bytes = self.bytes.saturating_sub(old.bytes);
+        }
+        self.bytes += bytes;
+
+        while self.bytes > self.max_bytes {
+            let (seq, index, key) = match self.order.pop_front() {
+                Some(entry) => entry,
+                None => break,
+            };
+            if self.is_current(seq, index, &key) {
+                self.remove(index, &key);
+            }
+        }
+
+        // Keys that were taken or invalidated stay in `order` until they reach the front, so
+        // periodically drop them to keep it from growing without bound
+        let len = self.entries.values().map(HashMap::len).sum::<usize>();
+        if self.order.len() > 2 * len + MIN_ORDER_LEN_TO_COMPACT {
+            let mut order = std::mem::take(&mut self.order);
+            order.retain(|(seq, index, key)| self.is_current(*seq, *index, key));
+            self.order = order;
+        }
+    }
+
+    /// Returns true if the entry spilled with the given `seq` is still the current entry for
+    /// `key` in the index at position `index`
+    fn is_current(&self, seq: u64, index: usize, key: &[DataType]) -> bool {
+        self.entries
+            .get(&index)
+            .and_then(|keys| keys.get(key))
+            .map_or(false, |entry| entry.seq == seq)
+    }
+
+    /// Remove and return the rows spilled for `key` in the index at position `index`, if any
+    pub(crate) fn take(&mut self, index: usize, key: &[DataType]) -> Option<Vec<Vec<DataType>>> {
+        if !self.remove_entry(index, key) {
+            return None;
+        }
+
+        let db_key = serialize_key(index, key);
+        let value = match self.db.get_pinned(&db_key) {
+            Ok(Some(value)) => value,
+            Ok(None) => return None,
+            Err(error) => {
+                warn!(%error, "Error reading spilled state from disk");
+                return None;
+            }
+        };
+        let rows = bincode::options().deserialize(&value);
+        drop(value);
+        self.delete(&db_key);
+
+        match rows {
+            Ok(rows) => Some(rows),
+            Err(error) => {
+                warn!(%error, "Error deserializing spilled state");
+                None
+            }
+        }
+    }
+
+    /// Discard any rows spilled for `key` in the index at position `index`. This must be called
+    /// whenever a write to that key may have been dropped by the owning state.
+    pub(crate) fn invalidate(&mut self, index: usize, key: &[DataType]) {
+        self.remove(index, key);
+    }
+
+    /// Discard all rows spilled for the index at position `index`
+    pub(crate) fn invalidate_index(&mut self, index: usize) {
+        let keys = match self.entries.remove(&index) {
+            Some(keys) => keys,
+            None => return,
+        };
+        for (key, entry) in keys {
+            self.bytes = self.bytes.saturating_sub(entry.bytes);
+            self.delete(&serialize_key(index, &key));
+        }
+    }
+
+    /// Discard all spilled rows
+    pub(crate) fn clear(&mut self) {
+        let indices = self.entries.keys().copied().collect::<Vec<_>>();
+        for index in indices {
+            self.invalidate_index(index);
+        }
+        self.order.clear();
+    }
+
+    fn remove(&mut self, index: usize, key: &[DataType]) {
+        if self.remove_entry(index, key) {
+            self.delete(&serialize_key(index, key));
+        }
+    }
+
+    /// Remove the in-memory bookkeeping for the given key, returning whether it was spilled
+    fn remove_entry(&mut self, index: usize, key: &[DataType]) -> bool {
+        match self
+            .entries
+            .get_mut(&index)
+            .and_then(|keys| keys.remove(key))
+        {
+            Some(entry) => {
+                self.bytes = self.bytes.saturating_sub(entry.bytes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn delete(&self, db_key: &[u8]) {
+        let mut write_opts = rocksdb::WriteOptions::default();
+        write_opts.disable_wal(true);
+        if let Err(error) = self.db.delete_opt(db_key, &write_opts) {
+            warn!(%error, "Error removing spilled state from disk");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_bytes: u64) -> SpillCache {
+        SpillCache::new(&SpillParameters {
+            dir: None,
+            max_bytes,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn spill_and_take() {
+        let mut cache = cache(1024 * 1024);
+        let rows = vec![vec![1.into(), "a".into()], vec![1.into(), "b".into()]];
+        cache.spill(0, vec![1.into()], rows.clone());
+        assert!(!cache.is_empty());
+
+        assert_eq!(cache.take(1, &[1.into()]), None);
+        assert_eq!(cache.take(0, &[1.into()]), Some(rows));
+        assert_eq!(cache.take(0, &[1.into()]), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn invalidate() {
+        let mut cache = cache(1024 * 1024);
+        cache.spill(0, vec![1.into()], vec![vec![1.into()]]);
+        cache.spill(0, vec![2.into()], vec![vec![2.into()]]);
+        cache.invalidate(0, &[1.into()]);
+        assert_eq!(cache.take(0, &[1.into()]), None);
+        assert_eq!(cache.take(0, &[2.into()]), Some(vec![vec![2.into()]]));
+    }
+
+    #[test]
+    fn discards_oldest_over_capacity() {
+        let row_bytes = bincode::options()
+            .serialize(&vec![vec![DataType::from(1)]])
+            .unwrap()
+            .len() as u64;
+        let mut cache = cache(row_bytes * 2);
+        cache.spill(0, vec![1.into()], vec![vec![1.into()]]);
+        cache.spill(0, vec![2.into()], vec![vec![2.into()]]);
+        cache.spill(0, vec![3.into()], vec![vec![3.into()]]);
+
+        assert_eq!(cache.take(0, &[1.into()]), None);
+        assert_eq!(cache.take(0, &[2.into()]), Some(vec![vec![2.into()]]));
+        assert_eq!(cache.take(0, &[3.into()]), Some(vec![vec![3.into()]]));
+    }
+}
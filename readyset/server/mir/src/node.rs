@@ -87,6 +87,7 @@ impl MirNode {
         node: MirNodeRef,
         added_cols: Vec<&ColumnSpecification>,
         removed_cols: Vec<&ColumnSpecification>,
+        retyped_cols: Vec<&ColumnSpecification>,
     ) -> MirNodeRef {
         let over_node = node.borrow();
         match &over_node.inner {
@@ -100,6 +101,15 @@ impl MirNode {
                     .iter()
                     .cloned()
                     .filter(|&(ref cs, _)| !removed_cols.contains(&cs))
+                    .map(|(cs, id)| {
+                        match retyped_cols
+                            .iter()
+                            .find(|rc| rc.column.name == cs.column.name)
+                        {
+                            Some(rc) => ((*rc).clone(), id),
+                            None => (cs, id),
+                        }
+                    })
                     .chain(
                         added_cols
                             .iter()
@@ -120,6 +130,7 @@ impl MirNode {
                         over: node.clone(),
                         columns_added: added_cols.into_iter().cloned().collect(),
                         columns_removed: removed_cols.into_iter().cloned().collect(),
+                        columns_retyped: retyped_cols.into_iter().cloned().collect(),
                     }),
                 };
                 MirNode::new(
@@ -720,13 +731,17 @@ impl<R: Relationship> Iterator for Topo<R> {
     }
 }
 
-/// Specifies the adapatation of an existing base node by column addition/removal.
-/// `over` is a `MirNode` of type `Base`.
+/// Specifies the adapatation of an existing base node by column addition/removal, or by changing
+/// the type of existing columns in place. `over` is a `MirNode` of type `Base`.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct BaseNodeAdaptation {
     pub over: MirNodeRef,
     pub columns_added: Vec<ColumnSpecification>,
     pub columns_removed: Vec<ColumnSpecification>,
+    /// The new specifications of existing columns whose type (or other properties) changed,
+    /// matched to the existing columns by name
+    #[serde(default)]
+    pub columns_retyped: Vec<ColumnSpecification>,
 }
 
 impl Display for MirNode {
@@ -1,17 +1,29 @@
 mod decorrelate;
+mod prune_columns;
 mod pull_columns;
+mod push_filters;
 
 use readyset_errors::ReadySetResult;
 
 use self::decorrelate::eliminate_dependent_joins;
+use self::prune_columns::prune_join_columns;
 use self::pull_columns::pull_all_required_columns;
+use self::push_filters::push_filters_down;
 use crate::query::MirQuery;
 
 impl MirQuery {
     /// Run a set of rewrite and optimization passes on self, and return the modified
     /// query
-    pub fn optimize(mut self) -> ReadySetResult<Self> {
+    ///
+    /// If `prune_columns` is true, columns that aren't needed by the query are removed from the
+    /// output of its joins. Since that makes those joins less likely to be reusable by other
+    /// queries, this should only be set if reuse is disabled.
+    pub fn optimize(mut self, prune_columns: bool) -> ReadySetResult<Self> {
         eliminate_dependent_joins(&mut self)?;
+        push_filters_down(&mut self)?;
+        if prune_columns {
+            prune_join_columns(&mut self)?;
+        }
         pull_all_required_columns(&mut self)?;
         Ok(self)
     }
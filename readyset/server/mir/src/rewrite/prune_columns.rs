@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use nom_sql::analysis::ReferredColumns;
+use readyset_errors::ReadySetResult;
+use tracing::{instrument, trace};
+
+use crate::node::{MirNode, MirNodeInner};
+use crate::query::MirQuery;
+use crate::Column;
+
+/// Returns the columns that `node` needs its parents to provide, given `needed`, the columns that
+/// the children of each node in the query need that node to provide (or `None` if they might need
+/// all of them). Returns `None` if the node might need all of the columns of its parents.
+fn needed_from_parents(
+    node: &MirNode,
+    needed: &HashMap<String, Option<Vec<Column>>>,
+) -> Option<Vec<Column>> {
+    match &node.inner {
+        MirNodeInner::Filter { conditions } => {
+            let mut columns = needed.get(&node.versioned_name())?.clone()?;
+            for c in conditions.referred_columns() {
+                if !columns.iter().any(|col| col == c) {
+                    columns.push(c.into());
+                }
+            }
+            Some(columns)
+        }
+        MirNodeInner::Project { .. }
+        | MirNodeInner::Aggregation { .. }
+        | MirNodeInner::Extremum { .. } => Some(node.referenced_columns()),
+        MirNodeInner::Join {
+            on_left,
+            on_right,
+            project,
+        }
+        | MirNodeInner::LeftJoin {
+            on_left,
+            on_right,
+            project,
+        } => Some(
+            project
+                .iter()
+                .chain(on_left)
+                .chain(on_right)
+                .cloned()
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// A MIR rewrite pass that removes the columns from the output of each [join][] in the query that
+/// nothing after the join needs.
+///
+/// By default, joins project every column of both of their parents, which increases opportunities
+/// for reusing those joins in other queries, but means every row of their state (and every row sent
+/// through them) holds all of those columns, which can take a lot of memory for wide tables. This
+/// pass should only be run when reuse is disabled.
+///
+/// Join keys are always kept, and columns are only removed when every node after the join is known
+/// to need only a subset of its columns, so this pass never removes a column that's still
+/// needed - but [`pull_all_required_columns`][] will add back any column that ends up missing
+/// anyway.
+///
+/// This pass only changes joins that are new to the query, never ones that already exist in the
+/// dataflow graph.
+///
+/// [join]: MirNodeInner::Join
+/// [`pull_all_required_columns`]: crate::rewrite::pull_columns::pull_all_required_columns
+#[instrument(skip_all, fields(query = %query.name))]
+pub(super) fn prune_join_columns(query: &mut MirQuery) -> ReadySetResult<()> {
+    let mut needed: HashMap<String, Option<Vec<Column>>> = HashMap::new();
+
+    // Visit each node after all of its children, so that we know everything they need from it
+    for node in query.topo_nodes().into_iter().rev() {
+        let node_needed = {
+            let node = node.borrow();
+            node.children()
+                .iter()
+                .map(|child| needed_from_parents(&child.borrow(), &needed))
+                .try_fold(Vec::new(), |mut columns, child_needed| {
+                    for c in child_needed? {
+                        if !columns.contains(&c) {
+                            columns.push(c);
+                        }
+                    }
+                    Some(columns)
+                })
+                .filter(|_| !node.children().is_empty())
+        };
+
+        if let Some(node_needed) = &node_needed {
+            let mut node = node.borrow_mut();
+            let name = node.versioned_name();
+            if node.flow_node.is_none() {
+                if let MirNodeInner::Join {
+                    on_left,
+                    on_right,
+                    project,
+                }
+                | MirNodeInner::LeftJoin {
+                    on_left,
+                    on_right,
+                    project,
+                } = &mut node.inner
+                {
+                    let before = project.len();
+                    let first = project.first().cloned();
+                    project.retain(|c| {
+                        node_needed.contains(c) || on_left.contains(c) || on_right.contains(c)
+                    });
+                    if project.is_empty() {
+                        // Joins need to output at least one column
+                        project.extend(first);
+                    }
+                    if project.len() != before {
+                        trace!(
+                            join = %name,
+                            removed = before - project.len(),
+                            "Pruned join columns"
+                        );
+                    }
+                }
+            }
+        }
+
+        let name = node.borrow().versioned_name();
+        needed.insert(name, node_needed);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{BinaryOperator, ColumnSpecification, Expression, Literal, SqlType};
+
+    use super::*;
+    use crate::MirNodeRef;
+
+    fn make_base(name: &str, columns: &[&str]) -> MirNodeRef {
+        MirNode::new(
+            name.into(),
+            0,
+            MirNodeInner::Base {
+                column_specs: columns
+                    .iter()
+                    .map(|c| {
+                        (
+                            ColumnSpecification {
+                                column: nom_sql::Column::from(format!("{}.{}", name, c).as_str()),
+                                sql_type: SqlType::Int(None),
+                                constraints: vec![],
+                                comment: None,
+                            },
+                            None,
+                        )
+                    })
+                    .collect(),
+                primary_key: None,
+                unique_keys: Default::default(),
+                adapted_over: None,
+            },
+            vec![],
+            vec![],
+        )
+    }
+
+    fn make_join(
+        name: &str,
+        left: &MirNodeRef,
+        right: &MirNodeRef,
+        on: (&str, &str),
+    ) -> MirNodeRef {
+        let project = left
+            .borrow()
+            .columns()
+            .into_iter()
+            .chain(right.borrow().columns())
+            .collect();
+        MirNode::new(
+            name.into(),
+            0,
+            MirNodeInner::Join {
+                on_left: vec![Column::from(on.0)],
+                on_right: vec![Column::from(on.1)],
+                project,
+            },
+            vec![MirNodeRef::downgrade(left), MirNodeRef::downgrade(right)],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn prunes_unused_join_columns() {
+        // SELECT t1.b, t3.e FROM t1 JOIN t2 ON t1.a = t2.a JOIN t3 ON t2.c = t3.c WHERE t2.d = 1
+        let t1 = make_base("t1", &["a", "b", "x"]);
+        let t2 = make_base("t2", &["a", "c", "d", "y"]);
+        let t3 = make_base("t3", &["c", "e", "z"]);
+        let join1 = make_join("join1", &t1, &t2, ("t1.a", "t2.a"));
+        let join2 = make_join("join2", &join1, &t3, ("t2.c", "t3.c"));
+        let filter = MirNode::new(
+            "filter".into(),
+            0,
+            MirNodeInner::Filter {
+                conditions: Expression::BinaryOp {
+                    lhs: Box::new(Expression::Column("t2.d".into())),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expression::Literal(Literal::Integer(1))),
+                },
+            },
+            vec![MirNodeRef::downgrade(&join2)],
+            vec![],
+        );
+        let project = MirNode::new(
+            "project".into(),
+            0,
+            MirNodeInner::Project {
+                emit: vec![Column::from("t1.b"), Column::from("t3.e")],
+                expressions: vec![],
+                literals: vec![],
+            },
+            vec![MirNodeRef::downgrade(&filter)],
+            vec![],
+        );
+
+        let mut query = MirQuery {
+            name: "query".into(),
+            roots: vec![t1, t2, t3],
+            leaf: project,
+        };
+        prune_join_columns(&mut query).unwrap();
+
+        assert_eq!(
+            join2.borrow().columns(),
+            vec![
+                Column::from("t1.b"),
+                Column::from("t2.c"),
+                Column::from("t2.d"),
+                Column::from("t3.c"),
+                Column::from("t3.e"),
+            ]
+        );
+        assert_eq!(
+            join1.borrow().columns(),
+            vec![
+                Column::from("t1.a"),
+                Column::from("t1.b"),
+                Column::from("t2.a"),
+                Column::from("t2.c"),
+                Column::from("t2.d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_columns_needed_by_unknown_nodes() {
+        let t1 = make_base("t1", &["a", "b"]);
+        let t2 = make_base("t2", &["a", "c"]);
+        let join = make_join("join", &t1, &t2, ("t1.a", "t2.a"));
+        let identity = MirNode::new(
+            "identity".into(),
+            0,
+            MirNodeInner::Identity,
+            vec![MirNodeRef::downgrade(&join)],
+            vec![],
+        );
+
+        let mut query = MirQuery {
+            name: "query".into(),
+            roots: vec![t1, t2],
+            leaf: identity,
+        };
+        prune_join_columns(&mut query).unwrap();
+
+        assert_eq!(join.borrow().columns().len(), 4);
+    }
+}
@@ -0,0 +1,289 @@
+use std::mem;
+use std::rc::Rc;
+
+use nom_sql::analysis::ReferredColumns;
+use readyset_errors::ReadySetResult;
+use tracing::{instrument, trace};
+
+use crate::node::MirNodeInner;
+use crate::query::MirQuery;
+use crate::{Column, MirNodeRef};
+
+/// If the given `node` is a new [filter][] node directly below a new [join][] (with no other
+/// children) that it can be pushed above, returns that join along with the index of the parent of
+/// the join that the filter only references columns from.
+///
+/// Filters can be pushed into either side of an inner join, but only into the left side of a left
+/// join, since filtering rows from the right side of a left join before the join would replace
+/// those rows with NULLs in the join's output, rather than removing them.
+///
+/// [filter]: MirNodeInner::Filter
+/// [join]: MirNodeInner::Join
+fn pushdown_target(node: &MirNodeRef) -> Option<(MirNodeRef, usize)> {
+    let node = node.borrow();
+    let conditions = match &node.inner {
+        MirNodeInner::Filter { conditions } if node.flow_node.is_none() => conditions,
+        _ => return None,
+    };
+
+    let join = node.parent()?;
+    let join_ref = join.borrow();
+    if join_ref.flow_node.is_some() || join_ref.children().len() != 1 {
+        return None;
+    }
+    let parents = join_ref
+        .ancestors()
+        .iter()
+        .map(|p| p.upgrade())
+        .collect::<Option<Vec<_>>>()?;
+    if parents.len() != 2 || Rc::ptr_eq(&parents[0], &parents[1]) {
+        return None;
+    }
+
+    let columns = conditions
+        .referred_columns()
+        .map(Column::from)
+        .collect::<Vec<_>>();
+    let can_push_into = |idx: usize| {
+        let parent = parents[idx].borrow();
+        columns.iter().all(|c| parent.provides_column(c))
+    };
+    let idx = match &join_ref.inner {
+        MirNodeInner::Join { .. } => (0..2).find(|&idx| can_push_into(idx))?,
+        MirNodeInner::LeftJoin { .. } if can_push_into(0) => 0,
+        _ => return None,
+    };
+
+    drop(join_ref);
+    Some((join, idx))
+}
+
+/// Replace `old` in the ancestors of `node` with `new`, keeping its position (which matters for
+/// nodes like joins, which treat their first and second parents differently)
+fn replace_ancestor(node: &MirNodeRef, old: &MirNodeRef, new: &MirNodeRef) {
+    for ancestor in node.borrow_mut().ancestors.iter_mut() {
+        if ancestor.upgrade().map_or(false, |a| Rc::ptr_eq(&a, old)) {
+            *ancestor = MirNodeRef::downgrade(new);
+        }
+    }
+}
+
+/// Move the `filter` node from directly below the `join` node to between the join and its parent
+/// at index `idx`
+fn push_below_join(filter: MirNodeRef, join: MirNodeRef, idx: usize) {
+    #[allow(clippy::indexing_slicing, clippy::unwrap_used)] // checked in pushdown_target
+    let parent = join.borrow().ancestors()[idx].upgrade().unwrap();
+
+    trace!(
+        filter = %filter.borrow().versioned_name(),
+        join = %join.borrow().versioned_name(),
+        parent = %parent.borrow().versioned_name(),
+        "Pushing filter below join"
+    );
+
+    // Connect the filter's children directly to the join...
+    let children = mem::take(&mut filter.borrow_mut().children);
+    for child in &children {
+        replace_ancestor(child, &filter, &join);
+    }
+    join.borrow_mut().children = children;
+
+    // ...then put the filter between the join and the parent
+    for child in parent.borrow_mut().children.iter_mut() {
+        if Rc::ptr_eq(child, &join) {
+            *child = filter.clone();
+        }
+    }
+    replace_ancestor(&join, &parent, &filter);
+    let mut filter = filter.borrow_mut();
+    filter.ancestors = vec![MirNodeRef::downgrade(&parent)];
+    filter.children = vec![join];
+}
+
+/// A MIR rewrite pass that pushes [filters][] below the [joins][] they're placed after, into the
+/// side of the join whose columns they reference, so that rows are removed before being joined
+/// rather than after.
+///
+/// Local predicates on the tables in a query are converted to filters placed after all the joins in
+/// the query, which means every join has to keep state for (and upqueries through the joins have to
+/// look up) rows that are only going to be filtered out later. Filters are pushed as far down as
+/// they can go, so a filter on a single table ends up directly after that table.
+///
+/// Filters that reference columns from both sides of a join stay after that join, as do filters on
+/// the right-hand side of a [left join][].
+///
+/// This pass only moves filters and joins that are new to the query, never ones that already exist
+/// in the dataflow graph.
+///
+/// [filters]: MirNodeInner::Filter
+/// [joins]: MirNodeInner::Join
+/// [left join]: MirNodeInner::LeftJoin
+#[instrument(skip_all, fields(query = %query.name))]
+pub(super) fn push_filters_down(query: &mut MirQuery) -> ReadySetResult<()> {
+    // Each push moves a filter closer to the roots of the query, so this always terminates
+    while let Some((filter, join, idx)) = query
+        .topo_nodes()
+        .into_iter()
+        .find_map(|node| pushdown_target(&node).map(|(join, idx)| (node, join, idx)))
+    {
+        push_below_join(filter, join, idx);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{BinaryOperator, ColumnSpecification, Expression, Literal, SqlType};
+
+    use super::*;
+    use crate::node::MirNode;
+
+    fn make_base(name: &str, columns: &[&str]) -> MirNodeRef {
+        MirNode::new(
+            name.into(),
+            0,
+            MirNodeInner::Base {
+                column_specs: columns
+                    .iter()
+                    .map(|c| {
+                        (
+                            ColumnSpecification {
+                                column: nom_sql::Column::from(format!("{}.{}", name, c).as_str()),
+                                sql_type: SqlType::Int(None),
+                                constraints: vec![],
+                                comment: None,
+                            },
+                            None,
+                        )
+                    })
+                    .collect(),
+                primary_key: None,
+                unique_keys: Default::default(),
+                adapted_over: None,
+            },
+            vec![],
+            vec![],
+        )
+    }
+
+    fn equals_one(column: &str) -> Expression {
+        Expression::BinaryOp {
+            lhs: Box::new(Expression::Column(column.into())),
+            op: BinaryOperator::Equal,
+            rhs: Box::new(Expression::Literal(Literal::Integer(1))),
+        }
+    }
+
+    /// Builds `SELECT t1.a, t2.c FROM t1 <join> t2 ON t1.a = t2.a WHERE <condition>`
+    fn make_query(left_join: bool, condition: Expression) -> (MirQuery, [MirNodeRef; 5]) {
+        let t1 = make_base("t1", &["a", "b"]);
+        let t2 = make_base("t2", &["a", "c"]);
+        let on_left = vec![Column::from("t1.a")];
+        let on_right = vec![Column::from("t2.a")];
+        let project = vec![
+            Column::from("t1.a"),
+            Column::from("t1.b"),
+            Column::from("t2.a"),
+            Column::from("t2.c"),
+        ];
+        let join = MirNode::new(
+            "join".into(),
+            0,
+            if left_join {
+                MirNodeInner::LeftJoin {
+                    on_left,
+                    on_right,
+                    project,
+                }
+            } else {
+                MirNodeInner::Join {
+                    on_left,
+                    on_right,
+                    project,
+                }
+            },
+            vec![MirNodeRef::downgrade(&t1), MirNodeRef::downgrade(&t2)],
+            vec![],
+        );
+        let filter = MirNode::new(
+            "filter".into(),
+            0,
+            MirNodeInner::Filter {
+                conditions: condition,
+            },
+            vec![MirNodeRef::downgrade(&join)],
+            vec![],
+        );
+        let project = MirNode::new(
+            "project".into(),
+            0,
+            MirNodeInner::Project {
+                emit: vec![Column::from("t1.a"), Column::from("t2.c")],
+                expressions: vec![],
+                literals: vec![],
+            },
+            vec![MirNodeRef::downgrade(&filter)],
+            vec![],
+        );
+
+        let query = MirQuery {
+            name: "query".into(),
+            roots: vec![t1.clone(), t2.clone()],
+            leaf: project.clone(),
+        };
+        (query, [t1, t2, join, filter, project])
+    }
+
+    fn parent_names(node: &MirNodeRef) -> Vec<String> {
+        node.borrow()
+            .ancestors()
+            .iter()
+            .map(|a| a.upgrade().unwrap().borrow().name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn pushes_filter_into_right_of_inner_join() {
+        let (mut query, [t1, t2, join, filter, project]) = make_query(false, equals_one("t2.c"));
+        push_filters_down(&mut query).unwrap();
+
+        assert_eq!(parent_names(&filter), vec!["t2"]);
+        assert_eq!(parent_names(&join), vec!["t1", "filter"]);
+        assert_eq!(parent_names(&project), vec!["join"]);
+        assert!(Rc::ptr_eq(&t2.borrow().children()[0], &filter));
+        assert!(Rc::ptr_eq(&t1.borrow().children()[0], &join));
+        assert!(Rc::ptr_eq(&join.borrow().children()[0], &project));
+    }
+
+    #[test]
+    fn pushes_filter_into_left_of_left_join() {
+        let (mut query, [_, _, join, filter, _]) = make_query(true, equals_one("t1.b"));
+        push_filters_down(&mut query).unwrap();
+
+        assert_eq!(parent_names(&filter), vec!["t1"]);
+        assert_eq!(parent_names(&join), vec!["filter", "t2"]);
+    }
+
+    #[test]
+    fn doesnt_push_filter_into_right_of_left_join() {
+        let (mut query, [_, _, join, filter, _]) = make_query(true, equals_one("t2.c"));
+        push_filters_down(&mut query).unwrap();
+
+        assert_eq!(parent_names(&filter), vec!["join"]);
+        assert_eq!(parent_names(&join), vec!["t1", "t2"]);
+    }
+
+    #[test]
+    fn doesnt_push_filter_on_both_sides() {
+        let condition = Expression::BinaryOp {
+            lhs: Box::new(Expression::Column("t1.b".into())),
+            op: BinaryOperator::Greater,
+            rhs: Box::new(Expression::Column("t2.c".into())),
+        };
+        let (mut query, [_, _, join, filter, _]) = make_query(false, condition);
+        push_filters_down(&mut query).unwrap();
+
+        assert_eq!(parent_names(&filter), vec!["join"]);
+        assert_eq!(parent_names(&join), vec!["t1", "t2"]);
+    }
+}
@@ -231,6 +231,7 @@ mod tests {
                 name: "PaperTag".into(),
                 alias: Some("t".into()),
                 schema: None,
+                sample: None,
             }],
             fields: vec![FieldDefinitionExpression::from(Column::from("t.id"))],
             where_clause: Some(Expression::BinaryOp {
@@ -267,6 +268,7 @@ mod tests {
                         name: "PaperTag".into(),
                         alias: None,
                         schema: None,
+                        sample: None,
                     }]
                 );
             }
@@ -300,6 +302,7 @@ mod tests {
                 name: "PaperTag".into(),
                 alias: Some("t".into()),
                 schema: None,
+                sample: None,
             }],
             fields: vec![FieldDefinitionExpression::from(col_small.clone())],
             where_clause: Some(Expression::BinaryOp {
@@ -336,6 +339,7 @@ mod tests {
                         name: "PaperTag".into(),
                         alias: None,
                         schema: None,
+                        sample: None,
                     }]
                 );
             }
@@ -375,6 +379,7 @@ mod tests {
                         name: "__query_name__t1".into(),
                         alias: None,
                         schema: None,
+                        sample: None,
                     }]
                 );
                 assert_eq!(
@@ -385,6 +390,7 @@ mod tests {
                             name: "__query_name__t2".into(),
                             alias: None,
                             schema: None,
+                            sample: None,
                         }),
                         constraint: JoinConstraint::On(Expression::BinaryOp {
                             op: BinaryOperator::Equal,
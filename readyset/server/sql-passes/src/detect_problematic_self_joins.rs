@@ -116,7 +116,7 @@ fn check_select_statement<'a>(
                             break;
                         }
                     }
-                    JoinRightSide::NestedSelect(stmt, t) if t == table_alias => {
+                    JoinRightSide::NestedSelect(stmt, t, _) if t == table_alias => {
                         res = Some(Either::Right(trace_subquery(
                             stmt,
                             table_alias,
@@ -181,6 +181,7 @@ fn check_select_statement<'a>(
     for join in &stmt.join {
         match &join.constraint {
             JoinConstraint::Using(_) => unsupported!("USING is unsupported"),
+            JoinConstraint::Natural => unsupported!("NATURAL joins are unsupported"),
             JoinConstraint::On(expr) => {
                 if expr_is_problematic(expr, stmt, cte_ctx)? {
                     unsupported!("Self-joins using the same column are unsupported")
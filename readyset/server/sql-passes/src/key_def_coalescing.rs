@@ -70,6 +70,7 @@ mod tests {
             ],
             keys: None,
             if_not_exists: false,
+            temporary: false,
             options: vec![],
         };
 
@@ -10,6 +10,7 @@ mod normalize_topk_with_aggregate;
 mod order_limit_removal;
 mod remove_numeric_field_references;
 mod rewrite_between;
+mod rewrite_join_using;
 mod star_expansion;
 mod strip_post_filters;
 
@@ -34,6 +35,7 @@ pub use crate::normalize_topk_with_aggregate::NormalizeTopKWithAggregate;
 pub use crate::order_limit_removal::OrderLimitRemoval;
 pub use crate::remove_numeric_field_references::RemoveNumericFieldReferences;
 pub use crate::rewrite_between::RewriteBetween;
+pub use crate::rewrite_join_using::RewriteJoinUsing;
 pub use crate::star_expansion::StarExpansion;
 pub use crate::strip_post_filters::StripPostFilters;
 
@@ -45,7 +47,9 @@ pub fn outermost_referred_tables(stmt: &SelectStatement) -> impl Iterator<Item =
         .chain(stmt.join.iter().flat_map(|join| match &join.right {
             JoinRightSide::Table(table) => Either::Left(iter::once(table)),
             JoinRightSide::Tables(tables) => Either::Right(Either::Left(tables.iter())),
-            JoinRightSide::NestedSelect(..) => Either::Right(Either::Right(iter::empty())),
+            JoinRightSide::NestedSelect(..) | JoinRightSide::Values(_) => {
+                Either::Right(Either::Right(iter::empty()))
+            }
         }))
 }
 
@@ -82,12 +86,21 @@ pub(self) fn subquery_schemas<'a>(
     join: &'a [JoinClause],
 ) -> HashMap<&'a SqlIdentifier, Vec<&'a SqlIdentifier>> {
     ctes.iter()
-        .map(|cte| (&cte.name, &cte.statement))
+        .map(|cte| (&cte.name, (&cte.statement, &[][..])))
         .chain(join.iter().filter_map(|join| match &join.right {
-            JoinRightSide::NestedSelect(stmt, name) => Some((name, stmt.as_ref())),
+            JoinRightSide::NestedSelect(stmt, name, columns) => {
+                Some((name, (stmt.as_ref(), columns.as_slice())))
+            }
             _ => None,
         }))
-        .map(|(name, stmt)| (name, field_names(stmt).collect()))
+        .map(|(name, (stmt, columns))| {
+            // Column names given in the subquery's alias override the names of its fields
+            let fields = columns
+                .iter()
+                .chain(field_names(stmt).skip(columns.len()))
+                .collect();
+            (name, fields)
+        })
         .collect()
 }
 
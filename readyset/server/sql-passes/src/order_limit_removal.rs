@@ -156,6 +156,7 @@ mod tests {
             name: "t".into(),
             alias: None,
             schema: None,
+            sample: None,
         };
 
         let col1 = ColumnSpecification {
@@ -217,6 +218,7 @@ mod tests {
                 fields,
                 keys,
                 if_not_exists,
+                temporary: false,
                 options: vec![],
             },
         );
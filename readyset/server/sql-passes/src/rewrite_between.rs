@@ -112,6 +112,7 @@ fn rewrite_expression(expr: Expression) -> Expression {
                 count_nulls,
             },
             FunctionExpression::CountStar => FunctionExpression::CountStar,
+            unsupported @ FunctionExpression::Unsupported { .. } => unsupported,
             FunctionExpression::Sum { expr, distinct } => FunctionExpression::Sum {
                 expr: Box::new(rewrite_expression(*expr)),
                 distinct,
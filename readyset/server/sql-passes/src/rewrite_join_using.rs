@@ -0,0 +1,385 @@
+use std::collections::{HashMap, HashSet};
+
+use nom_sql::analysis::visit::{
+    walk_group_by_clause, walk_order_clause, walk_select_statement, Visitor,
+};
+use nom_sql::{
+    BinaryOperator, Column, CommonTableExpression, Expression, FieldDefinitionExpression,
+    JoinConstraint, JoinOperator, JoinRightSide, SelectStatement, SqlIdentifier, SqlQuery, Table,
+};
+use readyset_errors::{invalid_err, unsupported, ReadySetError, ReadySetResult};
+
+use crate::StarExpansion;
+
+pub trait RewriteJoinUsing: Sized {
+    /// Recursively rewrite all `USING` and `NATURAL` joins in the given query into joins with an
+    /// explicit `ON` condition comparing the shared columns of both sides. For example, the
+    /// following query:
+    ///
+    /// ```sql
+    /// SELECT * FROM t1 JOIN t2 USING (id);
+    /// ```
+    ///
+    /// becomes (if `t1` has columns `id` and `a`, and `t2` has columns `id` and `b`):
+    ///
+    /// ```sql
+    /// SELECT t1.id, t1.a, t2.b FROM t1 JOIN t2 ON t1.id = t2.id;
+    /// ```
+    ///
+    /// Since the shared columns appear only once in the result of the join, `*` is expanded
+    /// accordingly, and unqualified references to them are resolved to the left side of the join.
+    fn rewrite_join_using(
+        self,
+        write_schemas: &HashMap<SqlIdentifier, Vec<SqlIdentifier>>,
+    ) -> ReadySetResult<Self>;
+}
+
+/// Qualifies unqualified references to the columns shared by the two sides of a `USING` or
+/// `NATURAL` join, without descending into subqueries
+struct QualifyJoinColumnsVisitor<'a> {
+    join_columns: &'a HashMap<SqlIdentifier, SqlIdentifier>,
+    aliases: HashSet<SqlIdentifier>,
+    // Are we currently in a position in the query that can reference aliases in the projected
+    // field list?
+    can_reference_aliases: bool,
+}
+
+impl<'ast, 'a> Visitor<'ast> for QualifyJoinColumnsVisitor<'a> {
+    type Error = !;
+
+    fn visit_column(&mut self, column: &'ast mut Column) -> Result<(), Self::Error> {
+        if column.table.is_none()
+            && !(self.can_reference_aliases && self.aliases.contains(&column.name))
+        {
+            column.table = self.join_columns.get(&column.name).cloned();
+        }
+        Ok(())
+    }
+
+    fn visit_order_clause(
+        &mut self,
+        order: &'ast mut nom_sql::OrderClause,
+    ) -> Result<(), Self::Error> {
+        self.can_reference_aliases = true;
+        walk_order_clause(self, order)?;
+        self.can_reference_aliases = false;
+        Ok(())
+    }
+
+    fn visit_group_by_clause(
+        &mut self,
+        group_by: &'ast mut nom_sql::GroupByClause,
+    ) -> Result<(), Self::Error> {
+        self.can_reference_aliases = true;
+        walk_group_by_clause(self, group_by)?;
+        self.can_reference_aliases = false;
+        Ok(())
+    }
+
+    fn visit_select_statement(&mut self, _: &'ast mut SelectStatement) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+struct RewriteJoinUsingVisitor<'schema> {
+    write_schemas: &'schema HashMap<SqlIdentifier, Vec<SqlIdentifier>>,
+}
+
+impl<'schema> RewriteJoinUsingVisitor<'schema> {
+    /// Returns the names of the columns of the given (star-expanded) subquery
+    fn subquery_columns(&self, stmt: &SelectStatement) -> ReadySetResult<Vec<SqlIdentifier>> {
+        let stmt = stmt.clone().expand_stars(self.write_schemas)?;
+        Ok(crate::field_names(&stmt).cloned().collect())
+    }
+
+    /// Returns the name the given relation is referred to by in the query, and the names of its
+    /// columns
+    fn relation_columns(
+        &self,
+        ctes: &[CommonTableExpression],
+        relation: &JoinRightSide,
+    ) -> ReadySetResult<(SqlIdentifier, Vec<SqlIdentifier>)> {
+        match relation {
+            JoinRightSide::Table(table) => self.table_columns(ctes, table),
+            JoinRightSide::NestedSelect(subquery, alias, columns) => {
+                let columns = if columns.is_empty() {
+                    self.subquery_columns(subquery)?
+                } else {
+                    columns.clone()
+                };
+                Ok((alias.clone(), columns))
+            }
+            JoinRightSide::Tables(_) => {
+                unsupported!("USING and NATURAL joins against lists of tables are not supported")
+            }
+            JoinRightSide::Values(_) => {
+                unsupported!("USING and NATURAL joins against VALUES are not supported")
+            }
+        }
+    }
+
+    fn table_columns(
+        &self,
+        ctes: &[CommonTableExpression],
+        table: &Table,
+    ) -> ReadySetResult<(SqlIdentifier, Vec<SqlIdentifier>)> {
+        let name = table.alias.clone().unwrap_or_else(|| table.name.clone());
+        let columns = match ctes.iter().find(|cte| cte.name == table.name) {
+            Some(cte) => self.subquery_columns(&cte.statement)?,
+            None => self
+                .write_schemas
+                .get(&table.name)
+                .cloned()
+                .ok_or_else(|| ReadySetError::TableNotFound(table.name.to_string()))?,
+        };
+        Ok((name, columns))
+    }
+
+    fn rewrite_select(&self, stmt: &mut SelectStatement) -> ReadySetResult<()> {
+        if !stmt.join.iter().any(|join| {
+            matches!(
+                join.constraint,
+                JoinConstraint::Using(_) | JoinConstraint::Natural
+            )
+        }) {
+            return Ok(());
+        }
+
+        // The columns in the result of the join so far, in order, along with the name of the
+        // relation each comes from
+        let mut output: Vec<(SqlIdentifier, SqlIdentifier)> = vec![];
+        for table in &stmt.tables {
+            let (name, columns) = self.table_columns(&stmt.ctes, table)?;
+            output.extend(columns.into_iter().map(|col| (name.clone(), col)));
+        }
+
+        // Map from the names of columns shared by both sides of a join to the relation references
+        // to them should resolve to
+        let mut join_columns = HashMap::new();
+        for join in stmt.join.iter_mut() {
+            let (rhs_name, rhs_columns) = self.relation_columns(&stmt.ctes, &join.right)?;
+            let shared: Vec<SqlIdentifier> = match &join.constraint {
+                JoinConstraint::Using(cols) => cols.iter().map(|col| col.name.clone()).collect(),
+                JoinConstraint::Natural => rhs_columns
+                    .iter()
+                    .filter(|col| output.iter().any(|(_, c)| c == *col))
+                    .cloned()
+                    .collect(),
+                JoinConstraint::On(_) | JoinConstraint::Empty => {
+                    output.extend(rhs_columns.into_iter().map(|col| (rhs_name.clone(), col)));
+                    continue;
+                }
+            };
+
+            let mut shared_output = Vec::with_capacity(shared.len());
+            let mut predicates = Vec::with_capacity(shared.len());
+            for col in &shared {
+                let left_idx = output.iter().position(|(_, c)| c == col);
+                let (left_name, _) = match left_idx {
+                    Some(idx) if rhs_columns.contains(col) => output.remove(idx),
+                    _ => return Err(invalid_err(format!("Unknown column '{}' in USING", col))),
+                };
+                predicates.push(Expression::BinaryOp {
+                    lhs: Box::new(Expression::Column(Column {
+                        name: col.clone(),
+                        table: Some(left_name.clone()),
+                    })),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expression::Column(Column {
+                        name: col.clone(),
+                        table: Some(rhs_name.clone()),
+                    })),
+                });
+                // The shared column takes its value from the side of the join that's always
+                // present
+                let source = if join.operator == JoinOperator::RightJoin {
+                    rhs_name.clone()
+                } else {
+                    left_name
+                };
+                join_columns.insert(col.clone(), source.clone());
+                shared_output.push((source, col.clone()));
+            }
+
+            join.constraint = predicates
+                .into_iter()
+                .reduce(|lhs, rhs| Expression::BinaryOp {
+                    lhs: Box::new(lhs),
+                    op: BinaryOperator::And,
+                    rhs: Box::new(rhs),
+                })
+                .map_or(JoinConstraint::Empty, JoinConstraint::On);
+
+            // The shared columns come first in the result of the join, followed by the rest of the
+            // columns of the left and then the right side
+            output = shared_output
+                .into_iter()
+                .chain(output)
+                .chain(
+                    rhs_columns
+                        .into_iter()
+                        .filter(|col| !shared.contains(col))
+                        .map(|col| (rhs_name.clone(), col)),
+                )
+                .collect();
+        }
+
+        stmt.fields = std::mem::take(&mut stmt.fields)
+            .into_iter()
+            .flat_map(|field| match field {
+                FieldDefinitionExpression::All => output
+                    .iter()
+                    .map(|(table, name)| FieldDefinitionExpression::Expression {
+                        expr: Expression::Column(Column {
+                            name: name.clone(),
+                            table: Some(table.clone()),
+                        }),
+                        alias: None,
+                    })
+                    .collect(),
+                field => vec![field],
+            })
+            .collect();
+
+        let aliases = stmt
+            .fields
+            .iter()
+            .filter_map(|field| match field {
+                FieldDefinitionExpression::Expression {
+                    alias: Some(alias), ..
+                } => Some(alias.clone()),
+                _ => None,
+            })
+            .collect();
+        let Ok(()) = walk_select_statement(
+            &mut QualifyJoinColumnsVisitor {
+                join_columns: &join_columns,
+                aliases,
+                can_reference_aliases: false,
+            },
+            stmt,
+        );
+
+        Ok(())
+    }
+}
+
+impl<'ast, 'schema> Visitor<'ast> for RewriteJoinUsingVisitor<'schema> {
+    type Error = ReadySetError;
+
+    fn visit_select_statement(
+        &mut self,
+        select_statement: &'ast mut SelectStatement,
+    ) -> Result<(), Self::Error> {
+        walk_select_statement(self, select_statement)?;
+        self.rewrite_select(select_statement)
+    }
+}
+
+impl RewriteJoinUsing for SelectStatement {
+    fn rewrite_join_using(
+        mut self,
+        write_schemas: &HashMap<SqlIdentifier, Vec<SqlIdentifier>>,
+    ) -> ReadySetResult<Self> {
+        RewriteJoinUsingVisitor { write_schemas }.visit_select_statement(&mut self)?;
+        Ok(self)
+    }
+}
+
+impl RewriteJoinUsing for SqlQuery {
+    fn rewrite_join_using(
+        self,
+        write_schemas: &HashMap<SqlIdentifier, Vec<SqlIdentifier>>,
+    ) -> ReadySetResult<Self> {
+        Ok(match self {
+            SqlQuery::Select(stmt) => SqlQuery::Select(stmt.rewrite_join_using(write_schemas)?),
+            SqlQuery::CompoundSelect(mut compound_select) => {
+                for (_, select) in compound_select.selects.iter_mut() {
+                    *select = select.clone().rewrite_join_using(write_schemas)?;
+                }
+                SqlQuery::CompoundSelect(compound_select)
+            }
+            _ => self,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+    use nom_sql::{parse_query, Dialect};
+
+    use super::*;
+
+    fn schemas() -> HashMap<SqlIdentifier, Vec<SqlIdentifier>> {
+        hashmap! {
+            "t1".into() => vec!["id".into(), "a".into()],
+            "t2".into() => vec!["id".into(), "b".into()],
+            "t3".into() => vec!["id".into(), "a".into(), "c".into()],
+        }
+    }
+
+    macro_rules! rewrites_to {
+        ($source: expr, $expected: expr) => {{
+            let q = parse_query(Dialect::MySQL, $source).unwrap();
+            let expected = parse_query(Dialect::MySQL, $expected).unwrap();
+            let res = q.rewrite_join_using(&schemas()).unwrap();
+            assert_eq!(res, expected, "{} != {}", res, expected);
+        }};
+    }
+
+    #[test]
+    fn using_star() {
+        rewrites_to!(
+            "SELECT * FROM t1 JOIN t2 USING (id)",
+            "SELECT t1.id, t1.a, t2.b FROM t1 JOIN t2 ON t1.id = t2.id"
+        );
+    }
+
+    #[test]
+    fn using_qualifies_shared_columns() {
+        rewrites_to!(
+            "SELECT id, b FROM t1 LEFT JOIN t2 USING (id) WHERE id = ? ORDER BY id",
+            "SELECT t1.id, b FROM t1 LEFT JOIN t2 ON t1.id = t2.id WHERE t1.id = ? ORDER BY t1.id"
+        );
+    }
+
+    #[test]
+    fn natural_join() {
+        rewrites_to!(
+            "SELECT * FROM t1 NATURAL JOIN t3",
+            "SELECT t1.id, t1.a, t3.c FROM t1 JOIN t3 ON t1.id = t3.id AND t1.a = t3.a"
+        );
+    }
+
+    #[test]
+    fn natural_join_without_shared_columns() {
+        rewrites_to!(
+            "SELECT * FROM t1 NATURAL JOIN (SELECT t2.b AS c FROM t2) sq",
+            "SELECT t1.id, t1.a, sq.c FROM t1 JOIN (SELECT t2.b AS c FROM t2) sq"
+        );
+    }
+
+    #[test]
+    fn chained_using() {
+        rewrites_to!(
+            "SELECT * FROM t1 JOIN t2 USING (id) JOIN t3 USING (a)",
+            "SELECT t1.a, t1.id, t2.b, t3.id, t3.c FROM t1 JOIN t2 ON t1.id = t2.id \
+             JOIN t3 ON t1.a = t3.a"
+        );
+    }
+
+    #[test]
+    fn in_subquery() {
+        rewrites_to!(
+            "SELECT x FROM t1 WHERE a IN (SELECT b FROM t2 NATURAL JOIN t3)",
+            "SELECT x FROM t1 WHERE a IN (SELECT b FROM t2 JOIN t3 ON t2.id = t3.id)"
+        );
+    }
+
+    #[test]
+    fn unknown_using_column() {
+        let q = parse_query(Dialect::MySQL, "SELECT * FROM t1 JOIN t2 USING (a)").unwrap();
+        assert!(q.rewrite_join_using(&schemas()).is_err());
+    }
+}
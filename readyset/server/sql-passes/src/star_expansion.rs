@@ -5,18 +5,46 @@ use nom_sql::{
     Column, CommonTableExpression, Expression, FieldDefinitionExpression, JoinClause,
     JoinRightSide, SelectStatement, SqlIdentifier, SqlQuery, Table,
 };
-use readyset_errors::{ReadySetError, ReadySetResult};
+use readyset_errors::{invalid_err, ReadySetError, ReadySetResult};
 
 fn extract_tables_from_join(joins: &[JoinClause]) -> impl Iterator<Item = &Table> {
     use itertools::Either;
     joins.iter().flat_map(|j| match j.right {
         // subquery schemas should be handled separately
-        JoinRightSide::NestedSelect(..) => Either::Left(Either::Left(std::iter::empty())),
+        JoinRightSide::NestedSelect(..) | JoinRightSide::Values(_) => {
+            Either::Left(Either::Left(std::iter::empty()))
+        }
         JoinRightSide::Table(ref t) => Either::Left(Either::Right(std::iter::once(t))),
         JoinRightSide::Tables(ref v) => Either::Right(v.iter()),
     })
 }
 
+/// Apply the column names given to a subquery in its alias (as in `(SELECT a, b FROM t) AS x (c1,
+/// c2)`) to the fields of the (star-expanded) subquery, so that later passes only have to look at
+/// the field aliases
+fn rename_fields(
+    stmt: &mut SelectStatement,
+    name: &SqlIdentifier,
+    columns: Vec<SqlIdentifier>,
+) -> ReadySetResult<()> {
+    if columns.len() > stmt.fields.len() {
+        return Err(invalid_err(format!(
+            "{} has {} columns available but {} columns specified",
+            name,
+            stmt.fields.len(),
+            columns.len()
+        )));
+    }
+
+    for (field, column) in stmt.fields.iter_mut().zip(columns) {
+        if let FieldDefinitionExpression::Expression { alias, .. } = field {
+            *alias = Some(column);
+        }
+    }
+
+    Ok(())
+}
+
 pub trait StarExpansion: Sized {
     fn expand_stars(
         self,
@@ -44,10 +72,11 @@ impl StarExpansion for SelectStatement {
             .map(|join| -> ReadySetResult<_> {
                 Ok(JoinClause {
                     right: match join.right {
-                        JoinRightSide::NestedSelect(stmt, name) => JoinRightSide::NestedSelect(
-                            Box::new(stmt.expand_stars(write_schemas)?),
-                            name,
-                        ),
+                        JoinRightSide::NestedSelect(stmt, name, columns) => {
+                            let mut stmt = stmt.expand_stars(write_schemas)?;
+                            rename_fields(&mut stmt, &name, columns)?;
+                            JoinRightSide::NestedSelect(Box::new(stmt), name, vec![])
+                        }
                         r => r,
                     },
                     ..join
@@ -211,6 +240,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn subquery_column_aliases() {
+        expands_stars!(
+            "SELECT x.* FROM PaperTag JOIN (SELECT Users.* FROM Users) AS x (id, n) ON paper_id = id",
+            "SELECT x.id, x.n FROM PaperTag JOIN (SELECT Users.uid AS id, Users.name AS n FROM Users) AS x ON paper_id = id",
+            schema: {
+                "Users".into() => vec!["uid".into(), "name".into()]
+            }
+        );
+    }
+
+    #[test]
+    fn subquery_too_many_column_aliases() {
+        let q = parse_query(
+            Dialect::MySQL,
+            "SELECT x.a FROM t JOIN (SELECT u.a FROM u) AS x (a, b) ON t.a = x.a",
+        )
+        .unwrap();
+        assert!(q.expand_stars(&Default::default()).is_err());
+    }
+
     #[test]
     fn simple_join() {
         expands_stars!(
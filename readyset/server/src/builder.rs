@@ -1,10 +1,14 @@
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time;
 
 use dataflow::PersistenceParameters;
+use nom_sql::{Dialect, SqlIdentifier};
 use readyset::consensus::{Authority, LocalAuthority, LocalAuthorityStore};
+use readyset_data::IntegerOverflow;
 
 use crate::handle::Handle;
 use crate::{Config, FrontierStrategy, ReuseConfigType, VolumeId};
@@ -20,6 +24,7 @@ pub struct Builder {
     region: Option<String>,
     reader_only: bool,
     volume_id: Option<VolumeId>,
+    admin_api_token: Option<String>,
 }
 
 impl Default for Builder {
@@ -34,6 +39,7 @@ impl Default for Builder {
             region: None,
             reader_only: false,
             volume_id: None,
+            admin_api_token: None,
         }
     }
 }
@@ -86,6 +92,17 @@ impl Builder {
             .allow_full_materialization = false;
     }
 
+    /// Set the maximum memory, in bytes, that the state of the nodes added by a single migration
+    /// may be estimated to use.
+    ///
+    /// If `enforce` is true, migrations that exceed the budget will return
+    /// [`ReadySetError::MigrationMemoryBudgetExceeded`]; otherwise they're only logged with a
+    /// warning.
+    pub fn set_migration_memory_budget(&mut self, budget: Option<u64>, enforce: bool) {
+        self.config.materialization_config.migration_memory_budget = budget;
+        self.config.materialization_config.enforce_memory_budget = enforce;
+    }
+
     /// Set sharding policy for all subsequent migrations; `None` or `Some(x)` where x <= 1 disables
     pub fn set_sharding(&mut self, shards: Option<usize>) {
         self.config.sharding = shards.filter(|s| *s > 1);
@@ -137,6 +154,12 @@ impl Builder {
         self.config.mir_config.allow_mixed_comparisons = allow_mixed_comparisons;
     }
 
+    /// Set the SQL dialect of the upstream database, which determines the dialect-specific
+    /// semantics of queries, such as where `NULL` values sort in `ORDER BY`. Defaults to MySQL.
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.config.mir_config.null_order = dialect.into();
+    }
+
     /// Set the value of [`DomainConfig::aggressively_update_state_sizes`][0]. See the documentation
     /// of that field for more information
     ///
@@ -155,16 +178,98 @@ impl Builder {
         self.config.primary_region = Some(region);
     }
 
+    /// Sets how long standbys wait after the leader stops responding before deposing it and taking
+    /// over as the leader.
+    pub fn set_leader_failover_timeout(&mut self, timeout: time::Duration) {
+        self.config.leader_failover_timeout = Some(timeout);
+    }
+
+    /// Sets whether to move some of the most heavily loaded domains onto each worker that joins
+    /// the cluster.
+    pub fn set_rebalance_on_join(&mut self, value: bool) {
+        self.config.rebalance_on_join = value;
+    }
+
+    /// Sets what integer arithmetic in expressions does when its result doesn't fit in a 64-bit
+    /// integer. Defaults to returning an error, as both MySQL and PostgreSQL do.
+    pub fn set_integer_overflow(&mut self, integer_overflow: IntegerOverflow) {
+        self.config.integer_overflow = integer_overflow;
+    }
+
     /// Sets the primary MySQL/PostgreSQL server to replicate from.
     pub fn set_replicator_url(&mut self, url: String) {
         self.config.replication_url = Some(url);
     }
 
+    /// Sets whether adapters write directly to base tables as well as to the upstream database,
+    /// in which case the replicator resolves conflicts with those writes in favor of the upstream.
+    pub fn set_write_through(&mut self, write_through: bool) {
+        self.config.replicator_config.write_through = write_through;
+    }
+
+    /// Adds an additional MySQL/PostgreSQL server to replicate from, whose tables are created in
+    /// the given namespace.
+    pub fn add_additional_upstream(&mut self, namespace: String, url: String) {
+        self.config.additional_upstreams.insert(namespace, url);
+    }
+
     /// Sets the server uuid to use when registering for a binlog replication slot.
     pub fn set_replication_server_id(&mut self, id: u32) {
         self.config.replication_server_id = Some(id);
     }
 
+    /// Sets the maximum number of tables to copy concurrently during the replicator's initial
+    /// snapshot of the upstream database.
+    pub fn set_snapshot_parallelism(&mut self, parallelism: usize) {
+        self.config.replicator_config.snapshot_parallelism = parallelism;
+    }
+
+    /// Sets the number of replicated row operations beyond which the replicator writes its
+    /// buffered writes to base tables, and the maximum amount of time it buffers a write for.
+    pub fn set_replication_write_batch(&mut self, size: usize, latency: std::time::Duration) {
+        self.config.replicator_config.write_batch_size = size;
+        self.config.replicator_config.write_batch_latency = latency;
+    }
+
+    /// Sets the dump file to load the initial snapshot of the primary upstream database from,
+    /// instead of snapshotting the upstream database itself.
+    pub fn set_snapshot_dump(&mut self, path: PathBuf) {
+        self.config.replicator_config.snapshot_dump = Some(path);
+    }
+
+    /// Sets the columns, of the form `<table>.<column>`, whose values the replicator should
+    /// replace with NULL instead of copying into ReadySet.
+    pub fn set_nullified_columns(&mut self, columns: Vec<String>) {
+        self.config.replicator_config.nullified_columns = columns;
+        self.update_filtered_columns();
+    }
+
+    /// Sets the columns, of the form `<table>.<column>`, that the replicator should leave out of
+    /// ReadySet's tables entirely.
+    pub fn set_dropped_columns(&mut self, columns: Vec<String>) {
+        self.config.replicator_config.dropped_columns = columns;
+        self.update_filtered_columns();
+    }
+
+    /// Keeps [`controller::sql::Config::filtered_columns`] in sync with the columns the replicator
+    /// nullifies or drops, so that caches reading them are rejected
+    fn update_filtered_columns(&mut self) {
+        let replicator_config = &self.config.replicator_config;
+        let mut filtered_columns: HashMap<SqlIdentifier, HashSet<SqlIdentifier>> = HashMap::new();
+        for (table, column) in replicator_config
+            .nullified_columns
+            .iter()
+            .chain(&replicator_config.dropped_columns)
+            .filter_map(|column| column.rsplit_once('.'))
+        {
+            filtered_columns
+                .entry(table.into())
+                .or_default()
+                .insert(column.into());
+        }
+        self.config.mir_config.filtered_columns = filtered_columns;
+    }
+
     /// Sets whether we should keep the chain of prior recipes when storing a new
     /// recipe. Setting this to false may have unexpected behavior and should be
     /// used with caution. It is currently only used in test environments.
@@ -182,6 +287,12 @@ impl Builder {
         self.volume_id = Some(volume_id);
     }
 
+    /// Enables the HTTP admin API (the endpoints under `/api/v1/`), which must be authenticated
+    /// with a bearer token equal to `token`. The admin API is disabled if no token is set.
+    pub fn set_admin_api_token(&mut self, token: String) {
+        self.admin_api_token = Some(token);
+    }
+
     /// Set the value of [`Config::abort_on_task_failure`]. See the documentation of that field for
     /// more information.
     pub fn set_abort_on_task_failure(&mut self, abort_on_task_failure: bool) {
@@ -218,6 +329,24 @@ impl Builder {
         self.config.domain_config.eviction_kind = value;
     }
 
+    /// Sets the value of [`Config::domain_config::partial_spill`]. See documentation of
+    /// that field for more information.
+    pub fn set_partial_spill(&mut self, value: Option<dataflow::SpillParameters>) {
+        self.config.domain_config.partial_spill = value;
+    }
+
+    /// Sets the value of [`Config::domain_config::max_value_size`]. See documentation of
+    /// that field for more information.
+    pub fn set_max_value_size(&mut self, value: Option<usize>) {
+        self.config.domain_config.max_value_size = value;
+    }
+
+    /// Sets the value of [`Config::domain_config::max_queued_packets`]. See documentation of
+    /// that field for more information.
+    pub fn set_max_queued_packets(&mut self, value: Option<usize>) {
+        self.config.domain_config.max_queued_packets = value;
+    }
+
     /// Start a server instance and return a handle to it.
     pub fn start(
         self,
@@ -232,6 +361,7 @@ impl Builder {
             region,
             reader_only,
             volume_id,
+            admin_api_token,
         } = self;
 
         let config = config.clone();
@@ -246,6 +376,7 @@ impl Builder {
             region,
             reader_only,
             volume_id,
+            admin_api_token,
         )
     }
 
@@ -267,6 +398,7 @@ impl Builder {
             region,
             reader_only,
             volume_id,
+            admin_api_token,
         } = self;
 
         let config = config.clone();
@@ -281,6 +413,7 @@ impl Builder {
             region,
             reader_only,
             volume_id,
+            admin_api_token,
             readers,
             reader_addr,
             valve,
@@ -7,18 +7,28 @@
     clippy::unreachable
 )]
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use backoff::backoff::Backoff;
+use backoff::exponential::ExponentialBackoff;
+use backoff::SystemClock;
 use failpoint_macros::failpoint;
 use hyper::Method;
-use readyset::consensus::Authority;
+use metrics::counter;
+use nom_sql::{CacheInner, CreateCacheStatement, SqlIdentifier};
+use parking_lot::Mutex;
+use readyset::consensus::{Authority, AuthorityControl};
+use readyset::internal::MaterializationStatus;
+use readyset::metrics::recorded;
+use readyset::recipe::changelist::{Change, ChangeList};
 use readyset::recipe::ExtendRecipeSpec;
-use readyset::replication::ReplicationOffset;
+use readyset::replication::{ReplicationOffset, ReplicationSlotStatus, SnapshotProgress};
 use readyset::status::{ReadySetStatus, SnapshotStatus};
+use readyset::storage::CompressionOptions;
 use readyset::WorkerDescriptor;
-use readyset_errors::{ReadySetError, ReadySetResult};
+use readyset_errors::{bad_request_err, internal_err, ReadySetError, ReadySetResult};
 use reqwest::Url;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::Notify;
@@ -29,6 +39,28 @@ use crate::controller::{ControllerRequest, ControllerState, Worker, WorkerIdenti
 use crate::coordination::DomainDescriptor;
 use crate::worker::WorkerRequestKind;
 
+/// The path in the authority at which the progress of unfinished table snapshots is stored
+const SNAPSHOT_PROGRESS_PATH: &str = "/snapshot_progress";
+/// The authority path at which the state of the upstream replication slot is stored
+const REPLICATION_SLOT_STATUS_PATH: &str = "/replication_slot_status";
+/// The authority path at which the largest replicated value of each table's auto-increment column
+/// is stored, keyed by table name
+const AUTO_INCREMENTS_PATH: &str = "/auto_increments";
+/// The authority path at which the schema replication offsets of namespaced upstream databases
+/// are stored, keyed by namespace
+const NAMESPACE_SCHEMA_OFFSETS_PATH: &str = "/namespace_schema_replication_offsets";
+/// The delay before restarting a replicator the first time it fails. Each consecutive failure
+/// increases the delay exponentially, up to the configured replicator restart timeout.
+const REPLICATOR_INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// The number of times the replicators have been restarted after failing, and the error that
+/// caused the most recent restart
+#[derive(Debug, Default)]
+struct ReplicatorReconnects {
+    count: u64,
+    last_error: Option<String>,
+}
+
 /// The ReadySet leader, responsible for making control-plane decisions for the whole of a ReadySet
 /// cluster.
 ///
@@ -46,6 +78,9 @@ pub struct Leader {
     quorum: usize,
     controller_uri: Url,
 
+    /// Whether to move domains onto workers when they join the cluster
+    rebalance_on_join: bool,
+
     /// The amount of time to wait for a worker request to complete.
     worker_request_timeout: Duration,
     /// The amount of time to wait before restarting the replicator on error.
@@ -54,10 +89,19 @@ pub struct Leader {
     pub(super) replicator_url: Option<String>,
     /// A handle to the replicator task
     pub(super) replicator_task: Option<tokio::task::JoinHandle<()>>,
+    /// Additional upstream database URLs to replicate from, keyed by the namespace of the tables
+    /// replicated from each of them
+    additional_upstreams: BTreeMap<String, String>,
+    /// Handles to the replicator tasks for each of the additional upstream databases
+    additional_replicator_tasks: Vec<tokio::task::JoinHandle<()>>,
+    /// Restarts of the replicator tasks, shown by `SHOW READYSET STATUS`
+    replicator_reconnects: Arc<Mutex<ReplicatorReconnects>>,
     /// A client to the current authority.
     pub(super) authority: Arc<Authority>,
     /// Optional server id to use when registering for a slot for binlog replication.
     pub(super) server_id: Option<u32>,
+    /// Configuration for the replicator
+    replicator_config: replicators::Config,
 }
 
 impl Leader {
@@ -80,7 +124,12 @@ impl Leader {
     }
 
     async fn stop_replication_task(&mut self) {
-        if let Some(handle) = self.replicator_task.take() {
+        let handles = self
+            .replicator_task
+            .take()
+            .into_iter()
+            .chain(self.additional_replicator_tasks.drain(..));
+        for handle in handles {
             handle.abort();
             let _ = handle.await;
         }
@@ -91,12 +140,35 @@ impl Leader {
     /// a connection to the primary was lost for any reason, all we want is to
     /// connect again, and catch up from the binlog
     ///
+    /// A separate task is started for each of the additional upstream databases. The leader only
+    /// waits for the primary upstream database to be snapshotted before becoming ready.
+    ///
     /// TODO: how to handle the case where we need a full new replica
     async fn start_replication_task(
         &mut self,
         ready_notification: Arc<Notify>,
         replication_error: UnboundedSender<ReadySetError>,
     ) {
+        let additional_tasks = self
+            .additional_upstreams
+            .iter()
+            .map(|(namespace, url)| {
+                let replicator_config = replicators::Config {
+                    namespace: Some(namespace.clone()),
+                    // The dump is only of the primary upstream database
+                    snapshot_dump: None,
+                    ..self.replicator_config.clone()
+                };
+                self.spawn_replicator(
+                    url.clone(),
+                    replicator_config,
+                    None,
+                    replication_error.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        self.additional_replicator_tasks.extend(additional_tasks);
+
         let url = match &self.replicator_url {
             Some(url) => url.to_string(),
             None => {
@@ -106,19 +178,57 @@ impl Leader {
             }
         };
 
+        let replicator_config = replicators::Config {
+            other_namespaces: self.additional_upstreams.keys().cloned().collect(),
+            ..self.replicator_config.clone()
+        };
+        self.replicator_task = Some(self.spawn_replicator(
+            url,
+            replicator_config,
+            Some(ready_notification),
+            replication_error,
+        ));
+    }
+
+    /// Spawn a task that replicates from the upstream database at `url`, restarting the
+    /// replicator whenever it fails with a recoverable error.
+    ///
+    /// The replicator resumes from the replication offsets persisted in ReadySet each time it's
+    /// restarted. Consecutive restarts back off exponentially, with jitter, up to the replicator
+    /// restart timeout, so that an upstream database that's restarting isn't overwhelmed with
+    /// reconnection attempts.
+    fn spawn_replicator(
+        &self,
+        url: String,
+        replicator_config: replicators::Config,
+        ready_notification: Option<Arc<Notify>>,
+        replication_error: UnboundedSender<ReadySetError>,
+    ) -> tokio::task::JoinHandle<()> {
         let server_id = self.server_id;
         let authority = Arc::clone(&self.authority);
         let replicator_restart_timeout = self.replicator_restart_timeout;
-        self.replicator_task = Some(tokio::spawn(async move {
+        let reconnects = Arc::clone(&self.replicator_reconnects);
+        tokio::spawn(async move {
+            let initial_delay = REPLICATOR_INITIAL_RESTART_DELAY.min(replicator_restart_timeout);
+            let mut backoff: ExponentialBackoff<SystemClock> = ExponentialBackoff {
+                initial_interval: initial_delay,
+                current_interval: initial_delay,
+                max_interval: replicator_restart_timeout,
+                max_elapsed_time: None,
+                ..Default::default()
+            };
+
             loop {
+                let started = Instant::now();
                 let noria: readyset::ControllerHandle =
                     readyset::ControllerHandle::new(Arc::clone(&authority)).await;
 
                 match replicators::NoriaAdapter::start_with_url(
                     &url,
                     noria,
+                    replicator_config.clone(),
                     server_id,
-                    Some(ready_notification.clone()),
+                    ready_notification.clone(),
                 )
                 .await
                 {
@@ -131,13 +241,28 @@ impl Leader {
                         break;
                     }
                     Err(err) => {
-                        // On each replication error we wait for 30 seconds and then try again
                         tracing::error!(error = %err, "replication error");
-                        tokio::time::sleep(replicator_restart_timeout).await;
+
+                        // If the replicator ran for a while before failing, it was connected, so
+                        // this is the first of a new series of failures
+                        if started.elapsed() > replicator_restart_timeout {
+                            backoff.reset();
+                        }
+                        let delay = backoff.next_backoff().unwrap_or(replicator_restart_timeout);
+
+                        counter!(recorded::REPLICATOR_RECONNECTS, 1);
+                        {
+                            let mut reconnects = reconnects.lock();
+                            reconnects.count += 1;
+                            reconnects.last_error = Some(err.to_string());
+                        }
+
+                        info!(?delay, "Restarting replicator");
+                        tokio::time::sleep(delay).await;
                     }
                 }
             }
-        }));
+        })
     }
 
     #[allow(unused_variables)] // `query` is not used unless debug_assertions is enabled
@@ -157,6 +282,14 @@ impl Leader {
             }};
         }
 
+        // Responses from the admin API (under `/api/v1/`) are JSON rather than bincode, so that
+        // they can be consumed by tools other than the ReadySet client
+        macro_rules! return_json {
+            ($expr:expr) => {{
+                return Ok(::serde_json::to_vec(&$expr)?);
+            }};
+        }
+
         let require_leader_ready = || -> ReadySetResult<()> {
             if !leader_ready {
                 Err(ReadySetError::LeaderNotReady)
@@ -200,6 +333,14 @@ impl Leader {
                     });
                     return_serialized!(ret);
                 }
+                (&Method::GET | &Method::POST, "/table_statistics") => {
+                    let ret = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        check_quorum!(ds);
+                        ds.base_table_statistics().await
+                    });
+                    return_serialized!(ret);
+                }
                 (&Method::GET | &Method::POST, "/instances") => {
                     let ds = futures::executor::block_on(self.dataflow_state_handle.read());
                     return_serialized!(ds.get_instances());
@@ -226,6 +367,19 @@ impl Leader {
 
             // *** Read methods that do require quorum ***
 
+            if let (&Method::GET, Some(cache_path)) =
+                (&method, path.strip_prefix("/api/v1/caches/"))
+            {
+                let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                check_quorum!(ds);
+                if let Some(name) = cache_path.strip_suffix("/graph.json") {
+                    return_json!(ds.graph_json(Some(name))?);
+                }
+                if let Some(name) = cache_path.strip_suffix("/graph") {
+                    return Ok(ds.graphviz_for_query(name, true)?.into_bytes());
+                }
+            }
+
             match (&method, path) {
                 (&Method::GET | &Method::POST, "/controller_uri") => {
                     return_serialized!(self.controller_uri);
@@ -324,6 +478,18 @@ impl Leader {
                     let ret = ds.view_builder(body);
                     return_serialized!(ret);
                 }
+                (&Method::POST, "/graphviz_for_query") => {
+                    let (name, detailed): (String, bool) = bincode::deserialize(&body)?;
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return_serialized!(ds.graphviz_for_query(&name, detailed)?);
+                }
+                (&Method::POST, "/query_reuse") => {
+                    let name: String = bincode::deserialize(&body)?;
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return_serialized!(ds.query_reuse(&name)?);
+                }
                 (&Method::POST, "/get_info") => {
                     let ds = futures::executor::block_on(self.dataflow_state_handle.read());
                     check_quorum!(ds);
@@ -339,6 +505,12 @@ impl Leader {
                     })?;
                     return_serialized!(res);
                 }
+                (&Method::POST, "/table_schema_versions") => {
+                    let table: String = bincode::deserialize(&body)?;
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return_serialized!(ds.table_schema_versions(&table));
+                }
                 (&Method::POST, "/snapshotting_tables") => {
                     // this method can't be `async` since `Leader` isn't Send because `Graph`
                     // isn't Send :(
@@ -349,10 +521,137 @@ impl Leader {
                     })?;
                     return_serialized!(res);
                 }
+                (&Method::POST, "/snapshot_progress") => {
+                    let res: HashMap<String, SnapshotProgress> =
+                        futures::executor::block_on(authority.try_read(SNAPSHOT_PROGRESS_PATH))
+                            .map_err(|e| {
+                                internal_err(format!("Unable to read snapshot progress: {}", e))
+                            })?
+                            .unwrap_or_default();
+                    return_serialized!(res);
+                }
+                (&Method::POST, "/replication_slot_status") => {
+                    let res: Option<ReplicationSlotStatus> = futures::executor::block_on(
+                        authority.try_read(REPLICATION_SLOT_STATUS_PATH),
+                    )
+                    .map_err(|e| {
+                        internal_err(format!("Unable to read replication slot status: {}", e))
+                    })?
+                    .flatten();
+                    return_serialized!(res);
+                }
+                (&Method::POST, "/auto_increments") => {
+                    let res: HashMap<String, u64> =
+                        futures::executor::block_on(authority.try_read(AUTO_INCREMENTS_PATH))
+                            .map_err(|e| {
+                                internal_err(format!("Unable to read auto-increments: {}", e))
+                            })?
+                            .unwrap_or_default();
+                    return_serialized!(res);
+                }
+                (&Method::POST, "/namespace_schema_replication_offset") => {
+                    let namespace: String = bincode::deserialize(&body)?;
+                    let mut offsets: HashMap<String, ReplicationOffset> =
+                        futures::executor::block_on(
+                            authority.try_read(NAMESPACE_SCHEMA_OFFSETS_PATH),
+                        )
+                        .map_err(|e| {
+                            internal_err(format!(
+                                "Unable to read schema replication offsets: {}",
+                                e
+                            ))
+                        })?
+                        .unwrap_or_default();
+                    return_serialized!(offsets.remove(&namespace));
+                }
+                (&Method::POST, "/compact_table") => {
+                    let table: String = bincode::deserialize(&body)?;
+                    let res = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        check_quorum!(ds);
+                        ds.compact_table(&table).await
+                    })?;
+                    return_serialized!(res);
+                }
+                (&Method::POST, "/flush_table") => {
+                    let table: String = bincode::deserialize(&body)?;
+                    let res = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        check_quorum!(ds);
+                        ds.flush_table(&table).await
+                    })?;
+                    return_serialized!(res);
+                }
+                (&Method::POST, "/refresh_table_statistics") => {
+                    let table: String = bincode::deserialize(&body)?;
+                    let res = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        check_quorum!(ds);
+                        ds.refresh_table_statistics(&table).await
+                    })?;
+                    return_serialized!(res);
+                }
+                (&Method::POST, "/set_table_compression") => {
+                    let (table, options): (String, CompressionOptions) =
+                        bincode::deserialize(&body)?;
+                    let res = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        check_quorum!(ds);
+                        ds.set_table_compression(&table, options).await
+                    })?;
+                    return_serialized!(res);
+                }
                 (&Method::POST, "/leader_ready") => {
                     return_serialized!(leader_ready);
                 }
                 (&Method::POST, "/status") => {
+                    let (replicator_reconnects, last_replicator_error) = {
+                        let reconnects = self.replicator_reconnects.lock();
+                        (reconnects.count, reconnects.last_error.clone())
+                    };
+                    let snapshot_progress: HashMap<String, SnapshotProgress> =
+                        futures::executor::block_on(authority.try_read(SNAPSHOT_PROGRESS_PATH))
+                            .map_err(|e| {
+                                internal_err(format!("Unable to read snapshot progress: {}", e))
+                            })?
+                            .unwrap_or_default();
+                    let (stats, offsets, snapshotting_tables) =
+                        futures::executor::block_on(async move {
+                            let ds = self.dataflow_state_handle.read().await;
+                            ReadySetResult::Ok((
+                                ds.get_statistics().await?,
+                                ds.replication_offsets().await?,
+                                ds.snapshotting_tables().await?,
+                            ))
+                        })?;
+
+                    let table_snapshot_progress = snapshotting_tables
+                        .into_iter()
+                        .map(|table| {
+                            let percent =
+                                snapshot_progress.get(table.as_str()).and_then(|progress| {
+                                    progress.total_rows.filter(|total| *total > 0).map(|total| {
+                                        (progress.rows_copied as f64 / total as f64 * 100.)
+                                            .min(100.)
+                                    })
+                                });
+                            (table.to_string(), percent)
+                        })
+                        .collect();
+
+                    let mut total_memory_bytes = 0;
+                    let mut partial_memory_bytes = 0;
+                    let mut evictions = 0;
+                    for (domain_stats, node_stats) in stats.values() {
+                        evictions += domain_stats.evictions;
+                        for node in node_stats.values() {
+                            total_memory_bytes += node.mem_size;
+                            if matches!(node.materialized, MaterializationStatus::Partial { .. }) {
+                                partial_memory_bytes += node.mem_size;
+                            }
+                        }
+                    }
+
                     let status = ReadySetStatus {
                         // Use whether the leader is ready or not as a proxy for if we have
                         // completed snapshotting.
@@ -361,9 +660,42 @@ impl Leader {
                         } else {
                             SnapshotStatus::InProgress
                         },
+                        table_snapshot_progress,
+                        replicator_reconnects,
+                        last_replicator_error,
+                        replication_offset: offsets.min_present_offset().ok().flatten().cloned(),
+                        replication_lag_bytes: None,
+                        total_memory_bytes,
+                        partial_memory_bytes,
+                        evictions,
+                        controller_leader: Some(self.controller_uri.to_string()),
                     };
                     return_serialized!(status);
                 }
+                (&Method::GET, "/api/v1/caches") => {
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return_json!(ds
+                        .verbose_outputs()
+                        .into_iter()
+                        .map(|(name, query)| {
+                            serde_json::json!({
+                                "name": name.as_str(),
+                                "query": query.to_string(),
+                            })
+                        })
+                        .collect::<Vec<_>>())
+                }
+                (&Method::GET, "/api/v1/graph") => {
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return Ok(ds.graphviz(true).into_bytes());
+                }
+                (&Method::GET, "/api/v1/graph.json") => {
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return_json!(ds.graph_json(None)?)
+                }
                 (&Method::POST, "/dry_run") => {
                     let body: ExtendRecipeSpec = bincode::deserialize(&body)?;
                     if body.require_leader_ready {
@@ -379,6 +711,18 @@ impl Leader {
                     })?;
                     return_serialized!(ret);
                 }
+                (&Method::POST, "/explain_migration") => {
+                    let changes: ChangeList = bincode::deserialize(&body)?;
+                    let ret = futures::executor::block_on(async move {
+                        let mut state_copy: DataflowState = {
+                            let reader = self.dataflow_state_handle.read().await;
+                            check_quorum!(reader);
+                            reader.clone()
+                        };
+                        state_copy.explain_migration(changes).await
+                    })?;
+                    return_serialized!(ret);
+                }
 
                 _ => {}
             }
@@ -386,6 +730,17 @@ impl Leader {
 
         // *** Write methods (all of them require quorum) ***
 
+        if let (&Method::DELETE, Some(name)) = (&method, path.strip_prefix("/api/v1/caches/")) {
+            require_leader_ready()?;
+            futures::executor::block_on(async move {
+                let mut writer = self.dataflow_state_handle.write().await;
+                check_quorum!(writer.as_ref());
+                writer.as_mut().remove_query(name).await?;
+                self.dataflow_state_handle.commit(writer, authority).await
+            })?;
+            return_json!(serde_json::json!({ "dropped": name }));
+        }
+
         match (method, path) {
             (Method::GET, "/flush_partial") => {
                 let ret = futures::executor::block_on(async move {
@@ -411,6 +766,56 @@ impl Leader {
                 })?;
                 return_serialized!(ret);
             }
+            (Method::POST, "/api/v1/caches") => {
+                require_leader_ready()?;
+                let changes: ChangeList = std::str::from_utf8(&body)
+                    .map_err(|_| bad_request_err("Request body must be valid UTF-8"))?
+                    .parse()
+                    .map_err(|e: ReadySetError| bad_request_err(e.to_string()))?;
+                // Caches can't be created from a query id, since the ids of proxied queries are
+                // only known to the adapters
+                if !changes.changes.iter().all(|change| {
+                    matches!(
+                        change,
+                        Change::CreateCache(CreateCacheStatement {
+                            inner: CacheInner::Statement(_),
+                            ..
+                        })
+                    )
+                }) {
+                    return Err(bad_request_err(
+                        "Request body must only contain CREATE CACHE ... FROM SELECT statements",
+                    ));
+                }
+                let ret = futures::executor::block_on(async move {
+                    let mut writer = self.dataflow_state_handle.write().await;
+                    check_quorum!(writer.as_ref());
+                    let spec = ExtendRecipeSpec {
+                        changes,
+                        ..Default::default()
+                    };
+                    let r = writer.as_mut().extend_recipe(spec, false).await?;
+                    self.dataflow_state_handle.commit(writer, authority).await?;
+                    Ok(r)
+                })?;
+                return_json!(serde_json::json!({
+                    "created": ret.new_nodes.keys().map(|name| name.as_str()).collect::<Vec<_>>(),
+                }));
+            }
+            (Method::POST, "/create_caches") => {
+                require_leader_ready()?;
+                let changes: ChangeList = bincode::deserialize(&body)?;
+                let ret = futures::executor::block_on(async move {
+                    let mut writer = self.dataflow_state_handle.write().await;
+                    check_quorum!(writer.as_ref());
+                    let r = writer.as_mut().create_caches(changes).await?;
+                    if r.iter().all(|status| status.error.is_none()) {
+                        self.dataflow_state_handle.commit(writer, authority).await?;
+                    }
+                    Ok(r)
+                })?;
+                return_serialized!(ret);
+            }
             (Method::POST, "/remove_query") => {
                 require_leader_ready()?;
                 let query_name = bincode::deserialize(&body)?;
@@ -433,6 +838,81 @@ impl Leader {
                 })?;
                 return_serialized!(ret);
             }
+            (Method::POST, "/set_snapshot_progress") => {
+                let (table, progress): (String, Option<SnapshotProgress>) =
+                    bincode::deserialize(&body)?;
+                futures::executor::block_on(authority.read_modify_write(
+                    SNAPSHOT_PROGRESS_PATH,
+                    |all: Option<HashMap<String, SnapshotProgress>>| {
+                        let mut all = all.unwrap_or_default();
+                        match &progress {
+                            Some(progress) => {
+                                all.insert(table.clone(), progress.clone());
+                            }
+                            None => {
+                                all.remove(&table);
+                            }
+                        }
+                        Ok::<_, ()>(all)
+                    },
+                ))
+                .map_err(|e| internal_err(format!("Unable to write snapshot progress: {}", e)))?
+                .map_err(|_| internal_err("Unable to write snapshot progress"))?;
+                return_serialized!(());
+            }
+            (Method::POST, "/set_replication_slot_status") => {
+                let status: Option<ReplicationSlotStatus> = bincode::deserialize(&body)?;
+                futures::executor::block_on(authority.read_modify_write(
+                    REPLICATION_SLOT_STATUS_PATH,
+                    |_: Option<Option<ReplicationSlotStatus>>| Ok::<_, ()>(status.clone()),
+                ))
+                .map_err(|e| {
+                    internal_err(format!("Unable to write replication slot status: {}", e))
+                })?
+                .map_err(|_| internal_err("Unable to write replication slot status"))?;
+                return_serialized!(());
+            }
+            (Method::POST, "/record_auto_increments") => {
+                let values: HashMap<String, u64> = bincode::deserialize(&body)?;
+                futures::executor::block_on(authority.read_modify_write(
+                    AUTO_INCREMENTS_PATH,
+                    |all: Option<HashMap<String, u64>>| {
+                        let mut all = all.unwrap_or_default();
+                        for (table, value) in &values {
+                            let recorded = all.entry(table.clone()).or_default();
+                            *recorded = (*recorded).max(*value);
+                        }
+                        Ok::<_, ()>(all)
+                    },
+                ))
+                .map_err(|e| internal_err(format!("Unable to write auto-increments: {}", e)))?
+                .map_err(|_| internal_err("Unable to write auto-increments"))?;
+                return_serialized!(());
+            }
+            (Method::POST, "/set_namespace_schema_replication_offset") => {
+                let (namespace, offset): (String, Option<ReplicationOffset>) =
+                    bincode::deserialize(&body)?;
+                futures::executor::block_on(authority.read_modify_write(
+                    NAMESPACE_SCHEMA_OFFSETS_PATH,
+                    |all: Option<HashMap<String, ReplicationOffset>>| {
+                        let mut all = all.unwrap_or_default();
+                        match &offset {
+                            Some(offset) => {
+                                all.insert(namespace.clone(), offset.clone());
+                            }
+                            None => {
+                                all.remove(&namespace);
+                            }
+                        }
+                        Ok::<_, ()>(all)
+                    },
+                ))
+                .map_err(|e| {
+                    internal_err(format!("Unable to write schema replication offset: {}", e))
+                })?
+                .map_err(|_| internal_err("Unable to write schema replication offset"))?;
+                return_serialized!(());
+            }
             (Method::POST, "/replicate_readers") => {
                 let body = bincode::deserialize(&body)?;
                 let ret = futures::executor::block_on(async move {
@@ -444,6 +924,26 @@ impl Leader {
                 })?;
                 return_serialized!(ret);
             }
+            (Method::POST, "/drain_worker") => {
+                let worker: WorkerIdentifier = bincode::deserialize(&body)?;
+                futures::executor::block_on(async move {
+                    let mut writer = self.dataflow_state_handle.write().await;
+                    check_quorum!(writer.as_ref());
+                    writer.as_mut().drain_worker(&worker).await?;
+                    self.dataflow_state_handle.commit(writer, authority).await
+                })?;
+                return_serialized!(());
+            }
+            (Method::POST, "/set_table_sharding") => {
+                let (table, column): (SqlIdentifier, SqlIdentifier) = bincode::deserialize(&body)?;
+                futures::executor::block_on(async move {
+                    let mut writer = self.dataflow_state_handle.write().await;
+                    check_quorum!(writer.as_ref());
+                    writer.as_mut().set_table_sharding(table, column)?;
+                    self.dataflow_state_handle.commit(writer, authority).await
+                })?;
+                return_serialized!(());
+            }
             (Method::POST, "/remove_node") => {
                 require_leader_ready()?;
                 let body = bincode::deserialize(&body)?;
@@ -467,6 +967,7 @@ impl Leader {
         let mut writer = self.dataflow_state_handle.write().await;
         let ds = writer.as_mut();
 
+        let mut new_workers = Vec::new();
         for desc in workers {
             let WorkerDescriptor {
                 worker_uri,
@@ -524,7 +1025,8 @@ impl Leader {
             }
 
             ds.workers.insert(worker_uri.clone(), ws);
-            ds.read_addrs.insert(worker_uri, reader_addr);
+            ds.read_addrs.insert(worker_uri.clone(), reader_addr);
+            new_workers.push(worker_uri);
 
             info!(
                 "now have {} of {} required workers",
@@ -535,13 +1037,26 @@ impl Leader {
 
         if ds.workers.len() >= self.quorum && self.pending_recovery {
             self.pending_recovery = false;
-            let domain_nodes = ds
-                .domain_nodes
-                .iter()
-                .map(|(k, v)| (*k, v.values().copied().collect::<HashSet<_>>()))
-                .collect::<HashMap<_, _>>();
-            ds.recover(&domain_nodes).await?;
+            // Every domain needs to be recovered, including the ones a previous leader deferred -
+            // those that can't be placed on the workers we have yet stay deferred
+            for (domain_index, nodes) in &ds.domain_nodes {
+                ds.deferred_recovery
+                    .entry(*domain_index)
+                    .or_default()
+                    .extend(nodes.values().copied());
+            }
+            ds.recover_deferred().await?;
             info!("Finished restoring graph configuration");
+        } else if !self.pending_recovery {
+            // Now that there's a new worker, we may be able to recover domains which couldn't be
+            // placed when the workers running them failed
+            ds.recover_deferred().await?;
+        }
+
+        if !self.pending_recovery && self.rebalance_on_join {
+            for worker in &new_workers {
+                ds.rebalance_onto(worker).await?;
+            }
         }
 
         self.dataflow_state_handle
@@ -557,14 +1072,13 @@ impl Leader {
         let ds = writer.as_mut();
 
         // first, translate from the affected workers to affected data-flow nodes
-        let mut affected_nodes = HashMap::new();
         for wi in failed {
             warn!(worker = ?wi, "handling failure of worker");
             let mut domain_nodes_on_worker = ds.nodes_on_worker(Some(&wi));
             for (domain_index, node_indices) in domain_nodes_on_worker.drain() {
                 ds.domains.remove(&domain_index);
                 ds.materializations.remove_nodes(&node_indices);
-                affected_nodes
+                ds.deferred_recovery
                     .entry(domain_index)
                     .or_insert_with(|| HashSet::new())
                     .extend(node_indices);
@@ -572,7 +1086,10 @@ impl Leader {
             ds.workers.remove(&wi);
         }
 
-        ds.recover(&affected_nodes).await?;
+        // then, recover those nodes onto the remaining workers. Domains with base tables whose
+        // persisted state is on a failed worker's volume can't be placed until a worker with that
+        // volume registers again, so their recovery is deferred until then.
+        ds.recover_deferred().await?;
 
         self.dataflow_state_handle
             .commit(writer, &self.authority)
@@ -586,6 +1103,8 @@ impl Leader {
         authority: Arc<Authority>,
         replicator_url: Option<String>,
         server_id: Option<u32>,
+        replicator_config: replicators::Config,
+        additional_upstreams: BTreeMap<String, String>,
         worker_request_timeout: Duration,
         replicator_restart_timeout: Duration,
     ) -> Self {
@@ -608,10 +1127,16 @@ impl Leader {
 
             controller_uri,
 
+            rebalance_on_join: state.config.rebalance_on_join,
+
             replicator_url,
             replicator_task: None,
+            additional_upstreams,
+            additional_replicator_tasks: vec![],
+            replicator_reconnects: Default::default(),
             authority,
             server_id,
+            replicator_config,
             worker_request_timeout,
             replicator_restart_timeout,
         }
@@ -625,11 +1150,16 @@ pub(super) fn request_type(req: &ControllerRequest) -> ControllerRequestType {
         (&Method::GET, "/flush_partial")
         | (&Method::GET | &Method::POST, "/controller_uri")
         | (&Method::POST, "/extend_recipe")
+        | (&Method::POST, "/create_caches")
         | (&Method::POST, "/remove_query")
         | (&Method::POST, "/set_replication_offset")
         | (&Method::POST, "/replicate_readers")
-        | (&Method::POST, "/remove_node") => ControllerRequestType::Write,
-        (&Method::POST, "/dry_run") => ControllerRequestType::DryRun,
+        | (&Method::POST, "/remove_node")
+        | (&Method::POST, "/drain_worker")
+        | (&Method::POST, "/set_table_sharding") => ControllerRequestType::Write,
+        (&Method::POST, "/dry_run") | (&Method::POST, "/explain_migration") => {
+            ControllerRequestType::DryRun
+        }
         _ => ControllerRequestType::Read,
     }
 }
@@ -595,6 +595,7 @@ mod tests {
     use dataflow::utils::make_columns;
     use dataflow::{node, ops};
     use nom_sql::OrderType;
+    use readyset_data::NullOrder;
 
     use super::*;
 
@@ -1130,6 +1131,7 @@ mod tests {
             ops::NodeOperator::Paginate(ops::paginate::Paginate::new(
                 a,
                 vec![(0, OrderType::OrderAscending)],
+                NullOrder::default(),
                 vec![1],
                 3,
             )),
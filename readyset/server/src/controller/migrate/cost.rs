@@ -0,0 +1,161 @@
+//! Estimates of the memory the state of the nodes added by a migration will use, made while the
+//! migration is being planned from statistics about the base tables the nodes read from, so that
+//! migrations which would use too much memory can be caught before they're applied.
+//!
+//! The estimates are deliberately simple upper bounds:
+//!
+//! - Only fully materialized nodes are counted, since partially materialized state starts out empty
+//!   and only holds the keys that are actually read.
+//! - The number of rows of each node is estimated from the number of rows of its parents. Joins are
+//!   assumed to produce as many rows as their largest parent (as joins along a foreign key do),
+//!   unions as many rows as all of their parents combined, and every other operator as many rows as
+//!   its parent. Filters never produce more rows than their parent, so this overestimates the size
+//!   of their state.
+//! - Aggregations produce one row per group, so when all the columns they group by are copied
+//!   unmodified from base table columns, they're assumed to produce as many rows as there are
+//!   combinations of distinct values of those columns (if that's fewer than their parent's rows).
+//! - The size of each row is estimated as the number of columns of the node times the average size
+//!   of a value in the base tables it reads from (the largest average, if it reads from several).
+
+use std::collections::HashMap;
+
+use dataflow::prelude::*;
+use petgraph::graph::NodeIndex;
+
+/// Statistics about a base table, summed over all of its shards
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(in crate::controller) struct TableStatistics {
+    /// The (estimated) number of rows in the table
+    pub(in crate::controller) rows: u64,
+    /// The (estimated) size of the table's data, in bytes
+    pub(in crate::controller) bytes: u64,
+    /// The (estimated) number of distinct values in each of the table's columns, in order
+    pub(in crate::controller) distinct: Vec<u64>,
+}
+
+/// The estimated size of the output of a node
+#[derive(Debug, Clone, Copy, Default)]
+struct SizeEstimate {
+    rows: u64,
+    /// The average size of a single value, in bytes
+    value_bytes: f64,
+}
+
+impl SizeEstimate {
+    fn state_bytes(&self, columns: usize) -> u64 {
+        (self.rows as f64 * self.value_bytes * columns as f64) as u64
+    }
+}
+
+/// Returns the estimated number of distinct values in `column` of the node `ni`, if that column is
+/// an unmodified copy of a column of a single base table
+fn distinct_values(
+    graph: &Graph,
+    ni: NodeIndex,
+    column: usize,
+    tables: &HashMap<NodeIndex, TableStatistics>,
+) -> Option<u64> {
+    #[allow(clippy::indexing_slicing)] // came from the graph
+    let node = &graph[ni];
+    if node.is_base() {
+        return tables.get(&ni)?.distinct.get(column).copied();
+    }
+
+    let (parent, column) = if node.is_internal() {
+        match node.resolve(column)?.as_slice() {
+            [source] => *source,
+            _ => return None,
+        }
+    } else {
+        // Ingress, egress, sharder and reader nodes have the same columns as their parent
+        let mut parents = graph.neighbors_directed(ni, petgraph::EdgeDirection::Incoming);
+        match (parents.next(), parents.next()) {
+            (Some(parent), None) => (parent, column),
+            _ => return None,
+        }
+    };
+    distinct_values(graph, parent, column, tables)
+}
+
+fn estimate_output(
+    graph: &Graph,
+    ni: NodeIndex,
+    tables: &HashMap<NodeIndex, TableStatistics>,
+    estimates: &mut HashMap<NodeIndex, SizeEstimate>,
+) -> SizeEstimate {
+    if let Some(estimate) = estimates.get(&ni) {
+        return *estimate;
+    }
+
+    #[allow(clippy::indexing_slicing)] // came from the graph
+    let node = &graph[ni];
+    let estimate = if node.is_base() {
+        let stats = tables.get(&ni).cloned().unwrap_or_default();
+        let values = stats.rows * node.columns().len() as u64;
+        SizeEstimate {
+            rows: stats.rows,
+            value_bytes: if values == 0 {
+                0.0
+            } else {
+                stats.bytes as f64 / values as f64
+            },
+        }
+    } else {
+        let parents = graph
+            .neighbors_directed(ni, petgraph::EdgeDirection::Incoming)
+            .map(|parent| estimate_output(graph, parent, tables, estimates))
+            .collect::<Vec<_>>();
+        let parent_rows = parents.iter().map(|p| p.rows).max().unwrap_or(0);
+        let rows = match node.as_internal() {
+            Some(NodeOperator::Union(_)) => parents.iter().map(|p| p.rows).sum(),
+            Some(
+                NodeOperator::Aggregation(_) | NodeOperator::Extremum(_) | NodeOperator::Concat(_),
+            ) => node
+                .suggest_indexes(ni)
+                .get(&ni)
+                .and_then(|index| {
+                    index
+                        .index()
+                        .columns
+                        .iter()
+                        .map(|&col| distinct_values(graph, ni, col, tables))
+                        .try_fold(1u64, |groups, distinct| {
+                            Some(groups.saturating_mul(distinct?))
+                        })
+                })
+                .map_or(parent_rows, |groups| groups.min(parent_rows)),
+            _ => parent_rows,
+        };
+        let value_bytes = parents.iter().map(|p| p.value_bytes).fold(0.0, f64::max);
+        SizeEstimate { rows, value_bytes }
+    };
+
+    estimates.insert(ni, estimate);
+    estimate
+}
+
+/// Estimates the memory, in bytes, that the state of each of `nodes` in `graph` for which
+/// `is_fully_materialized` returns true will use, given `tables`, statistics about the base tables
+/// in the graph
+pub(in crate::controller) fn estimate_state_sizes<I, F>(
+    graph: &Graph,
+    nodes: I,
+    is_fully_materialized: F,
+    tables: &HashMap<NodeIndex, TableStatistics>,
+) -> HashMap<NodeIndex, u64>
+where
+    I: IntoIterator<Item = NodeIndex>,
+    F: Fn(NodeIndex) -> bool,
+{
+    let mut estimates = HashMap::new();
+    nodes
+        .into_iter()
+        .filter(|&ni| is_fully_materialized(ni))
+        .map(|ni| {
+            let estimate = estimate_output(graph, ni, tables, &mut estimates);
+            #[allow(clippy::indexing_slicing)] // came from the graph
+            let columns = graph[ni].columns().len();
+            (ni, estimate.state_bytes(columns))
+        })
+        .collect()
+}
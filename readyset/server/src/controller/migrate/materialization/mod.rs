@@ -96,6 +96,22 @@ pub struct Config {
     ///
     /// Defaults to true.
     pub partial_enabled: bool,
+
+    /// The maximum memory, in bytes, that the state of the nodes added by a single migration may
+    /// be estimated to use, based on the sizes of the base tables they read from. Migrations
+    /// that exceed the budget are logged with a warning, or refused if
+    /// [`Self::enforce_memory_budget`] is set.
+    ///
+    /// Defaults to `None`, for no budget
+    #[serde(default)]
+    pub migration_memory_budget: Option<u64>,
+
+    /// Whether migrations that exceed the [`Self::migration_memory_budget`] are refused with
+    /// [`ReadySetError::MigrationMemoryBudgetExceeded`], rather than just logged with a warning.
+    ///
+    /// Defaults to false
+    #[serde(default)]
+    pub enforce_memory_budget: bool,
 }
 
 impl Default for Config {
@@ -105,6 +121,8 @@ impl Default for Config {
             allow_full_materialization: true,
             partial_enabled: true,
             frontier_strategy: FrontierStrategy::None,
+            migration_memory_budget: None,
+            enforce_memory_budget: false,
         }
     }
 }
@@ -710,6 +728,18 @@ impl Materializations {
         Ok(())
     }
 
+    /// Returns the indexes of the given node, sorted, or an empty list if the node isn't
+    /// materialized.
+    pub(in crate::controller) fn indexes(&self, index: NodeIndex) -> Vec<&Index> {
+        let mut indexes = self
+            .have
+            .get(&index)
+            .map(|indexes| indexes.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        indexes.sort();
+        indexes
+    }
+
     /// Retrieves the materialization status of a given node, or None
     /// if the node isn't materialized.
     pub(in crate::controller) fn get_status(
@@ -864,7 +894,10 @@ impl Materializations {
                                                 // should
                                                 // never happen.
                                                 // This code should probably just be taken out soon.
-                                                println!("{}", graphviz(graph, true, self, None));
+                                                println!(
+                                                    "{}",
+                                                    graphviz(graph, true, self, None, None)
+                                                );
                                                 error!(
                                                     parent = %node.index(),
                                                     parent_index = ?parent_index,
@@ -900,7 +933,7 @@ impl Materializations {
             }
             while let Some(ni) = non_purge.pop() {
                 if graph[ni].purge {
-                    println!("{}", graphviz(graph, true, self, None));
+                    println!("{}", graphviz(graph, true, self, None, None));
                     internal!("found purge node {} above non-purge node", ni.index())
                 }
                 if self.have.contains_key(&ni) {
@@ -1038,7 +1071,7 @@ impl Materializations {
                             != self.have.get(&child).map(|i| i.len()).unwrap_or(0)
                         {
                             // node was previously materialized!
-                            println!("{}", graphviz(graph, true, self, None));
+                            println!("{}", graphviz(graph, true, self, None, None));
                             error!(
                                 node = %node.index(),
                                 child = %child.index(),
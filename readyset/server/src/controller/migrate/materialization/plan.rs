@@ -494,7 +494,7 @@ impl<'a> Plan<'a> {
                 //  a domain may appear multiple times in this list if a path crosses into the same
                 //  domain more than once. currently, that will cause a deadlock.
                 if seen.contains(&domain) {
-                    trace!("{}", graphviz(self.graph, true, self.m, None));
+                    trace!("{}", graphviz(self.graph, true, self.m, None, None));
                     internal!("detected A-B-A domain replay path");
                 }
                 seen.insert(domain);
@@ -42,8 +42,9 @@ use metrics::{counter, histogram};
 use nom_sql::SqlIdentifier;
 use readyset::metrics::recorded;
 use readyset::{KeyColumnIdx, ReadySetError, ViewPlaceholder};
-use tracing::{debug, debug_span, error, info, info_span, instrument, trace};
+use tracing::{debug, debug_span, error, info, info_span, instrument, trace, warn};
 
+use crate::controller::migrate::cost::TableStatistics;
 use crate::controller::migrate::materialization::InvalidEdge;
 use crate::controller::migrate::node_changes::{MigrationNodeChanges, NodeChanges};
 use crate::controller::migrate::scheduling::Scheduler;
@@ -52,6 +53,7 @@ use crate::controller::WorkerIdentifier;
 
 pub(crate) mod assignment;
 mod augmentation;
+pub(in crate::controller) mod cost;
 pub(crate) mod materialization;
 pub(in crate::controller) mod node_changes;
 pub(in crate::controller) mod routing;
@@ -168,6 +170,52 @@ pub struct MigrationPlan<'dataflow> {
 }
 
 impl<'dataflow> MigrationPlan<'dataflow> {
+    /// Estimates the memory the state of `new_nodes` will use once the plan is applied, given
+    /// `tables`, statistics about the base tables in the graph, and checks it against `budget`.
+    ///
+    /// Exceeding the budget is only logged with a warning, unless the budget is configured to be
+    /// enforced, in which case [`ReadySetError::MigrationMemoryBudgetExceeded`] is returned.
+    fn check_memory_budget(
+        &self,
+        new_nodes: HashSet<NodeIndex>,
+        tables: &HashMap<NodeIndex, TableStatistics>,
+        budget: u64,
+    ) -> ReadySetResult<()> {
+        let graph = &self.dataflow_state.ingredients;
+        let materializations = &self.dataflow_state.materializations;
+        #[allow(clippy::indexing_slicing)] // new nodes are in the graph
+        let sizes = cost::estimate_state_sizes(
+            graph,
+            new_nodes,
+            |ni| {
+                !graph[ni].is_base()
+                    && matches!(
+                        materializations.get_status(ni, &graph[ni]),
+                        MaterializationStatus::Full
+                    )
+            },
+            tables,
+        );
+        let estimated_bytes = sizes.values().sum::<u64>();
+        debug!(estimated_bytes, ?sizes, "estimated migration memory");
+
+        if estimated_bytes <= budget {
+            return Ok(());
+        }
+        if materializations.config.enforce_memory_budget {
+            return Err(ReadySetError::MigrationMemoryBudgetExceeded {
+                estimated_bytes,
+                budget_bytes: budget,
+            });
+        }
+        warn!(
+            estimated_bytes,
+            budget_bytes = budget,
+            "Migration is estimated to use more memory than its budget"
+        );
+        Ok(())
+    }
+
     /// Apply the migration plan to the provided `Leader`.
     ///
     /// If the plan fails, the `Leader`'s state is left unchanged; however, no attempt
@@ -564,6 +612,25 @@ impl<'dataflow> Migration<'dataflow> {
         Ok(())
     }
 
+    /// Change the type of an existing column of a base node, without changing its position.
+    ///
+    /// Values already stored in the base are left as they are; only values written after the
+    /// change are expected to be of the new type.
+    pub fn retype_column(
+        &mut self,
+        node: NodeIndex,
+        column: usize,
+        new_column: Column,
+    ) -> ReadySetResult<()> {
+        // not allowed to retype columns of new nodes
+        invariant!(!self.changes.contains_new(&node));
+
+        #[allow(clippy::indexing_slicing)] // NodeIndex must exist in ingredients
+        let base = &mut self.dataflow_state.ingredients[node];
+        invariant!(base.is_base());
+        base.set_column(column, new_column)
+    }
+
     #[cfg(test)]
     pub(crate) fn graph(&self) -> &Graph {
         &self.dataflow_state.ingredients
@@ -664,11 +731,37 @@ impl<'dataflow> Migration<'dataflow> {
     pub(super) async fn commit(self, dry_run: bool) -> ReadySetResult<()> {
         let start = self.start;
 
+        // The statistics about base tables needed to estimate the memory the migration will use
+        // have to be fetched from the domains before planning, since planning can't wait on them
+        let memory_budget = self
+            .dataflow_state
+            .materializations
+            .config
+            .migration_memory_budget;
+        let table_statistics = match memory_budget {
+            Some(_) => self
+                .dataflow_state
+                .table_statistics()
+                .await
+                .map_err(|error| {
+                    warn!(%error, "Could not get table statistics to estimate migration memory")
+                })
+                .ok(),
+            None => None,
+        };
+        let new_nodes = self.changes.new_nodes();
+
         let plan = self
             .plan()
             .map_err(|e| ReadySetError::MigrationPlanFailed {
                 source: Box::new(e),
             })?;
+        if let (Some(budget), Some(tables)) = (memory_budget, table_statistics) {
+            plan.check_memory_budget(new_nodes, &tables, budget)
+                .map_err(|e| ReadySetError::MigrationPlanFailed {
+                    source: Box::new(e),
+                })?;
+        }
         // We skip the actual migration when we run in dry-run mode.
         if dry_run {
             return Ok(());
@@ -758,6 +851,7 @@ fn plan_add_nodes(
             &mut new_nodes,
             &topo,
             shards,
+            &dataflow_state.table_sharding,
         )?;
         topo = t;
 
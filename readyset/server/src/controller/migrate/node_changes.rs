@@ -81,6 +81,19 @@ impl MigrationNodeChanges {
         }
         found
     }
+
+    /// Returns the nodes being added, excluding the ones that are dropped again after they're
+    /// added.
+    pub(in crate::controller) fn new_nodes(&self) -> HashSet<NodeIndex> {
+        let mut new_nodes = HashSet::new();
+        for nc in self.0.iter() {
+            match nc {
+                NodeChanges::Add(nodes) => new_nodes.extend(nodes),
+                NodeChanges::Drop(nodes) => new_nodes.retain(|ni| !nodes.contains(ni)),
+            }
+        }
+        new_nodes
+    }
 }
 
 impl IntoIterator for MigrationNodeChanges {
@@ -145,4 +158,21 @@ mod tests {
         assert!(changes.contains_new(&NodeIndex::new(1)));
         assert!(!changes.contains_new(&NodeIndex::new(5)));
     }
+
+    #[test]
+    fn new_nodes() {
+        let mut changes = MigrationNodeChanges::default();
+        changes.add_node(NodeIndex::new(1));
+        changes.add_node(NodeIndex::new(2));
+        changes.drop_node(NodeIndex::new(1));
+        changes.drop_node(NodeIndex::new(3));
+        changes.add_node(NodeIndex::new(4));
+
+        assert_eq!(
+            changes.new_nodes(),
+            vec![NodeIndex::new(2), NodeIndex::new(4)]
+                .into_iter()
+                .collect()
+        );
+    }
 }
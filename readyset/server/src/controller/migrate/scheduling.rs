@@ -2,9 +2,10 @@
 //!
 //! The domain scheduling algorithm, which is currently quite simplistic, works as follows:
 //!
-//! 1. We filter the set of workers in the cluster by two criteria:
-//!    a. The worker must be healhty, and
-//!    b. The worker can be [configured to only run reader nodes][reader_only], in which case only
+//! 1. We filter the set of workers in the cluster by three criteria:
+//!    a. The worker must be healhty,
+//!    b. The worker must not be [draining][draining], and
+//!    c. The worker can be [configured to only run reader nodes][reader_only], in which case only
 //!       domains that contain a reader node can run on that worker
 //! 2. Migrations can optionally [be restricted to a single worker][worker] - if so, all
 //!    shards of all domains within the migration will be scheduled to that worker, *if* it's valid
@@ -15,6 +16,7 @@
 //!       other base tables, or otherwise
 //!    c. Run it on the worker that has the smallest number of domain shards scheduled onto it
 //!
+//! [draining]: Worker::draining
 //! [reader_only]: Worker::reader_only
 //! [worker]: Migration::worker
 //! [placement restrictions]: DomainPlacementRestriction
@@ -62,7 +64,7 @@ impl<'state> Scheduler<'state> {
         let valid_workers = dataflow_state
             .workers
             .iter()
-            .filter(|(_, w)| w.healthy)
+            .filter(|(_, w)| w.healthy && !w.draining)
             .filter(|(wi, _)| worker.iter().all(|target_worker| *target_worker == **wi))
             .collect();
 
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 
 use dataflow::prelude::*;
 use dataflow::{node, ops};
+use nom_sql::SqlIdentifier;
 use readyset_errors::{internal, invariant, invariant_eq, ReadySetResult};
 use petgraph::graph::NodeIndex;
 use tracing::{debug, error, info_span, trace};
@@ -12,6 +13,7 @@ pub fn shard(
     new: &mut HashSet<NodeIndex>,
     topo_list: &[NodeIndex],
     sharding_factor: usize,
+    table_sharding: &HashMap<SqlIdentifier, SqlIdentifier>,
 ) -> ReadySetResult<(Vec<NodeIndex>, HashMap<(NodeIndex, NodeIndex), NodeIndex>)> {
     // we must keep track of changes we make to the parent of a node, since this remapping must be
     // communicated to the nodes so they know the true identifier of their parent in the graph.
@@ -27,6 +29,21 @@ pub fn shard(
             .map(|ni| (ni, graph[ni].sharded_by()))
             .collect();
 
+        // base tables can be explicitly sharded by a column other than the one they'd otherwise
+        // be sharded by
+        if graph[node].is_base() {
+            if let Some(col) = table_sharding.get(graph[node].name()).and_then(|column| {
+                graph[node]
+                    .columns()
+                    .iter()
+                    .position(|c| column == c.name())
+            }) {
+                debug!(column = col, "sharding base node by configured column");
+                graph[node].shard_by(Sharding::ByColumn(col, sharding_factor));
+                continue;
+            }
+        }
+
         let mut need_sharding = if graph[node].is_internal() || graph[node].is_base() {
             // suggest_indexes is okay because `node` *must* be new, and therefore will return
             // global node indices.
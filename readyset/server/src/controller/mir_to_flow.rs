@@ -30,6 +30,7 @@ use nom_sql::{
 use readyset::internal::{Index, IndexType};
 use readyset::ViewPlaceholder;
 use readyset_data::noria_type::Type;
+use readyset_data::NullOrder;
 use readyset_errors::{
     internal, internal_err, invariant, invariant_eq, unsupported, ReadySetError, ReadySetResult,
 };
@@ -49,6 +50,7 @@ fn set_names(names: &[&str], columns: &mut [DataflowColumn]) -> ReadySetResult<(
 
 pub(super) fn mir_query_to_flow_parts(
     mir_query: &mut MirQuery,
+    null_order: NullOrder,
     mig: &mut Migration<'_>,
 ) -> ReadySetResult<QueryFlowParts> {
     use std::collections::VecDeque;
@@ -77,13 +79,14 @@ pub(super) fn mir_query_to_flow_parts(
             let n = n.borrow_mut();
             (n.name.clone(), n.from_version)
         };
-        let flow_node = mir_node_to_flow_parts(&mut n.borrow_mut(), mig).map_err(|e| {
-            ReadySetError::MirNodeCreationFailed {
-                name: name.to_string(),
-                from_version,
-                source: Box::new(e),
-            }
-        })?;
+        let flow_node =
+            mir_node_to_flow_parts(&mut n.borrow_mut(), null_order, mig).map_err(|e| {
+                ReadySetError::MirNodeCreationFailed {
+                    name: name.to_string(),
+                    from_version,
+                    source: Box::new(e),
+                }
+            })?;
         match flow_node {
             FlowNode::New(na) => new_nodes.push(na),
             FlowNode::Existing(na) => reused_nodes.push(na),
@@ -121,6 +124,7 @@ pub(super) fn mir_query_to_flow_parts(
 
 fn mir_node_to_flow_parts(
     mir_node: &mut MirNode,
+    null_order: NullOrder,
     mig: &mut Migration<'_>,
 ) -> ReadySetResult<FlowNode> {
     let name = mir_node.name.clone();
@@ -166,6 +170,7 @@ fn mir_node_to_flow_parts(
                         column_specs.as_mut_slice(),
                         &bna.columns_added,
                         &bna.columns_removed,
+                        &bna.columns_retyped,
                     )?,
                 },
                 MirNodeInner::Extremum {
@@ -255,6 +260,7 @@ fn mir_node_to_flow_parts(
                     let post_lookup = make_post_lookup(
                         &parent,
                         order_by,
+                        null_order,
                         limit,
                         returned_cols,
                         default_row.clone(),
@@ -369,6 +375,7 @@ fn mir_node_to_flow_parts(
                         parent,
                         &mir_node.columns(),
                         order,
+                        null_order,
                         group_by,
                         limit,
                         matches!(mir_node.inner, MirNodeInner::TopK { .. }),
@@ -400,6 +407,7 @@ fn adapt_base_node(
     column_specs: &mut [(ColumnSpecification, Option<usize>)],
     add: &[ColumnSpecification],
     remove: &[ColumnSpecification],
+    retype: &[ColumnSpecification],
 ) -> ReadySetResult<FlowNode> {
     let na = match over_node.borrow().flow_node {
         None => internal!("adapted base node must have a flow node already!"),
@@ -407,13 +415,7 @@ fn adapt_base_node(
     };
 
     for a in add.iter() {
-        let mut default_value = DataType::None;
-        for c in &a.constraints {
-            if let ColumnConstraint::DefaultValue(dv) = c {
-                default_value = dv.try_into()?;
-                break;
-            }
-        }
+        let default_value = column_default(a)?;
         let column_id = mig.add_column(na, DataflowColumn::from(a.clone()), default_value)?;
 
         // store the new column ID in the column specs for this node
@@ -443,6 +445,20 @@ fn adapt_base_node(
             .ok_or_else(|| internal_err("base column ID must be set to remove column"))?;
         mig.drop_column(na, cid)?;
     }
+    for r in retype.iter() {
+        // retyped columns keep their position in the base, so their ID is unchanged
+        let cid = column_specs
+            .iter()
+            .find(|(cs, _)| cs.column.name == r.column.name)
+            .and_then(|(_, cid)| *cid)
+            .ok_or_else(|| {
+                internal_err(format!(
+                    "could not find base column ID for retyped column {:?}",
+                    r
+                ))
+            })?;
+        mig.retype_column(na, cid, DataflowColumn::from(r.clone()))?;
+    }
 
     Ok(FlowNode::Existing(na))
 }
@@ -451,6 +467,22 @@ fn column_names(cs: &[Column]) -> Vec<&str> {
     cs.iter().map(|c| c.name.as_str()).collect()
 }
 
+/// Returns the default value of the column with the given specification, or `NULL` if it doesn't
+/// have one. Defaults of `ENUM` columns are stored as the index of their variant, like all other
+/// values of those columns.
+fn column_default(cs: &ColumnSpecification) -> ReadySetResult<DataType> {
+    for c in &cs.constraints {
+        if let ColumnConstraint::DefaultValue(ref dv) = *c {
+            let default = DataType::try_from(dv)?;
+            return match cs.sql_type {
+                SqlType::Enum(_) => default.coerce_to(&cs.sql_type),
+                _ => Ok(default),
+            };
+        }
+    }
+    Ok(DataType::None)
+}
+
 fn make_base_node(
     name: &str,
     column_specs: &mut [(ColumnSpecification, Option<usize>)],
@@ -472,14 +504,7 @@ fn make_base_node(
     // specified; we don't currently handle a "NOT NULL" SQL constraint for defaults
     let default_values = column_specs
         .iter()
-        .map(|&(ref cs, _)| {
-            for c in &cs.constraints {
-                if let ColumnConstraint::DefaultValue(ref dv) = *c {
-                    return dv.try_into();
-                }
-            }
-            Ok(DataType::None)
-        })
+        .map(|&(ref cs, _)| column_default(cs))
         .collect::<Result<Vec<DataType>, _>>()?;
 
     let cols_from_spec = |cols: &[Column]| -> ReadySetResult<Vec<usize>> {
@@ -1033,6 +1058,9 @@ fn lower_expression(
             let (func, ty) = BuiltinFunction::from_name_and_args(&fname, args)?;
             Ok(DataflowExpression::Call { func, ty })
         }
+        Expression::Call(FunctionExpression::Unsupported { name, .. }) => {
+            unsupported!("Function {} is not supported with these arguments", name)
+        }
         Expression::Call(call) => internal!(
             "Unexpected (aggregate?) call node in project expression: {:?}",
             Sensitive(&call)
@@ -1062,14 +1090,9 @@ fn lower_expression(
         }
         Expression::BinaryOp { lhs, op, rhs } => {
             // TODO: Consider rhs and op when inferring type
-            let left = Box::new(lower_expression(parent, *lhs, parent_cols)?);
+            let left = lower_expression(parent, *lhs, parent_cols)?;
             let ty = left.ty().clone();
-            Ok(DataflowExpression::Op {
-                op,
-                left,
-                right: Box::new(lower_expression(parent, *rhs, parent_cols)?),
-                ty,
-            })
+            make_binary_op(left, op, lower_expression(parent, *rhs, parent_cols)?, ty)
         }
         Expression::UnaryOp {
             op: UnaryOperator::Neg,
@@ -1141,12 +1164,12 @@ fn lower_expression(
 
                 let lhs = lower_expression(parent, *lhs, parent_cols)?;
                 let make_comparison = |rhs| -> ReadySetResult<_> {
-                    Ok(DataflowExpression::Op {
-                        left: Box::new(lhs.clone()),
-                        op: comparison_op,
-                        right: Box::new(lower_expression(parent, rhs, parent_cols)?),
-                        ty: Type::Sql(SqlType::Bool), // type of =/!= is always bool
-                    })
+                    make_binary_op(
+                        lhs.clone(),
+                        comparison_op,
+                        lower_expression(parent, rhs, parent_cols)?,
+                        Type::Sql(SqlType::Bool), // type of =/!= is always bool
+                    )
                 };
 
                 exprs.try_fold(make_comparison(fst)?, |acc, rhs| {
@@ -1157,16 +1180,10 @@ fn lower_expression(
                         ty: Type::Sql(SqlType::Bool), // type of =/!= is always bool
                     })
                 })
-            } else if negated {
-                // x IN () is always false
-                Ok(DataflowExpression::Literal {
-                    val: DataType::None,
-                    ty: Type::Sql(SqlType::Bool),
-                })
             } else {
-                // x NOT IN () is always false
+                // x IN () is always false, and x NOT IN () is always true, even if x is NULL
                 Ok(DataflowExpression::Literal {
-                    val: DataType::from(1),
+                    val: DataType::from(negated),
                     ty: Type::Sql(SqlType::Bool),
                 })
             }
@@ -1179,6 +1196,43 @@ fn lower_expression(
     }
 }
 
+/// Build a binary operation between `left` and `right`. If one of them has an `ENUM` type and the
+/// other is a string literal, the literal is replaced with the index of the variant it names, since
+/// that's how the values of `ENUM` columns are stored (see [`readyset_data::enums`]).
+fn make_binary_op(
+    mut left: DataflowExpression,
+    op: BinaryOperator,
+    mut right: DataflowExpression,
+    ty: Type,
+) -> ReadySetResult<DataflowExpression> {
+    let coerce_to_enum = |enum_expr: &DataflowExpression,
+                          expr: &mut DataflowExpression|
+     -> ReadySetResult<()> {
+        if let (Type::Sql(enum_ty @ SqlType::Enum(_)), DataflowExpression::Literal { val, ty }) =
+            (enum_expr.ty(), expr)
+        {
+            if val.is_string() {
+                if op.is_comparison() {
+                    // MySQL compares these as strings, not in the order of the ENUM's variants
+                    unsupported!("Ordered comparisons between ENUM values and strings");
+                }
+                *val = val.coerce_to(enum_ty)?;
+                *ty = enum_expr.ty().clone();
+            }
+        }
+        Ok(())
+    };
+    coerce_to_enum(&left, &mut right)?;
+    coerce_to_enum(&right, &mut left)?;
+
+    Ok(DataflowExpression::Op {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+        ty,
+    })
+}
+
 fn make_project_node(
     name: &str,
     parent: MirNodeRef,
@@ -1347,6 +1401,7 @@ fn make_paginate_or_topk_node(
     parent: MirNodeRef,
     columns: &[Column],
     order: &Option<Vec<(Column, OrderType)>>,
+    null_order: NullOrder,
     group_by: &[Column],
     limit: usize,
     is_topk: bool,
@@ -1407,13 +1462,13 @@ fn make_paginate_or_topk_node(
         mig.add_ingredient(
             String::from(name),
             parent_cols,
-            ops::topk::TopK::new(parent_na, cmp_rows, group_by_indx, limit),
+            ops::topk::TopK::new(parent_na, cmp_rows, null_order, group_by_indx, limit),
         )
     } else {
         mig.add_ingredient(
             String::from(name),
             parent_cols,
-            ops::paginate::Paginate::new(parent_na, cmp_rows, group_by_indx, limit),
+            ops::paginate::Paginate::new(parent_na, cmp_rows, null_order, group_by_indx, limit),
         )
     };
     Ok(FlowNode::New(na))
@@ -1422,6 +1477,7 @@ fn make_paginate_or_topk_node(
 fn make_post_lookup(
     parent: &MirNodeRef,
     order_by: &Option<Vec<(Column, OrderType)>>,
+    null_order: NullOrder,
     limit: Option<usize>,
     returned_cols: &Option<Vec<Column>>,
     default_row: Option<Vec<DataType>>,
@@ -1459,6 +1515,7 @@ fn make_post_lookup(
 
     Ok(PostLookup {
         order_by,
+        null_order,
         limit,
         returned_cols,
         default_row,
@@ -20,12 +20,13 @@ use readyset::consensus::{
 use readyset::metrics::recorded;
 use readyset::ControllerDescriptor;
 use readyset_errors::{internal, internal_err, ReadySetError};
+use readyset_tracing::propagation::RequestContext;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use stream_cancel::Valve;
 use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
 use tokio::sync::{Notify, RwLock, RwLockReadGuard, RwLockWriteGuard};
-use tracing::{error, info, warn};
+use tracing::{error, info, info_span, warn, Span};
 use tracing_futures::Instrument;
 use url::Url;
 
@@ -51,6 +52,9 @@ mod state;
 const LEADER_STATE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 /// Amount of time to wait for watches on the authority.
 const WATCH_DURATION: Duration = Duration::from_secs(5);
+/// Amount of time to wait for the leader's controller to respond when checking whether it is still
+/// reachable.
+const LEADER_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// A set of placement restrictions applied to a domain
 /// that a dataflow node is in. Each base table node can have
@@ -95,6 +99,8 @@ impl Debug for ControllerState {
 #[derive(Clone)]
 pub struct Worker {
     healthy: bool,
+    /// Whether the worker is being drained, in which case no new domains are scheduled onto it.
+    draining: bool,
     uri: Url,
     http: reqwest::Client,
     region: Option<String>,
@@ -114,6 +120,7 @@ impl Worker {
     ) -> Self {
         Worker {
             healthy: true,
+            draining: false,
             uri: instance_uri,
             http: reqwest::Client::new(),
             region,
@@ -200,8 +207,13 @@ pub struct ControllerRequest {
     pub query: Option<String>,
     /// The request body.
     pub body: hyper::body::Bytes,
+    /// The context of the trace the request was made in, if it is being traced
+    pub trace_context: Option<RequestContext>,
     /// Sender to send the response down.
-    pub reply_tx: tokio::sync::oneshot::Sender<Result<Result<Vec<u8>, Vec<u8>>, StatusCode>>,
+    ///
+    /// Errors are sent along with the status code the response should be returned with.
+    pub reply_tx:
+        tokio::sync::oneshot::Sender<Result<Result<Vec<u8>, (StatusCode, Vec<u8>)>, StatusCode>>,
 }
 
 /// A request made from a `Handle` to a controller.
@@ -390,6 +402,13 @@ impl Controller {
         match msg {
             AuthorityUpdate::LeaderChange(descr) => {
                 gauge!(recorded::CONTROLLER_IS_LEADER, 0f64);
+                // If we were the leader, we've since been deposed, so stop acting as one
+                let deposed_leader = self.inner.write().await.take();
+                if let Some(mut leader) = deposed_leader {
+                    warn!("lost leadership, stopping leader");
+                    self.leader_ready.store(false, Ordering::Release);
+                    leader.stop().await;
+                }
                 self.send_worker_request(WorkerRequestKind::NewController {
                     controller_uri: descr.controller_uri,
                 })
@@ -404,6 +423,8 @@ impl Controller {
                     self.authority.clone(),
                     self.config.replication_url.clone(),
                     self.config.replication_server_id,
+                    self.config.replicator_config.clone(),
+                    self.config.additional_upstreams.clone(),
                     self.config.worker_request_timeout,
                     self.config.replicator_restart_timeout,
                 );
@@ -594,6 +615,12 @@ struct AuthorityLeaderElectionState {
     leader_eligible: bool,
     /// True if we are the current leader.
     is_leader: bool,
+    /// The current leader, if there is one and it isn't us.
+    leader: Option<ControllerDescriptor>,
+    /// The last time the current leader's controller was reachable.
+    leader_last_seen: Instant,
+    /// Client used to check whether the leader's controller is reachable.
+    http_client: reqwest::Client,
 }
 
 impl AuthorityLeaderElectionState {
@@ -618,6 +645,9 @@ impl AuthorityLeaderElectionState {
             config,
             leader_eligible: can_be_leader,
             is_leader: false,
+            leader: None,
+            leader_last_seen: Instant::now(),
+            http_client: reqwest::Client::new(),
         }
     }
 
@@ -629,12 +659,61 @@ impl AuthorityLeaderElectionState {
         self.authority.watch_leader().await
     }
 
+    /// Returns true if the leader's controller responds to requests at all, even if it isn't
+    /// ready to handle them yet.
+    async fn leader_is_reachable(&self, leader: &ControllerDescriptor) -> bool {
+        let url = match leader.controller_uri.join("controller_uri") {
+            Ok(url) => url,
+            Err(_) => return true,
+        };
+        self.http_client
+            .get(url)
+            .timeout(LEADER_HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await
+            .is_ok()
+    }
+
+    /// If the leader has been unreachable for longer than the configured failover timeout, depose
+    /// it so that we can take over as the leader. Returns true if the leader was deposed.
+    async fn depose_unreachable_leader(&mut self) -> anyhow::Result<bool> {
+        let timeout = match self.config.leader_failover_timeout {
+            Some(timeout) if self.leader_eligible && !self.is_leader => timeout,
+            _ => return Ok(false),
+        };
+        let leader = match &self.leader {
+            Some(leader) => leader,
+            None => return Ok(false),
+        };
+
+        if self.leader_is_reachable(leader).await {
+            self.leader_last_seen = Instant::now();
+            return Ok(false);
+        }
+        if self.leader_last_seen.elapsed() < timeout {
+            return Ok(false);
+        }
+
+        warn!(
+            leader = %leader.controller_uri,
+            unreachable_for = ?self.leader_last_seen.elapsed(),
+            "leader is unreachable, deposing it"
+        );
+        let deposed = self.authority.depose_leader(leader).await?;
+        if deposed {
+            self.leader = None;
+        }
+        Ok(deposed)
+    }
+
     async fn update_leader_state(&mut self) -> anyhow::Result<()> {
         let mut should_attempt_leader_election = false;
         match self.authority.try_get_leader().await? {
             // The leader has changed, inform the worker.
             GetLeaderResult::NewLeader(payload) => {
                 self.is_leader = false;
+                self.leader = Some(payload.clone());
+                self.leader_last_seen = Instant::now();
                 let authority_update = AuthorityUpdate::LeaderChange(payload);
                 self.event_tx
                     .send(authority_update)
@@ -642,10 +721,13 @@ impl AuthorityLeaderElectionState {
                     .map_err(|_| format_err!("send failed"))?;
             }
 
-            GetLeaderResult::NoLeader if self.leader_eligible => {
-                should_attempt_leader_election = true;
+            GetLeaderResult::NoLeader => {
+                self.leader = None;
+                should_attempt_leader_election = self.leader_eligible;
+            }
+            GetLeaderResult::Unchanged => {
+                should_attempt_leader_election = self.depose_unreachable_leader().await?;
             }
-            _ => {}
         }
 
         if should_attempt_leader_election {
@@ -984,6 +1066,28 @@ async fn controller_req_processing_runner(
     Ok(())
 }
 
+/// Serializes an error returned by a controller request. Errors are returned by the admin API as a
+/// JSON object with an `error` message, and by every other endpoint with bincode.
+fn serialize_error(error: &ReadySetError, admin_api: bool) -> ReadySetResult<Vec<u8>> {
+    if admin_api {
+        Ok(serde_json::json!({ "error": error.to_string() })
+            .to_string()
+            .into_bytes())
+    } else {
+        Ok(bincode::serialize(error)?)
+    }
+}
+
+/// Returns the status code to respond to a controller request that failed with `error`. Requests
+/// to the admin API that were rejected as malformed get a `400 Bad Request`; every other error
+/// (including all errors from the bincode endpoints, whose clients expect them) is a `500`.
+fn error_status(error: &ReadySetError, admin_api: bool) -> StatusCode {
+    match error {
+        ReadySetError::BadRequest(_) if admin_api => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 async fn handle_controller_request(
     req: ControllerRequest,
     authority: Arc<Authority>,
@@ -995,11 +1099,16 @@ async fn handle_controller_request(
         path,
         query,
         body,
+        trace_context,
         reply_tx,
     } = req;
+    let span = trace_context.map_or_else(Span::none, |ctx| {
+        ctx.child_span(info_span!("controller_request", %method, %path))
+    });
 
+    let admin_api = path.starts_with("/api/");
     let request_start = Instant::now();
-    let ret: Result<Result<Vec<u8>, Vec<u8>>, StatusCode> = {
+    let ret: Result<Result<Vec<u8>, (StatusCode, Vec<u8>)>, StatusCode> = {
         let guard = leader_handle.read().await;
         let resp = match (&method, path.as_str()) {
             // Requests that do not need to be handled by the leader.
@@ -1011,14 +1120,16 @@ async fn handle_controller_request(
             _ => {
                 if let Some(ref ci) = *guard {
                     Ok(tokio::task::block_in_place(|| {
-                        ci.external_request(
-                            method,
-                            path.as_ref(),
-                            query,
-                            body,
-                            &authority,
-                            leader_ready,
-                        )
+                        span.in_scope(|| {
+                            ci.external_request(
+                                method,
+                                path.as_ref(),
+                                query,
+                                body,
+                                &authority,
+                                leader_ready,
+                            )
+                        })
                     }))
                 } else {
                     Err(ReadySetError::NotLeader)
@@ -1031,10 +1142,16 @@ async fn handle_controller_request(
             Ok(Ok(r)) => Ok(Ok(r)),
             Ok(Err(ReadySetError::NoQuorum)) => Err(StatusCode::SERVICE_UNAVAILABLE),
             Ok(Err(ReadySetError::UnknownEndpoint)) => Err(StatusCode::NOT_FOUND),
-            Ok(Err(e)) => Ok(Err(bincode::serialize(&e)?)),
+            Ok(Err(e)) => Ok(Err((
+                error_status(&e, admin_api),
+                serialize_error(&e, admin_api)?,
+            ))),
             // something else failed:
             Err(ReadySetError::NotLeader) => Err(StatusCode::SERVICE_UNAVAILABLE),
-            Err(e) => Ok(Err(bincode::serialize(&e)?)),
+            Err(e) => Ok(Err((
+                error_status(&e, admin_api),
+                serialize_error(&e, admin_api)?,
+            ))),
         }
     };
 
@@ -1058,9 +1175,15 @@ async fn handle_controller_request(
 
 #[cfg(test)]
 mod tests {
-    use readyset::replication::ReplicationOffset;
+    use std::collections::HashMap;
+
+    use readyset::internal::MaterializationStatus;
+    use readyset::replication::{
+        ReplicationOffset, ReplicationSlotStatus, SnapshotProgress, TableNamespace,
+    };
 
-    use crate::integration_utils::start_simple;
+    use crate::integration_utils::{get_persistence_params, start_simple};
+    use crate::Builder;
 
     #[tokio::test(flavor = "multi_thread")]
     async fn remove_query() {
@@ -1084,6 +1207,95 @@ mod tests {
         assert!(!queries.contains_key("test_query"));
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn admin_api() {
+        let mut builder = Builder::for_tests();
+        builder.set_persistence(get_persistence_params("admin_api"));
+        builder.set_admin_api_token("secret".to_owned());
+        let mut noria = builder.start_local().await.unwrap();
+        noria.backend_ready().await;
+        noria
+            .extend_recipe(
+                "CREATE TABLE users (id INT PRIMARY KEY, name TEXT);"
+                    .parse()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let client = reqwest::Client::new();
+        let caches_url = noria.get_address().join("/api/v1/caches").unwrap();
+        let get_json = |url: reqwest::Url| {
+            let request = client.get(url).bearer_auth("secret");
+            async move {
+                let res = request.send().await.unwrap();
+                assert!(res.status().is_success());
+                serde_json::from_slice::<serde_json::Value>(&res.bytes().await.unwrap()).unwrap()
+            }
+        };
+
+        let res = client.get(caches_url.clone()).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let res = client
+            .get(caches_url.clone())
+            .bearer_auth("wrong")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post(caches_url.clone())
+            .bearer_auth("secret")
+            .body("CREATE CACHE test_query FROM SELECT * FROM users WHERE id = ?")
+            .send()
+            .await
+            .unwrap();
+        assert!(res.status().is_success());
+        let caches = get_json(caches_url.clone()).await;
+        assert_eq!(caches[0]["name"], "test_query");
+
+        let graph = get_json(noria.get_address().join("/api/v1/graph.json").unwrap()).await;
+        assert!(graph["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|node| node["kind"] == "reader"));
+
+        // Only CREATE CACHE statements are accepted
+        let res = client
+            .post(caches_url.clone())
+            .bearer_auth("secret")
+            .body("CREATE TABLE t (id INT)")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // Malformed bodies are rejected as bad requests, with the error in the response
+        let res = client
+            .post(caches_url.clone())
+            .bearer_auth("secret")
+            .body("CREATE CACHE FROM")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body =
+            serde_json::from_slice::<serde_json::Value>(&res.bytes().await.unwrap()).unwrap();
+        assert!(body["error"].is_string());
+
+        let res = client
+            .delete(caches_url.join("caches/test_query").unwrap())
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert!(res.status().is_success());
+        let caches = get_json(caches_url).await;
+        assert_eq!(caches, serde_json::json!([]));
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn replication_offsets() {
         let mut noria = start_simple("all_tables").await;
@@ -1132,4 +1344,249 @@ mod tests {
         assert_eq!(offsets.tables["t2"].as_ref().unwrap().offset, 3);
         assert_eq!(offsets.tables["t3"], None);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn snapshot_progress() {
+        let mut noria = start_simple("snapshot_progress").await;
+        assert!(noria.snapshot_progress().await.unwrap().is_empty());
+
+        let progress = SnapshotProgress {
+            offset: ReplicationOffset {
+                offset: 1,
+                replication_log_name: "binlog".to_owned(),
+            },
+            last_key: vec![1.into(), "a".into()],
+            rows_copied: 1000,
+            total_rows: Some(4000),
+        };
+        noria
+            .set_snapshot_progress("t1", Some(progress.clone()))
+            .await
+            .unwrap();
+        noria
+            .set_snapshot_progress(
+                "t2",
+                Some(SnapshotProgress {
+                    rows_copied: 2000,
+                    ..progress.clone()
+                }),
+            )
+            .await
+            .unwrap();
+
+        let all_progress = noria.snapshot_progress().await.unwrap();
+        assert_eq!(all_progress.len(), 2);
+        assert_eq!(all_progress["t1"], progress);
+        assert_eq!(all_progress["t2"].rows_copied, 2000);
+
+        noria.set_snapshot_progress("t2", None).await.unwrap();
+        let all_progress = noria.snapshot_progress().await.unwrap();
+        assert_eq!(all_progress.len(), 1);
+        assert_eq!(all_progress["t1"], progress);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replication_slot_status() {
+        let mut noria = start_simple("replication_slot_status").await;
+        assert_eq!(noria.replication_slot_status().await.unwrap(), None);
+
+        let status = ReplicationSlotStatus {
+            slot_name: "readyset".to_owned(),
+            active: true,
+            restart_lsn: Some("0/16B3748".to_owned()),
+            confirmed_flush_lsn: Some("0/16B3780".to_owned()),
+            lag_bytes: Some(56),
+        };
+        noria
+            .set_replication_slot_status(Some(status.clone()))
+            .await
+            .unwrap();
+        assert_eq!(noria.replication_slot_status().await.unwrap(), Some(status));
+
+        noria.set_replication_slot_status(None).await.unwrap();
+        assert_eq!(noria.replication_slot_status().await.unwrap(), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn auto_increments() {
+        let mut noria = start_simple("auto_increments").await;
+        assert!(noria.auto_increments().await.unwrap().is_empty());
+
+        noria
+            .record_auto_increments(HashMap::from([("t1".to_owned(), 10), ("t2".to_owned(), 3)]))
+            .await
+            .unwrap();
+        noria
+            .record_auto_increments(HashMap::from([("t1".to_owned(), 7), ("t2".to_owned(), 5)]))
+            .await
+            .unwrap();
+
+        let recorded = noria.auto_increments().await.unwrap();
+        assert_eq!(recorded["t1"], 10);
+        assert_eq!(recorded["t2"], 5);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn table_schema_versions() {
+        let mut noria = start_simple("table_schema_versions").await;
+        assert!(noria.table_schema_versions("t1").await.unwrap().is_empty());
+
+        let offset = ReplicationOffset {
+            offset: 1,
+            replication_log_name: "binlog".to_owned(),
+        };
+        noria
+            .extend_recipe_with_offset("CREATE TABLE t1 (id int);".parse().unwrap(), &offset, false)
+            .await
+            .unwrap();
+        noria
+            .extend_recipe_with_offset(
+                "ALTER TABLE t1 ADD COLUMN name text;".parse().unwrap(),
+                &ReplicationOffset {
+                    offset: 2,
+                    ..offset.clone()
+                },
+                false,
+            )
+            .await
+            .unwrap();
+
+        let versions = noria.table_schema_versions("t1").await.unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].version, 0);
+        assert_eq!(versions[0].statement.fields.len(), 1);
+        assert_eq!(versions[0].replication_offset, Some(offset));
+        assert_eq!(versions[1].version, 1);
+        assert_eq!(versions[1].statement.fields.len(), 2);
+        assert_eq!(versions[1].replication_offset.as_ref().unwrap().offset, 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn explain_migration() {
+        let mut noria = start_simple("explain_migration").await;
+        noria
+            .extend_recipe("CREATE TABLE t1 (id int, value int);".parse().unwrap())
+            .await
+            .unwrap();
+
+        let nodes = noria
+            .explain_migration(
+                "CREATE CACHE q FROM SELECT value FROM t1 WHERE id = ?;"
+                    .parse()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(!nodes.is_empty());
+        assert!(nodes
+            .iter()
+            .any(|node| !matches!(node.materialization, MaterializationStatus::Not)));
+        // Nothing was actually installed
+        assert!(noria.outputs().await.unwrap().is_empty());
+
+        noria
+            .explain_migration(
+                "CREATE CACHE q FROM SELECT * FROM nonexistent;"
+                    .parse()
+                    .unwrap(),
+            )
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn create_caches() {
+        let mut noria = start_simple("create_caches").await;
+        noria
+            .extend_recipe("CREATE TABLE t1 (id int, value int);".parse().unwrap())
+            .await
+            .unwrap();
+
+        let statuses = noria
+            .create_caches(
+                "CREATE CACHE q1 FROM SELECT value FROM t1 WHERE id = ?;
+                 CREATE CACHE q2 FROM SELECT * FROM nonexistent;
+                 CREATE CACHE q3 FROM SELECT id FROM t1 WHERE value = ?;"
+                    .parse()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(statuses.len(), 3);
+        assert!(statuses[0].error.is_none());
+        assert!(statuses[1].error.is_some());
+        assert!(statuses[2].error.is_none());
+        // None of the caches were created, since one of them couldn't be
+        assert!(noria.outputs().await.unwrap().is_empty());
+
+        let statuses = noria
+            .create_caches(
+                "CREATE CACHE q1 FROM SELECT value FROM t1 WHERE id = ?;
+                 CREATE CACHE q3 FROM SELECT id FROM t1 WHERE value = ?;"
+                    .parse()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(statuses.iter().all(|status| status.error.is_none()));
+        let outputs = noria.outputs().await.unwrap();
+        assert!(outputs.contains_key("q1"));
+        assert!(outputs.contains_key("q3"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn namespaced_replication_offsets() {
+        let mut noria = start_simple("namespaced_replication_offsets").await;
+        noria.set_table_namespace(TableNamespace::new(None, vec!["billing".to_owned()]));
+        let mut billing = noria.clone();
+        billing.set_table_namespace(TableNamespace::new(Some("billing".to_owned()), vec![]));
+
+        let offset = ReplicationOffset {
+            offset: 1,
+            replication_log_name: "binlog".to_owned(),
+        };
+        let billing_offset = ReplicationOffset {
+            offset: 5,
+            replication_log_name: "wal".to_owned(),
+        };
+
+        noria
+            .extend_recipe_with_offset(
+                "CREATE TABLE users (id int);".parse().unwrap(),
+                &offset,
+                false,
+            )
+            .await
+            .unwrap();
+        billing
+            .extend_recipe_with_offset(
+                "CREATE TABLE invoices (id int);".parse().unwrap(),
+                &billing_offset,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let tables = noria.inputs().await.unwrap();
+        assert!(tables.contains_key("users"));
+        assert!(tables.contains_key("billing_invoices"));
+
+        billing
+            .table("invoices")
+            .await
+            .unwrap()
+            .set_replication_offset(billing_offset.clone())
+            .await
+            .unwrap();
+
+        let offsets = noria.replication_offsets().await.unwrap();
+        assert_eq!(offsets.schema, Some(offset));
+        assert_eq!(offsets.tables.len(), 1);
+        assert_eq!(offsets.tables["users"], None);
+
+        let offsets = billing.replication_offsets().await.unwrap();
+        assert_eq!(offsets.schema, Some(billing_offset.clone()));
+        assert_eq!(offsets.tables.len(), 1);
+        assert_eq!(offsets.tables["invoices"], Some(billing_offset));
+    }
 }
@@ -1,6 +1,8 @@
+use std::collections::HashSet;
+
 use nom_sql::{
     AlterColumnOperation, AlterTableDefinition, AlterTableStatement, ColumnConstraint,
-    CreateTableStatement, TableKey,
+    CreateTableStatement, SqlIdentifier, TableKey,
 };
 use readyset_errors::{ReadySetError, ReadySetResult};
 
@@ -114,6 +116,80 @@ pub(super) fn rewrite_table_definition(
     Ok(new_table)
 }
 
+/// Returns true if the alterations specified in the [`AlterTableStatement`], which turn
+/// `original_table` into `new_table`, can be applied to the existing base table in place, keeping
+/// its data.
+///
+/// This is the case when the alterations only add or drop columns, or change the type or default
+/// value of existing columns. Renaming columns or changing the keys of the table requires
+/// recreating it.
+pub(super) fn can_alter_in_place(
+    alter_table_definition: &AlterTableStatement,
+    original_table: &CreateTableStatement,
+    new_table: &CreateTableStatement,
+) -> bool {
+    let is_key_constraint =
+        |c: &ColumnConstraint| matches!(c, ColumnConstraint::PrimaryKey | ColumnConstraint::Unique);
+    let simple_alterations = alter_table_definition
+        .definitions
+        .iter()
+        .all(|def| match def {
+            AlterTableDefinition::AddColumn(spec) => {
+                !spec.constraints.iter().any(is_key_constraint)
+            }
+            AlterTableDefinition::AlterColumn { .. } | AlterTableDefinition::DropColumn { .. } => {
+                true
+            }
+            AlterTableDefinition::ChangeColumn { name, spec } => spec.column.name == *name,
+            AlterTableDefinition::AddKey(_) | AlterTableDefinition::RenameColumn { .. } => false,
+        });
+
+    let mut key_columns = original_table
+        .keys
+        .iter()
+        .flatten()
+        .flat_map(|key| match key {
+            TableKey::PrimaryKey { columns, .. }
+            | TableKey::UniqueKey { columns, .. }
+            | TableKey::FulltextKey { columns, .. }
+            | TableKey::Key { columns, .. }
+            | TableKey::ForeignKey { columns, .. } => columns.as_slice(),
+            TableKey::CheckConstraint { .. } => &[][..],
+        })
+        .map(|column| &column.name)
+        .chain(
+            original_table
+                .fields
+                .iter()
+                .filter(|f| f.constraints.iter().any(is_key_constraint))
+                .map(|f| &f.column.name),
+        );
+    let key_columns_kept =
+        key_columns.all(|name| new_table.fields.iter().any(|f| f.column.name == *name));
+
+    simple_alterations && key_columns_kept && original_table.keys == new_table.keys
+}
+
+/// Returns the names of the columns that differ between `original_table` and `new_table`: those
+/// that were added, those that were dropped, and those whose specification changed.
+pub(super) fn changed_columns(
+    original_table: &CreateTableStatement,
+    new_table: &CreateTableStatement,
+) -> HashSet<SqlIdentifier> {
+    original_table
+        .fields
+        .iter()
+        .filter(|spec| !new_table.fields.contains(spec))
+        .chain(
+            new_table
+                .fields
+                .iter()
+                .filter(|spec| !original_table.fields.contains(spec)),
+        )
+        .map(|spec| spec.column.name.clone())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use nom_sql::{CreateTableStatement, Dialect, Literal, SqlIdentifier, SqlType};
@@ -336,4 +412,60 @@ mod tests {
         assert!(new_table.fields.iter().any(|f| f.column.name == "new_id"));
         assert!(!new_table.fields.iter().any(|f| f.column.name == "id"));
     }
+
+    #[test]
+    fn alter_in_place() {
+        let in_place = |alter: &str| {
+            let original_table = create_table();
+            let alteration = nom_sql::parse_alter_table(Dialect::MySQL, alter).unwrap();
+            let new_table = rewrite_table_definition(&alteration, original_table.clone()).unwrap();
+            can_alter_in_place(&alteration, &original_table, &new_table)
+        };
+
+        assert!(in_place("ALTER TABLE test ADD COLUMN age DOUBLE NOT NULL;"));
+        assert!(in_place("ALTER TABLE test DROP COLUMN name;"));
+        assert!(in_place(
+            "ALTER TABLE test CHANGE COLUMN name name VARCHAR(255) NOT NULL;"
+        ));
+        assert!(in_place(
+            "ALTER TABLE test ALTER COLUMN name SET DEFAULT 'default_name';"
+        ));
+
+        assert!(!in_place("ALTER TABLE test DROP COLUMN id;"));
+        assert!(!in_place("ALTER TABLE test RENAME COLUMN id new_id;"));
+        assert!(!in_place(
+            "ALTER TABLE test CHANGE COLUMN name new_name TEXT NOT NULL;"
+        ));
+        assert!(!in_place(
+            "ALTER TABLE test ADD UNIQUE KEY new_key (id) USING hash;"
+        ));
+    }
+
+    #[test]
+    fn changed_columns_of_alteration() {
+        let changed = |alter: &str| {
+            let original_table = create_table();
+            let alteration = nom_sql::parse_alter_table(Dialect::MySQL, alter).unwrap();
+            let new_table = rewrite_table_definition(&alteration, original_table.clone()).unwrap();
+            changed_columns(&original_table, &new_table)
+        };
+
+        assert_eq!(
+            changed("ALTER TABLE test ADD COLUMN age DOUBLE NOT NULL;"),
+            HashSet::from(["age".into()])
+        );
+        assert_eq!(
+            changed("ALTER TABLE test DROP COLUMN name;"),
+            HashSet::from(["name".into()])
+        );
+        assert_eq!(
+            changed("ALTER TABLE test CHANGE COLUMN name name VARCHAR(255) NOT NULL;"),
+            HashSet::from(["name".into()])
+        );
+        assert_eq!(
+            changed("ALTER TABLE test RENAME COLUMN name new_name;"),
+            HashSet::<SqlIdentifier>::from(["name".into(), "new_name".into()])
+        );
+        assert!(changed("ALTER TABLE test CHANGE COLUMN name name TEXT NOT NULL;").is_empty());
+    }
 }
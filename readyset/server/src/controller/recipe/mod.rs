@@ -6,9 +6,10 @@ use nom_sql::{
     CacheInner, CreateCacheStatement, CreateTableStatement, Dialect, SqlIdentifier, SqlQuery,
 };
 use readyset::recipe::changelist::{Change, ChangeList};
+use readyset::recipe::QueryReuse;
 use readyset::ActivationResult;
 use readyset_errors::{
-    internal, internal_err, invariant, invariant_eq, ReadySetError, ReadySetResult,
+    internal, internal_err, invariant, invariant_eq, unsupported, ReadySetError, ReadySetResult,
 };
 use petgraph::graph::NodeIndex;
 use petgraph::visit::Bfs;
@@ -16,7 +17,9 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
 use super::sql;
-use crate::controller::recipe::alter_table::rewrite_table_definition;
+use crate::controller::recipe::alter_table::{
+    can_alter_in_place, changed_columns, rewrite_table_definition,
+};
 use crate::controller::recipe::registry::{ExpressionRegistry, RecipeExpression};
 use crate::controller::sql::SqlIncorporator;
 use crate::controller::Migration;
@@ -58,6 +61,17 @@ pub(super) enum Schema {
     View(Vec<String>),
 }
 
+/// The views and queries affected by changes to the tables they depend on while activating a
+/// recipe.
+#[derive(Debug, Default)]
+struct InvalidatedQueries {
+    /// Names of the views and queries that were re-planned against the new schema of a table
+    replanned: Vec<SqlIdentifier>,
+    /// Names of the views and queries that were removed, either because a table they depend on
+    /// was dropped or because they could no longer be planned against its new schema
+    dropped: Vec<SqlIdentifier>,
+}
+
 #[allow(unused)]
 impl Recipe {
     /// Get the id associated with an alias
@@ -146,6 +160,13 @@ impl Recipe {
         }
     }
 
+    /// Returns which existing query (if any) the query named `name` reused the nodes of when it
+    /// was added.
+    pub(in crate::controller) fn query_reuse(&self, name: &SqlIdentifier) -> Option<&QueryReuse> {
+        let query_name = self.registry.resolve_alias(name).unwrap_or(name);
+        self.inc.get_query_reuse(query_name)
+    }
+
     /// Get schema for a base table or view in the recipe.
     pub(super) fn schema_for(&self, name: &str) -> Option<Schema> {
         match self.inc.get_base_schema(name) {
@@ -160,6 +181,28 @@ impl Recipe {
         }
     }
 
+    /// Returns an error if the view or cache `expression` might read any of the columns whose
+    /// values the replicator doesn't copy into ReadySet, since it would return NULLs (or the wrong
+    /// set of columns) where the upstream database returns the real values.
+    fn check_filtered_columns(&self, expression: &RecipeExpression) -> ReadySetResult<()> {
+        let mut filtered = self
+            .inc
+            .mir_config()
+            .filtered_columns
+            .iter()
+            .filter(|(table, columns)| expression.references_columns(table, columns))
+            .flat_map(|(table, columns)| columns.iter().map(move |c| format!("{}.{}", table, c)))
+            .collect::<Vec<_>>();
+        if !filtered.is_empty() {
+            filtered.sort();
+            unsupported!(
+                "Query may read columns that aren't replicated into ReadySet: {}",
+                filtered.join(", ")
+            );
+        }
+        Ok(())
+    }
+
     /// Iterates through the list of changes that have to be made, and updates the MIR
     /// state accordingly.
     /// All the MIR graph changes are added to the [`Migration`], but it's up to the caller
@@ -177,6 +220,7 @@ impl Recipe {
 
         let mut added = HashMap::new();
         let mut removed = HashSet::new();
+        let mut invalidated = InvalidatedQueries::default();
         for change in changelist.changes {
             match change {
                 Change::CreateTable(cts) => {
@@ -193,6 +237,7 @@ impl Recipe {
                     let query = SqlQuery::CreateView(cvs.clone());
                     let name = cvs.name.clone();
                     let expression = RecipeExpression::View(cvs);
+                    self.check_filtered_columns(&expression)?;
                     if !self.registry.add_query(expression)? {
                         // The expression is already present, and we successfully added
                         // a new alias for it.
@@ -214,6 +259,10 @@ impl Recipe {
                             internal!("CREATE CACHE should've had its ID resolved by the adapter");
                         }
                     };
+                    self.check_filtered_columns(&RecipeExpression::Cache {
+                        name: ccqs.name.clone().unwrap_or_default(),
+                        statement: select.clone(),
+                    })?;
                     let query = SqlQuery::Select(select.clone());
                     if let Some(name) = ccqs.name {
                         let expression = RecipeExpression::Cache {
@@ -247,8 +296,10 @@ impl Recipe {
                 // return an error.
                 // 2. Rewrite the table copy to reflect the changes specified by the ALTER TABLE
                 // statement.
-                // 3. Drop the original table.
-                // 4. Install the new table.
+                // 3. If the changes can be applied to the existing base table in place, do so, and
+                // re-plan only the queries that reference the columns that changed.
+                // 4. Otherwise, drop the original table and install the new table, and re-plan all
+                // the queries that depend on it.
                 Change::AlterTable(ats) => {
                     let original_expression =
                         self.registry.get(&ats.table.name).ok_or_else(|| {
@@ -265,6 +316,20 @@ impl Recipe {
                         ),
                     };
                     let new_table = rewrite_table_definition(&ats, original_table.clone())?;
+                    if can_alter_in_place(&ats, original_table, &new_table) {
+                        let changed_columns = changed_columns(original_table, &new_table);
+                        self.alter_table_in_place(
+                            new_table,
+                            &changed_columns,
+                            mig,
+                            &mut added,
+                            &mut removed,
+                            &mut invalidated,
+                        )?;
+                        continue;
+                    }
+                    let dependents = self.registry.dependents(&ats.table.name);
+                    self.remove_dependents(&dependents, mig, &mut added, &mut removed)?;
                     let removed_node_indices = self.remove_expression(&ats.table.name, mig)?;
                     match removed_node_indices {
                         None => {
@@ -289,10 +354,26 @@ impl Recipe {
                             .add_parsed_query(query, Some(new_name.clone()), false, mig)?;
                     self.registry
                         .add_query(RecipeExpression::Table(new_table))?;
-                    added.insert(new_name, qfp.query_leaf);
+                    added.insert(new_name.clone(), qfp.query_leaf);
                     removed.remove(&qfp.query_leaf);
+                    self.replan_dependents(
+                        &new_name,
+                        dependents,
+                        mig,
+                        &mut added,
+                        &mut removed,
+                        &mut invalidated,
+                    )?;
                 }
                 Change::Drop { name, if_exists } => {
+                    if let Some(RecipeExpression::Table(_)) = self.registry.get(&name) {
+                        invalidated.dropped.extend(
+                            self.registry
+                                .dependents(&name)
+                                .iter()
+                                .map(|dependent| dependent.name().clone()),
+                        );
+                    }
                     let removed_indices = self.remove_expression(&name, mig)?;
                     if let Some(removed_indices) = removed_indices {
                         for removed_index in removed_indices {
@@ -319,9 +400,113 @@ impl Recipe {
             removed_leaves: removed,
             expressions_added,
             expressions_removed,
+            replanned_queries: invalidated.replanned,
+            dropped_queries: invalidated.dropped,
         })
     }
 
+    /// Apply the new definition of an existing table to its base table in place, keeping the data
+    /// it already contains, and re-plan the queries that reference any of the `changed_columns` of
+    /// the table against its new schema. Queries that don't reference any of those columns are left
+    /// untouched.
+    fn alter_table_in_place(
+        &mut self,
+        new_table: CreateTableStatement,
+        changed_columns: &HashSet<SqlIdentifier>,
+        mig: &mut Migration<'_>,
+        added: &mut HashMap<SqlIdentifier, NodeIndex>,
+        removed: &mut HashSet<NodeIndex>,
+        invalidated: &mut InvalidatedQueries,
+    ) -> ReadySetResult<()> {
+        let name = new_table.table.name.clone();
+        let affected = self
+            .registry
+            .dependents(&name)
+            .into_iter()
+            .filter(|dependent| dependent.references_columns(&name, changed_columns))
+            .collect::<Vec<_>>();
+        self.remove_dependents(&affected, mig, added, removed)?;
+
+        let qfp = self.inc.add_parsed_query(
+            SqlQuery::CreateTable(new_table.clone()),
+            Some(name.clone()),
+            false,
+            mig,
+        )?;
+        self.registry.replace_table(new_table)?;
+        added.insert(name.clone(), qfp.query_leaf);
+        removed.remove(&qfp.query_leaf);
+
+        self.replan_dependents(&name, affected, mig, added, removed, invalidated)
+    }
+
+    /// Remove the given views and queries, which depend on a table that is about to change, from
+    /// the recipe.
+    fn remove_dependents(
+        &mut self,
+        dependents: &[RecipeExpression],
+        mig: &mut Migration<'_>,
+        added: &mut HashMap<SqlIdentifier, NodeIndex>,
+        removed: &mut HashSet<NodeIndex>,
+    ) -> ReadySetResult<()> {
+        for dependent in dependents {
+            if let Some(removed_node_indices) = self.remove_expression(dependent.name(), mig)? {
+                for removed_node_index in removed_node_indices {
+                    added.retain(|_, v| *v != removed_node_index);
+                    removed.insert(removed_node_index);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-plan the given views and queries, previously removed with [`Self::remove_dependents`],
+    /// against the new schema of `table`. Queries that can no longer be planned (for example,
+    /// because they reference a column that was dropped) are left out of the recipe.
+    fn replan_dependents(
+        &mut self,
+        table: &SqlIdentifier,
+        dependents: Vec<RecipeExpression>,
+        mig: &mut Migration<'_>,
+        added: &mut HashMap<SqlIdentifier, NodeIndex>,
+        removed: &mut HashSet<NodeIndex>,
+        invalidated: &mut InvalidatedQueries,
+    ) -> ReadySetResult<()> {
+        for dependent in dependents {
+            let (query, query_name, is_leaf) = match &dependent {
+                RecipeExpression::View(cvs) => {
+                    (SqlQuery::CreateView(cvs.clone()), cvs.name.clone(), false)
+                }
+                RecipeExpression::Cache { name, statement } => {
+                    (SqlQuery::Select(statement.clone()), name.clone(), true)
+                }
+                RecipeExpression::Table(_) => continue,
+            };
+            match self
+                .inc
+                .add_parsed_query(query, Some(query_name.clone()), is_leaf, mig)
+            {
+                Ok(qfp) => {
+                    self.registry.add_query(dependent)?;
+                    added.insert(query_name.clone(), qfp.query_leaf);
+                    removed.remove(&qfp.query_leaf);
+                    invalidated.replanned.push(query_name);
+                }
+                Err(error) => {
+                    warn!(
+                        %error,
+                        query = %query_name,
+                        %table,
+                        "Could not re-plan query after ALTER TABLE, removing it"
+                    );
+                    invalidated.dropped.push(query_name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Helper method to reparent a recipe. This is needed for some of t
     pub(super) fn sql_inc(&self) -> &SqlIncorporator {
         &self.inc
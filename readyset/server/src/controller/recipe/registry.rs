@@ -1,10 +1,14 @@
 use std::borrow::Borrow;
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
-use std::convert::TryInto;
+use std::convert::{Infallible, TryInto};
+use std::iter;
 
+use nom_sql::analysis::visit::{self, Visitor};
 use nom_sql::{
-    CreateTableStatement, CreateViewStatement, SelectSpecification, SelectStatement, SqlIdentifier,
+    Column, CommonTableExpression, CreateTableStatement, CreateViewStatement,
+    FieldDefinitionExpression, JoinConstraint, SelectSpecification, SelectStatement, SqlIdentifier,
+    Table,
 };
 use readyset_errors::{ReadySetError, ReadySetResult};
 use serde::{Deserialize, Serialize};
@@ -38,32 +42,63 @@ impl RecipeExpression {
     }
 
     /// Returns the set of table names being referenced by the [`RecipeExpression`] (for views and
-    /// queries).
+    /// queries), including the tables referenced in joins and subqueries.
     /// If the [`RecipeExpression`] is a [`RecipeExpression::Table`], then the set will be empty.
     pub(super) fn table_references(&self) -> HashSet<SqlIdentifier> {
-        match self {
-            RecipeExpression::Table(_) => HashSet::new(),
-            RecipeExpression::View(cvs) => {
-                let mut references = HashSet::new();
-                let select = cvs.definition.borrow();
-                match select {
-                    SelectSpecification::Compound(compound_select) => {
-                        references.extend(compound_select.selects.iter().flat_map(
-                            |(_, select)| select.tables.iter().map(|table| table.name.clone()),
-                        ));
-                    }
-                    SelectSpecification::Simple(select) => {
-                        references.extend(select.tables.iter().map(|table| table.name.clone()));
-                    }
-                }
-                references
-            }
-            RecipeExpression::Cache { statement, .. } => {
-                let mut references = HashSet::with_capacity(statement.tables.len());
-                references.extend(statement.tables.iter().map(|t| t.name.clone()));
-                references
-            }
+        let references = self.references();
+        references
+            .tables
+            .into_iter()
+            .filter(|table| !references.ctes.contains(table))
+            .collect()
+    }
+
+    /// Returns true if the [`RecipeExpression`] (if it's a view or a query) might reference any of
+    /// the given `columns` of `table`, and so needs to be re-planned if those columns change.
+    ///
+    /// This errs on the side of caution: unqualified columns with a matching name, wildcards that
+    /// could expand to the columns of `table`, and natural joins all count as references.
+    pub(super) fn references_columns(
+        &self,
+        table: &SqlIdentifier,
+        columns: &HashSet<SqlIdentifier>,
+    ) -> bool {
+        let references = self.references();
+        let qualifiers = match references.qualifiers.get(table) {
+            Some(qualifiers) if !references.ctes.contains(table) => qualifiers,
+            _ => return false,
+        };
+        let qualifies =
+            |t: &Option<SqlIdentifier>| t.as_ref().map_or(true, |t| qualifiers.contains(t));
+
+        references.natural_join
+            || references.wildcards.iter().any(qualifies)
+            || references
+                .columns
+                .iter()
+                .any(|c| columns.contains(&c.name) && qualifies(&c.table))
+    }
+
+    /// Collects all the tables and columns referenced by the [`RecipeExpression`].
+    fn references(&self) -> References {
+        let mut references = References::default();
+        let selects = match self {
+            RecipeExpression::Table(_) => vec![],
+            RecipeExpression::View(cvs) => match cvs.definition.borrow() {
+                SelectSpecification::Compound(compound_select) => compound_select
+                    .selects
+                    .iter()
+                    .map(|(_, select)| select)
+                    .collect(),
+                SelectSpecification::Simple(select) => vec![select],
+            },
+            RecipeExpression::Cache { statement, .. } => vec![statement],
+        };
+        for select in selects {
+            // The visitor needs mutable access to the statement, even though it never modifies it
+            let _ = references.visit_select_statement(&mut select.clone());
         }
+        references
     }
 
     /// Calculates a SHA-1 hash of the [`RecipeExpression`], to identify it based on its contents.
@@ -81,6 +116,74 @@ impl RecipeExpression {
     }
 }
 
+/// The tables and columns referenced by a view or query, collected by walking its AST.
+#[derive(Debug, Default)]
+struct References {
+    /// The names of all the tables referenced
+    tables: HashSet<SqlIdentifier>,
+    /// The names that the columns of each referenced table can be qualified with (the name of the
+    /// table itself and any aliases given to it), keyed by table name
+    qualifiers: HashMap<SqlIdentifier, HashSet<SqlIdentifier>>,
+    /// The names of all the common table expressions defined
+    ctes: HashSet<SqlIdentifier>,
+    /// All the columns referenced
+    columns: Vec<Column>,
+    /// All the wildcards selected, along with the table they're qualified with, if any
+    wildcards: Vec<Option<SqlIdentifier>>,
+    /// Whether there is a `NATURAL JOIN`, which implicitly references columns by name
+    natural_join: bool,
+}
+
+impl<'ast> Visitor<'ast> for References {
+    type Error = Infallible;
+
+    fn visit_table(&mut self, table: &'ast mut Table) -> Result<(), Self::Error> {
+        self.tables.insert(table.name.clone());
+        self.qualifiers
+            .entry(table.name.clone())
+            .or_default()
+            .extend(iter::once(table.name.clone()).chain(table.alias.clone()));
+        Ok(())
+    }
+
+    fn visit_column(&mut self, column: &'ast mut Column) -> Result<(), Self::Error> {
+        self.columns.push(column.clone());
+        Ok(())
+    }
+
+    fn visit_common_table_expression(
+        &mut self,
+        cte: &'ast mut CommonTableExpression,
+    ) -> Result<(), Self::Error> {
+        self.ctes.insert(cte.name.clone());
+        visit::walk_common_table_expression(self, cte)
+    }
+
+    fn visit_field_definition_expression(
+        &mut self,
+        fde: &'ast mut FieldDefinitionExpression,
+    ) -> Result<(), Self::Error> {
+        match fde {
+            FieldDefinitionExpression::All => self.wildcards.push(None),
+            FieldDefinitionExpression::AllInTable(table) => {
+                self.wildcards.push(Some(table.clone()))
+            }
+            FieldDefinitionExpression::Expression { .. } => {}
+        }
+        visit::walk_field_definition_expression(self, fde)
+    }
+
+    fn visit_join_constraint(
+        &mut self,
+        join_constraint: &'ast mut JoinConstraint,
+    ) -> Result<(), Self::Error> {
+        if matches!(join_constraint, JoinConstraint::Natural) {
+            self.natural_join = true;
+        }
+        visit::walk_join_constraint(self, join_constraint)
+    }
+}
+
 /// The set of all [`RecipeExpression`]s installed in a ReadySet server cluster.
 #[derive(Clone, Default, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub(super) struct ExpressionRegistry {
@@ -194,6 +297,59 @@ impl ExpressionRegistry {
         Some(expression)
     }
 
+    /// Replaces the definition of an existing table with `new_table`, keeping all the aliases of
+    /// the table and all the [`RecipeExpression`]s that depend on it.
+    ///
+    /// # Errors
+    /// A [`ReadySetError::RecipeInvariantViolated`] error is returned if there is no table with the
+    /// same name as `new_table` in the registry.
+    pub(super) fn replace_table(&mut self, new_table: CreateTableStatement) -> ReadySetResult<()> {
+        let name = new_table.table.name.clone();
+        let old_id = match self.aliases.get(&name) {
+            Some(query_id)
+                if matches!(
+                    self.expressions.get(query_id),
+                    Some(RecipeExpression::Table(_))
+                ) =>
+            {
+                *query_id
+            }
+            _ => {
+                return Err(ReadySetError::RecipeInvariantViolated(format!(
+                    "Table {} does not exist",
+                    name
+                )))
+            }
+        };
+        let expression = RecipeExpression::Table(new_table);
+        let new_id = expression.calculate_hash();
+        debug!(%name, %old_id, %new_id, "Replacing table definition in the registry");
+        self.expressions.remove(&old_id);
+        self.expressions.insert(new_id, expression);
+        self.aliases
+            .values_mut()
+            .filter(|query_id| **query_id == old_id)
+            .for_each(|query_id| *query_id = new_id);
+        if let Some(deps) = self.dependencies.remove(&old_id) {
+            self.dependencies.insert(new_id, deps);
+        }
+        Ok(())
+    }
+
+    /// Returns the [`RecipeExpression`]s that depend on the table with the given name (or alias).
+    pub(super) fn dependents(&self, name_or_alias: &SqlIdentifier) -> Vec<RecipeExpression> {
+        self.aliases
+            .get(name_or_alias)
+            .and_then(|query_id| self.dependencies.get(query_id))
+            .map(|deps| {
+                deps.iter()
+                    .filter_map(|query_id| self.expressions.get(query_id))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Returns the number of [`RecipeExpression`]s being stored in the registry.
     pub(super) fn len(&self) -> usize {
         self.expressions.len()
@@ -314,6 +470,55 @@ mod tests {
             assert_eq!(view_table_refs.len(), 1);
             assert_eq!(view_table_refs.iter().next().unwrap(), &table_name);
         }
+
+        #[test]
+        fn table_references_in_joins_and_subqueries() {
+            let cached_query = RecipeExpression::Cache {
+                name: "test_query".into(),
+                statement: nom_sql::parse_select_statement(
+                    Dialect::MySQL,
+                    "WITH c AS (SELECT id FROM t3)
+                     SELECT t1.id FROM t1
+                     JOIN t2 ON t1.id = t2.id
+                     JOIN c ON t1.id = c.id
+                     WHERE t1.x IN (SELECT x FROM t4);",
+                )
+                .unwrap(),
+            };
+
+            assert_eq!(
+                cached_query.table_references(),
+                HashSet::from(["t1".into(), "t2".into(), "t3".into(), "t4".into()])
+            );
+        }
+
+        #[test]
+        fn references_columns() {
+            let cached_query = |query: &str| RecipeExpression::Cache {
+                name: "test_query".into(),
+                statement: nom_sql::parse_select_statement(Dialect::MySQL, query).unwrap(),
+            };
+            let table: SqlIdentifier = "t1".into();
+            let columns = HashSet::from(["x".into()]);
+
+            assert!(cached_query("SELECT x FROM t1").references_columns(&table, &columns));
+            assert!(cached_query("SELECT a.x FROM t1 AS a").references_columns(&table, &columns));
+            assert!(cached_query("SELECT * FROM t1").references_columns(&table, &columns));
+            assert!(cached_query("SELECT t1.* FROM t1").references_columns(&table, &columns));
+            assert!(
+                cached_query("SELECT id FROM t1 WHERE x = ?").references_columns(&table, &columns)
+            );
+            assert!(!cached_query("SELECT id FROM t1").references_columns(&table, &columns));
+            assert!(
+                !cached_query("SELECT t1.id, t2.x FROM t1 JOIN t2 ON t1.id = t2.id")
+                    .references_columns(&table, &columns)
+            );
+            assert!(
+                !cached_query("SELECT t2.* FROM t1 JOIN t2 ON t1.id = t2.id")
+                    .references_columns(&table, &columns)
+            );
+            assert!(!cached_query("SELECT x FROM t2").references_columns(&table, &columns));
+        }
     }
 
     mod registry {
@@ -661,6 +866,34 @@ mod tests {
             assert!(registry.aliases.is_empty());
         }
 
+        #[test]
+        fn replace_table() {
+            let mut registry = create_registry();
+            let name: SqlIdentifier = "test_table".into();
+            let new_table = nom_sql::parse_create_table(
+                Dialect::MySQL,
+                "CREATE TABLE test_table (col1 INT, col2 INT);",
+            )
+            .unwrap();
+            registry.replace_table(new_table.clone()).unwrap();
+
+            assert_eq!(
+                registry.get(&name).unwrap(),
+                &RecipeExpression::Table(new_table)
+            );
+            assert_eq!(registry.len(), 3);
+            assert_eq!(registry.dependents(&name).len(), 2);
+        }
+
+        #[test]
+        fn replace_missing_table() {
+            let mut registry = create_registry();
+            let new_table =
+                nom_sql::parse_create_table(Dialect::MySQL, "CREATE TABLE test_table2 (col1 INT);")
+                    .unwrap();
+            assert!(registry.replace_table(new_table).is_err());
+        }
+
         #[test]
         fn len() {
             let registry = create_registry();
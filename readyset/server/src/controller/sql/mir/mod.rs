@@ -20,7 +20,7 @@ use nom_sql::{
     OrderClause, OrderType, SelectStatement, SqlIdentifier, TableKey, UnaryOperator,
 };
 use readyset::ViewPlaceholder;
-use readyset_data::DataType;
+use readyset_data::{DataType, NullOrder};
 use readyset_errors::{internal, internal_err, invariant, invariant_eq, unsupported, ReadySetError};
 use readyset_sql_passes::is_correlated;
 use petgraph::graph::NodeIndex;
@@ -102,6 +102,7 @@ fn default_row_for_select(st: &SelectStatement) -> Option<Vec<DataType>> {
                     FunctionExpression::Min(..) => DataType::None,
                     FunctionExpression::GroupConcat { .. } => DataType::None,
                     FunctionExpression::Call { .. } => DataType::None,
+                    FunctionExpression::Unsupported { .. } => DataType::None,
                 },
                 _ => DataType::None,
             })
@@ -141,6 +142,18 @@ pub(crate) struct Config {
     /// Enable support for mixing equality and range comparisons in a query. Support for mixed
     /// comparisons is currently unfinished, so these queries may return incorrect results.
     pub(crate) allow_mixed_comparisons: bool,
+
+    /// Where `NULL` values sort in `ORDER BY`, which depends on the dialect of the upstream
+    /// database. Defaults to MySQL's behavior.
+    #[serde(default)]
+    pub(crate) null_order: NullOrder,
+
+    /// The columns of each table whose values the replicator doesn't copy into ReadySet, because
+    /// they're either replaced with NULL or left out of the table entirely. Caches that might read
+    /// any of these columns are rejected, since they would return different results than the
+    /// upstream database.
+    #[serde(default)]
+    pub(crate) filtered_columns: HashMap<SqlIdentifier, HashSet<SqlIdentifier>>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -561,33 +574,41 @@ impl SqlToMirConverter {
                         "base table already exists, but has a different schema!",
                     );
 
-                    // Find out if this is a simple case of adding or removing a column
+                    // Find out if this is a simple case of adding, removing, or changing the
+                    // type of a column. Columns are matched up by name, so that a column whose
+                    // type changed keeps its position in the base
                     let mut columns_added = Vec::new();
                     let mut columns_removed = Vec::new();
+                    let mut columns_retyped = Vec::new();
                     let mut columns_unchanged = Vec::new();
                     for c in cols {
-                        if !schema.contains(c) {
+                        match schema.iter().find(|sc| sc.column.name == c.column.name) {
                             // new column
-                            columns_added.push(c);
-                        } else {
-                            columns_unchanged.push(c);
+                            None => columns_added.push(c),
+                            Some(sc) if sc == c => columns_unchanged.push(c),
+                            // same column, but with a different type or constraints
+                            Some(_) => columns_retyped.push(c),
                         }
                     }
                     for c in schema {
-                        if !cols.contains(c) {
+                        if !cols.iter().any(|cc| cc.column.name == c.column.name) {
                             // dropped column
                             columns_removed.push(c);
                         }
                     }
 
-                    if !columns_unchanged.is_empty()
-                        && (!columns_added.is_empty() || !columns_removed.is_empty())
+                    if !(columns_unchanged.is_empty() && columns_retyped.is_empty())
+                        && (!columns_added.is_empty()
+                            || !columns_removed.is_empty()
+                            || !columns_retyped.is_empty())
                     {
-                        error!(
+                        debug!(
                             %name,
                             ?columns_added,
                             ?columns_removed,
-                            %existing_version
+                            ?columns_retyped,
+                            %existing_version,
+                            "adapting existing base table to new schema"
                         );
                         let node_key = (name.clone(), existing_version);
                         let existing_node =
@@ -604,6 +625,14 @@ impl SqlToMirConverter {
                             .iter()
                             .map(|&(ref cs, _)| cs.clone())
                             .collect();
+                        for retyped in &columns_retyped {
+                            if let Some(cs) = columns
+                                .iter_mut()
+                                .find(|cs| cs.column.name == retyped.column.name)
+                            {
+                                *cs = (*retyped).clone();
+                            }
+                        }
                         for added in &columns_added {
                             columns.push((*added).clone());
                         }
@@ -635,6 +664,7 @@ impl SqlToMirConverter {
                             existing_node,
                             columns_added,
                             columns_removed,
+                            columns_retyped,
                         ));
                     } else {
                         warn!("base table has complex schema change");
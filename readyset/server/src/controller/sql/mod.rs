@@ -1,4 +1,3 @@
-use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::str;
 use std::vec::Vec;
@@ -12,11 +11,12 @@ use ::serde::{Deserialize, Serialize};
 use launchpad::redacted::Sensitive;
 use nom_sql::analysis::ReferredTables;
 use nom_sql::{
-    parser as sql_parser, BinaryOperator, CompoundSelectOperator, CompoundSelectStatement,
-    CreateTableStatement, FieldDefinitionExpression, SelectStatement, SqlIdentifier, SqlQuery,
-    Table,
+    parser as sql_parser, BinaryOperator, ColumnConstraint, CompoundSelectOperator,
+    CompoundSelectStatement, CreateTableOption, CreateTableStatement, FieldDefinitionExpression,
+    SelectStatement, SqlIdentifier, SqlQuery, SqlType, Table,
 };
 use readyset::internal::IndexType;
+use readyset::recipe::QueryReuse;
 use readyset_errors::{
     internal, internal_err, invalid_err, unsupported, ReadySetError, ReadySetResult,
 };
@@ -24,8 +24,8 @@ use readyset_sql_passes::alias_removal::TableAliasRewrite;
 use readyset_sql_passes::{
     contains_aggregate, AliasRemoval, CountStarRewrite, DetectProblematicSelfJoins,
     ImpliedTableExpansion, KeyDefinitionCoalescing, NegationRemoval, NormalizeTopKWithAggregate,
-    OrderLimitRemoval, RemoveNumericFieldReferences, RewriteBetween, StarExpansion,
-    StripPostFilters,
+    OrderLimitRemoval, RemoveNumericFieldReferences, RewriteBetween, RewriteJoinUsing,
+    StarExpansion, StripPostFilters,
 };
 use petgraph::graph::NodeIndex;
 use tracing::{debug, trace, warn};
@@ -49,8 +49,15 @@ mod serde;
 enum QueryGraphReuse<'a> {
     ExactMatch(&'a MirQuery),
     ExtendExisting(Vec<u64>),
-    /// (node, columns to re-project if necessary, parameters, index_type)
-    ReaderOntoExisting(MirNodeRef, Option<Vec<Column>>, Vec<Column>, IndexType),
+    /// (name of the existing query, node, columns to re-project if necessary, parameters,
+    /// index_type)
+    ReaderOntoExisting(
+        SqlIdentifier,
+        MirNodeRef,
+        Option<Vec<Column>>,
+        Vec<Column>,
+        IndexType,
+    ),
     None,
 }
 
@@ -90,6 +97,10 @@ pub(crate) struct SqlIncorporator {
     base_schemas: HashMap<SqlIdentifier, CreateTableStatement>,
     view_schemas: HashMap<SqlIdentifier, Vec<SqlIdentifier>>,
 
+    /// Records, for each query that was built on top of the nodes of an existing query, which
+    /// query it reused and how much of it
+    reused_queries: HashMap<SqlIdentifier, QueryReuse>,
+
     pub(crate) config: Config,
 }
 
@@ -168,6 +179,12 @@ impl SqlIncorporator {
             .map(|s| s.iter().map(SqlIdentifier::to_string).collect())
     }
 
+    /// Returns which existing query (if any) the query named `name` reused the nodes of when it
+    /// was added
+    pub(super) fn get_query_reuse(&self, name: &str) -> Option<&QueryReuse> {
+        self.reused_queries.get(name)
+    }
+
     #[cfg(test)]
     fn get_flow_node_address(&self, name: &SqlIdentifier, v: usize) -> Option<NodeIndex> {
         self.mir_converter.get_flow_node_address(name, v)
@@ -355,6 +372,7 @@ impl SqlIncorporator {
                             return Ok((
                                 qg,
                                 QueryGraphReuse::ReaderOntoExisting(
+                                    mir_query.name.clone(),
                                     parent,
                                     None,
                                     params,
@@ -422,6 +440,7 @@ impl SqlIncorporator {
                             return Ok((
                                 qg,
                                 QueryGraphReuse::ReaderOntoExisting(
+                                    mir_query.name.clone(),
                                     ancestor,
                                     project_columns,
                                     params,
@@ -460,9 +479,13 @@ impl SqlIncorporator {
     fn add_base_via_mir(
         &mut self,
         query_name: &SqlIdentifier,
-        stmt: CreateTableStatement,
+        mut stmt: CreateTableStatement,
         mig: &mut Migration<'_>,
     ) -> ReadySetResult<QueryFlowParts> {
+        // make the table's default collation explicit on its text columns, so that clients (which
+        // only see column specifications) know how to compare values in each column
+        apply_default_collation(&mut stmt);
+
         // first, compute the MIR representation of the SQL query
         let mut mir = self.mir_converter.named_base_to_mir(query_name, &stmt)?;
 
@@ -471,7 +494,7 @@ impl SqlIncorporator {
         // no optimization, because standalone base nodes can't be optimized
 
         // push it into the flow graph using the migration in `mig`, and obtain `QueryFlowParts`
-        let qfp = mir_query_to_flow_parts(&mut mir, mig)?;
+        let qfp = mir_query_to_flow_parts(&mut mir, self.mir_converter.config.null_order, mig)?;
 
         // remember the schema in case we need it later
         // on base table schema change, we will overwrite the existing schema here.
@@ -518,7 +541,11 @@ impl SqlIncorporator {
             is_leaf,
         )?;
 
-        let qfp = mir_query_to_flow_parts(&mut combined_mir_query, mig)?;
+        let qfp = mir_query_to_flow_parts(
+            &mut combined_mir_query,
+            self.mir_converter.config.null_order,
+            mig,
+        )?;
 
         self.register_query(query_name, None, &combined_mir_query);
 
@@ -534,13 +561,19 @@ impl SqlIncorporator {
     ) -> ReadySetResult<(QueryGraph, MirQuery)> {
         let (qg, reuse) = self.consider_query_graph(query_name, is_name_required, sq, is_leaf)?;
 
-        let mir_query = match reuse {
-            QueryGraphReuse::ExactMatch(mir_query) => mir_query.clone(),
+        let (mir_query, reused) = match reuse {
+            QueryGraphReuse::ExactMatch(mir_query) => (
+                mir_query.clone(),
+                Some(QueryReuse {
+                    reused_query: mir_query.name.to_string(),
+                    reused_nodes: mir_query.topo_nodes().len(),
+                }),
+            ),
             QueryGraphReuse::ExtendExisting(reuse_mirs) => {
                 let mut new_query_mir = self
                     .mir_converter
                     .named_query_to_mir(query_name, sq, &qg, is_leaf)?;
-                let mut num_reused_nodes = 0;
+                let mut reused: Option<QueryReuse> = None;
                 for m in reuse_mirs {
                     if !self.mir_queries.contains_key(&m) {
                         continue;
@@ -548,27 +581,62 @@ impl SqlIncorporator {
                     let mq = &self.mir_queries[&m];
                     let (merged_mir, merged_nodes) = merge_mir_for_queries(&new_query_mir, mq);
                     new_query_mir = merged_mir;
-                    num_reused_nodes = max(merged_nodes, num_reused_nodes);
+                    if reused
+                        .as_ref()
+                        .map_or(merged_nodes > 0, |r| merged_nodes > r.reused_nodes)
+                    {
+                        reused = Some(QueryReuse {
+                            reused_query: mq.name.to_string(),
+                            reused_nodes: merged_nodes,
+                        });
+                    }
                 }
-                new_query_mir
+                (new_query_mir, reused)
             }
             QueryGraphReuse::ReaderOntoExisting(
+                existing_query,
                 final_query_node,
                 project_columns,
                 params,
                 index_type,
-            ) => self.mir_converter.add_leaf_below(
-                final_query_node,
-                query_name,
-                &params,
-                index_type,
-                project_columns,
+            ) => {
+                let reused = QueryReuse {
+                    reused_query: existing_query.to_string(),
+                    reused_nodes: count_ancestors(&final_query_node) + 1,
+                };
+                (
+                    self.mir_converter.add_leaf_below(
+                        final_query_node,
+                        query_name,
+                        &params,
+                        index_type,
+                        project_columns,
+                    ),
+                    Some(reused),
+                )
+            }
+            QueryGraphReuse::None => (
+                self.mir_converter
+                    .named_query_to_mir(query_name, sq, &qg, is_leaf)?,
+                None,
             ),
-            QueryGraphReuse::None => self
-                .mir_converter
-                .named_query_to_mir(query_name, sq, &qg, is_leaf)?,
         };
 
+        match reused {
+            Some(reused) => {
+                debug!(
+                    %query_name,
+                    reused_query = %reused.reused_query,
+                    reused_nodes = reused.reused_nodes,
+                    "Reusing nodes of an existing query"
+                );
+                self.reused_queries.insert(query_name.clone(), reused);
+            }
+            None => {
+                self.reused_queries.remove(query_name);
+            }
+        }
+
         Ok((qg, mir_query))
     }
 
@@ -592,10 +660,14 @@ impl SqlIncorporator {
             .map_err(on_err)?;
 
         trace!(pre_opt_mir = %mir_query.to_graphviz());
-        let mut opt_mir = mir_query.optimize().map_err(on_err)?;
+        // Joins project all of their parents' columns so they can be reused by other queries, so
+        // only prune the columns nothing needs if we're not going to reuse them
+        let prune_columns = self.config.reuse_type.is_none();
+        let mut opt_mir = mir_query.optimize(prune_columns).map_err(on_err)?;
         trace!(post_opt_mir = %opt_mir.to_graphviz());
 
-        let qfp = mir_query_to_flow_parts(&mut opt_mir, mig).map_err(on_err)?;
+        let qfp = mir_query_to_flow_parts(&mut opt_mir, self.mir_converter.config.null_order, mig)
+            .map_err(on_err)?;
         self.register_query(query_name, Some(qg), &opt_mir);
         Ok((qfp, opt_mir))
     }
@@ -627,6 +699,7 @@ impl SqlIncorporator {
         self.mir_queries.remove(&(qg_hash)).unwrap();
         self.query_graphs.remove(&qg_hash).unwrap();
         self.view_schemas.remove(query_name).unwrap();
+        self.reused_queries.remove(query_name);
 
         // traverse self.leaf__addresses
         if !self.leaf_addresses.values().any(|id| *id == nodeid) {
@@ -721,8 +794,10 @@ impl SqlIncorporator {
         let name = match q {
             SqlQuery::CreateTable(ref ctq) => ctq.table.name.clone(),
             SqlQuery::CreateView(ref cvq) => cvq.name.clone(),
+            // Derive the name from the text of the query rather than the number of queries, so
+            // that it's the same however many queries were added before it
             SqlQuery::Select(_) | SqlQuery::CompoundSelect(_) => {
-                format!("q_{}", self.num_queries).into()
+                format!("q_{:x}", readyset::recipe::hash_query(&q)).into()
             }
             _ => unsupported!("only CREATE TABLE and SELECT queries can be added to the graph!"),
         };
@@ -753,7 +828,18 @@ impl SqlIncorporator {
             | SqlQuery::Show(_)
             | SqlQuery::Explain(_)
             | SqlQuery::DropCache(_)
-            | SqlQuery::DropView(_) => (),
+            | SqlQuery::DropAllCaches(_)
+            | SqlQuery::AlterCache(_)
+            | SqlQuery::FlushWait(_)
+            | SqlQuery::DropView(_)
+            | SqlQuery::Fetch(_)
+            | SqlQuery::CloseCursor(_)
+            | SqlQuery::Values(_)
+            | SqlQuery::Maintenance(_)
+            | SqlQuery::Merge(_)
+            | SqlQuery::Prepare(_)
+            | SqlQuery::Execute(_)
+            | SqlQuery::Deallocate(_) => (),
             // other kinds of queries *do* require their referred tables to exist!
             ref q @ SqlQuery::CompoundSelect(_)
             | ref q @ SqlQuery::Select(_)
@@ -762,9 +848,11 @@ impl SqlIncorporator {
             | ref q @ SqlQuery::Delete(_)
             | ref q @ SqlQuery::DropTable(_)
             | ref q @ SqlQuery::AlterTable(_)
+            | ref q @ SqlQuery::AlterReadyset(_)
             | ref q @ SqlQuery::RenameTable(_)
             | ref q @ SqlQuery::Insert(_)
-            | ref q @ SqlQuery::CreateCache(_) => {
+            | ref q @ SqlQuery::CreateCache(_)
+            | ref q @ SqlQuery::DeclareCursor(_) => {
                 for t in &q.referred_tables() {
                     if !self.view_schemas.contains_key(&t.name) {
                         return Err(ReadySetError::TableNotFound(t.name.to_string()));
@@ -778,6 +866,7 @@ impl SqlIncorporator {
             .remove_negation()?
             .strip_post_filters()
             .coalesce_key_definitions()
+            .rewrite_join_using(&self.view_schemas)?
             .expand_stars(&self.view_schemas)?
             .expand_implied_tables(&self.view_schemas)?
             .normalize_topk_with_aggregate()?
@@ -895,6 +984,54 @@ impl SqlIncorporator {
     }
 }
 
+/// Returns the number of distinct transitive ancestors of `node`
+fn count_ancestors(node: &MirNodeRef) -> usize {
+    let mut seen = HashSet::new();
+    let mut stack = vec![node.clone()];
+    while let Some(n) = stack.pop() {
+        for ancestor in n.borrow().ancestors() {
+            let ancestor = ancestor.upgrade().unwrap();
+            if seen.insert(ancestor.borrow().versioned_name()) {
+                stack.push(ancestor.clone());
+            }
+        }
+    }
+    seen.len()
+}
+
+/// Add the default collation declared for the table created by `stmt` (if any) to all of its text
+/// columns that don't declare their own collation
+fn apply_default_collation(stmt: &mut CreateTableStatement) {
+    let collation = match stmt.options.iter().find_map(|o| match o {
+        CreateTableOption::Collate(c) => Some(c.clone()),
+        _ => None,
+    }) {
+        Some(c) => c,
+        None => return,
+    };
+
+    for field in &mut stmt.fields {
+        let is_text = matches!(
+            field.sql_type,
+            SqlType::Char(_)
+                | SqlType::Varchar(_)
+                | SqlType::Tinytext
+                | SqlType::Mediumtext
+                | SqlType::Longtext
+                | SqlType::Text
+        );
+        let has_collation = field
+            .constraints
+            .iter()
+            .any(|c| matches!(c, ColumnConstraint::Collation(_)));
+        if is_text && !has_collation {
+            field
+                .constraints
+                .push(ColumnConstraint::Collation(collation.clone()));
+        }
+    }
+}
+
 /// Enables incorporation of a textual SQL query into a Soup graph.
 trait ToFlowParts {
     /// Turn a SQL query into a set of nodes inserted into the Soup graph managed by
@@ -1474,6 +1611,8 @@ mod tests {
             // Add a new query
             let res = inc.add_query("SELECT id, name FROM users WHERE users.id = ?;", None, mig);
             assert!(res.is_ok());
+            let existing_name = res.unwrap().name;
+            assert_eq!(inc.get_query_reuse(&existing_name), None);
 
             // Add the same query again, but with a parameter on a different column.
             // Project the same columns, so we can reuse the projection that already exists and only
@@ -1494,6 +1633,10 @@ mod tests {
             // we should be based off the identity as our leaf
             let id_node = qfp.new_nodes.get(0).unwrap();
             assert_eq!(qfp.query_leaf, *id_node);
+            // and the reuse of the existing query should have been recorded
+            let reuse = inc.get_query_reuse(&qfp.name).unwrap();
+            assert_eq!(reuse.reused_query, existing_name.to_string());
+            assert!(reuse.reused_nodes > 0);
 
             // Do it again with a parameter on yet a different column.
             // Project different columns, so we need to add a new projection (not an identity).
@@ -2475,7 +2618,7 @@ mod tests {
             // XXX(malte): non-deterministic join ordering make it difficult to assert on the join
             // views
             // leaf view
-            let leaf_view = get_node(&inc, mig, "q_3");
+            let leaf_view = get_node(&inc, mig, &q.as_ref().unwrap().name);
             assert_eq!(
                 leaf_view
                     .columns()
@@ -2554,7 +2697,7 @@ mod tests {
                 &["aid", "title", "author", "name", "uid"]
             );
             // leaf view
-            let leaf_view = get_node(&inc, mig, "q_3");
+            let leaf_view = get_node(&inc, mig, &q.as_ref().unwrap().name);
             assert_eq!(
                 leaf_view
                     .columns()
@@ -2663,7 +2806,7 @@ mod tests {
             assert_eq!(join.description(true), "[1:0, 1:1, 2:1] 1:(1) ⋈ 2:(0)");
 
             // Check leaf projection node
-            let leaf_view = get_node(&inc, mig, "q_1");
+            let leaf_view = get_node(&inc, mig, &qfp.name);
             assert_eq!(
                 leaf_view
                     .columns()
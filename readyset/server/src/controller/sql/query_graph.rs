@@ -17,7 +17,9 @@ use readyset_errors::{
     internal, internal_err, invalid_err, invariant, invariant_eq, no_table_for_col, unsupported,
     unsupported_err, ReadySetResult,
 };
-use readyset_sql_passes::{is_aggregate, is_predicate, map_aggregates, LogicalOp};
+use readyset_sql_passes::{
+    is_aggregate, is_predicate, map_aggregates, outermost_referred_tables, LogicalOp,
+};
 use serde::{Deserialize, Serialize};
 
 use super::mir::{self, PAGE_NUMBER_COL};
@@ -773,6 +775,12 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
         unsupported!("SELECT statements with no tables are unsupported")
     }
 
+    // Sampling returns a different random subset of the rows on every execution, so it can't be
+    // cached
+    if outermost_referred_tables(st).any(|table| table.sample.is_some()) {
+        unsupported!("TABLESAMPLE is unsupported")
+    }
+
     // a handy closure for making new relation nodes
     let new_node = |rel: SqlIdentifier,
                     preds: Vec<Expression>,
@@ -844,7 +852,7 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
                     );
                 }
             }
-            JoinRightSide::NestedSelect(subquery, alias) => {
+            JoinRightSide::NestedSelect(subquery, alias, _) => {
                 if !qg.relations.contains_key(alias) {
                     let mut node = new_node(alias.clone(), vec![], st)?;
                     node.subgraph = Some((Box::new(to_query_graph(subquery)?), *subquery.clone()));
@@ -852,6 +860,9 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
                 }
             }
             JoinRightSide::Tables(_) => unsupported!("JoinRightSide::Tables not yet implemented"),
+            JoinRightSide::Values(_) => {
+                unsupported!("VALUES table constructors are not yet supported in cached queries")
+            }
         }
     }
 
@@ -872,7 +883,7 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
     for jc in &st.join {
         let rhs_name = match &jc.right {
             JoinRightSide::Table(table) => &table.name,
-            JoinRightSide::NestedSelect(_, alias) => alias,
+            JoinRightSide::NestedSelect(_, alias, _) => alias,
             _ => internal!(),
         };
         // will be defined by join constraint
@@ -948,6 +959,9 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
                     right: col_expr(&right_table, &col.name),
                 }]
             }
+            JoinConstraint::Natural => {
+                internal!("NATURAL joins should have been rewritten before building a query graph")
+            }
             JoinConstraint::Empty => {
                 // prev_table must exist because we error on st.tables.is_empty()
                 #[allow(clippy::unwrap_used)]
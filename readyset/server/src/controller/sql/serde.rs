@@ -32,6 +32,7 @@ use mir::node::MirNode;
 use mir::query::MirQuery;
 use mir::MirNodeRef;
 use nom_sql::{CreateTableStatement, SqlIdentifier};
+use readyset::recipe::QueryReuse;
 use readyset_errors::{internal_err, invariant, ReadySetError, ReadySetResult};
 use petgraph::graph::NodeIndex;
 use serde::{Deserialize, Serialize, Serializer};
@@ -136,6 +137,8 @@ struct SerializableSqlIncorporator {
     base_schemas: HashMap<SqlIdentifier, CreateTableStatement>,
     view_schemas: HashMap<SqlIdentifier, Vec<SqlIdentifier>>,
 
+    reused_queries: HashMap<SqlIdentifier, QueryReuse>,
+
     pub(crate) config: Config,
 }
 
@@ -166,6 +169,7 @@ impl<'a> TryFrom<&'a SqlIncorporator> for SerializableSqlIncorporator {
             num_queries: inc.num_queries,
             base_schemas: inc.base_schemas.clone(),
             view_schemas: inc.view_schemas.clone(),
+            reused_queries: inc.reused_queries.clone(),
             config: inc.config.clone(),
         })
     }
@@ -301,6 +305,7 @@ where
         num_queries: serialized_inc.num_queries,
         base_schemas: serialized_inc.base_schemas,
         view_schemas: serialized_inc.view_schemas,
+        reused_queries: serialized_inc.reused_queries,
         config: serialized_inc.config,
     })
 }
@@ -35,18 +35,19 @@ use nom_sql::{CacheInner, CreateCacheStatement, SelectStatement, SqlIdentifier,
 use readyset::builders::{ReplicaShard, TableBuilder, ViewBuilder, ViewReplica};
 use readyset::consensus::{Authority, AuthorityControl};
 use readyset::debug::info::{DomainKey, GraphInfo};
-use readyset::debug::stats::{DomainStats, GraphStats, NodeStats};
+use readyset::debug::stats::{self, DomainStats, GraphStats, NodeStats};
 use readyset::internal::MaterializationStatus;
 use readyset::metrics::recorded;
 use readyset::recipe::changelist::{Change, ChangeList};
-use readyset::recipe::ExtendRecipeSpec;
-use readyset::replication::{ReplicationOffset, ReplicationOffsets};
+use readyset::recipe::{ExtendRecipeSpec, QueryReuse};
+use readyset::replication::{ReplicationOffset, ReplicationOffsets, TableSchemaVersion};
+use readyset::storage::CompressionOptions;
 use readyset::{
-    ActivationResult, ReaderReplicationResult, ReaderReplicationSpec, ReadySetError,
-    ReadySetResult, ViewFilter, ViewRequest, ViewSchema,
+    ActivationResult, CacheCreationStatus, PlannedNode, ReaderReplicationResult,
+    ReaderReplicationSpec, ReadySetError, ReadySetResult, ViewFilter, ViewRequest, ViewSchema,
 };
 use readyset_errors::{bad_request_err, internal, internal_err, invariant_eq, NodeType};
-use petgraph::visit::Bfs;
+use petgraph::visit::{Bfs, Reversed};
 use regex::Regex;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -55,6 +56,7 @@ use tracing::{debug, error, info, instrument, trace, warn};
 use vec1::Vec1;
 
 use crate::controller::domain_handle::DomainHandle;
+use crate::controller::migrate::cost::{self, TableStatistics};
 use crate::controller::migrate::materialization::Materializations;
 use crate::controller::migrate::scheduling::Scheduler;
 use crate::controller::migrate::{routing, DomainMigrationPlan, Migration};
@@ -104,6 +106,14 @@ pub struct DataflowState {
     /// Placement restrictions for nodes and the domains they are placed into.
     #[serde(with = "serde_with::rust::hashmap_as_tuple_list")]
     pub(super) node_restrictions: HashMap<NodeRestrictionKey, DomainPlacementRestriction>,
+    /// Columns to shard base tables by when they're created, keyed by table name, overriding the
+    /// column that would otherwise be chosen automatically
+    #[serde(default)]
+    pub(super) table_sharding: HashMap<SqlIdentifier, SqlIdentifier>,
+    /// Every version of the definition of each base table, oldest first, keyed by table name.
+    /// A new version is recorded whenever a table is created or altered.
+    #[serde(default)]
+    schema_versions: HashMap<SqlIdentifier, Vec<TableSchemaVersion>>,
 
     #[serde(skip)]
     pub(super) domains: HashMap<DomainIndex, DomainHandle>,
@@ -118,6 +128,17 @@ pub struct DataflowState {
     #[serde(skip)]
     pub(super) workers: HashMap<WorkerIdentifier, Worker>,
 
+    /// Nodes in domains that were running on workers that failed, but which couldn't be placed
+    /// onto any of the remaining workers - usually because they contain base tables whose
+    /// persisted state lives on the failed worker's volume. These domains are recovered once a
+    /// worker that can run them registers, so that their base tables are reopened from disk
+    /// rather than being lost.
+    ///
+    /// This is persisted along with the rest of the controller state, so that a new leader elected
+    /// while recovery is deferred still knows which domains are waiting for a worker.
+    #[serde(with = "serde_with::rust::hashmap_as_tuple_list")]
+    pub(super) deferred_recovery: HashMap<DomainIndex, HashSet<NodeIndex>>,
+
     /// State between migrations
     #[serde(with = "serde_with::rust::hashmap_as_tuple_list")]
     pub(super) remap: HashMap<DomainIndex, HashMap<NodeIndex, IndexPair>>,
@@ -156,11 +177,14 @@ impl DataflowState {
             recipe,
             schema_replication_offset,
             node_restrictions,
+            table_sharding: Default::default(),
+            schema_versions: Default::default(),
             domains: Default::default(),
             domain_nodes: Default::default(),
             channel_coordinator,
             read_addrs: Default::default(),
             workers: Default::default(),
+            deferred_recovery: Default::default(),
             remap: Default::default(),
             keep_prior_recipes,
         }
@@ -170,6 +194,41 @@ impl DataflowState {
         &self.schema_replication_offset
     }
 
+    /// Returns every recorded version of the definition of the base table with the given name,
+    /// oldest first.
+    pub(super) fn table_schema_versions(&self, table: &str) -> Vec<TableSchemaVersion> {
+        self.schema_versions.get(table).cloned().unwrap_or_default()
+    }
+
+    /// Records the current definition of each of the given tables as a new version in the schema
+    /// registry, unless it is the same as the latest version already recorded for that table.
+    fn record_schema_versions(
+        &mut self,
+        tables: Vec<SqlIdentifier>,
+        replication_offset: Option<&ReplicationOffset>,
+    ) {
+        for table in tables {
+            let statement = match self.recipe.schema_for(&table) {
+                Some(Schema::Table(statement)) => statement,
+                _ => continue,
+            };
+            let versions = self.schema_versions.entry(table).or_default();
+            if versions.last().map_or(false, |v| v.statement == statement) {
+                continue;
+            }
+            debug!(
+                table = %statement.table.name,
+                version = versions.len(),
+                "recording new version of table schema"
+            );
+            versions.push(TableSchemaVersion {
+                version: versions.len() as u64,
+                statement,
+                replication_offset: replication_offset.cloned(),
+            });
+        }
+    }
+
     pub(super) fn get_info(&self) -> ReadySetResult<GraphInfo> {
         let mut worker_info = HashMap::new();
         for (di, dh) in self.domains.iter() {
@@ -491,6 +550,11 @@ impl DataflowState {
             is_primary = true;
         }
 
+        let shard_column = match node.sharded_by() {
+            Sharding::ByColumn(col, _) if key != [col] => Some(col),
+            _ => None,
+        };
+
         let txs = (0..self
             .domains
             .get(&node.domain())
@@ -548,11 +612,13 @@ impl DataflowState {
             addr: node.local_addr(),
             key,
             key_is_primary: is_primary,
+            shard_column,
             dropped: base_operator.get_dropped(),
             table_name: node.name().clone(),
             columns,
             schema,
             table_request_timeout: self.domain_config.table_request_timeout,
+            max_value_size: self.domain_config.max_value_size,
         }))
     }
 
@@ -575,6 +641,64 @@ impl DataflowState {
         Ok(GraphStats { domains })
     }
 
+    /// Returns statistics about every base table in the graph, summed over all of its shards.
+    pub(super) async fn table_statistics(
+        &self,
+    ) -> ReadySetResult<HashMap<NodeIndex, TableStatistics>> {
+        let graph_stats = self.get_statistics().await?;
+        let mut tables: HashMap<NodeIndex, TableStatistics> = HashMap::new();
+        let mut collected: HashMap<NodeIndex, stats::TableStatistics> = HashMap::new();
+        for (_, nodes) in graph_stats.domains.values() {
+            for (ni, node_stats) in nodes {
+                #[allow(clippy::indexing_slicing)] // came from a domain in the graph
+                if !self.ingredients[*ni].is_base() {
+                    continue;
+                }
+                let table = tables.entry(*ni).or_default();
+                table.rows += node_stats.rows;
+                table.bytes += node_stats.mem_size;
+                if let Some(table_stats) = &node_stats.table_statistics {
+                    collected.entry(*ni).or_default().merge(table_stats.clone());
+                }
+            }
+        }
+        for (ni, table_stats) in collected {
+            if let Some(table) = tables.get_mut(&ni) {
+                table.distinct = table_stats.columns.iter().map(|c| c.distinct).collect();
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Returns the statistics collected about the rows of every base table in the graph, summed
+    /// over all of its shards, keyed by the name of the table, along with the names of the
+    /// table's columns.
+    pub(super) async fn base_table_statistics(
+        &self,
+    ) -> ReadySetResult<BTreeMap<SqlIdentifier, (Vec<SqlIdentifier>, stats::TableStatistics)>> {
+        let graph_stats = self.get_statistics().await?;
+        let mut tables = BTreeMap::new();
+        for (_, nodes) in graph_stats.domains.values() {
+            for (ni, node_stats) in nodes {
+                let table_stats = match &node_stats.table_statistics {
+                    Some(table_stats) => table_stats,
+                    None => continue,
+                };
+                #[allow(clippy::indexing_slicing)] // came from a domain in the graph
+                let node = &self.ingredients[*ni];
+                tables
+                    .entry(node.name().clone())
+                    .or_insert_with(|| {
+                        let columns = node.columns().iter().map(|c| c.name().into()).collect();
+                        (columns, stats::TableStatistics::default())
+                    })
+                    .1
+                    .merge(table_stats.clone());
+            }
+        }
+        Ok(tables)
+    }
+
     pub(super) fn get_instances(&self) -> Vec<(WorkerIdentifier, bool)> {
         self.workers
             .iter()
@@ -588,9 +712,120 @@ impl DataflowState {
             detailed,
             &self.materializations,
             Some(&self.domain_nodes),
+            None,
         )
     }
 
+    /// Returns the nodes in the dataflow graph that the query named `name` reads from: the
+    /// query's readers, and all of their ancestors.
+    fn nodes_for_query(&self, name: &str) -> ReadySetResult<HashSet<NodeIndex>> {
+        let name = SqlIdentifier::from(name);
+        let node = match self.recipe.node_addr_for(&name) {
+            Ok(ni) => ni,
+            Err(_) => *self
+                .outputs()
+                .get(&name)
+                .ok_or_else(|| ReadySetError::ViewNotFound(name.to_string()))?,
+        };
+        let name = self.recipe.resolve_alias(&name).unwrap_or(&name);
+
+        let mut readers = self.find_readers_for(node, name, &None);
+        if readers.is_empty() {
+            readers.push(node);
+        }
+        let mut nodes = HashSet::new();
+        for reader in readers {
+            let mut bfs = Bfs::new(Reversed(&self.ingredients), reader);
+            while let Some(ancestor) = bfs.next(Reversed(&self.ingredients)) {
+                nodes.insert(ancestor);
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Returns a graphviz representation of the part of the dataflow graph that the query named
+    /// `name` reads from.
+    pub(super) fn graphviz_for_query(&self, name: &str, detailed: bool) -> ReadySetResult<String> {
+        Ok(graphviz(
+            &self.ingredients,
+            detailed,
+            &self.materializations,
+            Some(&self.domain_nodes),
+            Some(&self.nodes_for_query(name)?),
+        ))
+    }
+
+    /// Returns which existing query the query named `name` reused the dataflow nodes of when it was
+    /// added, or `None` if it was built from scratch.
+    pub(super) fn query_reuse(&self, name: &str) -> ReadySetResult<Option<QueryReuse>> {
+        let name = SqlIdentifier::from(name);
+        if self.recipe.node_addr_for(&name).is_err() {
+            return Err(ReadySetError::ViewNotFound(name.to_string()));
+        }
+        Ok(self.recipe.query_reuse(&name).cloned())
+    }
+
+    /// Returns the dataflow graph as a JSON object with a list of `nodes`, each with the domain
+    /// it's in and its materialization status and indexes, and a list of `edges` between them.
+    ///
+    /// If `query` is set, only the part of the graph that the query with that name reads from is
+    /// returned.
+    pub(super) fn graph_json(&self, query: Option<&str>) -> ReadySetResult<serde_json::Value> {
+        let query_nodes = query.map(|name| self.nodes_for_query(name)).transpose()?;
+        let included = |index: &NodeIndex| query_nodes.as_ref().map_or(true, |n| n.contains(index));
+        let domain_for_node = self
+            .domain_nodes
+            .iter()
+            .flat_map(|(di, nodes)| nodes.iter().map(move |(_, ni)| (*ni, *di)))
+            .collect::<HashMap<_, _>>();
+
+        let nodes = self
+            .ingredients
+            .node_indices()
+            .filter(included)
+            .map(|index| {
+                #[allow(clippy::indexing_slicing)] // just got this out of the graph
+                let node = &self.ingredients[index];
+                let kind = if node.is_source() {
+                    "source"
+                } else if node.is_base() {
+                    "base"
+                } else if node.is_reader() {
+                    "reader"
+                } else if node.is_ingress() {
+                    "ingress"
+                } else if node.is_egress() {
+                    "egress"
+                } else {
+                    "internal"
+                };
+                serde_json::json!({
+                    "id": index.index(),
+                    "name": node.name().as_str(),
+                    "kind": kind,
+                    "description": node.description(true),
+                    "domain": domain_for_node.get(&index).map(|di| usize::from(*di)),
+                    "materialization": self.materializations.get_status(index, node),
+                    "indexes": self.materializations.indexes(index),
+                })
+            })
+            .collect::<Vec<_>>();
+        let edges = self
+            .ingredients
+            .raw_edges()
+            .iter()
+            .filter(|edge| included(&edge.source()) && included(&edge.target()))
+            .map(|edge| {
+                serde_json::json!({
+                    "source": edge.source().index(),
+                    "target": edge.target().index(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(serde_json::json!({ "nodes": nodes, "edges": edges }))
+    }
+
     /// List data-flow nodes, on a specific worker if `worker` specified.
     pub(super) fn nodes_on_worker(
         &self,
@@ -619,6 +854,201 @@ impl DataflowState {
             })
     }
 
+    /// Move all the domains running on `worker` onto other workers, and stop scheduling domains
+    /// onto it, so that it can be shut down without its domains being unavailable until its
+    /// failure is detected.
+    ///
+    /// Like when a worker fails, every shard of each affected domain is shut down and then
+    /// recovered on the remaining workers, so the materialized state in those domains is rebuilt
+    /// by replays (for full materializations) or upqueries (for partial materializations).
+    pub(super) async fn drain_worker(&mut self, worker: &WorkerIdentifier) -> ReadySetResult<()> {
+        if !self
+            .workers
+            .iter()
+            .any(|(wi, w)| wi != worker && w.healthy && !w.draining)
+        {
+            return Err(bad_request_err(format!(
+                "Can't drain worker {}, since there are no other workers to move its domains to",
+                worker
+            )));
+        }
+        match self.workers.get_mut(worker) {
+            Some(w) => w.draining = true,
+            None => return Err(bad_request_err(format!("Unknown worker {}", worker))),
+        }
+
+        let domain_nodes = self.nodes_on_worker(Some(worker));
+
+        // Make sure every domain can be placed on another worker before shutting any of them down
+        let schedulable = Scheduler::new(self, &None).and_then(|mut scheduler| {
+            domain_nodes.iter().try_for_each(|(domain, nodes)| {
+                let nodes = nodes.iter().map(|n| (*n, true)).collect::<Vec<_>>();
+                scheduler.schedule_domain(*domain, &nodes).map(|_| ())
+            })
+        });
+        if let Err(e) = schedulable {
+            if let Some(w) = self.workers.get_mut(worker) {
+                w.draining = false;
+            }
+            return Err(e);
+        }
+
+        info!(
+            %worker,
+            num_domains = domain_nodes.len(),
+            "draining domains from worker"
+        );
+        self.move_domains(&domain_nodes).await?;
+        info!(%worker, "finished draining worker");
+        Ok(())
+    }
+
+    /// Move some of the most heavily loaded domains from the other workers onto `worker`, which
+    /// has just joined the cluster, so that it runs roughly its share of the cluster's domain
+    /// shards.
+    ///
+    /// Domains are ranked by the thread time they've spent processing, and are only moved off of
+    /// workers running more than their share of domain shards. Domains containing base tables are
+    /// never moved, since their state can't be rebuilt from elsewhere in the graph, and neither
+    /// are domains with placement restrictions.
+    ///
+    /// Note that this moves domains as a whole, and doesn't change the number of shards of any
+    /// domain - sharding is still decided when queries are migrated.
+    pub(super) async fn rebalance_onto(&mut self, worker: &WorkerIdentifier) -> ReadySetResult<()> {
+        let reader_only = match self.workers.get(worker) {
+            Some(w) if w.healthy && !w.draining => w.reader_only,
+            _ => return Ok(()),
+        };
+        let num_workers = self
+            .workers
+            .values()
+            .filter(|w| w.healthy && !w.draining)
+            .count();
+        let target = self.domains.values().map(|dh| dh.shards()).sum::<usize>() / num_workers;
+        if target == 0 {
+            return Ok(());
+        }
+
+        let mut load: HashMap<DomainIndex, u64> = HashMap::new();
+        for ((domain, _), (domain_stats, _)) in self.get_statistics().await?.domains {
+            *load.entry(domain).or_default() += domain_stats.total_ptime;
+        }
+
+        let mut shards_per_worker: HashMap<&WorkerIdentifier, usize> = HashMap::new();
+        for dh in self.domains.values() {
+            for wi in &dh.shards {
+                *shards_per_worker.entry(wi).or_default() += 1;
+            }
+        }
+
+        let mut candidates = self
+            .domains
+            .values()
+            .filter(|dh| !dh.assigned_to_worker(worker))
+            .filter_map(|dh| {
+                let nodes = self.domain_nodes.get(&dh.index())?;
+                #[allow(clippy::indexing_slicing)] // came from self.domain_nodes
+                let movable = nodes.values().all(|ni| {
+                    let node = &self.ingredients[*ni];
+                    !node.is_base()
+                        && (!reader_only || node.is_reader())
+                        && !(0..dh.shards()).any(|shard| {
+                            self.node_restrictions.contains_key(&NodeRestrictionKey {
+                                node_name: node.name().clone(),
+                                shard,
+                            })
+                        })
+                });
+                movable.then(|| (dh, load.get(&dh.index()).copied().unwrap_or_default()))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, load)| std::cmp::Reverse(*load));
+
+        let mut moved = 0;
+        let mut domain_nodes = HashMap::new();
+        for (dh, _) in candidates {
+            if moved >= target {
+                break;
+            }
+            if dh
+                .shards
+                .iter()
+                .any(|wi| shards_per_worker.get(wi).copied().unwrap_or_default() <= target)
+            {
+                continue;
+            }
+            for wi in &dh.shards {
+                if let Some(n) = shards_per_worker.get_mut(wi) {
+                    *n -= 1;
+                }
+            }
+            moved += dh.shards();
+            domain_nodes.insert(
+                dh.index(),
+                self.domain_nodes
+                    .get(&dh.index())
+                    .map(|nm| nm.values().copied().collect::<HashSet<_>>())
+                    .unwrap_or_default(),
+            );
+        }
+
+        if domain_nodes.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            %worker,
+            num_domains = domain_nodes.len(),
+            "moving domains onto new worker"
+        );
+        self.move_domains(&domain_nodes).await
+    }
+
+    /// Shut down every shard of each of the domains in `domain_nodes`, and then recover them on
+    /// the least loaded workers that can run them. The materialized state in those domains is
+    /// rebuilt by replays (for full materializations) or upqueries (for partial
+    /// materializations).
+    async fn move_domains(
+        &mut self,
+        domain_nodes: &HashMap<DomainIndex, HashSet<NodeIndex>>,
+    ) -> ReadySetResult<()> {
+        let mut kills: HashMap<WorkerIdentifier, (Vec<(DomainIndex, usize)>, Vec<NodeIndex>)> =
+            HashMap::new();
+        for (domain_index, node_indices) in domain_nodes {
+            #[allow(clippy::indexing_slicing)] // came from self.domain_nodes
+            let readers = node_indices
+                .iter()
+                .filter(|ni| self.ingredients[**ni].is_reader())
+                .copied()
+                .collect::<Vec<_>>();
+            if let Some(dh) = self.domains.remove(domain_index) {
+                for (shard, wi) in dh.shards.iter().enumerate() {
+                    let (domains, worker_readers) = kills.entry(wi.clone()).or_default();
+                    domains.push((*domain_index, shard));
+                    worker_readers.extend(readers.iter().copied());
+                }
+            }
+            self.materializations.remove_nodes(node_indices);
+        }
+
+        for (wi, (domains, readers)) in kills {
+            if let Some(w) = self.workers.get(&wi) {
+                if let Err(e) = w
+                    .rpc::<()>(WorkerRequestKind::KillDomains { domains, readers })
+                    .await
+                {
+                    error!(
+                        worker = %wi,
+                        %e,
+                        "Worker could not be reached to shut down moved domains",
+                    );
+                }
+            }
+        }
+
+        self.recover(domain_nodes).await
+    }
+
     /// Issue all of `requests` to their corresponding domains asynchronously, and return a stream
     /// of the results (potentially in a different order)
     ///
@@ -739,6 +1169,87 @@ impl DataflowState {
             .collect()
     }
 
+    /// Returns the handle for the domain containing the base table with the given name, along
+    /// with the local index of the base table node within that domain
+    fn base_table_domain(&self, table: &str) -> ReadySetResult<(&DomainHandle, LocalNodeIndex)> {
+        let ni = *self
+            .inputs()
+            .get(table)
+            .ok_or_else(|| ReadySetError::TableNotFound(table.into()))?;
+        let node = self
+            .ingredients
+            .node_weight(ni)
+            .ok_or_else(|| ReadySetError::NodeNotFound { index: ni.index() })?;
+        let domain =
+            self.domains
+                .get(&node.domain())
+                .ok_or_else(|| ReadySetError::NoSuchDomain {
+                    domain_index: node.domain().index(),
+                    shard: 0,
+                })?;
+        Ok((domain, node.local_addr()))
+    }
+
+    /// Manually compact the persisted state of the base table with the given name, on every shard
+    /// of the domain that contains it. Resolves once all compactions have finished.
+    pub(super) async fn compact_table(&self, table: &str) -> ReadySetResult<()> {
+        let (domain, node) = self.base_table_domain(table)?;
+
+        info!(%table, "compacting base table");
+        domain
+            .send_to_healthy::<()>(DomainRequest::CompactBase { node }, &self.workers)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Flush all writes to the persisted state of the base table with the given name to disk, on
+    /// every shard of the domain that contains it.
+    pub(super) async fn flush_table(&self, table: &str) -> ReadySetResult<()> {
+        let (domain, node) = self.base_table_domain(table)?;
+
+        debug!(%table, "flushing base table");
+        domain
+            .send_to_healthy::<()>(DomainRequest::FlushBase { node }, &self.workers)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Recompute the statistics about the base table with the given name from the rows it
+    /// currently holds, on every shard of the domain that contains it.
+    pub(super) async fn refresh_table_statistics(&self, table: &str) -> ReadySetResult<()> {
+        let (domain, node) = self.base_table_domain(table)?;
+
+        debug!(%table, "refreshing base table statistics");
+        domain
+            .send_to_healthy::<()>(DomainRequest::RefreshBaseStatistics { node }, &self.workers)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Change the compression options for the persisted state of the base table with the given
+    /// name, on every shard of the domain that contains it. Resolves once all existing data has
+    /// been rewritten with the new compression options.
+    pub(super) async fn set_table_compression(
+        &self,
+        table: &str,
+        options: CompressionOptions,
+    ) -> ReadySetResult<()> {
+        let (domain, node) = self.base_table_domain(table)?;
+
+        info!(%table, ?options, "changing base table compression");
+        domain
+            .send_to_healthy::<()>(
+                DomainRequest::SetBaseCompression { node, options },
+                &self.workers,
+            )
+            .await?;
+
+        Ok(())
+    }
+
     // ** Modify operations **
 
     /// Perform a new query schema migration.
@@ -1113,6 +1624,44 @@ impl DataflowState {
         );
     }
 
+    /// Shard the base table named `table` by `column` when it's created, rather than by the
+    /// automatically chosen column (usually its primary key).
+    ///
+    /// The sharding of a base table can't be changed once it's been created, so this returns an
+    /// error if the table already exists and is sharded by a different column.
+    pub(super) fn set_table_sharding(
+        &mut self,
+        table: SqlIdentifier,
+        column: SqlIdentifier,
+    ) -> ReadySetResult<()> {
+        if self.sharding.is_none() {
+            return Err(bad_request_err("Sharding is not enabled"));
+        }
+
+        if let Some(ni) = self.inputs().get(&table) {
+            #[allow(clippy::indexing_slicing)] // came from self.inputs()
+            let node = &self.ingredients[*ni];
+            let col = node
+                .columns()
+                .iter()
+                .position(|c| column == c.name())
+                .ok_or_else(|| {
+                    bad_request_err(format!("Table {} has no column {}", table, column))
+                })?;
+            if !matches!(node.sharded_by(), Sharding::ByColumn(c, _) if c == col) {
+                return Err(bad_request_err(format!(
+                    "Table {} is already sharded by a different column. The sharding of a table \
+                     can only be changed by re-creating it",
+                    table
+                )));
+            }
+        }
+
+        info!(%table, %column, "overriding base table sharding column");
+        self.table_sharding.insert(table, column);
+        Ok(())
+    }
+
     pub(super) fn set_schema_replication_offset(&mut self, offset: Option<ReplicationOffset>) {
         self.schema_replication_offset = offset;
     }
@@ -1210,11 +1759,28 @@ impl DataflowState {
             }
         }
 
+        let altered_tables = recipe_spec
+            .changes
+            .changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::CreateTable(cts) => Some(cts.table.name.clone()),
+                Change::AlterTable(ats) => Some(ats.table.name.clone()),
+                _ => None,
+            })
+            .collect();
+
         match self.apply_recipe(recipe_spec.changes, dry_run).await {
             Ok(x) => {
                 if let Some(offset) = &recipe_spec.replication_offset {
                     offset.try_max_into(&mut self.schema_replication_offset)?
                 }
+                if !dry_run {
+                    self.record_schema_versions(
+                        altered_tables,
+                        recipe_spec.replication_offset.as_deref(),
+                    );
+                }
 
                 Ok(x)
             }
@@ -1222,6 +1788,138 @@ impl DataflowState {
         }
     }
 
+    /// Plans the migration for the given changes without applying it, and returns the dataflow
+    /// nodes it would add to the graph, along with an estimate of the memory their state would
+    /// use.
+    ///
+    /// Planning the migration adds its nodes to `self`, so this must only be called on a copy of
+    /// the dataflow state that is thrown away afterwards.
+    pub(super) async fn explain_migration(
+        &mut self,
+        changes: ChangeList,
+    ) -> ReadySetResult<Vec<PlannedNode>> {
+        let existing_nodes = self.ingredients.node_indices().collect::<HashSet<_>>();
+        let table_statistics = self
+            .table_statistics()
+            .await
+            .map_err(|error| warn!(%error, "Could not get table statistics to estimate state size"))
+            .ok();
+
+        let spec = ExtendRecipeSpec {
+            changes,
+            ..Default::default()
+        };
+        self.extend_recipe(spec, true).await?;
+
+        let new_nodes = self
+            .ingredients
+            .node_indices()
+            .filter(|ni| !existing_nodes.contains(ni))
+            .collect::<Vec<_>>();
+        #[allow(clippy::indexing_slicing)] // new nodes are in the graph
+        let is_materialized = |ni: NodeIndex| {
+            let node = &self.ingredients[ni];
+            !node.is_base()
+                && !matches!(
+                    self.materializations.get_status(ni, node),
+                    MaterializationStatus::Not
+                )
+        };
+        let estimated_sizes = table_statistics
+            .map(|tables| {
+                cost::estimate_state_sizes(
+                    &self.ingredients,
+                    new_nodes.iter().copied(),
+                    is_materialized,
+                    &tables,
+                )
+            })
+            .unwrap_or_default();
+
+        Ok(new_nodes
+            .into_iter()
+            .map(|index| {
+                #[allow(clippy::indexing_slicing)] // new nodes are in the graph
+                let node = &self.ingredients[index];
+                PlannedNode {
+                    index,
+                    description: node.description(true),
+                    materialization: self.materializations.get_status(index, node),
+                    estimated_bytes: estimated_sizes.get(&index).copied(),
+                }
+            })
+            .collect())
+    }
+
+    /// Creates all of the caches in `changes`, which must only contain `CREATE CACHE` changes, in
+    /// a single migration. If any of the caches can't be planned, none of them are created, and
+    /// the returned statuses record the error that each failing cache failed with.
+    ///
+    /// The caches are planned on a copy of the dataflow state before any of them are created, so
+    /// that a cache which can't be planned doesn't leave the others partially migrated.
+    pub(super) async fn create_caches(
+        &mut self,
+        changes: ChangeList,
+    ) -> ReadySetResult<Vec<CacheCreationStatus>> {
+        let names = changes
+            .changes
+            .iter()
+            .map(|change| match change {
+                Change::CreateCache(CreateCacheStatement {
+                    name,
+                    inner: CacheInner::Statement(_),
+                }) => Ok(name.clone()),
+                _ => Err(bad_request_err(
+                    "Only CREATE CACHE ... FROM SELECT statements can be created as a batch",
+                )),
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+
+        let spec = ExtendRecipeSpec {
+            changes: changes.clone(),
+            ..Default::default()
+        };
+        if let Err(error) = self.clone().extend_recipe(spec, true).await {
+            // Plan each cache in turn on top of the ones before it, to find out which of them
+            // can't be created
+            let mut planned = self.clone();
+            let mut statuses = Vec::with_capacity(names.len());
+            for (change, name) in changes.changes.into_iter().zip(names) {
+                let mut attempt = planned.clone();
+                let spec = ExtendRecipeSpec {
+                    changes: ChangeList {
+                        changes: vec![change],
+                    },
+                    ..Default::default()
+                };
+                let error = match attempt.extend_recipe(spec, true).await {
+                    Ok(_) => {
+                        planned = attempt;
+                        None
+                    }
+                    Err(error) => Some(error),
+                };
+                statuses.push(CacheCreationStatus { name, error });
+            }
+
+            if statuses.iter().all(|status| status.error.is_none()) {
+                // Every cache could be planned on its own, so the batch as a whole failed
+                return Err(error);
+            }
+            return Ok(statuses);
+        }
+
+        let spec = ExtendRecipeSpec {
+            changes,
+            ..Default::default()
+        };
+        self.extend_recipe(spec, false).await?;
+        Ok(names
+            .into_iter()
+            .map(|name| CacheCreationStatus { name, error: None })
+            .collect())
+    }
+
     pub(super) async fn remove_query(&mut self, query_name: &str) -> ReadySetResult<()> {
         let name = match self.recipe.resolve_alias(query_name) {
             None => return Ok(()),
@@ -1291,6 +1989,35 @@ impl DataflowState {
         dmp.apply(self).await
     }
 
+    /// Recover the domains in [`Self::deferred_recovery`] onto the current set of workers.
+    ///
+    /// If any of those domains can't be placed on one of the current workers, recovery of all of
+    /// them is deferred again, rather than failing - the domains downstream of a base table can't
+    /// be rebuilt until the base table itself is running again.
+    pub(super) async fn recover_deferred(&mut self) -> ReadySetResult<()> {
+        if self.deferred_recovery.is_empty() {
+            return Ok(());
+        }
+
+        let domain_nodes = std::mem::take(&mut self.deferred_recovery);
+        match self.recover(&domain_nodes).await {
+            Err(ReadySetError::NoAvailableWorkers {
+                domain_index,
+                shard,
+            }) => {
+                warn!(
+                    domain_index,
+                    shard,
+                    num_domains = domain_nodes.len(),
+                    "No worker can currently run domain; deferring recovery until one registers"
+                );
+                self.deferred_recovery = domain_nodes;
+                Ok(())
+            }
+            res => res,
+        }
+    }
+
     /// This method is a hack to make sure the [`ControllerState`] "persisted" in the
     /// [`LocalAuthority`] is stored similarly to the way it would be, if it were serialized and
     /// then deserailized, but without paying the extreme performance penalty actually serializing
@@ -1558,6 +2285,7 @@ pub(super) fn graphviz(
     detailed: bool,
     materializations: &Materializations,
     domain_nodes: Option<&HashMap<DomainIndex, NodeMap<NodeIndex>>>,
+    nodes: Option<&HashSet<NodeIndex>>,
 ) -> String {
     let mut s = String::new();
 
@@ -1591,8 +2319,9 @@ pub(super) fn graphviz(
         .flat_map(|m| m.iter())
         .flat_map(|(di, nodes)| nodes.iter().map(|(_, ni)| (*ni, *di)))
         .collect::<HashMap<_, _>>();
+    let included = |index: &NodeIndex| nodes.map_or(true, |nodes| nodes.contains(index));
     let mut domains_to_nodes = HashMap::new();
-    for index in graph.node_indices() {
+    for index in graph.node_indices().filter(included) {
         let domain = domain_for_node.get(&index).copied();
         domains_to_nodes
             .entry(domain)
@@ -1625,7 +2354,11 @@ pub(super) fn graphviz(
     }
 
     // edges.
-    for (_, edge) in graph.raw_edges().iter().enumerate() {
+    for edge in graph
+        .raw_edges()
+        .iter()
+        .filter(|edge| included(&edge.source()) && included(&edge.target()))
+    {
         indentln(&mut s);
         s.push_str(&format!(
             "n{} -> n{} [ {} ]",
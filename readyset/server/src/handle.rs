@@ -150,6 +150,13 @@ impl Handle {
             .map_err(|e| format_err!("failed to make table: {:?}", e))
     }
 
+    /// Move all the domains running on this server's worker onto other workers, so that it can be
+    /// shut down without its domains being unavailable until its failure is detected.
+    pub async fn drain(&mut self) -> ReadySetResult<()> {
+        let worker = self.descriptor.controller_uri.clone();
+        self.drain_worker(worker).await
+    }
+
     /// Inform the local instance that it should exit.
     pub fn shutdown(&mut self) {
         if let Some(kill) = self.kill.take() {
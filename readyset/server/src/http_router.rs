@@ -7,12 +7,13 @@ use std::task::{Context, Poll};
 
 use anyhow::anyhow;
 use futures::TryFutureExt;
-use hyper::header::CONTENT_TYPE;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE};
 use hyper::service::make_service_fn;
 use hyper::{self, Body, Method, Request, Response, StatusCode};
 use readyset::consensus::{Authority, AuthorityControl};
 use readyset::metrics::recorded;
 use readyset::ReadySetError;
+use readyset_tracing::propagation::RequestContext;
 use stream_cancel::Valve;
 use tokio::net::TcpListener;
 use tokio::sync::mpsc::Sender;
@@ -43,6 +44,18 @@ pub struct NoriaServerHttpRouter {
     pub authority: Arc<Authority>,
     /// A valve for the http stream to trigger closing.
     pub valve: Valve,
+    /// The bearer token that requests to the admin API must be authenticated with. The admin API
+    /// is disabled if this is `None`.
+    pub admin_api_token: Option<Arc<str>>,
+}
+
+/// Prefix of the paths of the admin API, which is served by the controller and returns JSON
+const ADMIN_API_PREFIX: &str = "/api/";
+
+/// Compares `a` and `b` in time that doesn't depend on where they differ, so that the admin API
+/// token can't be guessed by timing requests
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
 }
 
 impl NoriaServerHttpRouter {
@@ -58,6 +71,21 @@ impl NoriaServerHttpRouter {
         Ok(http_listener)
     }
 
+    /// Returns true if `req` is authenticated with the admin API token
+    fn is_admin_authorized(&self, req: &Request<Body>) -> bool {
+        let token = match &self.admin_api_token {
+            Some(token) => token,
+            None => return false,
+        };
+        req.headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map_or(false, |given| {
+                constant_time_eq(given.as_bytes(), token.as_bytes())
+            })
+    }
+
     /// Routes requests for a ReadySet server http router received on `http_listener`
     /// the service layer of the NoriaServerHttpRouter, see
     /// mpl Service<_> for NoriaServerHttpRouter.
@@ -121,7 +149,7 @@ impl Service<Request<Body>> for NoriaServerHttpRouter {
                     Ok(res.unwrap())
                 })
             }
-            (&Method::GET, "/prometheus") => {
+            (&Method::GET, "/prometheus" | "/metrics") => {
                 let render = get_global_recorder().and_then(|r| r.render(RecorderType::Prometheus));
                 let res = res.header(CONTENT_TYPE, "text/plain");
                 let res = match render {
@@ -202,12 +230,36 @@ impl Service<Request<Body>> for NoriaServerHttpRouter {
                     Ok(res.unwrap())
                 })
             }
+            (_, path) if path.starts_with(ADMIN_API_PREFIX) && !self.is_admin_authorized(&req) => {
+                let res = if self.admin_api_token.is_none() {
+                    res.status(StatusCode::NOT_FOUND)
+                        .header(CONTENT_TYPE, "text/plain")
+                        .body(hyper::Body::from("The admin API is not enabled. To fix this, run ReadySet with --admin-api-token"))
+                } else {
+                    res.status(StatusCode::UNAUTHORIZED)
+                        .header(WWW_AUTHENTICATE, "Bearer")
+                        .body(hyper::Body::empty())
+                };
+                Box::pin(async move { Ok(res.unwrap()) })
+            }
             _ => {
                 metrics::increment_counter!(recorded::SERVER_CONTROLLER_REQUESTS);
 
                 let method = req.method().clone();
                 let path = req.uri().path().to_string();
+                // Errors from the admin API are returned as JSON, like most of its responses
+                let (content_type, error_content_type) = match path.as_str() {
+                    path if path.starts_with(ADMIN_API_PREFIX) && path.ends_with("/graph") => {
+                        ("text/vnd.graphviz; charset=utf-8", "application/json")
+                    }
+                    path if path.starts_with(ADMIN_API_PREFIX) => {
+                        ("application/json", "application/json")
+                    }
+                    _ => ("application/octet-stream", "application/octet-stream"),
+                };
                 let query = req.uri().query().map(ToOwned::to_owned);
+                let trace_context =
+                    RequestContext::from_headers(|name| req.headers().get(name)?.to_str().ok());
                 let controller_tx = self.controller_tx.clone();
 
                 Box::pin(async move {
@@ -219,6 +271,7 @@ impl Service<Request<Body>> for NoriaServerHttpRouter {
                         path,
                         query,
                         body,
+                        trace_context,
                         reply_tx: tx,
                     };
 
@@ -233,11 +286,11 @@ impl Service<Request<Body>> for NoriaServerHttpRouter {
                         Ok(reply) => {
                             let res = match reply {
                                 Ok(Ok(reply)) => res
-                                    .header("Content-Type", "application/octet-stream")
+                                    .header("Content-Type", content_type)
                                     .body(hyper::Body::from(reply)),
-                                Ok(Err(reply)) => res
-                                    .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                    .header("Content-Type", "application/octet-stream")
+                                Ok(Err((status_code, reply))) => res
+                                    .status(status_code)
+                                    .header("Content-Type", error_content_type)
                                     .body(hyper::Body::from(reply)),
                                 Err(status_code) => {
                                     res.status(status_code).body(hyper::Body::empty())
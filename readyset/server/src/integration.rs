@@ -27,7 +27,8 @@ use dataflow::{DurabilityMode, Expression as DataflowExpression, PersistencePara
 use futures::StreamExt;
 use itertools::Itertools;
 use nom_sql::{
-    BinaryOperator, Column, ColumnConstraint, ColumnSpecification, OrderType, SqlType, TableKey,
+    BinaryOperator, Column, ColumnConstraint, ColumnSpecification, OrderType, SqlIdentifier,
+    SqlType, TableKey,
 };
 use readyset::consensus::{Authority, LocalAuthority, LocalAuthorityStore};
 use readyset::consistency::Timestamp;
@@ -1208,6 +1209,84 @@ async fn it_recovers_persisted_bases() {
     drop(g);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn it_warms_readers_from_persisted_keys() {
+    let authority_store = Arc::new(LocalAuthorityStore::new());
+    let authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store.clone(),
+    )));
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("it_warms_readers_from_persisted_keys");
+    let mut persistence_params = PersistenceParameters::new(
+        DurabilityMode::Permanent,
+        Some(path.to_string_lossy().into()),
+        1,
+        None,
+    );
+    persistence_params.reader_key_snapshot_interval = Some(Duration::from_millis(100));
+
+    {
+        let mut g = Builder::for_tests();
+        g.set_persistence(persistence_params.clone());
+        let mut g = g.start(authority.clone()).await.unwrap();
+        g.backend_ready().await;
+
+        g.extend_recipe(
+            "CREATE TABLE Car (id int, price int, PRIMARY KEY(id));
+             CREATE CACHE CarPrice FROM SELECT price FROM Car WHERE id = ?;"
+                .parse()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let mut mutator = g.table("Car").await.unwrap();
+        for i in 1..10 {
+            mutator
+                .insert(vec![i.into(), (i * 10).into()])
+                .await
+                .unwrap();
+        }
+        sleep().await;
+
+        // Fill some of the keys in the reader, and give the worker time to snapshot them
+        let mut getter = g.view("CarPrice").await.unwrap();
+        for i in 1..5 {
+            getter.lookup(&[i.into()], true).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        g.shutdown();
+        g.wait_done().await;
+        if let Authority::LocalAuthority(l) = authority.as_ref() {
+            l.delete_ephemeral();
+        }
+    }
+
+    sleep().await;
+
+    let authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store.clone(),
+    )));
+
+    let mut g = Builder::for_tests();
+    g.set_persistence(persistence_params);
+    let mut g = g.start(authority.clone()).await.unwrap();
+    g.backend_ready().await;
+    let mut getter = g.view("CarPrice").await.unwrap();
+    sleep().await;
+
+    // Non-blocking lookups only return results for keys that are already present, so the keys
+    // that were filled before the restart should have been filled again
+    for i in 1..5 {
+        let result = getter.lookup(&[i.into()], false).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0][0], (i * 10).into());
+    }
+    let result = getter.lookup(&[7.into()], false).await.unwrap();
+    assert!(result.is_empty());
+}
+
 // TODO(ENG-860): Flaky test.
 #[tokio::test(flavor = "multi_thread")]
 async fn it_recovers_persisted_bases_with_volume_id() {
@@ -4834,6 +4913,148 @@ async fn post_read_ilike() {
     )
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn lookup_stream() {
+    let mut g = {
+        let mut builder = Builder::for_tests();
+        builder.disable_partial();
+        builder.set_sharding(None);
+        builder.set_persistence(get_persistence_params("lookup_stream"));
+        builder.start_local()
+    }
+    .await
+    .unwrap();
+
+    g.migrate(|mig| {
+        let a = mig.add_base(
+            "a",
+            make_columns(&["a", "b"]),
+            Base::new().with_primary_key([0]),
+        );
+        mig.maintain_anonymous_with_post_lookup(
+            a,
+            &Index::btree_map(vec![0]),
+            PostLookup {
+                order_by: Some(vec![(0, OrderType::OrderAscending)]),
+                ..Default::default()
+            },
+        );
+    })
+    .await;
+
+    let mut a = g.table("a").await.unwrap();
+    let reader = g.view("a").await.unwrap();
+    a.insert_many((0..25).map(|i| vec![DataType::from(i), DataType::from(i * 2)]))
+        .await
+        .unwrap();
+
+    sleep().await;
+
+    let pages = reader
+        .lookup_stream(
+            ViewQuery::from((vec![KeyComparison::from_range(&(..))], true)),
+            10,
+        )
+        .map(|page| {
+            let mut page = page.unwrap();
+            assert_eq!(page.len(), 1);
+            Vec::<Vec<DataType>>::from(page.pop().unwrap())
+        })
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(
+        pages.iter().map(|page| page.len()).collect::<Vec<_>>(),
+        vec![10, 10, 5]
+    );
+    assert_eq!(
+        pages.into_iter().flatten().collect::<Vec<_>>(),
+        (0..25)
+            .map(|i| vec![DataType::from(i), DataType::from(i * 2)])
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn truncate_wide_values() {
+    let mut g = {
+        let mut builder = Builder::for_tests();
+        builder.set_sharding(None);
+        builder.set_persistence(get_persistence_params("truncate_wide_values"));
+        builder.set_max_value_size(Some(8));
+        builder.start_local()
+    }
+    .await
+    .unwrap();
+
+    g.extend_recipe(
+        "CREATE TABLE t (id int, PRIMARY KEY(id), body text);
+         CREATE CACHE q FROM SELECT id, body FROM t WHERE id = ?;"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t = g.table("t").await.unwrap();
+    let mut q = g.view("q").await.unwrap();
+    t.insert_many(vec![
+        vec![DataType::from(1), DataType::from("short")],
+        vec![DataType::from(2), DataType::from("much too long")],
+        // 'é' is two bytes long, and would be split by truncating at 8 bytes
+        vec![DataType::from(3), DataType::from("abcdefgé")],
+    ])
+    .await
+    .unwrap();
+
+    sleep().await;
+
+    for (id, body) in [(1, "short"), (2, "much too"), (3, "abcdefg")] {
+        assert_eq!(
+            q.lookup(&[DataType::from(id)], true).await.unwrap(),
+            vec![vec![DataType::from(id), DataType::from(body)]]
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn writes_with_max_queued_packets() {
+    let mut g = {
+        let mut builder = Builder::for_tests();
+        builder.set_sharding(None);
+        builder.set_persistence(get_persistence_params("writes_with_max_queued_packets"));
+        // Pause writes as soon as any packet is queued, so that every write has to wait for the
+        // dataflow graph to catch up
+        builder.set_max_queued_packets(Some(1));
+        builder.start_local()
+    }
+    .await
+    .unwrap();
+
+    g.extend_recipe(
+        "CREATE TABLE t (id int, PRIMARY KEY(id), x int);
+         CREATE CACHE q FROM SELECT COUNT(*) FROM t WHERE x = ?;"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t = g.table("t").await.unwrap();
+    let mut q = g.view("q").await.unwrap();
+    for i in 0..100 {
+        t.insert(vec![DataType::from(i), DataType::from(i % 2)])
+            .await
+            .unwrap();
+    }
+
+    sleep().await;
+
+    let result = q.lookup(&[DataType::from(1)], true).await.unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0][0], 50.into());
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn cast_projection() {
     let mut g = start_simple("cast").await;
@@ -4920,6 +5141,44 @@ async fn aggregate_missing_columns() {
     assert!(res.err().unwrap().to_string().contains("idd"));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn create_cache_rejects_filtered_columns() {
+    let mut builder = Builder::for_tests();
+    builder.set_persistence(get_persistence_params(
+        "create_cache_rejects_filtered_columns",
+    ));
+    builder.set_nullified_columns(vec!["t.data".to_owned()]);
+    builder.set_dropped_columns(vec!["t.payload".to_owned()]);
+    let mut g = builder.start_local().await.unwrap();
+
+    g.extend_recipe(
+        "CREATE TABLE t (id INT PRIMARY KEY, name TEXT, data TEXT);"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    for query in [
+        "CREATE CACHE q1 FROM SELECT data FROM t WHERE id = ?",
+        "CREATE CACHE q2 FROM SELECT name FROM t WHERE data = ?",
+        "CREATE CACHE q3 FROM SELECT * FROM t WHERE id = ?",
+        "CREATE VIEW v1 AS SELECT id, data FROM t",
+    ] {
+        let err = g.extend_recipe(query.parse().unwrap()).await.unwrap_err();
+        assert!(err.to_string().contains("t.data"), "{}", err);
+        assert!(err.to_string().contains("t.payload"), "{}", err);
+    }
+
+    g.extend_recipe(
+        "CREATE CACHE q4 FROM SELECT name FROM t WHERE id = ?"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn post_join_filter() {
     let mut g = start_simple("post_join_filter").await;
@@ -6882,6 +7141,230 @@ async fn assign_nonreader_domains_to_nonreader_workers() {
     assert!(matches!(result, Ok(_)));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn drain_worker() {
+    let authority_store = Arc::new(LocalAuthorityStore::new());
+    let w1_authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store.clone(),
+    )));
+    let w2_authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store,
+    )));
+    let cluster_name = "drain_worker";
+
+    let mut w1 = build_custom(
+        cluster_name,
+        Some(DEFAULT_SHARDING),
+        true,
+        w1_authority,
+        None,
+        false,
+        None,
+    )
+    .await;
+
+    // There's nowhere to move the domains of the only worker
+    let w1_addr = w1.get_address().clone();
+    w1.drain_worker(w1_addr).await.unwrap_err();
+
+    let mut w2 = build_custom(
+        cluster_name,
+        Some(DEFAULT_SHARDING),
+        false,
+        w2_authority,
+        None,
+        true,
+        None,
+    )
+    .await;
+    sleep().await;
+
+    w1.extend_recipe(
+        "CREATE TABLE test (id integer, name text);
+         CREATE CACHE testquery FROM SELECT * FROM test WHERE id = ?;"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut table = w1.table("test").await.unwrap();
+    table.insert(vec![1.into(), "a".into()]).await.unwrap();
+    sleep().await;
+
+    w1.drain_worker(w2.get_address().clone()).await.unwrap();
+    // The drained worker no longer runs any domains, so it can be shut down without waiting for
+    // its failure to be detected
+    w2.shutdown();
+
+    let mut view = w1.view("testquery").await.unwrap();
+    assert_eq!(
+        view.lookup(&[1.into()], true).await.unwrap(),
+        vec![vec![1.into(), "a".into()]]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rebalance_on_join() {
+    let authority_store = Arc::new(LocalAuthorityStore::new());
+    let cluster_name = "rebalance_on_join";
+
+    let mut builder = Builder::for_tests();
+    builder.set_sharding(None);
+    builder.set_persistence(get_persistence_params(cluster_name));
+    builder.set_rebalance_on_join(true);
+    let mut w1 = builder
+        .clone()
+        .start_local_custom(Arc::new(Authority::from(LocalAuthority::new_with_store(
+            authority_store.clone(),
+        ))))
+        .await
+        .unwrap();
+
+    w1.extend_recipe(
+        "CREATE TABLE test (id integer, name text);
+         CREATE CACHE q1 FROM SELECT * FROM test WHERE id = ?;
+         CREATE CACHE q2 FROM SELECT count(*) FROM test WHERE name = ?;
+         CREATE CACHE q3 FROM SELECT id, count(*) FROM test GROUP BY id;"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut table = w1.table("test").await.unwrap();
+    table.insert(vec![1.into(), "a".into()]).await.unwrap();
+    sleep().await;
+
+    let _w2 = builder
+        .start(Arc::new(Authority::from(LocalAuthority::new_with_store(
+            authority_store,
+        ))))
+        .await
+        .unwrap();
+    sleep().await;
+
+    // Some of the domains have been moved onto the new worker
+    let info = w1.get_info().await.unwrap();
+    assert_eq!(info.len(), 2);
+    assert!(info.values().all(|domains| !domains.is_empty()));
+
+    let mut q1 = w1.view("q1").await.unwrap();
+    assert_eq!(
+        q1.lookup(&[1.into()], true).await.unwrap(),
+        vec![vec![1.into(), "a".into()]]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_recovers_failed_worker_from_its_volume() {
+    let authority_store = Arc::new(LocalAuthorityStore::new());
+    let cluster_name = "it_recovers_failed_worker_from_its_volume";
+    let dir = tempfile::tempdir().unwrap();
+    let persistence_params = PersistenceParameters::new(
+        DurabilityMode::Permanent,
+        Some(dir.path().join(cluster_name).to_string_lossy().into()),
+        1,
+        None,
+    );
+
+    // The controller is reader-only, so the base table has to be placed on the worker with the
+    // volume
+    let mut builder = Builder::for_tests();
+    builder.set_persistence(persistence_params.clone());
+    builder.as_reader_only();
+    let mut w1 = builder
+        .start_local_custom(Arc::new(Authority::from(LocalAuthority::new_with_store(
+            authority_store.clone(),
+        ))))
+        .await
+        .unwrap();
+
+    let start_worker = |authority| {
+        let mut builder = Builder::for_tests();
+        builder.set_persistence(persistence_params.clone());
+        builder.set_volume_id("ef731j2".into());
+        builder.start(authority)
+    };
+    let w2_authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store.clone(),
+    )));
+    let mut w2 = start_worker(w2_authority.clone()).await.unwrap();
+    sleep().await;
+
+    w1.extend_recipe(
+        "CREATE TABLE test (id int, name text, PRIMARY KEY(id));
+         CREATE CACHE testquery FROM SELECT * FROM test WHERE id = ?;"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut table = w1.table("test").await.unwrap();
+    table.insert(vec![1.into(), "a".into()]).await.unwrap();
+    sleep().await;
+
+    // Kill the worker. Its base table can't be placed anywhere else, so it's recovered once a
+    // worker with the same volume comes back
+    w2.shutdown();
+    w2.wait_done().await;
+    if let Authority::LocalAuthority(l) = w2_authority.as_ref() {
+        l.delete_ephemeral();
+    }
+    sleep().await;
+
+    let _w3 = start_worker(Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store,
+    ))))
+    .await
+    .unwrap();
+    sleep().await;
+
+    let mut view = w1.view("testquery").await.unwrap();
+    assert_eq!(
+        view.lookup(&[1.into()], true).await.unwrap(),
+        vec![vec![1.into(), "a".into()]]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn table_sharding_override() {
+    let mut g = start_simple("table_sharding_override").await;
+
+    g.set_table_sharding("t", "tenant_id").await.unwrap();
+    g.extend_recipe(
+        "CREATE TABLE t (id int, tenant_id int, PRIMARY KEY(id));
+         CREATE CACHE by_tenant FROM SELECT id FROM t WHERE tenant_id = ?;"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    // The table already exists, so it can't be sharded by a different column
+    g.set_table_sharding("t", "id").await.unwrap_err();
+    g.set_table_sharding("t", "tenant_id").await.unwrap();
+
+    let mut t = g.table("t").await.unwrap();
+    t.insert_many((1..=4).map(|id: i32| vec![id.into(), (id % 2).into()]))
+        .await
+        .unwrap();
+    // Deleting by key has to find the row in whichever shard it's in
+    t.delete(vec![3.into()]).await.unwrap();
+    sleep().await;
+
+    let mut q = g.view("by_tenant").await.unwrap();
+    let res = q.lookup(&[1.into()], true).await.unwrap();
+    assert_eq!(res, vec![vec![DataType::from(1i32)]]);
+    let mut res = q.lookup(&[0.into()], true).await.unwrap();
+    res.sort();
+    assert_eq!(
+        res,
+        vec![vec![DataType::from(2i32)], vec![DataType::from(4i32)]]
+    );
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn join_straddled_columns() {
     let mut g = start_simple("join_straddled_columns").await;
@@ -8677,34 +9160,29 @@ async fn simple_alter_table_add_column() {
     table.insert(vec![11.into()]).await.unwrap();
     table.insert(vec![21.into()]).await.unwrap();
 
-    let alter_table = "ALTER TABLE table_1 ADD COLUMN column_2 INT;";
+    let alter_table = "ALTER TABLE table_1 ADD COLUMN column_2 INT DEFAULT 5;";
     g.extend_recipe(alter_table.parse().unwrap()).await.unwrap();
 
-    // Altering a table currently means we delete and recreate the whole thing.
-    assert!(g.view("t1").await.is_err());
-
-    let recreate_view = "CREATE CACHE t1 FROM SELECT * FROM table_1;";
-    g.extend_recipe(recreate_view.parse().unwrap())
-        .await
-        .unwrap();
-
-    let mut view = g.view("t1").await.unwrap();
-    let results = view.lookup(&[0.into()], true).await.unwrap();
-    assert!(results.is_empty());
-
     let mut table = g.table("table_1").await.unwrap();
     // This should fail as we currently have more columns than before
-    assert!(table.insert(vec![11.into()]).await.is_err());
+    assert!(table.insert(vec![31.into()]).await.is_err());
+    table.insert(vec![31.into(), 32.into()]).await.unwrap();
 
-    table.insert(vec![11.into(), 12.into()]).await.unwrap();
-    table.insert(vec![21.into(), 22.into()]).await.unwrap();
+    sleep().await;
 
-    let results = view.lookup(&[0.into()], true).await.unwrap();
-    assert!(!results.is_empty());
-    assert_eq!(results[0][0], 11.into());
-    assert_eq!(results[0][1], 12.into());
-    assert_eq!(results[1][0], 21.into());
-    assert_eq!(results[1][1], 22.into());
+    // The table is altered in place, so the cache is re-planned against the new schema and the
+    // existing rows are kept, with the default value for the new column
+    let mut view = g.view("t1").await.unwrap();
+    let mut results = view.lookup(&[0.into()], true).await.unwrap();
+    results.sort();
+    assert_eq!(
+        results,
+        vec![
+            vec![11.into(), 5.into()],
+            vec![21.into(), 5.into()],
+            vec![31.into(), 32.into()],
+        ]
+    );
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -8727,31 +9205,88 @@ async fn simple_alter_table_drop_column() {
     let alter_table = "ALTER TABLE table_1 DROP COLUMN column_2;";
     g.extend_recipe(alter_table.parse().unwrap()).await.unwrap();
 
-    // Altering a table currently means we delete and recreate the whole thing.
-    assert!(g.view("t1").await.is_err());
+    let mut table = g.table("table_1").await.unwrap();
+    // This should fail as we currently have fewer columns than before
+    assert!(table.insert(vec![31.into(), 32.into()]).await.is_err());
+    table.insert(vec![31.into()]).await.unwrap();
+
+    sleep().await;
+
+    // The table is altered in place, so the cache is re-planned against the new schema and the
+    // existing rows are kept
+    let mut view = g.view("t1").await.unwrap();
+    let mut results = view.lookup(&[0.into()], true).await.unwrap();
+    results.sort();
+    assert_eq!(
+        results,
+        vec![vec![11.into()], vec![21.into()], vec![31.into()]]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn alter_table_drop_column_removes_dependent_queries() {
+    let mut g = start_simple_unsharded("alter_table_drop_column_removes_dependent_queries").await;
 
-    let recreate_view = "CREATE CACHE t1 FROM SELECT * FROM table_1;";
-    g.extend_recipe(recreate_view.parse().unwrap())
+    g.extend_recipe(
+        "CREATE TABLE table_1 (column_1 INT, column_2 INT);
+         CREATE CACHE t1 FROM SELECT column_1 FROM table_1;
+         CREATE CACHE t2 FROM SELECT column_2 FROM table_1;"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    g.extend_recipe("ALTER TABLE table_1 DROP COLUMN column_2;".parse().unwrap())
         .await
         .unwrap();
 
-    let mut view = g.view("t1").await.unwrap();
-    let results = view.lookup(&[0.into()], true).await.unwrap();
-    assert!(results.is_empty());
+    // Queries that don't reference the dropped column are kept, but the ones that do can no
+    // longer be planned
+    assert!(g.view("t1").await.is_ok());
+    assert!(g.view("t2").await.is_err());
+}
 
-    let mut table = g.table("table_1").await.unwrap();
-    // This should fail as we currently have more columns than before
-    assert!(table.insert(vec![11.into(), 12.into()]).await.is_err());
+#[tokio::test(flavor = "multi_thread")]
+async fn alter_table_only_invalidates_affected_queries() {
+    let mut g = start_simple_unsharded("alter_table_only_invalidates_affected_queries").await;
 
-    table.insert(vec![11.into()]).await.unwrap();
-    table.insert(vec![21.into()]).await.unwrap();
+    g.extend_recipe(
+        "CREATE TABLE table_1 (column_1 INT, column_2 INT);
+         CREATE CACHE t1 FROM SELECT column_1 FROM table_1 WHERE column_1 = ?;
+         CREATE CACHE t2 FROM SELECT column_2 FROM table_1;
+         CREATE CACHE t3 FROM SELECT * FROM table_1 WHERE column_1 = ?;"
+            .parse()
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut table = g.table("table_1").await.unwrap();
+    table.insert(vec![11.into(), 12.into()]).await.unwrap();
 
     sleep().await;
 
-    let results = view.lookup(&[0.into()], true).await.unwrap();
-    assert!(!results.is_empty());
-    assert_eq!(results[0][0], 11.into());
-    assert_eq!(results[1][0], 21.into());
+    let res = g
+        .extend_recipe("ALTER TABLE table_1 DROP COLUMN column_2;".parse().unwrap())
+        .await
+        .unwrap();
+
+    // Only the queries that reference the dropped column (explicitly or through a wildcard) are
+    // re-planned or dropped, the rest are left untouched
+    assert_eq!(res.replanned_queries, vec![SqlIdentifier::from("t3")]);
+    assert_eq!(res.dropped_queries, vec![SqlIdentifier::from("t2")]);
+
+    let mut view = g.view("t1").await.unwrap();
+    assert_eq!(
+        view.lookup(&[11.into()], true).await.unwrap(),
+        vec![vec![DataType::from(11)]]
+    );
+    let mut view = g.view("t3").await.unwrap();
+    assert_eq!(
+        view.lookup(&[11.into()], true).await.unwrap(),
+        vec![vec![DataType::from(11)]]
+    );
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -8890,8 +9425,14 @@ async fn simple_alter_table_alter_column() {
     let alter_table = "ALTER TABLE table_1 ALTER COLUMN column_2 SET DEFAULT 1;";
     g.extend_recipe(alter_table.parse().unwrap()).await.unwrap();
 
-    // Altering a table currently means we delete and recreate the whole thing.
-    assert!(g.view("t1").await.is_err());
+    // Changing a column's default alters the table in place, keeping the cache and its data
+    let mut view = g.view("t1").await.unwrap();
+    let mut results = view.lookup(&[0.into()], true).await.unwrap();
+    results.sort();
+    assert_eq!(
+        results,
+        vec![vec![11.into(), 12.into()], vec![21.into(), 22.into()]]
+    );
 
     let table = g.table("table_1").await.unwrap();
     let statement = table.schema().unwrap();
@@ -64,9 +64,11 @@ pub mod manual {
     pub use crate::controller::migrate::Migration;
 }
 
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use dataflow::DomainConfig;
+use readyset_data::IntegerOverflow;
 use serde::{Deserialize, Serialize};
 
 /// Configuration for an running ReadySet cluster
@@ -93,14 +95,34 @@ pub struct Config {
     pub(crate) mir_config: sql::mir::Config,
     pub(crate) replication_url: Option<String>,
     pub(crate) replication_server_id: Option<u32>,
+    /// Configuration for the replicator
+    #[serde(default)]
+    pub(crate) replicator_config: replicators::Config,
+    /// Additional upstream database URLs to replicate from, keyed by the namespace of the tables
+    /// replicated from each of them
+    #[serde(default)]
+    pub(crate) additional_upstreams: BTreeMap<String, String>,
     pub(crate) keep_prior_recipes: bool,
     /// The duration to wait before canceling the task waiting on an upquery.
     pub(crate) upquery_timeout: Duration,
     /// The duration to wait before canceling a task waiting on a worker request. Worker requests
     /// are typically issued as part of migrations.
     pub(crate) worker_request_timeout: Duration,
-    // The duration to wait after a failure of the replication task before restarting it.
+    /// The maximum duration to wait after a failure of the replication task before restarting
+    /// it. Consecutive failures back off exponentially up to this duration.
     pub(crate) replicator_restart_timeout: Duration,
+    /// How long a standby waits after the leader stops responding before deposing it and taking
+    /// over as the leader. If unset, standbys only take over once the authority expires the
+    /// leader's session.
+    #[serde(default)]
+    pub(crate) leader_failover_timeout: Option<Duration>,
+    /// Whether to move some of the most heavily loaded domains onto each worker that joins the
+    /// cluster, so that the new worker runs its share of the load.
+    #[serde(default)]
+    pub(crate) rebalance_on_join: bool,
+    /// What integer arithmetic in expressions does when its result overflows
+    #[serde(default)]
+    pub(crate) integer_overflow: IntegerOverflow,
 }
 
 impl Default for Config {
@@ -120,6 +142,9 @@ impl Default for Config {
                 // now.
                 table_request_timeout: Duration::from_millis(1800000),
                 eviction_kind: dataflow::EvictionKind::Random,
+                partial_spill: None,
+                max_value_size: None,
+                max_queued_packets: None,
             },
             persistence: Default::default(),
             quorum: 1,
@@ -129,10 +154,15 @@ impl Default for Config {
             mir_config: Default::default(),
             replication_url: None,
             replication_server_id: None,
+            replicator_config: Default::default(),
+            additional_upstreams: Default::default(),
             keep_prior_recipes: true,
             upquery_timeout: Duration::from_millis(5000),
             worker_request_timeout: Duration::from_millis(1800000),
             replicator_restart_timeout: Duration::from_secs(30),
+            leader_failover_timeout: None,
+            rebalance_on_join: false,
+            integer_overflow: Default::default(),
         }
     }
 }
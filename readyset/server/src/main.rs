@@ -9,12 +9,16 @@ use clap::{ArgEnum, Parser};
 use futures_util::future::{self, Either};
 use launchpad::redacted::RedactedString;
 use metrics_exporter_prometheus::PrometheusBuilder;
+use nom_sql::Dialect;
 use readyset::metrics::recorded;
+use readyset::storage::{CompressionOptions, CompressionType};
+use readyset_data::IntegerOverflow;
 use readyset_server::consensus::AuthorityType;
 use readyset_server::metrics::{
     install_global_recorder, CompositeMetricsRecorder, MetricsRecorder,
 };
-use readyset_server::{Builder, DurabilityMode, NoriaMetricsRecorder, VolumeId};
+use readyset_server::{Builder, DurabilityMode, NoriaMetricsRecorder, ReuseConfigType, VolumeId};
+use tokio::signal::unix::{signal, SignalKind};
 use tracing::{error, info};
 
 #[cfg(not(target_env = "msvc"))]
@@ -97,14 +101,47 @@ struct Opts {
     #[clap(long, default_value = "6")]
     persistence_threads: i32,
 
-    /// Authority connection string.
+    /// Target size, in bytes, of level 1 of the RocksDB LSM tree for each base table index.
+    /// Deeper levels are sized as multiples of this value.
+    #[clap(long, default_value = "1073741824")]
+    max_bytes_for_level_base: u64,
+
+    /// Target size, in bytes, of the files written by RocksDB compactions of base tables.
+    #[clap(long, default_value = "268435456")]
+    target_file_size_base: u64,
+
+    /// If set, limit the rate (in bytes per second) at which RocksDB writes base table data to
+    /// disk during flushes and compactions.
+    #[clap(long, env = "COMPACTION_RATE_LIMIT")]
+    compaction_rate_limit: Option<i64>,
+
+    /// Compression algorithm used for the on-disk data of newly created base tables. One of
+    /// none, lz4, or zstd. Can be changed for an existing table with
+    /// `ALTER READYSET SET COMPRESSION`.
+    #[clap(long, env = "COMPRESSION", default_value = "lz4")]
+    compression: CompressionType,
+
+    /// Maximum size, in bytes, of the shared dictionary built when compressing the on-disk data
+    /// of newly created base tables. If 0, dictionary compression is disabled.
+    #[clap(long, default_value = "0")]
+    compression_dictionary_bytes: u32,
+
+    /// If set, the keys present in each partially materialized cache are written to disk at this
+    /// interval, in seconds, and re-filled when the cache is recreated after a restart, so that
+    /// readyset-server comes back with warm caches. Requires `--durability persistent`.
+    #[clap(long, env = "READER_KEY_SNAPSHOT_INTERVAL_SECS")]
+    reader_key_snapshot_interval_secs: Option<u64>,
+
+    /// Authority connection string. For the kubernetes authority, this is the URL of the
+    /// Kubernetes API server if it starts with http:// or https://, and is otherwise ignored in
+    /// favor of the in-cluster API server.
     // TODO(justin): The default address should depend on the authority
     // value.
     #[clap(long, env = "AUTHORITY_ADDRESS", default_value = "127.0.0.1:8500")]
     authority_address: String,
 
-    /// The authority to use. Possible values: zookeeper, consul.
-    #[clap(long, env = "AUTHORITY", default_value = "consul", possible_values = &["consul", "zookeeper"])]
+    /// The authority to use. Possible values: zookeeper, consul, kubernetes.
+    #[clap(long, env = "AUTHORITY", default_value = "consul", possible_values = &["consul", "zookeeper", "kubernetes"])]
     authority: AuthorityType,
 
     /// Memory, in bytes, available for partially materialized state (0 = unlimited)
@@ -123,6 +160,26 @@ struct Opts {
     #[clap(long = "eviction-policy", arg_enum, default_value_t = dataflow::EvictionKind::Random)]
     eviction_kind: dataflow::EvictionKind,
 
+    /// If set, partially materialized state evicted due to the memory limit is written to a local
+    /// on-disk cache of up to this many bytes per node rather than discarded, so that refilling
+    /// recently evicted keys doesn't require an upquery.
+    #[clap(long, env = "PARTIAL_SPILL_BYTES")]
+    partial_spill_bytes: Option<u64>,
+
+    /// Directory to write spilled partial state to. Defaults to the system temporary directory.
+    #[clap(long, env = "PARTIAL_SPILL_DIR", requires = "partial-spill-bytes")]
+    partial_spill_dir: Option<PathBuf>,
+
+    /// If set, text and byte values longer than this many bytes are truncated to this length,
+    /// with a warning, before they're written to base tables.
+    #[clap(long, env = "MAX_VALUE_SIZE")]
+    max_value_size: Option<usize>,
+
+    /// If set, writes to base tables (including those from the replicator) are paused while more
+    /// than this many packets are queued to be processed by the domains on a worker.
+    #[clap(long, env = "MAX_QUEUED_PACKETS")]
+    max_queued_packets: Option<usize>,
+
     /// Disable partial
     #[clap(long = "nopartial")]
     no_partial: bool,
@@ -131,6 +188,21 @@ struct Opts {
     #[clap(long, env = "FORBID_FULL_MATERIALIZATION")]
     forbid_full_materialization: bool,
 
+    /// Maximum memory, in bytes, that the fully materialized state added by a single migration may
+    /// be estimated to use, based on the sizes of the base tables it reads from. Migrations that
+    /// exceed it are logged with a warning
+    #[clap(long, env = "MIGRATION_MEMORY_BUDGET_BYTES")]
+    migration_memory_budget_bytes: Option<u64>,
+
+    /// Refuse migrations that exceed --migration-memory-budget-bytes, rather than just logging a
+    /// warning
+    #[clap(
+        long,
+        env = "ENFORCE_MIGRATION_MEMORY_BUDGET",
+        requires = "migration-memory-budget-bytes"
+    )]
+    enforce_migration_memory_budget: bool,
+
     /// Enable packet filters in egresses before readers
     #[clap(long)]
     enable_packet_filters: bool,
@@ -151,6 +223,28 @@ struct Opts {
     #[clap(long, env = "NORIA_PRIMARY_REGION")]
     primary_region: Option<String>,
 
+    /// If set, standby servers which could become the leader check whether the leader's controller
+    /// is reachable, and depose it and take over as the leader once it has been unreachable for
+    /// this many seconds. Otherwise, standbys only take over once the authority considers the
+    /// leader dead.
+    ///
+    /// A leader which is still running but unreachable from a standby will step down once it has
+    /// been deposed.
+    #[clap(long, env = "LEADER_FAILOVER_TIMEOUT")]
+    leader_failover_timeout: Option<u64>,
+
+    /// When a server joins the deployment, move some of the most heavily loaded domains from the
+    /// other servers onto it, so that scaling out takes load off of the existing servers without
+    /// a restart. Domains containing base tables are never moved.
+    #[clap(long, env = "REBALANCE_ON_JOIN")]
+    rebalance_on_join: bool,
+
+    /// When the result of integer arithmetic in a query doesn't fit in a 64-bit integer, promote
+    /// it to a NUMERIC (or, for negative results of unsigned arithmetic, a signed integer) instead
+    /// of returning an error like the upstream database does
+    #[clap(long, env = "PROMOTE_ON_INTEGER_OVERFLOW")]
+    promote_on_integer_overflow: bool,
+
     /// The region the worker is hosted in. Required to route view requests to specific regions.
     #[clap(long, env = "NORIA_REGION")]
     region: Option<String>,
@@ -164,6 +258,12 @@ struct Opts {
     #[clap(long)]
     reader_only: bool,
 
+    /// When receiving SIGTERM or SIGINT, move all the domains running on this server onto other
+    /// servers in the deployment before shutting down, so that rolling restarts don't leave those
+    /// domains unavailable until the server's failure is detected.
+    #[clap(long, env = "DRAIN_ON_SHUTDOWN")]
+    drain: bool,
+
     /// Output prometheus metrics
     #[clap(long, env = "PROMETHEUS_METRICS")]
     prometheus_metrics: bool,
@@ -184,6 +284,12 @@ struct Opts {
     #[clap(long, env = "EXPERIMENTAL_PAGINATE_SUPPORT", hide = true)]
     enable_experimental_paginate_support: bool,
 
+    /// Enable (experimental) reuse of the dataflow nodes of existing caches when creating new
+    /// caches that are specializations of them. Reused nodes can't be restored from the persisted
+    /// controller state, so this should not be used with a persistent authority
+    #[clap(long, env = "EXPERIMENTAL_REUSE_SUPPORT", hide = true)]
+    enable_experimental_reuse_support: bool,
+
     #[clap(flatten)]
     tracing: readyset_tracing::Options,
 
@@ -200,9 +306,65 @@ struct Opts {
     #[clap(long, env = "DB_DIR")]
     db_dir: Option<PathBuf>,
 
-    /// The time to wait before restarting the replicator in seconds.
+    /// The maximum time to wait before restarting the replicator in seconds. Consecutive
+    /// failures back off exponentially up to this time.
     #[clap(long, hide = true)]
     replicator_restart_timeout: Option<u64>,
+
+    /// The maximum number of tables to copy concurrently during the initial snapshot of the
+    /// upstream database. Each table being copied uses a separate connection to the upstream.
+    #[clap(long, env = "SNAPSHOT_PARALLELISM", default_value_t = replicators::DEFAULT_SNAPSHOT_PARALLELISM)]
+    snapshot_parallelism: usize,
+
+    /// The number of replicated row operations the replicator buffers before writing them to base
+    /// tables. Consecutive writes to the same table are written in a single batch.
+    #[clap(long, env = "REPLICATION_WRITE_BATCH_SIZE", default_value_t = replicators::DEFAULT_WRITE_BATCH_SIZE)]
+    replication_write_batch_size: usize,
+
+    /// The maximum time, in milliseconds, the replicator buffers a replicated write for before
+    /// writing it to its base table.
+    #[clap(long, env = "REPLICATION_WRITE_BATCH_LATENCY_MS", default_value_t = replicators::DEFAULT_WRITE_BATCH_LATENCY_MS)]
+    replication_write_batch_latency_ms: u64,
+
+    /// Comma-separated list of columns, of the form `<table>.<column>`, whose values should be
+    /// replaced with NULL instead of being replicated. Useful for large blob or text columns that
+    /// are never referenced by cached queries.
+    #[clap(long, env = "NULLIFY_COLUMNS", use_delimiter = true)]
+    nullify_columns: Vec<String>,
+
+    /// Comma-separated list of columns, of the form `<table>.<column>`, to leave out of
+    /// ReadySet's tables entirely. Unlike nullified columns, dropped columns take up no space at
+    /// all in ReadySet. Columns that are part of a key are always replicated.
+    #[clap(long, env = "DROP_COLUMNS", use_delimiter = true)]
+    drop_columns: Vec<String>,
+
+    /// A plain-format `mysqldump` or `pg_dump` file to load the initial snapshot of the primary
+    /// upstream database from, instead of snapshotting the upstream database itself. Useful when
+    /// the upstream database can't tolerate the read load of a snapshot.
+    ///
+    /// A MySQL dump must be taken with `--single-transaction --source-data=2`. For PostgreSQL, the
+    /// `readyset` publication and replication slot must be created first, and the dump taken
+    /// from the slot's exported snapshot with `pg_dump --snapshot`.
+    #[clap(long, env = "SNAPSHOT_DUMP")]
+    snapshot_dump: Option<PathBuf>,
+
+    /// An additional MySQL or PostgreSQL server to replicate from, of the form
+    /// `<namespace>=<url>`. Tables replicated from the server are named `<namespace>_<table>`,
+    /// so that queries can join them with tables replicated from the primary server. May be
+    /// specified multiple times.
+    #[clap(long, multiple_occurrences = true)]
+    additional_upstream: Vec<RedactedString>,
+
+    /// Set if adapters are run with `--write-through`, so that rows replicated from the upstream
+    /// database replace the rows written directly to base tables by adapters.
+    #[clap(long, env = "WRITE_THROUGH")]
+    write_through: bool,
+
+    /// Enables the HTTP admin API, served under `/api/v1/` on the server's external address, for
+    /// listing, creating and dropping caches and viewing the dataflow graph. Requests to the
+    /// admin API must include this token in an `Authorization: Bearer <token>` header.
+    #[clap(long, env = "ADMIN_API_TOKEN")]
+    admin_api_token: Option<RedactedString>,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -262,6 +424,14 @@ fn main() -> anyhow::Result<()> {
         builder.set_memory_limit(opts.memory, Duration::from_secs(opts.memory_check_freq));
     }
     builder.set_eviction_kind(opts.eviction_kind);
+    if let Some(max_bytes) = opts.partial_spill_bytes {
+        builder.set_partial_spill(Some(dataflow::SpillParameters {
+            dir: opts.partial_spill_dir,
+            max_bytes,
+        }));
+    }
+    builder.set_max_value_size(opts.max_value_size);
+    builder.set_max_queued_packets(opts.max_queued_packets);
 
     builder.set_sharding(sharding);
     builder.set_quorum(opts.quorum);
@@ -271,13 +441,20 @@ fn main() -> anyhow::Result<()> {
     if opts.forbid_full_materialization {
         builder.forbid_full_materialization();
     }
+    builder.set_migration_memory_budget(
+        opts.migration_memory_budget_bytes,
+        opts.enforce_migration_memory_budget,
+    );
     if opts.enable_packet_filters {
         builder.enable_packet_filters();
     }
 
-    // TODO(fran): Reuse will be disabled until we refactor MIR to make it serializable.
-    // See `noria/server/src/controller/sql/serde.rs` for details.
-    builder.set_reuse(None);
+    // TODO(fran): Reuse will be disabled by default until we refactor MIR to make it
+    // serializable. See `noria/server/src/controller/sql/serde.rs` for details.
+    builder.set_reuse(
+        opts.enable_experimental_reuse_support
+            .then(|| ReuseConfigType::Finkelstein),
+    );
 
     builder.set_allow_topk(opts.enable_experimental_topk_support);
     builder.set_allow_paginate(opts.enable_experimental_paginate_support);
@@ -291,10 +468,24 @@ fn main() -> anyhow::Result<()> {
         builder.set_volume_id(volume_id);
     }
 
+    if let Some(token) = opts.admin_api_token {
+        builder.set_admin_api_token(token.0);
+    }
+
     if let Some(pr) = opts.primary_region {
         builder.set_primary_region(pr);
     }
 
+    if let Some(t) = opts.leader_failover_timeout {
+        builder.set_leader_failover_timeout(Duration::from_secs(t));
+    }
+
+    builder.set_rebalance_on_join(opts.rebalance_on_join);
+
+    if opts.promote_on_integer_overflow {
+        builder.set_integer_overflow(IntegerOverflow::Promote);
+    }
+
     if let Some(r) = opts.max_concurrent_replays {
         builder.set_max_concurrent_replay(r)
     }
@@ -307,15 +498,29 @@ fn main() -> anyhow::Result<()> {
         builder.as_reader_only()
     }
 
-    let persistence_params = readyset_server::PersistenceParameters::new(
+    let mut persistence_params = readyset_server::PersistenceParameters::new(
         opts.durability,
         Some(opts.deployment.clone()),
         opts.persistence_threads,
         opts.db_dir,
     );
+    persistence_params.max_bytes_for_level_base = opts.max_bytes_for_level_base;
+    persistence_params.target_file_size_base = opts.target_file_size_base;
+    persistence_params.compaction_rate_limit = opts.compaction_rate_limit;
+    persistence_params.compression = CompressionOptions {
+        compression_type: opts.compression,
+        max_dict_bytes: opts.compression_dictionary_bytes,
+    };
+    persistence_params.reader_key_snapshot_interval = opts
+        .reader_key_snapshot_interval_secs
+        .map(Duration::from_secs);
     builder.set_persistence(persistence_params);
 
     if let Some(url) = opts.replication_url {
+        // evaluate queries with the semantics of the database we're replicating from
+        if url.0.starts_with("postgres://") || url.0.starts_with("postgresql://") {
+            builder.set_dialect(Dialect::PostgreSQL);
+        }
         builder.set_replicator_url(url.0);
     }
 
@@ -323,6 +528,34 @@ fn main() -> anyhow::Result<()> {
         builder.set_replicator_restart_timeout(Duration::from_secs(t));
     }
 
+    builder.set_snapshot_parallelism(opts.snapshot_parallelism);
+    builder.set_replication_write_batch(
+        opts.replication_write_batch_size,
+        Duration::from_millis(opts.replication_write_batch_latency_ms),
+    );
+    builder.set_nullified_columns(opts.nullify_columns);
+    builder.set_dropped_columns(opts.drop_columns);
+    if let Some(snapshot_dump) = opts.snapshot_dump {
+        builder.set_snapshot_dump(snapshot_dump);
+    }
+    builder.set_write_through(opts.write_through);
+
+    for upstream in opts.additional_upstream {
+        let (namespace, url) = match upstream.0.split_once('=') {
+            Some((namespace, url))
+                if !namespace.is_empty()
+                    && namespace
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                (namespace.to_owned(), url.to_owned())
+            }
+            _ => anyhow::bail!("Invalid additional upstream, expected <namespace>=<url>"),
+        };
+        builder.add_additional_upstream(namespace, url);
+    }
+
+    let drain = opts.drain;
     let authority = opts.authority;
     let authority_addr = opts.authority_address;
     let deployment = opts.deployment;
@@ -337,7 +570,29 @@ fn main() -> anyhow::Result<()> {
         builder.set_external_addr(SocketAddr::from((external_addr, external_port)));
         builder.start(Arc::new(authority)).await
     })?;
-    rt.block_on(handle.wait_done());
+    rt.block_on(async move {
+        if !drain {
+            handle.wait_done().await;
+            return Ok(());
+        }
+
+        let mut sigterm = signal(SignalKind::terminate())?;
+        let shutdown_requested = tokio::select! {
+            _ = handle.wait_done() => false,
+            _ = tokio::signal::ctrl_c() => true,
+            _ = sigterm.recv() => true,
+        };
+        if shutdown_requested {
+            info!("Draining domains before shutting down");
+            match handle.drain().await {
+                Ok(()) => info!("Finished draining domains"),
+                Err(error) => error!(%error, "Error draining domains"),
+            }
+            handle.shutdown();
+            handle.wait_done().await;
+        }
+        Ok::<_, anyhow::Error>(())
+    })?;
     drop(rt);
     Ok(())
 }
@@ -35,6 +35,8 @@
 //!   - ...which can contain a `DomainRequest`
 //! - requests sent from clients to the `Leader`
 //!   - see `Leader::external_request`
+//! - the admin API under `/api/v1/`, which returns JSON and is authenticated with a bearer token
+//!   - also handled by `Leader::external_request`
 //! - other misc. endpoints for things like metrics (see the `NoriaServer` implementation for more)
 //!
 //! Typically, an incoming HTTP request is deserialized and then sent via a `tokio::sync::mpsc`
@@ -51,7 +53,7 @@ use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
 use std::{process, time};
 
-use dataflow::Readers;
+use dataflow::{PersistenceParameters, Readers};
 use futures_util::future::{Either, TryFutureExt};
 use launchpad::futures::abort_on_panic;
 use readyset::consensus::Authority;
@@ -112,6 +114,7 @@ async fn start_worker(
     readers: Readers,
     memory_limit: Option<usize>,
     memory_check_frequency: Option<time::Duration>,
+    persistence: PersistenceParameters,
     valve: Valve,
 ) -> Result<(), anyhow::Error> {
     let worker = Worker {
@@ -119,11 +122,18 @@ async fn start_worker(
         // this initial duration doesn't matter; it gets set upon worker registration
         evict_interval: memory_check_frequency.map(|f| tokio::time::interval(f)),
         memory_limit,
+        // don't snapshot until a full interval has passed, so that the snapshot taken before a
+        // restart isn't overwritten before the readers have been warmed back up from it
+        reader_key_snapshot_interval: persistence
+            .reader_key_snapshot_interval
+            .map(|f| tokio::time::interval_at(tokio::time::Instant::now() + f, f)),
+        persistence,
         rx: worker_rx,
         coord: Arc::new(Default::default()),
         domain_bind: listen_addr,
         domain_external: external_addr.ip(),
         state_sizes: Default::default(),
+        queue_depths: Default::default(),
         readers,
         valve,
         domains: Default::default(),
@@ -194,6 +204,7 @@ async fn start_request_router(
     worker_tx: Sender<WorkerRequest>,
     controller_tx: Sender<ControllerRequest>,
     abort_on_task_failure: bool,
+    admin_api_token: Option<String>,
     valve: Valve,
 ) -> Result<Url, anyhow::Error> {
     let http_server = NoriaServerHttpRouter {
@@ -203,6 +214,7 @@ async fn start_request_router(
         worker_tx: worker_tx.clone(),
         controller_tx,
         authority: authority.clone(),
+        admin_api_token: admin_api_token.map(Into::into),
     };
 
     let http_listener = http_server.create_listener().await?;
@@ -229,6 +241,7 @@ pub async fn start_instance_inner(
     region: Option<String>,
     reader_only: bool,
     volume_id: Option<VolumeId>,
+    admin_api_token: Option<String>,
     readers: Readers,
     reader_addr: SocketAddr,
     valve: Valve,
@@ -240,9 +253,14 @@ pub async fn start_instance_inner(
 
     let Config {
         abort_on_task_failure,
+        integer_overflow,
         ..
     } = config;
 
+    // Expressions are evaluated by dataflow nodes and readers all over this process, so the
+    // overflow behavior of integer arithmetic is set once, for the whole process
+    integer_overflow.set_global();
+
     let http_uri = start_request_router(
         authority.clone(),
         listen_addr,
@@ -250,6 +268,7 @@ pub async fn start_instance_inner(
         worker_tx.clone(),
         controller_tx,
         abort_on_task_failure,
+        admin_api_token,
         valve.clone(),
     )
     .await?;
@@ -262,6 +281,7 @@ pub async fn start_instance_inner(
         readers,
         memory_limit,
         memory_check_frequency,
+        config.persistence.clone(),
         valve.clone(),
     )
     .await?;
@@ -297,6 +317,7 @@ pub(super) async fn start_instance(
     region: Option<String>,
     reader_only: bool,
     volume_id: Option<VolumeId>,
+    admin_api_token: Option<String>,
 ) -> Result<Handle, anyhow::Error> {
     let (trigger, valve) = Valve::new();
     let Config {
@@ -326,6 +347,7 @@ pub(super) async fn start_instance(
         region,
         reader_only,
         volume_id,
+        admin_api_token,
         readers,
         reader_addr,
         valve,
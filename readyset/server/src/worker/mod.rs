@@ -1,12 +1,13 @@
-use std::cmp;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
 use std::future::Future;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::{cmp, fs, io};
 
-use dataflow::{DomainBuilder, DomainRequest, Packet, Readers};
+use dataflow::prelude::NodeIndex;
+use dataflow::{DomainBuilder, DomainRequest, Packet, PersistenceParameters, Readers};
 use futures::stream::FuturesUnordered;
 use futures::FutureExt;
 use futures_util::future::TryFutureExt;
@@ -30,7 +31,7 @@ use tokio::time::Interval;
 use tracing::{debug, error, info, info_span, trace, warn};
 use url::Url;
 
-use self::replica::Replica;
+use self::replica::{QueueDepths, Replica};
 use crate::coordination::{DomainDescriptor, RunDomainResponse};
 use crate::worker::replica::WrappedDomainRequest;
 use crate::ReadySetResult;
@@ -59,6 +60,15 @@ pub enum WorkerRequestKind {
     /// Clear domains.
     ClearDomains,
 
+    /// Shut down the given shards of domains running on this worker, along with the readers in
+    /// them, so that they can be restarted elsewhere.
+    KillDomains {
+        /// The (domain index, shard) pairs to shut down.
+        domains: Vec<(DomainIndex, usize)>,
+        /// The global indices of the reader nodes in those domains.
+        readers: Vec<NodeIndex>,
+    },
+
     /// A set of domains has been started elsewhere in the distributed system.
     ///
     /// The message contains information on how the domain can be reached, in order that
@@ -187,6 +197,10 @@ pub struct Worker {
     pub(crate) evict_interval: Option<Interval>,
     /// A memory limit for state, in bytes.
     pub(crate) memory_limit: Option<usize>,
+    /// A timer for snapshotting the keys of partially materialized readers to disk.
+    pub(crate) reader_key_snapshot_interval: Option<Interval>,
+    /// Persistence parameters, used to decide where reader keys are snapshotted to.
+    pub(crate) persistence: PersistenceParameters,
     /// Channel through which worker requests are received.
     pub(crate) rx: Receiver<WorkerRequest>,
     /// Channel coordinator (used by domains to figure out where other domains are).
@@ -197,6 +211,8 @@ pub struct Worker {
     pub(crate) domain_external: IpAddr,
     /// A store of the current state size of each domain, used for eviction purposes.
     pub(crate) state_sizes: Arc<Mutex<HashMap<(DomainIndex, usize), Arc<AtomicUsize>>>>,
+    /// The number of packets queued for each domain, used to apply backpressure to writes.
+    pub(crate) queue_depths: QueueDepths,
     /// Read handles.
     pub(crate) readers: Readers,
     /// Valve for shutting down; triggered by the [`Handle`] when [`Handle::shutdown`] is called.
@@ -245,6 +261,7 @@ impl Worker {
             WorkerRequestKind::ClearDomains => {
                 info!("controller requested that this worker clears its existing domains");
                 self.coord.clear();
+                self.queue_depths.clear();
                 self.domains.clear();
                 while let Some(res) = self.domain_wait_queue.next().await {
                     handle_domain_future_completion(res);
@@ -252,6 +269,29 @@ impl Worker {
 
                 Ok(None)
             }
+            WorkerRequestKind::KillDomains { domains, readers } => {
+                let mut state_sizes = self.state_sizes.lock().await;
+                for key in domains {
+                    info!(
+                        domain_index = key.0.index(),
+                        shard = key.1,
+                        "controller requested that this worker shuts down domain"
+                    );
+                    self.coord.remove(&key);
+                    state_sizes.remove(&key);
+                    self.queue_depths.remove(&key);
+                    // Dropping the handle sends the domain its abort signal
+                    self.domains.remove(&key);
+                }
+
+                #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+                self.readers
+                    .lock()
+                    .unwrap()
+                    .retain(|(gid, _, _), _| !readers.contains(gid));
+
+                Ok(None)
+            }
             WorkerRequestKind::RunDomain(builder) => {
                 let idx = builder.index;
                 let shard = builder.shard.unwrap_or(0);
@@ -298,7 +338,16 @@ impl Worker {
                     .await
                     .insert((idx, shard), state_size);
 
-                let replica = Replica::new(domain, listener, local_rx, req_rx, self.coord.clone());
+                let queue_depth = self.queue_depths.register((idx, shard));
+                let replica = Replica::new(
+                    domain,
+                    listener,
+                    local_rx,
+                    req_rx,
+                    self.coord.clone(),
+                    queue_depth,
+                    self.queue_depths.clone(),
+                );
                 // Each domain is single threaded in nature, so we spawn each one in a separate
                 // thread, so we can avoid running blocking operations on the multi
                 // threaded tokio runtime
@@ -395,6 +444,15 @@ impl Worker {
                 }
             };
 
+            let si = &mut self.reader_key_snapshot_interval;
+            let reader_key_snapshot = async {
+                if let Some(ref mut si) = si {
+                    si.tick().await
+                } else {
+                    futures_util::future::pending().await
+                }
+            };
+
             select! {
                 req = self.rx.recv() => {
                     if let Some(req) = req {
@@ -412,6 +470,11 @@ impl Worker {
                 _ = eviction => {
                     self.process_eviction();
                 }
+                _ = reader_key_snapshot => {
+                    let readers = self.readers.clone();
+                    let persistence = self.persistence.clone();
+                    tokio::task::spawn_blocking(move || snapshot_reader_keys(readers, persistence));
+                }
                 Some(res) = self.domain_wait_queue.next() => {
                     handle_domain_future_completion(res);
                 }
@@ -420,6 +483,34 @@ impl Worker {
     }
 }
 
+/// Write the keys present in every partially materialized reader on this worker to the file given
+/// by [`PersistenceParameters::reader_keys_path`], so that the readers can be warmed back up with
+/// the same keys when they're recreated after a restart.
+fn snapshot_reader_keys(readers: Readers, persistence: PersistenceParameters) {
+    #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+    let snapshots = readers
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, handle)| handle.is_partial() && !handle.is_empty())
+        .filter_map(|((_, name, shard), handle)| {
+            Some((persistence.reader_keys_path(name, *shard)?, handle.keys()))
+        })
+        .collect::<Vec<_>>();
+
+    for (path, keys) in snapshots {
+        // Write to a temporary file first, so a crash mid-write never leaves a truncated snapshot
+        let tmp_path = path.with_extension("keys.tmp");
+        let res = bincode::serialize(&keys)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+            .and_then(|bytes| fs::write(&tmp_path, bytes))
+            .and_then(|_| fs::rename(&tmp_path, &path));
+        if let Err(error) = res {
+            warn!(%error, path = %path.display(), "Could not snapshot reader keys");
+        }
+    }
+}
+
 /// Calculate the total memory used by the process (by querying [`jemalloc_ctl`]), then perform an
 /// eviction if that's over the configured `memory_limit`.
 ///
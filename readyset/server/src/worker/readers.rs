@@ -7,6 +7,7 @@ use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::task::Poll;
 use std::time::Duration;
 use std::{iter, mem, time};
@@ -23,10 +24,13 @@ use launchpad::intervals;
 use nom_sql::SqlIdentifier;
 use readyset::consistency::Timestamp;
 use readyset::metrics::recorded;
-use readyset::{KeyComparison, LookupResult, ReadQuery, ReadReply, ReadReplyStats, Tagged, ViewQuery};
+use readyset::{
+    KeyComparison, LookupResult, ReadQuery, ReadReply, ReadReplyStats, Tagged, ViewQuery,
+};
 use readyset_errors::internal_err;
 use pin_project::pin_project;
 use readyset_tracing::presampled::instrument_if_enabled;
+use readyset_tracing::propagation::Instrumented;
 use serde::ser::Serializer;
 use serde::Serialize;
 use stream_cancel::Valve;
@@ -34,7 +38,7 @@ use tokio::task_local;
 use tokio_stream::wrappers::TcpListenerStream;
 use tokio_tower::multiplex::server;
 use tower::Service;
-use tracing::{error, instrument, warn};
+use tracing::{error, warn, Span};
 
 /// Retry reads every this often.
 const RETRY_TIMEOUT: Duration = Duration::from_micros(100);
@@ -44,6 +48,10 @@ const RETRY_TIMEOUT: Duration = Duration::from_micros(100);
 /// while, waiting readers will use exponential backoff on this delay if they continue to miss.
 const TRIGGER_TIMEOUT_MS: u64 = 20;
 
+/// Cursors opened by paged reads which haven't been read from for this long are dropped, along
+/// with the rows left in them.
+const CURSOR_TIMEOUT: Duration = Duration::from_secs(60);
+
 task_local! {
     pub static READERS: RefCell<HashMap<
         (NodeIndex, SqlIdentifier, usize),
@@ -191,6 +199,106 @@ impl serde::Serialize for ServerReadReplyBatch {
     }
 }
 
+/// The rows left to be read from the result set of a paged read.
+///
+/// The result set is read from the reader in full when the first page is requested, so every page
+/// comes from the same snapshot of the reader, and the lookup and post-lookup operations only run
+/// once for the whole result set.
+struct Cursor {
+    /// The rows left in the result set for each key of the read
+    results: Vec<std::vec::IntoIter<Vec<DataType>>>,
+    /// When a page was last read from this cursor
+    last_read: time::Instant,
+}
+
+impl Cursor {
+    /// Take the next page of at most `page_size` rows of the result set for each key, returning
+    /// the page and whether there are any rows left after it
+    fn next_page(&mut self, page_size: usize) -> (Vec<ServerReadReplyBatch>, bool) {
+        self.last_read = time::Instant::now();
+        let page = self
+            .results
+            .iter_mut()
+            .map(|rows| ServerReadReplyBatch::Unserialized(rows.take(page_size).collect()))
+            .collect();
+        let more = self.results.iter().any(|rows| !rows.as_slice().is_empty());
+        (page, more)
+    }
+}
+
+#[derive(Default)]
+struct CursorsInner {
+    next_id: u64,
+    cursors: HashMap<u64, Cursor>,
+}
+
+/// The cursors opened by the paged reads against the readers on a worker.
+///
+/// These are shared between all the connections to the worker, since clients can request the
+/// pages of a read over any of their connections to it.
+#[derive(Clone, Default)]
+pub struct Cursors {
+    inner: Arc<Mutex<CursorsInner>>,
+}
+
+impl Cursors {
+    /// Split the first page of at most `page_size` rows for each key off of the unserialized
+    /// results of a read, keeping the rest of the rows in a new cursor if there are any left
+    fn open(
+        &self,
+        results: Vec<ServerReadReplyBatch>,
+        page_size: usize,
+    ) -> ReadySetResult<(Vec<ServerReadReplyBatch>, Option<u64>)> {
+        let results = results
+            .into_iter()
+            .map(|batch| match batch {
+                ServerReadReplyBatch::Empty => Ok(Vec::new().into_iter()),
+                ServerReadReplyBatch::Unserialized(rows) => Ok(rows.into_iter()),
+                ServerReadReplyBatch::Serialized(_) => {
+                    Err(internal_err("Can't page a serialized read batch"))
+                }
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+        let mut cursor = Cursor {
+            results,
+            last_read: time::Instant::now(),
+        };
+        let (page, more) = cursor.next_page(page_size);
+        if !more {
+            return Ok((page, None));
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let now = time::Instant::now();
+        inner
+            .cursors
+            .retain(|_, cursor| now.duration_since(cursor.last_read) < CURSOR_TIMEOUT);
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.cursors.insert(id, cursor);
+        Ok((page, Some(id)))
+    }
+
+    /// Take the next page of at most `page_size` rows for each key from the cursor with the given
+    /// id, dropping the cursor once it has been read in full
+    fn next_page(
+        &self,
+        id: u64,
+        page_size: usize,
+    ) -> ReadySetResult<(Vec<ServerReadReplyBatch>, Option<u64>)> {
+        let mut inner = self.inner.lock().unwrap();
+        let (page, more) = inner
+            .cursors
+            .get_mut(&id)
+            .ok_or(ReadySetError::CursorNotFound(id))?
+            .next_page(page_size);
+        if !more {
+            inner.cursors.remove(&id);
+        }
+        Ok((page, more.then(|| id)))
+    }
+}
+
 pub type Ack =
     tokio::sync::oneshot::Sender<Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>>;
 
@@ -202,7 +310,16 @@ pub struct ReadRequestHandler {
     wait: tokio::sync::mpsc::UnboundedSender<(BlockingRead, Ack)>,
     miss_ctr: metrics::Counter,
     hit_ctr: metrics::Counter,
+    reader_ctrs: HashMap<SqlIdentifier, ReaderCounters>,
     upquery_timeout: Duration,
+    cursors: Cursors,
+}
+
+/// Hit and miss counters for a single reader, labeled with the reader's name
+#[derive(Clone)]
+struct ReaderCounters {
+    hit: metrics::Counter,
+    miss: metrics::Counter,
 }
 
 impl ReadRequestHandler {
@@ -211,16 +328,36 @@ impl ReadRequestHandler {
         readers: Readers,
         wait: tokio::sync::mpsc::UnboundedSender<(BlockingRead, Ack)>,
         upquery_timeout: Duration,
+        cursors: Cursors,
     ) -> Self {
         Self {
             readers,
             wait,
             miss_ctr: metrics::register_counter!(recorded::SERVER_VIEW_QUERY_MISS),
             hit_ctr: metrics::register_counter!(recorded::SERVER_VIEW_QUERY_HIT),
+            reader_ctrs: HashMap::new(),
             upquery_timeout,
+            cursors,
         }
     }
 
+    /// Returns the hit and miss counters for the reader with the given name, registering them the
+    /// first time a reader is read from
+    fn reader_counters(&mut self, name: &SqlIdentifier) -> &ReaderCounters {
+        self.reader_ctrs
+            .entry(name.clone())
+            .or_insert_with(|| ReaderCounters {
+                hit: metrics::register_counter!(
+                    recorded::READER_LOOKUP_HIT,
+                    "reader" => name.to_string()
+                ),
+                miss: metrics::register_counter!(
+                    recorded::READER_LOOKUP_MISS,
+                    "reader" => name.to_string()
+                ),
+            })
+    }
+
     /// Always returns `ServerReadReplyBatch::Unserialized` if `raw_result` is passed.
     pub fn handle_normal_read_query(
         &mut self,
@@ -395,19 +532,25 @@ impl ReadRequestHandler {
             // Hit on all the keys and were RYW consistent
             if !consistency_miss && miss_keys.is_empty() {
                 self.hit_ctr.increment(1);
+                self.reader_counters(&target.1).hit.increment(1);
                 return Ok(Ok(Tagged {
                     tag,
                     v: ReadReply::Normal(Ok(LookupResult::Results(ret, ReadReplyStats::default()))),
                 }));
             }
             self.miss_ctr.increment(1);
+            self.reader_counters(&target.1).miss.increment(1);
+
+            // Record how many evictions the reader has had before triggering the backfills, so we
+            // can tell whether keys were evicted while the read was waiting for them
+            let evictions = reader.evictions();
 
             // Trigger backfills for all the keys we missed on, regardless of a consistency hit/miss
             if !keys_to_replay.is_empty() {
                 reader.trigger(keys_to_replay.iter());
             }
 
-            Ok(Err((miss_keys, ret, miss_indices)))
+            Ok(Err((miss_keys, ret, miss_indices, evictions)))
         });
 
         let immediate = match immediate {
@@ -417,7 +560,7 @@ impl ReadRequestHandler {
 
         match immediate {
             Ok(reply) => Either::Left(future::ready(Ok(reply))),
-            Err((pending_keys, ret, pending_indices)) => {
+            Err((pending_keys, ret, pending_indices, evictions)) => {
                 if !block {
                     Either::Left(future::ready(Ok(Tagged {
                         tag,
@@ -444,6 +587,8 @@ impl ReadRequestHandler {
                             timestamp,
                             upquery_timeout: self.upquery_timeout,
                             raw_result,
+                            evictions,
+                            span: readyset_tracing::child_span!(INFO, "upquery"),
                         },
                         tx,
                     ));
@@ -460,6 +605,59 @@ impl ReadRequestHandler {
         }
     }
 
+    /// Perform a normal read, but only reply with the first page of at most `page_size` rows of
+    /// the results for each key, keeping the rest of the rows in a cursor to be read with
+    /// [`handle_next_page_query`](Self::handle_next_page_query)
+    fn handle_paged_read_query(
+        &mut self,
+        tag: u32,
+        target: (NodeIndex, SqlIdentifier, usize),
+        query: ViewQuery,
+        page_size: usize,
+    ) -> impl Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send
+    {
+        let cursors = self.cursors.clone();
+        self.handle_normal_read_query(tag, target, query, true)
+            .map_ok(move |reply| {
+                let page = match reply.v {
+                    ReadReply::Normal(result) => result.and_then(|result| match result {
+                        LookupResult::NonBlockingMiss => Ok((LookupResult::NonBlockingMiss, None)),
+                        LookupResult::Results(results, stats) => {
+                            let (page, cursor) = cursors.open(results, page_size)?;
+                            Ok((LookupResult::Results(page, stats), cursor))
+                        }
+                    }),
+                    _ => Err(internal_err("Unexpected reply to a normal read query")),
+                };
+                Tagged {
+                    tag: reply.tag,
+                    v: ReadReply::Page(page),
+                }
+            })
+    }
+
+    fn handle_next_page_query(
+        &mut self,
+        tag: u32,
+        cursor: u64,
+        page_size: usize,
+    ) -> impl Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send
+    {
+        let page = self
+            .cursors
+            .next_page(cursor, page_size)
+            .map(|(page, cursor)| {
+                (
+                    LookupResult::Results(page, ReadReplyStats::default()),
+                    cursor,
+                )
+            });
+        future::ready(Ok(Tagged {
+            tag,
+            v: ReadReply::Page(page),
+        }))
+    }
+
     fn handle_size_query(
         &mut self,
         tag: u32,
@@ -501,22 +699,28 @@ impl ReadRequestHandler {
 }
 
 #[pin_project(project = ReadResponseFutureProj)]
-enum ReadResponseFuture<N, S, K>
+enum ReadResponseFuture<N, S, K, P, C>
 where
     N: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
     S: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
     K: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
+    P: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
+    C: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
 {
     Normal(#[pin] N),
     Size(#[pin] S),
     Keys(#[pin] K),
+    Paged(#[pin] P),
+    NextPage(#[pin] C),
 }
 
-impl<N, S, K> Future for ReadResponseFuture<N, S, K>
+impl<N, S, K, P, C> Future for ReadResponseFuture<N, S, K, P, C>
 where
     N: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
     S: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
     K: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
+    P: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
+    C: Future<Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> + Send,
 {
     type Output = Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>;
     #[inline]
@@ -525,11 +729,13 @@ where
             ReadResponseFutureProj::Normal(f) => f.poll(cx),
             ReadResponseFutureProj::Size(f) => f.poll(cx),
             ReadResponseFutureProj::Keys(f) => f.poll(cx),
+            ReadResponseFutureProj::Paged(f) => f.poll(cx),
+            ReadResponseFutureProj::NextPage(f) => f.poll(cx),
         }
     }
 }
 
-impl Service<Tagged<ReadQuery>> for ReadRequestHandler {
+impl Service<Instrumented<Tagged<ReadQuery>>> for ReadRequestHandler {
     type Response = Tagged<ReadReply<ServerReadReplyBatch>>;
     type Error = ReadySetError;
     type Future = impl Future<Output = Result<Self::Response, Self::Error>> + Send;
@@ -538,26 +744,44 @@ impl Service<Tagged<ReadQuery>> for ReadRequestHandler {
         Poll::Ready(Ok(()))
     }
 
-    #[instrument(level = "info", skip_all)]
     #[inline]
-    fn call(&mut self, m: Tagged<ReadQuery>) -> Self::Future {
+    fn call(&mut self, m: Instrumented<Tagged<ReadQuery>>) -> Self::Future {
+        // Continue the trace of the read from the client, if it was sampled
+        let (span, m) = readyset_tracing::remote_span!(m, INFO, "read_request");
+        let _guard = span.enter();
         let tag = m.tag;
         match m.v {
             ReadQuery::Normal { target, query } => {
-                let future = self.handle_normal_read_query(tag, target, query, false);
                 let span = readyset_tracing::child_span!(INFO, "normal_read_query");
+                let future =
+                    span.in_scope(|| self.handle_normal_read_query(tag, target, query, false));
                 ReadResponseFuture::Normal(instrument_if_enabled(future, span))
             }
             ReadQuery::Size { target } => {
-                let future = self.handle_size_query(tag, target);
                 let span = readyset_tracing::child_span!(INFO, "size_query");
+                let future = span.in_scope(|| self.handle_size_query(tag, target));
                 ReadResponseFuture::Size(instrument_if_enabled(future, span))
             }
             ReadQuery::Keys { target } => {
-                let future = self.handle_keys_query(tag, target);
                 let span = readyset_tracing::child_span!(INFO, "keys_query");
+                let future = span.in_scope(|| self.handle_keys_query(tag, target));
                 ReadResponseFuture::Keys(instrument_if_enabled(future, span))
             }
+            ReadQuery::Paged {
+                target,
+                query,
+                page_size,
+            } => {
+                let span = readyset_tracing::child_span!(INFO, "paged_read_query");
+                let future =
+                    span.in_scope(|| self.handle_paged_read_query(tag, target, query, page_size));
+                ReadResponseFuture::Paged(instrument_if_enabled(future, span))
+            }
+            ReadQuery::NextPage { cursor, page_size } => {
+                let span = readyset_tracing::child_span!(INFO, "next_page_query");
+                let future = span.in_scope(|| self.handle_next_page_query(tag, cursor, page_size));
+                ReadResponseFuture::NextPage(instrument_if_enabled(future, span))
+            }
         }
     }
 }
@@ -569,6 +793,7 @@ pub(crate) async fn listen(
     upquery_timeout: Duration,
 ) {
     let mut stream = valve.wrap(TcpListenerStream::new(on)).into_stream();
+    let cursors = Cursors::default();
     while let Some(stream) = stream.next().await {
         set_failpoint!("read-query");
         if stream.is_err() {
@@ -592,7 +817,13 @@ pub(crate) async fn listen(
                 tokio::time::sleep(RETRY_TIMEOUT / 4).await;
                 loop {
                     if let Poll::Ready(res) = pending.check() {
-                        upquery_hist.record(pending.first.elapsed().as_micros() as f64);
+                        let upquery_duration = pending.first.elapsed().as_micros() as f64;
+                        upquery_hist.record(upquery_duration);
+                        metrics::histogram!(
+                            recorded::READER_UPQUERY_DURATION,
+                            upquery_duration,
+                            "reader" => pending.target.1.to_string()
+                        );
                         let _ = ack.send(res);
                         break;
                     }
@@ -602,7 +833,7 @@ pub(crate) async fn listen(
         });
         tokio::spawn(retries);
 
-        let r = ReadRequestHandler::new(readers, tx, upquery_timeout);
+        let r = ReadRequestHandler::new(readers, tx, upquery_timeout, cursors.clone());
 
         let server = READERS.scope(
             Default::default(),
@@ -718,6 +949,11 @@ pub struct BlockingRead {
     timestamp: Option<Timestamp>,
     upquery_timeout: Duration,
     raw_result: bool,
+    /// Number of evictions from the reader when the read first missed
+    evictions: u64,
+    /// Span covering the time spent waiting for the upquery, which is closed when the read is
+    /// dropped after completing
+    span: Span,
 }
 
 impl std::fmt::Debug for BlockingRead {
@@ -741,7 +977,7 @@ impl BlockingRead {
     pub fn check(
         &mut self,
     ) -> Poll<Result<Tagged<ReadReply<ServerReadReplyBatch>>, ReadySetError>> {
-        READERS.with(|readers_cache| {
+        let evicted = READERS.with(|readers_cache| {
             let mut readers_cache = readers_cache.borrow_mut();
             let s = &self.truth;
             let target = &self.target;
@@ -837,7 +1073,7 @@ impl BlockingRead {
                 }
             }
 
-            Ok(())
+            Ok(reader.evictions() > self.evictions)
         })?;
 
         if self.pending_keys.is_empty() {
@@ -845,7 +1081,11 @@ impl BlockingRead {
                 tag: self.tag,
                 v: ReadReply::Normal(Ok(LookupResult::Results(
                     mem::take(&mut self.read),
-                    ReadReplyStats { cache_misses: 1 },
+                    ReadReplyStats {
+                        cache_misses: 1,
+                        upquery_duration: self.first.elapsed(),
+                        evicted_during_upquery: evicted,
+                    },
                 ))),
             }))
         } else {
@@ -1087,3 +1327,72 @@ mod readreply {
         .await;
     }
 }
+
+#[cfg(test)]
+mod cursors {
+    use super::*;
+
+    fn rows(batch: ServerReadReplyBatch) -> Vec<Vec<DataType>> {
+        batch.into_unserialized().unwrap()
+    }
+
+    #[test]
+    fn pages_from_cursor() {
+        let cursors = Cursors::default();
+        let results = vec![
+            ServerReadReplyBatch::Unserialized((0..5).map(|i| vec![DataType::from(i)]).collect()),
+            ServerReadReplyBatch::Empty,
+            ServerReadReplyBatch::Unserialized(vec![vec![DataType::from(10)]]),
+        ];
+
+        let (page, cursor) = cursors.open(results, 2).unwrap();
+        let cursor = cursor.unwrap();
+        assert_eq!(
+            page.into_iter().map(rows).collect::<Vec<_>>(),
+            vec![
+                vec![vec![DataType::from(0)], vec![DataType::from(1)]],
+                vec![],
+                vec![vec![DataType::from(10)]],
+            ]
+        );
+
+        let (page, next) = cursors.next_page(cursor, 2).unwrap();
+        assert_eq!(next, Some(cursor));
+        assert_eq!(
+            page.into_iter().map(rows).collect::<Vec<_>>(),
+            vec![
+                vec![vec![DataType::from(2)], vec![DataType::from(3)]],
+                vec![],
+                vec![],
+            ]
+        );
+
+        let (page, next) = cursors.next_page(cursor, 2).unwrap();
+        assert_eq!(next, None);
+        assert_eq!(
+            page.into_iter().map(rows).collect::<Vec<_>>(),
+            vec![vec![vec![DataType::from(4)]], vec![], vec![]]
+        );
+
+        assert!(matches!(
+            cursors.next_page(cursor, 2),
+            Err(ReadySetError::CursorNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn no_cursor_for_single_page() {
+        let cursors = Cursors::default();
+        let (page, cursor) = cursors
+            .open(
+                vec![ServerReadReplyBatch::Unserialized(vec![vec![
+                    DataType::from(1),
+                ]])],
+                2,
+            )
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert!(cursor.is_none());
+        assert!(cursors.inner.lock().unwrap().cursors.is_empty());
+    }
+}
@@ -1,7 +1,8 @@
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::{HashSet, VecDeque};
 use std::net::SocketAddr;
-use std::sync::{atomic, Arc};
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
 use std::time;
 
 use ahash::AHashMap;
@@ -13,8 +14,10 @@ use dataflow::{Domain, DomainRequest, Packet};
 use futures_util::sink::{Sink, SinkExt};
 use futures_util::stream::StreamExt;
 use futures_util::FutureExt;
+use metrics::{register_counter, register_gauge, Counter, Gauge};
 use readyset::channel::{self, CONNECTION_FROM_BASE};
 use readyset::internal::{DomainIndex, LocalOrNot};
+use readyset::metrics::recorded;
 use readyset::{KeyComparison, PacketData, PacketPayload, Tagged};
 use strawpoll::Strawpoll;
 use time::Duration;
@@ -45,6 +48,54 @@ fn next_token() -> u64 {
     NEXT_TOKEN.fetch_add(1, atomic::Ordering::Relaxed)
 }
 
+/// The number of packets sent to each domain running on this worker over its in-process channel
+/// which the domain hasn't received yet, used to stop domains accepting writes to their base tables
+/// while the rest of the dataflow graph on the worker is falling behind.
+///
+/// Only packets sent by [`Replica::send_packets`] are counted, so the depths may be lower than the
+/// real number of queued packets, but never higher.
+#[derive(Clone, Default)]
+pub(crate) struct QueueDepths(Arc<std::sync::Mutex<AHashMap<ReplicaAddr, Arc<AtomicUsize>>>>);
+
+impl QueueDepths {
+    /// Start tracking the queue depth of the domain at `addr`, returning its counter
+    pub(super) fn register(&self, addr: ReplicaAddr) -> Arc<AtomicUsize> {
+        let depth = Arc::new(AtomicUsize::new(0));
+        self.lock().insert(addr, depth.clone());
+        depth
+    }
+
+    /// Stop tracking the queue depth of the domain at `addr`
+    pub(super) fn remove(&self, addr: &ReplicaAddr) {
+        self.lock().remove(addr);
+    }
+
+    /// Stop tracking the queue depths of all domains
+    pub(super) fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Record that `n` packets are about to be sent to the domain at `addr`, if it's tracked
+    fn add(&self, addr: &ReplicaAddr, n: usize) {
+        if let Some(depth) = self.lock().get(addr) {
+            depth.fetch_add(n, atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Return the total number of packets queued for the domains on this worker
+    fn total(&self) -> usize {
+        self.lock()
+            .values()
+            .map(|depth| depth.load(atomic::Ordering::Relaxed))
+            .sum()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, AHashMap<ReplicaAddr, Arc<AtomicUsize>>> {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        self.0.lock().unwrap()
+    }
+}
+
 /// A domain request wrapped together with the sender to which to send the processing result
 pub struct WrappedDomainRequest {
     pub req: DomainRequest,
@@ -68,6 +119,18 @@ pub struct Replica {
     /// A receiver for locally sent messages
     locals: mpsc::UnboundedReceiver<Box<Packet>>,
 
+    /// The number of packets queued for this domain on `locals`
+    queue_depth: Arc<AtomicUsize>,
+
+    /// The queue depths of all of the domains on this worker
+    queue_depths: QueueDepths,
+
+    /// Records the value of `queue_depth`
+    queue_depth_gauge: Gauge,
+
+    /// Counts the times this domain stopped accepting writes to its base tables
+    ingestion_paused: Counter,
+
     /// A receiver for domain messages
     requests: mpsc::Receiver<WrappedDomainRequest>,
 
@@ -82,12 +145,28 @@ impl Replica {
         locals: mpsc::UnboundedReceiver<Box<Packet>>,
         requests: mpsc::Receiver<WrappedDomainRequest>,
         cc: Arc<ChannelCoordinator>,
+        queue_depth: Arc<AtomicUsize>,
+        queue_depths: QueueDepths,
     ) -> Self {
+        let index = domain.index().index().to_string();
+        let shard = domain.shard().to_string();
         Replica {
             coord: cc,
+            queue_depth_gauge: register_gauge!(
+                recorded::DOMAIN_INPUT_QUEUE_DEPTH,
+                "domain" => index.clone(),
+                "shard" => shard.clone(),
+            ),
+            ingestion_paused: register_counter!(
+                recorded::DOMAIN_INGESTION_PAUSED,
+                "domain" => index,
+                "shard" => shard,
+            ),
             domain,
             incoming: Strawpoll::from(on),
             locals,
+            queue_depth,
+            queue_depths,
             out: Outboxes::new(),
             refresh_sizes: IntervalStream::new(tokio::time::interval(Duration::from_millis(500))),
             requests,
@@ -115,6 +194,9 @@ impl Executor for Outboxes {
 }
 
 /// Merge multiple [`RequestReaderReplay`] packets into a single packet
+///
+/// The merged packet keeps the trace context of the first packet, so the replays requested by the
+/// other packets aren't attributed to their own traces.
 fn flatten_request_reader_replay(
     n: readyset::internal::LocalNodeIndex,
     c: &[usize],
@@ -125,9 +207,9 @@ fn flatten_request_reader_replay(
     let mut i = 0;
     while i < packets.len() {
         match packets.get_mut(i) {
-            Some(box Packet::RequestReaderReplay { node, cols, keys })
-                if *node == n && *cols == c =>
-            {
+            Some(box Packet::RequestReaderReplay {
+                node, cols, keys, ..
+            }) if *node == n && *cols == c => {
                 unique_keys.extend(keys.drain(..));
                 packets.remove(i);
             }
@@ -149,11 +231,11 @@ impl Replica {
     }
 
     /// Read the first byte of a connection to determine if it is from a base node, and convert
-    /// it to a DualTcpStream, returning a unique token for the connection together with the
-    /// upgraded connection
+    /// it to a DualTcpStream, returning a unique token for the connection together with whether
+    /// it's from a base node and the upgraded connection
     async fn handle_new_connection(
         mut stream: TcpStream,
-    ) -> Result<(u64, DualTcpStream), anyhow::Error> {
+    ) -> Result<(u64, bool, DualTcpStream), anyhow::Error> {
         let mut tag: u8 = 0;
         stream.read_exact(std::slice::from_mut(&mut tag)).await?;
         let is_base = tag == CONNECTION_FROM_BASE;
@@ -188,13 +270,17 @@ impl Replica {
             .into()
         };
 
-        Ok((token, tcp))
+        Ok((token, is_base, tcp))
     }
 
-    /// Receive packets from local and remote connections
+    /// Receive packets from local and remote connections, only reading writes from base
+    /// connections if `ingest` is true
     async fn receive_packets(
         locals: &mut mpsc::UnboundedReceiver<Box<Packet>>,
+        queue_depth: &AtomicUsize,
         connections: &mut tokio_stream::StreamMap<u64, DualTcpStream>,
+        base_connections: &mut tokio_stream::StreamMap<u64, DualTcpStream>,
+        ingest: bool,
     ) -> ReadySetResult<Option<VecDeque<Box<Packet>>>> {
         const MAX_PACKETS_PER_CALL: usize = 64;
 
@@ -222,6 +308,11 @@ impl Replica {
                             }
                         }
                     }
+                    let _ = queue_depth.fetch_update(
+                        atomic::Ordering::Relaxed,
+                        atomic::Ordering::Relaxed,
+                        |depth| Some(depth.saturating_sub(packets.len())),
+                    );
                 }
             },
 
@@ -236,6 +327,18 @@ impl Replica {
                     }
                 }
             }
+
+            Some((_, packet)) = base_connections.next(), if ingest => {
+                let packet = packet?;
+                packets.push_back(packet);
+                // Try to read more packets without waiting
+                while let Some(Some((_, packet))) = base_connections.next().now_or_never() {
+                    packets.push_back(packet?);
+                    if packets.len() > MAX_PACKETS_PER_CALL {
+                        break;
+                    }
+                }
+            }
         }
 
         Ok(Some(packets))
@@ -250,6 +353,7 @@ impl Replica {
         connections: &tokio::sync::Mutex<Outputs>,
         coord: &ChannelCoordinator,
         failed: &Mutex<HashSet<SocketAddr>>,
+        queue_depths: &QueueDepths,
     ) -> ReadySetResult<()> {
         let mut lock = connections.lock().await;
 
@@ -289,6 +393,7 @@ impl Replica {
             //
             // The next time a batch of packets is sent to a domain, a new connection
             // will be established if the domain has been repaired.
+            queue_depths.add(&replica_address, messages.len());
             let mut send_err = false;
             while let Some(m) = messages.pop_front() {
                 if let Err(e) = tx.feed(m).await {
@@ -323,6 +428,10 @@ impl Replica {
         let mut connection_preambles = futures::stream::FuturesUnordered::new();
         // Every established connection goes here
         let mut connections: tokio_stream::StreamMap<u64, DualTcpStream> = Default::default();
+        // Except for connections from base table handles, which we stop reading from while too
+        // many packets are queued for the domains on this worker
+        let mut base_connections: tokio_stream::StreamMap<u64, DualTcpStream> = Default::default();
+        let mut ingest = true;
         // A cache of established connections to other Replicas we may send messages to
         // sadly have to use Mutex here to make it possible to pass a mutable reference to outputs
         // to an async function
@@ -345,11 +454,26 @@ impl Replica {
             refresh_sizes,
             incoming,
             locals,
+            queue_depth,
+            queue_depths,
+            queue_depth_gauge,
+            ingestion_paused,
             requests,
             out,
         } = &mut self;
 
         loop {
+            if let Some(max_queued_packets) = domain.max_queued_packets() {
+                let queued =
+                    queue_depths.total() + out.domains.values().map(VecDeque::len).sum::<usize>();
+                let was_ingesting = ingest;
+                ingest = queued < max_queued_packets;
+                if was_ingesting && !ingest && !base_connections.is_empty() {
+                    span.in_scope(|| debug!(queued, "pausing writes to base tables"));
+                    ingestion_paused.increment(1);
+                }
+            }
+
             // we have three logical input sources: receives from local domains, receives from
             // remote domains, and remote mutators.
 
@@ -365,12 +489,16 @@ impl Replica {
 
                 // Handle any connections that we accepted but still need to preprocess and convert to DualTcpStream
                 Some(established_conn) = connection_preambles.next() => {
-                    let (token, tcp) = match established_conn {
+                    let (token, is_base, tcp) = match established_conn {
                         Err(_) => continue, // Ignore the errors on unestablished connections, they don't matter
                         Ok(tcp) => tcp,
                     };
 
-                    connections.insert(token, tcp);
+                    if is_base {
+                        base_connections.insert(token, tcp);
+                    } else {
+                        connections.insert(token, tcp);
+                    }
                 },
 
                 // Handle domain requests
@@ -388,7 +516,7 @@ impl Replica {
                 },
 
                 // Handle incoming messages
-                packets = Self::receive_packets(locals, &mut connections) => match packets? {
+                packets = Self::receive_packets(locals, queue_depth, &mut connections, &mut base_connections, ingest) => match packets? {
                     None => {
                         span.in_scope(|| warn!("local input stream ended"));
                         return Ok(())
@@ -399,9 +527,9 @@ impl Replica {
                                 Packet::Timestamp { src: SourceChannelIdentifier { token, tag }, .. } |
                                 Packet::Input { src: SourceChannelIdentifier { token, tag }, .. } => {
                                     // After processing we need to ack timestamp and input messages from base
-                                    connections.iter_mut().find(|(t, _)| *t == *token).map(|(_, conn)| (*tag, conn))
+                                    base_connections.iter_mut().find(|(t, _)| *t == *token).map(|(_, conn)| (*tag, conn))
                                 }
-                                Packet::RequestReaderReplay { node, cols, keys } => {
+                                Packet::RequestReaderReplay { node, cols, keys, .. } => {
                                     // We want to batch multiple reader replay requests into a single call while
                                     // deduplicating non unique keys
                                     let mut unique_keys: HashSet<_> = keys.drain(..).collect();
@@ -425,7 +553,13 @@ impl Replica {
                 Some(res) = send_packets.next() => res?,
 
                 // Update domain sizes when `refresh_sizes` expires
-                Some(_) = refresh_sizes.next() => domain.update_state_sizes(),
+                Some(_) = refresh_sizes.next() => {
+                    domain.update_state_sizes();
+                    queue_depth_gauge.set(queue_depth.load(atomic::Ordering::Relaxed) as f64);
+                },
+
+                // While writes are paused, periodically check whether the queues have drained
+                _ = tokio::time::sleep(Duration::from_millis(10)), if !ingest => {},
 
                 // Wait for a possible sleep
                 _ = tokio::time::sleep(domain.next_poll_duration().unwrap_or_else(|| Duration::from_secs(3600))) => domain.handle_timeout()?,
@@ -434,7 +568,13 @@ impl Replica {
             // Check if the previous batch of send packets is done, and issue a new batch if needed
             if send_packets.is_empty() && !out.domains.is_empty() {
                 let to_send: Vec<_> = out.domains.drain().collect();
-                send_packets.push(Self::send_packets(to_send, &outputs, coord, &failed));
+                send_packets.push(Self::send_packets(
+                    to_send,
+                    &outputs,
+                    coord,
+                    &failed,
+                    queue_depths,
+                ));
             }
         }
     }
@@ -9,7 +9,7 @@ struct ViewChecker {
     #[clap(short, long, env("AUTHORITY_ADDRESS"), default_value("127.0.0.1:2181"))]
     authority_address: String,
 
-    #[clap(long, env("AUTHORITY"), default_value("zookeeper"), possible_values = &["consul", "zookeeper"])]
+    #[clap(long, env("AUTHORITY"), default_value("zookeeper"), possible_values = &["consul", "zookeeper", "kubernetes"])]
     authority: AuthorityType,
 
     #[clap(short, long, env("NORIA_DEPLOYMENT"), forbid_empty_values = true)]
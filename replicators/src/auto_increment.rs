@@ -0,0 +1,187 @@
+//! Tracking of the values generated upstream for auto-increment columns.
+//!
+//! Adapters that write directly to ReadySet's base tables generate values for `AUTO_INCREMENT`
+//! (in MySQL) and `SERIAL` or identity (in PostgreSQL) columns themselves, so they need to know the
+//! largest value the upstream database has already generated to avoid handing out the same keys
+//! again and to report the keys they generate through `LAST_INSERT_ID()`. The replicator keeps
+//! track of the largest value of each table's auto-increment column it has copied, both during
+//! snapshot and during replication, and records it with the controller (see
+//! [`ControllerHandle::record_auto_increments`]).
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use nom_sql::ColumnConstraint;
+use readyset::{ControllerHandle, Table, TableOperation};
+use readyset_data::DataType;
+use tracing::warn;
+
+/// The minimum time between recording auto-increment values seen during replication with the
+/// controller, so that replicating many inserts doesn't write to the authority for each of them
+const RECORD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Returns the position of the auto-increment column of `table`, if it has one
+pub(crate) fn auto_increment_column(table: &Table) -> Option<usize> {
+    let schema = table.schema()?;
+    let field = schema
+        .fields
+        .iter()
+        .find(|f| f.constraints.contains(&ColumnConstraint::AutoIncrement))?;
+    table.columns().iter().position(|c| *c == field.column.name)
+}
+
+/// Returns the largest value of the column at position `column` in `rows`, if there are any
+/// positive integer values
+pub(crate) fn max_value<'a, I>(column: usize, rows: I) -> Option<u64>
+where
+    I: IntoIterator<Item = &'a [DataType]>,
+{
+    rows.into_iter()
+        .filter_map(|row| u64::try_from(row.get(column)?).ok())
+        .max()
+}
+
+/// Returns the largest value of the column at position `column` in the rows inserted by `actions`
+pub(crate) fn max_inserted_value(column: usize, actions: &[TableOperation]) -> Option<u64> {
+    max_value(
+        column,
+        actions.iter().filter_map(|action| match action {
+            TableOperation::Insert(row) | TableOperation::InsertOrUpdate { row, .. } => {
+                Some(row.as_slice())
+            }
+            _ => None,
+        }),
+    )
+}
+
+/// The largest value of the auto-increment column of a table whose rows are being copied during
+/// snapshot
+#[derive(Debug)]
+pub(crate) struct SnapshotAutoIncrement {
+    column: Option<usize>,
+    max: Option<u64>,
+}
+
+impl SnapshotAutoIncrement {
+    /// Start tracking the auto-increment column of `table`, if it has one
+    pub(crate) fn new(table: &Table) -> Self {
+        let column = auto_increment_column(table);
+        // The `AUTO_INCREMENT` table option, if the upstream gave us one, is the next value it will
+        // generate, which may be larger than any value still in the table
+        let max = column
+            .and(table.schema())
+            .and_then(|schema| schema.get_autoincrement())
+            .and_then(|next| next.checked_sub(1));
+        Self { column, max }
+    }
+
+    /// Note that `row` has been copied into the table
+    pub(crate) fn observe(&mut self, row: &[DataType]) {
+        if let Some(column) = self.column {
+            self.max = self.max.max(max_value(column, [row]));
+        }
+    }
+
+    /// Returns the largest value of the auto-increment column copied so far
+    pub(crate) fn max(&self) -> Option<u64> {
+        self.max
+    }
+}
+
+/// The largest auto-increment values seen during replication that are yet to be recorded with the
+/// controller
+#[derive(Debug)]
+pub(crate) struct AutoIncrements {
+    pending: HashMap<String, u64>,
+    last_recorded: Instant,
+}
+
+impl Default for AutoIncrements {
+    fn default() -> Self {
+        Self {
+            pending: HashMap::new(),
+            last_recorded: Instant::now(),
+        }
+    }
+}
+
+impl AutoIncrements {
+    /// Note that `value` has been replicated into the auto-increment column of `table`
+    pub(crate) fn observe(&mut self, table: &str, value: u64) {
+        match self.pending.get_mut(table) {
+            Some(max) => *max = (*max).max(value),
+            None => {
+                self.pending.insert(table.to_owned(), value);
+            }
+        }
+    }
+
+    /// Returns the time at which the values observed since they were last recorded with the
+    /// controller should be recorded, or `None` if there aren't any.
+    ///
+    /// Replication waits for this as well as for the next action, so that the values are recorded
+    /// even if no more actions are replicated for a while.
+    pub(crate) fn record_deadline(&self) -> Option<Instant> {
+        (!self.pending.is_empty()).then(|| self.last_recorded + RECORD_INTERVAL)
+    }
+
+    /// Record the values observed since they were last recorded with the controller, if it's been
+    /// long enough since then.
+    ///
+    /// Errors are logged rather than returned, since failing to record these values shouldn't stop
+    /// replication, and they'll be retried once [`RECORD_INTERVAL`] has passed again.
+    pub(crate) async fn record(&mut self, noria: &mut ControllerHandle) {
+        if self.pending.is_empty() || self.last_recorded.elapsed() < RECORD_INTERVAL {
+            return;
+        }
+
+        match noria.record_auto_increments(self.pending.clone()).await {
+            Ok(()) => self.pending.clear(),
+            Err(error) => warn!(%error, "Error recording auto-increment values"),
+        }
+        self.last_recorded = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_inserted() {
+        let actions = vec![
+            TableOperation::Insert(vec![3.into(), "a".into()]),
+            TableOperation::DeleteByKey {
+                key: vec![10.into()],
+            },
+            TableOperation::Insert(vec![DataType::None, "b".into()]),
+            TableOperation::InsertOrUpdate {
+                row: vec![5.into(), "c".into()],
+                update: vec![],
+            },
+        ];
+        assert_eq!(max_inserted_value(0, &actions), Some(5));
+        assert_eq!(max_inserted_value(0, &actions[1..3]), None);
+    }
+
+    #[test]
+    fn observe_keeps_max() {
+        let mut auto_increments = AutoIncrements::default();
+        auto_increments.observe("t", 4);
+        auto_increments.observe("t", 2);
+        auto_increments.observe("u", 1);
+        assert_eq!(auto_increments.pending["t"], 4);
+        assert_eq!(auto_increments.pending["u"], 1);
+    }
+
+    #[test]
+    fn record_deadline() {
+        let mut auto_increments = AutoIncrements::default();
+        assert_eq!(auto_increments.record_deadline(), None);
+        auto_increments.observe("t", 1);
+        assert_eq!(
+            auto_increments.record_deadline(),
+            Some(auto_increments.last_recorded + RECORD_INTERVAL)
+        );
+    }
+}
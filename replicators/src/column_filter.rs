@@ -0,0 +1,515 @@
+//! Filtering of upstream columns whose contents should not be replicated into ReadySet.
+//!
+//! Some tables contain columns holding very large values (such as `longblob` or `bytea` payloads)
+//! which are never referenced by cached queries, but which would otherwise dominate the memory
+//! and disk used by the base tables. Such columns can be filtered in one of two ways:
+//!
+//! * Columns configured in [`Config::nullified_columns`] keep their place in the schema, but every
+//!   value written to them, both during snapshot and during replication, is replaced with NULL.
+//! * Columns configured in [`Config::dropped_columns`] are removed from the `CREATE TABLE` and
+//!   `ALTER TABLE` statements installed in ReadySet, so they don't exist in ReadySet at all. They
+//!   are left out of the queries that snapshot their tables, and their values are removed from
+//!   every replicated row. Since rows in the replication stream have the upstream table's columns,
+//!   the positions of the dropped columns in the upstream table are tracked as DDL is replicated.
+//!
+//! Columns that are part of one of a table's keys are never filtered, and caches that might read a
+//! filtered column are rejected by the controller.
+//!
+//! [`Config::nullified_columns`]: crate::Config::nullified_columns
+//! [`Config::dropped_columns`]: crate::Config::dropped_columns
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use nom_sql::{AlterTableDefinition, ColumnConstraint, CreateTableStatement, TableKey};
+use readyset::recipe::changelist::{Change, ChangeList};
+use readyset::{ControllerHandle, Modification, Table, TableOperation};
+use readyset_data::DataType;
+use tracing::warn;
+
+/// Parses a list of column references of the form `<table>.<column>` into the set of columns of
+/// each table. Entries that aren't of that form are ignored.
+fn parse_columns(columns: &[String]) -> HashMap<String, HashSet<String>> {
+    let mut res: HashMap<String, HashSet<String>> = HashMap::new();
+    for column in columns {
+        match column.rsplit_once('.') {
+            Some((table, column)) if !table.is_empty() && !column.is_empty() => {
+                res.entry(table.to_owned())
+                    .or_default()
+                    .insert(column.to_owned());
+            }
+            _ => warn!(%column, "Ignoring invalid column to filter, expected <table>.<column>"),
+        }
+    }
+    res
+}
+
+/// A column of an upstream table that has dropped columns
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct UpstreamColumn {
+    name: String,
+    /// Whether the column is left out of ReadySet
+    dropped: bool,
+}
+
+/// The columns, per table, whose values are replaced with NULL or that are dropped when
+/// replicated.
+///
+/// Cheap to clone. Clones share the columns recorded for upstream tables.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ColumnFilter {
+    nullified: Arc<HashMap<String, HashSet<String>>>,
+    dropped: Arc<HashMap<String, HashSet<String>>>,
+    /// The columns of each upstream table with dropped columns, in the order they appear in the
+    /// upstream table
+    upstream_columns: Arc<Mutex<HashMap<String, Vec<UpstreamColumn>>>>,
+}
+
+impl ColumnFilter {
+    /// Build a new column filter from lists of column references of the form `<table>.<column>`.
+    /// Entries that aren't of that form are ignored.
+    pub(crate) fn new(nullified: &[String], dropped: &[String]) -> Self {
+        Self {
+            nullified: Arc::new(parse_columns(nullified)),
+            dropped: Arc::new(parse_columns(dropped)),
+            upstream_columns: Default::default(),
+        }
+    }
+
+    /// Returns the positions of the columns of `table` whose values should be replaced with NULL.
+    ///
+    /// Columns that are part of the table's key are never nullified, since rows must still be
+    /// identifiable by their key.
+    pub(crate) fn nullified_indices(&self, table: &Table) -> Vec<usize> {
+        let columns = match self.nullified.get(table.table_name()) {
+            Some(columns) => columns,
+            None => return vec![],
+        };
+
+        table
+            .columns()
+            .iter()
+            .enumerate()
+            .filter(|(i, name)| columns.contains(name.as_str()) && !table.key().contains(i))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns the positions, in the rows of the upstream table named `table`, of the columns
+    /// that are left out of ReadySet
+    pub(crate) fn dropped_indices(&self, table: &str) -> Vec<usize> {
+        self.upstream_columns
+            .lock()
+            .unwrap()
+            .get(table)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, column)| column.dropped)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns true if `column` of `table` is configured to be dropped
+    pub(crate) fn is_dropped(&self, table: &str, column: &str) -> bool {
+        self.dropped
+            .get(table)
+            .map_or(false, |columns| columns.contains(column))
+    }
+
+    /// Returns the names of the tables that have columns configured to be dropped
+    pub(crate) fn tables_with_dropped_columns(&self) -> Vec<String> {
+        self.dropped.keys().cloned().collect()
+    }
+
+    /// Records the columns of the upstream table named `table`, in the order they appear in the
+    /// upstream table. The columns that are dropped are those that are configured to be dropped
+    /// and are missing from the table's base table, if ReadySet has one.
+    ///
+    /// This is how the positions of the dropped columns are learned for tables whose schema was
+    /// installed before the replicator started.
+    pub(crate) async fn load_upstream_columns(
+        &self,
+        noria: &mut ControllerHandle,
+        table: &str,
+        columns: Vec<String>,
+    ) {
+        let dropped = match self.dropped.get(table) {
+            Some(dropped) if !columns.is_empty() => dropped,
+            _ => return,
+        };
+        let base_table = match noria.table(table).await {
+            Ok(base_table) => base_table,
+            // The table isn't replicated, so we'll never see its rows
+            Err(_) => return,
+        };
+
+        let columns = columns
+            .into_iter()
+            .map(|name| UpstreamColumn {
+                dropped: dropped.contains(&name)
+                    && !base_table.columns().iter().any(|c| c == name.as_str()),
+                name,
+            })
+            .collect();
+        self.upstream_columns
+            .lock()
+            .unwrap()
+            .insert(table.to_owned(), columns);
+    }
+
+    /// Removes the dropped columns from the `CREATE TABLE` and `ALTER TABLE` statements in
+    /// `changelist`, keeping track of the columns of the upstream tables they change
+    pub(crate) fn filter_changelist(&self, mut changelist: ChangeList) -> ChangeList {
+        if self.dropped.is_empty() {
+            return changelist;
+        }
+
+        changelist.changes = changelist
+            .changes
+            .into_iter()
+            .filter_map(|mut change| {
+                match &mut change {
+                    Change::CreateTable(create_table) => self.filter_create_table(create_table),
+                    Change::AlterTable(alter_table) => {
+                        let table = alter_table.table.name.as_str();
+                        if !self.filter_alter_table(table, &mut alter_table.definitions) {
+                            return None;
+                        }
+                    }
+                    Change::Drop { name, .. } => {
+                        self.upstream_columns.lock().unwrap().remove(name.as_str());
+                    }
+                    Change::CreateView(_) | Change::CreateCache(_) => {}
+                }
+                Some(change)
+            })
+            .collect();
+        changelist
+    }
+
+    fn filter_create_table(&self, create_table: &mut CreateTableStatement) {
+        let table = create_table.table.name.as_str();
+        let dropped = match self.dropped.get(table) {
+            Some(dropped) => dropped,
+            None => return,
+        };
+
+        let key_columns = create_table
+            .keys
+            .iter()
+            .flatten()
+            .flat_map(|key| match key {
+                TableKey::PrimaryKey { columns, .. }
+                | TableKey::UniqueKey { columns, .. }
+                | TableKey::FulltextKey { columns, .. }
+                | TableKey::Key { columns, .. }
+                | TableKey::ForeignKey { columns, .. } => columns.as_slice(),
+                TableKey::CheckConstraint { .. } => &[][..],
+            })
+            .map(|column| column.name.as_str())
+            .collect::<HashSet<_>>();
+
+        let columns = create_table
+            .fields
+            .iter()
+            .map(|field| {
+                let name = field.column.name.as_str();
+                let is_key = key_columns.contains(name)
+                    || field.constraints.iter().any(|c| {
+                        matches!(c, ColumnConstraint::PrimaryKey | ColumnConstraint::Unique)
+                    });
+                if is_key && dropped.contains(name) {
+                    warn!(%table, column = %name, "Not dropping column that is part of a key");
+                }
+                UpstreamColumn {
+                    name: name.to_owned(),
+                    dropped: dropped.contains(name) && !is_key,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        create_table.fields.retain(|field| {
+            !columns
+                .iter()
+                .any(|c| c.dropped && c.name == field.column.name.as_str())
+        });
+        self.upstream_columns
+            .lock()
+            .unwrap()
+            .insert(table.to_owned(), columns);
+    }
+
+    /// Removes the definitions that refer to dropped columns from an `ALTER TABLE` statement for
+    /// `table`. Returns false if no definitions are left.
+    fn filter_alter_table(&self, table: &str, definitions: &mut Vec<AlterTableDefinition>) -> bool {
+        let dropped = match self.dropped.get(table) {
+            Some(dropped) => dropped,
+            None => return true,
+        };
+        let mut upstream_columns = self.upstream_columns.lock().unwrap();
+        let columns = match upstream_columns.get_mut(table) {
+            Some(columns) => columns,
+            None => return true,
+        };
+        let is_dropped = |columns: &[UpstreamColumn], name: &str| {
+            columns.iter().any(|c| c.name == name && c.dropped)
+        };
+
+        definitions.retain(|definition| match definition {
+            AlterTableDefinition::AddColumn(spec) => {
+                let name = spec.column.name.as_str();
+                let is_key = spec
+                    .constraints
+                    .iter()
+                    .any(|c| matches!(c, ColumnConstraint::PrimaryKey | ColumnConstraint::Unique));
+                let column_dropped = dropped.contains(name) && !is_key;
+                columns.push(UpstreamColumn {
+                    name: name.to_owned(),
+                    dropped: column_dropped,
+                });
+                !column_dropped
+            }
+            AlterTableDefinition::AddKey(key) => match key {
+                TableKey::PrimaryKey { columns: key, .. }
+                | TableKey::UniqueKey { columns: key, .. }
+                | TableKey::FulltextKey { columns: key, .. }
+                | TableKey::Key { columns: key, .. }
+                | TableKey::ForeignKey { columns: key, .. } => {
+                    !key.iter().any(|c| is_dropped(columns, c.name.as_str()))
+                }
+                TableKey::CheckConstraint { .. } => true,
+            },
+            AlterTableDefinition::AlterColumn { name, .. } => !is_dropped(columns, name.as_str()),
+            AlterTableDefinition::DropColumn { name, .. } => {
+                let column_dropped = is_dropped(columns, name.as_str());
+                columns.retain(|c| c.name != name.as_str());
+                !column_dropped
+            }
+            AlterTableDefinition::ChangeColumn { name, spec } => {
+                rename_column(columns, name.as_str(), spec.column.name.as_str())
+            }
+            AlterTableDefinition::RenameColumn { name, new_name } => {
+                rename_column(columns, name.as_str(), new_name.as_str())
+            }
+        });
+
+        !definitions.is_empty()
+    }
+}
+
+/// Renames the upstream column `name` to `new_name`. Returns false if the column is dropped, in
+/// which case ReadySet doesn't know about it.
+fn rename_column(columns: &mut [UpstreamColumn], name: &str, new_name: &str) -> bool {
+    match columns.iter_mut().find(|c| c.name == name) {
+        Some(column) => {
+            column.name = new_name.to_owned();
+            !column.dropped
+        }
+        None => true,
+    }
+}
+
+/// Replace the values at each of the given positions in `row` with NULL
+pub(crate) fn nullify_row(row: &mut [DataType], indices: &[usize]) {
+    for &i in indices {
+        if let Some(value) = row.get_mut(i) {
+            *value = DataType::None;
+        }
+    }
+}
+
+/// Replace every value written to the columns at the given positions by `op` with NULL
+pub(crate) fn nullify_operation(op: &mut TableOperation, indices: &[usize]) {
+    match op {
+        TableOperation::Insert(row) | TableOperation::DeleteRow { row } => {
+            nullify_row(row, indices)
+        }
+        TableOperation::InsertOrUpdate { row, update } => {
+            nullify_row(row, indices);
+            nullify_update(update, indices);
+        }
+        TableOperation::Update { update, .. } => nullify_update(update, indices),
+        TableOperation::DeleteByKey { .. }
+        | TableOperation::SetReplicationOffset(_)
+        | TableOperation::SetSnapshotMode(_)
+        | TableOperation::Truncate => {}
+    }
+}
+
+fn nullify_update(update: &mut [Modification], indices: &[usize]) {
+    for &i in indices {
+        if let Some(modification @ (Modification::Set(_) | Modification::Apply(..))) =
+            update.get_mut(i)
+        {
+            *modification = Modification::Set(DataType::None);
+        }
+    }
+}
+
+/// Remove the values at each of the given positions, which must be in ascending order, from
+/// `values`
+fn drop_values<T>(values: &mut Vec<T>, indices: &[usize]) {
+    for &i in indices.iter().rev() {
+        if i < values.len() {
+            values.remove(i);
+        }
+    }
+}
+
+/// Remove the values of the columns at the given positions in the upstream table, which must be in
+/// ascending order, from the rows and updates in `op`
+pub(crate) fn drop_operation(op: &mut TableOperation, indices: &[usize]) {
+    match op {
+        TableOperation::Insert(row) | TableOperation::DeleteRow { row } => {
+            drop_values(row, indices)
+        }
+        TableOperation::InsertOrUpdate { row, update } => {
+            drop_values(row, indices);
+            drop_values(update, indices);
+        }
+        TableOperation::Update { update, .. } => drop_values(update, indices),
+        TableOperation::DeleteByKey { .. }
+        | TableOperation::SetReplicationOffset(_)
+        | TableOperation::SetSnapshotMode(_)
+        | TableOperation::Truncate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::Dialect;
+
+    use super::*;
+
+    #[test]
+    fn nullify_operations() {
+        let indices = [1];
+
+        let mut insert = TableOperation::Insert(vec![1.into(), "blob".into()]);
+        nullify_operation(&mut insert, &indices);
+        assert_eq!(
+            insert,
+            TableOperation::Insert(vec![1.into(), DataType::None])
+        );
+
+        let mut update = TableOperation::Update {
+            key: vec![1.into()],
+            update: vec![Modification::None, Modification::Set("blob".into())],
+        };
+        nullify_operation(&mut update, &indices);
+        assert_eq!(
+            update,
+            TableOperation::Update {
+                key: vec![1.into()],
+                update: vec![Modification::None, Modification::Set(DataType::None)],
+            }
+        );
+
+        let mut unchanged = TableOperation::Update {
+            key: vec![1.into()],
+            update: vec![Modification::Set(2.into()), Modification::None],
+        };
+        nullify_operation(&mut unchanged, &indices);
+        assert_eq!(
+            unchanged,
+            TableOperation::Update {
+                key: vec![1.into()],
+                update: vec![Modification::Set(2.into()), Modification::None],
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_invalid_columns() {
+        let filter = ColumnFilter::new(
+            &["t1.data".to_owned(), "invalid".to_owned(), "t2.".to_owned()],
+            &[],
+        );
+        assert_eq!(filter.nullified.len(), 1);
+        assert!(filter.nullified["t1"].contains("data"));
+    }
+
+    #[test]
+    fn drop_operations() {
+        let indices = [1, 3];
+
+        let mut insert =
+            TableOperation::Insert(vec![1.into(), "blob".into(), 2.into(), "blob".into()]);
+        drop_operation(&mut insert, &indices);
+        assert_eq!(insert, TableOperation::Insert(vec![1.into(), 2.into()]));
+
+        let mut update = TableOperation::Update {
+            key: vec![1.into()],
+            update: vec![
+                Modification::None,
+                Modification::Set("blob".into()),
+                Modification::Set(3.into()),
+                Modification::None,
+            ],
+        };
+        drop_operation(&mut update, &indices);
+        assert_eq!(
+            update,
+            TableOperation::Update {
+                key: vec![1.into()],
+                update: vec![Modification::None, Modification::Set(3.into())],
+            }
+        );
+    }
+
+    fn changelist(ddl: &str) -> ChangeList {
+        ChangeList::from_str_with_dialect(ddl, Dialect::MySQL).unwrap()
+    }
+
+    #[test]
+    fn drops_columns_from_ddl() {
+        let filter = ColumnFilter::new(&[], &["t.data".to_owned(), "t.id".to_owned()]);
+
+        let filtered = filter.filter_changelist(changelist(
+            "CREATE TABLE t (id INT PRIMARY KEY, data BLOB, name TEXT);",
+        ));
+        match &filtered.changes[..] {
+            [Change::CreateTable(create_table)] => assert_eq!(
+                create_table
+                    .fields
+                    .iter()
+                    .map(|f| f.column.name.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["id", "name"]
+            ),
+            changes => panic!("unexpected changes: {:?}", changes),
+        }
+        assert_eq!(filter.dropped_indices("t"), vec![1]);
+
+        // Changes to dropped columns are left out, but the positions of the upstream columns are
+        // still tracked
+        let filtered = filter.filter_changelist(changelist(
+            "ALTER TABLE t CHANGE COLUMN data payload BLOB, DROP COLUMN name, \
+             ADD COLUMN data TEXT;",
+        ));
+        match &filtered.changes[..] {
+            [Change::AlterTable(alter_table)] => assert!(matches!(
+                &alter_table.definitions[..],
+                [AlterTableDefinition::DropColumn { .. }]
+            )),
+            changes => panic!("unexpected changes: {:?}", changes),
+        }
+        assert_eq!(filter.dropped_indices("t"), vec![1, 2]);
+
+        let filtered = filter.filter_changelist(changelist("ALTER TABLE t DROP COLUMN data;"));
+        assert!(filtered.changes.is_empty());
+        assert_eq!(filter.dropped_indices("t"), vec![1]);
+    }
+
+    #[test]
+    fn ignores_tables_without_dropped_columns() {
+        let filter = ColumnFilter::new(&[], &["t.data".to_owned()]);
+        let filtered = filter.filter_changelist(changelist("CREATE TABLE t2 (id INT, data BLOB);"));
+        assert!(matches!(
+            &filtered.changes[..],
+            [Change::CreateTable(create_table)] if create_table.fields.len() == 2
+        ));
+        assert!(filter.dropped_indices("t2").is_empty());
+    }
+}
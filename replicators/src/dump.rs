@@ -0,0 +1,1052 @@
+//! Loading the initial snapshot of the upstream database from a dump file, rather than by querying
+//! the upstream database itself, for upstream databases that can't tolerate the read load of a
+//! snapshot.
+//!
+//! Plain-format dumps written by `mysqldump` and `pg_dump` are supported. The `CREATE TABLE` and
+//! `CREATE VIEW` statements in the dump are installed in ReadySet, and the rows of each table are
+//! copied from its `INSERT` statements (`mysqldump`) or `COPY ... FROM stdin` data sections
+//! (`pg_dump`). Every other statement in the dump is ignored.
+//!
+//! The dump must be consistent with the position replication starts from once it's loaded:
+//!
+//! * For MySQL, the dump must be taken with `mysqldump --single-transaction --source-data=2` (or
+//!   `--master-data=2` before MySQL 8.0.26), so that it records the binlog position it was taken
+//!   at. Replication starts from that position.
+//! * For PostgreSQL, the `readyset` publication and logical replication slot (using the `pgoutput`
+//!   plugin) must be created before the dump is taken, and the dump must be taken from the snapshot
+//!   exported when the slot was created, with `pg_dump --snapshot`. Replication starts from that
+//!   slot. Custom-format archives (taken with `pg_dump --format=custom`) are converted to a
+//!   plain-format dump as they're read, by running `pg_restore --file=-` on them, so `pg_restore`
+//!   must be installed to load them.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Display;
+use std::mem;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use nom_sql::{
+    AlterTableDefinition, CreateTableStatement, CreateViewStatement, Dialect, DialectKind,
+    SqlQuery, SqlType, TableKey,
+};
+use readyset::recipe::changelist::{Change, ChangeList};
+use readyset::replication::{ReplicationOffset, ReplicationOffsets};
+use readyset::{ControllerHandle, ReadySetError, ReadySetResult, Table};
+use readyset_data::DataType;
+use tokio::fs::File;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tracing::{debug, error, info, warn};
+
+use crate::auto_increment::SnapshotAutoIncrement;
+use crate::column_filter::{nullify_row, ColumnFilter};
+use crate::mysql_connector::BinlogPosition;
+use crate::postgres_connector::PostgresPosition;
+
+/// How many rows to buffer before writing them to a base table
+const BATCH_SIZE: usize = 1000;
+
+/// The bytes a PostgreSQL custom-format archive starts with
+const CUSTOM_FORMAT_MAGIC: &[u8] = b"PGDMP";
+
+/// The prefixes of the statements in a dump that make up the schema of the database
+const SCHEMA_STATEMENTS: [&str; 4] = [
+    "CREATE TABLE",
+    "CREATE VIEW",
+    "CREATE ALGORITHM",
+    "ALTER TABLE",
+];
+
+fn dump_error<E: Display>(err: E) -> ReadySetError {
+    ReadySetError::ReplicationFailed(format!("Invalid snapshot dump: {}", err))
+}
+
+/// A quoted section of a statement, within which semicolons don't end the statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Quote {
+    Single,
+    Double,
+    Backtick,
+    /// A PostgreSQL dollar-quoted string, with its tag (including the dollar signs)
+    Dollar(String),
+}
+
+/// A statement, or a row of a `COPY` data section, read from a dump
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum DumpItem {
+    /// A statement, without its terminating semicolon
+    Statement(String),
+    /// A row of the data section following the last `COPY ... FROM stdin` statement, with `None`
+    /// for NULL values
+    CopyRow(Vec<Option<String>>),
+}
+
+/// Reads the statements and `COPY` data rows of a plain-format dump, one at a time
+pub(crate) struct DumpReader<R> {
+    reader: R,
+    dialect: Dialect,
+    /// Whether the start of the dump has been checked for a format we can't read
+    checked_format: bool,
+    /// The part of the current statement read so far
+    statement: String,
+    /// The quoted section of the current statement that the end of the last line was in
+    quote: Option<Quote>,
+    /// Statements that have been read but not yet returned
+    pending: VecDeque<String>,
+    /// Whether we're in the data section following a `COPY ... FROM stdin` statement
+    in_copy: bool,
+    /// The process converting the dump to plain format, if it's being read from one, which is
+    /// checked for failure once its output has been read
+    process: Option<Child>,
+}
+
+impl<R> DumpReader<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    pub(crate) fn new(reader: R, dialect: Dialect) -> Self {
+        Self {
+            reader,
+            dialect,
+            checked_format: false,
+            statement: String::new(),
+            quote: None,
+            pending: VecDeque::new(),
+            in_copy: false,
+            process: None,
+        }
+    }
+
+    /// Read the dump from the output of `process`, which is converting it to plain format
+    fn with_process(mut self, process: Child) -> Self {
+        self.process = Some(process);
+        self
+    }
+
+    /// Returns an error if the dump is in a format other than plain SQL
+    async fn check_format(&mut self) -> ReadySetResult<()> {
+        let start = self.reader.fill_buf().await?;
+        if start.starts_with(CUSTOM_FORMAT_MAGIC) {
+            return Err(dump_error(
+                "custom-format archives must be converted to a plain-format dump with \
+                 `pg_restore --file=-`",
+            ));
+        }
+        if start.starts_with(&[0x1f, 0x8b]) {
+            return Err(dump_error("compressed dumps aren't supported"));
+        }
+        self.checked_format = true;
+        Ok(())
+    }
+
+    /// Returns the next statement or `COPY` data row in the dump, or `None` at the end of the dump
+    pub(crate) async fn next(&mut self) -> ReadySetResult<Option<DumpItem>> {
+        if !self.checked_format {
+            self.check_format().await?;
+        }
+
+        let mut line = String::new();
+        loop {
+            if let Some(statement) = self.pending.pop_front() {
+                if copy_target(&statement).is_some() {
+                    self.in_copy = true;
+                }
+                return Ok(Some(DumpItem::Statement(statement)));
+            }
+
+            line.clear();
+            if self.reader.read_line(&mut line).await? == 0 {
+                if let Some(mut process) = self.process.take() {
+                    let status = process.wait().await?;
+                    if !status.success() {
+                        return Err(dump_error(format!(
+                            "converting the dump to plain format failed: {}",
+                            status
+                        )));
+                    }
+                }
+                if self.in_copy {
+                    return Err(dump_error("unterminated COPY data section"));
+                }
+                let statement = mem::take(&mut self.statement);
+                let statement = statement.trim();
+                return Ok((!statement.is_empty()).then(|| DumpItem::Statement(statement.into())));
+            }
+
+            if self.in_copy {
+                let row = line.trim_end_matches(&['\n', '\r'][..]);
+                if row == "\\." {
+                    self.in_copy = false;
+                    continue;
+                }
+                return Ok(Some(DumpItem::CopyRow(parse_copy_row(row)?)));
+            }
+
+            if self.statement.trim().is_empty() {
+                if let Some(comment) = line.trim_start().strip_prefix("--") {
+                    // `--source-data=2` writes the binlog position as a commented-out statement
+                    let comment = comment.trim().trim_end_matches(';');
+                    if binlog_position(comment).is_some() {
+                        self.pending.push_back(comment.to_owned());
+                    }
+                    continue;
+                }
+            }
+
+            self.split_statements(&line);
+        }
+    }
+
+    /// Adds `line` to the current statement, moving every statement it completes to `pending`
+    fn split_statements(&mut self, line: &str) {
+        let mut chars = line.char_indices();
+        let mut start = 0;
+        while let Some((i, c)) = chars.next() {
+            match &self.quote {
+                None => match c {
+                    '\'' => self.quote = Some(Quote::Single),
+                    '"' => self.quote = Some(Quote::Double),
+                    '`' => self.quote = Some(Quote::Backtick),
+                    '$' if self.dialect.kind() == DialectKind::PostgreSQL => {
+                        if let Some(tag) = dollar_quote_tag(&line[i..]) {
+                            chars.nth(tag.len() - 2);
+                            self.quote = Some(Quote::Dollar(tag.to_owned()));
+                        }
+                    }
+                    ';' => {
+                        self.statement.push_str(&line[start..i]);
+                        let statement = mem::take(&mut self.statement);
+                        let statement = statement.trim();
+                        if !statement.is_empty() {
+                            self.pending.push_back(statement.to_owned());
+                        }
+                        start = i + 1;
+                    }
+                    _ => {}
+                },
+                // MySQL strings can contain backslash escapes, including of their own quotes
+                Some(Quote::Single | Quote::Double) if c == '\\' => {
+                    if self.dialect.kind() == DialectKind::MySQL {
+                        chars.next();
+                    }
+                }
+                Some(Quote::Single) if c == '\'' => self.quote = None,
+                Some(Quote::Double) if c == '"' => self.quote = None,
+                Some(Quote::Backtick) if c == '`' => self.quote = None,
+                Some(Quote::Dollar(tag)) if line[i..].starts_with(tag.as_str()) => {
+                    chars.nth(tag.len() - 2);
+                    self.quote = None;
+                }
+                Some(_) => {}
+            }
+        }
+        self.statement.push_str(&line[start..]);
+    }
+}
+
+/// If `s` starts with a PostgreSQL dollar quote (`$$` or `$tag$`), returns the quote
+fn dollar_quote_tag(s: &str) -> Option<&str> {
+    let end = s[1..].find('$')? + 1;
+    let tag = &s[1..end];
+    let valid = tag.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !tag.starts_with(|c: char| c.is_ascii_digit());
+    valid.then(|| &s[..=end])
+}
+
+/// Strips `prefix` from the start of `s`, ignoring ASCII case
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    (s.len() >= prefix.len()
+        && s.is_char_boundary(prefix.len())
+        && s[..prefix.len()].eq_ignore_ascii_case(prefix))
+    .then(|| &s[prefix.len()..])
+}
+
+/// Strips `suffix` from the end of `s`, ignoring ASCII case
+fn strip_suffix_ignore_case<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    let start = s.len().checked_sub(suffix.len())?;
+    (s.is_char_boundary(start) && s[start..].eq_ignore_ascii_case(suffix)).then(|| &s[..start])
+}
+
+/// Removes the double quotes, if any, from a PostgreSQL identifier
+fn unquote_identifier(ident: &str) -> String {
+    let ident = ident.trim();
+    match ident
+        .strip_prefix('"')
+        .and_then(|ident| ident.strip_suffix('"'))
+    {
+        Some(ident) => ident.replace("\"\"", "\""),
+        None => ident.to_owned(),
+    }
+}
+
+/// If `statement` is a `COPY <table> [(<columns>)] FROM stdin` statement, returns the name of the
+/// table (without its schema) and the columns, if given, whose values are in its data section
+fn copy_target(statement: &str) -> Option<(String, Option<Vec<String>>)> {
+    let target = strip_prefix_ignore_case(statement, "COPY ")?;
+    let target = strip_suffix_ignore_case(target.trim_end(), " FROM stdin")?.trim();
+    let (table, columns) = match target.split_once('(') {
+        Some((table, columns)) => {
+            let columns = columns.trim_end().strip_suffix(')')?;
+            (
+                table,
+                Some(columns.split(',').map(unquote_identifier).collect()),
+            )
+        }
+        None => (target, None),
+    };
+    let table = table.trim();
+    let table = table.rsplit_once('.').map_or(table, |(_, table)| table);
+    Some((unquote_identifier(table), columns))
+}
+
+/// If `statement` is an `INSERT [IGNORE] INTO <table> ...` statement, returns the name of the table
+/// (without its schema or database), without parsing the rest of the statement
+fn insert_target(statement: &str) -> Option<String> {
+    let rest = strip_prefix_ignore_case(statement, "INSERT ")?.trim_start();
+    let rest = strip_prefix_ignore_case(rest, "IGNORE ").map_or(rest, str::trim_start);
+    let mut rest = strip_prefix_ignore_case(rest, "INTO ")?.trim_start();
+    loop {
+        let (ident, tail) = match rest.chars().next()? {
+            quote @ ('`' | '"') => {
+                // Quotes inside a quoted identifier are escaped by doubling them
+                let mut end = 1;
+                loop {
+                    end += rest[end..].find(quote)?;
+                    if !rest[end + 1..].starts_with(quote) {
+                        break;
+                    }
+                    end += 2;
+                }
+                let doubled = format!("{0}{0}", quote);
+                (
+                    rest[1..end].replace(&doubled, &quote.to_string()),
+                    &rest[end + 1..],
+                )
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == '(' || c == '.')
+                    .unwrap_or(rest.len());
+                if end == 0 {
+                    return None;
+                }
+                (rest[..end].to_owned(), &rest[end..])
+            }
+        };
+        match tail.strip_prefix('.') {
+            Some(tail) => rest = tail,
+            None => return Some(ident),
+        }
+    }
+}
+
+/// Parses a row of a `COPY` data section in PostgreSQL's text format, whose values are separated
+/// by tabs, with `\N` for NULL and backslash escapes for special characters
+fn parse_copy_row(row: &str) -> ReadySetResult<Vec<Option<String>>> {
+    row.split('\t')
+        .map(|field| {
+            if field == "\\N" {
+                return Ok(None);
+            }
+            if !field.contains('\\') {
+                return Ok(Some(field.to_owned()));
+            }
+
+            let mut bytes = Vec::with_capacity(field.len());
+            let mut rest = field.as_bytes();
+            while let Some((&b, tail)) = rest.split_first() {
+                rest = tail;
+                if b != b'\\' {
+                    bytes.push(b);
+                    continue;
+                }
+                let (&escaped, tail) = match rest.split_first() {
+                    Some(escaped) => escaped,
+                    None => break,
+                };
+                rest = tail;
+                match escaped {
+                    b'b' => bytes.push(0x08),
+                    b'f' => bytes.push(0x0c),
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'v' => bytes.push(0x0b),
+                    b'0'..=b'7' => {
+                        let len = rest
+                            .iter()
+                            .take(2)
+                            .take_while(|b| (b'0'..=b'7').contains(*b))
+                            .count();
+                        let value = rest[..len]
+                            .iter()
+                            .fold((escaped - b'0') as u32, |value, digit| {
+                                value * 8 + (digit - b'0') as u32
+                            });
+                        bytes.push(value as u8);
+                        rest = &rest[len..];
+                    }
+                    b'x' if rest.first().map_or(false, u8::is_ascii_hexdigit) => {
+                        let len = rest
+                            .iter()
+                            .take(2)
+                            .take_while(|b| b.is_ascii_hexdigit())
+                            .count();
+                        let digits = std::str::from_utf8(&rest[..len]).map_err(dump_error)?;
+                        bytes.push(u8::from_str_radix(digits, 16).map_err(dump_error)?);
+                        rest = &rest[len..];
+                    }
+                    other => bytes.push(other),
+                }
+            }
+            String::from_utf8(bytes).map(Some).map_err(dump_error)
+        })
+        .collect()
+}
+
+/// If `statement` is a `CHANGE MASTER TO` or `CHANGE REPLICATION SOURCE TO` statement, returns the
+/// binlog position it sets
+fn binlog_position(statement: &str) -> Option<BinlogPosition> {
+    let options = strip_prefix_ignore_case(statement, "CHANGE MASTER TO")
+        .or_else(|| strip_prefix_ignore_case(statement, "CHANGE REPLICATION SOURCE TO"))?;
+
+    let mut binlog_file = None;
+    let mut position = None;
+    for option in options.split(',') {
+        let (name, value) = option.split_once('=')?;
+        let value = value.trim();
+        match name.trim().to_ascii_uppercase().as_str() {
+            "MASTER_LOG_FILE" | "SOURCE_LOG_FILE" => {
+                binlog_file = Some(value.trim_matches('\'').to_owned())
+            }
+            "MASTER_LOG_POS" | "SOURCE_LOG_POS" => position = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    Some(BinlogPosition {
+        binlog_file: binlog_file?,
+        position: position?,
+    })
+}
+
+/// Removes the MySQL version comments that `mysqldump` wraps some statements in, such as
+/// `/*!50001 CREATE ALGORITHM=UNDEFINED */ /*!50001 VIEW ... */`, leaving their contents
+fn strip_version_comments(statement: &str) -> Cow<'_, str> {
+    if !statement.starts_with("/*!") {
+        return Cow::Borrowed(statement);
+    }
+    let parts = statement
+        .split("/*!")
+        .map(|part| {
+            let part = part.trim_start_matches(|c: char| c.is_ascii_digit()).trim();
+            part.strip_suffix("*/").unwrap_or(part).trim()
+        })
+        .filter(|part| !part.is_empty());
+    Cow::Owned(itertools::join(parts, " "))
+}
+
+/// The schema of the database in a dump
+#[derive(Debug, Default)]
+struct DumpSchema {
+    tables: Vec<CreateTableStatement>,
+    views: Vec<CreateViewStatement>,
+    /// The binlog position the dump was taken at, if it's a `mysqldump` dump which records it
+    binlog_position: Option<BinlogPosition>,
+}
+
+/// Converts a value of a `COPY` data row into a value of a column of type `ty`
+fn copy_value(value: Option<String>, ty: Option<&SqlType>) -> ReadySetResult<DataType> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(DataType::None),
+    };
+    match ty {
+        Some(SqlType::Bool) => match value.as_str() {
+            "t" => Ok(true.into()),
+            "f" => Ok(false.into()),
+            _ => Err(dump_error(format!("invalid boolean value {}", value))),
+        },
+        Some(SqlType::ByteArray) => match value.strip_prefix("\\x") {
+            Some(hex) => Ok(DataType::ByteArray(
+                hex::decode(hex).map_err(dump_error)?.into(),
+            )),
+            None => Ok(DataType::ByteArray(value.into_bytes().into())),
+        },
+        Some(ty) => DataType::from(value).coerce_to(ty),
+        None => Ok(DataType::from(value)),
+    }
+}
+
+/// Converts a value of an `INSERT` statement into a value of a column of type `ty`
+fn insert_value(value: DataType, ty: Option<&SqlType>) -> ReadySetResult<DataType> {
+    match ty {
+        // Values such as dates and times are written as strings
+        Some(ty) if value.is_string() => value.coerce_to(ty),
+        _ => Ok(value),
+    }
+}
+
+/// Returns the position in `table` of each of `columns`, or `None` for columns that are dropped
+fn column_positions(
+    table: &Table,
+    columns: &[String],
+    column_filter: &ColumnFilter,
+) -> ReadySetResult<Vec<Option<usize>>> {
+    columns
+        .iter()
+        .map(
+            |column| match table.columns().iter().position(|c| c == column.as_str()) {
+                Some(position) => Ok(Some(position)),
+                None if column_filter.is_dropped(table.table_name(), column) => Ok(None),
+                None => Err(dump_error(format!(
+                    "unknown column {} in table {}",
+                    column,
+                    table.table_name()
+                ))),
+            },
+        )
+        .collect()
+}
+
+/// Copies the rows of a single table in a dump into its base table
+struct TableLoader {
+    table: Table,
+    /// The type of each of the table's columns
+    types: Vec<SqlType>,
+    /// The position in the base table of each of the columns of the table in the dump, or `None`
+    /// for columns that are dropped
+    positions: Vec<Option<usize>>,
+    nullified: Vec<usize>,
+    auto_increment: SnapshotAutoIncrement,
+    rows: Vec<Vec<DataType>>,
+    rows_copied: usize,
+}
+
+impl TableLoader {
+    /// Creates a loader for the base table `table`, whose columns in the dump are `columns`
+    async fn new(
+        mut table: Table,
+        columns: &[String],
+        column_filter: &ColumnFilter,
+    ) -> ReadySetResult<Self> {
+        let types = table
+            .schema()
+            .map(|schema| schema.fields.iter().map(|f| f.sql_type.clone()).collect())
+            .unwrap_or_default();
+        let positions = column_positions(&table, columns, column_filter)?;
+        let nullified = column_filter.nullified_indices(&table);
+        let auto_increment = SnapshotAutoIncrement::new(&table);
+        table.set_snapshot_mode(true).await?;
+        Ok(Self {
+            table,
+            types,
+            positions,
+            nullified,
+            auto_increment,
+            rows: Vec::with_capacity(BATCH_SIZE),
+            rows_copied: 0,
+        })
+    }
+
+    /// Adds a row to the table, whose values are given by `convert` for each of the columns at
+    /// `positions`, or for each of the table's columns in the dump in order if `positions` is
+    /// `None`
+    async fn push<V, F>(
+        &mut self,
+        positions: Option<&[Option<usize>]>,
+        values: Vec<V>,
+        convert: F,
+    ) -> ReadySetResult<()>
+    where
+        F: Fn(V, Option<&SqlType>) -> ReadySetResult<DataType>,
+    {
+        let positions = positions.unwrap_or(&self.positions[..]);
+        if values.len() != positions.len() {
+            return Err(dump_error(format!(
+                "wrong number of values in row for table {}",
+                self.table.table_name()
+            )));
+        }
+        let mut row = vec![DataType::None; self.table.columns().len()];
+        for (value, position) in values.into_iter().zip(positions) {
+            if let Some(column) = *position {
+                row[column] = convert(value, self.types.get(column))?;
+            }
+        }
+
+        nullify_row(&mut row, &self.nullified);
+        self.auto_increment.observe(&row);
+        self.rows.push(row);
+        self.rows_copied += 1;
+
+        if self.rows.len() == BATCH_SIZE {
+            let rows = mem::replace(&mut self.rows, Vec::with_capacity(BATCH_SIZE));
+            self.table.insert_many(rows).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes any buffered rows, and marks the table as snapshotted at `offset`
+    async fn finish(
+        mut self,
+        noria: &mut ControllerHandle,
+        offset: &ReplicationOffset,
+    ) -> ReadySetResult<()> {
+        if !self.rows.is_empty() {
+            self.table.insert_many(mem::take(&mut self.rows)).await?;
+        }
+
+        if let Some(max) = self.auto_increment.max() {
+            noria
+                .record_auto_increments(HashMap::from([(self.table.table_name().to_owned(), max)]))
+                .await?;
+        }
+
+        self.table.set_replication_offset(offset.clone()).await?;
+        self.table.set_snapshot_mode(false).await?;
+        info!(
+            table = %self.table.table_name(),
+            rows_replicated = %self.rows_copied,
+            "Replication finished"
+        );
+        Ok(())
+    }
+}
+
+/// Loads the initial snapshot of the upstream database from a dump file. See the [module
+/// documentation](self) for how the dump must be taken.
+pub(crate) struct DumpSnapshot {
+    path: PathBuf,
+    dialect: Dialect,
+    column_filter: ColumnFilter,
+}
+
+impl DumpSnapshot {
+    pub(crate) fn new(path: PathBuf, dialect: Dialect, column_filter: ColumnFilter) -> Self {
+        Self {
+            path,
+            dialect,
+            column_filter,
+        }
+    }
+
+    async fn open(&self) -> ReadySetResult<DumpReader<Box<dyn AsyncBufRead + Send + Unpin>>> {
+        let file = File::open(&self.path)
+            .await
+            .map_err(|e| dump_error(format!("{}: {}", self.path.display(), e)))?;
+        let mut file = BufReader::new(file);
+        if !file.fill_buf().await?.starts_with(CUSTOM_FORMAT_MAGIC) {
+            return Ok(DumpReader::new(Box::new(file), self.dialect));
+        }
+
+        let mut process = Command::new("pg_restore")
+            .arg("--file=-")
+            .arg(&self.path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                dump_error(format!(
+                    "running `pg_restore` to convert the custom-format archive failed: {}",
+                    e
+                ))
+            })?;
+        let output = process
+            .stdout
+            .take()
+            .ok_or_else(|| dump_error("`pg_restore` has no output to read the dump from"))?;
+        Ok(DumpReader::new(Box::new(BufReader::new(output)), self.dialect).with_process(process))
+    }
+
+    /// Reads the schema of the database from the dump, skipping over its rows
+    async fn load_schema(&self) -> ReadySetResult<DumpSchema> {
+        let mut schema = DumpSchema::default();
+        let mut reader = self.open().await?;
+        while let Some(item) = reader.next().await? {
+            let statement = match item {
+                DumpItem::Statement(statement) => statement,
+                DumpItem::CopyRow(_) => continue,
+            };
+            let statement = strip_version_comments(&statement);
+            let statement = statement.as_ref();
+
+            if let Some(position) = binlog_position(statement) {
+                schema.binlog_position = Some(position);
+                continue;
+            }
+            if !SCHEMA_STATEMENTS
+                .iter()
+                .any(|prefix| strip_prefix_ignore_case(statement, prefix).is_some())
+            {
+                continue;
+            }
+
+            // `pg_dump` adds keys to tables after their rows, with `ALTER TABLE ONLY`
+            let statement = match strip_prefix_ignore_case(statement, "ALTER TABLE ONLY ") {
+                Some(rest) => format!("ALTER TABLE {}", rest),
+                None => statement.to_owned(),
+            };
+            match nom_sql::parse_query(self.dialect, &statement) {
+                Ok(SqlQuery::CreateTable(mut create_table)) => {
+                    create_table.table.schema = None;
+                    schema.tables.push(create_table);
+                }
+                Ok(SqlQuery::CreateView(create_view)) => {
+                    // `mysqldump` creates a placeholder for each view before replacing it with the
+                    // real view at the end of the dump
+                    schema.views.retain(|view| view.name != create_view.name);
+                    schema.views.push(create_view);
+                }
+                Ok(SqlQuery::AlterTable(alter_table)) => {
+                    let table = match schema
+                        .tables
+                        .iter_mut()
+                        .find(|table| table.table.name == alter_table.table.name)
+                    {
+                        Some(table) => table,
+                        None => continue,
+                    };
+                    for definition in alter_table.definitions {
+                        let mut key = match definition {
+                            AlterTableDefinition::AddKey(key) => key,
+                            _ => continue,
+                        };
+                        match &mut key {
+                            TableKey::PrimaryKey { columns, .. }
+                            | TableKey::UniqueKey { columns, .. } => {
+                                // Match the columns of keys given in the `CREATE TABLE` statement
+                                for column in columns {
+                                    column.table = Some(table.table.name.clone());
+                                }
+                            }
+                            _ => continue,
+                        }
+                        table.keys.get_or_insert_with(Vec::new).push(key);
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    error!(%statement, %error, "Error parsing statement, it will not be used");
+                }
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Installs the schema in the dump in ReadySet, if it hasn't been already, and copies the rows
+    /// in the dump of every table that hasn't already been snapshotted
+    pub(crate) async fn snapshot_to_noria(
+        &self,
+        noria: &mut ControllerHandle,
+        replication_offsets: &ReplicationOffsets,
+    ) -> ReadySetResult<()> {
+        info!(path = %self.path.display(), "Loading snapshot from dump");
+        let schema = self.load_schema().await?;
+        let offset: ReplicationOffset = match self.dialect.kind() {
+            DialectKind::MySQL => schema
+                .binlog_position
+                .ok_or_else(|| {
+                    dump_error(
+                        "the binlog position of the dump is missing, take the dump with \
+                         `mysqldump --source-data=2`",
+                    )
+                })?
+                .try_into()?,
+            DialectKind::PostgreSQL => PostgresPosition::default().into(),
+        };
+
+        if replication_offsets.schema.is_none() {
+            for create_table in &schema.tables {
+                debug!(%create_table, "Extending recipe");
+                let changelist = self.column_filter.filter_changelist(ChangeList {
+                    changes: vec![Change::CreateTable(create_table.clone())],
+                });
+                if let Err(err) = noria.extend_recipe_no_leader_ready(changelist).await {
+                    error!(%err, "Error extending CREATE TABLE, table will not be used");
+                }
+            }
+            for create_view in &schema.views {
+                debug!(%create_view, "Extending recipe");
+                let changelist = ChangeList {
+                    changes: vec![Change::CreateView(create_view.clone())],
+                };
+                if let Err(err) = noria.extend_recipe_no_leader_ready(changelist).await {
+                    error!(
+                        view = %create_view.name,
+                        %err,
+                        "Error extending CREATE VIEW, view will not be used"
+                    );
+                }
+            }
+            noria.set_schema_replication_offset(Some(&offset)).await?;
+        } else {
+            info!("Not loading recipe as replication offset already exists for schema");
+        }
+
+        let mut loaders = HashMap::new();
+        for create_table in &schema.tables {
+            let name = create_table.table.name.as_str();
+            if replication_offsets.has_table(name) {
+                info!(
+                    table_name = %name,
+                    "Replication offset already exists for table, skipping snapshot"
+                );
+                continue;
+            }
+            match noria.table(name).await {
+                Ok(table) => {
+                    let columns = create_table
+                        .fields
+                        .iter()
+                        .map(|field| field.column.name.to_string())
+                        .collect::<Vec<_>>();
+                    let loader = TableLoader::new(table, &columns, &self.column_filter).await?;
+                    loaders.insert(name.to_owned(), loader);
+                }
+                Err(error) => {
+                    warn!(table_name = %name, %error, "Table not found, skipping snapshot")
+                }
+            }
+        }
+
+        // The table and the positions of the columns that the rows of the current `COPY` data
+        // section are for, or `None` if the rows are to be discarded
+        let mut copying = None;
+        let mut reader = self.open().await?;
+        while let Some(item) = reader.next().await? {
+            match item {
+                DumpItem::Statement(statement) => {
+                    copying = None;
+                    if let Some((table, columns)) = copy_target(&statement) {
+                        if let Some(loader) = loaders.get(&table) {
+                            let positions = columns
+                                .map(|columns| {
+                                    column_positions(&loader.table, &columns, &self.column_filter)
+                                })
+                                .transpose()?;
+                            copying = Some((table, positions));
+                        }
+                        continue;
+                    }
+                    if strip_prefix_ignore_case(&statement, "INSERT").is_none() {
+                        continue;
+                    }
+
+                    // Only parse the inserts into tables we're loading, so that an insert we can't
+                    // parse into a table we skipped doesn't fail the whole snapshot
+                    let loader = match insert_target(&statement) {
+                        Some(table) => match loaders.get_mut(&table) {
+                            Some(loader) => loader,
+                            None => continue,
+                        },
+                        None => return Err(dump_error(format!("could not parse {}", statement))),
+                    };
+                    let insert = match nom_sql::parse_query(self.dialect, &statement) {
+                        Ok(SqlQuery::Insert(insert)) => insert,
+                        _ => return Err(dump_error(format!("could not parse {}", statement))),
+                    };
+                    let positions = insert
+                        .fields
+                        .map(|fields| {
+                            let columns = fields
+                                .into_iter()
+                                .map(|field| field.name.to_string())
+                                .collect::<Vec<_>>();
+                            column_positions(&loader.table, &columns, &self.column_filter)
+                        })
+                        .transpose()?;
+                    for row in insert.data {
+                        let values = row
+                            .into_iter()
+                            .map(DataType::try_from)
+                            .collect::<ReadySetResult<Vec<_>>>()?;
+                        loader
+                            .push(positions.as_deref(), values, insert_value)
+                            .await?;
+                    }
+                }
+                DumpItem::CopyRow(values) => {
+                    if let Some((table, positions)) = &copying {
+                        if let Some(loader) = loaders.get_mut(table) {
+                            loader
+                                .push(positions.as_deref(), values, copy_value)
+                                .await?;
+                        }
+                    }
+                }
+            }
+        }
+
+        for loader in loaders.into_values() {
+            loader.finish(noria, &offset).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read_all(dump: &str, dialect: Dialect) -> Vec<DumpItem> {
+        let mut reader = DumpReader::new(dump.as_bytes(), dialect);
+        let mut items = vec![];
+        while let Some(item) = reader.next().await.unwrap() {
+            items.push(item);
+        }
+        items
+    }
+
+    fn statement(s: &str) -> DumpItem {
+        DumpItem::Statement(s.to_owned())
+    }
+
+    #[tokio::test]
+    async fn read_mysqldump() {
+        let dump = "-- MySQL dump 10.13\n\
+            /*!40101 SET NAMES utf8mb4 */;\n\
+            \n\
+            -- CHANGE MASTER TO MASTER_LOG_FILE='binlog.000003', MASTER_LOG_POS=157;\n\
+            \n\
+            CREATE TABLE `t` (\n  `id` int NOT NULL,\n  `s` text,\n  PRIMARY KEY (`id`)\n) \
+            ENGINE=InnoDB;\n\
+            INSERT INTO `t` VALUES (1,'a;b'),(2,'it\\'s;');\n";
+        let items = read_all(dump, Dialect::MySQL).await;
+        assert_eq!(
+            items,
+            vec![
+                statement("/*!40101 SET NAMES utf8mb4 */"),
+                statement("CHANGE MASTER TO MASTER_LOG_FILE='binlog.000003', MASTER_LOG_POS=157"),
+                statement(
+                    "CREATE TABLE `t` (\n  `id` int NOT NULL,\n  `s` text,\n  PRIMARY KEY \
+                     (`id`)\n) ENGINE=InnoDB"
+                ),
+                statement("INSERT INTO `t` VALUES (1,'a;b'),(2,'it\\'s;')"),
+            ]
+        );
+        assert_eq!(
+            binlog_position("CHANGE MASTER TO MASTER_LOG_FILE='binlog.000003', MASTER_LOG_POS=157"),
+            Some(BinlogPosition {
+                binlog_file: "binlog.000003".to_owned(),
+                position: 157,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn read_pg_dump() {
+        let dump = "SET client_encoding = 'UTF8';\n\
+            CREATE FUNCTION public.f() RETURNS integer AS $_$ SELECT 1; $_$ LANGUAGE sql;\n\
+            CREATE TABLE public.t (\n    id integer NOT NULL,\n    s text\n);\n\
+            COPY public.t (id, s) FROM stdin;\n\
+            1\tfirst\\tline\\nsecond\n\
+            2\t\\N\n\
+            \\.\n\
+            ALTER TABLE ONLY public.t ADD CONSTRAINT t_pkey PRIMARY KEY (id);\n";
+        let items = read_all(dump, Dialect::PostgreSQL).await;
+        assert_eq!(
+            items,
+            vec![
+                statement("SET client_encoding = 'UTF8'"),
+                statement(
+                    "CREATE FUNCTION public.f() RETURNS integer AS $_$ SELECT 1; $_$ LANGUAGE sql"
+                ),
+                statement("CREATE TABLE public.t (\n    id integer NOT NULL,\n    s text\n)"),
+                statement("COPY public.t (id, s) FROM stdin"),
+                DumpItem::CopyRow(vec![
+                    Some("1".to_owned()),
+                    Some("first\tline\nsecond".to_owned())
+                ]),
+                DumpItem::CopyRow(vec![Some("2".to_owned()), None]),
+                statement("ALTER TABLE ONLY public.t ADD CONSTRAINT t_pkey PRIMARY KEY (id)"),
+            ]
+        );
+        assert_eq!(
+            copy_target("COPY public.t (id, \"Name\") FROM stdin"),
+            Some((
+                "t".to_owned(),
+                Some(vec!["id".to_owned(), "Name".to_owned()])
+            ))
+        );
+    }
+
+    #[test]
+    fn insert_targets() {
+        assert_eq!(
+            insert_target("INSERT INTO `t` VALUES (1)"),
+            Some("t".to_owned())
+        );
+        assert_eq!(
+            insert_target("insert ignore into `db`.`a``b` (`id`) VALUES (1)"),
+            Some("a`b".to_owned())
+        );
+        assert_eq!(
+            insert_target("INSERT INTO public.t(id) VALUES (1)"),
+            Some("t".to_owned())
+        );
+        assert_eq!(
+            insert_target("INSERT INTO \"public\".\"Some \"\"t\"\"\" VALUES (1)"),
+            Some("Some \"t\"".to_owned())
+        );
+        assert_eq!(insert_target("INSERT INTO (id) VALUES (1)"), None);
+        assert_eq!(insert_target("INSERT INTO `t VALUES (1)"), None);
+    }
+
+    #[tokio::test]
+    async fn reject_custom_format() {
+        let mut reader = DumpReader::new(&b"PGDMP\x01\x0e\x00"[..], Dialect::PostgreSQL);
+        assert_eq!(
+            reader.next().await.unwrap_err().to_string(),
+            dump_error(
+                "custom-format archives must be converted to a plain-format dump with \
+                 `pg_restore --file=-`"
+            )
+            .to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn read_from_process() {
+        let mut process = Command::new("sh")
+            .args(["-c", "echo 'CREATE TABLE t (id int);'; exit 1"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let output = BufReader::new(process.stdout.take().unwrap());
+        let mut reader = DumpReader::new(output, Dialect::PostgreSQL).with_process(process);
+        assert_eq!(
+            reader.next().await.unwrap(),
+            Some(statement("CREATE TABLE t (id int)"))
+        );
+        // The process failing to convert the dump fails reading it
+        assert_eq!(
+            reader.next().await.unwrap_err().to_string(),
+            dump_error("converting the dump to plain format failed: exit status: 1").to_string()
+        );
+    }
+
+    #[test]
+    fn copy_values() {
+        assert_eq!(
+            parse_copy_row("\\101\\x42\\\\").unwrap(),
+            vec![Some("AB\\".to_owned())]
+        );
+        assert_eq!(
+            copy_value(Some("t".to_owned()), Some(&SqlType::Bool)).unwrap(),
+            DataType::from(true)
+        );
+        assert_eq!(
+            copy_value(Some("\\x0102".to_owned()), Some(&SqlType::ByteArray)).unwrap(),
+            DataType::ByteArray(vec![1, 2].into())
+        );
+        assert_eq!(
+            copy_value(Some("12".to_owned()), Some(&SqlType::Int(None))).unwrap(),
+            DataType::from(12)
+        );
+    }
+}
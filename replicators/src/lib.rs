@@ -1,9 +1,15 @@
 #![feature(never_type, hash_raw_entry)]
 
+pub(crate) mod auto_increment;
+pub(crate) mod column_filter;
+pub(crate) mod dump;
 pub(crate) mod mysql_connector;
 pub(crate) mod readyset_adapter;
 pub(crate) mod postgres_connector;
 
 pub use mysql_connector::BinlogPosition;
-pub use readyset_adapter::{AdapterOpts, NoriaAdapter};
+pub use readyset_adapter::{
+    AdapterOpts, Config, NoriaAdapter, DEFAULT_SNAPSHOT_PARALLELISM,
+    DEFAULT_WRITE_BATCH_LATENCY_MS, DEFAULT_WRITE_BATCH_SIZE,
+};
 pub use postgres_connector::PostgresPosition;
@@ -1,12 +1,21 @@
 #![feature(never_type, hash_raw_entry)]
 
+pub(crate) mod auto_increment;
+pub(crate) mod column_filter;
+pub(crate) mod dump;
 pub(crate) mod mysql_connector;
-pub(crate) mod readyset_adapter;
 pub(crate) mod postgres_connector;
+pub(crate) mod readyset_adapter;
+
+use std::path::PathBuf;
+use std::time::Duration;
 
 use clap::Parser;
 use readyset::consensus::AuthorityType;
-use readyset_adapter::{AdapterOpts, NoriaAdapter};
+use readyset_adapter::{
+    AdapterOpts, Config, NoriaAdapter, DEFAULT_SNAPSHOT_PARALLELISM,
+    DEFAULT_WRITE_BATCH_LATENCY_MS, DEFAULT_WRITE_BATCH_SIZE,
+};
 use {mysql_async as mysql, tokio_postgres as pgsql};
 
 /// A replication connector from an existing database to ReadySet
@@ -19,10 +28,35 @@ struct Opts {
     /// IP:PORT for Zookeeper.
     #[clap(short, long, env("AUTHORITY_ADDRESS"), default_value("127.0.0.1:2181"))]
     authority_address: String,
-    #[clap(long, env("AUTHORITY"), default_value("zookeeper"), possible_values = &["consul", "zookeeper"])]
+    #[clap(long, env("AUTHORITY"), default_value("zookeeper"), possible_values = &["consul", "zookeeper", "kubernetes"])]
     authority: AuthorityType,
     #[clap(long, env("SERVER_ID"))]
     server_id: Option<u32>,
+    /// The maximum number of tables to copy concurrently during the initial snapshot.
+    #[clap(long, env("SNAPSHOT_PARALLELISM"), default_value_t = DEFAULT_SNAPSHOT_PARALLELISM)]
+    snapshot_parallelism: usize,
+    /// The number of replicated row operations to buffer before writing them to base tables.
+    #[clap(long, env("WRITE_BATCH_SIZE"), default_value_t = DEFAULT_WRITE_BATCH_SIZE)]
+    write_batch_size: usize,
+    /// The maximum time, in milliseconds, to buffer a replicated write for.
+    #[clap(long, env("WRITE_BATCH_LATENCY_MS"), default_value_t = DEFAULT_WRITE_BATCH_LATENCY_MS)]
+    write_batch_latency_ms: u64,
+    /// Comma-separated list of columns, of the form `<table>.<column>`, whose values should be
+    /// replaced with NULL instead of being replicated.
+    #[clap(long, env("NULLIFY_COLUMNS"), use_delimiter = true)]
+    nullify_columns: Vec<String>,
+    /// Comma-separated list of columns, of the form `<table>.<column>`, to leave out of
+    /// ReadySet's tables entirely.
+    #[clap(long, env("DROP_COLUMNS"), use_delimiter = true)]
+    drop_columns: Vec<String>,
+    /// A plain-format `mysqldump` or `pg_dump` file to load the initial snapshot from, instead of
+    /// snapshotting the upstream database.
+    #[clap(long, env("SNAPSHOT_DUMP"))]
+    snapshot_dump: Option<PathBuf>,
+    /// Drop the replication slot and publication created in the upstream database by a previous
+    /// run of the replicator, then exit, rather than replicating.
+    #[clap(long)]
+    cleanup: bool,
     #[clap(subcommand)]
     subcmd: DbOpts,
 
@@ -126,7 +160,8 @@ impl From<PostgresOpts> for AdapterOpts {
             conf.password(&password);
         }
 
-        AdapterOpts::Postgres(conf)
+        let tls = native_tls::TlsConnector::builder().build().unwrap(); // Never returns an error
+        AdapterOpts::Postgres(conf, postgres_native_tls::MakeTlsConnector::new(tls))
     }
 }
 
@@ -142,10 +177,25 @@ async fn main() -> anyhow::Result<!> {
         DbOpts::Postgres(opts) => opts.into(),
     };
 
+    if opts.cleanup {
+        NoriaAdapter::cleanup(options).await?;
+        std::process::exit(0);
+    }
+
     let authority = opts
         .authority
         .to_authority(&opts.authority_address, &opts.deployment)
         .await;
 
-    NoriaAdapter::start_with_authority(authority, options, opts.server_id).await?
+    let config = Config {
+        snapshot_parallelism: opts.snapshot_parallelism,
+        nullified_columns: opts.nullify_columns,
+        dropped_columns: opts.drop_columns,
+        snapshot_dump: opts.snapshot_dump,
+        write_batch_size: opts.write_batch_size,
+        write_batch_latency: Duration::from_millis(opts.write_batch_latency_ms),
+        ..Default::default()
+    };
+
+    NoriaAdapter::start_with_authority(authority, options, config, opts.server_id).await?
 }
@@ -1,15 +1,20 @@
 use std::convert::{TryFrom, TryInto};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use binlog::consts::{BinlogChecksumAlg, EventType};
+use metrics::gauge;
 use mysql::binlog::jsonb::{self, JsonbToJsonError};
 use mysql::prelude::Queryable;
 use mysql_async as mysql;
 use mysql_common::binlog;
 use mysql_common::binlog::row::BinlogRow;
 use mysql_common::binlog::value::BinlogValue;
+use mysql_common::constants::SqlMode;
+use nom_sql::{Dialect, MySqlParseOptions, SqlQuery};
+use readyset::metrics::recorded;
 use readyset::replication::ReplicationOffset;
-use readyset::{ReadySetError, ReadySetResult};
+use readyset::{Modification, ReadySetError, ReadySetResult, TableOperation};
 use readyset_data::DataType;
 
 use super::BinlogPosition;
@@ -20,8 +25,10 @@ const DEFAULT_SERVER_ID: u32 = u32::MAX - 55;
 
 /// A connector that connects to a MySQL server and starts reading binlogs from a given position.
 ///
-/// The server must be configured with `binlog_format` set to `row` and `binlog_row_image` set to
-/// `full`.
+/// The server must be configured with `binlog_format` set to `row`. Any `binlog_row_image` setting
+/// is supported: when row images are `minimal`, rows are updated and deleted by their primary key,
+/// with the columns left out of the image taken from the row stored in the ReadySet base table,
+/// and the columns left out of the image of an inserted row are given their default value.
 ///
 /// The connector user may optionally have the following permissions:
 /// * `BACKUP_ADMIN` - (optional) to perform LOCK INSTANCE FOR BACKUP, not available on RDS
@@ -80,36 +87,9 @@ impl PartialOrd for BinlogPosition {
 impl TryFrom<&BinlogPosition> for ReplicationOffset {
     type Error = ReadySetError;
 
-    /// `ReplicationOffset` is a filename and a u128 offset
-    /// We use the binlog basefile name as the filename, and we use the binlog suffix len for
-    /// the top 5 bits, which can be as big as 31 digits in theory, but we only allow up to 17
-    /// decimal digits, which is more than enough for the binlog spec. This is required to be
-    /// able to properly format the integer back to string, including any leading zeroes.
-    /// The following 59 bits are used for the numerical value of the suffix, finally the last
-    /// 64 bits of the offset are the actual binlog offset.
+    /// See [`ReplicationOffset::from_binlog_position`] for how binlog positions are encoded
     fn try_from(value: &BinlogPosition) -> Result<Self, Self::Error> {
-        let (basename, suffix) = value.binlog_file.rsplit_once('.').ok_or_else(|| {
-            ReadySetError::ReplicationFailed(format!("Invalid binlog name {}", value.binlog_file))
-        })?;
-
-        let suffix_len = suffix.len() as u128;
-
-        if suffix_len > 17 {
-            // 17 digit decimal number is the most we can fit into 59 bits
-            return Err(ReadySetError::ReplicationFailed(format!(
-                "Invalid binlog suffix {}",
-                value.binlog_file
-            )));
-        }
-
-        let suffix = suffix.parse::<u128>().map_err(|_| {
-            ReadySetError::ReplicationFailed(format!("Invalid binlog suffix {}", value.binlog_file))
-        })?;
-
-        Ok(ReplicationOffset {
-            offset: (suffix_len << 123) + (suffix << 64) + (value.position as u128),
-            replication_log_name: basename.to_string(),
-        })
+        ReplicationOffset::from_binlog_position(&value.binlog_file, value.position)
     }
 }
 
@@ -144,6 +124,43 @@ impl From<ReplicationOffset> for BinlogPosition {
     }
 }
 
+/// Returns the dialect to parse the statement in `ev` with, according to the `sql_mode` it was
+/// executed with upstream, so that we parse statements the same way the upstream server did
+fn query_dialect(ev: &binlog::events::QueryEvent) -> Dialect {
+    use binlog::consts::StatusVarKey;
+    use binlog::events::StatusVarVal;
+
+    let sql_mode = match ev
+        .status_vars()
+        .get_status_var(StatusVarKey::SqlMode)
+        .and_then(|var| var.get_value().ok())
+    {
+        Some(StatusVarVal::SqlMode(sql_mode)) => sql_mode.get(),
+        _ => return Dialect::MySQL,
+    };
+    let ansi = sql_mode.contains(SqlMode::MODE_ANSI);
+
+    Dialect::mysql_with_options(MySqlParseOptions {
+        ansi_quotes: ansi || sql_mode.contains(SqlMode::MODE_ANSI_QUOTES),
+        pipes_as_concat: ansi || sql_mode.contains(SqlMode::MODE_PIPES_AS_CONCAT),
+        no_backslash_escapes: sql_mode.contains(SqlMode::MODE_NO_BACKSLASH_ESCAPES),
+    })
+}
+
+/// Returns true if `query` doesn't change the schema, and so must not be applied as a schema
+/// change. This is either a statement that modifies rows, such as an `INSERT ... SELECT`, whose
+/// changes are replicated by the row events that follow it since the binlog is row-based, or a
+/// table maintenance statement such as `ANALYZE TABLE` or `OPTIMIZE TABLE`.
+fn is_ignored_statement(query: &str, dialect: Dialect) -> bool {
+    matches!(
+        nom_sql::parse_query(dialect, query),
+        Ok(SqlQuery::Insert(_)
+            | SqlQuery::Update(_)
+            | SqlQuery::Delete(_)
+            | SqlQuery::Maintenance(_))
+    )
+}
+
 impl MySqlBinlogConnector {
     /// The binlog replica must be assigned a unique `server_id` in the replica topology
     /// if one is not assigned we will use (u32::MAX - 55)
@@ -244,6 +261,7 @@ impl MySqlBinlogConnector {
             let binlog_event = self.next_event().await?;
 
             self.next_position.position = binlog_event.header().log_pos();
+            record_replication_lag(binlog_event.header().timestamp());
 
             match binlog_event
                 .header()
@@ -285,9 +303,15 @@ impl MySqlBinlogConnector {
                         continue;
                     }
 
+                    let dialect = query_dialect(&ev);
+                    if is_ignored_statement(&ev.query(), dialect) {
+                        continue;
+                    }
+
                     return Ok((
                         ReplicationAction::SchemaChange {
                             ddl: ev.query().to_string(),
+                            dialect,
                         },
                         &self.next_position,
                     ));
@@ -320,15 +344,29 @@ impl MySqlBinlogConnector {
                         continue;
                     }
 
+                    let num_columns = ev.num_columns() as usize;
                     let mut inserted_rows = Vec::new();
+                    // Columns missing from a minimal after image were not given a value by the
+                    // statement, and take their default value
+                    let present = ev.columns_after_image().iter_ones().collect::<Vec<_>>();
+                    let default_columns = (0..num_columns)
+                        .filter(|col| !present.contains(col))
+                        .collect();
 
                     for row in ev.rows(tme) {
                         // For each row in the event we produce a vector of ReadySet types that
                         // represent that row
-                        inserted_rows.push(readyset::TableOperation::Insert(binlog_row_to_noria_row(
+                        let row = binlog_row_to_noria_row(
                             &row?.1.ok_or("Missing data in WRITE_ROWS_EVENT")?,
+                            num_columns,
+                            ev.columns_after_image().iter_ones(),
                             tme,
-                        )?));
+                        )?;
+                        inserted_rows.push(TableOperation::Insert(
+                            row.into_iter()
+                                .map(|val| val.unwrap_or(DataType::None))
+                                .collect(),
+                        ));
                     }
 
                     return Ok((
@@ -336,6 +374,7 @@ impl MySqlBinlogConnector {
                             table: tme.table_name().to_string(),
                             actions: inserted_rows,
                             txid: self.current_gtid,
+                            default_columns,
                         },
                         &self.next_position,
                     ));
@@ -354,29 +393,34 @@ impl MySqlBinlogConnector {
                         continue;
                     }
 
+                    let num_columns = ev.num_columns() as usize;
                     let mut updated_rows = Vec::new();
 
                     for row in ev.rows(tme) {
-                        // For each row in the event we produce a pair of ReadySet table operations to
-                        // delete the previous entry and insert the new one
+                        // For each row in the event we produce a pair of ReadySet table operations
+                        // to delete the previous entry and insert the new one, or a single update
+                        // by key if the before image only contains the key of the row
                         let row = &row?;
-                        updated_rows.push(readyset::TableOperation::DeleteRow {
-                            row: binlog_row_to_noria_row(
-                                row.0.as_ref().ok_or(format!(
-                                    "Missing before rows in UPDATE_ROWS_EVENT {:?}",
-                                    row
-                                ))?,
-                                tme,
-                            )?,
-                        });
-
-                        updated_rows.push(readyset::TableOperation::Insert(binlog_row_to_noria_row(
+                        let before = binlog_row_to_noria_row(
+                            row.0.as_ref().ok_or(format!(
+                                "Missing before rows in UPDATE_ROWS_EVENT {:?}",
+                                row
+                            ))?,
+                            num_columns,
+                            ev.columns_before_image().iter_ones(),
+                            tme,
+                        )?;
+                        let after = binlog_row_to_noria_row(
                             row.1.as_ref().ok_or(format!(
                                 "Missing after rows in UPDATE_ROWS_EVENT {:?}",
                                 row
                             ))?,
+                            num_columns,
+                            ev.columns_after_image().iter_ones(),
                             tme,
-                        )?));
+                        )?;
+
+                        updated_rows.extend(update_operations(before, after));
                     }
 
                     return Ok((
@@ -384,6 +428,7 @@ impl MySqlBinlogConnector {
                             table: tme.table_name().to_string(),
                             actions: updated_rows,
                             txid: self.current_gtid,
+                            default_columns: Vec::new(),
                         },
                         &self.next_position,
                     ));
@@ -402,17 +447,18 @@ impl MySqlBinlogConnector {
                         continue;
                     }
 
+                    let num_columns = ev.num_columns() as usize;
                     let mut deleted_rows = Vec::new();
 
                     for row in ev.rows(tme) {
                         // For each row in the event we produce a vector of Noria types that
-                        // represent that row
-                        deleted_rows.push(readyset::TableOperation::DeleteRow {
-                            row: binlog_row_to_noria_row(
-                                &row?.0.ok_or("Missing data in DELETE_ROWS_EVENT")?,
-                                tme,
-                            )?,
-                        });
+                        // represent that row, or just its key if the before image is minimal
+                        deleted_rows.push(delete_operation(binlog_row_to_noria_row(
+                            &row?.0.ok_or("Missing data in DELETE_ROWS_EVENT")?,
+                            num_columns,
+                            ev.columns_before_image().iter_ones(),
+                            tme,
+                        )?));
                     }
 
                     return Ok((
@@ -420,6 +466,7 @@ impl MySqlBinlogConnector {
                             table: tme.table_name().to_string(),
                             actions: deleted_rows,
                             txid: self.current_gtid,
+                            default_columns: Vec::new(),
                         },
                         &self.next_position,
                     ));
@@ -556,48 +603,140 @@ fn binlog_val_to_noria_val(
     }
 }
 
+/// Record the [`REPLICATOR_LAG_SECONDS`][lag] metric for a binlog event written at `timestamp`
+/// (in seconds since the unix epoch). Events generated by the server rather than written to the
+/// binlog, such as heartbeats, have a timestamp of 0 and are ignored.
+///
+/// [lag]: recorded::REPLICATOR_LAG_SECONDS
+fn record_replication_lag(timestamp: u32) {
+    if timestamp == 0 {
+        return;
+    }
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        gauge!(
+            recorded::REPLICATOR_LAG_SECONDS,
+            now.as_secs().saturating_sub(timestamp as u64) as f64
+        );
+    }
+}
+
+/// Convert a row image from a rows event into a row with an entry for every one of the
+/// `num_columns` columns of the table, given the positions of the table `columns` present in the
+/// image. Columns that are not present in the image, which happens when the server is configured
+/// with `binlog_row_image` set to `minimal` or `noblob`, are `None`.
 fn binlog_row_to_noria_row(
     binlog_row: &BinlogRow,
+    num_columns: usize,
+    columns: impl Iterator<Item = usize>,
     tme: &binlog::events::TableMapEvent<'static>,
-) -> mysql::Result<Vec<DataType>> {
-    (0..binlog_row.len())
-        .map(|idx| {
-            match binlog_row.as_ref(idx).unwrap() {
-                BinlogValue::Value(val) => {
-                    let (kind, meta) = (
-                        tme.get_column_type(idx)
-                            .map_err(|e| format!("Unable to get column type {}", e))?
-                            .unwrap(),
-                        tme.get_column_metadata(idx).unwrap(),
-                    );
-                    binlog_val_to_noria_val(val, kind, meta)
-                }
-                BinlogValue::Jsonb(val) => {
-                    let json: Result<serde_json::Value, _> = val.clone().try_into(); // urgh no TryFrom impl
-                    match json {
-                        Ok(val) => Ok(DataType::from(val.to_string())),
-                        Err(JsonbToJsonError::Opaque) => match val {
-                            jsonb::Value::Opaque(opaque_val) => {
-                                // As far as I can *tell* Opaque is just a raw JSON string, which we
-                                // can just translate into a DataType as JSON directly without going
-                                // through serde_json::Value first.
-                                Ok(DataType::from(opaque_val.data().as_ref()))
-                            }
-                            _ => {
-                                #[allow(clippy::unreachable)] // actually unreachable
-                                {
-                                    unreachable!("Opaque error only returned for opaque values")
-                                }
-                            }
-                        },
-                        Err(JsonbToJsonError::InvalidUtf8(err)) => Err(err.to_string().into()),
-                        Err(JsonbToJsonError::InvalidJsonb(e)) => Err(e.into()),
+) -> mysql::Result<Vec<Option<DataType>>> {
+    let mut row = vec![None; num_columns];
+    for (idx, col) in columns.enumerate() {
+        let val = binlog_row
+            .as_ref(idx)
+            .ok_or_else(|| format!("Missing value for column {} in {:?}", col, binlog_row))?;
+        let val = binlog_value_to_noria_value(val, col, tme)?;
+        *row.get_mut(col)
+            .ok_or_else(|| format!("Unexpected column {} in {:?}", col, binlog_row))? = Some(val);
+    }
+    Ok(row)
+}
+
+fn binlog_value_to_noria_value(
+    value: &BinlogValue,
+    col: usize,
+    tme: &binlog::events::TableMapEvent<'static>,
+) -> mysql::Result<DataType> {
+    match value {
+        BinlogValue::Value(val) => {
+            let (kind, meta) = (
+                tme.get_column_type(col)
+                    .map_err(|e| format!("Unable to get column type {}", e))?
+                    .unwrap(),
+                tme.get_column_metadata(col).unwrap(),
+            );
+            binlog_val_to_noria_val(val, kind, meta)
+        }
+        BinlogValue::Jsonb(val) => {
+            let json: Result<serde_json::Value, _> = val.clone().try_into(); // urgh no TryFrom impl
+            match json {
+                Ok(val) => Ok(DataType::from(val.to_string())),
+                Err(JsonbToJsonError::Opaque) => match val {
+                    jsonb::Value::Opaque(opaque_val) => {
+                        // As far as I can *tell* Opaque is just a raw JSON string, which we
+                        // can just translate into a DataType as JSON directly without going
+                        // through serde_json::Value first.
+                        Ok(DataType::from(opaque_val.data().as_ref()))
                     }
-                }
-                _ => Err(format!("Expected a value in WRITE_ROWS_EVENT {:?}", binlog_row).into()),
+                    _ => {
+                        #[allow(clippy::unreachable)] // actually unreachable
+                        {
+                            unreachable!("Opaque error only returned for opaque values")
+                        }
+                    }
+                },
+                Err(JsonbToJsonError::InvalidUtf8(err)) => Err(err.to_string().into()),
+                Err(JsonbToJsonError::InvalidJsonb(e)) => Err(e.into()),
             }
-        })
-        .collect()
+        }
+        _ => Err(format!("Expected a value in rows event, got {:?}", value).into()),
+    }
+}
+
+/// Returns the row if every column of the table is present in it
+fn full_row(row: &[Option<DataType>]) -> Option<Vec<DataType>> {
+    row.iter().cloned().collect()
+}
+
+/// Returns the values of the columns present in a partial before image. With
+/// `binlog_row_image=minimal`, the before image of a table with a primary key only contains the
+/// primary key columns, so this is the key of the row, with the columns in the order they appear
+/// in the table.
+fn partial_row_key(row: Vec<Option<DataType>>) -> Vec<DataType> {
+    row.into_iter().flatten().collect()
+}
+
+/// Convert the before image of a deleted row into the operation deleting it. If the image is
+/// partial, the row is deleted by its key.
+fn delete_operation(before: Vec<Option<DataType>>) -> TableOperation {
+    match full_row(&before) {
+        Some(row) => TableOperation::DeleteRow { row },
+        None => TableOperation::DeleteByKey {
+            key: partial_row_key(before),
+        },
+    }
+}
+
+/// Convert the before and after images of an updated row into the operations updating it.
+///
+/// If the before image is complete, any columns missing from the after image were left unchanged
+/// by the update, so the new row can be computed directly. Otherwise, the before image only
+/// contains the key of the row, and we rely on the base table to fill in the missing columns from
+/// the row it has stored for that key.
+fn update_operations(
+    before: Vec<Option<DataType>>,
+    after: Vec<Option<DataType>>,
+) -> Vec<TableOperation> {
+    match full_row(&before) {
+        Some(before) => {
+            let after = after
+                .into_iter()
+                .zip(&before)
+                .map(|(new, old)| new.unwrap_or_else(|| old.clone()))
+                .collect();
+            vec![
+                TableOperation::DeleteRow { row: before },
+                TableOperation::Insert(after),
+            ]
+        }
+        None => vec![TableOperation::Update {
+            key: partial_row_key(before),
+            update: after
+                .into_iter()
+                .map(|val| val.map_or(Modification::None, Modification::Set))
+                .collect(),
+        }],
+    }
 }
 
 #[async_trait]
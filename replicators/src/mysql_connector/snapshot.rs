@@ -1,26 +1,52 @@
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt::{self, Display};
 use std::future;
+use std::time::Duration;
 
 use futures::future::TryFutureExt;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use itertools::Itertools;
+use metrics::gauge;
 use mysql::prelude::*;
 use mysql::{Transaction, TxOpts};
 use mysql_async as mysql;
-use readyset::replication::{ReplicationOffset, ReplicationOffsets};
+use nom_sql::{Dialect, MySqlParseOptions, SqlIdentifier};
+use readyset::metrics::recorded;
+use readyset::recipe::changelist::ChangeList;
+use readyset::replication::{ReplicationOffset, ReplicationOffsets, SnapshotProgress};
 use readyset::ReadySetResult;
+use readyset_data::DataType;
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, info_span, warn};
 use tracing_futures::Instrument;
 
 use super::BinlogPosition;
+use crate::auto_increment::SnapshotAutoIncrement;
+use crate::column_filter::{nullify_row, ColumnFilter};
 
 const BATCH_SIZE: usize = 1000; // How many queries to buffer before pushing to ReadySet
 
-const MAX_SNAPSHOT_BATCH: usize = 8; // How many tables to snapshot at the same time
+/// How many rows to copy between each checkpoint of a table's snapshot progress. Must be a
+/// multiple of [`BATCH_SIZE`].
+const CHECKPOINT_INTERVAL: usize = 100 * BATCH_SIZE;
+
+/// How long to wait for the global read lock used to take a consistent snapshot of all tables
+/// before falling back to locking each table separately. Writes to the upstream database queue up
+/// behind the lock while we wait for it, so this is kept short.
+const GLOBAL_READ_LOCK_TIMEOUT_SECS: u32 = 10;
+
+/// How long to hold the global read lock for while starting the transactions of a consistent
+/// snapshot, before giving up and falling back to snapshotting tables at different binlog
+/// positions. Writes to the upstream database are blocked for as long as we hold the lock.
+const GLOBAL_READ_LOCK_HOLD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The number of connections from the pool that may be in use other than the transactions of a
+/// consistent snapshot while it's taken: the connections holding the instance lock, the metadata
+/// locks and the global read lock, and the connection reading the binlog position
+const CONSISTENT_SNAPSHOT_OTHER_CONNECTIONS: usize = 4;
 
 /// Pass the error forward while logging it
 fn log_err<E: Error>(err: E) -> E {
@@ -38,6 +64,15 @@ pub struct MySqlReplicator {
     pub(crate) pool: mysql::Pool,
     /// If Some then only snapshot those tables, otherwise will snapshot all tables
     pub(crate) tables: Option<Vec<String>>,
+    /// The maximum number of tables to snapshot at the same time
+    pub(crate) snapshot_parallelism: usize,
+    /// The maximum number of connections `pool` will open at the same time
+    pub(crate) max_connections: usize,
+    /// Columns whose values are replaced with NULL, or that are dropped, when written to ReadySet
+    pub(crate) column_filter: ColumnFilter,
+    /// The latest binlog position at which the snapshot of a table was resumed, if any. See
+    /// [`MySqlReplicator::snapshot_to_noria`].
+    pub(crate) resumed_offset: Option<ReplicationOffset>,
 }
 
 /// Get the list of tables defined in the database
@@ -47,6 +82,29 @@ async fn load_table_list<Q: Queryable>(q: &mut Q, kind: TableKind) -> mysql::Res
     Ok(tables_entries.into_iter().map(|(name, _)| name).collect())
 }
 
+/// Get the names and positions of the primary key columns of the named table, in key order.
+/// Returns an empty list if the table has no primary key.
+async fn primary_key_for_table<Q: Queryable>(
+    q: &mut Q,
+    table_name: &str,
+) -> mysql::Result<Vec<(String, usize)>> {
+    let query = "SELECT k.COLUMN_NAME, c.ORDINAL_POSITION \
+                 FROM information_schema.KEY_COLUMN_USAGE k \
+                 JOIN information_schema.COLUMNS c \
+                 ON c.TABLE_SCHEMA = k.TABLE_SCHEMA \
+                 AND c.TABLE_NAME = k.TABLE_NAME \
+                 AND c.COLUMN_NAME = k.COLUMN_NAME \
+                 WHERE k.TABLE_SCHEMA = DATABASE() \
+                 AND k.TABLE_NAME = ? \
+                 AND k.CONSTRAINT_NAME = 'PRIMARY' \
+                 ORDER BY k.ORDINAL_POSITION";
+    q.exec_map(query, (table_name,), |(name, position): (String, u64)| {
+        // ORDINAL_POSITION is 1-based
+        (name, position as usize - 1)
+    })
+    .await
+}
+
 /// Get the `CREATE TABLE` or `CREATE VIEW` statement for the named table
 async fn create_for_table<Q: Queryable>(
     q: &mut Q,
@@ -69,6 +127,31 @@ async fn create_for_table<Q: Queryable>(
     }
 }
 
+/// The outcome of a task replicating a single table: the name of the table, the replication offset
+/// of its snapshot, the result of the replication, and the transaction the table was copied with if
+/// that transaction belongs to a [`ConsistentSnapshot`] and can be used to copy another table
+type DumperTaskOutput = (
+    String,
+    ReplicationOffset,
+    ReadySetResult<()>,
+    Option<Transaction<'static>>,
+);
+
+/// A set of transactions that all see the database at the same point in time, along with the
+/// binlog position of that point in time
+struct ConsistentSnapshot {
+    transactions: Vec<Transaction<'static>>,
+    offset: ReplicationOffset,
+}
+
+impl ConsistentSnapshot {
+    /// Take one of the transactions of the snapshot, if any are left
+    fn take(&mut self) -> Option<(Transaction<'static>, ReplicationOffset)> {
+        let tx = self.transactions.pop()?;
+        Some((tx, self.offset.clone()))
+    }
+}
+
 fn tx_opts() -> TxOpts {
     let mut tx_opts = mysql::TxOpts::default();
     tx_opts
@@ -128,11 +211,20 @@ impl MySqlReplicator {
             return Ok(tx);
         }
 
+        // The output of `SHOW CREATE` depends on the session's `sql_mode` (with `ANSI_QUOTES`,
+        // identifiers are quoted with double quotes), so parse it according to that `sql_mode`
+        let sql_mode: Option<String> = tx.query_first("SELECT @@SESSION.sql_mode").await?;
+        let dialect = Dialect::mysql_with_options(MySqlParseOptions::from_sql_mode(
+            sql_mode.as_deref().unwrap_or_default(),
+        ));
+
         // Process `CREATE TABLE` statements
         for table in &tables {
             let create_table = create_for_table(&mut tx, table, TableKind::BaseTable).await?;
             debug!(%create_table, "Extending recipe");
-            if let Err(err) = future::ready(create_table.try_into())
+            let changelist = ChangeList::from_str_with_dialect(&create_table, dialect)
+                .map(|changelist| self.column_filter.filter_changelist(changelist));
+            if let Err(err) = future::ready(changelist)
                 .and_then(|changelist| async {
                     noria.extend_recipe_no_leader_ready(changelist).await
                 })
@@ -148,7 +240,8 @@ impl MySqlReplicator {
             let create_view = create_for_table(&mut tx, &view, TableKind::View).await?;
             debug!(%create_view, "Extending recipe");
 
-            if let Err(err) = future::ready(create_view.try_into())
+            let changelist = ChangeList::from_str_with_dialect(&create_view, dialect);
+            if let Err(err) = future::ready(changelist)
                 .and_then(|changelist| async {
                     noria.extend_recipe_no_leader_ready(changelist).await
                 })
@@ -173,8 +266,68 @@ impl MySqlReplicator {
 
     /// Call `SELECT * FROM table` and convert all rows into a ReadySet row
     /// it may seem inefficient but apparently that is the correct way to
-    /// replicate a table, and `mysqldump` and `debezium` do just that
-    pub async fn dump_table(&self, table: &str) -> mysql::Result<TableDumper> {
+    /// replicate a table, and `mysqldump` and `debezium` do just that.
+    ///
+    /// Only the given `columns` of the table, those of its base table, are selected, so that
+    /// columns that are dropped are never read.
+    ///
+    /// If the table has a primary key, rows are returned in primary key order, and if
+    /// `resume_from` is set only rows with a key greater than its `last_key` are returned.
+    ///
+    /// The rows are read with `tx`, so they reflect the database at the point in time the
+    /// transaction was started.
+    pub fn dump_table(
+        &self,
+        tx: Transaction<'static>,
+        table: &str,
+        columns: &[SqlIdentifier],
+        primary_key: Vec<(String, usize)>,
+        resume_from: Option<(SnapshotProgress, Vec<mysql::Value>)>,
+    ) -> TableDumper {
+        let key_columns = primary_key
+            .iter()
+            .map(|(name, _)| format!("`{}`", name))
+            .join(",");
+
+        let (filter, params, progress) = match resume_from {
+            Some((progress, params)) => {
+                let placeholders = params.iter().map(|_| "?").join(",");
+                (
+                    format!(" where ({}) > ({})", key_columns, placeholders),
+                    params,
+                    Some(progress),
+                )
+            }
+            None => (String::new(), vec![], None),
+        };
+
+        let query_count = format!("select count(*) from `{}`{}", table, filter);
+        let select_columns = columns.iter().map(|c| format!("`{}`", c)).join(",");
+        let query = if primary_key.is_empty() {
+            format!("select {} from `{}`", select_columns, table)
+        } else {
+            format!(
+                "select {} from `{}`{} order by {}",
+                select_columns, table, filter, key_columns
+            )
+        };
+
+        TableDumper {
+            query_count,
+            query,
+            params,
+            // Key columns are never dropped, so they're all in `columns`
+            key: primary_key
+                .iter()
+                .filter_map(|(name, _)| columns.iter().position(|c| c == name.as_str()))
+                .collect(),
+            progress,
+            tx,
+        }
+    }
+
+    /// Start a read-only transaction with a consistent snapshot, to copy tables with
+    async fn start_snapshot_transaction(&self) -> mysql::Result<Transaction<'static>> {
         let mut tx = self
             .pool
             .start_transaction(tx_opts())
@@ -186,13 +339,91 @@ impl MySqlReplicator {
             .await
             .map_err(log_err);
 
-        let query_count = format!("select count(*) from `{}`", table);
-        let query = format!("select * from `{}`", table);
-        Ok(TableDumper {
-            query_count,
-            query,
-            tx,
-        })
+        Ok(tx)
+    }
+
+    /// Start `count` transactions that all see the database at the same point in time, and get
+    /// the binlog position of that point in time, so that every table copied with one of them is
+    /// consistent with a single binlog position.
+    ///
+    /// This is done the same way `mysqldump --single-transaction --source-data` does it: writes
+    /// are briefly blocked with `FLUSH TABLES WITH READ LOCK` while the transactions are started
+    /// with `START TRANSACTION WITH CONSISTENT SNAPSHOT` and the binlog position is read. Returns
+    /// `None` if the global read lock can't be acquired, which is the case on RDS, or if it can't
+    /// be acquired quickly because of long running queries, or if the transactions can't all be
+    /// started within [`GLOBAL_READ_LOCK_HOLD_TIMEOUT`].
+    ///
+    /// Fewer than `count` transactions are started if the pool can't open that many connections
+    /// alongside the other connections in use while the snapshot is taken, since waiting for a
+    /// connection while holding the lock would block writes to the upstream database indefinitely.
+    async fn consistent_snapshot(
+        &self,
+        count: usize,
+    ) -> ReadySetResult<Option<ConsistentSnapshot>> {
+        let count = count.min(
+            self.max_connections
+                .saturating_sub(CONSISTENT_SNAPSHOT_OTHER_CONNECTIONS)
+                .max(1),
+        );
+        let mut lock = self.pool.get_conn().await?;
+        let _ = lock
+            .query_drop(format!(
+                "SET SESSION lock_wait_timeout={}",
+                GLOBAL_READ_LOCK_TIMEOUT_SECS
+            ))
+            .await
+            .map_err(log_err);
+
+        let snapshot = match lock.query_drop("FLUSH TABLES WITH READ LOCK").await {
+            Ok(()) => {
+                let snapshot = tokio::time::timeout(GLOBAL_READ_LOCK_HOLD_TIMEOUT, async {
+                    let mut transactions = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        transactions.push(self.start_snapshot_transaction().await?);
+                    }
+                    let offset = ReplicationOffset::try_from(self.get_binlog_position().await?)?;
+                    ReadySetResult::Ok(ConsistentSnapshot {
+                        transactions,
+                        offset,
+                    })
+                })
+                .await;
+
+                // Writes to the upstream database are blocked until we release the lock, so
+                // release it even if we failed to take the snapshot
+                lock.query_drop("UNLOCK TABLES").await?;
+                match snapshot {
+                    Ok(snapshot) => snapshot.map(Some),
+                    Err(_) => {
+                        warn!(
+                            "Timed out taking a consistent snapshot, tables will be snapshotted \
+                             at different binlog positions"
+                        );
+                        Ok(None)
+                    }
+                }
+            }
+            Err(err) => {
+                warn!(
+                    %err,
+                    "Failed to acquire global read lock, tables will be snapshotted at different \
+                     binlog positions"
+                );
+                Ok(None)
+            }
+        };
+
+        // The connection goes back to the pool, where it may later be used to lock a table
+        let _ = lock
+            .query_drop("SET SESSION lock_wait_timeout=DEFAULT")
+            .await
+            .map_err(log_err);
+
+        let snapshot = snapshot?;
+        if let Some(snapshot) = &snapshot {
+            info!(offset = %snapshot.offset, "Took consistent snapshot");
+        }
+        Ok(snapshot)
     }
 
     /// Use the SHOW MASTER STATUS statement to determine the current binary log
@@ -227,30 +458,56 @@ impl MySqlReplicator {
     }
 
     /// Replicate a single table from the provided TableDumper and into ReadySet by
-    /// converting every MySQL row into ReadySet row and calling `insert_many` in batches
+    /// converting every MySQL row into ReadySet row and calling `insert_many` in batches.
+    ///
+    /// If the table has a primary key, progress is periodically recorded with the controller as
+    /// rows are copied, so that the snapshot can be resumed if it is interrupted. `repl_offset` is
+    /// the replication offset that the table will be set to once the snapshot finishes.
+    ///
+    /// The values of the columns at the positions in `nullified` are replaced with NULL.
     async fn replicate_table(
-        mut dumper: TableDumper,
+        table_name: String,
+        dumper: &mut TableDumper,
         mut table_mutator: readyset::Table,
+        mut noria: readyset::ControllerHandle,
+        repl_offset: ReplicationOffset,
+        nullified: Vec<usize>,
     ) -> ReadySetResult<()> {
         let mut cnt = 0;
+        let rows_copied_before = dumper
+            .progress
+            .as_ref()
+            .map_or(0, |progress| progress.rows_copied);
 
         // Query for number of rows first
         let nrows: usize = dumper
             .tx
-            .query_first(&dumper.query_count)
+            .exec_first(&dumper.query_count, dumper.params.clone())
             .await
             .map_err(log_err)?
             .unwrap_or(0);
 
+        let key = dumper.key.clone();
+        // Base tables don't check for duplicate keys in snapshot mode, so when resuming we leave it
+        // disabled until we're past any rows that may have been copied after the last checkpoint
+        let resumed = dumper.progress.is_some();
+        let mut auto_increment = SnapshotAutoIncrement::new(&table_mutator);
         let mut row_stream = dumper.stream().await.map_err(log_err)?;
-        let mut rows = Vec::with_capacity(BATCH_SIZE);
+        let mut rows: Vec<Vec<DataType>> = Vec::with_capacity(BATCH_SIZE);
 
-        info!(rows = %nrows, "Replication started");
+        if rows_copied_before > 0 {
+            info!(rows = %nrows, %rows_copied_before, "Replication resumed");
+        } else {
+            info!(rows = %nrows, "Replication started");
+        }
+        gauge!(recorded::REPLICATOR_SNAPSHOT_PERCENT, 0.0, "table" => table_name.clone());
 
-        table_mutator.set_snapshot_mode(true).await?;
+        if !resumed {
+            table_mutator.set_snapshot_mode(true).await?;
+        }
 
         loop {
-            let row = match row_stream.next().await {
+            let mut row = match row_stream.next().await {
                 Ok(Some(row)) => row,
                 Ok(None) => break,
                 Err(err) if cnt == nrows => {
@@ -260,21 +517,60 @@ impl MySqlReplicator {
                 Err(err) => return Err(err).map_err(log_err),
             };
 
+            nullify_row(&mut row, &nullified);
+            auto_increment.observe(&row);
             rows.push(row);
             cnt += 1;
 
             if rows.len() == BATCH_SIZE {
                 // We aggregate rows into batches and then send them all to noria
                 let send_rows = std::mem::replace(&mut rows, Vec::with_capacity(BATCH_SIZE));
+                let last_key = match send_rows.last() {
+                    Some(row) if !key.is_empty() && cnt % CHECKPOINT_INTERVAL == 0 => {
+                        Some(key.iter().map(|i| row[*i].clone()).collect::<Vec<_>>())
+                    }
+                    _ => None,
+                };
+
                 table_mutator
                     .insert_many(send_rows)
                     .await
                     .map_err(log_err)?;
+
+                if let Some(last_key) = last_key {
+                    // Rows written in snapshot mode skip the write-ahead log, so they have to be
+                    // flushed to disk before the progress claims they were copied, or they could
+                    // be lost for good in a crash
+                    noria.flush_table(&table_name).await.map_err(log_err)?;
+
+                    // If we fail to record progress here we have to fail the snapshot, otherwise
+                    // a later resume could re-copy more than one interval's worth of rows
+                    let progress = SnapshotProgress {
+                        offset: repl_offset.clone(),
+                        last_key,
+                        rows_copied: rows_copied_before + cnt as u64,
+                        total_rows: Some(rows_copied_before + nrows as u64),
+                    };
+                    noria
+                        .set_snapshot_progress(&table_name, Some(progress))
+                        .await
+                        .map_err(log_err)?;
+
+                    if resumed && cnt == CHECKPOINT_INTERVAL {
+                        table_mutator.set_snapshot_mode(true).await?;
+                    }
+                }
             }
 
             if cnt % 1_000_000 == 0 && nrows > 0 {
-                let progress = format!("{:.2}%", (cnt as f64 / nrows as f64) * 100.);
+                let percent = (cnt as f64 / nrows as f64) * 100.;
+                let progress = format!("{:.2}%", percent);
                 info!(rows_replicated = %cnt, %progress, "Replication progress");
+                gauge!(
+                    recorded::REPLICATOR_SNAPSHOT_PERCENT,
+                    percent,
+                    "table" => table_name.clone()
+                );
             }
         }
 
@@ -282,7 +578,18 @@ impl MySqlReplicator {
             table_mutator.insert_many(rows).await.map_err(log_err)?;
         }
 
+        if let Some(max) = auto_increment.max() {
+            noria
+                .record_auto_increments(HashMap::from([(
+                    table_mutator.table_name().to_owned(),
+                    max,
+                )]))
+                .await
+                .map_err(log_err)?;
+        }
+
         info!(rows_replicated = %cnt, "Replication finished");
+        gauge!(recorded::REPLICATOR_SNAPSHOT_PERCENT, 100.0, "table" => table_name);
 
         Ok(())
     }
@@ -297,19 +604,24 @@ impl MySqlReplicator {
     ///   the schema
     /// * `extend_recipe`: Replicate and install the recipe (`CREATE TABLE` ...; `CREATE VIEW` ...;)
     ///   in addition to the rows
+    ///
+    /// Tables whose snapshot was interrupted part of the way through resume after the last row
+    /// recorded in their [`SnapshotProgress`]. The rows copied after resuming may reflect changes
+    /// made after the table's replication offset, so if any table was resumed this returns the
+    /// offset that replication must catch up to before the tables are consistent.
     pub async fn snapshot_to_noria(
         mut self,
         noria: &mut readyset::ControllerHandle,
         replication_offsets: &ReplicationOffsets,
         install_recipe: bool,
-    ) -> ReadySetResult<()> {
+    ) -> ReadySetResult<Option<ReplicationOffset>> {
         let result = self
             .replicate_to_noria_with_table_locks(noria, replication_offsets, install_recipe)
             .await;
 
         // Wait for all connections to finish, not strictly neccessary
         self.pool.disconnect().await?;
-        result
+        result.map(|_| self.resumed_offset)
     }
 
     /// This is a fallback method to obtaining a database lock, that obtains table level locks
@@ -317,6 +629,9 @@ impl MySqlReplicator {
     /// is that some `CREATE TABLE` or `CREATE VIEW` statements may be missed if they happen to
     /// execute at the narrow timeframe of us reading the tables list, and us reading the binlog
     /// offset.
+    ///
+    /// The rows of the tables are still copied at a single binlog position if possible, see
+    /// [`MySqlReplicator::consistent_snapshot`].
     async fn replicate_to_noria_with_table_locks(
         &mut self,
         noria: &mut readyset::ControllerHandle,
@@ -358,39 +673,121 @@ impl MySqlReplicator {
 
     /// Spawns a new tokio task that replicates a given table to ReadySet, returning
     /// the join handle
+    ///
+    /// The table is copied with one of the transactions of `snapshot` if there are any left,
+    /// otherwise it is locked so that it can be copied at a binlog position of its own.
     async fn dumper_task_for_table(
         &mut self,
         noria: &mut readyset::ControllerHandle,
         table_name: String,
-    ) -> ReadySetResult<JoinHandle<(String, ReplicationOffset, ReadySetResult<()>)>> {
+        snapshot: &mut Option<ConsistentSnapshot>,
+    ) -> ReadySetResult<JoinHandle<DumperTaskOutput>> {
         let span = info_span!("replicating table", table = %table_name);
-        span.in_scope(|| info!("Acquiring read lock"));
-        let mut read_lock = self.lock_table(&table_name).await?;
-        // We acquire the position for each table individually, since it changes from
-        // one lock to the other
-        let repl_offset = ReplicationOffset::try_from(self.get_binlog_position().await?)?;
+        // Looked up before the table is locked, since the columns of the base table determine
+        // which columns are copied
+        let table_mutator = noria.table(&table_name).instrument(span.clone()).await?;
+        let (read_lock, mut tx, mut repl_offset) =
+            match snapshot.as_mut().and_then(ConsistentSnapshot::take) {
+                Some((tx, repl_offset)) => (None, tx, repl_offset),
+                None => {
+                    span.in_scope(|| info!("Acquiring read lock"));
+                    let read_lock = self.lock_table(&table_name).await?;
+                    // We acquire the position for each table individually, since it changes from
+                    // one lock to the other
+                    let repl_offset =
+                        ReplicationOffset::try_from(self.get_binlog_position().await?)?;
+                    let tx = self
+                        .start_snapshot_transaction()
+                        .instrument(span.clone())
+                        .await?;
+                    (Some(read_lock), tx, repl_offset)
+                }
+            };
+        // Only a transaction from the consistent snapshot can be reused for another table
+        let reusable = read_lock.is_none();
         span.in_scope(|| info!("Replicating table"));
 
-        let dumper = self
-            .dump_table(&table_name)
-            .instrument(span.clone())
-            .await?;
+        let primary_key = primary_key_for_table(&mut tx, &table_name).await?;
+        let progress = if primary_key.is_empty() {
+            None
+        } else {
+            noria.snapshot_progress().await?.remove(&table_name)
+        };
+
+        // If we can't resume the snapshot for some reason, start over just as we would if no
+        // progress had been recorded
+        let resume_from = progress.and_then(|progress| {
+            if progress.last_key.len() != primary_key.len() {
+                span.in_scope(|| warn!("Primary key changed, restarting interrupted snapshot"));
+                return None;
+            }
+            match progress
+                .last_key
+                .iter()
+                .map(noria_value_to_mysql)
+                .collect::<ReadySetResult<Vec<_>>>()
+            {
+                Ok(params) => Some((progress, params)),
+                Err(error) => {
+                    span.in_scope(|| {
+                        warn!(%error, "Unsupported primary key, restarting interrupted snapshot")
+                    });
+                    None
+                }
+            }
+        });
+
+        if let Some((progress, _)) = &resume_from {
+            span.in_scope(|| {
+                info!(
+                    rows_copied = %progress.rows_copied,
+                    offset = %progress.offset,
+                    "Resuming interrupted snapshot"
+                )
+            });
+            // Rows copied from here on reflect the binlog at the current position, so the table
+            // will only be consistent once replication has caught up to it
+            repl_offset.try_max_into(&mut self.resumed_offset)?;
+            repl_offset = progress.offset.clone();
+        }
+
+        let mut dumper = self.dump_table(
+            tx,
+            &table_name,
+            table_mutator.columns(),
+            primary_key,
+            resume_from,
+        );
 
         // At this point we have a transaction that will see *that* table at *this* binlog
         // position, so we can drop the read lock
-        read_lock.query_drop("UNLOCK TABLES").await?;
-        span.in_scope(|| info!("Read lock released"));
-
-        let table_mutator = noria.table(&table_name).instrument(span.clone()).await?;
+        if let Some(mut read_lock) = read_lock {
+            read_lock.query_drop("UNLOCK TABLES").await?;
+            span.in_scope(|| info!("Read lock released"));
+        }
 
-        Ok(tokio::spawn(async {
-            (
-                table_name,
-                repl_offset,
-                Self::replicate_table(dumper, table_mutator)
-                    .instrument(span)
-                    .await,
+        let nullified = self.column_filter.nullified_indices(&table_mutator);
+
+        let noria = noria.clone();
+        Ok(tokio::spawn(async move {
+            let res = Self::replicate_table(
+                table_name.clone(),
+                &mut dumper,
+                table_mutator,
+                noria,
+                repl_offset.clone(),
+                nullified,
             )
+            .instrument(span)
+            .await;
+            // If the copy failed the transaction may be left in the middle of a query, so it's
+            // not worth trying to reuse
+            let tx = if reusable && res.is_ok() {
+                Some(dumper.tx)
+            } else {
+                None
+            };
+            (table_name, repl_offset, res, tx)
         }))
     }
 
@@ -404,16 +801,41 @@ impl MySqlReplicator {
         let mut compacting_tasks = FuturesUnordered::new();
 
         let mut table_list = self.tables.clone().expect("Must have loaded table list");
+        let parallelism = self.snapshot_parallelism.max(1);
+        info!(tables = table_list.len(), parallelism, "Replicating tables");
+
+        // Copy as many tables as we can at a single binlog position, so that they are consistent
+        // with each other and with the position replication starts from
+        let pending = table_list
+            .iter()
+            .filter(|table_name| !replication_offsets.has_table(table_name.as_str()))
+            .count();
+        let mut snapshot = if pending > 0 {
+            self.consistent_snapshot(parallelism.min(pending)).await?
+        } else {
+            None
+        };
+
+        // Progress may still be recorded for tables that finished snapshotting just before we
+        // were interrupted, which must not be used if the table is ever snapshotted again
+        for table_name in noria.snapshot_progress().await?.into_keys() {
+            if replication_offsets.has_table(table_name.as_str()) {
+                noria.set_snapshot_progress(&table_name, None).await?;
+            }
+        }
 
         // For each table we spawn a new task to parallelize the replication process, with a limit
         while let Some(table_name) = table_list.pop() {
             if replication_offsets.has_table(table_name.as_str()) {
                 info!(%table_name, "Replication offset already exists for table, skipping snapshot");
             } else {
-                replication_tasks.push(self.dumper_task_for_table(noria, table_name).await?);
+                replication_tasks.push(
+                    self.dumper_task_for_table(noria, table_name, &mut snapshot)
+                        .await?,
+                );
             }
 
-            if replication_tasks.len() == MAX_SNAPSHOT_BATCH {
+            if replication_tasks.len() == parallelism {
                 break;
             }
         }
@@ -421,8 +843,13 @@ impl MySqlReplicator {
         while let Some(task_result) = replication_tasks.next().await {
             // The unwrap is for the join handle in that case
             match task_result.unwrap() {
-                (table_name, repl_offset, Ok(())) => {
+                (table_name, repl_offset, Ok(()), tx) => {
+                    if let (Some(snapshot), Some(tx)) = (snapshot.as_mut(), tx) {
+                        snapshot.transactions.push(tx);
+                    }
+
                     let mut noria_table = noria.table(&table_name).await?;
+                    let mut noria = noria.clone();
                     compacting_tasks.push(tokio::spawn(async move {
                         let span = info_span!("Compacting table", table = %table_name);
                         span.in_scope(|| info!("Setting replication offset"));
@@ -432,6 +859,13 @@ impl MySqlReplicator {
                             .instrument(span.clone())
                             .await?;
 
+                        // The table is fully copied, so it no longer needs its snapshot progress
+                        noria
+                            .set_snapshot_progress(&table_name, None)
+                            .map_err(log_err)
+                            .instrument(span.clone())
+                            .await?;
+
                         span.in_scope(|| info!("Set replication offset, compacting table"));
                         noria_table
                             .set_snapshot_mode(false)
@@ -443,19 +877,25 @@ impl MySqlReplicator {
                         ReadySetResult::Ok(())
                     }));
                 }
-                (table_name, _, Err(err)) => {
+                (table_name, _, Err(err), _) => {
                     error!(table = %table_name, error = %err, "Replication failed, retrying");
-                    replication_tasks.push(self.dumper_task_for_table(noria, table_name).await?);
+                    replication_tasks.push(
+                        self.dumper_task_for_table(noria, table_name, &mut snapshot)
+                            .await?,
+                    );
                 }
             }
 
             // If still have tables to snapshot add them to the task list
-            while replication_tasks.len() < MAX_SNAPSHOT_BATCH && !table_list.is_empty() {
+            while replication_tasks.len() < parallelism && !table_list.is_empty() {
                 let table_name = table_list.pop().expect("Not empty");
                 if replication_offsets.has_table(table_name.as_str()) {
                     info!(%table_name, "Replication offset already exists for table, skipping snapshot");
                 } else {
-                    replication_tasks.push(self.dumper_task_for_table(noria, table_name).await?);
+                    replication_tasks.push(
+                        self.dumper_task_for_table(noria, table_name, &mut snapshot)
+                            .await?,
+                    );
                 }
             }
         }
@@ -475,13 +915,19 @@ impl MySqlReplicator {
 pub struct TableDumper {
     query_count: String,
     query: String,
+    /// Parameters for both `query_count` and `query`
+    params: Vec<mysql::Value>,
+    /// The positions of the columns in the table's primary key, if it has one
+    key: Vec<usize>,
+    /// The progress of the snapshot of the table that this dumper resumes, if any
+    progress: Option<SnapshotProgress>,
     tx: mysql::Transaction<'static>,
 }
 
 impl TableDumper {
     pub async fn stream(&mut self) -> mysql::Result<TableStream<'_>> {
         Ok(TableStream {
-            query: self.tx.exec_iter(&self.query, ()).await?,
+            query: self.tx.exec_iter(&self.query, self.params.clone()).await?,
         })
     }
 }
@@ -509,6 +955,24 @@ fn mysql_row_to_noria_row(row: mysql::Row) -> ReadySetResult<Vec<readyset_data::
     Ok(noria_row)
 }
 
+/// Convert a ReadySet value into a MySQL value that can be used as a query parameter
+fn noria_value_to_mysql(val: &DataType) -> ReadySetResult<mysql::Value> {
+    Ok(match mysql_common::value::Value::try_from(val)? {
+        mysql_common::value::Value::NULL => mysql::Value::NULL,
+        mysql_common::value::Value::Bytes(b) => mysql::Value::Bytes(b),
+        mysql_common::value::Value::Int(i) => mysql::Value::Int(i),
+        mysql_common::value::Value::UInt(u) => mysql::Value::UInt(u),
+        mysql_common::value::Value::Float(f) => mysql::Value::Float(f),
+        mysql_common::value::Value::Double(d) => mysql::Value::Double(d),
+        mysql_common::value::Value::Date(y, m, d, hh, mm, ss, us) => {
+            mysql::Value::Date(y, m, d, hh, mm, ss, us)
+        }
+        mysql_common::value::Value::Time(is_neg, d, hh, mm, ss, us) => {
+            mysql::Value::Time(is_neg, d, hh, mm, ss, us)
+        }
+    })
+}
+
 /// Although both are of the exact same type, there is a conflict between reexported versions
 fn value_to_value(val: &mysql::Value) -> mysql_common::value::Value {
     match val {
@@ -1,6 +1,8 @@
 use async_trait::async_trait;
 use futures::FutureExt;
 use launchpad::select;
+use nom_sql::Dialect;
+use postgres_native_tls::MakeTlsConnector;
 use readyset::replication::ReplicationOffset;
 use readyset::{ReadySetError, ReadySetResult, TableOperation};
 use tokio_postgres as pgsql;
@@ -8,9 +10,9 @@ use tracing::{debug, error, trace};
 
 use super::ddl_replication::setup_ddl_replication;
 use super::wal_reader::{WalEvent, WalReader};
-use super::{PostgresPosition, PUBLICATION_NAME, REPLICATION_SLOT};
-use crate::readyset_adapter::{Connector, ReplicationAction};
+use super::{slot, PostgresPosition, PUBLICATION_NAME, REPLICATION_SLOT};
 use crate::postgres_connector::ddl_replication::DdlEvent;
+use crate::readyset_adapter::{Connector, ReplicationAction};
 
 /// A connector that connects to a PostgreSQL server and starts reading WAL from the "noria"
 /// replication slot with the "noria" publication.
@@ -72,21 +74,20 @@ pub struct CreatedSlot {
 }
 
 impl PostgresWalConnector {
-    /// Connects to postgres and if needed creates a new replication slot for itself with an
-    /// exported snapshot.
+    /// Connects to postgres using `tls` and if needed creates a new replication slot for itself
+    /// with an exported snapshot.
     pub async fn connect<S: AsRef<str>>(
         mut config: pgsql::Config,
+        tls: MakeTlsConnector,
         dbname: S,
         next_position: Option<PostgresPosition>,
     ) -> ReadySetResult<Self> {
-        let connector = native_tls::TlsConnector::builder().build().unwrap(); // Never returns an error
-        let connector = postgres_native_tls::MakeTlsConnector::new(connector);
-
-        setup_ddl_replication(config.clone(), connector.clone()).await?;
+        setup_ddl_replication(config.clone(), tls.clone()).await?;
 
+        let regular_config = config.clone();
         config.dbname(dbname.as_ref()).set_replication_database();
 
-        let (client, connection) = config.connect(connector).await?;
+        let (client, connection) = config.connect(tls.clone()).await?;
         let connection_handle = tokio::spawn(connection);
 
         let mut connector = PostgresWalConnector {
@@ -99,19 +100,28 @@ impl PostgresWalConnector {
         };
 
         if next_position.is_none() {
-            connector.create_publication_and_slot().await?;
+            connector
+                .create_publication_and_slot(&regular_config, &tls)
+                .await?;
         }
 
         Ok(connector)
     }
 
-    async fn create_publication_and_slot(&mut self) -> ReadySetResult<()> {
+    /// Creates the publication and replication slot used by the replicator. `regular_config` and
+    /// `tls` are used to open regular, non-replication connections to the same database.
+    async fn create_publication_and_slot(
+        &mut self,
+        regular_config: &pgsql::Config,
+        tls: &MakeTlsConnector,
+    ) -> ReadySetResult<()> {
         let system = self.identify_system().await?;
         debug!(?system);
 
         match self.create_publication(PUBLICATION_NAME).await {
             Ok(()) => {
-                // Created a new publication, everything is good
+                // Created a new publication, so mark it as ours
+                slot::tag_publication(regular_config, tls).await?;
             }
             Err(err)
                 if err.to_string().contains("publication")
@@ -125,7 +135,8 @@ impl PostgresWalConnector {
             Err(err) => return Err(err),
         }
 
-        // Drop the existing slot if any
+        // Drop the existing slot if any, unless another replicator is still streaming from it
+        slot::check_slot_not_in_use(regular_config, tls).await?;
         let _ = self.drop_replication_slot(REPLICATION_SLOT).await;
 
         match self.create_replication_slot(REPLICATION_SLOT).await {
@@ -379,6 +390,7 @@ impl Connector for PostgresWalConnector {
                         table: cur_table,
                         actions,
                         txid: None,
+                        default_columns: Vec::new(),
                     },
                     cur_lsn.into(),
                 ));
@@ -399,6 +411,7 @@ impl Connector for PostgresWalConnector {
                 | WalEvent::DeleteByKey { table, .. }
                 | WalEvent::UpdateRow { table, .. }
                 | WalEvent::UpdateByKey { table, .. }
+                | WalEvent::Truncate { table }
                     if table.as_str() != cur_table =>
                 {
                     if !actions.is_empty() {
@@ -408,6 +421,7 @@ impl Connector for PostgresWalConnector {
                                 table: cur_table,
                                 actions,
                                 txid: None,
+                                default_columns: Vec::new(),
                             },
                             cur_lsn.into(),
                         ));
@@ -426,6 +440,8 @@ impl Connector for PostgresWalConnector {
                         return Ok((
                             ReplicationAction::SchemaChange {
                                 ddl: ddl_event.to_ddl(),
+                                // `to_ddl` returns statements in the ReadySet-native dialect
+                                dialect: Dialect::MySQL,
                             },
                             PostgresPosition::from(lsn).into(),
                         ));
@@ -436,6 +452,7 @@ impl Connector for PostgresWalConnector {
                                 table: cur_table,
                                 actions,
                                 txid: None,
+                                default_columns: Vec::new(),
                             },
                             cur_lsn.into(),
                         ));
@@ -463,6 +480,7 @@ impl Connector for PostgresWalConnector {
                                 table: cur_table,
                                 actions,
                                 txid: None,
+                                default_columns: Vec::new(),
                             },
                             cur_lsn.into(),
                         ));
@@ -486,6 +504,7 @@ impl Connector for PostgresWalConnector {
                 WalEvent::UpdateByKey { key, set, .. } => {
                     actions.push(TableOperation::Update { key, update: set })
                 }
+                WalEvent::Truncate { .. } => actions.push(TableOperation::Truncate),
             }
         }
     }
@@ -175,10 +175,12 @@ impl<'a> DdlEvent<'a> {
                     schema: Some(self.schema_name.into()),
                     name: self.object_name.into(),
                     alias: None,
+                    sample: None,
                 }],
                 // We might be getting a drop table event for a table we don't have, eg if the table
                 // originally failed to parse
                 if_exists: true,
+                temporary: false,
             }
             .to_string(),
             DdlEventKind::DropView => DropViewStatement {
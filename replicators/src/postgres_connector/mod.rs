@@ -1,5 +1,6 @@
 mod connector;
 mod ddl_replication;
+mod slot;
 mod snapshot;
 mod wal;
 mod wal_reader;
@@ -8,6 +9,7 @@ use std::fmt::{self, Display};
 
 pub use connector::PostgresWalConnector;
 use readyset::replication::ReplicationOffset;
+pub(crate) use slot::{drop_publication_and_slot, monitor_replication_slot};
 pub use snapshot::PostgresReplicator;
 
 pub(crate) const REPLICATION_SLOT: &str = "readyset";
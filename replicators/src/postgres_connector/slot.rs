@@ -0,0 +1,228 @@
+//! Management and monitoring of the logical replication slot and publication used by the
+//! PostgreSQL replicator.
+//!
+//! A logical replication slot causes the upstream database to retain all WAL from the slot's
+//! `restart_lsn` onwards, so a slot that is left behind (or that falls far behind) can eventually
+//! fill up the upstream database's disk. To make this easier to keep track of:
+//!
+//! * The publication created by the replicator is tagged with [`OWNERSHIP_COMMENT`], so that it can
+//!   be told apart from a publication that happens to have the same name but was created by someone
+//!   else.
+//! * While replicating, the state of the slot is periodically [recorded][monitor] with the
+//!   controller (so it can be shown by `SHOW READYSET REPLICATION STATUS`) and reported via the
+//!   [`REPLICATOR_SLOT_LAG_BYTES`][lag] metric.
+//! * [`drop_publication_and_slot`] removes the slot and publication once they're no longer needed,
+//!   refusing to remove either if it's still in use or wasn't created by ReadySet.
+//!
+//! All of these use a regular connection to the database, rather than the replication connection
+//! used to stream WAL.
+//!
+//! [monitor]: monitor_replication_slot
+//! [lag]: readyset::metrics::recorded::REPLICATOR_SLOT_LAG_BYTES
+
+use std::time::Duration;
+
+use metrics::gauge;
+use postgres_native_tls::MakeTlsConnector;
+use readyset::metrics::recorded;
+use readyset::replication::ReplicationSlotStatus;
+use readyset::{ControllerHandle, ReadySetError, ReadySetResult};
+use tokio_postgres as pgsql;
+use tracing::{debug, info, warn};
+
+use super::{PUBLICATION_NAME, REPLICATION_SLOT};
+
+/// The comment set on the publication created by the replicator, to mark it as owned by ReadySet
+pub(crate) const OWNERSHIP_COMMENT: &str = "Created by ReadySet for replication";
+
+/// How often to record the state of the replication slot while replicating
+const MONITOR_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Open a new regular (non-replication) connection to the database
+async fn connect(
+    config: &pgsql::Config,
+    tls: &MakeTlsConnector,
+) -> ReadySetResult<(
+    pgsql::Client,
+    tokio::task::JoinHandle<Result<(), pgsql::Error>>,
+)> {
+    let (client, connection) = config.connect(tls.clone()).await?;
+    Ok((client, tokio::spawn(connection)))
+}
+
+/// Read the state of the replication slot with the given name, if it exists
+pub(crate) async fn slot_status(
+    client: &pgsql::Client,
+    slot: &str,
+) -> ReadySetResult<Option<ReplicationSlotStatus>> {
+    let row = client
+        .query_opt(
+            "SELECT slot_name::text, active, restart_lsn::text, confirmed_flush_lsn::text, \
+             pg_wal_lsn_diff(pg_current_wal_lsn(), restart_lsn)::bigint \
+             FROM pg_replication_slots WHERE slot_name = $1",
+            &[&slot],
+        )
+        .await?;
+
+    Ok(row.map(|row| ReplicationSlotStatus {
+        slot_name: row.get(0),
+        active: row.get(1),
+        restart_lsn: row.get(2),
+        confirmed_flush_lsn: row.get(3),
+        lag_bytes: row.get::<_, Option<i64>>(4).map(|lag| lag.max(0) as u64),
+    }))
+}
+
+/// Mark the publication created by the replicator as owned by ReadySet
+pub(crate) async fn tag_publication(
+    config: &pgsql::Config,
+    tls: &MakeTlsConnector,
+) -> ReadySetResult<()> {
+    let (client, connection_handle) = connect(config, tls).await?;
+    client
+        .batch_execute(&format!(
+            "COMMENT ON PUBLICATION {} IS '{}'",
+            PUBLICATION_NAME, OWNERSHIP_COMMENT
+        ))
+        .await?;
+    connection_handle.abort();
+    Ok(())
+}
+
+/// Returns an error if the replication slot used by the replicator exists and is currently in
+/// use, which means that another replicator is streaming from it.
+pub(crate) async fn check_slot_not_in_use(
+    config: &pgsql::Config,
+    tls: &MakeTlsConnector,
+) -> ReadySetResult<()> {
+    let (client, connection_handle) = connect(config, tls).await?;
+    let status = slot_status(&client, REPLICATION_SLOT).await;
+    connection_handle.abort();
+
+    match status? {
+        Some(status) if status.active => Err(ReadySetError::ReplicationFailed(format!(
+            "Replication slot {} is in use by another process",
+            REPLICATION_SLOT
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Periodically record the state of the replication slot with the controller, and report its lag
+/// as a metric. This runs until it's aborted.
+///
+/// Failing to read or record the state of the slot doesn't affect replication, so errors are
+/// logged and retried at the next interval.
+pub(crate) async fn monitor_replication_slot(
+    config: pgsql::Config,
+    tls: MakeTlsConnector,
+    mut noria: ControllerHandle,
+) {
+    let mut interval = tokio::time::interval(MONITOR_INTERVAL);
+    let mut client = None;
+
+    loop {
+        interval.tick().await;
+
+        if client.is_none() {
+            match connect(&config, &tls).await {
+                Ok(c) => client = Some(c),
+                Err(error) => {
+                    warn!(%error, "Error connecting to monitor replication slot");
+                    continue;
+                }
+            }
+        }
+        let (c, _) = client.as_ref().unwrap();
+
+        let status = match slot_status(c, REPLICATION_SLOT).await {
+            Ok(status) => status,
+            Err(error) => {
+                warn!(%error, "Error reading replication slot status");
+                if let Some((_, connection_handle)) = client.take() {
+                    connection_handle.abort();
+                }
+                continue;
+            }
+        };
+
+        if let Some(lag_bytes) = status.as_ref().and_then(|status| status.lag_bytes) {
+            gauge!(recorded::REPLICATOR_SLOT_LAG_BYTES, lag_bytes as f64);
+        }
+        debug!(?status, "Recording replication slot status");
+        if let Err(error) = noria.set_replication_slot_status(status).await {
+            warn!(%error, "Error recording replication slot status");
+        }
+    }
+}
+
+/// Drop the replication slot and publication used by the replicator, so that the upstream
+/// database no longer retains WAL on ReadySet's behalf.
+///
+/// This fails if the slot is currently in use. The slot is only dropped if it's a `pgoutput`
+/// logical slot in the connected database, and the publication is only dropped if it was created
+/// by ReadySet, since objects with the same names may belong to someone else.
+pub(crate) async fn drop_publication_and_slot(
+    config: &pgsql::Config,
+    tls: &MakeTlsConnector,
+) -> ReadySetResult<()> {
+    let (client, connection_handle) = connect(config, tls).await?;
+    let res = drop_publication_and_slot_inner(&client).await;
+    connection_handle.abort();
+    res
+}
+
+async fn drop_publication_and_slot_inner(client: &pgsql::Client) -> ReadySetResult<()> {
+    let slot = client
+        .query_opt(
+            "SELECT active, plugin = 'pgoutput' AND database = current_database() \
+             FROM pg_replication_slots WHERE slot_name = $1",
+            &[&REPLICATION_SLOT],
+        )
+        .await?;
+    match slot.map(|row| (row.get::<_, bool>(0), row.get::<_, Option<bool>>(1))) {
+        Some((true, _)) => {
+            return Err(ReadySetError::ReplicationFailed(format!(
+                "Replication slot {} is in use, not dropping",
+                REPLICATION_SLOT
+            )))
+        }
+        Some((false, Some(true))) => {
+            client
+                .execute("SELECT pg_drop_replication_slot($1)", &[&REPLICATION_SLOT])
+                .await?;
+            info!(slot = %REPLICATION_SLOT, "Dropped replication slot");
+        }
+        Some((false, _)) => {
+            warn!(
+                slot = %REPLICATION_SLOT,
+                "Replication slot was not created by ReadySet, not dropping"
+            );
+        }
+        None => debug!(slot = %REPLICATION_SLOT, "Replication slot does not exist"),
+    }
+
+    let publication = client
+        .query_opt(
+            "SELECT obj_description(oid, 'pg_publication') FROM pg_publication WHERE pubname = $1",
+            &[&PUBLICATION_NAME],
+        )
+        .await?;
+    match publication.map(|row| row.get::<_, Option<String>>(0)) {
+        Some(Some(comment)) if comment == OWNERSHIP_COMMENT => {
+            client
+                .batch_execute(&format!("DROP PUBLICATION {}", PUBLICATION_NAME))
+                .await?;
+            info!(publication = %PUBLICATION_NAME, "Dropped publication");
+        }
+        Some(_) => {
+            warn!(
+                publication = %PUBLICATION_NAME,
+                "Publication was not created by ReadySet, not dropping"
+            );
+        }
+        None => debug!(publication = %PUBLICATION_NAME, "Publication does not exist"),
+    }
+
+    Ok(())
+}
@@ -1,28 +1,47 @@
+use std::collections::{HashMap, VecDeque};
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt::{self, Display};
 use std::future;
+use std::sync::{Arc, Mutex};
 
 use futures::stream::FuturesUnordered;
 use futures::{pin_mut, StreamExt, TryFutureExt};
 use launchpad::redacted::Sensitive;
+use metrics::gauge;
 use nom_sql::{parse_key_specification_string, Dialect, TableKey};
-use readyset::ReadySetResult;
-use readyset_data::DataType;
+use postgres_native_tls::MakeTlsConnector;
 use postgres_types::{accepts, FromSql, Type};
+use readyset::metrics::recorded;
+use readyset::recipe::changelist::ChangeList;
+use readyset::{ReadySetError, ReadySetResult};
+use readyset_data::DataType;
 use tokio_postgres as pgsql;
 use tracing::{debug, error, info, info_span, trace, Instrument};
 
 use super::PostgresPosition;
+use crate::auto_increment::SnapshotAutoIncrement;
+use crate::column_filter::{nullify_row, ColumnFilter};
 
 const BATCH_SIZE: usize = 1024; // How many queries to buffer before pushing to ReadySet
 
+/// A queue of tables waiting to be copied into ReadySet, shared between snapshot workers
+type TableQueue = Arc<Mutex<VecDeque<(TableDescription, readyset::Table)>>>;
+
 pub struct PostgresReplicator<'a> {
     /// This is the underlying (regular) PostgreSQL transaction
     pub(crate) transaction: pgsql::Transaction<'a>,
+    /// The options used to open additional connections, to copy tables in parallel
+    pub(crate) pgsql_opts: pgsql::Config,
+    /// The TLS connector used to open additional connections
+    pub(crate) tls: MakeTlsConnector,
     pub(crate) noria: &'a mut readyset::ControllerHandle,
     /// If Some then only snapshot those tables, otherwise will snapshot all tables
     pub(crate) tables: Option<Vec<String>>,
+    /// The maximum number of tables to copy at the same time
+    pub(crate) snapshot_parallelism: usize,
+    /// Columns whose values are replaced with NULL, or that are dropped, when written to ReadySet
+    pub(crate) column_filter: ColumnFilter,
 }
 
 #[derive(Debug)]
@@ -61,6 +80,9 @@ struct ColumnEntry {
     definition: String,
     not_null: bool,
     type_oid: Type,
+    /// Whether the column is an identity column or a `SERIAL` column, whose default value is
+    /// generated by a sequence
+    auto_increment: bool,
 }
 
 #[derive(Debug)]
@@ -97,6 +119,7 @@ impl TryFrom<pgsql::Row> for ColumnEntry {
             definition: row.try_get(1)?,
             not_null: row.try_get(2)?,
             type_oid: Type::from_oid(row.try_get(3)?).unwrap(),
+            auto_increment: row.try_get(4)?,
         })
     }
 }
@@ -105,10 +128,15 @@ impl Display for ColumnEntry {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "`{}` {}{}",
+            "`{}` {}{}{}",
             self.name,
             self.definition,
             if self.not_null { " NOT NULL" } else { "" },
+            if self.auto_increment {
+                " AUTO_INCREMENT"
+            } else {
+                ""
+            },
         )
     }
 }
@@ -156,8 +184,11 @@ impl TableEntry {
         transaction: &'a pgsql::Transaction<'a>,
     ) -> Result<Vec<ColumnEntry>, pgsql::Error> {
         let query = r"
-            SELECT a.attname, pg_catalog.format_type(a.atttypid, a.atttypmod), a.attnotnull, a.atttypid
-            FROM pg_catalog.pg_attribute a WHERE a.attrelid = $1 AND a.attnum > 0 AND NOT a.attisdropped
+            SELECT a.attname, pg_catalog.format_type(a.atttypid, a.atttypmod), a.attnotnull, a.atttypid,
+                a.attidentity <> '' OR coalesce(pg_catalog.pg_get_expr(d.adbin, d.adrelid) LIKE 'nextval(%', false)
+            FROM pg_catalog.pg_attribute a
+            LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE a.attrelid = $1 AND a.attnum > 0 AND NOT a.attisdropped
             ";
 
         let columns = transaction.query(query, &[&oid]).await?;
@@ -233,13 +264,17 @@ impl Display for TableDescription {
 }
 
 impl TableDescription {
-    /// Copy a table's contents from PostgreSQL to ReadySet
+    /// Copy a table's contents from PostgreSQL to ReadySet, replacing the values of the columns at
+    /// the positions in `nullified` with NULL. Returns the largest value copied into the table's
+    /// auto-increment column, if it has one.
     async fn dump<'a>(
         &self,
         transaction: &'a pgsql::Transaction<'a>,
         mut noria_table: readyset::Table,
-    ) -> ReadySetResult<()> {
+        nullified: &[usize],
+    ) -> ReadySetResult<Option<u64>> {
         let mut cnt = 0;
+        let mut auto_increment = SnapshotAutoIncrement::new(&noria_table);
 
         let nrows = transaction
             .query_one(
@@ -249,11 +284,22 @@ impl TableDescription {
             .await?
             .try_get::<_, i64>("nrows")?;
 
+        // Only the columns of the base table are copied, leaving out any that are dropped
+        let columns = self
+            .columns
+            .iter()
+            .filter(|c| noria_table.columns().iter().any(|n| n == c.name.as_str()))
+            .collect::<Vec<_>>();
+
         // The most efficient way to copy an entire table is COPY BINARY
-        let query = format!("COPY \"{}\" TO stdout BINARY", self.name);
+        let query = format!(
+            "COPY \"{}\" ({}) TO stdout BINARY",
+            self.name,
+            itertools::join(columns.iter().map(|c| format!("\"{}\"", c.name)), ", ")
+        );
         let rows = transaction.copy_out(query.as_str()).await?;
 
-        let type_map: Vec<_> = self.columns.iter().map(|c| c.type_oid.clone()).collect();
+        let type_map: Vec<_> = columns.iter().map(|c| c.type_oid.clone()).collect();
         let binary_rows = pgsql::binary_copy::BinaryCopyOutStream::new(rows, &type_map);
 
         pin_mut!(binary_rows);
@@ -261,10 +307,13 @@ impl TableDescription {
         let mut noria_rows = Vec::with_capacity(BATCH_SIZE);
 
         info!(rows = %nrows, "Replication started");
+        gauge!(recorded::REPLICATOR_SNAPSHOT_PERCENT, 0.0, "table" => self.name.clone());
         while let Some(Ok(row)) = binary_rows.next().await {
-            let noria_row = (0..type_map.len())
+            let mut noria_row = (0..type_map.len())
                 .map(|i| row.try_get::<DataType>(i))
                 .collect::<Result<Vec<_>, _>>()?;
+            nullify_row(&mut noria_row, nullified);
+            auto_increment.observe(&noria_row);
 
             noria_rows.push(noria_row);
             cnt += 1;
@@ -281,8 +330,14 @@ impl TableDescription {
             }
 
             if cnt % 1_000_000 == 0 {
-                let progress = format!("{:.2}%", (cnt as f64 / nrows as f64) * 100.);
+                let percent = (cnt as f64 / nrows as f64) * 100.;
+                let progress = format!("{:.2}%", percent);
                 info!(rows_replicated = %cnt, %progress, "Replication progress");
+                gauge!(
+                    recorded::REPLICATOR_SNAPSHOT_PERCENT,
+                    percent,
+                    "table" => self.name.clone()
+                );
             }
         }
 
@@ -291,16 +346,80 @@ impl TableDescription {
         }
 
         info!(rows_replicated = %cnt, "Replication finished");
+        gauge!(recorded::REPLICATOR_SNAPSHOT_PERCENT, 100.0, "table" => self.name.clone());
 
-        Ok(())
+        Ok(auto_increment.max())
+    }
+}
+
+/// Assign the specific snapshot to the given transaction
+async fn set_snapshot(
+    transaction: &pgsql::Transaction<'_>,
+    name: &str,
+) -> Result<(), pgsql::Error> {
+    let query = format!("SET TRANSACTION SNAPSHOT '{}'", name);
+    transaction.query(query.as_str(), &[]).await?;
+    Ok(())
+}
+
+/// Open a new connection to the upstream database, and copy tables from `queue` into ReadySet
+/// until it is empty, reading them at the point in time of the snapshot with the given name.
+/// Returns the largest value copied into the auto-increment column of each table that has one.
+async fn snapshot_worker(
+    pgsql_opts: pgsql::Config,
+    tls: MakeTlsConnector,
+    snapshot_name: String,
+    queue: TableQueue,
+    column_filter: ColumnFilter,
+) -> ReadySetResult<HashMap<String, u64>> {
+    let (mut client, connection) = pgsql_opts.connect(tls).await?;
+    let connection_handle = tokio::spawn(connection);
+
+    let transaction = client
+        .build_transaction()
+        .isolation_level(pgsql::IsolationLevel::RepeatableRead)
+        .read_only(true)
+        .start()
+        .await?;
+    set_snapshot(&transaction, &snapshot_name).await?;
+
+    let mut auto_increments = HashMap::new();
+    loop {
+        // Don't hold the lock across the await point below
+        let next = queue.lock().unwrap().pop_front();
+        let (table, noria_table) = match next {
+            Some(next) => next,
+            None => break,
+        };
+
+        let span = info_span!("Replicating table", table = %table.name);
+        span.in_scope(|| info!("Replicating table"));
+        let nullified = column_filter.nullified_indices(&noria_table);
+        let table_name = noria_table.table_name().to_owned();
+        if let Some(max) = table
+            .dump(&transaction, noria_table, &nullified)
+            .instrument(span)
+            .await?
+        {
+            auto_increments.insert(table_name, max);
+        }
     }
+
+    drop(transaction);
+    connection_handle.abort();
+
+    Ok(auto_increments)
 }
 
 impl<'a> PostgresReplicator<'a> {
     pub async fn new(
         client: &'a mut pgsql::Client,
+        pgsql_opts: &pgsql::Config,
+        tls: &MakeTlsConnector,
         noria: &'a mut readyset::ControllerHandle,
         tables: Option<Vec<String>>,
+        snapshot_parallelism: usize,
+        column_filter: ColumnFilter,
     ) -> ReadySetResult<PostgresReplicator<'a>> {
         let transaction = client
             .build_transaction()
@@ -312,13 +431,27 @@ impl<'a> PostgresReplicator<'a> {
 
         Ok(PostgresReplicator {
             transaction,
+            pgsql_opts: pgsql_opts.clone(),
+            tls: tls.clone(),
             noria,
             tables,
+            snapshot_parallelism,
+            column_filter,
         })
     }
 
     /// Begin the replication process, starting with the recipe for the database, followed
-    /// by each table's contents
+    /// by each table's contents.
+    ///
+    /// Table contents are copied by up to `snapshot_parallelism` workers, each with its own
+    /// connection to the upstream database. Every worker's transaction is set to the same
+    /// exported snapshot, so all tables are copied at a single consistent point in time.
+    ///
+    /// Unlike MySQL snapshots, the progress of copying each table isn't checkpointed as a
+    /// [`SnapshotProgress`](readyset::replication::SnapshotProgress), so an interrupted snapshot
+    /// can't be resumed: the exported snapshot only lives as long as the connection that created
+    /// the replication slot, and rows copied from a later snapshot wouldn't be consistent with the
+    /// slot's starting position. Instead, the next attempt drops the slot and starts over.
     pub async fn snapshot_to_noria<S: AsRef<str>>(
         &mut self,
         snapshot_name: S,
@@ -348,7 +481,9 @@ impl<'a> PostgresReplicator<'a> {
             };
 
             debug!(%create_table, "Extending recipe");
-            match future::ready(create_table.to_string().try_into())
+            let changelist = ChangeList::try_from(create_table.to_string())
+                .map(|changelist| self.column_filter.filter_changelist(changelist));
+            match future::ready(changelist)
                 .and_then(|changelist| async {
                     self.noria.extend_recipe_no_leader_ready(changelist).await
                 })
@@ -387,29 +522,61 @@ impl<'a> PostgresReplicator<'a> {
             .await?;
 
         // Finally copy each table into ReadySet
-        for table in &tables {
-            // TODO: parallelize with a connection pool if performance here matters
-            let span = info_span!("Replicating table", table = %table.name);
-            span.in_scope(|| info!("Replicating table"));
-            let mut noria_table = self
-                .noria
-                .table(&table.name)
-                .instrument(span.clone())
-                .await?;
-
+        let table_names = tables.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+        let mut queue = VecDeque::with_capacity(tables.len());
+        for table in tables {
+            let mut noria_table = self.noria.table(&table.name).await?;
             noria_table.set_snapshot_mode(true).await?;
+            queue.push_back((table, noria_table));
+        }
 
-            table
-                .dump(&self.transaction, noria_table)
-                .instrument(span.clone())
-                .await?;
+        let parallelism = self.snapshot_parallelism.clamp(1, queue.len().max(1));
+        info!(tables = queue.len(), parallelism, "Replicating tables");
+        info!("Snapshot progress isn't checkpointed, so an interrupted snapshot will start over");
+
+        let queue = Arc::new(Mutex::new(queue));
+        let mut workers = (0..parallelism)
+            .map(|_| {
+                tokio::spawn(
+                    snapshot_worker(
+                        self.pgsql_opts.clone(),
+                        self.tls.clone(),
+                        snapshot_name.as_ref().to_owned(),
+                        Arc::clone(&queue),
+                        self.column_filter.clone(),
+                    )
+                    .in_current_span(),
+                )
+            })
+            .collect::<FuturesUnordered<_>>();
+        let mut auto_increments = HashMap::new();
+        while let Some(worker_result) = workers.next().await {
+            // A worker that panicked or was cancelled fails the snapshot just like one that
+            // returned an error
+            let worker_result = worker_result.unwrap_or_else(|error| {
+                Err(ReadySetError::ReplicationFailed(format!(
+                    "Snapshot worker failed: {}",
+                    error
+                )))
+            });
+            match worker_result {
+                Ok(worker_auto_increments) => auto_increments.extend(worker_auto_increments),
+                Err(error) => {
+                    error!(%error, "Error replicating table");
+                    workers.iter().for_each(|worker| worker.abort());
+                    return Err(error);
+                }
+            }
+        }
+        if !auto_increments.is_empty() {
+            self.noria.record_auto_increments(auto_increments).await?;
         }
 
         let mut compacting = FuturesUnordered::new();
-        for table in tables {
-            let mut noria_table = self.noria.table(&table.name).await?;
+        for table_name in table_names {
+            let mut noria_table = self.noria.table(&table_name).await?;
             compacting.push(async move {
-                let span = info_span!("Compacting table", table = %table.name);
+                let span = info_span!("Compacting table", table = %table_name);
                 span.in_scope(|| info!("Setting replication offset"));
                 if let Err(error) = noria_table
                     .set_replication_offset(PostgresPosition::default().into())
@@ -458,9 +625,7 @@ impl<'a> PostgresReplicator<'a> {
 
     /// Assign the specific snapshot to the underlying transaction
     async fn set_snapshot(&mut self, name: &str) -> Result<(), pgsql::Error> {
-        let query = format!("SET TRANSACTION SNAPSHOT '{}'", name);
-        self.transaction.query(query.as_str(), &[]).await?;
-        Ok(())
+        set_snapshot(&self.transaction, name).await
     }
 }
 
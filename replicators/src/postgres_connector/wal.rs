@@ -19,6 +19,8 @@ pub enum WalError {
     CorruptTuple,
     UnknownTuple,
     CorruptRelation,
+    CorruptType,
+    CorruptOrigin,
     CorruptUpdate,
     CorruptInsert,
     CorruptDelete,
@@ -328,6 +330,8 @@ impl TryFrom<Bytes> for WalRecord {
             b'B' => WalRecord::begin(b),
             b'C' => WalRecord::commit(b),
             b'R' => WalRecord::relation(b),
+            b'Y' => WalRecord::data_type(b),
+            b'O' => WalRecord::origin(b),
             b'U' => WalRecord::update(b),
             b'I' => WalRecord::insert(b),
             b'D' => WalRecord::delete(b),
@@ -575,6 +579,49 @@ impl WalRecord {
         }))
     }
 
+    /// Parse as Type, assumes b[0] == 'Y'
+    fn data_type(mut b: Bytes) -> Result<Self, WalError> {
+        if b.len() < 5 {
+            return Err(WalError::CorruptType);
+        }
+
+        let id = i32::from_be_bytes(b[1..5].try_into()?);
+        let _ = b.split_to(5);
+
+        let namespace = Self::consume_string(&mut b)?;
+        let name = Self::consume_string(&mut b)?;
+
+        if !b.is_empty() {
+            // Should consume the entire record
+            return Err(WalError::CorruptType);
+        }
+
+        Ok(WalRecord::Type {
+            id,
+            namespace,
+            name,
+        })
+    }
+
+    /// Parse as Origin, assumes b[0] == 'O'
+    fn origin(mut b: Bytes) -> Result<Self, WalError> {
+        if b.len() < 9 {
+            return Err(WalError::CorruptOrigin);
+        }
+
+        let lsn = i64::from_be_bytes(b[1..9].try_into()?);
+        let _ = b.split_to(9);
+
+        let name = Self::consume_string(&mut b)?;
+
+        if !b.is_empty() {
+            // Should consume the entire record
+            return Err(WalError::CorruptOrigin);
+        }
+
+        Ok(WalRecord::Origin { lsn, name })
+    }
+
     /// Parse as Insert, assumes b[0] == 'I'
     fn insert(mut b: Bytes) -> Result<Self, WalError> {
         if b.len() < 6 || b[5] != b'N' {
@@ -798,4 +845,49 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn wal_parse_type() {
+        let wal: WalData = Bytes::copy_from_slice(
+            b"w\0\0\0\0\x01l\xafx\0\0\0\0\x01l\xafx\0\x02g?\x9e\xc7y\xbcY\0\0@0public\0mood\0",
+        )
+        .try_into()
+        .unwrap();
+
+        assert_eq!(
+            wal,
+            WalData::XLogData {
+                start: 23900024,
+                end: 23900024,
+                time: 676472897894844,
+                data: WalRecord::Type {
+                    id: 16432,
+                    namespace: Bytes::copy_from_slice(b"public"),
+                    name: Bytes::copy_from_slice(b"mood"),
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn wal_parse_origin() {
+        let wal: WalData = Bytes::copy_from_slice(
+            b"w\0\0\0\0\x01l\xafx\0\0\0\0\x01l\xafx\0\x02g?\x9e\xc7y\xbcO\0\0\0\0\x01l\xafxupstream\0",
+        )
+        .try_into()
+        .unwrap();
+
+        assert_eq!(
+            wal,
+            WalData::XLogData {
+                start: 23900024,
+                end: 23900024,
+                time: 676472897894844,
+                data: WalRecord::Origin {
+                    lsn: 23900024,
+                    name: Bytes::copy_from_slice(b"upstream"),
+                }
+            }
+        );
+    }
 }
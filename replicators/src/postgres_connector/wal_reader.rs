@@ -1,9 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bit_vec::BitVec;
+use metrics::gauge;
 use mysql_time::MysqlTime;
+use readyset::metrics::recorded;
 use readyset::{ReadySetError, ReadySetResult};
 use readyset_data::DataType;
 use readyset_errors::unsupported;
@@ -19,6 +22,11 @@ pub struct WalReader {
     wal: pgsql::client::Responses,
     /// Keeps track of the relation mappings that we had
     relations: HashMap<i32, (String, RelationMapping)>,
+    /// Keeps track of the user-defined types we were told about, by OID. The values are the
+    /// namespace and name of each type.
+    types: HashMap<u32, (String, String)>,
+    /// Events decoded from a single message which haven't been returned yet
+    pending: VecDeque<(WalEvent, i64)>,
 }
 
 #[derive(Debug)]
@@ -47,20 +55,34 @@ pub enum WalEvent {
         key: Vec<DataType>,
         set: Vec<readyset::Modification>,
     },
+    Truncate {
+        table: String,
+    },
 }
 
 impl WalReader {
     pub(crate) fn new(wal: pgsql::client::Responses) -> Self {
         WalReader {
             relations: Default::default(),
+            types: Default::default(),
+            pending: Default::default(),
             wal,
         }
     }
 
     pub(crate) async fn next_event(&mut self) -> ReadySetResult<(WalEvent, i64)> {
-        let WalReader { wal, relations } = self;
+        let WalReader {
+            wal,
+            relations,
+            types,
+            pending,
+        } = self;
 
         loop {
+            if let Some(event) = pending.pop_front() {
+                return Ok(event);
+            }
+
             let data: WalData = match wal.next().await? {
                 pgsql::Message::CopyData(body) => body.into_bytes().try_into()?,
                 _ => {
@@ -84,8 +106,24 @@ impl WalReader {
             trace!(?record);
 
             match record {
-                WalRecord::Commit { .. } => return Ok((WalEvent::Commit, end)),
-                WalRecord::Relation(mapping) => {
+                WalRecord::Commit { timestamp, .. } => {
+                    record_replication_lag(timestamp);
+                    return Ok((WalEvent::Commit, end));
+                }
+                WalRecord::Relation(mut mapping) => {
+                    // Columns with types that aren't built in will be of a type we were told about
+                    // in a previous `Type` message
+                    for col in &mut mapping.cols {
+                        if let Some((namespace, name)) = types.get(&col.data_type.oid()) {
+                            col.data_type = postgres_types::Type::new(
+                                name.clone(),
+                                col.data_type.oid(),
+                                postgres_types::Kind::Simple,
+                                namespace.clone(),
+                            );
+                        }
+                    }
+
                     // Store the relation in the hash map for future use
                     let id = mapping.id;
                     let name = String::from_utf8(mapping.name.to_vec()).map_err(|v| {
@@ -196,13 +234,33 @@ impl WalReader {
                     }
                 }
                 WalRecord::Begin { .. } => {}
-                msg @ WalRecord::Type { .. } => {
-                    // This happens when a `NEW TYPE` is used, unsupported yet
-                    error!(?msg, "Unhandled message");
+                WalRecord::Type {
+                    id,
+                    namespace,
+                    name,
+                } => {
+                    // This is sent before the first `Relation` message that references a type that
+                    // isn't built in, such as an enum
+                    let namespace = String::from_utf8_lossy(&namespace).into_owned();
+                    let name = String::from_utf8_lossy(&name).into_owned();
+                    debug!(id, %namespace, %name, "Registering type");
+                    types.insert(id as u32, (namespace, name));
                 }
-                msg @ WalRecord::Truncate { .. } => {
-                    // This happens when `TRUNCATE table` is used, unsupported yet
-                    error!(?msg, "Unhandled message");
+                WalRecord::Truncate { relation_ids, .. } => {
+                    // A single `TRUNCATE` statement can truncate several tables, each of which is
+                    // returned as a separate event
+                    for relation_id in relation_ids {
+                        let table = match relations.get(&relation_id) {
+                            Some((name, _)) => name.clone(),
+                            None => {
+                                return Err(ReadySetError::ReplicationFailed(format!(
+                                    "Truncate of unknown relation {}",
+                                    relation_id
+                                )))
+                            }
+                        };
+                        pending.push_back((WalEvent::Truncate { table }, end));
+                    }
                 }
                 WalRecord::Origin { .. } => {
                     // Just tells where the transaction originated
@@ -215,6 +273,24 @@ impl WalReader {
     }
 }
 
+/// The PostgreSQL epoch (2000-01-01), as seconds since the unix epoch
+const POSTGRES_EPOCH_SECS: u64 = 946_684_800;
+
+/// Record the [`REPLICATOR_LAG_SECONDS`][lag] metric for a transaction committed at `timestamp`,
+/// in microseconds since the PostgreSQL epoch
+///
+/// [lag]: recorded::REPLICATOR_LAG_SECONDS
+fn record_replication_lag(timestamp: i64) {
+    let committed = Duration::from_secs(POSTGRES_EPOCH_SECS)
+        + Duration::from_micros(u64::try_from(timestamp).unwrap_or(0));
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        gauge!(
+            recorded::REPLICATOR_LAG_SECONDS,
+            now.saturating_sub(committed).as_secs_f64()
+        );
+    }
+}
+
 impl wal::TupleData {
     pub(crate) fn into_noria_vec(
         self,
@@ -304,6 +380,14 @@ impl wal::TupleData {
                             }
                             DataType::from(bits)
                         }
+                        // Types that aren't built in, which were registered by a `Type` message,
+                        // are replicated using their text representation
+                        ref t
+                            if matches!(t.kind(), postgres_types::Kind::Simple)
+                                && PGType::from_oid(t.oid()).is_none() =>
+                        {
+                            DataType::from(str.as_ref())
+                        }
                         ref t => {
                             unsupported!("Conversion not implemented for type {:?}", t);
                         }
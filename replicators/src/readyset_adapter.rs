@@ -1,26 +1,41 @@
 use std::collections::{hash_map, HashMap, HashSet};
 use std::convert::TryInto;
-use std::future;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use std::{fmt, future, mem};
 
 use async_trait::async_trait;
+use futures::future::BoxFuture;
 use futures::{FutureExt, TryFutureExt};
 use launchpad::select;
 use metrics::{counter, histogram};
+use nom_sql::{ColumnConstraint, CreateTableStatement, Dialect, SqlType};
+use postgres_native_tls::MakeTlsConnector;
 use readyset::consensus::Authority;
 use readyset::consistency::Timestamp;
 use readyset::metrics::recorded::{self, SnapshotStatusTag};
-use readyset::replication::{ReplicationOffset, ReplicationOffsets};
-use readyset::{ControllerHandle, ReadySetError, ReadySetResult, Table, TableOperation};
+use readyset::recipe::changelist::ChangeList;
+use readyset::replication::{ReplicationOffset, ReplicationOffsets, TableNamespace};
+use readyset::{
+    ControllerHandle, Modification, ReadySetError, ReadySetResult, Table, TableOperation,
+};
+use readyset_data::DataType;
+use readyset_errors::internal_err;
+use readyset_tracing::presampled::instrument_if_enabled;
+use serde::{Deserialize, Serialize};
 use tokio::sync::Notify;
 use tracing::{debug, error, info, info_span, trace, warn, Instrument};
 use {mysql_async as mysql, tokio_postgres as pgsql};
 
+use crate::auto_increment::{auto_increment_column, max_inserted_value, AutoIncrements};
+use crate::column_filter::{drop_operation, nullify_operation, ColumnFilter};
+use crate::dump::DumpSnapshot;
 use crate::mysql_connector::{MySqlBinlogConnector, MySqlReplicator};
 use crate::postgres_connector::{
-    PostgresPosition, PostgresReplicator, PostgresWalConnector, PUBLICATION_NAME, REPLICATION_SLOT,
+    drop_publication_and_slot, monitor_replication_slot, PostgresPosition, PostgresReplicator,
+    PostgresWalConnector, PUBLICATION_NAME, REPLICATION_SLOT,
 };
 
 #[derive(Debug)]
@@ -33,9 +48,15 @@ pub(crate) enum ReplicationAction {
         /// the same transaction id. These id's should be monotonically
         /// increasing across transactions.
         txid: Option<u64>,
+        /// The positions of the columns of the upstream table which the rows inserted by
+        /// `actions` have no value for, because they were left out of a minimal row image. These
+        /// columns are given their default value.
+        default_columns: Vec<usize>,
     },
     SchemaChange {
         ddl: String,
+        /// The SQL dialect to parse `ddl` with
+        dialect: Dialect,
     },
     LogPosition,
 }
@@ -60,12 +81,96 @@ pub(crate) trait Connector {
     ) -> ReadySetResult<(ReplicationAction, ReplicationOffset)>;
 }
 
+/// A read of the next action from a [`Connector`], which owns the connector until it completes
+type NextAction = BoxFuture<
+    'static,
+    (
+        Box<dyn Connector + Send + Sync>,
+        ReadySetResult<(ReplicationAction, ReplicationOffset)>,
+    ),
+>;
+
+/// The replicated actions for a single table which are yet to be written to ReadySet
+struct BatchedTableActions {
+    table: String,
+    actions: Vec<TableOperation>,
+    /// The transaction id of the last of the actions
+    txid: Option<u64>,
+    /// The replication offset of the last of the actions
+    pos: ReplicationOffset,
+}
+
+/// Replicated table actions which are yet to be written to ReadySet, so that consecutive row
+/// events for the same table are written to its base table in a single, larger write
+#[derive(Default)]
+struct WriteBatch {
+    /// The buffered actions for each table, in the order the tables were first written to
+    tables: Vec<BatchedTableActions>,
+    /// The total number of buffered table operations
+    len: usize,
+    /// When the first of the buffered actions was received, and the replication offset of the
+    /// action before it, up to which everything has been written to ReadySet
+    started: Option<(Instant, ReplicationOffset)>,
+}
+
+impl WriteBatch {
+    /// Add the `actions` for `table` replicated at `pos` to the batch, where `prev_pos` is the
+    /// offset of the action before them
+    fn push(
+        &mut self,
+        table: String,
+        mut actions: Vec<TableOperation>,
+        txid: Option<u64>,
+        pos: ReplicationOffset,
+        prev_pos: ReplicationOffset,
+    ) {
+        self.started
+            .get_or_insert_with(|| (Instant::now(), prev_pos));
+        self.len += actions.len();
+        match self
+            .tables
+            .iter_mut()
+            .find(|batched| batched.table == table)
+        {
+            Some(batched) => {
+                batched.actions.append(&mut actions);
+                batched.txid = txid.or(batched.txid);
+                batched.pos = pos;
+            }
+            None => self.tables.push(BatchedTableActions {
+                table,
+                actions,
+                txid,
+                pos,
+            }),
+        }
+    }
+
+    /// Returns the replication offset up to which every action has been written to ReadySet,
+    /// if the batch isn't empty
+    fn written_offset(&self) -> Option<&ReplicationOffset> {
+        self.started.as_ref().map(|(_, pos)| pos)
+    }
+
+    /// Returns the time by which the batch must be written to ReadySet, if it isn't empty
+    fn deadline(&self, latency: Duration) -> Option<Instant> {
+        self.started.as_ref().map(|(started, _)| *started + latency)
+    }
+
+    /// Remove and return all of the buffered actions
+    fn take(&mut self) -> Vec<BatchedTableActions> {
+        self.len = 0;
+        self.started = None;
+        mem::take(&mut self.tables)
+    }
+}
+
 /// An adapter that converts database events into ReadySet API calls
 pub struct NoriaAdapter {
     /// The ReadySet API handle
     noria: ControllerHandle,
-    /// The binlog reader
-    connector: Box<dyn Connector + Send + Sync>,
+    /// The binlog reader. This is only `None` while an action is being read from it
+    connector: Option<Box<dyn Connector + Send + Sync>>,
     /// A map of cached table mutators
     mutator_map: HashMap<String, Option<Table>>,
     /// A HashSet of tables we've already warned about not existing
@@ -77,49 +182,171 @@ pub struct NoriaAdapter {
     /// replication at the *minimum* replication offset, but ignore any replication events that
     /// come before the offset for that table
     replication_offsets: ReplicationOffsets,
+    /// Columns whose values are replaced with NULL, or that are dropped, when written to ReadySet
+    column_filter: ColumnFilter,
+    /// Whether adapters may write directly to the base tables. See [`Config::write_through`].
+    write_through: bool,
+    /// The largest replicated values of tables' auto-increment columns that are yet to be
+    /// recorded with the controller
+    auto_increments: AutoIncrements,
+    /// See [`Config::write_batch_size`]
+    write_batch_size: usize,
+    /// See [`Config::write_batch_latency`]
+    write_batch_latency: Duration,
 }
 
-#[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum AdapterOpts {
     MySql(mysql::Opts),
-    Postgres(pgsql::Config),
+    /// The options for connecting to a PostgreSQL database, along with the TLS connector to use
+    Postgres(pgsql::Config, MakeTlsConnector),
 }
 
+impl fmt::Debug for AdapterOpts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MySql(opts) => f.debug_tuple("MySql").field(opts).finish(),
+            Self::Postgres(config, _) => f.debug_tuple("Postgres").field(config).finish(),
+        }
+    }
+}
+
+/// Parses MySQL and PostgreSQL URLs, including the TLS options described in
+/// [`database_utils::tls`]
 impl FromStr for AdapterOpts {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.starts_with("mysql://") {
-            let opts: mysql::Opts = s.parse().map_err(|e: mysql::UrlError| e.to_string())?;
+            let opts = database_utils::tls::mysql_opts(s).map_err(|e| e.to_string())?;
             if opts.db_name().is_none() {
                 return Err("Database name is required in MySQL URL".to_string());
             }
             Ok(AdapterOpts::MySql(opts))
         } else if s.starts_with("postgres://") || s.starts_with("postgresql://") {
-            let opts: pgsql::Config = s.parse().map_err(|e: pgsql::Error| e.to_string())?;
+            let (opts, tls) = database_utils::tls::postgres_config(s).map_err(|e| e.to_string())?;
             if opts.get_dbname().is_none() {
                 return Err("Database name is required in PostgreSQL URL".to_string());
             }
-            Ok(AdapterOpts::Postgres(opts))
+            Ok(AdapterOpts::Postgres(opts, tls))
         } else {
             Err("A valid URL should begin with mysql:// or postgresql://".to_string())
         }
     }
 }
 
+/// The default value for [`Config::snapshot_parallelism`]
+pub const DEFAULT_SNAPSHOT_PARALLELISM: usize = 8;
+
+/// The default value for [`Config::write_batch_size`]
+pub const DEFAULT_WRITE_BATCH_SIZE: usize = 1024;
+
+/// The default value for [`Config::write_batch_latency`], in milliseconds
+pub const DEFAULT_WRITE_BATCH_LATENCY_MS: u64 = 10;
+
+fn default_write_batch_size() -> usize {
+    DEFAULT_WRITE_BATCH_SIZE
+}
+
+fn default_write_batch_latency() -> Duration {
+    Duration::from_millis(DEFAULT_WRITE_BATCH_LATENCY_MS)
+}
+
+/// Configuration for the replicator
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// The maximum number of tables to copy concurrently during the initial snapshot of the
+    /// upstream database. Each table being copied uses a separate connection to the upstream.
+    pub snapshot_parallelism: usize,
+    /// Columns, of the form `<table>.<column>`, whose values should be replaced with NULL rather
+    /// than copied into ReadySet, both during the snapshot and during replication. This is
+    /// useful for large blob or text columns that are never referenced by cached queries.
+    ///
+    /// Columns that are part of a table's primary key are always replicated.
+    #[serde(default)]
+    pub nullified_columns: Vec<String>,
+    /// Columns, of the form `<table>.<column>`, that are left out of ReadySet's tables entirely.
+    /// Their values are never copied into ReadySet, and they don't take up any space there.
+    ///
+    /// Columns that are part of one of a table's keys are always replicated.
+    #[serde(default)]
+    pub dropped_columns: Vec<String>,
+    /// The namespace that tables replicated from the upstream database are created in, when
+    /// ReadySet replicates from more than one upstream database. Tables are named
+    /// `<namespace>_<table>`. If `None`, this is the primary upstream database, and tables keep
+    /// their upstream names.
+    ///
+    /// See [`TableNamespace`] for more information.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// The namespaces of all other upstream databases being replicated from. Tables in these
+    /// namespaces are ignored by the replicator.
+    #[serde(default)]
+    pub other_namespaces: Vec<String>,
+    /// Whether adapters write directly to base tables as well as to the upstream database. If
+    /// set, replicated inserts and deletes replace whatever row ReadySet has for the same primary
+    /// key, so that the upstream database's version of each row wins over the version written by
+    /// the adapter.
+    #[serde(default)]
+    pub write_through: bool,
+    /// The number of replicated row operations, across all tables, beyond which buffered
+    /// replicated writes are written to ReadySet. Consecutive writes to the same table are sent
+    /// to its base table in a single batch, which is much cheaper than writing them one at a time.
+    #[serde(default = "default_write_batch_size")]
+    pub write_batch_size: usize,
+    /// The maximum amount of time a replicated write is buffered before being written to
+    /// ReadySet.
+    #[serde(default = "default_write_batch_latency")]
+    pub write_batch_latency: Duration,
+    /// A plain-format `mysqldump` or `pg_dump` file to load the initial snapshot from, instead of
+    /// snapshotting the upstream database. See the [`dump`](crate::dump) module for how the dump
+    /// must be taken.
+    #[serde(default)]
+    pub snapshot_dump: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            snapshot_parallelism: DEFAULT_SNAPSHOT_PARALLELISM,
+            nullified_columns: vec![],
+            dropped_columns: vec![],
+            namespace: None,
+            other_namespaces: vec![],
+            write_through: false,
+            write_batch_size: DEFAULT_WRITE_BATCH_SIZE,
+            write_batch_latency: default_write_batch_latency(),
+            snapshot_dump: None,
+        }
+    }
+}
+
 impl NoriaAdapter {
     pub async fn start_with_authority(
         authority: Authority,
         options: AdapterOpts,
+        config: Config,
         server_id: Option<u32>,
     ) -> ReadySetResult<!> {
         let noria = readyset::ControllerHandle::new(authority).await;
-        NoriaAdapter::start_inner(noria, options, server_id, None).await
+        NoriaAdapter::start_inner(noria, options, config, server_id, None).await
     }
 }
 
 impl NoriaAdapter {
+    /// Remove any replication resources that the replicator created in the upstream database.
+    ///
+    /// For PostgreSQL, this drops the logical replication slot and publication, so that the
+    /// database no longer retains WAL for ReadySet. This fails if a replicator is still streaming
+    /// from the slot, and skips anything that wasn't created by ReadySet. MySQL replication
+    /// doesn't create any resources upstream, so there is nothing to remove.
+    pub async fn cleanup(options: AdapterOpts) -> ReadySetResult<()> {
+        match options {
+            AdapterOpts::MySql(_) => Ok(()),
+            AdapterOpts::Postgres(options, tls) => drop_publication_and_slot(&options, &tls).await,
+        }
+    }
+
     /// Same as [`start`](Builder::start), but accepts a MySQL/PostgreSQL url for options
     /// and an externally supplied ReadySet `ControllerHandle`.
     /// The MySQL url must contain the database name, and user and password if applicable.
@@ -129,6 +356,7 @@ impl NoriaAdapter {
     pub async fn start_with_url<U: AsRef<str>>(
         url: U,
         noria: ControllerHandle,
+        config: Config,
         server_id: Option<u32>,
         ready_notify: Option<Arc<Notify>>,
     ) -> ReadySetResult<!> {
@@ -137,53 +365,62 @@ impl NoriaAdapter {
             .parse()
             .map_err(|e| ReadySetError::ReplicationFailed(format!("Invalid URL format: {}", e)))?;
 
-        NoriaAdapter::start_inner(noria, options, server_id, ready_notify)
+        NoriaAdapter::start_inner(noria, options, config, server_id, ready_notify)
             .instrument(info_span!("replicator"))
             .await
     }
 
     async fn start_inner(
-        noria: ControllerHandle,
+        mut noria: ControllerHandle,
         options: AdapterOpts,
+        config: Config,
         server_id: Option<u32>,
         ready_notify: Option<Arc<Notify>>,
     ) -> ReadySetResult<!> {
+        noria.set_table_namespace(TableNamespace::new(
+            config.namespace.clone(),
+            config.other_namespaces.clone(),
+        ));
+
         match options {
             AdapterOpts::MySql(options) => {
-                NoriaAdapter::start_inner_mysql(options, noria, server_id, ready_notify).await
+                NoriaAdapter::start_inner_mysql(options, noria, config, server_id, ready_notify)
+                    .await
             }
-            AdapterOpts::Postgres(options) => {
-                NoriaAdapter::start_inner_postgres(options, noria, ready_notify).await
+            AdapterOpts::Postgres(options, tls) => {
+                NoriaAdapter::start_inner_postgres(options, tls, noria, config, ready_notify).await
             }
         }
     }
 
     /// Finish the build and begin monitoring the binlog for changes
-    /// If ReadySet has no replication offset information, it will replicate the target database in its
-    /// entirety to ReadySet before listening on the binlog
+    /// If ReadySet has no replication offset information, it will replicate the target database in
+    /// its entirety to ReadySet before listening on the binlog
     /// The replication happens in stages:
     /// * READ LOCK is acquired on the database
     /// * Next binlog position is read
     /// * The recipe (schema) DDL is replicated and installed in ReadySet (replacing current recipe)
-    /// * Each table is individually replicated into ReadySet
+    /// * Each table is individually replicated into ReadySet, with up to
+    ///   [`Config::snapshot_parallelism`] tables being replicated at the same time
     /// * READ LOCK is released
     /// * Adapter keeps reading binlog from the next position keeping ReadySet up to date
     async fn start_inner_mysql(
         mysql_options: mysql::Opts,
         mut noria: ControllerHandle,
+        config: Config,
         server_id: Option<u32>,
         ready_notify: Option<Arc<Notify>>,
     ) -> ReadySetResult<!> {
         use crate::mysql_connector::BinlogPosition;
+        let column_filter = ColumnFilter::new(&config.nullified_columns, &config.dropped_columns);
         // Load the replication offset for all tables and the schema from ReadySet
         let mut replication_offsets = noria.replication_offsets().await?;
+        // If any table snapshots were resumed, the offset we must catch up to before the tables
+        // are consistent with each other
+        let mut resumed_offset = None;
         let pos = match replication_offsets.max_offset()? {
             None => {
                 let span = info_span!("taking database snapshot");
-                let replicator_options = mysql_options.clone();
-                let pool = mysql::Pool::new(replicator_options);
-                let replicator = MySqlReplicator { pool, tables: None };
-
                 let snapshot_start = Instant::now();
                 counter!(
                     recorded::REPLICATOR_SNAPSHOT_STATUS,
@@ -191,10 +428,32 @@ impl NoriaAdapter {
                     "status" => SnapshotStatusTag::Started.value(),
                 );
                 span.in_scope(|| info!("Starting snapshot"));
-                let snapshot_result = replicator
-                    .snapshot_to_noria(&mut noria, &replication_offsets, true)
-                    .instrument(span.clone())
-                    .await;
+                let snapshot_result = match &config.snapshot_dump {
+                    Some(path) => {
+                        DumpSnapshot::new(path.clone(), Dialect::MySQL, column_filter.clone())
+                            .snapshot_to_noria(&mut noria, &replication_offsets)
+                            .instrument(span.clone())
+                            .await
+                            .map(|()| None)
+                    }
+                    None => {
+                        let replicator_options = mysql_options.clone();
+                        let max_connections = replicator_options.pool_opts().constraints().max();
+                        let pool = mysql::Pool::new(replicator_options);
+                        let replicator = MySqlReplicator {
+                            pool,
+                            tables: None,
+                            snapshot_parallelism: config.snapshot_parallelism,
+                            max_connections,
+                            column_filter: column_filter.clone(),
+                            resumed_offset: None,
+                        };
+                        replicator
+                            .snapshot_to_noria(&mut noria, &replication_offsets, true)
+                            .instrument(span.clone())
+                            .await
+                    }
+                };
 
                 let status = if snapshot_result.is_err() {
                     SnapshotStatusTag::Failed.value()
@@ -208,7 +467,7 @@ impl NoriaAdapter {
                     "status" => status
                 );
 
-                snapshot_result?;
+                resumed_offset = snapshot_result?;
 
                 // Get updated offests, after potential replication happened
                 replication_offsets = noria.replication_offsets().await?;
@@ -238,6 +497,27 @@ impl NoriaAdapter {
             Some(pos) => pos.clone().into(),
         };
 
+        // Rows in the binlog have all of the upstream table's columns, so the positions of the
+        // dropped columns in each upstream table have to be known before replicating
+        let tables = column_filter.tables_with_dropped_columns();
+        if !tables.is_empty() {
+            use mysql::prelude::Queryable;
+            let mut conn = mysql::Conn::new(mysql_options.clone()).await?;
+            for table in tables {
+                let columns: Vec<String> = conn
+                    .exec(
+                        "SELECT COLUMN_NAME FROM information_schema.COLUMNS \
+                         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? \
+                         ORDER BY ORDINAL_POSITION",
+                        (table.as_str(),),
+                    )
+                    .await?;
+                column_filter
+                    .load_upstream_columns(&mut noria, &table, columns)
+                    .await;
+            }
+        }
+
         let schemas = mysql_options
             .db_name()
             .map(|s| vec![s.to_string()])
@@ -252,10 +532,15 @@ impl NoriaAdapter {
 
         let mut adapter = NoriaAdapter {
             noria,
-            connector,
+            connector: Some(connector),
             replication_offsets,
             mutator_map: HashMap::new(),
             warned_missing_tables: HashSet::new(),
+            column_filter,
+            write_through: config.write_through,
+            auto_increments: Default::default(),
+            write_batch_size: config.write_batch_size,
+            write_batch_latency: config.write_batch_latency,
         };
 
         let mut current_pos: ReplicationOffset = pos.try_into()?;
@@ -266,10 +551,13 @@ impl NoriaAdapter {
         // in time, it is not safe to issue any queries. We therefore advance the binlog
         // to the position of the most recent table we have, applying changes as needed.
         // Only once binlog advanced to that point, can we send a ready signal to ReadySet.
-        match adapter.replication_offsets.max_offset()? {
-            Some(max) if max > &current_pos => {
+        let mut catchup_offset = adapter.replication_offsets.max_offset()?.cloned();
+        if let Some(resumed_offset) = resumed_offset {
+            resumed_offset.try_max_into(&mut catchup_offset)?;
+        }
+        match catchup_offset {
+            Some(max) if max > current_pos => {
                 info!(start = %current_pos, end = %max, "Catching up");
-                let max = max.clone();
                 adapter.main_loop(&mut current_pos, Some(max)).await?;
             }
             _ => {}
@@ -290,9 +578,12 @@ impl NoriaAdapter {
 
     async fn start_inner_postgres(
         pgsql_opts: pgsql::Config,
+        tls: MakeTlsConnector,
         mut noria: ControllerHandle,
+        config: Config,
         ready_notify: Option<Arc<Notify>>,
     ) -> ReadySetResult<!> {
+        let column_filter = ColumnFilter::new(&config.nullified_columns, &config.dropped_columns);
         // Attempt to retreive the latest replication offset from ReadySet, if none is present
         // begin the snapshot process
         let replication_offsets = noria.replication_offsets().await?;
@@ -302,28 +593,49 @@ impl NoriaAdapter {
             info!(wal_position = %pos);
         }
 
+        // The replication slot must already exist if the snapshot is loaded from a dump, since the
+        // dump is taken from the slot's snapshot
+        let snapshot_dump = config.snapshot_dump.as_ref().filter(|_| pos.is_none());
+
         let dbname = pgsql_opts
             .get_dbname()
             .map(|s| vec![s.to_string()])
             .unwrap_or_default();
 
         let mut connector = Box::new(
-            PostgresWalConnector::connect(pgsql_opts.clone(), dbname.first().unwrap(), pos).await?,
+            PostgresWalConnector::connect(
+                pgsql_opts.clone(),
+                tls.clone(),
+                dbname.first().unwrap(),
+                pos.or_else(|| snapshot_dump.map(|_| PostgresPosition::default())),
+            )
+            .await?,
         );
 
         info!("Connected to PostgreSQL");
 
-        if let Some(snapshot) = connector.snapshot_name.as_deref() {
-            // If snapshot name exists, it means we need to make a snapshot to ReadySet
-            let (mut client, connection) = pgsql_opts
-                .connect(postgres_native_tls::MakeTlsConnector::new(
-                    native_tls::TlsConnector::builder().build().unwrap(),
-                ))
+        if let Some(path) = snapshot_dump {
+            DumpSnapshot::new(path.clone(), Dialect::PostgreSQL, column_filter.clone())
+                .snapshot_to_noria(&mut noria, &replication_offsets)
                 .await?;
 
+            info!("Snapshot finished");
+        } else if let Some(snapshot) = connector.snapshot_name.as_deref() {
+            // If snapshot name exists, it means we need to make a snapshot to ReadySet
+            let (mut client, connection) = pgsql_opts.connect(tls.clone()).await?;
+
             let connection_handle = tokio::spawn(connection);
 
-            let mut replicator = PostgresReplicator::new(&mut client, &mut noria, None).await?;
+            let mut replicator = PostgresReplicator::new(
+                &mut client,
+                &pgsql_opts,
+                &tls,
+                &mut noria,
+                None,
+                config.snapshot_parallelism,
+                column_filter.clone(),
+            )
+            .await?;
 
             select! {
                 s = replicator.snapshot_to_noria(snapshot).fuse() => s?,
@@ -333,6 +645,31 @@ impl NoriaAdapter {
             info!("Snapshot finished");
         }
 
+        // Rows in the WAL have all of the upstream table's columns, so the positions of the
+        // dropped columns in each upstream table have to be known before replicating
+        let tables = column_filter.tables_with_dropped_columns();
+        if !tables.is_empty() {
+            let (client, connection) = pgsql_opts.connect(tls.clone()).await?;
+            let connection_handle = tokio::spawn(connection);
+            for table in tables {
+                let columns = client
+                    .query(
+                        "SELECT column_name::text FROM information_schema.columns \
+                         WHERE table_schema = ANY(current_schemas(false)) AND table_name = $1 \
+                         ORDER BY ordinal_position",
+                        &[&table],
+                    )
+                    .await?
+                    .into_iter()
+                    .map(|row| row.try_get(0))
+                    .collect::<Result<Vec<String>, _>>()?;
+                column_filter
+                    .load_upstream_columns(&mut noria, &table, columns)
+                    .await;
+            }
+            connection_handle.abort();
+        }
+
         // Let waiters know that the initial snapshotting is complete.
         if let Some(notify) = ready_notify {
             notify.notify_one();
@@ -344,17 +681,32 @@ impl NoriaAdapter {
 
         info!("Streaming replication started");
 
+        // Keep track of the state of the replication slot for as long as we're replicating. Only
+        // the state of the primary upstream database's slot is recorded.
+        let slot_monitor = config.namespace.is_none().then(|| {
+            tokio::spawn(monitor_replication_slot(pgsql_opts, tls, noria.clone()).in_current_span())
+        });
+
         let mut adapter = NoriaAdapter {
             noria,
-            connector,
+            connector: Some(connector),
             replication_offsets,
             mutator_map: HashMap::new(),
             warned_missing_tables: HashSet::new(),
+            column_filter,
+            write_through: config.write_through,
+            auto_increments: Default::default(),
+            write_batch_size: config.write_batch_size,
+            write_batch_latency: config.write_batch_latency,
         };
 
-        adapter
+        let res = adapter
             .main_loop(&mut PostgresPosition::default().into(), None)
-            .await?;
+            .await;
+        if let Some(slot_monitor) = slot_monitor {
+            slot_monitor.abort();
+        }
+        res?;
 
         unreachable!("`main_loop` will never stop with an Ok status if `until = None`");
     }
@@ -363,9 +715,12 @@ impl NoriaAdapter {
     async fn handle_ddl_change(
         &mut self,
         ddl: String,
+        dialect: Dialect,
         pos: ReplicationOffset,
     ) -> ReadySetResult<()> {
-        match future::ready(ddl.try_into())
+        let changelist = ChangeList::from_str_with_dialect(&ddl, dialect)
+            .map(|changelist| self.column_filter.filter_changelist(changelist));
+        match future::ready(changelist)
             .and_then(|changelist| async {
                 self.noria
                     .extend_recipe_with_offset(changelist, &pos, false)
@@ -379,7 +734,22 @@ impl NoriaAdapter {
                 error!(error = %e, "Error extending recipe, DDL statement will not be used");
                 counter!(recorded::REPLICATOR_FAILURE, 1u64,);
             }
-            Ok(_) => {}
+            Ok(result) => {
+                if !result.replanned_queries.is_empty() {
+                    info!(
+                        %ddl,
+                        queries = ?result.replanned_queries,
+                        "Re-planned caches affected by schema change"
+                    );
+                }
+                if !result.dropped_queries.is_empty() {
+                    warn!(
+                        %ddl,
+                        queries = ?result.dropped_queries,
+                        "Dropped caches invalidated by schema change"
+                    );
+                }
+            }
         }
         self.replication_offsets.schema = Some(pos);
         self.clear_mutator_cache();
@@ -424,7 +794,9 @@ impl NoriaAdapter {
         txid: Option<u64>,
         pos: ReplicationOffset,
     ) -> ReadySetResult<()> {
-        // Send the rows as are
+        let column_filter = self.column_filter.clone();
+        let write_through = self.write_through;
+        // Send the rows as are, other than any nullified or dropped columns
         let table_mutator = if let Some(table) = self.mutator_for_table(&table).await? {
             table
         } else {
@@ -443,6 +815,44 @@ impl NoriaAdapter {
             }
             return Ok(());
         };
+        // Rows and updates from the connectors have all of the upstream table's columns, so the
+        // values of the columns that were dropped have to be removed first
+        let dropped = column_filter.dropped_indices(table_mutator.table_name());
+        if !dropped.is_empty() {
+            for action in &mut actions {
+                drop_operation(action, &dropped);
+            }
+        }
+        // Connectors produce keys with their columns in the order they appear in the table, but
+        // the base table expects them in the order of its primary key
+        let key = table_mutator.key();
+        if !key.windows(2).all(|w| w[0] < w[1]) {
+            for action in &mut actions {
+                if let TableOperation::DeleteByKey { key: values }
+                | TableOperation::Update { key: values, .. } = action
+                {
+                    *values = key_in_primary_key_order(key, std::mem::take(values));
+                }
+            }
+        }
+        let unsigned = unsigned_columns(table_mutator);
+        if !unsigned.is_empty() {
+            for action in &mut actions {
+                reinterpret_unsigned(action, key, &unsigned);
+            }
+        }
+        let nullified = column_filter.nullified_indices(table_mutator);
+        if !nullified.is_empty() {
+            for action in &mut actions {
+                nullify_operation(action, &nullified);
+            }
+        }
+        if write_through && table_mutator.has_primary_key() {
+            actions = resolve_write_through_conflicts(table_mutator.key(), actions);
+        }
+        let auto_increment = auto_increment_column(table_mutator)
+            .and_then(|col| max_inserted_value(col, &actions))
+            .map(|max| (table_mutator.table_name().to_owned(), max));
         actions.push(TableOperation::SetReplicationOffset(pos.clone()));
         table_mutator.perform_all(actions).await?;
 
@@ -458,6 +868,9 @@ impl NoriaAdapter {
             table_mutator.update_timestamp(timestamp).await?;
         }
 
+        if let Some((name, max)) = auto_increment {
+            self.auto_increments.observe(&name, max);
+        }
         self.replication_offsets
             .tables
             .insert(table.into(), Some(pos));
@@ -465,74 +878,242 @@ impl NoriaAdapter {
         Ok(())
     }
 
-    /// Handle a single BinlogAction by calling the proper ReadySet RPC. If `catchup` is set,
-    /// we will not log warnings for skipping entries, as we may iterate over many entries tables
-    /// have already seen when catching each table up to the current binlog offset.
-    async fn handle_action(
-        &mut self,
-        action: ReplicationAction,
-        pos: ReplicationOffset,
+    /// Returns whether `action` should be skipped because the schema or its table has already
+    /// been replicated past `pos`. If `catchup` is set, we will not log warnings for skipping
+    /// entries, as we may iterate over many entries tables have already seen when catching each
+    /// table up to the current binlog offset.
+    fn skip_action(
+        &self,
+        action: &ReplicationAction,
+        pos: &ReplicationOffset,
         catchup: bool,
-    ) -> ReadySetResult<()> {
-        // First check if we should skip this action due to insufficient log position
-        match &action {
+    ) -> bool {
+        match action {
             ReplicationAction::SchemaChange { .. } | ReplicationAction::LogPosition => {
                 match &self.replication_offsets.schema {
-                    Some(cur) if pos <= *cur => {
+                    Some(cur) if pos <= cur => {
                         if !catchup {
                             warn!(%pos, %cur, "Skipping schema update for earlier entry");
                         }
-                        return Ok(());
+                        true
                     }
-                    _ => {}
+                    _ => false,
                 }
             }
             ReplicationAction::TableAction { table, .. } => {
                 match self.replication_offsets.tables.get(table.as_str()) {
-                    Some(Some(cur)) if pos <= *cur => {
+                    Some(Some(cur)) if pos <= cur => {
                         if !catchup {
                             warn!(%table, %pos, %cur, "Skipping table action for earlier entry");
                         }
-                        return Ok(());
+                        true
                     }
-                    _ => {}
+                    _ => false,
                 }
             }
         }
+    }
 
+    /// Handle a single BinlogAction by calling the proper ReadySet RPC
+    async fn handle_action(
+        &mut self,
+        action: ReplicationAction,
+        pos: ReplicationOffset,
+    ) -> ReadySetResult<()> {
         match action {
-            ReplicationAction::SchemaChange { ddl } => self.handle_ddl_change(ddl, pos).await,
+            ReplicationAction::SchemaChange { ddl, dialect } => {
+                self.handle_ddl_change(ddl, dialect, pos).await
+            }
             ReplicationAction::TableAction {
                 table,
-                actions,
+                mut actions,
                 txid,
-            } => self.handle_table_actions(table, actions, txid, pos).await,
+                default_columns,
+            } => {
+                self.insert_column_defaults(&table, &mut actions, &default_columns)
+                    .await?;
+                self.handle_table_actions(table, actions, txid, pos).await
+            }
             ReplicationAction::LogPosition => self.handle_log_position(pos).await,
         }
     }
 
+    /// Give the columns at the positions in `columns` of the rows inserted by `actions` into the
+    /// upstream table `table`, which have no value for those columns, the default values of the
+    /// columns in ReadySet's schema for the table
+    async fn insert_column_defaults(
+        &mut self,
+        table: &str,
+        actions: &mut [TableOperation],
+        columns: &[usize],
+    ) -> ReadySetResult<()> {
+        if columns.is_empty() {
+            return Ok(());
+        }
+        let column_filter = self.column_filter.clone();
+        let table_mutator = match self.mutator_for_table(table).await? {
+            Some(table) => table,
+            // The actions are discarded when they're written
+            None => return Ok(()),
+        };
+        let schema = table_mutator
+            .schema()
+            .ok_or_else(|| internal_err(format!("Table {} has no schema", table)))?;
+        let dropped = column_filter.dropped_indices(table_mutator.table_name());
+        let defaults = column_defaults(schema, &dropped, columns)?;
+        for action in actions {
+            if let TableOperation::Insert(row) = action {
+                for (col, default) in &defaults {
+                    if let Some(value) = row.get_mut(*col) {
+                        *value = default.clone();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write all of the table actions buffered in `batch` to ReadySet
+    async fn flush_write_batch(&mut self, batch: &mut WriteBatch) -> ReadySetResult<()> {
+        for BatchedTableActions {
+            table,
+            actions,
+            txid,
+            pos,
+        } in batch.take()
+        {
+            self.handle_table_actions(table, actions, txid, pos).await?;
+        }
+        self.auto_increments.record(&mut self.noria).await;
+        Ok(())
+    }
+
+    /// Start reading the next action from the connector, where `last_pos` is the offset up to
+    /// which every action has been written to ReadySet
+    fn read_next_action(
+        &mut self,
+        last_pos: ReplicationOffset,
+        until: Option<ReplicationOffset>,
+    ) -> ReadySetResult<NextAction> {
+        let mut connector = self
+            .connector
+            .take()
+            .ok_or_else(|| internal_err("Replication connector is already being read from"))?;
+        Ok(async move {
+            let res = connector.next_action(&last_pos, until.as_ref()).await;
+            (connector, res)
+        }
+        .boxed())
+    }
+
     /// Loop over the actions. `until` may be passed to set a replication offset to stop
     /// replicating at.
+    ///
+    /// Table actions are buffered and written to ReadySet in batches of up to
+    /// [`Config::write_batch_size`] operations, at most [`Config::write_batch_latency`] after
+    /// they're received. Any other action first writes the buffered table actions.
     async fn main_loop(
         &mut self,
         position: &mut ReplicationOffset,
         until: Option<ReplicationOffset>,
     ) -> ReadySetResult<()> {
+        let catchup = until.is_some();
+        let mut batch = WriteBatch::default();
+        // The in-progress read of the next action, which is kept across iterations so that the
+        // batch can be written while waiting for the next action without losing a partially read
+        // action
+        let mut next_action: Option<NextAction> = None;
+
         loop {
             if until.as_ref().map(|u| *position >= *u).unwrap_or(false) {
+                if let Err(err) = self.flush_write_batch(&mut batch).await {
+                    return Err(replication_failed(err));
+                }
                 return Ok(());
             }
 
-            let (action, pos) = self.connector.next_action(position, until.as_ref()).await?;
-            *position = pos.clone();
+            let next = match next_action.take() {
+                Some(next) => next,
+                None => self.read_next_action(
+                    batch.written_offset().unwrap_or(&*position).clone(),
+                    until.clone(),
+                )?,
+            };
+            let next = next_action.insert(next);
+            let deadline = batch.deadline(self.write_batch_latency);
+            let flush_at = tokio::time::Instant::from_std(deadline.unwrap_or_else(Instant::now));
+            let record_deadline = self.auto_increments.record_deadline();
+            let record_at =
+                tokio::time::Instant::from_std(record_deadline.unwrap_or_else(Instant::now));
+
+            let (connector, res) = tokio::select! {
+                res = next => res,
+                _ = tokio::time::sleep_until(flush_at), if deadline.is_some() => {
+                    if let Err(err) = self.flush_write_batch(&mut batch).await {
+                        return Err(replication_failed(err));
+                    }
+                    continue;
+                }
+                // Auto-increment values are otherwise only recorded after writing a batch, so
+                // they'd never be recorded if replication went idle right after a write
+                _ = tokio::time::sleep_until(record_at), if record_deadline.is_some() => {
+                    self.auto_increments.record(&mut self.noria).await;
+                    continue;
+                }
+            };
+            next_action = None;
+            self.connector = Some(connector);
+
+            let (action, pos) = res?;
+            let prev_pos = mem::replace(position, pos.clone());
             debug!(?position, "Received replication action");
 
             trace!(?action);
 
-            if let Err(err) = self.handle_action(action, pos, until.is_some()).await {
-                error!(error = %err, "Aborting replication task on error");
-                counter!(recorded::REPLICATOR_FAILURE, 1u64,);
-                return Err(err);
+            if self.skip_action(&action, &pos, catchup) {
+                continue;
+            }
+
+            // Writes made while handling a sampled action carry this span's context, so the
+            // action can be followed from the replicator through to the domains
+            let span = readyset_tracing::root_span!(INFO, "replication_action", %pos);
+            let res = match action {
+                ReplicationAction::TableAction {
+                    table,
+                    mut actions,
+                    txid,
+                    default_columns,
+                } => {
+                    instrument_if_enabled(
+                        async {
+                            // The defaults come from the schema the actions are written with,
+                            // since any schema change flushes the batch first
+                            self.insert_column_defaults(&table, &mut actions, &default_columns)
+                                .await?;
+                            batch.push(table, actions, txid, pos, prev_pos);
+                            if batch.len >= self.write_batch_size {
+                                self.flush_write_batch(&mut batch).await
+                            } else {
+                                Ok(())
+                            }
+                        },
+                        span,
+                    )
+                    .await
+                }
+                action => {
+                    instrument_if_enabled(
+                        async {
+                            self.flush_write_batch(&mut batch).await?;
+                            self.handle_action(action, pos).await
+                        },
+                        span,
+                    )
+                    .await
+                }
+            };
+            if let Err(err) = res {
+                return Err(replication_failed(err));
             };
             counter!(recorded::REPLICATOR_SUCCESS, 1u64);
             debug!(?position, "Successfully applied replication action");
@@ -564,3 +1145,328 @@ impl NoriaAdapter {
         }
     }
 }
+
+/// Log and record the failure of the replication task with `err`
+fn replication_failed(err: ReadySetError) -> ReadySetError {
+    error!(error = %err, "Aborting replication task on error");
+    counter!(recorded::REPLICATOR_FAILURE, 1u64,);
+    err
+}
+
+/// Reorder the values of a key whose columns are in the order they appear in the table, so that
+/// they're in the order of the columns of the table's primary key, given by `key_columns`
+fn key_in_primary_key_order(key_columns: &[usize], values: Vec<DataType>) -> Vec<DataType> {
+    let mut in_table_order = key_columns.to_vec();
+    in_table_order.sort_unstable();
+    let mut values = in_table_order
+        .into_iter()
+        .zip(values)
+        .collect::<HashMap<_, _>>();
+    key_columns
+        .iter()
+        .filter_map(|col| values.remove(col))
+        .collect()
+}
+
+/// Returns the positions and types of the unsigned integer columns of `table`
+fn unsigned_columns(table: &Table) -> Vec<(usize, SqlType)> {
+    let schema = match table.schema() {
+        Some(schema) => schema,
+        None => return vec![],
+    };
+    table
+        .columns()
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| {
+            let field = schema.fields.iter().find(|f| f.column.name == *name)?;
+            matches!(
+                field.sql_type,
+                SqlType::UnsignedTinyint(_)
+                    | SqlType::UnsignedSmallint(_)
+                    | SqlType::UnsignedInt(_)
+                    | SqlType::UnsignedBigint(_)
+            )
+            .then(|| (i, field.sql_type.clone()))
+        })
+        .collect()
+}
+
+/// Reinterpret negative values written to the unsigned integer `columns` (as returned by
+/// [`unsigned_columns`]) of a table with the given `key_columns` as unsigned integers of the width
+/// of the column.
+///
+/// MySQL binlogs only record whether integer columns are unsigned in their optional metadata,
+/// which servers older than 8.0.1 never write, so without it values above the maximum of the
+/// signed type of the same width (such as `BIGINT UNSIGNED` values above `i64::MAX`) are decoded
+/// as the negative integers with the same bits.
+fn reinterpret_unsigned(
+    op: &mut TableOperation,
+    key_columns: &[usize],
+    columns: &[(usize, SqlType)],
+) {
+    let reinterpret = |value: &mut DataType, ty: &SqlType| {
+        let i = match *value {
+            DataType::Int(i) if i < 0 => i,
+            _ => return,
+        };
+        let unsigned = match ty {
+            SqlType::UnsignedTinyint(_) => i as u8 as u64,
+            SqlType::UnsignedSmallint(_) => i as u16 as u64,
+            SqlType::UnsignedInt(_) => i as u32 as u64,
+            _ => i as u64,
+        };
+        *value = DataType::UnsignedInt(unsigned);
+    };
+    let reinterpret_row = |row: &mut Vec<DataType>| {
+        for (col, ty) in columns {
+            if let Some(value) = row.get_mut(*col) {
+                reinterpret(value, ty);
+            }
+        }
+    };
+    let reinterpret_update = |update: &mut Vec<Modification>| {
+        for (col, ty) in columns {
+            if let Some(Modification::Set(value)) = update.get_mut(*col) {
+                reinterpret(value, ty);
+            }
+        }
+    };
+    let reinterpret_key = |key: &mut Vec<DataType>| {
+        for (value, key_col) in key.iter_mut().zip(key_columns) {
+            if let Some((_, ty)) = columns.iter().find(|(col, _)| col == key_col) {
+                reinterpret(value, ty);
+            }
+        }
+    };
+
+    match op {
+        TableOperation::Insert(row) | TableOperation::DeleteRow { row } => reinterpret_row(row),
+        TableOperation::InsertOrUpdate { row, update } => {
+            reinterpret_row(row);
+            reinterpret_update(update);
+        }
+        TableOperation::Update { key, update } => {
+            reinterpret_key(key);
+            reinterpret_update(update);
+        }
+        TableOperation::DeleteByKey { key } => reinterpret_key(key),
+        TableOperation::SetReplicationOffset(_)
+        | TableOperation::SetSnapshotMode(_)
+        | TableOperation::Truncate => {}
+    }
+}
+
+/// Returns the default value of each of the columns at the positions in `columns` of an upstream
+/// table, paired with its position, given ReadySet's `schema` for the table and the positions of
+/// the upstream table's columns that are `dropped` from it. Columns without a default are given
+/// NULL, and the dropped columns are left out, since their values are removed anyway.
+fn column_defaults(
+    schema: &CreateTableStatement,
+    dropped: &[usize],
+    columns: &[usize],
+) -> ReadySetResult<Vec<(usize, DataType)>> {
+    columns
+        .iter()
+        .filter(|col| !dropped.contains(col))
+        .map(|&col| {
+            let spec = schema
+                .fields
+                .get(col - dropped.iter().filter(|&&d| d < col).count())
+                .ok_or_else(|| {
+                    internal_err(format!("Column {} not found in {}", col, schema.table))
+                })?;
+            let default = match spec.constraints.iter().find_map(|c| match c {
+                ColumnConstraint::DefaultValue(default) => Some(default),
+                _ => None,
+            }) {
+                Some(default) => DataType::try_from(default)?,
+                None => DataType::None,
+            };
+            // `ENUM` values are converted to the indices of their variants when they're written
+            let default = match spec.sql_type {
+                SqlType::Enum(_) => default,
+                _ => default.coerce_to(&spec.sql_type)?,
+            };
+            Ok((col, default))
+        })
+        .collect()
+}
+
+/// Rewrite replicated inserts and deletes of whole rows into a table with a primary key, given by
+/// `key_columns`, so that they replace or remove whatever row ReadySet has for the same key.
+///
+/// Adapters with write-through enabled apply writes to base tables before the same writes are
+/// replicated from the upstream database, and the rows they write may differ from the upstream's
+/// (for example because of column defaults), so the row replicated from upstream must win.
+fn resolve_write_through_conflicts(
+    key_columns: &[usize],
+    actions: Vec<TableOperation>,
+) -> Vec<TableOperation> {
+    let key_of = |row: &[DataType]| -> Vec<DataType> {
+        key_columns
+            .iter()
+            .map(|&col| row.get(col).cloned().unwrap_or(DataType::None))
+            .collect()
+    };
+
+    actions
+        .into_iter()
+        .flat_map(|action| match action {
+            TableOperation::Insert(row) => vec![
+                TableOperation::DeleteByKey { key: key_of(&row) },
+                TableOperation::Insert(row),
+            ],
+            TableOperation::DeleteRow { row } => {
+                vec![TableOperation::DeleteByKey { key: key_of(&row) }]
+            }
+            action => vec![action],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reinterpret_unsigned_values() {
+        let columns = [
+            (0, SqlType::UnsignedBigint(None)),
+            (2, SqlType::UnsignedInt(None)),
+        ];
+
+        let mut insert = TableOperation::Insert(vec![(-1).into(), (-1).into(), (-1).into()]);
+        reinterpret_unsigned(&mut insert, &[0], &columns);
+        assert_eq!(
+            insert,
+            TableOperation::Insert(vec![
+                DataType::UnsignedInt(u64::MAX),
+                (-1).into(),
+                DataType::UnsignedInt(u32::MAX.into()),
+            ])
+        );
+
+        let mut delete = TableOperation::DeleteByKey {
+            key: vec![DataType::Int(i64::MIN)],
+        };
+        reinterpret_unsigned(&mut delete, &[0], &columns);
+        assert_eq!(
+            delete,
+            TableOperation::DeleteByKey {
+                key: vec![DataType::UnsignedInt(i64::MAX as u64 + 1)]
+            }
+        );
+    }
+
+    #[test]
+    fn minimal_image_column_defaults() {
+        let schema = nom_sql::parse_create_table(
+            Dialect::MySQL,
+            "CREATE TABLE t (id INT PRIMARY KEY, x INT DEFAULT '5', dropped INT DEFAULT 1, y TEXT)",
+        )
+        .unwrap();
+        // ReadySet's schema doesn't have the dropped column
+        let schema = CreateTableStatement {
+            fields: schema
+                .fields
+                .into_iter()
+                .filter(|spec| spec.column.name != "dropped")
+                .collect(),
+            ..schema
+        };
+
+        assert_eq!(
+            column_defaults(&schema, &[2], &[1, 2, 3]).unwrap(),
+            vec![(1, DataType::Int(5)), (3, DataType::None)]
+        );
+    }
+
+    #[test]
+    fn reorder_key() {
+        // A primary key of (c, a) on a table with columns (a, b, c)
+        assert_eq!(
+            key_in_primary_key_order(&[2, 0], vec!["a".into(), "c".into()]),
+            vec![DataType::from("c"), DataType::from("a")]
+        );
+        assert_eq!(
+            key_in_primary_key_order(&[0, 2], vec!["a".into(), "c".into()]),
+            vec![DataType::from("a"), DataType::from("c")]
+        );
+    }
+
+    #[test]
+    fn write_through_conflicts() {
+        let update = TableOperation::Update {
+            key: vec![1.into()],
+            update: vec![Modification::None, Modification::Set("b".into())],
+        };
+        let actions = resolve_write_through_conflicts(
+            &[0],
+            vec![
+                TableOperation::Insert(vec![1.into(), "a".into()]),
+                update.clone(),
+                TableOperation::DeleteRow {
+                    row: vec![1.into(), "b".into()],
+                },
+            ],
+        );
+
+        assert_eq!(
+            actions,
+            vec![
+                TableOperation::DeleteByKey {
+                    key: vec![1.into()]
+                },
+                TableOperation::Insert(vec![1.into(), "a".into()]),
+                update,
+                TableOperation::DeleteByKey {
+                    key: vec![1.into()]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn write_batch_groups_by_table() {
+        let offset = |offset| ReplicationOffset {
+            offset,
+            replication_log_name: "binlog.000001".to_owned(),
+        };
+        let insert = |i: i32| TableOperation::Insert(vec![i.into()]);
+
+        let mut batch = WriteBatch::default();
+        assert_eq!(batch.written_offset(), None);
+        batch.push(
+            "a".to_owned(),
+            vec![insert(1)],
+            Some(1),
+            offset(2),
+            offset(1),
+        );
+        batch.push("b".to_owned(), vec![insert(2)], None, offset(3), offset(2));
+        batch.push(
+            "a".to_owned(),
+            vec![insert(3), insert(4)],
+            None,
+            offset(4),
+            offset(3),
+        );
+
+        assert_eq!(batch.len, 4);
+        assert_eq!(batch.written_offset(), Some(&offset(1)));
+
+        let tables = batch.take();
+        assert_eq!(
+            tables
+                .iter()
+                .map(|batched| (batched.table.as_str(), batched.actions.len(), batched.txid))
+                .collect::<Vec<_>>(),
+            vec![("a", 3, Some(1)), ("b", 1, None)]
+        );
+        assert_eq!(tables[0].pos, offset(4));
+        assert_eq!(tables[1].pos, offset(3));
+        assert_eq!(batch.len, 0);
+        assert_eq!(batch.written_offset(), None);
+    }
+}
@@ -9,7 +9,7 @@ use readyset::consensus::{Authority, LocalAuthority, LocalAuthorityStore};
 use readyset::{ControllerHandle, ReadySetError, ReadySetResult};
 use readyset_data::{DataType, TinyText};
 use readyset_server::Builder;
-use replicators::NoriaAdapter;
+use replicators::{Config, NoriaAdapter};
 use tracing::trace;
 
 const MAX_ATTEMPTS: usize = 40;
@@ -110,6 +110,7 @@ struct TestHandle {
     // connection spawns a background task we can only terminate by dropping the runtime
     replication_rt: Option<tokio::runtime::Runtime>,
     ready_notify: Option<Arc<tokio::sync::Notify>>,
+    config: Config,
 }
 
 impl Drop for TestHandle {
@@ -173,16 +174,21 @@ impl DbConnection {
 
 impl TestHandle {
     async fn start_noria(url: String) -> ReadySetResult<TestHandle> {
+        TestHandle::start_noria_with_config(url, Default::default()).await
+    }
+
+    async fn start_noria_with_config(url: String, config: Config) -> ReadySetResult<TestHandle> {
         let authority_store = Arc::new(LocalAuthorityStore::new());
         let authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
             authority_store,
         )));
-        TestHandle::start_with_authority(url, authority).await
+        TestHandle::start_with_authority(url, authority, config).await
     }
 
     async fn start_with_authority(
         url: String,
         authority: Arc<Authority>,
+        config: Config,
     ) -> ReadySetResult<TestHandle> {
         readyset_tracing::init_test_logging();
         let mut builder = Builder::for_tests();
@@ -197,6 +203,7 @@ impl TestHandle {
             authority,
             replication_rt: None,
             ready_notify: Some(Default::default()),
+            config,
         };
 
         handle.start_repl().await?;
@@ -227,6 +234,7 @@ impl TestHandle {
         let _ = runtime.spawn(NoriaAdapter::start_with_url(
             self.url.clone(),
             controller,
+            self.config.clone(),
             None,
             self.ready_notify.clone(),
         ));
@@ -363,6 +371,18 @@ async fn mysql_replication_many_tables() -> ReadySetResult<()> {
     replication_many_tables_inner(&mysql_url()).await
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[serial_test::serial]
+async fn pgsql_parallel_snapshot() -> ReadySetResult<()> {
+    parallel_snapshot_inner(&pgsql_url()).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial_test::serial]
+async fn mysql_parallel_snapshot() -> ReadySetResult<()> {
+    parallel_snapshot_inner(&mysql_url()).await
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial_test::serial]
 async fn pgsql_replication_big_tables() -> ReadySetResult<()> {
@@ -523,6 +543,64 @@ async fn replication_many_tables_inner(url: &str) -> ReadySetResult<()> {
     Ok(())
 }
 
+async fn parallel_snapshot_inner(url: &str) -> ReadySetResult<()> {
+    const TOTAL_TABLES: usize = 10;
+    const TOTAL_ROWS: usize = 100;
+
+    let mut client = DbConnection::connect(url).await?;
+
+    for t in 0..TOTAL_TABLES {
+        let values = (0..TOTAL_ROWS)
+            .map(|r| format!("({})", r))
+            .collect::<Vec<_>>();
+        client
+            .query(&format!(
+                "DROP VIEW IF EXISTS ps{t}_view; DROP TABLE IF EXISTS ps{t} CASCADE;
+                 CREATE TABLE ps{t} (id int);
+                 INSERT INTO ps{t} VALUES {values};
+                 CREATE VIEW ps{t}_view AS SELECT id FROM ps{t} ORDER BY id ASC",
+                t = t,
+                values = values.join(",")
+            ))
+            .await?;
+    }
+
+    // More tables than workers, so that each worker copies several tables
+    let mut ctx = TestHandle::start_noria_with_config(
+        url.to_string(),
+        Config {
+            snapshot_parallelism: 3,
+            ..Default::default()
+        },
+    )
+    .await?;
+    ctx.ready_notify.as_ref().unwrap().notified().await;
+
+    let expected = (0..TOTAL_ROWS)
+        .map(|r| vec![DataType::Int(r as _)])
+        .collect::<Vec<_>>();
+    let expected = expected.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    for t in 0..TOTAL_TABLES {
+        ctx.check_results(&format!("ps{}_view", t), "Parallel snapshot", &expected)
+            .await?;
+    }
+
+    ctx.stop().await;
+
+    for t in 0..TOTAL_TABLES {
+        client
+            .query(&format!(
+                "DROP VIEW IF EXISTS ps{t}_view; DROP TABLE IF EXISTS ps{t} CASCADE;",
+                t = t
+            ))
+            .await?;
+    }
+
+    client.stop().await;
+
+    Ok(())
+}
+
 // This test will definitely trigger the global timeout if a session one is not set
 async fn replication_big_tables_inner(url: &str) -> ReadySetResult<()> {
     const TOTAL_TABLES: usize = 2;